@@ -62,6 +62,8 @@ where t > 1695348002000;
 -- SQLNESS ARG pre_cmd=flush
 -- SQLNESS REPLACE duration=\d+.?\d*(µ|m|n) duration=xx
 -- SQLNESS REPLACE project_record_batch=\d+.?\d*(µ|m|n) project_record_batch=xx
+-- SQLNESS REPLACE fetched_bytes=\d+ fetched_bytes=xx
+-- SQLNESS REPLACE cache_hit_bytes=\d+ cache_hit_bytes=xx
 -- SQLNESS REPLACE metrics=\[.*?s\] metrics=xx
 -- This query should include SST
 explain analyze select t from `03_dml_select_real_time_range`
@@ -105,6 +107,8 @@ where t >= 1695348001000 and name = 'ceresdb';
 -- SQLNESS REPLACE since_init=\d+.?\d*(µ|m|n) since_init=xx
 -- SQLNESS REPLACE elapsed_compute=\d+.?\d*(µ|m|n) elapsed_compute=xx
 -- SQLNESS REPLACE project_record_batch=\d+.?\d*(µ|m|n) project_record_batch=xx
+-- SQLNESS REPLACE fetched_bytes=\d+ fetched_bytes=xx
+-- SQLNESS REPLACE cache_hit_bytes=\d+ cache_hit_bytes=xx
 -- SQLNESS REPLACE metrics=\[.*?s\] metrics=xx
 explain analyze select t from `03_append_mode_table`
 where t >= 1695348001000 and name = 'ceresdb';
@@ -0,0 +1,161 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Leases of the backups, which pin the files of the engine until the backups
+//! are done.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use logger::warn;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// A lease held by a running backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct BackupLease {
+    pub id: u64,
+    /// Milliseconds left before the lease expires.
+    pub remaining_ms: u64,
+}
+
+/// The leases held by the running backups.
+///
+/// While any lease is active, the automatic compactions and manifest snapshots
+/// are held, and the deleted ssts are kept until all the leases are released,
+/// so the files referenced by the manifests being copied are never removed
+/// halfway. A lease expires if its backup dies without releasing it.
+///
+/// The leases are local to the node, so a backup of a cluster must hold a
+/// lease on every node. The purges deferred by the leases are persisted, and
+/// they are done by the node opening the tables after a restart.
+#[derive(Debug, Default)]
+pub struct BackupLeases {
+    next_id: AtomicU64,
+    /// Deadlines of the active leases.
+    leases: Mutex<HashMap<u64, Instant>>,
+}
+
+pub type BackupLeasesRef = Arc<BackupLeases>;
+
+impl BackupLeases {
+    /// Acquire a lease expiring after `ttl`.
+    pub fn acquire(&self, ttl: Duration) -> BackupLease {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let deadline = Instant::now() + ttl;
+        self.leases.lock().unwrap().insert(id, deadline);
+
+        BackupLease {
+            id,
+            remaining_ms: ttl.as_millis() as u64,
+        }
+    }
+
+    /// Extend the lease to expire after `ttl`, and `None` is returned if the
+    /// lease is not found or already expired.
+    pub fn renew(&self, id: u64, ttl: Duration) -> Option<BackupLease> {
+        let now = Instant::now();
+        let mut leases = self.leases.lock().unwrap();
+        Self::remove_expired(&mut leases, now);
+        let deadline = leases.get_mut(&id)?;
+        *deadline = now + ttl;
+
+        Some(BackupLease {
+            id,
+            remaining_ms: ttl.as_millis() as u64,
+        })
+    }
+
+    /// Release the lease, and `false` is returned if the lease is not found or
+    /// already expired.
+    pub fn release(&self, id: u64) -> bool {
+        let mut leases = self.leases.lock().unwrap();
+        Self::remove_expired(&mut leases, Instant::now());
+        leases.remove(&id).is_some()
+    }
+
+    /// Whether any backup is running.
+    pub fn is_active(&self) -> bool {
+        let mut leases = self.leases.lock().unwrap();
+        Self::remove_expired(&mut leases, Instant::now());
+        !leases.is_empty()
+    }
+
+    /// The active leases ordered by their ids.
+    pub fn leases(&self) -> Vec<BackupLease> {
+        let now = Instant::now();
+        let mut leases = self.leases.lock().unwrap();
+        Self::remove_expired(&mut leases, now);
+        let mut leases: Vec<_> = leases
+            .iter()
+            .map(|(id, deadline)| BackupLease {
+                id: *id,
+                remaining_ms: deadline.saturating_duration_since(now).as_millis() as u64,
+            })
+            .collect();
+        leases.sort_unstable_by_key(|lease| lease.id);
+
+        leases
+    }
+
+    fn remove_expired(leases: &mut HashMap<u64, Instant>, now: Instant) {
+        leases.retain(|id, deadline| {
+            let expired = *deadline <= now;
+            if expired {
+                warn!("Backup lease expired without being released, id:{id}");
+            }
+            !expired
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_leases() {
+        let leases = BackupLeases::default();
+        assert!(!leases.is_active());
+
+        let first = leases.acquire(Duration::from_secs(60));
+        let second = leases.acquire(Duration::from_secs(60));
+        assert_ne!(first.id, second.id);
+        assert!(leases.is_active());
+        assert_eq!(
+            vec![first.id, second.id],
+            leases.leases().iter().map(|v| v.id).collect::<Vec<_>>()
+        );
+
+        assert!(leases.release(first.id));
+        assert!(!leases.release(first.id));
+        assert!(leases.renew(first.id, Duration::from_secs(60)).is_none());
+        assert!(leases.is_active());
+
+        // The lease expires immediately.
+        let renewed = leases.renew(second.id, Duration::ZERO).unwrap();
+        assert_eq!(0, renewed.remaining_ms);
+        assert!(!leases.is_active());
+        assert!(leases.leases().is_empty());
+    }
+}
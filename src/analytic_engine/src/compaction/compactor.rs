@@ -20,6 +20,7 @@ use std::cmp;
 use common_types::request_id::RequestId;
 use logger::{debug, info};
 use snafu::ResultExt;
+use table_engine::event::{self, EventKind};
 
 use crate::{
     compaction::{
@@ -59,7 +60,7 @@ impl Compactor {
         table_data: &TableData,
         task: &CompactionTask,
         sst_write_options: &SstWriteOptions,
-    ) -> Result<()> {
+    ) -> Result<u64> {
         debug!(
             "Begin compact table, table_name:{}, id:{}, task:{:?}",
             table_data.name, table_data.id, task
@@ -72,10 +73,20 @@ impl Compactor {
                 table_data.name, table_data.id, task
             );
 
-            return Ok(());
+            return Ok(0);
         }
 
         let inputs = task.inputs();
+        let input_files: Vec<_> = inputs
+            .iter()
+            .flat_map(|input| input.files.iter().map(|file| file.id()))
+            .collect();
+        event::record(
+            EventKind::CompactionBegin,
+            &table_data.name,
+            table_data.id,
+            format!("request_id:{request_id}, input_files:{input_files:?}"),
+        );
         let mut edit_meta = VersionEditMeta {
             space_id: table_data.space_id,
             table_id: table_data.id,
@@ -125,7 +136,27 @@ impl Compactor {
             .await
             .context(StoreVersionEdit)?;
 
-        Ok(())
+        let added_files: Vec<_> = edit_meta
+            .files_to_add
+            .iter()
+            .map(|add| add.file.id)
+            .collect();
+        let deleted_files: Vec<_> = edit_meta
+            .files_to_delete
+            .iter()
+            .map(|delete| delete.file_id)
+            .collect();
+        event::record(
+            EventKind::CompactionFinish,
+            &table_data.name,
+            table_data.id,
+            format!(
+                "request_id:{request_id}, added_files:{added_files:?}, deleted_files:{deleted_files:?}"
+            ),
+        );
+
+        let output_size = edit_meta.files_to_add.iter().map(|add| add.file.size).sum();
+        Ok(output_size)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -235,7 +266,7 @@ impl Compactor {
                 max_seq: sst_meta.max_sequence,
                 time_range: sst_meta.time_range,
                 storage_format: sst_info.storage_format,
-                associated_files: vec![sst_info.meta_path],
+                associated_files: sst_info.associated_files(),
             },
         });
 
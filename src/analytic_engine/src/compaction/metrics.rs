@@ -18,7 +18,10 @@
 //! Metrics of compaction.
 
 use lazy_static::lazy_static;
-use prometheus::{register_int_gauge, IntGauge};
+use prometheus::{
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
 
 lazy_static! {
     // Counters:
@@ -27,4 +30,36 @@ lazy_static! {
         "Pending request queue length of compaction"
     )
         .unwrap();
+    pub static ref COMPACTION_PENDING_REQUEST_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "compaction_pending_request_by_priority",
+        "Pending request queue length of compaction by the priority",
+        &["priority"]
+    )
+    .unwrap();
+    pub static ref COMPACTION_ONGOING_TASK_GAUGE: IntGauge = register_int_gauge!(
+        "compaction_ongoing_task_gauge",
+        "Number of the ongoing compaction tasks"
+    )
+    .unwrap();
+    pub static ref COMPACTION_THROTTLED_DURATION_COUNTER: IntCounter = register_int_counter!(
+        "compaction_throttled_duration_ms",
+        "Total duration in milliseconds the compaction tasks are delayed by the throttle"
+    )
+    .unwrap();
+    pub static ref RECOMPRESSION_PENDING_SSTS_GAUGE: IntGauge = register_int_gauge!(
+        "recompression_pending_ssts",
+        "Number of the old ssts waiting to be recompressed"
+    )
+    .unwrap();
+    pub static ref RECOMPRESSION_SSTS_COUNTER: IntCounter = register_int_counter!(
+        "recompression_ssts",
+        "Total number of the recompressed ssts"
+    )
+    .unwrap();
+    pub static ref RECOMPRESSION_BYTES_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "recompression_bytes",
+        "Total bytes of the ssts read and written by the recompression",
+        &["type"]
+    )
+    .unwrap();
 }
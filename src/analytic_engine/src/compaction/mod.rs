@@ -27,16 +27,22 @@ use time_ext::TimeUnit;
 use tokio::sync::oneshot;
 
 use crate::{
-    compaction::picker::{CommonCompactionPicker, CompactionPickerRef},
-    sst::file::{FileHandle, Level},
+    compaction::picker::{CommonCompactionPicker, CompactionPickerRef, MajorCompactionPicker},
+    sst::{
+        file::{FileHandle, Level},
+        manager::FileId,
+    },
     table::data::TableDataRef,
 };
 
 pub mod compactor;
 mod metrics;
 pub mod picker;
+pub mod recompression;
 pub mod runner;
 pub mod scheduler;
+pub mod simulator;
+pub(crate) mod tiering;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -476,6 +482,10 @@ impl PickerManager {
     pub fn get_picker(&self, strategy: CompactionStrategy) -> CompactionPickerRef {
         Arc::new(CommonCompactionPicker::new(strategy))
     }
+
+    pub fn get_major_picker(&self) -> CompactionPickerRef {
+        Arc::new(MajorCompactionPicker)
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -518,18 +528,49 @@ impl Drop for WaiterNotifier {
     }
 }
 
+/// Priority of the compaction requests, the pending requests of the higher
+/// priority are scheduled first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CompactionPriority {
+    Normal,
+    /// The table has too many ssts at the min level.
+    High,
+    /// Triggered by the user, which is also scheduled while the compaction is
+    /// paused.
+    Manual,
+}
+
+impl CompactionPriority {
+    pub const ALL: [CompactionPriority; 3] = [Self::Normal, Self::High, Self::Manual];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Manual => "manual",
+        }
+    }
+}
+
 /// Request to compact single table.
 pub struct TableCompactionRequest {
     pub table_data: TableDataRef,
     pub waiter: Option<oneshot::Sender<WaitResult<()>>>,
+    /// Rewrite the ssts whose id is not greater than it one by one, instead of
+    /// picking the ssts by the compaction strategy.
+    pub rewrite_until: Option<FileId>,
+    pub priority: CompactionPriority,
 }
 
 impl TableCompactionRequest {
+    /// Request a manual major compaction of the table.
     pub fn new(table_data: TableDataRef) -> (Self, oneshot::Receiver<WaitResult<()>>) {
         let (tx, rx) = oneshot::channel::<WaitResult<()>>();
         let req = Self {
             table_data,
             waiter: Some(tx),
+            rewrite_until: None,
+            priority: CompactionPriority::Manual,
         };
 
         (req, rx)
@@ -539,8 +580,24 @@ impl TableCompactionRequest {
         TableCompactionRequest {
             table_data,
             waiter: None,
+            rewrite_until: None,
+            priority: CompactionPriority::Normal,
+        }
+    }
+
+    pub fn rewrite(table_data: TableDataRef, max_file_id: FileId) -> Self {
+        TableCompactionRequest {
+            table_data,
+            waiter: None,
+            rewrite_until: Some(max_file_id),
+            priority: CompactionPriority::Normal,
         }
     }
+
+    #[inline]
+    pub fn is_manual(&self) -> bool {
+        self.priority == CompactionPriority::Manual
+    }
 }
 
 #[cfg(test)]
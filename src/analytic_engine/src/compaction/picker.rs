@@ -160,6 +160,46 @@ impl CompactionPicker for CommonCompactionPicker {
     }
 }
 
+/// Picker of the manual major compaction, which merges the ssts of each level
+/// in each segment into one sst at the max level regardless of the compaction
+/// strategy.
+#[derive(Default)]
+pub struct MajorCompactionPicker;
+
+impl CompactionPicker for MajorCompactionPicker {
+    fn pick_compaction(
+        &self,
+        ctx: PickerContext,
+        levels_controller: &mut LevelsController,
+    ) -> Result<CompactionTask> {
+        let expire_time = ctx.ttl.map(Timestamp::expire_time);
+        let mut builder =
+            CompactionTaskBuilder::with_expired(levels_controller.expired_ssts(expire_time));
+
+        for level in levels_controller.levels() {
+            let files_by_segment = SizeTieredPicker::files_by_segment(
+                levels_controller,
+                level,
+                ctx.segment_duration,
+                expire_time,
+            );
+            for files in files_by_segment.into_values() {
+                // A single sst at the max level is already compacted.
+                if files.len() < 2 && level == Level::MAX {
+                    continue;
+                }
+                builder.add_inputs(CompactionInputFiles {
+                    level,
+                    files,
+                    output_level: Level::MAX,
+                });
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
 #[inline]
 fn find_uncompact_files(
     levels_controller: &LevelsController,
@@ -863,6 +903,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_major_compaction_picker() {
+        let picker = PickerManager.get_major_picker();
+        let ctx = PickerContext {
+            segment_duration: Duration::from_millis(1000),
+            ttl: Some(Duration::from_secs(100000)),
+            strategy: CompactionStrategy::Default,
+        };
+        let now = Timestamp::now();
+        let mut lc = build_old_bucket_case(now.as_i64());
+        let task = picker.pick_compaction(ctx, &mut lc).unwrap();
+        // Even the segment with a single sst at level 0 is compacted.
+        assert_eq!(task.inputs.len(), 2);
+        assert_eq!(task.inputs[0].files.len(), 2);
+        assert_eq!(task.inputs[0].files[0].id(), 0);
+        assert_eq!(task.inputs[0].files[1].id(), 1);
+        assert_eq!(task.inputs[0].output_level, Level::MAX);
+        assert_eq!(task.inputs[1].files.len(), 1);
+        assert_eq!(task.inputs[1].files[0].id(), 2);
+        assert_eq!(task.expired[0].files[0].id(), 3);
+    }
+
     fn build_file_handles(sizes: Vec<(u64, TimeRange)>) -> Vec<FileHandle> {
         let (tx, _rx) = mpsc::unbounded_channel();
 
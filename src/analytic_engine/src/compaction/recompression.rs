@@ -0,0 +1,451 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Rewrite the old ssts with a stronger compression to cut the storage cost of
+//! the data rarely read, e.g. the ssts written by lz4 are rewritten by zstd-19
+//! once they are older than a week.
+//!
+//! The old ssts are rewritten one by one during the off-peak hours, by
+//! compacting every sst alone into the same level with the compression of the
+//! config. The rewritten ssts are marked in their parquet footers, so they
+//! won't be rewritten again after restarting, and they stay in the tier they
+//! were read from.
+//!
+//! The compactions whose inputs are all recompressed write their outputs with
+//! the compression of the config and the marker too, otherwise the merged data
+//! would be rewritten again.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use common_types::{request_id::RequestId, time::Timestamp};
+use generic_error::BoxError;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use table_engine::table::TableId;
+use time_ext::ReadableDuration;
+
+use crate::{
+    compaction::{
+        compactor::Compactor,
+        metrics::{
+            RECOMPRESSION_BYTES_COUNTER_VEC, RECOMPRESSION_PENDING_SSTS_GAUGE,
+            RECOMPRESSION_SSTS_COUNTER,
+        },
+        CompactionInputFiles, CompactionTask, CompactionTaskBuilder,
+    },
+    dynamic_config::DynamicConfigRef,
+    instance::flush_compaction::{ReadSstFooter, Result},
+    sst::{
+        factory::{ObjectStorePickerRef, ReadFrequency, SstWriteOptions},
+        file::{FileHandle, Level},
+        manager::FileId,
+        parquet::{async_reader::ChunkReaderAdapter, encoding::RECOMPRESSED_KEY},
+    },
+    table::{data::TableData, sst_util},
+    table_options::{ColumnOptions, Compression},
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecompressionConfig {
+    pub enable: bool,
+    /// The ssts whose data ended before it are rewritten.
+    pub older_than: ReadableDuration,
+    pub compression: Compression,
+    /// Level of the zstd compression, `None` means the default level.
+    pub compression_level: Option<i32>,
+    /// The off-peak hours of the day in UTC are in `[start, end)`, and the
+    /// `end` less than the `start` means the hours cross the midnight. The
+    /// ssts are rewritten at any hour if they are equal.
+    pub off_peak_start_hour: u32,
+    pub off_peak_end_hour: u32,
+    /// Max number of the ssts rewritten by a round of the compaction schedule.
+    pub max_ssts_per_round: usize,
+}
+
+impl Default for RecompressionConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            older_than: ReadableDuration(Duration::from_secs(7 * 24 * 60 * 60)),
+            compression: Compression::Zstd,
+            compression_level: Some(19),
+            off_peak_start_hour: 1,
+            off_peak_end_hour: 6,
+            max_ssts_per_round: 8,
+        }
+    }
+}
+
+impl RecompressionConfig {
+    /// Whether the `hour` of the day in UTC is in the off-peak hours.
+    fn is_off_peak(&self, hour: u32) -> bool {
+        let (start, end) = (self.off_peak_start_hour, self.off_peak_end_hour);
+        if start <= end {
+            start == end || (start..end).contains(&hour)
+        } else {
+            hour >= start || hour < end
+        }
+    }
+}
+
+/// Progress of the recompression, which can be paused by the admin api.
+#[derive(Debug, Default)]
+pub struct RecompressionProgress {
+    paused: AtomicBool,
+    /// Number of the old ssts to rewrite found by the last round.
+    pending_ssts: AtomicU64,
+    recompressed_ssts: AtomicU64,
+    input_bytes: AtomicU64,
+    output_bytes: AtomicU64,
+}
+
+pub type RecompressionProgressRef = Arc<RecompressionProgress>;
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RecompressionStatus {
+    pub paused: bool,
+    pub pending_ssts: u64,
+    pub recompressed_ssts: u64,
+    /// Total size of the rewritten ssts.
+    pub input_bytes: u64,
+    /// Total size of the ssts written by the rewrites.
+    pub output_bytes: u64,
+}
+
+impl RecompressionProgress {
+    #[inline]
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> RecompressionStatus {
+        RecompressionStatus {
+            paused: self.paused(),
+            pending_ssts: self.pending_ssts.load(Ordering::Relaxed),
+            recompressed_ssts: self.recompressed_ssts.load(Ordering::Relaxed),
+            input_bytes: self.input_bytes.load(Ordering::Relaxed),
+            output_bytes: self.output_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn set_pending(&self, pending_ssts: u64) {
+        self.pending_ssts.store(pending_ssts, Ordering::Relaxed);
+        RECOMPRESSION_PENDING_SSTS_GAUGE.set(pending_ssts as i64);
+    }
+
+    fn record(&self, input_bytes: u64, output_bytes: u64) {
+        self.recompressed_ssts.fetch_add(1, Ordering::Relaxed);
+        self.input_bytes.fetch_add(input_bytes, Ordering::Relaxed);
+        self.output_bytes.fetch_add(output_bytes, Ordering::Relaxed);
+        RECOMPRESSION_SSTS_COUNTER.inc();
+        RECOMPRESSION_BYTES_COUNTER_VEC
+            .with_label_values(&["input"])
+            .inc_by(input_bytes);
+        RECOMPRESSION_BYTES_COUNTER_VEC
+            .with_label_values(&["output"])
+            .inc_by(output_bytes);
+    }
+}
+
+/// Result of the recompression of a table.
+#[derive(Debug, Default)]
+pub(crate) struct TableRecompression {
+    pub recompressed: usize,
+    /// Old ssts left to rewrite.
+    pub pending: usize,
+}
+
+pub(crate) struct Recompressor {
+    compactor: Arc<Compactor>,
+    store_picker: ObjectStorePickerRef,
+    config: RecompressionConfig,
+    /// Provides the progress of the recompression and whether the compaction
+    /// is paused.
+    dynamic_config: DynamicConfigRef,
+    write_sst_max_buffer_size: usize,
+    /// Whether the old ssts of the tables are recompressed, so the footer of
+    /// every sst is read only once as the ssts are immutable.
+    checked: Mutex<HashMap<TableId, HashMap<FileId, bool>>>,
+}
+
+impl Recompressor {
+    pub fn new(
+        compactor: Arc<Compactor>,
+        store_picker: ObjectStorePickerRef,
+        config: RecompressionConfig,
+        dynamic_config: DynamicConfigRef,
+        write_sst_max_buffer_size: usize,
+    ) -> Self {
+        Self {
+            compactor,
+            store_picker,
+            config,
+            dynamic_config,
+            write_sst_max_buffer_size,
+            checked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[inline]
+    pub fn max_ssts_per_round(&self) -> usize {
+        self.config.max_ssts_per_round
+    }
+
+    /// Whether neither the recompression nor the automatic compactions are
+    /// paused, and it's in the off-peak hours.
+    pub fn is_runnable(&self) -> bool {
+        let hour = (Timestamp::now().as_i64() / (60 * 60 * 1000) % 24) as u32;
+        !self.dynamic_config.recompression().paused()
+            && !self.dynamic_config.auto_compaction_paused()
+            && self.config.is_off_peak(hour)
+    }
+
+    pub fn set_pending(&self, pending_ssts: usize) {
+        self.dynamic_config
+            .recompression()
+            .set_pending(pending_ssts as u64);
+    }
+
+    /// Rewrite at most `max_ssts` old ssts of the table, and stop once the
+    /// recompression is not runnable, e.g. paused.
+    pub async fn recompress_table(
+        &self,
+        table_data: &TableData,
+        max_ssts: usize,
+    ) -> Result<TableRecompression> {
+        let candidates = self.candidates(table_data).await?;
+        let mut result = TableRecompression {
+            recompressed: 0,
+            pending: candidates.len(),
+        };
+        for (level, file) in candidates {
+            if result.recompressed >= max_ssts
+                || !self.is_runnable()
+                || !table_data.allow_compaction()
+            {
+                break;
+            }
+            if !table_data.current_version().try_reserve_sst(level, &file) {
+                continue;
+            }
+
+            let input_bytes = file.size();
+            let mut builder = CompactionTaskBuilder::with_expired(Vec::new());
+            builder.add_inputs(CompactionInputFiles {
+                level,
+                files: vec![file],
+                output_level: level,
+            });
+            let task = builder.build();
+            let output_bytes = self
+                .compactor
+                .compact_table(
+                    RequestId::next_id(),
+                    table_data,
+                    &task,
+                    &self.write_options(table_data),
+                )
+                .await?;
+            self.dynamic_config
+                .recompression()
+                .record(input_bytes, output_bytes);
+            result.recompressed += 1;
+            result.pending -= 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Find the old ssts of the table not recompressed yet.
+    async fn candidates(&self, table_data: &TableData) -> Result<Vec<(Level, FileHandle)>> {
+        let end = Timestamp::now().sub_duration_or_min(self.config.older_than.0);
+        let ssts = table_data.current_version().ssts_ended_before(end);
+        let mut checked = self
+            .checked
+            .lock()
+            .unwrap()
+            .remove(&table_data.id)
+            .unwrap_or_default();
+
+        let mut candidates = Vec::new();
+        let mut still_checked = HashMap::with_capacity(ssts.len());
+        for (level, file) in ssts {
+            let recompressed = match checked.remove(&file.id()) {
+                Some(v) => v,
+                None => self.is_recompressed(table_data, &file).await?,
+            };
+            still_checked.insert(file.id(), recompressed);
+            if !recompressed {
+                candidates.push((level, file));
+            }
+        }
+        // The deleted ssts are forgotten.
+        self.checked
+            .lock()
+            .unwrap()
+            .insert(table_data.id, still_checked);
+
+        Ok(candidates)
+    }
+
+    /// Whether all the input ssts of the compaction task are recompressed, so
+    /// its output should be written by [Recompressor::write_options].
+    pub async fn is_task_recompressed(
+        &self,
+        table_data: &TableData,
+        task: &CompactionTask,
+    ) -> Result<bool> {
+        let mut files = task
+            .inputs()
+            .iter()
+            .flat_map(|input| &input.files)
+            .peekable();
+        if files.peek().is_none() {
+            return Ok(false);
+        }
+
+        for file in files {
+            let cached = self
+                .checked
+                .lock()
+                .unwrap()
+                .get(&table_data.id)
+                .and_then(|checked| checked.get(&file.id()).copied());
+            let recompressed = match cached {
+                Some(v) => v,
+                None => {
+                    let recompressed = self.is_recompressed(table_data, file).await?;
+                    self.checked
+                        .lock()
+                        .unwrap()
+                        .entry(table_data.id)
+                        .or_default()
+                        .insert(file.id(), recompressed);
+                    recompressed
+                }
+            };
+            if !recompressed {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    async fn is_recompressed(&self, table_data: &TableData, file: &FileHandle) -> Result<bool> {
+        let path = sst_util::new_sst_file_path(table_data.space_id, table_data.id, file.id());
+        let store = self.store_picker.pick_by_freq(ReadFrequency::Once);
+        let reader = ChunkReaderAdapter::new(&path, store);
+        let (meta_data, _) =
+            parquet_ext::meta_data::fetch_parquet_metadata(file.size() as usize, &reader)
+                .await
+                .box_err()
+                .context(ReadSstFooter {
+                    path: path.to_string(),
+                })?;
+
+        let recompressed = meta_data
+            .file_metadata()
+            .key_value_metadata()
+            .map(|kvs| kvs.iter().any(|kv| kv.key == RECOMPRESSED_KEY))
+            .unwrap_or(false);
+        Ok(recompressed)
+    }
+
+    /// Options to write the recompressed ssts, which are marked in the footers.
+    pub fn write_options(&self, table_data: &TableData) -> SstWriteOptions {
+        let table_options = table_data.table_options();
+        SstWriteOptions {
+            storage_format_hint: table_options.storage_format_hint,
+            num_rows_per_row_group: table_data.num_rows_per_row_group_to_build(),
+            data_page_size: table_options.data_page_size.as_byte() as usize,
+            compression: self.config.compression,
+            compression_level: self.config.compression_level,
+            // All the columns are compressed by the compression of the config.
+            column_options: ColumnOptions {
+                encodings: table_options.column_options.encodings.clone(),
+                compressions: BTreeMap::new(),
+            },
+            adaptive_compression: false,
+            separated_fields: table_options.separated_fields.clone(),
+            max_buffer_size: self.write_sst_max_buffer_size,
+            column_stats: Default::default(),
+            bloom_filter: table_options.bloom_filter_options(),
+            recompressed: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_off_peak_hours() {
+        let config = |start, end| RecompressionConfig {
+            off_peak_start_hour: start,
+            off_peak_end_hour: end,
+            ..Default::default()
+        };
+
+        let config1 = config(1, 6);
+        assert!(!config1.is_off_peak(0));
+        assert!(config1.is_off_peak(1));
+        assert!(config1.is_off_peak(5));
+        assert!(!config1.is_off_peak(6));
+
+        // Cross the midnight.
+        let config2 = config(22, 2);
+        assert!(config2.is_off_peak(23));
+        assert!(config2.is_off_peak(0));
+        assert!(!config2.is_off_peak(2));
+        assert!(!config2.is_off_peak(12));
+
+        let config3 = config(0, 0);
+        assert!((0..24).all(|hour| config3.is_off_peak(hour)));
+    }
+
+    #[test]
+    fn test_progress() {
+        let progress = RecompressionProgress::default();
+        progress.set_pending(3);
+        progress.record(100, 40);
+        progress.record(50, 20);
+        progress.set_paused(true);
+
+        let status = progress.status();
+        assert!(status.paused);
+        assert_eq!(3, status.pending_ssts);
+        assert_eq!(2, status.recompressed_ssts);
+        assert_eq!(150, status.input_bytes);
+        assert_eq!(60, status.output_bytes);
+    }
+}
@@ -20,6 +20,7 @@ use std::{collections::HashMap, sync::Arc};
 use async_trait::async_trait;
 use common_types::projected_schema::{ProjectedSchema, RowProjectorBuilder};
 use generic_error::BoxError;
+use object_store::tiered::TieredStore;
 use runtime::Runtime;
 use snafu::ResultExt;
 use table_engine::predicate::Predicate;
@@ -27,7 +28,7 @@ use table_engine::predicate::Predicate;
 use crate::{
     compaction::runner::{CompactionRunner, CompactionRunnerResult, CompactionRunnerTask},
     instance::flush_compaction::{
-        BuildMergeIterator, CreateSstWriter, ReadSstMeta, Result, WriteSst,
+        BuildMergeIterator, CreateSstWriter, FindSstTier, ReadSstMeta, Result, WriteSst,
     },
     row_iter::{
         self,
@@ -39,6 +40,7 @@ use crate::{
         meta_data::{cache::MetaCacheRef, SstMetaData, SstMetaReader},
         writer::MetaData,
     },
+    table::sst_util,
     Config, ScanType, SstReadOptionsBuilder,
 };
 
@@ -119,6 +121,7 @@ impl CompactionRunner for LocalCompactionRunner {
                 merge_iter_options: task.input_ctx.merge_iter_options.clone(),
                 need_dedup: task.input_ctx.need_dedup,
                 reverse: false,
+                corrupt_sst_skipper: None,
             });
             // Add all ssts in compaction input to builder.
             builder
@@ -134,6 +137,7 @@ impl CompactionRunner for LocalCompactionRunner {
                 request_id.clone(),
                 merge_iter,
                 task.input_ctx.merge_iter_options,
+                task.input_ctx.merge_policy,
             ))
         } else {
             row_iter::record_batch_with_key_iter_to_stream(merge_iter)
@@ -163,17 +167,35 @@ impl CompactionRunner for LocalCompactionRunner {
         let sst_write_options = SstWriteOptions {
             storage_format_hint: task.output_ctx.write_options.storage_format_hint,
             num_rows_per_row_group: task.output_ctx.write_options.num_rows_per_row_group,
+            data_page_size: task.output_ctx.write_options.data_page_size,
             compression: task.output_ctx.write_options.compression,
+            compression_level: task.output_ctx.write_options.compression_level,
+            column_options: task.output_ctx.write_options.column_options.clone(),
+            adaptive_compression: task.output_ctx.write_options.adaptive_compression,
+            separated_fields: task.output_ctx.write_options.separated_fields.clone(),
             max_buffer_size: task.output_ctx.write_options.max_buffer_size,
             column_stats,
+            bloom_filter: task.output_ctx.write_options.bloom_filter,
+            recompressed: task.output_ctx.write_options.recompressed,
         };
 
+        // The output is written to the cold tier if the inputs are out of the hot
+        // duration, or all in the cold tier, e.g. the recompressed old ssts.
+        let cold_tier = match self.store_picker.tiered_store() {
+            Some(tiered) if task.output_ctx.cold => Some(tiered),
+            Some(tiered) => is_input_cold(tiered, &task).await?.then_some(tiered),
+            None => None,
+        };
+        let store_picker = match cold_tier {
+            Some(tiered) => Arc::new(tiered.cold_store().clone()) as ObjectStorePickerRef,
+            None => self.store_picker.clone(),
+        };
         let mut sst_writer = self
             .sst_factory
             .create_writer(
                 &sst_write_options,
                 &task.output_ctx.file_path,
-                &self.store_picker,
+                &store_picker,
                 task.input_ctx.files.output_level,
             )
             .await
@@ -197,6 +219,21 @@ impl CompactionRunner for LocalCompactionRunner {
     }
 }
 
+/// Whether all the input ssts of the task are in the cold tier.
+async fn is_input_cold(tiered: &TieredStore, task: &CompactionRunnerTask) -> Result<bool> {
+    for file in &task.input_ctx.files.files {
+        let path = sst_util::new_sst_file_path(task.space_id, task.table_id, file.id());
+        let is_hot = tiered.is_hot(&path).await.box_err().context(FindSstTier {
+            path: path.to_string(),
+        })?;
+        if is_hot {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
 /// Collect the column stats from a batch of sst meta data.
 fn collect_column_stats_from_meta_datas(metas: &[SstMetaData]) -> HashMap<String, ColumnStats> {
     let mut low_cardinality_counts: HashMap<String, usize> = HashMap::new();
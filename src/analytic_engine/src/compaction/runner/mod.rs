@@ -20,7 +20,7 @@ pub mod local_runner;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use common_types::{request_id::RequestId, schema::Schema, SequenceNumber};
+use common_types::{request_id::RequestId, schema::Schema, time::Timestamp, SequenceNumber};
 use object_store::Path;
 use table_engine::table::TableId;
 
@@ -34,6 +34,7 @@ use crate::{
         writer::{MetaData, SstInfo},
     },
     table::data::TableData,
+    table_options::MergePolicy,
 };
 
 /// Compaction runner
@@ -79,6 +80,16 @@ impl CompactionRunnerTask {
         // Create executor task.
         let table_options = table_data.table_options();
 
+        // The output is written to the cold tier if all the inputs are out of the
+        // hot duration of the table, so it needn't be moved again.
+        let cold = table_options.hot_duration.is_some_and(|v| {
+            let hot_end = Timestamp::now().sub_duration_or_min(v.0);
+            input_files
+                .files
+                .iter()
+                .all(|file| file.time_range().exclusive_end() <= hot_end)
+        });
+
         let input_ctx = {
             let iter_options = IterOptions {
                 batch_size: table_options.num_rows_per_row_group,
@@ -89,6 +100,7 @@ impl CompactionRunnerTask {
                 num_rows_per_row_group: table_options.num_rows_per_row_group,
                 merge_iter_options: iter_options,
                 need_dedup: table_options.need_dedup(),
+                merge_policy: table_options.merge_policy,
             }
         };
 
@@ -97,6 +109,7 @@ impl CompactionRunnerTask {
             OutputContext {
                 file_path,
                 write_options: sst_write_options,
+                cold,
             }
         };
 
@@ -126,6 +139,8 @@ pub struct InputContext {
     pub num_rows_per_row_group: usize,
     pub merge_iter_options: IterOptions,
     pub need_dedup: bool,
+    /// Policy to merge the duplicate rows, only used if `need_dedup` is set.
+    pub merge_policy: MergePolicy,
 }
 
 #[derive(Debug, Clone)]
@@ -134,4 +149,6 @@ pub struct OutputContext {
     pub file_path: Path,
     /// Output sst write context
     pub write_options: SstWriteOptions,
+    /// Write the output sst to the cold tier if tiering is enabled.
+    pub cold: bool,
 }
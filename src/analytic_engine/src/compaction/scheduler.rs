@@ -18,13 +18,13 @@
 // Compaction scheduler.
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{btree_map::Entry, BTreeMap, HashMap, VecDeque},
     hash::Hash,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, RwLock,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
@@ -36,7 +36,10 @@ use runtime::{JoinHandle, Runtime};
 use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
 use snafu::{ResultExt, Snafu};
-use table_engine::table::TableId;
+use table_engine::{
+    table::TableId,
+    task::{RetryPolicy, TaskKind, TaskRegistryRef, TaskSpec},
+};
 use time_ext::{DurationExt, ReadableDuration};
 use tokio::{
     sync::{
@@ -48,15 +51,24 @@ use tokio::{
 
 use crate::{
     compaction::{
-        compactor::Compactor, metrics::COMPACTION_PENDING_REQUEST_GAUGE, picker::PickerContext,
-        runner::CompactionRunnerPtr, CompactionTask, PickerManager, TableCompactionRequest,
-        WaitError, WaiterNotifier,
+        compactor::Compactor,
+        metrics::{
+            COMPACTION_ONGOING_TASK_GAUGE, COMPACTION_PENDING_REQUEST_GAUGE,
+            COMPACTION_PENDING_REQUEST_GAUGE_VEC, COMPACTION_THROTTLED_DURATION_COUNTER,
+        },
+        picker::PickerContext,
+        recompression::{RecompressionConfig, Recompressor},
+        runner::CompactionRunnerPtr,
+        tiering::Migrator,
+        CompactionPriority, CompactionTask, PickerManager, TableCompactionRequest, WaitError,
+        WaiterNotifier,
     },
+    dynamic_config::DynamicConfigRef,
     instance::{
         flush_compaction::{Flusher, TableFlushOptions},
         SpaceStore,
     },
-    sst::factory::SstWriteOptions,
+    sst::{factory::SstWriteOptions, file::Level, manager::FileId},
     table::data::TableDataRef,
     TableOptions,
 };
@@ -69,6 +81,14 @@ pub enum Error {
 
 define_result!(Error);
 
+/// The migration of a table is retried as the cold tier is usually a remote
+/// storage.
+const MIGRATION_RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_retries: 2,
+    init_backoff: Duration::from_secs(1),
+    max_backoff: Duration::from_secs(10),
+};
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct SchedulerConfig {
@@ -78,6 +98,15 @@ pub struct SchedulerConfig {
     pub max_unflushed_duration: ReadableDuration,
     pub memory_limit: ReadableSize,
     pub max_pending_compaction_tasks: usize,
+    /// Max input bytes of the compactions per second, which bounds the IO and
+    /// CPU taken from the foreground traffic, while `max_ongoing_tasks` bounds
+    /// the concurrency. Zero means unlimited.
+    pub max_bytes_per_sec: ReadableSize,
+    /// The compaction of the table having so many ssts at the min level is
+    /// scheduled before the others.
+    pub high_priority_min_level_ssts: usize,
+    /// Rewrite the old ssts with a stronger compression.
+    pub recompression: RecompressionConfig,
 }
 
 impl Default for SchedulerConfig {
@@ -91,6 +120,9 @@ impl Default for SchedulerConfig {
             max_unflushed_duration: ReadableDuration(Duration::from_secs(60 * 60 * 5)),
             memory_limit: ReadableSize::gb(4),
             max_pending_compaction_tasks: 1024,
+            max_bytes_per_sec: ReadableSize(0),
+            high_priority_min_level_ssts: 16,
+            recompression: RecompressionConfig::default(),
         }
     }
 }
@@ -110,35 +142,87 @@ pub trait CompactionScheduler {
     async fn schedule_table_compaction(&self, request: TableCompactionRequest) -> bool;
 }
 
-// A FIFO queue that remove duplicate values by key.
-struct RequestQueue<K: Eq + Hash + Clone, V> {
-    keys: VecDeque<K>,
-    values: HashMap<K, V>,
+// A priority queue that remove duplicate values by key, the values of the
+// same priority are in FIFO order.
+struct RequestQueue<K: Eq + Hash + Clone, P: Ord + Copy, V> {
+    keys: BTreeMap<P, VecDeque<K>>,
+    values: HashMap<K, (P, V)>,
 }
 
-impl<K: Eq + Hash + Clone, V> Default for RequestQueue<K, V> {
+impl<K: Eq + Hash + Clone, P: Ord + Copy, V> Default for RequestQueue<K, P, V> {
     fn default() -> Self {
         Self {
-            keys: VecDeque::default(),
+            keys: BTreeMap::default(),
             values: HashMap::default(),
         }
     }
 }
 
-impl<K: Eq + Hash + Clone, V> RequestQueue<K, V> {
-    fn push_back(&mut self, key: K, value: V) -> bool {
-        if self.values.insert(key.clone(), value).is_none() {
-            self.keys.push_back(key);
-            return true;
+impl<K: Eq + Hash + Clone, P: Ord + Copy, V> RequestQueue<K, P, V> {
+    /// Returns true if the key is newly queued. A queued value of the same key
+    /// is replaced and moved to the higher `priority`, but the value of a lower
+    /// priority is dropped.
+    fn push_back(&mut self, key: K, priority: P, value: V) -> bool {
+        let queued = match self.values.get_mut(&key) {
+            Some(v) => v,
+            None => {
+                self.keys
+                    .entry(priority)
+                    .or_default()
+                    .push_back(key.clone());
+                self.values.insert(key, (priority, value));
+                return true;
+            }
+        };
+
+        if priority < queued.0 {
+            return false;
+        }
+        if priority > queued.0 {
+            if let Entry::Occupied(mut keys) = self.keys.entry(queued.0) {
+                keys.get_mut().retain(|k| k != &key);
+                if keys.get().is_empty() {
+                    keys.remove();
+                }
+            }
+            self.keys.entry(priority).or_default().push_back(key);
         }
+        *queued = (priority, value);
+
         false
     }
 
+    /// Pop the oldest value of the highest priority.
     fn pop_front(&mut self) -> Option<V> {
-        if let Some(key) = self.keys.pop_front() {
-            return self.values.remove(&key);
+        let priority = *self.keys.last_key_value()?.0;
+        self.pop_front_at(priority)
+    }
+
+    /// Pop the oldest value of the highest priority, which should not be lower
+    /// than `min_priority`.
+    fn pop_front_at_least(&mut self, min_priority: P) -> Option<V> {
+        let priority = *self.keys.last_key_value()?.0;
+        if priority < min_priority {
+            return None;
         }
-        None
+        self.pop_front_at(priority)
+    }
+
+    /// Pop the oldest value of the lowest priority.
+    fn pop_lowest(&mut self) -> Option<V> {
+        let priority = *self.keys.first_key_value()?.0;
+        self.pop_front_at(priority)
+    }
+
+    fn pop_front_at(&mut self, priority: P) -> Option<V> {
+        let keys = self.keys.get_mut(&priority)?;
+        let key = keys.pop_front();
+        if keys.is_empty() {
+            self.keys.remove(&priority);
+        }
+
+        let (_, value) = self.values.remove(&key?)?;
+        Some(value)
     }
 
     #[inline]
@@ -146,13 +230,19 @@ impl<K: Eq + Hash + Clone, V> RequestQueue<K, V> {
         self.values.len()
     }
 
+    #[inline]
+    fn len_at(&self, priority: &P) -> usize {
+        self.keys.get(priority).map_or(0, |keys| keys.len())
+    }
+
     #[inline]
     fn is_empty(&self) -> bool {
         self.values.is_empty()
     }
 }
 
-type RequestBuf = RwLock<RequestQueue<TableId, TableCompactionRequest>>;
+type PendingRequests = RequestQueue<TableId, CompactionPriority, TableCompactionRequest>;
+type RequestBuf = RwLock<PendingRequests>;
 
 /// Combined with [`MemoryUsageToken`], [`MemoryLimit`] provides a mechanism to
 /// impose limit on the memory usage.
@@ -222,11 +312,13 @@ impl OngoingTaskLimit {
     #[inline]
     fn start_task(&self) {
         self.ongoing_tasks.fetch_add(1, Ordering::SeqCst);
+        COMPACTION_ONGOING_TASK_GAUGE.inc();
     }
 
     #[inline]
     fn finish_task(&self) {
         self.ongoing_tasks.fetch_sub(1, Ordering::SeqCst);
+        COMPACTION_ONGOING_TASK_GAUGE.dec();
     }
 
     #[inline]
@@ -242,18 +334,15 @@ impl OngoingTaskLimit {
         {
             let mut req_buf = self.request_buf.write().unwrap();
 
-            // Remove older requests
-            if req_buf.len() >= self.max_pending_compaction_tasks {
-                while req_buf.len() >= self.max_pending_compaction_tasks {
-                    req_buf.pop_front();
-                    dropped += 1;
-                }
-                COMPACTION_PENDING_REQUEST_GAUGE.sub(dropped)
+            // Remove older requests of the lowest priority
+            while req_buf.len() >= self.max_pending_compaction_tasks
+                && req_buf.pop_lowest().is_some()
+            {
+                dropped += 1;
             }
 
-            if req_buf.push_back(request.table_data.id, request) {
-                COMPACTION_PENDING_REQUEST_GAUGE.add(1)
-            }
+            req_buf.push_back(request.table_data.id, request.priority, request);
+            update_pending_request_metrics(&req_buf);
         }
 
         if dropped > 0 {
@@ -264,18 +353,28 @@ impl OngoingTaskLimit {
         }
     }
 
-    fn drain_requests(&self, max_num: usize) -> Vec<TableCompactionRequest> {
+    /// Drain the requests of the higher priority first, and the requests lower
+    /// than `min_priority` are kept.
+    fn drain_requests(
+        &self,
+        max_num: usize,
+        min_priority: Option<CompactionPriority>,
+    ) -> Vec<TableCompactionRequest> {
         let mut result = Vec::with_capacity(max_num);
         let mut req_buf = self.request_buf.write().unwrap();
 
         while result.len() < max_num {
-            if let Some(req) = req_buf.pop_front() {
+            let req = match min_priority {
+                Some(min_priority) => req_buf.pop_front_at_least(min_priority),
+                None => req_buf.pop_front(),
+            };
+            if let Some(req) = req {
                 result.push(req);
             } else {
                 break;
             }
         }
-        COMPACTION_PENDING_REQUEST_GAUGE.sub(result.len() as i64);
+        update_pending_request_metrics(&req_buf);
 
         result
     }
@@ -296,6 +395,47 @@ impl OngoingTaskLimit {
     }
 }
 
+fn update_pending_request_metrics(req_buf: &PendingRequests) {
+    COMPACTION_PENDING_REQUEST_GAUGE.set(req_buf.len() as i64);
+    for priority in CompactionPriority::ALL {
+        COMPACTION_PENDING_REQUEST_GAUGE_VEC
+            .with_label_values(&[priority.as_str()])
+            .set(req_buf.len_at(&priority) as i64);
+    }
+}
+
+/// Limits the rate of the input bytes of the compaction tasks.
+struct Throttle {
+    /// Zero means unlimited.
+    bytes_per_sec: u64,
+    /// When the bytes acquired so far are all consumed at the rate.
+    next_free: std::sync::Mutex<Instant>,
+}
+
+impl Throttle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            next_free: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Acquire the `bytes` at `now`, returns how long to wait before consuming
+    /// them.
+    fn acquire(&self, bytes: u64, now: Instant) -> Duration {
+        if self.bytes_per_sec == 0 {
+            return Duration::ZERO;
+        }
+
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let mut next_free = self.next_free.lock().unwrap();
+        let start = (*next_free).max(now);
+        *next_free = start + cost;
+
+        start - now
+    }
+}
+
 pub type CompactionSchedulerRef = Arc<dyn CompactionScheduler + Send + Sync>;
 
 pub struct SchedulerImpl {
@@ -309,7 +449,9 @@ impl SchedulerImpl {
         space_store: Arc<SpaceStore>,
         runner: CompactionRunnerPtr,
         runtime: Arc<Runtime>,
+        task_registry: TaskRegistryRef,
         config: SchedulerConfig,
+        dynamic_config: DynamicConfigRef,
         write_sst_max_buffer_size: usize,
         min_flush_interval_ms: u64,
     ) -> Self {
@@ -317,16 +459,36 @@ impl SchedulerImpl {
         let running = Arc::new(AtomicBool::new(true));
 
         let compactor = Arc::new(Compactor::new(runner, space_store.manifest.clone()));
+        let migrator = space_store
+            .store_picker()
+            .tiered_store()
+            .map(|store| Arc::new(Migrator::new(space_store.manifest.clone(), store.clone())));
+        let recompressor = config.recompression.enable.then(|| {
+            Arc::new(Recompressor::new(
+                compactor.clone(),
+                space_store.store_picker().clone(),
+                config.recompression.clone(),
+                dynamic_config.clone(),
+                write_sst_max_buffer_size,
+            ))
+        });
         let mut worker = ScheduleWorker {
             sender: tx.clone(),
             receiver: rx,
             compactor,
+            migrator,
+            migrating: Arc::new(AtomicBool::new(false)),
+            recompressor,
+            recompressing: Arc::new(AtomicBool::new(false)),
             space_store,
             runtime: runtime.clone(),
+            task_registry: task_registry.clone(),
             schedule_interval: config.schedule_interval.0,
             picker_manager: PickerManager,
-            max_ongoing_tasks: config.max_ongoing_tasks,
+            dynamic_config,
             max_unflushed_duration: config.max_unflushed_duration.0,
+            high_priority_min_level_ssts: config.high_priority_min_level_ssts,
+            throttle: Throttle::new(config.max_bytes_per_sec.as_byte()),
             write_sst_max_buffer_size,
             min_flush_interval_ms,
             limit: Arc::new(OngoingTaskLimit {
@@ -338,7 +500,8 @@ impl SchedulerImpl {
             memory_limit: MemoryLimit::new(config.memory_limit.as_byte() as usize),
         };
 
-        let handle = runtime.spawn(async move {
+        let spec = TaskSpec::new(TaskKind::CompactionSchedule, "");
+        let handle = task_registry.spawn_tracked(&runtime, spec, async move {
             worker.schedule_loop().await;
         });
 
@@ -401,11 +564,26 @@ struct ScheduleWorker {
     receiver: Receiver<ScheduleTask>,
     space_store: Arc<SpaceStore>,
     compactor: Arc<Compactor>,
+    /// Moves the cold ssts to the cold tier, only provided if the cold tier is
+    /// configured.
+    migrator: Option<Arc<Migrator>>,
+    /// Whether a round of the sst migration is ongoing.
+    migrating: Arc<AtomicBool>,
+    /// Rewrites the old ssts with a stronger compression, only provided if the
+    /// recompression is enabled.
+    recompressor: Option<Arc<Recompressor>>,
+    /// Whether a round of the recompression is ongoing.
+    recompressing: Arc<AtomicBool>,
     runtime: Arc<Runtime>,
+    task_registry: TaskRegistryRef,
     schedule_interval: Duration,
     max_unflushed_duration: Duration,
     picker_manager: PickerManager,
-    max_ongoing_tasks: usize,
+    high_priority_min_level_ssts: usize,
+    throttle: Throttle,
+    /// Provides the max number of the ongoing compaction tasks and whether the
+    /// compaction is paused
+    dynamic_config: DynamicConfigRef,
     write_sst_max_buffer_size: usize,
     min_flush_interval_ms: u64,
     limit: Arc<OngoingTaskLimit>,
@@ -454,11 +632,20 @@ impl ScheduleWorker {
         match schedule_task {
             ScheduleTask::Request(compact_req) => {
                 debug!("Ongoing compaction tasks:{ongoing}");
-                if ongoing >= self.max_ongoing_tasks {
+                let compact_req = self.prioritize(compact_req);
+                let max_ongoing_tasks = self.dynamic_config.compaction_max_ongoing_tasks();
+                if self.dynamic_config.auto_compaction_paused() && !compact_req.is_manual() {
+                    debug!(
+                        "Compaction is paused, table:{}, buf_len:{}",
+                        compact_req.table_data.name,
+                        self.limit.request_buf_len()
+                    );
+                    self.limit.add_request(compact_req);
+                } else if ongoing >= max_ongoing_tasks {
                     self.limit.add_request(compact_req);
                     warn!(
                         "Too many compaction ongoing tasks:{ongoing}, max:{}, buf_len:{}",
-                        self.max_ongoing_tasks,
+                        max_ongoing_tasks,
                         self.limit.request_buf_len()
                     );
                 } else {
@@ -466,8 +653,16 @@ impl ScheduleWorker {
                 }
             }
             ScheduleTask::Schedule => {
-                if self.max_ongoing_tasks > ongoing {
-                    let pending = self.limit.drain_requests(self.max_ongoing_tasks - ongoing);
+                let max_ongoing_tasks = self.dynamic_config.compaction_max_ongoing_tasks();
+                if max_ongoing_tasks > ongoing {
+                    // Only the manual compactions are scheduled while paused.
+                    let min_priority = self
+                        .dynamic_config
+                        .auto_compaction_paused()
+                        .then_some(CompactionPriority::Manual);
+                    let pending = self
+                        .limit
+                        .drain_requests(max_ongoing_tasks - ongoing, min_priority);
                     let mut futures: FuturesUnordered<_> = pending
                         .into_iter()
                         .map(|req| self.handle_table_compaction_request(req))
@@ -478,7 +673,7 @@ impl ScheduleWorker {
                 } else {
                     warn!(
                         "Too many compaction ongoing tasks:{ongoing}, max:{}, buf_len:{}",
-                        self.max_ongoing_tasks,
+                        max_ongoing_tasks,
                         self.limit.request_buf_len()
                     );
                 }
@@ -491,12 +686,19 @@ impl ScheduleWorker {
         &self,
         table_data: TableDataRef,
         compaction_task: CompactionTask,
+        rewrite_until: Option<FileId>,
         waiter_notifier: WaiterNotifier,
         token: MemoryUsageToken,
     ) {
+        // Keep rewriting until no sst is left.
+        let continue_rewrite = rewrite_until.filter(|_| !compaction_task.is_empty());
         let keep_scheduling_compaction =
             self.is_pending_queue_hungry() && compaction_task.contains_min_level();
 
+        let throttle_delay = self.throttle.acquire(
+            compaction_task.estimated_total_input_file_size() as u64,
+            Instant::now(),
+        );
         let compactor = self.compactor.clone();
         self.limit.start_task();
         let task = OngoingTask {
@@ -505,14 +707,23 @@ impl ScheduleWorker {
         };
 
         let sender = self.sender.clone();
+        let task_registry = self.task_registry.clone();
+        let recompressor = self.recompressor.clone();
         let request_id = RequestId::next_id();
         let storage_format_hint = table_data.table_options().storage_format_hint;
         let sst_write_options = SstWriteOptions {
             storage_format_hint,
-            num_rows_per_row_group: table_data.table_options().num_rows_per_row_group,
+            num_rows_per_row_group: table_data.num_rows_per_row_group_to_build(),
+            data_page_size: table_data.table_options().data_page_size.as_byte() as usize,
             compression: table_data.table_options().compression,
+            compression_level: table_data.table_options().compression_level,
+            column_options: table_data.table_options().column_options.clone(),
+            adaptive_compression: table_data.table_options().adaptive_compression,
+            separated_fields: table_data.table_options().separated_fields.clone(),
             max_buffer_size: self.write_sst_max_buffer_size,
             column_stats: Default::default(),
+            bloom_filter: table_data.table_options().bloom_filter_options(),
+            recompressed: false,
         };
 
         // Do actual costly compact job in background.
@@ -523,26 +734,60 @@ impl ScheduleWorker {
             // We will reschedule table with many l0 sst as fast as we can.
             if keep_scheduling_compaction {
                 schedule_table_compaction(
-                    sender,
+                    sender.clone(),
                     TableCompactionRequest::no_waiter(table_data.clone()),
                 )
                 .await;
             }
-            let res = compactor
-                .compact_table(
-                    request_id.clone(),
-                    &table_data,
-                    &compaction_task,
-                    &sst_write_options,
-                )
-                .await;
+            // The throttled compaction is also visible as a running task.
+            let spec = TaskSpec::new(TaskKind::Compaction, table_data.name.clone());
+            let compact = async {
+                if !throttle_delay.is_zero() {
+                    debug!(
+                        "Compaction is throttled, table:{}, request_id:{request_id}, delay:{:?}",
+                        table_data.name, throttle_delay
+                    );
+                    COMPACTION_THROTTLED_DURATION_COUNTER
+                        .inc_by(throttle_delay.as_millis() as u64);
+                    time::sleep(throttle_delay).await;
+                }
+                // Keep the recompressed data recompressed after merging.
+                let recompressed = match &recompressor {
+                    Some(recompressor) => {
+                        recompressor
+                            .is_task_recompressed(&table_data, &compaction_task)
+                            .await?
+                    }
+                    None => false,
+                };
+                let sst_write_options = match &recompressor {
+                    Some(recompressor) if recompressed => recompressor.write_options(&table_data),
+                    _ => sst_write_options,
+                };
+                compactor
+                    .compact_table(
+                        request_id.clone(),
+                        &table_data,
+                        &compaction_task,
+                        &sst_write_options,
+                    )
+                    .await
+            };
+            let res = task_registry.track(spec, compact).await;
 
             task.limit.finish_task();
             task.schedule_worker_if_need().await;
 
             // Notify the background compact table result.
             match res {
-                Ok(()) => {
+                Ok(_) => {
+                    if let Some(max_file_id) = continue_rewrite {
+                        schedule_table_compaction(
+                            sender,
+                            TableCompactionRequest::rewrite(table_data.clone(), max_file_id),
+                        )
+                        .await;
+                    }
                     waiter_notifier.notify_wait_result(Ok(()));
                 }
                 Err(e) => {
@@ -590,8 +835,12 @@ impl ScheduleWorker {
         }
 
         let table_options = table_data.table_options();
-        let compaction_strategy = table_options.compaction_strategy;
-        let picker = self.picker_manager.get_picker(compaction_strategy);
+        // The manual compaction compacts all the ssts.
+        let picker = if compact_req.is_manual() {
+            self.picker_manager.get_major_picker()
+        } else {
+            self.picker_manager.get_picker(table_options.compaction_strategy)
+        };
         let picker_ctx = match new_picker_context(&table_options) {
             Some(v) => v,
             None => {
@@ -603,7 +852,10 @@ impl ScheduleWorker {
         let version = table_data.current_version();
 
         // Pick compaction task.
-        let compaction_task = version.pick_for_compaction(picker_ctx, &picker);
+        let compaction_task = match compact_req.rewrite_until {
+            Some(max_file_id) => Ok(version.pick_for_rewrite(max_file_id)),
+            None => version.pick_for_compaction(picker_ctx, &picker),
+        };
         let compaction_task = match compaction_task {
             Ok(v) => v,
             Err(e) => {
@@ -633,12 +885,112 @@ impl ScheduleWorker {
 
         let waiter_notifier = WaiterNotifier::new(compact_req.waiter);
 
-        self.do_table_compaction_task(table_data, compaction_task, waiter_notifier, token);
+        self.do_table_compaction_task(
+            table_data,
+            compaction_task,
+            compact_req.rewrite_until,
+            waiter_notifier,
+            token,
+        );
     }
 
     async fn schedule(&mut self) {
         self.compact_tables().await;
         self.flush_tables().await;
+        self.migrate_tables();
+        self.recompress_tables();
+        self.expire_version_history();
+    }
+
+    /// Release the ssts and memtables retained by the history of the versions,
+    /// even if the tables have no new edit.
+    fn expire_version_history(&self) {
+        let mut tables_buf = Vec::new();
+        self.space_store.list_all_tables(&mut tables_buf);
+        for table_data in &tables_buf {
+            table_data.current_version().expire_history();
+        }
+    }
+
+    fn migrate_tables(&self) {
+        let migrator = match &self.migrator {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        // Skip this round if the last one is not finished yet.
+        if self.migrating.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut tables_buf = Vec::new();
+        self.space_store.list_all_tables(&mut tables_buf);
+        let migrating = self.migrating.clone();
+        let task_registry = self.task_registry.clone();
+        self.runtime.spawn(async move {
+            for table_data in tables_buf {
+                let spec = TaskSpec::new(TaskKind::Migration, table_data.name.clone())
+                    .with_retry(MIGRATION_RETRY_POLICY);
+                let res = task_registry
+                    .run(spec, || migrator.migrate_table(&table_data))
+                    .await;
+                match res {
+                    Ok(0) => (),
+                    Ok(num_moved) => info!(
+                        "Period migrate ssts to cold tier, table:{}, table_id:{}, num_moved:{}",
+                        table_data.name, table_data.id, num_moved
+                    ),
+                    Err(e) => error!(
+                        "Failed to migrate ssts to cold tier, table:{}, table_id:{}, err:{}",
+                        table_data.name, table_data.id, e
+                    ),
+                }
+            }
+            migrating.store(false, Ordering::SeqCst);
+        });
+    }
+
+    fn recompress_tables(&self) {
+        let recompressor = match &self.recompressor {
+            Some(v) if v.is_runnable() => v.clone(),
+            _ => return,
+        };
+        // Skip this round if the last one is not finished yet.
+        if self.recompressing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut tables_buf = Vec::new();
+        self.space_store.list_all_tables(&mut tables_buf);
+        let recompressing = self.recompressing.clone();
+        let task_registry = self.task_registry.clone();
+        self.runtime.spawn(async move {
+            let mut budget = recompressor.max_ssts_per_round();
+            let mut pending = 0;
+            for table_data in tables_buf {
+                let spec = TaskSpec::new(TaskKind::Recompression, table_data.name.clone());
+                let res = task_registry
+                    .run(spec, || recompressor.recompress_table(&table_data, budget))
+                    .await;
+                match res {
+                    Ok(v) => {
+                        budget -= v.recompressed;
+                        pending += v.pending;
+                        if v.recompressed > 0 {
+                            info!(
+                                "Period recompress ssts, table:{}, table_id:{}, num_ssts:{}",
+                                table_data.name, table_data.id, v.recompressed
+                            );
+                        }
+                    }
+                    Err(e) => error!(
+                        "Failed to recompress ssts, table:{}, table_id:{}, err:{}",
+                        table_data.name, table_data.id, e
+                    ),
+                }
+            }
+            recompressor.set_pending(pending);
+            recompressing.store(false, Ordering::SeqCst);
+        });
     }
 
     async fn compact_tables(&mut self) {
@@ -654,8 +1006,8 @@ impl ScheduleWorker {
 
             // This will add a compaction request to queue and avoid schedule thread
             // blocked.
-            self.limit
-                .add_request(TableCompactionRequest::no_waiter(table_data));
+            let request = self.prioritize(TableCompactionRequest::no_waiter(table_data));
+            self.limit.add_request(request);
         }
         if let Err(e) = self.sender.send(ScheduleTask::Schedule).await {
             error!("Fail to schedule table compaction request, err:{}", e);
@@ -668,6 +1020,7 @@ impl ScheduleWorker {
         let flusher = Flusher {
             space_store: self.space_store.clone(),
             runtime: self.runtime.clone(),
+            task_registry: self.task_registry.clone(),
             write_sst_max_buffer_size: self.write_sst_max_buffer_size,
             min_flush_interval_ms: Some(self.min_flush_interval_ms),
         };
@@ -696,11 +1049,23 @@ impl ScheduleWorker {
         }
     }
 
+    /// Raise the priority of the table having too many ssts at the min level.
+    fn prioritize(&self, mut request: TableCompactionRequest) -> TableCompactionRequest {
+        if request.priority == CompactionPriority::Normal {
+            let version = request.table_data.current_version();
+            if version.num_ssts_at_level(Level::MIN) >= self.high_priority_min_level_ssts {
+                request.priority = CompactionPriority::High;
+            }
+        }
+
+        request
+    }
+
     fn is_pending_queue_hungry(&self) -> bool {
         // TODO: Currently we consider pending queue is hungry when number of pending
         // tasks is less than `max_ongoing_tasks`, maybe we can add a new option
         // to configure it.
-        self.limit.pending_task_size() < self.max_ongoing_tasks
+        self.limit.pending_task_size() < self.dynamic_config.compaction_max_ongoing_tasks()
     }
 }
 
@@ -787,13 +1152,13 @@ mod tests {
 
     #[test]
     fn test_request_queue() {
-        let mut q: RequestQueue<i32, String> = RequestQueue::default();
+        let mut q: RequestQueue<i32, u8, String> = RequestQueue::default();
         assert!(q.is_empty());
         assert_eq!(0, q.len());
 
-        q.push_back(1, "task1".to_string());
-        q.push_back(2, "task2".to_string());
-        q.push_back(3, "task3".to_string());
+        q.push_back(1, 0, "task1".to_string());
+        q.push_back(2, 0, "task2".to_string());
+        q.push_back(3, 0, "task3".to_string());
 
         assert_eq!(3, q.len());
         assert!(!q.is_empty());
@@ -804,12 +1169,12 @@ mod tests {
         assert!(q.pop_front().is_none());
         assert!(q.is_empty());
 
-        q.push_back(1, "task1".to_string());
-        q.push_back(2, "task2".to_string());
-        q.push_back(3, "task3".to_string());
-        q.push_back(1, "task11".to_string());
-        q.push_back(3, "task33".to_string());
-        q.push_back(3, "task333".to_string());
+        q.push_back(1, 0, "task1".to_string());
+        q.push_back(2, 0, "task2".to_string());
+        q.push_back(3, 0, "task3".to_string());
+        q.push_back(1, 0, "task11".to_string());
+        q.push_back(3, 0, "task33".to_string());
+        q.push_back(3, 0, "task333".to_string());
 
         assert_eq!(3, q.len());
         assert_eq!("task11", q.pop_front().unwrap());
@@ -819,4 +1184,52 @@ mod tests {
         assert!(q.is_empty());
         assert_eq!(0, q.len());
     }
+
+    #[test]
+    fn test_request_queue_priority() {
+        let mut q: RequestQueue<i32, u8, String> = RequestQueue::default();
+        assert!(q.push_back(1, 0, "task1".to_string()));
+        assert!(q.push_back(2, 0, "task2".to_string()));
+        assert!(q.push_back(3, 1, "task3".to_string()));
+        assert!(q.push_back(4, 2, "task4".to_string()));
+        // Raise the priority of the queued key.
+        assert!(!q.push_back(2, 1, "task22".to_string()));
+        // The value of the lower priority is dropped.
+        assert!(!q.push_back(4, 0, "task44".to_string()));
+
+        assert_eq!(4, q.len());
+        assert_eq!(1, q.len_at(&0));
+        assert_eq!(2, q.len_at(&1));
+        assert_eq!(1, q.len_at(&2));
+
+        assert_eq!("task4", q.pop_front_at_least(2).unwrap());
+        assert!(q.pop_front_at_least(2).is_none());
+        assert_eq!("task1", q.pop_lowest().unwrap());
+        assert_eq!("task3", q.pop_front().unwrap());
+        assert_eq!("task22", q.pop_front().unwrap());
+        assert!(q.pop_lowest().is_none());
+        assert!(q.is_empty());
+        assert_eq!(0, q.len_at(&1));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let now = Instant::now();
+        let unlimited = Throttle::new(0);
+        assert_eq!(Duration::ZERO, unlimited.acquire(1024, now));
+
+        let throttle = Throttle::new(1000);
+        *throttle.next_free.lock().unwrap() = now;
+        assert_eq!(Duration::ZERO, throttle.acquire(500, now));
+        assert_eq!(Duration::from_millis(500), throttle.acquire(1000, now));
+        assert_eq!(
+            Duration::from_millis(1000),
+            throttle.acquire(0, now + Duration::from_millis(500))
+        );
+        // The throttle is idle after all the acquired bytes are consumed.
+        assert_eq!(
+            Duration::ZERO,
+            throttle.acquire(100, now + Duration::from_secs(10))
+        );
+    }
 }
@@ -0,0 +1,333 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Offline compaction simulator.
+//!
+//! The simulator replays flushes and compactions on the metadata of ssts only,
+//! so the effect of a compaction strategy (write amplification, sst count and
+//! space usage) can be estimated without touching any data.
+//!
+//! A compaction is assumed to merge its inputs without deduplication, that is
+//! the output file is as large as all the inputs, which makes the estimation an
+//! upper bound for tables with updates.
+
+use std::time::Duration;
+
+use common_types::time::{TimeRange, Timestamp};
+use table_engine::table::TableId;
+use tokio::sync::mpsc;
+
+use crate::{
+    compaction::{
+        picker::{CommonCompactionPicker, CompactionPicker, PickerContext, Result},
+        CompactionTask,
+    },
+    sst::{
+        file::{FileMeta, FilePurgeQueue, Level},
+        manager::{FileId, LevelsController},
+    },
+    table_options::{StorageFormat, TableOptions, DEFAULT_SEGMENT_DURATION},
+};
+
+/// Max number of compactions run after a single flush.
+const MAX_COMPACTIONS_PER_FLUSH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct SimulationOptions {
+    /// Options of the table, only the segment duration, ttl and compaction
+    /// strategy are used. The default segment duration is used if it is not
+    /// set.
+    pub table_opts: TableOptions,
+    /// Number of flushes to simulate.
+    pub num_flushes: usize,
+    /// Time range covered by a flushed sst, also the interval between flushes.
+    pub flush_interval: Duration,
+    /// Size in bytes of a flushed sst.
+    pub flush_size: u64,
+    /// Row number of a flushed sst.
+    pub flush_rows: u64,
+}
+
+/// State of the table after a flush and the compactions triggered by it.
+#[derive(Debug, Clone)]
+pub struct SimulationStep {
+    /// Simulated time, the end of the data written so far.
+    pub time: Timestamp,
+    pub num_compactions: usize,
+    pub num_ssts: usize,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimulationReport {
+    pub steps: Vec<SimulationStep>,
+    /// Bytes written by flushes.
+    pub flushed_bytes: u64,
+    /// Bytes written by compactions.
+    pub compacted_bytes: u64,
+    /// Bytes deleted because of ttl.
+    pub expired_bytes: u64,
+    pub num_compactions: usize,
+    pub max_num_ssts: usize,
+    pub max_total_size: u64,
+}
+
+impl SimulationReport {
+    /// Bytes written to the storage per byte flushed.
+    pub fn write_amplification(&self) -> f64 {
+        if self.flushed_bytes == 0 {
+            return 0.0;
+        }
+
+        (self.flushed_bytes + self.compacted_bytes) as f64 / self.flushed_bytes as f64
+    }
+}
+
+pub struct Simulator {
+    opts: SimulationOptions,
+    picker: CommonCompactionPicker,
+    levels_controller: LevelsController,
+    next_file_id: FileId,
+    max_seq: u64,
+    /// Simulated clock, ttl is computed against it rather than the wall clock.
+    now: Timestamp,
+}
+
+impl Simulator {
+    /// Create a simulator starting from the given ssts, all of which are put
+    /// into the min level.
+    pub fn new(opts: SimulationOptions, files: Vec<FileMeta>) -> Self {
+        // Files are never purged by the simulator, so the receiver is dropped.
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let purge_queue = FilePurgeQueue::new(0, TableId::from(0), tx);
+        let mut levels_controller = LevelsController::new(purge_queue);
+
+        let now = files
+            .iter()
+            .map(|f| f.time_range.exclusive_end())
+            .max()
+            .unwrap_or_else(Timestamp::now);
+        let next_file_id = files.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+        let max_seq = files.iter().map(|f| f.max_seq).max().unwrap_or(0);
+        for file in files {
+            levels_controller.add_sst_to_level(Level::MIN, file);
+        }
+
+        Self {
+            picker: CommonCompactionPicker::new(opts.table_opts.compaction_strategy),
+            opts,
+            levels_controller,
+            next_file_id,
+            max_seq,
+            now,
+        }
+    }
+
+    pub fn run(mut self) -> Result<SimulationReport> {
+        let mut report = SimulationReport::default();
+        for _ in 0..self.opts.num_flushes {
+            self.flush(&mut report);
+            self.expire(&mut report);
+
+            let mut num_compactions = 0;
+            while num_compactions < MAX_COMPACTIONS_PER_FLUSH {
+                let task = self.pick_compaction()?;
+                if task.is_input_empty() {
+                    break;
+                }
+                self.apply(&task, &mut report);
+                num_compactions += 1;
+            }
+
+            let (num_ssts, total_size) = self.stats();
+            report.num_compactions += num_compactions;
+            report.max_num_ssts = report.max_num_ssts.max(num_ssts);
+            report.max_total_size = report.max_total_size.max(total_size);
+            report.steps.push(SimulationStep {
+                time: self.now,
+                num_compactions,
+                num_ssts,
+                total_size,
+            });
+        }
+
+        Ok(report)
+    }
+
+    fn flush(&mut self, report: &mut SimulationReport) {
+        let end = self
+            .now
+            .checked_add_i64(self.opts.flush_interval.as_millis() as i64)
+            .unwrap_or(Timestamp::MAX);
+        let time_range = TimeRange::new_unchecked(self.now, end);
+        self.now = end;
+        self.max_seq += self.opts.flush_rows;
+
+        let file = self.new_file(
+            self.opts.flush_size,
+            self.opts.flush_rows,
+            time_range,
+            self.max_seq,
+        );
+        self.levels_controller.add_sst_to_level(Level::MIN, file);
+        report.flushed_bytes += self.opts.flush_size;
+    }
+
+    /// Remove the files expired at the simulated clock.
+    fn expire(&mut self, report: &mut SimulationReport) {
+        let ttl = match self.opts.table_opts.ttl() {
+            Some(v) => v,
+            None => return,
+        };
+
+        let expire_time = Some(self.now.sub_duration_or_min(ttl.0));
+        for expired in self.levels_controller.expired_ssts(expire_time) {
+            let file_ids: Vec<_> = expired.files.iter().map(|f| f.id()).collect();
+            report.expired_bytes += expired.files.iter().map(|f| f.size()).sum::<u64>();
+            self.levels_controller
+                .remove_ssts_from_level(expired.level, &file_ids);
+        }
+    }
+
+    fn pick_compaction(&mut self) -> Result<CompactionTask> {
+        let table_opts = &self.opts.table_opts;
+        // The ttl is handled by `expire` against the simulated clock rather than
+        // the wall clock used by the picker.
+        let ctx = PickerContext {
+            segment_duration: table_opts
+                .segment_duration()
+                .unwrap_or(DEFAULT_SEGMENT_DURATION),
+            ttl: None,
+            strategy: table_opts.compaction_strategy,
+        };
+        self.picker
+            .pick_compaction(ctx, &mut self.levels_controller)
+    }
+
+    fn apply(&mut self, task: &CompactionTask, report: &mut SimulationReport) {
+        for input in &task.inputs {
+            if input.files.is_empty() {
+                continue;
+            }
+
+            let size = input.files.iter().map(|f| f.size()).sum();
+            let row_num = input.files.iter().map(|f| f.row_num()).sum();
+            let max_seq = input.files.iter().map(|f| f.max_sequence()).max().unwrap();
+            let start = input
+                .files
+                .iter()
+                .map(|f| f.time_range().inclusive_start())
+                .min()
+                .unwrap();
+            let end = input
+                .files
+                .iter()
+                .map(|f| f.time_range().exclusive_end())
+                .max()
+                .unwrap();
+            let file_ids: Vec<_> = input.files.iter().map(|f| f.id()).collect();
+
+            self.levels_controller
+                .remove_ssts_from_level(input.level, &file_ids);
+            let file = self.new_file(size, row_num, TimeRange::new_unchecked(start, end), max_seq);
+            self.levels_controller
+                .add_sst_to_level(input.output_level, file);
+            report.compacted_bytes += size;
+        }
+    }
+
+    fn stats(&self) -> (usize, u64) {
+        self.levels_controller
+            .levels()
+            .flat_map(|level| self.levels_controller.iter_ssts_at_level(level))
+            .fold((0, 0), |(num, size), file| (num + 1, size + file.size()))
+    }
+
+    fn new_file(
+        &mut self,
+        size: u64,
+        row_num: u64,
+        time_range: TimeRange,
+        max_seq: u64,
+    ) -> FileMeta {
+        let id = self.next_file_id;
+        self.next_file_id += 1;
+
+        FileMeta {
+            id,
+            size,
+            row_num,
+            time_range,
+            max_seq,
+            storage_format: StorageFormat::default(),
+            associated_files: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time_ext::ReadableDuration;
+
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn new_options(ttl: Option<Duration>) -> SimulationOptions {
+        let mut table_opts = TableOptions::default();
+        table_opts.segment_duration = Some(ReadableDuration(HOUR * 2));
+        table_opts.enable_ttl = ttl.is_some();
+        if let Some(ttl) = ttl {
+            table_opts.ttl = ReadableDuration(ttl);
+        }
+
+        SimulationOptions {
+            table_opts,
+            num_flushes: 24,
+            flush_interval: HOUR / 4,
+            flush_size: 1024,
+            flush_rows: 100,
+        }
+    }
+
+    #[test]
+    fn test_simulate_without_ttl() {
+        let report = Simulator::new(new_options(None), Vec::new()).run().unwrap();
+
+        assert_eq!(report.steps.len(), 24);
+        assert_eq!(report.flushed_bytes, 24 * 1024);
+        assert_eq!(report.expired_bytes, 0);
+        assert!(report.num_compactions > 0);
+        assert!(report.write_amplification() > 1.0);
+        // Nothing is deleted, so the space equals the flushed bytes.
+        let last = report.steps.last().unwrap();
+        assert_eq!(last.total_size, report.flushed_bytes);
+        assert!(last.num_ssts < 24);
+    }
+
+    #[test]
+    fn test_simulate_with_ttl() {
+        let report = Simulator::new(new_options(Some(HOUR)), Vec::new())
+            .run()
+            .unwrap();
+
+        assert!(report.expired_bytes > 0);
+        assert!(report.max_total_size < report.flushed_bytes);
+        let last = report.steps.last().unwrap();
+        assert_eq!(last.total_size + report.expired_bytes, report.flushed_bytes);
+    }
+}
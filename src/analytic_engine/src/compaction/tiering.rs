@@ -0,0 +1,186 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Move the ssts out of the hot duration of the table to the cold tier.
+//!
+//! An sst is moved by copying it and its associated files into the cold tier
+//! with a new file id, then replacing the old sst by the new one in a single
+//! version edit. The old sst is purged from the hot tier as the other deleted
+//! ssts, so the queries reading it are not affected.
+//!
+//! The compactions of the ssts out of the hot duration write their outputs to
+//! the cold tier directly, so the outputs needn't be moved again.
+
+use common_types::time::Timestamp;
+use generic_error::BoxError;
+use logger::info;
+use object_store::{tiered::TieredStoreRef, Path};
+use snafu::ResultExt;
+
+use crate::{
+    instance::flush_compaction::{AllocFileId, MigrateSst, Other, Result, StoreVersionEdit},
+    manifest::{
+        meta_edit::{MetaEdit, MetaEditRequest, MetaUpdate, VersionEditMeta},
+        ManifestRef,
+    },
+    sst::file::{FileHandle, FileMeta, Level},
+    table::{
+        data::TableData,
+        sst_util,
+        version_edit::{AddFile, DeleteFile},
+    },
+};
+
+pub(crate) struct Migrator {
+    manifest: ManifestRef,
+    store: TieredStoreRef,
+}
+
+impl Migrator {
+    pub fn new(manifest: ManifestRef, store: TieredStoreRef) -> Self {
+        Self { manifest, store }
+    }
+
+    /// Move the ssts of the table out of its hot duration to the cold tier.
+    ///
+    /// Returns the number of the moved ssts.
+    pub async fn migrate_table(&self, table_data: &TableData) -> Result<usize> {
+        let hot_duration = match table_data.table_options().hot_duration {
+            Some(v) => v.0,
+            None => return Ok(0),
+        };
+
+        let hot_end = Timestamp::now().sub_duration_or_min(hot_duration);
+        let candidates = table_data.current_version().ssts_ended_before(hot_end);
+        let mut num_moved = 0;
+        for (level, file) in candidates {
+            if !table_data.allow_compaction() {
+                break;
+            }
+
+            let path = sst_util::new_sst_file_path(table_data.space_id, table_data.id, file.id());
+            let is_hot = self
+                .store
+                .is_hot(&path)
+                .await
+                .box_err()
+                .context(MigrateSst {
+                    path: path.to_string(),
+                })?;
+            if !is_hot || !table_data.current_version().try_reserve_sst(level, &file) {
+                continue;
+            }
+
+            let result = self.migrate_sst(table_data, level, &file, &path).await;
+            file.set_being_compacted(false);
+            result?;
+            num_moved += 1;
+        }
+
+        Ok(num_moved)
+    }
+
+    async fn migrate_sst(
+        &self,
+        table_data: &TableData,
+        level: Level,
+        file: &FileHandle,
+        path: &Path,
+    ) -> Result<()> {
+        let file_id = table_data
+            .alloc_file_id(&self.manifest)
+            .await
+            .context(AllocFileId)?;
+        let new_path = sst_util::new_sst_file_path(table_data.space_id, table_data.id, file_id);
+
+        // The associated files are named after the sst, e.g. the meta data file.
+        let meta = file.meta();
+        let mut associated_files = Vec::with_capacity(meta.associated_files.len());
+        for associated in &meta.associated_files {
+            let suffix = match associated.strip_prefix(path.to_string().as_str()) {
+                Some(v) => v,
+                None => {
+                    return Other {
+                        msg: format!(
+                            "associated file is not named after the sst, sst:{path}, associated:{associated}"
+                        ),
+                    }
+                    .fail()
+                }
+            };
+            let new_associated = format!("{new_path}{suffix}");
+            self.store
+                .migrate(
+                    &Path::from(associated.as_str()),
+                    &Path::from(new_associated.as_str()),
+                )
+                .await
+                .box_err()
+                .context(MigrateSst {
+                    path: associated.clone(),
+                })?;
+            associated_files.push(new_associated);
+        }
+        self.store
+            .migrate(path, &new_path)
+            .await
+            .box_err()
+            .context(MigrateSst {
+                path: path.to_string(),
+            })?;
+
+        let edit_meta = VersionEditMeta {
+            space_id: table_data.space_id,
+            table_id: table_data.id,
+            flushed_sequence: 0,
+            files_to_add: vec![AddFile {
+                level,
+                file: FileMeta {
+                    id: file_id,
+                    associated_files,
+                    ..meta
+                },
+            }],
+            files_to_delete: vec![DeleteFile {
+                level,
+                file_id: file.id(),
+            }],
+            mems_to_remove: vec![],
+            max_file_id: 0,
+        };
+        let edit_req = MetaEditRequest {
+            shard_info: table_data.shard_info,
+            meta_edit: MetaEdit::Update(MetaUpdate::VersionEdit(edit_meta)),
+            table_catalog_info: table_data.table_catalog_info.clone(),
+        };
+        self.manifest
+            .apply_edit(edit_req)
+            .await
+            .context(StoreVersionEdit)?;
+
+        info!(
+            "Sst is moved to the cold tier, table:{}, table_id:{}, level:{}, old_file_id:{}, new_file_id:{}",
+            table_data.name,
+            table_data.id,
+            level,
+            file.id(),
+            file_id
+        );
+
+        Ok(())
+    }
+}
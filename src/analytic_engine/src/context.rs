@@ -19,7 +19,9 @@
 
 use std::{fmt, sync::Arc};
 
+use alloc_tracker::budget::MemoryBudgetRef;
 use table_engine::engine::EngineRuntimes;
+use wal::encryption::KeyRingRef;
 
 use crate::{sst::meta_data::cache::MetaCacheRef, Config};
 
@@ -33,6 +35,16 @@ pub struct OpenContext {
 
     /// Sst meta data cache.
     pub meta_cache: Option<MetaCacheRef>,
+
+    /// Rewrite the ssts in old format of the opened tables.
+    pub rewrite_old_ssts: bool,
+
+    /// Memory budget shared with other components of the process.
+    pub memory_budget: MemoryBudgetRef,
+
+    /// Key ring to encrypt the entries written into the data wal, which is
+    /// shared with the opened wals.
+    pub wal_key_ring: KeyRingRef,
 }
 
 impl fmt::Debug for OpenContext {
@@ -0,0 +1,172 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Options of the engine which can be changed without restarting, e.g. by
+//! reloading the config file.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+use crate::{
+    backup::{BackupLeases, BackupLeasesRef},
+    compaction::recompression::{RecompressionProgress, RecompressionProgressRef},
+    sst::meta_data::cache::MetaCacheRef,
+    Config,
+};
+
+#[derive(Debug)]
+pub struct DynamicConfig {
+    /// See [Config::db_write_buffer_size].
+    db_write_buffer_size: AtomicUsize,
+    /// See `max_ongoing_tasks` of [Config::compaction].
+    compaction_max_ongoing_tasks: AtomicUsize,
+    /// Resized by [Config::sst_meta_cache_cap], the cache can't be enabled or
+    /// disabled without restarting.
+    sst_meta_cache: Option<MetaCacheRef>,
+    /// Whether the compaction is paused by the admin api, which is not part of
+    /// the config file so it's kept across the reloads.
+    compaction_paused: AtomicBool,
+    /// Leases of the running backups.
+    backup_leases: BackupLeasesRef,
+    /// Progress of the recompression of the old ssts, whose pause is kept
+    /// across the reloads too.
+    recompression: RecompressionProgressRef,
+}
+
+pub type DynamicConfigRef = Arc<DynamicConfig>;
+
+impl DynamicConfig {
+    pub fn new(config: &Config, sst_meta_cache: Option<MetaCacheRef>) -> Self {
+        Self {
+            db_write_buffer_size: AtomicUsize::new(config.db_write_buffer_size),
+            compaction_max_ongoing_tasks: AtomicUsize::new(config.compaction.max_ongoing_tasks),
+            sst_meta_cache,
+            compaction_paused: AtomicBool::new(false),
+            backup_leases: Arc::new(BackupLeases::default()),
+            recompression: Arc::new(RecompressionProgress::default()),
+        }
+    }
+
+    /// Apply the dynamic options of the `config`, the others are ignored.
+    pub fn update(&self, config: &Config) {
+        self.db_write_buffer_size
+            .store(config.db_write_buffer_size, Ordering::Relaxed);
+        self.compaction_max_ongoing_tasks
+            .store(config.compaction.max_ongoing_tasks, Ordering::Relaxed);
+        if let (Some(cache), Some(cap)) = (&self.sst_meta_cache, config.sst_meta_cache_cap) {
+            cache.set_capacity(cap);
+        }
+    }
+
+    /// Clear the dynamic options of the `config`, so two configs can be
+    /// compared by their static options.
+    pub fn clear_dynamic_options(config: &mut Config) {
+        let default = Config::default();
+        config.db_write_buffer_size = default.db_write_buffer_size;
+        config.compaction.max_ongoing_tasks = default.compaction.max_ongoing_tasks;
+        config.sst_meta_cache_cap = config
+            .sst_meta_cache_cap
+            .map(|_| default.sst_meta_cache_cap.unwrap_or_default());
+    }
+
+    #[inline]
+    pub fn db_write_buffer_size(&self) -> usize {
+        self.db_write_buffer_size.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn compaction_max_ongoing_tasks(&self) -> usize {
+        self.compaction_max_ongoing_tasks.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume the compaction, only the manual compactions are
+    /// scheduled while it's paused.
+    #[inline]
+    pub fn set_compaction_paused(&self, paused: bool) {
+        self.compaction_paused.store(paused, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn compaction_paused(&self) -> bool {
+        self.compaction_paused.load(Ordering::Relaxed)
+    }
+
+    /// The automatic compactions are held while the compaction is paused or
+    /// any backup is running.
+    #[inline]
+    pub fn auto_compaction_paused(&self) -> bool {
+        self.compaction_paused() || self.backup_leases.is_active()
+    }
+
+    #[inline]
+    pub fn backup_leases(&self) -> &BackupLeasesRef {
+        &self.backup_leases
+    }
+
+    #[inline]
+    pub fn recompression(&self) -> &RecompressionProgressRef {
+        &self.recompression
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sst::meta_data::cache::MetaCache;
+
+    #[test]
+    fn test_dynamic_config() {
+        let mut config = Config::default();
+        let sst_meta_cache = Arc::new(MetaCache::new(config.sst_meta_cache_cap.unwrap()));
+        let dynamic_config = DynamicConfig::new(&config, Some(sst_meta_cache.clone()));
+        assert_eq!(
+            config.compaction.max_ongoing_tasks,
+            dynamic_config.compaction_max_ongoing_tasks()
+        );
+
+        let mut new_config = config.clone();
+        new_config.db_write_buffer_size = 1024;
+        new_config.compaction.max_ongoing_tasks = 1;
+        new_config.sst_meta_cache_cap = Some(0);
+        dynamic_config.update(&new_config);
+        assert_eq!(1024, dynamic_config.db_write_buffer_size());
+        assert_eq!(1, dynamic_config.compaction_max_ongoing_tasks());
+        assert_eq!(0, sst_meta_cache.capacity());
+
+        dynamic_config.set_compaction_paused(true);
+        dynamic_config.update(&new_config);
+        assert!(dynamic_config.compaction_paused());
+
+        dynamic_config.set_compaction_paused(false);
+        let lease = dynamic_config
+            .backup_leases()
+            .acquire(std::time::Duration::from_secs(60));
+        assert!(dynamic_config.auto_compaction_paused());
+        assert!(dynamic_config.backup_leases().release(lease.id));
+        assert!(!dynamic_config.auto_compaction_paused());
+
+        // Only the dynamic options are changed.
+        DynamicConfig::clear_dynamic_options(&mut config);
+        DynamicConfig::clear_dynamic_options(&mut new_config);
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(&new_config).unwrap()
+        );
+    }
+}
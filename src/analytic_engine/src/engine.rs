@@ -28,9 +28,9 @@ use snafu::{OptionExt, ResultExt};
 use table_engine::{
     engine::{
         Close, CloseShardRequest, CloseTableRequest, CreateTableParams, CreateTableRequest,
-        DropTableRequest, OpenShard, OpenShardRequest, OpenShardResult, OpenTableNoCause,
-        OpenTableRequest, OpenTableWithCause, Result, ShardStats, TableDef, TableEngine,
-        TableEngineStats, Unexpected,
+        DropTableRequest, InvalidTableOptions, OpenShard, OpenShardRequest, OpenShardResult,
+        OpenTableNoCause, OpenTableRequest, OpenTableWithCause, Result, ShardStats, TableDef,
+        TableEngine, TableEngineStats, Unexpected,
     },
     table::{SchemaId, TableRef},
     ANALYTIC_ENGINE_TYPE,
@@ -117,6 +117,15 @@ impl TableEngine for TableEngineImpl {
         Ok(())
     }
 
+    async fn validate_table_options(&self, options: &HashMap<String, String>) -> Result<()> {
+        self.instance
+            .validate_table_options(options)
+            .box_err()
+            .context(InvalidTableOptions)?;
+
+        Ok(())
+    }
+
     async fn create_table(&self, request: CreateTableRequest) -> Result<TableRef> {
         let space_id = build_space_id(request.schema_id);
 
@@ -249,6 +258,12 @@ impl TableEngine for TableEngineImpl {
 
         Ok(Some(table_engine_stats))
     }
+
+    async fn shrink_caches(&self) -> Result<()> {
+        self.instance.shrink_caches();
+
+        Ok(())
+    }
 }
 
 /// Collect the table engine stats from the two provided metric.
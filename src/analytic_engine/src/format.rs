@@ -0,0 +1,397 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Format versions of the data persisted by the engine.
+//!
+//! The format versions of wal, manifest and sst are recorded in a marker file
+//! in the object store. The marker is checked before opening the engine:
+//! - Data written in a newer format than the supported one is refused;
+//! - Data written in an older format is upgraded by the [UPGRADE_STEPS], the
+//! old data keeps readable and the new data is written in the current format.
+//! Some steps also suggest rewriting the old ssts in background.
+
+use std::{collections::HashSet, fmt};
+
+use futures::TryStreamExt;
+use generic_error::{BoxError, GenericError};
+use logger::{info, warn};
+use macros::define_result;
+use object_store::{ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    sst::parquet::encoding::{META_VERSION_CURRENT, META_VERSION_V1},
+    table::sst_util,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Data of {} is written in format version {}, which is newer than the supported version {}, please upgrade the server before opening it",
+        component,
+        found,
+        supported
+    ))]
+    UnsupportedFutureVersion {
+        component: Component,
+        found: u32,
+        supported: u32,
+    },
+
+    #[snafu(display(
+        "No upgrade step for {} from format version {}, current version:{}",
+        component,
+        from,
+        current
+    ))]
+    MissingUpgradeStep {
+        component: Component,
+        from: u32,
+        current: u32,
+    },
+
+    #[snafu(display("Failed to load format marker, path:{}, err:{}", path, source))]
+    LoadMarker { path: String, source: GenericError },
+
+    #[snafu(display("Failed to store format marker, path:{}, err:{}", path, source))]
+    StoreMarker { path: String, source: GenericError },
+
+    #[snafu(display("Failed to list the data to detect the format, err:{}", source))]
+    ListData { source: GenericError },
+}
+
+define_result!(Error);
+
+/// Path of the marker file in the object store.
+const FORMAT_MARKER_PATH: &str = "format_version.json";
+
+/// Components persisting data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Wal,
+    Manifest,
+    Sst,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Wal => f.write_str("wal"),
+            Component::Manifest => f.write_str("manifest"),
+            Component::Sst => f.write_str("sst"),
+        }
+    }
+}
+
+/// Format versions of all the components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatVersions {
+    pub wal: u32,
+    pub manifest: u32,
+    pub sst: u32,
+}
+
+impl FormatVersions {
+    /// Format versions written by this server.
+    pub fn current() -> Self {
+        Self {
+            wal: 1,
+            manifest: 1,
+            sst: parse_sst_version(META_VERSION_CURRENT),
+        }
+    }
+
+    /// Detect the format versions of the data written before the marker is
+    /// introduced.
+    ///
+    /// Only one format of wal and manifest was ever written, and the ssts in
+    /// the format version 1 have no separate meta data file, so the sst version
+    /// is the version 1 if any sst has no meta data file.
+    async fn detect(store: &ObjectStoreRef) -> Result<Self> {
+        let mut ssts = Vec::new();
+        let mut paths = HashSet::new();
+        let mut objects = store.list(None).await.box_err().context(ListData)?;
+        while let Some(object) = objects.try_next().await.box_err().context(ListData)? {
+            let path = object.location.to_string();
+            if sst_util::is_sst_file_path(&path) {
+                ssts.push(path.clone());
+            }
+            paths.insert(path);
+        }
+
+        let has_v1_sst = ssts
+            .iter()
+            .any(|sst| !paths.contains(&sst_util::new_metadata_path(sst)));
+        let sst = if has_v1_sst {
+            parse_sst_version(META_VERSION_V1)
+        } else {
+            parse_sst_version(META_VERSION_CURRENT)
+        };
+
+        Ok(Self {
+            sst,
+            ..Self::current()
+        })
+    }
+
+    fn version_of(&self, component: Component) -> u32 {
+        match component {
+            Component::Wal => self.wal,
+            Component::Manifest => self.manifest,
+            Component::Sst => self.sst,
+        }
+    }
+}
+
+fn parse_sst_version(version: &str) -> u32 {
+    version.parse().expect("sst meta version must be a number")
+}
+
+/// Step to upgrade the format of `component` from version `from` to
+/// `from + 1`.
+///
+/// The data in version `from` must be still readable after the upgrade.
+struct UpgradeStep {
+    component: Component,
+    from: u32,
+    /// Whether the old data should be rewritten in the new format.
+    rewrite: bool,
+}
+
+const UPGRADE_STEPS: &[UpgradeStep] = &[
+    // The custom meta data of sst is moved out of the parquet footer.
+    UpgradeStep {
+        component: Component::Sst,
+        from: 1,
+        rewrite: true,
+    },
+];
+
+const COMPONENTS: [Component; 3] = [Component::Wal, Component::Manifest, Component::Sst];
+
+/// Result of the [preflight] check.
+#[derive(Debug, Clone)]
+pub struct FormatCheck {
+    /// The format versions before the check.
+    pub previous: FormatVersions,
+    /// Whether the ssts in the old format should be rewritten.
+    pub rewrite_ssts: bool,
+}
+
+/// Check the format versions of the persisted data, and upgrade the marker to
+/// the current versions if the data is in an older format.
+///
+/// The marker is left as is if the `store` is `read_only`.
+pub async fn preflight(store: &ObjectStoreRef, read_only: bool) -> Result<FormatCheck> {
+    let current = FormatVersions::current();
+    let (previous, has_marker) = match load_marker(store).await? {
+        Some(versions) => (versions, true),
+        None => {
+            let versions = FormatVersions::detect(store).await?;
+            warn!("Format marker not found, detected format versions:{versions:?}");
+            (versions, false)
+        }
+    };
+
+    let rewrite_ssts = check_upgrade(&previous, &current)?;
+    if (previous != current || !has_marker) && !read_only {
+        info!("Upgrade data format, previous:{previous:?}, current:{current:?}");
+        store_marker(store, &current).await?;
+    }
+
+    Ok(FormatCheck {
+        previous,
+        rewrite_ssts,
+    })
+}
+
+/// Check whether the data in `previous` format can be upgraded to `current`,
+/// returns whether the ssts should be rewritten.
+fn check_upgrade(previous: &FormatVersions, current: &FormatVersions) -> Result<bool> {
+    let mut rewrite_ssts = false;
+    for component in COMPONENTS {
+        let found = previous.version_of(component);
+        let supported = current.version_of(component);
+        ensure!(
+            found <= supported,
+            UnsupportedFutureVersion {
+                component,
+                found,
+                supported,
+            }
+        );
+
+        for from in found..supported {
+            let step = UPGRADE_STEPS
+                .iter()
+                .find(|step| step.component == component && step.from == from)
+                .context(MissingUpgradeStep {
+                    component,
+                    from,
+                    current: supported,
+                })?;
+            rewrite_ssts |= step.rewrite && component == Component::Sst;
+        }
+    }
+
+    Ok(rewrite_ssts)
+}
+
+async fn load_marker(store: &ObjectStoreRef) -> Result<Option<FormatVersions>> {
+    let path = Path::from(FORMAT_MARKER_PATH);
+    let get_res = store.get(&path).await;
+    if let Err(object_store::ObjectStoreError::NotFound { .. }) = &get_res {
+        return Ok(None);
+    }
+
+    let payload = get_res
+        .box_err()
+        .context(LoadMarker {
+            path: FORMAT_MARKER_PATH,
+        })?
+        .bytes()
+        .await
+        .box_err()
+        .context(LoadMarker {
+            path: FORMAT_MARKER_PATH,
+        })?;
+    let versions = serde_json::from_slice(&payload)
+        .box_err()
+        .context(LoadMarker {
+            path: FORMAT_MARKER_PATH,
+        })?;
+
+    Ok(Some(versions))
+}
+
+async fn store_marker(store: &ObjectStoreRef, versions: &FormatVersions) -> Result<()> {
+    let payload = serde_json::to_vec(versions)
+        .box_err()
+        .context(StoreMarker {
+            path: FORMAT_MARKER_PATH,
+        })?;
+    store
+        .put(&Path::from(FORMAT_MARKER_PATH), payload.into())
+        .await
+        .box_err()
+        .context(StoreMarker {
+            path: FORMAT_MARKER_PATH,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use object_store::LocalFileSystem;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_check_upgrade() {
+        let current = FormatVersions::current();
+        assert!(!check_upgrade(&current, &current).unwrap());
+        let v1_sst = FormatVersions {
+            sst: parse_sst_version(META_VERSION_V1),
+            ..current
+        };
+        assert!(check_upgrade(&v1_sst, &current).unwrap());
+
+        let future = FormatVersions {
+            manifest: current.manifest + 1,
+            ..current
+        };
+        let err = check_upgrade(&future, &current).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnsupportedFutureVersion {
+                component: Component::Manifest,
+                ..
+            }
+        ));
+
+        let too_old = FormatVersions { wal: 0, ..current };
+        assert!(check_upgrade(&too_old, &current).is_err());
+    }
+
+    fn new_store() -> (TempDir, ObjectStoreRef) {
+        let dir = TempDir::new().unwrap();
+        let store: ObjectStoreRef = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        (dir, store)
+    }
+
+    async fn put_object(store: &ObjectStoreRef, path: &str) {
+        store
+            .put(&Path::from(path), vec![0u8].into())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_detect_versions() {
+        let current = FormatVersions::current();
+        // No data at all.
+        let (_dir, store) = new_store();
+        assert_eq!(current, FormatVersions::detect(&store).await.unwrap());
+
+        // All the ssts have the meta data files.
+        put_object(&store, "0/1/1.sst").await;
+        put_object(&store, "0/1/1.sst.metadata").await;
+        assert_eq!(current, FormatVersions::detect(&store).await.unwrap());
+
+        // The sst in version 1 has no meta data file.
+        put_object(&store, "0/1/2.sst").await;
+        let versions = FormatVersions::detect(&store).await.unwrap();
+        assert_eq!(parse_sst_version(META_VERSION_V1), versions.sst);
+        assert_eq!(current.manifest, versions.manifest);
+    }
+
+    #[tokio::test]
+    async fn test_preflight() {
+        let (_dir, store) = new_store();
+        put_object(&store, "0/1/1.sst").await;
+
+        // The marker is not upgraded in the read-only mode.
+        let check = preflight(&store, true).await.unwrap();
+        assert_eq!(parse_sst_version(META_VERSION_V1), check.previous.sst);
+        assert!(load_marker(&store).await.unwrap().is_none());
+
+        let check = preflight(&store, false).await.unwrap();
+        assert_eq!(parse_sst_version(META_VERSION_V1), check.previous.sst);
+        assert!(check.rewrite_ssts);
+
+        let check = preflight(&store, false).await.unwrap();
+        assert_eq!(check.previous, FormatVersions::current());
+        assert!(!check.rewrite_ssts);
+
+        let future = FormatVersions {
+            sst: FormatVersions::current().sst + 1,
+            ..FormatVersions::current()
+        };
+        store_marker(&store, &future).await.unwrap();
+        assert!(preflight(&store, false).await.is_err());
+
+        // The marker is stored for the new data.
+        let (_dir, store) = new_store();
+        let check = preflight(&store, false).await.unwrap();
+        assert_eq!(check.previous, FormatVersions::current());
+        assert!(!check.rewrite_ssts);
+        assert!(load_marker(&store).await.unwrap().is_some());
+    }
+}
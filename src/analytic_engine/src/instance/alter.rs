@@ -17,12 +17,17 @@
 
 //! Alter [Schema] and [TableOptions] logic of instance.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use generic_error::BoxError;
 use logger::info;
 use snafu::{ensure, ResultExt};
-use table_engine::table::AlterSchemaRequest;
+use table_engine::{
+    alter_diff,
+    event::{self, EventKind},
+    histogram::{ColumnHistograms, Histogram},
+    table::AlterSchemaRequest,
+};
 use wal::{kv_encoder::LogBatchEncoder, manager::WriteContext};
 
 use crate::{
@@ -40,8 +45,8 @@ use crate::{
         AlterOptionsMeta, AlterSchemaMeta, MetaEdit, MetaEditRequest, MetaUpdate,
     },
     payload::WritePayload,
-    table::data::TableDataRef,
-    table_options,
+    table::data::{TableData, TableDataRef},
+    table_options::{self, TableOptions},
 };
 
 pub struct Alterer<'a> {
@@ -76,13 +81,14 @@ impl<'a> Alterer<'a> {
 
         // Validate alter schema request.
         // if the alter schema request is idempotent, we can skip the alter operation.
-        if self.validate_before_alter(&request)? {
+        if validate_alter_schema(&self.table_data, &request)? {
             info!(
                 "Skip alter because of the altered schema is the same as the current, table:{}",
                 self.table_data.name
             );
             return Ok(());
         }
+        let current_schema = self.table_data.schema();
 
         // Now we can persist and update the schema, since this function is called by
         // write worker, so there is no other concurrent writer altering the
@@ -162,46 +168,24 @@ impl<'a> Alterer<'a> {
                 table: &self.table_data.name,
                 table_id: self.table_data.id,
             })?;
-
-        Ok(())
-    }
-
-    // Most validation should be done by catalog module, so we don't do too much
-    // duplicate check here, especially the schema compatibility.
-    // The returned value denotes whether the altered schema is same as the current
-    // one.
-    fn validate_before_alter(&self, request: &AlterSchemaRequest) -> Result<bool> {
-        ensure!(
-            !self.table_data.is_dropped(),
-            AlterDroppedTable {
-                table: &self.table_data.name,
-            }
+        event::record(
+            EventKind::AlterSchema,
+            &self.table_data.name,
+            self.table_data.id,
+            alter_diff::format_diffs(&alter_diff::schema_diffs(&current_schema, &request.schema)),
         );
 
-        if self.table_data.schema().columns() == request.schema.columns() {
-            return Ok(true);
+        // The options referring to the columns by name follow the renamed and
+        // dropped columns.
+        let current_table_options = self.table_data.table_options();
+        if let Some(table_opts) =
+            current_table_options.with_altered_columns(&current_schema, &request.schema)
+        {
+            self.persist_options(&current_table_options, table_opts)
+                .await?;
         }
 
-        let current_version = self.table_data.schema_version();
-        ensure!(
-            current_version < request.schema.version(),
-            InvalidSchemaVersion {
-                table: &self.table_data.name,
-                current_version,
-                given_version: request.schema.version(),
-            }
-        );
-
-        ensure!(
-            current_version == request.pre_schema_version,
-            InvalidPreVersion {
-                table: &self.table_data.name,
-                current_version,
-                pre_version: request.pre_schema_version,
-            }
-        );
-
-        Ok(false)
+        Ok(())
     }
 
     pub async fn alter_options_of_table(
@@ -214,13 +198,6 @@ impl<'a> Alterer<'a> {
             self.table_data.name, options
         );
 
-        ensure!(
-            !self.table_data.is_dropped(),
-            AlterDroppedTable {
-                table: &self.table_data.name,
-            }
-        );
-
         // AlterOptions doesn't need a flush.
 
         // Generate options after alter op
@@ -229,16 +206,49 @@ impl<'a> Alterer<'a> {
             "Instance alter options, space_id:{}, tables:{:?}, old_table_opts:{:?}, options:{:?}",
             self.table_data.space_id, self.table_data.name, current_table_options, options
         );
-        let table_opts = {
-            let mut opts =
-                table_options::merge_table_options_for_alter(&options, &current_table_options)
-                    .box_err()
-                    .context(InvalidOptions {
-                        table: &self.table_data.name,
-                    })?;
-            opts.sanitize();
-            opts
-        };
+        let table_opts = build_altered_options(&self.table_data, &options)?;
+        self.persist_options(&current_table_options, table_opts)
+            .await
+    }
+
+    /// Update the histograms of the columns, the histograms of the columns
+    /// mapped to `None` are dropped.
+    ///
+    /// The histograms are persisted with the options, so they are updated the
+    /// same way as altering the options.
+    pub async fn alter_histograms_of_table(
+        &self,
+        histograms: HashMap<String, Option<Histogram>>,
+    ) -> Result<()> {
+        ensure!(
+            !self.table_data.is_dropped(),
+            AlterDroppedTable {
+                table: &self.table_data.name,
+            }
+        );
+
+        let current_table_options = self.table_data.table_options();
+        let mut table_opts = TableOptions::clone(&current_table_options);
+        let mut current_histograms = ColumnHistograms::clone(&current_table_options.histograms);
+        for (column, histogram) in histograms {
+            match histogram {
+                Some(v) => current_histograms.insert(column, v),
+                None => current_histograms.remove(&column),
+            };
+        }
+        table_opts.histograms = Arc::new(current_histograms);
+
+        self.persist_options(&current_table_options, table_opts)
+            .await
+    }
+
+    /// Persist the options into the wal and the manifest, and the options of
+    /// the table are updated by the manifest.
+    async fn persist_options(
+        &self,
+        current_table_options: &TableOptions,
+        table_opts: TableOptions,
+    ) -> Result<()> {
         let manifest_update = AlterOptionsMeta {
             space_id: self.table_data.space_id,
             table_id: self.table_data.id,
@@ -296,7 +306,86 @@ impl<'a> Alterer<'a> {
                 table: &self.table_data.name,
                 table_id: self.table_data.id,
             })?;
+        event::record(
+            EventKind::AlterOptions,
+            &self.table_data.name,
+            self.table_data.id,
+            alter_diff::format_diffs(&alter_diff::options_diffs(
+                &current_table_options.to_raw_map(),
+                &table_opts.to_raw_map(),
+            )),
+        );
 
         Ok(())
     }
 }
+
+// Most validation should be done by catalog module, so we don't do too much
+// duplicate check here, especially the schema compatibility.
+// The returned value denotes whether the altered schema is same as the current
+// one.
+pub(crate) fn validate_alter_schema(
+    table_data: &TableData,
+    request: &AlterSchemaRequest,
+) -> Result<bool> {
+    ensure!(
+        !table_data.is_dropped(),
+        AlterDroppedTable {
+            table: &table_data.name,
+        }
+    );
+
+    if table_data.schema().columns() == request.schema.columns() {
+        return Ok(true);
+    }
+
+    let current_version = table_data.schema_version();
+    ensure!(
+        current_version < request.schema.version(),
+        InvalidSchemaVersion {
+            table: &table_data.name,
+            current_version,
+            given_version: request.schema.version(),
+        }
+    );
+
+    ensure!(
+        current_version == request.pre_schema_version,
+        InvalidPreVersion {
+            table: &table_data.name,
+            current_version,
+            pre_version: request.pre_schema_version,
+        }
+    );
+
+    Ok(false)
+}
+
+/// Build the validated and sanitized options of the table after altering the
+/// given options.
+pub(crate) fn build_altered_options(
+    table_data: &TableData,
+    options: &HashMap<String, String>,
+) -> Result<TableOptions> {
+    ensure!(
+        !table_data.is_dropped(),
+        AlterDroppedTable {
+            table: &table_data.name,
+        }
+    );
+
+    let current_table_options = table_data.table_options();
+    let mut opts = table_options::merge_table_options_for_alter(options, &current_table_options)
+        .box_err()
+        .context(InvalidOptions {
+            table: &table_data.name,
+        })?;
+    opts.validate_with_schema(&table_data.schema())
+        .box_err()
+        .context(InvalidOptions {
+            table: &table_data.name,
+        })?;
+    opts.sanitize();
+
+    Ok(opts)
+}
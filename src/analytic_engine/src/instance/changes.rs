@@ -0,0 +1,184 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Read the changes written into the tables from the wal.
+//!
+//! The changes after the last read sequence of a consumer are retained in the
+//! wal even if they are flushed, see [ChangeRetention]. The changes are
+//! expired if the consumer comes back after the retention, and it has to start
+//! from a new snapshot of the table.
+//!
+//! [ChangeRetention]: crate::table::change_retention::ChangeRetention
+
+use std::{collections::VecDeque, time::Instant};
+
+use common_types::{
+    record_batch::{FetchedRecordBatchBuilder, RecordBatch},
+    row::RowGroup,
+};
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use snafu::{ResultExt, Snafu};
+use table_engine::table::{ReadChangesRequest, TableChange, TableChanges};
+use wal::manager::{ReadBoundary, ReadContext, ReadRequest};
+
+use crate::{
+    instance::{self, Instance},
+    payload::{ReadPayload, SingleSchemaProviderAdapter, WalDecoder},
+    table::data::TableDataRef,
+};
+
+#[derive(Debug, Snafu)]
+pub(crate) enum Error {
+    #[snafu(display("Failed to read changes from wal, table:{table}, err:{source}"))]
+    ReadWal { table: String, source: GenericError },
+
+    #[snafu(display("Failed to load change retention, table:{table}, err:{source}"))]
+    LoadRetention {
+        table: String,
+        source: crate::table::change_retention::Error,
+    },
+
+    #[snafu(display("Failed to build rows of change, table:{table}, err:{source}"))]
+    BuildRows {
+        table: String,
+        source: common_types::record_batch::Error,
+    },
+}
+
+define_result!(Error);
+
+impl Instance {
+    pub(crate) async fn read_table_changes(
+        &self,
+        table_data: &TableDataRef,
+        request: ReadChangesRequest,
+    ) -> Result<TableChanges> {
+        // The consumers retained before the table is opened are loaded first.
+        let store = self.space_store.store_picker().default_store();
+        table_data
+            .change_retention
+            .load(store, table_data.id)
+            .await
+            .context(LoadRetention {
+                table: &table_data.name,
+            })?;
+
+        let after = request.after.unwrap_or_else(|| table_data.last_sequence());
+        let prev_retained = table_data.change_retention.retain(
+            &request.consumer,
+            after,
+            self.change_retention_ttl.0,
+            Instant::now(),
+        );
+
+        // The changes flushed before are only kept for the consumer retaining
+        // them.
+        let flushed_sequence = table_data.current_version().flushed_sequence();
+        let retained = prev_retained.is_some_and(|retained| retained <= after);
+        if after < flushed_sequence && !retained {
+            return Ok(TableChanges {
+                after,
+                changes: Vec::new(),
+                expired: true,
+            });
+        }
+        if after >= table_data.last_sequence() {
+            return Ok(TableChanges {
+                after,
+                ..Default::default()
+            });
+        }
+
+        let table = &table_data.name;
+        let table_location = table_data.table_location();
+        let wal_location =
+            instance::create_wal_location(table_location.id, table_location.shard_info);
+        let read_req = ReadRequest {
+            location: wal_location,
+            start: ReadBoundary::Excluded(after),
+            end: ReadBoundary::Max,
+        };
+        let read_ctx = ReadContext {
+            batch_size: self.replay_batch_size,
+            ..Default::default()
+        };
+        let mut log_iter = self
+            .space_store
+            .wal_manager
+            .read_batch(&read_ctx, &read_req)
+            .await
+            .box_err()
+            .context(ReadWal { table })?;
+
+        let table_id = table_data.id.as_u64();
+        let mut changes = Vec::new();
+        let mut num_rows = 0;
+        let mut log_entry_buf = VecDeque::with_capacity(self.replay_batch_size);
+        while num_rows < request.max_rows || changes.is_empty() {
+            let decoder = WalDecoder::new(SingleSchemaProviderAdapter {
+                schema: table_data.schema(),
+            });
+            let filter = move |id| id == table_id;
+            log_entry_buf = log_iter
+                .next_log_entries(decoder, filter, log_entry_buf)
+                .await
+                .box_err()
+                .context(ReadWal { table })?;
+            if log_entry_buf.is_empty() {
+                break;
+            }
+
+            for log_entry in log_entry_buf.drain(..) {
+                // The schema changes are not exposed as the rows carry their
+                // schemas.
+                let ReadPayload::Write { row_group } = log_entry.payload else {
+                    continue;
+                };
+                let rows = row_group_to_record_batch(row_group)
+                    .context(BuildRows { table })?;
+                num_rows += rows.num_rows();
+                changes.push(TableChange {
+                    sequence: log_entry.sequence,
+                    rows,
+                });
+                if num_rows >= request.max_rows {
+                    break;
+                }
+            }
+        }
+
+        Ok(TableChanges {
+            after,
+            changes,
+            expired: false,
+        })
+    }
+}
+
+fn row_group_to_record_batch(
+    mut row_group: RowGroup,
+) -> common_types::record_batch::Result<RecordBatch> {
+    let record_schema = row_group.schema().to_record_schema();
+    let mut builder =
+        FetchedRecordBatchBuilder::with_capacity(record_schema, None, row_group.num_rows());
+    for row in row_group.take_rows() {
+        builder.append_row(row)?;
+    }
+
+    Ok(builder.build()?.into_record_batch())
+}
@@ -17,20 +17,32 @@
 
 //! Close table logic of instance
 
-use logger::{info, warn};
+use std::{collections::HashMap, sync::Mutex};
+
+use common_types::table::ShardId;
+use futures::{stream, StreamExt};
+use logger::{error, info, warn};
 use snafu::ResultExt;
-use table_engine::engine::CloseTableRequest;
+use table_engine::{
+    engine::CloseTableRequest,
+    event::{self, EventKind},
+};
 
 use crate::{
     instance::{
         engine::{DoManifestSnapshot, FlushTable, Result},
         flush_compaction::{Flusher, TableFlushOptions},
+        Instance,
     },
     manifest::{ManifestRef, SnapshotRequest},
+    shutdown::{self, CleanShutdownMarker},
     space::SpaceRef,
     table::data::TableDataRef,
 };
 
+/// Max number of tables flushed concurrently on shutdown.
+const SHUTDOWN_FLUSH_CONCURRENCY: usize = 8;
+
 pub(crate) struct Closer {
     pub space: SpaceRef,
     pub manifest: ManifestRef,
@@ -67,6 +79,12 @@ impl Closer {
         // Table is already moved out of space, we should close it to stop background
         // jobs.
         table_data.set_closed();
+        event::record(
+            EventKind::TableClose,
+            &table_data.name,
+            table_data.id,
+            String::new(),
+        );
 
         info!(
             "table:{}-{} has been removed from the space_id:{}",
@@ -105,3 +123,75 @@ impl Closer {
             })
     }
 }
+
+impl Instance {
+    /// Flush all the opened tables within the configured deadline before
+    /// shutting down, so that the wal replay can be skipped on the next
+    /// startup for the tables flushed completely.
+    ///
+    /// Failures are only logged because they should not block the shutdown.
+    pub(crate) async fn flush_tables_before_shutdown(&self) {
+        let mut tables = Vec::new();
+        self.space_store.list_all_tables(&mut tables);
+        if tables.is_empty() {
+            return;
+        }
+
+        let table_num = tables.len();
+        let timeout = self.shutdown.flush_timeout.0;
+        info!("Flush tables before shutdown begin, table_num:{table_num}, timeout:{timeout:?}");
+
+        let flusher = self.make_flusher();
+        let markers: Mutex<HashMap<ShardId, CleanShutdownMarker>> = Mutex::new(HashMap::new());
+        let flush_tables =
+            stream::iter(tables).for_each_concurrent(SHUTDOWN_FLUSH_CONCURRENCY, |table_data| {
+                let flusher = &flusher;
+                let markers = &markers;
+                async move {
+                    let mut serial_exec = table_data.serial_exec.lock().await;
+                    let flush_scheduler = serial_exec.flush_scheduler();
+                    if let Err(e) = flusher
+                        .do_flush(flush_scheduler, &table_data, TableFlushOptions::default())
+                        .await
+                    {
+                        warn!(
+                            "Failed to flush table before shutdown, table:{}, table_id:{}, err:{e}",
+                            table_data.name, table_data.id
+                        );
+                        return;
+                    }
+
+                    let flushed_sequence = table_data.current_version().flushed_sequence();
+                    if table_data.last_sequence() <= flushed_sequence {
+                        let shard_id = table_data.shard_info.shard_id;
+                        markers
+                            .lock()
+                            .unwrap()
+                            .entry(shard_id)
+                            .or_default()
+                            .add_table(table_data.id, flushed_sequence);
+                    }
+                }
+            });
+
+        if tokio::time::timeout(timeout, flush_tables).await.is_err() {
+            warn!(
+                "Flush tables before shutdown timeout, table_num:{table_num}, timeout:{timeout:?}"
+            );
+        }
+
+        let markers = markers.into_inner().unwrap();
+        let flushed: usize = markers.values().map(|marker| marker.len()).sum();
+        info!("Flush tables before shutdown finish, table_num:{table_num}, flushed:{flushed}");
+
+        if !self.shutdown.skip_replay_after_clean_shutdown {
+            return;
+        }
+        let store = self.space_store.store_picker().default_store();
+        for (shard_id, marker) in markers {
+            if let Err(e) = shutdown::store_marker(store, shard_id, &marker).await {
+                error!("Failed to store clean shutdown marker, shard_id:{shard_id}, err:{e}");
+            }
+        }
+    }
+}
@@ -17,11 +17,14 @@
 
 //! Create table logic of instance
 
+use std::collections::HashMap;
+
 use generic_error::BoxError;
 use logger::info;
 use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::{
     engine::{CreateTableParams, CreateTableRequest},
+    event::{self, EventKind},
     partition::PartitionInfo,
 };
 
@@ -48,6 +51,12 @@ impl Instance {
                 .context(InvalidOptions {
                     table: &params.table_name,
                 })?;
+        table_opts
+            .validate_with_schema(&params.table_schema)
+            .box_err()
+            .context(InvalidOptions {
+                table: &params.table_name,
+            })?;
 
         if let Some(partition_info) = &params.partition_info {
             let dedup_on_random_partition =
@@ -64,6 +73,15 @@ impl Instance {
         Ok(table_opts)
     }
 
+    /// Validate the table options not bound to a table yet by merging them
+    /// into the global table options.
+    pub fn validate_table_options(
+        &self,
+        options: &HashMap<String, String>,
+    ) -> table_options::Result<TableOptions> {
+        table_options::merge_table_options_for_create(options, &self.table_opts)
+    }
+
     /// Create table need to be handled by write worker.
     pub async fn do_create_table(
         &self,
@@ -115,6 +133,12 @@ impl Instance {
                 table: &request.params.table_name,
                 table_id: request.table_id,
             })?;
+        event::record(
+            EventKind::TableCreate,
+            &request.params.table_name,
+            request.table_id,
+            format!("shard_id:{}", request.shard_id),
+        );
 
         // Table is sure to exist here.
         space
@@ -20,7 +20,10 @@
 use common_types::MAX_SEQUENCE_NUMBER;
 use logger::{info, warn};
 use snafu::ResultExt;
-use table_engine::engine::DropTableRequest;
+use table_engine::{
+    engine::DropTableRequest,
+    event::{self, EventKind},
+};
 
 use crate::{
     instance::{
@@ -104,6 +107,12 @@ impl Dropper {
                 table: &table_data.name,
                 table_id: table_data.id,
             })?;
+        event::record(
+            EventKind::TableDrop,
+            &table_data.name,
+            table_data.id,
+            String::new(),
+        );
 
         Ok(true)
     }
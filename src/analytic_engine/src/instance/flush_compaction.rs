@@ -32,10 +32,14 @@ use futures::{
     stream, SinkExt, StreamExt, TryStreamExt,
 };
 use generic_error::{BoxError, GenericError};
-use logger::{debug, error, info};
+use logger::{debug, error, info, warn};
 use macros::define_result;
 use runtime::RuntimeRef;
 use snafu::{Backtrace, ResultExt, Snafu};
+use table_engine::{
+    event::{self, EventKind},
+    task::{TaskKind, TaskRegistryRef, TaskSpec},
+};
 use time_ext::{self, ReadableDuration};
 use tokio::{sync::oneshot, time::Instant};
 use wal::manager::WalLocation;
@@ -47,7 +51,7 @@ use crate::{
     manifest::meta_edit::{
         AlterOptionsMeta, AlterSchemaMeta, MetaEdit, MetaEditRequest, MetaUpdate, VersionEditMeta,
     },
-    memtable::{ColumnarIterPtr, MemTableRef, ScanContext, ScanRequest},
+    memtable::{self, ColumnarIterPtr, MemTableRef, ScanContext, ScanRequest},
     sst::{
         factory::{self, SstWriteOptions},
         file::{FileMeta, Level},
@@ -58,6 +62,7 @@ use crate::{
         version::{FlushableMemTables, MemTableState, SamplingMemTable},
         version_edit::AddFile,
     },
+    row_iter::dedup::RowMerger,
     table_options::StorageFormatHint,
 };
 
@@ -156,8 +161,24 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Failed to move sst to the cold tier, path:{}, err:{}", path, source))]
+    MigrateSst { path: String, source: GenericError },
+
+    #[snafu(display("Failed to read footer of sst, path:{}, err:{}", path, source))]
+    ReadSstFooter { path: String, source: GenericError },
+
+    #[snafu(display("Failed to find the tier of sst, path:{}, err:{}", path, source))]
+    FindSstTier { path: String, source: GenericError },
+
     #[snafu(display("Failed to alloc file id, err:{}", source))]
     AllocFileId { source: data::Error },
+
+    #[snafu(display(
+        "Schema of the imported data mismatches the table, table:{}.\nBacktrace:\n{}",
+        table,
+        backtrace
+    ))]
+    ImportSchemaMismatch { table: String, backtrace: Backtrace },
 }
 
 define_result!(Error);
@@ -200,6 +221,7 @@ pub struct Flusher {
     pub space_store: SpaceStoreRef,
 
     pub runtime: RuntimeRef,
+    pub task_registry: TaskRegistryRef,
     pub write_sst_max_buffer_size: usize,
     /// If the interval is set, it will generate a [`FlushTask`] with min flush
     /// interval check.
@@ -277,7 +299,9 @@ impl Flusher {
             write_sst_max_buffer_size: self.write_sst_max_buffer_size,
             min_flush_interval_ms: self.min_flush_interval_ms,
         };
-        let flush_job = async move { flush_task.run().await };
+        let spec = TaskSpec::new(TaskKind::Flush, table_data.name.clone());
+        let task_registry = self.task_registry.clone();
+        let flush_job = async move { task_registry.run(spec, || flush_task.run()).await };
 
         flush_scheduler
             .flush_sequentially(flush_job, block_on, opts, &self.runtime, table_data.clone())
@@ -310,6 +334,15 @@ impl FlushTask {
         }
 
         let request_id = RequestId::next_id();
+        event::record(
+            EventKind::FlushBegin,
+            &self.table_data.name,
+            self.table_data.id,
+            format!(
+                "request_id:{request_id}, memtable_num:{}",
+                mems_to_flush.len()
+            ),
+        );
 
         // Start flush duration timer.
         let local_metrics = self.table_data.metrics.local_flush_metrics();
@@ -327,12 +360,16 @@ impl FlushTask {
         self.table_data
             .set_last_flush_time(time_ext::current_time_millis());
 
+        let cost_ms = instant.elapsed().as_millis();
         info!(
             "Instance flush memtables done, table:{}, table_id:{}, request_id:{}, cost:{}ms",
-            self.table_data.name,
+            self.table_data.name, self.table_data.id, request_id, cost_ms
+        );
+        event::record(
+            EventKind::FlushFinish,
+            &self.table_data.name,
             self.table_data.id,
-            request_id,
-            instant.elapsed().as_millis()
+            format!("request_id:{request_id}, cost:{cost_ms}ms"),
         );
 
         Ok(())
@@ -527,18 +564,46 @@ impl FlushTask {
             .apply_edit(edit_req)
             .await
             .context(StoreVersionEdit)?;
+        self.table_data.update_memtable_metrics();
+
+        // The retention of the table changes is persisted before the wal entries
+        // are deleted, so the retained changes are still found after the table
+        // is reopened. The wal is not purged until it's persisted.
+        let store = self.space_store.store_picker().default_store();
+        let retention = &self.table_data.change_retention;
+        let table_id = self.table_data.id;
+        let persist_res = match retention.load(store, table_id).await {
+            Ok(()) => retention.persist(store, table_id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = persist_res {
+            warn!(
+                "Failed to persist change retention, skip purging wal, table:{}, err:{}",
+                self.table_data.name, e
+            );
+            return Ok(());
+        }
 
-        // Mark sequence <= flushed_sequence to be deleted.
+        // Mark sequence <= flushed_sequence to be deleted, except the ones still
+        // needed by the consumers of the table changes.
+        let purge_sequence = match self
+            .table_data
+            .change_retention
+            .min_retained(std::time::Instant::now())
+        {
+            Some(retained) => retained.min(flushed_sequence),
+            None => flushed_sequence,
+        };
         let table_location = self.table_data.table_location();
         let wal_location =
             instance::create_wal_location(table_location.id, table_location.shard_info);
         self.space_store
             .wal_manager
-            .mark_delete_entries_up_to(wal_location, flushed_sequence)
+            .mark_delete_entries_up_to(wal_location, purge_sequence)
             .await
             .context(PurgeWal {
                 wal_location,
-                sequence: flushed_sequence,
+                sequence: purge_sequence,
             })?;
 
         Ok(())
@@ -575,10 +640,17 @@ impl FlushTask {
 
         let sst_write_options = SstWriteOptions {
             storage_format_hint: self.table_data.table_options().storage_format_hint,
-            num_rows_per_row_group: self.table_data.table_options().num_rows_per_row_group,
+            num_rows_per_row_group: self.table_data.num_rows_per_row_group_to_build(),
+            data_page_size: self.table_data.table_options().data_page_size.as_byte() as usize,
             compression: self.table_data.table_options().compression,
+            compression_level: self.table_data.table_options().compression_level,
+            column_options: self.table_data.table_options().column_options.clone(),
+            adaptive_compression: self.table_data.table_options().adaptive_compression,
+            separated_fields: self.table_data.table_options().separated_fields.clone(),
             max_buffer_size: self.write_sst_max_buffer_size,
             column_stats: Default::default(),
+            bloom_filter: self.table_data.table_options().bloom_filter_options(),
+            recompressed: false,
         };
 
         for time_range in &time_ranges {
@@ -705,7 +777,7 @@ impl FlushTask {
                     time_range: sst_info.time_range,
                     max_seq: sst_meta.max_sequence,
                     storage_format: sst_info.storage_format,
-                    associated_files: vec![sst_info.meta_path],
+                    associated_files: sst_info.associated_files(),
                 },
             })
         }
@@ -748,10 +820,17 @@ impl FlushTask {
         let storage_format_hint = self.table_data.table_options().storage_format_hint;
         let sst_write_options = SstWriteOptions {
             storage_format_hint,
-            num_rows_per_row_group: self.table_data.table_options().num_rows_per_row_group,
+            num_rows_per_row_group: self.table_data.num_rows_per_row_group_to_build(),
+            data_page_size: self.table_data.table_options().data_page_size.as_byte() as usize,
             compression: self.table_data.table_options().compression,
+            compression_level: self.table_data.table_options().compression_level,
+            column_options: self.table_data.table_options().column_options.clone(),
+            adaptive_compression: self.table_data.table_options().adaptive_compression,
+            separated_fields: self.table_data.table_options().separated_fields.clone(),
             max_buffer_size: self.write_sst_max_buffer_size,
             column_stats: Default::default(),
+            bloom_filter: self.table_data.table_options().bloom_filter_options(),
+            recompressed: false,
         };
         let mut writer = self
             .space_store
@@ -805,7 +884,7 @@ impl FlushTask {
             time_range: sst_info.time_range,
             max_seq: memtable_state.last_sequence(),
             storage_format: sst_info.storage_format,
-            associated_files: vec![sst_info.meta_path],
+            associated_files: sst_info.associated_files(),
         }))
     }
 }
@@ -861,11 +940,12 @@ fn build_mem_table_iter(
     memtable: MemTableRef,
     table_data: &TableDataRef,
 ) -> Result<ColumnarIterPtr> {
+    let table_options = table_data.table_options();
     let scan_ctx = ScanContext::default();
     let projected_schema = ProjectedSchema::no_projection(table_data.schema());
-    let fetched_schema = projected_schema.to_record_schema_with_key();
-    let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
-    let fetched_schema = fetched_schema.into_record_schema();
+    let schema_with_key = projected_schema.to_record_schema_with_key();
+    let primary_key_indexes = schema_with_key.primary_key_idx().to_vec();
+    let fetched_schema = schema_with_key.to_record_schema();
     let table_schema = projected_schema.table_schema().clone();
     let row_projector_builder =
         RowProjectorBuilder::new(fetched_schema, table_schema, Some(primary_key_indexes));
@@ -874,15 +954,41 @@ fn build_mem_table_iter(
         end_user_key: Bound::Unbounded,
         sequence: common_types::MAX_SEQUENCE_NUMBER,
         row_projector_builder,
-        need_dedup: table_data.dedup(),
+        need_dedup: table_options.need_dedup_memtable(),
         reverse: false,
         metrics_collector: None,
         time_range: TimeRange::min_to_max(),
     };
-    memtable
+    let iter = memtable
         .scan(scan_ctx, scan_req)
         .box_err()
-        .context(InvalidMemIter)
+        .context(InvalidMemIter)?;
+
+    // Merge the duplicate rows before flushing, so the merged row rather than
+    // all the rows with the same key is written into the sst.
+    let merger = table_options
+        .dedup_merge_policy()
+        .and_then(|merge_policy| RowMerger::new(merge_policy, schema_with_key));
+    let Some(mut merger) = merger else {
+        return Ok(iter);
+    };
+    let mut iter = iter.fuse();
+    let merged_iter = std::iter::from_fn(move || match iter.next() {
+        Some(record_batch) => Some(record_batch.and_then(|v| {
+            merger.merge_batch(v).box_err().context(memtable::Internal {
+                msg: "failed to merge duplicate rows",
+            })
+        })),
+        None => merger
+            .finish()
+            .box_err()
+            .context(memtable::Internal {
+                msg: "failed to merge the last duplicate rows",
+            })
+            .transpose(),
+    });
+
+    Ok(Box::new(merged_iter))
 }
 
 #[cfg(test)]
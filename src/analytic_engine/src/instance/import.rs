@@ -0,0 +1,216 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Import the data into the table directly as ssts.
+//!
+//! The imported data bypasses the wal and the memtable: it is sorted by the
+//! primary key, written into a new sst of level 0 and then added to the table
+//! version by a single version edit, so an import is either fully visible or
+//! not visible at all.
+
+use common_types::{
+    record_batch::{FetchedRecordBatch, RecordBatch},
+    request_id::RequestId,
+    schema::RecordSchema,
+};
+use futures::StreamExt;
+use generic_error::BoxError;
+use logger::info;
+use snafu::{ensure, ResultExt};
+
+use crate::{
+    instance::{
+        flush_compaction::{
+            AllocFileId, CreateSstWriter, ImportSchemaMismatch, ReorderMemIter, Result,
+            StoreVersionEdit, WriteSst,
+        },
+        reorder_memtable::Reorder,
+        Instance,
+    },
+    manifest::meta_edit::{MetaEdit, MetaEditRequest, MetaUpdate, VersionEditMeta},
+    memtable::ColumnarIterPtr,
+    sst::{
+        factory::SstWriteOptions,
+        file::{FileMeta, Level},
+        writer::MetaData,
+    },
+    table::{data::TableDataRef, version_edit::AddFile},
+};
+
+impl Instance {
+    /// Import the `batches` into the table as a new sst.
+    ///
+    /// The schema of the batches must be the same as the table schema. The
+    /// imported rows are treated as written before the rows not flushed yet,
+    /// so the latter win if they have the same primary keys.
+    ///
+    /// Returns the number of the imported rows.
+    pub(crate) async fn import_to_table(
+        &self,
+        table_data: &TableDataRef,
+        batches: Vec<RecordBatch>,
+    ) -> Result<usize> {
+        let schema = table_data.schema();
+        let record_schema = schema.to_record_schema();
+        let mut fetched_batches = Vec::with_capacity(batches.len());
+        for batch in batches {
+            ensure!(
+                has_same_columns(batch.schema(), &record_schema),
+                ImportSchemaMismatch {
+                    table: &table_data.name,
+                }
+            );
+            if batch.is_empty() {
+                continue;
+            }
+
+            let batch = FetchedRecordBatch::new_from_parts(
+                record_schema.clone(),
+                None,
+                batch.into_record_batch_data(),
+            );
+            fetched_batches.push(batch);
+        }
+        if fetched_batches.is_empty() {
+            return Ok(0);
+        }
+
+        let space_store = &self.space_store;
+        let file_id = table_data
+            .alloc_file_id(&space_store.manifest)
+            .await
+            .context(AllocFileId)?;
+        let sst_file_path = table_data.sst_file_path(file_id);
+        let table_options = table_data.table_options();
+        let storage_format_hint = table_options.storage_format_hint;
+        let sst_write_options = SstWriteOptions {
+            storage_format_hint,
+            num_rows_per_row_group: table_data.num_rows_per_row_group_to_build(),
+            data_page_size: table_options.data_page_size.as_byte() as usize,
+            compression: table_options.compression,
+            compression_level: table_options.compression_level,
+            column_options: table_options.column_options.clone(),
+            adaptive_compression: table_options.adaptive_compression,
+            separated_fields: table_options.separated_fields.clone(),
+            max_buffer_size: self.write_sst_max_buffer_size,
+            column_stats: Default::default(),
+            bloom_filter: table_options.bloom_filter_options(),
+            recompressed: false,
+        };
+        let mut writer = space_store
+            .sst_factory
+            .create_writer(
+                &sst_write_options,
+                &sst_file_path,
+                space_store.store_picker(),
+                Level::MIN,
+            )
+            .await
+            .context(CreateSstWriter {
+                storage_format_hint,
+            })?;
+
+        let reorder = Reorder {
+            iter: Box::new(fetched_batches.into_iter().map(Ok)) as ColumnarIterPtr,
+            order_by_col_indexes: schema.primary_key_indexes().to_vec(),
+            schema: schema.clone(),
+        };
+        let record_batch_stream = reorder
+            .into_stream()
+            .await
+            .context(ReorderMemIter)?
+            .map(|batch| batch.box_err());
+
+        // The sst is not dumped from the memtables, so its max sequence must not
+        // exceed the flushed sequence, or the compaction of it moves the flushed
+        // sequence past the rows in the memtables, which are then skipped by the
+        // wal replay.
+        let max_sequence = table_data.current_version().flushed_sequence();
+        // TODO: `min_key` & `max_key` should be figured out when writing sst.
+        let sst_meta = MetaData {
+            min_key: Default::default(),
+            max_key: Default::default(),
+            // The real time range is figured out by the sst writer.
+            time_range: Default::default(),
+            max_sequence,
+            schema,
+        };
+        let sst_info = writer
+            .write(
+                RequestId::next_id(),
+                &sst_meta,
+                Box::new(record_batch_stream),
+            )
+            .await
+            .box_err()
+            .with_context(|| WriteSst {
+                path: sst_file_path.to_string(),
+            })?;
+
+        let file = FileMeta {
+            id: file_id,
+            row_num: sst_info.row_num as u64,
+            size: sst_info.file_size as u64,
+            time_range: sst_info.time_range,
+            max_seq: max_sequence,
+            storage_format: sst_info.storage_format,
+            associated_files: sst_info.associated_files(),
+        };
+        let edit_meta = VersionEditMeta {
+            space_id: table_data.space_id,
+            table_id: table_data.id,
+            flushed_sequence: 0,
+            files_to_add: vec![AddFile {
+                level: Level::MIN,
+                file,
+            }],
+            files_to_delete: vec![],
+            mems_to_remove: vec![],
+            max_file_id: 0,
+        };
+        let edit_req = MetaEditRequest {
+            shard_info: table_data.shard_info,
+            meta_edit: MetaEdit::Update(MetaUpdate::VersionEdit(edit_meta)),
+            table_catalog_info: table_data.table_catalog_info.clone(),
+        };
+        space_store
+            .manifest
+            .apply_edit(edit_req)
+            .await
+            .context(StoreVersionEdit)?;
+
+        info!(
+            "Data is imported, table:{}, table_id:{}, file_id:{}, num_rows:{}",
+            table_data.name, table_data.id, file_id, sst_info.row_num
+        );
+
+        Ok(sst_info.row_num)
+    }
+}
+
+fn has_same_columns(given: &RecordSchema, expect: &RecordSchema) -> bool {
+    given.num_columns() == expect.num_columns()
+        && given
+            .columns()
+            .iter()
+            .zip(expect.columns())
+            .all(|(given, expect)| {
+                given.name == expect.name
+                    && given.data_type == expect.data_type
+                    && given.is_dictionary == expect.is_dictionary
+            })
+}
@@ -20,6 +20,7 @@ use std::sync::{
     Arc,
 };
 
+use alloc_tracker::budget::{Category, MemoryBudgetRef};
 use arena::{Collector, CollectorRef};
 
 pub type MemUsageCollectorRef = Arc<MemUsageCollector>;
@@ -85,6 +86,22 @@ impl MemUsageCollector {
     }
 }
 
+/// Collector reserving the memory allocated by the memtables from the write
+/// buffer limit of the [MemoryBudget](alloc_tracker::budget::MemoryBudget).
+pub struct WriteBufferBudget(pub MemoryBudgetRef);
+
+impl Collector for WriteBufferBudget {
+    fn on_alloc(&self, bytes: usize) {
+        self.0.reserve(Category::WriteBuffer, bytes);
+    }
+
+    fn on_used(&self, _bytes: usize) {}
+
+    fn on_free(&self, _used: usize, allocated: usize) {
+        self.0.release(Category::WriteBuffer, allocated);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{atomic::Ordering, Arc};
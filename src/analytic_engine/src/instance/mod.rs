@@ -21,21 +21,27 @@
 //! divided into the sub crates
 
 pub(crate) mod alter;
+mod changes;
 mod close;
 mod create;
 mod drop;
 pub mod engine;
 pub mod flush_compaction;
+mod import;
 pub(crate) mod mem_collector;
 pub mod open;
+mod preload;
 mod read;
+mod recovery;
 mod reorder_memtable;
+mod rewrite;
 pub(crate) mod serial_executor;
 pub mod wal_replayer;
 pub(crate) mod write;
 
 use std::sync::Arc;
 
+use alloc_tracker::budget::{Category, MemoryBudgetRef};
 use common_types::{projected_schema::RowProjectorBuilder, table::TableId};
 use generic_error::{BoxError, GenericError};
 use logger::{error, info};
@@ -46,11 +52,15 @@ use snafu::{ResultExt, Snafu};
 use table_engine::{engine::EngineRuntimes, predicate::PredicateRef, table::FlushRequest};
 use time_ext::ReadableDuration;
 use tokio::sync::oneshot::{self, error::RecvError};
-use wal::manager::{WalLocation, WalManagerRef};
+use wal::{
+    kv_encoder::LogValueEncoder,
+    manager::{WalLocation, WalManagerRef},
+};
 
 use self::flush_compaction::{Flusher, TableFlushOptions};
 use crate::{
     compaction::{scheduler::CompactionSchedulerRef, TableCompactionRequest},
+    dynamic_config::DynamicConfigRef,
     manifest::ManifestRef,
     row_iter::IterOptions,
     space::{SpaceId, SpaceRef, SpacesRef},
@@ -60,11 +70,13 @@ use crate::{
             SstReadOptions,
         },
         file::FilePurgerRef,
+        io_scheduler::{IoSchedulerRef, ScheduledIo},
         meta_data::cache::MetaCacheRef,
         metrics::MaybeTableLevelMetrics,
+        quarantine::{CorruptSstPolicy, SstQuarantineRef},
     },
     table::data::{TableDataRef, TableShardInfo},
-    RecoverMode, TableOptions, WalEncodeConfig,
+    PreloadConfig, RecoverMode, RecoveryConfig, ShutdownConfig, TableOptions, WalEncodeConfig,
 };
 
 #[allow(clippy::enum_variant_names)]
@@ -125,7 +137,7 @@ impl SpaceStore {
 }
 
 impl SpaceStore {
-    fn store_picker(&self) -> &ObjectStorePickerRef {
+    pub(crate) fn store_picker(&self) -> &ObjectStorePickerRef {
         &self.store_picker
     }
 
@@ -169,9 +181,11 @@ pub struct Instance {
     meta_cache: Option<MetaCacheRef>,
     /// Engine memtable memory usage collector
     mem_usage_collector: Arc<MemUsageCollector>,
+    /// Memory budget shared with other components of the process
+    memory_budget: MemoryBudgetRef,
     pub(crate) max_rows_in_write_queue: usize,
     /// Engine write buffer size
-    pub(crate) db_write_buffer_size: usize,
+    pub(crate) dynamic_config: DynamicConfigRef,
     /// Space write buffer size
     pub(crate) space_write_buffer_size: usize,
     /// Replay wal batch size
@@ -188,23 +202,44 @@ pub struct Instance {
     pub(crate) mem_usage_sampling_interval: ReadableDuration,
     /// Options for scanning sst
     pub(crate) scan_options: ScanOptions,
+    /// Scheduler of the sst reads of the queries, `None` if not limited
+    pub(crate) io_scheduler: Option<IoSchedulerRef>,
     pub(crate) iter_options: Option<IterOptions>,
     pub(crate) recover_mode: RecoverMode,
     pub(crate) wal_encode: WalEncodeConfig,
     pub(crate) disable_wal: bool,
+    /// Encoder compressing, encrypting and checksumming the payloads written
+    /// into the data wal
+    pub(crate) wal_value_encoder: LogValueEncoder,
+    /// Time to retain the wal entries for the consumers of the table changes
+    pub(crate) change_retention_ttl: ReadableDuration,
+    /// Options for preloading the caches of the opened tables
+    pub(crate) preload: PreloadConfig,
+    /// Rewrite the ssts in old format of the opened tables
+    pub(crate) rewrite_old_ssts: bool,
+    pub(crate) corrupt_sst_policy: CorruptSstPolicy,
+    /// Corrupt ssts skipped by the queries.
+    pub(crate) sst_quarantine: SstQuarantineRef,
+    /// Options for shutting down the engine gracefully
+    pub(crate) shutdown: ShutdownConfig,
+    /// Options for checking and repairing the tables when opening them
+    pub(crate) recovery: RecoveryConfig,
 }
 
 impl Instance {
     /// Close the instance gracefully.
     pub async fn close(&self) -> Result<()> {
-        self.file_purger.stop().await.context(StopFilePurger)?;
-
-        self.space_store.close().await?;
-
+        // Stop scheduling new background jobs before the final flush.
         self.compaction_scheduler
             .stop_scheduler()
             .await
-            .context(StopScheduler)
+            .context(StopScheduler)?;
+
+        self.flush_tables_before_shutdown().await;
+
+        self.file_purger.stop().await.context(StopFilePurger)?;
+
+        self.space_store.close().await
     }
 
     pub async fn manual_flush_table(
@@ -252,6 +287,19 @@ impl Instance {
         Ok(())
     }
 
+    /// Release the memory held by the caches, the caches are refilled by the
+    /// later reads.
+    pub fn shrink_caches(&self) {
+        if let Some(meta_cache) = &self.meta_cache {
+            let before = meta_cache.len();
+            meta_cache.shrink();
+            info!(
+                "Instance shrink sst meta cache, before:{before}, after:{}",
+                meta_cache.len()
+            );
+        }
+    }
+
     // This method will wait until compaction finished.
     pub async fn manual_compact_table(&self, table_data: &TableDataRef) -> Result<()> {
         let (request, rx) = TableCompactionRequest::new(table_data.clone());
@@ -285,11 +333,14 @@ impl Instance {
     }
 
     /// Returns true when engine instance's total memtable memory usage reaches
-    /// db_write_buffer_size limit.
+    /// db_write_buffer_size limit, or the memory of all the memtables reaches
+    /// the write buffer limit of the memory budget.
     #[inline]
     fn should_flush_instance(&self) -> bool {
-        self.db_write_buffer_size > 0
-            && self.space_store.total_memory_usage_space() >= self.db_write_buffer_size
+        let db_write_buffer_size = self.dynamic_config.db_write_buffer_size();
+        (db_write_buffer_size > 0
+            && self.space_store.total_memory_usage_space() >= db_write_buffer_size)
+            || self.memory_budget.exceeded(Category::WriteBuffer)
     }
 
     #[inline]
@@ -308,6 +359,7 @@ impl Instance {
             space_store: self.space_store.clone(),
             // Do flush in write runtime
             runtime: self.runtimes.write_runtime.clone(),
+            task_registry: self.runtimes.task_registry.clone(),
             write_sst_max_buffer_size: self.write_sst_max_buffer_size,
             min_flush_interval_ms: None,
         }
@@ -319,6 +371,7 @@ impl Instance {
             space_store: self.space_store.clone(),
             // Do flush in write runtime
             runtime: self.runtimes.write_runtime.clone(),
+            task_registry: self.runtimes.task_registry.clone(),
             write_sst_max_buffer_size: self.write_sst_max_buffer_size,
             min_flush_interval_ms: Some(self.min_flush_interval.as_millis()),
         }
@@ -339,6 +392,7 @@ pub struct SstReadOptionsBuilder {
     predicate: PredicateRef,
     meta_cache: Option<MetaCacheRef>,
     runtime: Arc<Runtime>,
+    scheduled_io: Option<ScheduledIo>,
 }
 
 impl SstReadOptionsBuilder {
@@ -359,9 +413,15 @@ impl SstReadOptionsBuilder {
             predicate,
             meta_cache,
             runtime,
+            scheduled_io: None,
         }
     }
 
+    pub fn scheduled_io(mut self, scheduled_io: Option<ScheduledIo>) -> Self {
+        self.scheduled_io = scheduled_io;
+        self
+    }
+
     pub fn build(self, row_projector_builder: RowProjectorBuilder) -> SstReadOptions {
         SstReadOptions {
             maybe_table_level_metrics: self.maybe_table_level_metrics.clone(),
@@ -371,6 +431,7 @@ impl SstReadOptionsBuilder {
             predicate: self.predicate,
             meta_cache: self.meta_cache,
             scan_options: self.scan_options,
+            scheduled_io: self.scheduled_io,
             runtime: self.runtime,
         }
     }
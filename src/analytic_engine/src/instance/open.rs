@@ -22,12 +22,16 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use common_types::table::ShardId;
-use logger::{error, info};
+use common_types::{table::ShardId, SequenceNumber};
+use logger::{error, info, warn};
 use object_store::ObjectStoreRef;
 use snafu::ResultExt;
-use table_engine::{engine::TableDef, table::TableId};
-use wal::manager::WalManagerRef;
+use table_engine::{
+    engine::TableDef,
+    event::{self, EventKind},
+    table::TableId,
+};
+use wal::{kv_encoder::LogValueEncoder, manager::WalManagerRef};
 
 use crate::{
     compaction::{
@@ -35,20 +39,26 @@ use crate::{
         scheduler::SchedulerImpl,
     },
     context::OpenContext,
+    dynamic_config::DynamicConfig,
     engine,
     instance::{
         engine::{OpenManifest, OpenTablesOfShard, ReadMetaUpdate, Result},
         flush_compaction::Flusher,
-        mem_collector::MemUsageCollector,
-        wal_replayer::{ReplayMode, WalReplayer},
+        mem_collector::{MemUsageCollector, WriteBufferBudget},
+        recovery::{self, IssueKind, RecoveryReport},
+        wal_replayer::{ReplayMode, ReplayResult, SkippedEntry, WalReplayer},
         Instance, InstanceRef, SpaceStore,
     },
     manifest::{details::ManifestImpl, LoadRequest, Manifest, ManifestRef},
+    memtable::interner,
     row_iter::IterOptions,
+    shutdown,
     space::{SpaceAndTable, SpaceRef, Spaces},
     sst::{
         factory::{FactoryRef as SstFactoryRef, ObjectStorePickerRef, ScanOptions},
         file::FilePurger,
+        io_scheduler::IoScheduler,
+        quarantine::SstQuarantine,
     },
     table::data::{TableCatalogInfo, TableDataRef},
     table_meta_set_impl::TableMetaSetImpl,
@@ -109,11 +119,16 @@ impl Instance {
         sst_factory: SstFactoryRef,
         compaction_runner: CompactionRunnerPtr,
     ) -> Result<Arc<Self>> {
+        interner::init(&ctx.config.tag_interner);
+
         let spaces: Arc<RwLock<Spaces>> = Arc::new(RwLock::new(Spaces::default()));
         let default_runtime = ctx.runtimes.default_runtime.clone();
+        let dynamic_config = Arc::new(DynamicConfig::new(&ctx.config, ctx.meta_cache.clone()));
         let file_purger = Arc::new(FilePurger::start(
             &default_runtime,
+            &ctx.runtimes.task_registry,
             store_picker.default_store().clone(),
+            dynamic_config.backup_leases().clone(),
         ));
 
         let table_meta_set_impl = Arc::new(TableMetaSetImpl {
@@ -122,6 +137,7 @@ impl Instance {
             preflush_write_buffer_size_ratio: ctx.config.preflush_write_buffer_size_ratio,
             manifest_snapshot_every_n_updates: ctx.config.manifest.snapshot_every_n_updates,
             enable_primary_key_sampling: ctx.config.enable_primary_key_sampling,
+            version_retention: ctx.config.version_retention,
             metrics_opt: ctx.config.metrics.clone(),
         });
         let manifest = ManifestImpl::open(
@@ -129,6 +145,7 @@ impl Instance {
             manifest_storages.wal_manager,
             manifest_storages.oss_storage,
             table_meta_set_impl,
+            dynamic_config.backup_leases().clone(),
         )
         .await
         .context(OpenManifest)?;
@@ -147,7 +164,9 @@ impl Instance {
             space_store.clone(),
             compaction_runner,
             compaction_runtime,
+            ctx.runtimes.task_registry.clone(),
             scheduler_config,
+            dynamic_config.clone(),
             ctx.config.write_sst_max_buffer_size.as_byte() as usize,
             ctx.config.min_flush_interval.as_millis(),
         ));
@@ -170,9 +189,12 @@ impl Instance {
             compaction_scheduler,
             file_purger,
             meta_cache: ctx.meta_cache.clone(),
-            mem_usage_collector: Arc::new(MemUsageCollector::default()),
+            mem_usage_collector: Arc::new(MemUsageCollector::with_parent(Arc::new(
+                WriteBufferBudget(ctx.memory_budget.clone()),
+            ))),
+            memory_budget: ctx.memory_budget.clone(),
             max_rows_in_write_queue: ctx.config.max_rows_in_write_queue,
-            db_write_buffer_size: ctx.config.db_write_buffer_size,
+            dynamic_config,
             space_write_buffer_size: ctx.config.space_write_buffer_size,
             replay_batch_size: ctx.config.replay_batch_size,
             write_sst_max_buffer_size: ctx.config.write_sst_max_buffer_size.as_byte() as usize,
@@ -185,9 +207,22 @@ impl Instance {
                 .map(|v| v.as_byte() as usize),
             iter_options,
             scan_options,
+            io_scheduler: IoScheduler::try_new(&ctx.config.io_scheduler).map(Arc::new),
             recover_mode: ctx.config.recover_mode,
             wal_encode: ctx.config.wal_encode,
             disable_wal: ctx.config.wal.disable_data,
+            wal_value_encoder: LogValueEncoder::new(
+                ctx.config.wal.compression,
+                ctx.config.wal.checksum,
+                Some(ctx.wal_key_ring.clone()),
+            ),
+            change_retention_ttl: ctx.config.change_retention_ttl,
+            preload: ctx.config.preload.clone(),
+            rewrite_old_ssts: ctx.rewrite_old_ssts,
+            corrupt_sst_policy: ctx.config.corrupt_sst_policy,
+            sst_quarantine: Arc::new(SstQuarantine::default()),
+            shutdown: ctx.config.shutdown.clone(),
+            recovery: ctx.config.recovery.clone(),
         });
 
         Ok(instance)
@@ -198,17 +233,66 @@ impl Instance {
         self: &Arc<Self>,
         context: TablesOfShardContext,
     ) -> Result<OpenTablesOfShardResult> {
+        let opened_at = time_ext::current_time_millis() as i64;
+        let clean_tables = self.take_clean_shutdown_tables(&context).await;
         let mut shard_opener = ShardOpener::init(
             context,
+            clean_tables,
             self.space_store.manifest.clone(),
             self.space_store.wal_manager.clone(),
             self.replay_batch_size,
             self.make_flusher(),
             self.max_retry_flush_limit,
             self.recover_mode,
+            self.recovery.repair,
         )?;
 
-        shard_opener.open().await
+        let results = shard_opener.open().await?;
+        let mut report = std::mem::take(&mut shard_opener.report);
+        self.check_ssts_of_opened_tables(&results, opened_at, &mut report)
+            .await;
+        report.finish(shard_opener.shard_id);
+
+        self.preload_opened_tables(&results).await;
+        self.rewrite_old_ssts_of_opened_tables(&results).await;
+
+        Ok(results)
+    }
+
+    /// Take the flushed sequences of the tables in `context` which are flushed
+    /// completely in the last shutdown of the shard.
+    ///
+    /// The marker of the shard is always taken so that a stale one won't be
+    /// used after the skipping is enabled. Failing to load it only makes the
+    /// tables replay the wal.
+    async fn take_clean_shutdown_tables(
+        &self,
+        context: &TablesOfShardContext,
+    ) -> HashMap<TableId, SequenceNumber> {
+        let shard_id = context.shard_id;
+        let store = self.space_store.store_picker().default_store();
+        let marker = match shutdown::take_marker(store, shard_id).await {
+            Ok(marker) => marker,
+            Err(e) => {
+                warn!("Failed to take clean shutdown marker, shard_id:{shard_id}, err:{e}");
+                None
+            }
+        };
+        let Some(mut marker) = marker else {
+            return HashMap::new();
+        };
+        if !self.shutdown.skip_replay_after_clean_shutdown {
+            return HashMap::new();
+        }
+
+        context
+            .table_ctxs
+            .iter()
+            .filter_map(|table_ctx| {
+                let table_id = table_ctx.table_def.id;
+                marker.take_table(table_id).map(|seq| (table_id, seq))
+            })
+            .collect()
     }
 }
 
@@ -254,21 +338,31 @@ struct ShardOpener {
     manifest: ManifestRef,
     wal_manager: WalManagerRef,
     stages: HashMap<TableId, TableOpenStage>,
+    /// Flushed sequences of the tables flushed completely in the last shutdown
+    clean_tables: HashMap<TableId, SequenceNumber>,
     wal_replay_batch_size: usize,
     flusher: Flusher,
     max_retry_flush_limit: usize,
     recover_mode: RecoverMode,
+    /// Open the tables failing to replay the wal with the data replayed so
+    /// far
+    repair: bool,
+    /// Issues found when opening the tables
+    report: RecoveryReport,
 }
 
 impl ShardOpener {
+    #[allow(clippy::too_many_arguments)]
     fn init(
         shard_context: TablesOfShardContext,
+        clean_tables: HashMap<TableId, SequenceNumber>,
         manifest: ManifestRef,
         wal_manager: WalManagerRef,
         wal_replay_batch_size: usize,
         flusher: Flusher,
         max_retry_flush_limit: usize,
         recover_mode: RecoverMode,
+        repair: bool,
     ) -> Result<Self> {
         let mut stages = HashMap::with_capacity(shard_context.table_ctxs.len());
         for table_ctx in shard_context.table_ctxs {
@@ -292,10 +386,13 @@ impl ShardOpener {
             manifest,
             wal_manager,
             stages,
+            clean_tables,
             wal_replay_batch_size,
             flusher,
             max_retry_flush_limit,
             recover_mode,
+            repair,
+            report: RecoveryReport::default(),
         })
     }
 
@@ -315,6 +412,15 @@ impl ShardOpener {
                     table_results.insert(table_id, Err(e));
                 }
                 TableOpenStage::Success(data) => {
+                    if let Some(space_table) = &data {
+                        let table_data = space_table.table_data();
+                        event::record(
+                            EventKind::TableOpen,
+                            &table_data.name,
+                            table_data.id,
+                            format!("shard_id:{}", self.shard_id),
+                        );
+                    }
                     table_results.insert(table_id, Ok(data));
                 }
                 TableOpenStage::RecoverTableMeta(_) | TableOpenStage::RecoverTableData(_) => {
@@ -362,6 +468,13 @@ impl ShardOpener {
                         }
                         Err(e) => {
                             error!("ShardOpener recover single table meta failed, table:{table_def:?}, shard_id:{shard_id}, err:{e}");
+                            self.report.add(
+                                &table_def.name,
+                                table_def.id,
+                                IssueKind::RecoverManifest,
+                                e.to_string(),
+                                false,
+                            );
                             *state = TableOpenStage::Failed(e)
                         }
                     };
@@ -394,6 +507,21 @@ impl ShardOpener {
             match stage {
                 // Only do the wal recovery work in `RecoverTableData` state.
                 TableOpenStage::RecoverTableData(ctx) => {
+                    // Nothing to replay if the table is not flushed by others since the
+                    // last clean shutdown.
+                    let flushed_sequence = ctx.table_data.current_version().flushed_sequence();
+                    if self.clean_tables.get(table_id) == Some(&flushed_sequence) {
+                        info!(
+                            "ShardOpener skip replaying table flushed in clean shutdown, table:{}, table_id:{}, shard_id:{}, flushed_sequence:{flushed_sequence}",
+                            ctx.table_data.name, table_id, self.shard_id
+                        );
+                        ctx.table_data.set_last_sequence(flushed_sequence);
+                        let space_table =
+                            SpaceAndTable::new(ctx.space.clone(), ctx.table_data.clone());
+                        *stage = TableOpenStage::Success(Some(space_table));
+                        continue;
+                    }
+
                     replay_table_datas.push(ctx.table_data.clone());
                 }
                 // Table was found opened, or failed in meta recovery stage.
@@ -431,8 +559,12 @@ impl ShardOpener {
             self.flusher.clone(),
             self.max_retry_flush_limit,
             replay_mode,
+            self.repair,
         );
-        let mut table_results = wal_replayer.replay().await?;
+        let ReplayResult {
+            mut failed_tables,
+            mut skipped_entries,
+        } = wal_replayer.replay().await?;
 
         // Process the replay results.
         for table_data in replay_table_datas {
@@ -440,14 +572,53 @@ impl ShardOpener {
             // Each `table_data` has its related `stage` in `stages`, impossible to panic
             // here.
             let stage = self.stages.get_mut(&table_id).unwrap();
-            let failed_table_opt = table_results.remove(&table_id);
+            let failed_table_opt = failed_tables.remove(&table_id);
+            let skipped = skipped_entries.remove(&table_id).unwrap_or_default();
 
             match (&stage, failed_table_opt) {
                 (TableOpenStage::RecoverTableData(ctx), None) => {
                     let space_table = SpaceAndTable::new(ctx.space.clone(), ctx.table_data.clone());
+                    if !skipped.is_empty() {
+                        let causes: Vec<_> = skipped
+                            .iter()
+                            .map(|entry| format!("{}:{}", entry.sequence, entry.cause))
+                            .collect();
+                        let detail = format!("unreplayable entries:[{}]", causes.join(", "));
+                        Self::repair_wal(
+                            &self.flusher,
+                            self.max_retry_flush_limit,
+                            &table_data,
+                            skipped,
+                            false,
+                            detail,
+                            &mut self.report,
+                        )
+                        .await;
+                    }
                     *stage = TableOpenStage::Success(Some(space_table));
                 }
 
+                (TableOpenStage::RecoverTableData(ctx), Some(e)) if self.repair => {
+                    warn!("ShardOpener skip the wal failing to replay in repair mode, table:{}, table_id:{}, shard_id:{}, err:{e}", table_data.name, table_data.id, self.shard_id);
+                    let space_table = SpaceAndTable::new(ctx.space.clone(), ctx.table_data.clone());
+                    let detail = format!("failed to replay, err:{e}");
+                    let repaired = Self::repair_wal(
+                        &self.flusher,
+                        self.max_retry_flush_limit,
+                        &table_data,
+                        skipped,
+                        true,
+                        detail,
+                        &mut self.report,
+                    )
+                    .await;
+                    *stage = if repaired {
+                        TableOpenStage::Success(Some(space_table))
+                    } else {
+                        TableOpenStage::Failed(e)
+                    };
+                }
+
                 (TableOpenStage::RecoverTableData(_), Some(e)) => {
                     error!("ShardOpener replay wals of single table failed, table:{}, table_id:{}, shard_id:{}, err:{e}", table_data.name, table_data.id, self.shard_id);
                     *stage = TableOpenStage::Failed(e);
@@ -468,6 +639,45 @@ impl ShardOpener {
         Ok(())
     }
 
+    /// Quarantine the wal entries skipped in the replay and persist the skip,
+    /// see [recovery::repair_wal].
+    ///
+    /// The table replayed without error keeps serving if the repair fails,
+    /// and the skipped entries are skipped again in the next open. Returns
+    /// whether the wal is repaired.
+    async fn repair_wal(
+        flusher: &Flusher,
+        max_retry_flush_limit: usize,
+        table_data: &TableDataRef,
+        skipped: Vec<SkippedEntry>,
+        replay_failed: bool,
+        detail: String,
+        report: &mut RecoveryReport,
+    ) -> bool {
+        let table = &table_data.name;
+        match recovery::repair_wal(
+            flusher,
+            max_retry_flush_limit,
+            table_data,
+            skipped,
+            replay_failed,
+        )
+        .await
+        {
+            Ok(quarantined) => {
+                let detail = format!("{detail}, quarantined sequences:{quarantined:?}");
+                report.add(table, table_data.id, IssueKind::ReplayWal, detail, true);
+                true
+            }
+            Err(e) => {
+                error!("ShardOpener failed to repair wal, table:{table}, err:{e}");
+                let detail = format!("{detail}, repair err:{e}");
+                report.add(table, table_data.id, IssueKind::ReplayWal, detail, false);
+                false
+            }
+        }
+    }
+
     /// Recover meta data from manifest.
     ///
     /// Return None if no meta data is found for the table.
@@ -0,0 +1,158 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Preload the caches of the recent ssts when opening tables.
+//!
+//! Warming the sst meta cache and the object store caches before the table
+//! serves avoids the latency spikes after every restart.
+
+use std::{sync::Arc, time::Instant};
+
+use common_types::{
+    projected_schema::{ProjectedSchema, RowProjectorBuilder},
+    time::{TimeRange, Timestamp},
+};
+use logger::{info, warn};
+use macros::define_result;
+use snafu::{ResultExt, Snafu};
+use table_engine::predicate::Predicate;
+
+use crate::{
+    instance::{open::OpenTablesOfShardResult, Instance, ScanType, SstReadOptionsBuilder},
+    prefetchable_stream::PrefetchableStream,
+    sst::factory::SstReadHint,
+    table::{data::TableDataRef, sst_util},
+};
+
+#[derive(Debug, Snafu)]
+pub(crate) enum Error {
+    #[snafu(display("Failed to create sst reader, file_id:{file_id}, err:{source}"))]
+    CreateSstReader {
+        file_id: u64,
+        source: crate::sst::factory::Error,
+    },
+
+    #[snafu(display("Failed to read sst meta data, file_id:{file_id}, err:{source}"))]
+    ReadMetaData {
+        file_id: u64,
+        source: crate::sst::reader::Error,
+    },
+
+    #[snafu(display("Failed to read sst data, file_id:{file_id}, err:{source}"))]
+    ReadData {
+        file_id: u64,
+        source: crate::sst::reader::Error,
+    },
+}
+
+define_result!(Error);
+
+#[derive(Debug, Default)]
+struct PreloadStats {
+    num_ssts: usize,
+    num_rows: usize,
+}
+
+impl Instance {
+    /// Preload the caches of the opened tables configured in the preload
+    /// config.
+    ///
+    /// Failure of preloading is only logged, and won't fail the open.
+    pub(crate) async fn preload_opened_tables(&self, results: &OpenTablesOfShardResult) {
+        if self.preload.tables.is_empty() {
+            return;
+        }
+
+        for space_table in results.values().flatten().flatten() {
+            let table_data = space_table.table_data();
+            if !self.preload.tables.contains(&table_data.name) {
+                continue;
+            }
+
+            let begin = Instant::now();
+            match self.preload_table(table_data).await {
+                Ok(stats) => info!(
+                    "Preload table finished, table:{}, ssts:{}, rows:{}, cost:{:?}",
+                    table_data.name,
+                    stats.num_ssts,
+                    stats.num_rows,
+                    begin.elapsed()
+                ),
+                Err(e) => warn!(
+                    "Failed to preload table, table:{}, err:{e}",
+                    table_data.name
+                ),
+            }
+        }
+    }
+
+    async fn preload_table(&self, table_data: &TableDataRef) -> Result<PreloadStats> {
+        let now = Timestamp::now();
+        let time_range = TimeRange::new_unchecked(
+            now.sub_duration_or_min(self.preload.time_range.0),
+            Timestamp::MAX,
+        );
+        let read_view = table_data.current_version().pick_read_view(time_range);
+
+        let schema = table_data.schema();
+        let projected_schema = ProjectedSchema::no_projection(schema.clone());
+        let row_projector_builder =
+            RowProjectorBuilder::new(projected_schema.to_record_schema(), schema, None);
+        let table_options = table_data.table_options();
+        let sst_read_options = SstReadOptionsBuilder::new(
+            ScanType::Query,
+            self.scan_options.clone(),
+            None,
+            table_options.num_rows_per_row_group,
+            Arc::new(Predicate::empty()),
+            self.meta_cache.clone(),
+            self.read_runtime().low().clone(),
+        )
+        .build(row_projector_builder);
+
+        let sst_factory = &self.space_store.sst_factory;
+        let store_picker = self.space_store.store_picker();
+        let mut stats = PreloadStats::default();
+        for sst_file in read_view.leveled_ssts.iter().flatten() {
+            let file_id = sst_file.id();
+            let path = sst_util::new_sst_file_path(table_data.space_id, table_data.id, file_id);
+            let read_hint = SstReadHint {
+                file_size: Some(sst_file.size() as usize),
+                file_format: Some(sst_file.storage_format()),
+            };
+            let mut reader = sst_factory
+                .create_reader(&path, &sst_read_options, read_hint, store_picker, None)
+                .await
+                .context(CreateSstReader { file_id })?;
+            reader.meta_data().await.context(ReadMetaData { file_id })?;
+            stats.num_ssts += 1;
+
+            if !self.preload.preload_data {
+                continue;
+            }
+
+            // Read through the sst to fill the object store caches.
+            let mut stream = reader.read().await.context(ReadData { file_id })?;
+            while let Some(batch) = stream.fetch_next().await {
+                let batch = batch.context(ReadData { file_id })?;
+                stats.num_rows += batch.num_rows();
+            }
+        }
+
+        Ok(stats)
+    }
+}
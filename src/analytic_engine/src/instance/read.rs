@@ -20,6 +20,7 @@
 use std::{
     collections::BTreeMap,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
@@ -28,21 +29,23 @@ use common_types::{
     projected_schema::ProjectedSchema,
     record_batch::{FetchedRecordBatch, RecordBatch},
     schema::RecordSchema,
-    time::TimeRange,
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
 };
 use futures::stream::Stream;
 use generic_error::BoxError;
 use logger::debug;
 use macros::define_result;
-use snafu::{ResultExt, Snafu};
+use prometheus::HistogramTimer;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use table_engine::{
     stream::{
         self, ErrWithSource, PartitionedStreams, RecordBatchStream, SendableRecordBatchStream,
     },
-    table::ReadRequest,
+    table::{ReadOptions, ReadRequest},
 };
 use time_ext::current_time_millis;
-use trace_metric::Metric;
+use trace_metric::{Metric, MetricsCollector};
 
 use crate::{
     instance::{Instance, ScanType, SstReadOptionsBuilder},
@@ -53,6 +56,10 @@ use crate::{
         merge::{MergeBuilder, MergeConfig, MergeIterator},
         FetchedRecordBatchIterator, IterOptions,
     },
+    sst::{
+        io_scheduler::{IoClass, ScheduledIo},
+        quarantine::{CorruptSstPolicy, CorruptSstSkipper},
+    },
     table::{
         data::TableData,
         version::{ReadView, TableVersion},
@@ -79,12 +86,36 @@ pub enum Error {
         table: String,
         source: crate::row_iter::chain::Error,
     },
+
+    #[snafu(display(
+        "Snapshot of table is unavailable, table:{}, snapshot_time:{:?}",
+        table,
+        snapshot_time
+    ))]
+    SnapshotUnavailable {
+        table: String,
+        snapshot_time: Timestamp,
+    },
+
+    #[snafu(display(
+        "Rows after the snapshot sequence are flushed, table:{}, sequence:{}",
+        table,
+        sequence
+    ))]
+    SequenceUnavailable {
+        table: String,
+        sequence: SequenceNumber,
+    },
 }
 
 define_result!(Error);
 
 const MERGE_SORT_METRIC_NAME: &str = "do_merge_sort";
 const ITER_NUM_METRIC_NAME: &str = "iter_num";
+const MEMTABLE_NUM_METRIC_NAME: &str = "memtable_num";
+const SST_NUM_METRIC_NAME: &str = "sst_num";
+/// Number of the ssts pruned by the time range of the query.
+const PRUNED_SST_NUM_METRIC_NAME: &str = "pruned_sst_num";
 const MERGE_ITER_METRICS_COLLECTOR_NAME_PREFIX: &str = "merge_iter";
 const CHAIN_ITER_METRICS_COLLECTOR_NAME_PREFIX: &str = "chain_iter";
 
@@ -118,6 +149,8 @@ impl Instance {
         // Collect trace metrics.
         let table_options = table_data.table_options();
         table_data.metrics.on_read_request_begin();
+        // Observed when all the streams of the request are dropped.
+        let request_timer = Arc::new(table_data.metrics.start_read_request_timer());
         let need_merge_sort = table_options.need_dedup();
         request.metrics_collector.collect(Metric::boolean(
             MERGE_SORT_METRIC_NAME.to_string(),
@@ -137,7 +170,14 @@ impl Instance {
             request.predicate.clone(),
             self.meta_cache.clone(),
             runtime,
-        );
+        )
+        .scheduled_io(self.io_scheduler.as_ref().map(|scheduler| {
+            let class = IoClass {
+                space_id: table_data.space_id,
+                priority: request.priority,
+            };
+            ScheduledIo::new(scheduler.clone(), class)
+        }));
 
         if need_merge_sort {
             let merge_iters = self
@@ -148,7 +188,7 @@ impl Instance {
                     sst_read_options_builder,
                 )
                 .await?;
-            self.build_partitioned_streams(&request, merge_iters)
+            self.build_partitioned_streams(&request, merge_iters, request_timer)
         } else {
             let chain_iters = self
                 .build_chain_iters(
@@ -158,17 +198,24 @@ impl Instance {
                     sst_read_options_builder,
                 )
                 .await?;
-            self.build_partitioned_streams(&request, chain_iters)
+            self.build_partitioned_streams(&request, chain_iters, request_timer)
         }
     }
 
     fn build_partitioned_streams(
         &self,
         request: &ReadRequest,
-        partitioned_iters: Vec<impl FetchedRecordBatchIterator + 'static>,
+        mut partitioned_iters: Vec<impl FetchedRecordBatchIterator + 'static>,
+        request_timer: Arc<HistogramTimer>,
     ) -> Result<PartitionedStreams> {
         let read_parallelism = request.opts.read_parallelism;
 
+        // The iterators are ordered by the time of their segments, so every
+        // stream reads the newest segment first if the read is reversed.
+        if request.opts.reverse {
+            partitioned_iters.reverse();
+        }
+
         // Split iterators into `read_parallelism` groups.
         let mut splitted_iters: Vec<_> = std::iter::repeat_with(Vec::new)
             .take(read_parallelism)
@@ -180,7 +227,11 @@ impl Instance {
 
         let mut streams = Vec::with_capacity(read_parallelism);
         for iters in splitted_iters {
-            let stream = iters_to_stream(iters, request.projected_schema.clone());
+            let stream = iters_to_stream(
+                iters,
+                request.projected_schema.clone(),
+                request_timer.clone(),
+            );
             streams.push(stream);
         }
 
@@ -196,11 +247,17 @@ impl Instance {
         table_options: &TableOptions,
         sst_read_options_builder: SstReadOptionsBuilder,
     ) -> Result<Vec<DedupIterator<MergeIterator>>> {
-        // Current visible sequence
-        let sequence = table_data.last_sequence();
+        let sequence = visible_sequence(table_data, &request.opts);
         let time_range = request.predicate.time_range();
         let version = table_data.current_version();
-        let read_views = self.partition_ssts_and_memtables(time_range, version, table_options);
+        let read_views = self.partition_ssts_and_memtables(
+            table_data,
+            time_range,
+            &request.opts,
+            version,
+            table_options,
+            &request.metrics_collector,
+        )?;
         let iter_options = self.make_iter_options(table_options.num_rows_per_row_group);
 
         let mut iters = Vec::with_capacity(read_views.len());
@@ -221,8 +278,9 @@ impl Instance {
                 sst_read_options_builder: sst_read_options_builder.clone(),
                 store_picker: self.space_store.store_picker(),
                 merge_iter_options: iter_options.clone(),
-                need_dedup: table_options.need_dedup(),
+                need_dedup: table_options.need_dedup_memtable(),
                 reverse: false,
+                corrupt_sst_skipper: self.corrupt_sst_skipper(table_data, request),
             };
 
             let merge_iter = MergeBuilder::new(merge_config)
@@ -234,8 +292,12 @@ impl Instance {
                 .context(BuildMergeIterator {
                     table: &table_data.name,
                 })?;
-            let dedup_iter =
-                DedupIterator::new(request.request_id.clone(), merge_iter, iter_options.clone());
+            let dedup_iter = DedupIterator::new(
+                request.request_id.clone(),
+                merge_iter,
+                iter_options.clone(),
+                table_options.merge_policy,
+            );
 
             iters.push(dedup_iter);
         }
@@ -258,9 +320,17 @@ impl Instance {
     ) -> Result<Vec<ChainIterator>> {
         let projected_schema = request.projected_schema.clone();
 
+        let sequence = visible_sequence(table_data, &request.opts);
         let time_range = request.predicate.time_range();
         let version = table_data.current_version();
-        let read_views = self.partition_ssts_and_memtables(time_range, version, table_options);
+        let read_views = self.partition_ssts_and_memtables(
+            table_data,
+            time_range,
+            &request.opts,
+            version,
+            table_options,
+            &request.metrics_collector,
+        )?;
 
         let mut iters = Vec::with_capacity(read_views.len());
         for (idx, read_view) in read_views.into_iter().enumerate() {
@@ -274,11 +344,13 @@ impl Instance {
                 num_streams_to_prefetch: self.scan_options.num_streams_to_prefetch,
                 space_id: table_data.space_id,
                 table_id: table_data.id,
+                sequence,
                 projected_schema: projected_schema.clone(),
                 predicate: request.predicate.clone(),
                 sst_read_options_builder: sst_read_options_builder.clone(),
                 sst_factory: &self.space_store.sst_factory,
                 store_picker: self.space_store.store_picker(),
+                corrupt_sst_skipper: self.corrupt_sst_skipper(table_data, request),
             };
             let builder = chain::Builder::new(chain_config);
             let chain_iter = builder
@@ -297,13 +369,80 @@ impl Instance {
         Ok(iters)
     }
 
+    fn corrupt_sst_skipper(
+        &self,
+        table_data: &TableData,
+        request: &ReadRequest,
+    ) -> Option<CorruptSstSkipper> {
+        (self.corrupt_sst_policy == CorruptSstPolicy::SkipAndWarn).then(|| {
+            CorruptSstSkipper::new(
+                request.request_id.clone(),
+                request.opts.warnings.clone(),
+                table_data.name.clone(),
+                table_data.id,
+                self.sst_quarantine.clone(),
+            )
+        })
+    }
+
     fn partition_ssts_and_memtables(
         &self,
+        table_data: &TableData,
         time_range: TimeRange,
+        opts: &ReadOptions,
         version: &TableVersion,
         table_options: &TableOptions,
-    ) -> Vec<ReadView> {
-        let read_view = version.pick_read_view(time_range);
+        metrics_collector: &MetricsCollector,
+    ) -> Result<Vec<ReadView>> {
+        let snapshot_time = opts.snapshot_time;
+        let read_view = match snapshot_time {
+            Some(snapshot_time) => version
+                .pick_read_view_as_of(time_range, snapshot_time)
+                .context(SnapshotUnavailable {
+                    table: &table_data.name,
+                    snapshot_time,
+                })?,
+            None => version.pick_read_view(time_range),
+        };
+        // The rows in the ssts carry no sequences, so they can't be read as of
+        // the sequence once the rows after it are flushed.
+        if let Some(sequence) = opts.snapshot_sequence {
+            let flushed_after = read_view
+                .leveled_ssts
+                .iter()
+                .flatten()
+                .any(|file| file.max_sequence() > sequence);
+            ensure!(
+                !flushed_after,
+                SequenceUnavailable {
+                    table: &table_data.name,
+                    sequence,
+                }
+            );
+        }
+
+        let num_memtables =
+            read_view.memtables.len() + usize::from(read_view.sampling_mem.is_some());
+        let num_ssts: usize = read_view.leveled_ssts.iter().map(|ssts| ssts.len()).sum();
+        metrics_collector.collect(Metric::number(
+            MEMTABLE_NUM_METRIC_NAME.to_string(),
+            num_memtables,
+            None,
+        ));
+        metrics_collector.collect(Metric::number(
+            SST_NUM_METRIC_NAME.to_string(),
+            num_ssts,
+            None,
+        ));
+        // The ssts of the history version are unknown.
+        if snapshot_time.is_none() {
+            let num_pruned_ssts = version.num_ssts().saturating_sub(num_ssts);
+            metrics_collector.collect(Metric::number(
+                PRUNED_SST_NUM_METRIC_NAME.to_string(),
+                num_pruned_ssts,
+                None,
+            ));
+        }
 
         let segment_duration = match table_options.segment_duration {
             Some(v) => v.0,
@@ -311,12 +450,12 @@ impl Instance {
                 // Segment duration is unknown, the table maybe still in sampling phase
                 // or the segment duration is still not applied to the table options,
                 // just return one partition.
-                return vec![read_view];
+                return Ok(vec![read_view]);
             }
         };
         if read_view.contains_sampling() {
             // The table contains sampling memtable, just return one partition.
-            return vec![read_view];
+            return Ok(vec![read_view]);
         }
 
         // Collect the aligned ssts and memtables into the map.
@@ -346,7 +485,7 @@ impl Instance {
             entry.memtables.push(memtable);
         }
 
-        read_view_by_time.into_values().collect()
+        Ok(read_view_by_time.into_values().collect())
     }
 
     fn make_iter_options(&self, num_rows_per_row_group: usize) -> IterOptions {
@@ -393,9 +532,23 @@ impl<I: FetchedRecordBatchIterator + 'static> StreamStateOnMultiIters<I> {
     }
 }
 
+/// The max sequence of the memtables visible to the read, the rows written
+/// after the `snapshot_time` or the `snapshot_sequence` are invisible.
+fn visible_sequence(table_data: &TableData, opts: &ReadOptions) -> SequenceNumber {
+    let last_sequence = table_data.last_sequence();
+    if let Some(sequence) = opts.snapshot_sequence {
+        return sequence.min(last_sequence);
+    }
+
+    opts.snapshot_time
+        .and_then(|time| table_data.current_version().sequence_as_of(time))
+        .unwrap_or(last_sequence)
+}
+
 fn iters_to_stream(
     iters: Vec<impl FetchedRecordBatchIterator + 'static>,
     projected_schema: ProjectedSchema,
+    request_timer: Arc<HistogramTimer>,
 ) -> SendableRecordBatchStream {
     let mut state = StreamStateOnMultiIters {
         projected_schema: projected_schema.clone(),
@@ -404,6 +557,8 @@ fn iters_to_stream(
     };
 
     let record_batch_stream = try_stream! {
+        // Hold the timer until the stream is dropped.
+        let _request_timer = request_timer;
         while let Some(value) = state.fetch_next_batch().await {
             let record_batch = value
                 .box_err()
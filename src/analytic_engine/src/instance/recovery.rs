@@ -0,0 +1,544 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Check and repair the tables when opening them.
+//!
+//! The process may be killed in the middle of a flush or compaction, which
+//! leaves the ssts not referenced by the manifest, and the storage may lose
+//! the ssts or corrupt the wal entries. The issues found when opening the
+//! tables of a shard are collected into a [RecoveryReport], and the tables are
+//! repaired instead of refusing to open in the repair mode, see
+//! [RecoveryConfig].
+//!
+//! The wal entries skipped in the repair mode are kept in the quarantine
+//! directory of the object store, see [repair_wal].
+//!
+//! [RecoveryConfig]: crate::RecoveryConfig
+
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::Infallible,
+    time::Instant,
+};
+
+use bytes_ext::Buf;
+use common_types::{table::ShardId, SequenceNumber};
+use futures::TryStreamExt;
+use generic_error::{BoxError, GenericError};
+use logger::{info, warn};
+use macros::define_result;
+use object_store::{ObjectStoreError, Path};
+use snafu::{ResultExt, Snafu};
+use table_engine::{
+    event::{self, EventKind},
+    table::TableId,
+};
+use wal::{
+    log_batch::{PayloadDecodeContext, PayloadDecoder},
+    manager::{ReadBoundary, ReadContext, ReadRequest, WalLocation, WalManagerRef},
+};
+
+use crate::{
+    instance::{
+        self,
+        flush_compaction::{Flusher, TableFlushOptions},
+        open::OpenTablesOfShardResult,
+        wal_replayer::SkippedEntry,
+        Instance,
+    },
+    manifest::meta_edit::{MetaEdit, MetaEditRequest, MetaUpdate, VersionEditMeta},
+    sst::manager::FileId,
+    table::{data::TableDataRef, sst_util, version_edit::DeleteFile},
+};
+
+/// Directory of the object store to move the orphan ssts and the skipped wal
+/// entries into.
+const QUARANTINE_DIR: &str = "quarantine";
+
+#[derive(Debug, Snafu)]
+pub(crate) enum Error {
+    #[snafu(display("Failed to list ssts, table:{table}, err:{source}"))]
+    ListSsts {
+        table: String,
+        source: ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to check sst, path:{path}, err:{source}"))]
+    HeadSst {
+        path: String,
+        source: ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to remove missing ssts from manifest, table:{table}, err:{source}"))]
+    RemoveMissingSsts { table: String, source: GenericError },
+
+    #[snafu(display("Failed to quarantine orphan sst, path:{path}, err:{source}"))]
+    QuarantineSst {
+        path: String,
+        source: ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to read wal, table:{table}, err:{source}"))]
+    ReadWal { table: String, source: GenericError },
+
+    #[snafu(display("Failed to quarantine wal entry, path:{path}, err:{source}"))]
+    QuarantineWal {
+        path: String,
+        source: ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to flush table, table:{table}, err:{source}"))]
+    FlushTable { table: String, source: GenericError },
+
+    #[snafu(display("Failed to advance flushed sequence, table:{table}, err:{source}"))]
+    AdvanceWal { table: String, source: GenericError },
+}
+
+define_result!(Error);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IssueKind {
+    /// Failed to recover the table meta from the manifest.
+    RecoverManifest,
+    /// Failed to replay the wal of the table.
+    ReplayWal,
+    /// The sst referenced by the manifest is not found.
+    MissingSst,
+    /// The sst is not referenced by the manifest.
+    OrphanSst,
+}
+
+impl IssueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueKind::RecoverManifest => "recover_manifest",
+            IssueKind::ReplayWal => "replay_wal",
+            IssueKind::MissingSst => "missing_sst",
+            IssueKind::OrphanSst => "orphan_sst",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RecoveryIssue {
+    pub table: String,
+    pub table_id: TableId,
+    pub kind: IssueKind,
+    pub detail: String,
+    /// Whether the issue is repaired.
+    pub repaired: bool,
+}
+
+/// Issues found when opening the tables of a shard.
+#[derive(Debug, Default)]
+pub(crate) struct RecoveryReport {
+    issues: Vec<RecoveryIssue>,
+}
+
+impl RecoveryReport {
+    pub fn add(
+        &mut self,
+        table: &str,
+        table_id: TableId,
+        kind: IssueKind,
+        detail: String,
+        repaired: bool,
+    ) {
+        self.issues.push(RecoveryIssue {
+            table: table.to_string(),
+            table_id,
+            kind,
+            detail,
+            repaired,
+        });
+    }
+
+    pub fn issues(&self) -> &[RecoveryIssue] {
+        &self.issues
+    }
+
+    /// Log the report, and record the issues into the event log.
+    pub fn finish(&self, shard_id: ShardId) {
+        if self.issues.is_empty() {
+            info!("Recovery of shard finished without issues, shard_id:{shard_id}");
+            return;
+        }
+
+        for issue in self.issues() {
+            warn!(
+                "Recovery issue is found, shard_id:{shard_id}, table:{}, table_id:{}, kind:{}, repaired:{}, detail:{}",
+                issue.table,
+                issue.table_id,
+                issue.kind.as_str(),
+                issue.repaired,
+                issue.detail
+            );
+            event::record(
+                EventKind::RecoveryIssue,
+                &issue.table,
+                issue.table_id,
+                format!(
+                    "kind:{}, repaired:{}, {}",
+                    issue.kind.as_str(),
+                    issue.repaired,
+                    issue.detail
+                ),
+            );
+        }
+
+        let num_repaired = self.issues.iter().filter(|issue| issue.repaired).count();
+        warn!(
+            "Recovery of shard finished with issues, shard_id:{shard_id}, issues:{}, repaired:{num_repaired}",
+            self.issues.len()
+        );
+    }
+}
+
+impl Instance {
+    /// Check the ssts of the opened tables, and repair them in the repair
+    /// mode.
+    ///
+    /// The objects modified after `opened_at` are written by the flushes and
+    /// compactions triggered during opening, and they are not checked.
+    ///
+    /// Failure of checking is only logged, and won't fail the open.
+    pub(crate) async fn check_ssts_of_opened_tables(
+        &self,
+        results: &OpenTablesOfShardResult,
+        opened_at: i64,
+        report: &mut RecoveryReport,
+    ) {
+        if !self.recovery.check_ssts && !self.recovery.repair {
+            return;
+        }
+
+        for space_table in results.values().flatten().flatten() {
+            let table_data = space_table.table_data();
+            if let Err(e) = self.check_ssts_of_table(table_data, opened_at, report).await {
+                warn!(
+                    "Failed to check ssts of table, table:{}, err:{e}",
+                    table_data.name
+                );
+            }
+        }
+    }
+
+    async fn check_ssts_of_table(
+        &self,
+        table_data: &TableDataRef,
+        opened_at: i64,
+        report: &mut RecoveryReport,
+    ) -> Result<()> {
+        let store = self.space_store.store_picker.default_store();
+        let table = &table_data.name;
+        let repair = self.recovery.repair;
+        let ssts = table_data.current_version().all_ssts();
+        let referenced: HashSet<FileId> = ssts.iter().map(|(_, file)| file.id()).collect();
+
+        let mut missing_ssts = Vec::new();
+        for (level, file) in ssts {
+            let file_id = file.id();
+            let path = sst_util::new_sst_file_path(table_data.space_id, table_data.id, file_id);
+            match store.head(&path).await {
+                Ok(_) => {}
+                Err(ObjectStoreError::NotFound { .. }) => {
+                    missing_ssts.push(DeleteFile { level, file_id })
+                }
+                Err(e) => {
+                    return Err(e).context(HeadSst {
+                        path: path.to_string(),
+                    })
+                }
+            }
+        }
+        if !missing_ssts.is_empty() {
+            let file_ids: Vec<_> = missing_ssts.iter().map(|file| file.file_id).collect();
+            if repair {
+                self.remove_missing_ssts(table_data, missing_ssts).await?;
+            }
+            for file_id in file_ids {
+                let detail = format!("file_id:{file_id}");
+                report.add(table, table_data.id, IssueKind::MissingSst, detail, repair);
+            }
+        }
+
+        let dir = Path::from(sst_util::new_table_dir_path(
+            table_data.space_id,
+            table_data.id,
+        ));
+        let objects: Vec<_> = store
+            .list(Some(&dir))
+            .await
+            .context(ListSsts { table })?
+            .try_collect()
+            .await
+            .context(ListSsts { table })?;
+        for object in objects {
+            let Some(file_id) = parse_file_id(&object.location) else {
+                continue;
+            };
+            let modified_after_open = object.last_modified.timestamp_millis() > opened_at;
+            if referenced.contains(&file_id) || modified_after_open {
+                continue;
+            }
+
+            if repair {
+                let quarantine_path = Path::from(format!("{QUARANTINE_DIR}/{}", object.location));
+                store
+                    .rename(&object.location, &quarantine_path)
+                    .await
+                    .context(QuarantineSst {
+                        path: object.location.to_string(),
+                    })?;
+            }
+            let detail = format!("path:{}", object.location);
+            report.add(table, table_data.id, IssueKind::OrphanSst, detail, repair);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_missing_ssts(
+        &self,
+        table_data: &TableDataRef,
+        files_to_delete: Vec<DeleteFile>,
+    ) -> Result<()> {
+        let edit_meta = VersionEditMeta {
+            space_id: table_data.space_id,
+            table_id: table_data.id,
+            flushed_sequence: 0,
+            files_to_add: vec![],
+            files_to_delete,
+            mems_to_remove: vec![],
+            max_file_id: 0,
+        };
+        let edit_req = MetaEditRequest {
+            shard_info: table_data.shard_info,
+            meta_edit: MetaEdit::Update(MetaUpdate::VersionEdit(edit_meta)),
+            table_catalog_info: table_data.table_catalog_info.clone(),
+        };
+
+        self.space_store
+            .manifest
+            .apply_edit(edit_req)
+            .await
+            .context(RemoveMissingSsts {
+                table: &table_data.name,
+            })
+    }
+}
+
+/// Persist the skip of the wal entries failing to replay.
+///
+/// The raw payloads of the `skipped` entries are put into the quarantine
+/// directory, so are the entries after the replayed ones if the replay of the
+/// table failed. Then the table is flushed and its flushed sequence is advanced
+/// to the last sequence of the wal, so the skipped entries are not replayed
+/// again after reopening.
+///
+/// Returns the sequences of the quarantined entries. The wal is not advanced
+/// if any of the entries fails to be quarantined.
+pub(crate) async fn repair_wal(
+    flusher: &Flusher,
+    max_retry_flush_limit: usize,
+    table_data: &TableDataRef,
+    skipped: Vec<SkippedEntry>,
+    replay_failed: bool,
+) -> Result<Vec<SequenceNumber>> {
+    let space_store = &flusher.space_store;
+    let wal_manager = &space_store.wal_manager;
+    let table = &table_data.name;
+    let table_location = table_data.table_location();
+    let location = instance::create_wal_location(table_location.id, table_location.shard_info);
+    let last_sequence = wal_manager
+        .sequence_num(location)
+        .await
+        .box_err()
+        .context(ReadWal { table })?;
+    let replayed_sequence = table_data.last_sequence();
+    // The new writes must not reuse the sequences of the skipped entries.
+    table_data.set_last_sequence(last_sequence.max(replayed_sequence));
+
+    let mut entries: Vec<_> = skipped
+        .into_iter()
+        .map(|entry| (entry.sequence, entry.raw))
+        .collect();
+    if replay_failed {
+        let unreplayed = read_raw_entries(wal_manager, location, replayed_sequence)
+            .await
+            .context(ReadWal { table })?;
+        entries.extend(unreplayed);
+    }
+
+    let store = space_store.store_picker().default_store();
+    let mut quarantined = Vec::with_capacity(entries.len());
+    for (sequence, raw) in entries {
+        let path = Path::from(format!("{QUARANTINE_DIR}/wal/{}/{sequence}", table_data.id));
+        store
+            .put(&path, raw.into())
+            .await
+            .context(QuarantineWal {
+                path: path.to_string(),
+            })?;
+        quarantined.push(sequence);
+    }
+
+    // Force the flush even if the table is flushed recently.
+    let flusher = Flusher {
+        min_flush_interval_ms: None,
+        ..flusher.clone()
+    };
+    let opts = TableFlushOptions {
+        res_sender: None,
+        max_retry_flush_limit,
+    };
+    {
+        let mut serial_exec = table_data.serial_exec.lock().await;
+        let flush_scheduler = serial_exec.flush_scheduler();
+        flusher
+            .do_flush(flush_scheduler, table_data, opts)
+            .await
+            .box_err()
+            .context(FlushTable { table })?;
+    }
+
+    // The memtables may be empty, so the flushed sequence is advanced explicitly.
+    let edit_meta = VersionEditMeta {
+        space_id: table_data.space_id,
+        table_id: table_data.id,
+        flushed_sequence: last_sequence,
+        files_to_add: vec![],
+        files_to_delete: vec![],
+        mems_to_remove: vec![],
+        max_file_id: 0,
+    };
+    let edit_req = MetaEditRequest {
+        shard_info: table_data.shard_info,
+        meta_edit: MetaEdit::Update(MetaUpdate::VersionEdit(edit_meta)),
+        table_catalog_info: table_data.table_catalog_info.clone(),
+    };
+    space_store
+        .manifest
+        .apply_edit(edit_req)
+        .await
+        .context(AdvanceWal { table })?;
+
+    // Purge the skipped entries like the flush does, the ones still needed by
+    // the consumers of the table changes are kept.
+    let retention = &table_data.change_retention;
+    let persist_res = match retention.load(store, table_data.id).await {
+        Ok(()) => retention.persist(store, table_data.id).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = persist_res {
+        warn!("Failed to persist change retention, skip purging wal, table:{table}, err:{e}");
+        return Ok(quarantined);
+    }
+    let purge_sequence = match retention.min_retained(Instant::now()) {
+        Some(retained) => retained.min(last_sequence),
+        None => last_sequence,
+    };
+    if let Err(e) = wal_manager
+        .mark_delete_entries_up_to(location, purge_sequence)
+        .await
+    {
+        warn!("Failed to purge wal, table:{table}, sequence:{purge_sequence}, err:{e}");
+    }
+
+    Ok(quarantined)
+}
+
+/// Decoder keeping the raw payloads of the wal entries.
+struct RawPayloadDecoder;
+
+impl PayloadDecoder for RawPayloadDecoder {
+    type Error = Infallible;
+    type Target = Vec<u8>;
+
+    fn decode<B: Buf>(
+        &self,
+        _ctx: &PayloadDecodeContext,
+        buf: &mut B,
+    ) -> std::result::Result<Self::Target, Self::Error> {
+        let raw = buf.chunk().to_vec();
+        buf.advance(raw.len());
+        Ok(raw)
+    }
+}
+
+/// Read the raw payloads of the wal entries after `start`.
+async fn read_raw_entries(
+    wal_manager: &WalManagerRef,
+    location: WalLocation,
+    start: SequenceNumber,
+) -> std::result::Result<Vec<(SequenceNumber, Vec<u8>)>, GenericError> {
+    let read_req = ReadRequest {
+        location,
+        start: ReadBoundary::Excluded(start),
+        end: ReadBoundary::Max,
+    };
+    let mut iter = wal_manager
+        .read_batch(&ReadContext::default(), &read_req)
+        .await
+        .box_err()?;
+    let mut entries = Vec::new();
+    let mut buf = VecDeque::new();
+    loop {
+        buf = iter
+            .next_log_entries(RawPayloadDecoder, |_| true, buf)
+            .await
+            .box_err()?;
+        if buf.is_empty() {
+            return Ok(entries);
+        }
+        entries.extend(buf.drain(..).map(|entry| (entry.sequence, entry.payload)));
+    }
+}
+
+/// Parse the id of the sst from the path of the sst or its custom metadata.
+fn parse_file_id(path: &Path) -> Option<FileId> {
+    let file_name = path.filename()?;
+    file_name.split('.').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_id() {
+        let sst_path = sst_util::new_sst_file_path(1, TableId::new(2), 3);
+        assert_eq!(Some(3), parse_file_id(&sst_path));
+        let metadata_path = Path::from(sst_util::new_metadata_path(sst_path.as_ref()));
+        assert_eq!(Some(3), parse_file_id(&metadata_path));
+        assert_eq!(None, parse_file_id(&Path::from("1/2/unknown")));
+    }
+
+    #[test]
+    fn test_recovery_report() {
+        let mut report = RecoveryReport::default();
+        let table_id = TableId::new(1);
+        report.add("t", table_id, IssueKind::MissingSst, "file_id:1".to_string(), true);
+        report.add("t", table_id, IssueKind::OrphanSst, "path:1/1/2.sst".to_string(), false);
+
+        let issues = report.issues();
+        assert_eq!(2, issues.len());
+        assert_eq!("missing_sst", issues[0].kind.as_str());
+        assert!(issues[0].repaired);
+        assert!(!issues[1].repaired);
+    }
+}
@@ -0,0 +1,66 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Rewrite the ssts in old format after upgrading the data format.
+
+use logger::{info, warn};
+
+use crate::{
+    compaction::TableCompactionRequest,
+    instance::{open::OpenTablesOfShardResult, Instance},
+};
+
+impl Instance {
+    /// Schedule rewriting the existing ssts of the opened tables in the
+    /// current format.
+    ///
+    /// The rewrite is best effort and not persisted, the ssts not rewritten
+    /// are still readable.
+    pub(crate) async fn rewrite_old_ssts_of_opened_tables(
+        &self,
+        results: &OpenTablesOfShardResult,
+    ) {
+        if !self.rewrite_old_ssts {
+            return;
+        }
+
+        for space_table in results.values().flatten().flatten() {
+            let table_data = space_table.table_data();
+            // The ssts written after opening are already in the current format.
+            let Some(max_file_id) = table_data.current_version().max_sst_id() else {
+                continue;
+            };
+
+            let request = TableCompactionRequest::rewrite(table_data.clone(), max_file_id);
+            if self
+                .compaction_scheduler
+                .schedule_table_compaction(request)
+                .await
+            {
+                info!(
+                    "Schedule rewriting old ssts, table:{}, max_file_id:{max_file_id}",
+                    table_data.name
+                );
+            } else {
+                warn!(
+                    "Failed to schedule rewriting old ssts, table:{}",
+                    table_data.name
+                );
+            }
+        }
+    }
+}
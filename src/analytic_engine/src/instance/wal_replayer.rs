@@ -28,6 +28,7 @@ use async_trait::async_trait;
 use common_types::{
     schema::{IndexInWriterSchema, Schema},
     table::ShardId,
+    SequenceNumber,
 };
 use futures::StreamExt;
 use generic_error::BoxError;
@@ -89,6 +90,7 @@ impl<'a> WalReplayer<'a> {
         flusher: Flusher,
         max_retry_flush_limit: usize,
         replay_mode: ReplayMode,
+        repair: bool,
     ) -> Self {
         let context = ReplayContext {
             shard_id,
@@ -96,6 +98,7 @@ impl<'a> WalReplayer<'a> {
             wal_replay_batch_size,
             flusher,
             max_retry_flush_limit,
+            repair,
         };
 
         let replay = Self::build_replay(replay_mode);
@@ -116,8 +119,8 @@ impl<'a> WalReplayer<'a> {
         }
     }
 
-    /// Replay tables and return the failed tables and the causes.
-    pub async fn replay(&mut self) -> Result<FailedTables> {
+    /// Replay tables and return the failed tables and the skipped entries.
+    pub async fn replay(&mut self) -> Result<ReplayResult> {
         // Build replay action according to mode.
         info!(
             "Replay wal logs begin, context:{}, tables:{:?}",
@@ -139,6 +142,8 @@ pub struct ReplayContext {
     pub wal_replay_batch_size: usize,
     pub flusher: Flusher,
     pub max_retry_flush_limit: usize,
+    /// Skip the entries failing to decode instead of failing the replay.
+    pub repair: bool,
 }
 
 impl Display for ReplayContext {
@@ -147,6 +152,7 @@ impl Display for ReplayContext {
             .field("shard_id", &self.shard_id)
             .field("replay_batch_size", &self.wal_replay_batch_size)
             .field("max_retry_flush_limit", &self.max_retry_flush_limit)
+            .field("repair", &self.repair)
             .finish()
     }
 }
@@ -159,6 +165,54 @@ pub enum ReplayMode {
 
 pub type FailedTables = HashMap<TableId, Error>;
 
+/// The wal entry skipped in the replay because it fails to decode.
+#[derive(Debug)]
+pub struct SkippedEntry {
+    pub sequence: SequenceNumber,
+    /// The raw payload of the entry.
+    pub raw: Vec<u8>,
+    pub cause: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayResult {
+    /// The tables failing to replay and the causes.
+    pub failed_tables: FailedTables,
+    /// The entries skipped in the repair mode, grouped by table.
+    pub skipped_entries: HashMap<TableId, Vec<SkippedEntry>>,
+}
+
+/// The barrier keeping the replay of a single table strictly ordered by the
+/// sequence.
+///
+/// The entries at or below the barrier are either flushed or applied already,
+/// e.g. the ones rescanned after a crash in the middle of the replay, and are
+/// skipped, so the memtable never sees a sequence going backwards even when
+/// the batches of different tables are replayed in parallel.
+#[derive(Debug, Clone, Copy)]
+struct SequenceBarrier {
+    applied: SequenceNumber,
+}
+
+impl SequenceBarrier {
+    fn new(flushed_sequence: SequenceNumber) -> Self {
+        Self {
+            applied: flushed_sequence,
+        }
+    }
+
+    /// Returns true and advances the barrier if the entry of `sequence` should
+    /// be applied.
+    fn pass(&mut self, sequence: SequenceNumber) -> bool {
+        if sequence <= self.applied {
+            return false;
+        }
+
+        self.applied = sequence;
+        true
+    }
+}
+
 /// Replay action, the abstract of different replay strategies
 #[async_trait]
 trait Replay: Send + Sync + 'static {
@@ -166,7 +220,7 @@ trait Replay: Send + Sync + 'static {
         &self,
         context: &ReplayContext,
         table_datas: &[TableDataRef],
-    ) -> Result<FailedTables>;
+    ) -> Result<ReplayResult>;
 }
 
 /// Table based wal replay
@@ -178,22 +232,28 @@ impl Replay for TableBasedReplay {
         &self,
         context: &ReplayContext,
         table_datas: &[TableDataRef],
-    ) -> Result<FailedTables> {
+    ) -> Result<ReplayResult> {
         debug!("Replay wal logs on table mode, context:{context}, tables:{table_datas:?}",);
 
-        let mut failed_tables = HashMap::new();
+        let mut result = ReplayResult::default();
         let read_ctx = ReadContext {
             batch_size: context.wal_replay_batch_size,
             ..Default::default()
         };
         for table_data in table_datas {
             let table_id = table_data.id;
-            if let Err(e) = Self::recover_table_logs(context, table_data, &read_ctx).await {
-                failed_tables.insert(table_id, e);
+            let mut skipped = Vec::new();
+            if let Err(e) =
+                Self::recover_table_logs(context, table_data, &read_ctx, &mut skipped).await
+            {
+                result.failed_tables.insert(table_id, e);
+            }
+            if !skipped.is_empty() {
+                result.skipped_entries.insert(table_id, skipped);
             }
         }
 
-        Ok(failed_tables)
+        Ok(result)
     }
 }
 
@@ -202,6 +262,7 @@ impl TableBasedReplay {
         context: &ReplayContext,
         table_data: &TableDataRef,
         read_ctx: &ReadContext,
+        skipped: &mut Vec<SkippedEntry>,
     ) -> Result<()> {
         let table_location = table_data.table_location();
         let wal_location =
@@ -221,6 +282,7 @@ impl TableBasedReplay {
             .context(ReplayWalWithCause { msg: None })?;
 
         let mut serial_exec = table_data.serial_exec.lock().await;
+        let mut barrier = SequenceBarrier::new(table_data.current_version().flushed_sequence());
         let mut log_entry_buf = VecDeque::with_capacity(context.wal_replay_batch_size);
         loop {
             // fetch entries to log_entry_buf
@@ -228,7 +290,7 @@ impl TableBasedReplay {
             let adapter = SingleSchemaProviderAdapter {
                 schema: table_data.schema(),
             };
-            let decoder = WalDecoder::new(adapter);
+            let decoder = WalDecoder::new(adapter).keep_unreplayable(context.repair);
             // All the logs should belong the table, so no need to check again.
             let filter = |_| true;
             log_entry_buf = log_iter
@@ -247,8 +309,10 @@ impl TableBasedReplay {
                 &context.flusher,
                 context.max_retry_flush_limit,
                 &mut serial_exec,
+                &mut barrier,
                 table_data,
                 log_entry_buf.iter(),
+                skipped,
             )
             .await?;
         }
@@ -266,19 +330,19 @@ impl Replay for RegionBasedReplay {
         &self,
         context: &ReplayContext,
         table_datas: &[TableDataRef],
-    ) -> Result<FailedTables> {
+    ) -> Result<ReplayResult> {
         debug!("Replay wal logs on region mode, context:{context}, tables:{table_datas:?}",);
 
         // Init all table results to be oks, and modify to errs when failed to replay.
-        let mut failed_tables = FailedTables::new();
+        let mut result = ReplayResult::default();
         let scan_ctx = ScanContext {
             batch_size: context.wal_replay_batch_size,
             ..Default::default()
         };
 
-        Self::replay_region_logs(context, table_datas, &scan_ctx, &mut failed_tables).await?;
+        Self::replay_region_logs(context, table_datas, &scan_ctx, &mut result).await?;
 
-        Ok(failed_tables)
+        Ok(result)
     }
 }
 
@@ -304,7 +368,7 @@ impl RegionBasedReplay {
         context: &ReplayContext,
         table_datas: &[TableDataRef],
         scan_ctx: &ScanContext,
-        failed_tables: &mut FailedTables,
+        result: &mut ReplayResult,
     ) -> Result<()> {
         // Scan all wal logs of current shard.
         let scan_req = ScanRequest {
@@ -319,16 +383,20 @@ impl RegionBasedReplay {
             .context(ReplayWalWithCause { msg: None })?;
         let mut log_entry_buf = VecDeque::with_capacity(context.wal_replay_batch_size);
 
-        // Lock all related tables.
+        // Lock all related tables, the context of each table is guarded by its own
+        // mutex so that different tables can be replayed in parallel.
         let mut serial_exec_ctxs = HashMap::with_capacity(table_datas.len());
         let mut table_datas_by_id = HashMap::with_capacity(table_datas.len());
         for table_data in table_datas {
             let serial_exec = table_data.serial_exec.lock().await;
+            let flushed_sequence = table_data.current_version().flushed_sequence();
             let serial_exec_ctx = SerialExecContext {
                 table_data: table_data.clone(),
                 serial_exec,
+                barrier: SequenceBarrier::new(flushed_sequence),
+                skipped: Vec::new(),
             };
-            serial_exec_ctxs.insert(table_data.id, serial_exec_ctx);
+            serial_exec_ctxs.insert(table_data.id, Mutex::new(serial_exec_ctx));
             table_datas_by_id.insert(table_data.id.as_u64(), table_data.clone());
         }
 
@@ -336,11 +404,11 @@ impl RegionBasedReplay {
         let schema_provider = TableSchemaProviderAdapter {
             table_datas: table_datas_by_id.clone(),
         };
-        let serial_exec_ctxs = Arc::new(Mutex::new(serial_exec_ctxs));
         // Split and replay logs.
         loop {
             let _timer = PULL_LOGS_DURATION_HISTOGRAM.start_timer();
-            let decoder = WalDecoder::new(schema_provider.clone());
+            let decoder =
+                WalDecoder::new(schema_provider.clone()).keep_unreplayable(context.repair);
             let table_datas_for_filter = table_datas_by_id.clone();
             let log_filter = move |log_table_id| table_datas_for_filter.contains_key(&log_table_id);
             log_entry_buf = log_iter
@@ -354,8 +422,20 @@ impl RegionBasedReplay {
             }
 
             let _timer = APPLY_LOGS_DURATION_HISTOGRAM.start_timer();
-            Self::replay_single_batch(context, &log_entry_buf, &serial_exec_ctxs, failed_tables)
-                .await?;
+            Self::replay_single_batch(
+                context,
+                &log_entry_buf,
+                &serial_exec_ctxs,
+                &mut result.failed_tables,
+            )
+            .await?;
+        }
+
+        for (table_id, ctx) in serial_exec_ctxs {
+            let skipped = ctx.into_inner().skipped;
+            if !skipped.is_empty() {
+                result.skipped_entries.insert(table_id, skipped);
+            }
         }
 
         Ok(())
@@ -364,36 +444,42 @@ impl RegionBasedReplay {
     async fn replay_single_batch(
         context: &ReplayContext,
         log_batch: &VecDeque<LogEntry<ReadPayload>>,
-        serial_exec_ctxs: &Arc<Mutex<HashMap<TableId, SerialExecContext<'_>>>>,
+        serial_exec_ctxs: &HashMap<TableId, Mutex<SerialExecContext<'_>>>,
         failed_tables: &mut FailedTables,
     ) -> Result<()> {
         let mut table_batches = Vec::new();
         // TODO: No `group_by` method in `VecDeque`, so implement it manually here...
         Self::split_log_batch_by_table(log_batch, &mut table_batches);
+        // The logs of a table may be interleaved with others in the batch, all of
+        // them are replayed by a single task to keep them in order.
+        let table_ranges = Self::group_table_batches(table_batches);
 
-        // TODO: Replay logs of different tables in parallel.
-        let mut replay_tasks = Vec::with_capacity(table_batches.len());
-        for table_batch in table_batches {
+        let mut replay_tasks = Vec::with_capacity(table_ranges.len());
+        for (table_id, ranges) in table_ranges {
             // Some tables may have failed in previous replay, ignore them.
-            if failed_tables.contains_key(&table_batch.table_id) {
+            if failed_tables.contains_key(&table_id) {
                 continue;
             }
 
-            let serial_exec_ctxs = serial_exec_ctxs.clone();
             replay_tasks.push(async move {
                 // Some tables may have been moved to other shards or dropped, ignore such logs.
-                if let Some(ctx) = serial_exec_ctxs.lock().await.get_mut(&table_batch.table_id) {
+                if let Some(ctx) = serial_exec_ctxs.get(&table_id) {
+                    let mut ctx = ctx.lock().await;
+                    let ctx = &mut *ctx;
+                    let log_entries = ranges.into_iter().flat_map(|range| log_batch.range(range));
                     let result = replay_table_log_entries(
                         &context.flusher,
                         context.max_retry_flush_limit,
                         &mut ctx.serial_exec,
+                        &mut ctx.barrier,
                         &ctx.table_data,
-                        log_batch.range(table_batch.range),
+                        log_entries,
+                        &mut ctx.skipped,
                     )
                     .await;
-                    (table_batch.table_id, Some(result))
+                    (table_id, Some(result))
                 } else {
-                    (table_batch.table_id, None)
+                    (table_id, None)
                 }
             });
         }
@@ -462,6 +548,23 @@ impl RegionBasedReplay {
     }
 }
 
+    /// Group the ranges of the split batches by table, the tables and the ranges
+    /// of each table are kept in the order they appear in the log batch.
+    fn group_table_batches(table_batches: Vec<TableBatch>) -> Vec<(TableId, Vec<Range<usize>>)> {
+        let mut table_idxs = HashMap::with_capacity(table_batches.len());
+        let mut table_ranges: Vec<(TableId, Vec<Range<usize>>)> = Vec::new();
+        for TableBatch { table_id, range } in table_batches {
+            let idx = *table_idxs.entry(table_id).or_insert_with(|| {
+                table_ranges.push((table_id, Vec::new()));
+                table_ranges.len() - 1
+            });
+            table_ranges[idx].1.push(range);
+        }
+
+        table_ranges
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct TableBatch {
     table_id: TableId,
@@ -471,6 +574,8 @@ struct TableBatch {
 struct SerialExecContext<'a> {
     table_data: TableDataRef,
     serial_exec: MutexGuard<'a, TableOpSerialExecutor>,
+    barrier: SequenceBarrier,
+    skipped: Vec<SkippedEntry>,
 }
 
 /// Replay all log entries into memtable and flush if necessary
@@ -478,8 +583,10 @@ async fn replay_table_log_entries(
     flusher: &Flusher,
     max_retry_flush_limit: usize,
     serial_exec: &mut TableOpSerialExecutor,
+    barrier: &mut SequenceBarrier,
     table_data: &TableDataRef,
     log_entries: impl Iterator<Item = &LogEntry<ReadPayload>>,
+    skipped: &mut Vec<SkippedEntry>,
 ) -> Result<()> {
     let flushed_sequence = table_data.current_version().flushed_sequence();
     debug!(
@@ -490,8 +597,12 @@ async fn replay_table_log_entries(
     for log_entry in log_entries {
         let (sequence, payload) = (log_entry.sequence, &log_entry.payload);
 
-        // Ignore too old logs(sequence <= `flushed_sequence`).
-        if sequence <= flushed_sequence {
+        // Ignore the logs flushed or applied already.
+        if !barrier.pass(sequence) {
+            trace!(
+                "Ignore log entry behind the sequence barrier, table:{}, sequence:{sequence}, barrier:{}",
+                table_data.name, barrier.applied
+            );
             continue;
         }
 
@@ -571,6 +682,17 @@ async fn replay_table_log_entries(
                         })?;
                 }
             }
+            ReadPayload::Unreplayable { raw, cause } => {
+                warn!(
+                    "Skip unreplayable log entry, table:{}, table_id:{}, sequence:{sequence}, cause:{cause}",
+                    table_data.name, table_data.id
+                );
+                skipped.push(SkippedEntry {
+                    sequence,
+                    raw: raw.clone(),
+                    cause: cause.clone(),
+                });
+            }
             ReadPayload::AlterSchema { .. } | ReadPayload::AlterOptions { .. } => {
                 // Ignore records except Data.
                 //
@@ -597,7 +719,7 @@ mod tests {
     use table_engine::table::TableId;
     use wal::log_batch::LogEntry;
 
-    use crate::instance::wal_replayer::{RegionBasedReplay, TableBatch};
+    use crate::instance::wal_replayer::{RegionBasedReplay, SequenceBarrier, TableBatch};
 
     #[test]
     fn test_split_log_batch_by_table() {
@@ -680,4 +802,34 @@ mod tests {
         RegionBasedReplay::split_log_batch_by_table(batch, &mut table_batches);
         assert_eq!(&table_batches, expected);
     }
+
+    #[test]
+    fn test_group_table_batches() {
+        let table_batches = [(1, 0..2), (2, 2..3), (1, 3..5), (3, 5..6), (2, 6..8)]
+            .into_iter()
+            .map(|(table_id, range)| TableBatch {
+                table_id: TableId::new(table_id),
+                range,
+            })
+            .collect();
+        let table_ranges = RegionBasedReplay::group_table_batches(table_batches);
+        let expected = vec![
+            (TableId::new(1), vec![0..2, 3..5]),
+            (TableId::new(2), vec![2..3, 6..8]),
+            (TableId::new(3), vec![5..6]),
+        ];
+        assert_eq!(table_ranges, expected);
+    }
+
+    #[test]
+    fn test_sequence_barrier() {
+        let mut barrier = SequenceBarrier::new(3);
+        assert!(!barrier.pass(2));
+        assert!(!barrier.pass(3));
+        assert!(barrier.pass(4));
+        assert!(!barrier.pass(4));
+        assert!(barrier.pass(6));
+        assert!(!barrier.pass(5));
+        assert_eq!(barrier.applied, 6);
+    }
 }
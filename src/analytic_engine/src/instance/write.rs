@@ -17,7 +17,7 @@
 
 //! Write logic of instance
 
-use std::iter;
+use std::{iter, time::Instant};
 
 use bytes_ext::ByteVec;
 use codec::{
@@ -25,7 +25,8 @@ use codec::{
     row,
 };
 use common_types::{
-    row::RowGroup,
+    datum::{Datum, DatumKind},
+    row::{Row, RowGroup},
     schema::{IndexInWriterSchema, Schema},
 };
 use horaedbproto::{schema as schema_pb, table_requests};
@@ -34,7 +35,7 @@ use logger::{debug, error, info, trace, warn};
 use macros::define_result;
 use smallvec::SmallVec;
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
-use table_engine::table::WriteRequest;
+use table_engine::{table::WriteRequest, write_trace};
 use wal::{
     kv_encoder::LogBatchEncoder,
     log_batch::Payload,
@@ -50,7 +51,7 @@ use crate::{
     payload::WritePayload,
     space::SpaceRef,
     table::{data::TableDataRef, version::MemTableForWrite},
-    WalEncodeConfig, WalEncodeFormat,
+    TableOptions, WalEncodeConfig, WalEncodeFormat,
 };
 
 #[derive(Debug, Snafu)]
@@ -174,6 +175,81 @@ impl EncodeContext {
         }
     }
 
+    /// Truncate the timestamps to the `timestamp_resolution` of the table, and
+    /// keep the original ones in the `timestamp_original_column` if it is not
+    /// provided by the writer.
+    ///
+    /// The `index_in_writer` must be filled before.
+    fn truncate_timestamps(&mut self, table_schema: &Schema, table_opts: &TableOptions) {
+        let resolution_ms = match table_opts.timestamp_resolution {
+            Some(v) => v.0.as_millis() as i64,
+            None => return,
+        };
+
+        let original_index_in_table = table_opts
+            .timestamp_original_column
+            .as_ref()
+            .and_then(|name| table_schema.index_of(name));
+        if let Some(index_in_table) = original_index_in_table {
+            if self
+                .index_in_writer
+                .column_index_in_writer(index_in_table)
+                .is_none()
+            {
+                self.fill_missing_columns(table_schema);
+            }
+        }
+
+        let schema = self.row_group.schema();
+        let timestamp_index = schema.timestamp_index();
+        let original = original_index_in_table
+            .and_then(|i| self.index_in_writer.column_index_in_writer(i))
+            .map(|i| (i, schema.column(i).data_type));
+        for row_idx in 0..self.row_group.num_rows() {
+            let row = self.row_group.get_row_mut(row_idx).unwrap();
+            let timestamp = match row[timestamp_index].as_timestamp() {
+                Some(v) => v,
+                None => continue,
+            };
+            let truncated = timestamp
+                .checked_floor_by_i64(resolution_ms)
+                .unwrap_or(timestamp);
+            row[timestamp_index] = Datum::Timestamp(truncated);
+
+            if let Some((original_index, kind)) = original {
+                if row[original_index].is_null() {
+                    row[original_index] = match kind {
+                        DatumKind::Int64 => Datum::Int64(timestamp.as_i64()),
+                        _ => Datum::Timestamp(timestamp),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Rebuild the row group with the `table_schema`, the columns not provided
+    /// by the writer are filled with null.
+    fn fill_missing_columns(&mut self, table_schema: &Schema) {
+        let num_columns = table_schema.num_columns();
+        let rows = self
+            .row_group
+            .take_rows()
+            .into_iter()
+            .map(|row| {
+                let datums = (0..num_columns)
+                    .map(|i| match self.index_in_writer.column_index_in_writer(i) {
+                        Some(writer_index) => row[writer_index].clone(),
+                        None => Datum::Null,
+                    })
+                    .collect();
+                Row::from_datums(datums)
+            })
+            .collect();
+
+        self.row_group = RowGroup::new_unchecked(table_schema.clone(), rows);
+        self.index_in_writer = IndexInWriterSchema::for_same_schema(num_columns);
+    }
+
     fn encode(
         &mut self,
         config: &WalEncodeConfig,
@@ -415,7 +491,9 @@ impl<'a> MemTableWriter<'a> {
 }
 
 impl<'a> Writer<'a> {
-    pub(crate) async fn write(&mut self, request: WriteRequest) -> Result<usize> {
+    /// Write the request, returns the number of the written rows and the
+    /// sequence the write is committed at.
+    pub(crate) async fn write(&mut self, request: WriteRequest) -> Result<(usize, SequenceNumber)> {
         let _timer = self.table_data.metrics.start_table_write_execute_timer();
         self.table_data.metrics.on_write_request_begin();
 
@@ -425,6 +503,7 @@ impl<'a> Writer<'a> {
         self.preprocess_write(&mut encode_ctx).await?;
 
         let table_data = self.table_data.clone();
+        let wal_begin = Instant::now();
         let seq = if self.instance.disable_wal {
             // When wal is disabled, just update the last_seq one by one.
             table_data.next_sequence()
@@ -444,16 +523,19 @@ impl<'a> Writer<'a> {
                 }
             }
         };
+        write_trace::record_duration("wal", wal_begin.elapsed());
 
         // Write the row group to the memtable and update the state in the mem.
         let EncodeContext {
             row_group,
             index_in_writer,
         } = encode_ctx;
+        let memtable_begin = Instant::now();
         self.write_to_mem(&table_data, &row_group, index_in_writer, seq)
             .await?;
+        write_trace::record_duration("memtable", memtable_begin.elapsed());
 
-        Ok(row_group.num_rows())
+        Ok((row_group.num_rows(), seq))
     }
 
     async fn write_to_wal_in_rows(&self, encoded_rows: Vec<ByteVec>) -> Result<SequenceNumber> {
@@ -552,6 +634,7 @@ impl<'a> Writer<'a> {
         );
 
         table_data.set_last_sequence(sequence);
+        table_data.current_version().record_sequence(sequence);
 
         // Collect metrics.
         let num_columns = row_group.schema().num_columns();
@@ -561,6 +644,7 @@ impl<'a> Writer<'a> {
             num_columns,
             num_written_bytes,
         );
+        table_data.update_memtable_metrics();
 
         Ok(())
     }
@@ -594,22 +678,23 @@ impl<'a> Writer<'a> {
         );
 
         // Checks schema compatibility.
-        self.table_data
-            .schema()
+        let table_schema = self.table_data.schema();
+        table_schema
             .compatible_for_write(
                 encode_ctx.row_group.schema(),
                 &mut encode_ctx.index_in_writer,
             )
             .context(IncompatSchema)?;
+        encode_ctx.truncate_timestamps(&table_schema, &self.table_data.table_options());
 
         if self.instance.should_flush_instance() {
             if let Some(space) = self.instance.space_store.find_maximum_memory_usage_space() {
                 if let Some(table) = space.find_maximum_memory_usage_table() {
-                    info!("Trying to flush table {} bytes {} in space {} because engine total memtable memory usage exceeds db_write_buffer_size {}.",
+                    info!("Trying to flush table {} bytes {} in space {} because engine total memtable memory usage exceeds db_write_buffer_size {} or memory.write_buffer_limit.",
                           table.name,
                           table.memtable_memory_usage(),
                           space.id,
-                          self.instance.db_write_buffer_size,
+                          self.instance.dynamic_config.db_write_buffer_size(),
                     );
                     let _timer = self
                         .table_data
@@ -656,7 +741,8 @@ impl<'a> Writer<'a> {
         let table_location = self.table_data.table_location();
         let wal_location =
             instance::create_wal_location(table_location.id, table_location.shard_info);
-        let log_batch_encoder = LogBatchEncoder::create(wal_location);
+        let log_batch_encoder = LogBatchEncoder::create(wal_location)
+            .with_value_encoder(self.instance.wal_value_encoder.clone());
         let log_batch = log_batch_encoder
             .encode_batch(payloads)
             .context(EncodePayloads {
@@ -731,12 +817,10 @@ impl<'a> Writer<'a> {
 #[cfg(test)]
 mod tests {
     use common_types::{
-        column_schema::Builder as ColumnSchemaBuilder,
-        datum::{Datum, DatumKind},
-        row::Row,
-        schema::Builder as SchemaBuilder,
+        column_schema::Builder as ColumnSchemaBuilder, schema::Builder as SchemaBuilder,
         time::Timestamp,
     };
+    use time_ext::ReadableDuration;
 
     use super::*;
 
@@ -843,4 +927,61 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_truncate_timestamps() {
+        let table_schema = SchemaBuilder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                ColumnSchemaBuilder::new("ts".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                ColumnSchemaBuilder::new("raw_ts".to_string(), DatumKind::Int64)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .primary_key_indexes(vec![0])
+            .build()
+            .unwrap();
+        let writer_schema = SchemaBuilder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(table_schema.column(0).clone())
+            .unwrap()
+            .primary_key_indexes(vec![0])
+            .build()
+            .unwrap();
+        let rows = [1001, 1999, 3000]
+            .into_iter()
+            .map(|v| Row::from_datums(vec![Datum::Timestamp(Timestamp::new(v))]))
+            .collect();
+        let row_group = RowGroup::try_new(writer_schema, rows).unwrap();
+
+        let table_opts = TableOptions {
+            timestamp_resolution: Some(ReadableDuration::secs(1)),
+            timestamp_original_column: Some("raw_ts".to_string()),
+            ..Default::default()
+        };
+        table_opts.validate_with_schema(&table_schema).unwrap();
+
+        let mut encode_ctx = EncodeContext::new(row_group);
+        table_schema
+            .compatible_for_write(
+                encode_ctx.row_group.schema(),
+                &mut encode_ctx.index_in_writer,
+            )
+            .unwrap();
+        encode_ctx.truncate_timestamps(&table_schema, &table_opts);
+
+        assert_eq!(2, encode_ctx.row_group.schema().num_columns());
+        let expected = [(1000, 1001), (1000, 1999), (3000, 3000)];
+        for (row, (ts, raw_ts)) in encode_ctx.row_group.iter().zip(expected) {
+            assert_eq!(Datum::Timestamp(Timestamp::new(ts)), row[0]);
+            assert_eq!(Datum::Int64(raw_ts), row[1]);
+        }
+    }
 }
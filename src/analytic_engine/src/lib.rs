@@ -19,9 +19,12 @@
 
 #![feature(option_get_or_insert_default)]
 
+pub mod backup;
 mod compaction;
 mod context;
+pub mod dynamic_config;
 mod engine;
+pub mod format;
 mod instance;
 mod manifest;
 pub mod memtable;
@@ -30,6 +33,7 @@ pub mod prefetchable_stream;
 pub mod row_iter;
 mod sampler;
 pub mod setup;
+pub mod shutdown;
 pub mod space;
 pub mod sst;
 pub mod table;
@@ -43,11 +47,13 @@ use manifest::details::Options as ManifestOptions;
 use object_store::config::StorageOptions;
 use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
+use sst::{io_scheduler::IoSchedulerConfig, quarantine::CorruptSstPolicy};
 use time_ext::ReadableDuration;
 use wal::config::Config as WalConfig;
 
+use crate::memtable::interner::Config as InternerConfig;
 pub use crate::{
-    compaction::scheduler::SchedulerConfig,
+    compaction::{scheduler::SchedulerConfig, simulator},
     instance::{ScanType, SstReadOptionsBuilder},
     table_options::TableOptions,
 };
@@ -93,6 +99,14 @@ pub struct Config {
 
     pub enable_primary_key_sampling: bool,
 
+    /// How long the replaced ssts and the flushed memtables are retained for
+    /// reading the table as of an earlier time, e.g. `FOR SYSTEM_TIME AS OF`.
+    /// Zero disables such reads.
+    pub version_retention: ReadableDuration,
+
+    /// Fair sharing of the sst read bandwidth between the queries.
+    pub io_scheduler: IoSchedulerConfig,
+
     // Iterator scanning options
     /// Batch size for iterator.
     ///
@@ -129,6 +143,11 @@ pub struct Config {
     /// + Kafka
     pub wal: WalConfig,
 
+    /// The wal entries needed by the consumers of the table changes are kept
+    /// after they are flushed, until the consumer stops reading the changes
+    /// for this long.
+    pub change_retention_ttl: ReadableDuration,
+
     /// Recover mode
     ///
     /// + TableBased, tables on same shard will be recovered table by table.
@@ -138,6 +157,24 @@ pub struct Config {
     pub remote_engine_client: remote_engine_client::config::Config,
 
     pub metrics: MetricsOptions,
+
+    /// Preload the caches of the recent ssts when opening tables
+    pub preload: PreloadConfig,
+
+    /// Config of upgrading the data in old format
+    pub format: FormatConfig,
+
+    /// Config of shutting down the engine gracefully
+    pub shutdown: ShutdownConfig,
+
+    /// Config of checking and repairing the tables when opening them
+    pub recovery: RecoveryConfig,
+
+    /// Policy of reading the corrupt ssts in the queries
+    pub corrupt_sst_policy: CorruptSstPolicy,
+
+    /// Interner of the string tag values shared by the memtables
+    pub tag_interner: InternerConfig,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
@@ -146,6 +183,93 @@ pub struct MetricsOptions {
     enable_table_level_metrics: bool,
 }
 
+/// Config of preloading the caches when opening tables, so that the latency
+/// won't spike after restarting.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PreloadConfig {
+    /// Names of the tables to preload, no table is preloaded if empty
+    pub tables: Vec<String>,
+    /// Only the ssts overlapping with the recent `time_range` are preloaded
+    pub time_range: ReadableDuration,
+    /// Whether to preload the sst data besides the sst meta data
+    pub preload_data: bool,
+}
+
+impl Default for PreloadConfig {
+    fn default() -> Self {
+        Self {
+            tables: Vec::new(),
+            time_range: ReadableDuration::hours(2),
+            preload_data: false,
+        }
+    }
+}
+
+/// Config of upgrading the data written in old format, see [format].
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// Rewrite the ssts in old format in background after upgrading, the ssts
+    /// are readable even if they are not rewritten.
+    pub background_rewrite: bool,
+}
+
+/// Config of shutting down the engine gracefully, see [shutdown].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Max time to wait for the in-progress and final flushes of the tables
+    /// when closing the engine, the tables not flushed in time are replayed
+    /// from the wal on the next startup.
+    pub flush_timeout: ReadableDuration,
+    /// Skip the wal replay of the tables flushed completely in the last
+    /// shutdown.
+    ///
+    /// It's only safe if the tables are not written by other nodes after the
+    /// shutdown, e.g. in the standalone mode.
+    pub skip_replay_after_clean_shutdown: bool,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            flush_timeout: ReadableDuration::secs(30),
+            skip_replay_after_clean_shutdown: false,
+        }
+    }
+}
+
+/// Config of checking the consistency of the manifest, ssts and wal of the
+/// tables when opening them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecoveryConfig {
+    /// Check the ssts referenced by the manifest exist in the object store,
+    /// and report the orphan ssts not referenced by the manifest.
+    ///
+    /// Default is true.
+    pub check_ssts: bool,
+    /// Repair the tables instead of refusing to open them, and the ssts are
+    /// always checked in the repair mode:
+    /// - The wal entries failing to decode are skipped, and so are the rest
+    ///   entries of the table failing to replay. The skipped entries are moved
+    ///   to the quarantine directory, and the table is flushed to persist the
+    ///   skip.
+    /// - The missing ssts are removed from the manifest.
+    /// - The orphan ssts are moved to the quarantine directory.
+    pub repair: bool,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            check_ssts: true,
+            repair: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
 pub enum RecoverMode {
     TableBased,
@@ -190,10 +314,15 @@ impl Default for Config {
             // it.
             space_write_buffer_size: 0,
             // Zero means disabling this param, give a positive value to enable
-            // it.
-            db_write_buffer_size: 0,
+            // it. It's enabled if the memory is limited by the cgroup, to avoid
+            // being OOM-killed in the containers.
+            db_write_buffer_size: system_stats::cgroup_limits()
+                .memory_limit
+                .map(|limit| limit as usize / 4)
+                .unwrap_or(0),
             preflush_write_buffer_size_ratio: 0.75,
             enable_primary_key_sampling: false,
+            version_retention: ReadableDuration::hours(1),
             scan_batch_size: None,
             sst_background_read_parallelism: 8,
             num_streams_to_prefetch: 2,
@@ -205,9 +334,17 @@ impl Default for Config {
             mem_usage_sampling_interval: ReadableDuration::secs(0),
             wal_encode: WalEncodeConfig::default(),
             wal: WalConfig::default(),
+            change_retention_ttl: ReadableDuration::minutes(10),
             remote_engine_client: remote_engine_client::config::Config::default(),
             recover_mode: RecoverMode::TableBased,
             metrics: MetricsOptions::default(),
+            preload: PreloadConfig::default(),
+            format: FormatConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            recovery: RecoveryConfig::default(),
+            corrupt_sst_policy: CorruptSstPolicy::default(),
+            io_scheduler: IoSchedulerConfig::default(),
+            tag_interner: InternerConfig::default(),
             mutable_segment_switch_threshold: ReadableSize::mb(3),
         }
     }
@@ -29,14 +29,12 @@ use std::{
 
 use async_trait::async_trait;
 use generic_error::{BoxError, GenericError, GenericResult};
-use horaedbproto::manifest as manifest_pb;
 use lazy_static::lazy_static;
 use logger::{debug, info, warn};
 use macros::define_result;
 use object_store::{ObjectStoreRef, Path};
 use parquet::data_type::AsBytes;
 use prometheus::{exponential_buckets, register_histogram, Histogram};
-use prost::Message;
 use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, ResultExt, Snafu};
 use table_engine::table::TableId;
@@ -52,6 +50,7 @@ use wal::{
 };
 
 use crate::{
+    backup::BackupLeasesRef,
     manifest::{
         meta_edit::{
             MetaEdit, MetaEditRequest, MetaUpdate, MetaUpdateDecoder, MetaUpdatePayload, Snapshot,
@@ -113,16 +112,6 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
-    #[snafu(display(
-        "Failed to decode snapshot, err:{}.\nBacktrace:\n{:?}",
-        source,
-        backtrace
-    ))]
-    DecodeSnapshot {
-        source: prost::DecodeError,
-        backtrace: Backtrace,
-    },
-
     #[snafu(display("Failed to build snapshot, msg:{}.\nBacktrace:\n{:?}", msg, backtrace))]
     BuildSnapshotNoCause { msg: String, backtrace: Backtrace },
 
@@ -410,6 +399,10 @@ pub struct ManifestImpl {
     snapshot_write_guard: Arc<Mutex<()>>,
 
     table_meta_set: Arc<dyn TableMetaSet>,
+
+    /// The automatic snapshots are held while any backup is running, so the
+    /// snapshots being copied are not replaced.
+    backup_leases: BackupLeasesRef,
 }
 
 impl ManifestImpl {
@@ -418,6 +411,7 @@ impl ManifestImpl {
         wal_manager: WalManagerRef,
         store: ObjectStoreRef,
         table_meta_set: Arc<dyn TableMetaSet>,
+        backup_leases: BackupLeasesRef,
     ) -> Result<Self> {
         let manifest = Self {
             opts,
@@ -426,6 +420,7 @@ impl ManifestImpl {
             num_updates_since_snapshot: Arc::new(AtomicUsize::new(0)),
             snapshot_write_guard: Arc::new(Mutex::new(())),
             table_meta_set,
+            backup_leases,
         };
 
         Ok(manifest)
@@ -509,8 +504,9 @@ impl Manifest for ManifestImpl {
 
         // Update manifest updates count.
         table_data.increase_manifest_updates(1);
-        // Judge if snapshot is needed.
-        if table_data.should_do_manifest_snapshot() {
+        // Judge if snapshot is needed, and the updates are kept in the wal until
+        // the backups are done.
+        if table_data.should_do_manifest_snapshot() && !self.backup_leases.is_active() {
             self.do_snapshot_internal(space_id, table_id, location)
                 .await?;
             table_data.reset_manifest_updates();
@@ -627,8 +623,7 @@ impl MetaUpdateSnapshotStore for ObjectStoreBasedSnapshotStore {
     /// Store the latest snapshot to the underlying store by overwriting the old
     /// snapshot.
     async fn store(&self, snapshot: &Snapshot) -> Result<()> {
-        let snapshot_pb = manifest_pb::Snapshot::from(snapshot.clone());
-        let payload = snapshot_pb.encode_to_vec();
+        let payload = snapshot.encode_to_vec();
         // The atomic write is ensured by the [`ObjectStore`] implementation.
         self.store
             .put(&self.snapshot_path, payload.into())
@@ -650,24 +645,12 @@ impl MetaUpdateSnapshotStore for ObjectStoreBasedSnapshotStore {
             return Ok(None);
         };
 
-        // TODO: currently, this is just a workaround to handle the case where the error
-        // is not thrown as [object_store::ObjectStoreError::NotFound].
-        if let Err(err) = &get_res {
-            let err_msg = err.to_string().to_lowercase();
-            if err_msg.contains("404") || err_msg.contains("not found") {
-                warn!("Current snapshot file doesn't exist, err:{}", err);
-                return Ok(None);
-            }
-        }
-
         let payload = get_res
             .context(FetchSnapshot)?
             .bytes()
             .await
             .context(FetchSnapshot)?;
-        let snapshot_pb =
-            manifest_pb::Snapshot::decode(payload.as_bytes()).context(DecodeSnapshot)?;
-        let snapshot = Snapshot::try_from(snapshot_pb)
+        let snapshot = Snapshot::decode(payload.as_bytes())
             .box_err()
             .context(LoadSnapshot)?;
 
@@ -752,6 +735,7 @@ mod tests {
 
     use super::*;
     use crate::{
+        backup::BackupLeases,
         manifest::{
             details::{MetaUpdateLogEntryIterator, MetaUpdateLogStore},
             meta_edit::{
@@ -868,6 +852,7 @@ mod tests {
                     manifest_snapshot_every_n_updates: NonZeroUsize::new(usize::MAX).unwrap(),
                     metrics_opt: MetricsOptions::default(),
                     enable_primary_key_sampling: false,
+                    version_retention: ReadableDuration::hours(1),
                 },
                 &purger,
                 mem_size_options,
@@ -934,6 +919,7 @@ mod tests {
                 Arc::new(manifest_wal),
                 Arc::new(object_store),
                 self.mock_provider.clone(),
+                Arc::new(BackupLeases::default()),
             )
             .await
             .unwrap()
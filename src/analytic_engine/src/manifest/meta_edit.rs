@@ -17,10 +17,11 @@
 
 //! Update to meta
 
-use std::convert::TryFrom;
+use std::{collections::HashMap, convert::TryFrom};
 
 use bytes_ext::{Buf, BufMut};
 use common_types::{
+    column_schema::ColumnId,
     schema::{Schema, Version},
     SequenceNumber,
 };
@@ -40,7 +41,8 @@ use crate::{
         version::TableVersionMeta,
         version_edit::{AddFile, DeleteFile, VersionEdit},
     },
-    table_options, TableOptions,
+    table_options::{self, TableOptionsExt},
+    TableOptions,
 };
 
 #[derive(Debug, Snafu)]
@@ -129,6 +131,103 @@ impl MetaUpdate {
     }
 }
 
+impl MetaUpdate {
+    fn to_ext(&self) -> MetaExt {
+        match self {
+            MetaUpdate::AddTable(v) => MetaExt::new(Some(&v.schema), Some(&v.opts)),
+            MetaUpdate::AlterSchema(v) => MetaExt::new(Some(&v.schema), None),
+            MetaUpdate::AlterOptions(v) => MetaExt::new(None, Some(&v.options)),
+            MetaUpdate::DropTable(_) | MetaUpdate::VersionEdit(_) => MetaExt::default(),
+        }
+    }
+
+    fn apply_ext(&mut self, ext: MetaExt) -> Result<()> {
+        match self {
+            MetaUpdate::AddTable(v) => ext.restore(Some(&mut v.schema), Some(&mut v.opts)),
+            MetaUpdate::AlterSchema(v) => ext.restore(Some(&mut v.schema), None),
+            MetaUpdate::AlterOptions(v) => ext.restore(None, Some(&mut v.options)),
+            MetaUpdate::DropTable(_) | MetaUpdate::VersionEdit(_) => Ok(()),
+        }
+    }
+}
+
+/// Extension of the pb of the meta updates and the snapshots.
+///
+/// It's encoded after the pb in the same buffer, and its tags are unused by
+/// the pb, so the decoders of the pb skip it as unknown fields, and its
+/// decoder skips the fields of the pb in turn.
+#[derive(Clone, PartialEq, prost::Message)]
+struct MetaExt {
+    #[prost(message, optional, tag = "1000")]
+    table_options: Option<TableOptionsExt>,
+    #[prost(message, repeated, tag = "1001")]
+    column_annotations: Vec<ColumnAnnotationsExt>,
+}
+
+/// Annotations of a column not in the `schema_pb::ColumnSchema`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ColumnAnnotationsExt {
+    #[prost(uint32, tag = "1")]
+    column_id: ColumnId,
+    #[prost(string, tag = "2")]
+    display_name: String,
+    #[prost(string, tag = "3")]
+    unit: String,
+}
+
+impl MetaExt {
+    fn new(schema: Option<&Schema>, opts: Option<&TableOptions>) -> Self {
+        let column_annotations = schema
+            .map(|schema| {
+                schema
+                    .columns()
+                    .iter()
+                    .filter(|v| !v.display_name.is_empty() || !v.unit.is_empty())
+                    .map(|v| ColumnAnnotationsExt {
+                        column_id: v.id,
+                        display_name: v.display_name.clone(),
+                        unit: v.unit.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            table_options: opts.map(TableOptionsExt::from),
+            column_annotations,
+        }
+    }
+
+    /// Restore the schema and the options decoded from the pb by the
+    /// extension.
+    fn restore(self, schema: Option<&mut Schema>, opts: Option<&mut TableOptions>) -> Result<()> {
+        if let (Some(opts), Some(table_options)) = (opts, self.table_options) {
+            opts.apply_ext(table_options).context(ConvertTableOptions)?;
+        }
+
+        match schema {
+            Some(schema) if !self.column_annotations.is_empty() => {
+                let annotations: HashMap<_, _> = self
+                    .column_annotations
+                    .into_iter()
+                    .map(|v| (v.column_id, v))
+                    .collect();
+                let restored = schema.map_columns(|mut column| {
+                    if let Some(v) = annotations.get(&column.id) {
+                        column.display_name = v.display_name.clone();
+                        column.unit = v.unit.clone();
+                    }
+                    column
+                });
+                *schema = restored.context(ConvertSchema)?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
 impl TryFrom<manifest_pb::MetaUpdate> for MetaUpdate {
     type Error = Error;
 
@@ -378,11 +477,12 @@ impl TryFrom<manifest_pb::AlterOptionsMeta> for AlterOptionsMeta {
 /// An adapter to implement [wal::log_batch::Payload] for
 /// [proto::meta_update::MetaUpdate]
 #[derive(Debug)]
-pub struct MetaUpdatePayload(manifest_pb::MetaUpdate);
+pub struct MetaUpdatePayload(manifest_pb::MetaUpdate, MetaExt);
 
 impl From<MetaUpdate> for MetaUpdatePayload {
     fn from(src: MetaUpdate) -> Self {
-        Self(src.into())
+        let ext = src.to_ext();
+        Self(src.into(), ext)
     }
 }
 
@@ -396,11 +496,12 @@ impl Payload for MetaUpdatePayload {
     type Error = Error;
 
     fn encode_size(&self) -> usize {
-        self.0.encoded_len()
+        self.0.encoded_len() + self.1.encoded_len()
     }
 
     fn encode_to<B: BufMut>(&self, buf: &mut B) -> Result<()> {
-        self.0.encode(buf).context(EncodePayloadPb)
+        self.0.encode(buf).context(EncodePayloadPb)?;
+        self.1.encode(buf).context(EncodePayloadPb)
     }
 }
 
@@ -414,7 +515,11 @@ impl PayloadDecoder for MetaUpdateDecoder {
     fn decode<B: Buf>(&self, _ctx: &PayloadDecodeContext, buf: &mut B) -> Result<Self::Target> {
         let meta_update_pb =
             manifest_pb::MetaUpdate::decode(buf.chunk()).context(DecodePayloadPb)?;
-        MetaUpdate::try_from(meta_update_pb)
+        let ext = MetaExt::decode(buf.chunk()).context(DecodePayloadPb)?;
+        let mut meta_update = MetaUpdate::try_from(meta_update_pb)?;
+        meta_update.apply_ext(ext)?;
+
+        Ok(meta_update)
     }
 }
 
@@ -430,6 +535,33 @@ pub struct Snapshot {
     pub data: Option<MetaSnapshot>,
 }
 
+impl Snapshot {
+    /// Encode the snapshot into the pb followed by its extension.
+    pub fn encode_to_vec(&self) -> Vec<u8> {
+        let ext = self
+            .data
+            .as_ref()
+            .map(|v| MetaExt::new(Some(&v.table_meta.schema), Some(&v.table_meta.opts)))
+            .unwrap_or_default();
+
+        let mut buf = manifest_pb::Snapshot::from(self.clone()).encode_to_vec();
+        buf.extend(ext.encode_to_vec());
+        buf
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Self> {
+        let snapshot_pb = manifest_pb::Snapshot::decode(buf).context(DecodePayloadPb)?;
+        let ext = MetaExt::decode(buf).context(DecodePayloadPb)?;
+        let mut snapshot = Snapshot::try_from(snapshot_pb)?;
+        if let Some(data) = &mut snapshot.data {
+            let table_meta = &mut data.table_meta;
+            ext.restore(Some(&mut table_meta.schema), Some(&mut table_meta.opts))?;
+        }
+
+        Ok(snapshot)
+    }
+}
+
 impl TryFrom<manifest_pb::Snapshot> for Snapshot {
     type Error = Error;
 
@@ -535,3 +667,226 @@ pub struct MetaEditRequest {
     pub meta_edit: MetaEdit,
     pub table_catalog_info: TableCatalogInfo,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_types::datum::Datum;
+    use object_store::cache_priority::CachePriority;
+    use size_ext::ReadableSize;
+    use table_engine::histogram::Histogram;
+    use time_ext::ReadableDuration;
+
+    use super::*;
+    use crate::table_options::{
+        BloomFilterSizing, ColumnOptions, Compression, MergePolicy, RowGroupSizePolicy,
+        ValueEncoding,
+    };
+
+    /// Persist the table meta by the meta update and the snapshot, and return
+    /// the table metas restored from them.
+    fn restore_table_meta(table_meta: &AddTableMeta) -> (AddTableMeta, AddTableMeta) {
+        let payload = MetaUpdatePayload::from(MetaUpdate::AddTable(table_meta.clone()));
+        let mut buf = Vec::with_capacity(payload.encode_size());
+        payload.encode_to(&mut buf).unwrap();
+        let ctx = PayloadDecodeContext {
+            table_id: table_meta.table_id,
+        };
+        let MetaUpdate::AddTable(restored_meta) = MetaUpdateDecoder
+            .decode(&ctx, &mut buf.as_slice())
+            .unwrap()
+        else {
+            panic!("unexpected meta update");
+        };
+
+        let snapshot = Snapshot {
+            end_seq: 1,
+            data: Some(MetaSnapshot {
+                table_meta: table_meta.clone(),
+                version_meta: None,
+            }),
+        };
+        let restored_snapshot = Snapshot::decode(&snapshot.encode_to_vec()).unwrap();
+
+        (restored_meta, restored_snapshot.data.unwrap().table_meta)
+    }
+
+    fn build_table_meta(schema: Schema, opts: TableOptions) -> AddTableMeta {
+        AddTableMeta {
+            space_id: 0,
+            table_id: TableId::from(1),
+            table_name: "test_table".to_string(),
+            schema,
+            opts,
+        }
+    }
+
+    fn check_options_persisted(opts: TableOptions) {
+        let table_meta = build_table_meta(common_types::tests::build_schema(), opts);
+        let (from_update, from_snapshot) = restore_table_meta(&table_meta);
+        assert_eq!(table_meta, from_update);
+        assert_eq!(table_meta, from_snapshot);
+    }
+
+    #[test]
+    fn test_persist_column_annotations() {
+        let schema = common_types::tests::build_schema()
+            .map_columns(|mut column| {
+                if column.name == "field1" {
+                    column.display_name = "Field One".to_string();
+                    column.unit = "ms".to_string();
+                }
+                column
+            })
+            .unwrap();
+        let table_meta = build_table_meta(schema, TableOptions::default());
+
+        let (from_update, from_snapshot) = restore_table_meta(&table_meta);
+        for restored in [from_update, from_snapshot] {
+            assert_eq!(table_meta, restored);
+            let column = restored.schema.column_with_name("field1").unwrap();
+            assert_eq!("Field One", column.display_name);
+            assert_eq!("ms", column.unit);
+        }
+    }
+
+    #[test]
+    fn test_persist_default_options() {
+        check_options_persisted(TableOptions::default());
+    }
+
+    #[test]
+    fn test_persist_row_group_options() {
+        check_options_persisted(TableOptions {
+            row_group_size_policy: RowGroupSizePolicy::Adaptive,
+            data_page_size: ReadableSize::kb(256),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_cache_priority() {
+        check_options_persisted(TableOptions {
+            cache_priority: CachePriority::High,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_timestamp_resolution() {
+        check_options_persisted(TableOptions {
+            timestamp_resolution: Some(ReadableDuration::secs(1)),
+            timestamp_original_column: Some("raw_timestamp".to_string()),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_hot_duration() {
+        check_options_persisted(TableOptions {
+            hot_duration: Some(ReadableDuration::days(7)),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_transform_pipeline() {
+        check_options_persisted(TableOptions {
+            transform_pipeline: Some("RENAME host TO hostname".to_string()),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_bloom_filter_options() {
+        check_options_persisted(TableOptions {
+            bloom_filter_fpp: Some(0.05),
+            bloom_filter_sizing: BloomFilterSizing::Fixed,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_compression_options() {
+        let column_options = ColumnOptions {
+            encodings: [("host".to_string(), ValueEncoding::Dict)].into(),
+            compressions: [("value".to_string(), Compression::Lz4)].into(),
+        };
+        check_options_persisted(TableOptions {
+            compression_level: Some(9),
+            column_options,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_adaptive_compression() {
+        check_options_persisted(TableOptions {
+            adaptive_compression: true,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_merge_policy() {
+        check_options_persisted(TableOptions {
+            merge_policy: MergePolicy::Sum,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_separated_fields() {
+        check_options_persisted(TableOptions {
+            separated_fields: vec!["payload".to_string()],
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_bloom_filter_recent_duration() {
+        check_options_persisted(TableOptions {
+            bloom_filter_recent_duration: Some(ReadableDuration::days(3)),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_persist_histograms() {
+        let tag_histogram = Histogram {
+            num_rows: 10,
+            num_nulls: 2,
+            num_distinct: 3,
+            min: Some(Datum::String("a".into())),
+            bounds: vec![Datum::String("b".into()), Datum::String("c".into())],
+        };
+        let field_histogram = Histogram {
+            num_rows: 10,
+            num_nulls: 0,
+            num_distinct: 10,
+            min: Some(Datum::Int64(-5)),
+            bounds: vec![Datum::Int64(0), Datum::Int64(5), Datum::Int64(100)],
+        };
+        let null_histogram = Histogram {
+            num_rows: 10,
+            num_nulls: 10,
+            num_distinct: 0,
+            min: None,
+            bounds: Vec::new(),
+        };
+        let histograms = [
+            ("tag1", tag_histogram),
+            ("field1", field_histogram),
+            ("field2", null_histogram),
+        ]
+        .into_iter()
+        .map(|(column, histogram)| (column.to_string(), histogram))
+        .collect();
+
+        check_options_persisted(TableOptions {
+            histograms: Arc::new(histograms),
+            ..Default::default()
+        });
+    }
+}
@@ -20,7 +20,7 @@
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicU64, AtomicUsize},
+        atomic::{AtomicI64, AtomicU64, AtomicUsize},
         Arc, RwLock,
     },
 };
@@ -45,6 +45,9 @@ impl Factory for ColumnarMemTableFactory {
             row_num: AtomicUsize::new(0),
             opts,
             memtable_size: AtomicUsize::new(0),
+            // Init to the max value, so `min(min_time, timestamp)` gets the real one.
+            min_time: AtomicI64::new(i64::MAX),
+            max_time: AtomicI64::new(i64::MIN),
             metrics: Default::default(),
         });
 
@@ -18,7 +18,7 @@
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicU64, AtomicUsize, Ordering},
+        atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering},
         Arc, RwLock,
     },
 };
@@ -35,10 +35,10 @@ use skiplist::{BytewiseComparator, Skiplist};
 use snafu::{ensure, OptionExt, ResultExt};
 
 use crate::memtable::{
-    columnar::iter::ColumnarIterImpl, factory::Options, key::KeySequence,
+    columnar::iter::ColumnarIterImpl, factory::Options, interner, key::KeySequence,
     reversed_iter::ReversedColumnarIterator, ColumnarIterPtr, Internal, InternalNoCause,
     InvalidPutSequence, MemTable, Metrics as MemtableMetrics, PutContext, Result, ScanContext,
-    ScanRequest,
+    ScanRequest, TimestampNotFound,
 };
 
 pub mod factory;
@@ -60,6 +60,10 @@ pub struct ColumnarMemTable {
     row_num: AtomicUsize,
     opts: Options,
     memtable_size: AtomicUsize,
+    /// Min and max timestamp of the rows, used to skip this memtable when the
+    /// queried time range doesn't overlap with them.
+    min_time: AtomicI64,
+    max_time: AtomicI64,
 
     metrics: Metrics,
 }
@@ -122,8 +126,19 @@ impl MemTable for ColumnarMemTable {
 
             if let Some(writer_index) = ctx.index_in_writer.column_index_in_writer(i) {
                 let datum = &row[writer_index];
+                let interned = match datum {
+                    Datum::String(v) if column_schema.is_tag => interner::intern(v.as_str()),
+                    _ => None,
+                };
                 if datum == &Datum::Null {
                     column.append_nulls(1);
+                } else if let Some(interned) = interned {
+                    column
+                        .append_datum(Datum::String(interned))
+                        .box_err()
+                        .context(Internal {
+                            msg: "append datum failed",
+                        })?
                 } else {
                     column
                         .append_datum_ref(&row[writer_index])
@@ -151,6 +166,10 @@ impl MemTable for ColumnarMemTable {
 
         self.row_num.fetch_add(1, Ordering::Acquire);
 
+        let timestamp = row.timestamp(schema).context(TimestampNotFound)?.as_i64();
+        self.min_time.fetch_min(timestamp, Ordering::Relaxed);
+        self.max_time.fetch_max(timestamp, Ordering::Relaxed);
+
         // May have performance issue.
         self.memtable_size
             .store(self.memtable_size(), Ordering::Relaxed);
@@ -234,9 +253,10 @@ impl MemTable for ColumnarMemTable {
         self.last_sequence.load(Ordering::Relaxed)
     }
 
-    // TODO: implement this.
     fn time_range(&self) -> Option<TimeRange> {
-        None
+        let min_time = self.min_time.load(Ordering::Relaxed);
+        let max_time = self.max_time.load(Ordering::Relaxed);
+        TimeRange::new(min_time.into(), max_time.saturating_add(1).into())
     }
 
     fn metrics(&self) -> MemtableMetrics {
@@ -249,3 +269,41 @@ impl MemTable for ColumnarMemTable {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arena::NoopCollector;
+    use common_types::{
+        schema::IndexInWriterSchema,
+        tests::{build_row, build_schema},
+        time::Timestamp,
+    };
+
+    use super::*;
+    use crate::memtable::{columnar::factory::ColumnarMemTableFactory, factory::Factory};
+
+    #[test]
+    fn test_columnar_memtable_time_range() {
+        let schema = build_schema();
+        let memtable = ColumnarMemTableFactory
+            .create_memtable(Options {
+                schema: schema.clone(),
+                arena_block_size: 512,
+                creation_sequence: 1,
+                collector: Arc::new(NoopCollector {}),
+            })
+            .unwrap();
+        assert!(memtable.time_range().is_none());
+
+        let mut ctx = PutContext::new(IndexInWriterSchema::for_same_schema(schema.num_columns()));
+        for (seq, ts) in [(1, 1000), (2, 3000), (3, 2000)] {
+            let row = build_row(b"a", ts, 10.0, "v", 1000, 1_000_000);
+            memtable
+                .put(&mut ctx, KeySequence::new(seq, 0), &row, &schema)
+                .unwrap();
+        }
+
+        let expect = TimeRange::new(Timestamp::new(1000), Timestamp::new(3001)).unwrap();
+        assert_eq!(Some(expect), memtable.time_range());
+    }
+}
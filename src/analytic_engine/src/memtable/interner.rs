@@ -0,0 +1,157 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interner of the string tag values shared by the memtables of all the
+//! tables.
+//!
+//! The long tag values, e.g. urls and pod names, are usually repeated in a lot
+//! of rows, and interning them saves the memory of the memtables and makes the
+//! equality comparisons cheap as the interned values share the same storage.
+//! Only the columnar memtable stores the interned values as the other ones
+//! encode the rows into their own buffers.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    sync::{Mutex, OnceLock},
+};
+
+use common_types::string::StringBytes;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+
+const NUM_SHARDS: usize = 16;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max total size of the interned values, the interned values of a shard
+    /// are cleared once the size of the shard exceeds its share.
+    pub capacity: ReadableSize,
+    /// Values shorter than this are not interned.
+    pub min_value_len: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            capacity: ReadableSize::mb(64),
+            min_value_len: 16,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shard {
+    values: HashSet<StringBytes>,
+    size: usize,
+}
+
+pub struct StringInterner {
+    shard_capacity: usize,
+    min_value_len: usize,
+    shards: Vec<Mutex<Shard>>,
+}
+
+impl StringInterner {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            shard_capacity: config.capacity.as_byte() as usize / NUM_SHARDS,
+            min_value_len: config.min_value_len,
+            shards: (0..NUM_SHARDS).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    /// Intern the value, returns the interned one sharing the storage with the
+    /// same values, or `None` if the value is too short to be interned.
+    pub fn intern(&self, value: &str) -> Option<StringBytes> {
+        if value.len() < self.min_value_len || value.len() > self.shard_capacity {
+            return None;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let shard_idx = hasher.finish() as usize % NUM_SHARDS;
+
+        let mut shard = self.shards[shard_idx].lock().unwrap();
+        if let Some(interned) = shard.values.get(value) {
+            return Some(interned.clone());
+        }
+
+        // The values cleared are still alive in the memtables referencing them, so
+        // the memory is bounded by only dropping them from the interner.
+        if shard.size + value.len() > self.shard_capacity {
+            shard.values.clear();
+            shard.size = 0;
+        }
+        let interned = StringBytes::copy_from_str(value);
+        shard.size += value.len();
+        shard.values.insert(interned.clone());
+
+        Some(interned)
+    }
+}
+
+static INTERNER: OnceLock<StringInterner> = OnceLock::new();
+
+/// Initialize the global interner, nothing is interned if it's not enabled.
+pub fn init(config: &Config) {
+    if config.enable {
+        let _ = INTERNER.set(StringInterner::new(config));
+    }
+}
+
+/// Intern the value by the global interner, `None` if the interner isn't
+/// enabled or the value isn't interned.
+pub fn intern(value: &str) -> Option<StringBytes> {
+    INTERNER.get().and_then(|interner| interner.intern(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let interner = StringInterner::new(&Config {
+            enable: true,
+            capacity: ReadableSize(NUM_SHARDS as u64 * 64),
+            min_value_len: 4,
+        });
+
+        assert!(interner.intern("abc").is_none());
+
+        let value = "http://example.com/a";
+        let v1 = interner.intern(value).unwrap();
+        let v2 = interner.intern(&value.to_string()).unwrap();
+        assert_eq!(v1.as_str(), value);
+        assert!(v1.ptr_eq(&v2));
+
+        // Too large to be interned.
+        assert!(interner.intern(&"a".repeat(65)).is_none());
+
+        // The values are still interned after the shard is cleared.
+        let value = "a".repeat(40);
+        let v1 = interner.intern(&value).unwrap();
+        let v2 = interner.intern(&value).unwrap();
+        assert!(v1.ptr_eq(&v2));
+        let v3 = interner.intern(&"b".repeat(40)).unwrap();
+        assert_eq!(v3.as_str(), "b".repeat(40));
+    }
+}
@@ -19,6 +19,7 @@
 
 pub mod columnar;
 pub mod factory;
+pub mod interner;
 pub mod key;
 pub mod layered;
 mod reversed_iter;
@@ -249,7 +249,7 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send + 'static> MemTable
     fn time_range(&self) -> Option<TimeRange> {
         let min_time = self.min_time.load(atomic::Ordering::Relaxed);
         let max_time = self.max_time.load(atomic::Ordering::Relaxed);
-        TimeRange::new(min_time.into(), (max_time + 1).into())
+        TimeRange::new(min_time.into(), max_time.saturating_add(1).into())
     }
 
     fn metrics(&self) -> MemtableMetrics {
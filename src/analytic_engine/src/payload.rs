@@ -176,6 +176,9 @@ pub enum ReadPayload {
     Write { row_group: RowGroup },
     AlterSchema { schema: Schema },
     AlterOptions { options: TableOptions },
+    /// The payload failing to decode, only returned by the decoder keeping the
+    /// unreplayable payloads, see [WalDecoder::keep_unreplayable].
+    Unreplayable { raw: Vec<u8>, cause: String },
 }
 
 impl ReadPayload {
@@ -295,22 +298,30 @@ impl TableSchemaProvider for SingleSchemaProviderAdapter {
 /// Wal payload decoder
 pub struct WalDecoder<P> {
     schema_provider: P,
+    keep_unreplayable: bool,
 }
 
 impl<P: TableSchemaProvider> WalDecoder<P> {
     pub fn new(schema_provider: P) -> Self {
-        Self { schema_provider }
+        Self {
+            schema_provider,
+            keep_unreplayable: false,
+        }
     }
-}
 
-impl<P> PayloadDecoder for WalDecoder<P>
-where
-    P: TableSchemaProvider + Send + Sync,
-{
-    type Error = Error;
-    type Target = ReadPayload;
+    /// Return the payloads failing to decode as [ReadPayload::Unreplayable]
+    /// with their raw bytes rather than failing the whole read, so the other
+    /// entries can still be replayed.
+    pub fn keep_unreplayable(mut self, keep_unreplayable: bool) -> Self {
+        self.keep_unreplayable = keep_unreplayable;
+        self
+    }
 
-    fn decode<B: Buf>(&self, ctx: &PayloadDecodeContext, buf: &mut B) -> Result<Self::Target> {
+    fn decode_payload<B: Buf>(
+        &self,
+        ctx: &PayloadDecodeContext,
+        buf: &mut B,
+    ) -> Result<ReadPayload> {
         let header_value = buf.try_get_u8().context(DecodeHeader)?;
         let header = match Header::from_u8(header_value) {
             Some(header) => header,
@@ -336,3 +347,26 @@ where
         Ok(payload)
     }
 }
+
+impl<P> PayloadDecoder for WalDecoder<P>
+where
+    P: TableSchemaProvider + Send + Sync,
+{
+    type Error = Error;
+    type Target = ReadPayload;
+
+    fn decode<B: Buf>(&self, ctx: &PayloadDecodeContext, buf: &mut B) -> Result<Self::Target> {
+        if !self.keep_unreplayable {
+            return self.decode_payload(ctx, buf);
+        }
+
+        let raw = buf.chunk().to_vec();
+        match self.decode_payload(ctx, buf) {
+            Ok(payload) => Ok(payload),
+            Err(e) => Ok(ReadPayload::Unreplayable {
+                raw,
+                cause: e.to_string(),
+            }),
+        }
+    }
+}
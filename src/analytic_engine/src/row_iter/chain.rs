@@ -26,6 +26,7 @@ use common_types::{
     record_batch::FetchedRecordBatch,
     request_id::RequestId,
     schema::RecordSchemaWithKey,
+    SequenceNumber,
 };
 use generic_error::GenericError;
 use logger::debug;
@@ -46,6 +47,7 @@ use crate::{
     sst::{
         factory::{FactoryRef as SstFactoryRef, ObjectStorePickerRef},
         file::FileHandle,
+        quarantine::CorruptSstSkipper,
     },
     table::version::{MemTableVec, SamplingMemTable},
 };
@@ -76,6 +78,8 @@ pub struct ChainConfig<'a> {
     pub deadline: Option<Instant>,
     pub space_id: SpaceId,
     pub table_id: TableId,
+    /// Max visible sequence (inclusive) of the memtables
+    pub sequence: SequenceNumber,
     /// The projected schema to read.
     pub projected_schema: ProjectedSchema,
     /// Predicate of the query.
@@ -87,6 +91,9 @@ pub struct ChainConfig<'a> {
     pub sst_factory: &'a SstFactoryRef,
     /// Store picker for persisting sst.
     pub store_picker: &'a ObjectStorePickerRef,
+    /// Skip the corrupt ssts instead of failing the read, which should be set
+    /// only for the queries.
+    pub corrupt_sst_skipper: Option<CorruptSstSkipper>,
 }
 
 /// Builder for [ChainIterator].
@@ -137,6 +144,7 @@ impl<'a> Builder<'a> {
             .build(row_projector_builder.clone());
 
         let memtable_stream_ctx = MemtableStreamContext {
+            sequence: self.config.sequence,
             row_projector_builder,
             fetched_schema: fetched_schema.clone(),
             predicate: self.config.predicate,
@@ -181,6 +189,11 @@ impl<'a> Builder<'a> {
 
         for leveled_ssts in &self.ssts {
             for sst in leveled_ssts {
+                let skipper = self.config.corrupt_sst_skipper.as_ref();
+                if skipper.map_or(false, |v| v.skip_quarantined(sst.id())) {
+                    continue;
+                }
+
                 let stream = record_batch_stream::filtered_stream_from_sst_file(
                     self.config.space_id,
                     self.config.table_id,
@@ -190,9 +203,14 @@ impl<'a> Builder<'a> {
                     &sst_stream_ctx,
                     self.config.metrics_collector.clone(),
                 )
-                .await
-                .context(BuildStreamFromSst)?;
-                streams.push(stream);
+                .await;
+                let stream = match skipper {
+                    Some(skipper) => skipper.on_stream_built(sst.id(), stream),
+                    None => stream.map(Some),
+                };
+                if let Some(stream) = stream.context(BuildStreamFromSst)? {
+                    streams.push(stream);
+                }
             }
         }
 
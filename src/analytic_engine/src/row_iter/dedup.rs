@@ -17,8 +17,10 @@
 
 use std::cmp::Ordering;
 
+use arrow::array::BooleanArray;
 use async_trait::async_trait;
 use common_types::{
+    datum::Datum,
     record_batch::{FetchedRecordBatch, FetchedRecordBatchBuilder},
     request_id::RequestId,
     row::{Row, RowViewOnBatch, RowWithMeta},
@@ -29,20 +31,23 @@ use logger::{info, trace};
 use macros::define_result;
 use snafu::{ResultExt, Snafu};
 
-use crate::row_iter::{FetchedRecordBatchIterator, IterOptions};
+use crate::{
+    row_iter::{FetchedRecordBatchIterator, IterOptions},
+    table_options::MergePolicy,
+};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Failed to iterate column, error:{:?}", source))]
     IterateColumn { source: common_types::row::Error },
 
-    #[snafu(display("Failed to build record batch, error:{:?}", source))]
-    BuildRecordBatch {
+    #[snafu(display("Failed to select rows, err:{:?}", source))]
+    SelectRows {
         source: common_types::record_batch::Error,
     },
 
-    #[snafu(display("Failed to append row, err:{:?}", source))]
-    AppendRow {
+    #[snafu(display("Failed to build the merged rows, err:{:?}", source))]
+    BuildMergedRows {
         source: common_types::record_batch::Error,
     },
 
@@ -53,12 +58,13 @@ pub enum Error {
 define_result!(Error);
 
 /// Dedup the elements from the `iter` by choosing the first one in the
-/// duplicate rows.
+/// duplicate rows, or merging them by the [MergePolicy].
 pub struct DedupIterator<I> {
     request_id: RequestId,
     schema: RecordSchemaWithKey,
-    record_batch_builder: FetchedRecordBatchBuilder,
     iter: I,
+    /// Merger of the duplicate rows, `None` if the first one is chosen.
+    merger: Option<RowMerger>,
     /// Previous row returned.
     prev_row: Option<Row>,
     /// Store which row in record batch is keep, use Vec<bool> is a bit faster
@@ -71,22 +77,21 @@ pub struct DedupIterator<I> {
 }
 
 impl<I: FetchedRecordBatchIterator> DedupIterator<I> {
-    pub fn new(request_id: RequestId, iter: I, iter_options: IterOptions) -> Self {
-        let schema_with_key = iter.schema();
-        let primary_key_indexes = schema_with_key.primary_key_idx().to_vec();
-        let fetched_schema = schema_with_key.to_record_schema();
-        let record_batch_builder = FetchedRecordBatchBuilder::with_capacity(
-            fetched_schema,
-            Some(primary_key_indexes),
-            iter_options.batch_size,
-        );
+    pub fn new(
+        request_id: RequestId,
+        iter: I,
+        iter_options: IterOptions,
+        merge_policy: MergePolicy,
+    ) -> Self {
+        let schema_with_key = iter.schema().clone();
+        let merger = RowMerger::new(merge_policy, schema_with_key.clone());
         Self {
             request_id,
-            schema: schema_with_key.clone(),
-            record_batch_builder,
+            schema: schema_with_key,
             iter,
+            merger,
             prev_row: None,
-            selected_rows: Vec::new(),
+            selected_rows: Vec::with_capacity(iter_options.batch_size),
             total_duplications: 0,
             total_selected_rows: 0,
         }
@@ -148,7 +153,7 @@ impl<I: FetchedRecordBatchIterator> DedupIterator<I> {
     /// Filter batch by `selected_rows`.
     fn filter_batch(
         &mut self,
-        record_batch: FetchedRecordBatch,
+        mut record_batch: FetchedRecordBatch,
         selected_num: usize,
     ) -> Result<FetchedRecordBatch> {
         self.total_selected_rows += selected_num;
@@ -159,19 +164,14 @@ impl<I: FetchedRecordBatchIterator> DedupIterator<I> {
             return Ok(record_batch);
         }
 
-        self.record_batch_builder.clear();
-        for (row_idx, selected) in self.selected_rows.iter().enumerate() {
-            if *selected {
-                self.record_batch_builder
-                    .append_row_view(&RowViewOnBatch {
-                        record_batch: &record_batch,
-                        row_idx,
-                    })
-                    .context(AppendRow)?;
-            }
-        }
+        // Filter the arrow arrays directly rather than copying the selected rows
+        // one by one.
+        let filter_array = BooleanArray::from(self.selected_rows.clone());
+        record_batch
+            .select_data(&filter_array)
+            .context(SelectRows)?;
 
-        self.record_batch_builder.build().context(BuildRecordBatch)
+        Ok(record_batch)
     }
 }
 
@@ -198,9 +198,20 @@ impl<I: FetchedRecordBatchIterator> FetchedRecordBatchIterator for DedupIterator
                     record_batch
                 );
 
-                self.dedup_batch(record_batch).map(Some)
+                match &mut self.merger {
+                    Some(merger) => merger.merge_batch(record_batch).map(Some),
+                    None => self.dedup_batch(record_batch).map(Some),
+                }
             }
             None => {
+                if let Some(merger) = &mut self.merger {
+                    if let Some(record_batch) = merger.finish()? {
+                        return Ok(Some(record_batch));
+                    }
+                    self.total_duplications = merger.num_merged_rows;
+                    self.total_selected_rows = merger.num_output_rows;
+                }
+
                 info!(
                     "DedupIterator received none record batch, request_id:{}, total_duplications:{}, total_selected_rows:{}",
                     self.request_id, self.total_duplications, self.total_selected_rows,
@@ -212,6 +223,143 @@ impl<I: FetchedRecordBatchIterator> FetchedRecordBatchIterator for DedupIterator
     }
 }
 
+/// Merger of the rows with the same primary key by the [MergePolicy].
+///
+/// The rows must be sorted by the key and the later written one comes first
+/// in the duplicate rows, and the merged row is held until the rows of the
+/// next key come, so [RowMerger::finish] must be called after all the rows
+/// are merged.
+pub(crate) struct RowMerger {
+    merge_policy: MergePolicy,
+    schema: RecordSchemaWithKey,
+    /// The merged row not returned yet.
+    pending_row: Option<Row>,
+
+    // Metrics:
+    num_merged_rows: usize,
+    num_output_rows: usize,
+}
+
+impl RowMerger {
+    /// Returns `None` for the [MergePolicy::LastWrite] as nothing needs to be
+    /// merged besides choosing the first row.
+    pub(crate) fn new(merge_policy: MergePolicy, schema: RecordSchemaWithKey) -> Option<Self> {
+        if merge_policy == MergePolicy::LastWrite {
+            return None;
+        }
+
+        Some(Self {
+            merge_policy,
+            schema,
+            pending_row: None,
+            num_merged_rows: 0,
+            num_output_rows: 0,
+        })
+    }
+
+    pub(crate) fn merge_batch(
+        &mut self,
+        record_batch: FetchedRecordBatch,
+    ) -> Result<FetchedRecordBatch> {
+        let mut builder = self.new_builder(record_batch.num_rows());
+        for row_idx in 0..record_batch.num_rows() {
+            let row = record_batch.clone_row_at(row_idx);
+            match self.pending_row.take() {
+                Some(mut pending_row) if self.is_same_key(&pending_row, &row) => {
+                    self.merge_row(&mut pending_row, &row);
+                    self.pending_row = Some(pending_row);
+                    self.num_merged_rows += 1;
+                }
+                pending_row => {
+                    if let Some(pending_row) = pending_row {
+                        builder.append_row(pending_row).context(BuildMergedRows)?;
+                        self.num_output_rows += 1;
+                    }
+                    self.pending_row = Some(row);
+                }
+            }
+        }
+
+        builder.build().context(BuildMergedRows)
+    }
+
+    /// Returns the last merged row, `None` if no row is pending.
+    pub(crate) fn finish(&mut self) -> Result<Option<FetchedRecordBatch>> {
+        let Some(pending_row) = self.pending_row.take() else {
+            return Ok(None);
+        };
+
+        let mut builder = self.new_builder(1);
+        builder.append_row(pending_row).context(BuildMergedRows)?;
+        self.num_output_rows += 1;
+
+        builder.build().context(BuildMergedRows).map(Some)
+    }
+
+    fn new_builder(&self, capacity: usize) -> FetchedRecordBatchBuilder {
+        FetchedRecordBatchBuilder::with_capacity(
+            self.schema.to_record_schema(),
+            Some(self.schema.primary_key_idx().to_vec()),
+            capacity,
+        )
+    }
+
+    fn is_same_key(&self, lhs: &Row, rhs: &Row) -> bool {
+        self.schema
+            .primary_key_idx()
+            .iter()
+            .all(|idx| lhs[*idx] == rhs[*idx])
+    }
+
+    /// Merge the numeric fields of the `older` row into the `latest` row, and
+    /// the null values are regarded as absent.
+    fn merge_row(&self, latest: &mut Row, older: &Row) {
+        for (idx, column) in self.schema.columns().iter().enumerate() {
+            if self.schema.is_primary_key_index(idx) || !column.data_type.is_f64_castable() {
+                continue;
+            }
+
+            let value = &older[idx];
+            if value.is_null() {
+                continue;
+            }
+            let merged = &mut latest[idx];
+            if merged.is_null() {
+                *merged = value.clone();
+                continue;
+            }
+
+            match self.merge_policy {
+                MergePolicy::LastWrite => (),
+                MergePolicy::Max => {
+                    if *value > *merged {
+                        *merged = value.clone();
+                    }
+                }
+                MergePolicy::Sum => sum_datum(merged, value),
+            }
+        }
+    }
+}
+
+/// Add the `value` to the `sum`, and the integers wrap around on overflow.
+fn sum_datum(sum: &mut Datum, value: &Datum) {
+    match (sum, value) {
+        (Datum::Double(v), Datum::Double(delta)) => *v += delta,
+        (Datum::Float(v), Datum::Float(delta)) => *v += delta,
+        (Datum::UInt64(v), Datum::UInt64(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::UInt32(v), Datum::UInt32(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::UInt16(v), Datum::UInt16(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::UInt8(v), Datum::UInt8(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::Int64(v), Datum::Int64(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::Int32(v), Datum::Int32(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::Int16(v), Datum::Int16(delta)) => *v = v.wrapping_add(*delta),
+        (Datum::Int8(v), Datum::Int8(delta)) => *v = v.wrapping_add(*delta),
+        // The values of different kinds are not expected, keep the latest one.
+        _ => (),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use common_types::tests::{build_row, build_schema};
@@ -248,8 +396,12 @@ mod tests {
             ],
         );
 
-        let mut iter =
-            DedupIterator::new(RequestId::next_id(), iter, IterOptions { batch_size: 500 });
+        let mut iter = DedupIterator::new(
+            RequestId::next_id(),
+            iter,
+            IterOptions { batch_size: 500 },
+            MergePolicy::LastWrite,
+        );
         check_iterator(
             &mut iter,
             vec![
@@ -261,4 +413,58 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn test_merge_duplicate_rows() {
+        let schema = build_schema();
+        let new_iter = |merge_policy| {
+            let iter = VectorIterator::new(
+                schema.to_record_schema_with_key(),
+                vec![
+                    build_fetched_record_batch_with_key(
+                        schema.clone(),
+                        vec![
+                            build_row(b"a", 1, 10.0, "v1", 1000, 1_000_000),
+                            build_row(b"a", 1, 5.0, "v", 1000, 1_000_000),
+                            build_row(b"a", 2, 20.0, "v2", 2000, 2_000_000),
+                        ],
+                    ),
+                    build_fetched_record_batch_with_key(
+                        schema.clone(),
+                        vec![
+                            build_row(b"a", 2, 30.0, "v", 2000, 2_000_000),
+                            build_row(b"a", 3, 3.0, "v3", 3000, 3_000_000),
+                        ],
+                    ),
+                ],
+            );
+            DedupIterator::new(
+                RequestId::next_id(),
+                iter,
+                IterOptions { batch_size: 500 },
+                merge_policy,
+            )
+        };
+
+        // The values of the field `field1` are merged, and the others are taken
+        // from the first row.
+        check_iterator(
+            &mut new_iter(MergePolicy::Max),
+            vec![
+                build_row(b"a", 1, 10.0, "v1", 1000, 1_000_000),
+                build_row(b"a", 2, 30.0, "v2", 2000, 2_000_000),
+                build_row(b"a", 3, 3.0, "v3", 3000, 3_000_000),
+            ],
+        )
+        .await;
+        check_iterator(
+            &mut new_iter(MergePolicy::Sum),
+            vec![
+                build_row(b"a", 1, 15.0, "v1", 1000, 1_000_000),
+                build_row(b"a", 2, 50.0, "v2", 2000, 2_000_000),
+                build_row(b"a", 3, 3.0, "v3", 3000, 3_000_000),
+            ],
+        )
+        .await;
+    }
 }
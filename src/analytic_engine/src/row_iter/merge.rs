@@ -54,6 +54,7 @@ use crate::{
     sst::{
         factory::{FactoryRef as SstFactoryRef, ObjectStorePickerRef},
         file::{FileHandle, Level, SST_LEVEL_NUM},
+        quarantine::CorruptSstSkipper,
     },
     table::version::{MemTableVec, SamplingMemTable},
 };
@@ -107,8 +108,7 @@ pub struct MergeConfig<'a> {
     pub deadline: Option<Instant>,
     pub space_id: SpaceId,
     pub table_id: TableId,
-    /// Max visible sequence (inclusive)
-    // TODO: remove it? seems useless.
+    /// Max visible sequence (inclusive) of the memtables
     pub sequence: SequenceNumber,
     /// The projected schema to read.
     pub projected_schema: ProjectedSchema,
@@ -127,6 +127,9 @@ pub struct MergeConfig<'a> {
     // TODO: Currently, the read the sst in a reverse order is not supported yet, that is to say,
     // the output won't be expected if it is set.
     pub reverse: bool,
+    /// Skip the corrupt ssts instead of failing the read, which should be set
+    /// only for the queries.
+    pub corrupt_sst_skipper: Option<CorruptSstSkipper>,
 }
 
 /// Builder for building merge stream from memtables and sst files.
@@ -194,6 +197,7 @@ impl<'a> MergeBuilder<'a> {
             .build(row_projector_builder.clone());
 
         let memtable_stream_ctx = MemtableStreamContext {
+            sequence: self.config.sequence,
             row_projector_builder,
             fetched_schema: fetched_schema.clone(),
             predicate: self.config.predicate,
@@ -250,6 +254,11 @@ impl<'a> MergeBuilder<'a> {
         let mut sst_ids = Vec::with_capacity(self.ssts.len());
         for leveled_ssts in &self.ssts {
             for f in leveled_ssts {
+                let skipper = self.config.corrupt_sst_skipper.as_ref();
+                if skipper.map_or(false, |v| v.skip_quarantined(f.id())) {
+                    continue;
+                }
+
                 let stream = record_batch_stream::filtered_stream_from_sst_file(
                     self.config.space_id,
                     self.config.table_id,
@@ -259,10 +268,15 @@ impl<'a> MergeBuilder<'a> {
                     &sst_stream_ctx,
                     self.config.metrics_collector.clone(),
                 )
-                .await
-                .context(BuildStreamFromSst)?;
-                streams.push(stream);
-                sst_ids.push(f.id());
+                .await;
+                let stream = match skipper {
+                    Some(skipper) => skipper.on_stream_built(f.id(), stream),
+                    None => stream.map(Some),
+                };
+                if let Some(stream) = stream.context(BuildStreamFromSst)? {
+                    streams.push(stream);
+                    sst_ids.push(f.id());
+                }
             }
         }
 
@@ -22,8 +22,11 @@ use std::{
 };
 
 use arrow::{
-    array::BooleanArray,
-    datatypes::{DataType as ArrowDataType, SchemaRef as ArrowSchemaRef},
+    array::{Array, BooleanArray, DictionaryArray},
+    compute,
+    datatypes::{DataType as ArrowDataType, Int32Type, SchemaRef as ArrowSchemaRef},
+    error::ArrowError,
+    record_batch::RecordBatch as ArrowRecordBatch,
 };
 use common_types::{
     projected_schema::RowProjectorBuilder, record_batch::FetchedRecordBatch, schema::RecordSchema,
@@ -42,7 +45,7 @@ use itertools::Itertools;
 use macros::define_result;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 use table_engine::{
-    predicate::{Predicate, PredicateRef},
+    predicate::{ColumnRegexMatch, Predicate, PredicateRef},
     table::TableId,
 };
 use trace_metric::MetricsCollector;
@@ -95,6 +98,26 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display(
+        "Fail to downcast dictionary array, actual data type:{:?}.\nBacktrace:\n{}",
+        data_type,
+        backtrace
+    ))]
+    DowncastDictionaryArray {
+        data_type: ArrowDataType,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Fail to compute filter array, err:{}.\nBacktrace:\n{}",
+        source,
+        backtrace
+    ))]
+    ArrowCompute {
+        source: ArrowError,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display(
         "Failed to get datafusion schema, err:{}.\nBacktrace:\n{}",
         source,
@@ -152,27 +175,107 @@ pub type SequencedRecordBatchRes = GenericResult<SequencedRecordBatch>;
 pub type BoxedPrefetchableRecordBatchStream =
     Box<dyn PrefetchableStream<Item = SequencedRecordBatchRes>>;
 
-/// Filter the `sequenced_record_batch` according to the `predicate`.
+/// Row filter built from the pushed down predicate.
+struct RowFilter {
+    /// Regex matches on the dictionary encoded columns, which are evaluated
+    /// once per dictionary value instead of once per row.
+    dictionary_regex_matches: Vec<(usize, ColumnRegexMatch)>,
+    /// The physical expr of the rest predicates.
+    physical_expr: Option<Arc<dyn PhysicalExpr>>,
+}
+
+impl RowFilter {
+    fn try_new(input_schema: &ArrowSchemaRef, predicate: &Predicate) -> Result<Option<Self>> {
+        let mut dictionary_regex_matches = Vec::new();
+        let mut rest_exprs = Vec::with_capacity(predicate.exprs().len());
+        for expr in predicate.exprs() {
+            let dictionary_regex_match = ColumnRegexMatch::try_from_expr(expr).and_then(|v| {
+                let (idx, field) = input_schema.column_with_name(v.column())?;
+                matches!(field.data_type(), ArrowDataType::Dictionary(_, _)).then_some((idx, v))
+            });
+
+            match dictionary_regex_match {
+                Some(v) => dictionary_regex_matches.push(v),
+                None => rest_exprs.push(expr.clone()),
+            }
+        }
+
+        let physical_expr = match conjunction(rest_exprs) {
+            Some(filter) => {
+                let input_df_schema = input_schema
+                    .clone()
+                    .to_dfschema()
+                    .context(DatafusionSchema)?;
+                let execution_props = ExecutionProps::new();
+                let physical_expr = physical_expr::create_physical_expr(
+                    &filter,
+                    &input_df_schema,
+                    input_schema.as_ref(),
+                    &execution_props,
+                )
+                .context(DatafusionExpr)?;
+                Some(physical_expr)
+            }
+            None => None,
+        };
+
+        if dictionary_regex_matches.is_empty() && physical_expr.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            dictionary_regex_matches,
+            physical_expr,
+        }))
+    }
+
+    fn evaluate(&self, record_batch: &ArrowRecordBatch) -> Result<BooleanArray> {
+        let mut selected_rows = match &self.physical_expr {
+            Some(physical_expr) => {
+                let filter_array = physical_expr
+                    .evaluate(record_batch)
+                    .map(|v| v.into_array(record_batch.num_rows()))
+                    .context(FilterExec)?
+                    .context(FilterExec)?;
+                filter_array
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .context(DowncastBooleanArray {
+                        data_type: filter_array.as_ref().data_type().clone(),
+                    })?
+                    .clone()
+            }
+            None => BooleanArray::from(vec![true; record_batch.num_rows()]),
+        };
+
+        for (column_idx, regex_match) in &self.dictionary_regex_matches {
+            let column = record_batch.column(*column_idx);
+            let column_data_type = column.data_type().clone();
+            let dictionary = column
+                .as_any()
+                .downcast_ref::<DictionaryArray<Int32Type>>()
+                .context(DowncastDictionaryArray {
+                    data_type: column_data_type,
+                })?;
+            let matched_rows = regex_match.evaluate_dictionary(dictionary);
+            selected_rows = compute::and(&selected_rows, &matched_rows).context(ArrowCompute)?;
+        }
+
+        Ok(selected_rows)
+    }
+}
+
+/// Filter the `sequenced_record_batch` according to the `row_filter`.
 fn filter_record_batch(
     mut sequenced_record_batch: SequencedRecordBatch,
-    predicate: Arc<dyn PhysicalExpr>,
+    row_filter: &RowFilter,
 ) -> Result<Option<SequencedRecordBatch>> {
     let record_batch = sequenced_record_batch.record_batch.as_arrow_record_batch();
-    let filter_array = predicate
-        .evaluate(record_batch)
-        .map(|v| v.into_array(record_batch.num_rows()))
-        .context(FilterExec)?
-        .context(FilterExec)?;
-    let selected_rows = filter_array
-        .as_any()
-        .downcast_ref::<BooleanArray>()
-        .context(DowncastBooleanArray {
-            data_type: filter_array.as_ref().data_type().clone(),
-        })?;
+    let selected_rows = row_filter.evaluate(record_batch)?;
 
     sequenced_record_batch
         .record_batch
-        .select_data(selected_rows)
+        .select_data(&selected_rows)
         .context(SelectBatchData)?;
 
     sequenced_record_batch
@@ -189,29 +292,14 @@ pub fn filter_stream(
     input_schema: ArrowSchemaRef,
     predicate: &Predicate,
 ) -> Result<BoxedPrefetchableRecordBatchStream> {
-    let filter = match conjunction(predicate.exprs().to_owned()) {
-        Some(filter) => filter,
+    let row_filter = match RowFilter::try_new(&input_schema, predicate)? {
+        Some(v) => Arc::new(v),
         None => return Ok(origin_stream),
     };
 
-    let input_df_schema = input_schema
-        .clone()
-        .to_dfschema()
-        .context(DatafusionSchema)?;
-    let execution_props = ExecutionProps::new();
-    let predicate = physical_expr::create_physical_expr(
-        &filter,
-        &input_df_schema,
-        input_schema.as_ref(),
-        &execution_props,
-    )
-    .context(DatafusionExpr)?;
-
     let stream =
         origin_stream.filter_map(move |sequence_record_batch| match sequence_record_batch {
-            Ok(v) => filter_record_batch(v, predicate.clone())
-                .box_err()
-                .transpose(),
+            Ok(v) => filter_record_batch(v, &row_filter).box_err().transpose(),
             Err(e) => Some(Err(e)),
         });
 
@@ -244,7 +332,7 @@ pub fn stream_from_memtable(
         deadline: ctx.deadline,
         ..Default::default()
     };
-    let max_seq = memtable.last_sequence();
+    let max_seq = memtable.last_sequence().min(ctx.sequence);
     let fetched_cols = ctx
         .fetched_schema
         .columns()
@@ -276,6 +364,8 @@ pub fn stream_from_memtable(
 }
 
 pub struct MemtableStreamContext {
+    /// Max visible sequence (inclusive) of the rows in the memtable.
+    pub sequence: SequenceNumber,
     pub row_projector_builder: RowProjectorBuilder,
     pub fetched_schema: RecordSchema,
     pub predicate: PredicateRef,
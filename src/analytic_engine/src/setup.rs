@@ -19,6 +19,7 @@
 
 use std::{num::NonZeroUsize, path::Path, pin::Pin, sync::Arc};
 
+use alloc_tracker::budget::MemoryBudgetRef;
 use futures::Future;
 use macros::define_result;
 use object_store::{
@@ -29,17 +30,25 @@ use object_store::{
     metrics::StoreWithMetrics,
     obkv,
     prefix::StoreWithPrefix,
-    s3, LocalFileSystem, ObjectStoreRef,
+    read_only::ReadOnlyStore,
+    s3,
+    tiered::{TieredStore, TieredStoreRef},
+    LocalFileSystem, ObjectStoreRef,
 };
 use snafu::{ResultExt, Snafu};
 use table_engine::engine::{EngineRuntimes, TableEngineRef};
 use table_kv::obkv::ObkvImpl;
-use wal::manager::{OpenedWals, WalManagerRef};
+use wal::{
+    encryption::KeyRingRef,
+    manager::{OpenedWals, WalManagerRef},
+};
 
 use crate::{
     compaction::runner::CompactionRunnerRef,
     context::OpenContext,
+    dynamic_config::DynamicConfigRef,
     engine::TableEngineImpl,
+    format,
     instance::open::{InstanceContext, ManifestStorages},
     sst::{
         factory::{FactoryImpl, ObjectStorePicker, ObjectStorePickerRef, ReadFrequency},
@@ -76,15 +85,21 @@ pub enum Error {
     OpenMemCache {
         source: object_store::mem_cache::Error,
     },
+
+    #[snafu(display("Failed to check data format, err:{}", source))]
+    CheckFormat { source: crate::format::Error },
 }
 
 define_result!(Error);
 
-const STORE_DIR_NAME: &str = "store";
+pub(crate) const STORE_DIR_NAME: &str = "store";
 const DISK_CACHE_DIR_NAME: &str = "sst_cache";
+const COLD_DISK_CACHE_DIR_NAME: &str = "cold_sst_cache";
 
 pub struct TableEngineContext {
     pub table_engine: TableEngineRef,
+    /// Options of the engine which can be changed without restarting.
+    pub dynamic_config: DynamicConfigRef,
     // TODO: unused now, will be used in remote compaction.
     pub local_compaction_runner: Option<CompactionRunnerRef>,
 }
@@ -97,12 +112,25 @@ pub struct EngineBuilder<'a> {
     pub config: &'a Config,
     pub engine_runtimes: Arc<EngineRuntimes>,
     pub opened_wals: OpenedWals,
+    pub memory_budget: MemoryBudgetRef,
 }
 
 impl<'a> EngineBuilder<'a> {
     pub async fn build(self) -> Result<TableEngineContext> {
-        let opened_storages =
-            open_storage(self.config.storage.clone(), self.engine_runtimes.clone()).await?;
+        let opened_storages = open_storage(
+            self.config.storage.clone(),
+            self.engine_runtimes.clone(),
+            self.memory_budget.clone(),
+        )
+        .await?;
+        let read_only = matches!(
+            &self.config.storage.object_store,
+            ObjectStoreOptions::Local(v) if v.read_only
+        );
+        let format_check = format::preflight(opened_storages.default_store(), read_only)
+            .await
+            .context(CheckFormat)?;
+        let rewrite_old_ssts = format_check.rewrite_ssts && self.config.format.background_rewrite;
         let manifest_storages = ManifestStorages {
             wal_manager: self.opened_wals.manifest_wal.clone(),
             oss_storage: opened_storages.default_store().clone(),
@@ -115,15 +143,20 @@ impl<'a> EngineBuilder<'a> {
             self.config.clone(),
             self.engine_runtimes,
             self.opened_wals.data_wal,
+            self.opened_wals.key_ring,
             manifest_storages,
             Arc::new(opened_storages),
+            rewrite_old_ssts,
+            self.memory_budget,
         )
         .await?;
 
+        let dynamic_config = instance.dynamic_config.clone();
         let table_engine = Arc::new(TableEngineImpl::new(instance));
 
         Ok(TableEngineContext {
             table_engine,
+            dynamic_config,
             local_compaction_runner,
         })
     }
@@ -133,8 +166,11 @@ async fn build_instance_context(
     config: Config,
     engine_runtimes: Arc<EngineRuntimes>,
     wal_manager: WalManagerRef,
+    wal_key_ring: KeyRingRef,
     manifest_storages: ManifestStorages,
     store_picker: ObjectStorePickerRef,
+    rewrite_old_ssts: bool,
+    memory_budget: MemoryBudgetRef,
 ) -> Result<InstanceContext> {
     let meta_cache: Option<MetaCacheRef> = config
         .sst_meta_cache_cap
@@ -144,6 +180,9 @@ async fn build_instance_context(
         config,
         runtimes: engine_runtimes,
         meta_cache,
+        rewrite_old_ssts,
+        memory_budget,
+        wal_key_ring,
     };
 
     let instance_ctx = InstanceContext::new(
@@ -163,6 +202,7 @@ async fn build_instance_context(
 struct OpenedStorages {
     default_store: ObjectStoreRef,
     store_with_readonly_cache: ObjectStoreRef,
+    tiered_store: Option<TieredStoreRef>,
 }
 
 impl ObjectStorePicker for OpenedStorages {
@@ -176,6 +216,96 @@ impl ObjectStorePicker for OpenedStorages {
             ReadFrequency::Frequent => &self.default_store,
         }
     }
+
+    fn tiered_store(&self) -> Option<&TieredStoreRef> {
+        self.tiered_store.as_ref()
+    }
+}
+
+async fn open_object_store(
+    opts: ObjectStoreOptions,
+    engine_runtimes: &EngineRuntimes,
+) -> Result<ObjectStoreRef> {
+    let store = match opts {
+        ObjectStoreOptions::Local(local_opts) => {
+            let data_path = Path::new(&local_opts.data_dir);
+            let sst_path = data_path.join(STORE_DIR_NAME);
+            if local_opts.read_only {
+                let store = LocalFileSystem::new_with_prefix(sst_path).context(OpenObjectStore)?;
+                Arc::new(ReadOnlyStore::new(Arc::new(store))) as _
+            } else {
+                tokio::fs::create_dir_all(&sst_path)
+                    .await
+                    .context(CreateDir {
+                        path: sst_path.to_string_lossy().into_owned(),
+                    })?;
+                let store = LocalFileSystem::new_with_prefix(sst_path).context(OpenObjectStore)?;
+                Arc::new(store) as _
+            }
+        }
+        ObjectStoreOptions::Aliyun(aliyun_opts) => {
+            let oss: ObjectStoreRef =
+                Arc::new(aliyun::try_new(&aliyun_opts).context(OpenObjectStore)?);
+            let store_with_prefix = StoreWithPrefix::new(aliyun_opts.prefix, oss);
+            Arc::new(store_with_prefix.context(OpenObjectStore)?) as _
+        }
+        ObjectStoreOptions::Obkv(obkv_opts) => {
+            let obkv_config = obkv_opts.client;
+            let obkv = engine_runtimes
+                .write_runtime
+                .spawn_blocking(move || ObkvImpl::new(obkv_config).context(OpenObkv))
+                .await
+                .context(RuntimeExec)??;
+
+            let oss: ObjectStoreRef = Arc::new(
+                obkv::ObkvObjectStore::try_new(
+                    Arc::new(obkv),
+                    obkv_opts.shard_num,
+                    obkv_opts.part_size.0 as usize,
+                    obkv_opts.max_object_size.0 as usize,
+                    obkv_opts.upload_parallelism,
+                )
+                .context(OpenObjectStore)?,
+            );
+            Arc::new(StoreWithPrefix::new(obkv_opts.prefix, oss).context(OpenObjectStore)?) as _
+        }
+        ObjectStoreOptions::S3(s3_option) => {
+            let oss: ObjectStoreRef = Arc::new(s3::try_new(&s3_option).context(OpenObjectStore)?);
+            let store_with_prefix = StoreWithPrefix::new(s3_option.prefix, oss);
+            Arc::new(store_with_prefix.context(OpenObjectStore)?) as _
+        }
+    };
+
+    Ok(Arc::new(StoreWithMetrics::new(
+        store,
+        engine_runtimes.io_runtime.clone(),
+    )))
+}
+
+async fn open_disk_cache(
+    dir_name: &str,
+    capacity: usize,
+    opts: &StorageOptions,
+    store: ObjectStoreRef,
+    engine_runtimes: &EngineRuntimes,
+) -> Result<ObjectStoreRef> {
+    let path = Path::new(&opts.disk_cache_dir).join(dir_name);
+    tokio::fs::create_dir_all(&path).await.context(CreateDir {
+        path: path.to_string_lossy().into_owned(),
+    })?;
+
+    let store = DiskCacheStore::try_new(
+        path.to_string_lossy().into_owned(),
+        capacity,
+        opts.disk_cache_page_size.as_byte() as usize,
+        store,
+        opts.disk_cache_partition_bits,
+        engine_runtimes.io_runtime.clone(),
+    )
+    .await
+    .context(OpenObjectStore)?;
+
+    Ok(Arc::new(store))
 }
 
 // Build store in multiple layer, access speed decrease in turn.
@@ -191,81 +321,48 @@ impl ObjectStorePicker for OpenedStorages {
 // |       |      |    OSS/S3....  |
 // +-------+------+----------------+
 // ```
+//
+// If the cold tier is configured, the real ObjectStore is replaced by a
+// TieredStore over the hot store and the (optionally disk cached) cold store.
 fn open_storage(
     opts: StorageOptions,
     engine_runtimes: Arc<EngineRuntimes>,
+    memory_budget: MemoryBudgetRef,
 ) -> Pin<Box<dyn Future<Output = Result<OpenedStorages>> + Send>> {
     Box::pin(async move {
-        let mut store = match opts.object_store {
-            ObjectStoreOptions::Local(local_opts) => {
-                let data_path = Path::new(&local_opts.data_dir);
-                let sst_path = data_path.join(STORE_DIR_NAME);
-                tokio::fs::create_dir_all(&sst_path)
-                    .await
-                    .context(CreateDir {
-                        path: sst_path.to_string_lossy().into_owned(),
-                    })?;
-                let store = LocalFileSystem::new_with_prefix(sst_path).context(OpenObjectStore)?;
-                Arc::new(store) as _
-            }
-            ObjectStoreOptions::Aliyun(aliyun_opts) => {
-                let oss: ObjectStoreRef =
-                    Arc::new(aliyun::try_new(&aliyun_opts).context(OpenObjectStore)?);
-                let store_with_prefix = StoreWithPrefix::new(aliyun_opts.prefix, oss);
-                Arc::new(store_with_prefix.context(OpenObjectStore)?) as _
-            }
-            ObjectStoreOptions::Obkv(obkv_opts) => {
-                let obkv_config = obkv_opts.client;
-                let obkv = engine_runtimes
-                    .write_runtime
-                    .spawn_blocking(move || ObkvImpl::new(obkv_config).context(OpenObkv))
-                    .await
-                    .context(RuntimeExec)??;
-
-                let oss: ObjectStoreRef = Arc::new(
-                    obkv::ObkvObjectStore::try_new(
-                        Arc::new(obkv),
-                        obkv_opts.shard_num,
-                        obkv_opts.part_size.0 as usize,
-                        obkv_opts.max_object_size.0 as usize,
-                        obkv_opts.upload_parallelism,
-                    )
-                    .context(OpenObjectStore)?,
-                );
-                Arc::new(StoreWithPrefix::new(obkv_opts.prefix, oss).context(OpenObjectStore)?) as _
-            }
-            ObjectStoreOptions::S3(s3_option) => {
-                let oss: ObjectStoreRef =
-                    Arc::new(s3::try_new(&s3_option).context(OpenObjectStore)?);
-                let store_with_prefix = StoreWithPrefix::new(s3_option.prefix, oss);
-                Arc::new(store_with_prefix.context(OpenObjectStore)?) as _
+        let mut store = open_object_store(opts.object_store.clone(), &engine_runtimes).await?;
+
+        // The cold tier is wrapped by its own disk cache, so the blocks read from it
+        // are cached locally.
+        let mut tiered_store = None;
+        if let Some(cold_opts) = opts.cold_object_store.clone() {
+            let mut cold_store = open_object_store(cold_opts, &engine_runtimes).await?;
+            if opts.cold_disk_cache_capacity.as_byte() > 0 {
+                cold_store = open_disk_cache(
+                    COLD_DISK_CACHE_DIR_NAME,
+                    opts.cold_disk_cache_capacity.as_byte() as usize,
+                    &opts,
+                    cold_store,
+                    &engine_runtimes,
+                )
+                .await?;
             }
-        };
 
-        store = Arc::new(StoreWithMetrics::new(
-            store,
-            engine_runtimes.io_runtime.clone(),
-        ));
+            let tiered = Arc::new(TieredStore::new(store, cold_store));
+            store = tiered.clone() as _;
+            tiered_store = Some(tiered);
+        }
 
         if opts.disk_cache_capacity.as_byte() > 0 {
-            let path = Path::new(&opts.disk_cache_dir).join(DISK_CACHE_DIR_NAME);
-            tokio::fs::create_dir_all(&path).await.context(CreateDir {
-                path: path.to_string_lossy().into_owned(),
-            })?;
-
             // TODO: Consider the readonly cache.
-            store = Arc::new(
-                DiskCacheStore::try_new(
-                    path.to_string_lossy().into_owned(),
-                    opts.disk_cache_capacity.as_byte() as usize,
-                    opts.disk_cache_page_size.as_byte() as usize,
-                    store,
-                    opts.disk_cache_partition_bits,
-                    engine_runtimes.io_runtime.clone(),
-                )
-                .await
-                .context(OpenObjectStore)?,
-            ) as _;
+            store = open_disk_cache(
+                DISK_CACHE_DIR_NAME,
+                opts.disk_cache_capacity.as_byte() as usize,
+                &opts,
+                store,
+                &engine_runtimes,
+            )
+            .await?;
         }
 
         if opts.mem_cache_capacity.as_byte() > 0 {
@@ -274,7 +371,8 @@ fn open_storage(
                     opts.mem_cache_partition_bits,
                     NonZeroUsize::new(opts.mem_cache_capacity.as_byte() as usize).unwrap(),
                 )
-                .context(OpenMemCache)?,
+                .context(OpenMemCache)?
+                .with_budget(memory_budget),
             );
             let default_store = Arc::new(MemCacheStore::new(mem_cache.clone(), store.clone())) as _;
             let store_with_readonly_cache =
@@ -282,12 +380,14 @@ fn open_storage(
             Ok(OpenedStorages {
                 default_store,
                 store_with_readonly_cache,
+                tiered_store,
             })
         } else {
             let store_with_readonly_cache = store.clone();
             Ok(OpenedStorages {
                 default_store: store,
                 store_with_readonly_cache,
+                tiered_store,
             })
         }
     })
@@ -0,0 +1,184 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Clean shutdown marker of the engine.
+//!
+//! When the engine is closed gracefully, the tables flushed completely are
+//! recorded in a marker file of their shard in the object store together with
+//! their flushed sequences. The marker is taken (loaded and deleted) when the
+//! shard is opened again, and the tables whose flushed sequences are unchanged
+//! can skip the wal replay as there is nothing to replay.
+//!
+//! The marker is only trustworthy if the tables are never written by other
+//! nodes in the meantime, so the skipping must be enabled explicitly.
+
+use std::collections::BTreeMap;
+
+use common_types::{table::ShardId, SequenceNumber};
+use generic_error::{BoxError, GenericError};
+use logger::info;
+use macros::define_result;
+use object_store::{ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use table_engine::table::TableId;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to load clean shutdown marker, path:{}, err:{}", path, source))]
+    LoadMarker { path: String, source: GenericError },
+
+    #[snafu(display("Failed to store clean shutdown marker, path:{}, err:{}", path, source))]
+    StoreMarker { path: String, source: GenericError },
+
+    #[snafu(display(
+        "Failed to delete clean shutdown marker, path:{}, err:{}",
+        path,
+        source
+    ))]
+    DeleteMarker { path: String, source: GenericError },
+}
+
+define_result!(Error);
+
+/// Directory of the marker files in the object store.
+const CLEAN_SHUTDOWN_MARKER_DIR: &str = "clean_shutdown";
+
+/// Every shard has its own marker, which is taken by the node opening the
+/// shard, so a marker won't be used by other nodes after the shard is moved.
+fn marker_path(shard_id: ShardId) -> String {
+    format!("{CLEAN_SHUTDOWN_MARKER_DIR}/{shard_id}.json")
+}
+
+/// Tables flushed completely in the last shutdown.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanShutdownMarker {
+    /// Table id -> flushed sequence of the table.
+    tables: BTreeMap<u64, SequenceNumber>,
+}
+
+impl CleanShutdownMarker {
+    pub fn add_table(&mut self, table_id: TableId, flushed_sequence: SequenceNumber) {
+        self.tables.insert(table_id.as_u64(), flushed_sequence);
+    }
+
+    /// Take the flushed sequence of the table recorded in the marker.
+    ///
+    /// Every table is only taken once, as the table may be written after it's
+    /// opened.
+    pub fn take_table(&mut self, table_id: TableId) -> Option<SequenceNumber> {
+        self.tables.remove(&table_id.as_u64())
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+}
+
+/// Load the marker of the shard and delete it from the `store`, so it won't be
+/// used again if the server crashes later.
+pub async fn take_marker(
+    store: &ObjectStoreRef,
+    shard_id: ShardId,
+) -> Result<Option<CleanShutdownMarker>> {
+    let path = marker_path(shard_id);
+    let location = Path::from(path.as_str());
+    let get_res = store.get(&location).await;
+    if let Err(object_store::ObjectStoreError::NotFound { .. }) = &get_res {
+        return Ok(None);
+    }
+
+    let payload = get_res
+        .box_err()
+        .context(LoadMarker { path: &path })?
+        .bytes()
+        .await
+        .box_err()
+        .context(LoadMarker { path: &path })?;
+    let marker: CleanShutdownMarker = serde_json::from_slice(&payload)
+        .box_err()
+        .context(LoadMarker { path: &path })?;
+
+    store
+        .delete(&location)
+        .await
+        .box_err()
+        .context(DeleteMarker { path: &path })?;
+
+    info!(
+        "Clean shutdown marker taken, shard_id:{shard_id}, tables:{}",
+        marker.tables.len()
+    );
+
+    Ok(Some(marker))
+}
+
+pub async fn store_marker(
+    store: &ObjectStoreRef,
+    shard_id: ShardId,
+    marker: &CleanShutdownMarker,
+) -> Result<()> {
+    let path = marker_path(shard_id);
+    let payload = serde_json::to_vec(marker)
+        .box_err()
+        .context(StoreMarker { path: &path })?;
+    store
+        .put(&Path::from(path.as_str()), payload.into())
+        .await
+        .box_err()
+        .context(StoreMarker { path: &path })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use object_store::LocalFileSystem;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_take_marker() {
+        let dir = TempDir::new().unwrap();
+        let store: ObjectStoreRef = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        assert!(take_marker(&store, 0).await.unwrap().is_none());
+
+        let mut marker = CleanShutdownMarker::default();
+        marker.add_table(TableId::new(1), 10);
+        marker.add_table(TableId::new(2), 20);
+        store_marker(&store, 0, &marker).await.unwrap();
+
+        // The marker of other shards is untouched.
+        assert!(take_marker(&store, 1).await.unwrap().is_none());
+
+        let mut taken = take_marker(&store, 0).await.unwrap().unwrap();
+        assert_eq!(marker, taken);
+        assert_eq!(Some(10), taken.take_table(TableId::new(1)));
+        assert_eq!(None, taken.take_table(TableId::new(1)));
+        assert_eq!(1, taken.len());
+
+        // The marker is deleted after taken.
+        assert!(take_marker(&store, 0).await.unwrap().is_none());
+    }
+}
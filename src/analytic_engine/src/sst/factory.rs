@@ -22,7 +22,7 @@ use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use async_trait::async_trait;
 use common_types::projected_schema::RowProjectorBuilder;
 use macros::define_result;
-use object_store::{ObjectStoreRef, Path};
+use object_store::{tiered::TieredStoreRef, ObjectStoreRef, Path};
 use runtime::Runtime;
 use snafu::{ResultExt, Snafu};
 use table_engine::predicate::PredicateRef;
@@ -34,6 +34,7 @@ use crate::{
         file::Level,
         header,
         header::HeaderParser,
+        io_scheduler::ScheduledIo,
         meta_data::cache::MetaCacheRef,
         metrics::MaybeTableLevelMetrics as SstMaybeTableLevelMetrics,
         parquet::{
@@ -43,7 +44,9 @@ use crate::{
         reader::SstReader,
         writer::SstWriter,
     },
-    table_options::{Compression, StorageFormat, StorageFormatHint},
+    table_options::{
+        BloomFilterOptions, ColumnOptions, Compression, StorageFormat, StorageFormatHint,
+    },
 };
 
 #[derive(Debug, Snafu)]
@@ -62,6 +65,11 @@ pub trait ObjectStorePicker: Send + Sync + Debug {
 
     /// Pick an object store according to the read frequency.
     fn pick_by_freq(&self, freq: ReadFrequency) -> &ObjectStoreRef;
+
+    /// The store to move the cold ssts, `None` if tiering is disabled.
+    fn tiered_store(&self) -> Option<&TieredStoreRef> {
+        None
+    }
 }
 
 pub type ObjectStorePickerRef = Arc<dyn ObjectStorePicker>;
@@ -147,6 +155,8 @@ pub struct SstReadOptions {
     pub predicate: PredicateRef,
     pub meta_cache: Option<MetaCacheRef>,
     pub scan_options: ScanOptions,
+    /// Schedule the reads of the scan if it's set.
+    pub scheduled_io: Option<ScheduledIo>,
 
     pub runtime: Arc<Runtime>,
 }
@@ -159,9 +169,21 @@ pub struct ColumnStats {
 pub struct SstWriteOptions {
     pub storage_format_hint: StorageFormatHint,
     pub num_rows_per_row_group: usize,
+    pub data_page_size: usize,
     pub compression: Compression,
+    /// Level of the zstd compression, `None` means the default level.
+    pub compression_level: Option<i32>,
+    /// Encodings and compressions of the specific columns.
+    pub column_options: ColumnOptions,
+    /// Choose the compressions of the other columns by sampling their values.
+    pub adaptive_compression: bool,
+    /// Field columns stored in a separate file of the sst.
+    pub separated_fields: Vec<String>,
     pub max_buffer_size: usize,
     pub column_stats: HashMap<String, ColumnStats>,
+    pub bloom_filter: Option<BloomFilterOptions>,
+    /// Whether the sst is written by the recompression of the old ssts.
+    pub recompressed: bool,
 }
 
 impl From<&ColumnStats> for ColumnEncoding {
@@ -224,12 +246,32 @@ impl Factory for FactoryImpl {
             HashMap::from_iter(options.column_stats.iter().map(|(col_name, col_stats)| {
                 (col_name.to_owned(), ColumnEncoding::from(col_stats))
             }));
+        let compression_level = options.compression_level;
+        let column_compressions = options
+            .column_options
+            .compressions
+            .iter()
+            .map(|(col_name, compression)| {
+                (
+                    col_name.to_owned(),
+                    compression.to_parquet(compression_level),
+                )
+            })
+            .collect();
         let write_options = WriteOptions {
             num_rows_per_row_group: options.num_rows_per_row_group,
+            data_page_size: options.data_page_size,
             max_buffer_size: options.max_buffer_size,
-            compression: options.compression.into(),
+            compression: options.compression.to_parquet(compression_level),
+            compression_level,
             sst_level: level,
             column_encodings,
+            value_encodings: options.column_options.encodings.clone(),
+            column_compressions,
+            adaptive_compression: options.adaptive_compression,
+            separated_fields: options.separated_fields.clone(),
+            bloom_filter: options.bloom_filter,
+            recompressed: options.recompressed,
         };
         Ok(Box::new(ParquetSstWriter::new(
             path,
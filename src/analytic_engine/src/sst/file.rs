@@ -35,19 +35,27 @@ use common_types::{
     SequenceNumber,
 };
 use future_ext::{retry_async, BackoffConfig, RetryConfig};
-use logger::{error, info, trace, warn};
+use generic_error::BoxError;
+use logger::{debug, error, info, trace, warn};
 use macros::define_result;
 use metric_ext::Meter;
 use object_store::{ObjectStoreRef, Path};
 use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use table_engine::table::TableId;
+use table_engine::{
+    table::TableId,
+    task::{TaskKind, TaskRegistryRef, TaskSpec},
+};
 use tokio::sync::{
     mpsc::{self, UnboundedReceiver, UnboundedSender},
     Mutex,
 };
 
-use crate::{space::SpaceId, sst::manager::FileId, table::sst_util, table_options::StorageFormat};
+use crate::{
+    backup::BackupLeasesRef, space::SpaceId, sst::manager::FileId, table::sst_util,
+    table_options::StorageFormat,
+};
 
 /// Error of sst file.
 #[derive(Debug, Snafu)]
@@ -131,9 +139,10 @@ impl LevelHandler {
         self.files.files_by_time_range(time_range)
     }
 
+    /// Remove ssts by ids and return the removed file handles.
     #[inline]
-    pub fn remove_ssts(&mut self, file_ids: &[FileId]) {
-        self.files.remove_by_ids(file_ids);
+    pub fn remove_ssts(&mut self, file_ids: &[FileId]) -> Vec<FileHandle> {
+        self.files.remove_by_ids(file_ids)
     }
 
     pub fn iter_ssts(&self) -> Iter {
@@ -403,13 +412,17 @@ impl FileHandleSet {
         self.id_to_files.insert(FileHandleHash(file));
     }
 
-    fn remove_by_ids(&mut self, file_ids: &[FileId]) {
+    fn remove_by_ids(&mut self, file_ids: &[FileId]) -> Vec<FileHandle> {
+        let mut removed = Vec::with_capacity(file_ids.len());
         for file_id in file_ids {
             if let Some(file) = self.id_to_files.take(file_id) {
                 let key = FileOrdKey::key_of(&file.0);
                 self.file_map.remove(&key);
+                removed.push(file.0);
             }
         }
+
+        removed
     }
 
     /// Collect ssts with time range is expired.
@@ -479,6 +492,21 @@ impl FilePurgeQueue {
         }
     }
 
+    /// Purge the files of the table deferred by the backups before the table
+    /// is closed last time, which are deferred again if any backup is running.
+    pub fn recover_deferred(&self) {
+        let request = Request::Recover {
+            space_id: self.inner.space_id,
+            table_id: self.inner.table_id,
+        };
+        if let Err(send_res) = self.inner.sender.send(request) {
+            error!(
+                "Failed to send recover deferred purges request, request:{:?}",
+                send_res.0
+            );
+        }
+    }
+
     /// Close the purge queue, then all request pushed to this queue will be
     /// ignored. This is mainly used to avoid files being deleted after the
     /// db is closed.
@@ -528,9 +556,34 @@ pub struct FilePurgeRequest {
 #[derive(Debug)]
 pub enum Request {
     Purge(FilePurgeRequest),
+    /// Purge the deferred files of the table persisted in the store.
+    Recover {
+        space_id: SpaceId,
+        table_id: TableId,
+    },
     Exit,
 }
 
+/// A file whose purge is deferred by the backups, which is persisted in the
+/// directory of its table, so it's still purged after the server restarts or
+/// the table is opened by another node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DeferredFile {
+    file_id: FileId,
+    associated_files: Vec<String>,
+}
+
+impl DeferredFile {
+    fn into_purge_request(self, space_id: SpaceId, table_id: TableId) -> FilePurgeRequest {
+        FilePurgeRequest {
+            space_id,
+            table_id,
+            file_id: self.file_id,
+            associated_files: self.associated_files,
+        }
+    }
+}
+
 /// Background file purger.
 pub struct FilePurger {
     sender: UnboundedSender<Request>,
@@ -547,15 +600,27 @@ impl FilePurger {
         },
     };
 
-    pub fn start(runtime: &Runtime, store: ObjectStoreRef) -> Self {
+    /// Interval to check whether the purges deferred by the backups can be
+    /// done.
+    const DEFERRED_PURGE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+    pub fn start(
+        runtime: &Runtime,
+        task_registry: &TaskRegistryRef,
+        store: ObjectStoreRef,
+        backup_leases: BackupLeasesRef,
+    ) -> Self {
         // We must use unbound channel, so the sender wont block when the handle is
         // dropped.
         let (tx, rx) = mpsc::unbounded_channel();
 
         // Spawn a background job to purge files.
-        let handle = runtime.spawn(async {
-            Self::purge_file_loop(store, rx).await;
-        });
+        let spec = TaskSpec::new(TaskKind::FilePurge, "");
+        let handle = task_registry.spawn_tracked(
+            runtime,
+            spec,
+            Self::purge_file_loop(store, rx, backup_leases),
+        );
 
         Self {
             sender: tx,
@@ -590,35 +655,155 @@ impl FilePurger {
         }
     }
 
-    async fn purge_file_loop(store: ObjectStoreRef, mut receiver: UnboundedReceiver<Request>) {
+    async fn purge_file(store: &ObjectStoreRef, purge_request: FilePurgeRequest) {
+        let sst_file_path = sst_util::new_sst_file_path(
+            purge_request.space_id,
+            purge_request.table_id,
+            purge_request.file_id,
+        );
+
+        info!(
+            "File purger delete file, purge_request:{:?}, sst_file_path:{}",
+            purge_request,
+            sst_file_path.to_string()
+        );
+
+        for path in purge_request.associated_files {
+            let path = Path::from(path);
+            Self::delete_file(store, &path).await;
+        }
+
+        Self::delete_file(store, &sst_file_path).await;
+    }
+
+    async fn load_deferred(
+        store: &ObjectStoreRef,
+        space_id: SpaceId,
+        table_id: TableId,
+    ) -> Vec<DeferredFile> {
+        let path = sst_util::new_deferred_purges_path(space_id, table_id);
+        let payload = match store.get(&path).await {
+            Ok(get_res) => get_res.bytes().await,
+            Err(object_store::ObjectStoreError::NotFound { .. }) => return Vec::new(),
+            Err(e) => Err(e),
+        };
+        let files = payload
+            .box_err()
+            .and_then(|payload| serde_json::from_slice(&payload).box_err());
+        match files {
+            Ok(files) => files,
+            Err(e) => {
+                error!("File purger failed to load deferred files, path:{path}, err:{e}");
+                Vec::new()
+            }
+        }
+    }
+
+    async fn store_deferred(
+        store: &ObjectStoreRef,
+        space_id: SpaceId,
+        table_id: TableId,
+        files: &[DeferredFile],
+    ) {
+        let path = sst_util::new_deferred_purges_path(space_id, table_id);
+        let res = match serde_json::to_vec(files) {
+            Ok(payload) => store.put(&path, payload.into()).await.box_err(),
+            Err(e) => Err(e).box_err(),
+        };
+        if let Err(e) = res {
+            error!("File purger failed to store deferred files, path:{path}, err:{e}");
+        }
+    }
+
+    /// Purge the deferred files of the table, and delete the persisted ones
+    /// after that.
+    async fn purge_deferred(
+        store: &ObjectStoreRef,
+        space_id: SpaceId,
+        table_id: TableId,
+        files: Vec<DeferredFile>,
+    ) {
+        info!(
+            "File purger delete deferred files, space_id:{space_id}, table_id:{table_id}, num:{}",
+            files.len()
+        );
+        for file in files {
+            Self::purge_file(store, file.into_purge_request(space_id, table_id)).await;
+        }
+
+        let path = sst_util::new_deferred_purges_path(space_id, table_id);
+        Self::delete_file(store, &path).await;
+    }
+
+    async fn purge_file_loop(
+        store: ObjectStoreRef,
+        mut receiver: UnboundedReceiver<Request>,
+        backup_leases: BackupLeasesRef,
+    ) {
         info!("File purger start");
 
-        while let Some(request) = receiver.recv().await {
-            match request {
-                Request::Purge(purge_request) => {
-                    let sst_file_path = sst_util::new_sst_file_path(
-                        purge_request.space_id,
-                        purge_request.table_id,
-                        purge_request.file_id,
-                    );
-
-                    info!(
-                        "File purger delete file, purge_request:{:?}, sst_file_path:{}",
-                        purge_request,
-                        sst_file_path.to_string()
-                    );
-
-                    for path in purge_request.associated_files {
-                        let path = Path::from(path);
-                        Self::delete_file(&store, &path).await;
+        // The files are kept while any backup is running, because they may be
+        // referenced by the manifests being copied. The deferred files of every
+        // table are persisted, so they won't leak if the purger exits before
+        // the backups are done.
+        let mut deferred: BTreeMap<(SpaceId, TableId), Vec<DeferredFile>> = BTreeMap::new();
+        let mut interval = tokio::time::interval(Self::DEFERRED_PURGE_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                request = receiver.recv() => match request {
+                    Some(Request::Purge(purge_request)) => {
+                        if backup_leases.is_active() {
+                            debug!("File purge is deferred by backup, request:{purge_request:?}");
+                            let FilePurgeRequest {
+                                space_id,
+                                table_id,
+                                file_id,
+                                associated_files,
+                            } = purge_request;
+                            let files = deferred.entry((space_id, table_id)).or_default();
+                            files.push(DeferredFile {
+                                file_id,
+                                associated_files,
+                            });
+                            Self::store_deferred(&store, space_id, table_id, files).await;
+                        } else {
+                            Self::purge_file(&store, purge_request).await;
+                        }
+                    }
+                    Some(Request::Recover { space_id, table_id }) => {
+                        let recovered = Self::load_deferred(&store, space_id, table_id).await;
+                        let files = deferred.entry((space_id, table_id)).or_default();
+                        for file in recovered {
+                            if !files.contains(&file) {
+                                files.push(file);
+                            }
+                        }
+                        if files.is_empty() {
+                            deferred.remove(&(space_id, table_id));
+                        } else if !backup_leases.is_active() {
+                            let files = deferred.remove(&(space_id, table_id)).unwrap_or_default();
+                            Self::purge_deferred(&store, space_id, table_id, files).await;
+                        }
+                    }
+                    Some(Request::Exit) | None => break,
+                },
+                _ = interval.tick(), if !deferred.is_empty() => {
+                    if !backup_leases.is_active() {
+                        for ((space_id, table_id), files) in std::mem::take(&mut deferred) {
+                            Self::purge_deferred(&store, space_id, table_id, files).await;
+                        }
                     }
-
-                    Self::delete_file(&store, &sst_file_path).await;
                 }
-                Request::Exit => break,
             }
         }
 
+        if !deferred.is_empty() {
+            warn!(
+                "File purger exit with the files deferred by backup left, which will be \
+                 purged after their tables are opened again, tables:{}",
+                deferred.len()
+            );
+        }
         info!("File purger exit");
     }
 }
@@ -627,7 +812,12 @@ pub type FilePurgerRef = Arc<FilePurger>;
 
 #[cfg(test)]
 pub mod tests {
+    use object_store::LocalFileSystem;
+    use table_engine::task::{self, TaskRegistry};
+    use tempfile::TempDir;
+
     use super::*;
+    use crate::backup::BackupLeases;
 
     pub struct FilePurgerMocker;
 
@@ -641,4 +831,79 @@ pub mod tests {
             }
         }
     }
+
+    async fn exists(store: &ObjectStoreRef, path: &Path) -> bool {
+        store.head(path).await.is_ok()
+    }
+
+    #[test]
+    fn test_persist_deferred_purges() {
+        let runtime = Arc::new(
+            runtime::Builder::default()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        runtime.clone().block_on(async move {
+            let dir = TempDir::new().unwrap();
+            let store: ObjectStoreRef =
+                Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+            let (space_id, table_id, file_id) = (1, TableId::new(2), 3);
+            let sst_path = sst_util::new_sst_file_path(space_id, table_id, file_id);
+            let deferred_path = sst_util::new_deferred_purges_path(space_id, table_id);
+            store.put(&sst_path, b"sst".to_vec().into()).await.unwrap();
+
+            // The purge is deferred and persisted while the backup is running. The
+            // requests are handled in order, so the purge is done before the exit.
+            let task_registry = Arc::new(TaskRegistry::new(&task::Config::default()));
+            let backup_leases = Arc::new(BackupLeases::default());
+            let lease = backup_leases.acquire(Duration::from_secs(60));
+            let purger = FilePurger::start(
+                &runtime,
+                &task_registry,
+                store.clone(),
+                backup_leases.clone(),
+            );
+            let request = FilePurgeRequest {
+                space_id,
+                table_id,
+                file_id,
+                associated_files: Vec::new(),
+            };
+            purger.sender.send(Request::Purge(request)).unwrap();
+            purger.stop().await.unwrap();
+            assert!(exists(&store, &sst_path).await);
+            assert!(exists(&store, &deferred_path).await);
+
+            // The recovered files are still deferred while the backup is running.
+            let purger = FilePurger::start(
+                &runtime,
+                &task_registry,
+                store.clone(),
+                backup_leases.clone(),
+            );
+            purger
+                .create_purge_queue(space_id, table_id)
+                .recover_deferred();
+            purger.stop().await.unwrap();
+            assert!(exists(&store, &sst_path).await);
+            assert!(exists(&store, &deferred_path).await);
+
+            // The recovered files are purged after the backup is done.
+            assert!(backup_leases.release(lease.id));
+            let purger = FilePurger::start(
+                &runtime,
+                &task_registry,
+                store.clone(),
+                backup_leases.clone(),
+            );
+            purger
+                .create_purge_queue(space_id, table_id)
+                .recover_deferred();
+            purger.stop().await.unwrap();
+            assert!(!exists(&store, &sst_path).await);
+            assert!(!exists(&store, &deferred_path).await);
+        });
+    }
 }
@@ -0,0 +1,222 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Scheduler of the sst reads issued by the queries
+//!
+//! The read bandwidth is shared by the classes of the reads fairly. A class is
+//! the space (tenant) of the table along with the priority of the query, so a
+//! full table export scheduled with the low priority can't starve the
+//! dashboard queries, neither can one tenant starve the others.
+//!
+//! Every class has its own token bucket, and its rate is the share of the total
+//! rate by its weight among the recently active classes. The bucket of an idle
+//! class is refilled, so a short query can burst without waiting.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use runtime::Priority;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use time_ext::ReadableDuration;
+
+use crate::{space::SpaceId, sst::metrics::SST_READ_IO_DELAY_COUNTER};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IoSchedulerConfig {
+    /// Max bytes per second read from the ssts by the queries, zero means
+    /// unlimited.
+    pub max_bytes_per_sec: ReadableSize,
+    /// Bytes can be read without waiting by an idle class, as how long they
+    /// take at the rate of the class.
+    pub burst: ReadableDuration,
+    pub high_priority_weight: u32,
+    pub low_priority_weight: u32,
+}
+
+impl Default for IoSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes_per_sec: ReadableSize(0),
+            burst: ReadableDuration::secs(1),
+            high_priority_weight: 4,
+            low_priority_weight: 1,
+        }
+    }
+}
+
+/// Class of the reads sharing the bandwidth fairly with other classes.
+#[derive(Debug, Clone, Copy)]
+pub struct IoClass {
+    pub space_id: SpaceId,
+    pub priority: Priority,
+}
+
+impl IoClass {
+    fn key(&self) -> (SpaceId, u8) {
+        (self.space_id, self.priority.as_u8())
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    weight: u32,
+    /// When the bytes acquired so far are all consumed at the rate of the
+    /// class, and the tokens are accumulated if it's before now.
+    next_free: Instant,
+}
+
+#[derive(Debug)]
+pub struct IoScheduler {
+    bytes_per_sec: u64,
+    burst: Duration,
+    high_priority_weight: u32,
+    low_priority_weight: u32,
+    buckets: Mutex<HashMap<(SpaceId, u8), TokenBucket>>,
+}
+
+pub type IoSchedulerRef = Arc<IoScheduler>;
+
+impl IoScheduler {
+    /// Create the scheduler, `None` if the reads are not limited.
+    pub fn try_new(config: &IoSchedulerConfig) -> Option<Self> {
+        let bytes_per_sec = config.max_bytes_per_sec.as_byte();
+        if bytes_per_sec == 0 {
+            return None;
+        }
+
+        Some(Self {
+            bytes_per_sec,
+            burst: config.burst.0,
+            high_priority_weight: config.high_priority_weight.max(1),
+            low_priority_weight: config.low_priority_weight.max(1),
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Acquire the `bytes` for the `class` at `now`, returns how long to wait
+    /// before reading them.
+    pub fn acquire(&self, class: &IoClass, bytes: u64, now: Instant) -> Duration {
+        let weight = match class.priority {
+            Priority::High => self.high_priority_weight,
+            Priority::Low => self.low_priority_weight,
+        };
+        let full_at = now.checked_sub(self.burst).unwrap_or(now);
+
+        let mut buckets = self.buckets.lock().unwrap();
+        // The buckets full of tokens are the same as the new ones, and their
+        // classes are not active anymore.
+        buckets.retain(|_, bucket| bucket.next_free > full_at);
+        let bucket = buckets.entry(class.key()).or_insert(TokenBucket {
+            weight,
+            next_free: full_at,
+        });
+        let start = bucket.next_free;
+        let total_weight: u32 = buckets.values().map(|v| v.weight).sum();
+
+        let rate = self.bytes_per_sec as f64 * weight as f64 / total_weight as f64;
+        let cost = Duration::from_secs_f64(bytes as f64 / rate);
+        let bucket = buckets.get_mut(&class.key()).unwrap();
+        bucket.next_free = start + cost;
+
+        start.saturating_duration_since(now)
+    }
+}
+
+/// The reads of a scan scheduled as the `class`.
+#[derive(Debug, Clone)]
+pub struct ScheduledIo {
+    scheduler: IoSchedulerRef,
+    class: IoClass,
+}
+
+impl ScheduledIo {
+    pub fn new(scheduler: IoSchedulerRef, class: IoClass) -> Self {
+        Self { scheduler, class }
+    }
+
+    /// How long to wait before reading the `bytes`.
+    pub fn delay(&self, bytes: u64) -> Duration {
+        let delay = self.scheduler.acquire(&self.class, bytes, Instant::now());
+        if !delay.is_zero() {
+            SST_READ_IO_DELAY_COUNTER
+                .with_label_values(&[self.class.priority.as_str()])
+                .inc_by(delay.as_millis() as u64);
+        }
+
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_scheduler(bytes_per_sec: u64) -> IoScheduler {
+        let config = IoSchedulerConfig {
+            max_bytes_per_sec: ReadableSize(bytes_per_sec),
+            burst: ReadableDuration::secs(0),
+            ..Default::default()
+        };
+        IoScheduler::try_new(&config).unwrap()
+    }
+
+    fn class(space_id: SpaceId, priority: Priority) -> IoClass {
+        IoClass { space_id, priority }
+    }
+
+    #[test]
+    fn test_io_scheduler_fairness() {
+        assert!(IoScheduler::try_new(&IoSchedulerConfig::default()).is_none());
+
+        let scheduler = new_scheduler(1000);
+        let now = Instant::now();
+        let export = class(1, Priority::Low);
+        let dashboard = class(1, Priority::High);
+        let other_tenant = class(2, Priority::Low);
+
+        // The only active class takes all the bandwidth.
+        assert_eq!(Duration::ZERO, scheduler.acquire(&export, 1000, now));
+        assert_eq!(
+            Duration::from_secs(1),
+            scheduler.acquire(&export, 1000, now)
+        );
+
+        // The dashboard query doesn't wait behind the export, and it takes
+        // 4/5 of the bandwidth.
+        assert_eq!(Duration::ZERO, scheduler.acquire(&dashboard, 800, now));
+        assert_eq!(
+            Duration::from_secs(1),
+            scheduler.acquire(&dashboard, 800, now)
+        );
+
+        // Another tenant has its own share.
+        assert_eq!(Duration::ZERO, scheduler.acquire(&other_tenant, 100, now));
+
+        // The idle classes are forgotten.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(Duration::ZERO, scheduler.acquire(&export, 1000, later));
+        assert_eq!(
+            Duration::from_secs(1),
+            scheduler.acquire(&export, 1000, later)
+        );
+    }
+}
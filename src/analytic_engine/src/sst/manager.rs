@@ -77,12 +77,12 @@ impl LevelsController {
         }
     }
 
-    /// Remove sst files from level.
+    /// Remove sst files from level, returns the handles of the removed files.
     ///
     /// Panic: If the level is greater than the max level
-    pub fn remove_ssts_from_level(&mut self, level: Level, file_ids: &[FileId]) {
+    pub fn remove_ssts_from_level(&mut self, level: Level, file_ids: &[FileId]) -> Vec<FileHandle> {
         let level_handler = &mut self.levels[level.as_usize()];
-        level_handler.remove_ssts(file_ids);
+        level_handler.remove_ssts(file_ids)
     }
 
     pub fn levels(&self) -> impl Iterator<Item = Level> + '_ {
@@ -17,11 +17,17 @@
 
 use std::{
     fmt::Debug,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
 };
 
 use lru::LruCache;
-use object_store::{ObjectStoreRef, Path};
+use object_store::{
+    cache_priority::{self, CachePriority},
+    ObjectStoreRef, Path,
+};
 use parquet::{file::metadata::FileMetaData, format::KeyValue};
 use snafu::{ensure, OptionExt, ResultExt};
 
@@ -30,7 +36,10 @@ use crate::sst::{
         metadata_reader::parse_metadata, InvalidSize, KvMetaDataNotFound, KvMetaVersionEmpty,
         ParquetMetaDataRef, Result,
     },
-    metrics::{META_DATA_CACHE_HIT_COUNTER, META_DATA_CACHE_MISS_COUNTER},
+    metrics::{
+        META_DATA_CACHE_ENTRIES_GAUGE, META_DATA_CACHE_EVICT_COUNTER, META_DATA_CACHE_HIT_COUNTER,
+        META_DATA_CACHE_MISS_COUNTER,
+    },
     parquet::encoding,
 };
 
@@ -137,20 +146,37 @@ impl MetaData {
 }
 
 /// A cache for storing [`MetaData`].
+///
+/// The entries are split into tiers by the [`CachePriority`] of the sst, and
+/// the least recently used entry of the lowest non-empty tier is evicted when
+/// the cache is full.
 #[derive(Debug)]
 pub struct MetaCache {
-    cache: RwLock<LruCache<String, MetaData>>,
+    cap: AtomicUsize,
+    tiers: RwLock<Vec<LruCache<String, MetaData>>>,
 }
 
 impl MetaCache {
     pub fn new(cap: usize) -> Self {
+        let tiers = CachePriority::ALL
+            .iter()
+            .map(|_| LruCache::unbounded())
+            .collect();
+
         Self {
-            cache: RwLock::new(LruCache::new(cap)),
+            cap: AtomicUsize::new(cap),
+            tiers: RwLock::new(tiers),
         }
     }
 
     pub fn get(&self, key: &str) -> Option<MetaData> {
-        let v = self.cache.write().unwrap().get(key).cloned();
+        let priority = cache_priority::priority_of(key);
+        let mut tiers = self.tiers.write().unwrap();
+        // The priority of the sst may be changed after it is cached, so all the
+        // tiers are searched.
+        let v = std::iter::once(priority.index())
+            .chain((0..tiers.len()).filter(|idx| *idx != priority.index()))
+            .find_map(|idx| tiers[idx].get(key).cloned());
         if v.is_some() {
             META_DATA_CACHE_HIT_COUNTER.inc()
         } else {
@@ -161,7 +187,85 @@ impl MetaCache {
     }
 
     pub fn put(&self, key: String, value: MetaData) {
-        self.cache.write().unwrap().put(key, value);
+        let cap = self.cap.load(Ordering::Relaxed);
+        if cap == 0 {
+            return;
+        }
+
+        let priority = cache_priority::priority_of(&key);
+        let mut tiers = self.tiers.write().unwrap();
+        for (idx, tier) in tiers.iter_mut().enumerate() {
+            if idx != priority.index() {
+                tier.pop(&key);
+            }
+        }
+        tiers[priority.index()].put(key, value);
+
+        Self::evict_to_fit(&mut tiers, cap);
+        Self::update_entries_metrics(&tiers);
+    }
+
+    /// Change the capacity of the cache, the entries exceeding the new capacity
+    /// are evicted.
+    pub fn set_capacity(&self, cap: usize) {
+        self.cap.store(cap, Ordering::Relaxed);
+
+        let mut tiers = self.tiers.write().unwrap();
+        Self::evict_to_fit(&mut tiers, cap);
+        Self::update_entries_metrics(&tiers);
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap.load(Ordering::Relaxed)
+    }
+
+    fn evict_to_fit(tiers: &mut [LruCache<String, MetaData>], cap: usize) {
+        while tiers.iter().map(|tier| tier.len()).sum::<usize>() > cap {
+            let Some(victim) = tiers.iter().position(|tier| !tier.is_empty()) else {
+                break;
+            };
+            tiers[victim].pop_lru();
+            META_DATA_CACHE_EVICT_COUNTER
+                .with_label_values(&[CachePriority::ALL[victim].as_str()])
+                .inc();
+        }
+    }
+
+    /// Evict the least recently used half of the entries, starting from the
+    /// lowest priority.
+    pub fn shrink(&self) {
+        let mut tiers = self.tiers.write().unwrap();
+        let len: usize = tiers.iter().map(|tier| tier.len()).sum();
+        let mut num_to_evict = len - len / 2;
+        for tier in tiers.iter_mut() {
+            while num_to_evict > 0 && tier.pop_lru().is_some() {
+                num_to_evict -= 1;
+            }
+        }
+
+        Self::update_entries_metrics(&tiers);
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|tier| tier.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn update_entries_metrics(tiers: &[LruCache<String, MetaData>]) {
+        for (priority, tier) in CachePriority::ALL.iter().zip(tiers) {
+            META_DATA_CACHE_ENTRIES_GAUGE
+                .with_label_values(&[priority.as_str()])
+                .set(tier.len() as i64);
+        }
     }
 }
 
@@ -15,12 +15,16 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 use lazy_static::lazy_static;
 use prometheus::{
     exponential_buckets, register_counter, register_histogram, register_histogram_vec,
-    register_int_counter_vec, Counter, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    register_int_counter_vec, register_int_gauge_vec, Counter, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGaugeVec,
 };
 
 lazy_static! {
@@ -42,6 +46,18 @@ lazy_static! {
         "The counter for meta data cache miss"
     ).unwrap();
 
+    pub static ref META_DATA_CACHE_ENTRIES_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "meta_data_cache_entries",
+        "The entries in the meta data cache by cache priority",
+        &["priority"]
+    ).unwrap();
+
+    pub static ref META_DATA_CACHE_EVICT_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "meta_data_cache_evict",
+        "The counter for meta data cache eviction by cache priority",
+        &["priority"]
+    ).unwrap();
+
     static ref ROW_GROUP_BEFORE_PRUNE_COUNTER: IntCounterVec = register_int_counter_vec!(
         "row_group_before_prune",
         "The counter for row group before prune",
@@ -54,6 +70,12 @@ lazy_static! {
         &["table"]
     ).unwrap();
 
+    pub static ref SST_READ_IO_DELAY_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "sst_read_io_delay_ms",
+        "Delays of the sst reads by the io scheduler in milliseconds",
+        &["priority"]
+    ).unwrap();
+
     pub static ref FETCHED_SST_BYTES_HISTOGRAM: HistogramVec = register_histogram_vec!(
         "fetched_sst_bytes",
         "Histogram for sst get range length",
@@ -63,16 +85,66 @@ lazy_static! {
     ).unwrap();
 }
 
+/// Min row groups to observe before the scan ratio is trusted.
+const MIN_OBSERVED_ROW_GROUPS: u64 = 1024;
+/// The observed row groups are halved after exceeding this, so the recent
+/// queries weigh more.
+const MAX_OBSERVED_ROW_GROUPS: u64 = 1024 * 1024;
+
+/// Row groups read by the queries of a table, which is used to adapt the row
+/// group size of the table.
+#[derive(Debug, Default)]
+pub struct RowGroupAccessStats {
+    before_prune: AtomicU64,
+    after_prune: AtomicU64,
+}
+
+impl RowGroupAccessStats {
+    pub fn observe(&self, num_before_prune: usize, num_after_prune: usize) {
+        let before = self
+            .before_prune
+            .fetch_add(num_before_prune as u64, Ordering::Relaxed)
+            + num_before_prune as u64;
+        let after = self
+            .after_prune
+            .fetch_add(num_after_prune as u64, Ordering::Relaxed)
+            + num_after_prune as u64;
+
+        // The decay is racy, but it is fine for an estimation.
+        if before > MAX_OBSERVED_ROW_GROUPS {
+            self.before_prune.store(before / 2, Ordering::Relaxed);
+            self.after_prune.store(after / 2, Ordering::Relaxed);
+        }
+    }
+
+    /// Ratio of the row groups left after pruning, `None` if not enough row
+    /// groups are observed.
+    pub fn scan_ratio(&self) -> Option<f64> {
+        let before = self.before_prune.load(Ordering::Relaxed);
+        if before < MIN_OBSERVED_ROW_GROUPS {
+            return None;
+        }
+        let after = self.after_prune.load(Ordering::Relaxed).min(before);
+
+        Some(after as f64 / before as f64)
+    }
+}
+
 #[derive(Debug)]
 pub struct MaybeTableLevelMetrics {
     pub row_group_before_prune_counter: IntCounter,
     pub row_group_after_prune_counter: IntCounter,
     pub num_fetched_sst_bytes_hist: Histogram,
     pub num_fetched_sst_bytes: AtomicU64,
+    pub row_group_access_stats: Arc<RowGroupAccessStats>,
 }
 
 impl MaybeTableLevelMetrics {
-    pub fn new(table: &str, shard_id_label: &str) -> Self {
+    pub fn new(
+        table: &str,
+        shard_id_label: &str,
+        row_group_access_stats: Arc<RowGroupAccessStats>,
+    ) -> Self {
         Self {
             row_group_before_prune_counter: ROW_GROUP_BEFORE_PRUNE_COUNTER
                 .with_label_values(&[table]),
@@ -81,6 +153,7 @@ impl MaybeTableLevelMetrics {
             num_fetched_sst_bytes_hist: FETCHED_SST_BYTES_HISTOGRAM
                 .with_label_values(&[&shard_id_label, table]),
             num_fetched_sst_bytes: AtomicU64::new(0),
+            row_group_access_stats,
         }
     }
 
@@ -99,3 +172,26 @@ impl Drop for MaybeTableLevelMetrics {
         self.maybe_observe_num_fetched_sst_bytes();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_group_access_stats() {
+        let stats = RowGroupAccessStats::default();
+        stats.observe(100, 10);
+        assert!(stats.scan_ratio().is_none());
+
+        stats.observe(MIN_OBSERVED_ROW_GROUPS as usize, 92);
+        let ratio = stats.scan_ratio().unwrap();
+        assert!((ratio - 0.1).abs() < 0.01);
+
+        stats.observe(
+            MAX_OBSERVED_ROW_GROUPS as usize,
+            MAX_OBSERVED_ROW_GROUPS as usize,
+        );
+        assert!(stats.before_prune.load(Ordering::Relaxed) <= MAX_OBSERVED_ROW_GROUPS);
+        assert!(stats.scan_ratio().unwrap() > 0.9);
+    }
+}
@@ -20,9 +20,11 @@
 pub mod factory;
 pub mod file;
 pub mod header;
+pub mod io_scheduler;
 pub mod manager;
 pub mod meta_data;
 pub mod metrics;
 pub mod parquet;
+pub mod quarantine;
 pub mod reader;
 pub mod writer;
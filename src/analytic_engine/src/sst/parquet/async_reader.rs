@@ -18,9 +18,13 @@
 //! Sst reader implementation based on parquet.
 
 use std::{
+    collections::HashMap,
     ops::Range,
     pin::Pin,
-    sync::{atomic::Ordering, Arc},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
     time::{Duration, Instant},
 };
@@ -31,17 +35,22 @@ use bytes_ext::Bytes;
 use common_types::{
     projected_schema::{RowProjector, RowProjectorBuilder},
     record_batch::FetchedRecordBatch,
+    schema::Schema,
 };
 use datafusion::{
-    common::ToDFSchema,
+    common::{
+        tree_node::{Transformed, TreeNode},
+        Column, ToDFSchema,
+    },
     datasource::physical_plan::{parquet::page_filter::PagePruningPredicate, ParquetFileMetrics},
+    logical_expr::Expr,
     physical_expr::{create_physical_expr, execution_props::ExecutionProps},
     physical_plan::metrics::ExecutionPlanMetricsSet,
 };
 use futures::{Stream, StreamExt};
 use generic_error::{BoxError, GenericResult};
 use logger::{debug, error, warn};
-use object_store::{ObjectStoreRef, Path};
+use object_store::{read_stats::ReadStats, ObjectStoreRef, Path};
 use parquet::{
     arrow::{arrow_reader::RowSelection, ParquetRecordBatchStreamBuilder, ProjectionMask},
     file::metadata::RowGroupMetaData,
@@ -51,7 +60,7 @@ use parquet_ext::{
     reader::{MetricsObserver, ObjectStoreReader},
 };
 use runtime::{AbortOnDropMany, JoinHandle, Runtime};
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 use table_engine::predicate::PredicateRef;
 use time_ext::InstantExt;
 use tokio::sync::{
@@ -64,21 +73,24 @@ use crate::{
     prefetchable_stream::{NoopPrefetcher, PrefetchableStream},
     sst::{
         factory::{ObjectStorePickerRef, ReadFrequency, SstReadOptions},
+        io_scheduler::ScheduledIo,
         meta_data::{
             cache::{MetaCacheRef, MetaData},
             SstMetaData,
         },
         metrics::MaybeTableLevelMetrics,
         parquet::{
-            encoding::ParquetDecoder,
+            encoding::{self, ParquetDecoder},
             meta_data::{filter::ParquetFilter, ColumnValueSet},
-            row_group_pruner::RowGroupPruner,
+            row_group_pruner::{self, RowGroupPruner},
         },
         reader::{error::*, Result, SstReader},
     },
+    table::sst_util,
 };
 
 const PRUNE_ROW_GROUPS_METRICS_COLLECTOR_NAME: &str = "prune_row_groups";
+const FETCH_ROW_GROUPS_METRICS_COLLECTOR_NAME: &str = "fetch_row_groups";
 type SendableRecordBatchStream = Pin<Box<dyn Stream<Item = Result<ArrowRecordBatch>> + Send>>;
 type FetchedRecordBatchStream = Box<dyn Stream<Item = Result<FetchedRecordBatch>> + Send + Unpin>;
 
@@ -96,6 +108,9 @@ pub struct Reader<'a> {
     frequency: ReadFrequency,
     /// Init those fields in `init_if_necessary`
     meta_data: Option<MetaData>,
+    /// Fields stored in the separate fields file, in the order of the columns
+    /// of the fields file.
+    separated_fields: Vec<String>,
 
     row_projector_builder: RowProjectorBuilder,
     row_projector: Option<RowProjector>,
@@ -105,6 +120,7 @@ pub struct Reader<'a> {
     df_plan_metrics: ExecutionPlanMetricsSet,
 
     table_level_sst_metrics: Option<Arc<MaybeTableLevelMetrics>>,
+    scheduled_io: Option<ScheduledIo>,
 }
 
 #[derive(Default, Debug, Clone, TraceMetricWhenDrop)]
@@ -143,11 +159,13 @@ impl<'a> Reader<'a> {
             predicate: options.predicate.clone(),
             frequency: options.frequency,
             meta_data: None,
+            separated_fields: Vec::new(),
             row_projector_builder: options.row_projector_builder.clone(),
             row_projector: None,
             metrics,
             df_plan_metrics,
             table_level_sst_metrics: options.maybe_table_level_metrics.clone(),
+            scheduled_io: options.scheduled_io.clone(),
         }
     }
 
@@ -190,11 +208,12 @@ impl<'a> Reader<'a> {
             .metrics_collector
             .as_ref()
             .map(|v| v.span(PRUNE_ROW_GROUPS_METRICS_COLLECTOR_NAME.to_string()));
+        let exprs = self.pruning_exprs();
         let mut pruner = RowGroupPruner::try_new(
             &schema,
             row_groups,
             parquet_filter,
-            self.predicate.exprs(),
+            &exprs,
             metrics_collector,
             column_values,
         )?;
@@ -202,12 +221,107 @@ impl<'a> Reader<'a> {
         Ok(pruner.prune())
     }
 
+    /// Prune the row groups further by the bloom filters in the sst, which are
+    /// only fetched for the columns used by the predicates.
+    async fn prune_row_groups_by_bloom_filters(
+        &self,
+        schema: SchemaRef,
+        row_groups: Vec<usize>,
+    ) -> Result<Vec<usize>> {
+        let parquet_metadata = self.meta_data.as_ref().unwrap().parquet();
+        let exprs = self.pruning_exprs();
+        let columns: Vec<_> = row_group_pruner::bloom_filter_columns(&schema, &exprs)
+            .into_iter()
+            .filter(|col_idx| {
+                row_groups.iter().any(|row_group_idx| {
+                    parquet_metadata
+                        .row_group(*row_group_idx)
+                        .column(*col_idx)
+                        .bloom_filter_offset()
+                        .is_some()
+                })
+            })
+            .collect();
+        if columns.is_empty() {
+            return Ok(row_groups);
+        }
+
+        let object_store_reader = ObjectStoreReader::new(
+            self.store.clone(),
+            self.path.clone(),
+            parquet_metadata.clone(),
+        );
+        let mut builder = ParquetRecordBatchStreamBuilder::new(object_store_reader)
+            .await
+            .with_context(|| ParquetError)?;
+        let mut bloom_filters = HashMap::with_capacity(row_groups.len() * columns.len());
+        for row_group_idx in &row_groups {
+            for col_idx in &columns {
+                let bloom_filter = builder
+                    .get_row_group_column_bloom_filter(*row_group_idx, *col_idx)
+                    .await
+                    .with_context(|| ParquetError)?;
+                if let Some(v) = bloom_filter {
+                    bloom_filters.insert((*row_group_idx, *col_idx), v);
+                }
+            }
+        }
+
+        let pruned = row_group_pruner::prune_by_bloom_filters(
+            schema,
+            &exprs,
+            &row_groups,
+            &bloom_filters,
+        );
+        debug!(
+            "Prune row groups by bloom filters, path:{}, columns:{columns:?}, before:{}, after:{}",
+            self.path,
+            row_groups.len(),
+            pruned.len()
+        );
+
+        Ok(pruned)
+    }
+
     /// The final parallelism is ensured in the range: [1, num_row_groups].
     #[inline]
     fn decide_read_parallelism(suggested: usize, num_row_groups: usize) -> usize {
         suggested.min(num_row_groups).max(1)
     }
 
+    /// The predicates used to prune the row groups and the pages, and the ones
+    /// on the separated fields are excluded because their values in the sst
+    /// are all nulls.
+    ///
+    /// The predicates are on the columns of the table schema, which are mapped
+    /// to the columns of the sst by id if the schema is altered after the sst
+    /// is written, and the ones on the columns not in the sst are excluded.
+    fn pruning_exprs(&self) -> Vec<Expr> {
+        let sst_schema = &self.meta_data.as_ref().unwrap().custom().schema;
+        let table_schema = self.row_projector_builder.table_schema();
+        let altered = table_schema.version() != sst_schema.version();
+
+        self.predicate
+            .exprs()
+            .iter()
+            .filter_map(|expr| {
+                if altered {
+                    map_columns_by_id(expr, table_schema, sst_schema)
+                } else {
+                    Some(expr.clone())
+                }
+            })
+            .filter(|expr| {
+                self.separated_fields.is_empty()
+                    || expr.to_columns().is_ok_and(|columns| {
+                        columns
+                            .iter()
+                            .all(|column| !self.separated_fields.contains(&column.name))
+                    })
+            })
+            .collect()
+    }
+
     fn build_row_selection(
         &self,
         arrow_schema: SchemaRef,
@@ -216,7 +330,7 @@ impl<'a> Reader<'a> {
     ) -> Result<Option<RowSelection>> {
         // TODO: remove fixed partition
         let partition = 0;
-        let exprs = datafusion::optimizer::utils::conjunction(self.predicate.exprs().to_vec());
+        let exprs = datafusion::optimizer::utils::conjunction(self.pruning_exprs());
         let exprs = match exprs {
             Some(exprs) => exprs,
             None => return Ok(None),
@@ -259,6 +373,9 @@ impl<'a> Reader<'a> {
                 custom.column_values.as_ref(),
             )?
         };
+        let target_row_groups = self
+            .prune_row_groups_by_bloom_filters(arrow_schema.clone(), target_row_groups)
+            .await?;
 
         let num_row_group_before_prune = meta_data.parquet().num_row_groups();
         let num_row_group_after_prune = target_row_groups.len();
@@ -271,6 +388,9 @@ impl<'a> Reader<'a> {
             metrics
                 .row_group_after_prune_counter
                 .inc_by(num_row_group_after_prune as u64);
+            metrics
+                .row_group_access_stats
+                .observe(num_row_group_before_prune, num_row_group_after_prune);
         }
 
         debug!(
@@ -304,21 +424,50 @@ impl<'a> Reader<'a> {
             meta_data.parquet().file_metadata().schema_descr(),
             row_projector.existed_source_projection().iter().copied(),
         );
+        // The separated fields to read are fetched from the fields file, whose
+        // row groups are aligned with the ones of the sst.
+        let fields_projection: Vec<_> = row_projector
+            .existed_source_projection()
+            .iter()
+            .filter_map(|idx| {
+                let name = &meta_data.custom().schema.column(*idx).name;
+                self.separated_fields.iter().position(|v| v == name)
+            })
+            .collect();
+        let fields_file = if fields_projection.is_empty() {
+            None
+        } else {
+            let path = Path::from(sst_util::new_fields_path(self.path.as_ref()));
+            let fields_metadata = self.load_fields_meta_data(&path).await?;
+            let proj_mask = ProjectionMask::leaves(
+                fields_metadata.file_metadata().schema_descr(),
+                fields_projection,
+            );
+            Some((path, fields_metadata, proj_mask))
+        };
         debug!(
             "Reader fetch record batches, parallelism suggest:{}, real:{}, chunk_size:{}, project:{:?}",
             suggested_parallelism, parallelism, chunk_size, proj_mask
         );
 
         let mut streams = Vec::with_capacity(target_row_group_chunks.len());
-        let metrics_collector = ObjectStoreMetricsObserver {
-            table_level_sst_metrics: self.table_level_sst_metrics.clone(),
-        };
+        let fetch_metrics_collector = self
+            .metrics
+            .metrics_collector
+            .as_ref()
+            .map(|v| v.span(FETCH_ROW_GROUPS_METRICS_COLLECTOR_NAME.to_string()));
         for chunk in target_row_group_chunks {
+            let observer = ObjectStoreMetricsObserver {
+                table_level_sst_metrics: self.table_level_sst_metrics.clone(),
+                fetched_bytes: Arc::new(AtomicUsize::new(0)),
+                read_stats: Arc::new(ReadStats::default()),
+                scheduled_io: self.scheduled_io.clone(),
+            };
             let object_store_reader = ObjectStoreReader::with_metrics(
                 self.store.clone(),
                 self.path.clone(),
                 parquet_metadata.clone(),
-                metrics_collector.clone(),
+                observer.clone(),
             );
             let mut builder = ParquetRecordBatchStreamBuilder::new(object_store_reader)
                 .await
@@ -332,6 +481,31 @@ impl<'a> Reader<'a> {
                 self.path,
                 parquet_metadata.column_index().is_some()
             );
+            let fields_stream = match &fields_file {
+                Some((path, fields_metadata, fields_proj_mask)) => {
+                    let object_store_reader = ObjectStoreReader::with_metrics(
+                        self.store.clone(),
+                        path.clone(),
+                        fields_metadata.clone(),
+                        observer.clone(),
+                    );
+                    let mut builder = ParquetRecordBatchStreamBuilder::new(object_store_reader)
+                        .await
+                        .with_context(|| ParquetError)?;
+                    if let Some(selection) = &row_selection {
+                        builder = builder.with_row_selection(selection.clone());
+                    }
+                    let stream = builder
+                        .with_batch_size(self.num_rows_per_row_group)
+                        .with_row_groups(chunk.clone())
+                        .with_projection(fields_proj_mask.clone())
+                        .build()
+                        .with_context(|| ParquetError)?
+                        .map(|batch| batch.with_context(|| ParquetError));
+                    Some(stream)
+                }
+                None => None,
+            };
             if let Some(selection) = row_selection {
                 builder = builder.with_row_selection(selection);
             };
@@ -343,6 +517,23 @@ impl<'a> Reader<'a> {
                 .build()
                 .with_context(|| ParquetError)?
                 .map(|batch| batch.with_context(|| ParquetError));
+            let stream: SendableRecordBatchStream = match fields_stream {
+                Some(fields_stream) => {
+                    let stream = stream.zip(fields_stream).map(|(batch, fields_batch)| {
+                        stitch_separated_fields(batch?, fields_batch?)
+                    });
+                    Box::pin(stream)
+                }
+                None => Box::pin(stream),
+            };
+            let stream = FetchMetricsStream {
+                stream,
+                observer,
+                metrics: FetchMetrics {
+                    metrics_collector: fetch_metrics_collector.clone(),
+                    ..Default::default()
+                },
+            };
 
             streams.push(Box::pin(stream) as _);
         }
@@ -368,6 +559,7 @@ impl<'a> Reader<'a> {
             .box_err()
             .context(Projection)?;
 
+        self.separated_fields = separated_fields(meta_data.parquet());
         self.meta_data = Some(meta_data);
         self.row_projector = Some(row_projector);
 
@@ -386,6 +578,20 @@ impl<'a> Reader<'a> {
         Ok(file_size)
     }
 
+    /// Load the meta data of the fields file, which is not cached.
+    async fn load_fields_meta_data(&self, path: &Path) -> Result<parquet_ext::ParquetMetaDataRef> {
+        let object_meta = self.store.head(path).await.context(ObjectStoreError)?;
+        let chunk_reader_adapter = ChunkReaderAdapter::new(path, self.store);
+        let (meta_data, _) =
+            parquet_ext::meta_data::fetch_parquet_metadata(object_meta.size, &chunk_reader_adapter)
+                .await
+                .with_context(|| FetchAndDecodeSstMeta {
+                    file_path: path.to_string(),
+                })?;
+
+        Ok(Arc::new(meta_data))
+    }
+
     async fn load_meta_data_from_storage(&self, ignore_sst_filter: bool) -> Result<MetaData> {
         let file_size = self.load_file_size().await?;
         let chunk_reader_adapter = ChunkReaderAdapter::new(self.path, self.store);
@@ -481,6 +687,82 @@ impl<'a> Drop for Reader<'a> {
     }
 }
 
+/// Find the fields stored in the separate fields file of the sst.
+fn separated_fields(parquet_meta_data: &parquet_ext::ParquetMetaData) -> Vec<String> {
+    parquet_meta_data
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|kvs| {
+            kvs.iter()
+                .find(|kv| kv.key == encoding::SEPARATED_FIELDS_KEY)
+        })
+        .and_then(|kv| kv.value.as_ref())
+        .map(|v| v.split(',').map(|name| name.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Rename the columns of the `expr` from the `table_schema` to the ones of the
+/// `sst_schema` with the same ids, `None` if any of them is not in the sst,
+/// e.g. the columns added after the sst is written.
+fn map_columns_by_id(expr: &Expr, table_schema: &Schema, sst_schema: &Schema) -> Option<Expr> {
+    let mut renamed = HashMap::new();
+    for column in expr.to_columns().ok()? {
+        let table_idx = table_schema.index_of(&column.name)?;
+        let sst_idx = sst_schema.index_of_column_id(table_schema.column(table_idx).id)?;
+        renamed.insert(column.name, sst_schema.column(sst_idx).name.clone());
+    }
+
+    expr.clone()
+        .transform_up(&|expr| {
+            let Expr::Column(column) = &expr else {
+                return Ok(Transformed::No(expr));
+            };
+            match renamed.get(&column.name) {
+                Some(name) if name != &column.name => Ok(Transformed::Yes(Expr::Column(Column {
+                    relation: column.relation.clone(),
+                    name: name.clone(),
+                }))),
+                _ => Ok(Transformed::No(expr)),
+            }
+        })
+        .ok()
+}
+
+/// Replace the null columns of the separated fields in the `record_batch` with
+/// the ones read from the fields file.
+fn stitch_separated_fields(
+    record_batch: ArrowRecordBatch,
+    fields_record_batch: ArrowRecordBatch,
+) -> Result<ArrowRecordBatch> {
+    ensure!(
+        record_batch.num_rows() == fields_record_batch.num_rows(),
+        OtherNoCause {
+            msg: format!(
+                "rows of the fields file mismatch, expect:{}, given:{}",
+                record_batch.num_rows(),
+                fields_record_batch.num_rows()
+            ),
+        }
+    );
+
+    let schema = record_batch.schema();
+    let mut columns = record_batch.columns().to_vec();
+    let fields_schema = fields_record_batch.schema();
+    for (field, column) in fields_schema
+        .fields()
+        .iter()
+        .zip(fields_record_batch.columns())
+    {
+        if let Ok(idx) = schema.index_of(field.name()) {
+            columns[idx] = column.clone();
+        }
+    }
+
+    ArrowRecordBatch::try_new(schema, columns)
+        .box_err()
+        .context(Other)
+}
+
 pub struct ChunkReaderAdapter<'a> {
     path: &'a Path,
     store: &'a ObjectStoreRef,
@@ -798,6 +1080,10 @@ impl<'a> SstReader for ThreadedReader<'a> {
 #[derive(Clone)]
 struct ObjectStoreMetricsObserver {
     table_level_sst_metrics: Option<Arc<MaybeTableLevelMetrics>>,
+    /// Bytes fetched by the reader.
+    fetched_bytes: Arc<AtomicUsize>,
+    read_stats: Arc<ReadStats>,
+    scheduled_io: Option<ScheduledIo>,
 }
 
 impl MetricsObserver for ObjectStoreMetricsObserver {
@@ -806,12 +1092,67 @@ impl MetricsObserver for ObjectStoreMetricsObserver {
     }
 
     fn num_bytes_fetched(&self, _: &Path, num_bytes: usize) {
+        self.fetched_bytes.fetch_add(num_bytes, Ordering::Relaxed);
         if let Some(metrics) = &self.table_level_sst_metrics {
             metrics
                 .num_fetched_sst_bytes
                 .fetch_add(num_bytes as u64, Ordering::Relaxed);
         }
     }
+
+    fn read_stats(&self) -> Option<Arc<ReadStats>> {
+        Some(self.read_stats.clone())
+    }
+
+    fn fetch_delay(&self, _: &Path, num_bytes: usize) -> Duration {
+        match &self.scheduled_io {
+            Some(scheduled_io) => scheduled_io.delay(num_bytes as u64),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+/// Metrics of fetching and decoding the row groups.
+#[derive(Default, Debug, Clone, TraceMetricWhenDrop)]
+pub(crate) struct FetchMetrics {
+    #[metric(number, sum)]
+    pub fetched_bytes: usize,
+    /// Bytes served by the memory or disk cache, and the others are read from
+    /// the underlying storage.
+    #[metric(number, sum)]
+    pub cache_hit_bytes: usize,
+    /// Time spent in polling the parquet stream, which is mainly the decoding
+    /// cost because waiting for the io doesn't block the polling.
+    #[metric(duration, sum)]
+    pub decode_duration: Duration,
+    #[metric(collector)]
+    pub metrics_collector: Option<MetricsCollector>,
+}
+
+/// Stream collecting the [FetchMetrics] of the wrapped parquet stream.
+struct FetchMetricsStream {
+    stream: SendableRecordBatchStream,
+    observer: ObjectStoreMetricsObserver,
+    metrics: FetchMetrics,
+}
+
+impl Stream for FetchMetricsStream {
+    type Item = Result<ArrowRecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let start = Instant::now();
+        let poll = self.stream.poll_next_unpin(cx);
+        self.metrics.decode_duration += start.elapsed();
+        poll
+    }
+}
+
+impl Drop for FetchMetricsStream {
+    fn drop(&mut self) {
+        // The metrics are collected when dropped after this.
+        self.metrics.fetched_bytes = self.observer.fetched_bytes.load(Ordering::Relaxed);
+        self.metrics.cache_hit_bytes = self.observer.read_stats.cache_hit_bytes() as usize;
+    }
 }
 
 #[cfg(test)]
@@ -822,9 +1163,13 @@ mod tests {
         time::Duration,
     };
 
+    use common_types::{schema, tests::build_schema_with_dictionary};
+    use datafusion::logical_expr::{col, lit};
     use futures::{Stream, StreamExt};
     use tokio::sync::mpsc::{self, Receiver, Sender};
 
+    use super::map_columns_by_id;
+
     struct MockReceivers {
         rx_group: Vec<Receiver<u32>>,
         cur_rx_idx: usize,
@@ -919,4 +1264,41 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_map_columns_by_id() {
+        let sst_schema = build_schema_with_dictionary();
+        // The field2 is renamed to field5, and the field4 is dropped and added
+        // again with a new id.
+        let max_id = sst_schema.columns().iter().map(|v| v.id).max().unwrap();
+        let mut builder = schema::Builder::with_capacity(sst_schema.num_columns())
+            .primary_key_indexes(sst_schema.primary_key_indexes().to_vec())
+            .version(sst_schema.version() + 1);
+        for (idx, column) in sst_schema.columns().iter().enumerate() {
+            let mut column = column.clone();
+            match column.name.as_str() {
+                "field2" => column.name = "field5".to_string(),
+                "field4" => column.id = max_id + 1,
+                _ => (),
+            }
+            builder = if sst_schema.is_primary_key_index(&idx) {
+                builder.add_key_column(column)
+            } else {
+                builder.add_normal_column(column)
+            }
+            .unwrap();
+        }
+        let table_schema = builder.build().unwrap();
+
+        let expr = col("field5").eq(lit("a")).and(col("field1").gt(lit(1.0)));
+        let mapped = map_columns_by_id(&expr, &table_schema, &sst_schema).unwrap();
+        assert_eq!(
+            col("field2").eq(lit("a")).and(col("field1").gt(lit(1.0))),
+            mapped
+        );
+
+        // The data of the dropped field4 in the sst is not used.
+        let expr = col("field4").is_null();
+        assert!(map_columns_by_id(&expr, &table_schema, &sst_schema).is_none());
+    }
 }
@@ -27,7 +27,7 @@ use horaedbproto::sst as sst_pb;
 use macros::define_result;
 use parquet::{
     arrow::AsyncArrowWriter,
-    basic::Compression,
+    basic::{Compression, Encoding},
     file::{metadata::KeyValue, properties::WriterProperties},
     schema::types::ColumnPath,
 };
@@ -168,6 +168,16 @@ pub const META_PATH_KEY: &str = "meta_path"; // used in v2
 pub const META_SIZE_KEY: &str = "meta_size"; // used in v2
 pub const META_VERSION_KEY: &str = "meta_version";
 pub const META_VALUE_HEADER: u8 = 0;
+/// The compressions chosen by sampling the values of the columns, e.g.
+/// `host:ZSTD,value:UNCOMPRESSED`, and the other columns use the compression of
+/// the table.
+pub const COLUMN_COMPRESSIONS_KEY: &str = "column_compressions";
+/// The field columns whose values are stored in the separate fields file, e.g.
+/// `body,payload`, and they are all nulls in the sst itself.
+pub const SEPARATED_FIELDS_KEY: &str = "separated_fields";
+/// Marks the sst rewritten by the recompression of the old ssts, so it won't
+/// be rewritten again.
+pub const RECOMPRESSED_KEY: &str = "recompressed";
 
 /// Encode the sst custom meta data into binary key value pair.
 pub fn encode_sst_meta_data(meta_data: ParquetMetaData) -> Result<Bytes> {
@@ -230,6 +240,9 @@ trait RecordEncoder {
 
     fn set_meta_data_path(&mut self, metadata_path: Option<String>) -> Result<()>;
     fn set_meta_data_size(&mut self, size: usize) -> Result<()>;
+    fn set_column_compressions(&mut self, column_compressions: String) -> Result<()>;
+    fn set_separated_fields(&mut self, separated_fields: String) -> Result<()>;
+    fn set_recompressed(&mut self) -> Result<()>;
 
     /// Return encoded bytes
     /// Note: trait method cannot receive `self`, so take a &mut self here to
@@ -248,21 +261,35 @@ pub struct ColumnEncoding {
     pub enable_dict: bool,
 }
 
+/// Bloom filter of a column, built for every row group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnBloomFilter {
+    /// Target false positive probability.
+    pub fpp: f64,
+    /// Number of the distinct values in a row group the filter is sized for.
+    pub ndv: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct EncodeOptions {
     pub num_rows_per_row_group: usize,
+    pub data_page_size: usize,
     pub max_buffer_size: usize,
     pub compression: Compression,
     pub column_encodings: HashMap<String, ColumnEncoding>,
+    /// Encodings of the values of the columns, used as the fallback of the
+    /// dictionary encoding if it's enabled.
+    pub column_value_encodings: HashMap<String, Encoding>,
+    pub column_compressions: HashMap<String, Compression>,
+    pub column_bloom_filters: HashMap<String, ColumnBloomFilter>,
 }
 
 impl<W: AsyncWrite + Send + Unpin> ColumnarRecordEncoder<W> {
-    fn try_new(sink: W, schema: &Schema, options: &EncodeOptions) -> Result<Self> {
-        let arrow_schema = schema.to_arrow_schema_ref();
-
+    fn try_new(sink: W, arrow_schema: ArrowSchemaRef, options: &EncodeOptions) -> Result<Self> {
         let write_props = {
             let mut builder = WriterProperties::builder()
                 .set_max_row_group_size(options.num_rows_per_row_group)
+                .set_data_page_size_limit(options.data_page_size)
                 .set_compression(options.compression);
 
             for (col_name, encoding) in &options.column_encodings {
@@ -270,6 +297,24 @@ impl<W: AsyncWrite + Send + Unpin> ColumnarRecordEncoder<W> {
                 builder = builder.set_column_dictionary_enabled(col_path, encoding.enable_dict);
             }
 
+            for (col_name, encoding) in &options.column_value_encodings {
+                let col_path = ColumnPath::new(vec![col_name.to_string()]);
+                builder = builder.set_column_encoding(col_path, *encoding);
+            }
+
+            for (col_name, compression) in &options.column_compressions {
+                let col_path = ColumnPath::new(vec![col_name.to_string()]);
+                builder = builder.set_column_compression(col_path, *compression);
+            }
+
+            for (col_name, bloom_filter) in &options.column_bloom_filters {
+                let col_path = ColumnPath::new(vec![col_name.to_string()]);
+                builder = builder
+                    .set_column_bloom_filter_enabled(col_path.clone(), true)
+                    .set_column_bloom_filter_fpp(col_path.clone(), bloom_filter.fpp)
+                    .set_column_bloom_filter_ndv(col_path, bloom_filter.ndv);
+            }
+
             builder.build()
         };
 
@@ -336,6 +381,39 @@ impl<W: AsyncWrite + Send + Unpin> RecordEncoder for ColumnarRecordEncoder<W> {
         Ok(())
     }
 
+    fn set_column_compressions(&mut self, column_compressions: String) -> Result<()> {
+        let compressions_kv = KeyValue {
+            key: COLUMN_COMPRESSIONS_KEY.to_string(),
+            value: Some(column_compressions),
+        };
+        let writer = self.arrow_writer.as_mut().unwrap();
+        writer.append_key_value_metadata(compressions_kv);
+
+        Ok(())
+    }
+
+    fn set_separated_fields(&mut self, separated_fields: String) -> Result<()> {
+        let fields_kv = KeyValue {
+            key: SEPARATED_FIELDS_KEY.to_string(),
+            value: Some(separated_fields),
+        };
+        let writer = self.arrow_writer.as_mut().unwrap();
+        writer.append_key_value_metadata(fields_kv);
+
+        Ok(())
+    }
+
+    fn set_recompressed(&mut self) -> Result<()> {
+        let recompressed_kv = KeyValue {
+            key: RECOMPRESSED_KEY.to_string(),
+            value: Some(true.to_string()),
+        };
+        let writer = self.arrow_writer.as_mut().unwrap();
+        writer.append_key_value_metadata(recompressed_kv);
+
+        Ok(())
+    }
+
     async fn close(&mut self) -> Result<()> {
         assert!(self.arrow_writer.is_some());
 
@@ -360,8 +438,19 @@ impl ParquetEncoder {
         schema: &Schema,
         options: &EncodeOptions,
     ) -> Result<Self> {
+        Self::try_new_with_arrow_schema(sink, schema.to_arrow_schema_ref(), options)
+    }
+
+    /// Build the encoder writing the record batches of the `arrow_schema`,
+    /// which may contain only a part of the columns of the table.
+    pub fn try_new_with_arrow_schema<W: AsyncWrite + Unpin + Send + 'static>(
+        sink: W,
+        arrow_schema: ArrowSchemaRef,
+        options: &EncodeOptions,
+    ) -> Result<Self> {
+        let record_encoder = ColumnarRecordEncoder::try_new(sink, arrow_schema, options)?;
         Ok(ParquetEncoder {
-            record_encoder: Box::new(ColumnarRecordEncoder::try_new(sink, schema, options)?),
+            record_encoder: Box::new(record_encoder),
         })
     }
 
@@ -386,6 +475,19 @@ impl ParquetEncoder {
         self.record_encoder.set_meta_data_size(size)
     }
 
+    pub fn set_column_compressions(&mut self, column_compressions: String) -> Result<()> {
+        self.record_encoder
+            .set_column_compressions(column_compressions)
+    }
+
+    pub fn set_separated_fields(&mut self, separated_fields: String) -> Result<()> {
+        self.record_encoder.set_separated_fields(separated_fields)
+    }
+
+    pub fn set_recompressed(&mut self) -> Result<()> {
+        self.record_encoder.set_recompressed()
+    }
+
     pub async fn close(mut self) -> Result<()> {
         self.record_encoder.close().await
     }
@@ -23,7 +23,7 @@ use std::{
     collections::{HashMap, HashSet},
 };
 
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{DataType, SchemaRef};
 use common_types::datum::Datum;
 use datafusion::{
     logical_expr::Operator,
@@ -31,12 +31,13 @@ use datafusion::{
     scalar::ScalarValue,
 };
 use logger::debug;
-use parquet::file::metadata::RowGroupMetaData;
+use parquet::{bloom_filter::Sbbf, file::metadata::RowGroupMetaData};
 use parquet_ext::prune::{
     equal::{self, ColumnPosition},
     min_max,
 };
 use snafu::ensure;
+use table_engine::predicate::ColumnRegexMatch;
 use trace_metric::{MetricsCollector, TraceMetricWhenDrop};
 
 use crate::sst::{
@@ -176,6 +177,87 @@ fn rewrite_not_expr(expr: Expr, column_values: &HashMap<String, Option<ColumnVal
 
     expr
 }
+
+/// This function will rewrite the regex match expr on the column to the in
+/// list expr of all the matched values, e.g. `host ~ 'api-.*'` will be
+/// rewritten to `host in ('api-1', 'api-2')`, so the regex is evaluated only
+/// once for every distinct value and the bloom-filter like structure can be
+/// utilized.
+fn rewrite_regex_expr(expr: Expr, column_values: &HashMap<String, Option<ColumnValueSet>>) -> Expr {
+    let regex_match = match ColumnRegexMatch::try_from_expr(&expr) {
+        Some(v) => v,
+        None => return expr,
+    };
+
+    let all_values = match column_values.get(regex_match.column()) {
+        Some(Some(ColumnValueSet::StringValue(sv))) => sv,
+        _ => return expr,
+    };
+
+    let mut wanted_values: Vec<_> = all_values
+        .iter()
+        .filter(|value| regex_match.is_match(value))
+        .collect();
+    // Make the rewritten expr deterministic.
+    wanted_values.sort_unstable();
+
+    let column_expr = datafusion::prelude::col(regex_match.column());
+    let wanted_values = wanted_values.into_iter().map(lit).collect();
+    datafusion::logical_expr::in_list(column_expr, wanted_values, false)
+}
+
+/// Indexes of the string columns used by the `predicates`, whose bloom filters
+/// may help to prune the row groups.
+pub fn bloom_filter_columns(schema: &SchemaRef, predicates: &[Expr]) -> Vec<usize> {
+    let mut columns = HashSet::new();
+    for expr in predicates {
+        let Ok(expr_columns) = expr.to_columns() else {
+            continue;
+        };
+        for column in expr_columns {
+            if let Some((idx, field)) = schema.column_with_name(&column.name) {
+                let is_string = match field.data_type() {
+                    DataType::Utf8 => true,
+                    DataType::Dictionary(_, value_type) => **value_type == DataType::Utf8,
+                    _ => false,
+                };
+                if is_string {
+                    columns.insert(idx);
+                }
+            }
+        }
+    }
+
+    let mut columns: Vec<_> = columns.into_iter().collect();
+    columns.sort_unstable();
+    columns
+}
+
+/// Prune the `row_groups` by the bloom filters of the columns, which are keyed
+/// by the index of the row group and the column.
+pub fn prune_by_bloom_filters(
+    schema: SchemaRef,
+    predicates: &[Expr],
+    row_groups: &[usize],
+    bloom_filters: &HashMap<(usize, usize), Sbbf>,
+) -> Vec<usize> {
+    let is_equal = |col_pos: ColumnPosition, val: &ScalarValue, negated: bool| -> Option<bool> {
+        let row_group_idx = row_groups[col_pos.row_group_idx];
+        let bloom_filter = bloom_filters.get(&(row_group_idx, col_pos.column_idx))?;
+        match val {
+            // The bloom filter has false positivity, so only the absence of the
+            // value is certain.
+            ScalarValue::Utf8(Some(v)) if !bloom_filter.check(v.as_str()) => Some(negated),
+            _ => None,
+        }
+    };
+
+    equal::prune_row_groups(schema, predicates, row_groups.len(), is_equal)
+        .into_iter()
+        .map(|idx| row_groups[idx])
+        .collect()
+}
+
 impl<'a> RowGroupPruner<'a> {
     // TODO: DataFusion already change predicates to PhyscialExpr, we should keep up
     // with upstream.
@@ -210,7 +292,10 @@ impl<'a> RowGroupPruner<'a> {
             debug!("Pruner rewrite predicates, before:{predicates:?}");
             let predicates = predicates
                 .iter()
-                .map(|expr| rewrite_not_expr(expr.clone(), &column_values))
+                .map(|expr| {
+                    let expr = rewrite_not_expr(expr.clone(), &column_values);
+                    rewrite_regex_expr(expr, &column_values)
+                })
                 .collect();
             debug!(
                 "Pruner rewrite predicates, after:{predicates:?}, column_values:{column_values:?}"
@@ -412,4 +497,55 @@ mod tests {
             assert_eq!(expected, rewrite_not_expr(input, &column_values));
         }
     }
+
+    #[test]
+    fn test_rewrite_regex_expr() {
+        let column_values = [("host", Some(["api-1", "api-2", "web1"])), ("ip", None)]
+            .into_iter()
+            .map(|(column_name, values)| {
+                (
+                    column_name.to_string(),
+                    values.map(|vs| {
+                        ColumnValueSet::StringValue(HashSet::from_iter(
+                            vs.into_iter().map(|v| v.to_string()),
+                        ))
+                    }),
+                )
+            })
+            .collect();
+
+        let regex_expr = |column: &str, op: Operator, pattern: &str| {
+            datafusion::logical_expr::binary_expr(col(column), op, lit(pattern))
+        };
+        let testcases = [
+            (
+                // host ~ 'api-.*' --> host in (api-1, api-2)
+                regex_expr("host", Operator::RegexMatch, "api-.*"),
+                col("host").in_list(vec![lit("api-1"), lit("api-2")], false),
+            ),
+            (
+                // host !~ 'api-.*' --> host in (web1)
+                regex_expr("host", Operator::RegexNotMatch, "api-.*"),
+                col("host").in_list(vec![lit("web1")], false),
+            ),
+            (
+                // host ~* 'WEB' --> host in (web1)
+                regex_expr("host", Operator::RegexIMatch, "WEB"),
+                col("host").in_list(vec![lit("web1")], false),
+            ),
+            (
+                // host ~ 'db.*' --> host in ()
+                regex_expr("host", Operator::RegexMatch, "db.*"),
+                col("host").in_list(vec![], false),
+            ),
+            // Can't rewrite since ip in column_values is None.
+            (
+                regex_expr("ip", Operator::RegexMatch, "127.*"),
+                regex_expr("ip", Operator::RegexMatch, "127.*"),
+            ),
+        ];
+        for (input, expected) in testcases {
+            assert_eq!(expected, rewrite_regex_expr(input, &column_values));
+        }
+    }
 }
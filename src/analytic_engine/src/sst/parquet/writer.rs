@@ -17,14 +17,21 @@
 
 //! Sst writer implementation based on parquet.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
 
+use arrow::{array::new_null_array, record_batch::RecordBatch as ArrowRecordBatch};
 use async_trait::async_trait;
 use common_types::{
-    datum::DatumKind, record_batch::FetchedRecordBatch, request_id::RequestId, schema::Schema,
-    time::TimeRange,
+    datum::DatumKind,
+    record_batch::FetchedRecordBatch,
+    request_id::RequestId,
+    schema::Schema,
+    time::{TimeRange, Timestamp},
 };
-use datafusion::parquet::basic::Compression;
+use datafusion::parquet::basic::{Compression, Encoding};
 use futures::StreamExt;
 use generic_error::BoxError;
 use logger::{debug, error};
@@ -38,7 +45,10 @@ use crate::{
         factory::ObjectStorePickerRef,
         file::Level,
         parquet::{
-            encoding::{encode_sst_meta_data, ColumnEncoding, EncodeOptions, ParquetEncoder},
+            encoding::{
+                encode_sst_meta_data, ColumnBloomFilter, ColumnEncoding, EncodeOptions,
+                ParquetEncoder,
+            },
             meta_data::{
                 filter::{ParquetFilter, RowGroupFilter, RowGroupFilterBuilder},
                 ColumnValueSet, ParquetMetaData,
@@ -50,7 +60,9 @@ use crate::{
         },
     },
     table::sst_util,
-    table_options::StorageFormat,
+    table_options::{
+        self, BloomFilterOptions, BloomFilterSizing, StorageFormat, ValueEncoding,
+    },
 };
 
 const KEEP_COLUMN_VALUE_THRESHOLD: usize = 20;
@@ -62,6 +74,22 @@ const MIN_NUM_ROWS_SAMPLE_DICT_ENCODING: usize = 1024;
 /// `total_num_values * MAX_UNIQUE_VALUE_RATIO_DICT_ENCODING`, there is no need
 /// to do dictionary encoding for such column.
 const MAX_UNIQUE_VALUE_RATIO_DICT_ENCODING: f64 = 0.12;
+/// The adaptive bloom filters are sized for this multiple of the distinct
+/// values observed in the sampled row group, leaving room for the row groups
+/// with more distinct values.
+const ADAPTIVE_BLOOM_FILTER_NDV_FACTOR: u64 = 2;
+/// The compressions are chosen adaptively only if the sampled row group
+/// contains at least `MIN_NUM_ROWS_SAMPLE_COMPRESSION` rows.
+const MIN_NUM_ROWS_SAMPLE_COMPRESSION: usize = 1024;
+/// At most `MAX_NUM_BYTES_SAMPLE_COMPRESSION` bytes of a column are sampled to
+/// estimate its entropy.
+const MAX_NUM_BYTES_SAMPLE_COMPRESSION: usize = 64 * 1024;
+/// The values whose byte entropy (bits per byte) exceeds this are considered
+/// random, and compressing them wastes the cpu for nothing.
+const MIN_ENTROPY_SKIP_COMPRESSION: f64 = 7.5;
+/// The values whose byte entropy is below this are redundant enough to pay for
+/// the cpu of zstd.
+const MAX_ENTROPY_ZSTD_COMPRESSION: f64 = 3.0;
 
 /// The implementation of sst based on parquet and object storage.
 #[derive(Debug)]
@@ -103,15 +131,34 @@ struct RecordBatchGroupWriter<'a> {
     // `column_values` is used to collect distinct values in each columns,
     // its order is the same with schema's columns.
     column_values: Option<Vec<Option<ColumnValueSet>>>,
+    // Indexes of the fields written to the separate fields file.
+    separated_field_indexes: Vec<usize>,
 }
 
 #[derive(Clone, Debug)]
 pub struct WriteOptions {
     pub num_rows_per_row_group: usize,
+    pub data_page_size: usize,
     pub max_buffer_size: usize,
     pub compression: Compression,
+    /// Level of the zstd compression chosen adaptively.
+    pub compression_level: Option<i32>,
     pub sst_level: Level,
     pub column_encodings: HashMap<String, ColumnEncoding>,
+    /// Encodings specified for the columns, which override the ones decided by
+    /// sampling.
+    pub value_encodings: BTreeMap<String, ValueEncoding>,
+    /// Compressions specified for the columns.
+    pub column_compressions: HashMap<String, Compression>,
+    /// Choose the compressions of the columns not specified in the
+    /// `column_compressions` by sampling their values.
+    pub adaptive_compression: bool,
+    /// Names of the fields written to the separate fields file.
+    pub separated_fields: Vec<String>,
+    pub bloom_filter: Option<BloomFilterOptions>,
+    /// Mark the sst as recompressed, see
+    /// [crate::sst::parquet::encoding::RECOMPRESSED_KEY].
+    pub recompressed: bool,
 }
 
 impl WriteOptions {
@@ -145,6 +192,8 @@ impl<'a> RecordBatchGroupWriter<'a> {
                 })
                 .collect()
         });
+        let separated_field_indexes =
+            separated_field_indexes(&meta_data.schema, &options.separated_fields);
 
         Self {
             request_id,
@@ -154,9 +203,15 @@ impl<'a> RecordBatchGroupWriter<'a> {
             input_exhausted: false,
             real_time_range: None,
             column_values,
+            separated_field_indexes,
         }
     }
 
+    #[inline]
+    fn has_separated_fields(&self) -> bool {
+        !self.separated_field_indexes.is_empty()
+    }
+
     /// Fetch an integral row group from the `self.input`.
     ///
     /// Except the last one, every row group is ensured to contains exactly
@@ -234,6 +289,150 @@ impl<'a> RecordBatchGroupWriter<'a> {
         sampler.sample()
     }
 
+    /// Apply the encodings specified for the columns to the `column_encodings`
+    /// decided by sampling, and return the encodings of the values of the
+    /// columns.
+    fn apply_value_encodings(
+        &self,
+        column_encodings: &mut HashMap<String, ColumnEncoding>,
+    ) -> HashMap<String, Encoding> {
+        let schema = &self.meta_data.schema;
+        let mut value_encodings = HashMap::new();
+        for (col_name, value_encoding) in &self.options.value_encodings {
+            // The column may be missing in the ssts written before it's added.
+            let Some(col_idx) = schema.index_of(col_name) else {
+                continue;
+            };
+            let data_type = schema.column(col_idx).data_type;
+            if !value_encoding.support(data_type) {
+                continue;
+            }
+
+            let (enable_dict, encoding) = match value_encoding {
+                ValueEncoding::Plain => (false, Some(Encoding::PLAIN)),
+                ValueEncoding::Dict => (true, None),
+                ValueEncoding::Rle if data_type == DatumKind::Boolean => {
+                    (false, Some(Encoding::RLE))
+                }
+                // The indexes of the dictionary are run length encoded.
+                ValueEncoding::Rle => (true, None),
+                ValueEncoding::Delta => match data_type {
+                    DatumKind::String | DatumKind::Varbinary => {
+                        (false, Some(Encoding::DELTA_BYTE_ARRAY))
+                    }
+                    _ => (false, Some(Encoding::DELTA_BINARY_PACKED)),
+                },
+            };
+            column_encodings.insert(col_name.clone(), ColumnEncoding { enable_dict });
+            if let Some(encoding) = encoding {
+                value_encodings.insert(col_name.clone(), encoding);
+            }
+        }
+
+        value_encodings
+    }
+
+    /// Choose the compressions of the columns not specified by the entropy of
+    /// their bytes in the `sample_row_groups`: the random values are not
+    /// compressed, and the redundant values are compressed by zstd. The
+    /// other columns use the compression of the table.
+    fn choose_column_compressions(
+        &self,
+        sample_row_groups: &[FetchedRecordBatch],
+        column_encodings: &HashMap<String, ColumnEncoding>,
+    ) -> BTreeMap<String, table_options::Compression> {
+        let mut compressions = BTreeMap::new();
+        if !self.options.adaptive_compression {
+            return compressions;
+        }
+        let num_rows: usize = sample_row_groups.iter().map(|v| v.num_rows()).sum();
+        if num_rows < MIN_NUM_ROWS_SAMPLE_COMPRESSION {
+            return compressions;
+        }
+
+        for (col_idx, col_schema) in self.meta_data.schema.columns().iter().enumerate() {
+            if self.options.column_compressions.contains_key(&col_schema.name) {
+                continue;
+            }
+
+            let mut histogram = ByteHistogram::default();
+            'sample: for row_group in sample_row_groups {
+                let col_block = &row_group.columns()[col_idx];
+                for idx in 0..row_group.num_rows() {
+                    if histogram.num_bytes() >= MAX_NUM_BYTES_SAMPLE_COMPRESSION {
+                        break 'sample;
+                    }
+                    col_block
+                        .datum_view(idx)
+                        .do_with_bytes(|val| histogram.update(val));
+                }
+            }
+
+            // The indexes of the dictionary are still worth compressing even
+            // if the values are random.
+            let enable_dict = column_encodings
+                .get(&col_schema.name)
+                .is_some_and(|v| v.enable_dict);
+            if let Some(compression) = choose_compression(histogram.entropy(), enable_dict) {
+                compressions.insert(col_schema.name.clone(), compression);
+            }
+        }
+
+        compressions
+    }
+
+    /// Decide the bloom filters of the string tag columns, and the filters are
+    /// sized by the cardinality observed in the `sample_row_groups` if the
+    /// sizing is adaptive.
+    fn build_column_bloom_filters(
+        &self,
+        sample_row_groups: &[FetchedRecordBatch],
+    ) -> HashMap<String, ColumnBloomFilter> {
+        let Some(options) = self.options.bloom_filter else {
+            return HashMap::new();
+        };
+        // The cold ssts are not indexed.
+        if !options.covers(self.meta_data.time_range, Timestamp::now()) {
+            return HashMap::new();
+        }
+
+        let max_ndv = (self.options.num_rows_per_row_group as u64).max(1);
+        let mut column_hashes = HashSet::new();
+        let mut bloom_filters = HashMap::new();
+        for (col_idx, col_schema) in self.meta_data.schema.columns().iter().enumerate() {
+            // The filters are only checked against the string values now.
+            if !col_schema.is_tag || !matches!(col_schema.data_type, DatumKind::String) {
+                continue;
+            }
+
+            let ndv = match options.sizing {
+                BloomFilterSizing::Fixed => max_ndv,
+                BloomFilterSizing::Adaptive => {
+                    for row_group in sample_row_groups {
+                        let col_block = &row_group.columns()[col_idx];
+                        for idx in 0..row_group.num_rows() {
+                            col_block.datum_view(idx).do_with_bytes(|val| {
+                                column_hashes.insert(hash_ext::hash64(val));
+                            })
+                        }
+                    }
+                    let ndv = column_hashes.len() as u64 * ADAPTIVE_BLOOM_FILTER_NDV_FACTOR;
+                    column_hashes.clear();
+                    ndv.clamp(1, max_ndv)
+                }
+            };
+            bloom_filters.insert(
+                col_schema.name.clone(),
+                ColumnBloomFilter {
+                    fpp: options.fpp,
+                    ndv,
+                },
+            );
+        }
+
+        bloom_filters
+    }
+
     /// Build the parquet filter for the given `row_group`.
     fn build_row_group_filter(
         &self,
@@ -320,29 +519,65 @@ impl<'a> RecordBatchGroupWriter<'a> {
         }
     }
 
+    /// Write the rows into the `sink`, and the separated fields into the
+    /// `fields_sink` which is closed before returning.
     async fn write_all<W: AsyncWrite + Send + Unpin + 'static>(
         mut self,
         sink: W,
+        fields_sink: Option<W>,
         meta_path: &Path,
     ) -> Result<(usize, ParquetMetaData, ParquetEncoder)> {
         let mut prev_record_batch: Option<FetchedRecordBatch> = None;
         let mut arrow_row_group = Vec::new();
+        let mut fields_row_group = Vec::new();
         let mut total_num_rows = 0;
 
         // Build the parquet encoder.
         let mut row_group = self.fetch_next_row_group(&mut prev_record_batch).await?;
         let mut column_encodings = std::mem::take(&mut self.options.column_encodings);
         self.build_column_encodings(&row_group, &mut column_encodings)?;
+        let column_value_encodings = self.apply_value_encodings(&mut column_encodings);
+        let column_bloom_filters = self.build_column_bloom_filters(&row_group);
+        let adaptive_compressions = self.choose_column_compressions(&row_group, &column_encodings);
+        let mut column_compressions = std::mem::take(&mut self.options.column_compressions);
+        let compression_level = self.options.compression_level;
+        for (col_name, compression) in &adaptive_compressions {
+            column_compressions.insert(col_name.clone(), compression.to_parquet(compression_level));
+        }
         let encode_options = EncodeOptions {
             num_rows_per_row_group: self.options.num_rows_per_row_group,
+            data_page_size: self.options.data_page_size,
             max_buffer_size: self.options.max_buffer_size,
             compression: self.options.compression,
             column_encodings,
+            column_value_encodings,
+            column_compressions,
+            column_bloom_filters,
         };
         let mut parquet_encoder =
             ParquetEncoder::try_new(sink, &self.meta_data.schema, &encode_options)
                 .box_err()
                 .context(EncodeRecordBatch)?;
+        let mut fields_encoder = match fields_sink {
+            Some(sink) if self.has_separated_fields() => {
+                let arrow_schema = self
+                    .meta_data
+                    .schema
+                    .to_arrow_schema_ref()
+                    .project(&self.separated_field_indexes)
+                    .box_err()
+                    .context(EncodeRecordBatch)?;
+                let encoder = ParquetEncoder::try_new_with_arrow_schema(
+                    sink,
+                    Arc::new(arrow_schema),
+                    &encode_options,
+                )
+                .box_err()
+                .context(EncodeRecordBatch)?;
+                Some(encoder)
+            }
+            _ => None,
+        };
 
         let mut parquet_filter = self
             .options
@@ -367,13 +602,30 @@ impl<'a> RecordBatchGroupWriter<'a> {
                     Self::update_column_values(column_values, &record_batch);
                 }
 
-                arrow_row_group.push(record_batch.into_record_batch().into_arrow_record_batch());
+                let arrow_record_batch = record_batch.into_record_batch().into_arrow_record_batch();
+                if fields_encoder.is_some() {
+                    let (arrow_record_batch, fields_record_batch) =
+                        split_separated_fields(arrow_record_batch, &self.separated_field_indexes)?;
+                    fields_row_group.push(fields_record_batch);
+                    arrow_row_group.push(arrow_record_batch);
+                } else {
+                    arrow_row_group.push(arrow_record_batch);
+                }
             }
             let num_rows = parquet_encoder
                 .encode_record_batches(arrow_row_group)
                 .await
                 .box_err()
                 .context(EncodeRecordBatch)?;
+            // The row groups of the fields file are aligned with the ones of
+            // the sst, so the reader can stitch them together.
+            if let Some(encoder) = &mut fields_encoder {
+                encoder
+                    .encode_record_batches(std::mem::take(&mut fields_row_group))
+                    .await
+                    .box_err()
+                    .context(EncodeRecordBatch)?;
+            }
 
             // TODO: it will be better to use `arrow_row_group.clear()` to reuse the
             // allocated memory.
@@ -400,6 +652,37 @@ impl<'a> RecordBatchGroupWriter<'a> {
             .set_meta_data_path(Some(meta_path.to_string()))
             .box_err()
             .context(EncodeRecordBatch)?;
+        if !adaptive_compressions.is_empty() {
+            debug!(
+                "Compressions of the columns are chosen adaptively, request_id:{}, compressions:{:?}",
+                self.request_id, adaptive_compressions
+            );
+            let compressions = table_options::column_options_to_string(&adaptive_compressions);
+            parquet_encoder
+                .set_column_compressions(compressions)
+                .box_err()
+                .context(EncodeRecordBatch)?;
+        }
+        if let Some(encoder) = fields_encoder {
+            let schema = &self.meta_data.schema;
+            let separated_fields = self
+                .separated_field_indexes
+                .iter()
+                .map(|idx| schema.column(*idx).name.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            parquet_encoder
+                .set_separated_fields(separated_fields)
+                .box_err()
+                .context(EncodeRecordBatch)?;
+            encoder.close().await.box_err().context(EncodeRecordBatch)?;
+        }
+        if self.options.recompressed {
+            parquet_encoder
+                .set_recompressed()
+                .box_err()
+                .context(EncodeRecordBatch)?;
+        }
 
         Ok((total_num_rows, parquet_meta_data, parquet_encoder))
     }
@@ -461,6 +744,44 @@ where
     Ok(bytes_size)
 }
 
+/// Find the indexes of the `separated_fields` in the `schema`, the columns not
+/// found or not nullable are ignored.
+fn separated_field_indexes(schema: &Schema, separated_fields: &[String]) -> Vec<usize> {
+    let mut indexes: Vec<_> = separated_fields
+        .iter()
+        .filter_map(|name| schema.index_of(name))
+        .filter(|idx| {
+            let column_schema = schema.column(*idx);
+            !schema.is_primary_key_index(idx) && !column_schema.is_tag && column_schema.is_nullable
+        })
+        .collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    indexes
+}
+
+/// Split the separated fields out of the `record_batch`, and their columns in
+/// the returned record batch are replaced by nulls.
+fn split_separated_fields(
+    record_batch: ArrowRecordBatch,
+    field_indexes: &[usize],
+) -> Result<(ArrowRecordBatch, ArrowRecordBatch)> {
+    let fields_record_batch = record_batch
+        .project(field_indexes)
+        .box_err()
+        .context(EncodeRecordBatch)?;
+    let num_rows = record_batch.num_rows();
+    let mut columns = record_batch.columns().to_vec();
+    for idx in field_indexes {
+        columns[*idx] = new_null_array(columns[*idx].data_type(), num_rows);
+    }
+    let record_batch = ArrowRecordBatch::try_new(record_batch.schema(), columns)
+        .box_err()
+        .context(EncodeRecordBatch)?;
+
+    Ok((record_batch, fields_record_batch))
+}
+
 async fn multi_upload_abort(path: &Path, aborter: ObjectStoreMultiUploadAborter<'_>) {
     // The uploading file will be leaked if failed to abort. A repair command will
     // be provided to clean up the leaked files.
@@ -484,23 +805,52 @@ impl<'a> SstWriter for ParquetSstWriter<'a> {
 
         let write_options = WriteOptions {
             num_rows_per_row_group: self.options.num_rows_per_row_group,
+            data_page_size: self.options.data_page_size,
             max_buffer_size: self.options.max_buffer_size,
             compression: self.options.compression,
+            compression_level: self.options.compression_level,
             sst_level: self.options.sst_level,
             column_encodings: std::mem::take(&mut self.options.column_encodings),
+            value_encodings: std::mem::take(&mut self.options.value_encodings),
+            column_compressions: std::mem::take(&mut self.options.column_compressions),
+            adaptive_compression: self.options.adaptive_compression,
+            separated_fields: std::mem::take(&mut self.options.separated_fields),
+            bloom_filter: self.options.bloom_filter,
+            recompressed: self.options.recompressed,
         };
         let group_writer = RecordBatchGroupWriter::new(request_id, input, meta, write_options);
 
+        let fields_path = Path::from(sst_util::new_fields_path(self.path.as_ref()));
+        let (fields_aborter, fields_sink) = if group_writer.has_separated_fields() {
+            let (aborter, sink) =
+                ObjectStoreMultiUploadAborter::initialize_upload(self.store, &fields_path).await?;
+            (Some(aborter), Some(sink))
+        } else {
+            (None, None)
+        };
+
         let (aborter, sink) =
-            ObjectStoreMultiUploadAborter::initialize_upload(self.store, self.path).await?;
+            match ObjectStoreMultiUploadAborter::initialize_upload(self.store, self.path).await {
+                Ok(v) => v,
+                Err(e) => {
+                    if let Some(fields_aborter) = fields_aborter {
+                        multi_upload_abort(&fields_path, fields_aborter).await;
+                    }
+                    return Err(e);
+                }
+            };
 
         let meta_path = Path::from(sst_util::new_metadata_path(self.path.as_ref()));
 
+        let has_fields_file = fields_sink.is_some();
         let (total_num_rows, parquet_metadata, mut data_encoder) =
-            match group_writer.write_all(sink, &meta_path).await {
+            match group_writer.write_all(sink, fields_sink, &meta_path).await {
                 Ok(v) => v,
                 Err(e) => {
                     multi_upload_abort(self.path, aborter).await;
+                    if let Some(fields_aborter) = fields_aborter {
+                        multi_upload_abort(&fields_path, fields_aborter).await;
+                    }
                     return Err(e);
                 }
             };
@@ -534,11 +884,70 @@ impl<'a> SstWriter for ParquetSstWriter<'a> {
             row_num: total_num_rows,
             storage_format: StorageFormat::Columnar,
             meta_path: meta_path.to_string(),
+            fields_path: has_fields_file.then(|| fields_path.to_string()),
             time_range,
         })
     }
 }
 
+/// Histogram of the bytes to estimate their entropy.
+struct ByteHistogram {
+    counts: [usize; 256],
+    num_bytes: usize,
+}
+
+impl Default for ByteHistogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; 256],
+            num_bytes: 0,
+        }
+    }
+}
+
+impl ByteHistogram {
+    fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.counts[*b as usize] += 1;
+        }
+        self.num_bytes += bytes.len();
+    }
+
+    #[inline]
+    fn num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+
+    /// Shannon entropy in bits per byte, which is in [0, 8].
+    fn entropy(&self) -> f64 {
+        if self.num_bytes == 0 {
+            return 0.0;
+        }
+
+        let total = self.num_bytes as f64;
+        self.counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / total;
+                -p * p.log2()
+            })
+            .sum()
+    }
+}
+
+/// Choose the compression by the byte `entropy` of the values, and `None` means
+/// the compression of the table is used.
+fn choose_compression(entropy: f64, enable_dict: bool) -> Option<table_options::Compression> {
+    if entropy >= MIN_ENTROPY_SKIP_COMPRESSION && !enable_dict {
+        Some(table_options::Compression::Uncompressed)
+    } else if entropy <= MAX_ENTROPY_ZSTD_COMPRESSION {
+        Some(table_options::Compression::Zstd)
+    } else {
+        None
+    }
+}
+
 /// A sampler to decide the column encoding options (whether to do dictionary
 /// encoding) with a bunch of sample row groups.
 struct ColumnEncodingSampler<'a> {
@@ -620,11 +1029,16 @@ impl<'a> ColumnEncodingSampler<'a> {
 #[cfg(test)]
 mod tests {
 
-    use std::{sync::Arc, task::Poll};
+    use std::{sync::Arc, task::Poll, time::Duration};
 
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray},
+        datatypes::{DataType, Field, Schema as ArrowSchema},
+    };
     use bytes_ext::Bytes;
     use common_types::{
         projected_schema::{ProjectedSchema, RowProjectorBuilder},
+        row::Row,
         tests::{build_row, build_row_for_dictionary, build_schema, build_schema_with_dictionary},
         time::{TimeRange, Timestamp},
     };
@@ -644,7 +1058,7 @@ mod tests {
             parquet::AsyncParquetReader,
             reader::{tests::check_stream, SstReader},
         },
-        table_options::{self, StorageFormatHint},
+        table_options::{self, ColumnOptions, StorageFormatHint},
     };
 
     // TODO(xikai): add test for reverse reader
@@ -670,9 +1084,20 @@ mod tests {
             let sst_write_options = SstWriteOptions {
                 storage_format_hint: StorageFormatHint::Auto,
                 num_rows_per_row_group,
+                data_page_size: table_options::DEFAULT_DATA_PAGE_SIZE as usize,
                 compression: table_options::Compression::Uncompressed,
+                compression_level: None,
+                column_options: Default::default(),
+                adaptive_compression: false,
+                separated_fields: Vec::new(),
                 max_buffer_size: 0,
                 column_stats: Default::default(),
+                bloom_filter: Some(table_options::BloomFilterOptions {
+                    fpp: 0.01,
+                    sizing: table_options::BloomFilterSizing::Adaptive,
+                    recent_duration: None,
+                }),
+                recompressed: false,
             };
 
             let dir = tempdir().unwrap();
@@ -770,6 +1195,7 @@ mod tests {
                 predicate: Arc::new(Predicate::empty()),
                 meta_cache: None,
                 scan_options,
+                scheduled_io: None,
                 runtime: runtime.clone(),
                 row_projector_builder,
             };
@@ -912,10 +1338,18 @@ mod tests {
 
         let write_options = WriteOptions {
             num_rows_per_row_group,
+            data_page_size: table_options::DEFAULT_DATA_PAGE_SIZE as usize,
             max_buffer_size: 0,
             compression: Compression::UNCOMPRESSED,
+            compression_level: None,
             sst_level: Level::default(),
             column_encodings: Default::default(),
+            value_encodings: Default::default(),
+            column_compressions: Default::default(),
+            adaptive_compression: false,
+            separated_fields: Vec::new(),
+            bloom_filter: None,
+            recompressed: false,
         };
         let meta_data = MetaData {
             min_key: Default::default(),
@@ -1068,4 +1502,283 @@ mod tests {
         ];
         check_sample_column_encoding(sampler, expect_enable_dicts);
     }
+
+    #[test]
+    fn test_build_column_bloom_filters() {
+        let schema = build_schema_with_dictionary();
+        let rows: Vec<_> = (0..10)
+            .map(|i| {
+                let tag1 = format!("host{}", i % 3);
+                build_row_for_dictionary(
+                    b"a",
+                    100,
+                    10.0,
+                    "v4",
+                    1000,
+                    1_000_000,
+                    Some(&tag1),
+                    "tagv2",
+                )
+            })
+            .collect();
+        let sample_row_groups = vec![build_fetched_record_batch_with_key(schema.clone(), rows)];
+        let meta_data = MetaData {
+            min_key: Bytes::from_static(b""),
+            max_key: Bytes::from_static(b""),
+            time_range: TimeRange::new_unchecked(Timestamp::new(1), Timestamp::new(2)),
+            max_sequence: 200,
+            schema,
+        };
+
+        let recent = Some(Duration::from_secs(3600));
+        let testcases = [
+            (None, None, vec![]),
+            (
+                Some(BloomFilterSizing::Adaptive),
+                None,
+                vec![("tag1", 6), ("tag2", 2)],
+            ),
+            (
+                Some(BloomFilterSizing::Fixed),
+                None,
+                vec![("tag1", 100), ("tag2", 100)],
+            ),
+            // The sst is older than the recent duration.
+            (Some(BloomFilterSizing::Fixed), recent, vec![]),
+        ];
+        for (sizing, recent_duration, expect_ndvs) in testcases {
+            let write_options = WriteOptions {
+                num_rows_per_row_group: 100,
+                data_page_size: table_options::DEFAULT_DATA_PAGE_SIZE as usize,
+                max_buffer_size: 0,
+                compression: Compression::UNCOMPRESSED,
+                compression_level: None,
+                sst_level: Level::default(),
+                column_encodings: Default::default(),
+                value_encodings: Default::default(),
+                column_compressions: Default::default(),
+                adaptive_compression: false,
+                separated_fields: Vec::new(),
+                bloom_filter: sizing.map(|sizing| BloomFilterOptions {
+                    fpp: 0.01,
+                    sizing,
+                    recent_duration,
+                }),
+                recompressed: false,
+            };
+            let group_writer = RecordBatchGroupWriter::new(
+                RequestId::next_id(),
+                Box::new(stream::empty::<writer::RecordBatchStreamItem>()),
+                &meta_data,
+                write_options,
+            );
+
+            let expect: HashMap<_, _> = expect_ndvs
+                .into_iter()
+                .map(|(col, ndv)| (col.to_string(), ColumnBloomFilter { fpp: 0.01, ndv }))
+                .collect();
+            assert_eq!(
+                expect,
+                group_writer.build_column_bloom_filters(&sample_row_groups)
+            );
+        }
+    }
+    #[test]
+    fn test_write_with_column_options() {
+        test_util::init_log_for_test();
+
+        let runtime = Arc::new(runtime::Builder::default().build().unwrap());
+        let rows: Vec<_> = (0..4096)
+            .map(|i| {
+                let key1 = format!("device{}", i % 8);
+                let field2 = format!("host{}", i % 4);
+                build_row(
+                    key1.as_bytes(),
+                    1_700_000_000_000 + i * 1000,
+                    (i % 16) as f64,
+                    &field2,
+                    19000 + (i / 1024) as i32,
+                    i * 1_000_000,
+                )
+            })
+            .collect();
+
+        let plain_options = SstWriteOptions {
+            storage_format_hint: StorageFormatHint::Auto,
+            num_rows_per_row_group: 4096,
+            data_page_size: table_options::DEFAULT_DATA_PAGE_SIZE as usize,
+            compression: table_options::Compression::Uncompressed,
+            compression_level: None,
+            column_options: ColumnOptions {
+                encodings: ["key1", "key2", "field1", "field2", "field3", "field4"]
+                    .into_iter()
+                    .map(|col| (col.to_string(), ValueEncoding::Plain))
+                    .collect(),
+                compressions: BTreeMap::new(),
+            },
+            adaptive_compression: false,
+            separated_fields: Vec::new(),
+            max_buffer_size: 0,
+            column_stats: Default::default(),
+            bloom_filter: None,
+            recompressed: false,
+        };
+        let encoded_options = SstWriteOptions {
+            compression: table_options::Compression::Zstd,
+            compression_level: Some(9),
+            column_options: ColumnOptions {
+                encodings: BTreeMap::from([
+                    ("key1".to_string(), ValueEncoding::Dict),
+                    ("key2".to_string(), ValueEncoding::Delta),
+                    ("field1".to_string(), ValueEncoding::Rle),
+                    ("field2".to_string(), ValueEncoding::Dict),
+                    ("field3".to_string(), ValueEncoding::Delta),
+                    ("field4".to_string(), ValueEncoding::Delta),
+                ]),
+                compressions: BTreeMap::from([(
+                    "field1".to_string(),
+                    table_options::Compression::Lz4,
+                )]),
+            },
+            ..plain_options.clone()
+        };
+
+        // The ssts written with the different options are read back by the
+        // same reader.
+        let plain_size = write_and_then_read_back(runtime.clone(), &plain_options, rows.clone());
+        let encoded_size = write_and_then_read_back(runtime, &encoded_options, rows);
+        debug!("Size of the ssts, plain:{plain_size}, encoded:{encoded_size}");
+        assert!(encoded_size * 4 < plain_size);
+    }
+
+    /// Write the `rows` into a sst and check the rows read back, and return
+    /// the size of the sst.
+    fn write_and_then_read_back(
+        runtime: Arc<Runtime>,
+        sst_write_options: &SstWriteOptions,
+        rows: Vec<Row>,
+    ) -> usize {
+        runtime.clone().block_on(async {
+            let dir = tempdir().unwrap();
+            let store: ObjectStoreRef =
+                Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+            let store_picker: ObjectStorePickerRef = Arc::new(store);
+            let sst_file_path = Path::from("data.par");
+
+            let schema = build_schema();
+            let sst_meta = MetaData {
+                min_key: Bytes::from_static(b""),
+                max_key: Bytes::from_static(b""),
+                time_range: TimeRange::new_unchecked(Timestamp::new(1), Timestamp::new(2)),
+                max_sequence: 200,
+                schema: schema.clone(),
+            };
+            let batch: writer::RecordBatchStreamItem =
+                Ok(build_fetched_record_batch_with_key(schema.clone(), rows.clone()));
+            let mut writer = FactoryImpl
+                .create_writer(sst_write_options, &sst_file_path, &store_picker, Level::MAX)
+                .await
+                .unwrap();
+            let sst_info = writer
+                .write(
+                    RequestId::next_id(),
+                    &sst_meta,
+                    Box::new(stream::iter(vec![batch])),
+                )
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), sst_info.row_num);
+
+            let projected_schema = ProjectedSchema::no_projection(schema);
+            let row_projector_builder = RowProjectorBuilder::new(
+                projected_schema.to_record_schema(),
+                projected_schema.table_schema().clone(),
+                None,
+            );
+            let sst_read_options = SstReadOptions {
+                maybe_table_level_metrics: None,
+                frequency: ReadFrequency::Frequent,
+                num_rows_per_row_group: sst_write_options.num_rows_per_row_group,
+                predicate: Arc::new(Predicate::empty()),
+                meta_cache: None,
+                scan_options: ScanOptions::default(),
+                scheduled_io: None,
+                runtime,
+                row_projector_builder,
+            };
+            let mut reader = AsyncParquetReader::new(
+                &sst_file_path,
+                &sst_read_options,
+                None,
+                &store_picker,
+                None,
+            );
+            let mut stream = reader.read().await.unwrap();
+            check_stream(&mut stream, rows).await;
+
+            sst_info.file_size
+        })
+    }
+
+    #[test]
+    fn test_choose_compression_by_entropy() {
+        let mut constant = ByteHistogram::default();
+        constant.update(&[7; 1024]);
+        assert_eq!(0.0, constant.entropy());
+        assert_eq!(
+            Some(table_options::Compression::Zstd),
+            choose_compression(constant.entropy(), false)
+        );
+
+        let mut uniform = ByteHistogram::default();
+        for _ in 0..4 {
+            uniform.update(&(0..=255).collect::<Vec<u8>>());
+        }
+        assert_eq!(1024, uniform.num_bytes());
+        assert!((uniform.entropy() - 8.0).abs() < 1e-9);
+        assert_eq!(
+            Some(table_options::Compression::Uncompressed),
+            choose_compression(uniform.entropy(), false)
+        );
+        // The indexes of the dictionary are still compressed.
+        assert_eq!(None, choose_compression(uniform.entropy(), true));
+
+        let mut text = ByteHistogram::default();
+        text.update(b"the quick brown fox jumps over the lazy dog");
+        assert_eq!(None, choose_compression(text.entropy(), false));
+    }
+
+    #[test]
+    fn test_split_separated_fields() {
+        let schema = build_schema_with_dictionary();
+        let separated_fields = ["field2", "tag1", "not_exist", "field1", "key1"]
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        let indexes = separated_field_indexes(&schema, &separated_fields);
+        let expect = vec![
+            schema.index_of("field1").unwrap(),
+            schema.index_of("field2").unwrap(),
+        ];
+        assert_eq!(expect, indexes);
+
+        let arrow_schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("key", DataType::Int64, false),
+            Field::new("field1", DataType::Utf8, true),
+            Field::new("field2", DataType::Int64, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec!["a", "b"])),
+            Arc::new(Int64Array::from(vec![3, 4])),
+        ];
+        let record_batch = ArrowRecordBatch::try_new(arrow_schema, columns).unwrap();
+        let (record_batch, fields_record_batch) = split_separated_fields(record_batch, &[1]).unwrap();
+        assert_eq!(3, record_batch.num_columns());
+        assert_eq!(2, record_batch.column(1).null_count());
+        assert_eq!(0, record_batch.column(2).null_count());
+        assert_eq!(1, fields_record_batch.num_columns());
+        assert_eq!("field1", fields_record_batch.schema().field(0).name());
+        assert_eq!(0, fields_record_batch.column(0).null_count());
+    }
 }
@@ -0,0 +1,356 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Quarantine of the corrupt ssts.
+//!
+//! If the [CorruptSstPolicy] is [CorruptSstPolicy::SkipAndWarn], a sst failing
+//! to be decoded during a query is skipped instead of failing the whole query.
+//! The file is quarantined so that the following queries skip it directly,
+//! until the scrubber checks and releases it.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use common_types::request_id::RequestId;
+use generic_error::GenericError;
+use logger::warn;
+use serde::{Deserialize, Serialize};
+use table_engine::{
+    event::{self, EventKind},
+    query_warning::{QueryWarnings, WarningKind},
+    table::TableId,
+};
+
+use crate::{
+    prefetchable_stream::PrefetchableStream,
+    row_iter::record_batch_stream::{
+        self, BoxedPrefetchableRecordBatchStream, SequencedRecordBatchRes,
+    },
+    sst::{manager::FileId, reader},
+};
+
+/// Policy of reading the corrupt ssts in the queries, the background jobs
+/// like compaction always fail on them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CorruptSstPolicy {
+    /// Fail the query.
+    #[default]
+    Fail,
+    /// Skip the sst and return the partial results with a warning.
+    SkipAndWarn,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuarantinedSst {
+    pub table_id: TableId,
+    pub file_id: FileId,
+    pub reason: String,
+    /// Milliseconds since the unix epoch.
+    pub quarantined_at: i64,
+}
+
+/// The quarantined ssts of all the tables in the engine.
+#[derive(Debug, Default)]
+pub struct SstQuarantine {
+    ssts: RwLock<HashMap<(TableId, FileId), QuarantinedSst>>,
+}
+
+pub type SstQuarantineRef = Arc<SstQuarantine>;
+
+impl SstQuarantine {
+    /// Quarantine the sst, returns false if it's already quarantined.
+    pub fn quarantine(&self, table_id: TableId, file_id: FileId, reason: String) -> bool {
+        let mut ssts = self.ssts.write().unwrap();
+        if ssts.contains_key(&(table_id, file_id)) {
+            return false;
+        }
+
+        ssts.insert(
+            (table_id, file_id),
+            QuarantinedSst {
+                table_id,
+                file_id,
+                reason,
+                quarantined_at: time_ext::current_time_millis() as i64,
+            },
+        );
+        true
+    }
+
+    pub fn is_quarantined(&self, table_id: TableId, file_id: FileId) -> bool {
+        self.ssts.read().unwrap().contains_key(&(table_id, file_id))
+    }
+
+    /// List the quarantined ssts waiting to be checked by the scrubber.
+    pub fn list(&self) -> Vec<QuarantinedSst> {
+        self.ssts.read().unwrap().values().cloned().collect()
+    }
+
+    /// Release the sst after it's repaired or removed by the scrubber.
+    pub fn release(&self, table_id: TableId, file_id: FileId) -> Option<QuarantinedSst> {
+        self.ssts.write().unwrap().remove(&(table_id, file_id))
+    }
+}
+
+/// Whether the error of reading a sst is caused by the corrupt data rather
+/// than the unavailable storage.
+fn is_corruption(err: &reader::Error) -> bool {
+    matches!(
+        err,
+        reader::Error::DecodeRecordBatch { .. }
+            | reader::Error::FetchAndDecodeSstMeta { .. }
+            | reader::Error::DecodePageIndexes { .. }
+            | reader::Error::DecodeSstMeta { .. }
+            | reader::Error::SstMetaNotFound { .. }
+            | reader::Error::EmptySstMeta { .. }
+            | reader::Error::ParquetError { .. }
+    )
+}
+
+fn is_build_corruption(err: &record_batch_stream::Error) -> bool {
+    match err {
+        record_batch_stream::Error::ReadSstMeta { source }
+        | record_batch_stream::Error::ReadSstData { source } => is_corruption(source),
+        _ => false,
+    }
+}
+
+fn is_read_corruption(err: &GenericError) -> bool {
+    err.downcast_ref::<reader::Error>()
+        .map(is_corruption)
+        .unwrap_or(false)
+}
+
+/// Skip the corrupt ssts read by a query.
+#[derive(Debug, Clone)]
+pub struct CorruptSstSkipper {
+    request_id: RequestId,
+    /// Warnings of the query, the skipped ssts are reported to it.
+    warnings: QueryWarnings,
+    table: String,
+    table_id: TableId,
+    quarantine: SstQuarantineRef,
+}
+
+impl CorruptSstSkipper {
+    pub fn new(
+        request_id: RequestId,
+        warnings: QueryWarnings,
+        table: String,
+        table_id: TableId,
+        quarantine: SstQuarantineRef,
+    ) -> Self {
+        Self {
+            request_id,
+            warnings,
+            table,
+            table_id,
+            quarantine,
+        }
+    }
+
+    /// Returns true if the sst is quarantined and should be skipped.
+    pub fn skip_quarantined(&self, file_id: FileId) -> bool {
+        if !self.quarantine.is_quarantined(self.table_id, file_id) {
+            return false;
+        }
+
+        self.warnings.record(
+            WarningKind::PartialResult,
+            format!(
+                "quarantined sst is skipped, table:{}, file_id:{file_id}",
+                self.table
+            ),
+        );
+        true
+    }
+
+    /// Handle the result of building the stream of the sst, returns `None` if
+    /// the sst is corrupt and skipped, otherwise the stream skipping the rest
+    /// of the sst once it meets the corrupt data.
+    pub fn on_stream_built(
+        &self,
+        file_id: FileId,
+        result: record_batch_stream::Result<BoxedPrefetchableRecordBatchStream>,
+    ) -> record_batch_stream::Result<Option<BoxedPrefetchableRecordBatchStream>> {
+        match result {
+            Ok(stream) => Ok(Some(Box::new(SkipCorruptStream {
+                inner: stream,
+                file_id,
+                skipper: self.clone(),
+                skipped: false,
+            }))),
+            Err(e) if is_build_corruption(&e) => {
+                self.skip(file_id, e.to_string());
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn skip(&self, file_id: FileId, reason: String) {
+        warn!(
+            "Corrupt sst is skipped and quarantined, request_id:{}, table:{}, file_id:{file_id}, reason:{reason}",
+            self.request_id, self.table
+        );
+
+        if self
+            .quarantine
+            .quarantine(self.table_id, file_id, reason.clone())
+        {
+            event::record(
+                EventKind::SstQuarantine,
+                &self.table,
+                self.table_id,
+                format!("file_id:{file_id}, reason:{reason}"),
+            );
+        }
+        self.warnings.record(
+            WarningKind::PartialResult,
+            format!(
+                "corrupt sst is skipped, table:{}, file_id:{file_id}",
+                self.table
+            ),
+        );
+    }
+}
+
+/// Stream ending the read of the sst once it meets the corrupt data.
+struct SkipCorruptStream {
+    inner: BoxedPrefetchableRecordBatchStream,
+    file_id: FileId,
+    skipper: CorruptSstSkipper,
+    skipped: bool,
+}
+
+#[async_trait]
+impl PrefetchableStream for SkipCorruptStream {
+    type Item = SequencedRecordBatchRes;
+
+    async fn start_prefetch(&mut self) {
+        self.inner.start_prefetch().await;
+    }
+
+    async fn fetch_next(&mut self) -> Option<Self::Item> {
+        if self.skipped {
+            return None;
+        }
+
+        match self.inner.fetch_next().await {
+            Some(Err(e)) if is_read_corruption(&e) => {
+                self.skipper.skip(self.file_id, e.to_string());
+                self.skipped = true;
+                None
+            }
+            v => v,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+
+    use super::*;
+    use crate::prefetchable_stream::NoopPrefetcher;
+
+    fn build_error_stream(errors: Vec<reader::Error>) -> BoxedPrefetchableRecordBatchStream {
+        let items: Vec<SequencedRecordBatchRes> = errors
+            .into_iter()
+            .map(|e| Err(Box::new(e) as GenericError))
+            .collect();
+        Box::new(NoopPrefetcher(Box::new(stream::iter(items))))
+    }
+
+    #[test]
+    fn test_sst_quarantine() {
+        let quarantine = SstQuarantine::default();
+        let table_id = TableId::from(1);
+        assert!(quarantine.quarantine(table_id, 10, "bad".to_string()));
+        assert!(!quarantine.quarantine(table_id, 10, "bad".to_string()));
+        assert!(quarantine.is_quarantined(table_id, 10));
+        assert!(!quarantine.is_quarantined(table_id, 11));
+        assert!(!quarantine.is_quarantined(TableId::from(2), 10));
+        assert_eq!(1, quarantine.list().len());
+
+        assert_eq!("bad", quarantine.release(table_id, 10).unwrap().reason);
+        assert!(!quarantine.is_quarantined(table_id, 10));
+    }
+
+    #[test]
+    fn test_is_corruption() {
+        let decode_err = reader::Error::DecodeRecordBatch {
+            source: "bad page".into(),
+        };
+        assert!(is_read_corruption(&(Box::new(decode_err) as GenericError)));
+        let other_err = reader::Error::Other {
+            source: "io error".into(),
+        };
+        assert!(!is_read_corruption(&(Box::new(other_err) as GenericError)));
+        let err: GenericError = "unknown".into();
+        assert!(!is_read_corruption(&err));
+    }
+
+    #[tokio::test]
+    async fn test_skip_corrupt_stream() {
+        let warnings = QueryWarnings::default();
+        let table_id = TableId::from(1);
+        let quarantine = Arc::new(SstQuarantine::default());
+        let skipper = CorruptSstSkipper::new(
+            RequestId::next_id(),
+            warnings.clone(),
+            "t".to_string(),
+            table_id,
+            quarantine.clone(),
+        );
+
+        let corrupt_err = || reader::Error::DecodeRecordBatch {
+            source: "bad page".into(),
+        };
+        let mut stream = skipper
+            .on_stream_built(
+                1,
+                Ok(build_error_stream(vec![corrupt_err(), corrupt_err()])),
+            )
+            .unwrap()
+            .unwrap();
+        assert!(stream.fetch_next().await.is_none());
+        assert!(stream.fetch_next().await.is_none());
+        assert!(quarantine.is_quarantined(table_id, 1));
+        assert_eq!(1, warnings.take().len());
+
+        // The quarantined sst is skipped by the following reads.
+        assert!(skipper.skip_quarantined(1));
+        assert!(!skipper.skip_quarantined(2));
+        assert_eq!(1, warnings.take().len());
+
+        // Other errors are still returned.
+        let other_err = reader::Error::Other {
+            source: "io error".into(),
+        };
+        let mut stream = skipper
+            .on_stream_built(2, Ok(build_error_stream(vec![other_err])))
+            .unwrap()
+            .unwrap();
+        assert!(stream.fetch_next().await.unwrap().is_err());
+        assert!(!quarantine.is_quarantined(table_id, 2));
+        assert!(warnings.take().is_empty());
+    }
+}
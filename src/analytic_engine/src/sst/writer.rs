@@ -113,10 +113,22 @@ pub struct SstInfo {
     pub row_num: usize,
     pub storage_format: StorageFormat,
     pub meta_path: String,
+    /// Path of the file storing the separated fields, `None` if no field is
+    /// separated.
+    pub fields_path: Option<String>,
     /// Real time range, not aligned to segment.
     pub time_range: TimeRange,
 }
 
+impl SstInfo {
+    /// The files to be purged along with the sst.
+    pub fn associated_files(&self) -> Vec<String> {
+        let mut files = vec![self.meta_path.clone()];
+        files.extend(self.fields_path.clone());
+        files
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MetaData {
     /// Min key of the sst.
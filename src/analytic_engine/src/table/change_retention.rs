@@ -0,0 +1,247 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Retention of the wal entries needed by the consumers of the table changes
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use common_types::SequenceNumber;
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use object_store::{ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use table_engine::table::TableId;
+use time_ext::current_time_millis;
+use tokio::sync::OnceCell;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to load change retention, path:{}, err:{}", path, source))]
+    LoadRetention { path: String, source: GenericError },
+
+    #[snafu(display("Failed to store change retention, path:{}, err:{}", path, source))]
+    StoreRetention { path: String, source: GenericError },
+}
+
+define_result!(Error);
+
+/// Directory of the persisted retentions in the object store.
+const CHANGE_RETENTION_DIR: &str = "change_retention";
+
+fn retention_path(table_id: TableId) -> String {
+    format!("{CHANGE_RETENTION_DIR}/{table_id}.json")
+}
+
+/// Retention persisted in the object store.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PersistedRetention {
+    /// Consumer -> (retained sequence, expire time in millis)
+    consumers: BTreeMap<String, (SequenceNumber, u64)>,
+}
+
+/// The sequences after which the changes are still needed by the consumers,
+/// and the wal entries of these changes are kept even if they are flushed.
+///
+/// A consumer is forgotten if it doesn't read the changes for a while, so an
+/// abandoned consumer won't keep the wal entries forever.
+///
+/// The retention is persisted before the wal entries are deleted by the flush,
+/// and it's loaded before it's used after the table is opened, so the cursors
+/// of the consumers survive the restart or the move of the table.
+#[derive(Debug, Default)]
+pub struct ChangeRetention {
+    /// Consumer -> (retained sequence, expire time)
+    consumers: Mutex<HashMap<String, (SequenceNumber, Instant)>>,
+    loaded: OnceCell<()>,
+    /// Whether the persisted retention may have consumers, so it needs to be
+    /// persisted again even if there are no consumers.
+    persisted: AtomicBool,
+}
+
+impl ChangeRetention {
+    /// Retain the changes after `sequence` for the consumer until `ttl`
+    /// later.
+    ///
+    /// Returns the sequence retained for the consumer before, `None` if the
+    /// consumer is unknown or expired.
+    pub fn retain(
+        &self,
+        consumer: &str,
+        sequence: SequenceNumber,
+        ttl: Duration,
+        now: Instant,
+    ) -> Option<SequenceNumber> {
+        let mut consumers = self.consumers.lock().unwrap();
+        let prev = consumers.insert(consumer.to_string(), (sequence, now + ttl));
+        prev.and_then(|(sequence, expire_time)| (expire_time > now).then_some(sequence))
+    }
+
+    /// The min sequence retained by the consumers, and the expired consumers
+    /// are removed.
+    pub fn min_retained(&self, now: Instant) -> Option<SequenceNumber> {
+        let mut consumers = self.consumers.lock().unwrap();
+        consumers.retain(|_, (_, expire_time)| *expire_time > now);
+        consumers.values().map(|(sequence, _)| *sequence).min()
+    }
+
+    /// Load the retention persisted for the table, it's only loaded once and
+    /// the consumers retained since the table is opened are kept.
+    pub async fn load(&self, store: &ObjectStoreRef, table_id: TableId) -> Result<()> {
+        self.loaded
+            .get_or_try_init(|| async {
+                let path = retention_path(table_id);
+                let get_res = store.get(&Path::from(path.as_str())).await;
+                if let Err(object_store::ObjectStoreError::NotFound { .. }) = &get_res {
+                    return Ok(());
+                }
+                let payload = get_res
+                    .box_err()
+                    .context(LoadRetention { path: &path })?
+                    .bytes()
+                    .await
+                    .box_err()
+                    .context(LoadRetention { path: &path })?;
+                let persisted: PersistedRetention = serde_json::from_slice(&payload)
+                    .box_err()
+                    .context(LoadRetention { path: &path })?;
+
+                self.restore(persisted, current_time_millis(), Instant::now());
+                Ok(())
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Persist the retention of the consumers not expired for the table.
+    pub async fn persist(&self, store: &ObjectStoreRef, table_id: TableId) -> Result<()> {
+        let persisted = self.to_persisted(current_time_millis(), Instant::now());
+        let has_consumers = !persisted.consumers.is_empty();
+        // Nothing to persist for the tables never subscribed.
+        if !has_consumers && !self.persisted.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let path = retention_path(table_id);
+        let payload = serde_json::to_vec(&persisted)
+            .box_err()
+            .context(StoreRetention { path: &path })?;
+        store
+            .put(&Path::from(path.as_str()), payload.into())
+            .await
+            .box_err()
+            .context(StoreRetention { path: &path })?;
+        self.persisted.store(has_consumers, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn to_persisted(&self, now_millis: u64, now: Instant) -> PersistedRetention {
+        let consumers = self.consumers.lock().unwrap();
+        let consumers = consumers
+            .iter()
+            .filter(|(_, (_, expire_time))| *expire_time > now)
+            .map(|(consumer, (sequence, expire_time))| {
+                let ttl = expire_time.duration_since(now).as_millis() as u64;
+                (consumer.clone(), (*sequence, now_millis + ttl))
+            })
+            .collect();
+
+        PersistedRetention { consumers }
+    }
+
+    fn restore(&self, persisted: PersistedRetention, now_millis: u64, now: Instant) {
+        let mut consumers = self.consumers.lock().unwrap();
+        for (consumer, (sequence, expire_millis)) in persisted.consumers {
+            if expire_millis <= now_millis {
+                continue;
+            }
+            let expire_time = now + Duration::from_millis(expire_millis - now_millis);
+            consumers.entry(consumer).or_insert((sequence, expire_time));
+        }
+        self.persisted.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use object_store::LocalFileSystem;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_change_retention() {
+        let retention = ChangeRetention::default();
+        let ttl = Duration::from_secs(10);
+        let now = Instant::now();
+        assert_eq!(retention.min_retained(now), None);
+
+        assert_eq!(retention.retain("a", 10, ttl, now), None);
+        assert_eq!(retention.retain("b", 5, ttl, now), None);
+        assert_eq!(retention.min_retained(now), Some(5));
+        assert_eq!(retention.retain("b", 20, ttl, now), Some(5));
+        assert_eq!(retention.min_retained(now), Some(10));
+
+        // The consumer "a" expires while "b" keeps reading.
+        let later = now + Duration::from_secs(8);
+        assert_eq!(retention.retain("b", 30, ttl, later), Some(20));
+        let later = now + Duration::from_secs(15);
+        assert_eq!(retention.min_retained(later), Some(30));
+        assert_eq!(retention.retain("a", 40, ttl, later), None);
+    }
+
+    #[tokio::test]
+    async fn test_persist_change_retention() {
+        let dir = TempDir::new().unwrap();
+        let store: ObjectStoreRef = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        let table_id = TableId::new(1);
+        let ttl = Duration::from_secs(10);
+
+        // Nothing is persisted for the tables never subscribed.
+        let retention = ChangeRetention::default();
+        retention.persist(&store, table_id).await.unwrap();
+        let path = Path::from(retention_path(table_id));
+        assert!(store.head(&path).await.is_err());
+
+        retention.retain("a", 10, ttl, Instant::now());
+        retention.retain("b", 5, Duration::ZERO, Instant::now());
+        retention.persist(&store, table_id).await.unwrap();
+
+        // The expired consumer "b" is not persisted.
+        let reopened = ChangeRetention::default();
+        reopened.load(&store, table_id).await.unwrap();
+        assert_eq!(reopened.min_retained(Instant::now()), Some(10));
+        assert_eq!(reopened.retain("a", 20, ttl, Instant::now()), Some(10));
+        assert_eq!(reopened.retain("b", 20, ttl, Instant::now()), None);
+
+        // The consumers retained before loading are kept.
+        let reopened = ChangeRetention::default();
+        reopened.retain("a", 30, ttl, Instant::now());
+        reopened.load(&store, table_id).await.unwrap();
+        assert_eq!(reopened.min_retained(Instant::now()), Some(30));
+    }
+}
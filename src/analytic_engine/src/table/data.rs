@@ -43,9 +43,12 @@ use generic_error::{GenericError, GenericResult};
 use id_allocator::IdAllocator;
 use logger::{debug, info};
 use macros::define_result;
-use object_store::Path;
+use object_store::{cache_priority, Path};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
-use table_engine::table::{SchemaId, TableId};
+use table_engine::{
+    histogram::ColumnHistograms,
+    table::{SchemaId, TableId},
+};
 use time_ext::ReadableDuration;
 
 use crate::{
@@ -64,6 +67,7 @@ use crate::{
     space::SpaceId,
     sst::{file::FilePurger, manager::FileId},
     table::{
+        change_retention::ChangeRetention,
         metrics::{Metrics, MetricsContext},
         sst_util,
         version::{MemTableForWrite, MemTableState, SamplingMemTable, TableVersion},
@@ -152,6 +156,8 @@ pub struct TableConfig {
     pub manifest_snapshot_every_n_updates: NonZeroUsize,
     pub metrics_opt: MetricsOptions,
     pub enable_primary_key_sampling: bool,
+    /// How long the replaced ssts are retained, see [TableVersion::new].
+    pub version_retention: ReadableDuration,
 }
 
 #[derive(Debug, Clone)]
@@ -237,6 +243,9 @@ pub struct TableData {
     /// Whether enable layered memtable
     pub enable_layered_memtable: bool,
 
+    /// Retention of the wal entries for the consumers of the table changes
+    pub change_retention: ChangeRetention,
+
     /// Metrics of this table
     pub metrics: Metrics,
 
@@ -316,6 +325,7 @@ impl TableData {
             manifest_snapshot_every_n_updates,
             metrics_opt,
             enable_primary_key_sampling,
+            version_retention,
         } = config;
 
         let memtable_factory: MemTableFactoryRef = match opts.memtable_type {
@@ -338,9 +348,16 @@ impl TableData {
             memtable_factory
         };
 
+        cache_priority::set_dir_priority(
+            &sst_util::new_table_dir_path(space_id, id),
+            opts.cache_priority,
+        );
         let purge_queue = purger.create_purge_queue(space_id, id);
-        let current_version =
-            TableVersion::new(mem_size_options.size_sampling_interval, purge_queue);
+        let current_version = TableVersion::new(
+            mem_size_options.size_sampling_interval,
+            version_retention,
+            purge_queue,
+        );
         let metrics_ctx = MetricsContext::new(&name, shard_id, metrics_opt);
         let metrics = Metrics::new(metrics_ctx);
         let mutable_limit = AtomicU32::new(compute_mutable_limit(
@@ -376,6 +393,7 @@ impl TableData {
             manifest_snapshot_every_n_updates,
             enable_primary_key_sampling,
             enable_layered_memtable,
+            change_retention: ChangeRetention::default(),
         })
     }
 
@@ -396,6 +414,7 @@ impl TableData {
             manifest_snapshot_every_n_updates,
             metrics_opt,
             enable_primary_key_sampling,
+            version_retention,
         } = config;
 
         let memtable_factory: MemTableFactoryRef = match add_meta.opts.memtable_type {
@@ -418,9 +437,17 @@ impl TableData {
             memtable_factory as _
         };
 
+        cache_priority::set_dir_priority(
+            &sst_util::new_table_dir_path(add_meta.space_id, add_meta.table_id),
+            add_meta.opts.cache_priority,
+        );
         let purge_queue = purger.create_purge_queue(add_meta.space_id, add_meta.table_id);
-        let current_version =
-            TableVersion::new(mem_size_options.size_sampling_interval, purge_queue);
+        purge_queue.recover_deferred();
+        let current_version = TableVersion::new(
+            mem_size_options.size_sampling_interval,
+            version_retention,
+            purge_queue,
+        );
         let metrics_ctx = MetricsContext::new(&add_meta.table_name, shard_id, metrics_opt);
         let metrics = Metrics::new(metrics_ctx);
         let mutable_limit = AtomicU32::new(compute_mutable_limit(
@@ -452,9 +479,17 @@ impl TableData {
             manifest_snapshot_every_n_updates,
             enable_primary_key_sampling,
             enable_layered_memtable,
+            change_retention: ChangeRetention::default(),
         })
     }
 
+    /// Get the histograms of the columns, which are persisted with the
+    /// options.
+    #[inline]
+    pub fn histograms(&self) -> Arc<ColumnHistograms> {
+        self.table_options().histograms.clone()
+    }
+
     /// Get current schema of the table.
     pub fn schema(&self) -> Schema {
         self.schema.lock().unwrap().clone()
@@ -510,6 +545,15 @@ impl TableData {
         self.opts.load().clone()
     }
 
+    /// Row number of the row groups in the ssts to build, which is adapted to
+    /// the queries if required by the table options.
+    #[inline]
+    pub fn num_rows_per_row_group_to_build(&self) -> usize {
+        let scan_ratio = self.metrics.row_group_access_stats().scan_ratio();
+        self.table_options()
+            .adapted_num_rows_per_row_group(scan_ratio)
+    }
+
     /// Update table options.
     #[inline]
     pub fn set_table_options(&self, opts: TableOptions) {
@@ -518,6 +562,10 @@ impl TableData {
             self.mutable_limit_write_buffer_ratio,
         );
         self.mutable_limit.store(mutable_limit, Ordering::Relaxed);
+        cache_priority::set_dir_priority(
+            &sst_util::new_table_dir_path(self.space_id, self.id),
+            opts.cache_priority,
+        );
         self.opts.store(Arc::new(opts))
     }
 
@@ -529,6 +577,7 @@ impl TableData {
     /// Set the table is dropped and forbid any writes/alter on this table.
     #[inline]
     pub fn set_dropped(&self) {
+        cache_priority::remove_dir_priority(&sst_util::new_table_dir_path(self.space_id, self.id));
         self.status.store(TableStatus::Dropped, Ordering::SeqCst)
     }
 
@@ -551,6 +600,15 @@ impl TableData {
         self.current_version.total_memory_usage()
     }
 
+    /// Update the metrics of the memtables and the unflushed wal of the table.
+    pub fn update_memtable_metrics(&self) {
+        let unflushed_sequence = self
+            .last_sequence()
+            .saturating_sub(self.current_version.flushed_sequence());
+        self.metrics
+            .on_memtable_update(self.memtable_memory_usage(), unflushed_sequence);
+    }
+
     /// Returns mutable memtable memory usage in bytes.
     #[inline]
     pub fn mutable_memory_usage(&self) -> usize {
@@ -1001,6 +1059,7 @@ pub mod tests {
                     manifest_snapshot_every_n_updates: self.manifest_snapshot_every_n_updates,
                     metrics_opt: MetricsOptions::default(),
                     enable_primary_key_sampling: false,
+                    version_retention: ReadableDuration::hours(1),
                 },
                 &purger,
                 mem_size_options,
@@ -19,7 +19,7 @@
 
 use std::{
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicI64, AtomicU64, Ordering},
         Arc,
     },
     time::Duration,
@@ -31,11 +31,15 @@ use prometheus::{
     exponential_buckets,
     local::{LocalHistogram, LocalHistogramTimer},
     register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
-    Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
+    register_int_gauge_vec, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
 use table_engine::{partition::maybe_extract_partitioned_table_name, table::TableStats};
 
-use crate::{sst::metrics::MaybeTableLevelMetrics as SstMaybeTableLevelMetrics, MetricsOptions};
+use crate::{
+    sst::metrics::{MaybeTableLevelMetrics as SstMaybeTableLevelMetrics, RowGroupAccessStats},
+    MetricsOptions,
+};
 
 const KB: f64 = 1024.0;
 const DEFAULT_METRICS_KEY: &str = "total";
@@ -55,6 +59,21 @@ lazy_static! {
     )
     .unwrap();
 
+    // Gauges:
+    static ref TABLE_MEMTABLE_MEMORY_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "table_memtable_memory",
+        "Memory usage of the memtables of table",
+        &["shard_id", "table"]
+    )
+    .unwrap();
+
+    static ref TABLE_UNFLUSHED_SEQUENCE_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "table_unflushed_sequence",
+        "Number of the wal sequences not flushed yet of table",
+        &["shard_id", "table"]
+    )
+    .unwrap();
+
     static ref TABLE_WRITE_FIELDS_COUNTER: IntCounter = register_int_counter!(
         "table_write_fields_counter",
         "Fields counter of table write"
@@ -66,6 +85,13 @@ lazy_static! {
         "Read request counter of table"
     )
     .unwrap();
+
+    static ref TABLE_REQUEST_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "table_request_counter",
+        "Request counter of table by the request type",
+        &["shard_id", "table", "type"]
+    )
+    .unwrap();
     // End of counters.
 
     // Histograms:
@@ -135,6 +161,14 @@ lazy_static! {
         exponential_buckets(0.01, 2.0, 13).unwrap()
     ).unwrap();
 
+    // Buckets: 0, 0.001, .., 0.001 * 2^15
+    static ref TABLE_REQUEST_DURATION_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "table_request_duration",
+        "Histogram for request duration of table by the request type in seconds",
+        &["shard_id", "table", "type"],
+        exponential_buckets(0.001, 2.0, 16).unwrap()
+    ).unwrap();
+
     static ref QUERY_TIME_RANGE: HistogramVec = register_histogram_vec!(
         "query_time_range",
         "Histogram for query time range((15m,30m,...,7d)",
@@ -182,6 +216,8 @@ pub struct Metrics {
     shard_id_label: String,
     /// Stats of a single table.
     stats: Arc<AtomicTableStats>,
+    /// Row groups read by the queries of the table.
+    row_group_access_stats: Arc<RowGroupAccessStats>,
 
     compaction_input_sst_size_histogram: Histogram,
     compaction_output_sst_size_histogram: Histogram,
@@ -201,6 +237,19 @@ pub struct Metrics {
     table_write_queue_writer_duration: Histogram,
     table_write_total_duration: Histogram,
     table_write_bytes_counter: IntCounter,
+
+    write_request_counter: IntCounter,
+    read_request_counter: IntCounter,
+    write_request_duration: Histogram,
+    /// Duration of the read request until all its streams are dropped.
+    read_request_duration: Histogram,
+
+    /// The gauges may be shared by tables if the table level metrics is
+    /// disabled, so they are updated by the delta to the values of this table.
+    memtable_memory: AtomicI64,
+    unflushed_sequence: AtomicI64,
+    memtable_memory_gauge: IntGauge,
+    unflushed_sequence_gauge: IntGauge,
 }
 
 pub struct MaybeTableLevelMetrics {
@@ -214,10 +263,15 @@ pub struct MaybeTableLevelMetrics {
 }
 
 impl MaybeTableLevelMetrics {
-    pub fn new(maybe_table_name: &str, shard_id_label: &str) -> Self {
+    pub fn new(
+        maybe_table_name: &str,
+        shard_id_label: &str,
+        row_group_access_stats: Arc<RowGroupAccessStats>,
+    ) -> Self {
         let sst_metrics = Arc::new(SstMaybeTableLevelMetrics::new(
             maybe_table_name,
             shard_id_label,
+            row_group_access_stats,
         ));
 
         Self {
@@ -274,6 +328,7 @@ impl Metrics {
             maybe_table_name,
             shard_id_label,
             stats: Arc::new(AtomicTableStats::default()),
+            row_group_access_stats: Arc::new(RowGroupAccessStats::default()),
             compaction_input_sst_size_histogram: TABLE_COMPACTION_SST_SIZE_HISTOGRAM
                 .with_label_values(&["input"]),
             compaction_output_sst_size_histogram: TABLE_COMPACTION_SST_SIZE_HISTOGRAM
@@ -307,6 +362,20 @@ impl Metrics {
             table_write_total_duration: TABLE_WRITE_DURATION_HISTOGRAM
                 .with_label_values(&["total"]),
             table_write_bytes_counter,
+            write_request_counter: TABLE_REQUEST_COUNTER
+                .with_label_values(&[&shard_id_label, &maybe_table_name, "write"]),
+            read_request_counter: TABLE_REQUEST_COUNTER
+                .with_label_values(&[&shard_id_label, &maybe_table_name, "read"]),
+            write_request_duration: TABLE_REQUEST_DURATION_HISTOGRAM
+                .with_label_values(&[&shard_id_label, &maybe_table_name, "write"]),
+            read_request_duration: TABLE_REQUEST_DURATION_HISTOGRAM
+                .with_label_values(&[&shard_id_label, &maybe_table_name, "read"]),
+            memtable_memory: AtomicI64::new(0),
+            unflushed_sequence: AtomicI64::new(0),
+            memtable_memory_gauge: TABLE_MEMTABLE_MEMORY_GAUGE
+                .with_label_values(&[&shard_id_label, &maybe_table_name]),
+            unflushed_sequence_gauge: TABLE_UNFLUSHED_SEQUENCE_GAUGE
+                .with_label_values(&[&shard_id_label, &maybe_table_name]),
         }
     }
 
@@ -316,9 +385,15 @@ impl Metrics {
         Arc::new(MaybeTableLevelMetrics::new(
             &self.maybe_table_name,
             &self.shard_id_label,
+            self.row_group_access_stats.clone(),
         ))
     }
 
+    #[inline]
+    pub fn row_group_access_stats(&self) -> &RowGroupAccessStats {
+        &self.row_group_access_stats
+    }
+
     #[inline]
     pub fn table_stats(&self) -> TableStats {
         TableStats::from(&*self.stats)
@@ -328,6 +403,7 @@ impl Metrics {
     pub fn on_write_request_begin(&self) {
         self.stats.num_write.fetch_add(1, Ordering::Relaxed);
         TABLE_WRITE_REQUEST_COUNTER.inc();
+        self.write_request_counter.inc();
     }
 
     #[inline]
@@ -337,10 +413,36 @@ impl Metrics {
         self.table_write_bytes_counter.inc_by(num_bytes as u64);
     }
 
+    /// Update the memory usage of the memtables and the number of the unflushed
+    /// sequences of the table.
+    pub fn on_memtable_update(&self, memory_usage: usize, unflushed_sequence: u64) {
+        let prev = self
+            .memtable_memory
+            .swap(memory_usage as i64, Ordering::Relaxed);
+        self.memtable_memory_gauge.add(memory_usage as i64 - prev);
+
+        let prev = self
+            .unflushed_sequence
+            .swap(unflushed_sequence as i64, Ordering::Relaxed);
+        self.unflushed_sequence_gauge
+            .add(unflushed_sequence as i64 - prev);
+    }
+
     #[inline]
     pub fn on_read_request_begin(&self) {
         self.stats.num_read.fetch_add(1, Ordering::Relaxed);
         TABLE_READ_REQUEST_COUNTER.inc();
+        self.read_request_counter.inc();
+    }
+
+    #[inline]
+    pub fn start_write_request_timer(&self) -> HistogramTimer {
+        self.write_request_duration.start_timer()
+    }
+
+    #[inline]
+    pub fn start_read_request_timer(&self) -> HistogramTimer {
+        self.read_request_duration.start_timer()
     }
 
     #[inline]
@@ -456,6 +558,13 @@ impl Metrics {
     }
 }
 
+impl Drop for Metrics {
+    fn drop(&mut self) {
+        // Remove the values of this table from the gauges.
+        self.on_memtable_update(0, 0);
+    }
+}
+
 pub struct LocalFlushMetrics {
     stats: Arc<AtomicTableStats>,
 
@@ -28,21 +28,26 @@ use common_types::{
     row::{Row, RowGroup},
     schema::Schema,
     time::TimeRange,
+    SequenceNumber,
 };
 use datafusion::{common::Column, logical_expr::Expr};
 use future_ext::CancellationSafeFuture;
 use futures::TryStreamExt;
 use generic_error::BoxError;
 use logger::{error, warn};
+use rand::seq::SliceRandom;
 use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::{
+    access_stats,
+    histogram::{self, ColumnHistograms, Histogram},
     partition::PartitionInfo,
     predicate::PredicateBuilder,
     stream::{PartitionedStreams, SendableRecordBatchStream},
     table::{
         AlterOptions, AlterSchema, AlterSchemaRequest, Compact, Flush, FlushRequest, Get,
-        GetInvalidPrimaryKey, GetNullPrimaryKey, GetRequest, MergeWrite, ReadOptions, ReadRequest,
-        Result, Scan, Table, TableId, TableStats, TooManyPendingWrites, WaitForPendingWrites,
+        GetInvalidPrimaryKey, GetNullPrimaryKey, GetRequest, Import, ImportRequest, MergeWrite,
+        ReadChanges, ReadChangesRequest, ReadOptions, ReadRequest, ReadStatistics, Result, Scan,
+        Table, TableChanges, TableId, TableStats, TooManyPendingWrites, WaitForPendingWrites,
         Write, WriteRequest,
     },
     ANALYTIC_ENGINE_TYPE,
@@ -52,10 +57,15 @@ use trace_metric::MetricsCollector;
 
 use self::data::TableDataRef;
 use crate::{
-    instance::{alter::Alterer, write::Writer, InstanceRef},
+    instance::{
+        alter::{self, Alterer},
+        write::Writer,
+        InstanceRef,
+    },
     space::{SpaceAndTable, SpaceRef},
 };
 
+pub mod change_retention;
 pub mod data;
 pub mod metrics;
 pub mod sst_util;
@@ -140,7 +150,7 @@ struct PendingWriteQueue {
 #[derive(Default)]
 struct PendingWrites {
     writes: Vec<WriteRequest>,
-    notifiers: Vec<Sender<Result<()>>>,
+    notifiers: Vec<Sender<Result<SequenceNumber>>>,
     num_rows: usize,
 }
 
@@ -206,7 +216,7 @@ enum QueueResult {
     First,
     /// This request is pushed into the queue and the caller should wait for the
     /// finish notification.
-    Waiter(Receiver<Result<()>>),
+    Waiter(Receiver<Result<SequenceNumber>>),
 }
 
 impl PendingWriteQueue {
@@ -279,7 +289,10 @@ impl TableImpl {
     /// writing all the writes in the queue.
     ///
     /// NOTE: The write request will be rejected if the queue is full.
-    async fn write_with_pending_queue(&self, request: WriteRequest) -> Result<usize> {
+    async fn write_with_pending_queue(
+        &self,
+        request: WriteRequest,
+    ) -> Result<(usize, SequenceNumber)> {
         let num_rows = request.row_group.num_rows();
 
         // Failed to acquire the serial_exec, put the request into the
@@ -313,7 +326,7 @@ impl TableImpl {
                 )
                 .await
                 {
-                    Ok(_) => Ok(num_rows),
+                    Ok(seq) => Ok((num_rows, seq)),
                     Err(e) => Err(e),
                 }
             }
@@ -337,8 +350,8 @@ impl TableImpl {
                 .await
                 {
                     Ok(res) => {
-                        res.box_err().context(Write { table: self.name() })?;
-                        Ok(num_rows)
+                        let seq = res.box_err().context(Write { table: self.name() })?;
+                        Ok((num_rows, seq))
                     }
                     Err(_) => WaitForPendingWrites { table: self.name() }.fail(),
                 }
@@ -355,7 +368,9 @@ impl TableImpl {
         }
     }
 
-    async fn write_requests(write_requests: WriteRequests) -> Result<()> {
+    /// Write the pending requests, returns the sequence the merged write is
+    /// committed at, which is shared by all the pending requests.
+    async fn write_requests(write_requests: WriteRequests) -> Result<SequenceNumber> {
         let mut serial_exec = write_requests.table_data.serial_exec.lock().await;
         // The `serial_exec` is acquired, let's merge the pending requests and write
         // them all.
@@ -387,14 +402,14 @@ impl TableImpl {
         // There is no waiter for pending writes, return the write result.
         let notifiers = pending_writes.notifiers;
         if notifiers.is_empty() {
-            return Ok(());
+            return write_res.map(|(_, seq)| seq);
         }
 
         // Notify the waiters for the pending writes.
         match write_res {
-            Ok(_) => {
+            Ok((_, seq)) => {
                 for notifier in notifiers {
-                    if notifier.send(Ok(())).is_err() {
+                    if notifier.send(Ok(seq)).is_err() {
                         warn!(
                             "Failed to notify the ok result of pending writes, table:{}",
                             write_requests.table_data.name
@@ -467,6 +482,162 @@ impl Table for TableImpl {
         self.table_data.metrics.table_stats()
     }
 
+    fn compaction_debt(&self) -> u64 {
+        self.table_data.current_version().compaction_debt()
+    }
+
+    fn read_statistics(&self, request: &ReadRequest) -> Option<ReadStatistics> {
+        // Rows with the same primary key are only merged during reading for the
+        // overwrite mode, so the counters can't be trusted.
+        if self.table_data.table_options().need_dedup() {
+            return None;
+        }
+
+        let schema = self.table_data.schema();
+        if !request
+            .predicate
+            .is_time_range_only(schema.timestamp_name())
+        {
+            return None;
+        }
+
+        let query_range = request.predicate.time_range();
+        let read_view = self
+            .table_data
+            .current_version()
+            .pick_read_view(query_range);
+
+        let mut num_rows = 0;
+        let mut time_range: Option<TimeRange> = None;
+        let mut accumulate = |rows: usize, range: TimeRange| -> bool {
+            // A partially covered sst or memtable can't tell how many of its rows
+            // are inside the query range.
+            if range.inclusive_start() < query_range.inclusive_start()
+                || range.exclusive_end() > query_range.exclusive_end()
+            {
+                return false;
+            }
+
+            num_rows += rows;
+            time_range = Some(match time_range {
+                Some(v) => v.merge_range(range),
+                None => range,
+            });
+            true
+        };
+
+        let memtables = read_view
+            .sampling_mem
+            .iter()
+            .map(|v| &v.mem)
+            .chain(read_view.memtables.iter().map(|v| &v.mem));
+        for mem in memtables {
+            let row_count = mem.metrics().row_count;
+            match mem.time_range() {
+                // Some memtables don't maintain the row count.
+                Some(range) if row_count > 0 => {
+                    if !accumulate(row_count, range) {
+                        return None;
+                    }
+                }
+                Some(_) => return None,
+                None if row_count == 0 => (),
+                None => return None,
+            }
+        }
+
+        for file in read_view.leveled_ssts.iter().flatten() {
+            if !accumulate(file.row_num() as usize, file.time_range()) {
+                return None;
+            }
+        }
+
+        Some(ReadStatistics {
+            num_rows,
+            time_range,
+        })
+    }
+
+    fn estimate_rows(&self, request: &ReadRequest) -> Option<usize> {
+        let histograms = self.table_data.histograms();
+        if histograms.is_empty() {
+            return None;
+        }
+
+        // The rows of the ssts and memtables partially covered by the query range are
+        // all counted, so the estimation tends to be larger.
+        let read_view = self
+            .table_data
+            .current_version()
+            .pick_read_view(request.predicate.time_range());
+        let memtable_rows: usize = read_view
+            .sampling_mem
+            .iter()
+            .map(|v| &v.mem)
+            .chain(read_view.memtables.iter().map(|v| &v.mem))
+            .map(|mem| mem.metrics().row_count)
+            .sum();
+        let sst_rows: usize = read_view
+            .leveled_ssts
+            .iter()
+            .flatten()
+            .map(|file| file.row_num() as usize)
+            .sum();
+        let selectivity = histogram::estimate_selectivity(&histograms, request.predicate.exprs());
+
+        Some(((memtable_rows + sst_rows) as f64 * selectivity).round() as usize)
+    }
+
+    fn histograms(&self) -> Arc<ColumnHistograms> {
+        self.table_data.histograms()
+    }
+
+    fn sample_time_ranges(&self, max_rows: usize) -> Option<Vec<TimeRange>> {
+        let read_view = self
+            .table_data
+            .current_version()
+            .pick_read_view(TimeRange::min_to_max());
+        let mut ssts: Vec<_> = read_view.leveled_ssts.iter().flatten().collect();
+        let sst_rows: usize = ssts.iter().map(|file| file.row_num() as usize).sum();
+        if sst_rows <= max_rows {
+            return None;
+        }
+
+        // The rows of other ssts overlapping the picked ones are read too, so more
+        // rows than `max_rows` may be read.
+        ssts.shuffle(&mut rand::thread_rng());
+        let mut num_rows = 0;
+        let mut time_ranges = Vec::new();
+        for file in ssts {
+            if num_rows >= max_rows {
+                break;
+            }
+            num_rows += file.row_num() as usize;
+            time_ranges.push(file.time_range());
+        }
+
+        Some(time_ranges)
+    }
+
+    async fn update_histograms(
+        &self,
+        histograms: HashMap<String, Option<Histogram>>,
+    ) -> Result<()> {
+        let mut serial_exec = self.table_data.serial_exec.lock().await;
+        let alterer = Alterer::new(
+            self.table_data.clone(),
+            &mut serial_exec,
+            self.instance.clone(),
+        )
+        .await;
+
+        alterer
+            .alter_histograms_of_table(histograms)
+            .await
+            .box_err()
+            .context(AlterOptions { table: self.name() })
+    }
+
     fn support_pushdown(&self, read_schema: &Schema, col_names: &[String]) -> bool {
         let need_dedup = self.table_data.table_options().need_dedup();
 
@@ -474,10 +645,22 @@ impl Table for TableImpl {
     }
 
     async fn write(&self, request: WriteRequest) -> Result<usize> {
+        self.write_with_sequence(request)
+            .await
+            .map(|(num_rows, _)| num_rows)
+    }
+
+    async fn write_with_sequence(
+        &self,
+        request: WriteRequest,
+    ) -> Result<(usize, Option<SequenceNumber>)> {
         let _timer = self.table_data.metrics.start_table_total_timer();
+        let _request_timer = self.table_data.metrics.start_write_request_timer();
+        access_stats::record_write(self.table_data.id);
 
         if self.should_queue_write_request(&request) {
-            return self.write_with_pending_queue(request).await;
+            let (num_rows, seq) = self.write_with_pending_queue(request).await?;
+            return Ok((num_rows, Some(seq)));
         }
 
         let mut serial_exec = self.table_data.serial_exec.lock().await;
@@ -487,11 +670,12 @@ impl Table for TableImpl {
             self.table_data.clone(),
             &mut serial_exec,
         );
-        writer
+        let (num_rows, seq) = writer
             .write(request)
             .await
             .box_err()
-            .context(Write { table: self.name() })
+            .context(Write { table: self.name() })?;
+        Ok((num_rows, Some(seq)))
     }
 
     async fn read(&self, mut request: ReadRequest) -> Result<SendableRecordBatchStream> {
@@ -632,6 +816,23 @@ impl Table for TableImpl {
         Ok(0)
     }
 
+    fn validate_alter_schema(&self, request: &AlterSchemaRequest) -> Result<()> {
+        alter::validate_alter_schema(&self.table_data, request)
+            .box_err()
+            .context(AlterSchema { table: self.name() })?;
+        Ok(())
+    }
+
+    fn validate_alter_options(
+        &self,
+        options: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let table_opts = alter::build_altered_options(&self.table_data, options)
+            .box_err()
+            .context(AlterOptions { table: self.name() })?;
+        Ok(table_opts.to_raw_map())
+    }
+
     async fn flush(&self, request: FlushRequest) -> Result<()> {
         self.instance
             .manual_flush_table(&self.table_data, request)
@@ -648,6 +849,22 @@ impl Table for TableImpl {
             .context(Compact { table: self.name() })?;
         Ok(())
     }
+
+    async fn import(&self, request: ImportRequest) -> Result<usize> {
+        self.instance
+            .import_to_table(&self.table_data, request.batches)
+            .await
+            .box_err()
+            .context(Import { table: self.name() })
+    }
+
+    async fn read_changes(&self, request: ReadChangesRequest) -> Result<TableChanges> {
+        self.instance
+            .read_table_changes(&self.table_data, request)
+            .await
+            .box_err()
+            .context(ReadChanges { table: self.name() })
+    }
 }
 
 #[cfg(test)]
@@ -26,6 +26,8 @@ use crate::{space::SpaceId, sst::manager::FileId};
 
 const SST_FILE_SUFFIX: &str = "sst";
 const SST_CUSTOM_METADATA_FILE_SUFFIX: &str = "metadata";
+const SST_SEPARATED_FIELDS_FILE_SUFFIX: &str = "fields";
+const DEFERRED_PURGES_FILE_NAME: &str = "deferred_purges.json";
 
 #[inline]
 /// Generate the sst file name.
@@ -41,7 +43,33 @@ pub fn new_sst_file_path(space_id: SpaceId, table_id: TableId, file_id: FileId)
     ])
 }
 
+/// Generate the path of the directory holding all the ssts of the table.
+pub fn new_table_dir_path(space_id: SpaceId, table_id: TableId) -> String {
+    Path::from_iter([space_id.to_string(), table_id.to_string()]).to_string()
+}
+
+/// Generate the path of the file recording the ssts of the table whose purges
+/// are deferred.
+pub fn new_deferred_purges_path(space_id: SpaceId, table_id: TableId) -> Path {
+    Path::from_iter([
+        space_id.to_string(),
+        table_id.to_string(),
+        DEFERRED_PURGES_FILE_NAME.to_string(),
+    ])
+}
+
+/// Whether the path is the path of a sst file.
+pub fn is_sst_file_path(path: &str) -> bool {
+    path.strip_suffix(SST_FILE_SUFFIX)
+        .map_or(false, |prefix| prefix.ends_with('.'))
+}
+
 /// Convert sst_file_path into custom metadata path
 pub fn new_metadata_path(sst_file_path: &str) -> String {
     format!("{sst_file_path}.{SST_CUSTOM_METADATA_FILE_SUFFIX}")
 }
+
+/// Convert sst_file_path into the path of the file storing its separated fields
+pub fn new_fields_path(sst_file_path: &str) -> String {
+    format!("{sst_file_path}.{SST_SEPARATED_FIELDS_FILE_SUFFIX}")
+}
@@ -19,10 +19,13 @@
 
 use std::{
     cmp,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, VecDeque},
     fmt,
     ops::Bound,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
     time::Duration,
 };
 
@@ -40,12 +43,12 @@ use time_ext::ReadableDuration;
 use crate::{
     compaction::{
         picker::{self, CompactionPickerRef, PickerContext},
-        CompactionTask, ExpiredFiles,
+        CompactionInputFiles, CompactionTask, CompactionTaskBuilder, ExpiredFiles,
     },
     memtable::{self, key::KeySequence, MemTableRef, PutContext},
     sampler::{DefaultSampler, PrimaryKeySampler, SamplerRef, MAX_SUGGEST_PRIMARY_KEY_NUM},
     sst::{
-        file::{FileHandle, FilePurgeQueue, SST_LEVEL_NUM},
+        file::{FileHandle, FilePurgeQueue, Level, SST_LEVEL_NUM},
         manager::{FileId, LevelsController},
     },
     table::{
@@ -411,15 +414,23 @@ impl MemTableView {
 
     /// Remove memtable from immutables or sampling memtable.
     #[inline]
-    fn remove_immutable_or_sampling(&mut self, id: MemTableId) {
+    fn remove_immutable_or_sampling(&mut self, id: MemTableId) -> Option<MemTableState> {
         if let Some(v) = &self.sampling_mem {
             if v.id == id {
-                self.sampling_mem = None;
-                return;
+                let sampling_mem = self.sampling_mem.take().unwrap();
+                let aligned_time_range = sampling_mem
+                    .mem
+                    .time_range()
+                    .unwrap_or_else(TimeRange::min_to_max);
+                return Some(MemTableState {
+                    mem: sampling_mem.mem,
+                    aligned_time_range,
+                    id: sampling_mem.id,
+                });
             }
         }
 
-        self.immutables.0.remove(&id);
+        self.immutables.0.remove(&id)
     }
 
     /// Collect memtables intersect with `time_range`
@@ -433,7 +444,17 @@ impl MemTableView {
 
         self.immutables.memtables_for_read(time_range, mems);
 
-        *sampling_mem = self.sampling_mem.clone();
+        // The sampling memtable is skipped only if its rows are known to be out of
+        // the `time_range`.
+        *sampling_mem = self
+            .sampling_mem
+            .as_ref()
+            .filter(|v| {
+                v.mem.time_range().map_or(true, |mem_time_range| {
+                    mem_time_range.intersect_with(time_range)
+                })
+            })
+            .cloned();
     }
 }
 
@@ -577,6 +598,83 @@ impl ReadView {
     }
 }
 
+/// Max memory of the flushed memtables retained in the history of a table.
+const MAX_RETAINED_MEMTABLE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Sst changes of an edit applied to the version.
+struct AppliedEdit {
+    applied_at: Timestamp,
+    added: Vec<FileId>,
+    /// Handles of the deleted ssts, holding them prevents the files from being
+    /// purged while the edit is still in the history.
+    deleted: Vec<(Level, FileHandle)>,
+    /// Memtables removed by the edit, whose rows are read before the edit
+    /// instead of the flushed ssts.
+    removed_mems: Vec<MemTableState>,
+}
+
+impl AppliedEdit {
+    fn memtable_size(&self) -> usize {
+        self.removed_mems
+            .iter()
+            .map(|m| m.mem.approximate_memory_usage())
+            .sum()
+    }
+}
+
+/// Sequences of the writes at the granularity of second, to find the sequence
+/// visible as of an earlier time.
+struct SequenceHistory {
+    /// The start of a second and the max sequence written before it, ordered
+    /// by the second.
+    checkpoints: VecDeque<(i64, SequenceNumber)>,
+    version_retention: ReadableDuration,
+}
+
+impl SequenceHistory {
+    fn new(version_retention: ReadableDuration) -> Self {
+        Self {
+            checkpoints: VecDeque::new(),
+            version_retention,
+        }
+    }
+
+    fn record(&mut self, now: Timestamp, sequence: SequenceNumber) {
+        let second = now.truncate_by(Duration::from_secs(1)).as_i64();
+        if self.checkpoints.back().map_or(false, |(last, _)| *last >= second) {
+            return;
+        }
+
+        // The sequences are increasing, so the writes before this one have
+        // smaller sequences.
+        self.checkpoints
+            .push_back((second, sequence.saturating_sub(1)));
+        self.expire(now);
+    }
+
+    /// The max sequence visible as of `as_of`, the writes in the same second
+    /// after `as_of` may also be visible. Returns None if there is no write
+    /// after `as_of`.
+    fn sequence_as_of(&self, as_of: Timestamp) -> Option<SequenceNumber> {
+        let idx = self
+            .checkpoints
+            .partition_point(|(second, _)| *second <= as_of.as_i64());
+        self.checkpoints.get(idx).map(|(_, sequence)| *sequence)
+    }
+
+    fn expire(&mut self, now: Timestamp) {
+        // The checkpoints not after the expire time are never used as the reads
+        // are as of a time after it.
+        let expire_time = now.sub_duration_or_min(self.version_retention.0);
+        while let Some((second, _)) = self.checkpoints.front() {
+            if *second > expire_time.as_i64() {
+                break;
+            }
+            self.checkpoints.pop_front();
+        }
+    }
+}
+
 /// Data of TableVersion
 struct TableVersionInner {
     /// All memtables
@@ -593,6 +691,13 @@ struct TableVersionInner {
     /// than the max one. And this field is only a mem state for Manifest,
     /// it can only be updated during recover or by Manifest.
     max_file_id: FileId,
+
+    /// Edits applied in the retention, ordered by the applied time.
+    edit_history: VecDeque<AppliedEdit>,
+    /// How long the applied edits are kept in the `edit_history`.
+    version_retention: ReadableDuration,
+    /// The earliest time the ssts can be rebuilt from the `edit_history`.
+    history_start: Timestamp,
 }
 
 impl TableVersionInner {
@@ -610,6 +715,50 @@ impl TableVersionInner {
             .cloned()
             .map(MemTableForWrite::Normal)
     }
+
+    /// Bytes of the ssts at the level 0.
+    fn level0_bytes(&self) -> u64 {
+        self.levels_controller
+            .iter_ssts_at_level(Level::MIN)
+            .map(|file| file.size())
+            .sum()
+    }
+
+    fn record_edit(
+        &mut self,
+        added: Vec<FileId>,
+        deleted: Vec<(Level, FileHandle)>,
+        removed_mems: Vec<MemTableState>,
+    ) {
+        if added.is_empty() && deleted.is_empty() && removed_mems.is_empty() {
+            return;
+        }
+
+        let now = Timestamp::now();
+        self.edit_history.push_back(AppliedEdit {
+            applied_at: now,
+            added,
+            deleted,
+            removed_mems,
+        });
+        self.expire_history(now);
+    }
+
+    /// Evict the edits out of the retention, and the oldest edits if the
+    /// retained memtables take too much memory.
+    fn expire_history(&mut self, now: Timestamp) {
+        let expire_time = now.sub_duration_or_min(self.version_retention.0);
+        let mut memtable_size: usize = self.edit_history.iter().map(|e| e.memtable_size()).sum();
+        while let Some(edit) = self.edit_history.front() {
+            if edit.applied_at > expire_time && memtable_size <= MAX_RETAINED_MEMTABLE_SIZE {
+                break;
+            }
+            // The state before the evicted edit can't be rebuilt anymore.
+            memtable_size -= edit.memtable_size();
+            self.history_start = edit.applied_at;
+            self.edit_history.pop_front();
+        }
+    }
 }
 
 // TODO(yingwen): How to support snapshot?
@@ -621,20 +770,35 @@ impl TableVersionInner {
 /// should be done atomically.
 pub struct TableVersion {
     inner: RwLock<TableVersionInner>,
+    sequence_history: Mutex<SequenceHistory>,
+    /// Bytes of the ssts at the level 0, updated as the edits are applied.
+    compaction_debt: AtomicU64,
 
     cached_mem_size: SamplingCachedUsize,
 }
 
 impl TableVersion {
     /// Create an empty table version
-    pub fn new(mem_usage_sampling_interval: ReadableDuration, purge_queue: FilePurgeQueue) -> Self {
+    ///
+    /// The ssts replaced in `version_retention` are retained for reading the
+    /// table as of an earlier time.
+    pub fn new(
+        mem_usage_sampling_interval: ReadableDuration,
+        version_retention: ReadableDuration,
+        purge_queue: FilePurgeQueue,
+    ) -> Self {
         Self {
             inner: RwLock::new(TableVersionInner {
                 memtable_view: MemTableView::new(),
                 levels_controller: LevelsController::new(purge_queue),
                 flushed_sequence: 0,
                 max_file_id: 0,
+                edit_history: VecDeque::new(),
+                version_retention,
+                history_start: Timestamp::now(),
             }),
+            sequence_history: Mutex::new(SequenceHistory::new(version_retention)),
+            compaction_debt: AtomicU64::new(0),
 
             cached_mem_size: SamplingCachedUsize::new(mem_usage_sampling_interval.as_millis()),
         }
@@ -763,28 +927,60 @@ impl TableVersion {
         let mut inner = self.inner.write().unwrap();
 
         // TODO(yingwen): else, log warning
-        inner.flushed_sequence = cmp::max(inner.flushed_sequence, edit.flushed_sequence);
+        inner.flushed_sequence = cmp::max(inner.flushed_sequence, edit.covered_sequence());
 
         inner.max_file_id = cmp::max(inner.max_file_id, edit.max_file_id);
 
         // Add sst files to level first.
+        let mut added = Vec::with_capacity(edit.files_to_add.len());
         for add_file in edit.files_to_add {
+            added.push(add_file.file.id);
             inner
                 .levels_controller
                 .add_sst_to_level(add_file.level, add_file.file);
         }
 
         // Remove ssts from level.
+        let mut deleted = Vec::with_capacity(edit.files_to_delete.len());
         for delete_file in edit.files_to_delete {
-            inner
+            let files = inner
                 .levels_controller
                 .remove_ssts_from_level(delete_file.level, &[delete_file.file_id]);
+            deleted.extend(files.into_iter().map(|file| (delete_file.level, file)));
         }
 
         // Remove immutable memtables.
+        let mut removed_mems = Vec::with_capacity(edit.mems_to_remove.len());
         for mem_id in edit.mems_to_remove {
-            inner.memtable_view.remove_immutable_or_sampling(mem_id);
+            removed_mems.extend(inner.memtable_view.remove_immutable_or_sampling(mem_id));
         }
+
+        inner.record_edit(added, deleted, removed_mems);
+        self.compaction_debt
+            .store(inner.level0_bytes(), Ordering::Relaxed);
+    }
+
+    /// Record the sequence of a write, which is used to find the sequence
+    /// visible as of an earlier time.
+    pub fn record_sequence(&self, sequence: SequenceNumber) {
+        self.sequence_history
+            .lock()
+            .unwrap()
+            .record(Timestamp::now(), sequence);
+    }
+
+    /// Evict the history out of the retention, which is also done when the
+    /// edits are applied, but the tables may have no edit for a long time.
+    pub fn expire_history(&self) {
+        let now = Timestamp::now();
+        self.inner.write().unwrap().expire_history(now);
+        self.sequence_history.lock().unwrap().expire(now);
+    }
+
+    /// The max sequence of the memtables visible as of `as_of`, None means
+    /// all the written sequences are visible.
+    pub fn sequence_as_of(&self, as_of: Timestamp) -> Option<SequenceNumber> {
+        self.sequence_history.lock().unwrap().sequence_as_of(as_of)
     }
 
     /// Atomically apply the meta to the version, useful in recover.
@@ -800,6 +996,12 @@ impl TableVersion {
                 .levels_controller
                 .add_sst_to_level(add_file.level, add_file.file);
         }
+
+        // The ssts before recovery are unknown.
+        inner.edit_history.clear();
+        inner.history_start = Timestamp::now();
+        self.compaction_debt
+            .store(inner.level0_bytes(), Ordering::Relaxed);
     }
 
     pub fn pick_read_view(&self, time_range: TimeRange) -> ReadView {
@@ -830,6 +1032,75 @@ impl TableVersion {
         }
     }
 
+    /// Pick the memtables and ssts of the version as of `as_of` by undoing
+    /// the edits applied after it.
+    ///
+    /// The memtables flushed after `as_of` are picked instead of the flushed
+    /// ssts, and the rows written after `as_of` should be filtered by the
+    /// [TableVersion::sequence_as_of]. Returns None if `as_of` is earlier than
+    /// the retained history.
+    pub fn pick_read_view_as_of(
+        &self,
+        time_range: TimeRange,
+        as_of: Timestamp,
+    ) -> Option<ReadView> {
+        let mut files = HashMap::new();
+        let mut sampling_mem = None;
+        let mut memtables = MemTableVec::new();
+        {
+            let inner = self.inner.read().unwrap();
+            if as_of < inner.history_start {
+                return None;
+            }
+
+            inner
+                .memtable_view
+                .memtables_for_read(time_range, &mut memtables, &mut sampling_mem);
+
+            let controller = &inner.levels_controller;
+            for level in controller.levels() {
+                for file in controller.iter_ssts_at_level(level) {
+                    files.insert(file.id(), (level, file.clone()));
+                }
+            }
+
+            for edit in inner.edit_history.iter().rev() {
+                if edit.applied_at <= as_of {
+                    break;
+                }
+
+                for file_id in &edit.added {
+                    files.remove(file_id);
+                }
+                for (level, file) in &edit.deleted {
+                    files.insert(file.id(), (*level, file.clone()));
+                }
+                let removed_mems = edit
+                    .removed_mems
+                    .iter()
+                    .filter(|m| m.real_time_range().intersect_with(time_range));
+                memtables.extend(removed_mems.cloned());
+            }
+        }
+
+        let mut files: Vec<_> = files
+            .into_values()
+            .filter(|(_, file)| file.intersect_with_time_range(time_range))
+            .collect();
+        files.sort_unstable_by_key(|(_, file)| file.id());
+
+        let mut read_view = ReadView {
+            sampling_mem,
+            memtables,
+            ..Default::default()
+        };
+        for (level, file) in files {
+            read_view.leveled_ssts[level.as_usize()].push(file);
+        }
+
+        Some(read_view)
+    }
+
     /// Pick ssts for compaction using given `picker`.
     pub fn pick_for_compaction(
         &self,
@@ -841,6 +1112,113 @@ impl TableVersion {
         picker.pick_compaction(picker_ctx, &mut inner.levels_controller)
     }
 
+    /// Returns the number of all the ssts in the version.
+    pub fn num_ssts(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .map(|level| controller.iter_ssts_at_level(level).count())
+            .sum()
+    }
+
+    /// Returns the number of the ssts at the `level`.
+    pub fn num_ssts_at_level(&self, level: Level) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner.levels_controller.iter_ssts_at_level(level).count()
+    }
+
+    /// Returns the bytes of the ssts at the level 0, which are waiting to be
+    /// compacted.
+    pub fn compaction_debt(&self) -> u64 {
+        self.compaction_debt.load(Ordering::Relaxed)
+    }
+
+    /// Returns the max id of the ssts in the version.
+    pub fn max_sst_id(&self) -> Option<FileId> {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(move |level| controller.iter_ssts_at_level(level).map(|file| file.id()))
+            .max()
+    }
+
+    /// Returns all the ssts in the version.
+    pub fn all_ssts(&self) -> Vec<(Level, FileHandle)> {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(move |level| {
+                controller
+                    .iter_ssts_at_level(level)
+                    .map(move |file| (level, file.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the ssts not being compacted whose time range ends before
+    /// `end`.
+    pub fn ssts_ended_before(&self, end: Timestamp) -> Vec<(Level, FileHandle)> {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(move |level| {
+                controller
+                    .iter_ssts_at_level(level)
+                    .map(move |file| (level, file.clone()))
+            })
+            .filter(|(_, file)| file.time_range().exclusive_end() <= end && !file.being_compacted())
+            .collect()
+    }
+
+    /// Mark the `file` being compacted if it is still at the `level` of the
+    /// version and not being compacted, so the compaction won't pick it.
+    ///
+    /// Returns false if the `file` is not marked.
+    pub fn try_reserve_sst(&self, level: Level, file: &FileHandle) -> bool {
+        let inner = self.inner.write().unwrap();
+        let exists = inner
+            .levels_controller
+            .iter_ssts_at_level(level)
+            .any(|v| v.id() == file.id());
+        if !exists || file.being_compacted() {
+            return false;
+        }
+
+        file.set_being_compacted(true);
+        true
+    }
+
+    /// Pick the sst with the smallest id not greater than `max_file_id` to
+    /// rewrite it into the same level.
+    pub fn pick_for_rewrite(&self, max_file_id: FileId) -> CompactionTask {
+        let inner = self.inner.write().unwrap();
+        let controller = &inner.levels_controller;
+        let picked = controller
+            .levels()
+            .flat_map(move |level| {
+                controller
+                    .iter_ssts_at_level(level)
+                    .map(move |file| (level, file))
+            })
+            .filter(|(_, file)| file.id() <= max_file_id && !file.being_compacted())
+            .min_by_key(|(_, file)| file.id());
+
+        let mut builder = CompactionTaskBuilder::with_expired(Vec::new());
+        if let Some((level, file)) = picked {
+            builder.add_inputs(CompactionInputFiles {
+                level,
+                files: vec![file.clone()],
+                output_level: level,
+            });
+        }
+
+        builder.build()
+    }
+
     pub fn has_expired_sst(&self, expire_time: Option<Timestamp>) -> bool {
         let inner = self.inner.read().unwrap();
 
@@ -902,7 +1280,7 @@ pub struct TableVersionMeta {
 
 impl TableVersionMeta {
     pub fn apply_edit(&mut self, edit: VersionEdit) {
-        self.flushed_sequence = cmp::max(self.flushed_sequence, edit.flushed_sequence);
+        self.flushed_sequence = cmp::max(self.flushed_sequence, edit.covered_sequence());
 
         for add_file in edit.files_to_add {
             self.max_file_id = cmp::max(self.max_file_id, add_file.file.id);
@@ -939,7 +1317,10 @@ mod tests {
     use super::*;
     use crate::{
         sst::file::tests::FilePurgerMocker,
-        table::{data::tests::MemTableMocker, version_edit::tests::AddFileMocker},
+        table::{
+            data::tests::MemTableMocker,
+            version_edit::{tests::AddFileMocker, DeleteFile},
+        },
         table_options,
         tests::table,
     };
@@ -947,7 +1328,11 @@ mod tests {
     fn new_table_version() -> TableVersion {
         let purger = FilePurgerMocker::mock();
         let queue = purger.create_purge_queue(1, table::new_table_id(2, 2));
-        TableVersion::new(ReadableDuration::millis(0), queue)
+        TableVersion::new(
+            ReadableDuration::millis(0),
+            ReadableDuration::hours(1),
+            queue,
+        )
     }
 
     #[test]
@@ -991,6 +1376,57 @@ mod tests {
         assert!(version.switch_memtables().is_none());
     }
 
+    #[test]
+    fn test_compaction_debt() {
+        let version = new_table_version();
+        let add_file = |file_id, level, size| {
+            let mut add_file = AddFileMocker::new(file_id).build();
+            add_file.level = level;
+            add_file.file.size = size;
+            add_file
+        };
+        let edit = |files_to_add, files_to_delete| VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: vec![],
+            files_to_add,
+            files_to_delete,
+            max_file_id: 0,
+        };
+
+        // The flushed ssts are the debt.
+        version.apply_edit(edit(
+            vec![add_file(1, Level::MIN, 100), add_file(2, Level::MIN, 200)],
+            vec![],
+        ));
+        assert_eq!(300, version.compaction_debt());
+
+        // The debt is paid off once the ssts are compacted to the next level.
+        version.apply_edit(edit(
+            vec![add_file(3, Level::MAX, 250)],
+            vec![
+                DeleteFile {
+                    level: Level::MIN,
+                    file_id: 1,
+                },
+                DeleteFile {
+                    level: Level::MIN,
+                    file_id: 2,
+                },
+            ],
+        ));
+        assert_eq!(0, version.compaction_debt());
+
+        // The debt is recovered with the version.
+        let recovered = new_table_version();
+        let mut meta = TableVersionMeta::default();
+        meta.apply_edit(edit(
+            vec![add_file(4, Level::MIN, 400), add_file(5, Level::MAX, 500)],
+            vec![],
+        ));
+        recovered.apply_meta(meta);
+        assert_eq!(400, recovered.compaction_debt());
+    }
+
     fn check_flushable_mem_with_sampling(
         flushable_mems: &FlushableMemTables,
         memtable_id: MemTableId,
@@ -1234,4 +1670,221 @@ mod tests {
         assert_eq!(1, read_view.leveled_ssts[0].len());
         assert_eq!(file_id, read_view.leveled_ssts[0][0].id());
     }
+
+    #[test]
+    fn test_table_version_pick_read_view_as_of() {
+        let version = new_table_version();
+        let time_range = TimeRange::new_unchecked(Timestamp::new(0), Timestamp::new(100));
+        let wait = || std::thread::sleep(Duration::from_millis(2));
+
+        wait();
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 10,
+            mems_to_remove: vec![],
+            files_to_add: vec![AddFileMocker::new(1).time_range(time_range).build()],
+            files_to_delete: vec![],
+            max_file_id: 0,
+        });
+        wait();
+        let after_flush = Timestamp::now();
+        wait();
+
+        // Compact file 1 into file 2.
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 10,
+            mems_to_remove: vec![],
+            files_to_add: vec![AddFileMocker::new(2).time_range(time_range).build()],
+            files_to_delete: vec![DeleteFile {
+                level: Level::MIN,
+                file_id: 1,
+            }],
+            max_file_id: 0,
+        });
+        wait();
+        let after_compaction = Timestamp::now();
+
+        let file_ids = |read_view: ReadView| -> Vec<FileId> {
+            read_view
+                .leveled_ssts
+                .iter()
+                .flatten()
+                .map(|file| file.id())
+                .collect()
+        };
+        let read_view = version
+            .pick_read_view_as_of(time_range, after_flush)
+            .unwrap();
+        assert_eq!(vec![1], file_ids(read_view));
+        let read_view = version
+            .pick_read_view_as_of(time_range, after_compaction)
+            .unwrap();
+        assert_eq!(vec![2], file_ids(read_view));
+
+        // The history before the version is created is unknown.
+        assert!(version
+            .pick_read_view_as_of(time_range, Timestamp::new(0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_table_version_pick_memtables_as_of() {
+        let version = new_table_version();
+        let time_range = TimeRange::new_unchecked(Timestamp::new(0), Timestamp::new(100));
+        let memtable_id = 1;
+        version.insert_mutable(MemTableState {
+            mem: MemTableMocker.build(),
+            aligned_time_range: time_range,
+            id: memtable_id,
+        });
+        let before_flush = Timestamp::now();
+        std::thread::sleep(Duration::from_millis(2));
+
+        version.switch_memtables();
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 10,
+            mems_to_remove: vec![memtable_id],
+            files_to_add: vec![AddFileMocker::new(1).time_range(time_range).build()],
+            files_to_delete: vec![],
+            max_file_id: 0,
+        });
+
+        // The flushed memtable is read instead of the flushed sst.
+        let read_view = version
+            .pick_read_view_as_of(time_range, before_flush)
+            .unwrap();
+        assert_eq!(1, read_view.memtables.len());
+        assert_eq!(memtable_id, read_view.memtables[0].id);
+        assert!(read_view.leveled_ssts.iter().all(|ssts| ssts.is_empty()));
+
+        let read_view = version
+            .pick_read_view_as_of(time_range, Timestamp::now())
+            .unwrap();
+        assert!(read_view.memtables.is_empty());
+        assert_eq!(1, read_view.leveled_ssts[0].len());
+    }
+
+    #[test]
+    fn test_table_version_expire_history() {
+        let purger = FilePurgerMocker::mock();
+        let queue = purger.create_purge_queue(1, table::new_table_id(2, 2));
+        let version = TableVersion::new(
+            ReadableDuration::millis(0),
+            ReadableDuration::millis(10),
+            queue,
+        );
+        let time_range = TimeRange::new_unchecked(Timestamp::new(0), Timestamp::new(100));
+
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 10,
+            mems_to_remove: vec![],
+            files_to_add: vec![AddFileMocker::new(1).time_range(time_range).build()],
+            files_to_delete: vec![],
+            max_file_id: 0,
+        });
+        let after_flush = Timestamp::now();
+        std::thread::sleep(Duration::from_millis(2));
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 10,
+            mems_to_remove: vec![],
+            files_to_add: vec![AddFileMocker::new(2).time_range(time_range).build()],
+            files_to_delete: vec![DeleteFile {
+                level: Level::MIN,
+                file_id: 1,
+            }],
+            max_file_id: 0,
+        });
+        assert!(version
+            .pick_read_view_as_of(time_range, after_flush)
+            .is_some());
+
+        // The history is evicted without applying any edit.
+        std::thread::sleep(Duration::from_millis(20));
+        version.expire_history();
+        assert!(version.inner.read().unwrap().edit_history.is_empty());
+        assert!(version
+            .pick_read_view_as_of(time_range, after_flush)
+            .is_none());
+    }
+
+    #[test]
+    fn test_sequence_history() {
+        let mut history = SequenceHistory::new(ReadableDuration::secs(10));
+        history.record(Timestamp::new(1_500), 1);
+        history.record(Timestamp::new(1_800), 2);
+        history.record(Timestamp::new(3_200), 3);
+
+        assert_eq!(Some(0), history.sequence_as_of(Timestamp::new(900)));
+        // The writes in the same second are visible.
+        assert_eq!(Some(2), history.sequence_as_of(Timestamp::new(1_600)));
+        assert_eq!(None, history.sequence_as_of(Timestamp::new(3_500)));
+
+        // The checkpoints out of the retention are evicted.
+        history.record(Timestamp::new(12_500), 4);
+        assert_eq!(2, history.checkpoints.len());
+        assert_eq!(Some(2), history.sequence_as_of(Timestamp::new(2_600)));
+        assert_eq!(Some(3), history.sequence_as_of(Timestamp::new(3_500)));
+    }
+
+    #[test]
+    fn test_table_version_flushed_sequence_covers_ssts() {
+        let version = new_table_version();
+        let time_range = TimeRange::new_unchecked(Timestamp::new(0), Timestamp::new(100));
+        let edit = |flushed_sequence, file_id, max_seq| VersionEdit {
+            flushed_sequence,
+            mems_to_remove: vec![],
+            files_to_add: vec![AddFileMocker::new(file_id)
+                .time_range(time_range)
+                .max_seq(max_seq)
+                .build()],
+            files_to_delete: vec![],
+            max_file_id: 0,
+        };
+
+        version.apply_edit(edit(10, 1, 12));
+        assert_eq!(12, version.flushed_sequence());
+        // The ssts not dumped from the memtables cover no log entries.
+        version.apply_edit(edit(0, 2, 20));
+        assert_eq!(12, version.flushed_sequence());
+        // The flushed sequence never goes back.
+        version.apply_edit(edit(11, 3, 11));
+        assert_eq!(12, version.flushed_sequence());
+
+        // So does the version recovered from the meta.
+        let mut meta = TableVersionMeta::default();
+        meta.apply_edit(edit(0, 4, 30));
+        meta.apply_edit(edit(5, 5, 8));
+        let version = new_table_version();
+        version.apply_meta(meta);
+        assert_eq!(8, version.flushed_sequence());
+    }
+
+    #[test]
+    fn test_table_version_without_retention() {
+        let purger = FilePurgerMocker::mock();
+        let queue = purger.create_purge_queue(1, table::new_table_id(2, 2));
+        let version = TableVersion::new(
+            ReadableDuration::millis(0),
+            ReadableDuration(Duration::ZERO),
+            queue,
+        );
+        let time_range = TimeRange::new_unchecked(Timestamp::new(0), Timestamp::new(100));
+
+        let before_flush = Timestamp::now();
+        std::thread::sleep(Duration::from_millis(2));
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 10,
+            mems_to_remove: vec![],
+            files_to_add: vec![AddFileMocker::new(1).time_range(time_range).build()],
+            files_to_delete: vec![],
+            max_file_id: 0,
+        });
+
+        assert!(version
+            .pick_read_view_as_of(time_range, before_flush)
+            .is_none());
+        let read_view = version
+            .pick_read_view_as_of(time_range, Timestamp::now())
+            .unwrap();
+        assert_eq!(1, read_view.leveled_ssts[0].len());
+    }
 }
@@ -17,7 +17,7 @@
 
 //! Version edits
 
-use std::convert::TryFrom;
+use std::{cmp, convert::TryFrom};
 
 use common_types::{time::TimeRange, SequenceNumber};
 use horaedbproto::manifest as manifest_pb;
@@ -160,6 +160,28 @@ pub struct VersionEdit {
     pub max_file_id: FileId,
 }
 
+impl VersionEdit {
+    /// The last sequence covered by this edit, which is also bounded by the max
+    /// sequence recorded in the metadata of the flushed ssts, so the log
+    /// entries already flushed are never replayed.
+    ///
+    /// Only the edit of the flush, which sets the `flushed_sequence`, covers
+    /// the log entries. The ssts added by other operations (such as compaction
+    /// or import) are not dumped from the memtables, so their max sequences
+    /// must not move the flushed sequence, or the log entries not flushed yet
+    /// are skipped by the replay.
+    pub fn covered_sequence(&self) -> SequenceNumber {
+        if self.flushed_sequence == 0 {
+            return 0;
+        }
+
+        self.files_to_add
+            .iter()
+            .map(|add_file| add_file.file.max_seq)
+            .fold(self.flushed_sequence, cmp::max)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
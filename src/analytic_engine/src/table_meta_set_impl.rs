@@ -24,6 +24,7 @@ use id_allocator::IdAllocator;
 use logger::debug;
 use snafu::{OptionExt, ResultExt};
 use table_engine::table::TableId;
+use time_ext::ReadableDuration;
 
 use crate::{
     manifest::{
@@ -58,6 +59,7 @@ pub(crate) struct TableMetaSetImpl {
     pub(crate) preflush_write_buffer_size_ratio: f32,
     pub(crate) manifest_snapshot_every_n_updates: NonZeroUsize,
     pub(crate) enable_primary_key_sampling: bool,
+    pub(crate) version_retention: ReadableDuration,
     pub(crate) metrics_opt: MetricsOptions,
 }
 
@@ -157,6 +159,7 @@ impl TableMetaSetImpl {
                                 .manifest_snapshot_every_n_updates,
                             metrics_opt: self.metrics_opt.clone(),
                             enable_primary_key_sampling: self.enable_primary_key_sampling,
+                            version_retention: self.version_retention,
                         },
                         &self.file_purger,
                         mem_size_options,
@@ -293,6 +296,7 @@ impl TableMetaSetImpl {
                     manifest_snapshot_every_n_updates: self.manifest_snapshot_every_n_updates,
                     metrics_opt: self.metrics_opt.clone(),
                     enable_primary_key_sampling: self.enable_primary_key_sampling,
+                    version_retention: self.version_retention,
                 },
                 mem_size_options,
                 allocator,
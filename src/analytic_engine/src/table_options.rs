@@ -17,19 +17,36 @@
 
 //! Constants for table options.
 
-use std::{collections::HashMap, string::ToString, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    string::ToString,
+    sync::Arc,
+    time::Duration,
+};
 
+use codec::{
+    compact::{MemCompactDecoder, MemCompactEncoder},
+    DecodeTo, Encoder,
+};
 use common_types::{
-    time::Timestamp, ARENA_BLOCK_SIZE, COMPACTION_STRATEGY, COMPRESSION, ENABLE_TTL, MEMTABLE_TYPE,
-    NUM_ROWS_PER_ROW_GROUP, OPTION_KEY_ENABLE_TTL, SEGMENT_DURATION, STORAGE_FORMAT, TTL,
-    UPDATE_MODE, WRITE_BUFFER_SIZE,
+    datum::{Datum, DatumKind},
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+    ADAPTIVE_COMPRESSION, ARENA_BLOCK_SIZE, BLOOM_FILTER_FPP, BLOOM_FILTER_RECENT_DURATION,
+    BLOOM_FILTER_SIZING, CACHE_PRIORITY, COLUMN_COMPRESSION, COLUMN_ENCODING, COMPACTION_STRATEGY,
+    COMPRESSION, COMPRESSION_LEVEL, DATA_PAGE_SIZE, ENABLE_TTL, HOT_DURATION, MEMTABLE_TYPE,
+    MERGE_POLICY, NUM_ROWS_PER_ROW_GROUP, OPTION_KEY_ENABLE_TTL, ROW_GROUP_SIZE_POLICY,
+    SEGMENT_DURATION, SEPARATED_FIELDS, STORAGE_FORMAT, TIMESTAMP_ORIGINAL_COLUMN,
+    TIMESTAMP_RESOLUTION, TRANSFORM_PIPELINE, TTL, UPDATE_MODE, WRITE_BUFFER_SIZE,
 };
-use datafusion::parquet::basic::Compression as ParquetCompression;
+use datafusion::parquet::basic::{Compression as ParquetCompression, ZstdLevel};
 use horaedbproto::manifest as manifest_pb;
 use macros::define_result;
+use object_store::cache_priority::CachePriority;
 use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
-use snafu::{Backtrace, GenerateBacktrace, OptionExt, ResultExt, Snafu};
+use snafu::{ensure, Backtrace, GenerateBacktrace, OptionExt, ResultExt, Snafu};
+use table_engine::histogram::{ColumnHistograms, Histogram};
 use time_ext::{parse_duration, DurationExt, ReadableDuration, TimeUnit};
 
 use crate::{
@@ -41,12 +58,23 @@ use crate::{
 
 const UPDATE_MODE_OVERWRITE: &str = "OVERWRITE";
 const UPDATE_MODE_APPEND: &str = "APPEND";
+const MERGE_POLICY_LAST_WRITE: &str = "LAST_WRITE";
+const MERGE_POLICY_MAX: &str = "MAX";
+const MERGE_POLICY_SUM: &str = "SUM";
 const COMPRESSION_UNCOMPRESSED: &str = "UNCOMPRESSED";
 const COMPRESSION_LZ4: &str = "LZ4";
 const COMPRESSION_SNAPPY: &str = "SNAPPY";
 const COMPRESSION_ZSTD: &str = "ZSTD";
+const VALUE_ENCODING_PLAIN: &str = "PLAIN";
+const VALUE_ENCODING_DICT: &str = "DICT";
+const VALUE_ENCODING_RLE: &str = "RLE";
+const VALUE_ENCODING_DELTA: &str = "DELTA";
 const STORAGE_FORMAT_AUTO: &str = "AUTO";
 const STORAGE_FORMAT_COLUMNAR: &str = "COLUMNAR";
+const ROW_GROUP_SIZE_POLICY_FIXED: &str = "FIXED";
+const ROW_GROUP_SIZE_POLICY_ADAPTIVE: &str = "ADAPTIVE";
+const BLOOM_FILTER_SIZING_FIXED: &str = "FIXED";
+const BLOOM_FILTER_SIZING_ADAPTIVE: &str = "ADAPTIVE";
 
 /// Default bucket duration (1d)
 const BUCKET_DURATION_1D: Duration = Duration::from_secs(24 * 60 * 60);
@@ -60,6 +88,8 @@ const DEFAULT_WRITE_BUFFER_SIZE: u32 = 32 * 1024 * 1024;
 const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 /// Default row number of a row group.
 const DEFAULT_NUM_ROW_PER_ROW_GROUP: usize = 8192;
+/// Default data page size (1M).
+pub const DEFAULT_DATA_PAGE_SIZE: u64 = 1024 * 1024;
 
 /// Max arena block size (2G)
 const MAX_ARENA_BLOCK_SIZE: u32 = 2 * 1024 * 1024 * 1024;
@@ -67,6 +97,23 @@ const MAX_ARENA_BLOCK_SIZE: u32 = 2 * 1024 * 1024 * 1024;
 const MIN_ARENA_BLOCK_SIZE: u32 = 1024;
 const MIN_NUM_ROWS_PER_ROW_GROUP: usize = 100;
 const MAX_NUM_ROWS_PER_ROW_GROUP: usize = 10_000_000;
+/// Min data page size (4K)
+const MIN_DATA_PAGE_SIZE: u64 = 4 * 1024;
+/// Max data page size (64M)
+const MAX_DATA_PAGE_SIZE: u64 = 64 * 1024 * 1024;
+const MIN_BLOOM_FILTER_FPP: f64 = 0.0001;
+const MAX_BLOOM_FILTER_FPP: f64 = 0.5;
+const MIN_ZSTD_LEVEL: i32 = 1;
+const MAX_ZSTD_LEVEL: i32 = 22;
+
+/// Row groups are shrunk if the queries read less than this ratio of them after
+/// pruning.
+const SELECTIVE_SCAN_RATIO: f64 = 0.25;
+/// Row groups are enlarged if the queries read more than this ratio of them
+/// after pruning.
+const FULL_SCAN_RATIO: f64 = 0.75;
+/// Factor to shrink or enlarge the row groups by.
+const ADAPTIVE_ROW_GROUP_SIZE_FACTOR: usize = 4;
 
 #[derive(Debug, Snafu)]
 #[allow(clippy::enum_variant_names)]
@@ -95,6 +142,11 @@ pub enum Error {
         source: std::str::ParseBoolError,
         backtrace: Backtrace,
     },
+    #[snafu(display("Failed to parse float, err:{}.\nBacktrace:\n{}", source, backtrace))]
+    ParseFloat {
+        source: std::num::ParseFloatError,
+        backtrace: Backtrace,
+    },
     #[snafu(display(
         "Failed to parse update mode, raw str:{}.\nBacktrace:\n{}",
         s,
@@ -102,6 +154,26 @@ pub enum Error {
     ))]
     ParseUpdateMode { s: String, backtrace: Backtrace },
 
+    #[snafu(display(
+        "Failed to parse merge policy, raw str:{}.\nBacktrace:\n{}",
+        s,
+        backtrace
+    ))]
+    ParseMergePolicy { s: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Merge policy {} is not supported by the {} memtable, the rows it scans are not \
+         sorted.\nBacktrace:\n{}",
+        merge_policy,
+        memtable_type,
+        backtrace
+    ))]
+    UnsupportedMergePolicy {
+        merge_policy: String,
+        memtable_type: String,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display(
         "Failed to parse compression, name:{}.\nBacktrace:\n{}",
         name,
@@ -109,6 +181,42 @@ pub enum Error {
     ))]
     ParseCompressionName { name: String, backtrace: Backtrace },
 
+    #[snafu(display(
+        "Invalid compression level, it should be in [{}, {}], level:{}.\nBacktrace:\n{}",
+        MIN_ZSTD_LEVEL,
+        MAX_ZSTD_LEVEL,
+        level,
+        backtrace
+    ))]
+    InvalidCompressionLevel { level: i32, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse value encoding, raw str:{}.\nBacktrace:\n{}",
+        s,
+        backtrace
+    ))]
+    ParseValueEncoding { s: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse column options, it should be like `col1:value1,col2:value2`, raw \
+         str:{}.\nBacktrace:\n{}",
+        s,
+        backtrace
+    ))]
+    ParseColumnOptions { s: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid column option, column:{}, msg:{}.\nBacktrace:\n{}",
+        column,
+        msg,
+        backtrace
+    ))]
+    InvalidColumnOption {
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display(
         "Unknown storage format. value:{:?}.\nBacktrace:\n{}",
         value,
@@ -149,6 +257,68 @@ pub enum Error {
 
     #[snafu(display("Layered memtable options is missing.\nBacktrace:\n{backtrace}",))]
     MissingLayeredMemtableOptions { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse row group size policy, raw str:{}.\nBacktrace:\n{}",
+        s,
+        backtrace
+    ))]
+    ParseRowGroupSizePolicy { s: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse bloom filter sizing, raw str:{}.\nBacktrace:\n{}",
+        s,
+        backtrace
+    ))]
+    ParseBloomFilterSizing { s: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid bloom filter fpp, it should be in (0, 1), fpp:{}.\nBacktrace:\n{}",
+        fpp,
+        backtrace
+    ))]
+    InvalidBloomFilterFpp { fpp: f64, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse cache priority, err:{}.\nBacktrace:\n{}",
+        msg,
+        backtrace
+    ))]
+    ParseCachePriority { msg: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid timestamp resolution, resolution:{}.\nBacktrace:\n{}",
+        resolution,
+        backtrace
+    ))]
+    InvalidTimestampResolution {
+        resolution: ReadableDuration,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid column to keep the original timestamp, column:{}, msg:{}.\nBacktrace:\n{}",
+        column,
+        msg,
+        backtrace
+    ))]
+    InvalidTimestampOriginalColumn {
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid persisted histogram, column:{}, msg:{}.\nBacktrace:\n{}",
+        column,
+        msg,
+        backtrace
+    ))]
+    InvalidHistogram {
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
 }
 
 define_result!(Error);
@@ -180,6 +350,133 @@ impl ToString for UpdateMode {
     }
 }
 
+/// Policy to merge the rows with the same primary key, only takes effect
+/// when the table is in the `Overwrite` mode.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MergePolicy {
+    /// Keep the row with the largest sequence.
+    #[default]
+    LastWrite,
+    /// Keep the max value of each numeric field, and the other columns are
+    /// taken from the row with the largest sequence.
+    Max,
+    /// Sum up the values of each numeric field, and the other columns are
+    /// taken from the row with the largest sequence.
+    Sum,
+}
+
+impl MergePolicy {
+    pub fn parse_from(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case(MERGE_POLICY_LAST_WRITE) {
+            Ok(MergePolicy::LastWrite)
+        } else if s.eq_ignore_ascii_case(MERGE_POLICY_MAX) {
+            Ok(MergePolicy::Max)
+        } else if s.eq_ignore_ascii_case(MERGE_POLICY_SUM) {
+            Ok(MergePolicy::Sum)
+        } else {
+            ParseMergePolicy { s }.fail()
+        }
+    }
+}
+
+impl ToString for MergePolicy {
+    fn to_string(&self) -> String {
+        match self {
+            MergePolicy::LastWrite => MERGE_POLICY_LAST_WRITE.to_string(),
+            MergePolicy::Max => MERGE_POLICY_MAX.to_string(),
+            MergePolicy::Sum => MERGE_POLICY_SUM.to_string(),
+        }
+    }
+}
+
+/// Policy to decide the row number of the row groups in the ssts.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum RowGroupSizePolicy {
+    /// Always use the `num_rows_per_row_group`.
+    #[default]
+    Fixed,
+    /// Shrink the row groups for the tables whose queries prune most of the
+    /// row groups, and enlarge them for the tables mostly scanned, based on the
+    /// `num_rows_per_row_group`.
+    Adaptive,
+}
+
+impl RowGroupSizePolicy {
+    pub fn parse_from(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case(ROW_GROUP_SIZE_POLICY_FIXED) {
+            Ok(RowGroupSizePolicy::Fixed)
+        } else if s.eq_ignore_ascii_case(ROW_GROUP_SIZE_POLICY_ADAPTIVE) {
+            Ok(RowGroupSizePolicy::Adaptive)
+        } else {
+            ParseRowGroupSizePolicy { s }.fail()
+        }
+    }
+}
+
+impl ToString for RowGroupSizePolicy {
+    fn to_string(&self) -> String {
+        match self {
+            RowGroupSizePolicy::Fixed => ROW_GROUP_SIZE_POLICY_FIXED.to_string(),
+            RowGroupSizePolicy::Adaptive => ROW_GROUP_SIZE_POLICY_ADAPTIVE.to_string(),
+        }
+    }
+}
+
+/// Policy to decide the number of distinct values the bloom filters of the
+/// ssts are sized for.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum BloomFilterSizing {
+    /// Size the filters for the case every row of a row group is distinct.
+    Fixed,
+    /// Size the filters by the cardinality of the columns observed in the
+    /// first row group when writing the sst.
+    #[default]
+    Adaptive,
+}
+
+impl BloomFilterSizing {
+    pub fn parse_from(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case(BLOOM_FILTER_SIZING_FIXED) {
+            Ok(BloomFilterSizing::Fixed)
+        } else if s.eq_ignore_ascii_case(BLOOM_FILTER_SIZING_ADAPTIVE) {
+            Ok(BloomFilterSizing::Adaptive)
+        } else {
+            ParseBloomFilterSizing { s }.fail()
+        }
+    }
+}
+
+impl ToString for BloomFilterSizing {
+    fn to_string(&self) -> String {
+        match self {
+            BloomFilterSizing::Fixed => BLOOM_FILTER_SIZING_FIXED.to_string(),
+            BloomFilterSizing::Adaptive => BLOOM_FILTER_SIZING_ADAPTIVE.to_string(),
+        }
+    }
+}
+
+/// Options of the bloom filters built over the tag columns of the ssts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomFilterOptions {
+    /// Target false positive probability.
+    pub fpp: f64,
+    pub sizing: BloomFilterSizing,
+    /// Only the ssts with the data in this duration before now are indexed.
+    ///
+    /// `None` means all the ssts are indexed.
+    pub recent_duration: Option<Duration>,
+}
+
+impl BloomFilterOptions {
+    /// Whether the sst of the `time_range` is indexed at `now`.
+    pub fn covers(&self, time_range: TimeRange, now: Timestamp) -> bool {
+        match self.recent_duration {
+            Some(duration) => time_range.exclusive_end() > now.sub_duration_or_min(duration),
+            None => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Compression {
     Uncompressed,
@@ -202,6 +499,20 @@ impl Compression {
             ParseCompressionName { name }.fail()
         }
     }
+
+    /// Convert into the parquet compression, and the `level` only takes effect
+    /// for zstd.
+    pub fn to_parquet(self, level: Option<i32>) -> ParquetCompression {
+        match self {
+            Compression::Zstd => {
+                let level = level
+                    .and_then(|v| ZstdLevel::try_new(v).ok())
+                    .unwrap_or_default();
+                ParquetCompression::ZSTD(level)
+            }
+            _ => self.into(),
+        }
+    }
 }
 
 impl ToString for Compression {
@@ -248,6 +559,127 @@ impl From<Compression> for ParquetCompression {
     }
 }
 
+/// Encoding of the values of a column in the ssts.
+#[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ValueEncoding {
+    /// Store the values as is.
+    Plain,
+    /// Store the indexes into a dictionary of the distinct values, which suits
+    /// the low cardinality columns such as tags.
+    Dict,
+    /// Store the runs of the repeated values once with their lengths. Only the
+    /// boolean values are encoded so directly, and the others are dictionary
+    /// encoded with the indexes run length encoded.
+    Rle,
+    /// Store the deltas between the adjacent values, which suits the sorted or
+    /// increasing columns such as timestamps.
+    Delta,
+}
+
+impl ValueEncoding {
+    pub fn parse_from(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case(VALUE_ENCODING_PLAIN) {
+            Ok(ValueEncoding::Plain)
+        } else if s.eq_ignore_ascii_case(VALUE_ENCODING_DICT) {
+            Ok(ValueEncoding::Dict)
+        } else if s.eq_ignore_ascii_case(VALUE_ENCODING_RLE) {
+            Ok(ValueEncoding::Rle)
+        } else if s.eq_ignore_ascii_case(VALUE_ENCODING_DELTA) {
+            Ok(ValueEncoding::Delta)
+        } else {
+            ParseValueEncoding { s }.fail()
+        }
+    }
+
+    /// Whether the values of the `kind` can be encoded by this encoding.
+    pub fn support(&self, kind: DatumKind) -> bool {
+        match self {
+            ValueEncoding::Plain | ValueEncoding::Dict | ValueEncoding::Rle => true,
+            ValueEncoding::Delta => matches!(
+                kind,
+                DatumKind::Timestamp
+                    | DatumKind::Date
+                    | DatumKind::Time
+                    | DatumKind::UInt64
+                    | DatumKind::UInt32
+                    | DatumKind::UInt16
+                    | DatumKind::UInt8
+                    | DatumKind::Int64
+                    | DatumKind::Int32
+                    | DatumKind::Int16
+                    | DatumKind::Int8
+                    | DatumKind::String
+                    | DatumKind::Varbinary
+            ),
+        }
+    }
+}
+
+impl ToString for ValueEncoding {
+    fn to_string(&self) -> String {
+        match self {
+            ValueEncoding::Plain => VALUE_ENCODING_PLAIN.to_string(),
+            ValueEncoding::Dict => VALUE_ENCODING_DICT.to_string(),
+            ValueEncoding::Rle => VALUE_ENCODING_RLE.to_string(),
+            ValueEncoding::Delta => VALUE_ENCODING_DELTA.to_string(),
+        }
+    }
+}
+
+/// Options of the specific columns in the ssts, which override the ones of the
+/// table.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ColumnOptions {
+    /// Encodings of the columns, and the encodings of the other columns are
+    /// decided by sampling their values.
+    pub encodings: BTreeMap<String, ValueEncoding>,
+    /// Compressions of the columns, and the other columns use the compression
+    /// of the table.
+    pub compressions: BTreeMap<String, Compression>,
+}
+
+impl ColumnOptions {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.encodings.is_empty() && self.compressions.is_empty()
+    }
+}
+
+/// Parse the column options like `col1:value1,col2:value2`, and the empty
+/// string means no column is specified.
+fn parse_column_options<T>(
+    s: &str,
+    parse_value: impl Fn(&str) -> Result<T>,
+) -> Result<BTreeMap<String, T>> {
+    let mut options = BTreeMap::new();
+    for item in s.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let (column, value) = item.split_once(':').context(ParseColumnOptions { s })?;
+        let column = column.trim();
+        ensure!(!column.is_empty(), ParseColumnOptions { s });
+        options.insert(column.to_string(), parse_value(value.trim())?);
+    }
+
+    Ok(options)
+}
+
+pub(crate) fn column_options_to_string<T: ToString>(options: &BTreeMap<String, T>) -> String {
+    options
+        .iter()
+        .map(|(column, value)| format!("{column}:{}", value.to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn column_options_to_strings<T: ToString>(
+    options: &BTreeMap<String, T>,
+) -> BTreeMap<String, String> {
+    options
+        .iter()
+        .map(|(column, value)| (column.clone(), value.to_string()))
+        .collect()
+}
+
 /// A hint for building sst.
 #[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
 pub enum StorageFormatHint {
@@ -393,6 +825,8 @@ pub struct TableOptions {
     pub segment_duration: Option<ReadableDuration>,
     /// Table update mode, now support Overwrite(Default) and Append
     pub update_mode: UpdateMode,
+    /// Policy to merge the rows with the same primary key.
+    pub merge_policy: MergePolicy,
     /// Hint for storage format.
     pub storage_format_hint: StorageFormatHint,
 
@@ -409,13 +843,72 @@ pub struct TableOptions {
     pub compaction_strategy: CompactionStrategy,
     /// Row number in a row group.
     pub num_rows_per_row_group: usize,
+    /// Policy to decide the actual row number of the row groups.
+    pub row_group_size_policy: RowGroupSizePolicy,
+    /// Max size of a data page.
+    pub data_page_size: ReadableSize,
+    /// Target false positive probability of the bloom filters of the tag
+    /// columns in the ssts, e.g. `0.01`.
+    ///
+    /// `None` means no bloom filter is built.
+    pub bloom_filter_fpp: Option<f64>,
+    /// Policy to size the bloom filters.
+    pub bloom_filter_sizing: BloomFilterSizing,
+    /// Only the ssts with the data in this recent duration are indexed by the
+    /// bloom filters, and the filters are dropped as the ssts are compacted
+    /// after they get cold.
+    ///
+    /// `None` means all the ssts are indexed.
+    pub bloom_filter_recent_duration: Option<ReadableDuration>,
     /// Table Compression
     pub compression: Compression,
+    /// Level of the zstd compression, e.g. `9`.
+    ///
+    /// `None` means the default level is used.
+    pub compression_level: Option<i32>,
+    /// Encodings and compressions of the specific columns.
+    pub column_options: ColumnOptions,
+    /// Choose the compressions of the columns not specified in the
+    /// `column_options` by the entropy of their values sampled when writing
+    /// the ssts, e.g. the random values are not compressed.
+    pub adaptive_compression: bool,
+    /// Names of the large field columns stored in a separate file of the ssts,
+    /// so the queries not selecting them never read their data.
+    pub separated_fields: Vec<String>,
+
+    /// Priority of the cached sst meta data and blocks of the table, the
+    /// caches of the tables with lower priority are evicted first.
+    pub cache_priority: CachePriority,
+
+    /// The timestamps written are truncated to this resolution, e.g. `1s`.
+    ///
+    /// `None` means the timestamps are kept as is.
+    pub timestamp_resolution: Option<ReadableDuration>,
+    /// Name of the field column to keep the timestamp before truncation, only
+    /// takes effect when `timestamp_resolution` is set.
+    pub timestamp_original_column: Option<String>,
+
+    /// The ssts older than this duration are moved to the cold tier.
+    ///
+    /// `None` means the ssts are never moved.
+    pub hot_duration: Option<ReadableDuration>,
+
+    /// Steps transforming the rows before they are written, e.g.
+    /// `RENAME host TO hostname; SET latency = latency / 1000`.
+    ///
+    /// The steps are applied by the writers rather than the engine, so the
+    /// option is only stored here.
+    pub transform_pipeline: Option<String>,
 
     /// Memtable type
     pub memtable_type: MemtableType,
     /// Layered memtable options
     pub layered_memtable_opts: LayeredMemtableOptions,
+
+    /// Histograms of the columns built by `ANALYZE TABLE`, which are not
+    /// options but persisted with them.
+    #[serde(skip)]
+    pub histograms: Arc<ColumnHistograms>,
 }
 
 impl TableOptions {
@@ -429,6 +922,34 @@ impl TableOptions {
         self.segment_duration.map(|v| v.0)
     }
 
+    /// Row number of the row groups to build.
+    ///
+    /// `scan_ratio` is the ratio of the row groups read by the queries after
+    /// pruning, and `None` means not enough queries are observed yet.
+    pub fn adapted_num_rows_per_row_group(&self, scan_ratio: Option<f64>) -> usize {
+        let num_rows = match (self.row_group_size_policy, scan_ratio) {
+            (RowGroupSizePolicy::Adaptive, Some(ratio)) if ratio < SELECTIVE_SCAN_RATIO => {
+                self.num_rows_per_row_group / ADAPTIVE_ROW_GROUP_SIZE_FACTOR
+            }
+            (RowGroupSizePolicy::Adaptive, Some(ratio)) if ratio > FULL_SCAN_RATIO => self
+                .num_rows_per_row_group
+                .saturating_mul(ADAPTIVE_ROW_GROUP_SIZE_FACTOR),
+            _ => return self.num_rows_per_row_group,
+        };
+
+        num_rows.clamp(MIN_NUM_ROWS_PER_ROW_GROUP, MAX_NUM_ROWS_PER_ROW_GROUP)
+    }
+
+    /// Options of the bloom filters to build for the ssts, `None` if disabled.
+    #[inline]
+    pub fn bloom_filter_options(&self) -> Option<BloomFilterOptions> {
+        self.bloom_filter_fpp.map(|fpp| BloomFilterOptions {
+            fpp,
+            sizing: self.bloom_filter_sizing,
+            recent_duration: self.bloom_filter_recent_duration.map(|v| v.0),
+        })
+    }
+
     #[inline]
     pub fn ttl(&self) -> Option<ReadableDuration> {
         if self.enable_ttl {
@@ -462,15 +983,70 @@ impl TableOptions {
                 NUM_ROWS_PER_ROW_GROUP.to_string(),
                 format!("{}", self.num_rows_per_row_group),
             ),
+            (
+                ROW_GROUP_SIZE_POLICY.to_string(),
+                self.row_group_size_policy.to_string(),
+            ),
+            (
+                DATA_PAGE_SIZE.to_string(),
+                format!("{}", self.data_page_size.0),
+            ),
             (COMPRESSION.to_string(), self.compression.to_string()),
             (
                 STORAGE_FORMAT.to_string(),
                 self.storage_format_hint.to_string(),
             ),
             (MEMTABLE_TYPE.to_string(), self.memtable_type.to_string()),
+            (CACHE_PRIORITY.to_string(), self.cache_priority.to_string()),
         ]
         .into_iter()
         .collect();
+        if let Some(v) = self.timestamp_resolution {
+            m.insert(TIMESTAMP_RESOLUTION.to_string(), v.to_string());
+        }
+        if let Some(v) = &self.timestamp_original_column {
+            m.insert(TIMESTAMP_ORIGINAL_COLUMN.to_string(), v.clone());
+        }
+        if let Some(v) = self.hot_duration {
+            m.insert(HOT_DURATION.to_string(), v.to_string());
+        }
+        if let Some(v) = self.bloom_filter_fpp {
+            m.insert(BLOOM_FILTER_FPP.to_string(), v.to_string());
+            m.insert(
+                BLOOM_FILTER_SIZING.to_string(),
+                self.bloom_filter_sizing.to_string(),
+            );
+        }
+        if let Some(v) = self.bloom_filter_recent_duration {
+            m.insert(BLOOM_FILTER_RECENT_DURATION.to_string(), v.to_string());
+        }
+        if let Some(v) = &self.transform_pipeline {
+            m.insert(TRANSFORM_PIPELINE.to_string(), v.clone());
+        }
+        if let Some(v) = self.compression_level {
+            m.insert(COMPRESSION_LEVEL.to_string(), v.to_string());
+        }
+        if !self.column_options.encodings.is_empty() {
+            m.insert(
+                COLUMN_ENCODING.to_string(),
+                column_options_to_string(&self.column_options.encodings),
+            );
+        }
+        if !self.column_options.compressions.is_empty() {
+            m.insert(
+                COLUMN_COMPRESSION.to_string(),
+                column_options_to_string(&self.column_options.compressions),
+            );
+        }
+        if self.adaptive_compression {
+            m.insert(ADAPTIVE_COMPRESSION.to_string(), true.to_string());
+        }
+        if !self.separated_fields.is_empty() {
+            m.insert(SEPARATED_FIELDS.to_string(), self.separated_fields.join(","));
+        }
+        if self.merge_policy != MergePolicy::LastWrite {
+            m.insert(MERGE_POLICY.to_string(), self.merge_policy.to_string());
+        }
         self.compaction_strategy.fill_raw_map(&mut m);
 
         m
@@ -508,6 +1084,15 @@ impl TableOptions {
         if self.num_rows_per_row_group > MAX_NUM_ROWS_PER_ROW_GROUP {
             self.num_rows_per_row_group = MAX_NUM_ROWS_PER_ROW_GROUP;
         }
+
+        self.data_page_size.0 = self
+            .data_page_size
+            .0
+            .clamp(MIN_DATA_PAGE_SIZE, MAX_DATA_PAGE_SIZE);
+
+        if let Some(fpp) = &mut self.bloom_filter_fpp {
+            *fpp = fpp.clamp(MIN_BLOOM_FILTER_FPP, MAX_BLOOM_FILTER_FPP);
+        }
     }
 
     pub fn need_dedup(&self) -> bool {
@@ -517,6 +1102,19 @@ impl TableOptions {
         }
     }
 
+    /// Policy to merge the duplicate rows, `None` if the rows are not deduped.
+    #[inline]
+    pub fn dedup_merge_policy(&self) -> Option<MergePolicy> {
+        self.need_dedup().then_some(self.merge_policy)
+    }
+
+    /// Whether the duplicate rows can be dropped when scanning the memtables,
+    /// the merge policies other than `LastWrite` need all of them.
+    #[inline]
+    pub fn need_dedup_memtable(&self) -> bool {
+        self.dedup_merge_policy() == Some(MergePolicy::LastWrite)
+    }
+
     // Only support sample primary key for APPEND.
     pub fn support_sample_pk(&self) -> bool {
         match self.update_mode {
@@ -528,6 +1126,155 @@ impl TableOptions {
     pub fn is_expired(&self, timestamp: Timestamp) -> bool {
         self.enable_ttl && timestamp.is_expired(Timestamp::expire_time(self.ttl.0))
     }
+
+    /// The options following the columns renamed or dropped by altering the
+    /// schema from `old_schema` to `new_schema`, where the columns are matched
+    /// by id, and the options of the dropped columns are removed.
+    ///
+    /// Returns `None` if no option refers to the renamed or dropped columns.
+    pub fn with_altered_columns(&self, old_schema: &Schema, new_schema: &Schema) -> Option<Self> {
+        // Old name of the column -> new name, `None` if it's dropped.
+        let altered: HashMap<_, _> = old_schema
+            .columns()
+            .iter()
+            .filter_map(|column| {
+                let new_name = new_schema
+                    .index_of_column_id(column.id)
+                    .map(|idx| new_schema.column(idx).name.clone());
+                (new_name.as_ref() != Some(&column.name)).then(|| (column.name.clone(), new_name))
+            })
+            .collect();
+        let is_altered = |name: &String| altered.contains_key(name);
+        let referred = self.column_options.encodings.keys().any(is_altered)
+            || self.column_options.compressions.keys().any(is_altered)
+            || self.separated_fields.iter().any(is_altered)
+            || self.timestamp_original_column.iter().any(is_altered)
+            || self.histograms.keys().any(is_altered);
+        if !referred {
+            return None;
+        }
+
+        let rename = |name: &String| match altered.get(name) {
+            Some(new_name) => new_name.clone(),
+            None => Some(name.clone()),
+        };
+        let mut opts = self.clone();
+        opts.column_options.encodings = self
+            .column_options
+            .encodings
+            .iter()
+            .filter_map(|(name, encoding)| Some((rename(name)?, *encoding)))
+            .collect();
+        opts.column_options.compressions = self
+            .column_options
+            .compressions
+            .iter()
+            .filter_map(|(name, compression)| Some((rename(name)?, *compression)))
+            .collect();
+        opts.separated_fields = self.separated_fields.iter().filter_map(rename).collect();
+        opts.timestamp_original_column = self.timestamp_original_column.as_ref().and_then(rename);
+        opts.histograms = Arc::new(
+            self.histograms
+                .iter()
+                .filter_map(|(name, histogram)| Some((rename(name)?, histogram.clone())))
+                .collect(),
+        );
+
+        Some(opts)
+    }
+
+    /// Check the options depending on the table `schema`.
+    pub fn validate_with_schema(&self, schema: &Schema) -> Result<()> {
+        self.validate_column_options(schema)?;
+        self.validate_separated_fields(schema)?;
+
+        let column = match &self.timestamp_original_column {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        let msg = match schema.index_of(column) {
+            None => "column not found",
+            Some(idx) => {
+                let column_schema = schema.column(idx);
+                if schema.is_primary_key_index(&idx) || column_schema.is_tag {
+                    "column must be a field"
+                } else if !matches!(
+                    column_schema.data_type,
+                    DatumKind::Timestamp | DatumKind::Int64
+                ) {
+                    "column must be timestamp or bigint"
+                } else {
+                    return Ok(());
+                }
+            }
+        };
+
+        InvalidTimestampOriginalColumn {
+            column,
+            msg: msg.to_string(),
+        }
+        .fail()
+    }
+
+    fn validate_column_options(&self, schema: &Schema) -> Result<()> {
+        let columns = self
+            .column_options
+            .encodings
+            .keys()
+            .chain(self.column_options.compressions.keys());
+        for column in columns {
+            ensure!(
+                schema.index_of(column).is_some(),
+                InvalidColumnOption {
+                    column,
+                    msg: "column not found",
+                }
+            );
+        }
+
+        for (column, encoding) in &self.column_options.encodings {
+            let column_schema = schema.column(schema.index_of(column).unwrap());
+            let msg = if !encoding.support(column_schema.data_type) {
+                format!(
+                    "{} encoding doesn't support {}",
+                    encoding.to_string(),
+                    column_schema.data_type
+                )
+            } else if column_schema.is_dictionary && *encoding != ValueEncoding::Dict {
+                "dictionary column can only use DICT encoding".to_string()
+            } else {
+                continue;
+            };
+
+            return InvalidColumnOption { column, msg }.fail();
+        }
+
+        Ok(())
+    }
+
+    fn validate_separated_fields(&self, schema: &Schema) -> Result<()> {
+        for column in &self.separated_fields {
+            let msg = match schema.index_of(column) {
+                None => "column not found",
+                Some(idx) => {
+                    let column_schema = schema.column(idx);
+                    if schema.is_primary_key_index(&idx) || column_schema.is_tag {
+                        "column must be a field"
+                    } else if !column_schema.is_nullable {
+                        // The values of the column are replaced by nulls in the sst.
+                        "column must be nullable"
+                    } else {
+                        continue;
+                    }
+                }
+            };
+
+            return InvalidColumnOption { column, msg }.fail();
+        }
+
+        Ok(())
+    }
 }
 
 impl From<SizeTieredCompactionOptions> for manifest_pb::CompactionOptions {
@@ -619,11 +1366,242 @@ impl From<TableOptions> for manifest_pb::TableOptions {
                 opts.storage_format_hint,
             )),
             layered_memtable_options: Some(layered_memtable_opts),
+            // The other options are persisted by the `TableOptionsExt`.
             // TODO: persist `memtable_type` in PB.
         }
     }
 }
 
+/// Options not in the `manifest_pb::TableOptions`.
+///
+/// The `horaedbproto` is pinned to an upstream revision, so the options added
+/// since then are encoded by this message, which is written next to the pb
+/// of the manifest under a tag unknown to the pb (see `MetaExt`), so the
+/// manifests stay readable by the older versions.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TableOptionsExt {
+    #[prost(string, tag = "1")]
+    pub row_group_size_policy: String,
+    #[prost(uint64, tag = "2")]
+    pub data_page_size: u64,
+    #[prost(string, tag = "3")]
+    pub cache_priority: String,
+    #[prost(uint64, optional, tag = "4")]
+    pub timestamp_resolution: Option<u64>,
+    #[prost(string, optional, tag = "5")]
+    pub timestamp_original_column: Option<String>,
+    #[prost(uint64, optional, tag = "6")]
+    pub hot_duration: Option<u64>,
+    #[prost(string, optional, tag = "7")]
+    pub transform_pipeline: Option<String>,
+    #[prost(double, optional, tag = "8")]
+    pub bloom_filter_fpp: Option<f64>,
+    #[prost(string, tag = "9")]
+    pub bloom_filter_sizing: String,
+    #[prost(int32, optional, tag = "10")]
+    pub compression_level: Option<i32>,
+    #[prost(btree_map = "string, string", tag = "11")]
+    pub column_encodings: BTreeMap<String, String>,
+    #[prost(btree_map = "string, string", tag = "12")]
+    pub column_compressions: BTreeMap<String, String>,
+    #[prost(bool, tag = "13")]
+    pub adaptive_compression: bool,
+    #[prost(string, tag = "14")]
+    pub merge_policy: String,
+    #[prost(string, repeated, tag = "15")]
+    pub separated_fields: Vec<String>,
+    #[prost(uint64, optional, tag = "16")]
+    pub bloom_filter_recent_duration: Option<u64>,
+    #[prost(message, repeated, tag = "17")]
+    pub histograms: Vec<HistogramExt>,
+}
+
+/// Histogram of a column persisted by the [TableOptionsExt], the values are
+/// encoded by the mem compact codec.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HistogramExt {
+    #[prost(string, tag = "1")]
+    pub column: String,
+    #[prost(uint32, tag = "2")]
+    pub kind: u32,
+    #[prost(uint64, tag = "3")]
+    pub num_rows: u64,
+    #[prost(uint64, tag = "4")]
+    pub num_nulls: u64,
+    #[prost(uint64, tag = "5")]
+    pub num_distinct: u64,
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub min: Option<Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "7")]
+    pub bounds: Vec<Vec<u8>>,
+}
+
+impl HistogramExt {
+    fn try_new(
+        column: &str,
+        histogram: &Histogram,
+    ) -> std::result::Result<Self, codec::compact::Error> {
+        let encode = |datum: &Datum| {
+            let mut buf = Vec::with_capacity(MemCompactEncoder.estimate_encoded_size(datum));
+            MemCompactEncoder.encode(&mut buf, datum).map(|_| buf)
+        };
+        let kind = histogram
+            .min
+            .as_ref()
+            .map(|v| v.kind())
+            .unwrap_or(DatumKind::Null);
+
+        Ok(Self {
+            column: column.to_string(),
+            kind: kind.into_u8() as u32,
+            num_rows: histogram.num_rows as u64,
+            num_nulls: histogram.num_nulls as u64,
+            num_distinct: histogram.num_distinct as u64,
+            min: histogram.min.as_ref().map(encode).transpose()?,
+            bounds: histogram
+                .bounds
+                .iter()
+                .map(encode)
+                .collect::<std::result::Result<_, _>>()?,
+        })
+    }
+
+    fn into_histogram(self) -> Result<(String, Histogram)> {
+        let column = self.column;
+        let kind = u8::try_from(self.kind)
+            .ok()
+            .and_then(|v| DatumKind::try_from(v).ok())
+            .with_context(|| InvalidHistogram {
+                column: column.clone(),
+                msg: format!("unknown datum kind {}", self.kind),
+            })?;
+        let decode = |bytes: Vec<u8>| {
+            let mut datum = Datum::empty(&kind);
+            MemCompactDecoder
+                .decode_to(&mut bytes.as_slice(), &mut datum)
+                .map_err(|e| Error::InvalidHistogram {
+                    column: column.clone(),
+                    msg: e.to_string(),
+                    backtrace: Backtrace::generate(),
+                })?;
+            Ok(datum)
+        };
+        let histogram = Histogram {
+            num_rows: self.num_rows as usize,
+            num_nulls: self.num_nulls as usize,
+            num_distinct: self.num_distinct as usize,
+            min: self.min.map(decode).transpose()?,
+            bounds: self.bounds.into_iter().map(decode).collect::<Result<_>>()?,
+        };
+
+        Ok((column, histogram))
+    }
+}
+
+impl From<&TableOptions> for TableOptionsExt {
+    fn from(opts: &TableOptions) -> Self {
+        Self {
+            row_group_size_policy: opts.row_group_size_policy.to_string(),
+            data_page_size: opts.data_page_size.0,
+            cache_priority: opts.cache_priority.to_string(),
+            timestamp_resolution: opts.timestamp_resolution.map(|v| v.0.as_millis_u64()),
+            timestamp_original_column: opts.timestamp_original_column.clone(),
+            hot_duration: opts.hot_duration.map(|v| v.0.as_millis_u64()),
+            transform_pipeline: opts.transform_pipeline.clone(),
+            bloom_filter_fpp: opts.bloom_filter_fpp,
+            bloom_filter_sizing: opts.bloom_filter_sizing.to_string(),
+            compression_level: opts.compression_level,
+            column_encodings: column_options_to_strings(&opts.column_options.encodings),
+            column_compressions: column_options_to_strings(&opts.column_options.compressions),
+            adaptive_compression: opts.adaptive_compression,
+            merge_policy: opts.merge_policy.to_string(),
+            separated_fields: opts.separated_fields.clone(),
+            bloom_filter_recent_duration: opts
+                .bloom_filter_recent_duration
+                .map(|v| v.0.as_millis_u64()),
+            // The histograms failed to encode are dropped, and they can be
+            // built again by `ANALYZE TABLE`.
+            histograms: opts
+                .histograms
+                .iter()
+                .filter_map(|(column, histogram)| HistogramExt::try_new(column, histogram).ok())
+                .collect(),
+        }
+    }
+}
+
+impl TableOptions {
+    /// Restore the options persisted by the [TableOptionsExt], the missing
+    /// ones (written by the older versions) are left as is.
+    pub fn apply_ext(&mut self, ext: TableOptionsExt) -> Result<()> {
+        if !ext.row_group_size_policy.is_empty() {
+            self.row_group_size_policy =
+                RowGroupSizePolicy::parse_from(&ext.row_group_size_policy)?;
+        }
+        if ext.data_page_size > 0 {
+            self.data_page_size = ReadableSize(ext.data_page_size);
+        }
+        if !ext.cache_priority.is_empty() {
+            self.cache_priority = ext
+                .cache_priority
+                .parse()
+                .map_err(|msg| Error::ParseCachePriority {
+                    msg,
+                    backtrace: Backtrace::generate(),
+                })?;
+        }
+        if let Some(v) = ext.timestamp_resolution {
+            self.timestamp_resolution = Some(Duration::from_millis(v).into());
+        }
+        if let Some(v) = ext.timestamp_original_column {
+            self.timestamp_original_column = Some(v);
+        }
+        if let Some(v) = ext.hot_duration {
+            self.hot_duration = Some(Duration::from_millis(v).into());
+        }
+        if let Some(v) = ext.transform_pipeline {
+            self.transform_pipeline = Some(v);
+        }
+        if let Some(v) = ext.bloom_filter_fpp {
+            self.bloom_filter_fpp = Some(v);
+        }
+        if !ext.bloom_filter_sizing.is_empty() {
+            self.bloom_filter_sizing = BloomFilterSizing::parse_from(&ext.bloom_filter_sizing)?;
+        }
+        if let Some(v) = ext.compression_level {
+            self.compression_level = Some(v);
+        }
+        for (column, encoding) in ext.column_encodings {
+            let encoding = ValueEncoding::parse_from(&encoding)?;
+            self.column_options.encodings.insert(column, encoding);
+        }
+        for (column, compression) in ext.column_compressions {
+            let compression = Compression::parse_from(&compression)?;
+            self.column_options.compressions.insert(column, compression);
+        }
+        self.adaptive_compression |= ext.adaptive_compression;
+        if !ext.merge_policy.is_empty() {
+            self.merge_policy = MergePolicy::parse_from(&ext.merge_policy)?;
+        }
+        if !ext.separated_fields.is_empty() {
+            self.separated_fields = ext.separated_fields;
+        }
+        if let Some(v) = ext.bloom_filter_recent_duration {
+            self.bloom_filter_recent_duration = Some(Duration::from_millis(v).into());
+        }
+        if !ext.histograms.is_empty() {
+            let histograms = ext
+                .histograms
+                .into_iter()
+                .map(HistogramExt::into_histogram)
+                .collect::<Result<_>>()?;
+            self.histograms = Arc::new(histograms);
+        }
+
+        Ok(())
+    }
+}
+
 impl From<UpdateMode> for manifest_pb::UpdateMode {
     fn from(v: UpdateMode) -> Self {
         match v {
@@ -696,9 +1674,24 @@ impl TryFrom<manifest_pb::TableOptions> for TableOptions {
             arena_block_size: opts.arena_block_size,
             compaction_strategy,
             num_rows_per_row_group: opts.num_rows_per_row_group as usize,
+            row_group_size_policy: RowGroupSizePolicy::default(),
+            data_page_size: ReadableSize(DEFAULT_DATA_PAGE_SIZE),
+            bloom_filter_fpp: None,
+            bloom_filter_sizing: BloomFilterSizing::default(),
+            bloom_filter_recent_duration: None,
+            cache_priority: CachePriority::default(),
+            timestamp_resolution: None,
+            timestamp_original_column: None,
+            hot_duration: None,
+            transform_pipeline: None,
             update_mode: UpdateMode::from(update_mode),
+            merge_policy: MergePolicy::default(),
             write_buffer_size: opts.write_buffer_size,
             compression: Compression::from(compression),
+            compression_level: None,
+            column_options: ColumnOptions::default(),
+            adaptive_compression: false,
+            separated_fields: Vec::new(),
             storage_format_hint: StorageFormatHint::try_from(storage_format_hint)?,
             memtable_type: MemtableType::SkipList,
             layered_memtable_opts,
@@ -717,12 +1710,28 @@ impl Default for TableOptions {
             arena_block_size: DEFAULT_ARENA_BLOCK_SIZE,
             compaction_strategy: CompactionStrategy::default(),
             num_rows_per_row_group: DEFAULT_NUM_ROW_PER_ROW_GROUP,
+            row_group_size_policy: RowGroupSizePolicy::default(),
+            data_page_size: ReadableSize(DEFAULT_DATA_PAGE_SIZE),
+            bloom_filter_fpp: None,
+            bloom_filter_sizing: BloomFilterSizing::default(),
+            bloom_filter_recent_duration: None,
+            cache_priority: CachePriority::default(),
+            timestamp_resolution: None,
+            timestamp_original_column: None,
+            hot_duration: None,
+            transform_pipeline: None,
             update_mode: UpdateMode::Overwrite,
+            merge_policy: MergePolicy::default(),
             write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
             compression: Compression::Zstd,
+            compression_level: None,
+            column_options: ColumnOptions::default(),
+            adaptive_compression: false,
+            separated_fields: Vec::new(),
             storage_format_hint: StorageFormatHint::default(),
             memtable_type: MemtableType::SkipList,
             layered_memtable_opts: LayeredMemtableOptions::default(),
+            histograms: Arc::default(),
         }
     }
 }
@@ -759,6 +1768,9 @@ fn merge_table_options(
         if let Some(v) = options.get(UPDATE_MODE) {
             base_table_opts.update_mode = UpdateMode::parse_from(v)?;
         }
+        if let Some(v) = options.get(MERGE_POLICY) {
+            base_table_opts.merge_policy = MergePolicy::parse_from(v)?;
+        }
     }
 
     if let Some(v) = options.get(TTL) {
@@ -782,16 +1794,114 @@ fn merge_table_options(
     if let Some(v) = options.get(NUM_ROWS_PER_ROW_GROUP) {
         base_table_opts.num_rows_per_row_group = v.parse().context(ParseInt)?;
     }
+    if let Some(v) = options.get(ROW_GROUP_SIZE_POLICY) {
+        base_table_opts.row_group_size_policy = RowGroupSizePolicy::parse_from(v)?;
+    }
+    if let Some(v) = options.get(DATA_PAGE_SIZE) {
+        base_table_opts.data_page_size = parse_size(v)?;
+    }
+    if let Some(v) = options.get(BLOOM_FILTER_FPP) {
+        base_table_opts.bloom_filter_fpp = if v.is_empty() {
+            None
+        } else {
+            let fpp = v.parse::<f64>().context(ParseFloat)?;
+            ensure!(fpp > 0.0 && fpp < 1.0, InvalidBloomFilterFpp { fpp });
+            Some(fpp)
+        };
+    }
+    if let Some(v) = options.get(BLOOM_FILTER_SIZING) {
+        base_table_opts.bloom_filter_sizing = BloomFilterSizing::parse_from(v)?;
+    }
+    if let Some(v) = options.get(BLOOM_FILTER_RECENT_DURATION) {
+        base_table_opts.bloom_filter_recent_duration = if v.is_empty() {
+            None
+        } else {
+            Some(parse_duration(v).context(ParseDuration)?)
+        };
+    }
     if let Some(v) = options.get(COMPRESSION) {
         base_table_opts.compression = Compression::parse_from(v)?;
     }
+    if let Some(v) = options.get(COMPRESSION_LEVEL) {
+        base_table_opts.compression_level = if v.is_empty() {
+            None
+        } else {
+            let level = v.parse::<i32>().context(ParseInt)?;
+            ensure!(
+                (MIN_ZSTD_LEVEL..=MAX_ZSTD_LEVEL).contains(&level),
+                InvalidCompressionLevel { level }
+            );
+            Some(level)
+        };
+    }
+    if let Some(v) = options.get(COLUMN_ENCODING) {
+        base_table_opts.column_options.encodings =
+            parse_column_options(v, ValueEncoding::parse_from)?;
+    }
+    if let Some(v) = options.get(COLUMN_COMPRESSION) {
+        base_table_opts.column_options.compressions =
+            parse_column_options(v, Compression::parse_from)?;
+    }
+    if let Some(v) = options.get(ADAPTIVE_COMPRESSION) {
+        base_table_opts.adaptive_compression = v.parse::<bool>().context(ParseBool)?;
+    }
+    if let Some(v) = options.get(SEPARATED_FIELDS) {
+        base_table_opts.separated_fields = v
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .collect();
+    }
     if let Some(v) = options.get(STORAGE_FORMAT) {
         base_table_opts.storage_format_hint = v.as_str().try_into()?;
     }
+    if let Some(v) = options.get(CACHE_PRIORITY) {
+        base_table_opts.cache_priority = v.parse().map_err(|msg| Error::ParseCachePriority {
+            msg,
+            backtrace: Backtrace::generate(),
+        })?;
+    }
+    if let Some(v) = options.get(TIMESTAMP_RESOLUTION) {
+        base_table_opts.timestamp_resolution = if v.is_empty() {
+            None
+        } else {
+            let resolution = parse_duration(v).context(ParseDuration)?;
+            ensure!(
+                resolution.0.as_millis() > 0,
+                InvalidTimestampResolution { resolution }
+            );
+            Some(resolution)
+        };
+    }
+    if let Some(v) = options.get(TIMESTAMP_ORIGINAL_COLUMN) {
+        base_table_opts.timestamp_original_column =
+            if v.is_empty() { None } else { Some(v.clone()) };
+    }
+    if let Some(v) = options.get(HOT_DURATION) {
+        base_table_opts.hot_duration = if v.is_empty() {
+            None
+        } else {
+            Some(parse_duration(v).context(ParseDuration)?)
+        };
+    }
+    if let Some(v) = options.get(TRANSFORM_PIPELINE) {
+        base_table_opts.transform_pipeline = if v.is_empty() { None } else { Some(v.clone()) };
+    }
     if let Some(v) = options.get(MEMTABLE_TYPE) {
         base_table_opts.memtable_type = MemtableType::parse_from(v);
     }
 
+    // The duplicate rows to merge must be adjacent.
+    ensure!(
+        base_table_opts.merge_policy == MergePolicy::LastWrite
+            || base_table_opts.memtable_type == MemtableType::SkipList,
+        UnsupportedMergePolicy {
+            merge_policy: base_table_opts.merge_policy.to_string(),
+            memtable_type: base_table_opts.memtable_type.to_string(),
+        }
+    );
+
     let layered_memtable_opts =
         LayeredMemtableOptions::parse_from(options).context(ParseLayeredMemtableOptions)?;
     base_table_opts.layered_memtable_opts = layered_memtable_opts;
@@ -805,3 +1915,332 @@ fn parse_size(v: &str) -> Result<ReadableSize> {
         backtrace: Backtrace::generate(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapted_num_rows_per_row_group() {
+        let mut opts = TableOptions {
+            num_rows_per_row_group: 8192,
+            ..Default::default()
+        };
+        for ratio in [None, Some(0.1), Some(0.5), Some(0.9)] {
+            assert_eq!(8192, opts.adapted_num_rows_per_row_group(ratio));
+        }
+
+        opts.row_group_size_policy = RowGroupSizePolicy::Adaptive;
+        assert_eq!(8192, opts.adapted_num_rows_per_row_group(None));
+        assert_eq!(2048, opts.adapted_num_rows_per_row_group(Some(0.1)));
+        assert_eq!(8192, opts.adapted_num_rows_per_row_group(Some(0.5)));
+        assert_eq!(32768, opts.adapted_num_rows_per_row_group(Some(0.9)));
+
+        opts.num_rows_per_row_group = MIN_NUM_ROWS_PER_ROW_GROUP;
+        assert_eq!(
+            MIN_NUM_ROWS_PER_ROW_GROUP,
+            opts.adapted_num_rows_per_row_group(Some(0.1))
+        );
+    }
+
+    #[test]
+    fn test_parse_row_group_options() {
+        let options = HashMap::from([
+            (ROW_GROUP_SIZE_POLICY.to_string(), "adaptive".to_string()),
+            (DATA_PAGE_SIZE.to_string(), "256KB".to_string()),
+        ]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(RowGroupSizePolicy::Adaptive, opts.row_group_size_policy);
+        assert_eq!(ReadableSize::kb(256), opts.data_page_size);
+
+        let options = HashMap::from([(ROW_GROUP_SIZE_POLICY.to_string(), "x".to_string())]);
+        assert!(TableOptions::from_map(&options, true).is_err());
+    }
+
+    #[test]
+    fn test_parse_bloom_filter_options() {
+        let opts = TableOptions::from_map(&HashMap::new(), true).unwrap();
+        assert!(opts.bloom_filter_options().is_none());
+        assert!(!opts.to_raw_map().contains_key(BLOOM_FILTER_FPP));
+
+        let options = HashMap::from([
+            (BLOOM_FILTER_FPP.to_string(), "0.01".to_string()),
+            (BLOOM_FILTER_SIZING.to_string(), "fixed".to_string()),
+        ]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        let expect = BloomFilterOptions {
+            fpp: 0.01,
+            sizing: BloomFilterSizing::Fixed,
+            recent_duration: None,
+        };
+        assert_eq!(Some(expect), opts.bloom_filter_options());
+        assert_eq!("0.01", opts.to_raw_map()[BLOOM_FILTER_FPP]);
+        assert_eq!("FIXED", opts.to_raw_map()[BLOOM_FILTER_SIZING]);
+
+        // Only the recent ssts are indexed.
+        let options = HashMap::from([(
+            BLOOM_FILTER_RECENT_DURATION.to_string(),
+            "7d".to_string(),
+        )]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        let bloom_filter = opts.bloom_filter_options().unwrap();
+        assert_eq!(Some(Duration::from_secs(7 * 24 * 3600)), bloom_filter.recent_duration);
+        assert_eq!("7d", opts.to_raw_map()[BLOOM_FILTER_RECENT_DURATION]);
+        let now = Timestamp::new(30 * 24 * 3600 * 1000);
+        let day_ms = 24 * 3600 * 1000;
+        let cold = TimeRange::new_unchecked(Timestamp::new(0), Timestamp::new(day_ms));
+        assert!(!bloom_filter.covers(cold, now));
+        let recent = TimeRange::new_unchecked(Timestamp::new(29 * day_ms), now);
+        assert!(bloom_filter.covers(recent, now));
+
+        let options = HashMap::from([(BLOOM_FILTER_FPP.to_string(), "".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert!(opts.bloom_filter_options().is_none());
+
+        let mut opts = TableOptions {
+            bloom_filter_fpp: Some(0.9),
+            ..Default::default()
+        };
+        opts.sanitize();
+        assert_eq!(Some(MAX_BLOOM_FILTER_FPP), opts.bloom_filter_fpp);
+
+        for (key, value) in [
+            (BLOOM_FILTER_FPP, "0"),
+            (BLOOM_FILTER_FPP, "1.5"),
+            (BLOOM_FILTER_FPP, "x"),
+            (BLOOM_FILTER_SIZING, "x"),
+        ] {
+            let options = HashMap::from([(key.to_string(), value.to_string())]);
+            assert!(TableOptions::from_map(&options, true).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_cache_priority() {
+        let opts = TableOptions::from_map(&HashMap::new(), true).unwrap();
+        assert_eq!(CachePriority::Normal, opts.cache_priority);
+
+        let options = HashMap::from([(CACHE_PRIORITY.to_string(), "high".to_string())]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(CachePriority::High, opts.cache_priority);
+        assert_eq!("HIGH", opts.to_raw_map()[CACHE_PRIORITY]);
+
+        let options = HashMap::from([(CACHE_PRIORITY.to_string(), "low".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert_eq!(CachePriority::Low, opts.cache_priority);
+
+        let options = HashMap::from([(CACHE_PRIORITY.to_string(), "x".to_string())]);
+        assert!(TableOptions::from_map(&options, true).is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp_resolution() {
+        let opts = TableOptions::from_map(&HashMap::new(), true).unwrap();
+        assert!(opts.timestamp_resolution.is_none());
+        assert!(!opts.to_raw_map().contains_key(TIMESTAMP_RESOLUTION));
+
+        let options = HashMap::from([
+            (TIMESTAMP_RESOLUTION.to_string(), "1s".to_string()),
+            (TIMESTAMP_ORIGINAL_COLUMN.to_string(), "raw_ts".to_string()),
+        ]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(Some(ReadableDuration::secs(1)), opts.timestamp_resolution);
+        assert_eq!("1s", opts.to_raw_map()[TIMESTAMP_RESOLUTION]);
+        assert_eq!("raw_ts", opts.to_raw_map()[TIMESTAMP_ORIGINAL_COLUMN]);
+
+        let options = HashMap::from([(TIMESTAMP_RESOLUTION.to_string(), "".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert!(opts.timestamp_resolution.is_none());
+
+        let options = HashMap::from([(TIMESTAMP_RESOLUTION.to_string(), "0s".to_string())]);
+        assert!(TableOptions::from_map(&options, true).is_err());
+    }
+
+    #[test]
+    fn test_parse_hot_duration() {
+        let opts = TableOptions::from_map(&HashMap::new(), true).unwrap();
+        assert!(opts.hot_duration.is_none());
+
+        let options = HashMap::from([(HOT_DURATION.to_string(), "7d".to_string())]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(Some(ReadableDuration::days(7)), opts.hot_duration);
+        assert_eq!("7d", opts.to_raw_map()[HOT_DURATION]);
+
+        let options = HashMap::from([(HOT_DURATION.to_string(), "".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert!(opts.hot_duration.is_none());
+    }
+    #[test]
+    fn test_parse_column_options() {
+        let opts = TableOptions::from_map(&HashMap::new(), true).unwrap();
+        assert!(opts.compression_level.is_none());
+        assert!(opts.column_options.is_empty());
+        assert!(!opts.to_raw_map().contains_key(COLUMN_ENCODING));
+
+        let options = HashMap::from([
+            (COMPRESSION_LEVEL.to_string(), "9".to_string()),
+            (
+                COLUMN_ENCODING.to_string(),
+                "host:dict, ts:delta,ok:rle".to_string(),
+            ),
+            (COLUMN_COMPRESSION.to_string(), "value:lz4".to_string()),
+        ]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(Some(9), opts.compression_level);
+        let expect = BTreeMap::from([
+            ("host".to_string(), ValueEncoding::Dict),
+            ("ok".to_string(), ValueEncoding::Rle),
+            ("ts".to_string(), ValueEncoding::Delta),
+        ]);
+        assert_eq!(expect, opts.column_options.encodings);
+        let expect = BTreeMap::from([("value".to_string(), Compression::Lz4)]);
+        assert_eq!(expect, opts.column_options.compressions);
+        assert_eq!("9", opts.to_raw_map()[COMPRESSION_LEVEL]);
+        assert_eq!(
+            "host:DICT,ok:RLE,ts:DELTA",
+            opts.to_raw_map()[COLUMN_ENCODING]
+        );
+        assert_eq!("value:LZ4", opts.to_raw_map()[COLUMN_COMPRESSION]);
+        assert!(!opts.adaptive_compression);
+        assert!(!opts.to_raw_map().contains_key(ADAPTIVE_COMPRESSION));
+        assert_eq!(
+            ParquetCompression::ZSTD(ZstdLevel::try_new(9).unwrap()),
+            opts.compression.to_parquet(opts.compression_level)
+        );
+
+        let options = HashMap::from([(COLUMN_ENCODING.to_string(), "".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert!(opts.column_options.encodings.is_empty());
+        assert!(!opts.column_options.compressions.is_empty());
+
+        let options = HashMap::from([(ADAPTIVE_COMPRESSION.to_string(), "true".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert!(opts.adaptive_compression);
+        assert_eq!("true", opts.to_raw_map()[ADAPTIVE_COMPRESSION]);
+
+        for (key, value) in [
+            (COMPRESSION_LEVEL, "0"),
+            (COMPRESSION_LEVEL, "23"),
+            (COLUMN_ENCODING, "host"),
+            (COLUMN_ENCODING, ":dict"),
+            (COLUMN_ENCODING, "host:x"),
+            (COLUMN_COMPRESSION, "value:x"),
+            (ADAPTIVE_COMPRESSION, "x"),
+        ] {
+            let options = HashMap::from([(key.to_string(), value.to_string())]);
+            assert!(TableOptions::from_map(&options, true).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_merge_policy() {
+        let opts = TableOptions::from_map(&HashMap::new(), true).unwrap();
+        assert_eq!(MergePolicy::LastWrite, opts.merge_policy);
+        assert_eq!(Some(MergePolicy::LastWrite), opts.dedup_merge_policy());
+        assert!(!opts.to_raw_map().contains_key(MERGE_POLICY));
+
+        let options = HashMap::from([(MERGE_POLICY.to_string(), "sum".to_string())]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(MergePolicy::Sum, opts.merge_policy);
+        assert_eq!("SUM", opts.to_raw_map()[MERGE_POLICY]);
+
+        // The policy can't be altered.
+        let options = HashMap::from([(MERGE_POLICY.to_string(), "max".to_string())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert_eq!(MergePolicy::Sum, opts.merge_policy);
+
+        let options = HashMap::from([
+            (MERGE_POLICY.to_string(), "max".to_string()),
+            (UPDATE_MODE.to_string(), "append".to_string()),
+        ]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(MergePolicy::Max, opts.merge_policy);
+        assert!(opts.dedup_merge_policy().is_none());
+
+        let options = HashMap::from([(MERGE_POLICY.to_string(), "x".to_string())]);
+        assert!(TableOptions::from_map(&options, true).is_err());
+        let options = HashMap::from([
+            (MERGE_POLICY.to_string(), "sum".to_string()),
+            (MEMTABLE_TYPE.to_string(), "columnar".to_string()),
+        ]);
+        assert!(TableOptions::from_map(&options, true).is_err());
+    }
+
+    #[test]
+    fn test_validate_column_options() {
+        let schema = common_types::tests::build_schema_with_dictionary();
+        let validate = |encodings: &str| {
+            let options = HashMap::from([(COLUMN_ENCODING.to_string(), encodings.to_string())]);
+            let opts = TableOptions::from_map(&options, true).unwrap();
+            opts.validate_with_schema(&schema)
+        };
+
+        assert!(validate("key2:delta,field1:plain,field2:delta,tag1:dict").is_ok());
+        assert!(validate("not_exist:dict").is_err());
+        assert!(validate("field1:delta").is_err());
+        assert!(validate("tag1:plain").is_err());
+    }
+
+    #[test]
+    fn test_validate_separated_fields() {
+        let schema = common_types::tests::build_schema_with_dictionary();
+        let validate = |fields: &str| {
+            let options = HashMap::from([(SEPARATED_FIELDS.to_string(), fields.to_string())]);
+            let opts = TableOptions::from_map(&options, true).unwrap();
+            opts.validate_with_schema(&schema)
+        };
+
+        assert!(validate("field2, field4").is_ok());
+        assert!(validate("not_exist").is_err());
+        assert!(validate("key1").is_err());
+        assert!(validate("field2,tag1").is_err());
+
+        let options = HashMap::from([(SEPARATED_FIELDS.to_string(), "field2, field4".to_string())]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert_eq!(vec!["field2", "field4"], opts.separated_fields);
+        assert_eq!("field2,field4", opts.to_raw_map()[SEPARATED_FIELDS]);
+
+        let options = HashMap::from([(SEPARATED_FIELDS.to_string(), String::new())]);
+        let opts = merge_table_options_for_alter(&options, &opts).unwrap();
+        assert!(opts.separated_fields.is_empty());
+        assert!(!opts.to_raw_map().contains_key(SEPARATED_FIELDS));
+    }
+
+    #[test]
+    fn test_with_altered_columns() {
+        let schema = common_types::tests::build_schema_with_dictionary();
+        // Rename the field2 to field5 and drop the field4.
+        let mut builder = common_types::schema::Builder::with_capacity(schema.num_columns())
+            .primary_key_indexes(schema.primary_key_indexes().to_vec())
+            .version(schema.version() + 1);
+        for (idx, column) in schema.columns().iter().enumerate() {
+            let mut column = column.clone();
+            match column.name.as_str() {
+                "field4" => continue,
+                "field2" => column.name = "field5".to_string(),
+                _ => (),
+            }
+            builder = if schema.is_primary_key_index(&idx) {
+                builder.add_key_column(column)
+            } else {
+                builder.add_normal_column(column)
+            }
+            .unwrap();
+        }
+        let new_schema = builder.build().unwrap();
+
+        let options = HashMap::from([
+            (SEPARATED_FIELDS.to_string(), "field2, field4".to_string()),
+            (COLUMN_ENCODING.to_string(), "field1:plain".to_string()),
+        ]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        let altered = opts.with_altered_columns(&schema, &new_schema).unwrap();
+        assert_eq!(vec!["field5"], altered.separated_fields);
+        assert_eq!(opts.column_options, altered.column_options);
+
+        // Nothing is changed if no option refers to the altered columns.
+        let options = HashMap::from([(COLUMN_ENCODING.to_string(), "field1:plain".to_string())]);
+        let opts = TableOptions::from_map(&options, true).unwrap();
+        assert!(opts.with_altered_columns(&schema, &new_schema).is_none());
+    }
+}
@@ -17,8 +17,25 @@
 
 //! Engine open test.
 
-use crate::tests::util::{
-    EngineBuildContext, MemoryEngineBuildContext, RocksDBEngineBuildContext, TestEnv,
+use std::path::Path;
+
+use common_types::{table::DEFAULT_SHARD_ID, time::Timestamp};
+use object_store::config::ObjectStoreOptions;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use wal::{
+    kv_encoder::LogBatchEncoder,
+    log_batch::MemoryPayload,
+    manager::WriteContext,
+};
+
+use crate::{
+    instance,
+    setup::STORE_DIR_NAME,
+    table::data::TableShardInfo,
+    tests::util::{
+        self, memory_ctxs, rocksdb_ctxs, EngineBuildContext, MemoryEngineBuildContext,
+        RocksDBEngineBuildContext, TestEnv,
+    },
 };
 
 #[test]
@@ -44,3 +61,207 @@ fn test_open_engine<T: EngineBuildContext>(engine_context: T) {
         test_ctx.reopen().await;
     });
 }
+
+#[test]
+fn test_repair_unreplayable_wal_rocks() {
+    for ctx in rocksdb_ctxs() {
+        test_repair_unreplayable_wal(ctx);
+    }
+}
+
+#[test]
+fn test_repair_unreplayable_wal_mem_wal() {
+    for ctx in memory_ctxs() {
+        test_repair_unreplayable_wal(ctx);
+    }
+}
+
+/// The wal entry failing to decode is skipped and quarantined in the repair
+/// mode, and the entries around it are still replayed.
+fn test_repair_unreplayable_wal<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+    test_ctx.config_mut().recovery.repair = true;
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let table_id = test_ctx.table(test_table).id();
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+            (
+                "key3",
+                Timestamp::new(start_ms),
+                "tag1-3",
+                13.0,
+                110.0,
+                "tag2-3",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows[..2]);
+        test_ctx.write_to_table(test_table, row_group).await;
+
+        // The header of the payload is invalid.
+        let shard_info = TableShardInfo::new(DEFAULT_SHARD_ID);
+        let location = instance::create_wal_location(table_id.as_u64(), shard_info);
+        let batch = LogBatchEncoder::create(location)
+            .encode(&MemoryPayload { val: u32::MAX })
+            .unwrap();
+        let bad_sequence = test_ctx
+            .data_wal()
+            .write(&WriteContext::default(), &batch)
+            .await
+            .unwrap();
+
+        let row_group = fixed_schema_table.rows_to_row_group(&rows[2..]);
+        test_ctx.write_to_table(test_table, row_group).await;
+
+        test_ctx.crash_and_reopen_with_tables(&[test_table]).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after repair",
+            test_table,
+            &rows,
+        )
+        .await;
+
+        let ObjectStoreOptions::Local(local_opts) = &test_ctx.config_mut().storage.object_store
+        else {
+            unreachable!()
+        };
+        let quarantine_path = Path::new(&local_opts.data_dir)
+            .join(STORE_DIR_NAME)
+            .join(format!("quarantine/wal/{table_id}/{bad_sequence}"));
+        assert!(quarantine_path.exists());
+
+        // The skip is persisted, so the table opens without the repair mode.
+        test_ctx.config_mut().recovery.repair = false;
+        test_ctx.crash_and_reopen_with_tables(&[test_table]).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after reopen",
+            test_table,
+            &rows,
+        )
+        .await;
+    });
+}
+
+#[test]
+fn test_replay_with_interleaved_crashes_rocks() {
+    for seed in 0..4 {
+        for ctx in rocksdb_ctxs() {
+            test_replay_with_interleaved_crashes(ctx, seed);
+        }
+    }
+}
+
+#[test]
+fn test_replay_with_interleaved_crashes_mem_wal() {
+    for seed in 0..4 {
+        for ctx in memory_ctxs() {
+            test_replay_with_interleaved_crashes(ctx, seed);
+        }
+    }
+}
+
+/// The logs of several tables are interleaved in the wal, and the tables are
+/// flushed and crashed at random points, including right after the replay.
+/// Every table must end up with all its rows exactly once after the replay.
+fn test_replay_with_interleaved_crashes<T: EngineBuildContext>(engine_context: T, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+    // The logs of every table are split into several batches by the replay.
+    test_ctx.config_mut().replay_batch_size = 3;
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let num_tables = rng.gen_range(1..4);
+        let table_names: Vec<_> = (0..num_tables)
+            .map(|idx| format!("test_replay_table{idx}"))
+            .collect();
+        let tables: Vec<_> = table_names.iter().map(|name| name.as_str()).collect();
+        let mut fixed_schema_tables = Vec::with_capacity(num_tables);
+        for name in &tables {
+            fixed_schema_tables.push(test_ctx.create_fixed_schema_table(name).await);
+        }
+
+        let start_ms = test_ctx.start_ms();
+        // The keys written to every table, which are ordered as the rows read.
+        let mut keys: Vec<Vec<String>> = vec![Vec::new(); num_tables];
+        for key_idx in 0..rng.gen_range(10..40) {
+            let idx = rng.gen_range(0..num_tables);
+            let key = format!("key{key_idx:04}");
+            let row = (
+                key.as_str(),
+                Timestamp::new(start_ms),
+                "tag1",
+                1.0,
+                2.0,
+                "tag2",
+            );
+            let row_group = fixed_schema_tables[idx].rows_to_row_group(&[row]);
+            test_ctx.write_to_table(tables[idx], row_group).await;
+            keys[idx].push(key);
+
+            if rng.gen_bool(0.2) {
+                test_ctx
+                    .flush_table(tables[rng.gen_range(0..num_tables)])
+                    .await;
+            }
+            if rng.gen_bool(0.1) {
+                test_ctx.crash_and_reopen_with_tables(&tables).await;
+            }
+        }
+
+        test_ctx.crash_and_reopen_with_tables(&tables).await;
+        // Crash again before anything is written after the replay.
+        test_ctx.crash_and_reopen_with_tables(&tables).await;
+        for (idx, name) in tables.iter().enumerate() {
+            let rows: Vec<_> = keys[idx]
+                .iter()
+                .map(|key| {
+                    (
+                        key.as_str(),
+                        Timestamp::new(start_ms),
+                        "tag1",
+                        1.0,
+                        2.0,
+                        "tag2",
+                    )
+                })
+                .collect();
+            util::check_read(
+                &test_ctx,
+                &fixed_schema_tables[idx],
+                "Test read after replay",
+                name,
+                &rows,
+            )
+            .await;
+        }
+    });
+}
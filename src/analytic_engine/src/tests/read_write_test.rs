@@ -21,11 +21,15 @@ use std::{thread, time};
 
 use common_types::time::Timestamp;
 use logger::info;
+use table_engine::table::{ImportRequest, WriteRequest};
 use wal::manager::WalsOpener;
 
 use crate::{
     table_options,
-    tests::util::{self, memory_ctxs, rocksdb_ctxs, EngineBuildContext, TestContext, TestEnv},
+    tests::{
+        table,
+        util::{self, memory_ctxs, rocksdb_ctxs, EngineBuildContext, TestContext, TestEnv},
+    },
 };
 
 #[test]
@@ -267,6 +271,138 @@ fn test_table_write_read<T: EngineBuildContext>(engine_context: T) {
     });
 }
 
+#[test]
+fn test_table_write_with_sequence_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_table_write_with_sequence(ctx);
+    }
+}
+
+fn test_table_write_with_sequence<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table1).await;
+        let table = test_ctx.table(test_table1);
+
+        let start_ms = test_ctx.start_ms();
+        let mut last_sequence = None;
+        for i in 0..3 {
+            let rows = [(
+                "key1",
+                Timestamp::new(start_ms + i),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            )];
+            let row_group = fixed_schema_table.rows_to_row_group(&rows);
+            let (num_rows, sequence) = table
+                .write_with_sequence(WriteRequest { row_group })
+                .await
+                .unwrap();
+            assert_eq!(1, num_rows);
+
+            // Every write is committed at its own sequence.
+            let sequence = sequence.unwrap();
+            assert!(last_sequence.map_or(true, |v| v < sequence));
+            last_sequence = Some(sequence);
+        }
+    });
+}
+
+#[test]
+fn test_table_import_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_table_import(ctx);
+    }
+}
+
+fn test_table_import<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let source_table = "test_import_source";
+        let target_table = "test_import_target";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(source_table).await;
+        test_ctx.create_fixed_schema_table(target_table).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows: [(&str, Timestamp, &str, f64, f64, &str); 3] = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-3",
+                13.0,
+                110.0,
+                "tag2-3",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(source_table, row_group).await;
+        let mut batches = test_ctx
+            .read_table(
+                source_table,
+                fixed_schema_table.new_read_all_request(Default::default()),
+            )
+            .await;
+        // The imported batches are not required to be sorted.
+        batches.reverse();
+
+        let num_rows = test_ctx
+            .table(target_table)
+            .import(ImportRequest { batches })
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), num_rows);
+
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read imported table",
+            target_table,
+            &rows,
+        )
+        .await;
+
+        // The imported sst is persisted in the manifest.
+        test_ctx.reopen_with_tables(&[target_table]).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read imported table after reopen",
+            target_table,
+            &rows,
+        )
+        .await;
+    });
+}
+
 #[test]
 fn test_table_write_get_rocks() {
     let rocksdb_ctxs = rocksdb_ctxs();
@@ -532,6 +668,260 @@ fn test_table_write_get_override_case<T: EngineBuildContext>(
     });
 }
 
+#[test]
+fn test_append_mode_table_reopen_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_append_mode_table_reopen(ctx);
+    }
+}
+
+#[test]
+fn test_append_mode_table_reopen_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_append_mode_table_reopen(ctx);
+    }
+}
+
+fn test_append_mode_table_reopen<T: EngineBuildContext>(engine_context: T) {
+    test_append_mode_table_reopen_case::<T>(FlushPoint::NoFlush, engine_context.clone());
+
+    test_append_mode_table_reopen_case::<T>(FlushPoint::AfterFirstWrite, engine_context.clone());
+
+    test_append_mode_table_reopen_case::<T>(FlushPoint::AfterOverwrite, engine_context.clone());
+
+    test_append_mode_table_reopen_case::<T>(FlushPoint::FirstAndOverwrite, engine_context);
+}
+
+/// The rows of the append mode table are not deduplicated, so the log entries
+/// already flushed must not be replayed after reopen, or the rows are
+/// duplicated.
+fn test_append_mode_table_reopen_case<T: EngineBuildContext>(
+    flush_point: FlushPoint,
+    engine_context: T,
+) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        info!(
+            "test_append_mode_table_reopen_case, flush_point:{:?}",
+            flush_point
+        );
+
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        let fixed_schema_table = test_ctx.create_append_mode_table(test_table1).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-3",
+                13.0,
+                110.0,
+                "tag2-3",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table1, row_group).await;
+
+        if let FlushPoint::AfterFirstWrite | FlushPoint::FirstAndOverwrite = flush_point {
+            test_ctx.flush_table(test_table1).await;
+        }
+
+        // Write the same keys again, which are kept as duplicated rows.
+        let row_group = fixed_schema_table.rows_to_row_group(&rows[1..]);
+        test_ctx.write_to_table(test_table1, row_group).await;
+
+        if let FlushPoint::AfterOverwrite | FlushPoint::FirstAndOverwrite = flush_point {
+            test_ctx.flush_table(test_table1).await;
+        }
+
+        let expect_num_rows = 5;
+        check_num_rows(&test_ctx, &fixed_schema_table, test_table1, expect_num_rows).await;
+
+        // Crash and reopen, only the rows not flushed should be replayed.
+        test_ctx.crash_and_reopen_with_tables(&[test_table1]).await;
+        check_num_rows(&test_ctx, &fixed_schema_table, test_table1, expect_num_rows).await;
+
+        // Reopen db twice, the rows should not be replayed again.
+        for _ in 0..2 {
+            test_ctx.reopen_with_tables(&[test_table1]).await;
+            check_num_rows(&test_ctx, &fixed_schema_table, test_table1, expect_num_rows).await;
+        }
+    });
+}
+
+#[test]
+fn test_table_import_crash_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_table_import_crash(ctx);
+    }
+}
+
+#[test]
+fn test_table_import_crash_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_table_import_crash(ctx);
+    }
+}
+
+fn test_table_import_crash<T: EngineBuildContext>(engine_context: T) {
+    test_table_import_crash_case::<T>(ImportCrashPoint::AfterImport, engine_context.clone());
+
+    test_table_import_crash_case::<T>(ImportCrashPoint::AfterWrite, engine_context.clone());
+
+    test_table_import_crash_case::<T>(ImportCrashPoint::AfterFlush, engine_context.clone());
+
+    test_table_import_crash_case::<T>(ImportCrashPoint::AfterCompact, engine_context.clone());
+
+    test_table_import_crash_case::<T>(ImportCrashPoint::FlushBeforeImport, engine_context);
+}
+
+#[derive(Debug)]
+enum ImportCrashPoint {
+    /// Crash right after the import is persisted.
+    AfterImport,
+    /// Crash after more rows are written following the import.
+    AfterWrite,
+    /// Crash after the memtable is flushed following the import.
+    AfterFlush,
+    /// Crash after the imported sst is compacted, with the rows written before
+    /// the import left in the memtable.
+    AfterCompact,
+    /// Flush before the import, and crash after more rows are written.
+    FlushBeforeImport,
+}
+
+/// The import persists a new sst and the version edit adding it, but the rows
+/// written before and after it must still be recovered from the wal after
+/// crash, without being replayed twice.
+fn test_table_import_crash_case<T: EngineBuildContext>(
+    crash_point: ImportCrashPoint,
+    engine_context: T,
+) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        info!(
+            "test_table_import_crash_case, crash_point:{:?}",
+            crash_point
+        );
+
+        test_ctx.open().await;
+
+        let source_table = "test_import_crash_source";
+        let test_table = "test_import_crash_target";
+        let fixed_schema_table = test_ctx.create_append_mode_table(source_table).await;
+        test_ctx.create_append_mode_table(test_table).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(source_table, row_group).await;
+        let batches = test_ctx
+            .read_table(
+                source_table,
+                fixed_schema_table.new_read_all_request(Default::default()),
+            )
+            .await;
+
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+        if let ImportCrashPoint::FlushBeforeImport = crash_point {
+            test_ctx.flush_table(test_table).await;
+        }
+
+        let num_rows = test_ctx
+            .table(test_table)
+            .import(ImportRequest { batches })
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), num_rows);
+
+        let mut expect_num_rows = rows.len() * 2;
+        match crash_point {
+            ImportCrashPoint::AfterImport => {}
+            ImportCrashPoint::AfterFlush => test_ctx.flush_table(test_table).await,
+            ImportCrashPoint::AfterCompact => test_ctx.compact_table(test_table).await,
+            ImportCrashPoint::AfterWrite | ImportCrashPoint::FlushBeforeImport => {
+                let row_group = fixed_schema_table.rows_to_row_group(&rows);
+                test_ctx.write_to_table(test_table, row_group).await;
+                expect_num_rows += rows.len();
+            }
+        }
+        check_num_rows(&test_ctx, &fixed_schema_table, test_table, expect_num_rows).await;
+
+        test_ctx.crash_and_reopen_with_tables(&[test_table]).await;
+        check_num_rows(&test_ctx, &fixed_schema_table, test_table, expect_num_rows).await;
+
+        // Crash again after the wal is replayed.
+        test_ctx.crash_and_reopen_with_tables(&[test_table]).await;
+        check_num_rows(&test_ctx, &fixed_schema_table, test_table, expect_num_rows).await;
+
+        test_ctx.reopen_with_tables(&[test_table]).await;
+        check_num_rows(&test_ctx, &fixed_schema_table, test_table, expect_num_rows).await;
+    });
+}
+
+async fn check_num_rows<T: WalsOpener>(
+    test_ctx: &TestContext<T>,
+    fixed_schema_table: &table::FixedSchemaTable,
+    table_name: &str,
+    expect: usize,
+) {
+    for read_opts in table::read_opts_list() {
+        let record_batches = test_ctx
+            .read_table(
+                table_name,
+                fixed_schema_table.new_read_all_request(read_opts),
+            )
+            .await;
+        let num_rows: usize = record_batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(expect, num_rows);
+    }
+}
+
 #[test]
 fn test_db_write_buffer_size_rocks() {
     let rocksdb_ctxs = rocksdb_ctxs();
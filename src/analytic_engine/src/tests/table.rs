@@ -168,22 +168,22 @@ pub fn read_opts_list() -> Vec<ReadOptions> {
         ReadOptions {
             batch_size: 1,
             read_parallelism: 1,
-            deadline: None,
+            ..Default::default()
         },
         ReadOptions {
             batch_size: 1,
             read_parallelism: 4,
-            deadline: None,
+            ..Default::default()
         },
         ReadOptions {
             batch_size: 100,
             read_parallelism: 1,
-            deadline: None,
+            ..Default::default()
         },
         ReadOptions {
             batch_size: 100,
             read_parallelism: 4,
-            deadline: None,
+            ..Default::default()
         },
     ]
 }
@@ -291,6 +291,14 @@ impl Builder {
         self
     }
 
+    pub fn update_mode(mut self, update_mode: table_options::UpdateMode) -> Self {
+        self.create_request.params.table_options.insert(
+            common_types::UPDATE_MODE.to_string(),
+            update_mode.to_string(),
+        );
+        self
+    }
+
     pub fn ttl(mut self, duration: ReadableDuration) -> Self {
         self.create_request
             .params
@@ -19,6 +19,7 @@
 
 use std::{collections::HashMap, future::Future, sync::Arc};
 
+use alloc_tracker::budget::MemoryBudget;
 use common_types::{
     datum::Datum,
     record_batch::RecordBatch,
@@ -40,18 +41,20 @@ use table_engine::{
         AlterSchemaRequest, FlushRequest, GetRequest, ReadRequest, Result, SchemaId, TableId,
         TableRef, WriteRequest,
     },
+    task::{self, TaskRegistry},
 };
 use tempfile::TempDir;
 use time_ext::ReadableDuration;
 use wal::{
     config::{Config as WalConfig, StorageConfig},
-    manager::{OpenedWals, WalRuntimes, WalsOpener},
+    manager::{OpenedWals, WalManagerRef, WalRuntimes, WalsOpener},
     rocksdb_impl::{config::RocksDBStorageConfig, manager::RocksDBWalsOpener},
     table_kv_impl::wal::MemWalsOpener,
 };
 
 use crate::{
     setup::{EngineBuilder, TableEngineContext},
+    table_options::UpdateMode,
     tests::table::{self, FixedSchemaTable, RowTuple},
     Config, RecoverMode,
 };
@@ -141,6 +144,7 @@ impl<T: WalsOpener> TestContext<T> {
             config: &self.config,
             engine_runtimes: self.runtimes.clone(),
             opened_wals: opened_wals.clone(),
+            memory_budget: Arc::new(MemoryBudget::default()),
         };
         self.opened_wals = Some(opened_wals);
 
@@ -162,6 +166,17 @@ impl<T: WalsOpener> TestContext<T> {
     }
 
     pub async fn reopen_with_tables(&mut self, tables: &[&str]) {
+        self.reopen_tables(tables, true).await
+    }
+
+    /// Reopen the engine without closing it, just like the process crashes,
+    /// so the data in the memtables is lost and must be recovered by the wal
+    /// replay.
+    pub async fn crash_and_reopen_with_tables(&mut self, tables: &[&str]) {
+        self.reopen_tables(tables, false).await
+    }
+
+    async fn reopen_tables(&mut self, tables: &[&str], close: bool) {
         let table_infos: Vec<_> = tables
             .iter()
             .map(|name| {
@@ -173,9 +188,11 @@ impl<T: WalsOpener> TestContext<T> {
             // Close all tables.
             self.name_to_tables.clear();
 
-            // Close engine.
+            // Close engine, or just drop it without flushing anything.
             let engine = self.engine.take().unwrap();
-            engine.close().await.unwrap();
+            if close {
+                engine.close().await.unwrap();
+            }
         }
 
         self.open().await;
@@ -329,6 +346,21 @@ impl<T: WalsOpener> TestContext<T> {
         fixed_schema_table
     }
 
+    pub async fn create_append_mode_table(&mut self, table_name: &str) -> FixedSchemaTable {
+        let fixed_schema_table = FixedSchemaTable::builder()
+            .schema_id(self.schema_id)
+            .table_name(table_name.to_string())
+            .table_id(self.next_table_id())
+            .ttl("7d".parse::<ReadableDuration>().unwrap())
+            .update_mode(UpdateMode::Append)
+            .build_fixed();
+
+        self.create_table(fixed_schema_table.create_request().clone())
+            .await;
+
+        fixed_schema_table
+    }
+
     async fn create_table(&mut self, create_request: CreateTableRequest) {
         let table_name = create_request.params.table_name.clone();
         let table = self.engine().create_table(create_request).await.unwrap();
@@ -454,6 +486,10 @@ impl<T> TestContext<T> {
     pub fn clone_engine(&self) -> TableEngineRef {
         self.engine.clone().unwrap()
     }
+
+    pub fn data_wal(&self) -> WalManagerRef {
+        self.opened_wals.as_ref().unwrap().data_wal.clone()
+    }
 }
 
 pub struct TestEnv {
@@ -510,7 +546,10 @@ impl Builder {
                 disk_cache_partition_bits: 0,
                 object_store: ObjectStoreOptions::Local(LocalOptions {
                     data_dir: dir.path().to_str().unwrap().to_string(),
+                    read_only: false,
                 }),
+                cold_object_store: None,
+                cold_disk_cache_capacity: ReadableSize::mb(0),
             },
             wal: WalConfig {
                 storage: StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
@@ -518,6 +557,7 @@ impl Builder {
                     ..Default::default()
                 })),
                 disable_data: false,
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -540,6 +580,7 @@ impl Builder {
                 compact_runtime: runtime.clone(),
                 default_runtime: runtime.clone(),
                 io_runtime: runtime,
+                task_registry: Arc::new(TaskRegistry::new(&task::Config::default())),
             }),
         }
     }
@@ -588,7 +629,10 @@ impl Default for RocksDBEngineBuildContext {
                 disk_cache_partition_bits: 0,
                 object_store: ObjectStoreOptions::Local(LocalOptions {
                     data_dir: dir.path().to_str().unwrap().to_string(),
+                    read_only: false,
                 }),
+                cold_object_store: None,
+                cold_disk_cache_capacity: ReadableSize::mb(0),
             },
             wal: WalConfig {
                 storage: StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
@@ -596,6 +640,7 @@ impl Default for RocksDBEngineBuildContext {
                     ..Default::default()
                 })),
                 disable_data: false,
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -621,7 +666,10 @@ impl Clone for RocksDBEngineBuildContext {
             disk_cache_partition_bits: 0,
             object_store: ObjectStoreOptions::Local(LocalOptions {
                 data_dir: dir.path().to_str().unwrap().to_string(),
+                read_only: false,
             }),
+            cold_object_store: None,
+            cold_disk_cache_capacity: ReadableSize::mb(0),
         };
 
         config.storage = storage;
@@ -631,6 +679,7 @@ impl Clone for RocksDBEngineBuildContext {
                 ..Default::default()
             })),
             disable_data: false,
+            ..Default::default()
         };
         Self {
             config,
@@ -685,11 +734,15 @@ impl Default for MemoryEngineBuildContext {
                 disk_cache_partition_bits: 0,
                 object_store: ObjectStoreOptions::Local(LocalOptions {
                     data_dir: dir.path().to_str().unwrap().to_string(),
+                    read_only: false,
                 }),
+                cold_object_store: None,
+                cold_disk_cache_capacity: ReadableSize::mb(0),
             },
             wal: WalConfig {
                 storage: StorageConfig::Obkv(Box::default()),
                 disable_data: false,
+                ..Default::default()
             },
             ..Default::default()
         };
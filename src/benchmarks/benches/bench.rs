@@ -26,7 +26,7 @@ use benchmarks::{
     parquet_bench::ParquetBench,
     scan_memtable_bench::ScanMemTableBench,
     sst_bench::SstBench,
-    wal_write_bench::WalWriteBench,
+    wal_write_bench::{WalGroupCommitBench, WalWriteBench},
 };
 use criterion::*;
 use pprof::criterion::{Output, PProfProfiler};
@@ -208,6 +208,31 @@ fn bench_wal_write(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_wal_group_commit_iter(b: &mut Bencher<'_>, bench: &WalGroupCommitBench) {
+    b.iter(|| bench.run_bench())
+}
+
+fn bench_wal_group_commit(c: &mut Criterion) {
+    let config = init_bench();
+
+    let mut group = c.benchmark_group("wal_group_commit");
+
+    group.measurement_time(config.wal_group_commit_bench.bench_measurement_time.0);
+    group.sample_size(config.wal_group_commit_bench.bench_sample_size);
+
+    for (group_commit, name) in [(false, "no_group_commit"), (true, "group_commit")] {
+        let bench = WalGroupCommitBench::new(config.wal_group_commit_bench.clone(), group_commit);
+
+        group.bench_with_input(
+            BenchmarkId::new("wal_group_commit", name),
+            &bench,
+            bench_wal_group_commit_iter,
+        );
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
@@ -217,6 +242,7 @@ criterion_group!(
     bench_scan_memtable,
     bench_merge_memtable,
     bench_wal_write,
+    bench_wal_group_commit,
 );
 
 criterion_main!(benches);
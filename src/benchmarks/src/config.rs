@@ -28,6 +28,7 @@ use table_engine::{
     table::TableId,
 };
 use time_ext::ReadableDuration;
+use wal::config::Compression;
 
 const BENCH_CONFIG_PATH_KEY: &str = "ANALYTIC_BENCH_CONFIG_PATH";
 
@@ -38,6 +39,7 @@ pub struct BenchConfig {
     pub scan_memtable_bench: ScanMemTableBenchConfig,
     pub merge_memtable_bench: MergeMemTableBenchConfig,
     pub wal_write_bench: WalWriteBenchConfig,
+    pub wal_group_commit_bench: WalGroupCommitBenchConfig,
 }
 
 // TODO(yingwen): Maybe we can use layze static to load config first.
@@ -147,3 +149,18 @@ pub struct WalWriteBenchConfig {
     pub batch_size: usize,
     pub value_size: usize,
 }
+
+#[derive(Clone, Deserialize)]
+pub struct WalGroupCommitBenchConfig {
+    pub bench_measurement_time: ReadableDuration,
+    pub bench_sample_size: usize,
+    pub data_dir: String,
+    pub runtime_thread_num: usize,
+    /// Number of the concurrent writers.
+    pub writers: usize,
+    pub batch_size: usize,
+    pub value_size: usize,
+    pub compression: Compression,
+    pub flush_interval: ReadableDuration,
+    pub max_batch_bytes: ReadableSize,
+}
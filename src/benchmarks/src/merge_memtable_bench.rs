@@ -38,6 +38,7 @@ use analytic_engine::{
         sst_util,
         version::{MemTableState, MemTableVec},
     },
+    table_options::MergePolicy,
     ScanType, SstReadOptionsBuilder,
 };
 use arena::NoopCollector;
@@ -172,6 +173,7 @@ impl MergeMemTableBench {
             merge_iter_options: iter_options.clone(),
             need_dedup: true,
             reverse: false,
+            corrupt_sst_skipper: None,
         });
 
         builder.mut_memtables().extend_from_slice(&self.memtables);
@@ -184,8 +186,12 @@ impl MergeMemTableBench {
             let mut batch_num = 0;
 
             if self.dedup {
-                let mut dedup_iter =
-                    DedupIterator::new(request_id.clone(), merge_iter, iter_options);
+                let mut dedup_iter = DedupIterator::new(
+                    request_id.clone(),
+                    merge_iter,
+                    iter_options,
+                    MergePolicy::LastWrite,
+                );
                 while let Some(batch) = dedup_iter.next_batch().await.unwrap() {
                     let num_rows = batch.num_rows();
                     total_rows += num_rows;
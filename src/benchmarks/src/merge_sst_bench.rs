@@ -34,6 +34,7 @@ use analytic_engine::{
         meta_data::cache::MetaCacheRef,
     },
     table::sst_util,
+    table_options::MergePolicy,
     ScanType, SstReadOptionsBuilder,
 };
 use common_types::{projected_schema::ProjectedSchema, request_id::RequestId, schema::Schema};
@@ -164,6 +165,7 @@ impl MergeSstBench {
             merge_iter_options: iter_options.clone(),
             need_dedup: true,
             reverse: false,
+            corrupt_sst_skipper: None,
         });
 
         builder
@@ -175,7 +177,12 @@ impl MergeSstBench {
             let begin_instant = Instant::now();
 
             let merge_iter = builder.build().await.unwrap();
-            let mut dedup_iter = DedupIterator::new(request_id, merge_iter, iter_options);
+            let mut dedup_iter = DedupIterator::new(
+                request_id,
+                merge_iter,
+                iter_options,
+                MergePolicy::LastWrite,
+            );
             let mut total_rows = 0;
             let mut batch_num = 0;
 
@@ -214,6 +221,7 @@ impl MergeSstBench {
             sst_read_options_builder: self.sst_read_options_builder.clone(),
             store_picker: &store_picker,
             num_streams_to_prefetch: 0,
+            corrupt_sst_skipper: None,
         })
         .ssts(vec![self.file_handles.clone()]);
 
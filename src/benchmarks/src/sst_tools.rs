@@ -39,7 +39,7 @@ use analytic_engine::{
         writer::{MetaData, RecordBatchStream},
     },
     table::sst_util,
-    table_options::{Compression, StorageFormatHint},
+    table_options::{Compression, MergePolicy, StorageFormatHint, DEFAULT_DATA_PAGE_SIZE},
     ScanType, SstReadOptionsBuilder,
 };
 use common_types::{
@@ -70,9 +70,16 @@ async fn create_sst_from_stream(config: SstConfig, record_batch_stream: RecordBa
     let sst_write_options = SstWriteOptions {
         storage_format_hint: StorageFormatHint::Auto,
         num_rows_per_row_group: config.num_rows_per_row_group,
+        data_page_size: DEFAULT_DATA_PAGE_SIZE as usize,
         compression: config.compression,
+        compression_level: None,
+        column_options: Default::default(),
+        adaptive_compression: false,
+        separated_fields: Vec::new(),
         max_buffer_size: 1024 * 1024 * 10,
         column_stats: Default::default(),
+        bloom_filter: None,
+        recompressed: false,
     };
 
     info!(
@@ -138,6 +145,7 @@ pub async fn rebuild_sst(config: RebuildSstConfig, runtime: Arc<Runtime>) {
         predicate: config.predicate.into_predicate(),
         meta_cache: None,
         scan_options,
+        scheduled_io: None,
         runtime,
         row_projector_builder,
     };
@@ -281,6 +289,7 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
             merge_iter_options: iter_options.clone(),
             need_dedup: true,
             reverse: false,
+            corrupt_sst_skipper: None,
             sst_read_options_builder: sst_read_options_builder.clone(),
         });
         builder
@@ -291,7 +300,12 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
     };
 
     let record_batch_stream = if config.dedup {
-        let iter = DedupIterator::new(request_id.clone(), iter, iter_options);
+        let iter = DedupIterator::new(
+            request_id.clone(),
+            iter,
+            iter_options,
+            MergePolicy::LastWrite,
+        );
         row_iter::record_batch_with_key_iter_to_stream(iter)
     } else {
         row_iter::record_batch_with_key_iter_to_stream(iter)
@@ -137,6 +137,7 @@ pub async fn load_sst_to_memtable(
         predicate: Arc::new(Predicate::empty()),
         meta_cache: None,
         scan_options,
+        scheduled_io: None,
         runtime,
         row_projector_builder,
     };
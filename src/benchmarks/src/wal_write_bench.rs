@@ -17,19 +17,22 @@
 
 //! WalManager write  bench.
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use rand::prelude::*;
 use runtime::Runtime;
 use table_kv::memory::MemoryImpl;
 use wal::{
+    config::Compression,
+    group_commit::GroupCommitWal,
     kv_encoder::LogBatchEncoder,
-    manager::{WalLocation, WalManager, WalRuntimes, WriteContext},
+    manager::{WalLocation, WalManager, WalManagerRef, WalRuntimes, WriteContext},
+    rocksdb_impl::manager::Builder,
     table_kv_impl::{model::NamespaceConfig, wal::WalNamespaceImpl},
 };
 
 use crate::{
-    config::WalWriteBenchConfig,
+    config::{WalGroupCommitBenchConfig, WalWriteBenchConfig},
     util::{self, WritePayload},
 };
 
@@ -51,25 +54,11 @@ impl WalWriteBench {
     }
 
     pub fn build_value_vec(&self) -> Vec<Vec<u8>> {
-        let value_size = match self.value_size < 128 {
-            true => 128,
-            false => self.value_size,
-        };
-
-        let mut values = Vec::with_capacity(self.batch_size);
-        for _ in 0..self.batch_size {
-            let value = self.random_value(value_size);
-            values.push(value);
-        }
-
-        values
+        build_values(self.batch_size, self.value_size)
     }
 
     pub fn random_value(&self, size: usize) -> Vec<u8> {
-        let mut value = vec![0u8; size - 4];
-        let mut rng = rand::thread_rng();
-        value.extend_from_slice(rng.next_u32().to_le_bytes().as_slice());
-        value
+        random_value(size)
     }
 
     pub fn run_bench(&self) {
@@ -102,3 +91,96 @@ impl WalWriteBench {
         });
     }
 }
+
+fn build_values(batch_size: usize, value_size: usize) -> Vec<Vec<u8>> {
+    let value_size = match value_size < 128 {
+        true => 128,
+        false => value_size,
+    };
+
+    let mut values = Vec::with_capacity(batch_size);
+    for _ in 0..batch_size {
+        let value = random_value(value_size);
+        values.push(value);
+    }
+
+    values
+}
+
+fn random_value(size: usize) -> Vec<u8> {
+    let mut value = vec![0u8; size - 4];
+    let mut rng = rand::thread_rng();
+    value.extend_from_slice(rng.next_u32().to_le_bytes().as_slice());
+    value
+}
+
+/// Bench of the concurrent writers writing to the RocksDB wal, with or without
+/// the group commit.
+pub struct WalGroupCommitBench {
+    writers: usize,
+    compression: Compression,
+    values: Arc<Vec<Vec<u8>>>,
+    wal: WalManagerRef,
+    runtime: Arc<Runtime>,
+}
+
+impl WalGroupCommitBench {
+    pub fn new(config: WalGroupCommitBenchConfig, group_commit: bool) -> Self {
+        let runtime = Arc::new(util::new_runtime(config.runtime_thread_num));
+        let wal_dir = if group_commit {
+            "group_commit"
+        } else {
+            "no_group_commit"
+        };
+        let wal = Builder::new(Path::new(&config.data_dir).join(wal_dir), runtime.clone())
+            .build()
+            .expect("should succeed to open RocksImpl");
+        let wal: WalManagerRef = if group_commit {
+            Arc::new(GroupCommitWal::new(
+                Arc::new(wal),
+                config.flush_interval.0,
+                config.max_batch_bytes.as_byte() as usize,
+                &runtime,
+            ))
+        } else {
+            Arc::new(wal)
+        };
+
+        WalGroupCommitBench {
+            writers: config.writers,
+            compression: config.compression,
+            values: Arc::new(build_values(config.batch_size, config.value_size)),
+            wal,
+            runtime,
+        }
+    }
+
+    pub fn run_bench(&self) {
+        self.runtime.block_on(async {
+            let handles: Vec<_> = (0..self.writers)
+                .map(|table_id| {
+                    let wal = self.wal.clone();
+                    let values = self.values.clone();
+                    let compression = self.compression;
+                    self.runtime.spawn(async move {
+                        let wal_encoder =
+                            LogBatchEncoder::create(WalLocation::new(1, table_id as u64))
+                                .with_compression(compression);
+                        let payloads = values.iter().map(|v| WritePayload(v));
+                        let log_batch = wal_encoder
+                            .encode_batch(payloads)
+                            .expect("should succeed to encode payload batch");
+
+                        wal.write(&WriteContext::default(), &log_batch)
+                            .await
+                            .unwrap();
+                    })
+                })
+                .collect();
+
+            for handle in handles {
+                handle.await.unwrap();
+            }
+        });
+    }
+}
@@ -17,7 +17,7 @@
 
 //! Schema contains one or more tables
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
 use common_types::table::ShardId;
@@ -187,6 +187,13 @@ pub enum Error {
 
     #[snafu(display("Table is not ready, err:{}", source))]
     TableNotReady { source: GenericError },
+
+    #[snafu(display(
+        "Failed to persist default table options, schema:{}, err:{}",
+        schema,
+        source
+    ))]
+    PersistDefaultTableOptions { schema: String, source: GenericError },
 }
 
 define_result!(Error);
@@ -410,4 +417,30 @@ pub trait Schema {
 
     /// Unregister table
     fn unregister_table(&self, table_name: &str);
+
+    /// Default options of the tables created in this schema, the tables
+    /// inherit them unless they are overridden by the create table request.
+    fn default_table_options(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    /// Replace the default options of the tables created in this schema, the
+    /// existing tables are not affected.
+    ///
+    /// The options are persisted before they take effect.
+    async fn set_default_table_options(&self, _options: HashMap<String, String>) -> Result<()> {
+        UnSupported {
+            msg: format!("set default table options of schema {}", self.name()),
+        }
+        .fail()
+    }
+
+    /// Exchange the tables bound to the two names atomically, the readers
+    /// find either both of the old tables or both of the swapped ones.
+    async fn swap_tables(&self, table: NameRef, other: NameRef) -> Result<()> {
+        UnSupported {
+            msg: format!("swap tables {table} and {other} of schema {}", self.name()),
+        }
+        .fail()
+    }
 }
@@ -15,6 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use catalog::{
     schema,
@@ -115,4 +117,19 @@ impl Schema for SchemaWithCluster {
     fn unregister_table(&self, table_name: &str) {
         self.internal.unregister_table(table_name)
     }
+
+    fn default_table_options(&self) -> HashMap<String, String> {
+        self.internal.default_table_options()
+    }
+
+    async fn set_default_table_options(
+        &self,
+        options: HashMap<String, String>,
+    ) -> schema::Result<()> {
+        self.internal.set_default_table_options(options).await
+    }
+
+    async fn swap_tables(&self, table: NameRef, other: NameRef) -> schema::Result<()> {
+        self.internal.swap_tables(table, other).await
+    }
 }
@@ -23,7 +23,11 @@ use catalog::{
     schema::NameRef,
     CatalogRef,
 };
-use system_catalog::{tables::Tables, SystemTableAdapter};
+use system_catalog::{
+    continuous_queries::ContinuousQueries, events::Events, replica_divergences::ReplicaDivergences,
+    scheduler::Scheduler, tables::Tables, tasks::Tasks, users::Users, SystemTableAdapter,
+};
+use table_engine::task::TaskRegistryRef;
 
 use crate::system_tables::{SystemTables, SystemTablesBuilder};
 
@@ -40,10 +44,16 @@ pub struct CatalogManagerImpl {
 }
 
 impl CatalogManagerImpl {
-    pub fn new(manager: ManagerRef) -> Self {
+    pub fn new(manager: ManagerRef, task_registry: TaskRegistryRef) -> Self {
         let mut system_tables_builder = SystemTablesBuilder::new();
         system_tables_builder = system_tables_builder
-            .insert_table(SystemTableAdapter::new(Tables::new(manager.clone())));
+            .insert_table(SystemTableAdapter::new(Tables::new(manager.clone())))
+            .insert_table(SystemTableAdapter::new(Events::default()))
+            .insert_table(SystemTableAdapter::new(ContinuousQueries::default()))
+            .insert_table(SystemTableAdapter::new(Scheduler::default()))
+            .insert_table(SystemTableAdapter::new(Tasks::new(task_registry)))
+            .insert_table(SystemTableAdapter::new(Users::default()))
+            .insert_table(SystemTableAdapter::new(ReplicaDivergences::default()));
         Self {
             system_tables: system_tables_builder.build(),
             user_catalog_manager: manager,
@@ -29,8 +29,8 @@ use catalog::{
     schema::{
         self, AllocateTableId, CatalogMismatch, CreateExistTable, CreateOptions,
         CreateTableRequest, CreateTableWithCause, DropOptions, DropTableRequest,
-        DropTableWithCause, NameRef, Schema, SchemaMismatch, SchemaRef, TooManyTable,
-        WriteTableMeta,
+        DropTableWithCause, NameRef, PersistDefaultTableOptions, Schema, SchemaMismatch,
+        SchemaRef, TableNotFound, TooManyTable, WriteTableMeta,
     },
     Catalog, CatalogRef,
 };
@@ -230,6 +230,8 @@ impl TableBasedManager {
             mutex: Mutex::new(()),
             catalog_table: self.catalog_table.clone(),
             table_seq_generator: TableSeqGenerator::default(),
+            default_table_options: Default::default(),
+            names_to_open: Default::default(),
         });
         // Use table seq of `sys_catalog` table as last table seq.
         schema
@@ -282,6 +284,7 @@ impl TableBasedManager {
                     catalog_name: consts::DEFAULT_CATALOG.to_string(),
                     schema_name: consts::DEFAULT_SCHEMA.to_string(),
                     schema_id,
+                    default_table_options: HashMap::new(),
                 },
                 &catalog,
             )
@@ -388,12 +391,15 @@ impl<'a> VisitorInner for VisitorInnerImpl<'a> {
                 })?;
 
         let schema_id = request.schema_id;
-        let schema = Arc::new(SchemaImpl::new(
-            &request.catalog_name,
-            &request.schema_name,
-            schema_id,
-            self.catalog_table.clone(),
-        ));
+        let schema = Arc::new(
+            SchemaImpl::new(
+                &request.catalog_name,
+                &request.schema_name,
+                schema_id,
+                self.catalog_table.clone(),
+            )
+            .with_default_table_options(request.default_table_options),
+        );
 
         // If schema exists, we overwrite it.
         catalog.insert_schema_into_memory(schema);
@@ -439,6 +445,14 @@ impl<'a> VisitorInner for VisitorInnerImpl<'a> {
             return Ok(());
         }
 
+        // The table is registered by the name in the sys catalog once opened, which
+        // differs from the name in the engine if the table is swapped.
+        schema
+            .names_to_open
+            .write()
+            .unwrap()
+            .insert(table_id, table_info.table_name.clone());
+
         // Collect table infos for later opening.
         self.table_infos.push(table_info);
 
@@ -517,6 +531,7 @@ impl Catalog for CatalogImpl {
             catalog_name: self.name.to_string(),
             schema_name: name.to_string(),
             schema_id,
+            default_table_options: HashMap::new(),
         };
 
         let schema_id = request.schema_id;
@@ -575,6 +590,10 @@ struct SchemaImpl {
     /// Sys catalog table
     catalog_table: Arc<SysCatalogTable>,
     table_seq_generator: TableSeqGenerator,
+    /// Default options of the tables created in this schema
+    default_table_options: RwLock<HashMap<String, String>>,
+    /// Names of the tables to open in the sys catalog
+    names_to_open: RwLock<HashMap<TableId, String>>,
 }
 
 impl SchemaImpl {
@@ -592,9 +611,16 @@ impl SchemaImpl {
             mutex: Mutex::new(()),
             catalog_table,
             table_seq_generator: TableSeqGenerator::default(),
+            default_table_options: Default::default(),
+            names_to_open: Default::default(),
         }
     }
 
+    fn with_default_table_options(self, options: HashMap<String, String>) -> Self {
+        *self.default_table_options.write().unwrap() = options;
+        self
+    }
+
     fn validate_schema_info(&self, catalog_name: &str, schema_name: &str) -> schema::Result<()> {
         ensure!(
             self.catalog_name == catalog_name,
@@ -659,6 +685,19 @@ impl SchemaImpl {
             .cloned()
     }
 
+    /// Info of the table bound to the name in the sys catalog.
+    fn bound_table_info(&self, name: NameRef, table: &TableRef) -> TableInfo {
+        TableInfo {
+            catalog_name: self.catalog_name.clone(),
+            schema_name: self.schema_name.clone(),
+            schema_id: self.schema_id,
+            table_name: name.to_string(),
+            table_id: table.id(),
+            engine: table.engine_type().to_string(),
+            state: TableState::Stable,
+        }
+    }
+
     async fn alloc_table_id<'a>(&self, name: NameRef<'a>) -> schema::Result<TableId> {
         let table_seq = self
             .table_seq_generator
@@ -689,8 +728,11 @@ struct SchemaTables {
 
 impl SchemaTables {
     fn insert(&mut self, table_id: TableId, table: TableRef) {
-        self.tables_by_name
-            .insert(table.name().to_string(), table.clone());
+        self.bind(table.name().to_string(), table_id, table);
+    }
+
+    fn bind(&mut self, name: String, table_id: TableId, table: TableRef) {
+        self.tables_by_name.insert(name, table.clone());
         self.tables_by_id.insert(table_id, table);
     }
 
@@ -699,6 +741,22 @@ impl SchemaTables {
             self.tables_by_id.remove(&table.id());
         }
     }
+
+    /// Bind each of the two names to the table of the other one, returns the
+    /// name not found if any.
+    fn swap<'a>(&mut self, table: NameRef<'a>, other: NameRef<'a>) -> Option<NameRef<'a>> {
+        let first = self.tables_by_name.get(table).cloned();
+        let second = self.tables_by_name.get(other).cloned();
+        match (first, second) {
+            (Some(first), Some(second)) => {
+                self.tables_by_name.insert(table.to_string(), second);
+                self.tables_by_name.insert(other.to_string(), first);
+                None
+            }
+            (None, _) => Some(table),
+            (_, None) => Some(other),
+        }
+    }
 }
 
 #[async_trait]
@@ -809,6 +867,10 @@ impl Schema for SchemaImpl {
         // FIXME(xikai): the engine should not be part of the DropRequest.
         request.engine = table.engine_type().to_string();
         let request = request.into_engine_drop_request(self.schema_id);
+        // The table may be bound to another name after swapping, the sys catalog
+        // is keyed by the bound name while the engine knows its own name only.
+        let mut engine_request = request.clone();
+        engine_request.table_name = table.name().to_string();
 
         // Prepare to drop table info in the sys_catalog.
         self.catalog_table
@@ -821,14 +883,14 @@ impl Schema for SchemaImpl {
 
         let dropped = opts
             .table_engine
-            .drop_table(request.clone())
+            .drop_table(engine_request.clone())
             .await
             .box_err()
             .context(DropTableWithCause)?;
 
         info!(
             "Table engine drop table successfully, request:{:?}, dropped:{}",
-            request, dropped
+            engine_request, dropped
         );
 
         // Update the drop table record into the sys_catalog_table.
@@ -865,12 +927,75 @@ impl Schema for SchemaImpl {
     }
 
     fn register_table(&self, table: TableRef) {
-        self.insert_table_into_memory(table.id(), table);
+        let table_id = table.id();
+        let name = self.names_to_open.write().unwrap().remove(&table_id);
+        match name {
+            Some(name) => self.tables.write().unwrap().bind(name, table_id, table),
+            None => self.insert_table_into_memory(table_id, table),
+        }
     }
 
     fn unregister_table(&self, table_name: &str) {
         self.remove_table_in_memory(table_name);
     }
+
+    fn default_table_options(&self) -> HashMap<String, String> {
+        self.default_table_options.read().unwrap().clone()
+    }
+
+    async fn set_default_table_options(
+        &self,
+        options: HashMap<String, String>,
+    ) -> schema::Result<()> {
+        // Lock schema and persist the options to the sys catalog table.
+        let _lock = self.mutex.lock().await;
+
+        let request = CreateSchemaRequest {
+            catalog_name: self.catalog_name.clone(),
+            schema_name: self.schema_name.clone(),
+            schema_id: self.schema_id,
+            default_table_options: options.clone(),
+        };
+        self.catalog_table
+            .update_schema(request)
+            .await
+            .box_err()
+            .context(PersistDefaultTableOptions {
+                schema: &self.schema_name,
+            })?;
+
+        *self.default_table_options.write().unwrap() = options;
+        Ok(())
+    }
+
+    async fn swap_tables(&self, table: NameRef, other: NameRef) -> schema::Result<()> {
+        info!("Table based catalog manager swap tables, table:{table}, other:{other}");
+
+        // Serialize with the creating and dropping of the tables.
+        let _lock = self.mutex.lock().await;
+        let first = self
+            .find_table_by_name(table)
+            .context(TableNotFound { table })?;
+        let second = self
+            .find_table_by_name(other)
+            .context(TableNotFound { table: other })?;
+
+        // Persist the swapped bindings before exposing them, so they survive the
+        // restart.
+        self.catalog_table
+            .swap_tables(
+                self.bound_table_info(table, &first),
+                self.bound_table_info(other, &second),
+            )
+            .await
+            .box_err()
+            .context(WriteTableMeta { table })?;
+
+        let not_found = self.tables.write().unwrap().swap(table, other);
+        assert!(not_found.is_none());
+
+        Ok(())
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -1172,4 +1297,68 @@ mod tests {
             assert!(schema.table_by_name(table_name).unwrap().is_none());
         }
     }
+
+    #[tokio::test]
+    async fn test_swap_tables_rocks() {
+        let rocksdb_ctx = RocksDBEngineBuildContext::default();
+        test_swap_tables(rocksdb_ctx).await;
+    }
+
+    async fn test_swap_tables<T: EngineBuildContext>(engine_context: T) {
+        let env = TestEnv::builder().build();
+        let mut test_ctx = env.new_context(engine_context);
+        test_ctx.open().await;
+
+        let engine = test_ctx.engine().clone();
+        let engine_proxy = Arc::new(TableEngineProxy {
+            memory: MemoryTableEngine,
+            analytic: engine.clone(),
+        });
+
+        let catalog_manager = build_catalog_manager(engine.clone()).await;
+        let schema = build_default_schema_with_catalog(&catalog_manager).await;
+        let opts = CreateOptions {
+            table_engine: engine_proxy.clone(),
+            create_if_not_exists: false,
+        };
+        for table_name in ["live", "backfill"] {
+            let request = build_create_table_req(table_name, schema.clone()).await;
+            schema.create_table(request, opts.clone()).await.unwrap();
+        }
+        let live_id = schema.table_by_name("live").unwrap().unwrap().id();
+        let backfill_id = schema.table_by_name("backfill").unwrap().unwrap().id();
+
+        schema.swap_tables("live", "backfill").await.unwrap();
+        assert_eq!(backfill_id, schema.table_by_name("live").unwrap().unwrap().id());
+        assert_eq!(live_id, schema.table_by_name("backfill").unwrap().unwrap().id());
+        assert!(schema.swap_tables("live", "not_exist").await.is_err());
+        assert!(schema.swap_tables("not_exist", "live").await.is_err());
+
+        // The swapped tables are bound to the same names after reloading.
+        let mut reloaded = build_catalog_manager(engine.clone()).await;
+        let table_infos = reloaded.fetch_table_infos().await.unwrap();
+        let live = table_infos.iter().find(|v| v.table_name == "live").unwrap();
+        assert_eq!(backfill_id, live.table_id);
+        let reloaded_schema = build_default_schema_with_catalog(&reloaded).await;
+        for table_name in ["live", "backfill"] {
+            let table = schema.table_by_name(table_name).unwrap().unwrap();
+            reloaded_schema.register_table(table);
+        }
+        let live = reloaded_schema.table_by_name("live").unwrap().unwrap();
+        assert_eq!(backfill_id, live.id());
+
+        // Drop the old live table, which is bound to the backfill name now.
+        let request = DropTableRequest {
+            catalog_name: DEFAULT_CATALOG.to_string(),
+            schema_name: schema.name().to_string(),
+            table_name: "backfill".to_string(),
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        };
+        let opts = DropOptions {
+            table_engine: engine_proxy,
+        };
+        assert!(schema.drop_table(request, opts).await.unwrap());
+        assert!(schema.table_by_name("backfill").unwrap().is_none());
+        assert_eq!(backfill_id, schema.table_by_name("live").unwrap().unwrap().id());
+    }
 }
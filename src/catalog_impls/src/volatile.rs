@@ -427,4 +427,7 @@ impl Schema for SchemaImpl {
     fn unregister_table(&self, table_name: &str) {
         let _ = self.remove_table(table_name);
     }
+
+    // The schemas are managed by the horaemeta, which has no place to persist
+    // the default table options yet, so setting them is unsupported.
 }
@@ -0,0 +1,89 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Channel pool
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use snafu::ResultExt;
+use tokio::sync::OnceCell;
+use tonic::transport::{Channel, Endpoint as TonicEndpoint};
+
+use crate::{
+    config::Config,
+    error::{BuildChannel, Result},
+};
+
+/// Pool for reusing the built channels
+///
+/// Only one channel is built for an endpoint, and all the requests to the
+/// endpoint are multiplexed on it.
+pub(crate) struct ChannelPool {
+    channels: RwLock<HashMap<String, Arc<OnceCell<Channel>>>>,
+    config: Config,
+}
+
+impl ChannelPool {
+    pub fn new(config: Config) -> Self {
+        Self {
+            channels: RwLock::new(HashMap::new()),
+            config,
+        }
+    }
+
+    pub async fn get(&self, endpoint: &str) -> Result<Channel> {
+        let cell = {
+            let channels = self.channels.read().unwrap();
+            channels.get(endpoint).cloned()
+        };
+        let cell = match cell {
+            Some(v) => v,
+            None => {
+                let mut channels = self.channels.write().unwrap();
+                channels.entry(endpoint.to_string()).or_default().clone()
+            }
+        };
+
+        // The cell is left uninitialized if the building fails, so the channel will
+        // be built again by the next request.
+        cell.get_or_try_init(|| self.build(endpoint))
+            .await
+            .cloned()
+    }
+
+    async fn build(&self, endpoint: &str) -> Result<Channel> {
+        let formatted_endpoint = format!("http://{endpoint}");
+        let configured_endpoint = TonicEndpoint::from_shared(formatted_endpoint)
+            .context(BuildChannel {
+                endpoint,
+                msg: "invalid endpoint",
+            })?
+            .connect_timeout(self.config.connect_timeout.0)
+            .timeout(self.config.rpc_timeout.0)
+            .keep_alive_timeout(self.config.channel_keep_alive_timeout.0)
+            .http2_keep_alive_interval(self.config.channel_keep_alive_interval.0)
+            .keep_alive_while_idle(true);
+
+        configured_endpoint.connect().await.context(BuildChannel {
+            endpoint,
+            msg: "connect failed",
+        })
+    }
+}
@@ -0,0 +1,616 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Client of the storage service
+
+use std::{collections::HashMap, time::Duration};
+
+use arrow_ext::ipc::{self, CompressionMethod};
+use futures::future;
+use horaedbproto::storage::{
+    arrow_payload, sql_query_response, storage_service_client::StorageServiceClient,
+    RequestContext, SqlQueryRequest as SqlQueryRequestPb, WriteRequest as WriteRequestPb,
+};
+use logger::warn;
+use serde::Deserialize;
+use snafu::ResultExt;
+use tonic::metadata::{AsciiMetadataValue, MetadataMap};
+
+use crate::{
+    channel::ChannelPool,
+    config::Config,
+    error::{self, DecodeRecordBatches, Error, InvalidCredentials, InvalidResponse, Result, Rpc},
+    model::{self, Point, SqlQueryRequest, SqlQueryResponse, WriteRequest, WriteResult},
+    router::Router,
+};
+
+/// Metadata key of the credentials.
+const AUTHORIZATION_KEY: &str = "authorization";
+/// Metadata key of the status of the write, see `proxy::WRITE_STATUS_KEY`.
+const WRITE_STATUS_KEY: &str = "x-horaedb-write-status-bin";
+
+/// Builder of the [Client]
+#[derive(Debug)]
+pub struct Builder {
+    endpoint: String,
+    database: String,
+    config: Config,
+    authorization: Option<String>,
+}
+
+impl Builder {
+    /// The `endpoint` is `<ip>:<grpc port>` of any node of the cluster, which
+    /// serves the routes of the tables.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            database: "public".to_string(),
+            config: Config::default(),
+            authorization: None,
+        }
+    }
+
+    pub fn database(mut self, database: impl Into<String>) -> Self {
+        self.database = database.into();
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Authenticate the requests by the user and the password, which is
+    /// required once the authentication of the servers is enabled.
+    pub fn basic_auth(mut self, user: &str, password: &str) -> Self {
+        let credentials = base64::encode(format!("{user}:{password}"));
+        self.authorization = Some(format!("Basic {credentials}"));
+        self
+    }
+
+    /// Authenticate the requests by the token.
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.authorization = Some(format!("Bearer {token}"));
+        self
+    }
+
+    /// Build the client, and the connections are built by the first requests.
+    pub fn build(self) -> Result<Client> {
+        let authorization = match self.authorization {
+            Some(v) => match v.parse() {
+                Ok(v) => Some(v),
+                Err(_) => {
+                    return InvalidCredentials {
+                        msg: "credentials contain invalid characters",
+                    }
+                    .fail()
+                }
+            },
+            None => None,
+        };
+
+        Ok(Client {
+            database: self.database,
+            channel_pool: ChannelPool::new(self.config.clone()),
+            router: Router::new(self.endpoint),
+            config: self.config,
+            authorization,
+        })
+    }
+}
+
+/// Client sending the requests to the nodes owning the tables.
+pub struct Client {
+    database: String,
+    config: Config,
+    channel_pool: ChannelPool,
+    router: Router,
+    authorization: Option<AsciiMetadataValue>,
+}
+
+impl Client {
+    /// Write the points, which are sent to their nodes concurrently.
+    ///
+    /// Only the points sent to the nodes failed with the retryable errors are
+    /// retried, see [Config::retry_writes], so the points may be partially
+    /// written if an error is returned.
+    pub async fn write(&self, req: &WriteRequest) -> Result<WriteResult> {
+        let mut result = WriteResult::default();
+        let mut pending: Vec<&Point> = req.points.iter().collect();
+        let mut attempts = 0;
+        while !pending.is_empty() {
+            let mut tables: Vec<_> = pending.iter().map(|v| v.table.as_str()).collect();
+            tables.sort_unstable();
+            tables.dedup();
+            let routes = self
+                .router
+                .route(
+                    &self.channel_pool,
+                    self.authorization.as_ref(),
+                    &self.database,
+                    &tables,
+                )
+                .await?;
+
+            let mut points_by_endpoint: HashMap<&str, Vec<&Point>> = HashMap::new();
+            for point in pending {
+                let endpoint = routes
+                    .get(&point.table)
+                    .map(String::as_str)
+                    .unwrap_or_else(|| self.router.bootstrap_endpoint());
+                points_by_endpoint.entry(endpoint).or_default().push(point);
+            }
+            let writes = points_by_endpoint
+                .into_iter()
+                .map(|(endpoint, points)| async move {
+                    let res = self.write_to_endpoint(endpoint, &points).await;
+                    (points, res)
+                });
+
+            pending = Vec::new();
+            attempts += 1;
+            let mut retry_after = Duration::ZERO;
+            for (points, res) in future::join_all(writes).await {
+                match res {
+                    Ok(v) => result.merge(v),
+                    Err(e) => {
+                        self.handle_error(&e, points.iter().map(|v| v.table.as_str()));
+                        let retryable = if self.config.retry_writes {
+                            e.is_retryable()
+                        } else {
+                            e.is_write_rejected()
+                        };
+                        if !retryable || attempts > self.config.max_retries {
+                            return Err(e);
+                        }
+                        warn!("Retry the write, points:{}, err:{e}", points.len());
+                        retry_after = retry_after.max(e.retry_after().unwrap_or_default());
+                        pending.extend(points);
+                    }
+                }
+            }
+            if !pending.is_empty() {
+                // Wait for the delay suggested by the servers, e.g. the write
+                // quota is refilled, if it's longer than the backoff.
+                let backoff = self.config.backoff_of(attempts).max(retry_after);
+                tokio::time::sleep(backoff).await;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Execute the sql, which is sent to the node owning the first table of the
+    /// request.
+    pub async fn sql_query(&self, req: &SqlQueryRequest) -> Result<SqlQueryResponse> {
+        let mut attempts = 0;
+        loop {
+            let endpoint = match req.tables.first() {
+                Some(table) => self
+                    .router
+                    .route(
+                        &self.channel_pool,
+                        self.authorization.as_ref(),
+                        &self.database,
+                        &[table.as_str()],
+                    )
+                    .await?
+                    .remove(table)
+                    .unwrap_or_else(|| self.router.bootstrap_endpoint().to_string()),
+                None => self.router.bootstrap_endpoint().to_string(),
+            };
+
+            attempts += 1;
+            match self.query_endpoint(&endpoint, req).await {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    self.handle_error(&e, req.tables.iter().map(String::as_str));
+                    if !e.is_retryable() || attempts > self.config.max_retries {
+                        return Err(e);
+                    }
+                    warn!("Retry the sql query, sql:{}, err:{e}", req.sql);
+                }
+            }
+            tokio::time::sleep(self.config.backoff_of(attempts)).await;
+        }
+    }
+
+    fn handle_error<'a>(&self, e: &Error, tables: impl IntoIterator<Item = &'a str>) {
+        if e.is_route_stale() {
+            self.router.evict(tables);
+        }
+    }
+
+    async fn write_to_endpoint(&self, endpoint: &str, points: &[&Point]) -> Result<WriteResult> {
+        let channel = self.channel_pool.get(endpoint).await?;
+        let request = WriteRequestPb {
+            context: Some(self.request_context()),
+            table_requests: model::build_table_requests(points.iter().copied()),
+        };
+        let response = StorageServiceClient::new(channel)
+            .write(new_request(request, self.authorization.as_ref()))
+            .await
+            .context(Rpc { endpoint })?;
+        let retry_after = retry_after_of(response.metadata());
+        let response = response.into_inner();
+        error::check_header(endpoint, response.header, retry_after)?;
+
+        Ok(WriteResult {
+            success: response.success,
+            failed: response.failed,
+        })
+    }
+
+    async fn query_endpoint(
+        &self,
+        endpoint: &str,
+        req: &SqlQueryRequest,
+    ) -> Result<SqlQueryResponse> {
+        let channel = self.channel_pool.get(endpoint).await?;
+        let request = SqlQueryRequestPb {
+            context: Some(self.request_context()),
+            tables: req.tables.clone(),
+            sql: req.sql.clone(),
+        };
+        let response = StorageServiceClient::new(channel)
+            .sql_query(new_request(request, self.authorization.as_ref()))
+            .await
+            .context(Rpc { endpoint })?
+            .into_inner();
+        error::check_header(endpoint, response.header, None)?;
+
+        match response.output {
+            Some(sql_query_response::Output::AffectedRows(affected_rows)) => Ok(SqlQueryResponse {
+                affected_rows,
+                record_batches: Vec::new(),
+            }),
+            Some(sql_query_response::Output::Arrow(payload)) => {
+                let compression = match payload.compression() {
+                    arrow_payload::Compression::None => CompressionMethod::None,
+                    arrow_payload::Compression::Zstd => CompressionMethod::Zstd,
+                };
+                let mut record_batches = Vec::new();
+                for bytes in payload.record_batches {
+                    let batches = ipc::decode_record_batches(bytes, compression)
+                        .context(DecodeRecordBatches { endpoint })?;
+                    record_batches.extend(batches);
+                }
+
+                Ok(SqlQueryResponse {
+                    affected_rows: 0,
+                    record_batches,
+                })
+            }
+            None => InvalidResponse {
+                endpoint,
+                msg: "output is missing",
+            }
+            .fail(),
+        }
+    }
+
+    fn request_context(&self) -> RequestContext {
+        RequestContext {
+            database: self.database.clone(),
+        }
+    }
+}
+
+/// Build the request carrying the credentials.
+pub(crate) fn new_request<T>(
+    message: T,
+    authorization: Option<&AsciiMetadataValue>,
+) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    if let Some(v) = authorization {
+        request.metadata_mut().insert(AUTHORIZATION_KEY, v.clone());
+    }
+    request
+}
+
+/// Part of the status of the write returned in the metadata.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WriteStatus {
+    retry_after_ms: Option<u64>,
+}
+
+/// Delay before retrying the write suggested by the server, which is set if
+/// the write is rejected by the admission control.
+fn retry_after_of(metadata: &MetadataMap) -> Option<Duration> {
+    let buf = metadata.get_bin(WRITE_STATUS_KEY)?.to_bytes().ok()?;
+    let status: WriteStatus = serde_json::from_slice(&buf).ok()?;
+    status.retry_after_ms.map(Duration::from_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Instant,
+    };
+
+    use async_trait::async_trait;
+    use futures::{stream, stream::BoxStream};
+    use horaedbproto::{
+        common::ResponseHeader,
+        storage::{
+            storage_service_server::{StorageService, StorageServiceServer},
+            Endpoint, PrometheusQueryRequest, PrometheusQueryResponse,
+            PrometheusRemoteQueryRequest, PrometheusRemoteQueryResponse, Route, RouteRequest,
+            RouteResponse, SqlQueryResponse, WriteResponse,
+        },
+    };
+    use time_ext::ReadableDuration;
+    use tokio::net::TcpListener;
+    use tonic::{metadata::MetadataValue, transport::Server};
+
+    use super::*;
+    use crate::model::{PointBuilder, Value};
+
+    /// Node of the cluster serving the routes and the writes.
+    #[derive(Default)]
+    struct MockNode {
+        /// Table -> endpoint returned by the route requests.
+        routes: Mutex<HashMap<String, String>>,
+        /// Codes of the response headers and the retry after of the next
+        /// writes, and the writes succeed once it's drained.
+        write_failures: Mutex<VecDeque<(u32, Option<u64>)>>,
+        route_requests: AtomicUsize,
+        write_requests: AtomicUsize,
+        authorizations: Mutex<Vec<String>>,
+    }
+
+    impl MockNode {
+        fn record_authorization<T>(&self, req: &tonic::Request<T>) {
+            let authorization = req
+                .metadata()
+                .get(AUTHORIZATION_KEY)
+                .map(|v| v.to_str().unwrap().to_string())
+                .unwrap_or_default();
+            self.authorizations.lock().unwrap().push(authorization);
+        }
+    }
+
+    #[async_trait]
+    impl StorageService for MockNode {
+        type StreamSqlQueryStream =
+            BoxStream<'static, std::result::Result<SqlQueryResponse, tonic::Status>>;
+
+        async fn route(
+            &self,
+            req: tonic::Request<RouteRequest>,
+        ) -> std::result::Result<tonic::Response<RouteResponse>, tonic::Status> {
+            self.record_authorization(&req);
+            self.route_requests.fetch_add(1, Ordering::Relaxed);
+            let all_routes = self.routes.lock().unwrap();
+            let routes = req
+                .into_inner()
+                .tables
+                .into_iter()
+                .filter_map(|table| {
+                    let (ip, port) = all_routes.get(&table)?.split_once(':')?;
+                    Some(Route {
+                        table,
+                        endpoint: Some(Endpoint {
+                            ip: ip.to_string(),
+                            port: port.parse().unwrap(),
+                        }),
+                    })
+                })
+                .collect();
+
+            Ok(tonic::Response::new(RouteResponse {
+                header: Some(ResponseHeader {
+                    code: 200,
+                    error: String::new(),
+                }),
+                routes,
+            }))
+        }
+
+        async fn write(
+            &self,
+            req: tonic::Request<WriteRequestPb>,
+        ) -> std::result::Result<tonic::Response<WriteResponse>, tonic::Status> {
+            self.record_authorization(&req);
+            self.write_requests.fetch_add(1, Ordering::Relaxed);
+            let num_rows = req
+                .get_ref()
+                .table_requests
+                .iter()
+                .flat_map(|v| &v.entries)
+                .map(|v| v.field_groups.len() as u32)
+                .sum();
+
+            let failure = self.write_failures.lock().unwrap().pop_front();
+            let resp = match failure {
+                Some((code, retry_after_ms)) => {
+                    let mut resp = tonic::Response::new(WriteResponse {
+                        header: Some(ResponseHeader {
+                            code,
+                            error: "mock failure".to_string(),
+                        }),
+                        ..Default::default()
+                    });
+                    if let Some(v) = retry_after_ms {
+                        let status = format!("{{\"tables\":[],\"retry_after_ms\":{v}}}");
+                        resp.metadata_mut().insert_bin(
+                            WRITE_STATUS_KEY,
+                            MetadataValue::from_bytes(status.as_bytes()),
+                        );
+                    }
+                    resp
+                }
+                None => tonic::Response::new(WriteResponse {
+                    header: Some(ResponseHeader {
+                        code: 200,
+                        error: String::new(),
+                    }),
+                    success: num_rows,
+                    failed: 0,
+                }),
+            };
+
+            Ok(resp)
+        }
+
+        async fn sql_query(
+            &self,
+            _req: tonic::Request<SqlQueryRequestPb>,
+        ) -> std::result::Result<tonic::Response<SqlQueryResponse>, tonic::Status> {
+            Err(tonic::Status::unimplemented("sql query"))
+        }
+
+        async fn prom_remote_query(
+            &self,
+            _req: tonic::Request<PrometheusRemoteQueryRequest>,
+        ) -> std::result::Result<tonic::Response<PrometheusRemoteQueryResponse>, tonic::Status>
+        {
+            Err(tonic::Status::unimplemented("prom remote query"))
+        }
+
+        async fn prom_query(
+            &self,
+            _req: tonic::Request<PrometheusQueryRequest>,
+        ) -> std::result::Result<tonic::Response<PrometheusQueryResponse>, tonic::Status> {
+            Err(tonic::Status::unimplemented("prom query"))
+        }
+
+        async fn stream_write(
+            &self,
+            _req: tonic::Request<tonic::Streaming<WriteRequestPb>>,
+        ) -> std::result::Result<tonic::Response<WriteResponse>, tonic::Status> {
+            Err(tonic::Status::unimplemented("stream write"))
+        }
+
+        async fn stream_sql_query(
+            &self,
+            _req: tonic::Request<SqlQueryRequestPb>,
+        ) -> std::result::Result<tonic::Response<Self::StreamSqlQueryStream>, tonic::Status>
+        {
+            Err(tonic::Status::unimplemented("stream sql query"))
+        }
+    }
+
+    /// Serve the node on a random port, and return its endpoint.
+    async fn start_node(node: Arc<MockNode>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = listener.local_addr().unwrap().to_string();
+        let incoming = stream::unfold(listener, |listener| async move {
+            let conn = listener.accept().await.map(|(v, _)| v);
+            Some((conn, listener))
+        });
+        tokio::spawn(
+            Server::builder()
+                .add_service(StorageServiceServer::from_arc(node))
+                .serve_with_incoming(incoming),
+        );
+
+        endpoint
+    }
+
+    fn new_write_request(table: &str) -> WriteRequest {
+        let point = PointBuilder::new(table)
+            .timestamp(1)
+            .tag("host", Value::String("host1".to_string()))
+            .field("value", Value::Double(1.0))
+            .build()
+            .unwrap();
+        let mut req = WriteRequest::default();
+        req.add_point(point);
+        req
+    }
+
+    #[tokio::test]
+    async fn test_write_with_mock_servers() {
+        let bootstrap = Arc::new(MockNode::default());
+        let owner = Arc::new(MockNode::default());
+        let new_owner = Arc::new(MockNode::default());
+        let bootstrap_endpoint = start_node(bootstrap.clone()).await;
+        let owner_endpoint = start_node(owner.clone()).await;
+        let new_owner_endpoint = start_node(new_owner.clone()).await;
+        bootstrap
+            .routes
+            .lock()
+            .unwrap()
+            .insert("t1".to_string(), owner_endpoint);
+
+        let config = Config {
+            retry_backoff: ReadableDuration::millis(1),
+            ..Default::default()
+        };
+        let client = Builder::new(bootstrap_endpoint)
+            .config(config)
+            .basic_auth("user", "pass")
+            .build()
+            .unwrap();
+
+        // The points are written to the owner by the route.
+        let result = client.write(&new_write_request("t1")).await.unwrap();
+        assert_eq!(1, result.success);
+        assert_eq!(1, owner.write_requests.load(Ordering::Relaxed));
+        assert_eq!(0, bootstrap.write_requests.load(Ordering::Relaxed));
+        let authorization = format!("Basic {}", base64::encode("user:pass"));
+        assert_eq!(
+            vec![authorization.clone()],
+            *bootstrap.authorizations.lock().unwrap()
+        );
+        assert_eq!(
+            vec![authorization.clone()],
+            *owner.authorizations.lock().unwrap()
+        );
+
+        // The table is moved, and the write failed with the unavailable code is
+        // not retried, as the rows may have been written, but the route is
+        // evicted.
+        bootstrap
+            .routes
+            .lock()
+            .unwrap()
+            .insert("t1".to_string(), new_owner_endpoint);
+        owner.write_failures.lock().unwrap().push_back((503, None));
+        let err = client.write(&new_write_request("t1")).await.unwrap_err();
+        assert!(matches!(err, Error::Server { code: 503, .. }));
+        assert_eq!(2, owner.write_requests.load(Ordering::Relaxed));
+
+        // The write rejected by the admission control is retried after the
+        // delay suggested by the server.
+        new_owner
+            .write_failures
+            .lock()
+            .unwrap()
+            .push_back((429, Some(200)));
+        let begin = Instant::now();
+        let result = client.write(&new_write_request("t1")).await.unwrap();
+        assert_eq!(1, result.success);
+        assert!(begin.elapsed() >= Duration::from_millis(200));
+        assert_eq!(2, new_owner.write_requests.load(Ordering::Relaxed));
+        assert_eq!(2, bootstrap.route_requests.load(Ordering::Relaxed));
+        assert_eq!(2, owner.write_requests.load(Ordering::Relaxed));
+        assert!(new_owner
+            .authorizations
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|v| *v == authorization));
+    }
+}
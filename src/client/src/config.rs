@@ -0,0 +1,74 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Config of the [Client](crate::Client)
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub connect_timeout: ReadableDuration,
+    /// Timeout of a single rpc, the retries of a request have their own
+    /// timeouts.
+    pub rpc_timeout: ReadableDuration,
+    pub channel_keep_alive_timeout: ReadableDuration,
+    pub channel_keep_alive_interval: ReadableDuration,
+    /// Max times to retry a request failed with the retryable errors, zero
+    /// means no retry.
+    pub max_retries: usize,
+    /// Wait time before the first retry, and it's doubled for every retry
+    /// after.
+    pub retry_backoff: ReadableDuration,
+    pub max_retry_backoff: ReadableDuration,
+    /// Whether to retry the writes failed with the errors after which the rows
+    /// may have been written, e.g. the rpc times out, and the retries write
+    /// duplicate rows into the append mode tables.
+    ///
+    /// The writes surely not applied, e.g. the node is unreachable or the
+    /// write quota is exceeded, are always retried.
+    pub retry_writes: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            connect_timeout: ReadableDuration::secs(3),
+            rpc_timeout: ReadableDuration::secs(60),
+            channel_keep_alive_timeout: ReadableDuration::secs(3),
+            channel_keep_alive_interval: ReadableDuration::secs(600),
+            max_retries: 3,
+            retry_backoff: ReadableDuration::millis(100),
+            max_retry_backoff: ReadableDuration::secs(5),
+            retry_writes: false,
+        }
+    }
+}
+
+impl Config {
+    /// Wait time before the `attempt`th retry, starting from 1.
+    pub fn backoff_of(&self, attempt: usize) -> Duration {
+        let exp = attempt.saturating_sub(1).min(16) as u32;
+        self.retry_backoff
+            .0
+            .saturating_mul(1 << exp)
+            .min(self.max_retry_backoff.0)
+    }
+}
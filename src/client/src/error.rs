@@ -0,0 +1,202 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Error of the client
+
+use std::time::Duration;
+
+use horaedbproto::common::ResponseHeader;
+use macros::define_result;
+use snafu::{Backtrace, Snafu};
+use tonic::Code;
+
+/// Code of the response header of the successful requests.
+const CODE_OK: u32 = 200;
+/// Code of the response header when the server is overloaded or the write
+/// quota is exceeded.
+const CODE_TOO_MANY_REQUESTS: u32 = 429;
+/// Code of the response header when the server can't serve the request for
+/// now, e.g. the table is under maintenance or the write is rejected by the
+/// circuit breaker.
+const CODE_SERVICE_UNAVAILABLE: u32 = 503;
+
+define_result!(Error);
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Failed to connect, endpoint:{}, msg:{}, err:{}", endpoint, msg, source))]
+    BuildChannel {
+        endpoint: String,
+        msg: String,
+        source: tonic::transport::Error,
+    },
+
+    #[snafu(display("Rpc failed, endpoint:{}, err:{}", endpoint, source))]
+    Rpc {
+        endpoint: String,
+        source: tonic::Status,
+    },
+
+    #[snafu(display(
+        "Request is rejected by server, endpoint:{}, code:{}, msg:{}",
+        endpoint,
+        code,
+        msg
+    ))]
+    Server {
+        endpoint: String,
+        code: u32,
+        msg: String,
+        /// Delay suggested by the server before retrying the request.
+        retry_after: Option<Duration>,
+    },
+
+    #[snafu(display("Invalid credentials, msg:{}", msg))]
+    InvalidCredentials { msg: String },
+
+    #[snafu(display("Invalid point, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    InvalidPoint { msg: String, backtrace: Backtrace },
+
+    #[snafu(display("Invalid response, endpoint:{}, msg:{}", endpoint, msg))]
+    InvalidResponse { endpoint: String, msg: String },
+
+    #[snafu(display("Failed to decode record batches, endpoint:{}, err:{}", endpoint, source))]
+    DecodeRecordBatches {
+        endpoint: String,
+        source: arrow_ext::ipc::Error,
+    },
+}
+
+impl Error {
+    /// Whether the request failed with the error may succeed if it's sent
+    /// again, i.e. the node is unreachable, or the server rejects the request
+    /// with the retryable codes of the response header.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::BuildChannel { .. } => true,
+            Error::Rpc { source, .. } => matches!(
+                source.code(),
+                Code::Unavailable | Code::ResourceExhausted | Code::Aborted
+            ),
+            Error::Server { code, .. } => {
+                *code == CODE_TOO_MANY_REQUESTS || *code == CODE_SERVICE_UNAVAILABLE
+            }
+            Error::InvalidPoint { .. }
+            | Error::InvalidCredentials { .. }
+            | Error::InvalidResponse { .. }
+            | Error::DecodeRecordBatches { .. } => false,
+        }
+    }
+
+    /// Whether the write failed with the error is surely not applied by the
+    /// server, i.e. the node is unreachable or the write is rejected by the
+    /// admission control, so it can be sent again without duplicating the rows.
+    ///
+    /// The write may have been applied if the rpc fails, e.g. it times out
+    /// after the rows are written.
+    pub fn is_write_rejected(&self) -> bool {
+        match self {
+            Error::BuildChannel { .. } => true,
+            Error::Server { code, .. } => *code == CODE_TOO_MANY_REQUESTS,
+            Error::Rpc { .. }
+            | Error::InvalidPoint { .. }
+            | Error::InvalidCredentials { .. }
+            | Error::InvalidResponse { .. }
+            | Error::DecodeRecordBatches { .. } => false,
+        }
+    }
+
+    /// Delay suggested by the server before retrying the request.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Server { retry_after, .. } => *retry_after,
+            Error::BuildChannel { .. }
+            | Error::Rpc { .. }
+            | Error::InvalidPoint { .. }
+            | Error::InvalidCredentials { .. }
+            | Error::InvalidResponse { .. }
+            | Error::DecodeRecordBatches { .. } => None,
+        }
+    }
+
+    /// Whether the routes of the tables of the failed request should be
+    /// queried again, as the tables may have been moved to other nodes.
+    pub(crate) fn is_route_stale(&self) -> bool {
+        match self {
+            Error::BuildChannel { .. } | Error::Rpc { .. } => true,
+            Error::Server { code, .. } => *code == CODE_SERVICE_UNAVAILABLE,
+            Error::InvalidPoint { .. }
+            | Error::InvalidCredentials { .. }
+            | Error::InvalidResponse { .. }
+            | Error::DecodeRecordBatches { .. } => false,
+        }
+    }
+}
+
+/// Check the header of the response from the `endpoint`, and it's ok if the
+/// header is missing.
+///
+/// The `retry_after` is the delay suggested by the server in the metadata of
+/// the response.
+pub(crate) fn check_header(
+    endpoint: &str,
+    header: Option<ResponseHeader>,
+    retry_after: Option<Duration>,
+) -> Result<()> {
+    match header {
+        Some(header) if header.code != CODE_OK => Server {
+            endpoint,
+            code: header.code,
+            msg: header.error,
+            retry_after,
+        }
+        .fail(),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_error() {
+        let server_err = |code| Error::Server {
+            endpoint: "127.0.0.1:8831".to_string(),
+            code,
+            msg: String::new(),
+            retry_after: None,
+        };
+        assert!(server_err(CODE_TOO_MANY_REQUESTS).is_retryable());
+        assert!(server_err(CODE_SERVICE_UNAVAILABLE).is_retryable());
+        assert!(!server_err(400).is_retryable());
+        assert!(!server_err(500).is_retryable());
+
+        let rpc_err = |code| Error::Rpc {
+            endpoint: "127.0.0.1:8831".to_string(),
+            source: tonic::Status::new(code, "test"),
+        };
+        assert!(rpc_err(Code::Unavailable).is_retryable());
+        assert!(!rpc_err(Code::InvalidArgument).is_retryable());
+
+        // Only the writes surely not applied are retried by default.
+        assert!(server_err(CODE_TOO_MANY_REQUESTS).is_write_rejected());
+        assert!(!server_err(CODE_SERVICE_UNAVAILABLE).is_write_rejected());
+        assert!(!rpc_err(Code::Unavailable).is_write_rejected());
+    }
+}
@@ -0,0 +1,44 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Async client of HoraeDB talking with the servers by the grpc protocol.
+//!
+//! The client is maintained along with the servers, so the changes of the
+//! protocol, e.g. the metadata keys and the codes of the response header, are
+//! supported by the client in the same change.
+//!
+//! The requests are sent to the nodes owning the tables by the routes queried
+//! from the bootstrap endpoint, and the routes are cached until the nodes fail.
+//! The requests rejected by the servers with the retryable codes, or failed
+//! to reach the nodes, are retried with the exponential backoff, see
+//! [Error::is_retryable]. The writes are only retried if they're surely not
+//! applied, see [Error::is_write_rejected], unless [Config::retry_writes] is
+//! set, as the retries may write duplicate rows.
+
+mod channel;
+mod client;
+pub mod config;
+mod error;
+pub mod model;
+mod router;
+
+pub use client::{Builder, Client};
+pub use config::Config;
+pub use error::{Error, Result};
+pub use model::{
+    Point, PointBuilder, SqlQueryRequest, SqlQueryResponse, Value, WriteRequest, WriteResult,
+};
@@ -0,0 +1,355 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Models of the requests and the responses
+
+use std::collections::{BTreeMap, HashMap};
+
+use arrow::record_batch::RecordBatch;
+use horaedbproto::storage::{
+    value, Field, FieldGroup, Tag, Value as ValuePb, WriteSeriesEntry, WriteTableRequest,
+};
+use prost::Message;
+use snafu::ensure;
+
+use crate::error::{InvalidPoint, Result};
+
+/// Value of a tag or a field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Timestamp(i64),
+    Double(f64),
+    Float(f32),
+    Varbinary(Vec<u8>),
+    String(String),
+    UInt64(u64),
+    UInt32(u32),
+    UInt16(u16),
+    UInt8(u8),
+    Int64(i64),
+    Int32(i32),
+    Int16(i16),
+    Int8(i8),
+    Boolean(bool),
+}
+
+impl From<Value> for ValuePb {
+    fn from(value: Value) -> Self {
+        let value = match value {
+            Value::Timestamp(v) => value::Value::TimestampValue(v),
+            Value::Double(v) => value::Value::Float64Value(v),
+            Value::Float(v) => value::Value::Float32Value(v),
+            Value::Varbinary(v) => value::Value::VarbinaryValue(v),
+            Value::String(v) => value::Value::StringValue(v),
+            Value::UInt64(v) => value::Value::Uint64Value(v),
+            Value::UInt32(v) => value::Value::Uint32Value(v),
+            Value::UInt16(v) => value::Value::Uint16Value(v.into()),
+            Value::UInt8(v) => value::Value::Uint8Value(v.into()),
+            Value::Int64(v) => value::Value::Int64Value(v),
+            Value::Int32(v) => value::Value::Int32Value(v),
+            Value::Int16(v) => value::Value::Int16Value(v.into()),
+            Value::Int8(v) => value::Value::Int8Value(v.into()),
+            Value::Boolean(v) => value::Value::BoolValue(v),
+        };
+
+        ValuePb { value: Some(value) }
+    }
+}
+
+/// A row of a table, built by the [PointBuilder].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub table: String,
+    pub timestamp: i64,
+    pub tags: BTreeMap<String, Value>,
+    pub fields: BTreeMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct PointBuilder {
+    table: String,
+    timestamp: Option<i64>,
+    tags: BTreeMap<String, Value>,
+    fields: BTreeMap<String, Value>,
+}
+
+impl PointBuilder {
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            timestamp: None,
+            tags: BTreeMap::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Timestamp of the point in milliseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn tag(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.tags.insert(name.into(), value);
+        self
+    }
+
+    pub fn field(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.fields.insert(name.into(), value);
+        self
+    }
+
+    pub fn build(self) -> Result<Point> {
+        ensure!(
+            !self.table.is_empty(),
+            InvalidPoint {
+                msg: "table is empty",
+            }
+        );
+        let Some(timestamp) = self.timestamp else {
+            return InvalidPoint {
+                msg: format!("timestamp is missing, table:{}", self.table),
+            }
+            .fail();
+        };
+        ensure!(
+            !self.fields.is_empty(),
+            InvalidPoint {
+                msg: format!("fields are empty, table:{}", self.table),
+            }
+        );
+        if let Some(name) = self.tags.keys().find(|v| self.fields.contains_key(*v)) {
+            return InvalidPoint {
+                msg: format!("column is both tag and field, table:{}, column:{name}", self.table),
+            }
+            .fail();
+        }
+
+        Ok(Point {
+            table: self.table,
+            timestamp,
+            tags: self.tags,
+            fields: self.fields,
+        })
+    }
+}
+
+/// Points to write, which can be of different tables.
+#[derive(Debug, Clone, Default)]
+pub struct WriteRequest {
+    pub points: Vec<Point>,
+}
+
+impl WriteRequest {
+    pub fn add_point(&mut self, point: Point) -> &mut Self {
+        self.points.push(point);
+        self
+    }
+
+    pub fn add_points(&mut self, points: impl IntoIterator<Item = Point>) -> &mut Self {
+        self.points.extend(points);
+        self
+    }
+}
+
+/// Numbers of the rows written, summed over all the nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteResult {
+    pub success: u32,
+    pub failed: u32,
+}
+
+impl WriteResult {
+    pub(crate) fn merge(&mut self, other: WriteResult) {
+        self.success += other.success;
+        self.failed += other.failed;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SqlQueryRequest {
+    /// Tables queried by the sql, the request is sent to the node owning the
+    /// first table, and to the bootstrap endpoint if it's empty.
+    pub tables: Vec<String>,
+    pub sql: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SqlQueryResponse {
+    /// Rows affected by the sql, e.g. the insert, zero for the queries.
+    pub affected_rows: u32,
+    /// Rows returned by the query.
+    pub record_batches: Vec<RecordBatch>,
+}
+
+/// Names of the columns in a table request, indexed by the order they're
+/// added.
+#[derive(Default)]
+struct NameIndex {
+    names: Vec<String>,
+    indexes: HashMap<String, u32>,
+}
+
+impl NameIndex {
+    fn index_of(&mut self, name: &str) -> u32 {
+        if let Some(idx) = self.indexes.get(name) {
+            return *idx;
+        }
+
+        let idx = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.indexes.insert(name.to_string(), idx);
+        idx
+    }
+}
+
+#[derive(Default)]
+struct TableRequestBuilder {
+    tag_names: NameIndex,
+    field_names: NameIndex,
+    /// Encoded tags of the series -> index of its entry.
+    series: HashMap<Vec<u8>, usize>,
+    entries: Vec<WriteSeriesEntry>,
+}
+
+impl TableRequestBuilder {
+    fn add_point(&mut self, point: &Point) {
+        let tags: Vec<_> = point
+            .tags
+            .iter()
+            .map(|(name, value)| Tag {
+                name_index: self.tag_names.index_of(name),
+                value: Some(value.clone().into()),
+            })
+            .collect();
+        let fields = point
+            .fields
+            .iter()
+            .map(|(name, value)| Field {
+                name_index: self.field_names.index_of(name),
+                value: Some(value.clone().into()),
+            })
+            .collect();
+        let field_group = FieldGroup {
+            timestamp: point.timestamp,
+            fields,
+        };
+
+        // The tags are sorted by their names, so the same tags are always encoded
+        // into the same key.
+        let series_key = tags.iter().fold(Vec::new(), |mut buf, tag| {
+            tag.encode_length_delimited(&mut buf)
+                .expect("vec has enough capacity");
+            buf
+        });
+        match self.series.get(&series_key) {
+            Some(idx) => self.entries[*idx].field_groups.push(field_group),
+            None => {
+                self.series.insert(series_key, self.entries.len());
+                self.entries.push(WriteSeriesEntry {
+                    tags,
+                    field_groups: vec![field_group],
+                });
+            }
+        }
+    }
+
+    fn build(self, table: String) -> WriteTableRequest {
+        WriteTableRequest {
+            table,
+            tag_names: self.tag_names.names,
+            field_names: self.field_names.names,
+            entries: self.entries,
+        }
+    }
+}
+
+/// Build the write requests of the tables from the points, where the points of
+/// the same series are put into one entry.
+pub(crate) fn build_table_requests<'a>(
+    points: impl IntoIterator<Item = &'a Point>,
+) -> Vec<WriteTableRequest> {
+    let mut builders: BTreeMap<&str, TableRequestBuilder> = BTreeMap::new();
+    for point in points {
+        builders
+            .entry(point.table.as_str())
+            .or_default()
+            .add_point(point);
+    }
+
+    builders
+        .into_iter()
+        .map(|(table, builder)| builder.build(table.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(table: &str, host: &str, timestamp: i64) -> Point {
+        PointBuilder::new(table)
+            .timestamp(timestamp)
+            .tag("host", Value::String(host.to_string()))
+            .field("value", Value::Double(1.0))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_build_point() {
+        assert!(PointBuilder::new("t").timestamp(1).build().is_err());
+        assert!(PointBuilder::new("t")
+            .field("value", Value::Double(1.0))
+            .build()
+            .is_err());
+        assert!(PointBuilder::new("t")
+            .timestamp(1)
+            .tag("a", Value::String("x".to_string()))
+            .field("a", Value::Double(1.0))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_build_table_requests() {
+        let points = vec![
+            point("t1", "a", 1),
+            point("t2", "a", 1),
+            point("t1", "b", 1),
+            point("t1", "a", 2),
+        ];
+        let requests = build_table_requests(&points);
+        assert_eq!(requests.len(), 2);
+
+        let t1 = &requests[0];
+        assert_eq!(t1.table, "t1");
+        assert_eq!(t1.tag_names, vec!["host".to_string()]);
+        assert_eq!(t1.field_names, vec!["value".to_string()]);
+        assert_eq!(t1.entries.len(), 2);
+        let timestamps: Vec<_> = t1.entries[0]
+            .field_groups
+            .iter()
+            .map(|v| v.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![1, 2]);
+        assert_eq!(t1.entries[1].field_groups.len(), 1);
+
+        assert_eq!(requests[1].table, "t2");
+        assert_eq!(requests[1].entries.len(), 1);
+    }
+}
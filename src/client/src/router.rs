@@ -0,0 +1,125 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cached routes of the tables
+
+use std::{collections::HashMap, sync::RwLock};
+
+use horaedbproto::storage::{
+    storage_service_client::StorageServiceClient, RequestContext, RouteRequest,
+};
+use logger::debug;
+use snafu::ResultExt;
+use tonic::metadata::AsciiMetadataValue;
+
+use crate::{
+    channel::ChannelPool,
+    client,
+    error::{self, Result, Rpc},
+};
+
+/// Routes of the tables queried from the bootstrap endpoint.
+///
+/// The tables without routes, e.g. the tables not created yet, are sent to the
+/// bootstrap endpoint, which forwards the requests or creates the tables. Such
+/// tables are not cached, so they're routed to the owners once created.
+pub(crate) struct Router {
+    bootstrap_endpoint: String,
+    /// Table -> endpoint of the node owning it
+    cache: RwLock<HashMap<String, String>>,
+}
+
+impl Router {
+    pub fn new(bootstrap_endpoint: String) -> Self {
+        Self {
+            bootstrap_endpoint,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn bootstrap_endpoint(&self) -> &str {
+        &self.bootstrap_endpoint
+    }
+
+    /// Route the tables to the endpoints, and the tables missing in the cache
+    /// are routed by one request.
+    pub async fn route(
+        &self,
+        channel_pool: &ChannelPool,
+        authorization: Option<&AsciiMetadataValue>,
+        database: &str,
+        tables: &[&str],
+    ) -> Result<HashMap<String, String>> {
+        let mut routes = HashMap::with_capacity(tables.len());
+        let mut missing = Vec::new();
+        {
+            let cache = self.cache.read().unwrap();
+            for table in tables {
+                match cache.get(*table) {
+                    Some(endpoint) => {
+                        routes.insert(table.to_string(), endpoint.clone());
+                    }
+                    None => missing.push(table.to_string()),
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(routes);
+        }
+
+        debug!("Route the tables missing in the cache, tables:{missing:?}");
+        let endpoint = &self.bootstrap_endpoint;
+        let channel = channel_pool.get(endpoint).await?;
+        let request = RouteRequest {
+            context: Some(RequestContext {
+                database: database.to_string(),
+            }),
+            tables: missing.clone(),
+        };
+        let response = StorageServiceClient::new(channel)
+            .route(client::new_request(request, authorization))
+            .await
+            .context(Rpc { endpoint })?
+            .into_inner();
+        error::check_header(endpoint, response.header, None)?;
+
+        let mut cache = self.cache.write().unwrap();
+        for route in response.routes {
+            if let Some(v) = route.endpoint {
+                let endpoint = format!("{}:{}", v.ip, v.port);
+                cache.insert(route.table.clone(), endpoint.clone());
+                routes.insert(route.table, endpoint);
+            }
+        }
+        for table in missing {
+            routes
+                .entry(table)
+                .or_insert_with(|| self.bootstrap_endpoint.clone());
+        }
+
+        Ok(routes)
+    }
+
+    /// Evict the routes of the tables, which will be routed again by the next
+    /// requests.
+    pub fn evict<'a>(&self, tables: impl IntoIterator<Item = &'a str>) {
+        let mut cache = self.cache.write().unwrap();
+        for table in tables {
+            cache.remove(table);
+        }
+    }
+}
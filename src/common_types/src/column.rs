@@ -242,6 +242,21 @@ impl Column {
         Ok(())
     }
 
+    /// Append the datum, and the string is stored as is instead of being
+    /// copied, so the storage shared with others, e.g. the interned strings,
+    /// is kept.
+    pub fn append_datum(&mut self, value: Datum) -> Result<()> {
+        match (&mut self.data, value) {
+            (ColumnData::String(data), Datum::String(v)) => {
+                data[self.to_insert] = v;
+                self.valid.set(self.to_insert);
+                self.to_insert += 1;
+                Ok(())
+            }
+            (_, value) => self.append_datum_ref(&value),
+        }
+    }
+
     pub fn get_datum(&self, idx: usize) -> Datum {
         if !self.valid.get(idx) {
             return Datum::Null;
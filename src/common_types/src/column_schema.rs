@@ -142,6 +142,8 @@ struct ArrowFieldMeta {
     is_tag: bool,
     comment: String,
     is_dictionary: bool,
+    display_name: String,
+    unit: String,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -150,6 +152,8 @@ pub enum ArrowFieldMetaKey {
     IsTag,
     IsDictionary,
     Comment,
+    DisplayName,
+    Unit,
 }
 
 impl ArrowFieldMetaKey {
@@ -159,6 +163,8 @@ impl ArrowFieldMetaKey {
             ArrowFieldMetaKey::IsTag => "field::is_tag",
             ArrowFieldMetaKey::Comment => "field::comment",
             ArrowFieldMetaKey::IsDictionary => "field::is_dictionary",
+            ArrowFieldMetaKey::DisplayName => "field::display_name",
+            ArrowFieldMetaKey::Unit => "field::unit",
         }
     }
 
@@ -197,6 +203,11 @@ pub struct ColumnSchema {
     pub escaped_name: String,
     /// Default value expr
     pub default_value: Option<Expr>,
+    /// Name of the column displayed by the clients, e.g. the dashboards, empty
+    /// if not set
+    pub display_name: String,
+    /// Unit of the values of the column, e.g. `bytes` or `ms`, empty if not set
+    pub unit: String,
 }
 
 impl ColumnSchema {
@@ -333,6 +344,10 @@ impl TryFrom<schema_pb::ColumnSchema> for ColumnSchema {
             comment: column_schema.comment,
             escaped_name,
             default_value,
+            // The annotations are not in the pb, and the manifest persists them
+            // separately.
+            display_name: String::new(),
+            unit: String::new(),
         })
     }
 }
@@ -346,6 +361,8 @@ impl TryFrom<&Arc<Field>> for ColumnSchema {
             is_tag,
             is_dictionary,
             comment,
+            display_name,
+            unit,
         } = decode_arrow_field_meta_data(field.metadata())?;
         Ok(Self {
             id,
@@ -361,6 +378,8 @@ impl TryFrom<&Arc<Field>> for ColumnSchema {
             comment,
             escaped_name: field.name().escape_debug().to_string(),
             default_value: None,
+            display_name,
+            unit,
         })
     }
 }
@@ -425,6 +444,8 @@ fn decode_arrow_field_meta_data(meta: &HashMap<String, String>) -> Result<ArrowF
             is_tag: parse_arrow_field_meta_value(meta, ArrowFieldMetaKey::IsTag)?,
             comment: parse_arrow_field_meta_value(meta, ArrowFieldMetaKey::Comment)?,
             is_dictionary: parse_arrow_field_meta_value(meta, ArrowFieldMetaKey::IsDictionary)?,
+            display_name: parse_arrow_field_meta_value(meta, ArrowFieldMetaKey::DisplayName)?,
+            unit: parse_arrow_field_meta_value(meta, ArrowFieldMetaKey::Unit)?,
         })
     }
 }
@@ -445,6 +466,17 @@ fn encode_arrow_field_meta_data(col_schema: &ColumnSchema) -> HashMap<String, St
         ArrowFieldMetaKey::Comment.to_string(),
         col_schema.comment.clone(),
     );
+    // The annotations are only encoded if set, so the fields of the columns
+    // without them are the same as before.
+    if !col_schema.display_name.is_empty() {
+        meta.insert(
+            ArrowFieldMetaKey::DisplayName.to_string(),
+            col_schema.display_name.clone(),
+        );
+    }
+    if !col_schema.unit.is_empty() {
+        meta.insert(ArrowFieldMetaKey::Unit.to_string(), col_schema.unit.clone());
+    }
 
     meta
 }
@@ -460,6 +492,8 @@ pub struct Builder {
     is_dictionary: bool,
     comment: String,
     default_value: Option<Expr>,
+    display_name: String,
+    unit: String,
 }
 
 impl Builder {
@@ -474,6 +508,8 @@ impl Builder {
             is_dictionary: false,
             comment: String::new(),
             default_value: None,
+            display_name: String::new(),
+            unit: String::new(),
         }
     }
 
@@ -510,6 +546,16 @@ impl Builder {
         self
     }
 
+    pub fn display_name(mut self, display_name: String) -> Self {
+        self.display_name = display_name;
+        self
+    }
+
+    pub fn unit(mut self, unit: String) -> Self {
+        self.unit = unit;
+        self
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.is_tag {
             ensure!(
@@ -545,6 +591,8 @@ impl Builder {
             comment: self.comment,
             escaped_name,
             default_value: self.default_value,
+            display_name: self.display_name,
+            unit: self.unit,
         })
     }
 }
@@ -604,11 +652,33 @@ mod tests {
             comment: "Comment of this column".to_string(),
             escaped_name: "test_column_schema".escape_debug().to_string(),
             default_value: Some(Expr::Value(Value::Boolean(true))),
+            display_name: String::new(),
+            unit: String::new(),
         };
 
         assert_eq!(&lhs, &rhs);
     }
 
+    #[test]
+    fn test_arrow_field_annotations() {
+        let column_schema = Builder::new("used".to_string(), DatumKind::UInt64)
+            .id(1)
+            .is_nullable(true)
+            .display_name("Used Memory".to_string())
+            .unit("bytes".to_string())
+            .build()
+            .unwrap();
+        let field = Arc::new(column_schema.to_arrow_field());
+        assert_eq!(column_schema, ColumnSchema::try_from(&field).unwrap());
+
+        // The fields without annotations are not changed.
+        let column_schema = new_test_column_schema();
+        let field = column_schema.to_arrow_field();
+        assert!(!field
+            .metadata()
+            .contains_key(ArrowFieldMetaKey::Unit.as_str()));
+    }
+
     #[test]
     fn test_pb_convert() {
         let column_schema = new_test_column_schema();
@@ -660,6 +730,8 @@ mod tests {
                     is_tag: true,
                     comment: "".to_string(),
                     is_dictionary: false,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
             ),
             (
@@ -674,6 +746,8 @@ mod tests {
                     is_tag: false,
                     comment: "abc".to_string(),
                     is_dictionary: true,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
             ),
         ];
@@ -24,6 +24,7 @@ pub mod column_schema;
 pub mod datum;
 pub(crate) mod hex;
 pub mod projected_schema;
+pub mod protocol;
 pub mod record_batch;
 pub mod request_id;
 pub mod row;
@@ -49,10 +50,26 @@ pub const ARENA_BLOCK_SIZE: &str = "arena_block_size";
 pub const WRITE_BUFFER_SIZE: &str = "write_buffer_size";
 pub const COMPACTION_STRATEGY: &str = "compaction_strategy";
 pub const NUM_ROWS_PER_ROW_GROUP: &str = "num_rows_per_row_group";
+pub const ROW_GROUP_SIZE_POLICY: &str = "row_group_size_policy";
+pub const DATA_PAGE_SIZE: &str = "data_page_size";
+pub const BLOOM_FILTER_FPP: &str = "bloom_filter_fpp";
+pub const BLOOM_FILTER_SIZING: &str = "bloom_filter_sizing";
+pub const BLOOM_FILTER_RECENT_DURATION: &str = "bloom_filter_recent_duration";
 pub const UPDATE_MODE: &str = "update_mode";
+pub const MERGE_POLICY: &str = "merge_policy";
 pub const COMPRESSION: &str = "compression";
+pub const COMPRESSION_LEVEL: &str = "compression_level";
+pub const COLUMN_ENCODING: &str = "column_encoding";
+pub const COLUMN_COMPRESSION: &str = "column_compression";
+pub const ADAPTIVE_COMPRESSION: &str = "adaptive_compression";
+pub const SEPARATED_FIELDS: &str = "separated_fields";
 pub const STORAGE_FORMAT: &str = "storage_format";
 pub const MEMTABLE_TYPE: &str = "memtable_type";
+pub const CACHE_PRIORITY: &str = "cache_priority";
+pub const TIMESTAMP_RESOLUTION: &str = "timestamp_resolution";
+pub const TIMESTAMP_ORIGINAL_COLUMN: &str = "timestamp_original_column";
+pub const HOT_DURATION: &str = "hot_duration";
+pub const TRANSFORM_PIPELINE: &str = "transform_pipeline";
 pub const MUTABLE_SEGMENT_SWITCH_THRESHOLD: &str = "mutable_segment_switch_threshold";
 
 #[cfg(any(test, feature = "test"))]
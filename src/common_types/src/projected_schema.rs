@@ -166,7 +166,14 @@ impl RowProjector {
         fetched_source_column_indexes: &mut Vec<Option<usize>>,
         projected_source_indexes: &mut Vec<usize>,
     ) -> Result<()> {
-        match source_schema.index_of(&column.name) {
+        // The columns are found by id if the schema is altered, so the renamed
+        // columns can still be read from the source.
+        let source_idx = if table_schema.version() == source_schema.version() {
+            source_schema.index_of(&column.name)
+        } else {
+            source_schema.index_of_column_id(column.id)
+        };
+        match source_idx {
             Some(source_idx) => {
                 // Column is in source
                 if table_schema.version() == source_schema.version() {
@@ -284,6 +291,11 @@ impl RowProjectorBuilder {
         }
     }
 
+    #[inline]
+    pub fn table_schema(&self) -> &Schema {
+        &self.table_schema
+    }
+
     pub fn build(&self, source_schema: &Schema) -> Result<RowProjector> {
         RowProjector::new(
             &self.fetched_schema,
@@ -458,7 +470,11 @@ impl ProjectedSchemaInner {
 
 #[cfg(test)]
 mod tests {
-    use crate::{projected_schema::ProjectedSchema, tests::build_schema};
+    use crate::{
+        projected_schema::{ProjectedSchema, RowProjector},
+        schema,
+        tests::build_schema,
+    };
 
     #[test]
     fn test_projected_schema() {
@@ -472,4 +488,34 @@ mod tests {
         );
         assert!(!projected_schema.is_all_projection());
     }
+
+    #[test]
+    fn test_project_renamed_column() {
+        let source_schema = build_schema();
+        let mut builder = schema::Builder::new()
+            .primary_key_indexes(source_schema.primary_key_indexes().to_vec())
+            .version(source_schema.version() + 1);
+        for (idx, column) in source_schema.columns().iter().enumerate() {
+            let mut column = column.clone();
+            if column.name == "field2" {
+                column.name = "field2_renamed".to_string();
+            }
+            builder = if source_schema.is_primary_key_index(&idx) {
+                builder.add_key_column(column).unwrap()
+            } else {
+                builder.add_normal_column(column).unwrap()
+            };
+        }
+        let table_schema = builder.build().unwrap();
+
+        let projector = RowProjector::new(
+            &table_schema.to_record_schema(),
+            None,
+            &table_schema,
+            &source_schema,
+        )
+        .unwrap();
+        let expected: Vec<_> = (0..source_schema.num_columns()).map(Some).collect();
+        assert_eq!(expected, projector.fetched_source_column_indexes());
+    }
 }
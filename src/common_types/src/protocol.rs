@@ -0,0 +1,276 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Version negotiation of the inter-node rpc protocol.
+//!
+//! Every inter-node rpc carries the protocol version and the enabled features
+//! of the sender in its metadata, so that the receiver knows which wire fields
+//! the sender understands. A node of the new version keeps working with the
+//! nodes of the old version during a rolling upgrade by only using the
+//! features supported by both of them.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+
+/// Metadata key of the protocol version of the sender.
+pub const PROTOCOL_VERSION_KEY: &str = "x-horaedb-protocol-version";
+/// Metadata key of the enabled protocol features of the sender.
+pub const PROTOCOL_FEATURES_KEY: &str = "x-horaedb-protocol-features";
+/// Metadata key of the token shared by the nodes of the cluster, the requests
+/// between the nodes are only trusted if they carry it.
+pub const INTERNAL_TOKEN_KEY: &str = "x-horaedb-internal-token";
+
+/// Protocol version of this node.
+pub const CURRENT_PROTOCOL_VERSION: u32 = 2;
+/// Protocol version of the peer which doesn't carry its version, that is, the
+/// peer built before the negotiation is introduced.
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+/// The minimal protocol version of the peer this node can work with.
+pub const MIN_COMPATIBLE_PROTOCOL_VERSION: u32 = LEGACY_PROTOCOL_VERSION;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Invalid protocol version, value:{value}, err:{source}.\nBacktrace:\n{backtrace}"
+    ))]
+    InvalidVersion {
+        value: String,
+        source: std::num::ParseIntError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Incompatible protocol version, peer:{peer}, min_compatible:{MIN_COMPATIBLE_PROTOCOL_VERSION}.\nBacktrace:\n{backtrace}"
+    ))]
+    IncompatibleVersion { peer: u32, backtrace: Backtrace },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The optional features of the protocol, each one gates some wire fields
+/// which can't be understood by the peer of the old version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// The arrow payload of the read response can be compressed by zstd.
+    ZstdArrowPayload,
+    /// The read response can carry the metrics of the remote execution.
+    RemoteMetrics,
+    /// The read response can carry the warnings of the remote execution in
+    /// its last message.
+    RemoteWarnings,
+}
+
+impl Feature {
+    /// All the features known by this node.
+    pub const ALL: [Feature; 3] = [
+        Feature::ZstdArrowPayload,
+        Feature::RemoteMetrics,
+        Feature::RemoteWarnings,
+    ];
+    /// The features a peer of the legacy version supports without declaring
+    /// them.
+    const LEGACY: [Feature; 2] = [Feature::ZstdArrowPayload, Feature::RemoteMetrics];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Feature::ZstdArrowPayload => "zstd_arrow_payload",
+            Feature::RemoteMetrics => "remote_metrics",
+            Feature::RemoteWarnings => "remote_warnings",
+        }
+    }
+
+    #[inline]
+    fn mask(&self) -> u64 {
+        1 << (*self as u64)
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Feature {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Feature::ALL
+            .into_iter()
+            .find(|feature| feature.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// Config of the protocol.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProtocolConfig {
+    /// The features not to be used even if the peer supports them, which is
+    /// useful to keep the behavior unchanged until all the nodes are upgraded.
+    pub disabled_features: Vec<Feature>,
+}
+
+/// The protocol version and the features supported by one node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Protocol {
+    pub version: u32,
+    features: u64,
+}
+
+impl Protocol {
+    /// The protocol of this node with the features enabled by the `config`.
+    pub fn local(config: &ProtocolConfig) -> Self {
+        let features = Feature::ALL
+            .iter()
+            .filter(|feature| !config.disabled_features.contains(feature))
+            .fold(0, |mask, feature| mask | feature.mask());
+
+        Self {
+            version: CURRENT_PROTOCOL_VERSION,
+            features,
+        }
+    }
+
+    /// The protocol of the peer which doesn't carry its version.
+    pub fn legacy() -> Self {
+        Self {
+            version: LEGACY_PROTOCOL_VERSION,
+            features: Feature::LEGACY
+                .iter()
+                .fold(0, |mask, feature| mask | feature.mask()),
+        }
+    }
+
+    /// Decode the protocol of the peer from the values of the
+    /// [PROTOCOL_VERSION_KEY] and [PROTOCOL_FEATURES_KEY] in the metadata.
+    ///
+    /// The unknown features are ignored because they are introduced by a newer
+    /// version.
+    pub fn decode(version: Option<&str>, features: Option<&str>) -> Result<Self> {
+        let Some(version) = version else {
+            return Ok(Self::legacy());
+        };
+
+        let version = version
+            .trim()
+            .parse()
+            .context(InvalidVersion { value: version })?;
+        let features = features
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|feature| feature.trim().parse::<Feature>().ok())
+            .fold(0, |mask, feature| mask | feature.mask());
+
+        Ok(Self { version, features })
+    }
+
+    /// Encode the version for the [PROTOCOL_VERSION_KEY] in the metadata.
+    pub fn encode_version(&self) -> String {
+        self.version.to_string()
+    }
+
+    /// Encode the features for the [PROTOCOL_FEATURES_KEY] in the metadata.
+    pub fn encode_features(&self) -> String {
+        self.features()
+            .map(|feature| feature.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn features(&self) -> impl Iterator<Item = Feature> + '_ {
+        Feature::ALL
+            .into_iter()
+            .filter(|feature| self.supports(*feature))
+    }
+
+    #[inline]
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features & feature.mask() != 0
+    }
+
+    /// Negotiate the protocol used to talk with the `peer`, the features of
+    /// which are supported by both sides.
+    pub fn negotiate(&self, peer: &Protocol) -> Result<Protocol> {
+        ensure!(
+            peer.version >= MIN_COMPATIBLE_PROTOCOL_VERSION,
+            IncompatibleVersion { peer: peer.version }
+        );
+
+        Ok(Self {
+            version: self.version.min(peer.version),
+            features: self.features & peer.features,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_and_decode() {
+        let local = Protocol::local(&ProtocolConfig::default());
+        let decoded = Protocol::decode(
+            Some(&local.encode_version()),
+            Some(&local.encode_features()),
+        )
+        .unwrap();
+        assert_eq!(local, decoded);
+
+        // Missing version means the peer is legacy.
+        let decoded = Protocol::decode(None, None).unwrap();
+        assert_eq!(Protocol::legacy(), decoded);
+
+        // Unknown features are ignored.
+        let decoded = Protocol::decode(Some("3"), Some("remote_metrics,unknown")).unwrap();
+        assert_eq!(3, decoded.version);
+        assert_eq!(
+            vec![Feature::RemoteMetrics],
+            decoded.features().collect::<Vec<_>>()
+        );
+
+        assert!(Protocol::decode(Some("x"), None).is_err());
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let config = ProtocolConfig {
+            disabled_features: vec![Feature::ZstdArrowPayload],
+        };
+        let local = Protocol::local(&config);
+        assert!(!local.supports(Feature::ZstdArrowPayload));
+        assert!(local.supports(Feature::RemoteMetrics));
+
+        let peer = Protocol::decode(Some("5"), Some("zstd_arrow_payload")).unwrap();
+        let negotiated = local.negotiate(&peer).unwrap();
+        assert_eq!(CURRENT_PROTOCOL_VERSION, negotiated.version);
+        assert_eq!(0, negotiated.features().count());
+
+        let negotiated = local.negotiate(&Protocol::legacy()).unwrap();
+        assert_eq!(LEGACY_PROTOCOL_VERSION, negotiated.version);
+        assert!(negotiated.supports(Feature::RemoteMetrics));
+        // The legacy peer can't understand the warnings.
+        assert!(!negotiated.supports(Feature::RemoteWarnings));
+
+        let peer = Protocol::decode(Some("0"), None).unwrap();
+        assert!(local.negotiate(&peer).is_err());
+    }
+}
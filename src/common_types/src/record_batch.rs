@@ -17,7 +17,12 @@
 
 //! Record batch
 
-use std::{cmp, convert::TryFrom, mem, sync::Arc};
+use std::{
+    cmp,
+    convert::TryFrom,
+    mem,
+    sync::{Arc, OnceLock},
+};
 
 use arrow::{
     array::BooleanArray,
@@ -74,6 +79,16 @@ pub enum Error {
     #[snafu(display("Failed to convert arrow schema, err:{}", source))]
     ConvertArrowSchema { source: crate::schema::Error },
 
+    #[snafu(display(
+        "Unsupported data type to build column block, data_type:{}.\nBacktrace:\n{}",
+        data_type,
+        backtrace
+    ))]
+    UnsupportedDataType {
+        data_type: DataType,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Mismatch record schema to build RecordBatch, column_name:{}, schema_type:{:?}, column_type:{:?}.\nBacktrace:\n{}", column_name, schema_type, column_type, backtrace))]
     MismatchRecordSchema {
         column_name: String,
@@ -120,7 +135,10 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Clone)]
 pub struct RecordBatchData {
     arrow_record_batch: ArrowRecordBatch,
-    column_blocks: Vec<ColumnBlock>,
+    /// Column blocks are built lazily if the data is created from the arrow
+    /// record batch, so the batches only passed through in arrow format, e.g.
+    /// encoded into the query response, are never converted.
+    column_blocks: OnceLock<Vec<ColumnBlock>>,
 }
 
 impl RecordBatchData {
@@ -139,20 +157,39 @@ impl RecordBatchData {
 
         Ok(RecordBatchData {
             arrow_record_batch,
-            column_blocks,
+            column_blocks: OnceLock::from(column_blocks),
         })
     }
 
+    /// Create the data from the arrow record batch without building the column
+    /// blocks.
+    ///
+    /// REQUIRE: the data types of the `arrow_record_batch` must be supported,
+    /// e.g. it has the same schema with the data it is computed from.
+    fn from_arrow_unchecked(arrow_record_batch: ArrowRecordBatch) -> Self {
+        Self {
+            arrow_record_batch,
+            column_blocks: OnceLock::new(),
+        }
+    }
+
     fn num_rows(&self) -> usize {
         self.arrow_record_batch.num_rows()
     }
 
+    fn column_blocks(&self) -> &[ColumnBlock] {
+        self.column_blocks.get_or_init(|| {
+            build_column_blocks_from_arrow_record_batch(&self.arrow_record_batch)
+                .expect("data types are checked when the data is created")
+        })
+    }
+
     fn take_column_block(&mut self, index: usize) -> ColumnBlock {
         let num_rows = self.num_rows();
-        mem::replace(
-            &mut self.column_blocks[index],
-            ColumnBlock::new_null(num_rows),
-        )
+        // Make sure the column blocks are built.
+        self.column_blocks();
+        let column_blocks = self.column_blocks.get_mut().unwrap();
+        mem::replace(&mut column_blocks[index], ColumnBlock::new_null(num_rows))
     }
 
     /// Returns a zero-copy slice of this array with the indicated offset and
@@ -160,11 +197,15 @@ impl RecordBatchData {
     ///
     /// Panics if offset with length is greater than column length.
     fn slice(&self, offset: usize, length: usize) -> Self {
-        let column_blocks = self
-            .column_blocks
-            .iter()
-            .map(|col| col.slice(offset, length))
-            .collect();
+        let column_blocks = match self.column_blocks.get() {
+            Some(column_blocks) => OnceLock::from(
+                column_blocks
+                    .iter()
+                    .map(|col| col.slice(offset, length))
+                    .collect::<Vec<_>>(),
+            ),
+            None => OnceLock::new(),
+        };
 
         Self {
             arrow_record_batch: self.arrow_record_batch.slice(offset, length),
@@ -175,16 +216,20 @@ impl RecordBatchData {
 
 fn build_column_blocks_from_arrow_record_batch(
     arrow_record_batch: &ArrowRecordBatch,
-    record_schema: &RecordSchema,
 ) -> Result<Vec<ColumnBlock>> {
     let mut column_blocks = Vec::with_capacity(arrow_record_batch.num_columns());
-    for (column_schema, array) in record_schema
-        .columns()
+    for (field, array) in arrow_record_batch
+        .schema()
+        .fields()
         .iter()
         .zip(arrow_record_batch.columns())
     {
-        let column = ColumnBlock::try_from_arrow_array_ref(&column_schema.data_type, array)
-            .context(CreateColumnBlock)?;
+        let datum_kind =
+            DatumKind::from_data_type(field.data_type()).context(UnsupportedDataType {
+                data_type: field.data_type().clone(),
+            })?;
+        let column =
+            ColumnBlock::try_from_arrow_array_ref(&datum_kind, array).context(CreateColumnBlock)?;
         column_blocks.push(column);
     }
 
@@ -195,14 +240,10 @@ impl TryFrom<ArrowRecordBatch> for RecordBatchData {
     type Error = Error;
 
     fn try_from(arrow_record_batch: ArrowRecordBatch) -> Result<Self> {
-        let record_schema =
-            RecordSchema::try_from(arrow_record_batch.schema()).context(ConvertArrowSchema)?;
-        let column_blocks =
-            build_column_blocks_from_arrow_record_batch(&arrow_record_batch, &record_schema)?;
-        Ok(Self {
-            arrow_record_batch,
-            column_blocks,
-        })
+        // Check the data types are supported.
+        RecordSchema::try_from(arrow_record_batch.schema()).context(ConvertArrowSchema)?;
+
+        Ok(Self::from_arrow_unchecked(arrow_record_batch))
     }
 }
 
@@ -221,10 +262,7 @@ impl RecordBatch {
 
         Self {
             schema,
-            data: RecordBatchData {
-                arrow_record_batch,
-                column_blocks: Vec::new(),
-            },
+            data: RecordBatchData::from_arrow_unchecked(arrow_record_batch),
         }
     }
 
@@ -265,7 +303,7 @@ impl RecordBatch {
     // REQUIRE: index is valid
     #[inline]
     pub fn column(&self, index: usize) -> &ColumnBlock {
-        &self.data.column_blocks[index]
+        &self.data.column_blocks()[index]
     }
 
     #[inline]
@@ -301,16 +339,10 @@ impl TryFrom<ArrowRecordBatch> for RecordBatch {
         let record_schema =
             RecordSchema::try_from(arrow_record_batch.schema()).context(ConvertArrowSchema)?;
 
-        let column_blocks =
-            build_column_blocks_from_arrow_record_batch(&arrow_record_batch, &record_schema)?;
-
         let arrow_record_batch = cast_arrow_record_batch(arrow_record_batch)?;
         Ok(Self {
             schema: record_schema,
-            data: RecordBatchData {
-                arrow_record_batch,
-                column_blocks,
-            },
+            data: RecordBatchData::from_arrow_unchecked(arrow_record_batch),
         })
     }
 }
@@ -456,13 +488,13 @@ impl FetchedRecordBatch {
     }
 
     pub fn columns(&self) -> &[ColumnBlock] {
-        &self.data.column_blocks
+        self.data.column_blocks()
     }
 
     pub fn clone_row_at(&self, index: usize) -> Row {
         let datums = self
             .data
-            .column_blocks
+            .column_blocks()
             .iter()
             .map(|column_block| column_block.datum(index))
             .collect();
@@ -477,10 +509,7 @@ impl FetchedRecordBatch {
     pub fn try_project(mut self, projected_schema: &ProjectedSchema) -> Result<RecordBatch> {
         // Get the schema after projection.
         let record_schema = projected_schema.to_record_schema();
-        let mut column_blocks = Vec::with_capacity(record_schema.num_columns());
-        let num_rows = self.data.num_rows();
-        let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
-
+        let mut column_indexes = Vec::with_capacity(record_schema.num_columns());
         for column_schema in record_schema.columns() {
             let column_index =
                 self.schema
@@ -488,17 +517,38 @@ impl FetchedRecordBatch {
                     .context(ColumnNotInSchemaWithKey {
                         name: &column_schema.name,
                     })?;
-
-            // Take the column block out.
-            let column_block = self.data.take_column_block(column_index);
-            column_blocks.push(column_block);
+            column_indexes.push(column_index);
         }
 
-        let data =
-            RecordBatchData::new(record_schema.to_arrow_schema_ref(), column_blocks, options)?;
+        // Pick the arrow arrays directly, and the column blocks are only taken out if
+        // they have been built.
+        let arrays = column_indexes
+            .iter()
+            .map(|idx| self.data.arrow_record_batch.column(*idx).clone())
+            .collect();
+        let options = RecordBatchOptions::new().with_row_count(Some(self.data.num_rows()));
+        let arrow_record_batch = ArrowRecordBatch::try_new_with_options(
+            record_schema.to_arrow_schema_ref(),
+            arrays,
+            &options,
+        )
+        .context(CreateArrow)?;
+        let column_blocks = match self.data.column_blocks.get() {
+            Some(_) => OnceLock::from(
+                column_indexes
+                    .iter()
+                    .map(|idx| self.data.take_column_block(*idx))
+                    .collect::<Vec<_>>(),
+            ),
+            None => OnceLock::new(),
+        };
+
         Ok(RecordBatch {
             schema: record_schema,
-            data,
+            data: RecordBatchData {
+                arrow_record_batch,
+                column_blocks,
+            },
         })
     }
 
@@ -535,7 +585,7 @@ impl FetchedRecordBatch {
 
     #[inline]
     pub fn column(&self, index: usize) -> &ColumnBlock {
-        &self.data.column_blocks[index]
+        &self.data.column_blocks()[index]
     }
 
     /// Reverse the rows in the data.
@@ -546,9 +596,7 @@ impl FetchedRecordBatch {
             .map_err(|e| Box::new(e) as _)
             .context(ReverseRecordBatchData)?;
 
-        self.data = RecordBatchData::try_from(reversed_record_batch)
-            .map_err(|e| Box::new(e) as _)
-            .context(ReverseRecordBatchData)?;
+        self.data = RecordBatchData::from_arrow_unchecked(reversed_record_batch);
 
         Ok(())
     }
@@ -579,9 +627,7 @@ impl FetchedRecordBatch {
                 .map_err(|e| Box::new(e) as _)
                 .context(SelectRecordBatchData)?;
 
-        self.data = RecordBatchData::try_from(selected_record_batch)
-            .map_err(|e| Box::new(e) as _)
-            .context(SelectRecordBatchData)?;
+        self.data = RecordBatchData::from_arrow_unchecked(selected_record_batch);
 
         Ok(())
     }
@@ -760,6 +806,8 @@ impl FetchedRecordBatchBuilder {
 
 #[cfg(test)]
 mod tests {
+    use arrow::array::BooleanArray;
+
     use crate::{
         record_batch::{FetchedRecordBatch, FetchedRecordBatchBuilder},
         row::RowViewOnBatch,
@@ -826,4 +874,24 @@ mod tests {
 
         check_record_batch_with_key(record_batch_with_key, 2, 5);
     }
+
+    #[test]
+    fn test_select_data() {
+        let mut record_batch_with_key = build_fetched_record_batch();
+        let filter_array = BooleanArray::from(vec![true, false, true, false, true]);
+        record_batch_with_key.select_data(&filter_array).unwrap();
+        assert_eq!(record_batch_with_key.num_rows(), 3);
+        assert_eq!(record_batch_with_key.num_columns(), 5);
+
+        // The column blocks are built from the selected arrays on demand.
+        let rows = build_rows();
+        for (row_idx, selected_idx) in [0, 2, 4].into_iter().enumerate() {
+            for col_idx in 0..5 {
+                assert_eq!(
+                    rows[selected_idx][col_idx],
+                    record_batch_with_key.column(col_idx).datum(row_idx)
+                );
+            }
+        }
+    }
 }
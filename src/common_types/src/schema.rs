@@ -772,6 +772,12 @@ impl Schema {
         self.column_schemas.index_of(name)
     }
 
+    /// Find the index of the column with the given id, which is kept after
+    /// the column is renamed.
+    pub fn index_of_column_id(&self, id: ColumnId) -> Option<usize> {
+        self.columns().iter().position(|column| column.id == id)
+    }
+
     pub fn primary_key_indexes(&self) -> &[usize] {
         &self.primary_key_indexes
     }
@@ -808,6 +814,25 @@ impl Schema {
         self.version
     }
 
+    /// Rebuild the schema with the columns mapped by `f`, which must keep the
+    /// ids, the names and the types of the columns, e.g. to restore the
+    /// annotations of the columns not persisted with the schema.
+    pub fn map_columns(&self, f: impl Fn(ColumnSchema) -> ColumnSchema) -> Result<Schema> {
+        let mut builder = Builder::with_capacity(self.num_columns())
+            .primary_key_indexes(self.primary_key_indexes.clone())
+            .version(self.version);
+        for (idx, column) in self.columns().iter().enumerate() {
+            let column = f(column.clone());
+            builder = if self.is_primary_key_index(&idx) {
+                builder.add_key_column(column)?
+            } else {
+                builder.add_normal_column(column)?
+            };
+        }
+
+        builder.build()
+    }
+
     /// Compare the two rows.
     ///
     /// REQUIRES: the two rows must have the key columns defined by the schema.
@@ -17,7 +17,13 @@
 
 //! Bytes that can safely cast to str/string.
 
-use std::{convert::TryFrom, fmt, ops, str};
+use std::{
+    borrow::Borrow,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    ops, str,
+};
 
 use bytes_ext::Bytes;
 use snafu::{Backtrace, ResultExt, Snafu};
@@ -35,7 +41,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 /// String using [crate::bytes::Bytes] as storage so it can be cast into `Bytes`
 /// and clone like `Bytes`.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialOrd)]
 pub struct StringBytes(Bytes);
 
 impl StringBytes {
@@ -68,6 +74,34 @@ impl StringBytes {
     pub fn as_str(&self) -> &str {
         unsafe { str::from_utf8_unchecked(self.as_bytes()) }
     }
+
+    /// Whether the two strings share the same storage, e.g. both of them are
+    /// interned.
+    #[inline]
+    pub fn ptr_eq(&self, other: &StringBytes) -> bool {
+        self.0.len() == other.0.len() && self.0.as_ptr() == other.0.as_ptr()
+    }
+}
+
+impl PartialEq for StringBytes {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        // The interned strings can be compared by the pointers.
+        self.ptr_eq(other) || self.0 == other.0
+    }
+}
+
+// The hash must be the same as the one of `str` to implement `Borrow<str>`.
+impl Hash for StringBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Borrow<str> for StringBytes {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
 }
 
 impl Default for StringBytes {
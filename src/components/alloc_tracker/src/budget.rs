@@ -0,0 +1,145 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Memory budget shared by the components of the process.
+//!
+//! The memtables, the caches and the queries reserve their memory from the
+//! [MemoryBudget] of their [Category], and they are expected to react once the
+//! limit of the category is exceeded: the memtables are flushed earlier, the
+//! cached objects are evicted and the queries spill or abort, instead of
+//! running out of the memory of the process.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Category of the memory in the budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    WriteBuffer,
+    Query,
+    Cache,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::WriteBuffer, Category::Query, Category::Cache];
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::WriteBuffer => "write_buffer",
+            Category::Query => "query",
+            Category::Cache => "cache",
+        }
+    }
+}
+
+/// Limits and usages of the memory of all the categories, zero limit means no
+/// limit.
+#[derive(Debug, Default)]
+pub struct MemoryBudget {
+    limits: [usize; 3],
+    usages: [AtomicUsize; 3],
+}
+
+pub type MemoryBudgetRef = Arc<MemoryBudget>;
+
+impl MemoryBudget {
+    pub fn new(write_buffer_limit: usize, query_limit: usize, cache_limit: usize) -> Self {
+        Self {
+            limits: [write_buffer_limit, query_limit, cache_limit],
+            usages: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn limit(&self, category: Category) -> usize {
+        self.limits[category.index()]
+    }
+
+    #[inline]
+    pub fn usage(&self, category: Category) -> usize {
+        self.usages[category.index()].load(Ordering::Relaxed)
+    }
+
+    /// Whether the usage of the `category` reaches its limit.
+    #[inline]
+    pub fn exceeded(&self, category: Category) -> bool {
+        let limit = self.limit(category);
+        limit > 0 && self.usage(category) >= limit
+    }
+
+    /// Reserve the `bytes` regardless of the limit, used by the components
+    /// which can't refuse the memory, e.g. the memtables.
+    #[inline]
+    pub fn reserve(&self, category: Category, bytes: usize) {
+        self.usages[category.index()].fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Try to reserve the `bytes`, returns false and reserves nothing if the
+    /// limit will be exceeded.
+    pub fn try_reserve(&self, category: Category, bytes: usize) -> bool {
+        let limit = self.limit(category);
+        if limit == 0 {
+            self.reserve(category, bytes);
+            return true;
+        }
+
+        self.usages[category.index()]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                let usage = usage.checked_add(bytes)?;
+                (usage <= limit).then_some(usage)
+            })
+            .is_ok()
+    }
+
+    /// Release the `bytes` reserved before.
+    #[inline]
+    pub fn release(&self, category: Category, bytes: usize) {
+        self.usages[category.index()].fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_budget() {
+        let budget = MemoryBudget::new(100, 0, 10);
+
+        budget.reserve(Category::WriteBuffer, 150);
+        assert!(budget.exceeded(Category::WriteBuffer));
+        budget.release(Category::WriteBuffer, 100);
+        assert!(!budget.exceeded(Category::WriteBuffer));
+        assert_eq!(50, budget.usage(Category::WriteBuffer));
+
+        // No limit.
+        assert!(budget.try_reserve(Category::Query, usize::MAX / 2));
+        assert!(!budget.exceeded(Category::Query));
+
+        assert!(budget.try_reserve(Category::Cache, 10));
+        assert!(!budget.try_reserve(Category::Cache, 1));
+        assert_eq!(10, budget.usage(Category::Cache));
+        assert!(budget.exceeded(Category::Cache));
+    }
+}
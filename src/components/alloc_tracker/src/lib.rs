@@ -17,6 +17,8 @@
 
 //! Alloc tracker
 
+pub mod budget;
+
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Collect memory usage from tracker, useful for extending the tracker
@@ -18,7 +18,8 @@
 use std::{
     fmt,
     fs::{File, OpenOptions},
-    io,
+    future::Future,
+    io::{self, Write},
     str::FromStr,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -33,8 +34,9 @@ pub use log::{
 };
 use runtime::Priority;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 pub use slog::Level;
-use slog::{slog_o, Drain, Key, OwnedKVList, Record, KV};
+use slog::{slog_o, Drain, Key, OwnedKV, OwnedKVList, Record, SingleKV, KV};
 use slog_async::{Async, OverflowStrategy};
 use slog_term::{Decorator, PlainDecorator, RecordDecorator, TermDecorator};
 
@@ -43,6 +45,17 @@ const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
 pub const SLOW_QUERY_TAG: &str = "slow";
 pub const DEFAULT_TAG: &str = "";
 
+/// Max level of the std log set by the [RuntimeLevel], which is restored after
+/// all the verbose scopes exit.
+static STD_LOG_LEVEL: AtomicUsize = AtomicUsize::new(0);
+/// Number of the verbose scopes running now.
+static VERBOSE_SCOPES: AtomicUsize = AtomicUsize::new(0);
+
+tokio::task_local! {
+    static VERBOSE_SCOPE: VerboseScope;
+    static REQUEST_ID: String;
+}
+
 // Thanks to tikv
 // https://github.com/tikv/tikv/blob/eaeb39a2c85684de08c48cf4b9426b3faf4defe6/components/tikv_util/src/logger/mod.rs
 
@@ -99,6 +112,17 @@ pub fn file_drainer(path: &Option<String>) -> Option<LogFormat<PlainDecorator<Fi
     }
 }
 
+pub fn json_file_drainer(path: &Option<String>) -> Option<JsonFormat<File>> {
+    path.as_ref().map(|path| {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        JsonFormat::new(file)
+    })
+}
+
 /// Dispatcher for logs
 pub struct LogDispatcher<N, S> {
     normal: N,
@@ -132,11 +156,23 @@ where
     }
 }
 
+/// Format of the logs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// Plain text, see [LogFormat].
+    #[default]
+    Text,
+    /// A json object per line, see [JsonFormat].
+    Json,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 /// The config for logger.
 pub struct Config {
     pub level: String,
+    pub format: Format,
     pub enable_async: bool,
     pub async_channel_len: i32,
     pub slow_query_path: Option<String>,
@@ -147,6 +183,7 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             level: "info".to_string(),
+            format: Format::Text,
             enable_async: true,
             async_channel_len: 102400,
             slow_query_path: None,
@@ -167,18 +204,33 @@ pub fn init_log(config: &Config) -> Result<RuntimeLevel, SetLoggerError> {
         }
     };
 
-    let normal_drain = term_drainer();
-    let slow_drain = file_drainer(&config.slow_query_path);
-    let drain = LogDispatcher::new(normal_drain, slow_drain);
-
     // Use async and init stdlog
-    init_log_from_drain(
-        drain,
-        level,
-        config.enable_async,
-        config.async_channel_len,
-        true,
-    )
+    match config.format {
+        Format::Text => {
+            let normal_drain = term_drainer();
+            let slow_drain = file_drainer(&config.slow_query_path);
+            let drain = LogDispatcher::new(normal_drain, slow_drain);
+            init_log_from_drain(
+                drain,
+                level,
+                config.enable_async,
+                config.async_channel_len,
+                true,
+            )
+        }
+        Format::Json => {
+            let normal_drain = JsonFormat::new(io::stdout());
+            let slow_drain = json_file_drainer(&config.slow_query_path);
+            let drain = LogDispatcher::new(normal_drain, slow_drain);
+            init_log_from_drain(
+                drain,
+                level,
+                config.enable_async,
+                config.async_channel_len,
+                true,
+            )
+        }
+    }
 }
 
 pub fn init_log_from_drain<D>(
@@ -215,6 +267,7 @@ where
     slog_global::set_global(root_logger);
     if init_stdlog {
         slog_global::redirect_std_log(Some(level))?;
+        STD_LOG_LEVEL.store(level.as_usize(), Ordering::Relaxed);
     }
 
     Ok(runtime_level)
@@ -263,6 +316,65 @@ where
     }
 }
 
+// e.g.
+// ```text
+// {"ts":"2020-01-20 13:00:14.998","level":"INFO","location":"src/engine/rocksdb/rocks_kv.rs:394","msg":"RocksKV::open_with_op start, name:autogen","request_id":"1"}
+// ```
+pub struct JsonFormat<W: io::Write> {
+    writer: Mutex<W>,
+}
+
+impl<W: io::Write> JsonFormat<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<W: io::Write> Drain for JsonFormat<W> {
+    type Err = io::Error;
+    type Ok = ();
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let mut fields = Map::new();
+        fields.insert(
+            "ts".to_string(),
+            Value::from(chrono::Local::now().format(TIMESTAMP_FORMAT).to_string()),
+        );
+        fields.insert(
+            "level".to_string(),
+            Value::from(record.level().as_short_str()),
+        );
+        fields.insert(
+            "location".to_string(),
+            Value::from(format!("{}:{}", record.file(), record.line())),
+        );
+        fields.insert("msg".to_string(), Value::from(record.msg().to_string()));
+
+        let mut serializer = JsonSerializer(&mut fields);
+        record.kv().serialize(record, &mut serializer)?;
+        values.serialize(record, &mut serializer)?;
+
+        // Write the whole line at once, so lines from different threads are
+        // not interleaved.
+        let mut line = serde_json::to_vec(&fields)?;
+        line.push(b'\n');
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&line)?;
+        writer.flush()
+    }
+}
+
+struct JsonSerializer<'a>(&'a mut Map<String, Value>);
+
+impl<'a> slog::Serializer for JsonSerializer<'a> {
+    fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments<'_>) -> slog::Result {
+        self.0.insert(key.to_string(), Value::from(val.to_string()));
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct RuntimeLevel {
     level: Arc<AtomicUsize>,
@@ -285,7 +397,10 @@ impl RuntimeLevel {
     pub fn set_level(&self, level: Level) {
         self.level.store(level.as_usize(), Ordering::Relaxed);
         // Log level of std log is not changed unless we call `log::set_max_level`
-        log::set_max_level(convert_slog_level_to_log_level(level).to_level_filter());
+        STD_LOG_LEVEL.store(level.as_usize(), Ordering::Relaxed);
+        if VERBOSE_SCOPES.load(Ordering::Relaxed) == 0 {
+            log::set_max_level(convert_slog_level_to_log_level(level).to_level_filter());
+        }
 
         // We should not print things about logger use the logger...
         println!(
@@ -344,14 +459,121 @@ where
     fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
         let current_level = self.runtime_level.current_level();
 
-        if record.level().is_at_least(current_level) {
-            Ok(Some(self.drain.log(record, values)?))
-        } else {
-            Ok(None)
+        // The scopes are only visible here as the filter is called by the
+        // thread emitting the log, so the request id is attached here too.
+        let (enabled, request_id) = match VERBOSE_SCOPE.try_with(|scope| scope.clone()) {
+            Ok(scope) => (
+                record.level().is_at_least(current_level)
+                    || record.level().is_at_least(scope.level),
+                Some(scope.request_id),
+            ),
+            Err(_) => (
+                record.level().is_at_least(current_level),
+                current_request_id(),
+            ),
+        };
+        if !enabled {
+            return Ok(None);
+        }
+
+        match request_id {
+            Some(request_id) => {
+                let values = OwnedKVList::from(OwnedKV((
+                    SingleKV::from(("request_id", request_id)),
+                    values.clone(),
+                )));
+                Ok(Some(self.drain.log(record, &values)?))
+            }
+            None => Ok(Some(self.drain.log(record, values)?)),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct VerboseScope {
+    level: Level,
+    request_id: String,
+}
+
+/// Raise the max level of the std log during the verbose scopes, otherwise the
+/// verbose logs are dropped by the `log` macros before reaching the filter.
+struct VerboseScopeGuard;
+
+impl VerboseScopeGuard {
+    fn new() -> Self {
+        VERBOSE_SCOPES.fetch_add(1, Ordering::Relaxed);
+        log::set_max_level(log::LevelFilter::Trace);
+
+        Self
+    }
+}
+
+impl Drop for VerboseScopeGuard {
+    fn drop(&mut self) {
+        if VERBOSE_SCOPES.fetch_sub(1, Ordering::Relaxed) == 1 {
+            let level =
+                Level::from_usize(STD_LOG_LEVEL.load(Ordering::Relaxed)).unwrap_or(Level::Info);
+            log::set_max_level(convert_slog_level_to_log_level(level).to_level_filter());
+        }
+    }
+}
+
+/// Parse the level of the verbose scope, only the levels more verbose than the
+/// info level are allowed.
+pub fn parse_verbose_level(level_str: &str) -> Result<Level, String> {
+    Level::from_str(level_str)
+        .map_err(|_| format!("Invalid level {level_str}"))
+        .and_then(|level| match level {
+            Level::Trace | Level::Debug => Ok(level),
+            _ => Err("Only allow verbose level to be <trace|debug>".to_owned()),
+        })
+}
+
+/// Run the future in a verbose scope, where the logs at `level` or above are
+/// emitted regardless of the runtime level, and tagged with the `request_id`.
+///
+/// The scope is carried by the task running the future, so the logs of the
+/// tasks spawned by it are not covered, but the request id can still be
+/// carried to them by [with_current_request_id].
+pub async fn with_verbose_scope<F: Future>(level: Level, request_id: String, f: F) -> F::Output {
+    let _guard = VerboseScopeGuard::new();
+    let scope = VerboseScope {
+        level,
+        request_id: request_id.clone(),
+    };
+    REQUEST_ID
+        .scope(request_id, VERBOSE_SCOPE.scope(scope, f))
+        .await
+}
+
+/// Run the future with the `request_id`, which is attached to all the logs
+/// emitted by the task running it.
+pub async fn with_request_id<F: Future>(request_id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(request_id, f).await
+}
+
+/// Carry the request id of the current task (if any) to the future, which is
+/// useful when the future is spawned as another task.
+pub fn with_current_request_id<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    let request_id = current_request_id();
+    async move {
+        match request_id {
+            Some(request_id) => with_request_id(request_id, f).await,
+            None => f.await,
         }
     }
 }
 
+/// The request id of the current task if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|v| v.clone()).ok()
+}
+
+/// Whether the current task is running in a verbose scope.
+pub fn is_verbose() -> bool {
+    VERBOSE_SCOPE.try_with(|_| ()).is_ok()
+}
+
 fn write_log_header(decorator: &mut dyn RecordDecorator, record: &Record<'_>) -> io::Result<()> {
     decorator.start_timestamp()?;
     write!(
@@ -611,4 +833,98 @@ mod tests {
 
         assert_eq!(runtime_level.current_level(), Level::Info);
     }
+
+    #[tokio::test]
+    async fn test_verbose_scope() {
+        assert!(!is_verbose());
+        assert_eq!(parse_verbose_level("debug").unwrap(), Level::Debug);
+        assert_eq!(parse_verbose_level("trace").unwrap(), Level::Trace);
+        assert!(parse_verbose_level("info").is_err());
+        assert!(parse_verbose_level("no such level").is_err());
+
+        with_verbose_scope(Level::Debug, "1".to_string(), async {
+            assert!(is_verbose());
+            assert_eq!(log::max_level(), log::LevelFilter::Trace);
+
+            // The request id is kept for the spawned tasks.
+            assert_eq!(current_request_id().as_deref(), Some("1"));
+            let handle = tokio::spawn(with_current_request_id(async { current_request_id() }));
+            assert_eq!(handle.await.unwrap().as_deref(), Some("1"));
+        })
+        .await;
+        assert!(!is_verbose());
+        assert_eq!(VERBOSE_SCOPES.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_id() {
+        assert_eq!(current_request_id(), None);
+
+        with_request_id("1".to_string(), async {
+            assert_eq!(current_request_id().as_deref(), Some("1"));
+
+            let handle = tokio::spawn(with_current_request_id(async { current_request_id() }));
+            assert_eq!(handle.await.unwrap().as_deref(), Some("1"));
+            let handle = tokio::spawn(async { current_request_id() });
+            assert_eq!(handle.await.unwrap(), None);
+        })
+        .await;
+        assert_eq!(current_request_id(), None);
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        static LOCATION: slog::RecordLocation = slog::RecordLocation {
+            file: "src/lib.rs",
+            line: 1,
+            column: 1,
+            function: "",
+            module: "logger",
+        };
+        let record_static = slog::RecordStatic {
+            location: &LOCATION,
+            tag: DEFAULT_TAG,
+            level: Level::Info,
+        };
+        let kv = SingleKV::from(("rows", 10));
+        let values = OwnedKVList::from(OwnedKV(SingleKV::from(("table", "t"))));
+
+        let buf = SharedBuf::default();
+        let drain = JsonFormat::new(buf.clone());
+        drain
+            .log(
+                &Record::new(
+                    &record_static,
+                    &format_args!("hello {}", "world"),
+                    slog::BorrowedKV(&kv),
+                ),
+                &values,
+            )
+            .unwrap();
+
+        let lines = buf.0.lock().unwrap().clone();
+        let lines = String::from_utf8(lines).unwrap();
+        assert_eq!(lines.lines().count(), 1);
+
+        let log: Map<String, Value> = serde_json::from_str(lines.trim_end()).unwrap();
+        assert_eq!(log["level"], "INFO");
+        assert_eq!(log["msg"], "hello world");
+        assert_eq!(log["rows"], "10");
+        assert_eq!(log["table"], "t");
+        assert!(log.contains_key("ts"));
+        assert_eq!(log["location"], "src/lib.rs:1");
+    }
 }
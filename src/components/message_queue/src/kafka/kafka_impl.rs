@@ -139,6 +139,49 @@ impl KafkaImpl {
         let inner = KafkaImplInner::new(config).await?;
         Ok(Self(Arc::new(inner)))
     }
+
+    /// List the partitions of the topic, which is empty if the topic doesn't
+    /// exist.
+    ///
+    /// The topics created by [MessageQueue::create_topic_if_not_exist] have
+    /// only one partition, but the topics created by others may have more.
+    pub async fn partitions(&self, topic_name: &str) -> Result<Vec<i32>> {
+        let topics = self.0.client.list_topics().await.context(ListTopics)?;
+        let partitions = topics
+            .into_iter()
+            .find(|topic| topic.name == topic_name)
+            .map(|topic| topic.partitions.into_iter().collect())
+            .unwrap_or_default();
+
+        Ok(partitions)
+    }
+
+    /// Consume the given partition of the topic, unlike
+    /// [MessageQueue::consume] which only consumes the default partition.
+    pub async fn consume_partition(
+        &self,
+        topic_name: &str,
+        partition: i32,
+        start_offset: StartOffset,
+    ) -> Result<KafkaConsumeIterator> {
+        info!("Consume data in kafka topic:{topic_name}, partition:{partition}");
+
+        let partition_client = self
+            .0
+            .client
+            .partition_client(topic_name, partition, UnknownTopicHandling::Retry)
+            .await
+            .context(Consume {
+                topic_name: topic_name.to_string(),
+                when: ConsumeWhen::Start,
+            })?;
+        Ok(KafkaConsumeIterator::new(
+            topic_name,
+            self.0.config.consumer.clone(),
+            Arc::new(partition_client),
+            start_offset,
+        ))
+    }
 }
 
 struct KafkaImplInner {
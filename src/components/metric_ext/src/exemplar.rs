@@ -0,0 +1,155 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Exemplars of the histogram metrics
+//!
+//! The prometheus client doesn't support exemplars, so the latest exemplar of
+//! every bucket is kept here and appended to the bucket samples when the
+//! metrics are exported in the OpenMetrics format.
+
+use std::{
+    fmt::Write,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// An observation linked to a trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exemplar {
+    pub trace_id: String,
+    pub value: f64,
+    /// Seconds since the unix epoch.
+    pub timestamp: f64,
+}
+
+/// Latest exemplars of the buckets of a histogram without labels.
+#[derive(Debug)]
+pub struct HistogramExemplars {
+    /// Upper bounds of the buckets, the `+Inf` bucket is not included.
+    buckets: Vec<f64>,
+    /// Exemplars of the buckets, the last one is for the `+Inf` bucket.
+    exemplars: Mutex<Vec<Option<Exemplar>>>,
+}
+
+impl HistogramExemplars {
+    /// Create with the same `buckets` as the histogram.
+    pub fn new(buckets: Vec<f64>) -> Self {
+        let exemplars = vec![None; buckets.len() + 1];
+        Self {
+            buckets,
+            exemplars: Mutex::new(exemplars),
+        }
+    }
+
+    /// Record `value` observed by the trace `trace_id` as the exemplar of the
+    /// bucket it falls into.
+    pub fn observe(&self, value: f64, trace_id: &str) {
+        let idx = self.bucket_index(value);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        self.exemplars.lock().unwrap()[idx] = Some(Exemplar {
+            trace_id: trace_id.to_string(),
+            value,
+            timestamp,
+        });
+    }
+
+    /// Exemplar of the bucket whose upper bound is `le`.
+    pub fn get(&self, le: f64) -> Option<Exemplar> {
+        let idx = if le.is_infinite() {
+            self.buckets.len()
+        } else {
+            self.buckets.iter().position(|bound| *bound == le)?
+        };
+        self.exemplars.lock().unwrap()[idx].clone()
+    }
+
+    /// Append the exemplars to the bucket samples of the histogram `name` in
+    /// the exported `text`.
+    pub fn append_to(&self, name: &str, text: &str) -> String {
+        let bucket_prefix = format!("{name}_bucket{{");
+        let mut output = String::with_capacity(text.len());
+        for line in text.lines() {
+            output.push_str(line);
+            if let Some(exemplar) = line
+                .strip_prefix(&bucket_prefix)
+                .and_then(parse_le)
+                .and_then(|le| self.get(le))
+            {
+                let _ = write!(
+                    output,
+                    " # {{trace_id=\"{}\"}} {} {:.3}",
+                    exemplar.trace_id, exemplar.value, exemplar.timestamp
+                );
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn bucket_index(&self, value: f64) -> usize {
+        self.buckets
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.buckets.len())
+    }
+}
+
+/// Parse the `le` label of a bucket sample, e.g. `le="0.5"} 10`.
+fn parse_le(labels: &str) -> Option<f64> {
+    let start = labels.find("le=\"")? + 4;
+    let len = labels[start..].find('"')?;
+    labels[start..start + len].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_exemplars() {
+        let exemplars = HistogramExemplars::new(vec![0.1, 1.0]);
+        exemplars.observe(0.5, "req-1");
+        exemplars.observe(0.7, "req-2");
+        exemplars.observe(3.0, "req-3");
+
+        assert!(exemplars.get(0.1).is_none());
+        assert_eq!("req-2", exemplars.get(1.0).unwrap().trace_id);
+        assert_eq!("req-3", exemplars.get(f64::INFINITY).unwrap().trace_id);
+
+        let text = "# TYPE query_duration histogram\n\
+                    query_duration_bucket{le=\"0.1\"} 0\n\
+                    query_duration_bucket{le=\"1\"} 2\n\
+                    query_duration_bucket{le=\"+Inf\"} 3\n\
+                    query_duration_sum 4.2\n";
+        let lines: Vec<_> = exemplars
+            .append_to("query_duration", text)
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!("query_duration_bucket{le=\"0.1\"} 0", lines[1]);
+        assert!(
+            lines[2].starts_with("query_duration_bucket{le=\"1\"} 2 # {trace_id=\"req-2\"} 0.7 ")
+        );
+        assert!(
+            lines[3].starts_with("query_duration_bucket{le=\"+Inf\"} 3 # {trace_id=\"req-3\"} 3 ")
+        );
+        assert_eq!("query_duration_sum 4.2", lines[4]);
+    }
+}
@@ -15,6 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod exemplar;
+
 /// Copied from https://github.com/sunng87/metriki/blob/master/metriki-core/src/metrics/meter.rs
 /// But supports 1 hour and 2 hour rate.
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -17,7 +17,7 @@
 
 use upstream::{
     aws::{AmazonS3, AmazonS3Builder},
-    ClientOptions, RetryConfig,
+    ClientOptions,
 };
 
 use crate::config::AliyunOptions;
@@ -42,11 +42,7 @@ pub fn try_new(aliyun_opts: &AliyunOptions) -> upstream::Result<AmazonS3> {
         .with_http2_keep_alive_while_idle()
         .with_http2_keep_alive_interval(aliyun_opts.http.keep_alive_interval.0)
         .with_timeout(aliyun_opts.http.timeout.0);
-    let retry_config = RetryConfig {
-        max_retries: aliyun_opts.retry.max_retries,
-        retry_timeout: aliyun_opts.retry.retry_timeout.0,
-        ..Default::default()
-    };
+    let retry_config = aliyun_opts.retry.to_retry_config();
 
     let endpoint = &aliyun_opts.endpoint;
     let bucket = &aliyun_opts.bucket;
@@ -0,0 +1,158 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Priorities of the cached objects.
+//!
+//! The objects are grouped by their directory (e.g. all the ssts of a table),
+//! and the caches evict the objects of lower priority first, so the objects
+//! with [`CachePriority::High`] are evicted only when there is nothing else to
+//! evict.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    str::FromStr,
+    sync::RwLock,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum CachePriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl CachePriority {
+    /// All the priorities, in the order of eviction.
+    pub const ALL: [CachePriority; 3] = [
+        CachePriority::Low,
+        CachePriority::Normal,
+        CachePriority::High,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CachePriority::Low => "LOW",
+            CachePriority::Normal => "NORMAL",
+            CachePriority::High => "HIGH",
+        }
+    }
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Display for CachePriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CachePriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "LOW" => Ok(CachePriority::Low),
+            "NORMAL" => Ok(CachePriority::Normal),
+            "HIGH" => Ok(CachePriority::High),
+            _ => Err(format!(
+                "unknown cache priority:{s}, expect one of LOW, NORMAL and HIGH"
+            )),
+        }
+    }
+}
+
+lazy_static! {
+    /// The directories whose priority is not [`CachePriority::Normal`].
+    static ref DIR_PRIORITIES: RwLock<HashMap<String, CachePriority>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Set the cache priority of all the objects under the `dir`.
+pub fn set_dir_priority(dir: &str, priority: CachePriority) {
+    let dir = dir.trim_end_matches('/');
+    let mut priorities = DIR_PRIORITIES.write().unwrap();
+    if priority == CachePriority::Normal {
+        priorities.remove(dir);
+    } else {
+        priorities.insert(dir.to_string(), priority);
+    }
+}
+
+/// Reset the cache priority of the objects under the `dir` to the default.
+pub fn remove_dir_priority(dir: &str) {
+    set_dir_priority(dir, CachePriority::Normal)
+}
+
+/// Get the cache priority of the object at `location`, which is decided by the
+/// directory the object belongs to.
+pub fn priority_of(location: &str) -> CachePriority {
+    let priorities = DIR_PRIORITIES.read().unwrap();
+    if priorities.is_empty() {
+        return CachePriority::Normal;
+    }
+
+    let mut dir = location;
+    while let Some(pos) = dir.rfind('/') {
+        dir = &dir[..pos];
+        if let Some(priority) = priorities.get(dir) {
+            return *priority;
+        }
+    }
+
+    CachePriority::Normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache_priority() {
+        for priority in CachePriority::ALL {
+            assert_eq!(priority, priority.as_str().parse().unwrap());
+        }
+        assert_eq!(CachePriority::High, "high".parse().unwrap());
+        assert!("urgent".parse::<CachePriority>().is_err());
+    }
+
+    #[test]
+    fn test_priority_of() {
+        set_dir_priority("1000/1", CachePriority::High);
+        set_dir_priority("1000/2/", CachePriority::Low);
+
+        assert_eq!(CachePriority::High, priority_of("1000/1/3.sst"));
+        assert_eq!(CachePriority::High, priority_of("1000/1/3.sst.metadata"));
+        assert_eq!(CachePriority::Low, priority_of("1000/2/3.sst"));
+        assert_eq!(CachePriority::Normal, priority_of("1000/11/3.sst"));
+        assert_eq!(CachePriority::Normal, priority_of("3.sst"));
+
+        remove_dir_priority("1000/1");
+        remove_dir_priority("1000/2");
+        assert_eq!(CachePriority::Normal, priority_of("1000/1/3.sst"));
+        assert_eq!(CachePriority::Normal, priority_of("1000/2/3.sst"));
+    }
+}
@@ -21,6 +21,7 @@ use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
 use table_kv::config::ObkvConfig;
 use time_ext::ReadableDuration;
+use upstream::{BackoffConfig, RetryConfig};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
@@ -37,14 +38,30 @@ pub struct StorageOptions {
     pub disk_cache_partition_bits: usize,
     pub disk_cache_dir: String,
     pub object_store: ObjectStoreOptions,
+    /// Store of the cold tier, the ssts of the tables with `hot_duration` set
+    /// are moved to it once they are out of the hot duration. Tiering is
+    /// disabled if not set.
+    pub cold_object_store: Option<ObjectStoreOptions>,
+    /// Capacity of the local cache of the blocks read from the cold tier, 0
+    /// means disable it.
+    ///
+    /// The cache shares the `disk_cache_dir`, `disk_cache_page_size` and
+    /// `disk_cache_partition_bits` with the disk cache.
+    pub cold_disk_cache_capacity: ReadableSize,
 }
 
 impl Default for StorageOptions {
     fn default() -> Self {
         let root_path = "/tmp/horaedb".to_string();
 
+        // Don't let the cache take more than 1/8 of the memory available to the
+        // process, which may be limited by the cgroup.
+        let mem_cache_capacity = ReadableSize::mb(512)
+            .0
+            .min(system_stats::available_memory() / 8);
+
         StorageOptions {
-            mem_cache_capacity: ReadableSize::mb(512),
+            mem_cache_capacity: ReadableSize(mem_cache_capacity),
             mem_cache_partition_bits: 6,
             disk_cache_dir: root_path.clone(),
             disk_cache_capacity: ReadableSize::gb(0),
@@ -52,7 +69,10 @@ impl Default for StorageOptions {
             disk_cache_partition_bits: 4,
             object_store: ObjectStoreOptions::Local(LocalOptions {
                 data_dir: root_path,
+                read_only: false,
             }),
+            cold_object_store: None,
+            cold_disk_cache_capacity: ReadableSize::gb(0),
         }
     }
 }
@@ -70,6 +90,10 @@ pub enum ObjectStoreOptions {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LocalOptions {
     pub data_dir: String,
+    /// Reject all the writes to the store, and the `data_dir` is never
+    /// created or modified.
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -152,9 +176,16 @@ impl Default for HttpOptions {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct RetryOptions {
     pub max_retries: usize,
     pub retry_timeout: ReadableDuration,
+    /// The backoff before the first retry
+    pub init_backoff: ReadableDuration,
+    /// The max backoff between the retries
+    pub max_backoff: ReadableDuration,
+    /// The base of the exponential backoff
+    pub backoff_base: f64,
 }
 
 impl Default for RetryOptions {
@@ -162,6 +193,23 @@ impl Default for RetryOptions {
         Self {
             max_retries: 3,
             retry_timeout: ReadableDuration::from(Duration::from_secs(3 * 60)),
+            init_backoff: ReadableDuration::millis(100),
+            max_backoff: ReadableDuration::secs(15),
+            backoff_base: 2.0,
+        }
+    }
+}
+
+impl RetryOptions {
+    pub fn to_retry_config(&self) -> RetryConfig {
+        RetryConfig {
+            backoff: BackoffConfig {
+                init_backoff: self.init_backoff.0,
+                max_backoff: self.max_backoff.0,
+                base: self.backoff_base,
+            },
+            max_retries: self.max_retries,
+            retry_timeout: self.retry_timeout.0,
         }
     }
 }
@@ -48,8 +48,9 @@ use upstream::{
     ObjectStore, Result,
 };
 
-use crate::metrics::{
-    DISK_CACHE_DEDUP_COUNT, OBJECT_STORE_DISK_CACHE_HIT, OBJECT_STORE_DISK_CACHE_MISS,
+use crate::{
+    metrics::{DISK_CACHE_DEDUP_COUNT, OBJECT_STORE_DISK_CACHE_HIT, OBJECT_STORE_DISK_CACHE_MISS},
+    read_stats,
 };
 
 const FILE_SIZE_CACHE_CAP: usize = 1 << 18;
@@ -882,6 +883,7 @@ impl ObjectStore for DiskCacheStore {
             let range_in_file = (range.start - aligned_start)..(range.end - aligned_start);
             if let Some(bytes) = self.cache.get_data(&filename, &range_in_file).await {
                 OBJECT_STORE_DISK_CACHE_HIT.inc();
+                read_stats::record_cache_hit(bytes.len());
                 return Ok(bytes);
             }
 
@@ -917,6 +919,7 @@ impl ObjectStore for DiskCacheStore {
                 };
                 let filename = Self::page_cache_name(location, &(page_start..page_end));
                 if let Some(bytes) = self.cache.get_data(&filename, &range_in_file).await {
+                    read_stats::record_cache_hit(bytes.len());
                     paged_bytes[page_idx] = Some(bytes);
                 } else {
                     num_missing_pages += 1;
@@ -25,6 +25,7 @@ pub use upstream::{
 };
 
 pub mod aliyun;
+pub mod cache_priority;
 pub mod config;
 pub mod disk_cache;
 pub mod mem_cache;
@@ -32,8 +33,11 @@ pub mod metrics;
 pub mod multipart;
 pub mod obkv;
 pub mod prefix;
+pub mod read_only;
+pub mod read_stats;
 pub mod s3;
 #[cfg(test)]
 pub mod test_util;
+pub mod tiered;
 
 pub type ObjectStoreRef = Arc<dyn ObjectStore>;
@@ -18,6 +18,9 @@
 //! An implementation of ObjectStore, which support
 //! 1. Cache based on memory, and support evict based on memory usage
 //! 2. Builtin Partition to reduce lock contention
+//! 3. Evict the objects of lower [`CachePriority`] first
+//! 4. Evict the objects once the cache limit of the [`MemoryBudget`] is
+//!    exceeded
 
 use std::{
     fmt::{self, Display},
@@ -26,6 +29,7 @@ use std::{
     sync::Arc,
 };
 
+use alloc_tracker::budget::{Category, MemoryBudgetRef};
 use async_trait::async_trait;
 use bytes::Bytes;
 use clru::{CLruCache, CLruCacheConfig, WeightScale};
@@ -41,8 +45,12 @@ use upstream::{
 };
 
 use crate::{
-    metrics::{OBJECT_STORE_MEMORY_CACHE_HIT, OBJECT_STORE_MEMORY_CACHE_MISS},
-    ObjectStoreRef,
+    cache_priority::{self, CachePriority},
+    metrics::{
+        OBJECT_STORE_MEMORY_CACHE_EVICT_COUNTER, OBJECT_STORE_MEMORY_CACHE_HIT,
+        OBJECT_STORE_MEMORY_CACHE_MISS,
+    },
+    read_stats, ObjectStoreRef,
 };
 
 #[derive(Debug, Snafu)]
@@ -61,10 +69,132 @@ impl WeightScale<String, Bytes> for CustomScale {
     }
 }
 
+/// The cached objects of one partition, which are split into tiers by the
+/// [`CachePriority`] of the objects.
+///
+/// Every tier is able to hold all the memory of the partition, and the total
+/// memory is limited by evicting the least recently used objects from the
+/// lowest non-empty tier.
+struct TieredLru {
+    cap: NonZeroUsize,
+    tiers: Vec<CLruCache<String, Bytes, RandomState, CustomScale>>,
+    /// The weight of the objects is reserved from the budget if it's set.
+    budget: Option<MemoryBudgetRef>,
+}
+
+impl TieredLru {
+    fn new(cap: NonZeroUsize) -> Self {
+        let tiers = CachePriority::ALL
+            .iter()
+            .map(|_| {
+                CLruCache::with_config(
+                    CLruCacheConfig::new(cap)
+                        .with_hasher(build_fixed_seed_ahasher_builder())
+                        .with_scale(CustomScale),
+                )
+            })
+            .collect();
+
+        Self {
+            cap,
+            tiers,
+            budget: None,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.cap.get()
+    }
+
+    fn weight(&self) -> usize {
+        self.tiers.iter().map(|tier| tier.weight()).sum()
+    }
+
+    fn get(&mut self, key: &str, priority: CachePriority) -> Option<&Bytes> {
+        // The priority of the object may be changed after it is cached, so all
+        // the tiers are searched.
+        let idx = Self::search_order(priority).find(|idx| self.tiers[*idx].peek(key).is_some())?;
+        self.tiers[idx].get(key)
+    }
+
+    fn peek(&self, key: &str, priority: CachePriority) -> Option<&Bytes> {
+        Self::search_order(priority).find_map(|idx| self.tiers[idx].peek(key))
+    }
+
+    fn put(&mut self, key: String, value: Bytes, priority: CachePriority) {
+        let weight = self.weight();
+        for (idx, tier) in self.tiers.iter_mut().enumerate() {
+            if idx != priority.index() {
+                tier.pop(&key);
+            }
+        }
+
+        // don't care error now.
+        _ = self.tiers[priority.index()].put_with_weight(key, value);
+        self.update_budget(weight);
+
+        while self.weight() > self.cap.get() || self.budget_exceeded() {
+            let Some(victim) = self.tiers.iter().position(|tier| !tier.is_empty()) else {
+                break;
+            };
+            let weight = self.weight();
+            self.tiers[victim].pop_back();
+            self.update_budget(weight);
+            OBJECT_STORE_MEMORY_CACHE_EVICT_COUNTER
+                .with_label_values(&[CachePriority::ALL[victim].as_str()])
+                .inc();
+        }
+    }
+
+    /// Update the reserved memory of the budget after the weight is changed
+    /// from `old_weight`.
+    fn update_budget(&self, old_weight: usize) {
+        let Some(budget) = &self.budget else {
+            return;
+        };
+
+        let weight = self.weight();
+        if weight > old_weight {
+            budget.reserve(Category::Cache, weight - old_weight);
+        } else {
+            budget.release(Category::Cache, old_weight - weight);
+        }
+    }
+
+    fn budget_exceeded(&self) -> bool {
+        self.budget
+            .as_ref()
+            .map(|budget| budget.exceeded(Category::Cache))
+            .unwrap_or(false)
+    }
+
+    fn search_order(priority: CachePriority) -> impl Iterator<Item = usize> {
+        let first = priority.index();
+        std::iter::once(first).chain((0..CachePriority::ALL.len()).filter(move |idx| *idx != first))
+    }
+
+    #[cfg(test)]
+    fn keys(&self) -> Vec<String> {
+        self.tiers
+            .iter()
+            .rev()
+            .flat_map(|tier| tier.iter().map(|(key, _)| key.clone()))
+            .collect()
+    }
+}
+
+impl Drop for TieredLru {
+    fn drop(&mut self) {
+        if let Some(budget) = &self.budget {
+            budget.release(Category::Cache, self.weight());
+        }
+    }
+}
+
 pub struct MemCache {
     /// Max memory this store can use
     mem_cap: NonZeroUsize,
-    inner: PartitionedMutex<CLruCache<String, Bytes, RandomState, CustomScale>, RandomState>,
+    inner: PartitionedMutex<TieredLru, RandomState>,
 }
 
 pub type MemCacheRef = Arc<MemCache>;
@@ -74,11 +204,7 @@ impl MemCache {
         let init_lru = |partition_num: usize| -> Result<_> {
             let cap_per_part =
                 NonZeroUsize::new(mem_cap.get() / partition_num).context(InvalidCapacity)?;
-            Ok(CLruCache::with_config(
-                CLruCacheConfig::new(cap_per_part)
-                    .with_hasher(build_fixed_seed_ahasher_builder())
-                    .with_scale(CustomScale),
-            ))
+            Ok(TieredLru::new(cap_per_part))
         };
 
         let inner = PartitionedMutex::try_new(
@@ -90,32 +216,34 @@ impl MemCache {
         Ok(Self { mem_cap, inner })
     }
 
-    fn get(&self, key: &str) -> Option<Bytes> {
-        self.inner.lock(&key).get(key).cloned()
+    /// Reserve the memory of the cached objects from the `budget`, and the
+    /// objects are evicted once its cache limit is exceeded.
+    pub fn with_budget(self, budget: MemoryBudgetRef) -> Self {
+        for partition in self.inner.get_all_partition() {
+            partition.lock().unwrap().budget = Some(budget.clone());
+        }
+        self
     }
 
-    fn peek(&self, key: &str) -> Option<Bytes> {
-        self.inner.lock(&key).peek(key).cloned()
+    fn get(&self, key: &str, priority: CachePriority) -> Option<Bytes> {
+        self.inner.lock(&key).get(key, priority).cloned()
     }
 
-    fn insert(&self, key: String, value: Bytes) {
-        // don't care error now.
-        _ = self.inner.lock(&key).put_with_weight(key, value);
+    fn peek(&self, key: &str, priority: CachePriority) -> Option<Bytes> {
+        self.inner.lock(&key).peek(key, priority).cloned()
     }
 
-    /// Give a description of the cache state.
-
-    #[cfg(test)]
-    fn keys(&self, part: &CLruCache<String, Bytes, RandomState, CustomScale>) -> Vec<String> {
-        part.iter().map(|(key, _)| key).cloned().collect::<Vec<_>>()
+    fn insert(&self, key: String, value: Bytes, priority: CachePriority) {
+        self.inner.lock(&key).put(key, value, priority);
     }
 
+    /// Give a description of the cache state.
     #[cfg(test)]
     fn state_desc(&self) -> String {
         self.inner
             .get_all_partition()
             .iter()
-            .map(|part| self.keys(&part.lock().unwrap()).join(","))
+            .map(|part| part.lock().unwrap().keys().join(","))
             .enumerate()
             .map(|(part_no, keys)| format!("{part_no}: [{keys}]"))
             .collect::<Vec<_>>()
@@ -175,8 +303,10 @@ impl MemCacheStore {
         // TODO(chenxiang): What if there are some overlapping range in cache?
         // A request with range [5, 10) can also use [0, 20) cache
         let cache_key = Self::cache_key(location, &range);
-        if let Some(bytes) = self.cache.get(&cache_key) {
+        let priority = cache_priority::priority_of(location.as_ref());
+        if let Some(bytes) = self.cache.get(&cache_key, priority) {
             OBJECT_STORE_MEMORY_CACHE_HIT.inc();
+            read_stats::record_cache_hit(bytes.len());
             return Ok(bytes);
         }
 
@@ -184,7 +314,7 @@ impl MemCacheStore {
         // TODO(chenxiang): What if two threads reach here? It's better to
         // pend one thread, and only let one to fetch data from underlying store.
         let bytes = self.underlying_store.get_range(location, range).await?;
-        self.cache.insert(cache_key, bytes.clone());
+        self.cache.insert(cache_key, bytes.clone(), priority);
 
         Ok(bytes)
     }
@@ -195,7 +325,9 @@ impl MemCacheStore {
         range: Range<usize>,
     ) -> ObjectStoreResult<Bytes> {
         let cache_key = Self::cache_key(location, &range);
-        if let Some(bytes) = self.cache.peek(&cache_key) {
+        let priority = cache_priority::priority_of(location.as_ref());
+        if let Some(bytes) = self.cache.peek(&cache_key, priority) {
+            read_stats::record_cache_hit(bytes.len());
             return Ok(bytes);
         }
 
@@ -285,6 +417,7 @@ impl ObjectStore for MemCacheStore {
 
 #[cfg(test)]
 mod test {
+    use alloc_tracker::budget::MemoryBudget;
     use tempfile::tempdir;
     use upstream::local::LocalFileSystem;
 
@@ -316,7 +449,10 @@ mod test {
         _ = store.get_range(&location, range0_5.clone()).await.unwrap();
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range0_5))
+            .get(
+                &MemCacheStore::cache_key(&location, &range0_5),
+                CachePriority::Normal
+            )
             .is_some());
 
         // get bytes from [5, 10), insert to cache
@@ -324,11 +460,17 @@ mod test {
         _ = store.get_range(&location, range5_10.clone()).await.unwrap();
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range0_5))
+            .get(
+                &MemCacheStore::cache_key(&location, &range0_5),
+                CachePriority::Normal
+            )
             .is_some());
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range5_10))
+            .get(
+                &MemCacheStore::cache_key(&location, &range5_10),
+                CachePriority::Normal
+            )
             .is_some());
 
         // get bytes from [10, 15), insert to cache
@@ -340,15 +482,24 @@ mod test {
             .unwrap();
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range0_5))
+            .get(
+                &MemCacheStore::cache_key(&location, &range0_5),
+                CachePriority::Normal
+            )
             .is_none());
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range5_10))
+            .get(
+                &MemCacheStore::cache_key(&location, &range5_10),
+                CachePriority::Normal
+            )
             .is_some());
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range10_15))
+            .get(
+                &MemCacheStore::cache_key(&location, &range10_15),
+                CachePriority::Normal
+            )
             .is_some());
     }
 
@@ -380,14 +531,53 @@ mod test {
 
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range0_5))
+            .get(
+                &MemCacheStore::cache_key(&location, &range0_5),
+                CachePriority::Normal
+            )
             .is_some());
         assert!(store
             .cache
-            .get(&MemCacheStore::cache_key(&location, &range100_105))
+            .get(
+                &MemCacheStore::cache_key(&location, &range100_105),
+                CachePriority::Normal
+            )
             .is_some());
     }
 
+    #[tokio::test]
+    async fn test_mem_cache_evict_by_priority() {
+        // single partition
+        let store = prepare_store(0, 15);
+
+        let high = Path::from("mem_cache_high/1.sst");
+        let low = Path::from("mem_cache_low/1.sst");
+        cache_priority::set_dir_priority("mem_cache_high", CachePriority::High);
+        cache_priority::set_dir_priority("mem_cache_low", CachePriority::Low);
+        for location in [&high, &low] {
+            store
+                .put(location, Bytes::from_static(&[1; 1024]))
+                .await
+                .unwrap();
+        }
+
+        // the high priority object is the least recently used one.
+        _ = store.get_range(&high, 0..5).await.unwrap();
+        _ = store.get_range(&low, 0..5).await.unwrap();
+        _ = store.get_range(&low, 5..10).await.unwrap();
+
+        // cache is full, evict the low priority objects first.
+        _ = store.get_range(&low, 10..15).await.unwrap();
+        _ = store.get_range(&low, 15..20).await.unwrap();
+        assert_eq!(
+            "0: [mem_cache_high/1.sst-0-5,mem_cache_low/1.sst-15-20,mem_cache_low/1.sst-10-15]",
+            store.cache.as_ref().state_desc()
+        );
+
+        cache_priority::remove_dir_priority("mem_cache_high");
+        cache_priority::remove_dir_priority("mem_cache_low");
+    }
+
     #[test]
     fn test_mem_cache_capacity() {
         // 4 partitions
@@ -404,4 +594,32 @@ mod test {
                 .join(",")
         );
     }
+
+    #[test]
+    fn test_mem_cache_budget() {
+        let budget = Arc::new(MemoryBudget::new(0, 0, 10));
+        let cache = MemCache::try_new(0, NonZeroUsize::new(100).unwrap())
+            .unwrap()
+            .with_budget(budget.clone());
+
+        cache.insert(
+            "a".to_string(),
+            Bytes::from_static(&[1; 8]),
+            CachePriority::Normal,
+        );
+        assert_eq!(8, budget.usage(Category::Cache));
+
+        // The cache limit is exceeded, so the least recently used object is evicted.
+        cache.insert(
+            "b".to_string(),
+            Bytes::from_static(&[1; 8]),
+            CachePriority::Normal,
+        );
+        assert_eq!(8, budget.usage(Category::Cache));
+        assert!(cache.peek("a", CachePriority::Normal).is_none());
+        assert!(cache.peek("b", CachePriority::Normal).is_some());
+
+        drop(cache);
+        assert_eq!(0, budget.usage(Category::Cache));
+    }
 }
@@ -23,7 +23,8 @@ use futures::stream::BoxStream;
 use lazy_static::lazy_static;
 use logger::trace;
 use prometheus::{
-    exponential_buckets, register_histogram_vec, register_int_counter, HistogramVec, IntCounter,
+    exponential_buckets, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    HistogramVec, IntCounter, IntCounterVec,
 };
 use prometheus_static_metric::make_static_metric;
 use runtime::Runtime;
@@ -90,6 +91,12 @@ lazy_static! {
         "object store memory cache miss"
     )
     .unwrap();
+    pub static ref OBJECT_STORE_MEMORY_CACHE_EVICT_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "object_store_memory_cache_evict",
+        "object store memory cache evicted objects by cache priority",
+        &["priority"]
+    )
+    .unwrap();
     pub static ref OBJECT_STORE_DISK_CACHE_HIT: IntCounter = register_int_counter!(
         "object_store_disk_cache_hit",
         "object store disk cache hit"
@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store rejecting all the writes, e.g. to serve the queries over a
+//! restored backup without modifying it.
+
+use std::{fmt::Display, ops::Range};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use tokio::io::AsyncWrite;
+use upstream::{
+    path::Path, Error, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, Result,
+};
+
+use crate::ObjectStoreRef;
+
+#[derive(Debug)]
+pub struct ReadOnlyStore {
+    store: ObjectStoreRef,
+}
+
+impl Display for ReadOnlyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReadOnlyStore({})", self.store)
+    }
+}
+
+impl ReadOnlyStore {
+    pub fn new(store: ObjectStoreRef) -> Self {
+        Self { store }
+    }
+}
+
+fn read_only_error(op: &str, location: &Path) -> Error {
+    Error::NotSupported {
+        source: format!("{op} is rejected by the read-only store, location:{location}").into(),
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ReadOnlyStore {
+    async fn put(&self, location: &Path, _bytes: Bytes) -> Result<()> {
+        Err(read_only_error("put", location))
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        Err(read_only_error("put_multipart", location))
+    }
+
+    async fn abort_multipart(&self, location: &Path, _multipart_id: &MultipartId) -> Result<()> {
+        Err(read_only_error("abort_multipart", location))
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        self.store.get(location).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.store.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.store.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.store.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        Err(read_only_error("delete", location))
+    }
+
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        self.store.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.store.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, _from: &Path, to: &Path) -> Result<()> {
+        Err(read_only_error("copy", to))
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, to: &Path) -> Result<()> {
+        Err(read_only_error("copy_if_not_exists", to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+    use upstream::local::LocalFileSystem;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reject_writes() {
+        let dir = tempdir().unwrap();
+        let inner: ObjectStoreRef = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        let path = Path::from("1/2/3.sst");
+        inner.put(&path, Bytes::from("data")).await.unwrap();
+
+        let store = ReadOnlyStore::new(inner.clone());
+        assert_eq!(
+            Bytes::from("at"),
+            store.get_range(&path, 1..3).await.unwrap()
+        );
+        assert_eq!(4, store.head(&path).await.unwrap().size);
+
+        let other = Path::from("1/2/4.sst");
+        assert!(store.put(&other, Bytes::from("data")).await.is_err());
+        assert!(store.put_multipart(&other).await.is_err());
+        assert!(store.copy(&path, &other).await.is_err());
+        assert!(store.delete(&path).await.is_err());
+
+        // The underlying store is not modified.
+        assert!(matches!(
+            inner.head(&other).await,
+            Err(Error::NotFound { .. })
+        ));
+        assert_eq!(
+            Bytes::from("data"),
+            inner.get(&path).await.unwrap().bytes().await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Statistics of the reads issued by a scan
+//!
+//! The caches are shared by all the scans, so the scan that wants to know how
+//! many bytes are served by the caches runs its reads in the scope of a
+//! [ReadStats] by [with_read_stats], and the caches record into the one of
+//! the current scope.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+tokio::task_local! {
+    static READ_STATS: Arc<ReadStats>;
+}
+
+#[derive(Debug, Default)]
+pub struct ReadStats {
+    cache_hit_bytes: AtomicU64,
+}
+
+impl ReadStats {
+    /// Bytes served by the memory or disk cache.
+    pub fn cache_hit_bytes(&self) -> u64 {
+        self.cache_hit_bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Run `fut` in the scope of `stats`.
+pub async fn with_read_stats<F: Future>(stats: Arc<ReadStats>, fut: F) -> F::Output {
+    READ_STATS.scope(stats, fut).await
+}
+
+/// Record the bytes served by the cache into the stats of current scope if
+/// any.
+pub fn record_cache_hit(num_bytes: usize) {
+    let _ = READ_STATS.try_with(|stats| {
+        stats
+            .cache_hit_bytes
+            .fetch_add(num_bytes as u64, Ordering::Relaxed)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_stats_scope() {
+        // Nothing happens out of the scope.
+        record_cache_hit(10);
+
+        let stats = Arc::new(ReadStats::default());
+        with_read_stats(stats.clone(), async {
+            record_cache_hit(1);
+            record_cache_hit(2);
+        })
+        .await;
+        assert_eq!(3, stats.cache_hit_bytes());
+    }
+}
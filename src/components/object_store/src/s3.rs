@@ -17,7 +17,7 @@
 
 use upstream::{
     aws::{AmazonS3, AmazonS3Builder},
-    ClientOptions, RetryConfig,
+    ClientOptions,
 };
 
 use crate::config::S3Options;
@@ -30,11 +30,7 @@ pub fn try_new(s3_option: &S3Options) -> upstream::Result<AmazonS3> {
         .with_http2_keep_alive_while_idle()
         .with_http2_keep_alive_interval(s3_option.http.keep_alive_interval.0)
         .with_timeout(s3_option.http.timeout.0);
-    let retry_config = RetryConfig {
-        max_retries: s3_option.retry.max_retries,
-        retry_timeout: s3_option.retry.retry_timeout.0,
-        ..Default::default()
-    };
+    let retry_config = s3_option.retry.to_retry_config();
 
     AmazonS3Builder::new()
         .with_region(&s3_option.region)
@@ -0,0 +1,336 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store with a hot tier and a cold tier.
+//!
+//! The objects are written to the hot tier, and moved to the cold tier
+//! explicitly by [TieredStore::migrate] or written to the cold tier directly
+//! by [TieredStore::cold_store]. The reads go to the hot tier first and
+//! fall back to the cold tier if the object is not found, so the callers don't
+//! need to know where an object lives.
+//!
+//! The objects known to be in the cold tier are remembered, so they are read
+//! from the cold tier directly rather than looked up in the hot tier first.
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    ops::Range,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use upstream::{
+    path::Path, Error, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, Result,
+};
+
+use crate::ObjectStoreRef;
+
+pub type TieredStoreRef = Arc<TieredStore>;
+
+const TIERED: &str = "TIERED";
+
+#[derive(Debug)]
+pub struct TieredStore {
+    hot: ObjectStoreRef,
+    cold: ObjectStoreRef,
+    /// Paths of the objects known to be in the cold tier.
+    cold_objects: RwLock<HashSet<Path>>,
+}
+
+impl Display for TieredStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TieredStore, hot:{}, cold:{}", self.hot, self.cold)
+    }
+}
+
+impl TieredStore {
+    pub fn new(hot: ObjectStoreRef, cold: ObjectStoreRef) -> Self {
+        Self {
+            hot,
+            cold,
+            cold_objects: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// The cold tier, the objects written to it directly are found by the
+    /// reads of the [TieredStore] too.
+    pub fn cold_store(&self) -> &ObjectStoreRef {
+        &self.cold
+    }
+
+    /// Returns true if the object is in the hot tier.
+    pub async fn is_hot(&self, location: &Path) -> Result<bool> {
+        if self.is_known_cold(location) {
+            return Ok(false);
+        }
+
+        match self.hot.head(location).await {
+            Ok(_) => Ok(true),
+            Err(Error::NotFound { .. }) => {
+                self.mark_cold(location);
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Copy the object at `from` in the hot tier to `to` in the cold tier.
+    ///
+    /// The object is streamed to the cold tier by the multipart upload, so it
+    /// is never buffered in memory as a whole. The object at `from` is not
+    /// deleted, the caller should delete it once no one reads it.
+    pub async fn migrate(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut stream = self.hot.get(from).await?.into_stream();
+        let (multipart_id, mut writer) = self.cold.put_multipart(to).await?;
+        let result = async {
+            while let Some(bytes) = stream.next().await {
+                writer.write_all(&bytes?).await.map_err(io_error)?;
+            }
+            writer.shutdown().await.map_err(io_error)
+        }
+        .await;
+        if let Err(e) = result {
+            // The upload is aborted on a best effort basis.
+            let _ = self.cold.abort_multipart(to, &multipart_id).await;
+            return Err(e);
+        }
+
+        self.mark_cold(to);
+        Ok(())
+    }
+
+    fn is_known_cold(&self, location: &Path) -> bool {
+        self.cold_objects.read().unwrap().contains(location)
+    }
+
+    fn mark_cold(&self, location: &Path) {
+        self.cold_objects.write().unwrap().insert(location.clone());
+    }
+}
+
+fn io_error(source: std::io::Error) -> Error {
+    Error::Generic {
+        store: TIERED,
+        source: Box::new(source),
+    }
+}
+
+fn is_not_found<T>(result: &Result<T>) -> bool {
+    matches!(result, Err(Error::NotFound { .. }))
+}
+
+#[async_trait]
+impl ObjectStore for TieredStore {
+    async fn put(&self, location: &Path, bytes: Bytes) -> Result<()> {
+        self.cold_objects.write().unwrap().remove(location);
+        self.hot.put(location, bytes).await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.cold_objects.write().unwrap().remove(location);
+        self.hot.put_multipart(location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.hot.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        if self.is_known_cold(location) {
+            return self.cold.get(location).await;
+        }
+
+        let result = self.hot.get(location).await;
+        if is_not_found(&result) {
+            let result = self.cold.get(location).await;
+            if result.is_ok() {
+                self.mark_cold(location);
+            }
+            return result;
+        }
+        result
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        if self.is_known_cold(location) {
+            return self.cold.get_range(location, range).await;
+        }
+
+        let result = self.hot.get_range(location, range.clone()).await;
+        if is_not_found(&result) {
+            let result = self.cold.get_range(location, range).await;
+            if result.is_ok() {
+                self.mark_cold(location);
+            }
+            return result;
+        }
+        result
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        if self.is_known_cold(location) {
+            return self.cold.get_ranges(location, ranges).await;
+        }
+
+        let result = self.hot.get_ranges(location, ranges).await;
+        if is_not_found(&result) {
+            let result = self.cold.get_ranges(location, ranges).await;
+            if result.is_ok() {
+                self.mark_cold(location);
+            }
+            return result;
+        }
+        result
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        if self.is_known_cold(location) {
+            return self.cold.head(location).await;
+        }
+
+        let result = self.hot.head(location).await;
+        if is_not_found(&result) {
+            let result = self.cold.head(location).await;
+            if result.is_ok() {
+                self.mark_cold(location);
+            }
+            return result;
+        }
+        result
+    }
+
+    /// Delete the object in both of the tiers, it is ok if the object only
+    /// exists in one of them.
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.cold_objects.write().unwrap().remove(location);
+        let hot_result = self.hot.delete(location).await;
+        let cold_result = self.cold.delete(location).await;
+        match (hot_result, cold_result) {
+            (Ok(()), _) | (_, Ok(())) => Ok(()),
+            (Err(Error::NotFound { .. }), Err(e)) => Err(e),
+            (Err(e), _) => Err(e),
+        }
+    }
+
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        let hot = self.hot.list(prefix).await?;
+        let cold = self.cold.list(prefix).await?;
+        Ok(hot.chain(cold).boxed())
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let mut list_res = self.hot.list_with_delimiter(prefix).await?;
+        let cold_res = self.cold.list_with_delimiter(prefix).await?;
+        for dir in cold_res.common_prefixes {
+            if !list_res.common_prefixes.contains(&dir) {
+                list_res.common_prefixes.push(dir);
+            }
+        }
+        list_res.objects.extend(cold_res.objects);
+
+        Ok(list_res)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let result = self.hot.copy(from, to).await;
+        if is_not_found(&result) {
+            return self.cold.copy(from, to).await;
+        }
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let result = self.hot.copy_if_not_exists(from, to).await;
+        if is_not_found(&result) {
+            return self.cold.copy_if_not_exists(from, to).await;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+    use upstream::local::LocalFileSystem;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_migrate() {
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        let hot = Arc::new(LocalFileSystem::new_with_prefix(hot_dir.path()).unwrap()) as _;
+        let cold = Arc::new(LocalFileSystem::new_with_prefix(cold_dir.path()).unwrap()) as _;
+        let store = TieredStore::new(hot, cold);
+
+        let from = Path::from("1/2/3.sst");
+        let to = Path::from("1/2/4.sst");
+        store.put(&from, Bytes::from("data")).await.unwrap();
+        assert!(store.is_hot(&from).await.unwrap());
+
+        store.migrate(&from, &to).await.unwrap();
+        assert!(!store.is_hot(&to).await.unwrap());
+        let bytes = store.get_range(&to, 1..3).await.unwrap();
+        assert_eq!(Bytes::from("at"), bytes);
+        assert_eq!(4, store.head(&to).await.unwrap().size);
+
+        store.delete(&from).await.unwrap();
+        store.delete(&to).await.unwrap();
+        assert!(is_not_found(&store.head(&from).await));
+        assert!(is_not_found(&store.head(&to).await));
+    }
+
+    #[tokio::test]
+    async fn test_cold_objects() {
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        let hot: ObjectStoreRef =
+            Arc::new(LocalFileSystem::new_with_prefix(hot_dir.path()).unwrap());
+        let cold = Arc::new(LocalFileSystem::new_with_prefix(cold_dir.path()).unwrap()) as _;
+        let store = TieredStore::new(hot.clone(), cold);
+
+        // The objects written to the cold tier directly are found too.
+        let path = Path::from("1/2/3.sst");
+        store
+            .cold_store()
+            .put(&path, Bytes::from("cold"))
+            .await
+            .unwrap();
+        assert!(!store.is_hot(&path).await.unwrap());
+        assert_eq!(
+            Bytes::from("cold"),
+            store.get_range(&path, 0..4).await.unwrap()
+        );
+
+        // The known cold objects are not looked up in the hot tier any more.
+        hot.put(&path, Bytes::from("hot")).await.unwrap();
+        assert!(!store.is_hot(&path).await.unwrap());
+        assert_eq!(4, store.head(&path).await.unwrap().size);
+
+        // The objects written by the store are in the hot tier again.
+        store.put(&path, Bytes::from("hot")).await.unwrap();
+        assert!(store.is_hot(&path).await.unwrap());
+        assert_eq!(3, store.head(&path).await.unwrap().size);
+    }
+}
@@ -22,17 +22,28 @@ use std::{
 };
 
 use bytes::Bytes;
-use futures::{
-    future::{BoxFuture, FutureExt},
-    TryFutureExt,
+use futures::future::{BoxFuture, FutureExt};
+use object_store::{
+    read_stats::{self, ReadStats},
+    ObjectStoreRef, Path,
 };
-use object_store::{ObjectStoreRef, Path};
 use parquet::{arrow::async_reader::AsyncFileReader, file::metadata::ParquetMetaData};
 
 /// The observer for metrics of [ObjectStoreReader].
 pub trait MetricsObserver: Send {
     fn elapsed(&self, path: &Path, elapsed: Duration);
     fn num_bytes_fetched(&self, path: &Path, num_bytes: usize);
+
+    /// The stats to record the reads into, see [read_stats].
+    fn read_stats(&self) -> Option<Arc<ReadStats>> {
+        None
+    }
+
+    /// How long to wait before fetching the `num_bytes`, e.g. to share the
+    /// bandwidth with other readers.
+    fn fetch_delay(&self, _path: &Path, _num_bytes: usize) -> Duration {
+        Duration::ZERO
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +88,13 @@ impl<T: MetricsObserver> ObjectStoreReader<T> {
     }
 }
 
+/// Wait before fetching, see [MetricsObserver::fetch_delay].
+async fn delay_fetch(delay: Duration) {
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
 impl<T: MetricsObserver> Drop for ObjectStoreReader<T> {
     fn drop(&mut self) {
         self.metrics.elapsed(&self.path, self.begin.elapsed())
@@ -86,15 +104,17 @@ impl<T: MetricsObserver> Drop for ObjectStoreReader<T> {
 impl<T: MetricsObserver> AsyncFileReader for ObjectStoreReader<T> {
     fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
         async move {
-            let get_res = self
-                .storage
-                .get_range(&self.path, range)
-                .map_err(|e| {
-                    parquet::errors::ParquetError::General(format!(
-                        "Failed to fetch range from object store, err:{e}"
-                    ))
-                })
-                .await;
+            delay_fetch(self.metrics.fetch_delay(&self.path, range.len())).await;
+            let get_range = self.storage.get_range(&self.path, range);
+            let get_res = match self.metrics.read_stats() {
+                Some(stats) => read_stats::with_read_stats(stats, get_range).await,
+                None => get_range.await,
+            }
+            .map_err(|e| {
+                parquet::errors::ParquetError::General(format!(
+                    "Failed to fetch range from object store, err:{e}"
+                ))
+            });
 
             if let Ok(bytes) = &get_res {
                 self.metrics.num_bytes_fetched(&self.path, bytes.len());
@@ -110,15 +130,18 @@ impl<T: MetricsObserver> AsyncFileReader for ObjectStoreReader<T> {
         ranges: Vec<Range<usize>>,
     ) -> BoxFuture<'_, parquet::errors::Result<Vec<Bytes>>> {
         async move {
-            let get_res = self
-                .storage
-                .get_ranges(&self.path, &ranges)
-                .map_err(|e| {
-                    parquet::errors::ParquetError::General(format!(
-                        "Failed to fetch ranges from object store, err:{e}"
-                    ))
-                })
-                .await;
+            let num_bytes = ranges.iter().map(|v| v.len()).sum();
+            delay_fetch(self.metrics.fetch_delay(&self.path, num_bytes)).await;
+            let get_ranges = self.storage.get_ranges(&self.path, &ranges);
+            let get_res = match self.metrics.read_stats() {
+                Some(stats) => read_stats::with_read_stats(stats, get_ranges).await,
+                None => get_ranges.await,
+            }
+            .map_err(|e| {
+                parquet::errors::ParquetError::General(format!(
+                    "Failed to fetch ranges from object store, err:{e}"
+                ))
+            });
 
             if let Ok(bytes) = &get_res {
                 let num_bytes: usize = bytes.iter().map(|v| v.len()).sum();
@@ -0,0 +1,227 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Global allocator selection and its runtime controls.
+//!
+//! The allocator is chosen at compile time by the `jemalloc` or `mimalloc`
+//! feature, and the system allocator is used if none of them is enabled.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("features `jemalloc` and `mimalloc` are mutually exclusive");
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to purge the unused dirty pages in the background threads of
+    /// the allocator, only works for jemalloc.
+    pub background_thread: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            background_thread: true,
+        }
+    }
+}
+
+/// Statistics of the global allocator in bytes, see the `stats.*` mallctls of
+/// jemalloc for the meaning of every field.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct AllocatorStats {
+    pub allocated: usize,
+    pub active: usize,
+    pub resident: usize,
+    pub mapped: usize,
+    pub retained: usize,
+    pub metadata: usize,
+}
+
+/// Name of the global allocator in use.
+pub fn allocator_name() -> &'static str {
+    if cfg!(feature = "jemalloc") {
+        "jemalloc"
+    } else if cfg!(feature = "mimalloc") {
+        "mimalloc"
+    } else {
+        "system"
+    }
+}
+
+#[cfg(feature = "jemalloc")]
+mod imp {
+    use std::ptr;
+
+    use super::{AllocatorStats, Config};
+    use crate::{Error, Result};
+
+    /// `MALLCTL_ARENAS_ALL` of jemalloc, which stands for all the arenas.
+    const PURGE_ALL_ARENAS: &[u8] = b"arena.4096.purge\0";
+    const DECAY_ALL_ARENAS: &[u8] = b"arena.4096.decay\0";
+
+    pub fn init(config: &Config) -> Result<()> {
+        jemalloc_ctl::background_thread::write(config.background_thread).map_err(Error::Jemalloc)
+    }
+
+    pub fn stats() -> Result<Option<AllocatorStats>> {
+        use jemalloc_ctl::{epoch, stats};
+
+        // Statistics are cached by jemalloc and only refreshed by advancing the
+        // epoch.
+        epoch::advance().map_err(Error::Jemalloc)?;
+
+        Ok(Some(AllocatorStats {
+            allocated: stats::allocated::read().map_err(Error::Jemalloc)?,
+            active: stats::active::read().map_err(Error::Jemalloc)?,
+            resident: stats::resident::read().map_err(Error::Jemalloc)?,
+            mapped: stats::mapped::read().map_err(Error::Jemalloc)?,
+            retained: stats::retained::read().map_err(Error::Jemalloc)?,
+            metadata: stats::metadata::read().map_err(Error::Jemalloc)?,
+        }))
+    }
+
+    fn mallctl_void(name: &'static [u8]) -> Result<()> {
+        // These mallctls neither read nor write any value, so null pointers must
+        // be passed.
+        let ret = unsafe {
+            jemalloc_sys::mallctl(
+                name.as_ptr() as *const _,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+            )
+        };
+        if ret != 0 {
+            return Err(Error::Internal {
+                msg: format!(
+                    "failed to call mallctl {}, code:{ret}",
+                    String::from_utf8_lossy(&name[..name.len() - 1])
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn purge() -> Result<()> {
+        mallctl_void(PURGE_ALL_ARENAS)
+    }
+
+    pub fn decay() -> Result<()> {
+        mallctl_void(DECAY_ALL_ARENAS)
+    }
+}
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+mod imp {
+    use super::{AllocatorStats, Config};
+    use crate::Result;
+
+    pub fn init(_config: &Config) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn stats() -> Result<Option<AllocatorStats>> {
+        Ok(None)
+    }
+
+    pub fn purge() -> Result<()> {
+        unsafe { libmimalloc_sys::mi_collect(true) };
+        Ok(())
+    }
+
+    pub fn decay() -> Result<()> {
+        unsafe { libmimalloc_sys::mi_collect(false) };
+        Ok(())
+    }
+}
+
+#[cfg(not(any(feature = "jemalloc", feature = "mimalloc")))]
+mod imp {
+    use super::{AllocatorStats, Config};
+    use crate::Result;
+
+    pub fn init(_config: &Config) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn stats() -> Result<Option<AllocatorStats>> {
+        Ok(None)
+    }
+
+    pub fn purge() -> Result<()> {
+        Ok(())
+    }
+
+    pub fn decay() -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Apply the config to the global allocator, should be called once at the
+/// startup.
+pub fn init(config: &Config) -> Result<()> {
+    imp::init(config)
+}
+
+/// Current statistics of the global allocator, `None` if the allocator
+/// doesn't provide them.
+pub fn stats() -> Result<Option<AllocatorStats>> {
+    imp::stats()
+}
+
+/// Return all the unused memory of the allocator to the os immediately.
+pub fn purge() -> Result<()> {
+    imp::purge()
+}
+
+/// Return the unused memory which has passed its decay time to the os.
+pub fn decay() -> Result<()> {
+    imp::decay()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocator_controls() {
+        let _buf = vec![0u8; 1 << 20];
+        let stats = stats().unwrap();
+        if cfg!(feature = "jemalloc") {
+            let stats = stats.unwrap();
+            assert!(stats.allocated >= 1 << 20);
+            assert!(stats.active >= stats.allocated);
+        }
+
+        purge().unwrap();
+        decay().unwrap();
+    }
+}
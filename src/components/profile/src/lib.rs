@@ -17,6 +17,8 @@
 
 //! Profiler for running application.
 
+pub mod alloc;
+
 use std::{
     fmt::Formatter,
     fs::{File, OpenOptions},
@@ -27,13 +29,17 @@ use std::{
     time::Duration,
 };
 
+#[cfg(feature = "jemalloc")]
 use jemalloc_ctl::{Access, AsName};
 use logger::{error, info};
 
 #[derive(Debug)]
 pub enum Error {
-    Internal { msg: String },
+    Internal {
+        msg: String,
+    },
     IO(io::Error),
+    #[cfg(feature = "jemalloc")]
     Jemalloc(jemalloc_ctl::Error),
 }
 
@@ -47,26 +53,49 @@ impl std::error::Error for Error {}
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[global_allocator]
-static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
-
+#[cfg(feature = "jemalloc")]
 const PROF_ACTIVE: &[u8] = b"prof.active\0";
+#[cfg(feature = "jemalloc")]
 const PROF_DUMP: &[u8] = b"prof.dump\0";
+#[cfg(feature = "jemalloc")]
 const PROFILE_HEAP_OUTPUT_FILE_OS_PATH: &[u8] = b"/tmp/profile_heap.out\0";
 const PROFILE_HEAP_OUTPUT_FILE_PATH: &str = "/tmp/profile_heap.out";
 const PROFILE_CPU_OUTPUT_FILE_PATH: &str = "/tmp/flamegraph_cpu.svg";
 
+#[cfg(feature = "jemalloc")]
 fn set_prof_active(active: bool) -> Result<()> {
     let name = PROF_ACTIVE.name();
     name.write(active).map_err(Error::Jemalloc)
 }
 
+#[cfg(feature = "jemalloc")]
 fn dump_profile() -> Result<()> {
     let name = PROF_DUMP.name();
     name.write(PROFILE_HEAP_OUTPUT_FILE_OS_PATH)
         .map_err(Error::Jemalloc)
 }
 
+// Heap profiling is only supported by jemalloc.
+#[cfg(not(feature = "jemalloc"))]
+fn heap_prof_unsupported() -> Error {
+    Error::Internal {
+        msg: format!(
+            "heap profiling is not supported by allocator {}",
+            alloc::allocator_name()
+        ),
+    }
+}
+
+#[cfg(not(feature = "jemalloc"))]
+fn set_prof_active(_active: bool) -> Result<()> {
+    Err(heap_prof_unsupported())
+}
+
+#[cfg(not(feature = "jemalloc"))]
+fn dump_profile() -> Result<()> {
+    Err(heap_prof_unsupported())
+}
+
 #[allow(dead_code)]
 struct ProfLockGuard<'a>(MutexGuard<'a, ()>);
 
@@ -0,0 +1,190 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! CPU affinity of the runtime threads.
+
+use std::{fs, io, str::FromStr, thread};
+
+/// Prefix of the cpu set of a NUMA node, e.g. `numa:1`.
+const NUMA_NODE_PREFIX: &str = "numa:";
+
+/// A set of cpus the threads are pinned to.
+///
+/// It's parsed from a cpu list like `0-3,8,10-11`, or `numa:N` to use all the
+/// cpus of the NUMA node N.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CpuSet {
+    cpus: Vec<usize>,
+}
+
+impl CpuSet {
+    pub fn cpus(&self) -> &[usize] {
+        &self.cpus
+    }
+
+    /// Check whether all the cpus exist.
+    pub(crate) fn validate(&self) -> std::result::Result<(), String> {
+        let num_cpus = num_configured_cpus();
+        match self.cpus.last() {
+            None => Err("empty cpu set".to_string()),
+            Some(max) if *max >= num_cpus => {
+                Err(format!("cpu {max} exceeds the number of cpus {num_cpus}"))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Pin the current thread to the cpu set.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn bind_current_thread(&self) -> io::Result<()> {
+        if let Some(cpu) = self.cpus.iter().find(|cpu| **cpu >= libc::CPU_SETSIZE as usize) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cpu {cpu} is out of the range of cpu set"),
+            ));
+        }
+
+        // Safety: the set is zeroed and initialized by the libc macros, and the cpus
+        // are checked to be in range.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_ZERO(&mut set);
+            for cpu in &self.cpus {
+                libc::CPU_SET(*cpu, &mut set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pin a short-lived thread to the cpu set to check whether the binding
+    /// works.
+    pub(crate) fn bind_probe_thread(&self) -> io::Result<()> {
+        let cpu_set = self.clone();
+        thread::spawn(move || cpu_set.bind_current_thread())
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::Other, "probe thread panicked")))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn bind_current_thread(&self) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cpu affinity is only supported on linux",
+        ))
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut cpus = match s.strip_prefix(NUMA_NODE_PREFIX) {
+            Some(node) => {
+                let node = node
+                    .parse::<usize>()
+                    .map_err(|e| format!("invalid numa node {node}, err:{e}"))?;
+                let path = format!("/sys/devices/system/node/node{node}/cpulist");
+                let cpu_list = fs::read_to_string(&path)
+                    .map_err(|e| format!("failed to read cpus of numa node {node}, err:{e}"))?;
+                parse_cpu_list(&cpu_list)?
+            }
+            None => parse_cpu_list(s)?,
+        };
+        cpus.sort_unstable();
+        cpus.dedup();
+
+        Ok(Self { cpus })
+    }
+}
+
+/// Parse the cpu list in the format of the linux `cpulist`, e.g. `0-3,8`.
+fn parse_cpu_list(list: &str) -> std::result::Result<Vec<usize>, String> {
+    let parse_cpu = |v: &str| {
+        v.trim()
+            .parse::<usize>()
+            .map_err(|e| format!("invalid cpu {v} in list {list}, err:{e}"))
+    };
+
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|v| !v.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_cpu(start)?, parse_cpu(end)?);
+                if start > end {
+                    return Err(format!("invalid cpu range {part} in list {list}"));
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(parse_cpu(part)?),
+        }
+    }
+
+    Ok(cpus)
+}
+
+#[cfg(target_os = "linux")]
+fn num_configured_cpus() -> usize {
+    // Safety: sysconf is thread safe.
+    let num = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    if num > 0 {
+        (num as usize).min(libc::CPU_SETSIZE as usize)
+    } else {
+        1
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn num_configured_cpus() -> usize {
+    std::thread::available_parallelism()
+        .map(|v| v.get())
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_set() {
+        let set = CpuSet::from_str("0-3, 8,10-11,2").unwrap();
+        assert_eq!(&[0, 1, 2, 3, 8, 10, 11], set.cpus());
+
+        assert!(CpuSet::from_str("3-1").is_err());
+        assert!(CpuSet::from_str("a").is_err());
+        assert!(CpuSet::from_str("numa:x").is_err());
+        assert!(CpuSet::from_str("").unwrap().validate().is_err());
+        assert!(CpuSet::from_str("100000").unwrap().validate().is_err());
+        assert!(CpuSet::from_str("0").unwrap().validate().is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_bind_probe_thread() {
+        assert!(CpuSet::from_str("0").unwrap().bind_probe_thread().is_ok());
+
+        // Cpus out of the range of the kernel cpu set are rejected.
+        let set = CpuSet {
+            cpus: vec![libc::CPU_SETSIZE as usize + 1],
+        };
+        assert!(set.bind_probe_thread().is_err());
+    }
+}
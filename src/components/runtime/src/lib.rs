@@ -24,7 +24,7 @@ use std::{
 };
 
 use macros::define_result;
-use metrics::Metrics;
+use metrics::{Metrics, TaskGuard};
 use pin_project_lite::pin_project;
 use snafu::{Backtrace, GenerateBacktrace, ResultExt, Snafu};
 use tokio::{
@@ -32,9 +32,11 @@ use tokio::{
     task::{JoinError, JoinHandle as TokioJoinHandle},
 };
 
+mod affinity;
 mod metrics;
 mod priority_runtime;
 
+pub use affinity::CpuSet;
 pub use priority_runtime::{Priority, PriorityRuntime};
 
 #[derive(Debug, Snafu)]
@@ -59,6 +61,30 @@ pub enum Error {
         source: JoinError,
         backtrace: Backtrace,
     },
+
+    #[snafu(display(
+        "Runtime invalid cpu affinity, cpus:{:?}, msg:{}.\nBacktrace:\n{}",
+        cpus,
+        msg,
+        backtrace
+    ))]
+    InvalidCpuAffinity {
+        cpus: Vec<usize>,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Runtime failed to bind cpu affinity, cpus:{:?}, err:{}.\nBacktrace:\n{}",
+        cpus,
+        source,
+        backtrace
+    ))]
+    BindCpuAffinity {
+        cpus: Vec<usize>,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
 }
 
 define_result!(Error);
@@ -81,6 +107,12 @@ impl Runtime {
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
+        let mut guard = TaskGuard::new(self.metrics.clone());
+        let future = async move {
+            guard.start();
+            future.await
+        };
+
         JoinHandle {
             inner: self.rt.spawn(future),
         }
@@ -108,6 +140,9 @@ impl Runtime {
         RuntimeStats {
             alive_thread_num: self.metrics.thread_alive_gauge.get(),
             idle_thread_num: self.metrics.thread_idle_gauge.get(),
+            busy_thread_num: self.metrics.thread_busy_gauge.get(),
+            pending_task_num: self.metrics.task_pending_gauge.get(),
+            alive_task_num: self.metrics.task_alive_gauge.get(),
         }
     }
 }
@@ -156,11 +191,18 @@ impl<T> Drop for AbortOnDropMany<T> {
 pub struct RuntimeStats {
     pub alive_thread_num: i64,
     pub idle_thread_num: i64,
+    /// Threads not parked, i.e. running the tasks.
+    pub busy_thread_num: i64,
+    /// Tasks spawned but not yet polled, i.e. the queue depth.
+    pub pending_task_num: i64,
+    /// Tasks polled but not yet finished.
+    pub alive_task_num: i64,
 }
 
 pub struct Builder {
     thread_name: String,
     builder: RuntimeBuilder,
+    cpu_set: Option<CpuSet>,
 }
 
 impl Default for Builder {
@@ -168,6 +210,7 @@ impl Default for Builder {
         Self {
             thread_name: "runtime-worker".to_string(),
             builder: RuntimeBuilder::new_multi_thread(),
+            cpu_set: None,
         }
     }
 }
@@ -206,6 +249,13 @@ impl Builder {
         self
     }
 
+    /// Pin all the threads (including the blocking threads) of the Runtime to
+    /// the cpu set
+    pub fn cpu_affinity(&mut self, val: CpuSet) -> &mut Self {
+        self.cpu_set = Some(val);
+        self
+    }
+
     /// Enable all feature of the underlying runtime
     pub fn enable_all(&mut self) -> &mut Self {
         self.builder.enable_all();
@@ -213,13 +263,35 @@ impl Builder {
     }
 
     pub fn build(&mut self) -> Result<Runtime> {
+        if let Some(cpu_set) = &self.cpu_set {
+            if let Err(msg) = cpu_set.validate() {
+                return InvalidCpuAffinity {
+                    cpus: cpu_set.cpus().to_vec(),
+                    msg,
+                }
+                .fail();
+            }
+            // Bind a probe thread first, so that an unusable cpu set (e.g. not
+            // allowed by the cgroup) fails the build instead of being ignored by
+            // every worker thread.
+            cpu_set.bind_probe_thread().context(BindCpuAffinity {
+                cpus: cpu_set.cpus().to_vec(),
+            })?;
+        }
+
         let metrics = Arc::new(Metrics::new(&self.thread_name));
+        let cpu_set = self.cpu_set.clone();
 
         let rt = self
             .builder
             .thread_name(self.thread_name.clone())
-            .on_thread_start(with_metrics(&metrics, |m| {
+            .on_thread_start(with_metrics(&metrics, move |m| {
                 m.on_thread_start();
+                if let Some(cpu_set) = &cpu_set {
+                    if cpu_set.bind_current_thread().is_err() {
+                        m.on_bind_cpu_failed();
+                    }
+                }
             }))
             .on_thread_stop(with_metrics(&metrics, |m| {
                 m.on_thread_stop();
@@ -271,6 +343,7 @@ mod tests {
         let s = rt.stats();
         assert_eq!(5, s.alive_thread_num);
         assert_eq!(5, s.idle_thread_num);
+        assert_eq!(0, s.busy_thread_num);
 
         rt.spawn(async {
             thread::sleep(Duration::from_millis(50));
@@ -280,6 +353,24 @@ mod tests {
         let s = rt.stats();
         assert_eq!(5, s.alive_thread_num);
         assert_eq!(4, s.idle_thread_num);
+        assert_eq!(1, s.busy_thread_num);
+        assert_eq!(0, s.pending_task_num);
+        assert_eq!(1, s.alive_task_num);
+
+        thread::sleep(Duration::from_millis(100));
+        let s = rt.stats();
+        assert_eq!(0, s.pending_task_num);
+        assert_eq!(0, s.alive_task_num);
+    }
+
+    #[test]
+    fn test_invalid_cpu_affinity() {
+        let rt = Builder::default()
+            .worker_threads(2)
+            .thread_name("test_invalid_cpu_affinity")
+            .cpu_affinity("100000".parse().unwrap())
+            .build();
+        assert!(matches!(rt, Err(Error::InvalidCpuAffinity { .. })));
     }
 
     #[test]
@@ -15,8 +15,13 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::sync::Arc;
+
 use lazy_static::lazy_static;
-use prometheus::{register_int_gauge_vec, IntGauge, IntGaugeVec};
+use prometheus::{
+    register_int_counter_vec, register_int_gauge_vec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec,
+};
 
 lazy_static! {
     // Gauges:
@@ -32,6 +37,32 @@ lazy_static! {
         &["name"]
     )
         .unwrap();
+    static ref RUNTIME_THREAD_BUSY_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "runtime_thread_busy_gauge",
+        "busy (not parked) thread number for runtime",
+        &["name"]
+    )
+        .unwrap();
+    static ref RUNTIME_TASK_PENDING_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "runtime_task_pending_gauge",
+        "spawned but not yet polled task number for runtime",
+        &["name"]
+    )
+        .unwrap();
+    static ref RUNTIME_TASK_ALIVE_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "runtime_task_alive_gauge",
+        "polled but not yet finished task number for runtime",
+        &["name"]
+    )
+        .unwrap();
+
+    // Counters:
+    static ref RUNTIME_BIND_CPU_FAILED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "runtime_bind_cpu_failed_counter",
+        "thread number failed to bind the cpu affinity for runtime",
+        &["name"]
+    )
+        .unwrap();
 }
 
 /// Runtime metrics.
@@ -40,6 +71,12 @@ pub struct Metrics {
     // Gauges:
     pub thread_alive_gauge: IntGauge,
     pub thread_idle_gauge: IntGauge,
+    pub thread_busy_gauge: IntGauge,
+    pub task_pending_gauge: IntGauge,
+    pub task_alive_gauge: IntGauge,
+
+    // Counters:
+    pub bind_cpu_failed_counter: IntCounter,
 }
 
 impl Metrics {
@@ -47,26 +84,92 @@ impl Metrics {
         Self {
             thread_alive_gauge: RUNTIME_THREAD_ALIVE_GAUGE.with_label_values(&[name]),
             thread_idle_gauge: RUNTIME_THREAD_IDLE_GAUGE.with_label_values(&[name]),
+            thread_busy_gauge: RUNTIME_THREAD_BUSY_GAUGE.with_label_values(&[name]),
+            task_pending_gauge: RUNTIME_TASK_PENDING_GAUGE.with_label_values(&[name]),
+            task_alive_gauge: RUNTIME_TASK_ALIVE_GAUGE.with_label_values(&[name]),
+            bind_cpu_failed_counter: RUNTIME_BIND_CPU_FAILED_COUNTER.with_label_values(&[name]),
         }
     }
 
     #[inline]
     pub fn on_thread_start(&self) {
         self.thread_alive_gauge.inc();
+        self.thread_busy_gauge.inc();
     }
 
     #[inline]
     pub fn on_thread_stop(&self) {
         self.thread_alive_gauge.dec();
+        self.thread_busy_gauge.dec();
+    }
+
+    #[inline]
+    pub fn on_bind_cpu_failed(&self) {
+        self.bind_cpu_failed_counter.inc();
     }
 
     #[inline]
     pub fn on_thread_park(&self) {
         self.thread_idle_gauge.inc();
+        self.thread_busy_gauge.dec();
     }
 
     #[inline]
     pub fn on_thread_unpark(&self) {
         self.thread_idle_gauge.dec();
+        self.thread_busy_gauge.inc();
+    }
+
+    #[inline]
+    pub fn on_task_spawn(&self) {
+        self.task_pending_gauge.inc();
+    }
+
+    #[inline]
+    pub fn on_task_start(&self) {
+        self.task_pending_gauge.dec();
+        self.task_alive_gauge.inc();
+    }
+
+    /// `started` is false if the task is dropped before being polled, e.g.
+    /// aborted or the runtime is shutdown.
+    #[inline]
+    pub fn on_task_finish(&self, started: bool) {
+        if started {
+            self.task_alive_gauge.dec();
+        } else {
+            self.task_pending_gauge.dec();
+        }
+    }
+}
+
+/// Guard tracking the state of a spawned task, which is moved into the task
+/// so it is dropped along with the task.
+pub struct TaskGuard {
+    metrics: Arc<Metrics>,
+    started: bool,
+}
+
+impl TaskGuard {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        metrics.on_task_spawn();
+
+        Self {
+            metrics,
+            started: false,
+        }
+    }
+
+    pub fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            self.metrics.on_task_start();
+        }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.metrics.on_task_finish(self.started);
     }
 }
@@ -0,0 +1,166 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+// Detects the resource limits of the cgroup the process runs in, so the
+// defaults derived from the resources don't oversubscribe in containers.
+
+use std::{fs, path::Path, sync::OnceLock, thread};
+
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Memory limits above it are treated as unlimited, cgroup v1 reports a value
+/// near `i64::MAX` if no limit is set.
+const UNLIMITED_MEMORY_THRESHOLD: u64 = 1 << 60;
+
+/// Resource limits of the cgroup, `None` means no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CgroupLimits {
+    /// The cpu quota in number of cpus, may be fractional.
+    pub cpu_quota: Option<f64>,
+    /// The memory limit in bytes.
+    pub memory_limit: Option<u64>,
+}
+
+/// Get the limits of the cgroup, which are detected only once.
+pub fn cgroup_limits() -> CgroupLimits {
+    static LIMITS: OnceLock<CgroupLimits> = OnceLock::new();
+
+    *LIMITS.get_or_init(|| CgroupLimits {
+        cpu_quota: detect_cpu_quota(),
+        memory_limit: detect_memory_limit(),
+    })
+}
+
+/// Number of cpus available to the process, the cgroup cpu quota is rounded up.
+pub fn available_cpus() -> usize {
+    let num_cpus = thread::available_parallelism()
+        .map(|v| v.get())
+        .unwrap_or(1);
+    match cgroup_limits().cpu_quota {
+        Some(quota) => num_cpus.min(quota.ceil() as usize).max(1),
+        None => num_cpus,
+    }
+}
+
+/// Bytes of memory available to the process, which is the smaller one of the
+/// physical memory and the cgroup memory limit.
+pub fn available_memory() -> u64 {
+    static TOTAL_MEMORY: OnceLock<u64> = OnceLock::new();
+
+    let total = *TOTAL_MEMORY.get_or_init(|| {
+        let refresh_kind = RefreshKind::new().with_memory(MemoryRefreshKind::new().with_ram());
+        System::new_with_specifics(refresh_kind).total_memory()
+    });
+    match cgroup_limits().memory_limit {
+        Some(limit) if total > 0 => total.min(limit),
+        Some(limit) => limit,
+        None => total,
+    }
+}
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path).ok().map(|v| v.trim().to_string())
+}
+
+fn detect_cpu_quota() -> Option<f64> {
+    // cgroup v2
+    if let Some(cpu_max) = read_trimmed(Path::new(CGROUP_ROOT).join("cpu.max")) {
+        return parse_cpu_max(&cpu_max);
+    }
+
+    // cgroup v1
+    let dir = Path::new(CGROUP_ROOT).join("cpu");
+    let quota = read_trimmed(dir.join("cpu.cfs_quota_us"))?;
+    let period = read_trimmed(dir.join("cpu.cfs_period_us"))?;
+    parse_cfs_quota(&quota, &period)
+}
+
+fn detect_memory_limit() -> Option<u64> {
+    // cgroup v2
+    if let Some(memory_max) = read_trimmed(Path::new(CGROUP_ROOT).join("memory.max")) {
+        return parse_memory_limit(&memory_max);
+    }
+
+    // cgroup v1
+    let limit = read_trimmed(
+        Path::new(CGROUP_ROOT)
+            .join("memory")
+            .join("memory.limit_in_bytes"),
+    )?;
+    parse_memory_limit(&limit)
+}
+
+/// Parse the `cpu.max` of cgroup v2, in the format of `$MAX $PERIOD`.
+fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut parts = content.split_whitespace();
+    let quota = parts.next()?;
+    let period = parts.next().unwrap_or("100000");
+    if quota == "max" {
+        return None;
+    }
+
+    parse_cfs_quota(quota, period)
+}
+
+/// Parse the quota and period in microseconds, the quota of cgroup v1 is
+/// negative if not limited.
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota = quota.parse::<i64>().ok()?;
+    let period = period.parse::<i64>().ok()?;
+    if quota <= 0 || period <= 0 {
+        return None;
+    }
+
+    Some(quota as f64 / period as f64)
+}
+
+fn parse_memory_limit(content: &str) -> Option<u64> {
+    let limit = content.parse::<u64>().ok()?;
+    (limit < UNLIMITED_MEMORY_THRESHOLD).then_some(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_quota() {
+        assert_eq!(Some(2.0), parse_cpu_max("200000 100000"));
+        assert_eq!(Some(0.5), parse_cpu_max("50000 100000"));
+        assert_eq!(Some(1.5), parse_cpu_max("150000"));
+        assert_eq!(None, parse_cpu_max("max 100000"));
+        assert_eq!(None, parse_cpu_max(""));
+
+        assert_eq!(Some(4.0), parse_cfs_quota("400000", "100000"));
+        assert_eq!(None, parse_cfs_quota("-1", "100000"));
+        assert_eq!(None, parse_cfs_quota("100000", "0"));
+    }
+
+    #[test]
+    fn test_parse_memory_limit() {
+        assert_eq!(Some(1 << 30), parse_memory_limit("1073741824"));
+        assert_eq!(None, parse_memory_limit("max"));
+        assert_eq!(None, parse_memory_limit("9223372036854771712"));
+    }
+
+    #[test]
+    fn test_available_resources() {
+        assert!(available_cpus() >= 1);
+        assert!(available_memory() > 0);
+    }
+}
@@ -17,8 +17,11 @@
 
 // Helps to collect and report statistics about the system.
 
+mod cgroup;
+
 use std::{sync::Mutex, time::Duration};
 
+pub use cgroup::{available_cpus, available_memory, cgroup_limits, CgroupLimits};
 pub use sysinfo::LoadAvg;
 use sysinfo::{Cpu, CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 
@@ -148,6 +148,43 @@ impl CollectorVisitor for FormatCollectorVisitor {
     }
 }
 
+/// Visitor flattening the metrics into a map keyed by their paths, which are
+/// the names of the collectors and the metric joined by `.`, and the name of
+/// the root collector is omitted.
+#[derive(Default)]
+pub struct FlattenCollectorVisitor {
+    path: Vec<String>,
+    metrics: BTreeMap<String, String>,
+}
+
+impl FlattenCollectorVisitor {
+    pub fn into_map(self) -> BTreeMap<String, String> {
+        self.metrics
+    }
+}
+
+impl CollectorVisitor for FlattenCollectorVisitor {
+    fn visit(&mut self, level: usize, collector: &MetricsCollector) {
+        self.path.truncate(level.saturating_sub(1));
+        if level > 0 {
+            self.path.push(collector.name().to_string());
+        }
+        collector.for_each_metric(&mut |metric| {
+            let mut key = self.path.join(".");
+            if !key.is_empty() {
+                key.push('.');
+            }
+            key.push_str(metric.name());
+            let value = match metric {
+                Metric::Boolean(v) => v.val.to_string(),
+                Metric::Number(v) => v.val.to_string(),
+                Metric::Duration(v) => format!("{:?}", v.val),
+            };
+            self.metrics.insert(key, value);
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -193,5 +230,20 @@ mod tests {
     child_1_2:
 "#;
         assert_eq!(expect_output, &visitor.into_string());
+
+        let mut visitor = FlattenCollectorVisitor::default();
+        collector.visit(&mut visitor);
+        let expect_metrics = [
+            ("counter", "1"),
+            ("elapsed", "100ms"),
+            ("child_1_0.boolean", "false"),
+            ("child_1_0.child_2_0.counter", "1"),
+            ("child_1_0.child_2_0.elapsed", "100ms"),
+            ("child_1_1.boolean", "false"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect::<BTreeMap<_, _>>();
+        assert_eq!(expect_metrics, visitor.into_map());
     }
 }
@@ -3,5 +3,8 @@
 
 mod logging;
 
-pub use logging::{init_default_tracing, init_default_ut_tracing, init_tracing_with_file, Config};
+pub use logging::{
+    init_default_tracing, init_default_ut_tracing, init_tracing, init_tracing_with_file,
+    init_tracing_with_otlp, Config, OtlpConfig, OtlpGuard, TracingGuard,
+};
 pub use tracing_appender;
@@ -4,11 +4,16 @@ use std::{
     fs::OpenOptions,
     path::Path,
     sync::{Arc, Mutex, Once},
+    time::Duration,
 };
 
 use fmt::format::FmtSpan;
 use lazy_static::lazy_static;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{trace, Resource};
 use serde::{Deserialize, Serialize};
+use tokio::runtime::Runtime;
 use tracing::Subscriber;
 use tracing_appender::{
     non_blocking::WorkerGuard,
@@ -76,8 +81,13 @@ pub struct Config {
     pub dir: String,
     /// The level of tracing.
     pub level: String,
-    /// Console config.
+    /// Console config, only used by the file-based tracing.
     pub console: Option<ConsoleConfig>,
+    /// Export the spans by OTLP instead of writing them to the files if set.
+    pub otlp: Option<OtlpConfig>,
+    /// Attach the request ids of the queries as exemplars to the query
+    /// latency histogram exported in the OpenMetrics format.
+    pub exemplars: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -85,6 +95,24 @@ pub struct ConsoleConfig {
     pub port: u16,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// The grpc endpoint of the OTLP collector.
+    pub endpoint: String,
+    /// The service name attached to the exported spans.
+    pub service_name: String,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::from("http://127.0.0.1:4317"),
+            service_name: String::from("horaedb"),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -92,10 +120,79 @@ impl Default for Config {
             dir: String::from("/tmp/horaedb"),
             level: String::from("info"),
             console: None,
+            otlp: None,
+            exemplars: false,
+        }
+    }
+}
+
+/// The guard must be held during tracing, and the buffered spans are flushed
+/// when it's dropped.
+pub enum TracingGuard {
+    File(WorkerGuard),
+    Otlp(OtlpGuard),
+}
+
+pub struct OtlpGuard {
+    /// Runtime running the export tasks.
+    runtime: Option<Runtime>,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        opentelemetry::global::shutdown_tracer_provider();
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_timeout(Duration::from_secs(5));
         }
     }
 }
 
+/// Export the spans by OTLP if configured, otherwise write them to the files.
+pub fn init_tracing(config: &Config, node_addr: &str, rotation: Rotation) -> TracingGuard {
+    match &config.otlp {
+        Some(otlp) => TracingGuard::Otlp(init_tracing_with_otlp(config, otlp)),
+        None => TracingGuard::File(init_tracing_with_file(config, node_addr, rotation)),
+    }
+}
+
+/// Export the spans to the OTLP collector.
+pub fn init_tracing_with_otlp(config: &Config, otlp: &OtlpConfig) -> OtlpGuard {
+    // The tracing is set up before the runtimes of the server are built, so the
+    // spans are exported by a dedicated runtime.
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("otlp-export")
+        .enable_all()
+        .build()
+        .expect("failed to build otlp export runtime");
+    let service_name = KeyValue::new("service.name", otlp.service_name.clone());
+    let resource = Resource::new(vec![service_name]);
+    let exporter = opentelemetry_otlp::new_exporter()
+        .tonic()
+        .with_endpoint(&otlp.endpoint);
+    let tracer = {
+        let _enter = runtime.enter();
+        opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(exporter)
+            .with_trace_config(trace::config().with_resource(resource))
+            .install_batch(opentelemetry_sdk::runtime::Tokio)
+            .unwrap_or_else(|e| panic!("failed to init otlp exporter, err:{e}"))
+    };
+
+    // It is part of initializing logger, so just print it to stdout.
+    println!("Tracing spans are exported to {}...", otlp.endpoint);
+    let subscriber = Registry::default()
+        .with(EnvFilter::new(&config.level))
+        .with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("error setting global tracing subscriber");
+
+    OtlpGuard {
+        runtime: Some(runtime),
+    }
+}
+
 /// Write logs to file and rotation.
 pub fn init_tracing_with_file(config: &Config, node_addr: &str, rotation: Rotation) -> WorkerGuard {
     let file_appender = RollingFileAppender::new(rotation, &config.dir, &config.prefix);
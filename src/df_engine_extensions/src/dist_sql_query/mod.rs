@@ -18,6 +18,7 @@
 use std::{
     fmt,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use async_trait::async_trait;
@@ -39,6 +40,11 @@ pub mod resolver;
 pub mod test_util;
 
 /// Remote datafusion physical plan executor
+///
+/// The errors of the remote engine should be kept in the
+/// [DataFusionError::External] to tell whether the scan can be retried.
+///
+/// [DataFusionError::External]: datafusion::error::DataFusionError::External
 pub trait RemotePhysicalPlanExecutor: fmt::Debug + Send + Sync + 'static {
     fn execute(
         &self,
@@ -46,10 +52,43 @@ pub trait RemotePhysicalPlanExecutor: fmt::Debug + Send + Sync + 'static {
         table: TableIdentifier,
         plan: Arc<dyn ExecutionPlan>,
     ) -> DfResult<BoxFuture<'static, DfResult<SendableRecordBatchStream>>>;
+
+    /// Route the sub tables, the endpoints are returned in the order of the
+    /// `tables`, and the plans of the sub tables with the same endpoint are
+    /// sent to the remote node by one request.
+    ///
+    /// `None` means the plan of the sub table is always executed alone, e.g.
+    /// the sub table is opened by the local node.
+    fn route(
+        &self,
+        tables: Vec<TableIdentifier>,
+    ) -> BoxFuture<'static, DfResult<Vec<Option<String>>>> {
+        Box::pin(async move { Ok(vec![None; tables.len()]) })
+    }
 }
 
 pub type RemotePhysicalPlanExecutorRef = Arc<dyn RemotePhysicalPlanExecutor>;
 
+/// Retry policy of the scans of the partitions executed by the remote nodes.
+///
+/// The scan of a partition failed before returning any rows as its node is
+/// unavailable or its shard moved is sent again after the backoff, and it's
+/// routed again as the route of the failed table is evicted by the remote
+/// engine client. Other failures, e.g. the errors of the execution, are not
+/// retried. The scan failed after returning some rows fails the whole query, as
+/// the returned rows can't be taken back. The retried partitions are read
+/// later than the others, so the results may include the rows written during
+/// the retries, which is reported by the [WarningKind::RetriedScan] warning of
+/// the query.
+///
+/// [WarningKind::RetriedScan]: table_engine::query_warning::WarningKind::RetriedScan
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanRetryPolicy {
+    /// Zero means no retry.
+    pub max_retries: usize,
+    pub backoff: Duration,
+}
+
 /// Executable scan's builder
 ///
 /// It is not suitable to restrict the detailed implementation of executable
@@ -19,6 +19,8 @@
 
 use std::{
     any::Any,
+    collections::BTreeMap,
+    error::Error as StdError,
     fmt,
     pin::Pin,
     sync::{Arc, Mutex},
@@ -41,16 +43,31 @@ use datafusion::{
         metrics::{Count, MetricValue, MetricsSet},
         projection::ProjectionExec,
         repartition::RepartitionExec,
-        DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
-        SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
+        udaf::AggregateFunctionExpr,
+        union::UnionExec,
+        AggregateExpr, DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning,
+        RecordBatchStream, SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
     },
 };
 use futures::{future::BoxFuture, FutureExt, Stream, StreamExt};
+use logger::warn;
 use runtime::Priority;
-use table_engine::{remote::model::TableIdentifier, table::ReadRequest};
-use trace_metric::{collector::FormatCollectorVisitor, MetricsCollector, TraceMetricWhenDrop};
+use table_engine::{
+    query_warning::{QueryWarnings, WarningKind},
+    remote::{self, model::TableIdentifier},
+    table::ReadRequest,
+};
+use trace_metric::{
+    collector::{FlattenCollectorVisitor, FormatCollectorVisitor},
+    MetricsCollector, TraceMetricWhenDrop,
+};
 
-use crate::dist_sql_query::{RemotePhysicalPlanExecutor, RemoteTaskContext, TableScanContext};
+use crate::{
+    dist_sql_query::{
+        RemotePhysicalPlanExecutor, RemoteTaskContext, ScanRetryPolicy, TableScanContext,
+    },
+    metrics::PARTITION_SCAN_RETRY_COUNTER,
+};
 
 /// Placeholder of partitioned table's scan plan
 /// It is inexecutable actually and just for carrying the necessary information
@@ -165,10 +182,12 @@ impl ResolvedPartitionedScan {
         sub_table_plan_ctxs: Vec<SubTablePlanContext>,
         metrics_collector: MetricsCollector,
         is_analyze: bool,
+        scan_retry: ScanRetryPolicy,
     ) -> Self {
         let remote_exec_ctx = Arc::new(RemoteExecContext {
             executor: remote_executor,
             plan_ctxs: sub_table_plan_ctxs,
+            scan_retry,
         });
 
         Self::new_with_details(remote_exec_ctx, true, metrics_collector, is_analyze)
@@ -200,6 +219,7 @@ impl ResolvedPartitionedScan {
     pub fn try_to_push_down_more(
         &self,
         cur_node: Arc<dyn ExecutionPlan>,
+        aggr_checker: &AggregatePushDownChecker,
     ) -> DfResult<Arc<dyn ExecutionPlan>> {
         // Can not push more...
         if !self.pushdown_continue {
@@ -208,7 +228,7 @@ impl ResolvedPartitionedScan {
 
         // Push down more, and when occur the terminated push down able node, we need to
         // set `can_push_down_more` false.
-        let pushdown_status = Self::maybe_a_pushdown_node(cur_node.clone());
+        let pushdown_status = Self::maybe_a_pushdown_node(cur_node.clone(), aggr_checker);
         let (node, can_push_down_more) = match pushdown_status {
             PushDownEvent::Continue(node) => (node, true),
             PushDownEvent::Terminated(node) => (node, false),
@@ -237,6 +257,7 @@ impl ResolvedPartitionedScan {
         let remote_exec_ctx = Arc::new(RemoteExecContext {
             executor: self.remote_exec_ctx.executor.clone(),
             plan_ctxs: new_plan_ctxs,
+            scan_retry: self.remote_exec_ctx.scan_retry,
         });
         let plan = ResolvedPartitionedScan::new_with_details(
             remote_exec_ctx,
@@ -249,8 +270,93 @@ impl ResolvedPartitionedScan {
     }
 
     #[inline]
-    pub fn maybe_a_pushdown_node(plan: Arc<dyn ExecutionPlan>) -> PushDownEvent {
-        PushDownEvent::new(plan)
+    pub fn maybe_a_pushdown_node(
+        plan: Arc<dyn ExecutionPlan>,
+        aggr_checker: &AggregatePushDownChecker,
+    ) -> PushDownEvent {
+        PushDownEvent::new(plan, aggr_checker)
+    }
+
+    /// Merge the plans of the sub tables served by the same remote node into
+    /// one plan, so the node is sent one request rather than one request per
+    /// sub table. `None` is returned if no plans can be merged.
+    ///
+    /// The plans are not merged for analyze, to keep the metrics of every sub
+    /// table.
+    pub async fn batch_by_endpoint(&self) -> DfResult<Option<Arc<dyn ExecutionPlan>>> {
+        let plan_ctxs = &self.remote_exec_ctx.plan_ctxs;
+        if self.is_analyze || plan_ctxs.len() <= 1 {
+            return Ok(None);
+        }
+
+        let tables = plan_ctxs.iter().map(|v| v.table.clone()).collect();
+        let endpoints = match self.remote_exec_ctx.executor.route(tables).await {
+            Ok(v) => v,
+            Err(e) => {
+                // The sub tables will be routed again when they are executed.
+                warn!("Failed to route sub tables, skip batching them, err:{e}");
+                return Ok(None);
+            }
+        };
+
+        // Indexes of the plans grouped by the endpoint, in the order of the first
+        // plan of each group.
+        let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+        for (idx, endpoint) in endpoints.into_iter().enumerate() {
+            let group = match &endpoint {
+                Some(endpoint) => groups
+                    .iter_mut()
+                    .find(|group| group.0.as_ref() == Some(endpoint)),
+                None => None,
+            };
+            match group {
+                Some(group) => group.1.push(idx),
+                None => groups.push((endpoint, vec![idx])),
+            }
+        }
+        if groups.len() == plan_ctxs.len() {
+            return Ok(None);
+        }
+
+        let batched_plan_ctxs = groups
+            .into_iter()
+            .map(|(_, indexes)| {
+                let first = &plan_ctxs[indexes[0]];
+                if indexes.len() == 1 {
+                    return SubTablePlanContext::new(
+                        first.table.clone(),
+                        first.plan.clone(),
+                        first.metrics_collector.clone(),
+                    );
+                }
+
+                let plans = indexes.iter().map(|idx| plan_ctxs[*idx].plan.clone());
+                let table_names: Vec<_> = indexes
+                    .iter()
+                    .map(|idx| plan_ctxs[*idx].table.table.as_str())
+                    .collect();
+                let metrics_collector = self.metrics_collector.span(table_names.join(","));
+                SubTablePlanContext::new(
+                    first.table.clone(),
+                    Arc::new(UnionExec::new(plans.collect())),
+                    metrics_collector,
+                )
+            })
+            .collect();
+
+        let remote_exec_ctx = Arc::new(RemoteExecContext {
+            executor: self.remote_exec_ctx.executor.clone(),
+            plan_ctxs: batched_plan_ctxs,
+            scan_retry: self.remote_exec_ctx.scan_retry,
+        });
+        let plan = Self::new_with_details(
+            remote_exec_ctx,
+            self.pushdown_continue,
+            self.metrics_collector.clone(),
+            self.is_analyze,
+        );
+
+        Ok(Some(Arc::new(plan)))
     }
 
     /// `ResolvedPartitionedScan` can be executable after satisfying followings:
@@ -265,6 +371,7 @@ impl ResolvedPartitionedScan {
 pub struct RemoteExecContext {
     executor: Arc<dyn RemotePhysicalPlanExecutor>,
     plan_ctxs: Vec<SubTablePlanContext>,
+    scan_retry: ScanRetryPolicy,
 }
 
 #[derive(Debug)]
@@ -354,17 +461,41 @@ impl ExecutionPlan for ResolvedPartitionedScan {
             remote_metrics,
         } = &self.remote_exec_ctx.plan_ctxs[partition];
 
-        let remote_task_ctx =
-            RemoteTaskContext::new(context, remote_metrics.clone(), self.is_analyze);
+        let schema = plan.schema();
+        let table_name = sub_table.table.clone();
+        // The retries are reported by the warnings of the query if it's set.
+        let warnings = context
+            .session_config()
+            .get_extension::<QueryWarnings>()
+            .as_deref()
+            .cloned()
+            .unwrap_or_default();
+        let init_stream: InitStreamFn = {
+            let executor = self.remote_exec_ctx.executor.clone();
+            let (sub_table, plan, remote_metrics) =
+                (sub_table.clone(), plan.clone(), remote_metrics.clone());
+            let is_analyze = self.is_analyze;
+            Arc::new(move || {
+                let remote_task_ctx =
+                    RemoteTaskContext::new(context.clone(), remote_metrics.clone(), is_analyze);
+                // Send plan for remote execution.
+                executor.execute(remote_task_ctx, sub_table.clone(), plan.clone())
+            })
+        };
 
-        // Send plan for remote execution.
-        let stream_future = self.remote_exec_ctx.executor.execute(
-            remote_task_ctx,
-            sub_table.clone(),
-            plan.clone(),
-        )?;
-        let record_stream =
-            PartitionedScanStream::new(stream_future, plan.schema(), metrics_collector.clone());
+        let stream_future = init_stream()?;
+        let mut record_stream =
+            PartitionedScanStream::new(stream_future, schema, metrics_collector.clone());
+        let scan_retry = self.remote_exec_ctx.scan_retry;
+        if scan_retry.max_retries > 0 {
+            record_stream = record_stream.with_retry(ScanRetry {
+                policy: scan_retry,
+                table: table_name,
+                attempts: 0,
+                init_stream,
+                warnings,
+            });
+        }
 
         Ok(Box::pin(record_stream))
     }
@@ -403,10 +534,93 @@ impl ExecutionPlan for ResolvedPartitionedScan {
     }
 }
 
+/// Flatten the metrics traced by the scan of the partitioned table by their
+/// paths, the metrics of the sub tables scanned by the remote nodes are kept
+/// as the text plans returned by them.
+///
+/// `None` is returned if the `plan` isn't such a scan.
+pub fn partitioned_scan_metrics(plan: &dyn ExecutionPlan) -> Option<BTreeMap<String, String>> {
+    let scan = plan.as_any().downcast_ref::<ResolvedPartitionedScan>()?;
+    let mut visitor = FlattenCollectorVisitor::default();
+    scan.metrics_collector.visit(&mut visitor);
+    let mut metrics = visitor.into_map();
+    for sub_table_ctx in &scan.remote_exec_ctx.plan_ctxs {
+        if let Some(remote_metrics) = sub_table_ctx.remote_metrics.lock().unwrap().as_ref() {
+            metrics.insert(
+                format!("{}.remote", sub_table_ctx.table.table),
+                remote_metrics.clone(),
+            );
+        }
+    }
+
+    Some(metrics)
+}
+
+type StreamFuture = BoxFuture<'static, DfResult<DfSendableRecordBatchStream>>;
+type InitStreamFn = Arc<dyn Fn() -> DfResult<StreamFuture> + Send + Sync>;
+
+/// Send the scan of a partition again on the failures, see [ScanRetryPolicy].
+pub(crate) struct ScanRetry {
+    policy: ScanRetryPolicy,
+    table: String,
+    attempts: usize,
+    init_stream: InitStreamFn,
+    warnings: QueryWarnings,
+}
+
+impl ScanRetry {
+    /// Build the future to init the stream again after the backoff, `None` if
+    /// the error is not retryable or the retries are exhausted.
+    fn next_attempt(&mut self, e: &DataFusionError) -> Option<StreamFuture> {
+        if !is_retryable(e) || self.attempts >= self.policy.max_retries {
+            return None;
+        }
+        self.attempts += 1;
+        warn!(
+            "Retry the scan of the partition, table:{}, attempt:{}, err:{e}",
+            self.table, self.attempts
+        );
+        PARTITION_SCAN_RETRY_COUNTER.inc();
+        self.warnings.record(
+            WarningKind::RetriedScan,
+            format!(
+                "scan of partition {} is retried, its rows may be newer than the others",
+                self.table
+            ),
+        );
+
+        let init_stream = self.init_stream.clone();
+        let backoff = self.policy.backoff;
+        Some(Box::pin(async move {
+            tokio::time::sleep(backoff).await;
+            init_stream()?.await
+        }))
+    }
+}
+
+/// Whether the scan failed as the remote node is unavailable or the table is
+/// moved, which is marked by [remote::Error::Unavailable] in the error chain.
+fn is_retryable(e: &DataFusionError) -> bool {
+    let mut source: Option<&(dyn StdError + 'static)> = match e {
+        DataFusionError::External(e) => Some(e.as_ref()),
+        _ => return false,
+    };
+    while let Some(e) = source {
+        if e.downcast_ref::<remote::Error>()
+            .is_some_and(remote::Error::is_retryable)
+        {
+            return true;
+        }
+        source = e.source();
+    }
+
+    false
+}
+
 /// Partitioned scan stream
 pub(crate) struct PartitionedScanStream {
     /// Future to init the stream
-    stream_future: BoxFuture<'static, DfResult<DfSendableRecordBatchStream>>,
+    stream_future: StreamFuture,
 
     /// Stream to poll the records
     stream_state: StreamState,
@@ -419,6 +633,13 @@ pub(crate) struct PartitionedScanStream {
 
     /// Metrics collected for analyze
     metrics: Metrics,
+
+    /// Retry the scan if it fails before returning any rows, `None` if the
+    /// retry is disabled
+    retry: Option<ScanRetry>,
+
+    /// Whether any rows are returned
+    has_output: bool,
 }
 
 impl PartitionedScanStream {
@@ -438,6 +659,28 @@ impl PartitionedScanStream {
             arrow_record_schema,
             last_time_left: None,
             metrics,
+            retry: None,
+            has_output: false,
+        }
+    }
+
+    pub fn with_retry(mut self, retry: ScanRetry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Init the stream again if it's allowed, returns whether it's retried.
+    fn try_retry(&mut self, e: &DataFusionError) -> bool {
+        if self.has_output {
+            return false;
+        }
+        match self.retry.as_mut().and_then(|retry| retry.next_attempt(e)) {
+            Some(stream_future) => {
+                self.stream_future = stream_future;
+                self.stream_state = StreamState::Initializing;
+                true
+            }
+            None => false,
         }
     }
 }
@@ -462,23 +705,37 @@ impl Stream for PartitionedScanStream {
         this.metrics.total_duration += wait_cost;
 
         let poll_result = loop {
-            let stream_state = &mut this.stream_state;
-            match stream_state {
+            match &mut this.stream_state {
                 StreamState::Initializing => {
                     let poll_res = this.stream_future.poll_unpin(cx);
                     match poll_res {
                         Poll::Ready(Ok(stream)) => {
-                            *stream_state = StreamState::Polling(stream);
+                            this.stream_state = StreamState::Polling(stream);
                         }
                         Poll::Ready(Err(e)) => {
-                            *stream_state = StreamState::InitializeFailed;
+                            if this.try_retry(&e) {
+                                continue;
+                            }
+                            this.stream_state = StreamState::InitializeFailed;
                             break Poll::Ready(Some(Err(e)));
                         }
                         Poll::Pending => break Poll::Pending,
                     }
                 }
                 StreamState::InitializeFailed => return Poll::Ready(None),
-                StreamState::Polling(stream) => break stream.poll_next_unpin(cx),
+                StreamState::Polling(stream) => match stream.poll_next_unpin(cx) {
+                    Poll::Ready(Some(Err(e))) => {
+                        if this.try_retry(&e) {
+                            continue;
+                        }
+                        break Poll::Ready(Some(Err(e)));
+                    }
+                    Poll::Ready(Some(Ok(batch))) => {
+                        this.has_output |= batch.num_rows() > 0;
+                        break Poll::Ready(Some(Ok(batch)));
+                    }
+                    poll_res => break poll_res,
+                },
             }
         };
 
@@ -662,16 +919,10 @@ pub enum PushDownEvent {
 }
 
 impl PushDownEvent {
-    // Those aggregate functions can't be pushed down.
-    // https://github.com/apache/incubator-horaedb/issues/1405
-    fn blacklist_expr(expr: &dyn Any) -> bool {
-        expr.is::<ApproxPercentileCont>() || expr.is::<ApproxPercentileContWithWeight>()
-    }
-
-    pub fn new(plan: Arc<dyn ExecutionPlan>) -> Self {
+    pub fn new(plan: Arc<dyn ExecutionPlan>, aggr_checker: &AggregatePushDownChecker) -> Self {
         if let Some(aggr) = plan.as_any().downcast_ref::<AggregateExec>() {
             for aggr_expr in aggr.aggr_expr() {
-                if Self::blacklist_expr(aggr_expr.as_any()) {
+                if !aggr_checker.is_state_mergeable(aggr_expr.as_ref()) {
                     return Self::Unable;
                 }
             }
@@ -699,6 +950,59 @@ impl PushDownEvent {
         }
     }
 }
+
+/// Registry telling whether the states of the udafs are mergeable.
+///
+/// It's consulted when the plan is resolved rather than snapshotted, as the
+/// udafs can be registered and deregistered at runtime.
+pub trait UdafRegistry: fmt::Debug + Send + Sync {
+    /// Whether the states of the udaf are mergeable, and the unknown udafs are
+    /// considered mergeable.
+    fn is_state_mergeable(&self, name: &str) -> bool;
+}
+
+pub type UdafRegistryRef = Arc<dyn UdafRegistry>;
+
+/// Checker deciding whether the partial aggregation can be pushed down to the
+/// remote nodes.
+///
+/// Only the intermediate states of the partial aggregation will be sent back to
+/// the coordinator after pushing down, so it requires the states of all the
+/// aggregate functions to be mergeable by the final aggregation.
+#[derive(Debug, Clone, Default)]
+pub struct AggregatePushDownChecker {
+    /// Registry of the udafs, all the udafs are considered mergeable if it's
+    /// not set.
+    udaf_registry: Option<UdafRegistryRef>,
+}
+
+impl AggregatePushDownChecker {
+    pub fn new(udaf_registry: UdafRegistryRef) -> Self {
+        Self {
+            udaf_registry: Some(udaf_registry),
+        }
+    }
+
+    // Those builtin aggregate functions can't be pushed down.
+    // https://github.com/apache/incubator-horaedb/issues/1405
+    fn is_unmergeable_builtin(expr: &dyn Any) -> bool {
+        expr.is::<ApproxPercentileCont>() || expr.is::<ApproxPercentileContWithWeight>()
+    }
+
+    pub fn is_state_mergeable(&self, aggr_expr: &dyn AggregateExpr) -> bool {
+        let expr = aggr_expr.as_any();
+        if Self::is_unmergeable_builtin(expr) {
+            return false;
+        }
+
+        match (expr.downcast_ref::<AggregateFunctionExpr>(), &self.udaf_registry) {
+            (Some(udaf), Some(registry)) => registry.is_state_mergeable(udaf.fun().name()),
+            _ => true,
+        }
+
+    }
+}
+
 /// Metrics for [ChainIterator].
 #[derive(TraceMetricWhenDrop, Default)]
 struct Metrics {
@@ -714,14 +1018,45 @@ struct Metrics {
 
 #[cfg(test)]
 mod test {
-    use datafusion::error::DataFusionError;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use arrow::datatypes::DataType;
+    use datafusion::{
+        error::DataFusionError,
+        physical_plan::expressions::{lit, ApproxPercentileCont, Column, Count},
+        scalar::ScalarValue,
+    };
     use futures::StreamExt;
 
     use crate::dist_sql_query::{
-        physical_plan::PartitionedScanStream,
+        physical_plan::{AggregatePushDownChecker, PartitionedScanStream, ScanRetry},
         test_util::{MockPartitionedScanStreamBuilder, PartitionedScanStreamCase},
+        ScanRetryPolicy,
     };
 
+    #[test]
+    fn test_aggregate_push_down_checker() {
+        let checker = AggregatePushDownChecker::default();
+        let value = Arc::new(Column::new("value", 0));
+
+        let count = Count::new(value.clone(), "count", DataType::Int64);
+        assert!(checker.is_state_mergeable(&count));
+
+        let percentile = ApproxPercentileCont::new(
+            vec![value, lit(ScalarValue::Float64(Some(0.5)))],
+            "percentile",
+            DataType::Float64,
+        )
+        .unwrap();
+        assert!(!checker.is_state_mergeable(&percentile));
+    }
+
     #[tokio::test]
     async fn test_stream_poll_success() {
         let builder = MockPartitionedScanStreamBuilder::new(PartitionedScanStreamCase::Success);
@@ -745,6 +1080,59 @@ mod test {
         test_stream_failed_state(stream, "failed to poll").await
     }
 
+    fn build_retry_stream(
+        case: PartitionedScanStreamCase,
+        max_retries: usize,
+    ) -> (PartitionedScanStream, Arc<AtomicUsize>, QueryWarnings) {
+        let builder = MockPartitionedScanStreamBuilder::new(case);
+        let retried = Arc::new(AtomicUsize::new(0));
+        let retried_clone = retried.clone();
+        let success = MockPartitionedScanStreamBuilder::new(PartitionedScanStreamCase::Success);
+        let warnings = QueryWarnings::default();
+        let retry = ScanRetry {
+            policy: ScanRetryPolicy {
+                max_retries,
+                backoff: Duration::from_millis(1),
+            },
+            table: "test".to_string(),
+            attempts: 0,
+            init_stream: Arc::new(move || {
+                retried_clone.fetch_add(1, Ordering::Relaxed);
+                Ok(success.build().stream_future)
+            }),
+            warnings: warnings.clone(),
+        };
+        (builder.build().with_retry(retry), retried, warnings)
+    }
+
+    #[tokio::test]
+    async fn test_stream_retry() {
+        // The scan failed to init as the node is unavailable is sent again.
+        let (mut stream, retried, warnings) =
+            build_retry_stream(PartitionedScanStreamCase::Unavailable, 1);
+        assert!(stream.next().await.is_none());
+        assert_eq!(retried.load(Ordering::Relaxed), 1);
+        // The retry is reported to the query.
+        let warnings = warnings.take();
+        assert_eq!(1, warnings.len());
+        assert_eq!(WarningKind::RetriedScan, warnings[0].kind);
+
+        // No retry if it's disabled.
+        let (mut stream, retried, warnings) =
+            build_retry_stream(PartitionedScanStreamCase::Unavailable, 0);
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, DataFusionError::External(_)));
+        assert!(stream.next().await.is_none());
+        assert_eq!(retried.load(Ordering::Relaxed), 0);
+        assert!(warnings.take().is_empty());
+
+        // The scan failed by the non-retryable error fails immediately.
+        let (stream, retried, _) =
+            build_retry_stream(PartitionedScanStreamCase::InitializeFailed, 3);
+        test_stream_failed_state(stream, "failed to init").await;
+        assert_eq!(retried.load(Ordering::Relaxed), 0);
+    }
+
     async fn test_stream_failed_state(mut stream: PartitionedScanStream, failed_msg: &str) {
         // Error happened, check error message.
         let result_opt = stream.next().await;
@@ -29,10 +29,10 @@ use table_engine::{remote::model::TableIdentifier, table::TableRef};
 use crate::{
     dist_sql_query::{
         physical_plan::{
-            ResolvedPartitionedScan, SubTablePlanContext, UnresolvedPartitionedScan,
-            UnresolvedSubTableScan,
+            AggregatePushDownChecker, ResolvedPartitionedScan, SubTablePlanContext,
+            UnresolvedPartitionedScan, UnresolvedSubTableScan,
         },
-        ExecutableScanBuilderRef, RemotePhysicalPlanExecutorRef,
+        ExecutableScanBuilderRef, RemotePhysicalPlanExecutorRef, ScanRetryPolicy,
     },
     metrics::PUSH_DOWN_PLAN_COUNTER,
 };
@@ -50,6 +50,8 @@ pub struct Resolver {
     catalog_manager: CatalogManagerRef,
     scan_builder: ExecutableScanBuilderRef,
     priority: Priority,
+    aggr_checker: AggregatePushDownChecker,
+    scan_retry: ScanRetryPolicy,
 }
 
 impl Resolver {
@@ -58,15 +60,24 @@ impl Resolver {
         catalog_manager: CatalogManagerRef,
         scan_builder: ExecutableScanBuilderRef,
         priority: Priority,
+        aggr_checker: AggregatePushDownChecker,
     ) -> Self {
         Self {
             remote_executor,
             catalog_manager,
             scan_builder,
             priority,
+            aggr_checker,
+            scan_retry: ScanRetryPolicy::default(),
         }
     }
 
+    /// Retry the scans of the partitions by the policy, no retry by default.
+    pub fn with_scan_retry(mut self, scan_retry: ScanRetryPolicy) -> Self {
+        self.scan_retry = scan_retry;
+        self
+    }
+
     /// Resolve partitioned scan, including:
     ///   - Convert `UnresolvedPartitionedScan`(inexecutable) to
     ///     `ResolvedPartitionedScan`(executable).
@@ -151,6 +162,7 @@ impl Resolver {
                 remote_plans,
                 metrics_collector,
                 is_analyze,
+                self.scan_retry,
             )));
         }
 
@@ -168,10 +180,11 @@ impl Resolver {
             new_children.push(child);
         }
 
-        Self::maybe_push_down_to_remote_plans(new_children, plan)
+        self.maybe_push_down_to_remote_plans(new_children, plan)
     }
 
     fn maybe_push_down_to_remote_plans(
+        &self,
         mut new_children: Vec<Arc<dyn ExecutionPlan>>,
         current_node: Arc<dyn ExecutionPlan>,
     ) -> DfResult<Arc<dyn ExecutionPlan>> {
@@ -201,7 +214,40 @@ impl Resolver {
                 return current_node.with_new_children(new_children);
             };
 
-        partitioned_scan.try_to_push_down_more(current_node.clone())
+        partitioned_scan.try_to_push_down_more(current_node.clone(), &self.aggr_checker)
+    }
+
+    /// Merge the plans of the sub tables served by the same remote node in the
+    /// resolved partitioned scans, see
+    /// [ResolvedPartitionedScan::batch_by_endpoint].
+    #[async_recursion]
+    pub async fn batch_remote_plans(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> DfResult<Arc<dyn ExecutionPlan>> {
+        if let Some(partitioned_scan) = plan.as_any().downcast_ref::<ResolvedPartitionedScan>() {
+            let batched = partitioned_scan.batch_by_endpoint().await?;
+            return Ok(batched.unwrap_or(plan));
+        }
+
+        let children = plan.children();
+        if children.is_empty() {
+            return Ok(plan);
+        }
+
+        let mut new_children = Vec::with_capacity(children.len());
+        let mut changed = false;
+        for child in children {
+            let new_child = self.batch_remote_plans(child.clone()).await?;
+            changed |= !Arc::ptr_eq(&child, &new_child);
+            new_children.push(new_child);
+        }
+
+        if changed {
+            plan.with_new_children(new_children)
+        } else {
+            Ok(plan)
+        }
     }
 
     #[async_recursion]
@@ -265,10 +311,14 @@ impl Resolver {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
 
-    use datafusion::physical_plan::displayable;
+    use datafusion::physical_plan::{displayable, union::UnionExec};
 
-    use crate::dist_sql_query::test_util::TestContext;
+    use crate::dist_sql_query::{
+        physical_plan::AggregatePushDownChecker,
+        test_util::{build_udaf, MockUdafRegistry, TestContext},
+    };
 
     #[test]
     fn test_basic_partitioned_scan() {
@@ -281,6 +331,23 @@ mod test {
         insta::assert_snapshot!(new_plan);
     }
 
+    #[tokio::test]
+    async fn test_batch_remote_plans() {
+        let ctx = TestContext::new();
+        let plan = ctx.build_basic_partitioned_table_plan();
+        let resolver = ctx.resolver();
+        let plan = resolver.resolve_partitioned_scan(plan).unwrap();
+        assert_eq!(3, plan.output_partitioning().partition_count());
+
+        // The plans of the sub tables on the same node are merged.
+        let plan = resolver.batch_remote_plans(plan).await.unwrap();
+        assert_eq!(2, plan.output_partitioning().partition_count());
+        let children = plan.children();
+        assert!(children[0].as_any().is::<UnionExec>());
+        assert_eq!(2, children[0].children().len());
+        assert!(!children[1].as_any().is::<UnionExec>());
+    }
+
     #[tokio::test]
     async fn test_basic_sub_scan() {
         let ctx = TestContext::new();
@@ -326,6 +393,31 @@ mod test {
         insta::assert_snapshot!(new_plan);
     }
 
+    #[test]
+    fn test_unmergeable_udaf_not_pushed_down() {
+        let ctx = TestContext::new();
+        let registry = Arc::new(MockUdafRegistry::default());
+        let resolver = ctx.resolver_with_checker(AggregatePushDownChecker::new(registry.clone()));
+        let plan = ctx.build_udaf_push_down_plan(&build_udaf("my_udaf"));
+
+        // Whether the partial aggregation is under the partitioned scan, that
+        // is pushed down.
+        let is_pushed_down = || {
+            let new_plan = resolver.resolve_partitioned_scan(plan.clone()).unwrap();
+            let new_plan = displayable(new_plan.as_ref()).indent(true).to_string();
+            let position = |name| new_plan.find(name).unwrap();
+            position("AggregateExec: mode=Partial") > position("ResolvedPartitionedScan")
+        };
+
+        assert!(is_pushed_down());
+        // The registry is consulted when resolving the plan, so the udaf marked
+        // later is not pushed down.
+        registry.set_state_mergeable("my_udaf", false);
+        assert!(!is_pushed_down());
+        registry.set_state_mergeable("my_udaf", true);
+        assert!(is_pushed_down());
+    }
+
     #[test]
     fn test_compounded_aggr_push_down() {
         let ctx = TestContext::new();
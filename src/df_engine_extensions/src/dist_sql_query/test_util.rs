@@ -16,8 +16,9 @@
 // under the License.
 
 use std::{
+    collections::HashSet,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
 
@@ -33,13 +34,17 @@ use common_types::{
 use datafusion::{
     error::{DataFusionError, Result as DfResult},
     execution::FunctionRegistry,
-    logical_expr::{expr_fn, Literal, Operator},
+    logical_expr::{
+        expr_fn, AccumulatorFactoryFunction, AggregateUDF, Literal, Operator, ReturnTypeFunction,
+        Signature, StateTypeFunction, Volatility,
+    },
     physical_plan::{
         aggregates::{AggregateExec, AggregateMode, PhysicalGroupBy},
         coalesce_partitions::CoalescePartitionsExec,
         expressions::{binary, col, lit, Count},
         filter::FilterExec,
         projection::ProjectionExec,
+        udaf::create_aggregate_expr,
         union::UnionExec,
         AggregateExpr, DisplayAs, EmptyRecordBatchStream, ExecutionPlan, PhysicalExpr,
         RecordBatchStream, SendableRecordBatchStream,
@@ -51,14 +56,17 @@ use runtime::Priority;
 use table_engine::{
     memory::MemoryTable,
     predicate::PredicateBuilder,
-    remote::model::TableIdentifier,
+    remote::{self, model::TableIdentifier},
     table::{ReadOptions, ReadRequest, TableId, TableRef},
     ANALYTIC_ENGINE_TYPE,
 };
 use trace_metric::MetricsCollector;
 
 use crate::dist_sql_query::{
-    physical_plan::{PartitionedScanStream, UnresolvedPartitionedScan, UnresolvedSubTableScan},
+    physical_plan::{
+        AggregatePushDownChecker, PartitionedScanStream, UdafRegistry, UnresolvedPartitionedScan,
+        UnresolvedSubTableScan,
+    },
     resolver::Resolver,
     ExecutableScanBuilder, RemotePhysicalPlanExecutor, RemoteTaskContext, TableScanContext,
 };
@@ -239,11 +247,17 @@ impl TestContext {
 
     // Return resolver
     pub fn resolver(&self) -> Resolver {
+        self.resolver_with_checker(AggregatePushDownChecker::default())
+    }
+
+    // Return resolver deciding the aggregate push down by the checker
+    pub fn resolver_with_checker(&self, aggr_checker: AggregatePushDownChecker) -> Resolver {
         Resolver::new(
             Arc::new(MockRemotePhysicalPlanExecutor),
             self.catalog_manager.clone(),
             Box::new(MockScanBuilder),
             Priority::High,
+            aggr_checker,
         )
     }
 
@@ -255,13 +269,21 @@ impl TestContext {
     pub fn build_aggr_plan_with_input(
         &self,
         input: Arc<dyn ExecutionPlan>,
+    ) -> Arc<dyn ExecutionPlan> {
+        self.build_aggr_plan_with_exprs(input, self.aggr_exprs.clone())
+    }
+
+    fn build_aggr_plan_with_exprs(
+        &self,
+        input: Arc<dyn ExecutionPlan>,
+        aggr_exprs: Vec<Arc<dyn AggregateExpr>>,
     ) -> Arc<dyn ExecutionPlan> {
         let input_schema = input.schema();
         let partial_aggregate = Arc::new(
             AggregateExec::try_new(
                 AggregateMode::Partial,
                 self.group_by.clone(),
-                self.aggr_exprs.clone(),
+                aggr_exprs.clone(),
                 vec![None],
                 vec![None],
                 input,
@@ -287,7 +309,7 @@ impl TestContext {
             AggregateExec::try_new(
                 AggregateMode::Final,
                 final_group_by,
-                self.aggr_exprs.clone(),
+                aggr_exprs,
                 vec![None],
                 vec![None],
                 merge,
@@ -369,6 +391,21 @@ impl TestContext {
         self.build_aggr_plan_with_input(unresolved_scan)
     }
 
+    // Same as the aggregate push down plan, but aggregated by the udaf.
+    pub fn build_udaf_push_down_plan(&self, udaf: &AggregateUDF) -> Arc<dyn ExecutionPlan> {
+        let unresolved_scan = Arc::new(UnresolvedPartitionedScan::new(
+            "test",
+            self.sub_table_groups[0].clone(),
+            self.request.clone(),
+        ));
+        let schema = unresolved_scan.schema();
+        let value = col("value", &schema).unwrap();
+        let name = format!("{}(value)", udaf.name());
+        let aggr_expr = create_aggregate_expr(udaf, &[value], &schema, name).unwrap();
+
+        self.build_aggr_plan_with_exprs(unresolved_scan, vec![aggr_expr])
+    }
+
     // Compunded aggregate push down plan includes:
     // Aggr final
     //      Coalesce partition
@@ -435,7 +472,7 @@ impl ExecutableScanBuilder for MockScanBuilder {
             opts: ReadOptions {
                 batch_size: ctx.batch_size,
                 read_parallelism: ctx.read_parallelism,
-                deadline: None,
+                ..Default::default()
             },
             projected_schema: ctx.projected_schema.clone(),
             predicate: ctx.predicate.clone(),
@@ -520,6 +557,17 @@ impl RemotePhysicalPlanExecutor for MockRemotePhysicalPlanExecutor {
     ) -> DfResult<BoxFuture<'static, DfResult<SendableRecordBatchStream>>> {
         unimplemented!()
     }
+
+    // The sub tables are served by two nodes alternately.
+    fn route(
+        &self,
+        tables: Vec<TableIdentifier>,
+    ) -> BoxFuture<'static, DfResult<Vec<Option<String>>>> {
+        let endpoints = (0..tables.len())
+            .map(|idx| Some(format!("node_{}", idx % 2)))
+            .collect();
+        Box::pin(async move { Ok(endpoints) })
+    }
 }
 
 /// Used in [PartitionedScanStream]'s testing
@@ -531,6 +579,8 @@ pub struct MockPartitionedScanStreamBuilder {
 #[derive(Clone, Copy)]
 pub enum PartitionedScanStreamCase {
     InitializeFailed,
+    /// Failed to init as the remote node is unavailable.
+    Unavailable,
     PollFailed,
     Success,
 }
@@ -549,6 +599,12 @@ impl MockPartitionedScanStreamBuilder {
                     async move { Err(DataFusionError::Internal("failed to init".to_string())) },
                 )
             }
+            PartitionedScanStreamCase::Unavailable => Box::pin(async move {
+                let e = remote::Error::Unavailable {
+                    source: "node is down".into(),
+                };
+                Err(DataFusionError::External(Box::new(e)))
+            }),
             PartitionedScanStreamCase::PollFailed => {
                 let error_stream = self.build_error_record_stream();
                 Box::pin(async move { Ok(error_stream) })
@@ -615,3 +671,43 @@ impl Stream for ErrorRecordBatchStream {
         ))))
     }
 }
+
+/// Build a udaf only for planning, which fails to create the accumulator.
+#[allow(deprecated)]
+pub fn build_udaf(name: &str) -> AggregateUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+    let accumulator: AccumulatorFactoryFunction =
+        Arc::new(|_| Err(DataFusionError::NotImplemented("accumulator".to_string())));
+    let state_type: StateTypeFunction = Arc::new(|_| Ok(Arc::new(vec![DataType::Utf8])));
+
+    AggregateUDF::new(
+        name,
+        &Signature::any(1, Volatility::Immutable),
+        &return_type,
+        &accumulator,
+        &state_type,
+    )
+}
+
+/// Udaf registry whose unmergeable udafs can be changed after it's shared.
+#[derive(Debug, Default)]
+pub struct MockUdafRegistry {
+    unmergeable_udafs: Mutex<HashSet<String>>,
+}
+
+impl MockUdafRegistry {
+    pub fn set_state_mergeable(&self, name: &str, mergeable: bool) {
+        let mut unmergeable_udafs = self.unmergeable_udafs.lock().unwrap();
+        if mergeable {
+            unmergeable_udafs.remove(name);
+        } else {
+            unmergeable_udafs.insert(name.to_string());
+        }
+    }
+}
+
+impl UdafRegistry for MockUdafRegistry {
+    fn is_state_mergeable(&self, name: &str) -> bool {
+        !self.unmergeable_udafs.lock().unwrap().contains(name)
+    }
+}
@@ -16,7 +16,7 @@
 // under the License.
 
 use lazy_static::lazy_static;
-use prometheus::{register_int_counter_vec, IntCounterVec};
+use prometheus::{register_int_counter, register_int_counter_vec, IntCounter, IntCounterVec};
 
 lazy_static! {
     pub static ref PUSH_DOWN_PLAN_COUNTER: IntCounterVec = register_int_counter_vec!(
@@ -25,4 +25,9 @@ lazy_static! {
         &["type"]
     )
     .unwrap();
+    pub static ref PARTITION_SCAN_RETRY_COUNTER: IntCounter = register_int_counter!(
+        "partition_scan_retry",
+        "Number of the retried scans of the partitions executed by the remote nodes"
+    )
+    .unwrap();
 }
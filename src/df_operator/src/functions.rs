@@ -22,7 +22,7 @@ use std::{
     sync::Arc,
 };
 
-use arrow::datatypes::DataType;
+use arrow::{array::ArrayRef, datatypes::DataType};
 use common_types::{column_block::ColumnBlock, datum::DatumKind};
 use datafusion::{
     error::DataFusionError,
@@ -33,7 +33,7 @@ use datafusion::{
     physical_plan::ColumnarValue as DfColumnarValue,
     scalar::ScalarValue as DfScalarValue,
 };
-use generic_error::GenericError;
+use generic_error::{BoxError, GenericError};
 use macros::define_result;
 use smallvec::SmallVec;
 use snafu::{ResultExt, Snafu};
@@ -70,10 +70,19 @@ impl ScalarValue {
         self.0
     }
 
-    fn from_df_scalar_value(df_scalar: &DfScalarValue) -> Self {
+    pub(crate) fn from_df_scalar_value(df_scalar: &DfScalarValue) -> Self {
         Self(df_scalar.clone())
     }
 
+    /// Repeat the value `num_rows` times into an array, `num_rows` must be
+    /// positive.
+    pub(crate) fn to_arrow_array(&self, num_rows: usize) -> Result<ArrayRef> {
+        let values = std::iter::repeat(self.0.clone()).take(num_rows);
+        DfScalarValue::iter_to_array(values)
+            .box_err()
+            .context(InvalidArguments)
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         match &self.0 {
             DfScalarValue::Utf8(value_opt) => value_opt.as_ref().map(|v| v.as_str()),
@@ -94,6 +103,12 @@ impl From<u64> for ScalarValue {
     }
 }
 
+impl From<Option<f64>> for ScalarValue {
+    fn from(value: Option<f64>) -> Self {
+        Self(DfScalarValue::Float64(value))
+    }
+}
+
 pub struct ScalarValueRef<'a>(&'a DfScalarValue);
 
 impl<'a> ScalarValueRef<'a> {
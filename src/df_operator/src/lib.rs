@@ -24,3 +24,4 @@ pub mod scalar;
 pub mod udaf;
 pub mod udfs;
 pub mod visitor;
+pub mod wasm;
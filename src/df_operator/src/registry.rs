@@ -19,7 +19,7 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 use datafusion::{
@@ -38,17 +38,33 @@ use crate::{scalar::ScalarUdf, udaf::AggregateUdf, udfs};
 pub enum Error {
     #[snafu(display("Udf already exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
     UdfExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Builtin udf can't be deregistered, name:{}.\nBacktrace:\n{}",
+        name,
+        backtrace
+    ))]
+    DeregisterBuiltin { name: String, backtrace: Backtrace },
 }
 
 define_result!(Error);
 
 /// A registry knows how to build logical expressions out of user-defined
 /// function' names
+///
+/// Functions can be registered and deregistered at runtime after the registry
+/// is shared, and the change is visible to the queries planned later.
 // TODO: maybe unnecessary to define inner trait rather than using datafusion's?
 pub trait FunctionRegistry {
-    fn register_udf(&mut self, udf: ScalarUdf) -> Result<()>;
+    fn register_udf(&self, udf: ScalarUdf) -> Result<()>;
+
+    fn register_udaf(&self, udaf: AggregateUdf) -> Result<()>;
+
+    /// Remove the udf, returns the removed one if any.
+    fn deregister_udf(&self, name: &str) -> Result<Option<ScalarUdf>>;
 
-    fn register_udaf(&mut self, udaf: AggregateUdf) -> Result<()>;
+    /// Remove the udaf, returns the removed one if any.
+    fn deregister_udaf(&self, name: &str) -> Result<Option<AggregateUdf>>;
 
     fn find_udf(&self, name: &str) -> Result<Option<ScalarUdf>>;
 
@@ -56,6 +72,8 @@ pub trait FunctionRegistry {
 
     fn list_udfs(&self) -> Result<Vec<ScalarUdf>>;
 
+    fn list_udafs(&self) -> Result<Vec<AggregateUdf>>;
+
     // TODO: can we remove restriction about `Send` and `Sync`?
     fn to_df_function_registry(self: Arc<Self>) -> Arc<dyn DfFunctionRegistry + Send + Sync>;
 }
@@ -63,8 +81,10 @@ pub trait FunctionRegistry {
 /// Default function registry.
 #[derive(Debug, Default)]
 pub struct FunctionRegistryImpl {
-    scalar_functions: HashMap<String, ScalarUdf>,
-    aggregate_functions: HashMap<String, AggregateUdf>,
+    scalar_functions: RwLock<HashMap<String, ScalarUdf>>,
+    aggregate_functions: RwLock<HashMap<String, AggregateUdf>>,
+    /// Names of the provided udfs, which are not allowed to be deregistered.
+    builtins: HashSet<String>,
 }
 
 impl FunctionRegistryImpl {
@@ -74,46 +94,85 @@ impl FunctionRegistryImpl {
 
     /// Load all provided udfs.
     pub fn load_functions(&mut self) -> Result<()> {
-        udfs::register_all_udfs(self)
+        udfs::register_all_udfs(self)?;
+
+        let scalar_functions = self.scalar_functions.read().unwrap();
+        let aggregate_functions = self.aggregate_functions.read().unwrap();
+        self.builtins = scalar_functions
+            .keys()
+            .chain(aggregate_functions.keys())
+            .cloned()
+            .collect();
+
+        Ok(())
     }
 }
 
 impl FunctionRegistry for FunctionRegistryImpl {
-    fn register_udf(&mut self, udf: ScalarUdf) -> Result<()> {
+    fn register_udf(&self, udf: ScalarUdf) -> Result<()> {
+        let mut scalar_functions = self.scalar_functions.write().unwrap();
         ensure!(
-            !self.scalar_functions.contains_key(udf.name()),
+            !scalar_functions.contains_key(udf.name()),
             UdfExists { name: udf.name() }
         );
 
-        self.scalar_functions.insert(udf.name().to_string(), udf);
+        scalar_functions.insert(udf.name().to_string(), udf);
 
         Ok(())
     }
 
-    fn register_udaf(&mut self, udaf: AggregateUdf) -> Result<()> {
+    fn register_udaf(&self, udaf: AggregateUdf) -> Result<()> {
+        let mut aggregate_functions = self.aggregate_functions.write().unwrap();
         ensure!(
-            !self.aggregate_functions.contains_key(udaf.name()),
+            !aggregate_functions.contains_key(udaf.name()),
             UdfExists { name: udaf.name() }
         );
 
-        self.aggregate_functions
-            .insert(udaf.name().to_string(), udaf);
+        aggregate_functions.insert(udaf.name().to_string(), udaf);
 
         Ok(())
     }
 
+    fn deregister_udf(&self, name: &str) -> Result<Option<ScalarUdf>> {
+        ensure!(!self.builtins.contains(name), DeregisterBuiltin { name });
+
+        Ok(self.scalar_functions.write().unwrap().remove(name))
+    }
+
+    fn deregister_udaf(&self, name: &str) -> Result<Option<AggregateUdf>> {
+        ensure!(!self.builtins.contains(name), DeregisterBuiltin { name });
+
+        Ok(self.aggregate_functions.write().unwrap().remove(name))
+    }
+
     fn find_udf(&self, name: &str) -> Result<Option<ScalarUdf>> {
-        let udf = self.scalar_functions.get(name).cloned();
+        let udf = self.scalar_functions.read().unwrap().get(name).cloned();
         Ok(udf)
     }
 
     fn find_udaf(&self, name: &str) -> Result<Option<AggregateUdf>> {
-        let udaf = self.aggregate_functions.get(name).cloned();
+        let udaf = self.aggregate_functions.read().unwrap().get(name).cloned();
         Ok(udaf)
     }
 
     fn list_udfs(&self) -> Result<Vec<ScalarUdf>> {
-        Ok(self.scalar_functions.values().cloned().collect())
+        Ok(self
+            .scalar_functions
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    fn list_udafs(&self) -> Result<Vec<AggregateUdf>> {
+        Ok(self
+            .aggregate_functions
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect())
     }
 
     fn to_df_function_registry(self: Arc<Self>) -> Arc<dyn DfFunctionRegistry + Send + Sync> {
@@ -161,3 +220,23 @@ impl DfFunctionRegistry for DfFunctionRegistryAdapter {
 }
 
 pub type FunctionRegistryRef = Arc<dyn FunctionRegistry + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_udafs_state_mergeable() {
+        let mut registry = FunctionRegistryImpl::new();
+        registry.load_functions().unwrap();
+
+        let is_state_mergeable = |name| {
+            let udaf = registry.find_udaf(name).unwrap().unwrap();
+            udaf.is_state_mergeable()
+        };
+        for name in ["rate", "delta", "increase", "holt_winters", "zscore"] {
+            assert!(!is_state_mergeable(name), "udaf:{name}");
+        }
+        assert!(is_state_mergeable("thetasketch_distinct"));
+    }
+}
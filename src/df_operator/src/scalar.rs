@@ -42,6 +42,14 @@ impl ScalarUdf {
         Self { df_udf }
     }
 
+    /// Create from the datafusion's udf directly, e.g. the udf whose signature
+    /// can't be described by [ScalarFunction].
+    pub fn from_datafusion_udf(df_udf: ScalarUDF) -> Self {
+        Self {
+            df_udf: Arc::new(df_udf),
+        }
+    }
+
     #[inline]
     pub fn name(&self) -> &str {
         self.df_udf.name()
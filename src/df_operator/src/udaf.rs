@@ -28,6 +28,10 @@ use crate::functions::AggregateFunction;
 pub struct AggregateUdf {
     /// DataFusion UDAF.
     df_udaf: Arc<AggregateUDF>,
+    /// Whether the partial states of this UDAF can be merged by the final
+    /// aggregation, which decides whether the partial aggregation can be pushed
+    /// down to the remote nodes.
+    state_mergeable: bool,
 }
 
 impl AggregateUdf {
@@ -46,7 +50,17 @@ impl AggregateUdf {
             &state_type,
         ));
 
-        Self { df_udaf }
+        Self {
+            df_udaf,
+            state_mergeable: true,
+        }
+    }
+
+    /// Mark whether the partial states of this UDAF are mergeable, default is
+    /// true.
+    pub fn with_state_mergeable(mut self, state_mergeable: bool) -> Self {
+        self.state_mergeable = state_mergeable;
+        self
     }
 
     #[inline]
@@ -54,6 +68,11 @@ impl AggregateUdf {
         self.df_udaf.name()
     }
 
+    #[inline]
+    pub fn is_state_mergeable(&self) -> bool {
+        self.state_mergeable
+    }
+
     #[inline]
     pub fn to_datafusion_udaf(&self) -> Arc<AggregateUDF> {
         self.df_udaf.clone()
@@ -0,0 +1,163 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! rate(), delta() and increase() udafs.
+
+use arrow::datatypes::DataType;
+use common_types::datum::DatumKind;
+
+use crate::{
+    functions::{AggregateFunction, TypeSignature},
+    registry::{self, FunctionRegistry},
+    udaf::AggregateUdf,
+    udfs::samples::{self, Sample, SamplesAccumulator},
+};
+
+pub fn register_to_registry(registry: &dyn FunctionRegistry) -> registry::Result<()> {
+    registry.register_udaf(new_udaf("rate", rate))?;
+    registry.register_udaf(new_udaf("delta", delta))?;
+    registry.register_udaf(new_udaf("increase", increase))
+}
+
+fn new_udaf(name: &str, eval_fn: fn(&[f64], &[Sample]) -> Option<f64>) -> AggregateUdf {
+    let accumulator_fn = move |_: &DataType| Ok(SamplesAccumulator::new(eval_fn));
+
+    let type_signature = TypeSignature::Exact(vec![DatumKind::Timestamp, DatumKind::Double]);
+    let state_type = samples::make_state_type();
+    let aggregate_function = AggregateFunction::make_by_fn(
+        type_signature,
+        DatumKind::Double,
+        state_type,
+        accumulator_fn,
+    );
+
+    samples::new_udaf(name, aggregate_function)
+}
+
+/// `increase(timestamp, value)` produces the increase of a counter between its
+/// first and last samples. A value smaller than the previous one is considered
+/// as a counter reset, and the counter is assumed to restart from zero.
+fn increase(_params: &[f64], samples: &[Sample]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let increase = samples
+        .windows(2)
+        .map(|pair| {
+            let (prev, curr) = (pair[0].1, pair[1].1);
+            if curr < prev {
+                curr
+            } else {
+                curr - prev
+            }
+        })
+        .sum();
+
+    Some(increase)
+}
+
+/// `rate(timestamp, value)` produces the per-second average rate of a counter
+/// between its first and last samples, with the counter resets handled the
+/// same as `increase`.
+fn rate(params: &[f64], samples: &[Sample]) -> Option<f64> {
+    let increase = increase(params, samples)?;
+    let (first, last) = (samples.first()?.0, samples.last()?.0);
+    if last == first {
+        return None;
+    }
+
+    // The timestamp is in milliseconds.
+    Some(increase * 1000.0 / (last - first) as f64)
+}
+
+/// `delta(timestamp, value)` produces the difference between the last and the
+/// first values of a gauge.
+fn delta(_params: &[f64], samples: &[Sample]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+
+    Some(samples.last()?.1 - samples.first()?.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::udfs::samples::tests::{evaluate, Row};
+
+    /// A counter reset from 3 to 1 between the seconds 1 and 2.
+    const RESET_ROWS: [Row; 4] = [
+        (Some(0), Some(1.0)),
+        (Some(1000), Some(3.0)),
+        (Some(2000), Some(1.0)),
+        (Some(3000), Some(4.0)),
+    ];
+
+    #[test]
+    fn test_counter_empty() {
+        for eval_fn in [increase, rate, delta] {
+            assert_eq!(None, evaluate(eval_fn, &[], &[]));
+        }
+    }
+
+    #[test]
+    fn test_counter_single_point() {
+        let rows = [(Some(0), Some(1.0))];
+        for eval_fn in [increase, rate, delta] {
+            assert_eq!(None, evaluate(eval_fn, &[], &rows));
+        }
+    }
+
+    #[test]
+    fn test_counter_reset() {
+        // 2 before the reset, 1 from zero and 3 after the reset.
+        assert_eq!(Some(6.0), evaluate(increase, &[], &RESET_ROWS));
+        assert_eq!(Some(2.0), evaluate(rate, &[], &RESET_ROWS));
+        // The gauge doesn't reset.
+        assert_eq!(Some(3.0), evaluate(delta, &[], &RESET_ROWS));
+    }
+
+    #[test]
+    fn test_counter_negative_delta() {
+        let rows = [(Some(0), Some(5.0)), (Some(2000), Some(2.0))];
+        // The decrease of a counter is a reset.
+        assert_eq!(Some(2.0), evaluate(increase, &[], &rows));
+        assert_eq!(Some(1.0), evaluate(rate, &[], &rows));
+        assert_eq!(Some(-3.0), evaluate(delta, &[], &rows));
+    }
+
+    #[test]
+    fn test_counter_null_gaps() {
+        let mut rows = RESET_ROWS.to_vec();
+        rows.insert(1, (Some(500), None));
+        rows.insert(3, (None, Some(100.0)));
+        rows.push((Some(4000), None));
+
+        // The rows with null are skipped, and the range ends at the last
+        // non-null sample.
+        assert_eq!(Some(6.0), evaluate(increase, &[], &rows));
+        assert_eq!(Some(2.0), evaluate(rate, &[], &rows));
+        assert_eq!(Some(3.0), evaluate(delta, &[], &rows));
+    }
+
+    #[test]
+    fn test_rate_same_timestamp() {
+        let rows = [(Some(1000), Some(1.0)), (Some(1000), Some(2.0))];
+        assert_eq!(None, evaluate(rate, &[], &rows));
+    }
+}
@@ -0,0 +1,148 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! holt_winters() udaf.
+
+use arrow::datatypes::DataType;
+use common_types::datum::DatumKind;
+
+use crate::{
+    functions::{AggregateFunction, TypeSignature},
+    registry::{self, FunctionRegistry},
+    udaf::AggregateUdf,
+    udfs::samples::{self, Sample, SamplesAccumulator},
+};
+
+pub fn register_to_registry(registry: &dyn FunctionRegistry) -> registry::Result<()> {
+    registry.register_udaf(new_udaf())
+}
+
+fn new_udaf() -> AggregateUdf {
+    let aggregate_function = new_function();
+
+    samples::new_udaf("holt_winters", aggregate_function)
+}
+
+/// `holt_winters(timestamp, value, sf, tf)` produces the smoothed value of the
+/// series by double exponential smoothing, the same as the `holt_winters` of
+/// Prometheus.
+///
+/// Both the smoothing factor `sf` and the trend factor `tf` must be in (0, 1),
+/// otherwise null is returned, and so does a series with less than two samples.
+fn new_function() -> AggregateFunction {
+    let accumulator_fn = |_: &DataType| Ok(SamplesAccumulator::new(holt_winters));
+
+    let type_signature = TypeSignature::Exact(vec![
+        DatumKind::Timestamp,
+        DatumKind::Double,
+        DatumKind::Double,
+        DatumKind::Double,
+    ]);
+    let state_type = samples::make_state_type();
+
+    AggregateFunction::make_by_fn(
+        type_signature,
+        DatumKind::Double,
+        state_type,
+        accumulator_fn,
+    )
+}
+
+fn holt_winters(params: &[f64], samples: &[Sample]) -> Option<f64> {
+    let (sf, tf) = match params {
+        [sf, tf] => (*sf, *tf),
+        _ => return None,
+    };
+    if !(sf > 0.0 && sf < 1.0 && tf > 0.0 && tf < 1.0) || samples.len() < 2 {
+        return None;
+    }
+
+    let mut s0 = 0.0;
+    let mut s1 = samples[0].1;
+    let mut trend = samples[1].1 - samples[0].1;
+    for (i, (_, value)) in samples.iter().enumerate().skip(1) {
+        if i > 1 {
+            trend = tf * (s1 - s0) + (1.0 - tf) * trend;
+        }
+        let smoothed = sf * value + (1.0 - sf) * (s1 + trend);
+
+        s0 = s1;
+        s1 = smoothed;
+    }
+
+    Some(s1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::udfs::samples::tests::evaluate;
+
+    const FACTORS: [f64; 2] = [0.5, 0.5];
+
+    #[test]
+    fn test_holt_winters_empty() {
+        assert_eq!(None, evaluate(holt_winters, &FACTORS, &[]));
+    }
+
+    #[test]
+    fn test_holt_winters_single_point() {
+        let rows = [(Some(1), Some(1.0))];
+        assert_eq!(None, evaluate(holt_winters, &FACTORS, &rows));
+    }
+
+    #[test]
+    fn test_holt_winters_nulls() {
+        let rows = [(Some(1), None), (None, Some(1.0))];
+        assert_eq!(None, evaluate(holt_winters, &FACTORS, &rows));
+
+        let rows = [
+            (Some(1), Some(1.0)),
+            (Some(2), None),
+            (None, Some(5.0)),
+            (Some(3), Some(3.0)),
+            (Some(4), Some(2.0)),
+        ];
+        assert_eq!(Some(3.5), evaluate(holt_winters, &FACTORS, &rows));
+    }
+
+    #[test]
+    fn test_holt_winters_known_answer() {
+        // s1 = 1, trend = 2
+        // 3: s = 0.5 * 3 + 0.5 * (1 + 2) = 3
+        // 2: trend = 0.5 * (3 - 1) + 0.5 * 2 = 2, s = 0.5 * 2 + 0.5 * (3 + 2) = 3.5
+        let rows = [
+            (Some(3), Some(2.0)),
+            (Some(1), Some(1.0)),
+            (Some(2), Some(3.0)),
+        ];
+        assert_eq!(Some(3.5), evaluate(holt_winters, &FACTORS, &rows));
+
+        // The trend of a linear series is kept.
+        let rows: Vec<_> = (1_i64..=4).map(|i| (Some(i), Some(i as f64))).collect();
+        assert_eq!(Some(4.0), evaluate(holt_winters, &FACTORS, &rows));
+    }
+
+    #[test]
+    fn test_holt_winters_invalid_factors() {
+        let rows = [(Some(1), Some(1.0)), (Some(2), Some(2.0))];
+        for factors in [[0.0, 0.5], [1.0, 0.5], [0.5, 0.0], [0.5, 1.0]] {
+            assert_eq!(None, evaluate(holt_winters, &factors, &rows));
+        }
+        assert_eq!(None, evaluate(holt_winters, &[0.5], &rows));
+    }
+}
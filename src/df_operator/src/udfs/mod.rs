@@ -19,13 +19,23 @@
 
 use crate::registry::{FunctionRegistry, Result};
 
+mod counter;
+mod holt_winters;
+mod row_hash;
+pub mod sample_rate;
+mod samples;
 mod thetasketch_distinct;
 mod time_bucket;
+mod zscore;
 
-pub fn register_all_udfs(registry: &mut dyn FunctionRegistry) -> Result<()> {
+pub fn register_all_udfs(registry: &dyn FunctionRegistry) -> Result<()> {
     // Register all udfs
     time_bucket::register_to_registry(registry)?;
     thetasketch_distinct::register_to_registry(registry)?;
+    holt_winters::register_to_registry(registry)?;
+    counter::register_to_registry(registry)?;
+    zscore::register_to_registry(registry)?;
+    row_hash::register_to_registry(registry)?;
 
     Ok(())
 }
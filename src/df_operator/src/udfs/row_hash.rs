@@ -0,0 +1,164 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! row_hash() udf.
+
+use std::{
+    any::Any,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+};
+
+use arrow::{array::Int64Array, datatypes::DataType};
+use datafusion::{
+    error::Result as DataFusionResult,
+    logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility},
+    scalar::ScalarValue,
+};
+use hash_ext::SeaHasherBuilder;
+
+use crate::{
+    registry::{self, FunctionRegistry},
+    scalar::ScalarUdf,
+};
+
+pub fn register_to_registry(registry: &dyn FunctionRegistry) -> registry::Result<()> {
+    let udf = ScalarUDF::new_from_impl(RowHash {
+        signature: Signature::variadic_any(Volatility::Immutable),
+    });
+
+    registry.register_udf(ScalarUdf::from_datafusion_udf(udf))
+}
+
+/// `row_hash(arg, ...)` produces a hash of the arguments of every row, which is
+/// stable across the processes and the versions so that the rows of the tables
+/// on different clusters can be compared by the sums of the hashes.
+///
+/// The hash is kept in 31 bits so that summing up billions of them won't
+/// overflow.
+#[derive(Debug)]
+struct RowHash {
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for RowHash {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "row_hash"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> DataFusionResult<DataType> {
+        Ok(DataType::Int64)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> DataFusionResult<ColumnarValue> {
+        let num_rows = args
+            .iter()
+            .find_map(|arg| match arg {
+                ColumnarValue::Array(array) => Some(array.len()),
+                ColumnarValue::Scalar(_) => None,
+            })
+            .unwrap_or(1);
+        let arrays = args
+            .iter()
+            .map(|arg| arg.clone().into_array(num_rows))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+
+        let mut hashes = Vec::with_capacity(num_rows);
+        for row in 0..num_rows {
+            let mut hasher = SeaHasherBuilder.build_hasher();
+            for array in &arrays {
+                if array.is_null(row) {
+                    hasher.write_u8(0);
+                    continue;
+                }
+
+                // The values are hashed by their texts so that the same values of
+                // the different but compatible types, e.g. the ones read back from
+                // another database, have the same hash.
+                let value = ScalarValue::try_from_array(array, row)?.to_string();
+                hasher.write_u8(1);
+                hasher.write_u64(value.len() as u64);
+                hasher.write(value.as_bytes());
+            }
+            hashes.push((hasher.finish() >> 33) as i64);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(Int64Array::from(hashes))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Float64Array, StringArray};
+
+    use super::*;
+
+    fn row_hash(args: &[ColumnarValue]) -> Vec<Option<i64>> {
+        let udf = RowHash {
+            signature: Signature::variadic_any(Volatility::Immutable),
+        };
+        let ColumnarValue::Array(array) = udf.invoke(args).unwrap() else {
+            panic!("row_hash must return an array");
+        };
+
+        array
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_row_hash() {
+        let tags: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), Some("a"), None]));
+        let fields: ArrayRef = Arc::new(Float64Array::from(vec![1.0, 1.0, 1.0]));
+        let hashes = row_hash(&[
+            ColumnarValue::Array(tags.clone()),
+            ColumnarValue::Array(fields),
+        ]);
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+        assert!(hashes.iter().all(|hash| hash.is_some_and(|hash| hash >= 0)));
+
+        // A different field value changes the hash.
+        let fields: ArrayRef = Arc::new(Float64Array::from(vec![1.0, 2.0, 1.0]));
+        let changed = row_hash(&[ColumnarValue::Array(tags), ColumnarValue::Array(fields)]);
+        assert_eq!(hashes[0], changed[0]);
+        assert_ne!(hashes[1], changed[1]);
+
+        // The boundaries of the values matter.
+        let left = row_hash(&[
+            ColumnarValue::Scalar(ScalarValue::from("ab")),
+            ColumnarValue::Scalar(ScalarValue::from("c")),
+        ]);
+        let right = row_hash(&[
+            ColumnarValue::Scalar(ScalarValue::from("a")),
+            ColumnarValue::Scalar(ScalarValue::from("bc")),
+        ]);
+        assert_eq!(1, left.len());
+        assert_ne!(left, right);
+    }
+}
@@ -0,0 +1,100 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! sample_rate UDF.
+//!
+//! Returns the ingest sample rate of a table, i.e. only 1/N of the series of
+//! the table are kept if the rate is N, and 1 if the table isn't sampled. The
+//! counts and sums over the sampled tables are scaled by it in the queries,
+//! e.g. `SELECT count(*) * sample_rate('t') FROM t`, while the averages,
+//! minimums and maximums of the kept series need no scaling.
+
+use std::{collections::HashMap, sync::Arc};
+
+use common_types::datum::DatumKind;
+use generic_error::BoxError;
+use macros::define_result;
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::{
+    functions::{ColumnarValue, InvalidArguments, ScalarFunction, ScalarValue, TypeSignature},
+    registry::{self, FunctionRegistry},
+    scalar::ScalarUdf,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Invalid arguments, require the literal name of the table."))]
+    NotTableName,
+}
+
+define_result!(Error);
+
+/// Register the udf with the sample rates of the tables, the rates less than 2
+/// mean no sampling.
+pub fn register_to_registry(
+    registry: &dyn FunctionRegistry,
+    rates: HashMap<String, u64>,
+) -> registry::Result<()> {
+    registry.register_udf(new_udf(rates))
+}
+
+fn new_udf(rates: HashMap<String, u64>) -> ScalarUdf {
+    let rates = Arc::new(rates);
+    let func = move |args: &[ColumnarValue]| {
+        let rate = sample_rate(&rates, args)
+            .box_err()
+            .context(InvalidArguments)?;
+
+        Ok(ColumnarValue::Scalar(ScalarValue::from(rate)))
+    };
+
+    let signature = TypeSignature::Exact(vec![DatumKind::String]);
+    let scalar_function = ScalarFunction::make_by_fn(signature, DatumKind::UInt64, func);
+
+    ScalarUdf::create("sample_rate", scalar_function)
+}
+
+fn sample_rate(rates: &HashMap<String, u64>, args: &[ColumnarValue]) -> Result<u64> {
+    let table = match args {
+        [ColumnarValue::Scalar(v)] => v.as_str(),
+        _ => None,
+    }
+    .context(NotTableName)?;
+
+    Ok(rates.get(table).copied().unwrap_or(1).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_rate() {
+        let rates = HashMap::from([("t1".to_string(), 10), ("t2".to_string(), 0)]);
+        let rate_of = |table: &str| {
+            let args = [ColumnarValue::Scalar(ScalarValue::from(table.to_string()))];
+            sample_rate(&rates, &args).unwrap()
+        };
+
+        assert_eq!(10, rate_of("t1"));
+        assert_eq!(1, rate_of("t2"));
+        assert_eq!(1, rate_of("t3"));
+        assert!(sample_rate(&rates, &[]).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Accumulator collecting the samples of a time series.
+
+use std::fmt;
+
+use common_types::datum::DatumKind;
+use generic_error::BoxError;
+use macros::define_result;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    aggregate::{self, Accumulator, GetState, Input, MergeState, State, StateRef},
+    functions::{AggregateFunction, ScalarValue},
+    udaf::AggregateUdf,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Invalid state len."))]
+    InvalidStateLen,
+
+    #[snafu(display("Invalid state, state is not string."))]
+    StateNotString,
+
+    #[snafu(display("Failed to decode base64 of samples, err:{}.", source))]
+    DecodeBase64 { source: base64::DecodeError },
+
+    #[snafu(display("Invalid state, failed to decode samples, err:{}.", source))]
+    DecodeSamples { source: bincode::Error },
+
+    #[snafu(display("Invalid state, failed to encode samples, err:{}.", source))]
+    EncodeSamples { source: bincode::Error },
+}
+
+define_result!(Error);
+
+/// A sample is a pair of timestamp and value.
+pub type Sample = (i64, f64);
+
+/// Samples and the constant parameters of the function, encoded as the state.
+type SamplesState = (Vec<f64>, Vec<Sample>);
+
+pub fn make_state_type() -> Vec<DatumKind> {
+    vec![DatumKind::String]
+}
+
+/// Create the udaf of the function accumulating by [SamplesAccumulator].
+///
+/// Its state is all the samples rather than an aggregated value, which isn't
+/// reduced by pushing the partial aggregation down to the remote nodes but
+/// encoded into a single string for each group. So the state is marked
+/// unmergeable to keep the aggregation on the coordinator.
+pub fn new_udaf(name: &str, aggregate_function: AggregateFunction) -> AggregateUdf {
+    AggregateUdf::create(name, aggregate_function).with_state_mergeable(false)
+}
+
+/// Accumulator collecting the samples from the first two input columns, the
+/// timestamp and the value. The remaining input columns are the constant
+/// parameters of the function, which are taken from the first row.
+///
+/// The `eval_fn` is called with the parameters and the samples sorted by
+/// timestamp. The rows with null timestamp or value are ignored.
+pub struct SamplesAccumulator<F> {
+    params: Vec<f64>,
+    samples: Vec<Sample>,
+    eval_fn: F,
+}
+
+impl<F> SamplesAccumulator<F>
+where
+    F: Fn(&[f64], &[Sample]) -> Option<f64>,
+{
+    pub fn new(eval_fn: F) -> Self {
+        Self {
+            params: Vec::new(),
+            samples: Vec::new(),
+            eval_fn,
+        }
+    }
+
+    fn merge_impl(&mut self, states: StateRef) -> Result<()> {
+        ensure!(states.num_columns() == 1, InvalidStateLen);
+        let merged_col = states.column(0).unwrap();
+
+        for row_idx in 0..merged_col.num_rows() {
+            let datum = merged_col.datum_view(row_idx);
+            let samples_string = datum.into_str().context(StateNotString)?;
+            let samples_bytes = base64::decode(samples_string).context(DecodeBase64)?;
+            let (params, samples): SamplesState =
+                bincode::deserialize(&samples_bytes).context(DecodeSamples)?;
+
+            if self.params.is_empty() {
+                self.params = params;
+            }
+            self.samples.extend(samples);
+        }
+
+        Ok(())
+    }
+}
+
+impl<F> fmt::Debug for SamplesAccumulator<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SamplesAccumulator")
+            .field("samples", &self.samples.len())
+            .finish()
+    }
+}
+
+impl<F> Accumulator for SamplesAccumulator<F>
+where
+    F: Fn(&[f64], &[Sample]) -> Option<f64> + Send + Sync,
+{
+    fn state(&self) -> aggregate::Result<State> {
+        let buf = bincode::serialize(&(&self.params, &self.samples))
+            .context(EncodeSamples)
+            .box_err()
+            .context(GetState)?;
+        // Same as the thetasketch_distinct, the binary is encoded by base64.
+        let samples_string = base64::encode(buf);
+
+        Ok(State::from(ScalarValue::from(samples_string)))
+    }
+
+    fn update(&mut self, input: Input) -> aggregate::Result<()> {
+        let (timestamp_col, value_col) = match (input.column(0), input.column(1)) {
+            (Some(timestamp_col), Some(value_col)) => (timestamp_col, value_col),
+            _ => return Ok(()),
+        };
+
+        if self.params.is_empty() && timestamp_col.num_rows() > 0 {
+            self.params = (2..input.num_columns())
+                .filter_map(|col_idx| input.column(col_idx))
+                .map(|col| col.datum_view(0).as_f64().unwrap_or(f64::NAN))
+                .collect();
+        }
+
+        for row_idx in 0..timestamp_col.num_rows() {
+            let timestamp = timestamp_col.datum_view(row_idx).as_timestamp();
+            let value = value_col.datum_view(row_idx).as_f64();
+            if let (Some(timestamp), Some(value)) = (timestamp, value) {
+                self.samples.push((timestamp.as_i64(), value));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, states: StateRef) -> aggregate::Result<()> {
+        self.merge_impl(states).box_err().context(MergeState)
+    }
+
+    fn evaluate(&self) -> aggregate::Result<ScalarValue> {
+        let mut samples = self.samples.clone();
+        samples.sort_unstable_by_key(|sample| sample.0);
+
+        Ok(ScalarValue::from((self.eval_fn)(&self.params, &samples)))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Float64Array, TimestampMillisecondArray};
+    use datafusion::{
+        physical_plan::Accumulator as DfAccumulator, scalar::ScalarValue as DfScalarValue,
+    };
+
+    use super::*;
+    use crate::aggregate::ToDfAccumulator;
+
+    /// A row of timestamp and value, either of them may be null.
+    pub type Row = (Option<i64>, Option<f64>);
+
+    /// Evaluate the `eval_fn` over the `rows` with the constant `params`, and
+    /// check the same result is produced after merging the state.
+    pub fn evaluate<F>(eval_fn: F, params: &[f64], rows: &[Row]) -> Option<f64>
+    where
+        F: Fn(&[f64], &[Sample]) -> Option<f64> + Clone + Send + Sync + 'static,
+    {
+        let timestamps: TimestampMillisecondArray = rows.iter().map(|row| row.0).collect();
+        let values: Float64Array = rows.iter().map(|row| row.1).collect();
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(timestamps), Arc::new(values)];
+        for param in params {
+            columns.push(Arc::new(Float64Array::from(vec![*param; rows.len()])));
+        }
+
+        let mut accumulator = ToDfAccumulator::new(SamplesAccumulator::new(eval_fn.clone()));
+        accumulator.update_batch(&columns).unwrap();
+        let result = accumulator.evaluate().unwrap();
+
+        let states: Vec<_> = accumulator
+            .state()
+            .unwrap()
+            .iter()
+            .map(|state| state.to_array().unwrap())
+            .collect();
+        let mut merged = ToDfAccumulator::new(SamplesAccumulator::new(eval_fn));
+        merged.merge_batch(&states).unwrap();
+        assert_eq!(result, merged.evaluate().unwrap());
+
+        match result {
+            DfScalarValue::Float64(value) => value,
+            other => panic!("unexpected result:{other:?}"),
+        }
+    }
+
+    /// The last value scaled by the first param.
+    fn last_value(params: &[f64], samples: &[Sample]) -> Option<f64> {
+        let scale = params.first().copied().unwrap_or(1.0);
+        samples.last().map(|(_, value)| value * scale)
+    }
+
+    #[test]
+    fn test_samples_empty() {
+        assert_eq!(None, evaluate(last_value, &[], &[]));
+        assert_eq!(None, evaluate(last_value, &[2.0], &[]));
+    }
+
+    #[test]
+    fn test_samples_nulls() {
+        let rows = [(None, Some(1.0)), (Some(1), None), (None, None)];
+        assert_eq!(None, evaluate(last_value, &[], &rows));
+
+        // The rows with null are ignored.
+        let rows = [
+            (Some(3), Some(3.0)),
+            (None, Some(9.0)),
+            (Some(4), None),
+            (Some(1), Some(1.0)),
+        ];
+        assert_eq!(Some(3.0), evaluate(last_value, &[], &rows));
+    }
+
+    #[test]
+    fn test_samples_single_point() {
+        let rows = [(Some(1), Some(2.0))];
+        assert_eq!(Some(2.0), evaluate(last_value, &[], &rows));
+        assert_eq!(Some(20.0), evaluate(last_value, &[10.0], &rows));
+    }
+
+    #[test]
+    fn test_samples_sorted_by_timestamp() {
+        let rows = [
+            (Some(2), Some(2.0)),
+            (Some(3), Some(3.0)),
+            (Some(1), Some(1.0)),
+        ];
+        assert_eq!(Some(6.0), evaluate(last_value, &[2.0], &rows));
+    }
+}
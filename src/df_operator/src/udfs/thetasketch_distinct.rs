@@ -60,7 +60,7 @@ const HLL_ERROR_RATE: f64 = 0.01;
 // Hll seed:
 const HLL_KEY: u128 = 0;
 
-pub fn register_to_registry(registry: &mut dyn FunctionRegistry) -> registry::Result<()> {
+pub fn register_to_registry(registry: &dyn FunctionRegistry) -> registry::Result<()> {
     registry.register_udaf(new_udaf())
 }
 
@@ -82,7 +82,7 @@ define_result!(Error);
 /// Default timezone: +08:00
 const DEFAULT_TIMEZONE_OFFSET_SECS: i32 = 8 * 3600;
 
-pub fn register_to_registry(registry: &mut dyn FunctionRegistry) -> registry::Result<()> {
+pub fn register_to_registry(registry: &dyn FunctionRegistry) -> registry::Result<()> {
     registry.register_udf(new_udf())
 }
 
@@ -0,0 +1,127 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! zscore() udaf.
+
+use arrow::datatypes::DataType;
+use common_types::datum::DatumKind;
+
+use crate::{
+    functions::{AggregateFunction, TypeSignature},
+    registry::{self, FunctionRegistry},
+    udaf::AggregateUdf,
+    udfs::samples::{self, Sample, SamplesAccumulator},
+};
+
+pub fn register_to_registry(registry: &dyn FunctionRegistry) -> registry::Result<()> {
+    registry.register_udaf(new_udaf())
+}
+
+fn new_udaf() -> AggregateUdf {
+    let aggregate_function = new_function();
+
+    samples::new_udaf("zscore", aggregate_function)
+}
+
+/// `zscore(timestamp, value)` produces the z-score of the latest value in the
+/// series, that is how many standard deviations it is away from the mean of
+/// all the values, which is usually used to detect the anomaly.
+///
+/// Null is returned if the series has less than two samples or all the values
+/// are the same.
+fn new_function() -> AggregateFunction {
+    let accumulator_fn = |_: &DataType| Ok(SamplesAccumulator::new(zscore));
+
+    let type_signature = TypeSignature::Exact(vec![DatumKind::Timestamp, DatumKind::Double]);
+    let state_type = samples::make_state_type();
+
+    AggregateFunction::make_by_fn(
+        type_signature,
+        DatumKind::Double,
+        state_type,
+        accumulator_fn,
+    )
+}
+
+fn zscore(_params: &[f64], samples: &[Sample]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|(_, value)| value).sum::<f64>() / n;
+    let variance = samples
+        .iter()
+        .map(|(_, value)| (value - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let stddev = variance.sqrt();
+    if stddev == 0.0 {
+        return None;
+    }
+
+    samples.last().map(|(_, value)| (value - mean) / stddev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::udfs::samples::tests::evaluate;
+
+    #[test]
+    fn test_zscore_empty() {
+        assert_eq!(None, evaluate(zscore, &[], &[]));
+    }
+
+    #[test]
+    fn test_zscore_single_point() {
+        let rows = [(Some(1), Some(1.0))];
+        assert_eq!(None, evaluate(zscore, &[], &rows));
+    }
+
+    #[test]
+    fn test_zscore_nulls() {
+        let rows = [(Some(1), None), (None, Some(1.0)), (Some(2), Some(2.0))];
+        assert_eq!(None, evaluate(zscore, &[], &rows));
+
+        let rows = [
+            (Some(1), Some(1.0)),
+            (Some(2), None),
+            (None, Some(100.0)),
+            (Some(3), Some(3.0)),
+        ];
+        assert_eq!(Some(1.0), evaluate(zscore, &[], &rows));
+    }
+
+    #[test]
+    fn test_zscore_known_answer() {
+        // The mean is 5 and the standard deviation is 2, and the latest value
+        // is 9.
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let mut rows: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (Some(i as i64), Some(*value)))
+            .collect();
+        rows.reverse();
+        assert_eq!(Some(2.0), evaluate(zscore, &[], &rows));
+
+        // All the values are the same.
+        let rows = [(Some(1), Some(3.0)), (Some(2), Some(3.0))];
+        assert_eq!(None, evaluate(zscore, &[], &rows));
+    }
+}
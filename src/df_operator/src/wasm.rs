@@ -0,0 +1,664 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Scalar udfs exported by WASM modules.
+//!
+//! The modules are loaded from the udf directory, and every exported function
+//! whose parameters are numbers (i32, i64, f32 or f64) and which returns one
+//! number is registered as a scalar udf named after the export, e.g. a module
+//! exporting `celsius(f64) -> f64` can be called by `celsius(value)`. The null
+//! arguments make the result null without calling the function.
+//!
+//! The modules can't import anything from the host, and every call is bounded
+//! by fuel so a looping function fails the query instead of hanging it.
+//!
+//! A module put into the directory at runtime can be loaded by `CREATE
+//! FUNCTION` or by reloading the directory. The functions dropped by `DROP
+//! FUNCTION` are recorded in the directory and are not loaded again after
+//! restart. The functions are local to the node, and the sub plans calling them
+//! may be executed by other nodes, so the same modules should be deployed to
+//! every node of a cluster.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use arrow::{
+    array::{
+        new_empty_array, ArrayRef, AsArray, Float32Array, Float64Array, Int32Array, Int64Array,
+    },
+    compute,
+    datatypes::{DataType, Float32Type, Float64Type, Int32Type, Int64Type},
+};
+use common_types::{column_block::ColumnBlock, datum::DatumKind};
+use datafusion::scalar::ScalarValue as DfScalarValue;
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+use wasmtime::{Engine, ExternType, Func, FuncType, Instance, Module, Store, Val, ValType};
+
+use crate::{
+    functions::{
+        self, CallFunction, ColumnarValue, InvalidArguments, InvalidArray, ScalarFunction,
+        ScalarValue, TypeSignature,
+    },
+    registry::{self, FunctionRegistryRef},
+    scalar::ScalarUdf,
+};
+
+/// Extension of the module files.
+const MODULE_EXTENSION: &str = "wasm";
+/// File recording the names of the dropped functions, one name per line.
+const DROPPED_FUNCTIONS_FILE: &str = "dropped_functions";
+/// Fuel of one call, roughly the number of the executed instructions.
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to create wasm engine, err:{}", source))]
+    CreateEngine { source: GenericError },
+
+    #[snafu(display("Failed to access udf dir, path:{}, err:{}", path.display(), source))]
+    AccessDir { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Invalid wasm module name, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    InvalidModuleName { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to load wasm module, path:{}, err:{}", path.display(), source))]
+    LoadModule { path: PathBuf, source: GenericError },
+
+    #[snafu(display(
+        "Function is not exported by the module, module:{}, name:{}.\nBacktrace:\n{}",
+        module,
+        name,
+        backtrace
+    ))]
+    ExportNotFound {
+        module: String,
+        name: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Unsupported signature of the function, name:{}, signature:{}.\nBacktrace:\n{}",
+        name,
+        signature,
+        backtrace
+    ))]
+    UnsupportedSignature {
+        name: String,
+        signature: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Function is not loaded from wasm module, name:{}.\nBacktrace:\n{}",
+        name,
+        backtrace
+    ))]
+    NotWasmFunction { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to register function, name:{}, err:{}", name, source))]
+    Register {
+        name: String,
+        source: registry::Error,
+    },
+}
+
+define_result!(Error);
+
+/// A function loaded from a wasm module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFunctionInfo {
+    pub name: String,
+    /// File name of the module.
+    pub module: String,
+    /// e.g. `(f64, f64) -> f64`
+    pub signature: String,
+}
+
+/// Manager of the udfs loaded from the wasm modules in the udf directory.
+pub struct WasmUdfManager {
+    dir: PathBuf,
+    engine: Engine,
+    registry: FunctionRegistryRef,
+    /// Functions loaded by the manager, keyed by the name.
+    functions: Mutex<HashMap<String, WasmFunctionInfo>>,
+}
+
+impl WasmUdfManager {
+    pub fn try_new(dir: impl Into<PathBuf>, registry: FunctionRegistryRef) -> Result<Self> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(GenericError::from)
+            .context(CreateEngine)?;
+
+        Ok(Self {
+            dir: dir.into(),
+            engine,
+            registry,
+            functions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Load the functions of all the modules in the directory, except the
+    /// dropped ones and the ones already registered, whose names are returned.
+    ///
+    /// The exports with unsupported signatures are skipped.
+    pub fn load_all(&self) -> Result<Vec<String>> {
+        let dropped = self.dropped_functions()?;
+        let mut module_paths = Vec::new();
+        for entry in fs::read_dir(&self.dir).context(AccessDir { path: &self.dir })? {
+            let path = entry.context(AccessDir { path: &self.dir })?.path();
+            if path.is_file() && path.extension() == Some(OsStr::new(MODULE_EXTENSION)) {
+                module_paths.push(path);
+            }
+        }
+        module_paths.sort();
+
+        let mut loaded = Vec::new();
+        for path in module_paths {
+            for function in self.load_module(&path)? {
+                let Ok(function) = function else {
+                    continue;
+                };
+                let name = function.info.name.clone();
+                if dropped.contains(&name) || self.is_registered(&name) {
+                    continue;
+                }
+
+                self.register(function)?;
+                loaded.push(name);
+            }
+        }
+
+        Ok(loaded)
+    }
+
+    /// Load the function `name` exported by the `module` in the directory.
+    pub fn create_function(&self, name: &str, module: &str) -> Result<()> {
+        let is_valid_name = !module.contains(['/', '\\'])
+            && Path::new(module).extension() == Some(OsStr::new(MODULE_EXTENSION));
+        ensure!(is_valid_name, InvalidModuleName { name: module });
+
+        let function = self
+            .load_module(&self.dir.join(module))?
+            .into_iter()
+            .find(|function| match function {
+                Ok(function) => function.info.name == name,
+                Err(Error::UnsupportedSignature { name: export, .. }) => export == name,
+                Err(_) => false,
+            })
+            .context(ExportNotFound { module, name })??;
+        self.register(function)?;
+
+        // The function is registered even if it fails to be recorded, and it won't
+        // be loaded after restart if it was dropped before.
+        self.update_dropped_functions(name, false)
+    }
+
+    /// Drop the function loaded from a wasm module, and it won't be loaded
+    /// again after restart.
+    pub fn drop_function(&self, name: &str) -> Result<()> {
+        ensure!(self.contains(name), NotWasmFunction { name });
+
+        self.registry
+            .deregister_udf(name)
+            .context(Register { name })?;
+        self.functions.lock().unwrap().remove(name);
+
+        self.update_dropped_functions(name, true)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.lock().unwrap().contains_key(name)
+    }
+
+    /// Whether the function of the `name` exists in the registry, e.g. the
+    /// builtin or the wasm function.
+    pub fn is_registered(&self, name: &str) -> bool {
+        matches!(self.registry.find_udf(name), Ok(Some(_)))
+    }
+
+    /// List the loaded functions ordered by the name.
+    pub fn list(&self) -> Vec<WasmFunctionInfo> {
+        let mut functions: Vec<_> = self.functions.lock().unwrap().values().cloned().collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        functions
+    }
+
+    fn register(&self, function: WasmFunction) -> Result<()> {
+        let info = function.info.clone();
+        let udf = function.into_udf();
+        self.registry
+            .register_udf(udf)
+            .context(Register { name: &info.name })?;
+        self.functions
+            .lock()
+            .unwrap()
+            .insert(info.name.clone(), info);
+
+        Ok(())
+    }
+
+    /// Instantiate the module and return its exported functions, the ones with
+    /// unsupported signatures are returned as errors.
+    fn load_module(&self, path: &Path) -> Result<Vec<Result<WasmFunction>>> {
+        let module = Module::from_file(&self.engine, path)
+            .map_err(GenericError::from)
+            .context(LoadModule { path })?;
+        let mut store = Store::new(&self.engine, ());
+        // The start function of the module also consumes the fuel.
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(GenericError::from)
+            .context(LoadModule { path })?;
+        // No imports are provided, so the modules importing anything fail here.
+        let instance = Instance::new(&mut store, &module, &[])
+            .map_err(GenericError::from)
+            .context(LoadModule { path })?;
+
+        let module_name = path
+            .file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let exports: Vec<_> = module
+            .exports()
+            .filter_map(|export| match export.ty() {
+                ExternType::Func(ty) => Some((export.name().to_string(), ty)),
+                _ => None,
+            })
+            .collect();
+        let mut functions = Vec::with_capacity(exports.len());
+        for (name, ty) in exports {
+            let func = instance
+                .get_func(&mut store, &name)
+                .context(ExportNotFound {
+                    module: &module_name,
+                    name: &name,
+                })?;
+            functions.push((name, ty, func));
+        }
+
+        let store = Arc::new(Mutex::new(store));
+        let functions = functions
+            .into_iter()
+            .map(|(name, ty, func)| {
+                WasmFunction::try_new(name, module_name.clone(), &ty, func, store.clone())
+            })
+            .collect();
+
+        Ok(functions)
+    }
+
+    fn dropped_functions(&self) -> Result<BTreeSet<String>> {
+        let path = self.dir.join(DROPPED_FUNCTIONS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(content
+                .lines()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+            Err(e) => Err(e).context(AccessDir { path }),
+        }
+    }
+
+    fn update_dropped_functions(&self, name: &str, dropped: bool) -> Result<()> {
+        let mut names = self.dropped_functions()?;
+        let changed = if dropped {
+            names.insert(name.to_string())
+        } else {
+            names.remove(name)
+        };
+        if !changed {
+            return Ok(());
+        }
+
+        // Write a temporary file and rename it, so the file is never partially
+        // written.
+        let path = self.dir.join(DROPPED_FUNCTIONS_FILE);
+        let tmp_path = self.dir.join(format!("{DROPPED_FUNCTIONS_FILE}.tmp"));
+        let content: String = names.iter().map(|name| format!("{name}\n")).collect();
+        fs::write(&tmp_path, content).context(AccessDir { path: &tmp_path })?;
+        fs::rename(&tmp_path, &path).context(AccessDir { path })
+    }
+}
+
+/// A function exported by a wasm module.
+struct WasmFunction {
+    info: WasmFunctionInfo,
+    params: Vec<ValType>,
+    result: ValType,
+    func: Func,
+    /// Store of the module instance, shared by all the functions of the module.
+    store: Arc<Mutex<Store<()>>>,
+}
+
+impl WasmFunction {
+    fn try_new(
+        name: String,
+        module: String,
+        ty: &FuncType,
+        func: Func,
+        store: Arc<Mutex<Store<()>>>,
+    ) -> Result<Self> {
+        let params: Vec<_> = ty.params().collect();
+        let results: Vec<_> = ty.results().collect();
+        let signature = format!(
+            "({}) -> {}",
+            params
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            results
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        let is_supported = results.len() == 1
+            && params
+                .iter()
+                .chain(&results)
+                .all(|ty| datum_kind_of(ty).is_some());
+        ensure!(
+            is_supported,
+            UnsupportedSignature {
+                name: &name,
+                signature: &signature,
+            }
+        );
+
+        Ok(Self {
+            info: WasmFunctionInfo {
+                name,
+                module,
+                signature,
+            },
+            params,
+            result: results[0].clone(),
+            func,
+            store,
+        })
+    }
+
+    fn into_udf(self) -> ScalarUdf {
+        let kinds = self.params.iter().filter_map(datum_kind_of).collect();
+        // The signature is checked to be supported.
+        let return_kind = datum_kind_of(&self.result).unwrap();
+        let name = self.info.name.clone();
+        let signature = TypeSignature::Exact(kinds);
+        let scalar_function =
+            ScalarFunction::make_by_fn(signature, return_kind, move |args| self.call(args));
+
+        ScalarUdf::create(&name, scalar_function)
+    }
+
+    fn call(&self, args: &[ColumnarValue]) -> functions::Result<ColumnarValue> {
+        // The result is a scalar if all the arguments are scalars.
+        let num_rows = args.iter().find_map(|arg| match arg {
+            ColumnarValue::Array(v) => Some(v.num_rows()),
+            ColumnarValue::Scalar(_) => None,
+        });
+        if num_rows == Some(0) {
+            let array = new_empty_array(&data_type_of(&self.result));
+            let column_block =
+                ColumnBlock::try_cast_arrow_array_ref(&array).context(InvalidArray)?;
+            return Ok(ColumnarValue::Array(column_block));
+        }
+
+        let mut arrays = Vec::with_capacity(args.len());
+        for (arg, ty) in args.iter().zip(&self.params) {
+            let array = match arg {
+                ColumnarValue::Array(v) => v.to_arrow_array_ref(),
+                ColumnarValue::Scalar(v) => v.to_arrow_array(num_rows.unwrap_or(1))?,
+            };
+            let array = compute::cast(&array, &data_type_of(ty))
+                .box_err()
+                .context(InvalidArguments)?;
+            arrays.push(array);
+        }
+
+        let mut results = Vec::with_capacity(num_rows.unwrap_or(1));
+        let mut output = [Val::I32(0)];
+        let mut store = self.store.lock().unwrap();
+        for row_idx in 0..num_rows.unwrap_or(1) {
+            let params: Option<Vec<_>> = arrays
+                .iter()
+                .zip(&self.params)
+                .map(|(array, ty)| value_of(array, ty, row_idx))
+                .collect();
+            let Some(params) = params else {
+                results.push(None);
+                continue;
+            };
+
+            store
+                .set_fuel(FUEL_PER_CALL)
+                .map_err(GenericError::from)
+                .context(CallFunction)?;
+            self.func
+                .call(&mut *store, &params, &mut output)
+                .map_err(GenericError::from)
+                .context(CallFunction)?;
+            results.push(Some(output[0].clone()));
+        }
+
+        let array = to_array(&self.result, &results);
+        if num_rows.is_none() {
+            let value = DfScalarValue::try_from_array(&array, 0)
+                .box_err()
+                .context(CallFunction)?;
+            return Ok(ColumnarValue::Scalar(ScalarValue::from_df_scalar_value(
+                &value,
+            )));
+        }
+
+        let column_block = ColumnBlock::try_cast_arrow_array_ref(&array).context(InvalidArray)?;
+        Ok(ColumnarValue::Array(column_block))
+    }
+}
+
+fn datum_kind_of(ty: &ValType) -> Option<DatumKind> {
+    match ty {
+        ValType::I32 => Some(DatumKind::Int32),
+        ValType::I64 => Some(DatumKind::Int64),
+        ValType::F32 => Some(DatumKind::Float),
+        ValType::F64 => Some(DatumKind::Double),
+        _ => None,
+    }
+}
+
+/// Only called for the supported types.
+fn data_type_of(ty: &ValType) -> DataType {
+    match ty {
+        ValType::I32 => DataType::Int32,
+        ValType::I64 => DataType::Int64,
+        ValType::F32 => DataType::Float32,
+        _ => DataType::Float64,
+    }
+}
+
+/// Value of the row in the array already cast to the type, `None` if it's
+/// null.
+fn value_of(array: &ArrayRef, ty: &ValType, row_idx: usize) -> Option<Val> {
+    if array.is_null(row_idx) {
+        return None;
+    }
+
+    let value = match ty {
+        ValType::I32 => Val::from(array.as_primitive::<Int32Type>().value(row_idx)),
+        ValType::I64 => Val::from(array.as_primitive::<Int64Type>().value(row_idx)),
+        ValType::F32 => Val::from(array.as_primitive::<Float32Type>().value(row_idx)),
+        _ => Val::from(array.as_primitive::<Float64Type>().value(row_idx)),
+    };
+    Some(value)
+}
+
+fn to_array(ty: &ValType, values: &[Option<Val>]) -> ArrayRef {
+    let values = values.iter().map(Option::as_ref);
+    match ty {
+        ValType::I32 => Arc::new(Int32Array::from_iter(values.map(|v| v?.i32()))),
+        ValType::I64 => Arc::new(Int64Array::from_iter(values.map(|v| v?.i64()))),
+        ValType::F32 => Arc::new(Float32Array::from_iter(values.map(|v| v?.f32()))),
+        _ => Arc::new(Float64Array::from_iter(values.map(|v| v?.f64()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::FunctionRegistryImpl;
+
+    const MATH_MODULE: &str = r#"
+        (module
+            (func (export "add_f64") (param f64 f64) (result f64)
+                local.get 0
+                local.get 1
+                f64.add)
+            (func (export "double_i64") (param i64) (result i64)
+                local.get 0
+                i64.const 2
+                i64.mul)
+            (func (export "spin") (param i32) (result i32)
+                (loop $l (br $l))
+                local.get 0)
+            (func (export "pair") (param i32) (result i32 i32)
+                local.get 0
+                local.get 0))
+    "#;
+
+    fn new_udf_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        // The text format is also accepted.
+        fs::write(dir.path().join("math.wasm"), MATH_MODULE).unwrap();
+        fs::write(dir.path().join("readme.txt"), "not a module").unwrap();
+        dir
+    }
+
+    fn new_manager(dir: &Path) -> WasmUdfManager {
+        let registry = Arc::new(FunctionRegistryImpl::new());
+        WasmUdfManager::try_new(dir, registry).unwrap()
+    }
+
+    fn load_function(manager: &WasmUdfManager, name: &str) -> WasmFunction {
+        manager
+            .load_module(&manager.dir.join("math.wasm"))
+            .unwrap()
+            .into_iter()
+            .filter_map(Result::ok)
+            .find(|function| function.info.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_all() {
+        let dir = new_udf_dir();
+        let manager = new_manager(dir.path());
+
+        let loaded = manager.load_all().unwrap();
+        assert_eq!(loaded, vec!["add_f64", "double_i64", "spin"]);
+        assert!(manager.is_registered("add_f64"));
+        assert!(!manager.is_registered("pair"));
+        // The loaded functions are skipped.
+        assert!(manager.load_all().unwrap().is_empty());
+
+        let add = WasmFunctionInfo {
+            name: "add_f64".to_string(),
+            module: "math.wasm".to_string(),
+            signature: "(f64, f64) -> f64".to_string(),
+        };
+        assert_eq!(manager.list()[0], add);
+    }
+
+    #[test]
+    fn test_call_function() {
+        let dir = new_udf_dir();
+        let manager = new_manager(dir.path());
+
+        let add = load_function(&manager, "add_f64");
+        let array: ArrayRef = Arc::new(Float64Array::from(vec![Some(1.0), None, Some(3.0)]));
+        let args = [
+            ColumnarValue::Array(ColumnBlock::try_cast_arrow_array_ref(&array).unwrap()),
+            ColumnarValue::Scalar(ScalarValue::from(Some(0.5))),
+        ];
+        let ColumnarValue::Array(result) = add.call(&args).unwrap() else {
+            panic!("the result should be an array");
+        };
+        let expect: ArrayRef = Arc::new(Float64Array::from(vec![Some(1.5), None, Some(3.5)]));
+        assert_eq!(&result.to_arrow_array_ref(), &expect);
+
+        let double = load_function(&manager, "double_i64");
+        let arg = ScalarValue::from_df_scalar_value(&DfScalarValue::Int64(Some(21)));
+        let ColumnarValue::Scalar(result) = double.call(&[ColumnarValue::Scalar(arg)]).unwrap()
+        else {
+            panic!("the result should be a scalar");
+        };
+        assert_eq!(result.into_df_scalar_value(), DfScalarValue::Int64(Some(42)));
+
+        // The looping function runs out of the fuel.
+        let spin = load_function(&manager, "spin");
+        let arg = ScalarValue::from_df_scalar_value(&DfScalarValue::Int32(Some(1)));
+        assert!(spin.call(&[ColumnarValue::Scalar(arg)]).is_err());
+    }
+
+    #[test]
+    fn test_create_and_drop_function() {
+        let dir = new_udf_dir();
+        let manager = new_manager(dir.path());
+        manager.load_all().unwrap();
+
+        manager.drop_function("add_f64").unwrap();
+        assert!(!manager.is_registered("add_f64"));
+        assert!(matches!(
+            manager.drop_function("add_f64"),
+            Err(Error::NotWasmFunction { .. })
+        ));
+
+        // The dropped function isn't loaded after restart.
+        let manager = new_manager(dir.path());
+        assert_eq!(manager.load_all().unwrap(), vec!["double_i64", "spin"]);
+
+        manager.create_function("add_f64", "math.wasm").unwrap();
+        assert!(manager.is_registered("add_f64"));
+        assert!(matches!(
+            manager.create_function("add_f64", "../math.wasm"),
+            Err(Error::InvalidModuleName { .. })
+        ));
+        assert!(matches!(
+            manager.create_function("pair", "math.wasm"),
+            Err(Error::UnsupportedSignature { .. })
+        ));
+        assert!(matches!(
+            manager.create_function("sub", "math.wasm"),
+            Err(Error::ExportNotFound { .. })
+        ));
+
+        // The created function is loaded after restart.
+        let manager = new_manager(dir.path());
+        assert_eq!(manager.load_all().unwrap().len(), 3);
+    }
+}
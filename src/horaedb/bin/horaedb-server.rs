@@ -17,26 +17,10 @@
 
 //! The main entry point to start the server
 
-use std::env;
-
-use clap::{Arg, Command};
-use horaedb::{
-    config::{ClusterDeployment, Config},
-    setup,
-};
+use clap::{Arg, ArgAction, Command};
+use horaedb::{config::Config, config_reload, setup};
 use logger::info;
 
-/// By this environment variable, the address of current node can be overridden.
-/// And it could be domain name or ip address, but no port follows it.
-const HORAEDB_SERVER_ADDR: &str = "HORAEDB_SERVER_ADDR";
-/// By this environment variable, the address of horaemeta can be overridden.
-const HORAEMETA_SERVER_ADDR: &str = "HORAEMETA_SERVER_ADDR";
-/// By this environment variable, the etcd addresses can be overridden.
-const ETCD_ADDRS: &str = "ETCD_ADDRS";
-/// By this environment variable, the cluster name of current node can be
-/// overridden.
-const CLUSTER_NAME: &str = "CLUSTER_NAME";
-
 /// Default value for version information is not found from environment
 const UNKNOWN: &str = "Unknown";
 
@@ -76,29 +60,37 @@ fn main() {
                 .num_args(1)
                 .help("Set configuration file, eg: \"/path/server.toml\""),
         )
+        .arg(
+            Arg::new("repair")
+                .long("repair")
+                .action(ArgAction::SetTrue)
+                .help("Repair the broken tables instead of refusing to start"),
+        )
+        .arg(
+            Arg::new("query-only")
+                .long("query-only")
+                .num_args(1)
+                .value_name("DATA_DIR")
+                .conflicts_with("repair")
+                .help("Serve the queries only over the data directory, eg: a restored backup"),
+        )
         .get_matches();
 
-    let mut config = match matches.get_one::<String>("config") {
+    let config_path = matches.get_one::<String>("config").cloned();
+    let mut config = match &config_path {
         Some(path) => {
             let mut toml_buf = String::new();
             toml_ext::parse_toml_from_path(path, &mut toml_buf).expect("Failed to parse config.")
         }
         None => Config::default(),
     };
-
-    if let Ok(node_addr) = env::var(HORAEDB_SERVER_ADDR) {
-        config.node.addr = node_addr;
-    }
-    if let Ok(meta_addr) = env::var(HORAEMETA_SERVER_ADDR) {
-        config.set_meta_addr(meta_addr);
+    config.override_by_env();
+    if let Some(path) = &config_path {
+        config = config_reload::apply_override_file(config, path)
+            .expect("Failed to apply config overrides.");
     }
-    if let Ok(etcd_addrs) = env::var(ETCD_ADDRS) {
-        config.set_etcd_addrs(etcd_addrs);
-    }
-    if let Ok(cluster) = env::var(CLUSTER_NAME) {
-        if let Some(ClusterDeployment::WithMeta(v)) = &mut config.cluster_deployment {
-            v.meta_client.cluster_name = cluster;
-        }
+    if let Some(data_dir) = matches.get_one::<String>("query-only") {
+        config.apply_query_only(data_dir);
     }
 
     println!("HoraeDB server tries starting with config:{config:?}");
@@ -114,7 +106,8 @@ fn main() {
     // Log version.
     info!("version:{}", version);
 
-    setup::run_server(config, runtime_level);
+    let repair = matches.get_flag("repair");
+    setup::run_server(config, config_path, runtime_level, repair);
 }
 
 #[cfg(test)]
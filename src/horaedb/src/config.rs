@@ -17,12 +17,29 @@
 
 // Config for horaedb server.
 
+use std::env;
+
+use alloc_tracker::budget::MemoryBudget;
 use cluster::config::ClusterConfig;
+use object_store::config::{LocalOptions, ObjectStoreOptions};
 use proxy::limiter::LimiterConfig;
 use serde::{Deserialize, Serialize};
 use server::config::{ServerConfig, StaticRouteConfig};
 use size_ext::ReadableSize;
 
+use crate::systemd::SystemdConfig;
+
+/// By this environment variable, the address of current node can be overridden.
+/// And it could be domain name or ip address, but no port follows it.
+const HORAEDB_SERVER_ADDR: &str = "HORAEDB_SERVER_ADDR";
+/// By this environment variable, the address of horaemeta can be overridden.
+const HORAEMETA_SERVER_ADDR: &str = "HORAEMETA_SERVER_ADDR";
+/// By this environment variable, the etcd addresses can be overridden.
+const ETCD_ADDRS: &str = "ETCD_ADDRS";
+/// By this environment variable, the cluster name of current node can be
+/// overridden.
+const CLUSTER_NAME: &str = "CLUSTER_NAME";
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 pub struct NodeInfo {
@@ -74,9 +91,122 @@ pub struct Config {
 
     /// Config of limiter
     pub limiter: LimiterConfig,
+
+    /// Config of the integration with systemd.
+    pub systemd: SystemdConfig,
+
+    /// Config of the event log of the table engines.
+    pub event_log: table_engine::event::Config,
+
+    /// Config of the background tasks of the table engines.
+    pub tasks: table_engine::task::Config,
+
+    /// Config of the global allocator.
+    pub allocator: profile::alloc::Config,
+
+    /// Limits of the memory shared by the components of the process.
+    pub memory: MemoryConfig,
+}
+
+/// Limits of the memory shared by the components of the process, zero means
+/// no limit.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    /// Limit of the memory of all the memtables, the largest memtables are
+    /// flushed once it's exceeded.
+    pub write_buffer_limit: ReadableSize,
+    /// Limit of the memory of all the running queries, the queries spill or
+    /// abort once it's exceeded.
+    pub query_limit: ReadableSize,
+    /// Limit of the memory of the caches of the objects, the least recently
+    /// used objects are evicted once it's exceeded.
+    pub cache_limit: ReadableSize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            write_buffer_limit: ReadableSize(0),
+            query_limit: ReadableSize(0),
+            cache_limit: ReadableSize(0),
+        }
+    }
+}
+
+impl MemoryConfig {
+    pub fn build_budget(&self) -> MemoryBudget {
+        MemoryBudget::new(
+            self.write_buffer_limit.as_byte() as usize,
+            self.query_limit.as_byte() as usize,
+            self.cache_limit.as_byte() as usize,
+        )
+    }
 }
 
 impl Config {
+    /// Override the config by the environment variables.
+    pub fn override_by_env(&mut self) {
+        if let Ok(node_addr) = env::var(HORAEDB_SERVER_ADDR) {
+            self.node.addr = node_addr;
+        }
+        if let Ok(meta_addr) = env::var(HORAEMETA_SERVER_ADDR) {
+            self.set_meta_addr(meta_addr);
+        }
+        if let Ok(etcd_addrs) = env::var(ETCD_ADDRS) {
+            self.set_etcd_addrs(etcd_addrs);
+        }
+        if let Ok(cluster) = env::var(CLUSTER_NAME) {
+            if let Some(ClusterDeployment::WithMeta(v)) = &mut self.cluster_deployment {
+                v.meta_client.cluster_name = cluster;
+            }
+        }
+    }
+
+    /// Serve the queries only over the data directory `data_dir`, e.g. a
+    /// restored backup, in a single process:
+    /// - The ssts and the manifests are read from `data_dir`, which is opened
+    ///   read-only so it's never modified. The data wal is disabled, so the
+    ///   data not flushed before the backup is invisible.
+    /// - The writes and the DDLs are rejected, and the ssts are never
+    ///   compacted or rewritten.
+    /// - Only the sql services are served on the localhost.
+    pub fn apply_query_only(&mut self, data_dir: &str) {
+        self.cluster_deployment = None;
+
+        let storage = &mut self.analytic.storage;
+        storage.object_store = ObjectStoreOptions::Local(LocalOptions {
+            data_dir: data_dir.to_string(),
+            read_only: true,
+        });
+        storage.cold_object_store = None;
+        #[cfg(feature = "wal-rocksdb")]
+        {
+            use wal::config::{RocksDBStorageConfig, StorageConfig};
+
+            self.analytic.wal.storage = StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
+                data_dir: data_dir.to_string(),
+                read_only: true,
+                ..Default::default()
+            }));
+        }
+        self.analytic.wal.disable_data = true;
+        self.analytic.format.background_rewrite = false;
+        self.analytic.recovery.repair = false;
+        self.analytic.shutdown.skip_replay_after_clean_shutdown = false;
+
+        let server = &mut self.server;
+        server.query_only = true;
+        server.bind_addr = "127.0.0.1".to_string();
+        server.enable_grpc = false;
+        server.mqtt.enable = false;
+        server.statsd.enable = false;
+        server.graphite.enable = false;
+        server.source.enable = false;
+        server.continuous_query.enable = false;
+        server.replica_check.enable = false;
+    }
+
     pub fn set_meta_addr(&mut self, meta_addr: String) {
         if let Some(ClusterDeployment::WithMeta(v)) = &mut self.cluster_deployment {
             v.meta_client.meta_addr = meta_addr;
@@ -130,19 +260,80 @@ pub struct RuntimeConfig {
     pub default_thread_num: usize,
     /// Runtime for io
     pub io_thread_num: usize,
+    /// The cpus to pin the threads of the runtimes to, no pinning by default
+    pub cpu_affinity: RuntimeCpuAffinity,
+}
+
+/// The cpu set of every runtime, which is a cpu list like `0-7,16`, or
+/// `numa:N` to use the cpus of the NUMA node N.
+///
+/// On large multi-socket nodes, pinning the runtimes to NUMA nodes avoids
+/// remote memory accesses, and the write runtime (which also syncs the WAL)
+/// is better to be kept on the node the disk attaches to.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RuntimeCpuAffinity {
+    pub read: Option<String>,
+    pub low_read: Option<String>,
+    pub write: Option<String>,
+    pub meta: Option<String>,
+    pub compact: Option<String>,
+    pub default: Option<String>,
+    pub io: Option<String>,
 }
 
 impl Default for RuntimeConfig {
+    /// The thread numbers are bounded by the cpus available to the process,
+    /// which may be limited by the cgroup.
     fn default() -> Self {
+        let num_cpus = system_stats::available_cpus();
+        let bounded = |max: usize| max.min(num_cpus);
+        let half_bounded = |max: usize| max.min(num_cpus / 2).max(1);
+
         Self {
-            read_thread_num: 8,
+            read_thread_num: bounded(8),
             read_thread_stack_size: ReadableSize::mb(16),
             low_read_thread_num: 1,
-            write_thread_num: 8,
-            meta_thread_num: 2,
-            compact_thread_num: 4,
-            default_thread_num: 8,
-            io_thread_num: 4,
+            write_thread_num: bounded(8),
+            meta_thread_num: bounded(2),
+            compact_thread_num: half_bounded(4),
+            default_thread_num: bounded(8),
+            io_thread_num: half_bounded(4),
+            cpu_affinity: RuntimeCpuAffinity::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_query_only() {
+        let mut config = Config::default();
+        config.apply_query_only("/data/backup");
+
+        assert!(config.cluster_deployment.is_none());
+        assert!(config.server.query_only);
+        match &config.analytic.storage.object_store {
+            ObjectStoreOptions::Local(v) => {
+                assert_eq!("/data/backup", v.data_dir);
+                assert!(v.read_only);
+            }
+            v => panic!("unexpected object store:{v:?}"),
+        }
+        assert!(config.analytic.storage.cold_object_store.is_none());
+        assert!(config.analytic.wal.disable_data);
+        assert!(!config.analytic.format.background_rewrite);
+        assert!(!config.analytic.recovery.repair);
+
+        #[cfg(feature = "wal-rocksdb")]
+        match &config.analytic.wal.storage {
+            wal::config::StorageConfig::RocksDB(v) => {
+                assert_eq!("/data/backup", v.data_dir);
+                assert!(v.read_only);
+            }
+            v => panic!("unexpected wal:{v:?}"),
         }
     }
 }
@@ -0,0 +1,353 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reloading of the config file.
+//!
+//! Only the options which can be changed without restarting are applied:
+//! - `logger.level`
+//! - `limiter`
+//! - `server.admission`
+//! - `server.wildcard_limit`
+//! - `server.time_range_guard`
+//! - `server.masking_policies` and `server.row_policies`
+//! - `analytic.db_write_buffer_size`, `analytic.compaction.max_ongoing_tasks`
+//!   and `analytic.sst_meta_cache_cap` (the cache can only be resized)
+//!
+//! All the other options are bound to the components built on starting, e.g.
+//! the listeners, the runtimes, the storage and the wal, so they require
+//! restarting to change, and the reloading is rejected with the changed
+//! static options listed if any of them is changed.
+//!
+//! The options set by the `ALTER SYSTEM` statements are persisted in the
+//! override file beside the config file, and they override the config file on
+//! starting and reloading.
+
+use std::{collections::BTreeMap, fs, sync::Arc};
+
+use analytic_engine::dynamic_config::DynamicConfig;
+use logger::{info, RuntimeLevel};
+use server::{config_reload::ReloadResult, server::Server};
+
+use crate::config::Config;
+
+/// Options set by the `ALTER SYSTEM` statements, keyed by the paths of the
+/// options, e.g. `analytic.sst_meta_cache_cap`.
+type Overrides = BTreeMap<String, toml::Value>;
+
+/// Reloader of the config file.
+///
+/// The override file is read whenever the config is reloaded or an option is
+/// set, rather than cached, so a broken override file fails the reloading
+/// instead of the starting of the reloader.
+pub struct ConfigReloader {
+    /// Path of the config file, the config can't be reloaded if it's None.
+    path: Option<String>,
+    /// The config applied currently.
+    config: Config,
+    log_runtime: Arc<RuntimeLevel>,
+}
+
+impl ConfigReloader {
+    /// The `config` should be applied the override file of the `path` already.
+    pub fn new(path: Option<String>, config: Config, log_runtime: Arc<RuntimeLevel>) -> Self {
+        Self {
+            path,
+            config,
+            log_runtime,
+        }
+    }
+
+    /// Reload the config file and apply it to the `server`, the changed options
+    /// are returned.
+    pub fn reload(&mut self, server: &Server) -> ReloadResult {
+        let path = self
+            .path
+            .clone()
+            .ok_or_else(|| "The server is started without a config file".to_string())?;
+        let mut toml_buf = String::new();
+        let mut config: Config = toml_ext::parse_toml_from_path(&path, &mut toml_buf)
+            .map_err(|e| format!("Failed to parse config, path:{path}, err:{e}"))?;
+        config.override_by_env();
+        let config = apply_override_file(config, &path)?;
+
+        let changed = self.apply(config, server)?;
+        info!("Config is reloaded, path:{path}, changed:{changed:?}");
+
+        Ok(changed)
+    }
+
+    /// Set the option of the `key` to the `value`, and persist it in the
+    /// override file, the changed options are returned.
+    ///
+    /// The `value` is parsed as the type of the current value of the option.
+    pub fn set_option(&mut self, key: &str, value: &str, server: &Server) -> ReloadResult {
+        let path = self
+            .path
+            .as_deref()
+            .ok_or_else(|| "The server is started without a config file".to_string())?;
+        let current = to_value(&self.config)?;
+        let current_value = lookup_value(&current, key)
+            .ok_or_else(|| format!("Option not found in config, key:{key}"))?;
+        let value = parse_value(current_value, value)
+            .ok_or_else(|| format!("Invalid value of option, key:{key}, value:{value}"))?;
+
+        let override_path = override_path(path);
+        let mut overrides = load_overrides(&override_path)?;
+        overrides.insert(key.to_string(), value);
+        let config = apply_overrides(self.config.clone(), &overrides)?;
+
+        // The option is persisted only if it's applied successfully, and it's
+        // rolled back if it fails to be persisted.
+        let old_config = self.config.clone();
+        let changed = self.apply(config, server)?;
+        if let Err(e) = save_overrides(&override_path, &overrides) {
+            return match self.apply(old_config, server) {
+                Ok(_) => Err(e),
+                Err(rollback_err) => Err(format!(
+                    "{e}, and failed to roll back the option, err:{rollback_err}"
+                )),
+            };
+        }
+        info!("Config option is set, key:{key}, changed:{changed:?}");
+
+        Ok(changed)
+    }
+
+    fn check_static_options(&self, config: &Config) -> Result<(), String> {
+        let static_changed = changed_options(
+            &clear_dynamic_options(self.config.clone()),
+            &clear_dynamic_options(config.clone()),
+        )?;
+        if !static_changed.is_empty() {
+            return Err(format!(
+                "Options require restarting to change, options:{static_changed:?}"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn apply(&mut self, config: Config, server: &Server) -> ReloadResult {
+        self.check_static_options(&config)?;
+        let changed = changed_options(&self.config, &config)?;
+
+        if config.logger.level != self.config.logger.level {
+            self.log_runtime.set_level_by_str(&config.logger.level)?;
+        }
+        server.apply_dynamic_config(&config.server, &config.limiter, &config.analytic);
+
+        self.config = config;
+        Ok(changed)
+    }
+}
+
+/// Path of the file persisting the options set by the `ALTER SYSTEM`
+/// statements.
+pub fn override_path(config_path: &str) -> String {
+    format!("{config_path}.override")
+}
+
+fn load_overrides(path: &str) -> Result<Overrides, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse config overrides, path:{path}, err:{e}")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Overrides::new()),
+        Err(e) => Err(format!(
+            "Failed to read config overrides, path:{path}, err:{e}"
+        )),
+    }
+}
+
+fn save_overrides(path: &str, overrides: &Overrides) -> Result<(), String> {
+    let content = toml::to_string(overrides).map_err(|e| format!("{e}"))?;
+    // Write to a temporary file first, so the override file is never partially
+    // written.
+    let tmp_path = format!("{path}.tmp");
+    fs::write(&tmp_path, content)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| format!("Failed to save config overrides, path:{path}, err:{e}"))
+}
+
+/// Apply the override file of the `config_path` to the `config`.
+pub fn apply_override_file(config: Config, config_path: &str) -> Result<Config, String> {
+    let overrides = load_overrides(&override_path(config_path))?;
+    apply_overrides(config, &overrides)
+}
+
+fn apply_overrides(config: Config, overrides: &Overrides) -> Result<Config, String> {
+    if overrides.is_empty() {
+        return Ok(config);
+    }
+
+    let mut value = to_value(&config)?;
+    for (key, override_value) in overrides {
+        let current = lookup_value_mut(&mut value, key)
+            .ok_or_else(|| format!("Overridden option not found in config, key:{key}"))?;
+        *current = override_value.clone();
+    }
+
+    value
+        .try_into()
+        .map_err(|e| format!("Failed to apply config overrides, err:{e}"))
+}
+
+fn to_value(config: &Config) -> Result<toml::Value, String> {
+    toml::Value::try_from(config).map_err(|e| format!("{e}"))
+}
+
+fn lookup_value<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, name| value.get(name))
+}
+
+fn lookup_value_mut<'a>(value: &'a mut toml::Value, key: &str) -> Option<&'a mut toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, name| value.get_mut(name))
+}
+
+/// Parse the `value` as the type of the `current` value, only the options of
+/// the primitive types can be set.
+fn parse_value(current: &toml::Value, value: &str) -> Option<toml::Value> {
+    match current {
+        toml::Value::String(_) => Some(toml::Value::String(value.to_string())),
+        toml::Value::Integer(_) => value.parse().ok().map(toml::Value::Integer),
+        toml::Value::Float(_) => value.parse().ok().map(toml::Value::Float),
+        toml::Value::Boolean(_) => value.parse().ok().map(toml::Value::Boolean),
+        toml::Value::Datetime(_) | toml::Value::Array(_) | toml::Value::Table(_) => None,
+    }
+}
+
+fn clear_dynamic_options(mut config: Config) -> Config {
+    config.logger.level = String::new();
+    config.limiter = Default::default();
+    config.server.admission = Default::default();
+    config.server.wildcard_limit = Default::default();
+    config.server.time_range_guard = Default::default();
+    config.server.masking_policies = Default::default();
+    config.server.row_policies = Default::default();
+    DynamicConfig::clear_dynamic_options(&mut config.analytic);
+
+    config
+}
+
+/// Returns the paths of the changed options, e.g. `analytic.storage`.
+fn changed_options(old: &Config, new: &Config) -> Result<Vec<String>, String> {
+    let mut changed = Vec::new();
+    diff_value("", &to_value(old)?, &to_value(new)?, &mut changed);
+
+    Ok(changed)
+}
+
+fn diff_value(path: &str, old: &toml::Value, new: &toml::Value, changed: &mut Vec<String>) {
+    match (old, new) {
+        (toml::Value::Table(old), toml::Value::Table(new)) => {
+            let mut keys: Vec<_> = old.keys().chain(new.keys()).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let sub_path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_value(&sub_path, old, new, changed),
+                    _ => changed.push(sub_path),
+                }
+            }
+        }
+        _ => {
+            if old != new {
+                changed.push(path.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_options() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert!(changed_options(&old, &new).unwrap().is_empty());
+
+        new.logger.level = "debug".to_string();
+        new.server.wildcard_limit.max_columns = 10;
+        assert_eq!(
+            vec!["logger.level", "server.wildcard_limit.max_columns"],
+            changed_options(&old, &new).unwrap()
+        );
+        assert!(changed_options(
+            &clear_dynamic_options(old.clone()),
+            &clear_dynamic_options(new.clone())
+        )
+        .unwrap()
+        .is_empty());
+
+        // All the options applied by the server are dynamic.
+        new.limiter.write_block_list = vec!["t1".to_string()];
+        new.server.admission.max_pending_tasks = 10;
+        new.server.time_range_guard.tables = vec!["t1".to_string()];
+        new.analytic.db_write_buffer_size = 1024;
+        assert!(changed_options(
+            &clear_dynamic_options(old.clone()),
+            &clear_dynamic_options(new.clone())
+        )
+        .unwrap()
+        .is_empty());
+
+        new.server.http_port += 1;
+        assert_eq!(
+            vec!["server.http_port"],
+            changed_options(
+                &clear_dynamic_options(old.clone()),
+                &clear_dynamic_options(new)
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let config = Config::default();
+        let value = to_value(&config).unwrap();
+
+        let level = parse_value(lookup_value(&value, "logger.level").unwrap(), "debug").unwrap();
+        let max_columns = lookup_value(&value, "server.wildcard_limit.max_columns").unwrap();
+        assert!(parse_value(max_columns, "abc").is_none());
+        let max_columns = parse_value(max_columns, "10").unwrap();
+
+        let overrides = Overrides::from([
+            ("logger.level".to_string(), level),
+            ("server.wildcard_limit.max_columns".to_string(), max_columns),
+        ]);
+        let new = apply_overrides(config.clone(), &overrides).unwrap();
+        assert_eq!("debug", new.logger.level);
+        assert_eq!(10, new.server.wildcard_limit.max_columns);
+        assert_eq!(
+            vec!["logger.level", "server.wildcard_limit.max_columns"],
+            changed_options(&config, &new).unwrap()
+        );
+
+        let overrides =
+            Overrides::from([("server.not_exist".to_string(), toml::Value::Integer(1))]);
+        assert!(apply_overrides(config, &overrides).is_err());
+    }
+}
@@ -16,5 +16,7 @@
 // under the License.
 
 pub mod config;
+pub mod config_reload;
 pub mod setup;
 mod signal_handler;
+pub mod systemd;
@@ -19,6 +19,7 @@
 
 use std::sync::Arc;
 
+use alloc_tracker::budget::MemoryBudgetRef;
 use analytic_engine::{
     self,
     setup::{EngineBuilder, TableEngineContext},
@@ -27,9 +28,12 @@ use catalog::{manager::ManagerRef, schema::OpenOptions, table_operator::TableOpe
 use catalog_impls::{table_based::TableBasedManager, volatile, CatalogManagerImpl};
 use cluster::{cluster_impl::ClusterImpl, config::ClusterConfig, shard_set::ShardSet};
 use datafusion::execution::runtime_env::RuntimeConfig as DfRuntimeConfig;
-use df_operator::registry::{FunctionRegistry, FunctionRegistryImpl};
+use df_operator::{
+    registry::{FunctionRegistry, FunctionRegistryImpl},
+    udfs::sample_rate,
+};
 use interpreters::table_manipulator::{catalog_based, meta_based};
-use logger::{info, RuntimeLevel};
+use logger::{error, info, warn, RuntimeLevel};
 use meta_client::{meta_impl, types::NodeMetaInfo};
 use proxy::{
     limiter::Limiter,
@@ -37,10 +41,12 @@ use proxy::{
         cluster_based::ClusterBasedProvider, config_based::ConfigBasedProvider,
     },
 };
+use query_engine::remote_udf::{self, RemoteUdfs};
 use router::{rule_based::ClusterView, ClusterBasedRouter, RuleBasedRouter};
-use runtime::PriorityRuntime;
+use runtime::{AbortOnDropMany, CpuSet, PriorityRuntime};
 use server::{
     config::{StaticRouteConfig, StaticTopologyConfig},
+    config_reload::{self, ReloadKind},
     local_tables::LocalTablesRecoverer,
     server::{Builder, DatafusionContext},
 };
@@ -48,19 +54,20 @@ use table_engine::{
     engine::{EngineRuntimes, TableEngineRef},
     memory::MemoryTableEngine,
     proxy::TableEngineProxy,
+    task::{self, TaskRegistry},
 };
-use tracing_util::{
-    self,
-    tracing_appender::{non_blocking::WorkerGuard, rolling::Rotation},
-};
+use tracing_util::{self, tracing_appender::rolling::Rotation, TracingGuard};
 use wal::{
     config::StorageConfig,
+    group_commit,
     manager::{WalRuntimes, WalsOpener},
 };
 
 use crate::{
     config::{ClusterDeployment, Config, RuntimeConfig},
-    signal_handler,
+    config_reload::ConfigReloader,
+    signal_handler::{self, ShutdownMode},
+    systemd::Notifier,
 };
 
 /// Setup log with given `config`, returns the runtime log level switch.
@@ -68,15 +75,17 @@ pub fn setup_logger(config: &Config) -> RuntimeLevel {
     logger::init_log(&config.logger).expect("Failed to init log.")
 }
 
-/// Setup tracing with given `config`, returns the writer guard.
-pub fn setup_tracing(config: &Config) -> WorkerGuard {
-    tracing_util::init_tracing_with_file(&config.tracing, &config.node.addr, Rotation::NEVER)
+/// Setup tracing with given `config`, returns the tracing guard.
+pub fn setup_tracing(config: &Config) -> TracingGuard {
+    proxy::metrics::set_exemplars_enabled(config.tracing.exemplars);
+    tracing_util::init_tracing(&config.tracing, &config.node.addr, Rotation::NEVER)
 }
 
 fn build_runtime_with_stack_size(
     name: &str,
     threads_num: usize,
     stack_size: Option<usize>,
+    cpu_affinity: &Option<String>,
 ) -> runtime::Runtime {
     let mut builder = runtime::Builder::default();
 
@@ -84,6 +93,14 @@ fn build_runtime_with_stack_size(
         builder.stack_size(stack_size);
     }
 
+    if let Some(cpus) = cpu_affinity {
+        let cpu_set: CpuSet = cpus
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid cpu affinity of runtime {name}, err:{e}"));
+        info!("Runtime {name} is pinned to cpus:{:?}", cpu_set.cpus());
+        builder.cpu_affinity(cpu_set);
+    }
+
     builder
         .worker_threads(threads_num)
         .thread_name(name)
@@ -92,30 +109,58 @@ fn build_runtime_with_stack_size(
         .expect("Failed to create runtime")
 }
 
-fn build_runtime(name: &str, threads_num: usize) -> runtime::Runtime {
-    build_runtime_with_stack_size(name, threads_num, None)
+fn build_runtime(
+    name: &str,
+    threads_num: usize,
+    cpu_affinity: &Option<String>,
+) -> runtime::Runtime {
+    build_runtime_with_stack_size(name, threads_num, None, cpu_affinity)
 }
 
-fn build_engine_runtimes(config: &RuntimeConfig) -> EngineRuntimes {
+fn build_engine_runtimes(config: &RuntimeConfig, task_config: &task::Config) -> EngineRuntimes {
     let read_stack_size = config.read_thread_stack_size.as_byte() as usize;
+    let affinity = &config.cpu_affinity;
     EngineRuntimes {
         read_runtime: PriorityRuntime::new(
             Arc::new(build_runtime_with_stack_size(
                 "read-low",
                 config.low_read_thread_num,
                 Some(read_stack_size),
+                &affinity.low_read,
             )),
             Arc::new(build_runtime_with_stack_size(
                 "read-high",
                 config.read_thread_num,
                 Some(read_stack_size),
+                &affinity.read,
             )),
         ),
-        write_runtime: Arc::new(build_runtime("horaedb-write", config.write_thread_num)),
-        compact_runtime: Arc::new(build_runtime("horaedb-compact", config.compact_thread_num)),
-        meta_runtime: Arc::new(build_runtime("horaedb-meta", config.meta_thread_num)),
-        default_runtime: Arc::new(build_runtime("horaedb-default", config.default_thread_num)),
-        io_runtime: Arc::new(build_runtime("horaedb-io", config.io_thread_num)),
+        write_runtime: Arc::new(build_runtime(
+            "horaedb-write",
+            config.write_thread_num,
+            &affinity.write,
+        )),
+        compact_runtime: Arc::new(build_runtime(
+            "horaedb-compact",
+            config.compact_thread_num,
+            &affinity.compact,
+        )),
+        meta_runtime: Arc::new(build_runtime(
+            "horaedb-meta",
+            config.meta_thread_num,
+            &affinity.meta,
+        )),
+        default_runtime: Arc::new(build_runtime(
+            "horaedb-default",
+            config.default_thread_num,
+            &affinity.default,
+        )),
+        io_runtime: Arc::new(build_runtime(
+            "horaedb-io",
+            config.io_thread_num,
+            &affinity.io,
+        )),
+        task_registry: Arc::new(TaskRegistry::new(task_config)),
     }
 }
 
@@ -123,21 +168,55 @@ fn validate_config(config: &Config) {
     let is_data_wal_disabled = config.analytic.wal.disable_data;
     if is_data_wal_disabled {
         let is_cluster = config.cluster_deployment.is_some();
-        if !is_cluster {
-            panic!("Invalid config, we can only disable data wal in cluster deployments")
+        if !is_cluster && !config.server.query_only {
+            panic!(
+                "Invalid config, we can only disable data wal in cluster deployments or the \
+                 query-only mode"
+            )
         }
     }
 }
 
 /// Run a server, returns when the server is shutdown by user
-pub fn run_server(config: Config, log_runtime: RuntimeLevel) {
-    let runtimes = Arc::new(build_engine_runtimes(&config.runtime));
+///
+/// The config is reloaded from the `config_path` if it's set.
+/// Run the server, and the tables are repaired instead of refusing to open in
+/// the `repair` mode.
+pub fn run_server(
+    mut config: Config,
+    config_path: Option<String>,
+    log_runtime: RuntimeLevel,
+    repair: bool,
+) {
+    if repair {
+        warn!("Server starts in repair mode, the broken tables are repaired or skipped");
+        config.analytic.recovery.repair = true;
+    }
+    if config.server.query_only {
+        warn!("Server starts in query-only mode, the writes and the unflushed data are ignored");
+    }
+
+    let runtimes = Arc::new(build_engine_runtimes(&config.runtime, &config.tasks));
     let engine_runtimes = runtimes.clone();
     let log_runtime = Arc::new(log_runtime);
 
-    info!("Server starts up, config:{:#?}", config);
+    info!(
+        "Server starts up, cgroup_limits:{:?}, available_cpus:{}, available_memory:{}, config:{:#?}",
+        system_stats::cgroup_limits(),
+        system_stats::available_cpus(),
+        system_stats::available_memory(),
+        config
+    );
 
     validate_config(&config);
+    table_engine::event::init(&config.event_log);
+    if let Err(e) = profile::alloc::init(&config.allocator) {
+        warn!(
+            "Failed to init allocator {}, err:{}",
+            profile::alloc::allocator_name(),
+            e
+        );
+    }
 
     runtimes.default_runtime.block_on(async {
         match config.analytic.wal.storage {
@@ -147,6 +226,7 @@ pub fn run_server(config: Config, log_runtime: RuntimeLevel) {
                     use wal::rocksdb_impl::manager::RocksDBWalsOpener;
                     run_server_with_runtimes::<RocksDBWalsOpener>(
                         config,
+                        config_path,
                         engine_runtimes,
                         log_runtime,
                     )
@@ -164,6 +244,7 @@ pub fn run_server(config: Config, log_runtime: RuntimeLevel) {
                     use wal::table_kv_impl::wal::ObkvWalsOpener;
                     run_server_with_runtimes::<ObkvWalsOpener>(
                         config,
+                        config_path,
                         engine_runtimes,
                         log_runtime,
                     )
@@ -181,6 +262,7 @@ pub fn run_server(config: Config, log_runtime: RuntimeLevel) {
                     use wal::message_queue_impl::wal::KafkaWalsOpener;
                     run_server_with_runtimes::<KafkaWalsOpener>(
                         config,
+                        config_path,
                         engine_runtimes,
                         log_runtime,
                     )
@@ -197,6 +279,7 @@ pub fn run_server(config: Config, log_runtime: RuntimeLevel) {
 
 async fn run_server_with_runtimes<T>(
     config: Config,
+    config_path: Option<String>,
     engine_runtimes: Arc<EngineRuntimes>,
     log_runtime: Arc<RuntimeLevel>,
 ) where
@@ -207,17 +290,29 @@ async fn run_server_with_runtimes<T>(
     function_registry
         .load_functions()
         .expect("Failed to create function registry");
+    let remote_udfs = RemoteUdfs::try_new(&config.query_engine.remote_udfs)
+        .expect("Failed to init remote udfs");
+    remote_udf::register_stubs(&function_registry, &remote_udfs)
+        .expect("Failed to register remote udfs");
+    let sample_rates = config.server.ingest_sampling.tables.clone();
+    sample_rate::register_to_registry(&function_registry, sample_rates)
+        .expect("Failed to register sample rate udf");
     let function_registry = Arc::new(function_registry);
     let datafusion_context = DatafusionContext {
         function_registry: function_registry.clone().to_df_function_registry(),
         runtime_config: DfRuntimeConfig::default(),
+        remote_udfs: Arc::new(remote_udfs),
     };
 
     // Config limiter
     let limiter = Limiter::new(config.limiter.clone());
     let config_content = toml::to_string(&config).expect("Fail to serialize config");
 
+    let memory_budget = Arc::new(config.memory.build_budget());
+
+    let (reload_trigger, mut reload_rx) = config_reload::channel();
     let builder = Builder::new(config.server.clone())
+        .config_reload(reload_trigger)
         .node_addr(config.node.addr.clone())
         .config_content(config_content)
         .engine_runtimes(engine_runtimes.clone())
@@ -225,7 +320,8 @@ async fn run_server_with_runtimes<T>(
         .function_registry(function_registry)
         .limiter(limiter)
         .datafusion_context(datafusion_context)
-        .query_engine_config(config.query_engine.clone());
+        .query_engine_config(config.query_engine.clone())
+        .memory_budget(memory_budget.clone());
 
     let wal_builder = T::default();
     let builder = match &config.cluster_deployment {
@@ -236,11 +332,20 @@ async fn run_server_with_runtimes<T>(
                 builder,
                 engine_runtimes.clone(),
                 wal_builder,
+                memory_budget,
             )
             .await
         }
         Some(ClusterDeployment::NoMeta(v)) => {
-            build_without_meta(&config, v, builder, engine_runtimes.clone(), wal_builder).await
+            build_without_meta(
+                &config,
+                v,
+                builder,
+                engine_runtimes.clone(),
+                wal_builder,
+                memory_budget,
+            )
+            .await
         }
         Some(ClusterDeployment::WithMeta(cluster_config)) => {
             build_with_meta(
@@ -249,6 +354,7 @@ async fn run_server_with_runtimes<T>(
                 builder,
                 engine_runtimes.clone(),
                 wal_builder,
+                memory_budget,
             )
             .await
         }
@@ -258,11 +364,73 @@ async fn run_server_with_runtimes<T>(
     let mut server = builder.build().expect("Failed to create server");
     server.start().await.expect("Failed to start server");
 
-    // Wait for signal
-    signal_handler::wait_for_signal();
+    let notifier = Notifier::from_env(&config.systemd).map(Arc::new);
+    let _watchdog = notifier.as_ref().and_then(|notifier| {
+        notifier.ready();
+        start_watchdog(notifier.clone(), &engine_runtimes)
+    });
 
-    // Stop server
-    server.stop().await;
+    // Wait for signal, and reload the config on the way.
+    let mut config_reloader = ConfigReloader::new(config_path, config.clone(), log_runtime);
+    let mut reload_signal = signal_handler::ReloadSignal::register();
+    let shutdown = signal_handler::wait_for_signal();
+    tokio::pin!(shutdown);
+    let shutdown_mode = loop {
+        tokio::select! {
+            shutdown_mode = &mut shutdown => break shutdown_mode,
+            _ = reload_signal.recv() => {
+                if let Err(e) = config_reloader.reload(&server) {
+                    error!("Failed to reload config on signal, err:{e}");
+                }
+            }
+            Some(req) = reload_rx.recv() => {
+                let result = match &req.kind {
+                    ReloadKind::File => config_reloader.reload(&server),
+                    ReloadKind::SetOption { key, value } => {
+                        config_reloader.set_option(key, value, &server)
+                    }
+                };
+                if let Err(e) = &result {
+                    error!("Failed to reload config, kind:{:?}, err:{e}", req.kind);
+                }
+                let _ = req.result_tx.send(result);
+            }
+        }
+    };
+    if let Some(notifier) = &notifier {
+        notifier.stopping();
+    }
+    match shutdown_mode {
+        ShutdownMode::Graceful => {
+            // Another signal during the graceful shutdown stops the server immediately.
+            tokio::select! {
+                _ = server.stop() => {}
+                _ = signal_handler::wait_for_signal() => {
+                    warn!("Received signal again, stop server without waiting");
+                }
+            }
+        }
+        ShutdownMode::Fast => warn!("Fast shutdown, stop server without waiting"),
+    }
+}
+
+/// Ping the systemd watchdog periodically if the watchdog is enabled, and the
+/// pings stop once the default runtime is stuck.
+fn start_watchdog(
+    notifier: Arc<Notifier>,
+    runtimes: &EngineRuntimes,
+) -> Option<AbortOnDropMany<()>> {
+    let interval = notifier.watchdog_interval()?;
+    info!("Systemd watchdog is enabled, interval:{interval:?}");
+
+    let handle = runtimes.default_runtime.spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            notifier.watchdog();
+        }
+    });
+    Some(AbortOnDropMany(vec![handle]))
 }
 
 // Build proxy for all table engines.
@@ -292,6 +460,7 @@ async fn build_with_meta<T: WalsOpener>(
     builder: Builder,
     runtimes: Arc<EngineRuntimes>,
     wal_opener: T,
+    memory_budget: MemoryBudgetRef,
 ) -> Builder {
     // Build meta related modules.
     let node_meta_info = NodeMetaInfo {
@@ -326,18 +495,29 @@ async fn build_with_meta<T: WalsOpener>(
     let router = Arc::new(ClusterBasedRouter::new(
         cluster.clone(),
         config.server.route_cache.clone(),
+        config.server.route_rules.clone(),
     ));
 
-    let opened_wals = wal_opener
+    let mut opened_wals = wal_opener
         .open_wals(&config.analytic.wal, make_wal_runtime(runtimes.clone()))
         .await
         .expect("Failed to setup analytic engine");
+    opened_wals.data_wal = group_commit::maybe_group_commit(
+        opened_wals.data_wal,
+        &config.analytic.wal,
+        &runtimes.write_runtime,
+    );
     let engine_builder = EngineBuilder {
         config: &config.analytic,
         engine_runtimes: runtimes.clone(),
         opened_wals: opened_wals.clone(),
+        memory_budget,
     };
-    let TableEngineContext { table_engine, .. } = engine_builder
+    let TableEngineContext {
+        table_engine,
+        dynamic_config,
+        ..
+    } = engine_builder
         .build()
         .await
         .expect("Failed to setup analytic engine");
@@ -350,13 +530,17 @@ async fn build_with_meta<T: WalsOpener>(
     ));
 
     // Build catalog manager.
-    let catalog_manager = Arc::new(CatalogManagerImpl::new(meta_based_manager_ref));
+    let catalog_manager = Arc::new(CatalogManagerImpl::new(
+        meta_based_manager_ref,
+        runtimes.task_registry.clone(),
+    ));
 
     let table_manipulator = Arc::new(meta_based::TableManipulatorImpl::new(meta_client));
 
     let schema_config_provider = Arc::new(ClusterBasedProvider::new(cluster.clone()));
     builder
         .table_engine(engine_proxy)
+        .engine_dynamic_config(dynamic_config)
         .catalog_manager(catalog_manager)
         .table_manipulator(table_manipulator)
         .cluster(cluster)
@@ -371,18 +555,29 @@ async fn build_without_meta<T: WalsOpener>(
     builder: Builder,
     runtimes: Arc<EngineRuntimes>,
     wal_builder: T,
+    memory_budget: MemoryBudgetRef,
 ) -> Builder {
-    let opened_wals = wal_builder
+    let mut opened_wals = wal_builder
         .open_wals(&config.analytic.wal, make_wal_runtime(runtimes.clone()))
         .await
         .expect("Failed to setup analytic engine");
+    opened_wals.data_wal = group_commit::maybe_group_commit(
+        opened_wals.data_wal,
+        &config.analytic.wal,
+        &runtimes.write_runtime,
+    );
 
     let engine_builder = EngineBuilder {
         config: &config.analytic,
         engine_runtimes: runtimes.clone(),
         opened_wals: opened_wals.clone(),
+        memory_budget,
     };
-    let TableEngineContext { table_engine, .. } = engine_builder
+    let TableEngineContext {
+        table_engine,
+        dynamic_config,
+        ..
+    } = engine_builder
         .build()
         .await
         .expect("Failed to setup analytic engine");
@@ -400,7 +595,10 @@ async fn build_without_meta<T: WalsOpener>(
         .await
         .expect("Failed to fetch table infos for opening");
 
-    let catalog_manager = Arc::new(CatalogManagerImpl::new(Arc::new(table_based_manager)));
+    let catalog_manager = Arc::new(CatalogManagerImpl::new(
+        Arc::new(table_based_manager),
+        runtimes.task_registry.clone(),
+    ));
     let table_operator = TableOperator::new(catalog_manager.clone());
     let table_manipulator = Arc::new(catalog_based::TableManipulatorImpl::new(
         table_operator.clone(),
@@ -412,7 +610,12 @@ async fn build_without_meta<T: WalsOpener>(
     };
 
     // Create local tables recoverer.
-    let local_tables_recoverer = LocalTablesRecoverer::new(table_infos, table_operator, open_opts);
+    let local_tables_recoverer = LocalTablesRecoverer::new(
+        table_infos,
+        table_operator,
+        open_opts,
+        config.analytic.recovery.repair,
+    );
 
     // Create schema in default catalog.
     create_static_topology_schema(
@@ -433,6 +636,15 @@ async fn build_without_meta<T: WalsOpener>(
         config.server.default_schema_config.clone(),
     ));
 
+    // The ssts are never compacted in the query-only mode, and the compaction
+    // can't be resumed by the admin api without the dynamic config.
+    let builder = if config.server.query_only {
+        dynamic_config.set_compaction_paused(true);
+        builder
+    } else {
+        builder.engine_dynamic_config(dynamic_config)
+    };
+
     builder
         .table_engine(engine_proxy)
         .catalog_manager(catalog_manager)
@@ -17,28 +17,126 @@
 
 //! Signal handler
 //!
-//! Only works on unix like environments
+//! On unix like environments, SIGTERM and SIGINT trigger a graceful shutdown
+//! while SIGQUIT triggers a fast one. On windows, Ctrl-C and Ctrl-Break
+//! trigger a graceful shutdown while closing the console, logging off and
+//! shutting down the system trigger a fast one, because the process is killed
+//! after a few seconds in these cases.
+//!
+//! On unix like environments, SIGHUP triggers reloading the config file.
+
+pub use self::details::{wait_for_signal, ReloadSignal};
 
-pub use self::details::wait_for_signal;
+/// The way to stop the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Stop the services and flush the tables before exiting.
+    Graceful,
+    /// Exit as soon as possible, and the data not flushed is recovered from
+    /// the wal on the next startup.
+    Fast,
+}
 
 #[cfg(unix)]
 mod details {
     use logger::info;
-    use signal_hook::{consts::TERM_SIGNALS, iterator::Signals};
+    use tokio::signal::unix::{signal, Signal, SignalKind};
 
-    pub fn wait_for_signal() {
-        let mut sigs = Signals::new(TERM_SIGNALS).expect("Failed to register signal handlers");
+    use super::ShutdownMode;
 
-        for signal in &mut sigs {
-            if TERM_SIGNALS.contains(&signal) {
-                info!("Received signal {}, stopping server...", signal);
-                break;
+    /// Receiver of the signal to reload the config.
+    pub struct ReloadSignal(Signal);
+
+    impl ReloadSignal {
+        pub fn register() -> Self {
+            Self(signal(SignalKind::hangup()).expect("Failed to register SIGHUP handler"))
+        }
+
+        pub async fn recv(&mut self) {
+            if self.0.recv().await.is_none() {
+                std::future::pending::<()>().await;
             }
+            info!("Received signal SIGHUP, reloading config");
+        }
+    }
+
+    pub async fn wait_for_signal() -> ShutdownMode {
+        let mut term = signal(SignalKind::terminate()).expect("Failed to register SIGTERM handler");
+        let mut int = signal(SignalKind::interrupt()).expect("Failed to register SIGINT handler");
+        let mut quit = signal(SignalKind::quit()).expect("Failed to register SIGQUIT handler");
+
+        let (name, mode) = tokio::select! {
+            _ = term.recv() => ("SIGTERM", ShutdownMode::Graceful),
+            _ = int.recv() => ("SIGINT", ShutdownMode::Graceful),
+            _ = quit.recv() => ("SIGQUIT", ShutdownMode::Fast),
+        };
+        info!("Received signal {name}, stopping server, mode:{mode:?}");
+
+        mode
+    }
+}
+
+#[cfg(windows)]
+mod details {
+    use logger::info;
+    use tokio::signal::windows::{ctrl_break, ctrl_c, ctrl_close, ctrl_logoff, ctrl_shutdown};
+
+    use super::ShutdownMode;
+
+    /// No signal reloads the config on windows.
+    pub struct ReloadSignal;
+
+    impl ReloadSignal {
+        pub fn register() -> Self {
+            Self
+        }
+
+        pub async fn recv(&mut self) {
+            std::future::pending::<()>().await;
         }
     }
+
+    pub async fn wait_for_signal() -> ShutdownMode {
+        let mut c = ctrl_c().expect("Failed to register Ctrl-C handler");
+        let mut brk = ctrl_break().expect("Failed to register Ctrl-Break handler");
+        let mut close = ctrl_close().expect("Failed to register console close handler");
+        let mut logoff = ctrl_logoff().expect("Failed to register logoff handler");
+        let mut shutdown = ctrl_shutdown().expect("Failed to register shutdown handler");
+
+        let (name, mode) = tokio::select! {
+            _ = c.recv() => ("CTRL_C_EVENT", ShutdownMode::Graceful),
+            _ = brk.recv() => ("CTRL_BREAK_EVENT", ShutdownMode::Graceful),
+            _ = close.recv() => ("CTRL_CLOSE_EVENT", ShutdownMode::Fast),
+            _ = logoff.recv() => ("CTRL_LOGOFF_EVENT", ShutdownMode::Fast),
+            _ = shutdown.recv() => ("CTRL_SHUTDOWN_EVENT", ShutdownMode::Fast),
+        };
+        info!("Received console event {name}, stopping server, mode:{mode:?}");
+
+        mode
+    }
 }
 
-#[cfg(not(unix))]
+#[cfg(not(any(unix, windows)))]
 mod details {
-    pub fn wait_for_signal() {}
+    use super::ShutdownMode;
+
+    pub struct ReloadSignal;
+
+    impl ReloadSignal {
+        pub fn register() -> Self {
+            Self
+        }
+
+        pub async fn recv(&mut self) {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    pub async fn wait_for_signal() -> ShutdownMode {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to register Ctrl-C handler");
+
+        ShutdownMode::Graceful
+    }
 }
@@ -0,0 +1,136 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Integration with the systemd service manager
+//!
+//! Implements the `sd_notify` protocol: the state messages are sent to the
+//! datagram socket given by the `NOTIFY_SOCKET` environment variable, and the
+//! watchdog interval is given by `WATCHDOG_USEC`. All of these are no-ops if
+//! the server is not started by systemd (or not on linux).
+
+use std::{env, time::Duration};
+
+use logger::{info, warn};
+use serde::{Deserialize, Serialize};
+
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+const WATCHDOG_USEC: &str = "WATCHDOG_USEC";
+const WATCHDOG_PID: &str = "WATCHDOG_PID";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SystemdConfig {
+    /// Notify systemd of the server state, which should be enabled for the
+    /// units with `Type=notify`.
+    pub notify: bool,
+}
+
+/// Notifier sending state messages to systemd.
+pub struct Notifier {
+    socket_path: String,
+}
+
+impl Notifier {
+    /// Create the notifier if notify is enabled and the server is managed by
+    /// systemd.
+    pub fn from_env(config: &SystemdConfig) -> Option<Self> {
+        if !config.notify {
+            return None;
+        }
+
+        match env::var(NOTIFY_SOCKET) {
+            Ok(socket_path) if !socket_path.is_empty() => {
+                info!("Systemd notify is enabled, socket:{socket_path}");
+                Some(Self { socket_path })
+            }
+            _ => {
+                warn!("Systemd notify is enabled but {NOTIFY_SOCKET} is not set");
+                None
+            }
+        }
+    }
+
+    /// Tell systemd the server is ready to serve.
+    pub fn ready(&self) {
+        self.notify("READY=1");
+    }
+
+    /// Tell systemd the server is shutting down.
+    pub fn stopping(&self) {
+        self.notify("STOPPING=1");
+    }
+
+    /// Tell systemd the server is still alive.
+    pub fn watchdog(&self) {
+        self.notify("WATCHDOG=1");
+    }
+
+    /// The interval to ping the watchdog, which is half of the timeout
+    /// configured by `WatchdogSec` as suggested by systemd.
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        if let Ok(pid) = env::var(WATCHDOG_PID) {
+            if pid.parse::<u32>().ok() != Some(std::process::id()) {
+                return None;
+            }
+        }
+
+        let usec = env::var(WATCHDOG_USEC).ok()?.parse::<u64>().ok()?;
+        if usec == 0 {
+            return None;
+        }
+
+        Some(Duration::from_micros(usec / 2))
+    }
+
+    fn notify(&self, state: &str) {
+        if let Err(e) = details::send(&self.socket_path, state) {
+            warn!("Failed to notify systemd, state:{state}, err:{e}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod details {
+    use std::{
+        io,
+        os::{
+            linux::net::SocketAddrExt,
+            unix::net::{SocketAddr, UnixDatagram},
+        },
+    };
+
+    pub fn send(socket_path: &str, state: &str) -> io::Result<()> {
+        // A leading '@' means the socket is in the abstract namespace.
+        let addr = match socket_path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(socket_path)?,
+        };
+
+        let socket = UnixDatagram::unbound()?;
+        socket.send_to_addr(state.as_bytes(), &addr)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod details {
+    use std::io;
+
+    pub fn send(_socket_path: &str, _state: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
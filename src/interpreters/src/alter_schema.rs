@@ -0,0 +1,184 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for alter schema statements
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use catalog::manager::ManagerRef;
+use logger::info;
+use macros::define_result;
+use query_frontend::plan::AlterSchemaPlan;
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use table_engine::engine::TableEngineRef;
+
+use crate::{
+    context::Context,
+    interpreter::{AlterSchema, Interpreter, InterpreterPtr, Output, Result as InterpreterResult},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Catalog does not exist, catalog:{}.\nBacktrace\n:{}", name, backtrace))]
+    CatalogNotExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Schema does not exist, schema:{}.\nBacktrace\n:{}", name, backtrace))]
+    SchemaNotExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to fetch catalog, err:{}", source))]
+    FetchCatalog { source: catalog::manager::Error },
+
+    #[snafu(display("Failed to fetch schema, err:{}", source))]
+    FetchSchema { source: catalog::Error },
+
+    #[snafu(display("Invalid default table options, err:{}", source))]
+    InvalidDefaultTableOptions { source: table_engine::engine::Error },
+
+    #[snafu(display("Failed to set default table options, err:{}", source))]
+    SetDefaultTableOptions { source: catalog::schema::Error },
+}
+
+define_result!(Error);
+
+/// Alter schema interpreter
+pub struct AlterSchemaInterpreter {
+    ctx: Context,
+    plan: AlterSchemaPlan,
+    catalog_manager: ManagerRef,
+    table_engine: TableEngineRef,
+}
+
+impl AlterSchemaInterpreter {
+    pub fn create(
+        ctx: Context,
+        plan: AlterSchemaPlan,
+        catalog_manager: ManagerRef,
+        table_engine: TableEngineRef,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            ctx,
+            plan,
+            catalog_manager,
+            table_engine,
+        })
+    }
+
+    async fn execute_alter(self: Box<Self>) -> Result<Output> {
+        let catalog_name = self.ctx.default_catalog();
+        let schema_name = &self.plan.schema;
+        let schema = self
+            .catalog_manager
+            .catalog_by_name(catalog_name)
+            .context(FetchCatalog)?
+            .context(CatalogNotExists { name: catalog_name })?
+            .schema_by_name(schema_name)
+            .context(FetchSchema)?
+            .context(SchemaNotExists { name: schema_name })?;
+
+        let options = merge_default_table_options(
+            schema.default_table_options(),
+            self.plan.default_table_options,
+        );
+        // Reject the options which would fail the creation of every table.
+        self.table_engine
+            .validate_table_options(&options)
+            .await
+            .context(InvalidDefaultTableOptions)?;
+
+        info!("Alter default table options of schema, schema:{schema_name}, options:{options:?}");
+        schema
+            .set_default_table_options(options)
+            .await
+            .context(SetDefaultTableOptions)?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+/// Merge the modified options into the current default table options, the
+/// options with empty values are removed.
+fn merge_default_table_options(
+    mut current: HashMap<String, String>,
+    modified: HashMap<String, String>,
+) -> HashMap<String, String> {
+    for (key, value) in modified {
+        if value.is_empty() {
+            current.remove(&key);
+        } else {
+            current.insert(key, value);
+        }
+    }
+
+    current
+}
+
+/// Fill the options absent in the create table options by the default table
+/// options of the schema.
+pub(crate) fn inherit_default_table_options(
+    options: &mut HashMap<String, String>,
+    defaults: HashMap<String, String>,
+) {
+    for (key, value) in defaults {
+        options.entry(key).or_insert(value);
+    }
+}
+
+#[async_trait]
+impl Interpreter for AlterSchemaInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_alter().await.context(AlterSchema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_options(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_default_table_options() {
+        let current = to_options(&[("ttl", "7d"), ("compression", "ZSTD")]);
+        let merged = merge_default_table_options(
+            current,
+            to_options(&[("ttl", ""), ("compaction_strategy", "time_window")]),
+        );
+        assert_eq!(
+            to_options(&[
+                ("compression", "ZSTD"),
+                ("compaction_strategy", "time_window")
+            ]),
+            merged
+        );
+
+        let mut options = to_options(&[("compression", "SNAPPY")]);
+        inherit_default_table_options(&mut options, merged);
+        assert_eq!(
+            to_options(&[
+                ("compression", "SNAPPY"),
+                ("compaction_strategy", "time_window")
+            ]),
+            options
+        );
+    }
+}
@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for the alter system statement
+//!
+//! The options of the server config are changed by the [SystemConfigManager],
+//! which only accepts the options can be changed without restarting and
+//! persists the changes, so they survive the restarting. The interpreter only
+//! changes the options of the server executing it, and the statement is
+//! executed on the other servers of the cluster by the proxy.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::GenericError;
+use logger::info;
+use macros::define_result;
+use query_frontend::plan::AlterSystemPlan;
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::interpreter::{
+    AlterSystem, Interpreter, InterpreterPtr, Output, Result as InterpreterResult,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Alter system is not supported.\nBacktrace:\n{}", backtrace))]
+    AlterSystemNotSupported { backtrace: Backtrace },
+
+    #[snafu(display("Failed to set system option, key:{}, err:{}", key, source))]
+    SetOption { key: String, source: GenericError },
+}
+
+define_result!(Error);
+
+/// Manager of the server config.
+#[async_trait]
+pub trait SystemConfigManager: Send + Sync {
+    /// Set the option of the `key` to the `value` and persist it.
+    ///
+    /// Returns the changed options.
+    async fn set_option(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> std::result::Result<Vec<String>, GenericError>;
+}
+
+pub type SystemConfigManagerRef = Arc<dyn SystemConfigManager>;
+
+pub struct AlterSystemInterpreter {
+    plan: AlterSystemPlan,
+    system_config_manager: Option<SystemConfigManagerRef>,
+}
+
+impl AlterSystemInterpreter {
+    pub fn create(
+        plan: AlterSystemPlan,
+        system_config_manager: Option<SystemConfigManagerRef>,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            plan,
+            system_config_manager,
+        })
+    }
+
+    async fn execute_alter(self: Box<Self>) -> Result<Output> {
+        let manager = self
+            .system_config_manager
+            .context(AlterSystemNotSupported)?;
+        let AlterSystemPlan { key, value } = self.plan;
+        let changed = manager
+            .set_option(&key, &value)
+            .await
+            .context(SetOption { key: &key })?;
+        info!("System option is set, key:{key}, value:{value}, changed:{changed:?}");
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for AlterSystemInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_alter().await.context(AlterSystem)
+    }
+}
@@ -17,6 +17,13 @@
 
 //! Interpreter for insert statement
 
+use std::sync::Arc;
+
+use arrow::{
+    array::StringArray,
+    datatypes::{DataType, Field, Schema as ArrowSchema},
+    record_batch::RecordBatch,
+};
 use async_trait::async_trait;
 use common_types::{
     column_schema::{self, ColumnSchema},
@@ -25,9 +32,15 @@ use common_types::{
 use macros::define_result;
 use query_frontend::plan::{AlterTableOperation, AlterTablePlan};
 use snafu::{ensure, ResultExt, Snafu};
-use table_engine::table::AlterSchemaRequest;
+use table_engine::{
+    alter_diff::{self, AlterDiff},
+    table::AlterSchemaRequest,
+};
 
-use crate::interpreter::{self, AlterTable, Interpreter, InterpreterPtr, Output};
+use crate::{
+    interpreter::{self, AlterTable, Interpreter, InterpreterPtr, Output},
+    RecordBatchVec,
+};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -45,6 +58,23 @@ pub enum Error {
 
     #[snafu(display("Not allow to add a not null column, name:{}", name))]
     AddNotNull { name: String },
+
+    #[snafu(display("Column not found, name:{}", name))]
+    ColumnNotFound { name: String },
+
+    #[snafu(display("Column already exists, name:{}", name))]
+    ColumnExists { name: String },
+
+    #[snafu(display("Not allow to drop column, name:{}, reason:{}", name, reason))]
+    DropNotAllowed { name: String, reason: String },
+
+    #[snafu(display("Not allow to rename column, name:{}, reason:{}", name, reason))]
+    RenameNotAllowed { name: String, reason: String },
+
+    #[snafu(display("Failed to build dry run result, err:{}", source))]
+    BuildDryRunResult {
+        source: common_types::record_batch::Error,
+    },
 }
 
 define_result!(Error);
@@ -68,30 +98,94 @@ impl Interpreter for AlterTableInterpreter {
 
 impl AlterTableInterpreter {
     async fn execute_alter(self: Box<Self>) -> Result<Output> {
-        let AlterTablePlan { table, operations } = self.plan;
-
-        match operations {
-            AlterTableOperation::AddColumn(columns) => {
-                let current_schema = table.schema();
-                let new_schema = build_new_schema(&current_schema, columns)?;
-
-                let request = AlterSchemaRequest {
-                    schema: new_schema,
-                    pre_schema_version: current_schema.version(),
-                };
-
-                let num_rows = table.alter_schema(request).await.context(AlterSchema)?;
+        let AlterTablePlan {
+            table,
+            operations,
+            dry_run,
+        } = self.plan;
 
-                Ok(Output::AffectedRows(num_rows))
+        let current_schema = table.schema();
+        let new_schema = match operations {
+            AlterTableOperation::AddColumn(columns) => build_new_schema(&current_schema, columns)?,
+            AlterTableOperation::DropColumn(columns) => {
+                build_schema_without_columns(&current_schema, &columns)?
             }
+            AlterTableOperation::RenameColumn { from, to } => {
+                // The partition rule refers to the columns by name.
+                ensure!(
+                    table.partition_info().is_none(),
+                    RenameNotAllowed {
+                        name: from,
+                        reason: "columns of partitioned table can't be renamed",
+                    }
+                );
+                build_schema_with_renamed_column(&current_schema, &from, &to)?
+            }
+            AlterTableOperation::ModifyColumn {
+                name,
+                display_name,
+                unit,
+            } => build_schema_with_modified_column(&current_schema, &name, display_name, unit)?,
             AlterTableOperation::ModifySetting(options) => {
+                if dry_run {
+                    // The options are validated and sanitized by the table engine as the
+                    // alter does, so the reported values are the ones to be applied.
+                    let new_options = table
+                        .validate_alter_options(&options)
+                        .context(AlterOptions)?;
+                    let diffs = alter_diff::options_diffs(&table.options(), &new_options);
+                    return diffs_to_record_batch(&diffs).map(Output::Records);
+                }
+
                 let num_rows = table.alter_options(options).await.context(AlterOptions)?;
-                Ok(Output::AffectedRows(num_rows))
+                return Ok(Output::AffectedRows(num_rows));
             }
+        };
+
+        let request = AlterSchemaRequest {
+            schema: new_schema,
+            pre_schema_version: current_schema.version(),
+        };
+        if dry_run {
+            table.validate_alter_schema(&request).context(AlterSchema)?;
+            let diffs = alter_diff::schema_diffs(&current_schema, &request.schema);
+            return diffs_to_record_batch(&diffs).map(Output::Records);
         }
+
+        let num_rows = table.alter_schema(request).await.context(AlterSchema)?;
+
+        Ok(Output::AffectedRows(num_rows))
     }
 }
 
+/// Build the result of the dry run, one row for each changed item.
+fn diffs_to_record_batch(diffs: &[AlterDiff]) -> Result<RecordBatchVec> {
+    let schema = ArrowSchema::new(vec![
+        Field::new("item", DataType::Utf8, false),
+        Field::new("before", DataType::Utf8, true),
+        Field::new("after", DataType::Utf8, true),
+        Field::new("rewrite_estimate", DataType::Utf8, false),
+    ]);
+
+    let items: Vec<_> = diffs.iter().map(|diff| diff.item.as_str()).collect();
+    let befores: Vec<_> = diffs.iter().map(|diff| diff.before.as_deref()).collect();
+    let afters: Vec<_> = diffs.iter().map(|diff| diff.after.as_deref()).collect();
+    let rewrite_estimates: Vec<_> = diffs.iter().map(|diff| diff.rewrite_estimate()).collect();
+    let arrow_record_batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(StringArray::from(items)),
+            Arc::new(StringArray::from(befores)),
+            Arc::new(StringArray::from(afters)),
+            Arc::new(StringArray::from(rewrite_estimates)),
+        ],
+    )
+    .unwrap();
+
+    let record_batch = arrow_record_batch.try_into().context(BuildDryRunResult)?;
+    Ok(vec![record_batch])
+}
+
 fn build_new_schema(current_schema: &Schema, column_schemas: Vec<ColumnSchema>) -> Result<Schema> {
     let current_version = current_schema.version();
 
@@ -135,6 +229,148 @@ fn build_new_schema(current_schema: &Schema, column_schemas: Vec<ColumnSchema>)
     Ok(new_schema)
 }
 
+/// Build the schema without the dropped columns.
+///
+/// The ids of the remaining columns are kept, and the data of the dropped
+/// columns in the existing files is skipped by the readers as no column of
+/// the new schema has the same id.
+fn build_schema_without_columns(current_schema: &Schema, names: &[String]) -> Result<Schema> {
+    let mut dropped_indexes = Vec::with_capacity(names.len());
+    for name in names {
+        let idx = current_schema
+            .index_of(name)
+            .context(ColumnNotFound { name })?;
+        validate_drop_column(current_schema, idx)?;
+        dropped_indexes.push(idx);
+    }
+
+    // The primary key indexes are shifted by the dropped columns before them.
+    let primary_key_indexes = current_schema
+        .primary_key_indexes()
+        .iter()
+        .map(|idx| idx - dropped_indexes.iter().filter(|v| *v < idx).count())
+        .collect();
+    let mut builder = schema::Builder::with_capacity(current_schema.num_columns())
+        .primary_key_indexes(primary_key_indexes)
+        .version(current_schema.version() + 1);
+    for (idx, column) in current_schema.columns().iter().enumerate() {
+        if dropped_indexes.contains(&idx) {
+            continue;
+        }
+        builder = if current_schema.is_primary_key_index(&idx) {
+            builder.add_key_column(column.clone())
+        } else {
+            builder.add_normal_column(column.clone())
+        }
+        .context(AddColumnSchema)?;
+    }
+
+    builder.build().context(BuildSchema)
+}
+
+/// Build the schema with the column renamed, the id of the column is kept so
+/// the existing data is read as the renamed column.
+fn build_schema_with_renamed_column(
+    current_schema: &Schema,
+    from: &str,
+    to: &str,
+) -> Result<Schema> {
+    let from_idx = current_schema
+        .index_of(from)
+        .context(ColumnNotFound { name: from })?;
+    ensure!(
+        current_schema.index_of(to).is_none(),
+        ColumnExists { name: to }
+    );
+    // The name of the tsid column is used to find it.
+    ensure!(
+        current_schema.index_of_tsid() != Some(from_idx),
+        RenameNotAllowed {
+            name: from,
+            reason: "tsid column can't be renamed",
+        }
+    );
+
+    let mut builder = schema::Builder::with_capacity(current_schema.num_columns())
+        .primary_key_indexes(current_schema.primary_key_indexes().to_vec())
+        .version(current_schema.version() + 1);
+    for (idx, column) in current_schema.columns().iter().enumerate() {
+        let mut column = column.clone();
+        if idx == from_idx {
+            column.name = to.to_string();
+        }
+        builder = if current_schema.is_primary_key_index(&idx) {
+            builder.add_key_column(column)
+        } else {
+            builder.add_normal_column(column)
+        }
+        .context(AddColumnSchema)?;
+    }
+
+    builder.build().context(BuildSchema)
+}
+
+/// Build the schema with the annotations of the column changed, the ones of
+/// `None` are kept.
+fn build_schema_with_modified_column(
+    current_schema: &Schema,
+    name: &str,
+    display_name: Option<String>,
+    unit: Option<String>,
+) -> Result<Schema> {
+    let modified_idx = current_schema
+        .index_of(name)
+        .context(ColumnNotFound { name })?;
+
+    let mut builder = schema::Builder::with_capacity(current_schema.num_columns())
+        .primary_key_indexes(current_schema.primary_key_indexes().to_vec())
+        .version(current_schema.version() + 1);
+    for (idx, column) in current_schema.columns().iter().enumerate() {
+        let mut column = column.clone();
+        if idx == modified_idx {
+            if let Some(display_name) = &display_name {
+                column.display_name = display_name.clone();
+            }
+            if let Some(unit) = &unit {
+                column.unit = unit.clone();
+            }
+        }
+        builder = if current_schema.is_primary_key_index(&idx) {
+            builder.add_key_column(column)
+        } else {
+            builder.add_normal_column(column)
+        }
+        .context(AddColumnSchema)?;
+    }
+
+    builder.build().context(BuildSchema)
+}
+
+fn validate_drop_column(schema: &Schema, idx: usize) -> Result<()> {
+    let column = schema.column(idx);
+    let reason = if schema.is_primary_key_index(&idx) {
+        Some("primary key column can't be dropped")
+    } else if column.is_tag {
+        // The tags decide the series of the rows.
+        Some("tag column can't be dropped")
+    } else if schema.columns().iter().all(|v| v.id <= column.id) {
+        // The max column id is derived from the columns of the schema, the id of the
+        // dropped column would be reused by the next added column.
+        Some("the latest added column can't be dropped")
+    } else {
+        None
+    };
+
+    match reason {
+        Some(reason) => DropNotAllowed {
+            name: &column.name,
+            reason,
+        }
+        .fail(),
+        None => Ok(()),
+    }
+}
+
 fn validate_add_column(column_schema: &ColumnSchema) -> Result<()> {
     ensure!(
         column_schema.is_nullable,
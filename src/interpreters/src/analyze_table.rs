@@ -0,0 +1,177 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for analyze table statements
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use common_types::{projected_schema::ProjectedSchema, request_id::RequestId, time::TimeRange};
+use futures::TryStreamExt;
+use logger::info;
+use macros::define_result;
+use query_frontend::plan::{AnalyzeTableOperation, AnalyzeTablePlan};
+use snafu::{OptionExt, ResultExt, Snafu};
+use table_engine::{
+    histogram::{self, Histogram, HistogramBuilder},
+    predicate::PredicateBuilder,
+    table::{ReadOptions, ReadRequest, TableRef},
+};
+use trace_metric::MetricsCollector;
+
+use crate::interpreter::{self, AnalyzeTable, Interpreter, InterpreterPtr, Output};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Column not found in table, column:{}", column))]
+    ColumnNotFound { column: String },
+
+    #[snafu(display("Failed to project table schema, err:{}", source))]
+    ProjectSchema {
+        source: common_types::projected_schema::Error,
+    },
+
+    #[snafu(display("Failed to read table, err:{}", source))]
+    ReadTable { source: table_engine::table::Error },
+
+    #[snafu(display("Failed to read stream of table, err:{}", source))]
+    ReadStream { source: table_engine::stream::Error },
+
+    #[snafu(display("Failed to update histograms, err:{}", source))]
+    UpdateHistograms { source: table_engine::table::Error },
+}
+
+define_result!(Error);
+
+pub struct AnalyzeTableInterpreter {
+    plan: AnalyzeTablePlan,
+}
+
+impl AnalyzeTableInterpreter {
+    pub fn create(plan: AnalyzeTablePlan) -> InterpreterPtr {
+        Box::new(Self { plan })
+    }
+}
+
+#[async_trait]
+impl Interpreter for AnalyzeTableInterpreter {
+    async fn execute(self: Box<Self>) -> interpreter::Result<Output> {
+        self.execute_analyze().await.context(AnalyzeTable)
+    }
+}
+
+impl AnalyzeTableInterpreter {
+    async fn execute_analyze(self: Box<Self>) -> Result<Output> {
+        let AnalyzeTablePlan { table, operation } = self.plan;
+
+        let histograms = match operation {
+            AnalyzeTableOperation::Refresh => {
+                let columns = table
+                    .histograms()
+                    .iter()
+                    .map(|(column, histogram)| (column.clone(), histogram.num_buckets().max(1)))
+                    .collect();
+                build_histograms(&table, columns).await?
+            }
+            AnalyzeTableOperation::UpdateHistogram {
+                columns,
+                num_buckets,
+            } => {
+                let columns = columns
+                    .into_iter()
+                    .map(|column| (column, num_buckets))
+                    .collect();
+                build_histograms(&table, columns).await?
+            }
+            AnalyzeTableOperation::DropHistogram { columns } => {
+                columns.into_iter().map(|column| (column, None)).collect()
+            }
+        };
+
+        if !histograms.is_empty() {
+            info!(
+                "Update histograms of table, table:{}, columns:{:?}",
+                table.name(),
+                histograms.keys()
+            );
+            table
+                .update_histograms(histograms)
+                .await
+                .context(UpdateHistograms)?;
+        }
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+/// Scan the `columns` of the `table` to build their histograms, the number of
+/// the buckets of each column is given besides the column name.
+///
+/// Only the time ranges sampled by the table are scanned if the table is
+/// large.
+async fn build_histograms(
+    table: &TableRef,
+    columns: Vec<(String, usize)>,
+) -> Result<HashMap<String, Option<Histogram>>> {
+    if columns.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let schema = table.schema();
+    let projection = columns
+        .iter()
+        .map(|(column, _)| schema.index_of(column).context(ColumnNotFound { column }))
+        .collect::<Result<Vec<_>>>()?;
+    let projected_schema = ProjectedSchema::new(schema, Some(projection)).context(ProjectSchema)?;
+    let time_ranges = table
+        .sample_time_ranges(histogram::DEFAULT_MAX_SCAN_ROWS)
+        .unwrap_or_else(|| vec![TimeRange::min_to_max()]);
+
+    let mut builders: Vec<_> = columns
+        .iter()
+        .map(|_| HistogramBuilder::new(histogram::DEFAULT_MAX_SAMPLES))
+        .collect();
+    for time_range in time_ranges {
+        let request = ReadRequest {
+            request_id: RequestId::next_id(),
+            opts: ReadOptions::default(),
+            projected_schema: projected_schema.clone(),
+            predicate: PredicateBuilder::default()
+                .set_time_range(time_range)
+                .build(),
+            metrics_collector: MetricsCollector::default(),
+            priority: Default::default(),
+        };
+        let mut stream = table.read(request).await.context(ReadTable)?;
+        while let Some(batch) = stream.try_next().await.context(ReadStream)? {
+            for (idx, builder) in builders.iter_mut().enumerate() {
+                let column = batch.column(idx);
+                for row_idx in 0..batch.num_rows() {
+                    builder.push(column.datum(row_idx));
+                }
+            }
+        }
+    }
+
+    let histograms = columns
+        .into_iter()
+        .zip(builders)
+        .map(|((column, num_buckets), builder)| (column, Some(builder.build(num_buckets))))
+        .collect();
+
+    Ok(histograms)
+}
@@ -0,0 +1,63 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for compact table statements
+
+use async_trait::async_trait;
+use logger::info;
+use macros::define_result;
+use query_frontend::plan::CompactTablePlan;
+use snafu::{ResultExt, Snafu};
+
+use crate::interpreter::{
+    CompactTable, Interpreter, InterpreterPtr, Output, Result as InterpreterResult,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to compact table, err:{}", source))]
+    Compact { source: table_engine::table::Error },
+}
+
+define_result!(Error);
+
+/// Compact table interpreter, which waits until the manual major compaction
+/// is finished.
+pub struct CompactTableInterpreter {
+    plan: CompactTablePlan,
+}
+
+impl CompactTableInterpreter {
+    pub fn create(plan: CompactTablePlan) -> InterpreterPtr {
+        Box::new(Self { plan })
+    }
+
+    async fn execute_compact(self: Box<Self>) -> Result<Output> {
+        let table = self.plan.table;
+        info!("Compact table manually, table:{}", table.name());
+        table.compact().await.context(Compact)?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for CompactTableInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_compact().await.context(CompactTable)
+    }
+}
@@ -17,19 +17,50 @@
 
 //! Interpreter context
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use common_types::request_id::RequestId;
+use common_types::{record_batch::RecordBatch, request_id::RequestId, SequenceNumber};
 use macros::define_result;
-use query_engine::context::{Context as QueryContext, ContextRef as QueryContextRef};
+use query_engine::{
+    context::{Context as QueryContext, ContextRef as QueryContextRef},
+    stage::QueryStagesRef,
+};
 use runtime::Priority;
 use snafu::Snafu;
+use table_engine::{query_warning::QueryWarnings, scan_quota::ScanUsageRef};
+use tokio::sync::mpsc;
+
+use crate::result_limit::ResultLimit;
 
 #[derive(Debug, Snafu)]
 pub enum Error {}
 
 define_result!(Error);
 
+/// Sender of the query results streamed to the client as they are produced,
+/// the query is suspended while the channel is full.
+pub type ResultSender = mpsc::Sender<RecordBatch>;
+
+/// Collect the sequence the write of the insert is committed at, it's shared
+/// by the clones so the caller can get it after the interpreter is consumed.
+#[derive(Clone, Debug, Default)]
+pub struct WriteSequence(Arc<Mutex<Option<SequenceNumber>>>);
+
+impl WriteSequence {
+    pub fn set(&self, sequence: Option<SequenceNumber>) {
+        *self.0.lock().unwrap() = sequence;
+    }
+
+    /// The committed sequence, `None` if the write is not done or the table
+    /// doesn't track the sequences.
+    pub fn get(&self) -> Option<SequenceNumber> {
+        *self.0.lock().unwrap()
+    }
+}
+
 /// Interpreter context
 ///
 /// Contains information that all interpreters need
@@ -43,6 +74,21 @@ pub struct Context {
     /// If time range exceeds this threshold, the query will be marked as
     /// expensive
     expensive_query_threshold: u64,
+    /// Queries only reading the data older than this range are cold and
+    /// scheduled with the low priority, zero means no query is cold
+    hot_time_range: u64,
+    /// Collect the resources consumed by the table scans if it's set
+    scan_usage: Option<ScanUsageRef>,
+    /// Record the stages of the query if it's set
+    stages: Option<QueryStagesRef>,
+    /// Stream the query results instead of collecting them if it's set
+    result_sender: Option<ResultSender>,
+    /// Limits of the query results
+    result_limit: ResultLimit,
+    /// Collect the warnings of the query
+    warnings: QueryWarnings,
+    /// Collect the committed sequence of the insert
+    write_sequence: WriteSequence,
 }
 
 impl Context {
@@ -54,6 +100,13 @@ impl Context {
             default_schema: String::new(),
             enable_partition_table_access: false,
             expensive_query_threshold: 24 * 3600 * 1000, // default 24 hours
+            hot_time_range: 0,
+            scan_usage: None,
+            stages: None,
+            result_sender: None,
+            result_limit: ResultLimit::default(),
+            warnings: QueryWarnings::default(),
+            write_sequence: WriteSequence::default(),
         }
     }
 
@@ -65,6 +118,9 @@ impl Context {
             default_catalog: self.default_catalog.clone(),
             default_schema: self.default_schema.clone(),
             priority,
+            scan_usage: self.scan_usage.clone(),
+            stages: self.stages.clone(),
+            warnings: self.warnings.clone(),
         };
         Ok(Arc::new(ctx))
     }
@@ -93,6 +149,31 @@ impl Context {
     pub fn expensive_query_threshold(&self) -> u64 {
         self.expensive_query_threshold
     }
+
+    #[inline]
+    pub fn hot_time_range(&self) -> u64 {
+        self.hot_time_range
+    }
+
+    #[inline]
+    pub fn result_sender(&self) -> Option<ResultSender> {
+        self.result_sender.clone()
+    }
+
+    #[inline]
+    pub fn result_limit(&self) -> ResultLimit {
+        self.result_limit
+    }
+
+    #[inline]
+    pub fn warnings(&self) -> &QueryWarnings {
+        &self.warnings
+    }
+
+    #[inline]
+    pub fn write_sequence(&self) -> &WriteSequence {
+        &self.write_sequence
+    }
 }
 
 #[must_use]
@@ -103,6 +184,13 @@ pub struct Builder {
     default_schema: String,
     enable_partition_table_access: bool,
     expensive_query_threshold: u64,
+    hot_time_range: u64,
+    scan_usage: Option<ScanUsageRef>,
+    stages: Option<QueryStagesRef>,
+    result_sender: Option<ResultSender>,
+    result_limit: ResultLimit,
+    warnings: QueryWarnings,
+    write_sequence: WriteSequence,
 }
 
 impl Builder {
@@ -122,6 +210,41 @@ impl Builder {
         self
     }
 
+    pub fn hot_time_range(mut self, hot_time_range: u64) -> Self {
+        self.hot_time_range = hot_time_range;
+        self
+    }
+
+    pub fn scan_usage(mut self, scan_usage: Option<ScanUsageRef>) -> Self {
+        self.scan_usage = scan_usage;
+        self
+    }
+
+    pub fn stages(mut self, stages: Option<QueryStagesRef>) -> Self {
+        self.stages = stages;
+        self
+    }
+
+    pub fn result_sender(mut self, result_sender: Option<ResultSender>) -> Self {
+        self.result_sender = result_sender;
+        self
+    }
+
+    pub fn result_limit(mut self, result_limit: ResultLimit) -> Self {
+        self.result_limit = result_limit;
+        self
+    }
+
+    pub fn warnings(mut self, warnings: QueryWarnings) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    pub fn write_sequence(mut self, write_sequence: WriteSequence) -> Self {
+        self.write_sequence = write_sequence;
+        self
+    }
+
     pub fn build(self) -> Context {
         Context {
             request_id: self.request_id,
@@ -130,6 +253,13 @@ impl Builder {
             default_schema: self.default_schema,
             enable_partition_table_access: self.enable_partition_table_access,
             expensive_query_threshold: self.expensive_query_threshold,
+            hot_time_range: self.hot_time_range,
+            scan_usage: self.scan_usage,
+            stages: self.stages,
+            result_sender: self.result_sender,
+            result_limit: self.result_limit,
+            warnings: self.warnings,
+            write_sequence: self.write_sequence,
         }
     }
 }
@@ -0,0 +1,158 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreters for the continuous queries
+//!
+//! The continuous queries are managed by the [ContinuousQueryManager], which
+//! runs them periodically in background.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::GenericError;
+use macros::define_result;
+use query_frontend::plan::{
+    ContinuousQueryDef, CreateContinuousQueryPlan, DropContinuousQueryPlan,
+};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    context::Context,
+    interpreter::{
+        CreateContinuousQuery, DropContinuousQuery, Interpreter, InterpreterPtr, Output,
+        Result as InterpreterResult,
+    },
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Continuous query is not supported.\nBacktrace:\n{}", backtrace))]
+    ContinuousQueryNotSupported { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Continuous query already exists, name:{}.\nBacktrace:\n{}",
+        name,
+        backtrace
+    ))]
+    ContinuousQueryExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Continuous query not found, name:{}.\nBacktrace:\n{}",
+        name,
+        backtrace
+    ))]
+    ContinuousQueryNotFound { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to manage continuous query, name:{}, err:{}", name, source))]
+    ManageContinuousQuery { name: String, source: GenericError },
+}
+
+define_result!(Error);
+
+/// Manager of the continuous queries.
+#[async_trait]
+pub trait ContinuousQueryManager: Send + Sync {
+    fn contains(&self, name: &str) -> bool;
+
+    /// Persist the continuous query and start running it.
+    async fn create_continuous_query(
+        &self,
+        schema: &str,
+        continuous_query: ContinuousQueryDef,
+    ) -> std::result::Result<(), GenericError>;
+
+    /// Stop running the continuous query and remove it.
+    async fn drop_continuous_query(&self, name: &str) -> std::result::Result<(), GenericError>;
+}
+
+pub type ContinuousQueryManagerRef = Arc<dyn ContinuousQueryManager>;
+
+pub struct CreateContinuousQueryInterpreter {
+    ctx: Context,
+    plan: CreateContinuousQueryPlan,
+    manager: Option<ContinuousQueryManagerRef>,
+}
+
+impl CreateContinuousQueryInterpreter {
+    pub fn create(
+        ctx: Context,
+        plan: CreateContinuousQueryPlan,
+        manager: Option<ContinuousQueryManagerRef>,
+    ) -> InterpreterPtr {
+        Box::new(Self { ctx, plan, manager })
+    }
+
+    async fn execute_create(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(ContinuousQueryNotSupported)?;
+        let name = self.plan.continuous_query.name.clone();
+        if manager.contains(&name) {
+            ensure!(self.plan.if_not_exists, ContinuousQueryExists { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        manager
+            .create_continuous_query(self.ctx.default_schema(), self.plan.continuous_query)
+            .await
+            .context(ManageContinuousQuery { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for CreateContinuousQueryInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_create().await.context(CreateContinuousQuery)
+    }
+}
+
+pub struct DropContinuousQueryInterpreter {
+    plan: DropContinuousQueryPlan,
+    manager: Option<ContinuousQueryManagerRef>,
+}
+
+impl DropContinuousQueryInterpreter {
+    pub fn create(
+        plan: DropContinuousQueryPlan,
+        manager: Option<ContinuousQueryManagerRef>,
+    ) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_drop(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(ContinuousQueryNotSupported)?;
+        let name = self.plan.name;
+        if !manager.contains(&name) {
+            ensure!(self.plan.if_exists, ContinuousQueryNotFound { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        manager
+            .drop_continuous_query(&name)
+            .await
+            .context(ManageContinuousQuery { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for DropContinuousQueryInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_drop().await.context(DropContinuousQuery)
+    }
+}
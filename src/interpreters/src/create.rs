@@ -18,12 +18,14 @@
 //! Interpreter for create statements
 
 use async_trait::async_trait;
+use catalog::manager::ManagerRef;
 use macros::define_result;
 use query_frontend::plan::CreateTablePlan;
 use snafu::{ResultExt, Snafu};
 use table_engine::engine::TableEngineRef;
 
 use crate::{
+    alter_schema::inherit_default_table_options,
     context::Context,
     interpreter::{Create, Interpreter, InterpreterPtr, Output, Result as InterpreterResult},
     table_manipulator::{self, TableManipulatorRef},
@@ -34,6 +36,12 @@ use crate::{
 pub enum Error {
     #[snafu(display("Failed to create table by table manipulator, err:{}", source))]
     ManipulateTable { source: table_manipulator::Error },
+
+    #[snafu(display("Failed to fetch catalog, err:{}", source))]
+    FetchCatalog { source: catalog::manager::Error },
+
+    #[snafu(display("Failed to fetch schema, err:{}", source))]
+    FetchSchema { source: catalog::Error },
 }
 
 define_result!(Error);
@@ -42,6 +50,7 @@ define_result!(Error);
 pub struct CreateInterpreter {
     ctx: Context,
     plan: CreateTablePlan,
+    catalog_manager: ManagerRef,
     table_engine: TableEngineRef,
     table_manipulator: TableManipulatorRef,
 }
@@ -50,12 +59,14 @@ impl CreateInterpreter {
     pub fn create(
         ctx: Context,
         plan: CreateTablePlan,
+        catalog_manager: ManagerRef,
         table_engine: TableEngineRef,
         table_manipulator: TableManipulatorRef,
     ) -> InterpreterPtr {
         Box::new(Self {
             ctx,
             plan,
+            catalog_manager,
             table_engine,
             table_manipulator,
         })
@@ -63,12 +74,35 @@ impl CreateInterpreter {
 }
 
 impl CreateInterpreter {
-    async fn execute_create(self: Box<Self>) -> Result<Output> {
+    async fn execute_create(mut self: Box<Self>) -> Result<Output> {
+        self.inherit_schema_options()?;
+
         self.table_manipulator
             .create_table(self.ctx, self.plan, self.table_engine)
             .await
             .context(ManipulateTable)
     }
+
+    /// The options absent in the plan are inherited from the default table
+    /// options of the schema.
+    fn inherit_schema_options(&mut self) -> Result<()> {
+        let catalog = self
+            .catalog_manager
+            .catalog_by_name(self.ctx.default_catalog())
+            .context(FetchCatalog)?;
+        let schema = match catalog {
+            Some(catalog) => catalog
+                .schema_by_name(self.ctx.default_schema())
+                .context(FetchSchema)?,
+            // The missing catalog or schema is left to the table manipulator to report.
+            None => None,
+        };
+        if let Some(schema) = schema {
+            inherit_default_table_options(&mut self.plan.options, schema.default_table_options());
+        }
+
+        Ok(())
+    }
 }
 
 // TODO(yingwen): Wrap a method that returns self::Result, simplify some code to
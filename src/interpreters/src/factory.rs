@@ -24,17 +24,36 @@ use runtime::PriorityRuntime;
 use table_engine::engine::TableEngineRef;
 
 use crate::{
+    alter_schema::AlterSchemaInterpreter,
+    alter_system::{AlterSystemInterpreter, SystemConfigManagerRef},
     alter_table::AlterTableInterpreter,
+    analyze_table::AnalyzeTableInterpreter,
+    compact_table::CompactTableInterpreter,
     context::Context,
+    continuous_query::{
+        ContinuousQueryManagerRef, CreateContinuousQueryInterpreter,
+        DropContinuousQueryInterpreter,
+    },
     create::CreateInterpreter,
     describe::DescribeInterpreter,
     drop::DropInterpreter,
     exists::ExistsInterpreter,
+    function::{CreateFunctionInterpreter, DropFunctionInterpreter, WasmUdfManagerRef},
     insert::InsertInterpreter,
     interpreter::{InterpreterPtr, Result},
+    kill::KillQueryInterpreter,
+    offload::ResultOffloaderRef,
+    query_tracker::QueryTrackerRef,
+    resync::{ResyncRequestsRef, ResyncTableInterpreter},
     select::SelectInterpreter,
     show::ShowInterpreter,
+    source::{CreateSourceInterpreter, DropSourceInterpreter, SourceManagerRef},
+    swap_tables::SwapTablesInterpreter,
     table_manipulator::TableManipulatorRef,
+    user::{
+        CreateUserInterpreter, DropUserInterpreter, GrantInterpreter, RevokeInterpreter,
+        UserManagerRef,
+    },
     validator::{ValidateContext, Validator},
 };
 
@@ -46,9 +65,18 @@ pub struct Factory {
     catalog_manager: ManagerRef,
     table_engine: TableEngineRef,
     table_manipulator: TableManipulatorRef,
+    result_offloader: Option<ResultOffloaderRef>,
+    query_tracker: QueryTrackerRef,
+    resync_requests: ResyncRequestsRef,
+    source_manager: Option<SourceManagerRef>,
+    system_config_manager: Option<SystemConfigManagerRef>,
+    continuous_query_manager: Option<ContinuousQueryManagerRef>,
+    user_manager: Option<UserManagerRef>,
+    wasm_udf_manager: Option<WasmUdfManagerRef>,
 }
 
 impl Factory {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         query_executor: ExecutorRef,
         physical_planner: PhysicalPlannerRef,
@@ -56,6 +84,14 @@ impl Factory {
         table_engine: TableEngineRef,
         table_manipulator: TableManipulatorRef,
         query_runtime: PriorityRuntime,
+        result_offloader: Option<ResultOffloaderRef>,
+        query_tracker: QueryTrackerRef,
+        resync_requests: ResyncRequestsRef,
+        source_manager: Option<SourceManagerRef>,
+        system_config_manager: Option<SystemConfigManagerRef>,
+        continuous_query_manager: Option<ContinuousQueryManagerRef>,
+        user_manager: Option<UserManagerRef>,
+        wasm_udf_manager: Option<WasmUdfManagerRef>,
     ) -> Self {
         Self {
             query_executor,
@@ -64,6 +100,14 @@ impl Factory {
             catalog_manager,
             table_engine,
             table_manipulator,
+            result_offloader,
+            query_tracker,
+            resync_requests,
+            source_manager,
+            system_config_manager,
+            continuous_query_manager,
+            user_manager,
+            wasm_udf_manager,
         }
     }
 
@@ -81,18 +125,52 @@ impl Factory {
                 self.query_executor,
                 self.physical_planner,
                 self.query_runtime,
+                self.result_offloader,
             ),
             Plan::Insert(p) => InsertInterpreter::create(ctx, p),
-            Plan::Create(p) => {
-                CreateInterpreter::create(ctx, p, self.table_engine, self.table_manipulator)
-            }
+            Plan::Create(p) => CreateInterpreter::create(
+                ctx,
+                p,
+                self.catalog_manager,
+                self.table_engine,
+                self.table_manipulator,
+            ),
             Plan::Drop(p) => {
                 DropInterpreter::create(ctx, p, self.table_engine, self.table_manipulator)
             }
             Plan::Describe(p) => DescribeInterpreter::create(p),
             Plan::AlterTable(p) => AlterTableInterpreter::create(p),
-            Plan::Show(p) => ShowInterpreter::create(ctx, p, self.catalog_manager),
+            Plan::AlterSchema(p) => {
+                AlterSchemaInterpreter::create(ctx, p, self.catalog_manager, self.table_engine)
+            }
+            Plan::Show(p) => ShowInterpreter::create(
+                ctx,
+                p,
+                self.catalog_manager,
+                self.query_tracker,
+                self.source_manager,
+            ),
             Plan::Exists(p) => ExistsInterpreter::create(p),
+            Plan::KillQuery(p) => KillQueryInterpreter::create(p, self.query_tracker),
+            Plan::CreateSource(p) => CreateSourceInterpreter::create(ctx, p, self.source_manager),
+            Plan::DropSource(p) => DropSourceInterpreter::create(p, self.source_manager),
+            Plan::AlterSystem(p) => AlterSystemInterpreter::create(p, self.system_config_manager),
+            Plan::AnalyzeTable(p) => AnalyzeTableInterpreter::create(p),
+            Plan::CompactTable(p) => CompactTableInterpreter::create(p),
+            Plan::ResyncTable(p) => ResyncTableInterpreter::create(p, self.resync_requests),
+            Plan::CreateContinuousQuery(p) => {
+                CreateContinuousQueryInterpreter::create(ctx, p, self.continuous_query_manager)
+            }
+            Plan::DropContinuousQuery(p) => {
+                DropContinuousQueryInterpreter::create(p, self.continuous_query_manager)
+            }
+            Plan::SwapTables(p) => SwapTablesInterpreter::create(ctx, p, self.catalog_manager),
+            Plan::CreateUser(p) => CreateUserInterpreter::create(p, self.user_manager),
+            Plan::DropUser(p) => DropUserInterpreter::create(p, self.user_manager),
+            Plan::Grant(p) => GrantInterpreter::create(p, self.user_manager),
+            Plan::Revoke(p) => RevokeInterpreter::create(p, self.user_manager),
+            Plan::CreateFunction(p) => CreateFunctionInterpreter::create(p, self.wasm_udf_manager),
+            Plan::DropFunction(p) => DropFunctionInterpreter::create(p, self.wasm_udf_manager),
         };
 
         Ok(interpreter)
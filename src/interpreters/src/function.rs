@@ -0,0 +1,123 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Interpreters for the udfs loaded from the wasm modules
+//!
+//! The functions are managed by the [WasmUdfManager], which is only available
+//! when the udf directory is configured.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use df_operator::wasm::{self, WasmUdfManager};
+use macros::define_result;
+use query_frontend::plan::{CreateFunctionPlan, DropFunctionPlan};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::interpreter::{
+    CreateFunction, DropFunction, Interpreter, InterpreterPtr, Output, Result as InterpreterResult,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Wasm udf is not supported, the udf dir is not configured.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    FunctionNotSupported { backtrace: Backtrace },
+
+    #[snafu(display("Function already exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    FunctionExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Function not found, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    FunctionNotFound { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to manage function, name:{}, err:{}", name, source))]
+    ManageFunction { name: String, source: wasm::Error },
+}
+
+define_result!(Error);
+
+pub type WasmUdfManagerRef = Arc<WasmUdfManager>;
+
+pub struct CreateFunctionInterpreter {
+    plan: CreateFunctionPlan,
+    manager: Option<WasmUdfManagerRef>,
+}
+
+impl CreateFunctionInterpreter {
+    pub fn create(plan: CreateFunctionPlan, manager: Option<WasmUdfManagerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_create(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(FunctionNotSupported)?;
+        let name = self.plan.name;
+        if manager.is_registered(&name) {
+            ensure!(self.plan.if_not_exists, FunctionExists { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        manager
+            .create_function(&name, &self.plan.module)
+            .context(ManageFunction { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for CreateFunctionInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_create().await.context(CreateFunction)
+    }
+}
+
+pub struct DropFunctionInterpreter {
+    plan: DropFunctionPlan,
+    manager: Option<WasmUdfManagerRef>,
+}
+
+impl DropFunctionInterpreter {
+    pub fn create(plan: DropFunctionPlan, manager: Option<WasmUdfManagerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_drop(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(FunctionNotSupported)?;
+        let name = self.plan.name;
+        if !manager.is_registered(&name) {
+            ensure!(self.plan.if_exists, FunctionNotFound { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        // The builtin functions can't be dropped.
+        manager
+            .drop_function(&name)
+            .context(ManageFunction { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for DropFunctionInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_drop().await.context(DropFunction)
+    }
+}
@@ -31,6 +31,7 @@ use common_types::{
     column_schema::ColumnId,
     datum::Datum,
     row::RowGroup,
+    TRANSFORM_PIPELINE,
 };
 use datafusion::{
     common::ToDFSchema,
@@ -44,7 +45,10 @@ use datafusion::{
 use df_operator::visitor::find_columns_by_expr;
 use hash_ext::hash64;
 use macros::define_result;
-use query_frontend::plan::InsertPlan;
+use query_frontend::{
+    pipeline::{Pipeline, Step},
+    plan::InsertPlan,
+};
 use snafu::{OptionExt, ResultExt, Snafu};
 use table_engine::table::{TableRef, WriteRequest};
 
@@ -90,6 +94,11 @@ pub enum Error {
     #[snafu(display("Failed to find input columns of expr, column_name:{}", column_name))]
     FindExpressionInput { column_name: String },
 
+    #[snafu(display("Failed to parse transform pipeline of table, err:{}", source))]
+    ParsePipeline {
+        source: query_frontend::pipeline::Error,
+    },
+
     #[snafu(display("Failed to build column block, err:{}", source))]
     BuildColumnBlock {
         source: common_types::column_block::Error,
@@ -112,27 +121,32 @@ impl InsertInterpreter {
 #[async_trait]
 impl Interpreter for InsertInterpreter {
     async fn execute(mut self: Box<Self>) -> InterpreterResult<Output> {
+        // Transform the rows before the tsid is generated from the tags.
+        let transformed_columns =
+            apply_transform_pipeline(&self.plan.table, &mut self.plan.rows).context(Insert)?;
         // Generate tsid if needed.
         self.maybe_generate_tsid().context(Insert)?;
         let InsertPlan {
             table,
             mut rows,
-            default_value_map,
+            mut default_value_map,
         } = self.plan;
+        // The columns changed by the pipeline are not filled by default values.
+        for column_idx in transformed_columns {
+            default_value_map.remove(&column_idx);
+        }
 
         // Fill default values
         fill_default_values(table.clone(), &mut rows, &default_value_map).context(Insert)?;
 
-        // Context is unused now
-        let _ctx = self.ctx;
-
         let request = WriteRequest { row_group: rows };
 
-        let num_rows = table
-            .write(request)
+        let (num_rows, sequence) = table
+            .write_with_sequence(request)
             .await
             .context(WriteTable)
             .context(Insert)?;
+        self.ctx.write_sequence().set(sequence);
 
         Ok(Output::AffectedRows(num_rows))
     }
@@ -215,6 +229,38 @@ impl<'a> TsidBuilder<'a> {
     }
 }
 
+/// Apply the transform pipeline of the `table` to the rows, returns the index
+/// of the columns changed by it.
+fn apply_transform_pipeline(table: &TableRef, row_groups: &mut RowGroup) -> Result<Vec<usize>> {
+    let options = table.options();
+    let Some(pipeline) = options.get(TRANSFORM_PIPELINE) else {
+        return Ok(Vec::new());
+    };
+    let pipeline = Pipeline::parse(pipeline, &table.schema()).context(ParsePipeline)?;
+
+    let mut cached_column_values: HashMap<usize, DfColumnarValue> = HashMap::new();
+    for step in pipeline.steps() {
+        match step {
+            Step::Set { column_idx, expr } => fill_column_by_expr(
+                table,
+                row_groups,
+                *column_idx,
+                expr,
+                &mut cached_column_values,
+            )?,
+            Step::Drop { column_idx } => {
+                for row_idx in 0..row_groups.num_rows() {
+                    let row = row_groups.get_row_mut(row_idx).unwrap();
+                    row[*column_idx] = Datum::Null;
+                }
+                cached_column_values.remove(column_idx);
+            }
+        }
+    }
+
+    Ok(pipeline.columns().collect())
+}
+
 /// Fill missing columns which can be calculated via default value expr.
 fn fill_default_values(
     table: TableRef,
@@ -222,91 +268,104 @@ fn fill_default_values(
     default_value_map: &BTreeMap<usize, DfLogicalExpr>,
 ) -> Result<()> {
     let mut cached_column_values: HashMap<usize, DfColumnarValue> = HashMap::new();
+    for (column_idx, default_value_expr) in default_value_map.iter() {
+        fill_column_by_expr(
+            &table,
+            row_groups,
+            *column_idx,
+            default_value_expr,
+            &mut cached_column_values,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Fill the column at `column_idx` with the value of the `expr` on the other
+/// columns of the rows.
+fn fill_column_by_expr(
+    table: &TableRef,
+    row_groups: &mut RowGroup,
+    column_idx: usize,
+    expr: &DfLogicalExpr,
+    cached_column_values: &mut HashMap<usize, DfColumnarValue>,
+) -> Result<()> {
     let table_arrow_schema = table.schema().to_arrow_schema_ref();
     let df_schema_ref = table_arrow_schema
         .clone()
         .to_dfschema_ref()
         .context(DatafusionSchema)?;
-
-    for (column_idx, default_value_expr) in default_value_map.iter() {
-        let execution_props = ExecutionProps::default();
-
-        // Optimize logical expr
-        let simplifier = ExprSimplifier::new(
-            SimplifyContext::new(&execution_props).with_schema(df_schema_ref.clone()),
-        );
-        let default_value_expr = simplifier
-            .coerce(default_value_expr.clone(), df_schema_ref.clone())
-            .context(DatafusionExpr)?;
-        let simplified_expr = simplifier
-            .simplify(default_value_expr)
-            .context(DatafusionExpr)?;
-
-        // Find input columns
-        let required_column_idxes = find_columns_by_expr(&simplified_expr)
-            .iter()
-            .map(|column_name| {
-                table
-                    .schema()
-                    .index_of(column_name)
-                    .context(FindExpressionInput { column_name })
-            })
-            .collect::<Result<Vec<usize>>>()?;
-        let input_arrow_schema = table_arrow_schema
-            .project(&required_column_idxes)
-            .context(ArrowSchema)?;
-        let input_df_schema = input_arrow_schema
-            .clone()
-            .to_dfschema()
-            .context(DatafusionSchema)?;
-
-        // Create physical expr
-        let physical_expr = create_physical_expr(
-            &simplified_expr,
-            &input_df_schema,
-            &input_arrow_schema,
-            &execution_props,
-        )
+    let execution_props = ExecutionProps::default();
+
+    // Optimize logical expr
+    let simplifier = ExprSimplifier::new(
+        SimplifyContext::new(&execution_props).with_schema(df_schema_ref.clone()),
+    );
+    let expr = simplifier
+        .coerce(expr.clone(), df_schema_ref.clone())
         .context(DatafusionExpr)?;
+    let simplified_expr = simplifier.simplify(expr).context(DatafusionExpr)?;
+
+    // Find input columns
+    let required_column_idxes = find_columns_by_expr(&simplified_expr)
+        .iter()
+        .map(|column_name| {
+            table
+                .schema()
+                .index_of(column_name)
+                .context(FindExpressionInput { column_name })
+        })
+        .collect::<Result<Vec<usize>>>()?;
+    let input_arrow_schema = table_arrow_schema
+        .project(&required_column_idxes)
+        .context(ArrowSchema)?;
+    let input_df_schema = input_arrow_schema
+        .clone()
+        .to_dfschema()
+        .context(DatafusionSchema)?;
 
-        let from_type = physical_expr
-            .data_type(&input_arrow_schema)
-            .context(DatafusionDataType)?;
-        let to_type = row_groups.schema().column(*column_idx).data_type;
-
-        let casted_physical_expr = if from_type != to_type.into() {
-            Arc::new(TryCastExpr::new(physical_expr, to_type.into()))
-        } else {
-            physical_expr
-        };
-
-        // Build input record batch
-        let input_arrays = required_column_idxes
-            .into_iter()
-            .map(|col_idx| {
-                get_or_extract_column_from_row_groups(
-                    col_idx,
-                    row_groups,
-                    &mut cached_column_values,
-                )
-            })
-            .collect::<Result<Vec<_>>>()?;
-        let input = if input_arrays.is_empty() {
-            RecordBatch::new_empty(Arc::new(input_arrow_schema))
-        } else {
-            RecordBatch::try_new(Arc::new(input_arrow_schema), input_arrays)
-                .context(BuildArrowRecordBatch)?
-        };
-
-        let output = casted_physical_expr
-            .evaluate(&input)
-            .context(DatafusionExecutor)?;
-
-        fill_column_to_row_group(*column_idx, &output, row_groups)?;
-
-        // Write output to cache.
-        cached_column_values.insert(*column_idx, output);
-    }
+    // Create physical expr
+    let physical_expr = create_physical_expr(
+        &simplified_expr,
+        &input_df_schema,
+        &input_arrow_schema,
+        &execution_props,
+    )
+    .context(DatafusionExpr)?;
+
+    let from_type = physical_expr
+        .data_type(&input_arrow_schema)
+        .context(DatafusionDataType)?;
+    let to_type = row_groups.schema().column(column_idx).data_type;
+
+    let casted_physical_expr = if from_type != to_type.into() {
+        Arc::new(TryCastExpr::new(physical_expr, to_type.into()))
+    } else {
+        physical_expr
+    };
+
+    // Build input record batch
+    let input_arrays = required_column_idxes
+        .into_iter()
+        .map(|col_idx| {
+            get_or_extract_column_from_row_groups(col_idx, row_groups, cached_column_values)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let input = if input_arrays.is_empty() {
+        RecordBatch::new_empty(Arc::new(input_arrow_schema))
+    } else {
+        RecordBatch::try_new(Arc::new(input_arrow_schema), input_arrays)
+            .context(BuildArrowRecordBatch)?
+    };
+
+    let output = casted_physical_expr
+        .evaluate(&input)
+        .context(DatafusionExecutor)?;
+
+    fill_column_to_row_group(column_idx, &output, row_groups)?;
+
+    // Write output to cache.
+    cached_column_values.insert(column_idx, output);
 
     Ok(())
 }
@@ -45,6 +45,12 @@ pub enum Error {
     #[snafu(display("Failed to execute alter table, err:{}", source))]
     AlterTable { source: crate::alter_table::Error },
 
+    #[snafu(display("Failed to execute alter schema, err:{}", source))]
+    AlterSchema { source: crate::alter_schema::Error },
+
+    #[snafu(display("Failed to execute swap tables, err:{}", source))]
+    SwapTables { source: crate::swap_tables::Error },
+
     #[snafu(display("Failed to execute show create tables, err:{}", source))]
     ShowCreateTable { source: crate::show::Error },
 
@@ -54,9 +60,64 @@ pub enum Error {
     #[snafu(display("Failed to execute show database, err:{}", source))]
     ShowDatabases { source: crate::show::Error },
 
+    #[snafu(display("Failed to execute show processlist, err:{}", source))]
+    ShowProcessList { source: crate::show::Error },
+
     #[snafu(display("Failed to execute exists, err:{}", source))]
     Exists { source: crate::exists::Error },
 
+    #[snafu(display("Failed to execute kill query, err:{}", source))]
+    KillQuery { source: crate::kill::Error },
+
+    #[snafu(display("Failed to execute create source, err:{}", source))]
+    CreateSource { source: crate::source::Error },
+
+    #[snafu(display("Failed to execute drop source, err:{}", source))]
+    DropSource { source: crate::source::Error },
+
+    #[snafu(display("Failed to execute create continuous query, err:{}", source))]
+    CreateContinuousQuery {
+        source: crate::continuous_query::Error,
+    },
+
+    #[snafu(display("Failed to execute drop continuous query, err:{}", source))]
+    DropContinuousQuery {
+        source: crate::continuous_query::Error,
+    },
+
+    #[snafu(display("Failed to execute create user, err:{}", source))]
+    CreateUser { source: crate::user::Error },
+
+    #[snafu(display("Failed to execute drop user, err:{}", source))]
+    DropUser { source: crate::user::Error },
+
+    #[snafu(display("Failed to execute grant, err:{}", source))]
+    Grant { source: crate::user::Error },
+
+    #[snafu(display("Failed to execute revoke, err:{}", source))]
+    Revoke { source: crate::user::Error },
+
+    #[snafu(display("Failed to execute create function, err:{}", source))]
+    CreateFunction { source: crate::function::Error },
+
+    #[snafu(display("Failed to execute drop function, err:{}", source))]
+    DropFunction { source: crate::function::Error },
+
+    #[snafu(display("Failed to execute alter system, err:{}", source))]
+    AlterSystem { source: crate::alter_system::Error },
+
+    #[snafu(display("Failed to execute analyze table, err:{}", source))]
+    AnalyzeTable { source: crate::analyze_table::Error },
+
+    #[snafu(display("Failed to execute compact table, err:{}", source))]
+    CompactTable { source: crate::compact_table::Error },
+
+    #[snafu(display("Failed to execute show sources, err:{}", source))]
+    ShowSources { source: crate::show::Error },
+
+    #[snafu(display("Failed to execute show partitions, err:{}", source))]
+    ShowPartitions { source: crate::show::Error },
+
     #[snafu(display("Failed to transfer output to records"))]
     TryIntoRecords,
 
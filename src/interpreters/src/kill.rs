@@ -0,0 +1,64 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for kill query statement
+
+use async_trait::async_trait;
+use macros::define_result;
+use query_frontend::plan::KillQueryPlan;
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+
+use crate::{
+    interpreter::{Interpreter, InterpreterPtr, KillQuery, Output, Result as InterpreterResult},
+    query_tracker::QueryTrackerRef,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Query not found, id:{}.\nBacktrace:\n{}", id, backtrace))]
+    QueryNotFound { id: u64, backtrace: Backtrace },
+}
+
+define_result!(Error);
+
+pub struct KillQueryInterpreter {
+    plan: KillQueryPlan,
+    query_tracker: QueryTrackerRef,
+}
+
+impl KillQueryInterpreter {
+    pub fn create(plan: KillQueryPlan, query_tracker: QueryTrackerRef) -> InterpreterPtr {
+        Box::new(Self {
+            plan,
+            query_tracker,
+        })
+    }
+
+    fn execute_kill(self: Box<Self>) -> Result<Output> {
+        let id = self.plan.query_id;
+        ensure!(self.query_tracker.kill(id), QueryNotFound { id });
+
+        Ok(Output::AffectedRows(1))
+    }
+}
+
+#[async_trait]
+impl Interpreter for KillQueryInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_kill().context(KillQuery)
+    }
+}
@@ -23,20 +23,34 @@
 
 use common_types::record_batch::RecordBatch;
 
+pub mod alter_schema;
+pub mod alter_system;
 pub mod alter_table;
+pub mod analyze_table;
+pub mod compact_table;
 pub mod context;
+pub mod continuous_query;
 pub mod create;
 pub mod describe;
 pub mod drop;
 pub mod exists;
 pub mod factory;
+pub mod function;
 pub mod insert;
 pub mod interpreter;
+pub mod kill;
 mod metrics;
+pub mod offload;
+pub mod query_tracker;
+pub mod resync;
+pub mod result_limit;
 pub mod select;
 pub mod show;
-mod show_create;
+pub mod show_create;
+pub mod source;
+pub mod swap_tables;
 pub mod table_manipulator;
+pub mod user;
 pub mod validator;
 
 #[cfg(test)]
@@ -0,0 +1,345 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Offload the query results to the result store
+//!
+//! The results of `COPY (query) TO 'path'` are written into parquet files
+//! under `path` directly, and only a manifest of the output files is returned,
+//! so huge results won't be buffered in the memory of the serving node.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{StringArray, UInt64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch as ArrowRecordBatch,
+};
+use common_types::{record_batch::RecordBatch, request_id::RequestId};
+use futures::TryStreamExt;
+use generic_error::{BoxError, GenericError};
+use logger::{info, warn};
+use macros::define_result;
+use object_store::{ObjectStoreError, ObjectStoreRef, Path};
+use parquet::{arrow::AsyncArrowWriter, errors::ParquetError};
+use snafu::{ResultExt, Snafu};
+use table_engine::stream::SendableRecordBatchStream;
+use tokio::io::AsyncWrite;
+
+const MANIFEST_PATH_COLUMN: &str = "path";
+const MANIFEST_NUM_ROWS_COLUMN: &str = "num_rows";
+const MANIFEST_SIZE_COLUMN: &str = "size";
+const WRITER_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to poll query results, err:{}", source))]
+    PollResults { source: GenericError },
+
+    #[snafu(display("Failed to start upload, path:{}, err:{}", path, source))]
+    StartUpload {
+        path: Path,
+        source: ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to write file, path:{}, err:{}", path, source))]
+    WriteFile { path: Path, source: ParquetError },
+
+    #[snafu(display("Failed to head file, path:{}, err:{}", path, source))]
+    HeadFile {
+        path: Path,
+        source: ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to build manifest, err:{}", source))]
+    BuildManifest { source: arrow::error::ArrowError },
+
+    #[snafu(display("Failed to convert manifest, err:{}", source))]
+    ConvertManifest {
+        source: common_types::record_batch::Error,
+    },
+}
+
+define_result!(Error);
+
+/// Offloader writing the query results to the result store
+#[derive(Debug)]
+pub struct ResultOffloader {
+    store: ObjectStoreRef,
+    /// A new file is started after the rows of current file reach it
+    max_rows_per_file: usize,
+}
+
+pub type ResultOffloaderRef = Arc<ResultOffloader>;
+
+impl ResultOffloader {
+    pub fn new(store: ObjectStoreRef, max_rows_per_file: usize) -> Self {
+        Self {
+            store,
+            max_rows_per_file: max_rows_per_file.max(1),
+        }
+    }
+
+    /// Write all the results of `stream` to the files under `path`, and return
+    /// the manifest of the written files.
+    ///
+    /// The written files are deleted if the offload fails, so no orphaned
+    /// files are left in the result store.
+    pub async fn offload(
+        &self,
+        request_id: &RequestId,
+        path: &str,
+        stream: SendableRecordBatchStream,
+    ) -> Result<RecordBatch> {
+        let mut files = Vec::new();
+        if let Err(e) = self.write_files(request_id, path, stream, &mut files).await {
+            for file in files {
+                if let Err(delete_err) = self.store.delete(&file.path).await {
+                    warn!(
+                        "Failed to delete offloaded file, path:{}, err:{delete_err}",
+                        file.path
+                    );
+                }
+            }
+            return Err(e);
+        }
+
+        info!(
+            "Query results offloaded, request_id:{request_id}, path:{path}, files:{}",
+            files.len()
+        );
+
+        build_manifest(files)
+    }
+
+    /// Write the results to the files, and the completed files are pushed to
+    /// `files`.
+    async fn write_files(
+        &self,
+        request_id: &RequestId,
+        path: &str,
+        mut stream: SendableRecordBatchStream,
+        files: &mut Vec<OffloadedFile>,
+    ) -> Result<()> {
+        let mut current: Option<FileWriter> = None;
+        loop {
+            let batch = match stream.try_next().await.box_err().context(PollResults) {
+                Ok(Some(batch)) => batch,
+                Ok(None) => break,
+                Err(e) => {
+                    if let Some(writer) = current {
+                        writer.abort(&self.store).await;
+                    }
+                    return Err(e);
+                }
+            };
+            if batch.is_empty() {
+                continue;
+            }
+
+            let batch = batch.into_arrow_record_batch();
+            if current.is_none() {
+                let file_path = Path::from(format!(
+                    "{}/{}-{:05}.parquet",
+                    path.trim_end_matches('/'),
+                    request_id,
+                    files.len()
+                ));
+                current = Some(FileWriter::try_new(&self.store, file_path, batch.schema()).await?);
+            }
+
+            let writer = current.as_mut().unwrap();
+            if let Err(e) = writer.write(&batch).await {
+                current.unwrap().abort(&self.store).await;
+                return Err(e);
+            }
+            if writer.num_rows >= self.max_rows_per_file {
+                files.push(current.take().unwrap().close(&self.store).await?);
+            }
+        }
+        if let Some(writer) = current {
+            files.push(writer.close(&self.store).await?);
+        }
+
+        Ok(())
+    }
+}
+
+struct OffloadedFile {
+    path: Path,
+    num_rows: u64,
+    size: u64,
+}
+
+struct FileWriter {
+    path: Path,
+    multipart_id: String,
+    writer: AsyncArrowWriter<Box<dyn AsyncWrite + Send + Unpin>>,
+    num_rows: usize,
+}
+
+impl FileWriter {
+    async fn try_new(store: &ObjectStoreRef, path: Path, schema: SchemaRef) -> Result<Self> {
+        let (multipart_id, sink) = store
+            .put_multipart(&path)
+            .await
+            .context(StartUpload { path: path.clone() })?;
+        let writer = AsyncArrowWriter::try_new(sink, schema, WRITER_BUFFER_SIZE, None)
+            .context(WriteFile { path: path.clone() })?;
+
+        Ok(Self {
+            path,
+            multipart_id,
+            writer,
+            num_rows: 0,
+        })
+    }
+
+    async fn write(&mut self, batch: &ArrowRecordBatch) -> Result<()> {
+        self.writer.write(batch).await.context(WriteFile {
+            path: self.path.clone(),
+        })?;
+        self.num_rows += batch.num_rows();
+
+        Ok(())
+    }
+
+    async fn close(self, store: &ObjectStoreRef) -> Result<OffloadedFile> {
+        let Self {
+            path,
+            multipart_id,
+            writer,
+            num_rows,
+        } = self;
+        if let Err(e) = writer.close().await {
+            if let Err(abort_err) = store.abort_multipart(&path, &multipart_id).await {
+                warn!("Failed to abort upload, path:{path}, err:{abort_err}");
+            }
+            return Err(e).context(WriteFile { path });
+        }
+        let size = match store.head(&path).await {
+            Ok(meta) => meta.size,
+            Err(e) => {
+                if let Err(delete_err) = store.delete(&path).await {
+                    warn!("Failed to delete offloaded file, path:{path}, err:{delete_err}");
+                }
+                return Err(e).context(HeadFile { path });
+            }
+        };
+
+        Ok(OffloadedFile {
+            path,
+            num_rows: num_rows as u64,
+            size: size as u64,
+        })
+    }
+
+    async fn abort(self, store: &ObjectStoreRef) {
+        if let Err(e) = store.abort_multipart(&self.path, &self.multipart_id).await {
+            warn!("Failed to abort upload, path:{}, err:{e}", self.path);
+        }
+    }
+}
+
+fn build_manifest(files: Vec<OffloadedFile>) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new(MANIFEST_PATH_COLUMN, DataType::Utf8, false),
+        Field::new(MANIFEST_NUM_ROWS_COLUMN, DataType::UInt64, false),
+        Field::new(MANIFEST_SIZE_COLUMN, DataType::UInt64, false),
+    ]);
+    let paths = StringArray::from_iter_values(files.iter().map(|f| f.path.to_string()));
+    let num_rows = UInt64Array::from_iter_values(files.iter().map(|f| f.num_rows));
+    let sizes = UInt64Array::from_iter_values(files.iter().map(|f| f.size));
+    let record_batch = ArrowRecordBatch::try_new(
+        Arc::new(schema),
+        vec![Arc::new(paths), Arc::new(num_rows), Arc::new(sizes)],
+    )
+    .context(BuildManifest)?;
+
+    record_batch.try_into().context(ConvertManifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int64Array;
+    use datafusion::{error::DataFusionError, physical_plan::stream::RecordBatchStreamAdapter};
+    use futures::stream;
+    use object_store::LocalFileSystem;
+    use table_engine::stream::FromDfStream;
+
+    use super::*;
+
+    fn build_stream(num_batches: usize, fail: bool) -> SendableRecordBatchStream {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+        let mut batches: Vec<_> = (0..num_batches)
+            .map(|i| {
+                let values = Int64Array::from(vec![i as i64, i as i64 + 1]);
+                Ok(ArrowRecordBatch::try_new(schema.clone(), vec![Arc::new(values)]).unwrap())
+            })
+            .collect();
+        if fail {
+            batches.push(Err(DataFusionError::Execution("mock error".to_string())));
+        }
+        let df_stream = RecordBatchStreamAdapter::new(schema, stream::iter(batches));
+
+        Box::pin(FromDfStream::new(Box::pin(df_stream)).unwrap())
+    }
+
+    async fn list_files(store: &ObjectStoreRef) -> Vec<String> {
+        let mut files: Vec<_> = store
+            .list(None)
+            .await
+            .unwrap()
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    async fn test_offload() {
+        let dir = tempfile::tempdir().unwrap();
+        let store: ObjectStoreRef = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        let offloader = ResultOffloader::new(store.clone(), 2);
+        let request_id = RequestId::next_id();
+
+        let manifest = offloader
+            .offload(&request_id, "exports/", build_stream(3, false))
+            .await
+            .unwrap();
+        assert_eq!(3, manifest.num_rows());
+        let expected: Vec<_> = (0..3)
+            .map(|i| format!("exports/{request_id}-{i:05}.parquet"))
+            .collect();
+        assert_eq!(expected, list_files(&store).await);
+    }
+
+    #[tokio::test]
+    async fn test_offload_clean_files_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let store: ObjectStoreRef = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        let offloader = ResultOffloader::new(store.clone(), 2);
+
+        // The completed files are deleted after the stream fails.
+        let res = offloader
+            .offload(&RequestId::next_id(), "exports", build_stream(3, true))
+            .await;
+        assert!(res.is_err());
+        assert!(list_files(&store).await.is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tracker of the running queries
+//!
+//! Every query served by this node is registered into the tracker until it
+//! finishes, so the running queries can be listed by `SHOW PROCESSLIST` and
+//! killed by `KILL QUERY <id>`. Killing a query drops its execution future,
+//! which drops the DataFusion streams and the storage scans under them.
+//!
+//! The resources consumed by the table scans of a query are collected into its
+//! [ScanUsage], which is found by the request id of the query, and so are the
+//! [QueryStages] of it.
+//!
+//! The finished queries are kept in a bounded history together with their
+//! [QuerySnapshot], so a slow query can be reproduced later even if the configs
+//! or the table schemas have been changed.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use common_types::request_id::RequestId;
+use query_engine::stage::{QueryStages, QueryStagesRef};
+use table_engine::scan_quota::{ScanUsage, ScanUsageRef};
+use tokio::sync::oneshot;
+
+/// Max number of the finished queries kept in the history.
+const QUERY_HISTORY_CAPACITY: usize = 128;
+
+/// The effective settings of a query and the schema versions of the tables
+/// read by it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuerySnapshot {
+    pub settings: BTreeMap<String, String>,
+    /// Schema versions keyed by the full names of the tables.
+    pub table_versions: BTreeMap<String, u32>,
+}
+
+/// Information of a running query.
+#[derive(Debug, Clone)]
+pub struct QueryInfo {
+    /// Id used by `KILL QUERY <id>`.
+    pub id: u64,
+    pub request_id: String,
+    pub schema: String,
+    pub query: String,
+    pub start: Instant,
+    /// Whether the query has been asked to be killed.
+    pub killed: bool,
+    pub scan_usage: ScanUsageRef,
+    pub stages: QueryStagesRef,
+    pub snapshot: Option<Arc<QuerySnapshot>>,
+}
+
+impl QueryInfo {
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[inline]
+    pub fn state(&self) -> &'static str {
+        if self.killed {
+            "killing"
+        } else {
+            "running"
+        }
+    }
+}
+
+/// A finished query in the history.
+#[derive(Debug, Clone)]
+pub struct QueryRecord {
+    pub info: QueryInfo,
+    pub elapsed: Duration,
+}
+
+struct TrackedQuery {
+    info: QueryInfo,
+    kill_tx: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Default)]
+pub struct QueryTracker {
+    next_id: AtomicU64,
+    queries: Mutex<BTreeMap<u64, TrackedQuery>>,
+    history: Mutex<VecDeque<QueryRecord>>,
+}
+
+pub type QueryTrackerRef = Arc<QueryTracker>;
+
+impl QueryTracker {
+    /// Register a query, which is removed from the tracker once the returned
+    /// guard is dropped.
+    pub fn register(
+        self: &Arc<Self>,
+        request_id: &RequestId,
+        schema: &str,
+        query: &str,
+    ) -> QueryGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let info = QueryInfo {
+            id,
+            request_id: request_id.to_string(),
+            schema: schema.to_string(),
+            query: query.to_string(),
+            start: Instant::now(),
+            killed: false,
+            scan_usage: Arc::new(ScanUsage::default()),
+            stages: Arc::new(QueryStages::default()),
+            snapshot: None,
+        };
+        self.queries.lock().unwrap().insert(
+            id,
+            TrackedQuery {
+                info,
+                kill_tx: Some(kill_tx),
+            },
+        );
+
+        QueryGuard {
+            id,
+            tracker: self.clone(),
+            kill_rx,
+        }
+    }
+
+    /// List the running queries ordered by their ids.
+    pub fn list(&self) -> Vec<QueryInfo> {
+        self.queries
+            .lock()
+            .unwrap()
+            .values()
+            .map(|query| query.info.clone())
+            .collect()
+    }
+
+    /// Get the scan usage of the running query with the given `request_id`.
+    pub fn scan_usage(&self, request_id: &RequestId) -> Option<ScanUsageRef> {
+        self.queries
+            .lock()
+            .unwrap()
+            .values()
+            .find(|query| query.info.request_id == request_id.as_str())
+            .map(|query| query.info.scan_usage.clone())
+    }
+
+    /// Get the stages of the running query with the given `request_id`.
+    pub fn stages(&self, request_id: &RequestId) -> Option<QueryStagesRef> {
+        self.queries
+            .lock()
+            .unwrap()
+            .values()
+            .find(|query| query.info.request_id == request_id.as_str())
+            .map(|query| query.info.stages.clone())
+    }
+
+    /// List the recently finished queries, the latest one comes last.
+    pub fn history(&self) -> Vec<QueryRecord> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn stages_of(&self, id: u64) -> Option<QueryStagesRef> {
+        self.queries
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|query| query.info.stages.clone())
+    }
+
+    fn set_snapshot(&self, id: u64, snapshot: QuerySnapshot) {
+        if let Some(query) = self.queries.lock().unwrap().get_mut(&id) {
+            query.info.snapshot = Some(Arc::new(snapshot));
+        }
+    }
+
+    /// Kill the query with the given `id`, returns false if no such query is
+    /// running.
+    pub fn kill(&self, id: u64) -> bool {
+        let mut queries = self.queries.lock().unwrap();
+        let Some(query) = queries.get_mut(&id) else {
+            return false;
+        };
+
+        query.info.killed = true;
+        if let Some(kill_tx) = query.kill_tx.take() {
+            // The receiver is dropped only if the query is finishing.
+            let _ = kill_tx.send(());
+        }
+        true
+    }
+
+    fn deregister(&self, id: u64) {
+        let query = self.queries.lock().unwrap().remove(&id);
+        if let Some(query) = query {
+            let record = QueryRecord {
+                elapsed: query.info.elapsed(),
+                info: query.info,
+            };
+            let mut history = self.history.lock().unwrap();
+            if history.len() >= QUERY_HISTORY_CAPACITY {
+                history.pop_front();
+            }
+            history.push_back(record);
+        }
+    }
+}
+
+/// Guard of a registered query.
+pub struct QueryGuard {
+    id: u64,
+    tracker: QueryTrackerRef,
+    kill_rx: oneshot::Receiver<()>,
+}
+
+impl QueryGuard {
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Get the stages of the query, `None` if it has been deregistered.
+    pub fn stages(&self) -> Option<QueryStagesRef> {
+        self.tracker.stages_of(self.id)
+    }
+
+    /// Record the snapshot of the query, which is kept in the history after
+    /// the query finishes.
+    pub fn set_snapshot(&self, snapshot: QuerySnapshot) {
+        self.tracker.set_snapshot(self.id, snapshot);
+    }
+
+    /// Run the `fut` until it finishes or the query is killed, `None` is
+    /// returned if the query is killed.
+    pub async fn run<F: Future>(mut self, fut: F) -> Option<F::Output> {
+        tokio::select! {
+            output = fut => Some(output),
+            // The sender is held by the tracker until the guard is dropped, so the
+            // receiver is resolved only if the query is killed.
+            _ = &mut self.kill_rx => None,
+        }
+    }
+}
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        self.tracker.deregister(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_kill_query() {
+        let tracker = Arc::new(QueryTracker::default());
+        let guard = tracker.register(&RequestId::from("1"), "public", "select 1");
+        let id = guard.id();
+        let finished = tracker.register(&RequestId::from("2"), "public", "select 2");
+
+        let queries = tracker.list();
+        assert_eq!(2, queries.len());
+        assert_eq!("select 1", queries[0].query);
+        assert_eq!("running", queries[0].state());
+        let scan_usage = tracker.scan_usage(&RequestId::from("1")).unwrap();
+        assert!(Arc::ptr_eq(&queries[0].scan_usage, &scan_usage));
+        assert!(tracker.scan_usage(&RequestId::from("3")).is_none());
+        let stages = tracker.stages(&RequestId::from("1")).unwrap();
+        assert!(Arc::ptr_eq(&queries[0].stages, &stages));
+
+        let snapshot = QuerySnapshot {
+            settings: BTreeMap::from([("read_parallelism".to_string(), "8".to_string())]),
+            table_versions: BTreeMap::from([("horaedb.public.t".to_string(), 2)]),
+        };
+        finished.set_snapshot(snapshot.clone());
+        assert!(finished.run(async { 2 }).await.is_some());
+        assert_eq!(1, tracker.list().len());
+        let history = tracker.history();
+        assert_eq!(1, history.len());
+        assert_eq!("select 2", history[0].info.query);
+        assert_eq!(Some(&snapshot), history[0].info.snapshot.as_deref());
+
+        assert!(tracker.kill(id));
+        assert_eq!("killing", tracker.list()[0].state());
+        let output = guard.run(futures::future::pending::<()>()).await;
+        assert!(output.is_none());
+        assert!(tracker.list().is_empty());
+        assert!(!tracker.kill(id));
+        assert_eq!(2, tracker.history().len());
+    }
+
+    #[test]
+    fn test_query_history_capacity() {
+        let tracker = Arc::new(QueryTracker::default());
+        for i in 0..QUERY_HISTORY_CAPACITY + 2 {
+            let _guard = tracker.register(&RequestId::from(i.to_string()), "public", "select 1");
+        }
+
+        let history = tracker.history();
+        assert_eq!(QUERY_HISTORY_CAPACITY, history.len());
+        assert_eq!("2", history[0].info.request_id);
+    }
+}
@@ -0,0 +1,213 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Limits of the results returned by the queries
+//!
+//! The default limits are soft ones applied to the queries without an explicit
+//! `LIMIT`, and their results are truncated with a warning. The max limits are
+//! hard ones applied to all the queries, and the query fails once its results
+//! exceed them. Either way the query stops executing as soon as the limit is
+//! reached, so an unbounded query can't exhaust the memory of the server.
+
+use common_types::record_batch::RecordBatch;
+use datafusion::logical_expr::LogicalPlan;
+use macros::define_result;
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Query results exceed the max rows, max_rows:{}.\nBacktrace:\n{}",
+        max_rows,
+        backtrace
+    ))]
+    ExceedMaxRows {
+        max_rows: usize,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Query results exceed the max bytes, max_bytes:{}.\nBacktrace:\n{}",
+        max_bytes,
+        backtrace
+    ))]
+    ExceedMaxBytes { max_bytes: u64, backtrace: Backtrace },
+
+    #[snafu(display("Failed to truncate the results, err:{}", source))]
+    Truncate {
+        source: common_types::record_batch::Error,
+    },
+}
+
+define_result!(Error);
+
+/// Limits of the results of a query, `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResultLimit {
+    /// Soft limit of the rows of the query without an explicit `LIMIT`.
+    pub default_rows: Option<usize>,
+    /// Soft limit of the bytes of the query without an explicit `LIMIT`.
+    pub default_bytes: Option<u64>,
+    /// Hard limit of the rows of any query.
+    pub max_rows: Option<usize>,
+    /// Hard limit of the bytes of any query.
+    pub max_bytes: Option<u64>,
+}
+
+/// The results admitted by the limiter.
+#[derive(Debug)]
+pub enum Admitted {
+    /// The whole batch.
+    All(RecordBatch),
+    /// The batch truncated by the default limits, and the following ones should
+    /// be dropped.
+    Truncated(RecordBatch),
+}
+
+/// Count the results of a query against its limits.
+#[derive(Debug)]
+pub struct ResultLimiter {
+    limit: ResultLimit,
+    /// Whether the default limits are applied.
+    apply_defaults: bool,
+    rows: usize,
+    bytes: u64,
+}
+
+impl ResultLimiter {
+    pub fn new(limit: ResultLimit, explicit_limit: bool) -> Self {
+        Self {
+            limit,
+            apply_defaults: !explicit_limit,
+            rows: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Rows admitted so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn admit(&mut self, batch: RecordBatch) -> Result<Admitted> {
+        let num_rows = batch.num_rows();
+        let num_bytes = batch.as_arrow_record_batch().get_array_memory_size() as u64;
+
+        // Rows to keep under the default limits, and the bytes are assumed to
+        // be evenly distributed among the rows.
+        let mut keep = num_rows;
+        if self.apply_defaults {
+            if let Some(default_rows) = self.limit.default_rows {
+                keep = keep.min(default_rows.saturating_sub(self.rows));
+            }
+            if let Some(default_bytes) = self.limit.default_bytes {
+                if self.bytes + num_bytes > default_bytes {
+                    let left = default_bytes.saturating_sub(self.bytes);
+                    keep = keep.min((num_rows as u64 * left / num_bytes) as usize);
+                }
+            }
+        }
+        let kept_bytes = if keep == num_rows {
+            num_bytes
+        } else {
+            num_bytes * keep as u64 / num_rows as u64
+        };
+
+        self.rows += keep;
+        self.bytes += kept_bytes;
+        if let Some(max_rows) = self.limit.max_rows {
+            ensure!(self.rows <= max_rows, ExceedMaxRows { max_rows });
+        }
+        if let Some(max_bytes) = self.limit.max_bytes {
+            ensure!(self.bytes <= max_bytes, ExceedMaxBytes { max_bytes });
+        }
+
+        if keep == num_rows {
+            return Ok(Admitted::All(batch));
+        }
+        let arrow_batch = batch.as_arrow_record_batch().slice(0, keep);
+        let truncated = RecordBatch::try_from(arrow_batch).context(Truncate)?;
+        Ok(Admitted::Truncated(truncated))
+    }
+}
+
+/// Whether the query limits its results explicitly, e.g. by `LIMIT`.
+pub fn has_explicit_limit(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Limit(limit) => limit.fetch.is_some(),
+        LogicalPlan::Sort(sort) => sort.fetch.is_some() || has_explicit_limit(&sort.input),
+        LogicalPlan::Projection(projection) => has_explicit_limit(&projection.input),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::{build_fetched_record_batch_by_rows, build_rows};
+
+    use super::*;
+
+    fn new_batch() -> RecordBatch {
+        build_fetched_record_batch_by_rows(build_rows()).into_record_batch()
+    }
+
+    #[test]
+    fn test_result_limiter() {
+        let batch = new_batch();
+        let num_rows = batch.num_rows();
+        assert!(num_rows > 1);
+
+        // The results are truncated by the default rows.
+        let limit = ResultLimit {
+            default_rows: Some(num_rows + 1),
+            ..Default::default()
+        };
+        let mut limiter = ResultLimiter::new(limit, false);
+        assert!(matches!(
+            limiter.admit(new_batch()).unwrap(),
+            Admitted::All(_)
+        ));
+        match limiter.admit(new_batch()).unwrap() {
+            Admitted::Truncated(batch) => assert_eq!(1, batch.num_rows()),
+            Admitted::All(_) => panic!("The results should be truncated"),
+        }
+        assert_eq!(num_rows + 1, limiter.rows());
+
+        // The default rows are not applied to the query with a limit, but the
+        // max rows are.
+        let limit = ResultLimit {
+            default_rows: Some(1),
+            max_rows: Some(num_rows + 1),
+            ..Default::default()
+        };
+        let mut limiter = ResultLimiter::new(limit, true);
+        assert!(matches!(
+            limiter.admit(new_batch()).unwrap(),
+            Admitted::All(_)
+        ));
+        assert!(limiter.admit(new_batch()).is_err());
+
+        // Unlimited.
+        let mut limiter = ResultLimiter::new(ResultLimit::default(), false);
+        for _ in 0..10 {
+            assert!(matches!(
+                limiter.admit(new_batch()).unwrap(),
+                Admitted::All(_)
+            ));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for resync table statement
+//!
+//! The consumers of the table changes, e.g. the replicas, are re-synced by
+//! making their next subscriptions start over with a snapshot. The requests
+//! are kept by the node owning the table, which is where the statement is
+//! forwarded to and where the subscriptions of the table are served.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use logger::info;
+use query_frontend::plan::ResyncTablePlan;
+use table_engine::table::TableId;
+
+use crate::interpreter::{Interpreter, InterpreterPtr, Output, Result};
+
+/// Consumers to re-sync, keyed by the table ids and the consumers.
+#[derive(Debug, Default)]
+pub struct ResyncRequests {
+    requests: Mutex<HashSet<(TableId, String)>>,
+}
+
+pub type ResyncRequestsRef = Arc<ResyncRequests>;
+
+impl ResyncRequests {
+    pub fn request(&self, table_id: TableId, consumer: &str) {
+        self.requests
+            .lock()
+            .unwrap()
+            .insert((table_id, consumer.to_string()));
+    }
+
+    /// Whether the consumer of the table should start over with a snapshot, and
+    /// the request is cleared once it's taken.
+    pub fn take(&self, table_id: TableId, consumer: &str) -> bool {
+        self.requests
+            .lock()
+            .unwrap()
+            .remove(&(table_id, consumer.to_string()))
+    }
+}
+
+pub struct ResyncTableInterpreter {
+    plan: ResyncTablePlan,
+    resync_requests: ResyncRequestsRef,
+}
+
+impl ResyncTableInterpreter {
+    pub fn create(plan: ResyncTablePlan, resync_requests: ResyncRequestsRef) -> InterpreterPtr {
+        Box::new(Self {
+            plan,
+            resync_requests,
+        })
+    }
+}
+
+#[async_trait]
+impl Interpreter for ResyncTableInterpreter {
+    async fn execute(self: Box<Self>) -> Result<Output> {
+        let table = &self.plan.table;
+        info!(
+            "Resync table, table:{}, consumer:{}",
+            table.name(),
+            self.plan.consumer
+        );
+        self.resync_requests
+            .request(table.id(), &self.plan.consumer);
+
+        Ok(Output::AffectedRows(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resync_requests() {
+        let requests = ResyncRequests::default();
+        let table_id = TableId::from(1);
+        requests.request(table_id, "replica");
+
+        assert!(!requests.take(table_id, "other"));
+        assert!(!requests.take(TableId::from(2), "replica"));
+        assert!(requests.take(table_id, "replica"));
+        assert!(!requests.take(table_id, "replica"));
+    }
+}
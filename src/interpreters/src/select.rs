@@ -28,13 +28,17 @@ use query_engine::{
     physical_planner::{PhysicalPlanRef, PhysicalPlannerRef},
 };
 use query_frontend::plan::{PriorityContext, QueryPlan};
-use runtime::{Priority, PriorityRuntime};
-use snafu::{ResultExt, Snafu};
+use runtime::{AbortOnDropMany, Priority, PriorityRuntime};
+use snafu::{OptionExt, ResultExt, Snafu};
+use system_catalog::scheduler::scheduler_state;
+use table_engine::query_warning::WarningKind;
 
 use crate::{
-    context::Context,
+    context::{Context, ResultSender},
     interpreter::{Interpreter, InterpreterPtr, Output, Result as InterpreterResult, Select},
     metrics::ENGINE_QUERY_COUNTER,
+    offload::ResultOffloaderRef,
+    result_limit::{self, Admitted, ResultLimiter},
 };
 
 #[derive(Debug, Snafu)]
@@ -47,6 +51,18 @@ pub enum Error {
 
     #[snafu(display("Failed to spawn task, err:{}", source))]
     Spawn { source: runtime::Error },
+
+    #[snafu(display("Result offload is not enabled, path:{}", path))]
+    OffloadNotEnabled { path: String },
+
+    #[snafu(display("Failed to offload results, err:{}", source))]
+    Offload { source: crate::offload::Error },
+
+    #[snafu(display("Receiver of the streamed results is dropped"))]
+    ResultReceiverDropped,
+
+    #[snafu(display("Failed to limit the results, err:{}", source))]
+    LimitResult { source: result_limit::Error },
 }
 
 define_result!(Error);
@@ -58,6 +74,7 @@ pub struct SelectInterpreter {
     executor: ExecutorRef,
     physical_planner: PhysicalPlannerRef,
     query_runtime: PriorityRuntime,
+    result_offloader: Option<ResultOffloaderRef>,
 }
 
 impl SelectInterpreter {
@@ -67,6 +84,7 @@ impl SelectInterpreter {
         executor: ExecutorRef,
         physical_planner: PhysicalPlannerRef,
         query_runtime: PriorityRuntime,
+        result_offloader: Option<ResultOffloaderRef>,
     ) -> InterpreterPtr {
         Box::new(Self {
             ctx,
@@ -74,6 +92,7 @@ impl SelectInterpreter {
             executor,
             physical_planner,
             query_runtime,
+            result_offloader,
         })
     }
 }
@@ -82,10 +101,25 @@ impl SelectInterpreter {
 impl Interpreter for SelectInterpreter {
     async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
         let request_id = self.ctx.request_id();
-        let plan = self.plan;
+        let mut plan = self.plan;
+        // Check the offloader before the query is executed.
+        let offload = match plan.offload.take() {
+            Some(offload) => {
+                let offloader = self
+                    .result_offloader
+                    .clone()
+                    .with_context(|| OffloadNotEnabled {
+                        path: offload.path.clone(),
+                    })
+                    .context(Select)?;
+                Some((offloader, offload.path))
+            }
+            None => None,
+        };
         let priority = match plan
             .decide_query_priority(PriorityContext {
                 time_range_threshold: self.ctx.expensive_query_threshold(),
+                hot_time_range: self.ctx.hot_time_range(),
             })
             .box_err()
             .with_context(|| ExecutePlan {
@@ -106,6 +140,10 @@ impl Interpreter for SelectInterpreter {
             .with_label_values(&[priority.as_str()])
             .inc();
 
+        let limiter = ResultLimiter::new(
+            self.ctx.result_limit(),
+            result_limit::has_explicit_limit(&plan.df_plan),
+        );
         let query_ctx = self
             .ctx
             .new_query_context(priority)
@@ -127,35 +165,59 @@ impl Interpreter for SelectInterpreter {
             })
             .context(Select)?;
 
+        // The results are sent to the client as they are produced if streaming.
+        let sink = match offload {
+            Some((offloader, path)) => ResultSink::Offload(offloader, path),
+            None => match self.ctx.result_sender() {
+                Some(sender) => ResultSink::Stream(sender),
+                None => ResultSink::Collect,
+            },
+        };
+        // The low priority queries wait in the queue of the low priority runtime
+        // until they are polled.
+        let queued = scheduler_state().enqueue(priority.as_str());
         if matches!(priority, Priority::Low) {
             let executor = self.executor;
-            return self
-                .query_runtime
-                .spawn_with_priority(
-                    async move {
-                        execute_and_collect(query_ctx, executor, physical_plan)
-                            .await
-                            .context(Select)
-                    },
-                    Priority::Low,
-                )
-                .await
-                .context(Spawn)
-                .context(Select)?;
+            let handle = self.query_runtime.spawn_with_priority(
+                logger::with_current_request_id(async move {
+                    let _running = queued.start();
+                    execute_and_collect(query_ctx, executor, physical_plan, sink, limiter)
+                        .await
+                        .context(Select)
+                }),
+                Priority::Low,
+            );
+            // Abort the spawned query if the interpreter is dropped, e.g. the query is
+            // killed.
+            let mut handles = AbortOnDropMany(vec![handle]);
+            return (&mut handles.0[0]).await.context(Spawn).context(Select)?;
         }
 
-        execute_and_collect(query_ctx, self.executor, physical_plan)
+        let _running = queued.start();
+        execute_and_collect(query_ctx, self.executor, physical_plan, sink, limiter)
             .await
             .context(Select)
     }
 }
 
+/// Where the results of the query go.
+enum ResultSink {
+    /// Collect the results into the output.
+    Collect,
+    /// Offload the results to the path, and the output is the manifest.
+    Offload(ResultOffloaderRef, String),
+    /// Send the results as they are produced, and the output is empty.
+    Stream(ResultSender),
+}
+
 async fn execute_and_collect(
     query_ctx: QueryContextRef,
     executor: ExecutorRef,
     physical_plan: PhysicalPlanRef,
+    sink: ResultSink,
+    mut limiter: ResultLimiter,
 ) -> Result<Output> {
-    let record_batch_stream = executor
+    let mut record_batch_stream = executor
         .execute(&query_ctx, physical_plan)
         .await
         .box_err()
@@ -163,14 +225,72 @@ async fn execute_and_collect(
             msg: "failed to execute physical plan",
         })?;
 
-    let record_batches =
-        record_batch_stream
-            .try_collect()
-            .await
-            .box_err()
-            .context(ExecutePlan {
-                msg: "failed to collect execution results",
-            })?;
+    match sink {
+        ResultSink::Collect => (),
+        // The offloaded results are not limited as they are not returned.
+        ResultSink::Offload(offloader, path) => {
+            let manifest = offloader
+                .offload(&query_ctx.request_id, &path, record_batch_stream)
+                .await
+                .context(Offload)?;
+            return Ok(Output::Records(vec![manifest]));
+        }
+        ResultSink::Stream(sender) => {
+            while let Some(record_batch) = record_batch_stream
+                .try_next()
+                .await
+                .box_err()
+                .context(ExecutePlan {
+                    msg: "failed to fetch execution results",
+                })?
+            {
+                let admitted = limiter.admit(record_batch).context(LimitResult)?;
+                let (record_batch, truncated) = match admitted {
+                    Admitted::All(v) => (v, false),
+                    Admitted::Truncated(v) => (v, true),
+                };
+                // The client is gone, stop executing the query.
+                if sender.send(record_batch).await.is_err() {
+                    return ResultReceiverDropped.fail();
+                }
+                if truncated {
+                    warn_truncated(&query_ctx, &limiter);
+                    break;
+                }
+            }
+            return Ok(Output::Records(Vec::new()));
+        }
+    }
+
+    let mut record_batches = Vec::new();
+    while let Some(record_batch) = record_batch_stream
+        .try_next()
+        .await
+        .box_err()
+        .context(ExecutePlan {
+            msg: "failed to collect execution results",
+        })?
+    {
+        match limiter.admit(record_batch).context(LimitResult)? {
+            Admitted::All(v) => record_batches.push(v),
+            Admitted::Truncated(v) => {
+                record_batches.push(v);
+                warn_truncated(&query_ctx, &limiter);
+                break;
+            }
+        }
+    }
 
     Ok(Output::Records(record_batches))
 }
+
+fn warn_truncated(query_ctx: &QueryContextRef, limiter: &ResultLimiter) {
+    query_ctx.warnings.record(
+        WarningKind::ImplicitLimit,
+        format!(
+            "the results are truncated to {} rows by the default limit, add LIMIT to the \
+             query to get more rows",
+            limiter.rows()
+        ),
+    );
+}
@@ -18,7 +18,7 @@
 use std::{convert::TryInto, sync::Arc};
 
 use arrow::{
-    array::StringArray,
+    array::{BooleanArray, StringArray, UInt64Array},
     datatypes::{DataType, Field, Schema as DataSchema},
     record_batch::RecordBatch,
 };
@@ -27,18 +27,21 @@ use catalog::{manager::ManagerRef, schema::Schema, Catalog};
 use macros::define_result;
 use query_frontend::{
     ast::ShowCreateObject,
-    plan::{QueryType, ShowCreatePlan, ShowPlan, ShowTablesPlan},
+    plan::{QueryType, ShowCreatePlan, ShowPartitionsPlan, ShowPlan, ShowTablesPlan},
 };
 use regex::Regex;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use table_engine::partition;
 
 use crate::{
     context::Context,
     interpreter::{
         Interpreter, InterpreterPtr, Output, Result as InterpreterResult, ShowCreateTable,
-        ShowDatabases, ShowTables,
+        ShowDatabases, ShowPartitions, ShowProcessList, ShowSources, ShowTables,
     },
+    query_tracker::QueryTrackerRef,
     show_create::ShowCreateInterpreter,
+    source::SourceManagerRef,
 };
 
 const SHOW_TABLES_COLUMN_SCHEMA: &str = "Tables";
@@ -86,6 +89,9 @@ pub enum Error {
     #[snafu(display("Failed to fetch schema, err:{}", source))]
     FetchSchema { source: catalog::Error },
 
+    #[snafu(display("Table is not partitioned, table:{}.\nBacktrace\n:{}", name, backtrace))]
+    NotPartitioned { name: String, backtrace: Backtrace },
+
     #[snafu(display("Invalid regexp, err:{}.\nBacktrace\n:{}", source, backtrace))]
     InvalidRegexp {
         source: regex::Error,
@@ -99,14 +105,24 @@ pub struct ShowInterpreter {
     ctx: Context,
     plan: ShowPlan,
     catalog_manager: ManagerRef,
+    query_tracker: QueryTrackerRef,
+    source_manager: Option<SourceManagerRef>,
 }
 
 impl ShowInterpreter {
-    pub fn create(ctx: Context, plan: ShowPlan, catalog_manager: ManagerRef) -> InterpreterPtr {
+    pub fn create(
+        ctx: Context,
+        plan: ShowPlan,
+        catalog_manager: ManagerRef,
+        query_tracker: QueryTrackerRef,
+        source_manager: Option<SourceManagerRef>,
+    ) -> InterpreterPtr {
         Box::new(Self {
             ctx,
             plan,
             catalog_manager,
+            query_tracker,
+            source_manager,
         })
     }
 }
@@ -205,6 +221,141 @@ impl ShowInterpreter {
     }
 }
 
+impl ShowInterpreter {
+    fn show_process_list(query_tracker: QueryTrackerRef) -> Result<Output> {
+        let queries = query_tracker.list();
+
+        let schema = DataSchema::new(vec![
+            Field::new("id", DataType::UInt64, false),
+            Field::new("request_id", DataType::Utf8, false),
+            Field::new("schema", DataType::Utf8, false),
+            Field::new("elapsed_ms", DataType::UInt64, false),
+            Field::new("state", DataType::Utf8, false),
+            Field::new("scanned_rows", DataType::UInt64, false),
+            Field::new("scanned_bytes", DataType::UInt64, false),
+            Field::new("query", DataType::Utf8, false),
+        ]);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(UInt64Array::from_iter_values(queries.iter().map(|q| q.id))),
+                Arc::new(StringArray::from_iter_values(
+                    queries.iter().map(|q| &q.request_id),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    queries.iter().map(|q| &q.schema),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    queries.iter().map(|q| q.elapsed().as_millis() as u64),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    queries.iter().map(|q| q.state()),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    queries.iter().map(|q| q.scan_usage.rows()),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    queries.iter().map(|q| q.scan_usage.bytes()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    queries.iter().map(|q| &q.query),
+                )),
+            ],
+        )
+        .context(CreateRecordBatch)?;
+
+        let record_batch = record_batch.try_into().context(ToCommonRecordType)?;
+
+        Ok(Output::Records(vec![record_batch]))
+    }
+}
+
+impl ShowInterpreter {
+    fn show_sources(source_manager: Option<SourceManagerRef>) -> Result<Output> {
+        let sources = source_manager
+            .map(|manager| manager.list_sources())
+            .unwrap_or_default();
+
+        let schema = DataSchema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("schema", DataType::Utf8, false),
+            Field::new("table", DataType::Utf8, false),
+            Field::new("topic", DataType::Utf8, false),
+            Field::new("checkpoints", DataType::Utf8, false),
+            Field::new("active", DataType::Boolean, false),
+            Field::new("last_error", DataType::Utf8, true),
+        ]);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    sources.iter().map(|s| &s.name),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    sources.iter().map(|s| &s.schema),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    sources.iter().map(|s| &s.table),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    sources.iter().map(|s| &s.topic),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    sources.iter().map(|s| &s.checkpoints),
+                )),
+                Arc::new(BooleanArray::from_iter(
+                    sources.iter().map(|s| Some(s.active)),
+                )),
+                Arc::new(StringArray::from_iter(
+                    sources.iter().map(|s| s.last_error.as_deref()),
+                )),
+            ],
+        )
+        .context(CreateRecordBatch)?;
+
+        let record_batch = record_batch.try_into().context(ToCommonRecordType)?;
+
+        Ok(Output::Records(vec![record_batch]))
+    }
+}
+
+impl ShowInterpreter {
+    fn show_partitions(plan: ShowPartitionsPlan) -> Result<Output> {
+        let table = plan.table;
+        let partition_info = table.partition_info().context(NotPartitioned {
+            name: table.name(),
+        })?;
+        let rule = ShowCreateInterpreter::render_partition_info(Some(partition_info.clone()));
+        let definitions = partition_info.get_definitions();
+        let sub_tables = definitions
+            .iter()
+            .map(|d| partition::format_sub_partition_table_name(table.name(), &d.name));
+
+        let schema = DataSchema::new(vec![
+            Field::new("partition", DataType::Utf8, false),
+            Field::new("sub_table", DataType::Utf8, false),
+            Field::new("rule", DataType::Utf8, false),
+        ]);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    definitions.iter().map(|d| &d.name),
+                )),
+                Arc::new(StringArray::from_iter_values(sub_tables)),
+                Arc::new(StringArray::from_iter_values(
+                    definitions.iter().map(|_| rule.trim()),
+                )),
+            ],
+        )
+        .context(CreateRecordBatch)?;
+
+        let record_batch = record_batch.try_into().context(ToCommonRecordType)?;
+
+        Ok(Output::Records(vec![record_batch]))
+    }
+}
+
 fn to_pattern_re(pattern: &str) -> Result<Regex> {
     // In MySQL
     // `_` match any single character
@@ -227,6 +378,11 @@ impl Interpreter for ShowInterpreter {
             ShowPlan::ShowDatabase => {
                 Self::show_databases(self.ctx, self.catalog_manager).context(ShowDatabases)
             }
+            ShowPlan::ShowProcessList => {
+                Self::show_process_list(self.query_tracker).context(ShowProcessList)
+            }
+            ShowPlan::ShowSources => Self::show_sources(self.source_manager).context(ShowSources),
+            ShowPlan::ShowPartitions(t) => Self::show_partitions(t).context(ShowPartitions),
         }
     }
 }
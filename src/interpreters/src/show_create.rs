@@ -78,7 +78,8 @@ impl ShowCreateInterpreter {
         Ok(vec![record_batch])
     }
 
-    fn render_table_sql(table_ref: TableRef) -> String {
+    /// Render the `CREATE TABLE` statement of the table.
+    pub fn render_table_sql(table_ref: TableRef) -> String {
         // TODO(boyan) pretty output
         format!(
             "CREATE TABLE `{}` ({}){} ENGINE={}{}",
@@ -115,7 +116,15 @@ impl ShowCreateInterpreter {
             }
 
             if !col.comment.is_empty() {
-                res += format!(" COMMENT '{}'", col.comment).as_str();
+                res += format!(" COMMENT {}", quote_string(&col.comment)).as_str();
+            }
+
+            if !col.display_name.is_empty() {
+                res += format!(" DISPLAY_NAME {}", quote_string(&col.display_name)).as_str();
+            }
+
+            if !col.unit.is_empty() {
+                res += format!(" UNIT {}", quote_string(&col.unit)).as_str();
             }
             res += ", ";
         }
@@ -126,7 +135,7 @@ impl ShowCreateInterpreter {
         res
     }
 
-    fn render_partition_info(partition_info: Option<PartitionInfo>) -> String {
+    pub(crate) fn render_partition_info(partition_info: Option<PartitionInfo>) -> String {
         if partition_info.is_none() {
             return String::new();
         }
@@ -171,6 +180,14 @@ impl ShowCreateInterpreter {
             PartitionInfo::Random(v) => {
                 format!(" PARTITION BY RANDOM PARTITIONS {}", v.definitions.len())
             }
+            PartitionInfo::Time(v) => {
+                format!(
+                    " PARTITION BY TIME({}, {}) PARTITIONS {}",
+                    v.column,
+                    quote_string(&v.interval.to_string()),
+                    v.definitions.len()
+                )
+            }
         }
     }
 
@@ -189,18 +206,31 @@ impl ShowCreateInterpreter {
     }
 }
 
+/// Quote the string literal with its quotes and backslashes escaped, so the
+/// rendered DDL can be parsed back.
+fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''"))
+}
+
 #[cfg(test)]
 mod test {
-    use std::ops::Add;
+    use std::{ops::Add, time::Duration};
 
     use datafusion::logical_expr::col;
     use datafusion_proto::bytes::Serializeable;
     use table_engine::partition::{
-        HashPartitionInfo, KeyPartitionInfo, PartitionDefinition, PartitionInfo,
+        HashPartitionInfo, KeyPartitionInfo, PartitionDefinition, PartitionInfo, TimePartitionInfo,
     };
+    use time_ext::ReadableDuration;
 
     use super::*;
 
+    #[test]
+    fn test_quote_string() {
+        assert_eq!("'ms'", quote_string("ms"));
+        assert_eq!("'Bob''s \\\\ value'", quote_string("Bob's \\ value"));
+    }
+
     #[test]
     fn test_render_hash_partition_info() {
         let expr = col("col1").add(col("col2"));
@@ -252,4 +282,28 @@ mod test {
             ShowCreateInterpreter::render_partition_info(Some(partition_info))
         );
     }
+
+    #[test]
+    fn test_render_time_partition_info() {
+        let partition_info = PartitionInfo::Time(TimePartitionInfo {
+            definitions: vec![
+                PartitionDefinition {
+                    name: "0".to_string(),
+                    origin_name: None,
+                },
+                PartitionDefinition {
+                    name: "1".to_string(),
+                    origin_name: None,
+                },
+            ],
+            column: "ts".to_string(),
+            interval: ReadableDuration(Duration::from_secs(86400)),
+        });
+
+        let expected = " PARTITION BY TIME(ts, '1d') PARTITIONS 2".to_string();
+        assert_eq!(
+            expected,
+            ShowCreateInterpreter::render_partition_info(Some(partition_info))
+        );
+    }
 }
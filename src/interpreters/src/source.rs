@@ -0,0 +1,173 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreters for the ingestion sources
+//!
+//! The sources are managed by the [SourceManager], which consumes the messages
+//! in background and writes them into the tables.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::GenericError;
+use macros::define_result;
+use query_frontend::plan::{CreateSourcePlan, DropSourcePlan, SourceDef};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    context::Context,
+    interpreter::{
+        CreateSource, DropSource, Interpreter, InterpreterPtr, Output, Result as InterpreterResult,
+    },
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Source is not supported.\nBacktrace:\n{}", backtrace))]
+    SourceNotSupported { backtrace: Backtrace },
+
+    #[snafu(display("Source already exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    SourceExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Source not found, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    SourceNotFound { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to manage source, name:{}, err:{}", name, source))]
+    ManageSource { name: String, source: GenericError },
+}
+
+define_result!(Error);
+
+/// Status of a running source.
+#[derive(Debug, Clone)]
+pub struct SourceStatus {
+    pub name: String,
+    pub schema: String,
+    pub table: String,
+    pub topic: String,
+    /// Offsets of the last checkpointed messages of the partitions, e.g.
+    /// `0:10,1:20`
+    pub checkpoints: String,
+    /// Whether the source is consumed by this node, only the node owning the
+    /// checkpoint table consumes the sources
+    pub active: bool,
+    /// Error of the last failed consumption, `None` if the source is healthy
+    pub last_error: Option<String>,
+}
+
+/// Manager of the ingestion sources.
+#[async_trait]
+pub trait SourceManager: Send + Sync {
+    fn contains(&self, name: &str) -> bool;
+
+    /// Persist the source and start consuming it.
+    async fn create_source(
+        &self,
+        schema: &str,
+        source: SourceDef,
+    ) -> std::result::Result<(), GenericError>;
+
+    /// Stop consuming the source and remove it.
+    async fn drop_source(&self, name: &str) -> std::result::Result<(), GenericError>;
+
+    fn list_sources(&self) -> Vec<SourceStatus>;
+}
+
+pub type SourceManagerRef = Arc<dyn SourceManager>;
+
+pub struct CreateSourceInterpreter {
+    ctx: Context,
+    plan: CreateSourcePlan,
+    source_manager: Option<SourceManagerRef>,
+}
+
+impl CreateSourceInterpreter {
+    pub fn create(
+        ctx: Context,
+        plan: CreateSourcePlan,
+        source_manager: Option<SourceManagerRef>,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            ctx,
+            plan,
+            source_manager,
+        })
+    }
+
+    async fn execute_create(self: Box<Self>) -> Result<Output> {
+        let source_manager = self.source_manager.context(SourceNotSupported)?;
+        let name = self.plan.source.name.clone();
+        if source_manager.contains(&name) {
+            ensure!(self.plan.if_not_exists, SourceExists { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        source_manager
+            .create_source(self.ctx.default_schema(), self.plan.source)
+            .await
+            .context(ManageSource { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for CreateSourceInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_create().await.context(CreateSource)
+    }
+}
+
+pub struct DropSourceInterpreter {
+    plan: DropSourcePlan,
+    source_manager: Option<SourceManagerRef>,
+}
+
+impl DropSourceInterpreter {
+    pub fn create(
+        plan: DropSourcePlan,
+        source_manager: Option<SourceManagerRef>,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            plan,
+            source_manager,
+        })
+    }
+
+    async fn execute_drop(self: Box<Self>) -> Result<Output> {
+        let source_manager = self.source_manager.context(SourceNotSupported)?;
+        let name = self.plan.name;
+        if !source_manager.contains(&name) {
+            ensure!(self.plan.if_exists, SourceNotFound { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        source_manager
+            .drop_source(&name)
+            .await
+            .context(ManageSource { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for DropSourceInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_drop().await.context(DropSource)
+    }
+}
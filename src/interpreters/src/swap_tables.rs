@@ -0,0 +1,100 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for swap tables statements
+
+use async_trait::async_trait;
+use catalog::manager::ManagerRef;
+use logger::info;
+use macros::define_result;
+use query_frontend::plan::SwapTablesPlan;
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    context::Context,
+    interpreter::{Interpreter, InterpreterPtr, Output, Result as InterpreterResult, SwapTables},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Catalog does not exist, catalog:{}.\nBacktrace\n:{}", name, backtrace))]
+    CatalogNotExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Schema does not exist, schema:{}.\nBacktrace\n:{}", name, backtrace))]
+    SchemaNotExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to fetch catalog, err:{}", source))]
+    FetchCatalog { source: catalog::manager::Error },
+
+    #[snafu(display("Failed to fetch schema, err:{}", source))]
+    FetchSchema { source: catalog::Error },
+
+    #[snafu(display("Failed to swap tables, err:{}", source))]
+    SchemaSwapTables { source: catalog::schema::Error },
+}
+
+define_result!(Error);
+
+/// Swap tables interpreter
+pub struct SwapTablesInterpreter {
+    ctx: Context,
+    plan: SwapTablesPlan,
+    catalog_manager: ManagerRef,
+}
+
+impl SwapTablesInterpreter {
+    pub fn create(
+        ctx: Context,
+        plan: SwapTablesPlan,
+        catalog_manager: ManagerRef,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            ctx,
+            plan,
+            catalog_manager,
+        })
+    }
+
+    async fn execute_swap(self: Box<Self>) -> Result<Output> {
+        let catalog_name = self.ctx.default_catalog();
+        let schema_name = self.ctx.default_schema();
+        let schema = self
+            .catalog_manager
+            .catalog_by_name(catalog_name)
+            .context(FetchCatalog)?
+            .context(CatalogNotExists { name: catalog_name })?
+            .schema_by_name(schema_name)
+            .context(FetchSchema)?
+            .context(SchemaNotExists { name: schema_name })?;
+
+        let SwapTablesPlan { table, other } = self.plan;
+        info!("Swap tables, schema:{schema_name}, table:{table}, other:{other}");
+        schema
+            .swap_tables(&table, &other)
+            .await
+            .context(SchemaSwapTables)?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for SwapTablesInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_swap().await.context(SwapTables)
+    }
+}
@@ -20,7 +20,7 @@ use std::sync::Arc;
 use analytic_engine::tests::util::{EngineBuildContext, RocksDBEngineBuildContext, TestEnv};
 use catalog::{
     consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA},
-    manager::ManagerRef,
+    manager::{Manager, ManagerRef},
     table_operator::TableOperator,
 };
 use catalog_impls::table_based::TableBasedManager;
@@ -39,7 +39,10 @@ use crate::{
     context::Context,
     factory::Factory,
     interpreter::{Output, Result},
+    query_tracker::QueryTracker,
+    resync::ResyncRequests,
     table_manipulator::{catalog_based::TableManipulatorImpl, TableManipulatorRef},
+    RecordBatchVec,
 };
 
 async fn build_catalog_manager(analytic: TableEngineRef) -> TableBasedManager {
@@ -90,6 +93,14 @@ where
             self.engine(),
             self.table_manipulator.clone(),
             self.read_runtime.clone(),
+            None,
+            Arc::new(QueryTracker::default()),
+            Arc::new(ResyncRequests::default()),
+            None,
+            None,
+            None,
+            None,
+            None,
         )
     }
 
@@ -243,6 +254,14 @@ where
             self.engine(),
             table_manipulator.clone(),
             self.read_runtime.clone(),
+            None,
+            Arc::new(QueryTracker::default()),
+            Arc::new(ResyncRequests::default()),
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         let insert_sql = "INSERT INTO test_missing_columns_table(key1, key2, field4) VALUES('tagk', 1638428434000, 1), ('tagk2', 1638428434000, 10);";
 
@@ -264,6 +283,14 @@ where
             self.engine(),
             table_manipulator,
             self.read_runtime.clone(),
+            None,
+            Arc::new(QueryTracker::default()),
+            Arc::new(ResyncRequests::default()),
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         let ctx = Context::builder(RequestId::next_id(), None)
             .default_catalog_and_schema(DEFAULT_CATALOG.to_string(), DEFAULT_SCHEMA.to_string())
@@ -313,7 +340,77 @@ where
         test_util::assert_record_batches_eq(&expected, records);
     }
 
+    async fn test_show_partitions(&self) {
+        let sql = "SHOW PARTITIONS time_partitioned";
+        let output = self.sql_to_output(sql).await.unwrap();
+        let records = output.try_into().unwrap();
+        let expected = vec![
+            "+-----------+----------------------+--------------------------------------------+",
+            "| partition | sub_table            | rule                                       |",
+            "+-----------+----------------------+--------------------------------------------+",
+            "| 0         | __time_partitioned_0 | PARTITION BY TIME(time, '1d') PARTITIONS 4 |",
+            "| 1         | __time_partitioned_1 | PARTITION BY TIME(time, '1d') PARTITIONS 4 |",
+            "| 2         | __time_partitioned_2 | PARTITION BY TIME(time, '1d') PARTITIONS 4 |",
+            "| 3         | __time_partitioned_3 | PARTITION BY TIME(time, '1d') PARTITIONS 4 |",
+            "+-----------+----------------------+--------------------------------------------+",
+        ];
+        test_util::assert_record_batches_eq(&expected, records);
+
+        // The table is not partitioned.
+        let sql = "SHOW PARTITIONS test_table";
+        let res = self.sql_to_output(sql).await;
+        assert!(format!("{res:?}").contains("NotPartitioned"));
+    }
+
     async fn test_alter_table(&self) {
+        // The dry run only reports the changes, so the column can be added after it.
+        let sql = "alter table test_table add column add_col string dry run";
+        let output = self.sql_to_output(sql).await.unwrap();
+        let records: RecordBatchVec = output.try_into().unwrap();
+        assert_eq!(2, records[0].num_rows());
+
+        let sql = "alter table test_table drop column field1 dry run";
+        let output = self.sql_to_output(sql).await.unwrap();
+        let records: RecordBatchVec = output.try_into().unwrap();
+        assert_eq!(2, records[0].num_rows());
+
+        // The renamed column is reported as a dropped column and an added column.
+        let sql = "alter table test_table rename column field1 to field5 dry run";
+        let output = self.sql_to_output(sql).await.unwrap();
+        let records: RecordBatchVec = output.try_into().unwrap();
+        assert_eq!(3, records[0].num_rows());
+
+        let sql = "alter table test_table modify column field1 display_name 'Field' unit 'ms' dry run";
+        let output = self.sql_to_output(sql).await.unwrap();
+        let records: RecordBatchVec = output.try_into().unwrap();
+        assert_eq!(2, records[0].num_rows());
+
+        for sql in [
+            "alter table test_table drop column key1 dry run",
+            "alter table test_table drop column field4 dry run",
+            "alter table test_table drop column not_exist dry run",
+            "alter table test_table rename column field1 to field2 dry run",
+            "alter table test_table modify column not_exist unit 'ms' dry run",
+        ] {
+            assert!(self.sql_to_output(sql).await.is_err(), "sql:{sql}");
+        }
+
+        let sql = "alter table test_table modify SETTING ttl='9d' dry run";
+        let output = self.sql_to_output(sql).await.unwrap();
+        let records = output.try_into().unwrap();
+        let expected = vec![
+            "+------+--------+-------+---------------------------------------------------+",
+            "| item | before | after | rewrite_estimate                                  |",
+            "+------+--------+-------+---------------------------------------------------+",
+            "| ttl  | 7d     | 9d    | expired sst files are purged by later compactions |",
+            "+------+--------+-------+---------------------------------------------------+",
+        ];
+        test_util::assert_record_batches_eq(&expected, records);
+
+        // The dry run validates the options as the alter does.
+        let sql = "alter table test_table modify SETTING ttl='invalid' dry run";
+        assert!(self.sql_to_output(sql).await.is_err());
+
         let sql = "alter table test_table add column add_col string";
         let output = self.sql_to_output(sql).await.unwrap();
         assert!(
@@ -329,6 +426,57 @@ where
         );
     }
 
+    async fn test_alter_schema(&self) {
+        let sql = "alter schema public modify setting ttl='3d', update_mode='append'";
+        let output = self.sql_to_output(sql).await.unwrap();
+        assert!(
+            matches!(output, Output::AffectedRows(v) if v == 0),
+            "alter schema should success"
+        );
+
+        // The invalid options are rejected and the defaults are kept.
+        let sql = "alter schema public modify setting ttl='invalid'";
+        assert!(self.sql_to_output(sql).await.is_err());
+
+        // The options are persisted and loaded by a new catalog manager.
+        let catalog_manager = build_catalog_manager(self.engine()).await;
+        let options = catalog_manager
+            .catalog_by_name(DEFAULT_CATALOG)
+            .unwrap()
+            .unwrap()
+            .schema_by_name(DEFAULT_SCHEMA)
+            .unwrap()
+            .unwrap()
+            .default_table_options();
+        assert_eq!("3d", options["ttl"]);
+        assert_eq!("append", options["update_mode"]);
+
+        // The options absent in the create table statement are inherited.
+        let sql =
+            "CREATE TABLE test_schema_options(c1 string tag not null, ts timestamp not null, \
+        timestamp key(ts), primary key(c1, ts)) ENGINE=Analytic WITH (update_mode='overwrite')";
+        self.sql_to_output(sql).await.unwrap();
+        let table = self
+            .catalog_manager
+            .catalog_by_name(DEFAULT_CATALOG)
+            .unwrap()
+            .unwrap()
+            .schema_by_name(DEFAULT_SCHEMA)
+            .unwrap()
+            .unwrap()
+            .table_by_name("test_schema_options")
+            .unwrap()
+            .unwrap();
+        let options = table.options();
+        assert_eq!("3d", options["ttl"]);
+        assert_eq!("OVERWRITE", options["update_mode"]);
+
+        let sql = "alter schema public modify setting ttl='', update_mode=''";
+        self.sql_to_output(sql).await.unwrap();
+        let sql = "drop table test_schema_options";
+        self.sql_to_output(sql).await.unwrap();
+    }
+
     async fn test_drop_table(&self) {
         let sql = "drop table test_table";
         let output = self.sql_to_output(sql).await.unwrap();
@@ -396,6 +544,9 @@ async fn test_interpreters<T: EngineBuildContext>(
             function_registry.to_df_function_registry(),
             remote_engine,
             catalog_manager.clone(),
+            Default::default(),
+            None,
+            Default::default(),
         )
         .unwrap(),
     );
@@ -415,7 +566,9 @@ async fn test_interpreters<T: EngineBuildContext>(
     env.test_insert_table().await;
     env.test_select_table().await;
     env.test_show_create_table().await;
+    env.test_show_partitions().await;
     env.test_alter_table().await;
+    env.test_alter_schema().await;
     env.test_drop_table().await;
     env.test_insert_table_with_missing_columns().await;
     env.test_enable_partition_table_access().await;
@@ -0,0 +1,212 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Interpreters for the user management statements
+//!
+//! The users and their grants are kept by the [UserManager], which is only
+//! available when the authentication is enabled.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::GenericError;
+use macros::define_result;
+use query_frontend::plan::{CreateUserPlan, DropUserPlan, GrantPlan, RevokePlan, UserRole};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::interpreter::{
+    CreateUser, DropUser, Grant, Interpreter, InterpreterPtr, Output, Result as InterpreterResult,
+    Revoke,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "User management is not supported, the authentication is disabled.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    UserNotSupported { backtrace: Backtrace },
+
+    #[snafu(display("User already exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    UserExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("User not found, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    UserNotFound { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to manage user, name:{}, err:{}", name, source))]
+    ManageUser { name: String, source: GenericError },
+}
+
+define_result!(Error);
+
+/// Manager of the users and their grants.
+#[async_trait]
+pub trait UserManager: Send + Sync {
+    fn contains(&self, name: &str) -> bool;
+
+    /// Persist the user, who has no grant after created.
+    async fn create_user(
+        &self,
+        name: &str,
+        password: &str,
+    ) -> std::result::Result<(), GenericError>;
+
+    /// Remove the user and all the grants of it.
+    async fn drop_user(&self, name: &str) -> std::result::Result<(), GenericError>;
+
+    /// Grant the role on the catalog to the user, replacing the former role
+    /// on the catalog.
+    async fn grant(
+        &self,
+        name: &str,
+        catalog: &str,
+        role: UserRole,
+    ) -> std::result::Result<(), GenericError>;
+
+    /// Revoke the role on the catalog from the user.
+    async fn revoke(&self, name: &str, catalog: &str) -> std::result::Result<(), GenericError>;
+}
+
+pub type UserManagerRef = Arc<dyn UserManager>;
+
+pub struct CreateUserInterpreter {
+    plan: CreateUserPlan,
+    manager: Option<UserManagerRef>,
+}
+
+impl CreateUserInterpreter {
+    pub fn create(plan: CreateUserPlan, manager: Option<UserManagerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_create(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(UserNotSupported)?;
+        let name = self.plan.name;
+        if manager.contains(&name) {
+            ensure!(self.plan.if_not_exists, UserExists { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        manager
+            .create_user(&name, &self.plan.password)
+            .await
+            .context(ManageUser { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for CreateUserInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_create().await.context(CreateUser)
+    }
+}
+
+pub struct DropUserInterpreter {
+    plan: DropUserPlan,
+    manager: Option<UserManagerRef>,
+}
+
+impl DropUserInterpreter {
+    pub fn create(plan: DropUserPlan, manager: Option<UserManagerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_drop(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(UserNotSupported)?;
+        let name = self.plan.name;
+        if !manager.contains(&name) {
+            ensure!(self.plan.if_exists, UserNotFound { name });
+            return Ok(Output::AffectedRows(0));
+        }
+
+        manager.drop_user(&name).await.context(ManageUser { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for DropUserInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_drop().await.context(DropUser)
+    }
+}
+
+pub struct GrantInterpreter {
+    plan: GrantPlan,
+    manager: Option<UserManagerRef>,
+}
+
+impl GrantInterpreter {
+    pub fn create(plan: GrantPlan, manager: Option<UserManagerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_grant(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(UserNotSupported)?;
+        let name = self.plan.user;
+        ensure!(manager.contains(&name), UserNotFound { name });
+
+        manager
+            .grant(&name, &self.plan.catalog, self.plan.role)
+            .await
+            .context(ManageUser { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for GrantInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_grant().await.context(Grant)
+    }
+}
+
+pub struct RevokeInterpreter {
+    plan: RevokePlan,
+    manager: Option<UserManagerRef>,
+}
+
+impl RevokeInterpreter {
+    pub fn create(plan: RevokePlan, manager: Option<UserManagerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, manager })
+    }
+
+    async fn execute_revoke(self: Box<Self>) -> Result<Output> {
+        let manager = self.manager.context(UserNotSupported)?;
+        let name = self.plan.user;
+        ensure!(manager.contains(&name), UserNotFound { name });
+
+        manager
+            .revoke(&name, &self.plan.catalog)
+            .await
+            .context(ManageUser { name })?;
+
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for RevokeInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_revoke().await.context(Revoke)
+    }
+}
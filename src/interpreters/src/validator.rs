@@ -92,15 +92,48 @@ impl Validator {
                 is_sub_table!(plan.table.name())
             }
 
-            Plan::Show(show_plan) => {
-                if let ShowPlan::ShowCreatePlan(show_create_plan) = show_plan {
-                    is_sub_table!(show_create_plan.table.name())
-                } else {
-                    false
-                }
+            Plan::AnalyzeTable(plan) => {
+                is_sub_table!(plan.table.name())
+            }
+
+            Plan::CompactTable(plan) => {
+                is_sub_table!(plan.table.name())
             }
 
-            Plan::Exists(_) => false,
+            Plan::ResyncTable(plan) => {
+                is_sub_table!(plan.table.name())
+            }
+
+            Plan::SwapTables(plan) => is_sub_table!(&plan.table) || is_sub_table!(&plan.other),
+
+            Plan::CreateContinuousQuery(plan) => {
+                is_sub_table!(&plan.continuous_query.source_table)
+                    || is_sub_table!(&plan.continuous_query.target_table)
+            }
+
+            Plan::Show(show_plan) => match show_plan {
+                ShowPlan::ShowCreatePlan(show_create_plan) => {
+                    is_sub_table!(show_create_plan.table.name())
+                }
+                ShowPlan::ShowPartitions(show_partitions_plan) => {
+                    is_sub_table!(show_partitions_plan.table.name())
+                }
+                _ => false,
+            },
+
+            Plan::AlterSchema(_)
+            | Plan::Exists(_)
+            | Plan::KillQuery(_)
+            | Plan::CreateSource(_)
+            | Plan::DropSource(_)
+            | Plan::AlterSystem(_)
+            | Plan::DropContinuousQuery(_)
+            | Plan::CreateUser(_)
+            | Plan::DropUser(_)
+            | Plan::Grant(_)
+            | Plan::Revoke(_)
+            | Plan::CreateFunction(_)
+            | Plan::DropFunction(_) => false,
         }
     }
 }
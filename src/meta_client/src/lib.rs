@@ -90,6 +90,12 @@ pub enum Error {
         msg: String,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Incompatible protocol of the meta, addr:{}, err:{}", addr, source))]
+    IncompatibleProtocol {
+        addr: String,
+        source: common_types::protocol::Error,
+    },
 }
 
 define_result!(Error);
@@ -18,6 +18,9 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use common_types::protocol::{
+    Protocol, ProtocolConfig, PROTOCOL_FEATURES_KEY, PROTOCOL_VERSION_KEY,
+};
 use generic_error::BoxError;
 use horaedbproto::{
     common::ResponseHeader,
@@ -36,7 +39,8 @@ use crate::{
         RouteTablesRequest, RouteTablesResponse, ShardInfo,
     },
     BadResponse, FailAllocSchemaId, FailConnect, FailCreateTable, FailDropTable, FailGetTables,
-    FailRouteTables, FailSendHeartbeat, MetaClient, MetaClientRef, MissingHeader, Result,
+    FailRouteTables, FailSendHeartbeat, IncompatibleProtocol, MetaClient, MetaClientRef,
+    MissingHeader, Result,
 };
 
 type MetaServiceGrpcClient = MetaRpcServiceClient<tonic::transport::Channel>;
@@ -49,6 +53,8 @@ pub struct MetaClientConfig {
     pub lease: ReadableDuration,
     pub timeout: ReadableDuration,
     pub cq_count: usize,
+    /// Config of the protocol talking with the meta
+    pub protocol: ProtocolConfig,
 }
 
 impl Default for MetaClientConfig {
@@ -59,6 +65,7 @@ impl Default for MetaClientConfig {
             lease: ReadableDuration::secs(10),
             timeout: ReadableDuration::secs(5),
             cq_count: 8,
+            protocol: ProtocolConfig::default(),
         }
     }
 }
@@ -68,6 +75,8 @@ pub struct MetaClientImpl {
     config: MetaClientConfig,
     node_meta_info: NodeMetaInfo,
     client: MetaServiceGrpcClient,
+    /// Protocol of this node.
+    protocol: Protocol,
 }
 
 impl MetaClientImpl {
@@ -87,10 +96,12 @@ impl MetaClientImpl {
                 })?
         };
 
+        let protocol = Protocol::local(&config.protocol);
         Ok(Self {
             config,
             node_meta_info,
             client,
+            protocol,
         })
     }
 
@@ -105,6 +116,39 @@ impl MetaClientImpl {
     fn client(&self) -> MetaServiceGrpcClient {
         self.client.clone()
     }
+
+    /// Build the rpc request carrying the protocol of this node.
+    fn new_request<T>(&self, message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        let metadata = request.metadata_mut();
+        metadata.insert(
+            PROTOCOL_VERSION_KEY,
+            self.protocol.encode_version().parse().unwrap(),
+        );
+        metadata.insert(
+            PROTOCOL_FEATURES_KEY,
+            self.protocol.encode_features().parse().unwrap(),
+        );
+        request
+    }
+
+    /// Ensure the meta speaks a protocol compatible with this node, and take
+    /// the message out of the `response`.
+    fn check_protocol<T>(&self, response: tonic::Response<T>) -> Result<T> {
+        let metadata = response.metadata();
+        let get = |key| metadata.get(key).and_then(|value| value.to_str().ok());
+        let peer = Protocol::decode(get(PROTOCOL_VERSION_KEY), get(PROTOCOL_FEATURES_KEY))
+            .context(IncompatibleProtocol {
+                addr: &self.config.meta_addr,
+            })?;
+        self.protocol
+            .negotiate(&peer)
+            .context(IncompatibleProtocol {
+                addr: &self.config.meta_addr,
+            })?;
+
+        Ok(response.into_inner())
+    }
 }
 
 #[async_trait]
@@ -117,11 +161,11 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .alloc_schema_id(pb_req)
+            .alloc_schema_id(self.new_request(pb_req))
             .await
             .box_err()
-            .context(FailAllocSchemaId)?
-            .into_inner();
+            .context(FailAllocSchemaId)
+            .and_then(|resp| self.check_protocol(resp))?;
 
         info!(
             "Meta client finish allocating schema id, resp:{:?}",
@@ -140,11 +184,11 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .create_table(pb_req)
+            .create_table(self.new_request(pb_req))
             .await
             .box_err()
-            .context(FailCreateTable)?
-            .into_inner();
+            .context(FailCreateTable)
+            .and_then(|resp| self.check_protocol(resp))?;
 
         info!("Meta client finish creating table, resp:{:?}", pb_resp);
 
@@ -160,11 +204,11 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .drop_table(pb_req)
+            .drop_table(self.new_request(pb_req))
             .await
             .box_err()
-            .context(FailDropTable)?
-            .into_inner();
+            .context(FailDropTable)
+            .and_then(|resp| self.check_protocol(resp))?;
 
         info!("Meta client finish dropping table, resp:{:?}", pb_resp);
 
@@ -183,11 +227,11 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .get_tables_of_shards(pb_req)
+            .get_tables_of_shards(self.new_request(pb_req))
             .await
             .box_err()
-            .context(FailGetTables)?
-            .into_inner();
+            .context(FailGetTables)
+            .and_then(|resp| self.check_protocol(resp))?;
 
         info!("Meta client finish getting tables, resp:{:?}", pb_resp);
 
@@ -204,11 +248,11 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .route_tables(pb_req)
+            .route_tables(self.new_request(pb_req))
             .await
             .box_err()
-            .context(FailRouteTables)?
-            .into_inner();
+            .context(FailRouteTables)
+            .and_then(|resp| self.check_protocol(resp))?;
 
         debug!("Meta client finish routing tables, resp:{:?}", pb_resp);
 
@@ -224,11 +268,11 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .get_nodes(pb_req)
+            .get_nodes(self.new_request(pb_req))
             .await
             .box_err()
-            .context(FailRouteTables)?
-            .into_inner();
+            .context(FailRouteTables)
+            .and_then(|resp| self.check_protocol(resp))?;
 
         debug!("Meta client finish getting nodes, resp:{:?}", pb_resp);
 
@@ -250,13 +294,13 @@ impl MetaClient for MetaClientImpl {
 
         let pb_resp = self
             .client()
-            .node_heartbeat(pb_req)
+            .node_heartbeat(self.new_request(pb_req))
             .await
             .box_err()
             .context(FailSendHeartbeat {
                 cluster: &self.config.cluster_name,
-            })?
-            .into_inner();
+            })
+            .and_then(|resp| self.check_protocol(resp))?;
 
         info!("Meta client finish sending heartbeat, resp:{:?}", pb_resp);
 
@@ -30,6 +30,7 @@ use generic_error::BoxError;
 use logger::error;
 use snafu::ResultExt;
 use table_engine::{
+    access_stats,
     partition::{
         format_sub_partition_table_name,
         rule::{
@@ -232,6 +233,7 @@ impl Table for PartitionTableImpl {
         let _timer = PARTITION_TABLE_WRITE_DURATION_HISTOGRAM
             .with_label_values(&["total"])
             .start_timer();
+        access_stats::record_write(self.table_data.table_id);
 
         // Split write request.
         let schema = request.row_group.schema().clone();
@@ -301,23 +303,20 @@ impl Table for PartitionTableImpl {
                 .context(LocatePartitions)?
         };
 
-        // Query streams through remote engine.
-        let mut futures = FuturesUnordered::new();
-        for partition in partitions {
-            let read_partition = self.remote_engine.read(RemoteReadRequest {
+        // Query streams through remote engine, the partitions are read in batch.
+        let read_requests = partitions
+            .into_iter()
+            .map(|partition| RemoteReadRequest {
                 table: self.get_sub_table_ident(partition),
                 read_request: request.clone(),
-            });
-            futures.push(read_partition);
-        }
-
-        let mut record_batch_streams = Vec::with_capacity(futures.len());
-        while let Some(record_batch_stream) = futures.next().await {
-            let record_batch_stream = record_batch_stream
-                .box_err()
-                .context(Scan { table: self.name() })?;
-            record_batch_streams.push(record_batch_stream);
-        }
+            })
+            .collect();
+        let record_batch_streams = self
+            .remote_engine
+            .read_batch(read_requests)
+            .await
+            .box_err()
+            .context(Scan { table: self.name() })?;
 
         let streams = {
             let _remote_timer = PARTITION_TABLE_PARTITIONED_READ_DURATION_HISTOGRAM
@@ -0,0 +1,605 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Admission control of the requests of the tenants.
+//!
+//! The tenants, i.e. the schemas of the catalogs, share the same runtimes, so
+//! a noisy tenant may starve the others. The admission control bounds the rows
+//! written per second and the concurrent queries of each tenant by its quota,
+//! and sheds the load with a retryable error once the tasks pending in the
+//! runtime serving the request pile up. The writes to a table are also slowed
+//! down by the node owning it as the compaction debt of the table grows, so
+//! the compaction can catch up before the reads degrade.
+//!
+//! The quotas can be changed at runtime, and the new quotas take effect on the
+//! following requests.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use logger::debug;
+use macros::define_result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use snafu::Snafu;
+use table_engine::table::{TableId, TableRef};
+use time_ext::ReadableDuration;
+
+use crate::metrics::ADMISSION_REJECTED_COUNTER_VEC;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Write quota is exceeded, catalog:{}, schema:{}, limit:{} rows/s, retry after:{:?}",
+        catalog,
+        schema,
+        limit,
+        retry_after
+    ))]
+    WriteQuotaExceeded {
+        catalog: String,
+        schema: String,
+        limit: u64,
+        retry_after: Duration,
+    },
+
+    #[snafu(display(
+        "Concurrent query quota is exceeded, catalog:{}, schema:{}, limit:{}",
+        catalog,
+        schema,
+        limit
+    ))]
+    QueryQuotaExceeded {
+        catalog: String,
+        schema: String,
+        limit: usize,
+    },
+
+    #[snafu(display(
+        "Server is busy, retry later, pending tasks:{}, limit:{}",
+        pending,
+        limit
+    ))]
+    ServerBusy { pending: i64, limit: usize },
+
+    #[snafu(display(
+        "Write is throttled by the compaction debt, table:{}, debt:{} bytes, retry after:{:?}",
+        table,
+        debt,
+        retry_after
+    ))]
+    WriteThrottled {
+        table: String,
+        debt: u64,
+        retry_after: Duration,
+    },
+}
+
+define_result!(Error);
+
+impl Error {
+    /// Delay before the rejected write can be admitted.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::WriteQuotaExceeded { retry_after, .. }
+            | Error::WriteThrottled { retry_after, .. } => Some(*retry_after),
+            Error::QueryQuotaExceeded { .. } | Error::ServerBusy { .. } => None,
+        }
+    }
+}
+
+/// Quota of a tenant, and zero means unlimited.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Quota {
+    /// Max rows written per second.
+    pub write_rows_per_sec: u64,
+    /// Max queries executed at the same time.
+    pub max_concurrent_queries: usize,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct TenantQuota {
+    pub catalog: String,
+    pub schema: String,
+    pub quota: Quota,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(default)]
+#[schemars(rename = "Quotas")]
+pub struct Config {
+    /// Quota of the tenants not listed in `tenants`.
+    pub default_quota: Quota,
+    pub tenants: Vec<TenantQuota>,
+    /// The requests are rejected once the tasks pending in the runtime serving
+    /// them exceed it, and zero disables the load shedding.
+    pub max_pending_tasks: usize,
+    pub debt_throttle: DebtThrottle,
+}
+
+/// Throttle of the writes by the compaction debt, i.e. the bytes of the ssts
+/// waiting to be compacted.
+///
+/// Once the debt exceeds `slowdown_debt`, the writes are admitted at
+/// `max_rows_per_sec`, and the rate decreases linearly as the debt grows until
+/// all the writes are rejected at `stop_debt`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct DebtThrottle {
+    /// Zero disables the throttle.
+    #[schemars(with = "String")]
+    pub slowdown_debt: ReadableSize,
+    #[schemars(with = "String")]
+    pub stop_debt: ReadableSize,
+    pub max_rows_per_sec: u64,
+    /// The write is delayed if it can be admitted within it, otherwise it's
+    /// rejected with the time to retry.
+    #[schemars(with = "String")]
+    pub max_delay: ReadableDuration,
+}
+
+impl Default for DebtThrottle {
+    fn default() -> Self {
+        Self {
+            slowdown_debt: ReadableSize(0),
+            stop_debt: ReadableSize::gb(64),
+            max_rows_per_sec: 100_000,
+            max_delay: ReadableDuration::secs(1),
+        }
+    }
+}
+
+impl DebtThrottle {
+    /// Rows per second admitted at the `debt`, `None` if it's not throttled.
+    fn rate_at(&self, debt: u64) -> Option<u64> {
+        let slowdown = self.slowdown_debt.as_byte();
+        if slowdown == 0 || debt < slowdown {
+            return None;
+        }
+
+        let stop = self.stop_debt.as_byte().max(slowdown + 1);
+        if debt >= stop {
+            return Some(0);
+        }
+        let ratio = (stop - debt) as f64 / (stop - slowdown) as f64;
+        Some(((self.max_rows_per_sec as f64 * ratio) as u64).max(1))
+    }
+}
+
+type TenantKey = (String, String);
+
+/// The usage of the compaction debt throttle is dropped once the table is not
+/// throttled for a while.
+const IDLE_DEBT_USAGE: Duration = Duration::from_secs(60);
+
+/// Usage of the quota of a tenant.
+struct Usage {
+    /// Rows can be written now, which may be negative as a write is admitted
+    /// as long as there are tokens left.
+    write_tokens: f64,
+    refilled_at: Instant,
+    running_queries: usize,
+}
+
+impl Usage {
+    fn new(now: Instant) -> Self {
+        Self {
+            write_tokens: 0.0,
+            refilled_at: now,
+            running_queries: 0,
+        }
+    }
+
+    /// Refill the tokens at `rate` per second, at most one second of tokens
+    /// are accumulated.
+    fn refill(&mut self, rate: u64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let rate = rate as f64;
+        self.write_tokens = (self.write_tokens + elapsed.as_secs_f64() * rate).min(rate);
+        self.refilled_at = now;
+    }
+}
+
+pub struct AdmissionController {
+    config: RwLock<Config>,
+    /// Usages of the tenants, keyed by the catalog and schema name.
+    usages: Mutex<HashMap<TenantKey, Usage>>,
+    /// Usages of the writes throttled by the compaction debt, keyed by the
+    /// tables.
+    debt_usages: Mutex<HashMap<TableId, Usage>>,
+}
+
+impl AdmissionController {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: RwLock::new(config),
+            usages: Mutex::new(HashMap::new()),
+            debt_usages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Replace the quotas and the limit of the pending tasks.
+    pub fn set_config(&self, config: Config) {
+        *self.config.write().unwrap() = config;
+    }
+
+    pub fn set_default_quota(&self, quota: Quota) {
+        self.config.write().unwrap().default_quota = quota;
+    }
+
+    pub fn set_max_pending_tasks(&self, max_pending_tasks: usize) {
+        self.config.write().unwrap().max_pending_tasks = max_pending_tasks;
+    }
+
+    pub fn set_debt_throttle(&self, debt_throttle: DebtThrottle) {
+        self.config.write().unwrap().debt_throttle = debt_throttle;
+    }
+
+    /// Replace the quotas of the tenants.
+    pub fn set_tenants(&self, tenants: Vec<TenantQuota>) {
+        self.config.write().unwrap().tenants = tenants;
+    }
+
+    /// Add or update the quotas of the tenants.
+    pub fn upsert_tenants(&self, tenants: Vec<TenantQuota>) {
+        let mut config = self.config.write().unwrap();
+        for tenant in tenants {
+            let existing = config
+                .tenants
+                .iter_mut()
+                .find(|v| v.catalog == tenant.catalog && v.schema == tenant.schema);
+            match existing {
+                Some(v) => v.quota = tenant.quota,
+                None => config.tenants.push(tenant),
+            }
+        }
+    }
+
+    /// Remove the quotas of the tenants, and the default quota is applied to
+    /// them then.
+    pub fn remove_tenants(&self, tenants: &[TenantQuota]) {
+        let mut config = self.config.write().unwrap();
+        config.tenants.retain(|v| {
+            !tenants
+                .iter()
+                .any(|t| t.catalog == v.catalog && t.schema == v.schema)
+        });
+    }
+
+    fn quota_of(&self, catalog: &str, schema: &str) -> Quota {
+        let config = self.config.read().unwrap();
+        config
+            .tenants
+            .iter()
+            .find(|v| v.catalog == catalog && v.schema == schema)
+            .map(|v| v.quota)
+            .unwrap_or(config.default_quota)
+    }
+
+    /// Reject the request if the runtime serving it has more than
+    /// `max_pending_tasks` tasks pending.
+    pub fn check_load(&self, pending_tasks: i64) -> Result<()> {
+        let limit = self.config.read().unwrap().max_pending_tasks;
+        if limit > 0 && pending_tasks > limit as i64 {
+            ADMISSION_REJECTED_COUNTER_VEC
+                .with_label_values(&["server_busy"])
+                .inc();
+            return ServerBusy {
+                pending: pending_tasks,
+                limit,
+            }
+            .fail();
+        }
+
+        Ok(())
+    }
+
+    /// Admit the write of `num_rows` rows of the tenant.
+    pub fn admit_write(&self, catalog: &str, schema: &str, num_rows: usize) -> Result<()> {
+        self.admit_write_at(catalog, schema, num_rows, Instant::now())
+    }
+
+    fn admit_write_at(
+        &self,
+        catalog: &str,
+        schema: &str,
+        num_rows: usize,
+        now: Instant,
+    ) -> Result<()> {
+        let rate = self.quota_of(catalog, schema).write_rows_per_sec;
+        if rate == 0 {
+            return Ok(());
+        }
+
+        let mut usages = self.usages.lock().unwrap();
+        let usage = usages
+            .entry((catalog.to_string(), schema.to_string()))
+            .or_insert_with(|| {
+                let mut usage = Usage::new(now);
+                usage.write_tokens = rate as f64;
+                usage
+            });
+        usage.refill(rate, now);
+        if usage.write_tokens <= 0.0 {
+            ADMISSION_REJECTED_COUNTER_VEC
+                .with_label_values(&["write_quota"])
+                .inc();
+            let retry_after = Duration::from_secs_f64(-usage.write_tokens / rate as f64);
+            return WriteQuotaExceeded {
+                catalog,
+                schema,
+                limit: rate,
+                retry_after: retry_after.max(Duration::from_millis(1)),
+            }
+            .fail();
+        }
+        usage.write_tokens -= num_rows as f64;
+
+        Ok(())
+    }
+
+    /// Throttle the write of `num_rows` rows to the local `table` by its
+    /// compaction debt, the write is delayed or rejected with the time to
+    /// retry.
+    pub async fn throttle_table_write(&self, table: &TableRef, num_rows: usize) -> Result<()> {
+        let debt = table.compaction_debt();
+        let delay =
+            self.throttle_write_at(table.id(), table.name(), debt, num_rows, Instant::now())?;
+        if !delay.is_zero() {
+            debug!(
+                "Write is delayed by the compaction debt, table:{}, debt:{debt}, delay:{delay:?}",
+                table.name()
+            );
+            tokio::time::sleep(delay).await;
+        }
+
+        Ok(())
+    }
+
+    fn throttle_write_at(
+        &self,
+        table_id: TableId,
+        table: &str,
+        debt: u64,
+        num_rows: usize,
+        now: Instant,
+    ) -> Result<Duration> {
+        let throttle = self.config.read().unwrap().debt_throttle;
+        let Some(rate) = throttle.rate_at(debt) else {
+            return Ok(Duration::ZERO);
+        };
+        let max_delay = throttle.max_delay.0;
+        if rate == 0 {
+            ADMISSION_REJECTED_COUNTER_VEC
+                .with_label_values(&["compaction_debt"])
+                .inc();
+            return WriteThrottled {
+                table,
+                debt,
+                retry_after: max_delay.max(Duration::from_millis(1)),
+            }
+            .fail();
+        }
+
+        let mut usages = self.debt_usages.lock().unwrap();
+        // Drop the usages of the tables not throttled for a while, e.g. the
+        // closed ones.
+        usages
+            .retain(|_, usage| now.saturating_duration_since(usage.refilled_at) < IDLE_DEBT_USAGE);
+        let usage = usages.entry(table_id).or_insert_with(|| Usage::new(now));
+        usage.refill(rate, now);
+        // The write waits until the tokens taken by the previous writes are
+        // refilled.
+        let delay = Duration::from_secs_f64((-usage.write_tokens).max(0.0) / rate as f64);
+        if delay > max_delay {
+            ADMISSION_REJECTED_COUNTER_VEC
+                .with_label_values(&["compaction_debt"])
+                .inc();
+            return WriteThrottled {
+                table,
+                debt,
+                retry_after: (delay - max_delay).max(Duration::from_millis(1)),
+            }
+            .fail();
+        }
+        usage.write_tokens -= num_rows as f64;
+
+        Ok(delay)
+    }
+
+    /// Admit a query of the tenant, which is counted as running until the
+    /// returned permit is dropped.
+    pub fn admit_query(&self, catalog: &str, schema: &str) -> Result<QueryPermit<'_>> {
+        let limit = self.quota_of(catalog, schema).max_concurrent_queries;
+        let key = (catalog.to_string(), schema.to_string());
+        let mut usages = self.usages.lock().unwrap();
+        let usage = usages
+            .entry(key.clone())
+            .or_insert_with(|| Usage::new(Instant::now()));
+        if limit > 0 && usage.running_queries >= limit {
+            ADMISSION_REJECTED_COUNTER_VEC
+                .with_label_values(&["query_quota"])
+                .inc();
+            return QueryQuotaExceeded {
+                catalog,
+                schema,
+                limit,
+            }
+            .fail();
+        }
+        usage.running_queries += 1;
+
+        Ok(QueryPermit {
+            controller: self,
+            key,
+        })
+    }
+}
+
+/// The query of a tenant is counted as running while holding the permit.
+pub struct QueryPermit<'a> {
+    controller: &'a AdmissionController,
+    key: TenantKey,
+}
+
+impl<'a> Drop for QueryPermit<'a> {
+    fn drop(&mut self) {
+        let mut usages = self.controller.usages.lock().unwrap();
+        if let Some(usage) = usages.get_mut(&self.key) {
+            usage.running_queries = usage.running_queries.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_controller() -> AdmissionController {
+        AdmissionController::new(Config {
+            default_quota: Quota {
+                write_rows_per_sec: 100,
+                max_concurrent_queries: 1,
+            },
+            tenants: vec![TenantQuota {
+                catalog: "horaedb".to_string(),
+                schema: "vip".to_string(),
+                quota: Quota::default(),
+            }],
+            max_pending_tasks: 10,
+            debt_throttle: DebtThrottle {
+                slowdown_debt: ReadableSize(1000),
+                stop_debt: ReadableSize(2000),
+                max_rows_per_sec: 100,
+                max_delay: ReadableDuration::secs(1),
+            },
+        })
+    }
+
+    #[test]
+    fn test_admit_write() {
+        let controller = new_controller();
+        let now = Instant::now();
+        // A big write is admitted as long as there are tokens left.
+        controller
+            .admit_write_at("horaedb", "public", 150, now)
+            .unwrap();
+        let err = controller
+            .admit_write_at("horaedb", "public", 1, now)
+            .unwrap_err();
+        assert!(matches!(err, Error::WriteQuotaExceeded { .. }));
+        assert!(err.retry_after().is_some());
+
+        // Admitted after the tokens are refilled.
+        let later = now + Duration::from_millis(600);
+        controller
+            .admit_write_at("horaedb", "public", 1, later)
+            .unwrap();
+
+        // The tenant with the unlimited quota.
+        controller
+            .admit_write_at("horaedb", "vip", 1000, now)
+            .unwrap();
+        controller
+            .admit_write_at("horaedb", "vip", 1000, now)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_admit_query() {
+        let controller = new_controller();
+        let permit = controller.admit_query("horaedb", "public").unwrap();
+        assert!(controller.admit_query("horaedb", "public").is_err());
+        // Other tenants are not affected.
+        let _other = controller.admit_query("horaedb", "other").unwrap();
+        drop(permit);
+        let _permit = controller.admit_query("horaedb", "public").unwrap();
+
+        // Change the quota at runtime.
+        controller.upsert_tenants(vec![TenantQuota {
+            catalog: "horaedb".to_string(),
+            schema: "public".to_string(),
+            quota: Quota {
+                write_rows_per_sec: 0,
+                max_concurrent_queries: 2,
+            },
+        }]);
+        let _permit2 = controller.admit_query("horaedb", "public").unwrap();
+        assert!(controller.admit_query("horaedb", "public").is_err());
+    }
+
+    #[test]
+    fn test_throttle_write() {
+        let controller = new_controller();
+        let (t1, t2) = (TableId::new(1), TableId::new(2));
+        let throttle = |table_id, debt, num_rows, now| {
+            controller.throttle_write_at(table_id, "t", debt, num_rows, now)
+        };
+        let now = Instant::now();
+        // Not throttled before the debt reaches the slowdown threshold.
+        let delay = throttle(t1, 999, 10000, now).unwrap();
+        assert_eq!(Duration::ZERO, delay);
+
+        // The rate is halved at the middle of the thresholds, and the writes
+        // are delayed until the tokens are refilled.
+        let delay = throttle(t1, 1500, 30, now).unwrap();
+        assert_eq!(Duration::ZERO, delay);
+        let delay = throttle(t1, 1500, 30, now).unwrap();
+        assert!(delay > Duration::from_millis(500) && delay <= Duration::from_millis(600));
+        let err = throttle(t1, 1500, 30, now).unwrap_err();
+        assert!(matches!(err, Error::WriteThrottled { .. }));
+
+        // The tables are throttled separately.
+        let delay = throttle(t2, 1500, 30, now).unwrap();
+        assert_eq!(Duration::ZERO, delay);
+        let delay = throttle(t2, 0, 10000, now).unwrap();
+        assert_eq!(Duration::ZERO, delay);
+
+        let later = now + Duration::from_secs(10);
+        let delay = throttle(t1, 1500, 30, later).unwrap();
+        assert_eq!(Duration::ZERO, delay);
+
+        // All the writes are rejected at the stop threshold.
+        let err = throttle(t1, 2000, 1, later).unwrap_err();
+        assert!(matches!(err, Error::WriteThrottled { .. }));
+
+        controller.set_debt_throttle(DebtThrottle::default());
+        let delay = throttle(t1, 2000, 1, later).unwrap();
+        assert_eq!(Duration::ZERO, delay);
+    }
+
+    #[test]
+    fn test_check_load() {
+        let controller = new_controller();
+        controller.check_load(10).unwrap();
+        let err = controller.check_load(11).unwrap_err();
+        assert!(matches!(err, Error::ServerBusy { .. }));
+
+        controller.set_config(Config::default());
+        controller.check_load(1000).unwrap();
+    }
+}
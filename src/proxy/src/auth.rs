@@ -0,0 +1,1028 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Authentication and authorization of the requests.
+//!
+//! Once enabled, every request must carry the credentials of a user in the
+//! `authorization` header (or the gRPC metadata), either `Basic` with the
+//! name and the password, or `Bearer` with a token of the token file. The
+//! users are defined in the config, or created by `CREATE USER` and persisted
+//! into a table in the default schema.
+//!
+//! A user is granted a role on a catalog, or on all the catalogs by `*`, and
+//! every statement requires a role on the catalog of the request:
+//! - `read_only` for the queries,
+//! - `read_write` for the writes and the other statements changing the data
+//!   or the schemas,
+//! - `admin` for managing the users and the server.
+//!
+//! The requests forwarded between the nodes carry the credentials of the
+//! original request, which are authenticated again by the target node.
+//!
+//! The http service can be served over TLS, and the clients are required to
+//! present certificates signed by the configured CA if it's set. The grpc
+//! service can be served over TLS too, and its internal services, e.g. the
+//! remote engine service, only accept the requests from the nodes of the
+//! cluster, which carry the internal token or are verified by the client
+//! certificates. The
+//! protocols which can't carry the credentials, e.g. MySQL and StatsD, are
+//! not served when the authentication is enabled.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    sync::{Arc, Mutex, OnceLock, RwLock, Weak},
+};
+
+use async_trait::async_trait;
+use generic_error::{BoxError, GenericError};
+use http::StatusCode;
+use interpreters::{interpreter::Output, user::UserManager};
+use logger::{error, info, warn, Level};
+use query_frontend::plan::{Plan, UserRole, ALL_CATALOGS};
+use runtime::{JoinHandle, RuntimeRef};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::{ensure, OptionExt, ResultExt};
+use sqlparser::{
+    dialect::{keywords::Keyword, MySqlDialect},
+    tokenizer::{Token, Tokenizer},
+};
+use system_catalog::users::{user_registry, UserInfo};
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
+use time_ext::ReadableDuration;
+use tokio::sync::oneshot;
+
+use crate::{
+    error::{ErrNoCause, Internal, InternalNoCause, Result},
+    Context, Proxy,
+};
+
+/// Header (and gRPC metadata) carrying the credentials.
+pub const AUTHORIZATION_KEY: &str = "authorization";
+
+const BASIC_AUTH_PREFIX: &str = "Basic ";
+const BEARER_AUTH_PREFIX: &str = "Bearer ";
+/// Rounds of PBKDF2 to hash the passwords, which are stored with the hashes so
+/// it can be raised without breaking the persisted users.
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Users defined in the config, which can't be changed by the statements.
+    pub users: Vec<UserConfig>,
+    /// File of the tokens, and every line of it is `<token> <user>`. It's read
+    /// at startup.
+    pub token_file: Option<String>,
+    /// Table in the default schema to persist the users created by the
+    /// statements.
+    pub state_table: String,
+    /// Interval to reload the persisted users, so the users changed on the
+    /// other nodes are visible.
+    pub refresh_interval: ReadableDuration,
+    /// Timeout of loading and saving the users.
+    pub timeout: ReadableDuration,
+    /// TLS of the http service.
+    pub tls: TlsConfig,
+    /// TLS of the grpc service, which is used by the nodes connecting to each
+    /// other too.
+    pub grpc_tls: GrpcTlsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            users: Vec::new(),
+            token_file: None,
+            state_table: "__users".to_string(),
+            refresh_interval: ReadableDuration::secs(30),
+            timeout: ReadableDuration::secs(30),
+            tls: TlsConfig::default(),
+            grpc_tls: GrpcTlsConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct UserConfig {
+    pub name: String,
+    pub password: String,
+    /// Roles of the user keyed by the catalogs, and `*` means all the
+    /// catalogs.
+    #[serde(default)]
+    pub grants: BTreeMap<String, UserRole>,
+}
+
+impl fmt::Debug for UserConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserConfig")
+            .field("name", &self.name)
+            .field("password", &"******")
+            .field("grants", &self.grants)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub enable: bool,
+    /// Path of the certificate chain in PEM.
+    pub cert_path: String,
+    /// Path of the private key in PEM.
+    pub key_path: String,
+    /// Path of the CA certificates in PEM to verify the certificates of the
+    /// clients, and the clients are required to present a certificate signed
+    /// by them if it's set.
+    pub client_ca_path: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GrpcTlsConfig {
+    pub enable: bool,
+    /// Path of the certificate chain in PEM.
+    pub cert_path: String,
+    /// Path of the private key in PEM.
+    pub key_path: String,
+    /// Path of the CA certificates in PEM to verify the certificates of the
+    /// nodes when connecting to each other, which is required once enabled.
+    pub ca_path: String,
+    /// Whether the clients are required to present a certificate signed by
+    /// the CA, and the nodes present their own certificates to each other.
+    /// Once required, the clients are trusted as the nodes by the internal
+    /// services, so the CA should only sign the certificates of the nodes, the
+    /// meta servers and the trusted clients.
+    pub client_auth: bool,
+    /// Name in the certificates of the nodes to verify, the address of the
+    /// node is verified if it's not set.
+    pub domain_name: Option<String>,
+}
+
+/// User authenticated by the credentials of a request.
+#[derive(Clone)]
+pub struct AuthUser {
+    name: String,
+    /// The `authorization` of the request, which is forwarded with the
+    /// request to other nodes.
+    credentials: String,
+}
+
+impl AuthUser {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn credentials(&self) -> &str {
+        &self.credentials
+    }
+}
+
+impl fmt::Debug for AuthUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthUser")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// Password hashed by PBKDF2-HMAC-SHA256, with the parameters to verify it.
+#[derive(Clone, Deserialize, Serialize)]
+struct PasswordHash {
+    rounds: u32,
+    salt: String,
+    hash: String,
+}
+
+impl PasswordHash {
+    fn new(password: &str) -> Self {
+        let salt = hex::encode(rand::random::<[u8; 16]>());
+        let hash = hash_password(PBKDF2_ROUNDS, &salt, password);
+        Self {
+            rounds: PBKDF2_ROUNDS,
+            salt,
+            hash,
+        }
+    }
+
+    fn verify(&self, password: &str) -> bool {
+        let hash = hash_password(self.rounds, &self.salt, password);
+        constant_time_eq(hash.as_bytes(), self.hash.as_bytes())
+    }
+}
+
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PasswordHash")
+            .field("rounds", &self.rounds)
+            .finish()
+    }
+}
+
+/// User persisted in the state table.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PersistedUser {
+    password: PasswordHash,
+    grants: BTreeMap<String, UserRole>,
+}
+
+#[derive(Debug, Clone)]
+struct User {
+    password: PasswordHash,
+    grants: BTreeMap<String, UserRole>,
+    /// Whether the user is defined in the config
+    from_config: bool,
+    /// Digest of the password verified last time, so the slow hash isn't
+    /// computed for every request of the user.
+    verified: Arc<Mutex<Option<String>>>,
+}
+
+impl User {
+    fn new(password: &str, grants: BTreeMap<String, UserRole>, from_config: bool) -> Self {
+        Self::with_password(PasswordHash::new(password), grants, from_config)
+    }
+
+    fn with_password(
+        password: PasswordHash,
+        grants: BTreeMap<String, UserRole>,
+        from_config: bool,
+    ) -> Self {
+        Self {
+            password,
+            grants,
+            from_config,
+            verified: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn verify_password(&self, password: &str) -> bool {
+        let digest = hash_token(password);
+        let mut verified = self.verified.lock().unwrap();
+        if verified
+            .as_ref()
+            .is_some_and(|v| constant_time_eq(v.as_bytes(), digest.as_bytes()))
+        {
+            return true;
+        }
+
+        if !self.password.verify(password) {
+            return false;
+        }
+        *verified = Some(digest);
+
+        true
+    }
+
+    fn role_on(&self, catalog: &str) -> Option<UserRole> {
+        [catalog, ALL_CATALOGS]
+            .iter()
+            .filter_map(|catalog| self.grants.get(*catalog))
+            .max()
+            .copied()
+    }
+}
+
+struct RunningRefresh {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+pub type AuthenticatorRef = Arc<Authenticator>;
+
+pub struct Authenticator {
+    config: Config,
+    runtime: RuntimeRef,
+    task_registry: TaskRegistryRef,
+    /// Users keyed by the hashes of their tokens.
+    tokens: HashMap<String, String>,
+    users: RwLock<HashMap<String, User>>,
+    /// The proxy is set after it is built, as the proxy holds the
+    /// authenticator.
+    proxy: OnceLock<Weak<Proxy>>,
+    refresh: Mutex<Option<RunningRefresh>>,
+}
+
+impl Authenticator {
+    pub fn new(
+        config: Config,
+        runtime: RuntimeRef,
+        task_registry: TaskRegistryRef,
+    ) -> Result<Self> {
+        let tokens = match &config.token_file {
+            Some(path) => {
+                let content = fs::read_to_string(path).box_err().with_context(|| Internal {
+                    msg: format!("failed to read token file, path:{path}"),
+                })?;
+                parse_tokens(&content)
+            }
+            None => HashMap::new(),
+        };
+        let users = config
+            .users
+            .iter()
+            .map(|user| {
+                let grants = user.grants.clone();
+                (user.name.clone(), User::new(&user.password, grants, true))
+            })
+            .collect();
+
+        let authenticator = Self {
+            config,
+            runtime,
+            task_registry,
+            tokens,
+            users: RwLock::new(users),
+            proxy: OnceLock::new(),
+            refresh: Mutex::new(None),
+        };
+        authenticator.report_users();
+
+        Ok(authenticator)
+    }
+
+    pub fn set_proxy(&self, proxy: Weak<Proxy>) {
+        if self.proxy.set(proxy).is_err() {
+            warn!("Proxy of the authenticator is already set");
+        }
+    }
+
+    /// Load the persisted users and reload them periodically.
+    pub async fn open(self: &Arc<Self>) -> Result<()> {
+        let proxy = self.proxy()?;
+        create_state_table(&proxy, &self.config).await?;
+        self.reload(&proxy).await?;
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let spec = TaskSpec::new(TaskKind::UserRefresh, "users".to_string());
+        let refresh = refresh_users(Arc::downgrade(self), stop_rx);
+        let handle = self
+            .task_registry
+            .spawn_tracked(&self.runtime, spec, refresh);
+
+        let running = RunningRefresh { stop_tx, handle };
+        if let Some(old) = self.refresh.lock().unwrap().replace(running) {
+            old.handle.abort();
+        }
+
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        let running = self.refresh.lock().unwrap().take();
+        if let Some(running) = running {
+            let _ = running.stop_tx.send(());
+            if running.handle.await.is_err() {
+                warn!("User refresh task is aborted");
+            }
+        }
+    }
+
+    /// Authenticate the credentials in the `authorization` header.
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<AuthUser> {
+        let credentials = authorization.context(ErrNoCause {
+            code: StatusCode::UNAUTHORIZED,
+            msg: "Credentials are required",
+        })?;
+        let name = if let Some(token) = credentials.strip_prefix(BEARER_AUTH_PREFIX) {
+            self.tokens.get(&hash_token(token.trim())).cloned()
+        } else if let Some(encoded) = credentials.strip_prefix(BASIC_AUTH_PREFIX) {
+            self.verify_basic(encoded)
+        } else {
+            None
+        };
+        let name = name.context(ErrNoCause {
+            code: StatusCode::UNAUTHORIZED,
+            msg: "Invalid credentials",
+        })?;
+
+        Ok(AuthUser {
+            name,
+            credentials: credentials.to_string(),
+        })
+    }
+
+    /// Whether the user is granted the role, or a higher one, on the catalog.
+    pub fn authorize(&self, user: &str, catalog: &str, role: UserRole) -> bool {
+        self.users
+            .read()
+            .unwrap()
+            .get(user)
+            .and_then(|user| user.role_on(catalog))
+            .is_some_and(|granted| granted >= role)
+    }
+
+    fn verify_basic(&self, encoded: &str) -> Option<String> {
+        let decoded = base64::decode(encoded.trim()).ok()?;
+        let credentials = String::from_utf8(decoded).ok()?;
+        let (name, password) = credentials.split_once(':')?;
+
+        let users = self.users.read().unwrap();
+        users
+            .get(name)
+            .filter(|user| user.verify_password(password))
+            .map(|_| name.to_string())
+    }
+
+    fn proxy(&self) -> Result<Arc<Proxy>> {
+        self.proxy
+            .get()
+            .and_then(|proxy| proxy.upgrade())
+            .context(InternalNoCause {
+                msg: "proxy of the authenticator is not available",
+            })
+    }
+
+    /// Replace the users created by the statements with the persisted ones.
+    async fn reload(&self, proxy: &Proxy) -> Result<()> {
+        let sql = format!(
+            "SELECT user_name, definition, dropped FROM {}",
+            self.config.state_table
+        );
+        let output = proxy
+            .fetch_sql_query_output(
+                &Context::new(Some(self.config.timeout.0), None),
+                proxy.instance.catalog_manager.default_schema_name(),
+                &sql,
+                false,
+                false,
+            )
+            .await?;
+        let Output::Records(batches) = output else {
+            return InternalNoCause {
+                msg: "unexpected output of loading users",
+            }
+            .fail();
+        };
+
+        let mut loaded = HashMap::new();
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                if batch.column(2).datum_view(row).as_bool() == Some(true) {
+                    continue;
+                }
+                let Some(name) = batch.column(0).datum_view(row).into_str() else {
+                    continue;
+                };
+                let Some(definition) = batch.column(1).datum_view(row).into_str() else {
+                    continue;
+                };
+                match serde_json::from_str::<PersistedUser>(definition) {
+                    Ok(user) => {
+                        let user = User::with_password(user.password, user.grants, false);
+                        loaded.insert(name.to_string(), user);
+                    }
+                    Err(e) => error!("Failed to decode user, name:{name}, err:{e}"),
+                }
+            }
+        }
+
+        {
+            let mut users = self.users.write().unwrap();
+            users.retain(|_, user| user.from_config);
+            for (name, user) in loaded {
+                if users.contains_key(&name) {
+                    warn!("Ignore the persisted user defined in the config, name:{name}");
+                    continue;
+                }
+                users.insert(name, user);
+            }
+        }
+        self.report_users();
+
+        Ok(())
+    }
+
+    /// Get the user changed by the statements, which must not be defined in
+    /// the config.
+    fn user_to_change(&self, name: &str) -> Result<User> {
+        let users = self.users.read().unwrap();
+        let user = users.get(name).with_context(|| ErrNoCause {
+            code: StatusCode::NOT_FOUND,
+            msg: format!("User not found, name:{name}"),
+        })?;
+        ensure!(
+            !user.from_config,
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("User defined in the config can't be changed, name:{name}"),
+            }
+        );
+
+        Ok(user.clone())
+    }
+
+    /// Persist the user, or drop it if `user` is `None`.
+    async fn update_user(&self, name: &str, user: Option<User>) -> Result<()> {
+        let proxy = self.proxy()?;
+        create_state_table(&proxy, &self.config).await?;
+        let persisted = user.as_ref().map(|user| PersistedUser {
+            password: user.password.clone(),
+            grants: user.grants.clone(),
+        });
+        save_user(&proxy, &self.config, name, persisted.as_ref()).await?;
+
+        {
+            let mut users = self.users.write().unwrap();
+            match user {
+                Some(user) => users.insert(name.to_string(), user),
+                None => users.remove(name),
+            };
+        }
+        self.report_users();
+
+        Ok(())
+    }
+
+    fn report_users(&self) {
+        let users = self.users.read().unwrap();
+        let mut infos = Vec::with_capacity(users.len());
+        for (name, user) in users.iter() {
+            let source = if user.from_config { "config" } else { "sql" };
+            if user.grants.is_empty() {
+                infos.push(UserInfo {
+                    name: name.clone(),
+                    catalog: None,
+                    role: None,
+                    source,
+                });
+            }
+            for (catalog, role) in &user.grants {
+                infos.push(UserInfo {
+                    name: name.clone(),
+                    catalog: Some(catalog.clone()),
+                    role: Some(role.as_str()),
+                    source,
+                });
+            }
+        }
+        user_registry().set(infos);
+    }
+}
+
+#[async_trait]
+impl UserManager for Authenticator {
+    fn contains(&self, name: &str) -> bool {
+        self.users.read().unwrap().contains_key(name)
+    }
+
+    async fn create_user(
+        &self,
+        name: &str,
+        password: &str,
+    ) -> std::result::Result<(), GenericError> {
+        let user = User::new(password, BTreeMap::new(), false);
+        self.update_user(name, Some(user)).await.box_err()?;
+        info!("Create user, name:{name}");
+
+        Ok(())
+    }
+
+    async fn drop_user(&self, name: &str) -> std::result::Result<(), GenericError> {
+        self.user_to_change(name).box_err()?;
+        self.update_user(name, None).await.box_err()?;
+        info!("Drop user, name:{name}");
+
+        Ok(())
+    }
+
+    async fn grant(
+        &self,
+        name: &str,
+        catalog: &str,
+        role: UserRole,
+    ) -> std::result::Result<(), GenericError> {
+        let mut user = self.user_to_change(name).box_err()?;
+        user.grants.insert(catalog.to_string(), role);
+        self.update_user(name, Some(user)).await.box_err()?;
+        info!("Grant role, name:{name}, catalog:{catalog}, role:{role}");
+
+        Ok(())
+    }
+
+    async fn revoke(&self, name: &str, catalog: &str) -> std::result::Result<(), GenericError> {
+        let mut user = self.user_to_change(name).box_err()?;
+        if user.grants.remove(catalog).is_none() {
+            return Ok(());
+        }
+        self.update_user(name, Some(user)).await.box_err()?;
+        info!("Revoke role, name:{name}, catalog:{catalog}");
+
+        Ok(())
+    }
+}
+
+/// Reload the persisted users periodically until stopped or the
+/// authenticator is dropped.
+async fn refresh_users(weak: Weak<Authenticator>, mut stop_rx: oneshot::Receiver<()>) {
+    loop {
+        let Some(interval) = weak.upgrade().map(|v| v.config.refresh_interval.0) else {
+            return;
+        };
+        tokio::select! {
+            _ = &mut stop_rx => return,
+            _ = tokio::time::sleep(interval) => {},
+        }
+
+        let Some(authenticator) = weak.upgrade() else {
+            return;
+        };
+        let result = match authenticator.proxy() {
+            Ok(proxy) => authenticator.reload(&proxy).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to reload users, err:{e}");
+        }
+    }
+}
+
+impl Proxy {
+    /// Check the role of the user of the request on the catalog. The requests
+    /// without a user are internal ones, e.g. the continuous queries, which
+    /// are always allowed.
+    pub fn check_role(
+        &self,
+        user: Option<&AuthUser>,
+        catalog: &str,
+        role: UserRole,
+    ) -> Result<()> {
+        let (Some(authenticator), Some(user)) = (&self.instance.authenticator, user) else {
+            return Ok(());
+        };
+
+        ensure!(
+            authenticator.authorize(user.name(), catalog, role),
+            ErrNoCause {
+                code: StatusCode::FORBIDDEN,
+                msg: format!(
+                    "User {} is not granted the {role} role on catalog {catalog}",
+                    user.name()
+                ),
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Check the role required by the plan, see [required_role].
+    pub(crate) fn check_plan_role(
+        &self,
+        user: Option<&AuthUser>,
+        catalog: &str,
+        table_name: Option<&str>,
+        plan: &Plan,
+    ) -> Result<()> {
+        let Some(authenticator) = &self.instance.authenticator else {
+            return Ok(());
+        };
+
+        let state_table = &authenticator.config.state_table;
+        let role = required_role(plan, table_name, state_table);
+        self.check_role(user, catalog, role)
+    }
+
+    /// Check the role required by writing the tables.
+    pub(crate) fn check_write_role<'a>(
+        &self,
+        user: Option<&AuthUser>,
+        catalog: &str,
+        mut tables: impl Iterator<Item = &'a str>,
+    ) -> Result<()> {
+        let Some(authenticator) = &self.instance.authenticator else {
+            return Ok(());
+        };
+
+        let state_table = &authenticator.config.state_table;
+        let role = if tables.any(|table| table == state_table) {
+            UserRole::Admin
+        } else {
+            UserRole::ReadWrite
+        };
+        self.check_role(user, catalog, role)
+    }
+
+    /// The log level elevated by the request, which is only honored for the
+    /// admins as the verbose logs slow the server down and may expose the data
+    /// of other requests. It's ignored if the authentication is disabled, as
+    /// the user of the request is unknown.
+    pub(crate) fn log_level_of(&self, ctx: &Context) -> Option<Level> {
+        let level = ctx.log_level?;
+        let catalog = self.instance.catalog_manager.default_catalog_name();
+        let is_admin = match (&self.instance.authenticator, &ctx.auth_user) {
+            (Some(authenticator), Some(user)) => {
+                authenticator.authorize(user.name(), catalog, UserRole::Admin)
+            }
+            _ => false,
+        };
+        if !is_admin {
+            warn!(
+                "Log level of non-admin request is ignored, request_id:{}, level:{}",
+                ctx.request_id,
+                level.as_str()
+            );
+            return None;
+        }
+
+        Some(level)
+    }
+
+    /// Authenticate the credentials of a request, `None` if the authentication
+    /// is disabled.
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<Option<AuthUser>> {
+        let Some(authenticator) = &self.instance.authenticator else {
+            return Ok(None);
+        };
+
+        authenticator.authenticate(authorization).map(Some)
+    }
+}
+
+/// Attach the credentials of the user to the request forwarded to other nodes.
+pub(crate) fn attach_credentials<T>(req: &mut tonic::Request<T>, user: Option<&AuthUser>) {
+    if let Some(credentials) = user.and_then(|v| v.credentials().parse().ok()) {
+        req.metadata_mut().insert(AUTHORIZATION_KEY, credentials);
+    }
+}
+
+/// The role required by the plan, and the state table of the users can only
+/// be accessed by the admins.
+pub fn required_role(plan: &Plan, table_name: Option<&str>, state_table: &str) -> UserRole {
+    match plan {
+        Plan::CreateUser(_)
+        | Plan::DropUser(_)
+        | Plan::Grant(_)
+        | Plan::Revoke(_)
+        | Plan::AlterSystem(_)
+        | Plan::KillQuery(_)
+        | Plan::ResyncTable(_)
+        | Plan::CreateFunction(_)
+        | Plan::DropFunction(_) => UserRole::Admin,
+        _ if uses_table(plan, table_name, state_table) => UserRole::Admin,
+        _ if plan.is_read_only() => UserRole::ReadOnly,
+        _ => UserRole::ReadWrite,
+    }
+}
+
+fn uses_table(plan: &Plan, table_name: Option<&str>, table: &str) -> bool {
+    // The table name may be qualified by the schema.
+    let is_table = |name: &str| name.rsplit('.').next() == Some(table);
+    if table_name.is_some_and(is_table) {
+        return true;
+    }
+
+    match plan {
+        Plan::Query(plan) => {
+            let mut used = false;
+            let _ = plan.tables.visit::<_, ()>(|_, t| {
+                used |= is_table(t.name());
+                Ok(())
+            });
+            used
+        }
+        Plan::Insert(plan) => is_table(plan.table.name()),
+        _ => false,
+    }
+}
+
+/// Hide the password of `CREATE USER ... IDENTIFIED BY '...'` in the sql to
+/// log, by replacing the literal following `IDENTIFIED BY`.
+pub fn redact_sql(sql: &str) -> Cow<'_, str> {
+    const IDENTIFIED: &str = "IDENTIFIED";
+    const REDACTED: &str = "******";
+
+    let Some(pos) = sql.to_ascii_uppercase().find(IDENTIFIED) else {
+        return Cow::Borrowed(sql);
+    };
+    let Ok(mut tokens) = Tokenizer::new(&MySqlDialect {}, sql).tokenize() else {
+        // The password can't be located, so hide all the rest.
+        return Cow::Owned(format!("{}{IDENTIFIED} BY '{REDACTED}'", &sql[..pos]));
+    };
+
+    // Number of the matched keywords of `IDENTIFIED BY`.
+    let mut matched = 0;
+    let mut redacted = false;
+    for token in &mut tokens {
+        if matches!(token, Token::Whitespace(_)) {
+            continue;
+        }
+        if matched == 2 {
+            *token = Token::SingleQuotedString(REDACTED.to_string());
+            redacted = true;
+            matched = 0;
+            continue;
+        }
+        let word = match token {
+            Token::Word(w) if w.quote_style.is_none() => Some(w),
+            _ => None,
+        };
+        matched = match word {
+            Some(w) if matched == 1 && w.keyword == Keyword::BY => 2,
+            Some(w) if w.value.eq_ignore_ascii_case(IDENTIFIED) => 1,
+            _ => 0,
+        };
+    }
+
+    if redacted {
+        Cow::Owned(tokens.iter().map(ToString::to_string).collect())
+    } else {
+        Cow::Borrowed(sql)
+    }
+}
+
+fn parse_tokens(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (token, user) = line.split_once(char::is_whitespace)?;
+            Some((hash_token(token), user.trim().to_string()))
+        })
+        .collect()
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn hash_password(rounds: u32, salt: &str, password: &str) -> String {
+    let mut hash = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), rounds, &mut hash);
+    hex::encode(hash)
+}
+
+/// Compare the bytes without returning early on the first difference, so the
+/// password can't be guessed by the timing.
+pub fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    if lhs.len() != rhs.len() {
+        return false;
+    }
+
+    lhs.iter().zip(rhs).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+async fn create_state_table(proxy: &Proxy, config: &Config) -> Result<()> {
+    let sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (user_name string TAG, ts timestamp NOT NULL, \
+         definition string, dropped boolean, TIMESTAMP KEY(ts)) \
+         ENGINE=Analytic WITH(enable_ttl='false', update_mode='OVERWRITE')",
+        config.state_table
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+/// Every user has only one row in the state table, which is overwritten by
+/// the latest definition as the timestamp is always 0. The user is marked as
+/// dropped if the definition is `None`.
+async fn save_user(
+    proxy: &Proxy,
+    config: &Config,
+    name: &str,
+    user: Option<&PersistedUser>,
+) -> Result<()> {
+    let sql = match user {
+        Some(user) => {
+            let definition = serde_json::to_string(user).box_err().context(Internal {
+                msg: "failed to encode user",
+            })?;
+            format!(
+                "INSERT INTO {} (user_name, ts, definition, dropped) VALUES ('{}', 0, '{}', false)",
+                config.state_table,
+                escape_string(name),
+                escape_string(&definition),
+            )
+        }
+        None => format!(
+            "INSERT INTO {} (user_name, ts, dropped) VALUES ('{}', 0, true)",
+            config.state_table,
+            escape_string(name),
+        ),
+    };
+    execute_sql(proxy, config, &sql).await
+}
+
+async fn execute_sql(proxy: &Proxy, config: &Config, sql: &str) -> Result<()> {
+    proxy
+        .fetch_sql_query_output(
+            &Context::new(Some(config.timeout.0), None),
+            proxy.instance.catalog_manager.default_schema_name(),
+            sql,
+            false,
+            false,
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn escape_string(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_user(grants: &[(&str, UserRole)]) -> User {
+        let grants = grants
+            .iter()
+            .map(|(catalog, role)| (catalog.to_string(), *role))
+            .collect();
+        User::new("p:ss", grants, true)
+    }
+
+    #[test]
+    fn test_user() {
+        let user = new_user(&[("horaedb", UserRole::ReadWrite), ("*", UserRole::ReadOnly)]);
+        assert!(user.verify_password("p:ss"));
+        assert!(!user.verify_password("p"));
+        assert_eq!(Some(UserRole::ReadWrite), user.role_on("horaedb"));
+        assert_eq!(Some(UserRole::ReadOnly), user.role_on("other"));
+
+        let user = new_user(&[("horaedb", UserRole::ReadOnly), ("*", UserRole::Admin)]);
+        assert_eq!(Some(UserRole::Admin), user.role_on("horaedb"));
+        assert_eq!(None, new_user(&[]).role_on("horaedb"));
+    }
+
+    #[test]
+    fn test_parse_tokens() {
+        let tokens = parse_tokens("# comment\n\ntoken1 alice\n  token2\tbob  \ninvalid\n");
+        assert_eq!(2, tokens.len());
+        assert_eq!(Some("alice"), tokens.get(&hash_token("token1")).map(|v| v.as_str()));
+        assert_eq!(Some("bob"), tokens.get(&hash_token("token2")).map(|v| v.as_str()));
+    }
+
+    #[test]
+    fn test_password_hash() {
+        let password = PasswordHash::new("p:ss");
+        assert_eq!(PBKDF2_ROUNDS, password.rounds);
+        assert!(password.verify("p:ss"));
+        assert!(!password.verify("p:sS"));
+
+        // The persisted hash carries the rounds, so it's verified by them
+        // instead of the current ones.
+        let persisted = PasswordHash {
+            rounds: 1,
+            salt: "salt".to_string(),
+            hash: hash_password(1, "salt", "p:ss"),
+        };
+        let json = serde_json::to_string(&persisted).unwrap();
+        let decoded: PasswordHash = serde_json::from_str(&json).unwrap();
+        assert_eq!(1, decoded.rounds);
+        assert!(decoded.verify("p:ss"));
+        assert!(!decoded.verify("p"));
+        assert_ne!(hash_password(2, "salt", "p:ss"), decoded.hash);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"p:ss", b"p:ss"));
+        assert!(!constant_time_eq(b"p:ss", b"p:sS"));
+        assert!(!constant_time_eq(b"p:ss", b"p:s"));
+    }
+
+    #[test]
+    fn test_redact_sql() {
+        assert_eq!(
+            "CREATE USER alice IDENTIFIED BY '******'",
+            redact_sql("CREATE USER alice IDENTIFIED BY 'secret'")
+        );
+        assert_eq!(
+            "CREATE USER IF NOT EXISTS alice identified  by '******';",
+            redact_sql("CREATE USER IF NOT EXISTS alice identified  by \"secret\";")
+        );
+        assert_eq!("SELECT 1", redact_sql("SELECT 1"));
+
+        // Only the literal following `IDENTIFIED BY` is hidden.
+        let sql = "SELECT identified, name FROM t WHERE name = 'IDENTIFIED BY x'";
+        assert_eq!(sql, redact_sql(sql));
+        let sql = "SELECT `identified` FROM t WHERE v = 'x' ORDER BY identified";
+        assert_eq!(sql, redact_sql(sql));
+
+        // Hide all the rest if the sql can't be tokenized.
+        assert_eq!(
+            "CREATE USER alice IDENTIFIED BY '******'",
+            redact_sql("CREATE USER alice IDENTIFIED BY 'secret")
+        );
+    }
+}
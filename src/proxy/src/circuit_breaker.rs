@@ -0,0 +1,167 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Circuit breaker of the writes to the tables.
+//!
+//! Once the writes to a table fail `failure_threshold` times in a row, the
+//! breaker opens and further writes to the table are failed fast until the
+//! `cool_down` elapses. After that, writes are let through again, and the
+//! breaker closes on the first success or reopens on the next failure.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+use crate::metrics::WRITE_CIRCUIT_BREAKER_COUNTER_VEC;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Consecutive failures to open the breaker of a table.
+    pub failure_threshold: usize,
+    /// Duration the writes are failed fast after the breaker opens.
+    pub cool_down: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            failure_threshold: 10,
+            cool_down: ReadableDuration::secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    consecutive_failures: usize,
+    open_until: Option<Instant>,
+}
+
+pub struct CircuitBreaker {
+    failure_threshold: usize,
+    cool_down: Duration,
+    /// Tables with failed writes, keyed by the schema and table name.
+    states: Mutex<HashMap<(String, String), State>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            cool_down: config.cool_down.0,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the remaining time of the cool down if the breaker of the table
+    /// is open, and the write should be failed fast.
+    pub fn check(&self, schema: &str, table: &str) -> Option<Duration> {
+        self.check_at(schema, table, Instant::now())
+    }
+
+    pub fn on_success(&self, schema: &str, table: &str) {
+        let mut states = self.states.lock().unwrap();
+        // Only the failed tables are tracked, so nothing is allocated in the
+        // common path.
+        if !states.is_empty() {
+            states.remove(&(schema.to_string(), table.to_string()));
+        }
+    }
+
+    pub fn on_failure(&self, schema: &str, table: &str) {
+        self.on_failure_at(schema, table, Instant::now())
+    }
+
+    fn check_at(&self, schema: &str, table: &str, now: Instant) -> Option<Duration> {
+        let states = self.states.lock().unwrap();
+        if states.is_empty() {
+            return None;
+        }
+
+        let open_until = states
+            .get(&(schema.to_string(), table.to_string()))
+            .and_then(|state| state.open_until)?;
+        if now >= open_until {
+            return None;
+        }
+
+        WRITE_CIRCUIT_BREAKER_COUNTER_VEC
+            .with_label_values(&["rejected"])
+            .inc();
+        Some(open_until - now)
+    }
+
+    fn on_failure_at(&self, schema: &str, table: &str, now: Instant) {
+        let mut states = self.states.lock().unwrap();
+        let state = states
+            .entry((schema.to_string(), table.to_string()))
+            .or_default();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(now + self.cool_down);
+            WRITE_CIRCUIT_BREAKER_COUNTER_VEC
+                .with_label_values(&["opened"])
+                .inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let breaker = CircuitBreaker::new(&Config {
+            enable: true,
+            failure_threshold: 2,
+            cool_down: ReadableDuration::secs(10),
+        });
+        let now = Instant::now();
+
+        breaker.on_failure_at("public", "t1", now);
+        assert!(breaker.check_at("public", "t1", now).is_none());
+        breaker.on_failure_at("public", "t1", now);
+        assert_eq!(
+            breaker.check_at("public", "t1", now + Duration::from_secs(4)),
+            Some(Duration::from_secs(6))
+        );
+        assert!(breaker.check_at("public", "t2", now).is_none());
+        assert!(breaker.check_at("other", "t1", now).is_none());
+
+        // Let the writes through after the cool down, and the next failure reopens
+        // the breaker immediately.
+        let after_cool_down = now + Duration::from_secs(10);
+        assert!(breaker.check_at("public", "t1", after_cool_down).is_none());
+        breaker.on_failure_at("public", "t1", after_cool_down);
+        assert!(breaker.check_at("public", "t1", after_cool_down).is_some());
+
+        // Success closes the breaker.
+        breaker.on_success("public", "t1");
+        assert!(breaker.check_at("public", "t1", after_cool_down).is_none());
+        breaker.on_failure_at("public", "t1", after_cool_down);
+        assert!(breaker.check_at("public", "t1", after_cool_down).is_none());
+    }
+}
@@ -20,9 +20,12 @@
 use std::time::Duration;
 
 use common_types::request_id::RequestId;
+use logger::Level;
 use macros::define_result;
 use snafu::{ensure, Backtrace, Snafu};
 
+use crate::auth::AuthUser;
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -56,6 +59,10 @@ pub struct RequestContext {
     pub timeout: Option<Duration>,
     /// Request id
     pub request_id: RequestId,
+    /// Log level elevated for this request only
+    pub log_level: Option<Level>,
+    /// User authenticated by the credentials of the request
+    pub auth_user: Option<AuthUser>,
 }
 
 impl RequestContext {
@@ -69,6 +76,8 @@ pub struct Builder {
     catalog: String,
     schema: String,
     timeout: Option<Duration>,
+    log_level: Option<Level>,
+    auth_user: Option<AuthUser>,
 }
 
 impl Builder {
@@ -87,6 +96,16 @@ impl Builder {
         self
     }
 
+    pub fn log_level(mut self, log_level: Option<Level>) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    pub fn auth_user(mut self, auth_user: Option<AuthUser>) -> Self {
+        self.auth_user = auth_user;
+        self
+    }
+
     pub fn build(self) -> Result<RequestContext> {
         ensure!(!self.catalog.is_empty(), MissingCatalog);
         ensure!(!self.schema.is_empty(), MissingSchema);
@@ -96,6 +115,8 @@ impl Builder {
             schema: self.schema,
             timeout: self.timeout,
             request_id: RequestId::next_id(),
+            log_level: self.log_level,
+            auth_user: self.auth_user,
         })
     }
 }
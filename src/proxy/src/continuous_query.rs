@@ -0,0 +1,831 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Continuous queries, which aggregate the rows of the source tables into the
+//! target tables at a coarser interval.
+//!
+//! The continuous queries are persisted in a state table, and only the node
+//! owning the shard of the table runs them, so every window is aggregated by
+//! one node even in the cluster mode. Every node syncs the queries from the
+//! table periodically, so the queries are taken over once the shard is moved.
+//!
+//! Every continuous query is run by a task on the background runtime. The
+//! query is run once for every window of `every` after the window is closed
+//! for `delay`, and the aggregated rows are written with the start of the
+//! window as their timestamps. The string columns of the results are written
+//! as tags, and the others are written as fields.
+//!
+//! The end of the last aggregated window is persisted as the watermark into a
+//! table after the window is written, so the missed windows are caught up
+//! after restart. A window may be aggregated again if the server crashes
+//! before its watermark is persisted, which overwrites the same rows.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, Weak},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use common_types::{
+    datum::{Datum, DatumKind},
+    record_batch::RecordBatch,
+};
+use generic_error::{BoxError, GenericError};
+use horaedbproto::storage::{
+    value, RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest,
+};
+use http::StatusCode;
+use interpreters::{continuous_query::ContinuousQueryManager, interpreter::Output};
+use logger::{error, info, warn};
+use query_frontend::{continuous_query, plan::ContinuousQueryDef};
+use runtime::{JoinHandle, RuntimeRef};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use system_catalog::continuous_queries::{status_registry, ContinuousQueryStatus};
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
+use time_ext::{current_time_millis, ReadableDuration};
+use tokio::sync::oneshot;
+
+use crate::{
+    error::{ErrNoCause, Internal, InternalNoCause, Result},
+    schema_registry::types::{convert_records, Record, WriteParams},
+    Context, Proxy,
+};
+
+/// Timestamp of the row of the definition of a continuous query in the state
+/// table.
+const DEFINITION_ROW_TS: i64 = 0;
+/// Timestamp of the row of the watermark of a continuous query, which is
+/// separated from the definition so that advancing the watermark never
+/// overwrites a drop.
+const WATERMARK_ROW_TS: i64 = 1;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Table in the default schema to persist the continuous queries and
+    /// their watermarks.
+    pub state_table: String,
+    /// Max windows to catch up when a continuous query falls behind, the
+    /// older windows are skipped.
+    pub max_catch_up_windows: usize,
+    /// Interval to retry a continuous query after it fails.
+    pub retry_interval: ReadableDuration,
+    /// Interval to sync the continuous queries from the state table.
+    pub sync_interval: ReadableDuration,
+    /// Timeout of the query and the write of a window.
+    pub timeout: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            state_table: "__continuous_queries".to_string(),
+            max_catch_up_windows: 1440,
+            retry_interval: ReadableDuration::secs(30),
+            sync_interval: ReadableDuration::secs(10),
+            timeout: ReadableDuration::secs(300),
+        }
+    }
+}
+
+/// Continuous query persisted in the state table.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PersistedContinuousQuery {
+    schema: String,
+    name: String,
+    source_table: String,
+    target_table: String,
+    time_column: String,
+    every_ms: u64,
+    delay_ms: u64,
+    query: String,
+}
+
+impl PersistedContinuousQuery {
+    fn new(schema: String, def: ContinuousQueryDef) -> Self {
+        let ContinuousQueryDef {
+            name,
+            source_table,
+            target_table,
+            time_column,
+            every,
+            delay,
+            query,
+        } = def;
+
+        Self {
+            schema,
+            name,
+            source_table,
+            target_table,
+            time_column,
+            every_ms: every.as_millis() as u64,
+            delay_ms: delay.as_millis() as u64,
+            query,
+        }
+    }
+
+    /// The windows ending before the returned watermark are closed at `now`.
+    fn closed_watermark(&self, now: i64) -> i64 {
+        align_to_window(now - self.delay_ms as i64, self.every_ms as i64)
+    }
+}
+
+#[derive(Debug)]
+struct QueryState {
+    watermark: i64,
+    last_run_time: Option<i64>,
+    last_error: Option<String>,
+}
+
+/// A continuous query known by this node, which is run only if the node owns
+/// the state table.
+struct KnownQuery {
+    query: Arc<PersistedContinuousQuery>,
+    running: Option<RunningQuery>,
+}
+
+struct RunningQuery {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// Rows of a continuous query loaded from the state table.
+#[derive(Default)]
+struct LoadedQuery {
+    query: Option<PersistedContinuousQuery>,
+    watermark: Option<i64>,
+    dropped: bool,
+}
+
+pub type ContinuousQueryManagerImplRef = Arc<ContinuousQueryManagerImpl>;
+
+pub struct ContinuousQueryManagerImpl {
+    config: Config,
+    runtime: RuntimeRef,
+    task_registry: TaskRegistryRef,
+    /// The proxy is set after it is built, as the proxy holds the manager.
+    proxy: OnceLock<Weak<Proxy>>,
+    queries: Mutex<HashMap<String, KnownQuery>>,
+    sync_task: Mutex<Option<RunningQuery>>,
+    /// Serialize the syncs, as the sync task and the ddls may sync at the same
+    /// time.
+    sync_lock: tokio::sync::Mutex<()>,
+}
+
+impl ContinuousQueryManagerImpl {
+    pub fn new(config: Config, runtime: RuntimeRef, task_registry: TaskRegistryRef) -> Self {
+        Self {
+            config,
+            runtime,
+            task_registry,
+            proxy: OnceLock::new(),
+            queries: Mutex::new(HashMap::new()),
+            sync_task: Mutex::new(None),
+            sync_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn set_proxy(&self, proxy: Weak<Proxy>) {
+        if self.proxy.set(proxy).is_err() {
+            warn!("Proxy of the continuous query manager is already set");
+        }
+    }
+
+    /// Load the persisted continuous queries, and sync them periodically to
+    /// run the queries if this node owns the state table.
+    pub async fn open(self: &Arc<Self>) -> Result<()> {
+        let proxy = self.proxy()?;
+        create_state_table(&proxy, &self.config).await?;
+        self.sync().await?;
+
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+        let manager = Arc::downgrade(self);
+        let interval = self.config.sync_interval.0;
+        let spec = TaskSpec::new(TaskKind::ContinuousQuerySync, "continuous_queries");
+        let sync = async move {
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => return,
+                    _ = tokio::time::sleep(interval) => {},
+                }
+                let Some(manager) = manager.upgrade() else {
+                    return;
+                };
+                if let Err(e) = manager.sync().await {
+                    error!("Failed to sync continuous queries, err:{e}");
+                }
+            }
+        };
+        let handle = self.task_registry.spawn_tracked(&self.runtime, spec, sync);
+        *self.sync_task.lock().unwrap() = Some(RunningQuery { stop_tx, handle });
+
+        Ok(())
+    }
+
+    /// Stop running all the continuous queries.
+    pub async fn stop(&self) {
+        let sync_task = self.sync_task.lock().unwrap().take();
+        if let Some(sync_task) = sync_task {
+            let _ = stop_query(sync_task).await;
+        }
+
+        let running: Vec<_> = self
+            .queries
+            .lock()
+            .unwrap()
+            .drain()
+            .filter_map(|(name, known)| known.running.map(|running| (name, running)))
+            .collect();
+        for (name, running) in running {
+            if stop_query(running).await.is_err() {
+                warn!("Continuous query task is aborted, name:{name}");
+            }
+        }
+    }
+
+    fn proxy(&self) -> Result<Arc<Proxy>> {
+        self.proxy
+            .get()
+            .and_then(|proxy| proxy.upgrade())
+            .context(InternalNoCause {
+                msg: "proxy of the continuous query manager is not available",
+            })
+    }
+
+    /// Sync the continuous queries from the state table, and start (or stop)
+    /// running them if this node owns (or doesn't own) the table.
+    async fn sync(&self) -> Result<()> {
+        let _guard = self.sync_lock.lock().await;
+        let proxy = self.proxy()?;
+        let schema = proxy.instance.catalog_manager.default_schema_name();
+        let owned = proxy.is_local_table(schema, &self.config.state_table)?;
+        let loaded = load_queries(&proxy, &self.config).await?;
+
+        let mut to_stop = Vec::new();
+        {
+            let mut queries = self.queries.lock().unwrap();
+            queries.retain(|name, known| {
+                let alive = loaded
+                    .get(name)
+                    .map(|loaded| !loaded.dropped)
+                    .unwrap_or(false);
+                if !alive {
+                    to_stop.extend(known.running.take().map(|running| (name.clone(), running)));
+                }
+                alive
+            });
+
+            for (name, loaded) in loaded {
+                if loaded.dropped {
+                    continue;
+                }
+                let (Some(query), Some(watermark)) = (loaded.query, loaded.watermark) else {
+                    continue;
+                };
+                let known = queries.entry(name.clone()).or_insert_with(|| KnownQuery {
+                    query: Arc::new(query),
+                    running: None,
+                });
+
+                if !owned {
+                    to_stop.extend(known.running.take().map(|running| (name, running)));
+                    continue;
+                }
+                if known.running.is_none() {
+                    // The watermark may be advanced by the previous owner.
+                    info!("Start continuous query, name:{name}, watermark:{watermark}");
+                    known.running = Some(self.start_query(known.query.clone(), watermark));
+                }
+            }
+        }
+
+        for (name, running) in to_stop {
+            info!("Stop continuous query, name:{name}");
+            status_registry().remove(&name);
+            if stop_query(running).await.is_err() {
+                warn!("Continuous query task is aborted, name:{name}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start_query(&self, query: Arc<PersistedContinuousQuery>, watermark: i64) -> RunningQuery {
+        let runner = ContinuousQueryRunner {
+            config: self.config.clone(),
+            proxy: self.proxy.get().cloned().unwrap_or_default(),
+            query,
+            state: Mutex::new(QueryState {
+                watermark,
+                last_run_time: None,
+                last_error: None,
+            }),
+        };
+        runner.report_status();
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let spec = TaskSpec::new(TaskKind::ContinuousQuery, runner.query.name.clone());
+        let handle = self
+            .task_registry
+            .spawn_tracked(&self.runtime, spec, runner.run(stop_rx));
+
+        RunningQuery { stop_tx, handle }
+    }
+}
+
+#[async_trait]
+impl ContinuousQueryManager for ContinuousQueryManagerImpl {
+    fn contains(&self, name: &str) -> bool {
+        self.queries.lock().unwrap().contains_key(name)
+    }
+
+    async fn create_continuous_query(
+        &self,
+        schema: &str,
+        continuous_query: ContinuousQueryDef,
+    ) -> std::result::Result<(), GenericError> {
+        let proxy = self.proxy().box_err()?;
+        create_state_table(&proxy, &self.config).await.box_err()?;
+
+        let query = PersistedContinuousQuery::new(schema.to_string(), continuous_query);
+        // Only the windows closed after the creation are aggregated.
+        let watermark = query.closed_watermark(current_time_millis() as i64);
+        save_watermark(&proxy, &self.config, &query.name, watermark)
+            .await
+            .box_err()?;
+        save_definition(&proxy, &self.config, &query, false)
+            .await
+            .box_err()?;
+        info!("Create continuous query, query:{query:?}, watermark:{watermark}");
+        self.sync().await.box_err()
+    }
+
+    async fn drop_continuous_query(&self, name: &str) -> std::result::Result<(), GenericError> {
+        let proxy = self.proxy().box_err()?;
+        let query = self
+            .queries
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|known| known.query.clone());
+        let Some(query) = query else {
+            return Ok(());
+        };
+
+        save_definition(&proxy, &self.config, &query, true)
+            .await
+            .box_err()?;
+        info!("Drop continuous query, name:{name}");
+        // The query run by other nodes is stopped in their next syncs.
+        self.sync().await.box_err()
+    }
+}
+
+async fn stop_query(running: RunningQuery) -> std::result::Result<(), runtime::Error> {
+    let _ = running.stop_tx.send(());
+    running.handle.await
+}
+
+async fn create_state_table(proxy: &Proxy, config: &Config) -> Result<()> {
+    let sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (query_name string TAG, ts timestamp NOT NULL, \
+         definition string, watermark bigint, dropped boolean, TIMESTAMP KEY(ts)) \
+         ENGINE=Analytic WITH(enable_ttl='false', update_mode='OVERWRITE')",
+        config.state_table
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+/// Load the rows of all the continuous queries, including the dropped ones.
+async fn load_queries(proxy: &Proxy, config: &Config) -> Result<HashMap<String, LoadedQuery>> {
+    let sql = format!(
+        "SELECT query_name, ts, definition, watermark, dropped FROM {}",
+        config.state_table
+    );
+    let output = proxy
+        .fetch_sql_query_output(
+            &Context::new(Some(config.timeout.0), None),
+            proxy.instance.catalog_manager.default_schema_name(),
+            &sql,
+            false,
+            false,
+        )
+        .await?;
+    let Output::Records(batches) = output else {
+        return InternalNoCause {
+            msg: "unexpected output of loading continuous queries",
+        }
+        .fail();
+    };
+
+    let mut queries: HashMap<String, LoadedQuery> = HashMap::new();
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let Some(name) = batch.column(0).datum_view(row).into_str() else {
+                continue;
+            };
+            let loaded = queries.entry(name.to_string()).or_default();
+            let ts = batch.column(1).datum_view(row).as_timestamp();
+            if ts.map(|ts| ts.as_i64()) == Some(WATERMARK_ROW_TS) {
+                loaded.watermark = batch.column(3).datum_view(row).as_i64();
+                continue;
+            }
+
+            loaded.dropped = batch.column(4).datum_view(row).as_bool() == Some(true);
+            let Some(definition) = batch.column(2).datum_view(row).into_str() else {
+                continue;
+            };
+            match serde_json::from_str(definition) {
+                Ok(v) => loaded.query = Some(v),
+                Err(e) => {
+                    error!("Failed to decode continuous query, definition:{definition}, err:{e}")
+                }
+            }
+        }
+    }
+
+    Ok(queries)
+}
+
+/// Every continuous query has only one row of its definition in the state
+/// table, which is overwritten as the timestamp is always the same.
+async fn save_definition(
+    proxy: &Proxy,
+    config: &Config,
+    query: &PersistedContinuousQuery,
+    dropped: bool,
+) -> Result<()> {
+    let definition = serde_json::to_string(query).box_err().context(Internal {
+        msg: "failed to encode continuous query",
+    })?;
+    let sql = format!(
+        "INSERT INTO {} (query_name, ts, definition, dropped) \
+         VALUES ('{}', {DEFINITION_ROW_TS}, '{}', {dropped})",
+        config.state_table,
+        escape_string(&query.name),
+        escape_string(&definition),
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+/// Every continuous query has only one row of its watermark, like the
+/// definition.
+async fn save_watermark(proxy: &Proxy, config: &Config, name: &str, watermark: i64) -> Result<()> {
+    let sql = format!(
+        "INSERT INTO {} (query_name, ts, watermark) VALUES ('{}', {WATERMARK_ROW_TS}, {watermark})",
+        config.state_table,
+        escape_string(name),
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+async fn execute_sql(proxy: &Proxy, config: &Config, sql: &str) -> Result<()> {
+    proxy
+        .fetch_sql_query_output(
+            &Context::new(Some(config.timeout.0), None),
+            proxy.instance.catalog_manager.default_schema_name(),
+            sql,
+            false,
+            false,
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn escape_string(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+fn align_to_window(ts: i64, every: i64) -> i64 {
+    ts.div_euclid(every) * every
+}
+
+struct ContinuousQueryRunner {
+    config: Config,
+    proxy: Weak<Proxy>,
+    query: Arc<PersistedContinuousQuery>,
+    state: Mutex<QueryState>,
+}
+
+impl ContinuousQueryRunner {
+    async fn run(self, mut stop_rx: oneshot::Receiver<()>) {
+        loop {
+            let res = tokio::select! {
+                _ = &mut stop_rx => return,
+                res = self.run_closed_windows() => res,
+            };
+            let wait = match res {
+                Ok(wait) => wait,
+                Err(e) => {
+                    error!(
+                        "Failed to run continuous query, name:{}, err:{e}",
+                        self.query.name
+                    );
+                    self.state.lock().unwrap().last_error = Some(e.to_string());
+                    self.report_status();
+                    self.config.retry_interval.0
+                }
+            };
+
+            tokio::select! {
+                _ = &mut stop_rx => return,
+                _ = tokio::time::sleep(wait) => {},
+            }
+        }
+    }
+
+    /// Aggregate all the closed windows, returns the time to wait for the next
+    /// closed window.
+    async fn run_closed_windows(&self) -> Result<Duration> {
+        let every = self.query.every_ms as i64;
+        loop {
+            let now = current_time_millis() as i64;
+            let closed_watermark = self.query.closed_watermark(now);
+            let mut start = self.state.lock().unwrap().watermark;
+            if start + every > closed_watermark {
+                let next_closed = start + every + self.query.delay_ms as i64;
+                return Ok(Duration::from_millis((next_closed - now).max(0) as u64));
+            }
+
+            let max_catch_up = every.saturating_mul(self.config.max_catch_up_windows as i64);
+            if closed_watermark - start > max_catch_up {
+                let skip_to = closed_watermark - max_catch_up;
+                warn!(
+                    "Continuous query falls behind, skip windows, name:{}, from:{start}, to:{skip_to}",
+                    self.query.name
+                );
+                start = skip_to;
+            }
+
+            let end = start + every;
+            self.run_window(start, end).await?;
+            self.advance_watermark(end).await?;
+        }
+    }
+
+    async fn run_window(&self, start: i64, end: i64) -> Result<()> {
+        let proxy = self.proxy.upgrade().context(InternalNoCause {
+            msg: "proxy is dropped",
+        })?;
+        let sql =
+            continuous_query::window_sql(&self.query.query, &self.query.time_column, start, end)
+                .box_err()
+                .context(Internal {
+                    msg: "failed to build query of the window",
+                })?;
+        let ctx = Context::new(Some(self.config.timeout.0), None);
+        let output = proxy
+            .fetch_sql_query_output(&ctx, &self.query.schema, &sql, false, false)
+            .await?;
+        let Output::Records(batches) = output else {
+            return InternalNoCause {
+                msg: "unexpected output of continuous query",
+            }
+            .fail();
+        };
+
+        let (records, tags) = batches_to_records(&batches, &self.query.time_column, start);
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let params = WriteParams {
+            table: Some(self.query.target_table.clone()),
+            timestamp_field: self.query.time_column.clone(),
+            tags: tags.join(","),
+            ..Default::default()
+        };
+        let num_rows = records.len();
+        let req = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: self.query.schema.clone(),
+            }),
+            table_requests: vec![convert_records(
+                self.query.target_table.clone(),
+                &params,
+                records,
+            )?],
+        };
+        let resp = proxy.handle_write_internal(ctx, req).await?;
+        if resp.failed != 0 {
+            return ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!(
+                    "failed to write continuous query, rows:{num_rows}, failed:{}",
+                    resp.failed
+                ),
+            }
+            .fail();
+        }
+
+        Ok(())
+    }
+
+    async fn advance_watermark(&self, watermark: i64) -> Result<()> {
+        let proxy = self.proxy.upgrade().context(InternalNoCause {
+            msg: "proxy is dropped",
+        })?;
+        save_watermark(&proxy, &self.config, &self.query.name, watermark).await?;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.watermark = watermark;
+            state.last_run_time = Some(current_time_millis() as i64);
+            state.last_error = None;
+        }
+        self.report_status();
+
+        Ok(())
+    }
+
+    fn report_status(&self) {
+        let state = self.state.lock().unwrap();
+        status_registry().update(ContinuousQueryStatus {
+            name: self.query.name.clone(),
+            schema: self.query.schema.clone(),
+            source_table: self.query.source_table.clone(),
+            target_table: self.query.target_table.clone(),
+            interval_ms: self.query.every_ms,
+            watermark: Some(state.watermark),
+            last_run_time: state.last_run_time,
+            last_error: state.last_error.clone(),
+        });
+    }
+}
+
+/// Convert the results of a window into records timestamped by the start of
+/// the window, returns the records and the string columns written as tags.
+fn batches_to_records(
+    batches: &[RecordBatch],
+    time_column: &str,
+    start: i64,
+) -> (Vec<Record>, Vec<String>) {
+    let mut records = Vec::new();
+    let mut tags = Vec::new();
+    for batch in batches {
+        let columns = batch.schema().columns();
+        for column in columns {
+            if column.data_type == DatumKind::String && !tags.contains(&column.name) {
+                tags.push(column.name.clone());
+            }
+        }
+
+        for row in 0..batch.num_rows() {
+            let mut record = Vec::with_capacity(columns.len() + 1);
+            record.push((
+                time_column.to_string(),
+                value::Value::TimestampValue(start),
+            ));
+            for (idx, column) in columns.iter().enumerate() {
+                // The timestamp is decided by the window.
+                if column.name == time_column {
+                    continue;
+                }
+                if let Some(value) = datum_to_value(batch.column(idx).datum(row)) {
+                    record.push((column.name.clone(), value));
+                }
+            }
+            records.push(record);
+        }
+    }
+
+    (records, tags)
+}
+
+fn datum_to_value(datum: Datum) -> Option<value::Value> {
+    let value = match datum {
+        Datum::Null => return None,
+        Datum::Timestamp(v) => value::Value::TimestampValue(v.as_i64()),
+        Datum::Double(v) => value::Value::Float64Value(v),
+        Datum::Float(v) => value::Value::Float32Value(v),
+        Datum::Varbinary(v) => value::Value::VarbinaryValue(v.to_vec()),
+        Datum::String(v) => value::Value::StringValue(v.to_string()),
+        Datum::UInt64(v) => value::Value::Uint64Value(v),
+        Datum::UInt32(v) => value::Value::Uint32Value(v),
+        Datum::UInt16(v) => value::Value::Uint16Value(v.into()),
+        Datum::UInt8(v) => value::Value::Uint8Value(v.into()),
+        Datum::Int64(v) => value::Value::Int64Value(v),
+        Datum::Int32(v) => value::Value::Int32Value(v),
+        Datum::Int16(v) => value::Value::Int16Value(v.into()),
+        Datum::Int8(v) => value::Value::Int8Value(v.into()),
+        Datum::Boolean(v) => value::Value::BoolValue(v),
+        Datum::Date(v) => value::Value::Int32Value(v),
+        Datum::Time(v) => value::Value::Int64Value(v),
+    };
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::{
+        column_schema,
+        record_batch::FetchedRecordBatchBuilder,
+        row::Row,
+        schema::{self, RecordSchema},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_align_to_window() {
+        assert_eq!(align_to_window(0, 60), 0);
+        assert_eq!(align_to_window(59, 60), 0);
+        assert_eq!(align_to_window(60, 60), 60);
+        assert_eq!(align_to_window(-1, 60), -60);
+
+        let query = PersistedContinuousQuery {
+            schema: "public".to_string(),
+            name: "cq".to_string(),
+            source_table: "cpu".to_string(),
+            target_table: "cpu_1m".to_string(),
+            time_column: "ts".to_string(),
+            every_ms: 60_000,
+            delay_ms: 10_000,
+            query: "SELECT count(*) FROM cpu".to_string(),
+        };
+        assert_eq!(query.closed_watermark(69_999), 0);
+        assert_eq!(query.closed_watermark(70_000), 60_000);
+    }
+
+    #[test]
+    fn test_batches_to_records() {
+        let schema: RecordSchema = schema::Builder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                column_schema::Builder::new("ts".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("host".to_string(), DatumKind::String)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("value".to_string(), DatumKind::Double)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .primary_key_indexes(vec![0])
+            .build()
+            .unwrap()
+            .to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(schema, None);
+        builder
+            .append_row(Row::from_datums(vec![
+                Datum::Timestamp(1.into()),
+                Datum::from("a"),
+                Datum::Double(1.5),
+            ]))
+            .unwrap();
+        builder
+            .append_row(Row::from_datums(vec![
+                Datum::Timestamp(2.into()),
+                Datum::from("b"),
+                Datum::Null,
+            ]))
+            .unwrap();
+        let batch = builder.build().unwrap().into_record_batch();
+
+        let (records, tags) = batches_to_records(&[batch], "ts", 60_000);
+        assert_eq!(tags, vec!["host".to_string()]);
+        assert_eq!(
+            records,
+            vec![
+                vec![
+                    ("ts".to_string(), value::Value::TimestampValue(60_000)),
+                    ("host".to_string(), value::Value::StringValue("a".to_string())),
+                    ("value".to_string(), value::Value::Float64Value(1.5)),
+                ],
+                vec![
+                    ("ts".to_string(), value::Value::TimestampValue(60_000)),
+                    ("host".to_string(), value::Value::StringValue("b".to_string())),
+                ],
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Draining of the in-flight requests during the graceful shutdown.
+//!
+//! Once the draining starts, the server is reported as not ready so that the
+//! load balancers stop routing new requests to it, and the shutdown waits for
+//! the in-flight requests to finish before stopping the services.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+#[derive(Default)]
+pub struct Drainer {
+    draining: AtomicBool,
+    inflight: AtomicUsize,
+    /// Notified when the last in-flight request finishes.
+    idle: Notify,
+}
+
+impl Drainer {
+    /// Track a request until the returned guard is dropped.
+    pub fn track(&self) -> InflightGuard<'_> {
+        self.inflight.fetch_add(1, Ordering::Relaxed);
+        InflightGuard { drainer: self }
+    }
+
+    pub fn start_drain(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    pub fn inflight(&self) -> usize {
+        self.inflight.load(Ordering::Relaxed)
+    }
+
+    /// Wait for all the in-flight requests to finish, returns false if some
+    /// requests are still running after the `timeout`.
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.inflight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.is_ok()
+    }
+}
+
+pub struct InflightGuard<'a> {
+    drainer: &'a Drainer,
+}
+
+impl<'a> Drop for InflightGuard<'a> {
+    fn drop(&mut self) {
+        if self.drainer.inflight.fetch_sub(1, Ordering::Relaxed) == 1 {
+            self.drainer.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_idle() {
+        let drainer = Arc::new(Drainer::default());
+        assert!(drainer.wait_idle(Duration::from_millis(10)).await);
+
+        let guard = drainer.track();
+        drainer.start_drain();
+        assert!(drainer.is_draining());
+        assert!(!drainer.wait_idle(Duration::from_millis(10)).await);
+
+        let waiter = {
+            let drainer = drainer.clone();
+            tokio::spawn(async move { drainer.wait_idle(Duration::from_secs(10)).await })
+        };
+        drop(guard);
+        assert!(waiter.await.unwrap());
+        assert_eq!(drainer.inflight(), 0);
+    }
+}
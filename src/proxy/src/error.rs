@@ -15,13 +15,15 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::time::Duration;
+
 use generic_error::GenericError;
 use horaedbproto::common::ResponseHeader;
 use http::StatusCode;
 use macros::define_result;
 use snafu::{Backtrace, Snafu};
 
-use crate::error_util;
+use crate::{admission, error_util};
 
 define_result!(Error);
 
@@ -49,6 +51,11 @@ pub enum Error {
 }
 
 impl Error {
+    /// Code of the response header.
+    ///
+    /// The clients retry the requests failed with `TOO_MANY_REQUESTS` or
+    /// `SERVICE_UNAVAILABLE`, so they should only be used when the request can
+    /// succeed later, see the `client` crate.
     pub fn code(&self) -> StatusCode {
         match *self {
             Error::ErrNoCause { code, .. } => code,
@@ -60,6 +67,19 @@ impl Error {
         }
     }
 
+    /// Delay suggested to the clients before retrying the request rejected by
+    /// the admission control.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::ErrWithCause { source, .. } | Error::Internal { source, .. } => source
+                .downcast_ref::<admission::Error>()
+                .and_then(admission::Error::retry_after),
+            Error::ErrNoCause { .. }
+            | Error::InternalNoCause { .. }
+            | Error::QueryMaybeExceedTTL { .. } => None,
+        }
+    }
+
     /// Get the error message returned to the user.
     pub fn error_message(&self) -> String {
         match self {
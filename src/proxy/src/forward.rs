@@ -23,6 +23,7 @@ use std::{
 };
 
 use async_trait::async_trait;
+use common_types::protocol::INTERNAL_TOKEN_KEY;
 use horaedbproto::storage::{
     storage_service_client::StorageServiceClient, RequestContext, RouteRequest as RouteRequestPb,
 };
@@ -34,10 +35,10 @@ use snafu::{Backtrace, ResultExt, Snafu};
 use time_ext::ReadableDuration;
 use tonic::{
     metadata::errors::InvalidMetadataValue,
-    transport::{self, Channel},
+    transport::{self, Channel, ClientTlsConfig},
 };
 
-use crate::FORWARDED_FROM;
+use crate::{auth, metrics::FORWARD_COUNTER_VEC, FORWARDED_FROM};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -116,6 +117,16 @@ pub struct Config {
     pub keep_alive_while_idle: bool,
     pub connect_timeout: ReadableDuration,
     pub forward_timeout: Option<ReadableDuration>,
+    /// Token shared by the nodes of the cluster and attached to the forwarded
+    /// requests. The forwarded requests skip the admission on the receiving
+    /// nodes, so they are only trusted if they carry this token, and no request
+    /// is trusted if it's not set. It's attached to the remote engine requests
+    /// too, which are rejected without it once the authentication is enabled.
+    pub internal_token: Option<String>,
+    /// TLS of the connections to the other nodes, which is built from the
+    /// grpc TLS of the server rather than configured here.
+    #[serde(skip)]
+    pub tls: Option<ClientTlsConfig>,
 }
 
 impl Default for Config {
@@ -126,6 +137,8 @@ impl Default for Config {
             keep_alive_while_idle: true,
             connect_timeout: ReadableDuration::secs(3),
             forward_timeout: None,
+            internal_token: None,
+            tls: None,
         }
     }
 }
@@ -141,19 +154,32 @@ pub struct DefaultClientBuilder {
 
 impl DefaultClientBuilder {
     #[inline]
-    fn make_endpoint_with_scheme(endpoint: &Endpoint) -> String {
-        format!("http://{}:{}", endpoint.addr, endpoint.port)
+    fn make_endpoint_with_scheme(&self, endpoint: &Endpoint) -> String {
+        let scheme = if self.config.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        format!("{scheme}://{}:{}", endpoint.addr, endpoint.port)
     }
 }
 
 #[async_trait]
 impl ClientBuilder for DefaultClientBuilder {
     async fn connect(&self, endpoint: &Endpoint) -> Result<StorageServiceClient<Channel>> {
-        let endpoint_with_scheme = Self::make_endpoint_with_scheme(endpoint);
+        let endpoint_with_scheme = self.make_endpoint_with_scheme(endpoint);
         let configured_endpoint = transport::Endpoint::from_shared(endpoint_with_scheme.clone())
             .context(InvalidEndpoint {
                 endpoint: &endpoint_with_scheme,
             })?;
+        let configured_endpoint = match &self.config.tls {
+            Some(tls) => configured_endpoint
+                .tls_config(tls.clone())
+                .context(InvalidEndpoint {
+                    endpoint: &endpoint_with_scheme,
+                })?,
+            None => configured_endpoint,
+        };
 
         let configured_endpoint = match self.config.keep_alive_while_idle {
             true => configured_endpoint
@@ -227,6 +253,18 @@ impl<B> Forwarder<B> {
             .unwrap_or(false)
     }
 
+    /// Whether the request is forwarded by another node of the cluster, that is
+    /// it carries the internal token.
+    pub fn is_internal_request<T>(&self, req: &tonic::Request<T>) -> bool {
+        let Some(expected) = &self.config.internal_token else {
+            return false;
+        };
+
+        req.metadata()
+            .get(INTERNAL_TOKEN_KEY)
+            .is_some_and(|v| auth::constant_time_eq(v.as_bytes(), expected.as_bytes()))
+    }
+
     /// Check whether the target endpoint is the same as the local endpoint.
     pub fn is_local_endpoint(&self, target: &Endpoint) -> bool {
         if &self.local_endpoint == target {
@@ -325,6 +363,7 @@ impl<B: ClientBuilder> Forwarder<B> {
         Req: std::fmt::Debug + Clone,
     {
         if self.is_local_endpoint(&endpoint) {
+            FORWARD_COUNTER_VEC.with_label_values(&["local"]).inc();
             return Ok(ForwardResult::Local);
         }
 
@@ -335,13 +374,13 @@ impl<B: ClientBuilder> Forwarder<B> {
             }
         }
 
-        // TODO: add metrics to record the forwarding.
         debug!(
             "Try to forward request to {:?}, request:{:?}",
             endpoint, req,
         );
 
         if let Some(endpoint) = forwarded_from {
+            FORWARD_COUNTER_VEC.with_label_values(&["rejected"]).inc();
             return ForwardedErr { endpoint }.fail();
         }
 
@@ -350,16 +389,47 @@ impl<B: ClientBuilder> Forwarder<B> {
             FORWARDED_FROM,
             self.local_endpoint.to_string().parse().unwrap(),
         );
+        let token = self.config.internal_token.as_deref();
+        if let Some(token) = token.and_then(|v| v.parse().ok()) {
+            req.metadata_mut().insert(INTERNAL_TOKEN_KEY, token);
+        }
 
         let client = self.get_or_create_client(&endpoint).await?;
         match do_rpc(client, req, &endpoint).await {
             Err(e) => {
+                FORWARD_COUNTER_VEC.with_label_values(&["failed"]).inc();
                 // Release the grpc client for the error doesn't belong to the normal error.
                 self.release_client(&endpoint);
                 Ok(ForwardResult::Forwarded(Err(e)))
             }
-            Ok(resp) => Ok(ForwardResult::Forwarded(Ok(resp))),
+            Ok(resp) => {
+                FORWARD_COUNTER_VEC.with_label_values(&["forwarded"]).inc();
+                Ok(ForwardResult::Forwarded(Ok(resp)))
+            }
+        }
+    }
+
+    /// Send the request to the endpoint as the requests of the clients, that
+    /// is it's neither marked as forwarded nor carries the internal token, so
+    /// it's authenticated and admitted by the endpoint.
+    pub async fn send_with_endpoint<Req, Resp, Err, F>(
+        &self,
+        endpoint: Endpoint,
+        req: tonic::Request<Req>,
+        do_rpc: F,
+    ) -> Result<std::result::Result<Resp, Err>>
+    where
+        F: ForwarderRpc<Req, Resp, Err>,
+        Req: std::fmt::Debug + Clone,
+    {
+        debug!("Try to send request to {:?}, request:{:?}", endpoint, req);
+
+        let client = self.get_or_create_client(&endpoint).await?;
+        let result = do_rpc(client, req, &endpoint).await;
+        if result.is_err() {
+            self.release_client(&endpoint);
         }
+        Ok(result)
     }
 
     async fn get_or_create_client(
@@ -537,4 +607,59 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_internal_request() {
+        let local_endpoint = Endpoint::new("192.168.1.1".to_string(), 8831);
+        let remote_endpoint = Endpoint::new("192.168.1.2".to_string(), 8831);
+        let mock_router = Arc::new(MockRouter {
+            routing_tables: HashMap::new(),
+        });
+        let make_forwarder = |internal_token: Option<&str>| {
+            let config = Config {
+                internal_token: internal_token.map(|v| v.to_string()),
+                ..Default::default()
+            };
+            Forwarder::new_with_client_builder(
+                config,
+                mock_router.clone() as _,
+                local_endpoint.clone(),
+                MockClientBuilder,
+            )
+        };
+        let make_req = |token: Option<&str>| {
+            let mut req = SqlQueryRequest::default().into_request();
+            if let Some(token) = token {
+                req.metadata_mut()
+                    .insert(INTERNAL_TOKEN_KEY, token.parse().unwrap());
+            }
+            req
+        };
+
+        let forwarder = make_forwarder(Some("secret"));
+        assert!(forwarder.is_internal_request(&make_req(Some("secret"))));
+        assert!(!forwarder.is_internal_request(&make_req(Some("secreT"))));
+        assert!(!forwarder.is_internal_request(&make_req(None)));
+
+        // The forwarded requests carry the token.
+        let forwarded = forwarder
+            .forward_with_endpoint(
+                remote_endpoint.clone(),
+                make_req(None),
+                None,
+                |_client, req: tonic::Request<SqlQueryRequest>, _: &Endpoint| {
+                    assert!(make_forwarder(Some("secret")).is_internal_request(&req));
+                    let resp = SqlQueryResponse::default();
+                    Box::new(async move { Ok::<_, Error>(resp) }.boxed()) as _
+                },
+            )
+            .await
+            .unwrap();
+        assert!(matches!(forwarded, ForwardResult::Forwarded(Ok(_))));
+
+        // No request is trusted without the token configured.
+        let forwarder = make_forwarder(None);
+        assert!(!forwarder.is_internal_request(&make_req(Some("secret"))));
+        assert!(!forwarder.is_internal_request(&make_req(None)));
+    }
 }
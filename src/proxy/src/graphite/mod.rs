@@ -0,0 +1,163 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! This module implements the ingestion of the Graphite plaintext and pickle
+//! protocols, and a minimal [render api][1] returning the json format.
+//!
+//! [1]: https://graphite.readthedocs.io/en/latest/render_api.html
+
+use horaedbproto::storage::{
+    RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest,
+};
+use http::StatusCode;
+use interpreters::interpreter::Output;
+use logger::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::RequestContext,
+    error::{ErrNoCause, Result},
+    graphite::types::{now_millis, parse_render_time, Point, RenderParams, Series},
+    Context, Proxy,
+};
+
+pub mod types;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Enable the listeners of the write protocols, and the render api is
+    /// always served by the http service.
+    pub enable: bool,
+    /// Port of the plaintext protocol.
+    pub port: u16,
+    /// Port of the pickle protocol.
+    pub pickle_port: u16,
+    /// Schema of the tables, the default schema is used if empty.
+    pub schema: String,
+    /// Separator to join the measurement parts of a path into the table name.
+    pub separator: String,
+    pub templates: Vec<TemplateConfig>,
+    /// Max points of a write, the points received are written once reaching
+    /// it.
+    pub max_batch_points: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            port: 2003,
+            pickle_port: 2004,
+            schema: String::new(),
+            separator: "_".to_string(),
+            templates: Vec::new(),
+            max_batch_points: 1000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TemplateConfig {
+    /// Glob patterns of the leading parts of the paths using this template,
+    /// e.g. `servers.*`, and all the paths match if empty.
+    pub filter: String,
+    /// Names of the parts of the paths, the names are `measurement`,
+    /// `measurement*` (this and all the following parts), `_` (skipped), or the
+    /// tag names, e.g. `_.host.measurement*`.
+    pub template: String,
+}
+
+impl Proxy {
+    pub async fn handle_graphite_write(
+        &self,
+        ctx: RequestContext,
+        points: Vec<Point>,
+    ) -> Result<()> {
+        let num_points = points.len();
+        let table_requests = self.graphite_templates.convert_points(points)?;
+        let req = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: ctx.schema.clone(),
+            }),
+            table_requests,
+        };
+
+        let result = self
+            .handle_write_internal(Context::new(ctx.timeout, None), req)
+            .await?;
+        if result.failed != 0 {
+            return ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("fail to write storage, failed rows:{:?}", result.failed),
+            }
+            .fail();
+        }
+
+        debug!(
+            "Graphite write finished, catalog:{}, schema:{}, points:{num_points}, result:{result:?}",
+            ctx.catalog, ctx.schema
+        );
+
+        Ok(())
+    }
+
+    pub async fn handle_graphite_render(
+        &self,
+        ctx: RequestContext,
+        params: RenderParams,
+    ) -> Result<Vec<Series>> {
+        if params.format != "json" {
+            return ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("unsupported render format:{}", params.format),
+            }
+            .fail();
+        }
+        if params.target.is_empty() {
+            return ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: "target is required",
+            }
+            .fail();
+        }
+
+        let now = now_millis();
+        let from = parse_render_time(&params.from, now)?;
+        let until = parse_render_time(&params.until, now)?;
+        let query = self.graphite_templates.resolve_target(&params.target)?;
+        let sql = query.to_sql(from, until);
+        debug!("Graphite render, target:{}, sql:{sql}", params.target);
+
+        let output = self
+            .fetch_sql_query_output(
+                &Context::new(ctx.timeout, None)
+                    .with_auth_user(ctx.auth_user.clone()),
+                &ctx.schema,
+                &sql,
+                false,
+                true,
+            )
+            .await?;
+        let Output::Records(batches) = output else {
+            return Ok(Vec::new());
+        };
+
+        Ok(query.to_series(&batches))
+    }
+}
@@ -0,0 +1,760 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Types and conversions of the [Graphite][1] protocols.
+//!
+//! A dotted metric path is mapped to a table and the tags by the templates,
+//! which are borrowed from the [graphite templates][2] of InfluxDB. For
+//! example, the template `region.host.measurement*` maps the path
+//! `us-west.server01.cpu.load` to the table `cpu_load` with the tags
+//! `region=us-west` and `host=server01`. The values are written into the
+//! `value` field.
+//!
+//! [1]: https://graphite.readthedocs.io/en/latest/feeding-carbon.html
+//! [2]: https://github.com/influxdata/influxdb/tree/1.8/services/graphite
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common_types::{record_batch::RecordBatch, schema::TIMESTAMP_COLUMN};
+use generic_error::BoxError;
+use horaedbproto::storage::{value, WriteTableRequest};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_pickle::{DeOptions, Value as PickleValue};
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::{
+    error::{ErrNoCause, ErrWithCause, Result},
+    graphite::TemplateConfig,
+    schema_registry::types::{convert_records, Record, WriteParams},
+    util::quote_ident,
+};
+
+const VALUE_FIELD: &str = "value";
+const MEASUREMENT: &str = "measurement";
+const MEASUREMENT_REST: &str = "measurement*";
+/// Placeholder of the skipped part in the templates.
+const SKIP: &str = "_";
+
+/// A point of the graphite protocols.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub path: String,
+    /// Tags of the [tagged series](https://graphite.readthedocs.io/en/latest/tags.html).
+    pub tags: Vec<(String, String)>,
+    pub value: f64,
+    pub timestamp: i64,
+}
+
+fn invalid_input(msg: String) -> Result<Point> {
+    ErrNoCause {
+        code: StatusCode::BAD_REQUEST,
+        msg,
+    }
+    .fail()
+}
+
+/// Parse a line of the plaintext protocol: `path[;tag=value...] value
+/// timestamp`.
+///
+/// The timestamp is in seconds, and the current time is used if it's `-1`.
+pub fn parse_plaintext_line(line: &str) -> Result<Point> {
+    let mut parts = line.split_whitespace();
+    let (Some(series), Some(value), Some(timestamp), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return invalid_input(format!("invalid graphite line:{line}"));
+    };
+
+    let Ok(value) = value.parse::<f64>() else {
+        return invalid_input(format!("invalid value of graphite line:{line}"));
+    };
+    let Ok(timestamp) = timestamp.parse::<f64>() else {
+        return invalid_input(format!("invalid timestamp of graphite line:{line}"));
+    };
+    let (path, tags) = parse_series(series)?;
+
+    Ok(Point {
+        path,
+        tags,
+        value,
+        timestamp: to_millis(timestamp),
+    })
+}
+
+/// Parse the payload of a message of the pickle protocol, which is a pickled
+/// list of `(path, (timestamp, value))`.
+pub fn parse_pickle(payload: &[u8]) -> Result<Vec<Point>> {
+    let invalid = || ErrNoCause {
+        code: StatusCode::BAD_REQUEST,
+        msg: "invalid graphite pickle",
+    };
+
+    let value = serde_pickle::value_from_slice(payload, DeOptions::new())
+        .box_err()
+        .context(ErrWithCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "failed to decode graphite pickle",
+        })?;
+    let PickleValue::List(items) = value else {
+        return invalid().fail();
+    };
+
+    let mut points = Vec::with_capacity(items.len());
+    for item in items {
+        let (PickleValue::Tuple(item) | PickleValue::List(item)) = item else {
+            return invalid().fail();
+        };
+        let [series, datapoint] = <[PickleValue; 2]>::try_from(item)
+            .ok()
+            .with_context(invalid)?;
+        let (PickleValue::Tuple(datapoint) | PickleValue::List(datapoint)) = datapoint else {
+            return invalid().fail();
+        };
+        let [timestamp, value] = <[PickleValue; 2]>::try_from(datapoint)
+            .ok()
+            .with_context(invalid)?;
+
+        let series = match series {
+            PickleValue::String(v) => v,
+            PickleValue::Bytes(v) => String::from_utf8(v).ok().with_context(invalid)?,
+            _ => return invalid().fail(),
+        };
+        let (path, tags) = parse_series(&series)?;
+        let timestamp = pickle_to_f64(timestamp).with_context(invalid)?;
+        let value = pickle_to_f64(value).with_context(invalid)?;
+        points.push(Point {
+            path,
+            tags,
+            value,
+            timestamp: to_millis(timestamp),
+        });
+    }
+
+    Ok(points)
+}
+
+fn pickle_to_f64(value: PickleValue) -> Option<f64> {
+    match value {
+        PickleValue::I64(v) => Some(v as f64),
+        PickleValue::F64(v) => Some(v),
+        PickleValue::String(v) => v.parse().ok(),
+        _ => None,
+    }
+}
+
+fn parse_series(series: &str) -> Result<(String, Vec<(String, String)>)> {
+    let mut parts = series.split(';');
+    let path = parts.next().unwrap_or_default();
+    ensure!(
+        !path.is_empty(),
+        ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("empty graphite path, series:{series}"),
+        }
+    );
+
+    let tags = parts
+        .map(|tag| {
+            tag.split_once('=')
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .with_context(|| ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("invalid graphite tag, series:{series}"),
+                })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((path.to_string(), tags))
+}
+
+fn to_millis(timestamp_secs: f64) -> i64 {
+    if timestamp_secs < 0.0 {
+        return now_millis();
+    }
+    (timestamp_secs * 1000.0) as i64
+}
+
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Measurement,
+    /// The measurement consists of this part and all the following parts.
+    MeasurementRest,
+    Tag(String),
+    Skip,
+}
+
+/// Template mapping the matched paths to the tables and tags.
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// Glob patterns of the leading parts of the matched paths, matches all
+    /// the paths if empty.
+    filter: Vec<String>,
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    pub fn new(config: &TemplateConfig) -> Self {
+        let filter = if config.filter.is_empty() {
+            Vec::new()
+        } else {
+            config.filter.split('.').map(|v| v.to_string()).collect()
+        };
+        let parts = config
+            .template
+            .split('.')
+            .map(|part| match part {
+                MEASUREMENT => TemplatePart::Measurement,
+                MEASUREMENT_REST => TemplatePart::MeasurementRest,
+                "" | SKIP => TemplatePart::Skip,
+                tag => TemplatePart::Tag(tag.to_string()),
+            })
+            .collect();
+
+        Self { filter, parts }
+    }
+
+    /// Whether the path matches the filter, the parts of the path may be
+    /// patterns, and such parts are considered as matched.
+    fn matches(&self, path_parts: &[&str]) -> bool {
+        self.filter.len() <= path_parts.len()
+            && self
+                .filter
+                .iter()
+                .zip(path_parts)
+                .all(|(filter, part)| is_pattern(part) || glob_match(filter, part))
+    }
+
+    /// Map the path to its measurement parts and the tags, the parts not
+    /// covered by the template are ignored.
+    fn apply<'a>(&self, path_parts: &[&'a str]) -> (Vec<&'a str>, Vec<(&str, &'a str)>) {
+        let mut measurement = Vec::new();
+        let mut tags = Vec::new();
+        for (i, part) in self.parts.iter().enumerate() {
+            let Some(path_part) = path_parts.get(i) else {
+                break;
+            };
+            match part {
+                TemplatePart::Measurement => measurement.push(*path_part),
+                TemplatePart::MeasurementRest => {
+                    measurement.extend_from_slice(&path_parts[i..]);
+                    break;
+                }
+                TemplatePart::Tag(name) => tags.push((name.as_str(), *path_part)),
+                TemplatePart::Skip => {}
+            }
+        }
+
+        (measurement, tags)
+    }
+}
+
+/// Templates tried in order, and the whole path is the measurement if no
+/// template matches.
+#[derive(Debug, Clone)]
+pub struct Templates {
+    templates: Vec<Template>,
+    default: Template,
+    /// Separator to join the parts of the measurement into the table name.
+    separator: String,
+}
+
+impl Templates {
+    pub fn new(configs: &[TemplateConfig], separator: String) -> Self {
+        Self {
+            templates: configs.iter().map(Template::new).collect(),
+            default: Template {
+                filter: Vec::new(),
+                parts: vec![TemplatePart::MeasurementRest],
+            },
+            separator,
+        }
+    }
+
+    fn find(&self, path_parts: &[&str]) -> &Template {
+        self.templates
+            .iter()
+            .find(|t| t.matches(path_parts))
+            .unwrap_or(&self.default)
+    }
+
+    /// Convert the points into the write requests of the tables.
+    pub fn convert_points(&self, points: Vec<Point>) -> Result<Vec<WriteTableRequest>> {
+        let mut records_by_table: HashMap<String, (BTreeSet<String>, Vec<Record>)> = HashMap::new();
+        for point in points {
+            let path_parts: Vec<_> = point.path.split('.').collect();
+            let (measurement, tags) = self.find(&path_parts).apply(&path_parts);
+            ensure!(
+                !measurement.is_empty(),
+                ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("no measurement in graphite path:{}", point.path),
+                }
+            );
+
+            let table = measurement.join(&self.separator);
+            let (tag_names, records) = records_by_table.entry(table).or_default();
+            let mut record = Vec::with_capacity(tags.len() + point.tags.len() + 2);
+            record.push((
+                TIMESTAMP_COLUMN.to_string(),
+                value::Value::TimestampValue(point.timestamp),
+            ));
+            record.push((
+                VALUE_FIELD.to_string(),
+                value::Value::Float64Value(point.value),
+            ));
+            let tags = tags
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .chain(point.tags);
+            for (name, v) in tags {
+                tag_names.insert(name.clone());
+                record.push((name, value::Value::StringValue(v)));
+            }
+            records.push(record);
+        }
+
+        records_by_table
+            .into_iter()
+            .map(|(table, (tag_names, records))| {
+                let params = WriteParams {
+                    table: Some(table.clone()),
+                    timestamp_field: TIMESTAMP_COLUMN.to_string(),
+                    tags: tag_names.into_iter().collect::<Vec<_>>().join(","),
+                    ..Default::default()
+                };
+                convert_records(table, &params, records)
+            })
+            .collect()
+    }
+
+    /// Resolve the target of the render api into a query.
+    pub fn resolve_target(&self, target: &str) -> Result<RenderQuery> {
+        let path_parts: Vec<_> = target.split('.').collect();
+        let template = self.find(&path_parts);
+        let (measurement, tags) = template.apply(&path_parts);
+        ensure!(
+            !measurement.is_empty() && !measurement.iter().any(|v| is_pattern(v)),
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("measurement of the target must be literal, target:{target}"),
+            }
+        );
+
+        // The parts of the name of the returned series, the parts mapped to tags
+        // are filled with the tag values of the series.
+        let name_parts = template
+            .parts
+            .iter()
+            .chain(std::iter::repeat(&TemplatePart::Skip))
+            .zip(&path_parts)
+            .map(|(part, path_part)| match part {
+                TemplatePart::Tag(name) => NamePart::Tag(name.clone()),
+                _ => NamePart::Literal(path_part.to_string()),
+            })
+            .collect();
+
+        Ok(RenderQuery {
+            table: measurement.join(&self.separator),
+            tags: tags
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            name_parts,
+        })
+    }
+}
+
+/// Whether the part of a target is a glob pattern.
+fn is_pattern(part: &str) -> bool {
+    part.contains(['*', '?', '[', '{'])
+}
+
+/// Match the glob pattern supporting `*` and `?`.
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let (pattern, s): (Vec<_>, Vec<_>) = (pattern.chars().collect(), s.chars().collect());
+    let (mut p, mut i) = (0, 0);
+    // Position of the last `*` in the pattern and the matched position of s.
+    let mut backtrack = None;
+    while i < s.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == s[i]) {
+            p += 1;
+            i += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, i));
+            p += 1;
+        } else if let Some((star_p, star_i)) = backtrack {
+            p = star_p + 1;
+            i = star_i + 1;
+            backtrack = Some((star_p, star_i + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NamePart {
+    Literal(String),
+    Tag(String),
+}
+
+/// Query resolved from a target of the render api.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderQuery {
+    pub table: String,
+    /// Tag name and its pattern in the target.
+    pub tags: Vec<(String, String)>,
+    name_parts: Vec<NamePart>,
+}
+
+impl RenderQuery {
+    /// Build the sql to query the points between `[from, until)` in
+    /// milliseconds.
+    pub fn to_sql(&self, from: i64, until: i64) -> String {
+        let mut columns = vec![quote_ident(TIMESTAMP_COLUMN), quote_ident(VALUE_FIELD)];
+        columns.extend(self.tags.iter().map(|(name, _)| quote_ident(name)));
+
+        let mut conditions = vec![
+            format!("{} >= {from}", quote_ident(TIMESTAMP_COLUMN)),
+            format!("{} < {until}", quote_ident(TIMESTAMP_COLUMN)),
+        ];
+        for (name, pattern) in &self.tags {
+            let column = quote_ident(name);
+            if pattern == "*" {
+                continue;
+            }
+            let condition = if let Some(alternatives) =
+                pattern.strip_prefix('{').and_then(|v| v.strip_suffix('}'))
+            {
+                let values: Vec<_> = alternatives.split(',').map(quote_string).collect();
+                format!("{column} IN ({})", values.join(", "))
+            } else if is_pattern(pattern) {
+                let like = pattern
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+                    .replace('*', "%")
+                    .replace('?', "_");
+                format!("{column} LIKE {}", quote_string(&like))
+            } else {
+                format!("{column} = {}", quote_string(pattern))
+            };
+            conditions.push(condition);
+        }
+
+        format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY {}",
+            columns.join(", "),
+            quote_ident(&self.table),
+            conditions.join(" AND "),
+            quote_ident(TIMESTAMP_COLUMN),
+        )
+    }
+
+    /// Group the rows queried by the sql into the series.
+    pub fn to_series(&self, batches: &[RecordBatch]) -> Vec<Series> {
+        let mut series: BTreeMap<String, Vec<(Option<f64>, i64)>> = BTreeMap::new();
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                let Some(timestamp) = batch.column(0).datum_view(row).as_timestamp() else {
+                    continue;
+                };
+                let value = batch.column(1).datum_view(row).as_f64();
+                let tag_values: HashMap<_, _> = self
+                    .tags
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, _))| {
+                        let v = batch.column(i + 2).datum_view(row).into_str();
+                        (name.as_str(), v.unwrap_or_default())
+                    })
+                    .collect();
+                let name = self
+                    .name_parts
+                    .iter()
+                    .map(|part| match part {
+                        NamePart::Literal(v) => v.as_str(),
+                        NamePart::Tag(name) => {
+                            tag_values.get(name.as_str()).copied().unwrap_or_default()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".");
+
+                series
+                    .entry(name)
+                    .or_default()
+                    .push((value, timestamp.as_i64() / 1000));
+            }
+        }
+
+        series
+            .into_iter()
+            .map(|(target, datapoints)| Series { target, datapoints })
+            .collect()
+    }
+}
+
+fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Parse the time of the render api into milliseconds, which is `now`, a
+/// relative time like `-1h`, or the unix timestamp in seconds.
+pub fn parse_render_time(time: &str, now: i64) -> Result<i64> {
+    if time == "now" {
+        return Ok(now);
+    }
+    if let Ok(secs) = time.parse::<i64>() {
+        return Ok(secs * 1000);
+    }
+
+    let invalid = || ErrNoCause {
+        code: StatusCode::BAD_REQUEST,
+        msg: format!("invalid time:{time}"),
+    };
+    let relative = time.strip_prefix('-').with_context(invalid)?;
+    let unit_start = relative
+        .find(|c: char| !c.is_ascii_digit())
+        .with_context(invalid)?;
+    let (num, unit) = relative.split_at(unit_start);
+    let num: i64 = num.parse().ok().with_context(invalid)?;
+    let unit_secs = match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86400,
+        "w" | "week" | "weeks" => 7 * 86400,
+        "mon" | "month" | "months" => 30 * 86400,
+        "y" | "year" | "years" => 365 * 86400,
+        _ => return invalid().fail(),
+    };
+
+    Ok(now - num * unit_secs * 1000)
+}
+
+/// Parameters of the render api.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RenderParams {
+    pub target: String,
+    pub from: String,
+    pub until: String,
+    /// Only `json` is supported.
+    pub format: String,
+}
+
+impl Default for RenderParams {
+    fn default() -> Self {
+        Self {
+            target: String::new(),
+            from: "-24h".to_string(),
+            until: "now".to_string(),
+            format: "json".to_string(),
+        }
+    }
+}
+
+impl RenderParams {
+    /// Merge the params of the form body of a POST request.
+    pub fn merge_form(mut self, mut form: HashMap<String, String>) -> Self {
+        if let Some(v) = form.remove("target") {
+            self.target = v;
+        }
+        if let Some(v) = form.remove("from") {
+            self.from = v;
+        }
+        if let Some(v) = form.remove("until") {
+            self.until = v;
+        }
+        if let Some(v) = form.remove("format") {
+            self.format = v;
+        }
+        self
+    }
+}
+
+/// A series of the render api, and the datapoints are `[value, timestamp]` with
+/// the timestamp in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Series {
+    pub target: String,
+    pub datapoints: Vec<(Option<f64>, i64)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_pickle::SerOptions;
+
+    use super::*;
+
+    fn templates() -> Templates {
+        Templates::new(
+            &[
+                TemplateConfig {
+                    filter: "servers.*".to_string(),
+                    template: "_.host.measurement*".to_string(),
+                },
+                TemplateConfig {
+                    filter: "stats".to_string(),
+                    template: "_.region.measurement".to_string(),
+                },
+            ],
+            "_".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_plaintext_line() {
+        let point = parse_plaintext_line("servers.web01.cpu.load 0.5 1700000000").unwrap();
+        assert_eq!(
+            point,
+            Point {
+                path: "servers.web01.cpu.load".to_string(),
+                tags: vec![],
+                value: 0.5,
+                timestamp: 1_700_000_000_000,
+            }
+        );
+
+        let point = parse_plaintext_line("cpu.load;dc=eu;rack=1 3 1700000000.5").unwrap();
+        assert_eq!(point.path, "cpu.load");
+        assert_eq!(
+            point.tags,
+            vec![
+                ("dc".to_string(), "eu".to_string()),
+                ("rack".to_string(), "1".to_string())
+            ]
+        );
+        assert_eq!(point.timestamp, 1_700_000_000_500);
+
+        for line in [
+            "a.b 1",
+            "a.b x 1",
+            "a.b 1 x",
+            "a.b 1 2 3",
+            ";a=b 1 2",
+            "a;b 1 2",
+        ] {
+            assert!(parse_plaintext_line(line).is_err(), "line:{line}");
+        }
+    }
+
+    #[test]
+    fn test_parse_pickle() {
+        let value = PickleValue::List(vec![
+            PickleValue::Tuple(vec![
+                PickleValue::String("a.b".to_string()),
+                PickleValue::Tuple(vec![PickleValue::I64(1700000000), PickleValue::F64(1.5)]),
+            ]),
+            PickleValue::Tuple(vec![
+                PickleValue::Bytes(b"c.d;k=v".to_vec()),
+                PickleValue::Tuple(vec![
+                    PickleValue::F64(1700000001.0),
+                    PickleValue::String("2".to_string()),
+                ]),
+            ]),
+        ]);
+        let payload = serde_pickle::value_to_vec(&value, SerOptions::new()).unwrap();
+        let points = parse_pickle(&payload).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].path, "a.b");
+        assert_eq!(points[0].value, 1.5);
+        assert_eq!(points[1].tags, vec![("k".to_string(), "v".to_string())]);
+        assert_eq!(points[1].timestamp, 1_700_000_001_000);
+
+        let payload = serde_pickle::value_to_vec(&PickleValue::I64(1), SerOptions::new()).unwrap();
+        assert!(parse_pickle(&payload).is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "abc"));
+        assert!(glob_match("a*c", "abbc"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("web*", "web01"));
+        assert!(!glob_match("web*", "db01"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn test_convert_points() {
+        let points = vec![
+            parse_plaintext_line("servers.web01.cpu.load 0.5 1").unwrap(),
+            parse_plaintext_line("servers.web02.cpu.load 0.7 1").unwrap(),
+            parse_plaintext_line("stats.eu.requests 10 1").unwrap(),
+            parse_plaintext_line("other.metric;env=prod 1 1").unwrap(),
+        ];
+        let mut requests = templates().convert_points(points).unwrap();
+        requests.sort_by(|a, b| a.table.cmp(&b.table));
+
+        let tables: Vec<_> = requests.iter().map(|r| r.table.as_str()).collect();
+        assert_eq!(tables, vec!["cpu_load", "other_metric", "requests"]);
+        assert_eq!(requests[0].tag_names, vec!["host".to_string()]);
+        assert_eq!(requests[0].entries.len(), 2);
+        assert_eq!(requests[1].tag_names, vec!["env".to_string()]);
+        assert_eq!(requests[2].tag_names, vec!["region".to_string()]);
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let query = templates().resolve_target("servers.web*.cpu.load").unwrap();
+        assert_eq!(query.table, "cpu_load");
+        assert_eq!(query.tags, vec![("host".to_string(), "web*".to_string())]);
+        assert_eq!(
+            query.to_sql(1000, 2000),
+            "SELECT `timestamp`, `value`, `host` FROM `cpu_load` WHERE `timestamp` >= 1000 \
+             AND `timestamp` < 2000 AND `host` LIKE 'web%' ORDER BY `timestamp`"
+        );
+
+        let query = templates()
+            .resolve_target("stats.{eu,us}.requests")
+            .unwrap();
+        assert!(query.to_sql(0, 1).contains("`region` IN ('eu', 'us')"));
+
+        let query = templates().resolve_target("servers.*.cpu.load").unwrap();
+        assert!(!query.to_sql(0, 1).contains("AND `host`"));
+
+        assert!(templates().resolve_target("servers.web01.cpu.*").is_err());
+    }
+
+    #[test]
+    fn test_parse_render_time() {
+        let now = 10_000_000;
+        assert_eq!(parse_render_time("now", now).unwrap(), now);
+        assert_eq!(parse_render_time("-1h", now).unwrap(), now - 3_600_000);
+        assert_eq!(parse_render_time("-5min", now).unwrap(), now - 300_000);
+        assert_eq!(
+            parse_render_time("1700000000", now).unwrap(),
+            1_700_000_000_000
+        );
+        assert!(parse_render_time("-1x", now).is_err());
+        assert!(parse_render_time("yesterday", now).is_err());
+    }
+}
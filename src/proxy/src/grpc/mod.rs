@@ -18,4 +18,5 @@
 mod prom_query;
 mod route;
 mod sql_query;
+mod table_changes;
 mod write;
@@ -97,6 +97,7 @@ impl Proxy {
         let frontend = Frontend::new(provider, self.instance.dyn_config.fronted.clone());
 
         let mut sql_ctx = SqlContext::new(request_id.clone(), deadline);
+        sql_ctx.user = ctx.auth_user.as_ref().map(|v| v.name().to_string());
         let expr = frontend
             .parse_promql(&mut sql_ctx, req.expr)
             .box_err()
@@ -126,9 +127,17 @@ impl Proxy {
                 code: StatusCode::FORBIDDEN,
                 msg: "Query is blocked",
             })?;
+        self.check_plan_role(ctx.auth_user.as_ref(), catalog, None, &plan)?;
 
         let output = self
-            .execute_plan(request_id.clone(), catalog, &schema, plan, deadline)
+            .execute_query_plan(
+                request_id.clone(),
+                catalog,
+                &schema,
+                plan,
+                deadline,
+                ctx.auth_user.as_ref(),
+            )
             .await
             .box_err()
             .with_context(|| ErrWithCause {
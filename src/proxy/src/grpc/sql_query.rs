@@ -35,7 +35,10 @@ use interpreters::interpreter::Output;
 use logger::{error, warn};
 use router::endpoint::Endpoint;
 use snafu::ResultExt;
-use tonic::{transport::Channel, IntoRequest};
+use table_engine::query_warning::{self, Warning};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Channel;
 
 use crate::{
     error::{self, ErrNoCause, ErrWithCause, Error, Result},
@@ -47,6 +50,16 @@ use crate::{
 
 impl Proxy {
     pub async fn handle_sql_query(&self, ctx: Context, req: SqlQueryRequest) -> SqlQueryResponse {
+        self.handle_sql_query_with_warnings(ctx, req).await.0
+    }
+
+    /// Handle the sql query, and return the warnings of the query along with
+    /// the response.
+    pub async fn handle_sql_query_with_warnings(
+        &self,
+        ctx: Context,
+        req: SqlQueryRequest,
+    ) -> (SqlQueryResponse, Vec<Warning>) {
         // Incoming query maybe larger than query_failed + query_succeeded for some
         // corner case, like lots of time-consuming queries come in at the same time and
         // cause server OOM.
@@ -62,10 +75,11 @@ impl Proxy {
                     error: format!("{} sql:{}", err.error_message(), req.sql),
                 };
 
-                SqlQueryResponse {
+                let resp = SqlQueryResponse {
                     header: Some(header),
                     ..Default::default()
-                }
+                };
+                (resp, Vec::new())
             }
             Ok(v) => {
                 GRPC_HANDLER_COUNTER_VEC.query_succeeded.inc();
@@ -78,7 +92,7 @@ impl Proxy {
         &self,
         ctx: &Context,
         req: &SqlQueryRequest,
-    ) -> Result<SqlQueryResponse> {
+    ) -> Result<(SqlQueryResponse, Vec<Warning>)> {
         if req.context.is_none() {
             return ErrNoCause {
                 code: StatusCode::BAD_REQUEST,
@@ -114,8 +128,19 @@ impl Proxy {
         };
 
         match result {
-            SqlResponse::Forwarded(resp) => Ok(resp),
-            SqlResponse::Local(output) => convert_output(&output, self.resp_compress_min_length),
+            // The warnings of the forwarded query are merged into the context.
+            SqlResponse::Forwarded(resp) => Ok((resp, ctx.warnings.take())),
+            SqlResponse::Local(output) => {
+                let mut resp = convert_output(&output, self.resp_compress_min_length)?;
+                // The results are still returned with the ok code if there are warnings, and
+                // the warnings are also carried by the error message for the clients not
+                // reading the metadata.
+                let warnings = ctx.warnings.take();
+                if let (false, Some(header)) = (warnings.is_empty(), &mut resp.header) {
+                    header.error = query_warning::format_warnings(&warnings);
+                }
+                Ok((resp, warnings))
+            }
         }
     }
 
@@ -136,10 +161,7 @@ impl Proxy {
                 }
             })
             .boxed(),
-            Ok(v) => {
-                GRPC_HANDLER_COUNTER_VEC.stream_query_succeeded.inc();
-                v
-            }
+            Ok(v) => v,
         }
     }
 
@@ -157,57 +179,95 @@ impl Proxy {
         }
 
         let req_context = req.context.as_ref().unwrap();
-        let schema = &req_context.database;
+        let schema = req_context.database.clone();
         let req = match self.clone().maybe_forward_stream_sql_query(ctx, req).await {
             Some(resp) => match resp {
-                ForwardResult::Forwarded(resp) => return resp,
+                ForwardResult::Forwarded(resp) => {
+                    GRPC_HANDLER_COUNTER_VEC.stream_query_succeeded.inc();
+                    return resp;
+                }
                 ForwardResult::Local => req,
             },
             None => req,
         };
 
-        let resp_compress_min_length = self.resp_compress_min_length;
-        let output = self
-            .as_ref()
-            .fetch_sql_query_output(
-                ctx,
-                schema,
-                &req.sql,
-                self.sub_table_access_perm.enable_others,
-                true,
-            )
-            .await?;
-
-        match output {
-            Output::AffectedRows(rows) => {
-                GRPC_HANDLER_COUNTER_VEC
-                    .query_affected_row
-                    .inc_by(rows as u64);
-
-                let resp = QueryResponseBuilder::with_ok_header().build_with_affected_rows(rows);
-
-                Ok(Box::pin(stream::once(async { resp })))
+        let Some(permit) = self.stream_limiter.try_open() else {
+            GRPC_HANDLER_COUNTER_VEC.stream_query_rejected.inc();
+            return ErrNoCause {
+                code: StatusCode::TOO_MANY_REQUESTS,
+                msg: "Too many open streams of sql query",
             }
-            Output::Records(batches) => {
-                let mut num_rows = 0;
-                let mut results = Vec::with_capacity(batches.len());
-                for batch in &batches {
-                    let resp = {
-                        let mut writer = QueryResponseWriter::new(resp_compress_min_length);
-                        writer.write(batch)?;
-                        writer.finish()
-                    }?;
-                    results.push(resp);
-                    num_rows += batch.num_rows();
-                }
+            .fail();
+        };
 
-                GRPC_HANDLER_COUNTER_VEC
-                    .query_succeeded_row
-                    .inc_by(num_rows as u64);
+        // The executor sends the record batches through the bounded channel as soon
+        // as they are produced, and it is paused when the channel is full, that is
+        // the client doesn't consume the responses in time.
+        let (tx, rx) = mpsc::channel(self.stream_limiter.channel_capacity());
+        let query_ctx = ctx.clone().with_result_sender(Some(tx));
+        let sql = req.sql.clone();
+        let proxy = self.clone();
+        let handle = self.engine_runtimes.read_runtime.spawn(async move {
+            proxy
+                .fetch_sql_query_output(
+                    &query_ctx,
+                    &schema,
+                    &sql,
+                    proxy.sub_table_access_perm.enable_others,
+                    true,
+                )
+                .await
+        });
 
-                Ok(Box::pin(stream::iter(results)))
+        let resp_compress_min_length = self.resp_compress_min_length;
+        let batch_size = self.stream_limiter.batch_size();
+        let batches = ReceiverStream::new(rx).flat_map(move |batch| {
+            stream::iter(encode_stream_batch(&batch, batch_size, resp_compress_min_length))
+        });
+        // The output left after the batches are streamed, such as the affected rows
+        // or the records not produced by the executor, or the error of the query.
+        let output = stream::once(async move {
+            let output = handle
+                .await
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    msg: "Failed to join stream sql query task",
+                })
+                .and_then(|output| output);
+            // The stream is kept open until the query finishes.
+            drop(permit);
+
+            match output {
+                Ok(Output::AffectedRows(rows)) => {
+                    GRPC_HANDLER_COUNTER_VEC.stream_query_succeeded.inc();
+                    GRPC_HANDLER_COUNTER_VEC
+                        .query_affected_row
+                        .inc_by(rows as u64);
+                    vec![QueryResponseBuilder::with_ok_header().build_with_affected_rows(rows)]
+                }
+                Ok(Output::Records(batches)) => {
+                    GRPC_HANDLER_COUNTER_VEC.stream_query_succeeded.inc();
+                    batches
+                        .iter()
+                        .flat_map(|batch| {
+                            encode_stream_batch(batch, batch_size, resp_compress_min_length)
+                        })
+                        .collect()
+                }
+                Err(e) => {
+                    error!("Failed to handle stream sql query, err:{e}");
+                    GRPC_HANDLER_COUNTER_VEC.stream_query_failed.inc();
+                    vec![SqlQueryResponse {
+                        header: Some(error::build_err_header(e)),
+                        ..Default::default()
+                    }]
+                }
             }
-        }
+        })
+        .flat_map(stream::iter);
+
+        Ok(batches.chain(output).boxed())
     }
 
     async fn maybe_forward_stream_sql_query(
@@ -225,7 +285,7 @@ impl Proxy {
         let forward_req = ForwardRequest {
             schema: req_ctx.database.clone(),
             table: req.tables[0].clone(),
-            req: req.clone().into_request(),
+            req: ctx.forwarded_request(req.clone()),
             forwarded_from: ctx.forwarded_from.clone(),
         };
         let do_query = |mut client: StorageServiceClient<Channel>,
@@ -301,6 +361,36 @@ pub fn convert_output(
     }
 }
 
+/// Encode the batch into the responses of no more than `batch_size` rows.
+fn encode_stream_batch(
+    batch: &RecordBatch,
+    batch_size: usize,
+    resp_compress_min_length: usize,
+) -> Vec<SqlQueryResponse> {
+    let num_rows = batch.num_rows();
+    let mut results = Vec::with_capacity(num_rows.div_ceil(batch_size));
+    let mut offset = 0;
+    while offset < num_rows {
+        let length = batch_size.min(num_rows - offset);
+        let mut writer = QueryResponseWriter::new(resp_compress_min_length);
+        let resp = writer
+            .write_slice(batch, offset, length)
+            .and_then(|_| writer.finish())
+            .unwrap_or_else(|e| SqlQueryResponse {
+                header: Some(error::build_err_header(e)),
+                ..Default::default()
+            });
+        results.push(resp);
+        offset += length;
+    }
+
+    GRPC_HANDLER_COUNTER_VEC
+        .query_succeeded_row
+        .inc_by(num_rows as u64);
+
+    results
+}
+
 /// Builder for building [`SqlQueryResponse`].
 #[derive(Debug, Default)]
 pub struct QueryResponseBuilder {
@@ -375,6 +465,21 @@ impl QueryResponseWriter {
             })
     }
 
+    /// Write the `length` rows of the batch starting from the `offset`.
+    pub fn write_slice(&mut self, batch: &RecordBatch, offset: usize, length: usize) -> Result<()> {
+        if length == 0 {
+            return Ok(());
+        }
+
+        self.encoder
+            .write(&batch.as_arrow_record_batch().slice(offset, length))
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Failed to encode record batch",
+            })
+    }
+
     pub fn write_batches(&mut self, record_batch: &[RecordBatch]) -> Result<()> {
         for batch in record_batch {
             self.write(batch)?;
@@ -0,0 +1,455 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Table changes handler
+
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use common_types::{
+    projected_schema::ProjectedSchema, record_batch::RecordBatch, request_id::RequestId,
+};
+use datafusion::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch as ArrowRecordBatch},
+    datasource::{provider_as_source, MemTable},
+    logical_expr::{LogicalPlan, LogicalPlanBuilder},
+    prelude::SessionContext,
+    sql::TableReference,
+};
+use futures::{
+    stream,
+    stream::{BoxStream, Peekable},
+    StreamExt,
+};
+use generic_error::BoxError;
+use http::StatusCode;
+use interpreters::interpreter::Output;
+use logger::{error, info, warn};
+use query_frontend::{
+    frontend::{Context as SqlContext, Frontend},
+    plan::UserRole,
+    provider::CatalogMetaProvider,
+};
+use snafu::{ensure, OptionExt, ResultExt};
+use table_engine::{
+    predicate::PredicateBuilder,
+    stream::SendableRecordBatchStream,
+    table::{ReadChangesRequest, ReadOptions, ReadRequest, TableChanges, TableRef},
+};
+use trace_metric::MetricsCollector;
+
+use crate::{
+    error::{self, ErrNoCause, ErrWithCause, Result},
+    grpc::sql_query::convert_output,
+    table_changes::{self, SubscribeTableChangesRequest, TableChangesResponse},
+    Context, Proxy,
+};
+
+/// Max rows of the changes in a response if not given in the request.
+const DEFAULT_MAX_ROWS: usize = 4096;
+/// Interval to poll the table when there are no new changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Max attempts to read the snapshot, the read fails if the rows after the
+/// snapshot sequence are flushed before it.
+const MAX_SNAPSHOT_ATTEMPTS: usize = 3;
+
+/// Snapshot being sent in the responses of no more than `max_rows` rows.
+struct Snapshot {
+    /// The table is read as of this sequence.
+    sequence: u64,
+    stream: Peekable<SendableRecordBatchStream>,
+}
+
+/// State of a subscription between the responses.
+struct SubscriptionState {
+    proxy: Arc<Proxy>,
+    ctx: Context,
+    schema: String,
+    table: TableRef,
+    consumer: String,
+    max_rows: usize,
+    fingerprint: u64,
+    /// Sequence of the last sent rows, `None` to send a snapshot first.
+    after: Option<u64>,
+    snapshot: Option<Snapshot>,
+    /// Whether the next snapshot replaces the expired changes.
+    reset: bool,
+    finished: bool,
+}
+
+impl Proxy {
+    pub async fn handle_subscribe_table_changes(
+        self: Arc<Self>,
+        ctx: Context,
+        req: SubscribeTableChangesRequest,
+    ) -> BoxStream<'static, TableChangesResponse> {
+        match self.handle_subscribe_table_changes_internal(ctx, req) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to handle table changes subscription, err:{e}");
+                stream::once(async {
+                    TableChangesResponse {
+                        header: Some(error::build_err_header(e)),
+                        ..Default::default()
+                    }
+                })
+                .boxed()
+            }
+        }
+    }
+
+    fn handle_subscribe_table_changes_internal(
+        self: Arc<Self>,
+        ctx: Context,
+        req: SubscribeTableChangesRequest,
+    ) -> Result<BoxStream<'static, TableChangesResponse>> {
+        let schema = req
+            .context
+            .as_ref()
+            .map(|v| v.database.clone())
+            .context(ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: "Database is not set",
+            })?;
+        ensure!(
+            !req.consumer.is_empty(),
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: "Consumer is not set",
+            }
+        );
+
+        // The changes are only in the wal of the node serving the table.
+        let catalog = self.instance.catalog_manager.default_catalog_name();
+        self.check_role(ctx.auth_user.as_ref(), catalog, UserRole::ReadOnly)?;
+        let table = self
+            .try_get_table(catalog, &schema, &req.table)?
+            .with_context(|| ErrNoCause {
+                code: StatusCode::NOT_FOUND,
+                msg: format!("Table not found, schema:{schema}, table:{}", req.table),
+            })?;
+        let fingerprint = table_changes::fingerprint(&schema, &req.table, table.id().as_u64());
+        let after = if req.cursor.is_empty() {
+            None
+        } else {
+            Some(table_changes::decode_cursor(&req.cursor, fingerprint)?)
+        };
+        let max_rows = if req.max_rows > 0 {
+            req.max_rows as usize
+        } else {
+            DEFAULT_MAX_ROWS
+        };
+
+        info!(
+            "Table changes subscription begin, schema:{schema}, table:{}, consumer:{}, cursor:{}",
+            req.table, req.consumer, req.cursor
+        );
+        let state = SubscriptionState {
+            proxy: self,
+            ctx,
+            schema,
+            table,
+            consumer: req.consumer,
+            max_rows,
+            fingerprint,
+            after,
+            snapshot: None,
+            reset: false,
+            finished: false,
+        };
+
+        Ok(stream::unfold(state, |mut state| async move {
+            if state.finished {
+                return None;
+            }
+            let resp = match state.next_response().await {
+                Ok(v) => v,
+                Err(e) => {
+                    // The consumer can resume from the cursor of the last
+                    // response.
+                    error!(
+                        "Failed to read table changes, table:{}, consumer:{}, err:{e}",
+                        state.table.name(),
+                        state.consumer
+                    );
+                    state.finished = true;
+                    TableChangesResponse {
+                        header: Some(error::build_err_header(e)),
+                        ..Default::default()
+                    }
+                }
+            };
+            Some((resp, state))
+        })
+        .boxed())
+    }
+}
+
+impl SubscriptionState {
+    /// Wait for the next changes, or send a snapshot if the subscription
+    /// starts or its changes are expired.
+    async fn next_response(&mut self) -> Result<TableChangesResponse> {
+        loop {
+            let resync_requests = &self.proxy.instance.resync_requests;
+            if resync_requests.take(self.table.id(), &self.consumer) {
+                warn!(
+                    "Table changes are re-synced, table:{}, consumer:{}",
+                    self.table.name(),
+                    self.consumer
+                );
+                self.after = None;
+                self.snapshot = None;
+                self.reset = true;
+            }
+            if self.snapshot.is_some() {
+                return self.next_snapshot_response().await;
+            }
+            let Some(after) = self.after else {
+                self.snapshot = Some(self.read_snapshot().await?);
+                continue;
+            };
+
+            let changes = self.read_changes(Some(after)).await?;
+            if changes.expired {
+                warn!(
+                    "Table changes are expired, table:{}, consumer:{}, after:{after}",
+                    self.table.name(),
+                    self.consumer
+                );
+                self.after = None;
+                self.reset = true;
+                continue;
+            }
+            let Some(last) = changes.changes.last() else {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            };
+
+            let end_sequence = last.sequence;
+            let batches = changes.changes.into_iter().map(|v| v.rows).collect();
+            let batches = self.apply_policies(batches).await?;
+            let result = convert_output(
+                &Output::Records(batches),
+                self.proxy.resp_compress_min_length,
+            )?;
+            self.after = Some(end_sequence);
+            return Ok(TableChangesResponse {
+                header: Some(error::build_ok_header()),
+                snapshot: false,
+                reset: false,
+                start_sequence: after,
+                end_sequence,
+                result: Some(result),
+                cursor: table_changes::encode_cursor(end_sequence, self.fingerprint),
+                more: false,
+            });
+        }
+    }
+
+    /// Read the whole table as of the sequence from which the changes are
+    /// retained, so the rows written after it are only sent as the changes.
+    async fn read_snapshot(&self) -> Result<Snapshot> {
+        let mut attempt = 1;
+        loop {
+            let sequence = self.read_changes(None).await?.after;
+            let req = ReadRequest {
+                request_id: RequestId::next_id(),
+                opts: ReadOptions {
+                    snapshot_sequence: Some(sequence),
+                    ..Default::default()
+                },
+                projected_schema: ProjectedSchema::no_projection(self.table.schema()),
+                predicate: PredicateBuilder::default().build(),
+                metrics_collector: MetricsCollector::default(),
+                priority: Default::default(),
+            };
+            match self.table.read(req).await {
+                Ok(stream) => {
+                    return Ok(Snapshot {
+                        sequence,
+                        stream: stream.peekable(),
+                    });
+                }
+                // The rows after the sequence may be flushed before the read,
+                // and it's retried with a newer sequence.
+                Err(e) if attempt < MAX_SNAPSHOT_ATTEMPTS => {
+                    warn!(
+                        "Failed to read table snapshot, table:{}, sequence:{sequence}, attempt:{attempt}, err:{e}",
+                        self.table.name()
+                    );
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(e).box_err().with_context(|| ErrWithCause {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        msg: format!("Failed to read snapshot, table:{}", self.table.name()),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Send the next rows of the snapshot, and the subscription continues
+    /// with the changes after the last rows of it are sent.
+    async fn next_snapshot_response(&mut self) -> Result<TableChangesResponse> {
+        let snapshot = self.snapshot.as_mut().unwrap();
+        let sequence = snapshot.sequence;
+        let mut batches = Vec::new();
+        let mut num_rows = 0;
+        while num_rows < self.max_rows {
+            let Some(batch) = snapshot.stream.next().await else {
+                break;
+            };
+            let batch = batch.box_err().with_context(|| ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("Failed to read snapshot, table:{}", self.table.name()),
+            })?;
+            num_rows += batch.num_rows();
+            batches.push(batch);
+        }
+        let more = Pin::new(&mut snapshot.stream).peek().await.is_some();
+
+        let batches = self.apply_policies(batches).await?;
+        let result = convert_output(
+            &Output::Records(batches),
+            self.proxy.resp_compress_min_length,
+        )?;
+        // The consumer can only resume after the whole snapshot is applied.
+        let cursor = if more {
+            String::new()
+        } else {
+            self.snapshot = None;
+            self.after = Some(sequence);
+            table_changes::encode_cursor(sequence, self.fingerprint)
+        };
+        Ok(TableChangesResponse {
+            header: Some(error::build_ok_header()),
+            snapshot: true,
+            reset: std::mem::take(&mut self.reset),
+            start_sequence: 0,
+            end_sequence: sequence,
+            result: Some(result),
+            cursor,
+            more,
+        })
+    }
+
+    /// Mask the columns and filter the rows by the policies bound to the user
+    /// as the sql queries do, as the rows are not read by the sql queries.
+    async fn apply_policies(&self, batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>> {
+        let instance = &self.proxy.instance;
+        let provider = CatalogMetaProvider {
+            manager: instance.catalog_manager.clone(),
+            default_catalog: instance.catalog_manager.default_catalog_name(),
+            default_schema: &self.schema,
+            function_registry: &*instance.function_registry,
+        };
+        let frontend = Frontend::new(provider, instance.dyn_config.fronted.clone());
+        let mut sql_ctx = SqlContext::new(self.ctx.request_id.clone(), None);
+        sql_ctx.user = self.ctx.auth_user.as_ref().map(|v| v.name().to_string());
+
+        // The changes of different schemas are planned separately.
+        let mut applied = Vec::with_capacity(batches.len());
+        let mut batches = batches.into_iter().peekable();
+        while let Some(first) = batches.next() {
+            let schema = first.as_arrow_record_batch().schema();
+            let mut group = vec![first];
+            while let Some(batch) =
+                batches.next_if(|v| v.as_arrow_record_batch().schema() == schema)
+            {
+                group.push(batch);
+            }
+
+            let arrow_batches = group
+                .iter()
+                .map(|v| v.as_arrow_record_batch().clone())
+                .collect();
+            let plan = self.scan_plan(schema, arrow_batches).and_then(|plan| {
+                frontend
+                    .apply_policies_to_df_plan(&sql_ctx, plan)
+                    .box_err()
+                    .context(ErrWithCause {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        msg: "Failed to apply policies to table changes",
+                    })
+            })?;
+            // No policies are bound to the user.
+            if matches!(plan, LogicalPlan::TableScan(_)) {
+                applied.extend(group);
+                continue;
+            }
+
+            let arrow_batches = execute_plan(plan)
+                .await
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    msg: "Failed to apply policies to table changes",
+                })?;
+            for batch in arrow_batches {
+                let batch = RecordBatch::try_from(batch)
+                    .box_err()
+                    .context(ErrWithCause {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        msg: "Failed to convert table changes",
+                    })?;
+                applied.push(batch);
+            }
+        }
+
+        Ok(applied)
+    }
+
+    /// Plan to scan the batches as the subscribed table, so the policies of
+    /// the table are applied to it.
+    fn scan_plan(&self, schema: SchemaRef, batches: Vec<ArrowRecordBatch>) -> Result<LogicalPlan> {
+        let table_name = TableReference::bare(self.table.name().to_string());
+        MemTable::try_new(schema, vec![batches])
+            .and_then(|table| {
+                let source = provider_as_source(Arc::new(table));
+                LogicalPlanBuilder::scan(table_name, source, None)?.build()
+            })
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Failed to plan table changes",
+            })
+    }
+
+    async fn read_changes(&self, after: Option<u64>) -> Result<TableChanges> {
+        let req = ReadChangesRequest {
+            consumer: self.consumer.clone(),
+            after,
+            max_rows: self.max_rows,
+        };
+        self.table
+            .read_changes(req)
+            .await
+            .box_err()
+            .with_context(|| ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("Failed to read changes, table:{}", self.table.name()),
+            })
+    }
+}
+
+async fn execute_plan(plan: LogicalPlan) -> datafusion::error::Result<Vec<ArrowRecordBatch>> {
+    SessionContext::new()
+        .execute_logical_plan(plan)
+        .await?
+        .collect()
+        .await
+}
@@ -17,10 +17,24 @@
 
 use horaedbproto::storage::{WriteRequest, WriteResponse};
 
-use crate::{error, error::build_ok_header, metrics::GRPC_HANDLER_COUNTER_VEC, Context, Proxy};
+use crate::{
+    error, error::build_ok_header, metrics::GRPC_HANDLER_COUNTER_VEC, write_status::WriteStatus,
+    Context, Proxy,
+};
 
 impl Proxy {
     pub async fn handle_write(&self, ctx: Context, req: WriteRequest) -> WriteResponse {
+        self.handle_write_with_status(ctx, req).await.0
+    }
+
+    /// Handle the write and return the status of the written tables along
+    /// with the response, and the status only carries the delay before
+    /// retrying if the write fails.
+    pub async fn handle_write_with_status(
+        &self,
+        ctx: Context,
+        req: WriteRequest,
+    ) -> (WriteResponse, WriteStatus) {
         self.hotspot_recorder.inc_write_reqs(&req).await;
 
         let mut num_rows = 0;
@@ -37,10 +51,17 @@ impl Proxy {
                 GRPC_HANDLER_COUNTER_VEC
                     .write_failed_row
                     .inc_by(num_rows as u64);
-                WriteResponse {
+                // The clients wait for the quota or the compaction by the delay
+                // before retrying the rejected write.
+                let status = WriteStatus {
+                    retry_after_ms: e.retry_after().map(|v| v.as_millis() as u64),
+                    ..Default::default()
+                };
+                let resp = WriteResponse {
                     header: Some(error::build_err_header(e)),
                     ..Default::default()
-                }
+                };
+                (resp, status)
             }
             Ok(v) => {
                 GRPC_HANDLER_COUNTER_VEC.write_succeeded.inc();
@@ -50,11 +71,12 @@ impl Proxy {
                 GRPC_HANDLER_COUNTER_VEC
                     .write_succeeded_row
                     .inc_by(v.success as u64);
-                WriteResponse {
+                let resp = WriteResponse {
                     header: Some(build_ok_header()),
                     success: v.success,
                     failed: v.failed,
-                }
+                };
+                (resp, v.status)
             }
         }
     }
@@ -15,18 +15,46 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
-use crate::{handlers::prelude::*, limiter::BlockRule};
+use generic_error::BoxError;
+use interpreters::show_create::ShowCreateInterpreter;
+use logger::info;
+use router::{RouterRef, RuleList};
+use schemars::JsonSchema;
+use snafu::{ensure, OptionExt, ResultExt};
 
-#[derive(Debug, Deserialize)]
+use crate::{
+    admission::{Config as AdmissionConfig, DebtThrottle, Quota, TenantQuota},
+    handlers::{
+        error::{
+            Import, InvalidImportPath, JsonMappingNotFound, QueryNotFound, QueryOnlyMode,
+            RegisterJsonMapping, ReloadFunctions, RemoveJsonMapping, ReplicaNotFound,
+            ResyncReplica, SchemaDiff, SchemaNotFound, TableNotFound, TargetNotAllowed,
+            UdfDirNotConfigured, UpdateRouteRules,
+        },
+        prelude::*,
+    },
+    import::{self, FileFormat, ImportProgress},
+    json_write::MappingSpec,
+    limiter::BlockRule,
+    maintenance::{MaintainedTable, MaintenanceMode},
+    schema_diff::{self, Issue, Severity, TargetClient},
+    Proxy,
+};
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub enum Operation {
     Add,
     Set,
     Remove,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct BlockRequest {
     operation: Operation,
     write_block_list: Vec<String>,
@@ -34,7 +62,7 @@ pub struct BlockRequest {
     block_rules: Vec<BlockRule>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct BlockResponse {
     write_block_list: BTreeSet<String>,
     read_block_list: BTreeSet<String>,
@@ -77,3 +105,613 @@ pub async fn handle_block(
         block_rules: limiter.get_block_rules().into_iter().collect(),
     })
 }
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MaintenanceRequest {
+    operation: Operation,
+    /// Schema of the tables, the schema of the request is used if not set.
+    #[serde(default)]
+    schema: Option<String>,
+    tables: Vec<String>,
+    #[serde(default)]
+    mode: MaintenanceMode,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MaintenanceResponse {
+    tables: Vec<MaintainedTable>,
+}
+
+pub async fn handle_maintenance(
+    ctx: RequestContext,
+    instance: InstanceRef,
+    request: MaintenanceRequest,
+) -> Result<MaintenanceResponse> {
+    let maintenance = &instance.maintenance;
+    let schema = request.schema.unwrap_or(ctx.schema);
+    match request.operation {
+        Operation::Add => maintenance.enter(&schema, request.tables, request.mode),
+        Operation::Set => {
+            maintenance.exit_all();
+            maintenance.enter(&schema, request.tables, request.mode);
+        }
+        Operation::Remove => maintenance.exit(&schema, &request.tables),
+    }
+
+    Ok(MaintenanceResponse {
+        tables: maintenance.list(),
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ReadOnlyMode {
+    read_only: bool,
+}
+
+/// Switch the read-only mode of the server, the current mode is returned if
+/// the `request` is `None`.
+pub async fn handle_read_only(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+    request: Option<ReadOnlyMode>,
+) -> Result<ReadOnlyMode> {
+    if let Some(request) = request {
+        ensure!(request.read_only || !instance.query_only, QueryOnlyMode);
+        let prev = instance
+            .read_only
+            .swap(request.read_only, Ordering::Relaxed);
+        info!(
+            "Read-only mode of the server is changed, from:{prev}, to:{}",
+            request.read_only
+        );
+    }
+
+    Ok(ReadOnlyMode {
+        read_only: instance.read_only.load(Ordering::Relaxed),
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct QuotaRequest {
+    operation: Operation,
+    #[serde(default)]
+    tenants: Vec<TenantQuota>,
+    /// Replace the quota of the tenants not listed if set.
+    #[serde(default)]
+    default_quota: Option<Quota>,
+    /// Replace the limit of the pending tasks to shed the load if set.
+    #[serde(default)]
+    max_pending_tasks: Option<usize>,
+    /// Replace the throttle of the writes by the compaction debt if set.
+    #[serde(default)]
+    debt_throttle: Option<DebtThrottle>,
+}
+
+/// Update the quotas of the tenants, and the current quotas are returned if
+/// the `request` is `None`.
+pub async fn handle_quotas(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+    request: Option<QuotaRequest>,
+) -> Result<AdmissionConfig> {
+    let admission = &instance.admission;
+    if let Some(request) = request {
+        match request.operation {
+            Operation::Add => admission.upsert_tenants(request.tenants),
+            Operation::Set => admission.set_tenants(request.tenants),
+            Operation::Remove => admission.remove_tenants(&request.tenants),
+        }
+        if let Some(quota) = request.default_quota {
+            admission.set_default_quota(quota);
+        }
+        if let Some(max_pending_tasks) = request.max_pending_tasks {
+            admission.set_max_pending_tasks(max_pending_tasks);
+        }
+        if let Some(debt_throttle) = request.debt_throttle {
+            admission.set_debt_throttle(debt_throttle);
+        }
+        info!("Quotas are updated, quotas:{:?}", admission.config());
+    }
+
+    Ok(admission.config())
+}
+
+/// Replace the route rules of the `router`, and the current rules are
+/// returned if the `request` is `None`.
+pub async fn handle_route_rules(
+    _ctx: RequestContext,
+    router: RouterRef,
+    request: Option<RuleList>,
+) -> Result<Option<RuleList>> {
+    if let Some(rules) = request {
+        router.set_rules(rules).context(UpdateRouteRules)?;
+    }
+
+    Ok(router.rules())
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct QueryInfo {
+    id: u64,
+    request_id: String,
+    schema: String,
+    elapsed_ms: u64,
+    state: &'static str,
+    scanned_rows: u64,
+    scanned_bytes: u64,
+    query: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ListQueriesResponse {
+    queries: Vec<QueryInfo>,
+}
+
+pub async fn handle_list_queries(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+) -> Result<ListQueriesResponse> {
+    let queries = instance
+        .query_tracker
+        .list()
+        .into_iter()
+        .map(|query| QueryInfo {
+            id: query.id,
+            elapsed_ms: query.elapsed().as_millis() as u64,
+            state: query.state(),
+            scanned_rows: query.scan_usage.rows(),
+            scanned_bytes: query.scan_usage.bytes(),
+            request_id: query.request_id,
+            schema: query.schema,
+            query: query.query,
+        })
+        .collect();
+
+    Ok(ListQueriesResponse { queries })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct QueryRecord {
+    id: u64,
+    request_id: String,
+    schema: String,
+    elapsed_ms: u64,
+    scanned_rows: u64,
+    scanned_bytes: u64,
+    query: String,
+    settings: BTreeMap<String, String>,
+    table_versions: BTreeMap<String, u32>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct QueryHistoryResponse {
+    queries: Vec<QueryRecord>,
+}
+
+pub async fn handle_query_history(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+) -> Result<QueryHistoryResponse> {
+    let queries = instance
+        .query_tracker
+        .history()
+        .into_iter()
+        .map(|record| {
+            let query = record.info;
+            let snapshot = query.snapshot.as_deref().cloned().unwrap_or_default();
+            QueryRecord {
+                id: query.id,
+                elapsed_ms: record.elapsed.as_millis() as u64,
+                scanned_rows: query.scan_usage.rows(),
+                scanned_bytes: query.scan_usage.bytes(),
+                request_id: query.request_id,
+                schema: query.schema,
+                query: query.query,
+                settings: snapshot.settings,
+                table_versions: snapshot.table_versions,
+            }
+        })
+        .collect();
+
+    Ok(QueryHistoryResponse { queries })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct KillQueryResponse {
+    id: u64,
+}
+
+pub async fn handle_kill_query(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+    id: u64,
+) -> Result<KillQueryResponse> {
+    ensure!(instance.query_tracker.kill(id), QueryNotFound { id });
+
+    Ok(KillQueryResponse { id })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FunctionInfo {
+    name: String,
+    module: String,
+    signature: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ListFunctionsResponse {
+    functions: Vec<FunctionInfo>,
+}
+
+/// List the udfs loaded from the wasm modules of this node.
+pub async fn handle_list_functions(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+) -> Result<ListFunctionsResponse> {
+    let manager = instance
+        .wasm_udf_manager
+        .as_ref()
+        .context(UdfDirNotConfigured)?;
+    let functions = manager
+        .list()
+        .into_iter()
+        .map(|info| FunctionInfo {
+            name: info.name,
+            module: info.module,
+            signature: info.signature,
+        })
+        .collect();
+
+    Ok(ListFunctionsResponse { functions })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReloadFunctionsResponse {
+    /// Functions newly registered by the reload.
+    loaded: Vec<String>,
+}
+
+/// Load the functions of the modules added to the udf directory since the
+/// last load, the registered functions are left untouched.
+pub async fn handle_reload_functions(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+) -> Result<ReloadFunctionsResponse> {
+    let manager = instance
+        .wasm_udf_manager
+        .as_ref()
+        .context(UdfDirNotConfigured)?;
+    let loaded = manager.load_all().box_err().context(ReloadFunctions)?;
+    info!("Functions are reloaded, loaded:{loaded:?}");
+
+    Ok(ReloadFunctionsResponse { loaded })
+}
+
+/// Max number of the rows of an imported sst if not specified.
+const DEFAULT_IMPORT_ROWS_PER_SST: usize = 1_000_000;
+
+fn default_import_rows_per_sst() -> usize {
+    DEFAULT_IMPORT_ROWS_PER_SST
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImportRequest {
+    table: String,
+    /// Absolute path of the file on the local file system of the server, the
+    /// file is not uploaded by the client.
+    path: String,
+    format: FileFormat,
+    /// Max number of the rows of an imported sst, the rows of an sst are
+    /// sorted in memory.
+    #[serde(default = "default_import_rows_per_sst")]
+    rows_per_sst: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImportResponse {
+    /// Id of the import to query the progress.
+    id: u64,
+}
+
+pub async fn handle_import(
+    ctx: RequestContext,
+    proxy: Arc<Proxy>,
+    request: ImportRequest,
+) -> Result<ImportResponse> {
+    if let Err(msg) = import::check_path(&request.path) {
+        return InvalidImportPath {
+            path: &request.path,
+            msg,
+        }
+        .fail();
+    }
+
+    let table = proxy
+        .instance
+        .catalog_manager
+        .catalog_by_name(&ctx.catalog)
+        .box_err()
+        .context(Import {
+            msg: "find catalog",
+        })?
+        .context(SchemaNotFound {
+            catalog: &ctx.catalog,
+            schema: &ctx.schema,
+        })?
+        .schema_by_name(&ctx.schema)
+        .box_err()
+        .context(Import { msg: "find schema" })?
+        .context(SchemaNotFound {
+            catalog: &ctx.catalog,
+            schema: &ctx.schema,
+        })?
+        .table_by_name(&request.table)
+        .box_err()
+        .context(Import { msg: "find table" })?
+        .context(TableNotFound {
+            table: &request.table,
+        })?;
+
+    let id = proxy.import_tracker.spawn_import(
+        proxy.engine_runtimes.default_runtime.clone(),
+        &proxy.engine_runtimes.task_registry,
+        table,
+        request.path.clone(),
+        request.format,
+        request.rows_per_sst.max(1),
+    );
+    info!(
+        "Import started, id:{id}, table:{}, path:{}, format:{:?}",
+        request.table, request.path, request.format
+    );
+
+    Ok(ImportResponse { id })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ListImportsResponse {
+    imports: Vec<ImportProgress>,
+}
+
+pub async fn handle_list_imports(
+    _ctx: RequestContext,
+    proxy: Arc<Proxy>,
+) -> Result<ListImportsResponse> {
+    Ok(ListImportsResponse {
+        imports: proxy.import_tracker.list(),
+    })
+}
+
+/// Timeout of the requests to the target cluster if not specified.
+const DEFAULT_SCHEMA_DIFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SchemaDiffRequest {
+    /// Sql http endpoint of the target cluster, eg: http://127.0.0.1:5440,
+    /// which must be one of the configured targets.
+    target: String,
+    /// Tables to check, empty means all the tables of the schema.
+    #[serde(default)]
+    tables: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TableDiff {
+    table: String,
+    issues: Vec<Issue>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SchemaDiffResponse {
+    /// Whether all the tables can be replicated to the target.
+    compatible: bool,
+    /// Tables with issues only.
+    tables: Vec<TableDiff>,
+}
+
+pub async fn handle_schema_diff(
+    ctx: RequestContext,
+    instance: InstanceRef,
+    request: SchemaDiffRequest,
+) -> Result<SchemaDiffResponse> {
+    let catalog = instance
+        .catalog_manager
+        .catalog_by_name(&ctx.catalog)
+        .box_err()
+        .context(SchemaDiff {
+            msg: "find catalog",
+        })?
+        .context(SchemaNotFound {
+            catalog: &ctx.catalog,
+            schema: &ctx.schema,
+        })?;
+    let schema = catalog
+        .schema_by_name(&ctx.schema)
+        .box_err()
+        .context(SchemaDiff { msg: "find schema" })?
+        .context(SchemaNotFound {
+            catalog: &ctx.catalog,
+            schema: &ctx.schema,
+        })?;
+    let tables = if request.tables.is_empty() {
+        schema
+            .all_tables()
+            .box_err()
+            .context(SchemaDiff { msg: "list tables" })?
+    } else {
+        let mut tables = Vec::with_capacity(request.tables.len());
+        for name in &request.tables {
+            let table = schema
+                .table_by_name(name)
+                .box_err()
+                .context(SchemaDiff { msg: "find table" })?
+                .context(TableNotFound { table: name })?;
+            tables.push(table);
+        }
+        tables
+    };
+
+    ensure!(
+        schema_diff::is_allowed_target(&instance.schema_diff_targets, &request.target),
+        TargetNotAllowed {
+            target: &request.target,
+        }
+    );
+    let client = TargetClient::new(
+        &request.target,
+        ctx.schema.clone(),
+        ctx.timeout.unwrap_or(DEFAULT_SCHEMA_DIFF_TIMEOUT),
+    )
+    .context(SchemaDiff {
+        msg: "build client of target",
+    })?;
+    let target_tables: HashSet<_> = client
+        .list_tables()
+        .await
+        .context(SchemaDiff {
+            msg: "list tables of target",
+        })?
+        .into_iter()
+        .collect();
+
+    let mut diffs = Vec::new();
+    for table in tables {
+        let name = table.name().to_string();
+        let source_sql = ShowCreateInterpreter::render_table_sql(table);
+        let issues = if target_tables.contains(&name) {
+            let target_sql = client.show_create_table(&name).await.context(SchemaDiff {
+                msg: format!("show create table {name} of target"),
+            })?;
+            let source = schema_diff::parse_create_table(&source_sql).context(SchemaDiff {
+                msg: format!("parse create table {name} of source"),
+            })?;
+            let target = schema_diff::parse_create_table(&target_sql).context(SchemaDiff {
+                msg: format!("parse create table {name} of target"),
+            })?;
+            schema_diff::diff_tables(&source, &target)
+        } else {
+            vec![Issue {
+                severity: Severity::Error,
+                message: "table is missing in target".to_string(),
+                suggestion: Some(source_sql),
+            }]
+        };
+
+        if !issues.is_empty() {
+            diffs.push(TableDiff {
+                table: name,
+                issues,
+            });
+        }
+    }
+
+    let compatible = diffs
+        .iter()
+        .flat_map(|diff| &diff.issues)
+        .all(|issue| issue.severity != Severity::Error);
+    info!(
+        "Schema diff is checked, target:{}, compatible:{compatible}, tables:{}",
+        request.target,
+        diffs.len()
+    );
+
+    Ok(SchemaDiffResponse {
+        compatible,
+        tables: diffs,
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ResyncReplicaRequest {
+    /// Name of the replica in the config of the replica check.
+    replica: String,
+    /// Tables to re-sync, empty means all the diverged tables of the replica.
+    #[serde(default)]
+    tables: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ResyncReplicaResponse {
+    /// Tables to send a snapshot to the replica by their next changes.
+    tables: Vec<String>,
+}
+
+pub async fn handle_resync_replica(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+    request: ResyncReplicaRequest,
+) -> Result<ResyncReplicaResponse> {
+    let tables = match &instance.replica_checker {
+        Some(checker) => checker
+            .resync(&request.replica, &request.tables)
+            .await
+            .box_err()
+            .context(ResyncReplica)?,
+        None => None,
+    }
+    .context(ReplicaNotFound {
+        replica: &request.replica,
+    })?;
+
+    Ok(ResyncReplicaResponse { tables })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ListJsonMappingsResponse {
+    mappings: Vec<MappingSpec>,
+}
+
+pub async fn handle_list_json_mappings(
+    _ctx: RequestContext,
+    proxy: Arc<Proxy>,
+) -> Result<ListJsonMappingsResponse> {
+    Ok(ListJsonMappingsResponse {
+        mappings: proxy.json_mappings.list(),
+    })
+}
+
+/// Register the mapping of the json documents, and the schema of the request
+/// is used if the mapping has no schema.
+pub async fn handle_register_json_mapping(
+    ctx: RequestContext,
+    proxy: Arc<Proxy>,
+    mut spec: MappingSpec,
+) -> Result<MappingSpec> {
+    if spec.schema.is_empty() {
+        spec.schema = ctx.schema;
+    }
+
+    proxy
+        .json_mappings
+        .register(spec)
+        .await
+        .box_err()
+        .context(RegisterJsonMapping)
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RemoveJsonMappingResponse {
+    table: String,
+}
+
+pub async fn handle_remove_json_mapping(
+    ctx: RequestContext,
+    proxy: Arc<Proxy>,
+    table: String,
+) -> Result<RemoveJsonMappingResponse> {
+    let removed = proxy
+        .json_mappings
+        .remove(&ctx.schema, &table)
+        .await
+        .box_err()
+        .context(RemoveJsonMapping)?;
+    ensure!(
+        removed,
+        JsonMappingNotFound {
+            schema: &ctx.schema,
+            table: &table,
+        }
+    );
+
+    Ok(RemoveJsonMappingResponse { table })
+}
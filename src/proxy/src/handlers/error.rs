@@ -17,6 +17,7 @@
 
 //! Error of handlers
 
+use generic_error::GenericError;
 use macros::define_result;
 use snafu::{Backtrace, Snafu};
 use warp::reject::Reject;
@@ -86,6 +87,94 @@ pub enum Error {
         source: tokio::time::error::Elapsed,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Query not found, id:{}.\nBacktrace:\n{}", id, backtrace))]
+    QueryNotFound { id: u64, backtrace: Backtrace },
+
+    #[snafu(display("Failed to update route rules, err:{}", source))]
+    UpdateRouteRules { source: router::Error },
+
+    #[snafu(display(
+        "Schema not found, catalog:{}, schema:{}.\nBacktrace:\n{}",
+        catalog,
+        schema,
+        backtrace
+    ))]
+    SchemaNotFound {
+        catalog: String,
+        schema: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Table not found, table:{}.\nBacktrace:\n{}", table, backtrace))]
+    TableNotFound { table: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to check schema compatibility, msg:{}, err:{}", msg, source))]
+    SchemaDiff { msg: String, source: GenericError },
+
+    #[snafu(display(
+        "Target is not configured as a schema diff target, target:{}.\nBacktrace:\n{}",
+        target,
+        backtrace
+    ))]
+    TargetNotAllowed {
+        target: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Replica not found, replica:{}.\nBacktrace:\n{}", replica, backtrace))]
+    ReplicaNotFound {
+        replica: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to register json mapping, err:{}", source))]
+    RegisterJsonMapping { source: GenericError },
+
+    #[snafu(display("Failed to remove json mapping, err:{}", source))]
+    RemoveJsonMapping { source: GenericError },
+
+    #[snafu(display(
+        "Json mapping not found, schema:{}, table:{}.\nBacktrace:\n{}",
+        schema,
+        table,
+        backtrace
+    ))]
+    JsonMappingNotFound {
+        schema: String,
+        table: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to import file, msg:{}, err:{}", msg, source))]
+    Import { msg: String, source: GenericError },
+
+    #[snafu(display(
+        "Invalid path of the file to import, path:{}, msg:{}.\nBacktrace:\n{}",
+        path,
+        msg,
+        backtrace
+    ))]
+    InvalidImportPath {
+        path: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Read-only mode can't be switched off in the query-only mode.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    QueryOnlyMode { backtrace: Backtrace },
+
+    #[snafu(display("Udf directory is not configured.\nBacktrace:\n{}", backtrace))]
+    UdfDirNotConfigured { backtrace: Backtrace },
+
+    #[snafu(display("Failed to reload the functions, err:{}", source))]
+    ReloadFunctions { source: GenericError },
+
+    #[snafu(display("Failed to re-sync the replica, err:{}", source))]
+    ResyncReplica { source: GenericError },
 }
 
 define_result!(Error);
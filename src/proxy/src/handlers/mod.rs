@@ -18,7 +18,7 @@
 //! Request handlers
 
 pub mod admin;
-mod error;
+pub mod error;
 
 mod prelude {
     pub use serde::{Deserialize, Serialize};
@@ -25,6 +25,7 @@ use logger::{info, warn};
 use runtime::Runtime;
 use serde::{Deserialize, Serialize};
 use spin::Mutex as SpinMutex;
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
 use time_ext::ReadableDuration;
 use timed_task::TimedTask;
 use tokio::sync::mpsc::{self, Sender};
@@ -137,7 +138,7 @@ pub struct Dump {
 
 // TODO: move HotspotRecorder to components dir for reuse.
 impl HotspotRecorder {
-    pub fn new(config: Config, runtime: Arc<Runtime>) -> Self {
+    pub fn new(config: Config, runtime: Arc<Runtime>, task_registry: TaskRegistryRef) -> Self {
         let hotspot_query = Self::init_lru(config.query_cap);
         let hotspot_write = Self::init_lru(config.write_cap);
         let hotspot_field_write = Self::init_lru(config.write_cap);
@@ -187,7 +188,8 @@ impl HotspotRecorder {
         };
 
         let (tx, mut rx) = mpsc::channel(RECORDER_CHANNEL_CAP);
-        runtime.spawn(async move {
+        let spec = TaskSpec::new(TaskKind::HotspotRecord, "hotspot");
+        task_registry.spawn_tracked(&runtime, spec, async move {
             loop {
                 match rx.recv().await {
                     None => {
@@ -329,6 +331,7 @@ mod test {
         },
     };
     use runtime::Builder;
+    use table_engine::task::{self, TaskRegistry};
 
     fn new_runtime() -> Arc<Runtime> {
         let runtime = Builder::default()
@@ -340,6 +343,10 @@ mod test {
         Arc::new(runtime)
     }
 
+    fn new_task_registry() -> TaskRegistryRef {
+        Arc::new(TaskRegistry::new(&task::Config::default()))
+    }
+
     use super::*;
 
     #[test]
@@ -358,7 +365,7 @@ mod test {
                 auto_dump_interval: ReadableDuration::millis(5000),
                 auto_dump_num_items: 10,
             };
-            let recorder = HotspotRecorder::new(options, runtime.clone());
+            let recorder = HotspotRecorder::new(options, runtime.clone(), new_task_registry());
             assert!(recorder.stat.pop_read_hots().unwrap().is_empty());
             assert!(recorder.stat.pop_write_hots().unwrap().is_empty());
             let table = String::from("table1");
@@ -395,7 +402,7 @@ mod test {
                 auto_dump_num_items: 10,
             };
 
-            let recorder = HotspotRecorder::new(options, runtime.clone());
+            let recorder = HotspotRecorder::new(options, runtime.clone(), new_task_registry());
 
             assert!(recorder.stat.pop_read_hots().unwrap().is_empty());
             assert!(recorder.stat.pop_write_hots().unwrap().is_empty());
@@ -41,7 +41,8 @@ use http::StatusCode;
 use interpreters::{interpreter::Output, RecordBatchVec};
 use logger::{error, info};
 use prom_remote_api::types::{
-    Label, LabelMatcher, Query, QueryResult, RemoteStorage, Sample, TimeSeries, WriteRequest,
+    label_matcher, Label, LabelMatcher, Query, QueryResult, RemoteStorage, Sample, TimeSeries,
+    WriteRequest,
 };
 use prost::Message;
 use query_frontend::{
@@ -54,6 +55,7 @@ use time_ext::InstantExt;
 use warp::reject;
 
 use crate::{
+    auth::AuthUser,
     context::RequestContext,
     error::{build_ok_header, ErrNoCause, ErrWithCause, Error, Internal, InternalNoCause, Result},
     forward::ForwardResult,
@@ -83,7 +85,7 @@ impl Proxy {
             }),
             table_requests: write_table_requests,
         };
-        let ctx = ProxyContext::new(ctx.timeout, None);
+        let ctx = ProxyContext::new(ctx.timeout, None).with_auth_user(ctx.auth_user.clone());
 
         match self.handle_write_internal(ctx, table_request).await {
             Ok(result) => {
@@ -136,7 +138,8 @@ impl Proxy {
             function_registry: &*self.instance.function_registry,
         };
         let frontend = Frontend::new(provider, self.instance.dyn_config.fronted.clone());
-        let plan_ctx = Context::new(request_id.clone(), deadline);
+        let mut plan_ctx = Context::new(request_id.clone(), deadline);
+        plan_ctx.user = ctx.auth_user.as_ref().map(|v| v.name().to_string());
 
         let RemoteQueryPlan {
             plan,
@@ -158,13 +161,15 @@ impl Proxy {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 msg: "Query is blocked",
             })?;
+        self.check_plan_role(ctx.auth_user.as_ref(), &ctx.catalog, Some(&metric), &plan)?;
         let output = self
-            .execute_plan(
+            .execute_query_plan(
                 request_id.clone(),
                 &ctx.catalog,
                 &ctx.schema,
                 plan,
                 deadline,
+                ctx.auth_user.as_ref(),
             )
             .await?;
 
@@ -179,6 +184,7 @@ impl Proxy {
     pub async fn handle_prom_grpc_query(
         &self,
         timeout: Option<Duration>,
+        auth_user: Option<AuthUser>,
         req: PrometheusRemoteQueryRequest,
     ) -> Result<PrometheusRemoteQueryResponse> {
         let ctx = req.context.context(ErrNoCause {
@@ -194,6 +200,7 @@ impl Proxy {
         let metric = find_metric(&query.matchers)?;
         let builder = RequestContext::builder()
             .timeout(timeout)
+            .auth_user(auth_user)
             .schema(database)
             // TODO: support different catalog
             .catalog(DEFAULT_CATALOG.to_string());
@@ -235,7 +242,11 @@ impl RemoteStorage for Proxy {
                 query: query.encode_to_vec(),
             };
             if let Some(resp) = self
-                .maybe_forward_prom_remote_query(metric.clone(), remote_req)
+                .maybe_forward_prom_remote_query(
+                    metric.clone(),
+                    ctx.auth_user.as_ref(),
+                    remote_req,
+                )
                 .await
                 .map_err(|e| {
                     error!("Forward prom remote query failed, err:{e}");
@@ -423,18 +434,31 @@ impl Converter {
     }
 }
 
+/// Find the metric, which is also the table to query, and only the equal
+/// matcher of the metric name is supported.
 fn find_metric(matchers: &[LabelMatcher]) -> Result<String> {
-    let idx = matchers
+    let matcher = matchers
         .iter()
-        .position(|m| m.name == NAME_LABEL)
-        .context(InternalNoCause {
+        .find(|m| m.name == NAME_LABEL)
+        .context(ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
             msg: "Metric name is not found",
         })?;
+    ensure!(
+        matcher.r#type() == label_matcher::Type::Eq,
+        ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Only equal matcher is supported for metric name, matcher:{matcher:?}"),
+        }
+    );
 
-    Ok(matchers[idx].value.clone())
+    Ok(matcher.value.clone())
 }
 
 /// Separate metric from labels, and sort labels by name.
+///
+/// The labels with empty value are removed, which are the same as the absent
+/// ones in Prometheus.
 fn normalize_labels(mut labels: Vec<Label>) -> Result<(String, Vec<Label>)> {
     let metric_idx = labels
         .iter()
@@ -443,6 +467,7 @@ fn normalize_labels(mut labels: Vec<Label>) -> Result<(String, Vec<Label>)> {
             msg: "Metric name is not found",
         })?;
     let metric = labels.swap_remove(metric_idx).value;
+    labels.retain(|label| !label.value.is_empty());
     labels.sort_unstable_by(|a, b| a.name.cmp(&b.name));
 
     Ok((metric, labels))
@@ -574,6 +599,33 @@ mod tests {
         );
 
         assert!(normalize_labels(vec![]).is_err());
+
+        let labels = make_labels(vec![("aa", ""), (NAME_LABEL, "cpu"), ("bb", "vb")]);
+        let (metric, labels) = normalize_labels(labels).unwrap();
+        assert_eq!("cpu", metric);
+        assert_eq!(make_labels(vec![("bb", "vb")]), labels);
+    }
+
+    #[test]
+    fn test_find_metric() {
+        let make_matcher =
+            |name: &str, value: &str, matcher_type: label_matcher::Type| LabelMatcher {
+                name: name.to_string(),
+                value: value.to_string(),
+                r#type: matcher_type as i32,
+            };
+
+        let matchers = vec![
+            make_matcher("tag1", "v1", label_matcher::Type::Eq),
+            make_matcher(NAME_LABEL, "cpu", label_matcher::Type::Eq),
+        ];
+        assert_eq!("cpu", find_metric(&matchers).unwrap());
+
+        let matchers = vec![make_matcher(NAME_LABEL, "cpu.*", label_matcher::Type::Re)];
+        assert!(find_metric(&matchers).is_err());
+
+        let matchers = vec![make_matcher("tag1", "v1", label_matcher::Type::Eq)];
+        assert!(find_metric(&matchers).is_err());
     }
 
     // Build a schema with
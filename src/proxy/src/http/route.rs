@@ -17,16 +17,17 @@
 
 use horaedbproto::storage::RouteRequest as RouteRequestPb;
 use router::{endpoint::Endpoint, RouteRequest};
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::{context::RequestContext, error::Result, Proxy};
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RouteResponse {
     routes: Vec<RouteItem>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RouteItem {
     pub table: String,
     pub endpoint: Option<Endpoint>,
@@ -15,13 +15,19 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::io::Cursor;
+use std::{collections::BTreeMap, io::Cursor, sync::Arc};
 
 use arrow::{ipc::reader::StreamReader, record_batch::RecordBatch as ArrowRecordBatch};
+use bytes::Bytes;
 use common_types::{
     datum::{Datum, DatumKind},
     record_batch::RecordBatch,
 };
+use futures::{
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    FutureExt, StreamExt,
+};
 use generic_error::BoxError;
 use horaedbproto::storage::{
     arrow_payload::Compression, sql_query_response::Output as OutputPb, ArrowPayload,
@@ -30,15 +36,23 @@ use horaedbproto::storage::{
 use http::StatusCode;
 use interpreters::{interpreter::Output, RecordBatchVec};
 use logger::error;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, SubschemaValidation},
+    JsonSchema,
+};
 use serde::{
     ser::{SerializeMap, SerializeSeq},
     Deserialize, Serialize,
 };
 use snafu::{OptionExt, ResultExt};
+use table_engine::query_warning::{QueryWarnings, Warning};
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
     context::RequestContext,
-    error::{ErrNoCause, Internal, InternalNoCause, Result},
+    error::{ErrNoCause, ErrWithCause, Internal, InternalNoCause, Result},
     read::SqlResponse,
     Context, Proxy,
 };
@@ -49,8 +63,20 @@ impl Proxy {
         ctx: &RequestContext,
         req: Request,
     ) -> Result<Output> {
+        self.handle_http_sql_query_with_warnings(ctx, req)
+            .await
+            .map(|(output, _)| output)
+    }
+
+    /// Handle the sql query, and return the output with the warnings if the
+    /// tables are read partially.
+    pub async fn handle_http_sql_query_with_warnings(
+        &self,
+        ctx: &RequestContext,
+        req: Request,
+    ) -> Result<(Output, Vec<Warning>)> {
         let schema = &ctx.schema;
-        let ctx = Context::new(ctx.timeout, None);
+        let ctx = query_context(ctx);
 
         let query_res = self
             .handle_sql(
@@ -70,12 +96,198 @@ impl Proxy {
                 );
                 Err(e)
             }
-            Ok(SqlResponse::Forwarded(resp)) => convert_sql_response_to_output(resp),
-            Ok(SqlResponse::Local(output)) => Ok(output),
+            // The warnings of the forwarded query are merged into the context.
+            Ok(SqlResponse::Forwarded(resp)) => {
+                convert_sql_response_to_output(resp).map(|output| (output, ctx.warnings.take()))
+            }
+            Ok(SqlResponse::Local(output)) => Ok((output, ctx.warnings.take())),
+        }
+    }
+
+    /// Handle the sql query, and stream the json response whose rows are
+    /// encoded as soon as they are produced by the executor rather than being
+    /// buffered.
+    ///
+    /// The response is the same as the buffered one. The errors before any
+    /// row is produced are returned as usual, while the ones after that can
+    /// only abort the body.
+    pub async fn handle_http_stream_sql_query(
+        self: Arc<Self>,
+        ctx: &RequestContext,
+        req: Request,
+    ) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let Some(permit) = self.stream_limiter.try_open() else {
+            return ErrNoCause {
+                code: StatusCode::TOO_MANY_REQUESTS,
+                msg: "Too many open streams of sql query",
+            }
+            .fail();
+        };
+
+        // The executor is paused when the channel is full, that is the client
+        // doesn't consume the body in time.
+        let (tx, rx) = mpsc::channel(self.stream_limiter.channel_capacity());
+        let query_ctx = query_context(ctx).with_result_sender(Some(tx));
+        let warnings = query_ctx.warnings.clone();
+        let schema = ctx.schema.clone();
+        let proxy = self.clone();
+        let handle = self.engine_runtimes.read_runtime.spawn(async move {
+            let result = proxy
+                .handle_sql(
+                    &query_ctx,
+                    &schema,
+                    &req.query,
+                    proxy.sub_table_access_perm.enable_http,
+                    false,
+                )
+                .await;
+            if let Err(e) = &result {
+                error!(
+                    "Handle sql query failed, schema:{schema}, ctx:{query_ctx:?}, sql:{}, err:{e}",
+                    req.query,
+                );
+            }
+            result
+        });
+        let mut output = async move {
+            let resp = handle.await.box_err().context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Failed to join http sql query task",
+            })??;
+            match resp {
+                SqlResponse::Forwarded(resp) => convert_sql_response_to_output(resp),
+                SqlResponse::Local(output) => Ok(output),
+            }
+        }
+        .boxed();
+
+        let mut batches = ReceiverStream::new(rx);
+        let Some(first) = batches.next().await else {
+            // Nothing is streamed, e.g. the query is forwarded or it fails.
+            let output = output.await?;
+            let response = ResponseWithWarnings::new(convert_output(output), warnings.take());
+            let body = serde_json::to_vec(&response).box_err().context(Internal {
+                msg: "encode http sql query response",
+            })?;
+            return Ok(stream::once(async { Ok(Bytes::from(body)) }).boxed());
+        };
+
+        let mut encoder = RowsEncoder::default();
+        let head = encoder.encode(vec![first]);
+        let state = StreamState {
+            encoder,
+            batches,
+            output: Some(output),
+            warnings,
+            _permit: permit,
+        };
+        let rest = stream::unfold(state, |mut state| async move {
+            if let Some(batch) = state.batches.next().await {
+                let chunk = state.encoder.encode(vec![batch]);
+                return Some((chunk, state));
+            }
+
+            // The output left after the batches are streamed, e.g. the records
+            // not produced by the executor.
+            let chunk = match state.output.take()?.await {
+                Ok(Output::Records(batches)) => state
+                    .encoder
+                    .encode(batches)
+                    .and_then(|chunk| state.encoder.finish(chunk, state.warnings.take())),
+                Ok(Output::AffectedRows(_)) => InternalNoCause {
+                    msg: "Affected rows are returned after the streamed rows",
+                }
+                .fail(),
+                Err(e) => Err(e),
+            };
+            Some((chunk, state))
+        });
+
+        Ok(stream::once(async { head }).chain(rest).boxed())
+    }
+}
+
+fn query_context(ctx: &RequestContext) -> Context {
+    Context::new(ctx.timeout, None)
+        .with_log_level(ctx.log_level)
+        .with_auth_user(ctx.auth_user.clone())
+}
+
+struct StreamState {
+    encoder: RowsEncoder,
+    batches: ReceiverStream<RecordBatch>,
+    output: Option<BoxFuture<'static, Result<Output>>>,
+    warnings: QueryWarnings,
+    /// The stream is kept open until the body is consumed or dropped.
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Encode the rows of the streamed response into the same json as the
+/// buffered [ResponseWithWarnings].
+#[derive(Default)]
+struct RowsEncoder {
+    started: bool,
+    has_rows: bool,
+    columns: Vec<ColumnMetadata>,
+}
+
+impl RowsEncoder {
+    /// Encode the rows of the batches, the first call starts the response.
+    fn encode(&mut self, batches: RecordBatchVec) -> Result<Bytes> {
+        let rows = convert_records(batches);
+        for column in rows.column_metadata() {
+            if !self.columns.contains(&column) {
+                self.columns.push(column);
+            }
+        }
+
+        let mut buf = Vec::new();
+        if !self.started {
+            buf.extend_from_slice(br#"{"rows":["#);
+            self.started = true;
+        }
+        for row_idx in 0..rows.data.len() {
+            if self.has_rows {
+                buf.push(b',');
+            }
+            serde_json::to_writer(&mut buf, &rows.row(row_idx))
+                .box_err()
+                .context(Internal {
+                    msg: "encode http sql query rows",
+                })?;
+            self.has_rows = true;
+        }
+
+        Ok(buf.into())
+    }
+
+    /// Append the end of the response to the last chunk.
+    fn finish(&self, chunk: Bytes, warnings: Vec<Warning>) -> Result<Bytes> {
+        let mut buf = chunk.to_vec();
+        buf.push(b']');
+        if !warnings.is_empty() {
+            buf.extend_from_slice(br#","warnings":"#);
+            serde_json::to_writer(&mut buf, &warnings)
+                .box_err()
+                .context(Internal {
+                    msg: "encode http sql query warnings",
+                })?;
+        }
+        if !self.columns.is_empty() {
+            buf.extend_from_slice(br#","columns":"#);
+            serde_json::to_writer(&mut buf, &self.columns)
+                .box_err()
+                .context(Internal {
+                    msg: "encode http sql query columns",
+                })?;
         }
+        buf.push(b'}');
+
+        Ok(buf.into())
     }
 }
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(rename = "SqlRequest")]
 pub struct Request {
     pub query: String,
 }
@@ -88,6 +300,91 @@ pub enum Response {
     Rows(ResponseRows),
 }
 
+/// The response is flattened into [ResponseWithWarnings], so unlike the
+/// derived schema of an externally tagged enum, the variants allow the other
+/// properties.
+impl JsonSchema for Response {
+    fn schema_name() -> String {
+        "SqlResult".to_string()
+    }
+
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let variants = [
+            ("affected_rows", gen.subschema_for::<usize>()),
+            ("rows", gen.subschema_for::<ResponseRows>()),
+        ];
+        let one_of = variants
+            .into_iter()
+            .map(|(name, schema)| {
+                let mut variant = SchemaObject {
+                    instance_type: Some(InstanceType::Object.into()),
+                    ..Default::default()
+                };
+                let object = variant.object();
+                object.properties.insert(name.to_string(), schema);
+                object.required.insert(name.to_string());
+                Schema::Object(variant)
+            })
+            .collect();
+
+        Schema::Object(SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(one_of),
+                ..Default::default()
+            })),
+            ..Default::default()
+        })
+    }
+}
+
+/// Response with the warnings of the query, e.g. the results are partial
+/// because some corrupt ssts are skipped.
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "SqlResponse")]
+pub struct ResponseWithWarnings {
+    #[serde(flatten)]
+    pub response: Response,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schemars(default)]
+    pub warnings: Vec<Warning>,
+    /// Annotations of the result columns, only the annotated ones are listed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schemars(default)]
+    pub columns: Vec<ColumnMetadata>,
+}
+
+impl ResponseWithWarnings {
+    pub fn new(response: Response, warnings: Vec<Warning>) -> Self {
+        let columns = match &response {
+            Response::AffectedRows(_) => Vec::new(),
+            Response::Rows(rows) => rows.column_metadata(),
+        };
+
+        Self {
+            response,
+            warnings,
+            columns,
+        }
+    }
+}
+
+/// Display name and unit of a result column, which are annotated on the
+/// column of the table read by it, so the clients can format the values.
+#[derive(Debug, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ColumnMetadata {
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[schemars(default)]
+    pub display_name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[schemars(default)]
+    pub unit: String,
+}
+
 pub struct ResponseRows {
     pub column_names: Vec<ResponseColumn>,
     pub data: Vec<Vec<Datum>>,
@@ -96,6 +393,42 @@ pub struct ResponseRows {
 pub struct ResponseColumn {
     pub name: String,
     pub data_type: DatumKind,
+    pub display_name: String,
+    pub unit: String,
+}
+
+impl ResponseRows {
+    fn column_metadata(&self) -> Vec<ColumnMetadata> {
+        let mut columns: Vec<ColumnMetadata> = Vec::new();
+        for column in &self.column_names {
+            if column.display_name.is_empty() && column.unit.is_empty() {
+                continue;
+            }
+            // The columns are pushed for each record batch.
+            if columns.iter().any(|v| v.name == column.name) {
+                continue;
+            }
+            columns.push(ColumnMetadata {
+                name: column.name.clone(),
+                display_name: column.display_name.clone(),
+                unit: column.unit.clone(),
+            });
+        }
+
+        columns
+    }
+
+    fn row(&self, row_idx: usize) -> Row<'_> {
+        let data = self.data[row_idx]
+            .iter()
+            .enumerate()
+            .map(|(col_idx, datum)| {
+                let column_name = &self.column_names[col_idx].name;
+                (column_name, datum)
+            })
+            .collect::<Vec<_>>();
+        Row(data)
+    }
 }
 
 struct Row<'a>(Vec<(&'a String, &'a Datum)>);
@@ -122,37 +455,43 @@ impl Serialize for ResponseRows {
         let total_count = self.data.len();
         let mut seq = serializer.serialize_seq(Some(total_count))?;
 
-        for rows in &self.data {
-            let data = rows
-                .iter()
-                .enumerate()
-                .map(|(col_idx, datum)| {
-                    let column_name = &self.column_names[col_idx].name;
-                    (column_name, datum)
-                })
-                .collect::<Vec<_>>();
-            let row = Row(data);
-            seq.serialize_element(&row)?;
+        for row_idx in 0..total_count {
+            seq.serialize_element(&self.row(row_idx))?;
         }
 
         seq.end()
     }
 }
 
+/// The rows are serialized as the objects keyed by the column names.
+impl JsonSchema for ResponseRows {
+    fn schema_name() -> String {
+        "ResponseRows".to_string()
+    }
+
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        <Vec<BTreeMap<String, serde_json::Value>>>::json_schema(gen)
+    }
+}
+
 // Convert output to json
 pub fn convert_output(output: Output) -> Response {
     match output {
         Output::AffectedRows(n) => Response::AffectedRows(n),
-        Output::Records(records) => convert_records(records),
+        Output::Records(records) => Response::Rows(convert_records(records)),
     }
 }
 
-fn convert_records(records: RecordBatchVec) -> Response {
+fn convert_records(records: RecordBatchVec) -> ResponseRows {
     if records.is_empty() {
-        return Response::Rows(ResponseRows {
+        return ResponseRows {
             column_names: Vec::new(),
             data: Vec::new(),
-        });
+        };
     }
 
     let mut column_names = vec![];
@@ -168,6 +507,8 @@ fn convert_records(records: RecordBatchVec) -> Response {
             column_names.push(ResponseColumn {
                 name: column_schema.name,
                 data_type: column_schema.data_type,
+                display_name: column_schema.display_name,
+                unit: column_schema.unit,
             });
         }
 
@@ -184,13 +525,15 @@ fn convert_records(records: RecordBatchVec) -> Response {
         }
     }
 
-    Response::Rows(ResponseRows {
+    ResponseRows {
         column_names,
         data: column_data,
-    })
+    }
 }
 
-fn convert_sql_response_to_output(sql_query_response: SqlQueryResponse) -> Result<Output> {
+pub(crate) fn convert_sql_response_to_output(
+    sql_query_response: SqlQueryResponse,
+) -> Result<Output> {
     if let Some(header) = sql_query_response.header {
         if header.code as u16 != StatusCode::OK.as_u16() {
             return ErrNoCause {
@@ -277,3 +620,68 @@ fn decode_arrow_payload(arrow_payload: ArrowPayload) -> Result<Vec<ArrowRecordBa
 
     Ok(record_batches)
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Float64Array, TimestampMillisecondArray};
+    use common_types::{
+        column_schema,
+        schema::{self, TIMESTAMP_COLUMN},
+    };
+    use table_engine::query_warning::WarningKind;
+
+    use super::*;
+
+    fn build_record_batch(timestamps: Vec<i64>, values: Vec<f64>) -> RecordBatch {
+        let schema = schema::Builder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                column_schema::Builder::new(TIMESTAMP_COLUMN.to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("value".to_string(), DatumKind::Double)
+                    .unit("ms".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .primary_key_indexes(vec![0])
+            .build()
+            .unwrap();
+        let timestamp: ArrayRef = Arc::new(TimestampMillisecondArray::from(timestamps));
+        let value: ArrayRef = Arc::new(Float64Array::from(values));
+        let batch = ArrowRecordBatch::try_new(schema.to_arrow_schema_ref(), vec![timestamp, value])
+            .unwrap();
+
+        RecordBatch::try_from(batch).unwrap()
+    }
+
+    #[test]
+    fn test_rows_encoder() {
+        let batches = vec![
+            build_record_batch(vec![1, 2], vec![1.0, 2.0]),
+            build_record_batch(vec![], vec![]),
+            build_record_batch(vec![3], vec![3.0]),
+        ];
+        let warnings = vec![Warning::new(
+            WarningKind::PartialResult,
+            "skipped".to_string(),
+        )];
+
+        let mut encoder = RowsEncoder::default();
+        let mut body = Vec::new();
+        for batch in &batches[..2] {
+            body.extend_from_slice(&encoder.encode(vec![batch.clone()]).unwrap());
+        }
+        let chunk = encoder.encode(batches[2..].to_vec()).unwrap();
+        body.extend_from_slice(&encoder.finish(chunk, warnings.clone()).unwrap());
+
+        // The streamed body is the same as the buffered one.
+        let output = Output::Records(batches);
+        let response = ResponseWithWarnings::new(convert_output(output), warnings);
+        assert_eq!(serde_json::to_vec(&response).unwrap(), body);
+    }
+}
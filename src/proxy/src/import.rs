@@ -0,0 +1,386 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Bulk import of the parquet/csv files into the tables.
+//!
+//! The files are read from the local file system of the server, converted to
+//! the table schema and imported into the table directly as ssts, chunk by
+//! chunk, bypassing the wal and the memtable. The progress of the imports is
+//! kept in memory.
+//!
+//! The files are not uploaded by the clients, so they must be copied to the
+//! server host before the import.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Seek,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use arrow::{array::new_null_array, compute, csv, record_batch::RecordBatch as ArrowRecordBatch};
+use common_types::{record_batch::RecordBatch, schema::Schema};
+use generic_error::{BoxError, GenericResult};
+use logger::{error, info};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use runtime::RuntimeRef;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use table_engine::{
+    table::{ImportRequest, TableRef},
+    task::{TaskKind, TaskRegistryRef, TaskSpec},
+};
+
+/// Number of the rows to read from the file at a time.
+const READ_BATCH_SIZE: usize = 8192;
+/// Number of the rows used to infer the schema of the csv file.
+const CSV_INFER_SCHEMA_ROWS: usize = 1000;
+/// Max number of the finished imports whose progress is kept.
+const MAX_FINISHED_IMPORTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    Parquet,
+    /// Csv file with the header line.
+    Csv,
+}
+
+/// Check the file to import is on the local file system of the server, so a
+/// relative path or a path only valid on the client host is rejected before
+/// the import starts.
+pub fn check_path(path: &str) -> std::result::Result<(), String> {
+    if !Path::new(path).is_absolute() {
+        return Err("the path must be absolute".to_string());
+    }
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => Ok(()),
+        Ok(_) => Err("the path is not a file".to_string()),
+        Err(e) => Err(format!("the file is not found on the server, err:{e}")),
+    }
+}
+
+type FileReader = Box<dyn Iterator<Item = GenericResult<ArrowRecordBatch>> + Send>;
+
+fn open_file(path: &str, format: FileFormat) -> GenericResult<FileReader> {
+    let mut file = File::open(path).box_err()?;
+    let reader: FileReader = match format {
+        FileFormat::Parquet => {
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+                .box_err()?
+                .with_batch_size(READ_BATCH_SIZE)
+                .build()
+                .box_err()?;
+            Box::new(reader.map(|batch| batch.box_err()))
+        }
+        FileFormat::Csv => {
+            let (schema, _) = csv::reader::Format::default()
+                .with_header(true)
+                .infer_schema(&mut file, Some(CSV_INFER_SCHEMA_ROWS))
+                .box_err()?;
+            file.rewind().box_err()?;
+            let reader = csv::ReaderBuilder::new(Arc::new(schema))
+                .with_header(true)
+                .with_batch_size(READ_BATCH_SIZE)
+                .build(file)
+                .box_err()?;
+            Box::new(reader.map(|batch| batch.box_err()))
+        }
+    };
+
+    Ok(reader)
+}
+
+/// Convert the `batch` read from the file to the table `schema`.
+///
+/// The columns are matched by name and casted to the column types, and the
+/// missing nullable columns are filled with nulls.
+pub fn to_table_batch(schema: &Schema, batch: &ArrowRecordBatch) -> GenericResult<RecordBatch> {
+    if let Some(field) = batch
+        .schema()
+        .fields()
+        .iter()
+        .find(|field| schema.index_of(field.name()).is_none())
+    {
+        return Err(format!("column not found in table, column:{}", field.name()).into());
+    }
+
+    let arrow_schema = schema.to_arrow_schema_ref();
+    let mut columns = Vec::with_capacity(schema.num_columns());
+    for (column_schema, field) in schema.columns().iter().zip(arrow_schema.fields()) {
+        let column = match batch.column_by_name(&column_schema.name) {
+            Some(array) => compute::cast(array, field.data_type()).box_err()?,
+            None => new_null_array(field.data_type(), batch.num_rows()),
+        };
+        // The values failed to cast are nulls too.
+        if !column_schema.is_nullable && column.null_count() > 0 {
+            return Err(format!(
+                "missing or invalid values of not null column, column:{}",
+                column_schema.name
+            )
+            .into());
+        }
+        columns.push(column);
+    }
+
+    let batch = ArrowRecordBatch::try_new(arrow_schema, columns).box_err()?;
+    RecordBatch::try_from(batch).box_err()
+}
+
+/// Read the next chunk of at most about `max_rows` rows from the `reader`.
+fn read_chunk(
+    reader: &mut FileReader,
+    schema: &Schema,
+    max_rows: usize,
+) -> GenericResult<Vec<RecordBatch>> {
+    let mut batches = Vec::new();
+    let mut num_rows = 0;
+    while num_rows < max_rows {
+        let batch = match reader.next() {
+            Some(batch) => batch?,
+            None => break,
+        };
+        num_rows += batch.num_rows();
+        batches.push(to_table_batch(schema, &batch)?);
+    }
+
+    Ok(batches)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ImportState {
+    Running,
+    Finished,
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ImportProgress {
+    pub id: u64,
+    pub table: String,
+    pub path: String,
+    #[serde(flatten)]
+    pub state: ImportState,
+    /// Number of the imported rows.
+    pub num_rows: usize,
+    /// Number of the imported ssts.
+    pub num_ssts: usize,
+}
+
+/// Track the progress of the imports.
+#[derive(Debug, Default)]
+pub struct ImportTracker {
+    next_id: AtomicU64,
+    imports: Mutex<BTreeMap<u64, ImportProgress>>,
+}
+
+impl ImportTracker {
+    fn start(&self, table: String, path: String) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut imports = self.imports.lock().unwrap();
+        let finished = imports
+            .values()
+            .filter(|v| v.state != ImportState::Running)
+            .map(|v| v.id)
+            .collect::<Vec<_>>();
+        if finished.len() >= MAX_FINISHED_IMPORTS {
+            for id in &finished[..=finished.len() - MAX_FINISHED_IMPORTS] {
+                imports.remove(id);
+            }
+        }
+        imports.insert(
+            id,
+            ImportProgress {
+                id,
+                table,
+                path,
+                state: ImportState::Running,
+                num_rows: 0,
+                num_ssts: 0,
+            },
+        );
+
+        id
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut ImportProgress)) {
+        if let Some(progress) = self.imports.lock().unwrap().get_mut(&id) {
+            f(progress);
+        }
+    }
+
+    /// List the progress of the running and the recently finished imports.
+    pub fn list(&self) -> Vec<ImportProgress> {
+        self.imports.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Import the file into the `table` in background.
+    ///
+    /// Returns the id of the import to query the progress.
+    pub fn spawn_import(
+        self: &Arc<Self>,
+        runtime: RuntimeRef,
+        task_registry: &TaskRegistryRef,
+        table: TableRef,
+        path: String,
+        format: FileFormat,
+        rows_per_sst: usize,
+    ) -> u64 {
+        let id = self.start(table.name().to_string(), path.clone());
+        let tracker = self.clone();
+        let blocking_runtime = runtime.clone();
+        let spec = TaskSpec::new(TaskKind::Import, table.name().to_string());
+        let import = async move {
+            let result = tracker
+                .import_file(blocking_runtime, &table, path, format, rows_per_sst, id)
+                .await;
+            let state = match &result {
+                Ok(()) => {
+                    info!("Import finished, id:{id}, table:{}", table.name());
+                    ImportState::Finished
+                }
+                Err(e) => {
+                    error!("Import failed, id:{id}, table:{}, err:{e}", table.name());
+                    ImportState::Failed {
+                        error: e.to_string(),
+                    }
+                }
+            };
+            tracker.update(id, |progress| progress.state = state);
+            result
+        };
+        task_registry.spawn_tracked(&runtime, spec, import);
+
+        id
+    }
+
+    async fn import_file(
+        &self,
+        runtime: RuntimeRef,
+        table: &TableRef,
+        path: String,
+        format: FileFormat,
+        rows_per_sst: usize,
+        id: u64,
+    ) -> GenericResult<()> {
+        let schema = table.schema();
+        let mut reader = Some(open_file(&path, format)?);
+        loop {
+            // Reading the file blocks.
+            let mut chunk_reader = reader.take().unwrap();
+            let chunk_schema = schema.clone();
+            let (chunk_reader, batches) = runtime
+                .spawn_blocking(move || {
+                    let batches = read_chunk(&mut chunk_reader, &chunk_schema, rows_per_sst);
+                    (chunk_reader, batches)
+                })
+                .await
+                .box_err()?;
+            let batches = batches?;
+            if batches.is_empty() {
+                return Ok(());
+            }
+            reader = Some(chunk_reader);
+
+            let num_rows = table.import(ImportRequest { batches }).await.box_err()?;
+            self.update(id, |progress| {
+                progress.num_rows += num_rows;
+                progress.num_ssts += 1;
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use common_types::{datum::Datum, tests::build_schema, time::Timestamp};
+
+    use super::*;
+
+    #[test]
+    fn test_import_tracker() {
+        let tracker = ImportTracker::default();
+        for i in 0..MAX_FINISHED_IMPORTS + 10 {
+            let id = tracker.start("t".to_string(), format!("file_{i}"));
+            tracker.update(id, |progress| {
+                progress.num_rows = 10;
+                if i > 0 {
+                    progress.state = ImportState::Finished;
+                }
+            });
+        }
+
+        let imports = tracker.list();
+        // The running one is always kept.
+        assert_eq!(imports.len(), MAX_FINISHED_IMPORTS);
+        assert_eq!(imports[0].state, ImportState::Running);
+        assert_eq!(imports[0].num_rows, 10);
+    }
+
+    #[test]
+    fn test_check_path() {
+        let exe = std::env::current_exe().unwrap();
+        assert!(check_path(exe.to_str().unwrap()).is_ok());
+
+        let dir = std::env::temp_dir();
+        assert!(check_path(dir.to_str().unwrap()).is_err());
+        assert!(check_path("data/cpu.parquet").is_err());
+        let missing = dir.join("horaedb_import_test_missing.parquet");
+        assert!(check_path(missing.to_str().unwrap()).is_err());
+    }
+
+    fn build_batch(columns: Vec<(&str, ArrayRef)>) -> ArrowRecordBatch {
+        ArrowRecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    #[test]
+    fn test_to_table_batch() {
+        let schema = build_schema();
+        let key1: ArrayRef = Arc::new(StringArray::from(vec!["a", "b"]));
+        let key2: ArrayRef = Arc::new(Int64Array::from(vec![1000, 2000]));
+        let field1: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), None]));
+
+        let batch = build_batch(vec![
+            ("field1", field1.clone()),
+            ("key2", key2.clone()),
+            ("key1", key1.clone()),
+        ]);
+        let batch = to_table_batch(&schema, &batch).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), schema.num_columns());
+        assert_eq!(
+            batch.column(1).datum(1),
+            Datum::Timestamp(Timestamp::new(2000))
+        );
+        assert_eq!(batch.column(2).datum(0), Datum::Double(1.0));
+        assert!(batch.column(3).datum(0).is_null());
+
+        // The not null column is missing.
+        let batch = build_batch(vec![("key1", key1.clone()), ("field1", field1.clone())]);
+        assert!(to_table_batch(&schema, &batch).is_err());
+
+        // The column is not in the table.
+        let batch = build_batch(vec![("key1", key1), ("key2", key2), ("unknown", field1)]);
+        assert!(to_table_batch(&schema, &batch).is_err());
+    }
+}
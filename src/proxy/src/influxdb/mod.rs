@@ -34,6 +34,7 @@ use query_frontend::{
     frontend::{Context as SqlContext, Frontend},
     provider::CatalogMetaProvider,
 };
+use serde::{Deserialize, Serialize};
 use snafu::{ensure, ResultExt};
 use time_ext::InstantExt;
 
@@ -48,6 +49,23 @@ use crate::{
     Context, Proxy,
 };
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Precision of the timestamps written by the 1.x write api without the
+    /// `precision` parameter, set it to `ns` to be compatible with InfluxDB
+    /// (and the agents like Telegraf).
+    pub default_precision: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_precision: "ms".to_string(),
+        }
+    }
+}
+
 impl Proxy {
     pub async fn handle_influxdb_query(
         &self,
@@ -81,7 +99,9 @@ impl Proxy {
             }),
             table_requests: write_table_requests,
         };
-        let proxy_context = Context::new(ctx.timeout, None);
+        let proxy_context = Context::new(ctx.timeout, None)
+            .with_log_level(ctx.log_level)
+            .with_auth_user(ctx.auth_user.clone());
 
         match self
             .handle_write_internal(proxy_context, table_request)
@@ -140,7 +160,8 @@ impl Proxy {
             function_registry: &*self.instance.function_registry,
         };
         let frontend = Frontend::new(provider, self.instance.dyn_config.fronted.clone());
-        let sql_ctx = SqlContext::new(request_id.clone(), deadline);
+        let mut sql_ctx = SqlContext::new(request_id.clone(), deadline);
+        sql_ctx.user = ctx.auth_user.as_ref().map(|v| v.name().to_string());
 
         let mut stmts = frontend
             .parse_influxql(&sql_ctx, &req.query)
@@ -182,13 +203,15 @@ impl Proxy {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 msg: "Query is blocked",
             })?;
+        self.check_plan_role(ctx.auth_user.as_ref(), &ctx.catalog, None, &plan)?;
         let output = self
-            .execute_plan(
+            .execute_query_plan(
                 request_id.clone(),
                 &ctx.catalog,
                 &ctx.schema,
                 plan,
                 deadline,
+                ctx.auth_user.as_ref(),
             )
             .await?;
 
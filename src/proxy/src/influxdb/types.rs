@@ -32,6 +32,7 @@ use http::Method;
 use influxdb_line_protocol::FieldValue;
 use interpreters::interpreter::Output;
 use query_frontend::influxql::planner::HORAEDB_MEASUREMENT_COLUMN_NAME;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use snafu::{ensure, OptionExt, ResultExt};
 
@@ -53,10 +54,15 @@ pub struct WriteRequest {
 }
 
 impl WriteRequest {
-    pub fn new(lines: Bytes, params: WriteParams) -> Self {
+    /// Build the request of the 1.x write api, and the `default_precision` is
+    /// used if the `precision` is not specified in the `params`.
+    pub fn new(lines: Bytes, params: WriteParams, default_precision: Precision) -> Self {
         let lines = String::from_utf8_lossy(&lines).to_string();
 
-        let precision = params.precision.as_str().into();
+        let precision = params
+            .precision
+            .as_deref()
+            .map_or(default_precision, Precision::from);
 
         WriteRequest {
             lines,
@@ -64,6 +70,20 @@ impl WriteRequest {
             precision,
         }
     }
+
+    /// Build the request of the 2.x write api, whose bucket is used as the
+    /// `db`.
+    pub fn new_v2(lines: Bytes, params: WriteV2Params) -> Self {
+        let lines = String::from_utf8_lossy(&lines).to_string();
+
+        let precision = params.precision.as_str().into();
+
+        WriteRequest {
+            lines,
+            db: params.bucket,
+            precision,
+        }
+    }
 }
 
 pub type WriteResponse = ();
@@ -76,19 +96,46 @@ pub type WriteResponse = ();
 ///
 /// NOTE:
 ///     - `db` is not required and default to `public` in HoraeDB.
-///     - `precision`'s default value is `ms` but not `ns` in HoraeDB.
-#[derive(Debug, Deserialize)]
+///     - `precision`'s default value is configurable and `ms` by default but
+///       not `ns` in HoraeDB.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct WriteParams {
     pub db: String,
-    pub precision: String,
+    pub precision: Option<String>,
 }
 
 impl Default for WriteParams {
     fn default() -> Self {
         Self {
             db: "public".to_string(),
-            precision: "ms".to_string(),
+            precision: None,
+        }
+    }
+}
+
+/// Query string parameters for 2.x write api
+///
+/// It's derived from query string parameters of write described in
+/// doc of influxdb 2.x:
+///     https://docs.influxdata.com/influxdb/v2/api/#operation/PostWrite
+///
+/// NOTE:
+///     - `org` is ignored, and `bucket` is not required in HoraeDB.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WriteV2Params {
+    pub org: String,
+    pub bucket: String,
+    pub precision: String,
+}
+
+impl Default for WriteV2Params {
+    fn default() -> Self {
+        Self {
+            org: String::new(),
+            bucket: "public".to_string(),
+            precision: "ns".to_string(),
         }
     }
 }
@@ -157,7 +204,7 @@ impl InfluxqlRequest {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Precision {
     #[default]
     Millisecond,
@@ -185,7 +232,7 @@ impl From<&str> for Precision {
     fn from(value: &str) -> Self {
         match value {
             "ns" | "n" => Precision::Nanosecond,
-            "u" | "µ" => Precision::Microsecond,
+            "us" | "u" | "µ" => Precision::Microsecond,
             "ms" => Precision::Millisecond,
             "s" => Precision::Second,
             "m" => Precision::Minute,
@@ -740,6 +787,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_request_precision() {
+        let lines = Bytes::from("demo field1=90 1678675992000000000");
+
+        let params = WriteParams::default();
+        let req = WriteRequest::new(lines.clone(), params, Precision::Nanosecond);
+        assert_eq!(req.precision, Precision::Nanosecond);
+
+        let params = WriteParams {
+            db: "public".to_string(),
+            precision: Some("s".to_string()),
+        };
+        let req = WriteRequest::new(lines.clone(), params, Precision::Nanosecond);
+        assert_eq!(req.precision, Precision::Second);
+
+        let params = WriteV2Params::default();
+        let req = WriteRequest::new_v2(lines.clone(), params);
+        assert_eq!(req.precision, Precision::Nanosecond);
+        let pb_req = convert_write_request(req).unwrap();
+        assert_eq!(
+            pb_req[0].entries[0].field_groups[0].timestamp,
+            1678675992000
+        );
+
+        let params = WriteV2Params {
+            precision: "us".to_string(),
+            ..Default::default()
+        };
+        let req = WriteRequest::new_v2(lines, params);
+        assert_eq!(req.precision, Precision::Microsecond);
+    }
+
     #[test]
     fn test_influxql_result() {
         let record_schema = build_test_record_schema();
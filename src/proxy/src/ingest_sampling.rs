@@ -0,0 +1,159 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sample the rows written to the configured tables, only 1/N of the series
+//! are kept, so the extremely high-volume tables, e.g. the debug metrics, can
+//! keep the signal without paying the full storage cost.
+//!
+//! The series are kept or dropped deterministically by the hash of their tags,
+//! so the kept series are complete. The counts and sums over the sampled
+//! tables should be scaled by the `sample_rate` udf in the queries, e.g.
+//! `SELECT count(*) * sample_rate('t') FROM t`, while the averages, minimums
+//! and maximums of the kept series need no scaling.
+
+use std::collections::HashMap;
+
+use horaedbproto::storage::{WriteRequest, WriteSeriesEntry};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Sample rate of the tables, 1/N of the series of the table are kept if
+    /// the rate is N, and the rates less than 2 mean no sampling.
+    pub tables: HashMap<String, u64>,
+}
+
+pub struct IngestSampler {
+    rates: HashMap<String, u64>,
+}
+
+impl IngestSampler {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            rates: config
+                .tables
+                .iter()
+                .filter(|(_, rate)| **rate > 1)
+                .map(|(table, rate)| (table.clone(), *rate))
+                .collect(),
+        }
+    }
+
+    /// Drop the series not sampled from the request, returns the number of the
+    /// dropped series.
+    pub fn sample(&self, req: &mut WriteRequest) -> usize {
+        if self.rates.is_empty() {
+            return 0;
+        }
+
+        let mut dropped = 0;
+        for table_req in &mut req.table_requests {
+            let rate = match self.rates.get(&table_req.table) {
+                Some(rate) => *rate,
+                None => continue,
+            };
+
+            let num_entries = table_req.entries.len();
+            let tag_names = &table_req.tag_names;
+            table_req
+                .entries
+                .retain(|entry| series_hash(tag_names, entry) % rate == 0);
+            dropped += num_entries - table_req.entries.len();
+        }
+
+        dropped
+    }
+}
+
+/// Hash of the series, which is independent of the order of the tags.
+pub(crate) fn series_hash(tag_names: &[String], entry: &WriteSeriesEntry) -> u64 {
+    let mut tags: Vec<_> = entry
+        .tags
+        .iter()
+        .filter_map(|tag| {
+            tag_names
+                .get(tag.name_index as usize)
+                .map(|name| (name.as_str(), tag))
+        })
+        .collect();
+    tags.sort_unstable_by_key(|(name, _)| *name);
+
+    let mut buf = Vec::new();
+    for (name, tag) in tags {
+        buf.extend_from_slice(name.as_bytes());
+        if let Some(value) = &tag.value {
+            buf.extend_from_slice(&value.encode_to_vec());
+        }
+    }
+
+    hash_ext::hash64(&buf[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use horaedbproto::storage::{value, Tag, Value, WriteTableRequest};
+
+    use super::*;
+
+    fn write_request(table: &str, num_series: usize) -> WriteRequest {
+        let entries = (0..num_series)
+            .map(|i| WriteSeriesEntry {
+                tags: vec![Tag {
+                    name_index: 0,
+                    value: Some(Value {
+                        value: Some(value::Value::StringValue(format!("host-{i}"))),
+                    }),
+                }],
+                field_groups: vec![],
+            })
+            .collect();
+
+        WriteRequest {
+            context: None,
+            table_requests: vec![WriteTableRequest {
+                table: table.to_string(),
+                tag_names: vec!["host".to_string()],
+                field_names: vec![],
+                entries,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let sampler = IngestSampler::new(&Config {
+            tables: HashMap::from([("t1".to_string(), 4), ("t2".to_string(), 1)]),
+        });
+
+        let mut req = write_request("t2", 100);
+        assert_eq!(sampler.sample(&mut req), 0);
+        assert_eq!(req.table_requests[0].entries.len(), 100);
+
+        let mut req = write_request("t1", 1000);
+        let dropped = sampler.sample(&mut req);
+        let kept = req.table_requests[0].entries.len();
+        assert_eq!(dropped + kept, 1000);
+        assert!(kept > 100 && kept < 500);
+
+        // The same series are always kept.
+        let mut req2 = write_request("t1", 1000);
+        sampler.sample(&mut req2);
+        assert_eq!(req, req2);
+    }
+}
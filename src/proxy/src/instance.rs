@@ -17,17 +17,28 @@
 
 //! Instance contains shared states of service
 
-use std::sync::{atomic::AtomicU64, Arc};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64},
+    Arc,
+};
 
 use catalog::manager::ManagerRef;
 use df_operator::registry::FunctionRegistryRef;
-use interpreters::table_manipulator::TableManipulatorRef;
+use interpreters::{
+    alter_system::SystemConfigManagerRef, continuous_query::ContinuousQueryManagerRef,
+    function::WasmUdfManagerRef, offload::ResultOffloaderRef, query_tracker::QueryTrackerRef,
+    resync::ResyncRequestsRef, source::SourceManagerRef, table_manipulator::TableManipulatorRef,
+};
 use query_engine::QueryEngineRef;
 use query_frontend::config::DynamicConfig as FrontendDynamicConfig;
 use runtime::PriorityRuntime;
 use table_engine::{engine::TableEngineRef, remote::RemoteEngineRef};
 
-use crate::limiter::Limiter;
+use crate::{
+    admission::AdmissionController, auth::AuthenticatorRef, drain::Drainer, limiter::Limiter,
+    maintenance::TableMaintenance, replica_check::ReplicaCheckerRef, schema_cache::SchemaCache,
+    schema_events::SchemaEventBus, write_tee::WriteTeeRef,
+};
 
 /// A cluster instance. Usually there is only one instance per cluster
 pub struct Instance {
@@ -39,10 +50,48 @@ pub struct Instance {
     // User defined functions registry.
     // TODO: remove it, it should be part of query engine...
     pub function_registry: FunctionRegistryRef,
+    /// Manager of the udfs loaded from the wasm modules, `None` if the udf
+    /// dir is not configured
+    pub wasm_udf_manager: Option<WasmUdfManagerRef>,
     pub limiter: Limiter,
+    /// Quotas of the tenants and the load shedding
+    pub admission: AdmissionController,
     pub table_manipulator: TableManipulatorRef,
     pub remote_engine_ref: RemoteEngineRef,
     pub dyn_config: DynamicConfig,
+    /// Offloader of the query results, `None` if result offloading is disabled
+    pub result_offloader: Option<ResultOffloaderRef>,
+    /// Tracker of the running queries
+    pub query_tracker: QueryTrackerRef,
+    /// Consumers of the table changes to re-sync, requested by `RESYNC TABLE`
+    pub resync_requests: ResyncRequestsRef,
+    /// Manager of the ingestion sources, `None` if sources are disabled
+    pub source_manager: Option<SourceManagerRef>,
+    /// Manager of the server config, `None` if the config can't be changed
+    pub system_config_manager: Option<SystemConfigManagerRef>,
+    /// Manager of the continuous queries, `None` if they are disabled
+    pub continuous_query_manager: Option<ContinuousQueryManagerRef>,
+    /// Checker of the consistency of the replicas, `None` if it's disabled
+    pub replica_checker: Option<ReplicaCheckerRef>,
+    /// Sql http endpoints allowed to be the targets of the schema diff
+    pub schema_diff_targets: Vec<String>,
+    /// Mirror of the writes of the selected tables, `None` if it's disabled
+    pub write_tee: Option<WriteTeeRef>,
+    /// Authenticator of the requests, `None` if the authentication is
+    /// disabled
+    pub authenticator: Option<AuthenticatorRef>,
+    /// Tables under maintenance
+    pub maintenance: TableMaintenance,
+    /// Notify the subscribers about the changes of the tables
+    pub schema_events: SchemaEventBus,
+    /// Cache of the tables looked up by the writes
+    pub schema_cache: SchemaCache,
+    /// Reject the writes and DDLs if set, the queries are still served
+    pub read_only: AtomicBool,
+    /// Serve the queries only, and the read-only mode can't be switched off
+    pub query_only: bool,
+    /// Tracker of the in-flight requests drained during the graceful shutdown
+    pub drainer: Drainer,
 }
 
 /// A reference counted instance pointer
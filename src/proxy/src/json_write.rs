@@ -0,0 +1,1117 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Ingestion of the arbitrary json documents by the mappings of the tables.
+//!
+//! The mapping of a table extracts the columns from the documents by their
+//! JSONPaths, coerces them to the column types and fills the defaults of the
+//! missing ones, so the producers can write their events without reshaping
+//! them. The mappings are validated against the schemas of the tables and
+//! compiled once when they are loaded from the config or registered by the
+//! admin API, and the types of the columns not given are taken from the tables.
+//!
+//! The mappings registered by the admin API are persisted in a state table,
+//! and every node reloads them periodically, so they survive the restarts and
+//! the documents can be written to any node. The mappings in the config can't
+//! be changed by the admin API.
+//!
+//! Only the JSONPaths addressing a single value are supported, eg:
+//! `$.device.id`, `$.readings[0]` and `$['key with spaces']`.
+//!
+//! The documents are written by the http API `POST /write/json?table={table}`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex, OnceLock, RwLock, Weak},
+};
+
+use catalog::consts::DEFAULT_SCHEMA;
+use common_types::datum::DatumKind;
+use generic_error::BoxError;
+use horaedbproto::storage::{
+    value, RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest,
+};
+use http::StatusCode;
+use interpreters::interpreter::Output;
+use logger::{debug, error, info, warn};
+use runtime::{JoinHandle, RuntimeRef};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use snafu::{ensure, OptionExt, ResultExt};
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
+use time_ext::ReadableDuration;
+use tokio::sync::oneshot;
+
+use crate::{
+    context::RequestContext,
+    error::{ErrNoCause, ErrWithCause, Internal, InternalNoCause, Result},
+    http::sql::convert_sql_response_to_output,
+    read::SqlResponse,
+    schema_registry::types::{convert_records, Record, WriteParams},
+    util::{quote_ident, quote_string},
+    Context, Proxy,
+};
+
+/// Name of the timestamp in the mapped records, which can't be a column name.
+const TIMESTAMP_FIELD: &str = "__timestamp";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Mappings loaded on start, more can be registered by the admin API.
+    pub mappings: Vec<MappingSpec>,
+    /// Table in the default schema to persist the mappings registered by the
+    /// admin API.
+    pub state_table: String,
+    /// Interval to reload the mappings, so the mappings changed on the other
+    /// nodes are visible.
+    pub refresh_interval: ReadableDuration,
+    /// Timeout of loading, saving and validating the mappings.
+    pub timeout: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            mappings: Vec::new(),
+            state_table: "__json_mappings".to_string(),
+            refresh_interval: ReadableDuration::secs(30),
+            timeout: ReadableDuration::secs(30),
+        }
+    }
+}
+
+/// Mapping of the json documents to the rows of a table.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[schemars(rename = "JsonMapping")]
+pub struct MappingSpec {
+    /// Schema of the table, the default schema if empty.
+    #[serde(default)]
+    pub schema: String,
+    pub table: String,
+    /// Path of the timestamp in milliseconds.
+    pub timestamp: String,
+    pub columns: Vec<ColumnSpec>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[schemars(rename = "JsonColumn")]
+pub struct ColumnSpec {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub tag: bool,
+    /// Type the value is coerced to. If not set, the tags are coerced to
+    /// strings, and the numbers of the fields to doubles so the type of a
+    /// column won't change across the documents.
+    #[serde(default)]
+    pub coerce: Option<CoerceType>,
+    /// Value of the column missing in the document, or the column is skipped.
+    #[serde(default)]
+    pub default: Option<JsonValue>,
+    /// Reject the document missing the column which has no default.
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CoerceType {
+    String,
+    Double,
+    Int64,
+    Uint64,
+    Bool,
+}
+
+impl CoerceType {
+    /// The type written into the column of the kind, `None` if the kind can't
+    /// be written by the json documents.
+    fn of_column(kind: DatumKind) -> Option<Self> {
+        match kind {
+            DatumKind::String => Some(Self::String),
+            DatumKind::Double => Some(Self::Double),
+            DatumKind::Int64 => Some(Self::Int64),
+            DatumKind::UInt64 => Some(Self::Uint64),
+            DatumKind::Boolean => Some(Self::Bool),
+            _ => None,
+        }
+    }
+}
+
+/// Column of the table described by `DESCRIBE`.
+#[derive(Debug, Clone)]
+struct TableColumn {
+    name: String,
+    kind: DatumKind,
+    is_tag: bool,
+}
+
+/// Check the mapping against the columns of the table, and the types of the
+/// mapped columns not given are set to the ones of the table columns.
+fn resolve_mapping(mut spec: MappingSpec, table_columns: &[TableColumn]) -> Result<MappingSpec> {
+    for column in &mut spec.columns {
+        let invalid = |msg: String| ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!(
+                "Invalid json mapping, table:{}, column:{}, {msg}",
+                spec.table, column.name
+            ),
+        };
+        let table_column = table_columns
+            .iter()
+            .find(|v| v.name == column.name)
+            .with_context(|| invalid("column not found in table".to_string()))?;
+        ensure!(
+            table_column.is_tag == column.tag,
+            invalid(format!("tag mismatch, is_tag:{}", table_column.is_tag))
+        );
+        let expected = CoerceType::of_column(table_column.kind)
+            .with_context(|| invalid(format!("unsupported column type:{}", table_column.kind)))?;
+        ensure!(
+            column.coerce.unwrap_or(expected) == expected,
+            invalid(format!(
+                "coerce mismatch, coerce:{:?}, column type:{}",
+                column.coerce, table_column.kind
+            ))
+        );
+        column.coerce = Some(expected);
+    }
+
+    Ok(spec)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// JSONPath addressing a single value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath(Vec<PathSegment>);
+
+impl JsonPath {
+    pub fn parse(path: &str) -> std::result::Result<Self, String> {
+        let mut rest = path
+            .strip_prefix('$')
+            .ok_or_else(|| "path must start with $".to_string())?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(v) = rest.strip_prefix('.') {
+                let end = v.find(['.', '[']).unwrap_or(v.len());
+                if end == 0 {
+                    return Err("empty key after .".to_string());
+                }
+                segments.push(PathSegment::Key(v[..end].to_string()));
+                rest = &v[end..];
+            } else if let Some(v) = rest.strip_prefix('[') {
+                let end = v.find(']').ok_or_else(|| "unclosed [".to_string())?;
+                let inner = &v[..end];
+                let quoted = ['\'', '"'].iter().find_map(|quote| {
+                    inner
+                        .strip_prefix(*quote)
+                        .and_then(|inner| inner.strip_suffix(*quote))
+                });
+                let segment = match quoted {
+                    Some(key) => PathSegment::Key(key.to_string()),
+                    None => PathSegment::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| format!("invalid index, index:{inner}"))?,
+                    ),
+                };
+                segments.push(segment);
+                rest = &v[end + 1..];
+            } else {
+                return Err(format!("unexpected path, rest:{rest}"));
+            }
+        }
+
+        Ok(Self(segments))
+    }
+
+    /// Get the value of the path, `None` if it's missing or null.
+    pub fn get<'a>(&self, mut value: &'a JsonValue) -> Option<&'a JsonValue> {
+        for segment in &self.0 {
+            value = match (segment, value) {
+                (PathSegment::Key(key), JsonValue::Object(object)) => object.get(key)?,
+                (PathSegment::Index(idx), JsonValue::Array(array)) => array.get(*idx)?,
+                _ => return None,
+            };
+        }
+
+        (!value.is_null()).then_some(value)
+    }
+}
+
+fn coerce(value: &JsonValue, coerce: CoerceType) -> Option<value::Value> {
+    let coerced = match (coerce, value) {
+        (CoerceType::String, JsonValue::String(v)) => value::Value::StringValue(v.clone()),
+        (CoerceType::String, v) => value::Value::StringValue(v.to_string()),
+        (CoerceType::Double, JsonValue::Number(v)) => value::Value::Float64Value(v.as_f64()?),
+        (CoerceType::Double, JsonValue::String(v)) => {
+            value::Value::Float64Value(v.trim().parse().ok()?)
+        }
+        (CoerceType::Int64, JsonValue::Number(v)) => value::Value::Int64Value(v.as_i64()?),
+        (CoerceType::Int64, JsonValue::String(v)) => {
+            value::Value::Int64Value(v.trim().parse().ok()?)
+        }
+        (CoerceType::Uint64, JsonValue::Number(v)) => value::Value::Uint64Value(v.as_u64()?),
+        (CoerceType::Uint64, JsonValue::String(v)) => {
+            value::Value::Uint64Value(v.trim().parse().ok()?)
+        }
+        (CoerceType::Bool, JsonValue::Bool(v)) => value::Value::BoolValue(*v),
+        (CoerceType::Bool, JsonValue::String(v)) => value::Value::BoolValue(v.trim().parse().ok()?),
+        _ => return None,
+    };
+
+    Some(coerced)
+}
+
+/// Coerce the value by the json type if the type of the column is not set.
+fn coerce_auto(value: &JsonValue) -> value::Value {
+    match value {
+        JsonValue::Number(v) => value::Value::Float64Value(v.as_f64().unwrap_or_default()),
+        JsonValue::Bool(v) => value::Value::BoolValue(*v),
+        JsonValue::String(v) => value::Value::StringValue(v.clone()),
+        v => value::Value::StringValue(v.to_string()),
+    }
+}
+
+#[derive(Debug)]
+struct CompiledColumn {
+    name: String,
+    path: JsonPath,
+    coerce: Option<CoerceType>,
+    default: Option<value::Value>,
+    required: bool,
+}
+
+impl CompiledColumn {
+    fn coerce(&self, value: &JsonValue) -> Option<value::Value> {
+        match self.coerce {
+            Some(v) => coerce(value, v),
+            None => Some(coerce_auto(value)),
+        }
+    }
+}
+
+/// The validated mapping ready to map the documents.
+#[derive(Debug)]
+pub struct CompiledMapping {
+    spec: MappingSpec,
+    timestamp: JsonPath,
+    columns: Vec<CompiledColumn>,
+    /// Comma separated tags.
+    tags: String,
+    /// Whether the mapping is defined in the config.
+    from_config: bool,
+}
+
+impl CompiledMapping {
+    pub fn compile(mut spec: MappingSpec) -> Result<Self> {
+        let invalid = |msg: String| ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Invalid json mapping, table:{}, {msg}", spec.table),
+        };
+        ensure!(
+            !spec.table.is_empty(),
+            invalid("table is empty".to_string())
+        );
+        ensure!(!spec.columns.is_empty(), invalid("no columns".to_string()));
+        if spec.schema.is_empty() {
+            spec.schema = DEFAULT_SCHEMA.to_string();
+        }
+        let timestamp = match JsonPath::parse(&spec.timestamp) {
+            Ok(v) => v,
+            Err(e) => return invalid(format!("timestamp path:{}, err:{e}", spec.timestamp)).fail(),
+        };
+
+        let mut names = HashSet::with_capacity(spec.columns.len());
+        let mut columns = Vec::with_capacity(spec.columns.len());
+        let mut tags = Vec::new();
+        for column in &spec.columns {
+            let name = &column.name;
+            ensure!(
+                !name.is_empty() && name != TIMESTAMP_FIELD,
+                invalid(format!("invalid column name:{name}"))
+            );
+            ensure!(
+                names.insert(name.as_str()),
+                invalid(format!("duplicate column:{name}"))
+            );
+            let path = match JsonPath::parse(&column.path) {
+                Ok(v) => v,
+                Err(e) => return invalid(format!("column:{name}, err:{e}")).fail(),
+            };
+            // The tags are strings unless their types are given.
+            let coerce = match (column.coerce, column.tag) {
+                (None, true) => Some(CoerceType::String),
+                (v, _) => v,
+            };
+            let mut compiled = CompiledColumn {
+                name: name.clone(),
+                path,
+                coerce,
+                default: None,
+                required: column.required,
+            };
+            if let Some(default) = &column.default {
+                let default = compiled.coerce(default).with_context(|| {
+                    invalid(format!("column:{name}, invalid default:{default}"))
+                })?;
+                compiled.default = Some(default);
+            }
+            if column.tag {
+                tags.push(name.as_str());
+            }
+            columns.push(compiled);
+        }
+        let tags = tags.join(",");
+
+        Ok(Self {
+            spec,
+            timestamp,
+            columns,
+            tags,
+            from_config: false,
+        })
+    }
+
+    pub fn spec(&self) -> &MappingSpec {
+        &self.spec
+    }
+
+    /// Map the document into a record of the table.
+    pub fn map(&self, doc: &JsonValue) -> Result<Record> {
+        let timestamp = self
+            .timestamp
+            .get(doc)
+            .and_then(|v| match v {
+                JsonValue::Number(v) => v.as_i64(),
+                JsonValue::String(v) => v.trim().parse().ok(),
+                _ => None,
+            })
+            .with_context(|| ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!(
+                    "timestamp is missing or invalid, path:{}",
+                    self.spec.timestamp
+                ),
+            })?;
+
+        let mut record = Vec::with_capacity(self.columns.len() + 1);
+        record.push((
+            TIMESTAMP_FIELD.to_string(),
+            value::Value::TimestampValue(timestamp),
+        ));
+        for column in &self.columns {
+            let value = match column.path.get(doc) {
+                Some(v) => Some(column.coerce(v).with_context(|| ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("failed to coerce column:{}, value:{v}", column.name),
+                })?),
+                None => column.default.clone(),
+            };
+            match value {
+                Some(v) => record.push((column.name.clone(), v)),
+                None => ensure!(
+                    !column.required,
+                    ErrNoCause {
+                        code: StatusCode::BAD_REQUEST,
+                        msg: format!("required column is missing, column:{}", column.name),
+                    }
+                ),
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+pub type JsonMappingsRef = Arc<JsonMappings>;
+
+struct RunningRefresh {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// The compiled mappings keyed by the schemas and the tables.
+pub struct JsonMappings {
+    config: Config,
+    runtime: RuntimeRef,
+    task_registry: TaskRegistryRef,
+    /// The proxy is set after it is built, as the proxy holds the mappings.
+    proxy: OnceLock<Weak<Proxy>>,
+    mappings: RwLock<BTreeMap<(String, String), Arc<CompiledMapping>>>,
+    refresh: Mutex<Option<RunningRefresh>>,
+}
+
+impl JsonMappings {
+    pub fn new(config: Config, runtime: RuntimeRef, task_registry: TaskRegistryRef) -> Self {
+        Self {
+            config,
+            runtime,
+            task_registry,
+            proxy: OnceLock::new(),
+            mappings: RwLock::new(BTreeMap::new()),
+            refresh: Mutex::new(None),
+        }
+    }
+
+    pub fn set_proxy(&self, proxy: Weak<Proxy>) {
+        if self.proxy.set(proxy).is_err() {
+            warn!("Proxy of the json mappings is already set");
+        }
+    }
+
+    /// Load the mappings and reload them periodically.
+    pub async fn open(self: &Arc<Self>) -> Result<()> {
+        let proxy = self.proxy()?;
+        create_state_table(&proxy, &self.config).await?;
+        self.reload(&proxy).await?;
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let spec = TaskSpec::new(TaskKind::JsonMappingRefresh, "json_mappings".to_string());
+        let refresh = refresh_mappings(Arc::downgrade(self), stop_rx);
+        let handle = self
+            .task_registry
+            .spawn_tracked(&self.runtime, spec, refresh);
+
+        let running = RunningRefresh { stop_tx, handle };
+        if let Some(old) = self.refresh.lock().unwrap().replace(running) {
+            old.handle.abort();
+        }
+
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        let running = self.refresh.lock().unwrap().take();
+        if let Some(running) = running {
+            let _ = running.stop_tx.send(());
+            if running.handle.await.is_err() {
+                warn!("Json mapping refresh task is aborted");
+            }
+        }
+    }
+
+    fn proxy(&self) -> Result<Arc<Proxy>> {
+        self.proxy
+            .get()
+            .and_then(|proxy| proxy.upgrade())
+            .context(InternalNoCause {
+                msg: "proxy of the json mappings is not available",
+            })
+    }
+
+    /// Replace the mappings with the ones in the config and the persisted
+    /// ones. The mappings in the config are validated every time as their
+    /// tables may be created or altered later, and the invalid ones are
+    /// skipped.
+    async fn reload(&self, proxy: &Proxy) -> Result<()> {
+        let mut loaded = BTreeMap::new();
+        for spec in &self.config.mappings {
+            let result = match self.validate(proxy, spec.clone()).await {
+                Ok(spec) => CompiledMapping::compile(spec),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(mut mapping) => {
+                    mapping.from_config = true;
+                    let key = (mapping.spec.schema.clone(), mapping.spec.table.clone());
+                    loaded.insert(key, Arc::new(mapping));
+                }
+                Err(e) => warn!("Skip invalid json mapping, table:{}, err:{e}", spec.table),
+            }
+        }
+
+        let sql = format!(
+            "SELECT schema_name, table_name, definition, dropped FROM {}",
+            self.config.state_table
+        );
+        let output = proxy
+            .fetch_sql_query_output(
+                &Context::new(Some(self.config.timeout.0), None),
+                proxy.instance.catalog_manager.default_schema_name(),
+                &sql,
+                false,
+                false,
+            )
+            .await?;
+        let Output::Records(batches) = output else {
+            return InternalNoCause {
+                msg: "unexpected output of loading json mappings",
+            }
+            .fail();
+        };
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                if batch.column(3).datum_view(row).as_bool() == Some(true) {
+                    continue;
+                }
+                let Some(definition) = batch.column(2).datum_view(row).into_str() else {
+                    continue;
+                };
+                // The persisted mappings have been validated when registered.
+                let mapping = serde_json::from_str::<MappingSpec>(definition)
+                    .box_err()
+                    .context(Internal {
+                        msg: "failed to decode json mapping",
+                    })
+                    .and_then(CompiledMapping::compile);
+                let mapping = match mapping {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("Failed to load json mapping, definition:{definition}, err:{e}");
+                        continue;
+                    }
+                };
+                let key = (mapping.spec.schema.clone(), mapping.spec.table.clone());
+                if loaded.contains_key(&key) {
+                    warn!(
+                        "Ignore the persisted json mapping defined in the config, schema:{}, \
+                         table:{}",
+                        key.0, key.1
+                    );
+                    continue;
+                }
+                loaded.insert(key, Arc::new(mapping));
+            }
+        }
+
+        *self.mappings.write().unwrap() = loaded;
+
+        Ok(())
+    }
+
+    /// Validate the mapping against the schema of its table, which is described
+    /// by the node owning the table.
+    async fn validate(&self, proxy: &Proxy, mut spec: MappingSpec) -> Result<MappingSpec> {
+        if spec.schema.is_empty() {
+            spec.schema = DEFAULT_SCHEMA.to_string();
+        }
+        let columns = describe_table(proxy, &self.config, &spec.schema, &spec.table).await?;
+
+        resolve_mapping(spec, &columns)
+    }
+
+    fn ensure_not_from_config(&self, schema: &str, table: &str) -> Result<()> {
+        let from_config = self
+            .get(schema, table)
+            .is_some_and(|mapping| mapping.from_config);
+        ensure!(
+            !from_config,
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!(
+                    "Json mapping defined in the config can't be changed, schema:{schema}, \
+                     table:{table}"
+                ),
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Register the mapping, which replaces the old one of the table, and the
+    /// validated mapping is returned.
+    pub async fn register(&self, spec: MappingSpec) -> Result<MappingSpec> {
+        let proxy = self.proxy()?;
+        let spec = self.validate(&proxy, spec).await?;
+        let mapping = CompiledMapping::compile(spec)?;
+        let (schema, table) = (&mapping.spec.schema, &mapping.spec.table);
+        self.ensure_not_from_config(schema, table)?;
+
+        create_state_table(&proxy, &self.config).await?;
+        save_mapping(&proxy, &self.config, schema, table, Some(&mapping.spec)).await?;
+        info!("Json mapping is registered, schema:{schema}, table:{table}");
+
+        let spec = mapping.spec.clone();
+        let key = (spec.schema.clone(), spec.table.clone());
+        self.mappings
+            .write()
+            .unwrap()
+            .insert(key, Arc::new(mapping));
+
+        Ok(spec)
+    }
+
+    /// Remove the mapping of the table, returns whether it exists.
+    pub async fn remove(&self, schema: &str, table: &str) -> Result<bool> {
+        if self.get(schema, table).is_none() {
+            return Ok(false);
+        }
+        self.ensure_not_from_config(schema, table)?;
+
+        let proxy = self.proxy()?;
+        save_mapping(&proxy, &self.config, schema, table, None).await?;
+        info!("Json mapping is removed, schema:{schema}, table:{table}");
+
+        let key = (schema.to_string(), table.to_string());
+        Ok(self.mappings.write().unwrap().remove(&key).is_some())
+    }
+
+    pub fn get(&self, schema: &str, table: &str) -> Option<Arc<CompiledMapping>> {
+        let key = (schema.to_string(), table.to_string());
+        self.mappings.read().unwrap().get(&key).cloned()
+    }
+
+    pub fn list(&self) -> Vec<MappingSpec> {
+        self.mappings
+            .read()
+            .unwrap()
+            .values()
+            .map(|v| v.spec.clone())
+            .collect()
+    }
+}
+
+/// Reload the mappings periodically until stopped or the mappings are
+/// dropped.
+async fn refresh_mappings(weak: Weak<JsonMappings>, mut stop_rx: oneshot::Receiver<()>) {
+    loop {
+        let Some(interval) = weak.upgrade().map(|v| v.config.refresh_interval.0) else {
+            return;
+        };
+        tokio::select! {
+            _ = &mut stop_rx => return,
+            _ = tokio::time::sleep(interval) => {},
+        }
+
+        let Some(mappings) = weak.upgrade() else {
+            return;
+        };
+        let result = match mappings.proxy() {
+            Ok(proxy) => mappings.reload(&proxy).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to reload json mappings, err:{e}");
+        }
+    }
+}
+
+/// Describe the table by the node owning it, as the table may not be on this
+/// node in the cluster mode.
+async fn describe_table(
+    proxy: &Proxy,
+    config: &Config,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<TableColumn>> {
+    let ctx = Context::new(Some(config.timeout.0), None);
+    let sql = format!("DESCRIBE TABLE {}", quote_ident(table));
+    let output = match proxy.handle_sql(&ctx, schema, &sql, false, false).await? {
+        SqlResponse::Forwarded(resp) => convert_sql_response_to_output(resp)?,
+        SqlResponse::Local(output) => output,
+    };
+    let Output::Records(batches) = output else {
+        return InternalNoCause {
+            msg: "unexpected output of describing table",
+        }
+        .fail();
+    };
+
+    let mut columns = Vec::new();
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let name = batch.column(0).datum_view(row).into_str();
+            let kind = batch.column(1).datum_view(row).into_str().and_then(|kind| {
+                DatumKind::VALUES
+                    .into_iter()
+                    .find(|v| v.to_string() == kind)
+            });
+            let (Some(name), Some(kind)) = (name, kind) else {
+                continue;
+            };
+            columns.push(TableColumn {
+                name: name.to_string(),
+                kind,
+                is_tag: batch.column(4).datum_view(row).as_bool() == Some(true),
+            });
+        }
+    }
+
+    Ok(columns)
+}
+
+async fn create_state_table(proxy: &Proxy, config: &Config) -> Result<()> {
+    let sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (schema_name string TAG, table_name string TAG, \
+         ts timestamp NOT NULL, definition string, dropped boolean, TIMESTAMP KEY(ts)) \
+         ENGINE=Analytic WITH(enable_ttl='false', update_mode='OVERWRITE')",
+        config.state_table
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+/// Every mapping has only one row in the state table, which is overwritten by
+/// the latest definition as the timestamp is always 0. The mapping is marked
+/// as dropped if the definition is `None`.
+async fn save_mapping(
+    proxy: &Proxy,
+    config: &Config,
+    schema: &str,
+    table: &str,
+    spec: Option<&MappingSpec>,
+) -> Result<()> {
+    let sql = match spec {
+        Some(spec) => {
+            let definition = serde_json::to_string(spec).box_err().context(Internal {
+                msg: "failed to encode json mapping",
+            })?;
+            format!(
+                "INSERT INTO {} (schema_name, table_name, ts, definition, dropped) \
+                 VALUES ({}, {}, 0, {}, false)",
+                config.state_table,
+                quote_string(schema),
+                quote_string(table),
+                quote_string(&definition),
+            )
+        }
+        None => format!(
+            "INSERT INTO {} (schema_name, table_name, ts, dropped) VALUES ({}, {}, 0, true)",
+            config.state_table,
+            quote_string(schema),
+            quote_string(table),
+        ),
+    };
+    execute_sql(proxy, config, &sql).await
+}
+
+async fn execute_sql(proxy: &Proxy, config: &Config, sql: &str) -> Result<()> {
+    proxy
+        .fetch_sql_query_output(
+            &Context::new(Some(config.timeout.0), None),
+            proxy.instance.catalog_manager.default_schema_name(),
+            sql,
+            false,
+            false,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Parse the documents, which may be a json object, an array of objects, or
+/// the objects separated by whitespaces like newlines.
+fn parse_documents(body: &[u8]) -> Result<Vec<JsonValue>> {
+    let mut docs = Vec::new();
+    for value in serde_json::Deserializer::from_slice(body).into_iter::<JsonValue>() {
+        let value = value.box_err().context(ErrWithCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "invalid json documents",
+        })?;
+        match value {
+            JsonValue::Array(values) => docs.extend(values),
+            v => docs.push(v),
+        }
+    }
+
+    Ok(docs)
+}
+
+/// Query string parameters of the http write api.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct JsonWriteParams {
+    pub table: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct JsonWriteResponse {
+    /// Number of the written rows.
+    pub success: u32,
+}
+
+impl Proxy {
+    pub async fn handle_http_json_write(
+        &self,
+        ctx: RequestContext,
+        table: &str,
+        documents: &[u8],
+    ) -> Result<JsonWriteResponse> {
+        let proxy_ctx = Context::new(ctx.timeout, None).with_auth_user(ctx.auth_user.clone());
+        let success = self
+            .write_json_documents(proxy_ctx, &ctx.schema, table, documents)
+            .await?;
+
+        Ok(JsonWriteResponse { success })
+    }
+
+    /// Write the json documents to the table by its mapping, and returns the
+    /// number of the written rows.
+    async fn write_json_documents(
+        &self,
+        ctx: Context,
+        schema: &str,
+        table: &str,
+        documents: &[u8],
+    ) -> Result<u32> {
+        let mapping = self
+            .json_mappings
+            .get(schema, table)
+            .with_context(|| ErrNoCause {
+                code: StatusCode::NOT_FOUND,
+                msg: format!("No json mapping of table, schema:{schema}, table:{table}"),
+            })?;
+        let records = parse_documents(documents)?
+            .iter()
+            .map(|doc| mapping.map(doc))
+            .collect::<Result<Vec<_>>>()?;
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        let num_rows = records.len();
+        let params = WriteParams {
+            table: Some(table.to_string()),
+            timestamp_field: TIMESTAMP_FIELD.to_string(),
+            tags: mapping.tags.clone(),
+            ..Default::default()
+        };
+        let req = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: schema.to_string(),
+            }),
+            table_requests: vec![convert_records(table.to_string(), &params, records)?],
+        };
+        let resp = self.handle_write_internal(ctx, req).await?;
+        ensure!(
+            resp.failed == 0,
+            ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!(
+                    "Failed to write json documents, rows:{num_rows}, failed:{}",
+                    resp.failed
+                ),
+            }
+        );
+        debug!("Json documents are written, schema:{schema}, table:{table}, rows:{num_rows}");
+
+        Ok(resp.success)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn column(name: &str, path: &str) -> ColumnSpec {
+        ColumnSpec {
+            name: name.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_json_path() {
+        let doc = json!({"a": {"b c": [1, {"d": "x"}]}, "n": null});
+        let cases = [
+            ("$.a['b c'][0]", Some(json!(1))),
+            ("$.a[\"b c\"][1].d", Some(json!("x"))),
+            ("$.a.missing", None),
+            ("$.a['b c'][5]", None),
+            ("$.n", None),
+        ];
+        for (path, expected) in cases {
+            let path = JsonPath::parse(path).unwrap();
+            assert_eq!(expected.as_ref(), path.get(&doc));
+        }
+
+        for path in ["a.b", "$.", "$[x]", "$['a'", "$a"] {
+            assert!(JsonPath::parse(path).is_err(), "path:{path}");
+        }
+    }
+
+    #[test]
+    fn test_compile_mapping() {
+        let spec = MappingSpec {
+            table: "t".to_string(),
+            timestamp: "$.ts".to_string(),
+            columns: vec![column("a", "$.a"), column("a", "$.b")],
+            ..Default::default()
+        };
+        assert!(CompiledMapping::compile(spec.clone()).is_err());
+
+        let mut invalid_default = spec.clone();
+        invalid_default.columns = vec![ColumnSpec {
+            coerce: Some(CoerceType::Int64),
+            default: Some(json!("x")),
+            ..column("a", "$.a")
+        }];
+        assert!(CompiledMapping::compile(invalid_default).is_err());
+
+        let mut invalid_path = spec;
+        invalid_path.columns = vec![column("a", "a")];
+        assert!(CompiledMapping::compile(invalid_path).is_err());
+    }
+
+    #[test]
+    fn test_resolve_mapping() {
+        let table_column = |name: &str, kind, is_tag| TableColumn {
+            name: name.to_string(),
+            kind,
+            is_tag,
+        };
+        let table_columns = [
+            table_column("host", DatumKind::String, true),
+            table_column("value", DatumKind::Double, false),
+            table_column("code", DatumKind::Int64, false),
+            table_column("ts", DatumKind::Timestamp, false),
+        ];
+        let spec = MappingSpec {
+            table: "t".to_string(),
+            timestamp: "$.ts".to_string(),
+            columns: vec![
+                ColumnSpec {
+                    tag: true,
+                    ..column("host", "$.host")
+                },
+                column("value", "$.value"),
+                ColumnSpec {
+                    coerce: Some(CoerceType::Int64),
+                    ..column("code", "$.code")
+                },
+            ],
+            ..Default::default()
+        };
+        let resolved = resolve_mapping(spec.clone(), &table_columns).unwrap();
+        let coerces: Vec<_> = resolved.columns.iter().map(|v| v.coerce).collect();
+        assert_eq!(
+            vec![
+                Some(CoerceType::String),
+                Some(CoerceType::Double),
+                Some(CoerceType::Int64)
+            ],
+            coerces
+        );
+
+        let invalid_columns = [
+            // Not in the table.
+            column("missing", "$.missing"),
+            // Not a tag in the table.
+            ColumnSpec {
+                tag: true,
+                ..column("value", "$.value")
+            },
+            // The type mismatches.
+            ColumnSpec {
+                coerce: Some(CoerceType::String),
+                ..column("code", "$.code")
+            },
+            // The type can't be written.
+            column("ts", "$.ts"),
+        ];
+        for invalid_column in invalid_columns {
+            let mut invalid = spec.clone();
+            invalid.columns.push(invalid_column.clone());
+            assert!(
+                resolve_mapping(invalid, &table_columns).is_err(),
+                "column:{invalid_column:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_map_documents() {
+        let spec = MappingSpec {
+            table: "t".to_string(),
+            timestamp: "$.time".to_string(),
+            columns: vec![
+                ColumnSpec {
+                    tag: true,
+                    ..column("host", "$.device.id")
+                },
+                column("value", "$.readings[0]"),
+                ColumnSpec {
+                    coerce: Some(CoerceType::Int64),
+                    ..column("code", "$.code")
+                },
+                ColumnSpec {
+                    default: Some(json!("unknown")),
+                    ..column("region", "$.region")
+                },
+                ColumnSpec {
+                    required: true,
+                    ..column("ok", "$.ok")
+                },
+            ],
+            ..Default::default()
+        };
+        let mapping = CompiledMapping::compile(spec).unwrap();
+        assert_eq!(DEFAULT_SCHEMA, mapping.spec().schema);
+        assert_eq!("host", mapping.tags);
+
+        let docs = parse_documents(
+            br#"{"time": 1000, "device": {"id": 7}, "readings": [1], "code": "3", "ok": true}
+            [{"time": "2000", "device": {"id": "a"}, "region": "r", "ok": false}]"#,
+        )
+        .unwrap();
+        assert_eq!(2, docs.len());
+        assert_eq!(
+            vec![
+                (
+                    TIMESTAMP_FIELD.to_string(),
+                    value::Value::TimestampValue(1000)
+                ),
+                (
+                    "host".to_string(),
+                    value::Value::StringValue("7".to_string())
+                ),
+                ("value".to_string(), value::Value::Float64Value(1.0)),
+                ("code".to_string(), value::Value::Int64Value(3)),
+                (
+                    "region".to_string(),
+                    value::Value::StringValue("unknown".to_string())
+                ),
+                ("ok".to_string(), value::Value::BoolValue(true)),
+            ],
+            mapping.map(&docs[0]).unwrap()
+        );
+        assert_eq!(
+            vec![
+                (
+                    TIMESTAMP_FIELD.to_string(),
+                    value::Value::TimestampValue(2000)
+                ),
+                (
+                    "host".to_string(),
+                    value::Value::StringValue("a".to_string())
+                ),
+                (
+                    "region".to_string(),
+                    value::Value::StringValue("r".to_string())
+                ),
+                ("ok".to_string(), value::Value::BoolValue(false)),
+            ],
+            mapping.map(&docs[1]).unwrap()
+        );
+
+        // Missing the required column.
+        assert!(mapping.map(&json!({"time": 1})).is_err());
+        // Invalid code.
+        assert!(mapping
+            .map(&json!({"time": 1, "ok": true, "code": "x"}))
+            .is_err());
+    }
+}
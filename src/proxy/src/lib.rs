@@ -20,28 +20,70 @@
 
 #![feature(trait_alias)]
 
+pub mod admission;
+pub mod auth;
+pub mod circuit_breaker;
 pub mod context;
+pub mod continuous_query;
+pub mod drain;
 pub mod error;
 mod error_util;
 pub mod forward;
+pub mod graphite;
 mod grpc;
 pub mod handlers;
 pub mod hotspot;
 mod hotspot_lru;
 pub mod http;
+pub mod import;
 pub mod influxdb;
+pub mod ingest_sampling;
 pub mod instance;
+pub mod json_write;
 pub mod limiter;
-mod metrics;
+pub mod maintenance;
+pub mod metrics;
+pub mod mqtt;
 pub mod opentsdb;
 mod read;
+pub mod replica_check;
+pub mod result_limit;
+pub mod schema_cache;
 pub mod schema_config_provider;
+pub mod schema_diff;
+pub mod schema_events;
+pub mod schema_registry;
+pub mod source;
+pub mod statsd;
+pub mod stream_query;
+pub mod table_changes;
+pub mod tiering;
+pub mod user_metrics;
 mod util;
 mod write;
+pub mod write_batcher;
+pub mod write_queue;
+pub mod write_status;
+pub mod write_tee;
+pub mod write_trace;
 
 pub const FORWARDED_FROM: &str = "forwarded-from";
+/// Binary metadata key of the warnings of the grpc sql query, the value is
+/// the warnings encoded in json.
+pub const QUERY_WARNINGS_KEY: &str = "x-horaedb-warnings-bin";
+/// Key of the header/metadata to elevate the log level of a single request,
+/// the value should be `debug` or `trace`, and it's only honored for the
+/// admins.
+pub const LOG_LEVEL_KEY: &str = "x-horaedb-log-level";
+/// Binary metadata key of the status of the tables of the grpc write, see
+/// [write_status] for the details.
+pub const WRITE_STATUS_KEY: &str = "x-horaedb-write-status-bin";
+/// Key of the metadata to skip the invalid rows of the grpc write rather than
+/// rejecting the request, the value should be `true` or `false`.
+pub const PARTIAL_WRITE_KEY: &str = "x-horaedb-partial-write";
 
 use std::{
+    future::Future,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -61,11 +103,14 @@ use horaedbproto::storage::{
     PrometheusRemoteQueryResponse, Route,
 };
 use interpreters::{
-    context::Context as InterpreterContext,
+    context::{Context as InterpreterContext, ResultSender, WriteSequence},
     factory::Factory,
-    interpreter::{InterpreterPtr, Output},
+    interpreter::{self, InterpreterPtr, Output},
+    kill,
+    result_limit::ResultLimit,
+    user::UserManagerRef,
 };
-use logger::{error, info, warn};
+use logger::{error, info, warn, Level};
 use query_frontend::plan::Plan;
 use router::{endpoint::Endpoint, RouteRequest, Router};
 use serde::{Deserialize, Serialize};
@@ -73,19 +118,35 @@ use snafu::{OptionExt, ResultExt};
 use table_engine::{
     engine::{CreateTableParams, EngineRuntimes, TableState},
     partition::PartitionInfo,
+    query_warning::QueryWarnings,
     remote::model::{GetTableInfoRequest, TableIdentifier, TableInfo},
     table::{TableId, TableRef},
     PARTITION_TABLE_ENGINE_TYPE,
 };
-use tonic::{transport::Channel, IntoRequest};
+use tonic::{metadata::MetadataValue, transport::Channel, IntoRequest};
 
 use crate::{
+    auth::AuthUser,
+    circuit_breaker::CircuitBreaker,
     error::{ErrNoCause, ErrWithCause, Error, Internal, Result},
     forward::{ForwardRequest, ForwardResult, Forwarder, ForwarderRef},
+    graphite::types::Templates as GraphiteTemplates,
     hotspot::HotspotRecorder,
+    import::ImportTracker,
+    ingest_sampling::IngestSampler,
     instance::InstanceRef,
+    json_write::{JsonMappings, JsonMappingsRef},
     read::ReadRequestNotifiers,
+    result_limit::ResultLimits,
     schema_config_provider::SchemaConfigProviderRef,
+    schema_events::{SchemaEvent, SchemaEventKind},
+    schema_registry::client::{self as schema_registry_client, SchemaRegistry, SchemaRegistryRef},
+    stream_query::StreamLimiter,
+    tiering::ColdQueryRouter,
+    user_metrics::UserMetrics,
+    write_batcher::{WriteBatcher, WriteBatcherRef},
+    write_queue::WriteQueue,
+    write_trace::WriteTraceSampler,
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -117,6 +178,32 @@ pub struct Proxy {
     sub_table_access_perm: SubTableAccessPerm,
     request_notifiers: Option<ReadRequestNotifiers>,
     expensive_query_threshold: u64,
+    /// Coalesce the tiny writes, `None` if disabled
+    write_batcher: Option<WriteBatcherRef>,
+    /// Schema registry to decode the ingested rows, `None` if not configured
+    schema_registry: Option<SchemaRegistryRef>,
+    /// Templates mapping the graphite paths to the tables and tags
+    graphite_templates: GraphiteTemplates,
+    /// Sample the writes of the configured tables to trace
+    write_trace_sampler: WriteTraceSampler,
+    /// Fail fast the writes to the tables failing repeatedly, `None` if
+    /// disabled
+    write_circuit_breaker: Option<CircuitBreaker>,
+    /// Route the cold queries to the cold read nodes
+    cold_query_router: ColdQueryRouter,
+    ingest_sampler: IngestSampler,
+    /// Bound and prioritize the writes to execute, `None` if disabled
+    write_queue: Option<WriteQueue>,
+    /// Track the progress of the bulk imports
+    import_tracker: Arc<ImportTracker>,
+    /// Bound the streamed queries
+    stream_limiter: StreamLimiter,
+    /// Limits of the query results by the authenticated users
+    result_limits: ResultLimits,
+    /// Mappings of the json documents to the tables
+    json_mappings: JsonMappingsRef,
+    /// Label the metrics of the requests by the users, `None` if disabled
+    user_metrics: Option<UserMetrics>,
 }
 
 impl Proxy {
@@ -135,12 +222,29 @@ impl Proxy {
         sub_table_access_perm: SubTableAccessPerm,
         request_notifiers: Option<ReadRequestNotifiers>,
         expensive_query_threshold: u64,
+        write_batch_config: write_batcher::Config,
+        schema_registry_config: schema_registry_client::Config,
+        graphite_config: &graphite::Config,
+        write_trace_config: &write_trace::Config,
+        write_circuit_breaker_config: &circuit_breaker::Config,
+        tiering_config: &tiering::Config,
+        ingest_sampling_config: &ingest_sampling::Config,
+        write_queue_config: &write_queue::Config,
+        stream_query_config: &stream_query::Config,
+        result_limit_config: &result_limit::Config,
+        json_write_config: &json_write::Config,
+        user_metrics_config: &user_metrics::Config,
     ) -> Self {
         let forwarder = Arc::new(Forwarder::new(
             forward_config,
             router.clone(),
             local_endpoint,
         ));
+        let json_mappings = Arc::new(JsonMappings::new(
+            json_write_config.clone(),
+            engine_runtimes.default_runtime.clone(),
+            engine_runtimes.task_registry.clone(),
+        ));
 
         Self {
             router,
@@ -155,6 +259,31 @@ impl Proxy {
             sub_table_access_perm,
             request_notifiers,
             expensive_query_threshold,
+            write_batcher: write_batch_config
+                .enable
+                .then(|| Arc::new(WriteBatcher::new(write_batch_config))),
+            schema_registry: (!schema_registry_config.endpoint.is_empty())
+                .then(|| Arc::new(SchemaRegistry::new(schema_registry_config))),
+            graphite_templates: GraphiteTemplates::new(
+                &graphite_config.templates,
+                graphite_config.separator.clone(),
+            ),
+            write_trace_sampler: WriteTraceSampler::new(write_trace_config),
+            write_circuit_breaker: write_circuit_breaker_config
+                .enable
+                .then(|| CircuitBreaker::new(write_circuit_breaker_config)),
+            cold_query_router: ColdQueryRouter::new(tiering_config),
+            ingest_sampler: IngestSampler::new(ingest_sampling_config),
+            write_queue: write_queue_config
+                .enable
+                .then(|| WriteQueue::new(write_queue_config)),
+            import_tracker: Arc::new(ImportTracker::default()),
+            stream_limiter: StreamLimiter::new(stream_query_config),
+            result_limits: ResultLimits::new(result_limit_config),
+            json_mappings,
+            user_metrics: user_metrics_config
+                .enable
+                .then(|| UserMetrics::new(user_metrics_config)),
         }
     }
 
@@ -162,6 +291,19 @@ impl Proxy {
         self.instance.clone()
     }
 
+    pub fn router(&self) -> Arc<dyn Router + Send + Sync> {
+        self.router.clone()
+    }
+
+    pub fn json_mappings(&self) -> JsonMappingsRef {
+        self.json_mappings.clone()
+    }
+
+    /// Whether the request is forwarded by another node of the cluster.
+    pub fn is_internal_request<T>(&self, req: &tonic::Request<T>) -> bool {
+        self.forwarder.is_internal_request(req)
+    }
+
     fn default_catalog_name(&self) -> NameRef {
         self.instance.catalog_manager.default_catalog_name()
     }
@@ -169,13 +311,16 @@ impl Proxy {
     async fn maybe_forward_prom_remote_query(
         &self,
         metric: String,
+        auth_user: Option<&AuthUser>,
         req: PrometheusRemoteQueryRequest,
     ) -> Result<Option<ForwardResult<PrometheusRemoteQueryResponse, Error>>> {
-        let req_ctx = req.context.as_ref().unwrap();
+        let schema = req.context.as_ref().unwrap().database.clone();
+        let mut req = req.into_request();
+        auth::attach_credentials(&mut req, auth_user);
         let forward_req = ForwardRequest {
-            schema: req_ctx.database.clone(),
+            schema,
             table: metric,
-            req: req.into_request(),
+            req,
             forwarded_from: None,
         };
         let do_query = |mut client: StorageServiceClient<Channel>,
@@ -455,24 +600,93 @@ impl Proxy {
         plan: Plan,
         deadline: Option<Instant>,
     ) -> Result<Output> {
-        let interpreter =
-            self.build_interpreter(request_id, catalog, schema, plan, deadline, false)?;
-        Self::interpreter_execute_plan(interpreter, deadline).await
+        self.execute_plan_with_options(
+            request_id,
+            catalog,
+            schema,
+            plan,
+            deadline,
+            false,
+            None,
+            ResultLimit::default(),
+            QueryWarnings::default(),
+            WriteSequence::default(),
+        )
+        .await
+    }
+
+    /// Execute the query plan, and its results are limited by the limits of
+    /// the authenticated user of the request.
+    async fn execute_query_plan(
+        &self,
+        request_id: RequestId,
+        catalog: &str,
+        schema: &str,
+        plan: Plan,
+        deadline: Option<Instant>,
+        auth_user: Option<&AuthUser>,
+    ) -> Result<Output> {
+        self.execute_plan_with_options(
+            request_id,
+            catalog,
+            schema,
+            plan,
+            deadline,
+            false,
+            None,
+            self.result_limits.of_user(auth_user.map(|v| v.name())),
+            QueryWarnings::default(),
+            WriteSequence::default(),
+        )
+        .await
     }
 
-    async fn execute_plan_involving_partition_table(
+    /// Execute the plan, and the rows of the query are sent through the
+    /// `result_sender` as soon as they are produced if it is set.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_plan_with_options(
         &self,
         request_id: RequestId,
         catalog: &str,
         schema: &str,
         plan: Plan,
         deadline: Option<Instant>,
+        enable_partition_table_access: bool,
+        result_sender: Option<ResultSender>,
+        result_limit: ResultLimit,
+        warnings: QueryWarnings,
+        write_sequence: WriteSequence,
     ) -> Result<Output> {
-        let interpreter =
-            self.build_interpreter(request_id, catalog, schema, plan, deadline, true)?;
-        Self::interpreter_execute_plan(interpreter, deadline).await
+        let events = SchemaEvent::from_plan(catalog, schema, &plan);
+        let interpreter = self.build_interpreter(
+            request_id,
+            catalog,
+            schema,
+            plan,
+            deadline,
+            enable_partition_table_access,
+            result_sender,
+            result_limit,
+            warnings,
+            write_sequence,
+        )?;
+        let output = Self::interpreter_execute_plan(interpreter, deadline).await?;
+        self.notify_schema_events(events).await;
+
+        Ok(output)
     }
 
+    async fn notify_schema_events(&self, events: Vec<SchemaEvent>) {
+        for event in events {
+            if event.kind == SchemaEventKind::DropTable {
+                self.router.evict(&[event.table.clone()]).await;
+            }
+            self.instance.schema_cache.on_schema_event(&event);
+            self.instance.schema_events.publish(event);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn build_interpreter(
         &self,
         request_id: RequestId,
@@ -481,12 +695,25 @@ impl Proxy {
         plan: Plan,
         deadline: Option<Instant>,
         enable_partition_table_access: bool,
+        result_sender: Option<ResultSender>,
+        result_limit: ResultLimit,
+        warnings: QueryWarnings,
+        write_sequence: WriteSequence,
     ) -> Result<InterpreterPtr> {
+        let scan_usage = self.instance.query_tracker.scan_usage(&request_id);
+        let stages = self.instance.query_tracker.stages(&request_id);
         let interpreter_ctx = InterpreterContext::builder(request_id, deadline)
             // Use current ctx's catalog and schema as default catalog and schema
             .default_catalog_and_schema(catalog.to_string(), schema.to_string())
+            .scan_usage(scan_usage)
+            .stages(stages)
             .enable_partition_table_access(enable_partition_table_access)
             .expensive_query_threshold(self.expensive_query_threshold)
+            .hot_time_range(self.cold_query_router.hot_time_range())
+            .result_sender(result_sender)
+            .result_limit(result_limit)
+            .warnings(warnings)
+            .write_sequence(write_sequence)
             .build();
         let interpreter_factory = Factory::new(
             self.instance.query_engine.executor(),
@@ -495,6 +722,17 @@ impl Proxy {
             self.instance.table_engine.clone(),
             self.instance.table_manipulator.clone(),
             self.instance.query_runtime.clone(),
+            self.instance.result_offloader.clone(),
+            self.instance.query_tracker.clone(),
+            self.instance.resync_requests.clone(),
+            self.instance.source_manager.clone(),
+            self.instance.system_config_manager.clone(),
+            self.instance.continuous_query_manager.clone(),
+            self.instance
+                .authenticator
+                .clone()
+                .map(|v| v as UserManagerRef),
+            self.instance.wasm_udf_manager.clone(),
         );
         interpreter_factory
             .create(interpreter_ctx, plan)
@@ -518,15 +756,24 @@ impl Proxy {
             .context(Internal {
                 msg: "Plan execution timeout",
             })
-            .and_then(|v| {
-                v.box_err().context(Internal {
-                    msg: "Failed to execute interpreter",
-                })
-            })
+            .and_then(|v| v.map_err(Self::interpreter_error))
         } else {
-            interpreter.execute().await.box_err().context(Internal {
-                msg: "Failed to execute interpreter",
-            })
+            interpreter.execute().await.map_err(Self::interpreter_error)
+        }
+    }
+
+    fn interpreter_error(err: interpreter::Error) -> Error {
+        let code = match &err {
+            interpreter::Error::KillQuery {
+                source: kill::Error::QueryNotFound { .. },
+            } => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        Error::ErrWithCause {
+            code,
+            msg: "Failed to execute interpreter".to_string(),
+            source: Box::new(err),
         }
     }
 }
@@ -536,6 +783,24 @@ pub struct Context {
     request_id: RequestId,
     timeout: Option<Duration>,
     forwarded_from: Option<String>,
+    /// Whether the request is forwarded by another node of the cluster, that
+    /// is it carries the internal token. The admission of the internal requests
+    /// is done by the nodes forwarding them.
+    ///
+    /// NOTE: The `forwarded_from` is set by the clients too, so it must not be
+    /// trusted for the admission.
+    internal: bool,
+    /// Log level elevated for this request only.
+    log_level: Option<Level>,
+    /// Stream the rows of the query through it if set.
+    result_sender: Option<ResultSender>,
+    /// Skip the invalid rows of the write rather than rejecting it.
+    partial_write: bool,
+    /// User authenticated by the credentials of the request, `None` if the
+    /// authentication is disabled or the request is internal.
+    auth_user: Option<AuthUser>,
+    /// Warnings of the query, taken by the handler to tell the client.
+    warnings: QueryWarnings,
 }
 
 impl Context {
@@ -544,6 +809,67 @@ impl Context {
             request_id: RequestId::next_id(),
             timeout,
             forwarded_from,
+            internal: false,
+            log_level: None,
+            result_sender: None,
+            partial_write: false,
+            auth_user: None,
+            warnings: QueryWarnings::default(),
+        }
+    }
+
+    pub fn with_internal(mut self, internal: bool) -> Self {
+        self.internal = internal;
+        self
+    }
+
+    pub fn with_log_level(mut self, log_level: Option<Level>) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    pub fn with_result_sender(mut self, result_sender: Option<ResultSender>) -> Self {
+        self.result_sender = result_sender;
+        self
+    }
+
+    pub fn with_partial_write(mut self, partial_write: bool) -> Self {
+        self.partial_write = partial_write;
+        self
+    }
+
+    pub fn with_auth_user(mut self, auth_user: Option<AuthUser>) -> Self {
+        self.auth_user = auth_user;
+        self
+    }
+
+    /// Build the request forwarded to other nodes, which keeps the partial
+    /// write and the credentials of this request.
+    fn forwarded_request<T>(&self, req: T) -> tonic::Request<T> {
+        let mut req = tonic::Request::new(req);
+        if self.partial_write {
+            req.metadata_mut()
+                .insert(PARTIAL_WRITE_KEY, MetadataValue::from_static("true"));
+        }
+        auth::attach_credentials(&mut req, self.auth_user.as_ref());
+        req
+    }
+}
+
+/// Run the handling of the request with its request id attached to the logs,
+/// and in a verbose scope if its log level is elevated, see
+/// [Proxy::log_level_of].
+async fn with_log_level<F: Future>(ctx: &Context, level: Option<Level>, f: F) -> F::Output {
+    let request_id = ctx.request_id.to_string();
+    match level {
+        Some(level) => {
+            info!(
+                "Handle request verbosely, request_id:{}, level:{}",
+                ctx.request_id,
+                level.as_str()
+            );
+            logger::with_verbose_scope(level, request_id, f).await
         }
+        None => logger::with_request_id(request_id, f).await,
     }
 }
@@ -15,12 +15,21 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{collections::HashSet, str::FromStr, sync::RwLock};
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        RwLock,
+    },
+};
 
 use datafusion::logical_expr::logical_plan::LogicalPlan;
 use logger::error;
 use macros::define_result;
 use query_frontend::plan::Plan;
+use runtime::Priority;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use time_ext::ReadableDuration;
@@ -35,21 +44,40 @@ pub enum Error {
 
     #[snafu(display("Query is blocked by rule:{:?}", rule))]
     BlockedByRule { rule: BlockRule },
+
+    #[snafu(display("Request is rejected under memory pressure:{:?}", pressure))]
+    BlockedByMemoryPressure { pressure: MemoryPressure },
 }
 
 define_result!(Error);
 
-#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq, Hash, Serialize, PartialOrd, Ord)]
+#[derive(
+    Clone, Copy, Deserialize, Debug, PartialEq, Eq, Hash, Serialize, PartialOrd, Ord, JsonSchema,
+)]
 #[serde(tag = "type", content = "content")]
 pub enum BlockRule {
     QueryWithoutPredicate,
     /// Max time range a query can scan.
-    #[serde(deserialize_with = "deserialize_readable_duration")]
+    #[serde(
+        serialize_with = "serialize_readable_duration",
+        deserialize_with = "deserialize_readable_duration"
+    )]
+    #[schemars(with = "String")]
     QueryRange(i64),
     AnyQuery,
     AnyInsert,
 }
 
+fn serialize_readable_duration<S>(
+    millis: &i64,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    ReadableDuration::millis(*millis as u64).serialize(serializer)
+}
+
 fn deserialize_readable_duration<'de, D>(deserializer: D) -> std::result::Result<i64, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -68,6 +96,45 @@ pub struct LimiterConfig {
     pub rules: Vec<BlockRule>,
 }
 
+/// Levels of the memory pressure of the process, the load is shed
+/// progressively as the level goes up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[repr(u8)]
+pub enum MemoryPressure {
+    #[default]
+    Normal = 0,
+    /// Shrink the caches.
+    ShrinkCaches = 1,
+    /// Flush the memtables.
+    Flush = 2,
+    /// Reject the expensive queries.
+    RejectHeavyQueries = 3,
+    /// Reject the writes.
+    RejectWrites = 4,
+}
+
+impl MemoryPressure {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Self::Normal,
+            1 => Self::ShrinkCaches,
+            2 => Self::Flush,
+            3 => Self::RejectHeavyQueries,
+            _ => Self::RejectWrites,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::ShrinkCaches => "shrink_caches",
+            Self::Flush => "flush",
+            Self::RejectHeavyQueries => "reject_heavy_queries",
+            Self::RejectWrites => "reject_writes",
+        }
+    }
+}
+
 impl BlockRule {
     fn should_limit(&self, plan: &Plan) -> bool {
         match self {
@@ -122,6 +189,8 @@ pub struct Limiter {
     write_block_list: RwLock<HashSet<String>>,
     read_block_list: RwLock<HashSet<String>>,
     rules: RwLock<HashSet<BlockRule>>,
+    /// Current [MemoryPressure] of the process.
+    memory_pressure: AtomicU8,
 }
 
 impl Default for Limiter {
@@ -130,6 +199,7 @@ impl Default for Limiter {
             write_block_list: RwLock::new(HashSet::new()),
             read_block_list: RwLock::new(HashSet::new()),
             rules: RwLock::new(HashSet::new()),
+            memory_pressure: AtomicU8::new(MemoryPressure::Normal as u8),
         }
     }
 }
@@ -140,6 +210,7 @@ impl Limiter {
             write_block_list: RwLock::new(limit_config.write_block_list.into_iter().collect()),
             read_block_list: RwLock::new(limit_config.read_block_list.into_iter().collect()),
             rules: RwLock::new(limit_config.rules.into_iter().collect()),
+            memory_pressure: AtomicU8::new(MemoryPressure::Normal as u8),
         }
     }
 
@@ -200,7 +271,12 @@ impl Limiter {
     pub fn try_limit(&self, plan: &Plan) -> Result<()> {
         let result = {
             self.try_limit_by_block_list(plan)?;
-            self.try_limit_by_rules(plan)
+            self.try_limit_by_rules(plan)?;
+            if matches!(plan, Plan::Insert(_)) {
+                self.try_limit_by_memory(MemoryPressure::RejectWrites)
+            } else {
+                Ok(())
+            }
         };
 
         if result.is_err() {
@@ -212,6 +288,53 @@ impl Limiter {
         result
     }
 
+    /// Try to limit the query of the `priority` according to the memory
+    /// pressure, only the low priority (expensive) queries are rejected.
+    pub fn try_limit_query_by_memory(&self, priority: Option<Priority>) -> Result<()> {
+        if priority != Some(Priority::Low) {
+            return Ok(());
+        }
+
+        let result = self.try_limit_by_memory(MemoryPressure::RejectHeavyQueries);
+        if result.is_err() {
+            BLOCKED_REQUEST_COUNTER_VEC_GLOBAL
+                .with_label_values(&["query"])
+                .inc();
+        }
+
+        result
+    }
+
+    /// Try to limit the write according to the memory pressure.
+    pub fn try_limit_write_by_memory(&self) -> Result<()> {
+        let result = self.try_limit_by_memory(MemoryPressure::RejectWrites);
+        if result.is_err() {
+            BLOCKED_REQUEST_COUNTER_VEC_GLOBAL
+                .with_label_values(&["write"])
+                .inc();
+        }
+
+        result
+    }
+
+    fn try_limit_by_memory(&self, reject_pressure: MemoryPressure) -> Result<()> {
+        let pressure = self.memory_pressure();
+        if pressure >= reject_pressure {
+            BlockedByMemoryPressure { pressure }.fail()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn memory_pressure(&self) -> MemoryPressure {
+        MemoryPressure::from_u8(self.memory_pressure.load(Ordering::Relaxed))
+    }
+
+    pub fn set_memory_pressure(&self, pressure: MemoryPressure) {
+        self.memory_pressure
+            .store(pressure as u8, Ordering::Relaxed);
+    }
+
     pub fn add_write_block_list(&self, block_list: Vec<String>) {
         self.write_block_list.write().unwrap().extend(block_list)
     }
@@ -279,8 +402,9 @@ mod tests {
         config::DynamicConfig, parser::Parser, plan::Plan, planner::Planner,
         tests::MockMetaProvider,
     };
+    use runtime::Priority;
 
-    use super::{BlockRule, LimiterConfig};
+    use super::{BlockRule, LimiterConfig, MemoryPressure};
     use crate::limiter::Limiter;
 
     fn sql_to_plan(meta_provider: &MockMetaProvider, sql: &str) -> Plan {
@@ -428,4 +552,43 @@ mod tests {
         limiter.set_block_rules(vec![BlockRule::QueryWithoutPredicate]);
         assert!(limiter.try_limit(&query_plan).is_err());
     }
+
+    #[test]
+    fn test_limit_by_memory_pressure() {
+        let mock = MockMetaProvider::default();
+        let limiter = Limiter::default();
+        let insert="INSERT INTO test_table(key1, key2, field1, field2) VALUES('tagk', 1638428434000, 100, 'hello3')";
+        let insert_plan = sql_to_plan(&mock, insert);
+
+        for pressure in [
+            MemoryPressure::Normal,
+            MemoryPressure::ShrinkCaches,
+            MemoryPressure::Flush,
+        ] {
+            limiter.set_memory_pressure(pressure);
+            assert!(limiter
+                .try_limit_query_by_memory(Some(Priority::Low))
+                .is_ok());
+            assert!(limiter.try_limit_write_by_memory().is_ok());
+            assert!(limiter.try_limit(&insert_plan).is_ok());
+        }
+
+        limiter.set_memory_pressure(MemoryPressure::RejectHeavyQueries);
+        assert!(limiter
+            .try_limit_query_by_memory(Some(Priority::Low))
+            .is_err());
+        assert!(limiter
+            .try_limit_query_by_memory(Some(Priority::High))
+            .is_ok());
+        assert!(limiter.try_limit_query_by_memory(None).is_ok());
+        assert!(limiter.try_limit_write_by_memory().is_ok());
+
+        limiter.set_memory_pressure(MemoryPressure::RejectWrites);
+        assert_eq!(MemoryPressure::RejectWrites, limiter.memory_pressure());
+        assert!(limiter
+            .try_limit_query_by_memory(Some(Priority::Low))
+            .is_err());
+        assert!(limiter.try_limit_write_by_memory().is_err());
+        assert!(limiter.try_limit(&insert_plan).is_err());
+    }
 }
@@ -0,0 +1,308 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Maintenance mode of the tables.
+//!
+//! The writes to a table under maintenance, e.g. during repair or migration,
+//! are either rejected with a retryable code or queued until the maintenance
+//! ends, and the queries to it can be redirected to a replica. The tables are
+//! identified by their schemas and names, so the tables of the same name in
+//! other schemas are not affected.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+use tokio::sync::Notify;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Max time a queued write waits for the maintenance to end.
+    pub max_queue_wait: ReadableDuration,
+    /// Max number of the writes queued among all the tables.
+    pub max_queued_writes: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_queue_wait: ReadableDuration::secs(5),
+            max_queued_writes: 1024,
+        }
+    }
+}
+
+/// How the writes to a table under maintenance are handled.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+pub enum WriteMode {
+    #[default]
+    Reject,
+    Queue,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct MaintenanceMode {
+    pub write_mode: WriteMode,
+    /// Endpoint of the replica serving the queries to the table, the queries
+    /// are executed as usual if not set.
+    pub redirect_endpoint: Option<String>,
+}
+
+/// Table under maintenance.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct MaintainedTable {
+    pub schema: String,
+    pub table: String,
+    pub mode: MaintenanceMode,
+}
+
+/// Reason of a write being rejected by the maintenance.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rejected {
+    UnderMaintenance,
+    QueueFull,
+    QueueTimeout,
+}
+
+struct Entry {
+    mode: MaintenanceMode,
+    /// Notified when the maintenance ends.
+    end: Arc<Notify>,
+}
+
+pub struct TableMaintenance {
+    max_queue_wait: Duration,
+    max_queued_writes: usize,
+    queued_writes: AtomicUsize,
+    /// Entries keyed by the schemas and the names of the tables.
+    tables: RwLock<HashMap<(String, String), Entry>>,
+}
+
+impl Default for TableMaintenance {
+    fn default() -> Self {
+        Self::new(&Config::default())
+    }
+}
+
+impl TableMaintenance {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            max_queue_wait: config.max_queue_wait.0,
+            max_queued_writes: config.max_queued_writes,
+            queued_writes: AtomicUsize::new(0),
+            tables: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Put the tables into maintenance, and the mode of the ones already under
+    /// maintenance is updated.
+    pub fn enter(&self, schema: &str, tables: Vec<String>, mode: MaintenanceMode) {
+        let mut entries = self.tables.write().unwrap();
+        for table in tables {
+            let key = (schema.to_string(), table);
+            match entries.get_mut(&key) {
+                Some(entry) => entry.mode = mode.clone(),
+                None => {
+                    entries.insert(
+                        key,
+                        Entry {
+                            mode: mode.clone(),
+                            end: Arc::new(Notify::new()),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Bring the tables back from maintenance, and the queued writes to them
+    /// are resumed.
+    pub fn exit(&self, schema: &str, tables: &[String]) {
+        let mut entries = self.tables.write().unwrap();
+        for table in tables {
+            if let Some(entry) = entries.remove(&(schema.to_string(), table.clone())) {
+                entry.end.notify_waiters();
+            }
+        }
+    }
+
+    /// Bring all the tables back from maintenance.
+    pub fn exit_all(&self) {
+        let mut entries = self.tables.write().unwrap();
+        for (_, entry) in entries.drain() {
+            entry.end.notify_waiters();
+        }
+    }
+
+    /// List the tables under maintenance, sorted by the schemas and the names.
+    pub fn list(&self) -> Vec<MaintainedTable> {
+        let entries = self.tables.read().unwrap();
+        let mut tables: Vec<_> = entries
+            .iter()
+            .map(|((schema, table), entry)| MaintainedTable {
+                schema: schema.clone(),
+                table: table.clone(),
+                mode: entry.mode.clone(),
+            })
+            .collect();
+        tables.sort_by(|a, b| (&a.schema, &a.table).cmp(&(&b.schema, &b.table)));
+
+        tables
+    }
+
+    /// Endpoint to redirect the queries to the table to, if it's under
+    /// maintenance.
+    pub fn redirect_endpoint(&self, schema: &str, table: &str) -> Option<String> {
+        let entries = self.tables.read().unwrap();
+        // Only the tables under maintenance are tracked, so nothing is allocated
+        // in the common path.
+        if entries.is_empty() {
+            return None;
+        }
+
+        entries
+            .get(&(schema.to_string(), table.to_string()))
+            .and_then(|entry| entry.mode.redirect_endpoint.clone())
+    }
+
+    /// Wait until the table is writable, returns the reason if the write
+    /// should be rejected.
+    pub async fn wait_writable(
+        &self,
+        schema: &str,
+        table: &str,
+    ) -> std::result::Result<(), Rejected> {
+        let (key, end) = {
+            let entries = self.tables.read().unwrap();
+            if entries.is_empty() {
+                return Ok(());
+            }
+            let key = (schema.to_string(), table.to_string());
+            match entries.get(&key) {
+                None => return Ok(()),
+                Some(entry) if entry.mode.write_mode == WriteMode::Reject => {
+                    return Err(Rejected::UnderMaintenance)
+                }
+                Some(entry) => (key, entry.end.clone()),
+            }
+        };
+
+        // Register the waiter before checking again, so the end of the maintenance
+        // between the checks won't be missed.
+        let notified = end.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if !self.tables.read().unwrap().contains_key(&key) {
+            return Ok(());
+        }
+
+        if self.queued_writes.fetch_add(1, Ordering::Relaxed) >= self.max_queued_writes {
+            self.queued_writes.fetch_sub(1, Ordering::Relaxed);
+            return Err(Rejected::QueueFull);
+        }
+        let result = tokio::time::timeout(self.max_queue_wait, notified).await;
+        self.queued_writes.fetch_sub(1, Ordering::Relaxed);
+
+        result.map_err(|_| Rejected::QueueTimeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reject_writes() {
+        let maintenance = TableMaintenance::default();
+        let mode = MaintenanceMode {
+            write_mode: WriteMode::Reject,
+            redirect_endpoint: Some("127.0.0.1:8831".to_string()),
+        };
+        maintenance.enter("s1", vec!["t1".to_string()], mode.clone());
+
+        assert_eq!(
+            maintenance.wait_writable("s1", "t1").await,
+            Err(Rejected::UnderMaintenance)
+        );
+        assert_eq!(maintenance.wait_writable("s1", "t2").await, Ok(()));
+        // The table of the same name in another schema is not affected.
+        assert_eq!(maintenance.wait_writable("s2", "t1").await, Ok(()));
+        assert_eq!(
+            maintenance.redirect_endpoint("s1", "t1").as_deref(),
+            Some("127.0.0.1:8831")
+        );
+        assert!(maintenance.redirect_endpoint("s1", "t2").is_none());
+        assert!(maintenance.redirect_endpoint("s2", "t1").is_none());
+        assert_eq!(
+            maintenance.list(),
+            vec![MaintainedTable {
+                schema: "s1".to_string(),
+                table: "t1".to_string(),
+                mode,
+            }]
+        );
+
+        maintenance.exit("s2", &["t1".to_string()]);
+        assert_eq!(maintenance.list().len(), 1);
+        maintenance.exit("s1", &["t1".to_string()]);
+        assert_eq!(maintenance.wait_writable("s1", "t1").await, Ok(()));
+        assert!(maintenance.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_queue_writes() {
+        let maintenance = Arc::new(TableMaintenance::new(&Config {
+            max_queue_wait: ReadableDuration::millis(50),
+            max_queued_writes: 1,
+        }));
+        let mode = MaintenanceMode {
+            write_mode: WriteMode::Queue,
+            redirect_endpoint: None,
+        };
+        maintenance.enter("s1", vec!["t1".to_string()], mode);
+
+        // The queued write times out.
+        assert_eq!(
+            maintenance.wait_writable("s1", "t1").await,
+            Err(Rejected::QueueTimeout)
+        );
+
+        // The queued write is resumed after the maintenance ends, and the others
+        // are rejected as the queue is full.
+        let queued = {
+            let maintenance = maintenance.clone();
+            tokio::spawn(async move { maintenance.wait_writable("s1", "t1").await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            maintenance.wait_writable("s1", "t1").await,
+            Err(Rejected::QueueFull)
+        );
+        maintenance.exit_all();
+        assert_eq!(queued.await.unwrap(), Ok(()));
+    }
+}
@@ -17,8 +17,14 @@
 
 // Grpc proxy metrics
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use lazy_static::lazy_static;
-use prometheus::{register_int_counter_vec, IntCounterVec};
+use metric_ext::exemplar::HistogramExemplars;
+use prometheus::{
+    exponential_buckets, register_histogram, register_histogram_vec, register_int_counter_vec,
+    register_int_gauge_vec, Histogram, HistogramVec, IntCounterVec, IntGaugeVec,
+};
 use prometheus_static_metric::{auto_flush_from, make_auto_flush_static_metric};
 
 make_auto_flush_static_metric! {
@@ -38,6 +44,7 @@ make_auto_flush_static_metric! {
         query_succeeded_row,
         query_affected_row,
         dedupped_stream_query,
+        stream_query_rejected,
     }
 
     pub struct GrpcHandlerCounterVec: LocalIntCounter {
@@ -64,12 +71,102 @@ lazy_static! {
     pub static ref HTTP_HANDLER_COUNTER_VEC_GLOBAL: IntCounterVec =
         register_int_counter_vec!("http_handler_counter", "Http handler counter", &["type"])
             .unwrap();
+    pub static ref FORWARD_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_forward_counter",
+        "Counter of the requests routed by the forwarder",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref WRITE_QUEUE_DEPTH_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "proxy_write_queue_depth",
+        "Number of the writes waiting in the write queue",
+        &["priority"]
+    )
+    .unwrap();
+    pub static ref WRITE_QUEUE_REJECTED_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_write_queue_rejected_counter",
+        "Counter of the writes rejected as the write queue is full",
+        &["priority"]
+    )
+    .unwrap();
+    pub static ref ADMISSION_REJECTED_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_admission_rejected_counter",
+        "Counter of the requests rejected by the admission control",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref REPLICA_CHECK_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_replica_check_counter",
+        "Counter of the tables checked against the replicas",
+        &["replica", "result"]
+    )
+    .unwrap();
+    pub static ref REPLICA_DIVERGED_WINDOWS_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "proxy_replica_diverged_windows",
+        "Number of the windows diverged from the replicas found by the last check",
+        &["replica"]
+    )
+    .unwrap();
+    pub static ref SCHEMA_CACHE_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_schema_cache_counter",
+        "Counter of the lookups, invalidations and stale retries of the schema cache",
+        &["type"]
+    )
+    .unwrap();
     pub static ref BLOCKED_REQUEST_COUNTER_VEC_GLOBAL: IntCounterVec = register_int_counter_vec!(
         "blocked_request_counter",
         "Blocked request counter",
         &["type"]
     )
     .unwrap();
+    pub static ref WRITE_TEE_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_write_tee_counter",
+        "Counter of the rows mirrored, dropped and failed to mirror by the write tee",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref WRITE_CIRCUIT_BREAKER_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "write_circuit_breaker_counter",
+        "Counter of the circuit breaker opened and the writes rejected by it",
+        &["type"]
+    )
+    .unwrap();
+    // 0.001s, 0.002s, ... 524.288s
+    static ref QUERY_DURATION_BUCKETS: Vec<f64> = exponential_buckets(0.001, 2.0, 20).unwrap();
+    pub static ref QUERY_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "query_duration",
+        "Bucketed histogram of sql query duration",
+        QUERY_DURATION_BUCKETS.clone()
+    )
+    .unwrap();
+    pub static ref QUERY_DURATION_EXEMPLARS: HistogramExemplars =
+        HistogramExemplars::new(QUERY_DURATION_BUCKETS.clone());
+    // 1, 2, ... 32768
+    pub static ref WRITE_BATCH_ROWS_HISTOGRAM: Histogram = register_histogram!(
+        "write_batch_rows",
+        "Bucketed histogram of rows of the coalesced write batches",
+        exponential_buckets(1.0, 2.0, 16).unwrap()
+    )
+    .unwrap();
+    pub static ref USER_REQUEST_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_user_request_counter",
+        "Counter of the requests by the users",
+        &["user", "kind", "result"]
+    )
+    .unwrap();
+    pub static ref USER_REQUEST_DURATION_HISTOGRAM_VEC: HistogramVec = register_histogram_vec!(
+        "proxy_user_request_duration",
+        "Bucketed histogram of the duration of the requests by the users",
+        &["user", "kind"],
+        QUERY_DURATION_BUCKETS.clone()
+    )
+    .unwrap();
+    pub static ref USER_WRITE_ROWS_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "proxy_user_write_rows_counter",
+        "Counter of the rows written by the users",
+        &["user"]
+    )
+    .unwrap();
 }
 
 lazy_static! {
@@ -78,3 +175,28 @@ lazy_static! {
     pub static ref HTTP_HANDLER_COUNTER_VEC: HttpHandlerCounterVec =
         auto_flush_from!(HTTP_HANDLER_COUNTER_VEC_GLOBAL, HttpHandlerCounterVec);
 }
+
+/// Name of the histogram with exemplars.
+pub const QUERY_DURATION_METRIC: &str = "query_duration";
+
+static EXEMPLARS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Enable recording the request ids of the queries as the exemplars of the
+/// query duration histogram.
+pub fn set_exemplars_enabled(enabled: bool) {
+    EXEMPLARS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn exemplars_enabled() -> bool {
+    EXEMPLARS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Observe the duration of a sql query, and the request id is linked to it if
+/// the exemplars are enabled.
+pub fn observe_query_duration(request_id: &str, duration_secs: f64) {
+    QUERY_DURATION_HISTOGRAM.observe(duration_secs);
+    if exemplars_enabled() {
+        QUERY_DURATION_EXEMPLARS.observe(duration_secs, request_id);
+    }
+}
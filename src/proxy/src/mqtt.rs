@@ -0,0 +1,245 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Ingest the json payloads published by the mqtt clients.
+//!
+//! The topic of a publish is matched against the configured topic mappings,
+//! and the payload is written into the table of the first matched mapping.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use horaedbproto::storage::{
+    value, RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest,
+};
+use http::StatusCode;
+use logger::debug;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+
+use crate::{
+    context::RequestContext,
+    error::{ErrNoCause, Result},
+    schema_registry::types::{convert_records, WriteParams},
+    source::json_to_records,
+    Context, Proxy,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    pub port: u16,
+    /// Packets larger than it are rejected and the connection is closed.
+    pub max_packet_size: ReadableSize,
+    pub topics: Vec<TopicMapping>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            port: 1883,
+            max_packet_size: ReadableSize::mb(1),
+            topics: Vec::new(),
+        }
+    }
+}
+
+/// Mapping from the topics to a table.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TopicMapping {
+    /// Topic filter, which supports the `+` and `#` wildcards.
+    pub topic: String,
+    /// Schema of the table, the default schema is used if empty.
+    pub schema: String,
+    pub table: String,
+    /// Fields of the payload written as tags.
+    pub tags: Vec<String>,
+    /// Field of the payload holding the timestamp in milliseconds, the
+    /// receiving time is used if the payload doesn't contain it.
+    pub timestamp_field: String,
+    /// Tag to write the topic of the publish into, so the devices publishing
+    /// to different topics can be told apart.
+    pub topic_tag: Option<String>,
+}
+
+impl Default for TopicMapping {
+    fn default() -> Self {
+        Self {
+            topic: String::new(),
+            schema: String::new(),
+            table: String::new(),
+            tags: Vec::new(),
+            timestamp_field: "timestamp".to_string(),
+            topic_tag: None,
+        }
+    }
+}
+
+/// Find the first mapping whose filter matches the topic.
+pub fn find_mapping<'a>(mappings: &'a [TopicMapping], topic: &str) -> Option<&'a TopicMapping> {
+    mappings
+        .iter()
+        .find(|mapping| topic_matches(&mapping.topic, topic))
+}
+
+/// Match the topic against the filter as the mqtt specification defines, `+`
+/// matches exactly one level and `#` matches any number of the remaining
+/// levels.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    // Topics starting with `$` are reserved and not matched by the wildcards.
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+
+    let mut topic_levels = topic.split('/');
+    for filter_level in filter.split('/') {
+        if filter_level == "#" {
+            return true;
+        }
+        match topic_levels.next() {
+            Some(topic_level) if filter_level == "+" || filter_level == topic_level => {}
+            _ => return false,
+        }
+    }
+
+    topic_levels.next().is_none()
+}
+
+impl Proxy {
+    pub async fn handle_mqtt_publish(
+        &self,
+        ctx: RequestContext,
+        mapping: &TopicMapping,
+        topic: &str,
+        payload: &[u8],
+    ) -> Result<()> {
+        let mut records = json_to_records(payload, &mapping.timestamp_field)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.as_millis() as i64)
+            .unwrap_or_default();
+        for record in &mut records {
+            if !record
+                .iter()
+                .any(|(name, _)| name == &mapping.timestamp_field)
+            {
+                record.push((
+                    mapping.timestamp_field.clone(),
+                    value::Value::TimestampValue(now),
+                ));
+            }
+            if let Some(topic_tag) = &mapping.topic_tag {
+                record.push((
+                    topic_tag.clone(),
+                    value::Value::StringValue(topic.to_string()),
+                ));
+            }
+        }
+
+        let mut tags = mapping.tags.clone();
+        tags.extend(mapping.topic_tag.clone());
+        let params = WriteParams {
+            table: Some(mapping.table.clone()),
+            timestamp_field: mapping.timestamp_field.clone(),
+            tags: tags.join(","),
+            ..Default::default()
+        };
+        let num_rows = records.len();
+        let req = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: ctx.schema.clone(),
+            }),
+            table_requests: vec![convert_records(mapping.table.clone(), &params, records)?],
+        };
+        let write_ctx = Context::new(ctx.timeout, None).with_auth_user(ctx.auth_user);
+        let resp = self.handle_write_internal(write_ctx, req).await?;
+        if resp.failed != 0 {
+            return ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!(
+                    "fail to write storage, rows:{num_rows}, failed rows:{}",
+                    resp.failed
+                ),
+            }
+            .fail();
+        }
+
+        debug!(
+            "Mqtt publish written, schema:{}, topic:{topic}, table:{}, rows:{num_rows}",
+            ctx.schema, mapping.table
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches() {
+        let cases = [
+            ("sensors/temp", "sensors/temp", true),
+            ("sensors/temp", "sensors/humidity", false),
+            ("sensors/+", "sensors/temp", true),
+            ("sensors/+", "sensors/temp/1", false),
+            ("sensors/+/data", "sensors/dev1/data", true),
+            ("sensors/#", "sensors", true),
+            ("sensors/#", "sensors/dev1/data", true),
+            ("#", "sensors/dev1", true),
+            ("#", "$SYS/broker", false),
+            ("+/temp", "$SYS/temp", false),
+            ("sensors/temp", "sensors/temp/1", false),
+            ("sensors/temp/1", "sensors/temp", false),
+        ];
+        for (filter, topic, expected) in cases {
+            assert_eq!(
+                topic_matches(filter, topic),
+                expected,
+                "filter:{filter}, topic:{topic}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_mapping() {
+        let mappings = vec![
+            TopicMapping {
+                topic: "devices/+/cpu".to_string(),
+                table: "cpu".to_string(),
+                ..Default::default()
+            },
+            TopicMapping {
+                topic: "devices/#".to_string(),
+                table: "others".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            find_mapping(&mappings, "devices/a/cpu").unwrap().table,
+            "cpu"
+        );
+        assert_eq!(
+            find_mapping(&mappings, "devices/a/mem").unwrap().table,
+            "others"
+        );
+        assert!(find_mapping(&mappings, "other").is_none());
+    }
+}
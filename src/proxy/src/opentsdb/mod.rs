@@ -58,7 +58,8 @@ impl Proxy {
             }),
             table_requests: write_table_requests,
         };
-        let proxy_context = Context::new(ctx.timeout, None);
+        let proxy_context = Context::new(ctx.timeout, None)
+            .with_auth_user(ctx.auth_user.clone());
 
         match self
             .handle_write_internal(proxy_context, table_request)
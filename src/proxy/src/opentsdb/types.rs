@@ -26,6 +26,7 @@ use horaedbproto::storage::{
     value, Field, FieldGroup, Tag, Value as ProtoValue, WriteSeriesEntry, WriteTableRequest,
 };
 use http::StatusCode;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::from_slice;
 use snafu::{OptionExt, ResultExt};
@@ -67,7 +68,7 @@ pub type PutResponse = ();
 ///
 /// NOTE:
 ///     - all the params is unimplemented.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct PutParams {
     pub summary: Option<String>,
@@ -76,7 +77,8 @@ pub struct PutParams {
     pub sync_timeout: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(rename = "OpenTsdbPoint")]
 pub struct Point {
     pub metric: String,
     pub timestamp: i64,
@@ -84,7 +86,7 @@ pub struct Point {
     pub tags: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Value {
     // TODO: telegraf parse 0.0 as 0, which will confuse int and double type
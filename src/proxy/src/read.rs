@@ -17,36 +17,53 @@
 
 //! Contains common methods used by the read process.
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::BTreeMap,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
+use arrow::{array::StringArray, record_batch::RecordBatch as ArrowRecordBatch};
+use common_types::record_batch::RecordBatch;
 use futures::FutureExt;
 use generic_error::BoxError;
 use horaedbproto::storage::{
     storage_service_client::StorageServiceClient, RequestContext, SqlQueryRequest, SqlQueryResponse,
 };
 use http::StatusCode;
-use interpreters::interpreter::Output;
+use interpreters::{context::WriteSequence, interpreter::Output, query_tracker::QuerySnapshot};
 use logger::{error, info, warn, SlowTimer};
 use notifier::notifier::{ExecutionGuard, RequestNotifiers, RequestResult};
+use query_engine::stage::{QueryStages, STAGE_PARSE, STAGE_PLAN};
 use query_frontend::{
+    ast::Statement,
     frontend,
     frontend::{Context as SqlContext, Frontend},
-    plan::{Plan, PriorityContext},
+    plan::{Plan, PriorityContext, QueryPlan, UserRole},
     provider::CatalogMetaProvider,
 };
 use router::endpoint::Endpoint;
-use snafu::{ensure, ResultExt};
+use snafu::{ensure, OptionExt, ResultExt};
+use sqlparser::ast::Statement as SqlStatement;
+use system_catalog::scheduler::scheduler_state;
+use table_engine::query_warning::{self, QueryWarnings, WarningKind};
 use tokio::sync::mpsc::{self, Sender};
-use tonic::{transport::Channel, IntoRequest};
+use tonic::transport::Channel;
 
 use crate::{
+    auth,
     error::{ErrNoCause, ErrWithCause, Error, Internal, InternalNoCause, Result},
     forward::{ForwardRequest, ForwardResult},
-    metrics::GRPC_HANDLER_COUNTER_VEC,
-    Context, Proxy,
+    http::sql::convert_sql_response_to_output,
+    metrics::{self, GRPC_HANDLER_COUNTER_VEC},
+    user_metrics::RequestKind,
+    with_log_level, Context, Proxy, QUERY_WARNINGS_KEY,
 };
 
 const DEDUP_READ_CHANNEL_LEN: usize = 1;
+/// Queries taking longer than this ratio of their timeout are warned as
+/// approaching the timeout.
+const APPROACHING_TIMEOUT_RATIO: f64 = 0.8;
 pub type ReadRequestNotifiers = Arc<RequestNotifiers<String, Sender<Result<SqlResponse>>>>;
 
 pub enum SqlResponse {
@@ -63,27 +80,34 @@ impl Proxy {
         enable_partition_table_access: bool,
         enable_block_query: bool, // true for grpc, false for http
     ) -> Result<SqlResponse> {
-        if let Some(resp) = self
-            .maybe_forward_sql_query(ctx.clone(), schema, sql)
-            .await?
-        {
-            match resp {
-                ForwardResult::Forwarded(resp) => return Ok(SqlResponse::Forwarded(resp?)),
-                ForwardResult::Local => (),
-            }
-        };
+        let user = self.metrics_user_of(ctx, Some(schema));
+        let begin_instant = Instant::now();
+        let result = with_log_level(ctx, self.log_level_of(ctx), async {
+            if let Some(resp) = self
+                .maybe_forward_sql_query(ctx.clone(), schema, sql)
+                .await?
+            {
+                match resp {
+                    ForwardResult::Forwarded(resp) => return Ok(SqlResponse::Forwarded(resp?)),
+                    ForwardResult::Local => (),
+                }
+            };
 
-        let output = self
-            .fetch_sql_query_output(
-                ctx,
-                schema,
-                sql,
-                enable_partition_table_access,
-                enable_block_query,
-            )
-            .await?;
+            let output = self
+                .fetch_sql_query_output(
+                    ctx,
+                    schema,
+                    sql,
+                    enable_partition_table_access,
+                    enable_block_query,
+                )
+                .await?;
 
-        Ok(SqlResponse::Local(output))
+            Ok(SqlResponse::Local(output))
+        })
+        .await;
+        self.observe_user_request(user, RequestKind::Query, result.is_ok(), 0, begin_instant);
+        result
     }
 
     pub(crate) async fn dedup_handle_sql(
@@ -173,6 +197,7 @@ impl Proxy {
         enable_partition_table_access: bool,
         enable_block_query: bool,
     ) -> Result<Output> {
+        let _inflight = self.instance.drainer.track();
         let request_id = &ctx.request_id;
         let slow_threshold_secs = self
             .instance()
@@ -180,11 +205,14 @@ impl Proxy {
             .slow_threshold
             .load(std::sync::atomic::Ordering::Relaxed);
         let slow_threshold = Duration::from_secs(slow_threshold_secs);
-        let mut slow_timer = SlowTimer::new(request_id.as_str(), sql, slow_threshold);
+        // The password of the user statements must not be logged.
+        let logged_sql = auth::redact_sql(sql);
+        let mut slow_timer = SlowTimer::new(request_id.as_str(), &logged_sql, slow_threshold);
         let deadline = ctx.timeout.map(|t| slow_timer.start_time() + t);
         let catalog = self.instance.catalog_manager.default_catalog_name();
 
-        info!("Handle sql query begin, request_id:{request_id}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}, sql:{sql}");
+        info!("Handle sql query begin, request_id:{request_id}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}, sql:{logged_sql}");
+        self.check_role(ctx.auth_user.as_ref(), catalog, UserRole::ReadOnly)?;
 
         let instance = &self.instance;
         // TODO(yingwen): Privilege check, cannot access data of other tenant
@@ -198,8 +226,11 @@ impl Proxy {
         let frontend = Frontend::new(provider, instance.dyn_config.fronted.clone());
 
         let mut sql_ctx = SqlContext::new(request_id.clone(), deadline);
+        sql_ctx.user = ctx.auth_user.as_ref().map(|v| v.name().to_string());
+        sql_ctx.warnings = ctx.warnings.clone();
         // Parse sql, frontend error of invalid sql already contains sql
         // TODO(yingwen): Maybe move sql from frontend error to outer error
+        let parse_begin = Instant::now();
         let mut stmts = frontend
             .parse_sql(&mut sql_ctx, sql)
             .box_err()
@@ -207,6 +238,7 @@ impl Proxy {
                 code: StatusCode::BAD_REQUEST,
                 msg: "Failed to parse sql",
             })?;
+        let parse_elapsed = parse_begin.elapsed();
 
         // TODO: For simplicity, we only support executing one statement
         let stmts_len = stmts.len();
@@ -218,6 +250,24 @@ impl Proxy {
             }
         );
 
+        let explain_settings = match &stmts[0] {
+            Statement::ExplainWithSettings(_) => true,
+            Statement::ExplainJson(explain) => explain.with_settings,
+            _ => false,
+        };
+        let explain_analyze = match &stmts[0] {
+            Statement::Standard(stmt) | Statement::ExplainWithSettings(stmt) => {
+                matches!(**stmt, SqlStatement::Explain { analyze: true, .. })
+            }
+            Statement::ExplainJson(explain) => {
+                matches!(
+                    *explain.statement,
+                    SqlStatement::Explain { analyze: true, .. }
+                )
+            }
+            _ => false,
+        };
+
         // Open partition table if needed.
         let table_name = frontend::parse_table_name(&stmts);
         if let Some(table_name) = &table_name {
@@ -227,6 +277,7 @@ impl Proxy {
 
         // Create logical plan
         // Note: Remember to store sql in error when creating logical plan
+        let plan_begin = Instant::now();
         let plan = frontend
             // TODO(yingwen): Check error, some error may indicate that the sql is invalid. Now we
             // return internal server error in those cases
@@ -236,6 +287,22 @@ impl Proxy {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 msg: "Failed to create plan",
             })?;
+        let plan_elapsed = plan_begin.elapsed();
+
+        ensure!(
+            plan.is_read_only() || !self.instance.read_only.load(Ordering::Relaxed),
+            ErrNoCause {
+                code: StatusCode::FORBIDDEN,
+                msg: "Request is rejected as the server is in read-only mode",
+            }
+        );
+        self.check_plan_role(
+            ctx.auth_user.as_ref(),
+            catalog,
+            table_name.as_deref(),
+            &plan,
+        )?;
+        let alter_system = matches!(plan, Plan::AlterSystem(_));
 
         if enable_block_query {
             self.instance
@@ -248,45 +315,257 @@ impl Proxy {
         }
 
         if let Plan::Query(plan) = &plan {
-            if let Some(priority) = plan
+            if let Some(output) = self.maybe_route_cold_query(ctx, schema, sql, plan).await? {
+                return Ok(output);
+            }
+
+            let priority = plan
                 .decide_query_priority(PriorityContext {
                     time_range_threshold: self.expensive_query_threshold,
+                    hot_time_range: self.cold_query_router.hot_time_range(),
                 })
                 .box_err()
                 .context(Internal {
                     msg: format!("Decide query priority failed, table_name:{table_name:?}"),
-                })?
-            {
+                })?;
+            // Expensive queries are rejected regardless of `enable_block_query` to
+            // protect the process from OOM.
+            self.instance
+                .limiter
+                .try_limit_query_by_memory(priority)
+                .map_err(|e| {
+                    if let Some(priority) = priority {
+                        scheduler_state().reject(priority.as_str(), e.to_string());
+                    }
+                    e
+                })
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::TOO_MANY_REQUESTS,
+                    msg: format!("Query is rejected, table_name:{table_name:?}"),
+                })?;
+            if let Some(priority) = priority {
                 slow_timer.priority(priority);
             }
+
+            let runtime = self
+                .instance
+                .query_runtime
+                .choose_runtime(&priority.unwrap_or_default());
+            self.instance
+                .admission
+                .check_load(runtime.stats().pending_task_num)
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::SERVICE_UNAVAILABLE,
+                    msg: format!("Query is rejected, table_name:{table_name:?}"),
+                })?;
         }
 
-        let output = if enable_partition_table_access {
-            self.execute_plan_involving_partition_table(
-                request_id.clone(),
-                catalog,
-                schema,
-                plan,
-                deadline,
-            )
-            .await
+        // The queries are bounded by the quotas of the tenants, except the
+        // internal ones which are admitted by the node receiving them. The
+        // `forwarded_from` is set by the clients too, so it's not trusted.
+        let _query_permit = if matches!(plan, Plan::Query(_)) && !ctx.internal {
+            let permit = self
+                .instance
+                .admission
+                .admit_query(catalog, schema)
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::TOO_MANY_REQUESTS,
+                    msg: format!("Query is rejected, table_name:{table_name:?}"),
+                })?;
+            Some(permit)
+        } else {
+            None
+        };
+
+        // Track the query until it finishes, so it can be listed and killed.
+        let query_guard = self
+            .instance
+            .query_tracker
+            .register(request_id, schema, &logged_sql);
+        let snapshot = self.query_snapshot(ctx, slow_threshold, &plan);
+        query_guard.set_snapshot(snapshot.clone());
+        let stages = query_guard.stages().unwrap_or_default();
+        stages.record(STAGE_PARSE, parse_elapsed, 0, 0);
+        stages.record(STAGE_PLAN, plan_elapsed, 0, 0);
+        // The explained output is rewritten after the execution, so it can't be
+        // streamed.
+        let result_sender = if explain_settings || explain_analyze {
+            None
         } else {
-            self.execute_plan(request_id.clone(), catalog, schema, plan, deadline)
-                .await
+            ctx.result_sender.clone()
         };
-        let output = output.box_err().with_context(|| ErrWithCause {
-            code: StatusCode::INTERNAL_SERVER_ERROR,
-            msg: "Failed to execute plan",
+        let execute = self.execute_plan_with_options(
+            request_id.clone(),
+            catalog,
+            schema,
+            plan,
+            deadline,
+            enable_partition_table_access,
+            result_sender,
+            self.result_limits
+                .of_user(ctx.auth_user.as_ref().map(|v| v.name())),
+            ctx.warnings.clone(),
+            WriteSequence::default(),
+        );
+        let output = query_guard.run(execute).await.with_context(|| ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Query is killed, request_id:{request_id}"),
+        })?;
+        let output = output.map_err(|e| Error::ErrWithCause {
+            code: e.code(),
+            msg: "Failed to execute plan".to_string(),
+            source: Box::new(e),
         })?;
+        // The system options are set on all the nodes of the cluster, and the
+        // statements forwarded from other nodes only set the local options.
+        if alter_system && ctx.forwarded_from.is_none() {
+            self.broadcast_alter_system(ctx, schema, sql).await?;
+        }
+        let output = if explain_settings {
+            Self::explain_snapshot(output, &snapshot)?
+        } else {
+            output
+        };
+        let output = if explain_analyze {
+            Self::explain_stages(output, &stages)?
+        } else {
+            output
+        };
 
         let cost = slow_timer.elapsed();
+        metrics::observe_query_duration(request_id.as_str(), cost.as_secs_f64());
+        if let Some(timeout) = ctx.timeout {
+            if cost.as_secs_f64() >= timeout.as_secs_f64() * APPROACHING_TIMEOUT_RATIO {
+                ctx.warnings.record(
+                    WarningKind::ApproachingTimeout,
+                    format!(
+                        "query is approaching the timeout, elapsed:{cost:?}, timeout:{timeout:?}"
+                    ),
+                );
+            }
+        }
         info!(
-            "Handle sql query finished, sql:{sql}, elapsed:{cost:?}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}",
+            "Handle sql query finished, sql:{logged_sql}, elapsed:{cost:?}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}",
         );
 
         Ok(output)
     }
 
+    /// Take the snapshot of the effective settings of the query and the schema
+    /// versions of the tables read by it.
+    fn query_snapshot(
+        &self,
+        ctx: &Context,
+        slow_threshold: Duration,
+        plan: &Plan,
+    ) -> QuerySnapshot {
+        let mut settings = BTreeMap::new();
+        if let Ok(serde_json::Value::Object(config)) =
+            serde_json::to_value(self.instance.query_engine.config())
+        {
+            for (key, value) in config {
+                let value = match value {
+                    serde_json::Value::String(v) => v,
+                    v => v.to_string(),
+                };
+                settings.insert(format!("query_engine.{key}"), value);
+            }
+        }
+        let frontend_config = &self.instance.dyn_config.fronted;
+        settings.insert(
+            "frontend.enable_dist_query_push_down".to_string(),
+            frontend_config
+                .enable_dist_query_push_down
+                .load(Ordering::Relaxed)
+                .to_string(),
+        );
+        if let Ok(wildcard_limit) =
+            serde_json::to_string(&*frontend_config.wildcard_limit.read().unwrap())
+        {
+            settings.insert("frontend.wildcard_limit".to_string(), wildcard_limit);
+        }
+        if let Ok(time_range_guard) =
+            serde_json::to_string(&*frontend_config.time_range_guard.read().unwrap())
+        {
+            settings.insert("frontend.time_range_guard".to_string(), time_range_guard);
+        }
+        settings.insert(
+            "expensive_query_threshold".to_string(),
+            format!("{}ms", self.expensive_query_threshold),
+        );
+        settings.insert("slow_threshold".to_string(), format!("{slow_threshold:?}"));
+        settings.insert("timeout".to_string(), format!("{:?}", ctx.timeout));
+
+        let mut table_versions = BTreeMap::new();
+        if let Plan::Query(plan) = plan {
+            let _ = plan.tables.visit::<_, ()>(|table_ref, table| {
+                table_versions.insert(table_ref.to_string(), table.schema().version());
+                Ok(())
+            });
+        }
+
+        QuerySnapshot {
+            settings,
+            table_versions,
+        }
+    }
+
+    /// Append the settings and the schema versions in the snapshot to the
+    /// output of `EXPLAIN ... WITH SETTINGS`.
+    fn explain_snapshot(output: Output, snapshot: &QuerySnapshot) -> Result<Output> {
+        let settings = snapshot
+            .settings
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let table_versions = snapshot
+            .table_versions
+            .iter()
+            .map(|(table, version)| format!("{table}={version}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Self::append_explain_rows(
+            output,
+            vec!["settings", "schema_versions"],
+            vec![settings, table_versions],
+        )
+    }
+
+    /// Explain the wall time, rows and bytes of the stages of the query in
+    /// json format.
+    fn explain_stages(output: Output, stages: &QueryStages) -> Result<Output> {
+        Self::append_explain_rows(output, vec!["stages"], vec![stages.to_json()])
+    }
+
+    fn append_explain_rows(
+        output: Output,
+        plan_types: Vec<&str>,
+        plans: Vec<String>,
+    ) -> Result<Output> {
+        let mut batches = match output {
+            Output::Records(batches) if !batches.is_empty() => batches,
+            output => return Ok(output),
+        };
+
+        let schema = batches[0].as_arrow_record_batch().schema();
+        let plan_types = StringArray::from(plan_types);
+        let plans = StringArray::from(plans);
+        let batch = ArrowRecordBatch::try_new(schema, vec![Arc::new(plan_types), Arc::new(plans)])
+            .box_err()
+            .and_then(|batch| RecordBatch::try_from(batch).box_err())
+            .context(Internal {
+                msg: "Failed to build explain output",
+            })?;
+        batches.push(batch);
+
+        Ok(Output::Records(batches))
+    }
+
     async fn maybe_forward_sql_query(
         &self,
         ctx: Context,
@@ -299,9 +578,17 @@ impl Proxy {
                 msg: "parse table name",
             })?;
         if table_name.is_none() {
-            warn!("Unable to forward sql query without table name, sql:{sql}",);
+            warn!(
+                "Unable to forward sql query without table name, sql:{}",
+                auth::redact_sql(sql)
+            );
             return Ok(None);
         }
+        let table_name = table_name.unwrap();
+        let redirect_endpoint = self
+            .instance
+            .maintenance
+            .redirect_endpoint(schema, &table_name);
 
         let sql_request = SqlQueryRequest {
             context: Some(RequestContext {
@@ -313,30 +600,32 @@ impl Proxy {
 
         let forward_req = ForwardRequest {
             schema: schema.to_string(),
-            table: table_name.unwrap(),
-            req: sql_request.into_request(),
+            table: table_name,
+            req: ctx.forwarded_request(sql_request),
             forwarded_from: ctx.forwarded_from,
         };
-        let do_query = |mut client: StorageServiceClient<Channel>,
-                        request: tonic::Request<SqlQueryRequest>,
-                        _: &Endpoint| {
-            let query = async move {
-                client
-                    .sql_query(request)
+        let warnings = ctx.warnings;
+        let forward_rpc = move |client, request, endpoint: &Endpoint| {
+            forward_sql_query(client, request, endpoint, warnings)
+        };
+        let forward_result = match redirect_endpoint {
+            // The table is under maintenance, redirect the query to the replica.
+            Some(endpoint) => {
+                let endpoint = endpoint.parse::<Endpoint>().with_context(|| ErrWithCause {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    msg: format!("Invalid redirect endpoint, endpoint:{endpoint}"),
+                })?;
+                self.forwarder
+                    .forward_with_endpoint(
+                        endpoint,
+                        forward_req.req,
+                        forward_req.forwarded_from,
+                        forward_rpc,
+                    )
                     .await
-                    .map(|resp| resp.into_inner())
-                    .box_err()
-                    .context(ErrWithCause {
-                        code: StatusCode::INTERNAL_SERVER_ERROR,
-                        msg: "Forwarded sql query failed",
-                    })
             }
-            .boxed();
-
-            Box::new(query) as _
+            None => self.forwarder.forward(forward_req, forward_rpc).await,
         };
-
-        let forward_result = self.forwarder.forward(forward_req, do_query).await;
         Ok(match forward_result {
             Ok(forward_res) => Some(forward_res),
             Err(e) => {
@@ -345,4 +634,153 @@ impl Proxy {
             }
         })
     }
+
+    /// Route the query only reading the cold data to the cold read nodes,
+    /// returns `None` if the query should be executed locally.
+    async fn maybe_route_cold_query(
+        &self,
+        ctx: &Context,
+        schema: &str,
+        sql: &str,
+        plan: &QueryPlan,
+    ) -> Result<Option<Output>> {
+        // The query routed from other nodes is always executed locally.
+        if ctx.forwarded_from.is_some() {
+            return Ok(None);
+        }
+        let endpoint = match self.cold_query_router.route(plan) {
+            Some(v) => v.clone(),
+            None => return Ok(None),
+        };
+
+        let sql_request = SqlQueryRequest {
+            context: Some(RequestContext {
+                database: schema.to_string(),
+            }),
+            tables: vec![],
+            sql: sql.to_string(),
+        };
+        let warnings = ctx.warnings.clone();
+        let forward_result = self
+            .forwarder
+            .forward_with_endpoint(
+                endpoint.clone(),
+                ctx.forwarded_request(sql_request),
+                None,
+                move |client, request, endpoint: &Endpoint| {
+                    forward_sql_query(client, request, endpoint, warnings)
+                },
+            )
+            .await;
+        match forward_result {
+            Ok(ForwardResult::Forwarded(Ok(resp))) => {
+                info!(
+                    "Cold query is routed, request_id:{}, endpoint:{endpoint:?}, sql:{sql}",
+                    ctx.request_id
+                );
+                convert_sql_response_to_output(resp).map(Some)
+            }
+            Ok(ForwardResult::Local) => Ok(None),
+            // The query is read only, so it's safe to execute it locally when the
+            // cold read nodes fail to serve it.
+            Ok(ForwardResult::Forwarded(Err(e))) => {
+                warn!("Cold read nodes failed to serve the query, it's executed locally, endpoint:{endpoint:?}, err:{e}");
+                self.cold_query_router.mark_unavailable();
+                Ok(None)
+            }
+            Err(e) => {
+                warn!("Failed to route cold query, it's executed locally, endpoint:{endpoint:?}, err:{e}");
+                self.cold_query_router.mark_unavailable();
+                Ok(None)
+            }
+        }
+    }
+
+    /// Execute the `ALTER SYSTEM` statement already executed locally on the
+    /// other nodes of the cluster.
+    async fn broadcast_alter_system(&self, ctx: &Context, schema: &str, sql: &str) -> Result<()> {
+        let nodes = self
+            .router
+            .fetch_nodes()
+            .await
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Failed to fetch the nodes to set the system option",
+            })?;
+
+        let mut failed_nodes = Vec::new();
+        for endpoint in nodes {
+            let sql_request = SqlQueryRequest {
+                context: Some(RequestContext {
+                    database: schema.to_string(),
+                }),
+                tables: vec![],
+                sql: sql.to_string(),
+            };
+            let warnings = ctx.warnings.clone();
+            let forward_result = self
+                .forwarder
+                .forward_with_endpoint(
+                    endpoint.clone(),
+                    ctx.forwarded_request(sql_request),
+                    None,
+                    move |client, request, endpoint: &Endpoint| {
+                        forward_sql_query(client, request, endpoint, warnings)
+                    },
+                )
+                .await;
+            let err = match forward_result {
+                Ok(ForwardResult::Local) | Ok(ForwardResult::Forwarded(Ok(_))) => continue,
+                Ok(ForwardResult::Forwarded(Err(e))) => e.to_string(),
+                Err(e) => e.to_string(),
+            };
+            error!("Failed to set system option on node, endpoint:{endpoint:?}, err:{err}");
+            failed_nodes.push(endpoint.to_string());
+        }
+
+        ensure!(
+            failed_nodes.is_empty(),
+            ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!(
+                    "System option is set on this node but failed on the nodes:{failed_nodes:?}"
+                ),
+            }
+        );
+
+        Ok(())
+    }
+}
+
+/// Forward the sql query, the warnings of it are merged into the `warnings`.
+fn forward_sql_query(
+    mut client: StorageServiceClient<Channel>,
+    request: tonic::Request<SqlQueryRequest>,
+    _: &Endpoint,
+    warnings: QueryWarnings,
+) -> Box<dyn std::future::Future<Output = Result<SqlQueryResponse>> + Send + Unpin> {
+    let query = async move {
+        client
+            .sql_query(request)
+            .await
+            .map(|resp| {
+                if let Some(Ok(v)) = resp
+                    .metadata()
+                    .get_bin(QUERY_WARNINGS_KEY)
+                    .map(|v| v.to_bytes())
+                {
+                    warnings.extend(query_warning::decode_warnings(&String::from_utf8_lossy(&v)));
+                }
+                resp.into_inner()
+            })
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Forwarded sql query failed",
+            })
+    }
+    .boxed();
+
+    Box::new(query)
 }
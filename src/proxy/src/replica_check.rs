@@ -0,0 +1,478 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Consistency check between the tables and their replicas.
+//!
+//! The replicas consume the tables by the table changes subscription, and
+//! serve the sql http API. Every `interval`, the recent windows of the tables
+//! are compared by their row counts and checksums computed by the same query
+//! on both sides, and the diverged windows are reported to the metrics and the
+//! `system.public.replica_divergences` table.
+//!
+//! The checksum of a window is the sum of the `row_hash()` of all the columns
+//! of the rows in the window, so the missing, duplicated and changed rows are
+//! all detected, and the replicas must provide the same udf.
+//!
+//! The windows closed less than `delay` ago are skipped as the replicas lag
+//! behind. A diverged replica is re-synced by `RESYNC TABLE`, which is
+//! forwarded to the node owning the table and makes the next subscription of
+//! the consumer start over with a snapshot with `reset` set.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use common_types::datum::DatumView;
+use generic_error::GenericResult;
+use interpreters::interpreter::Output;
+use logger::{error, info, warn};
+use runtime::{JoinHandle, RuntimeRef};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt};
+use system_catalog::replica_divergences::{divergence_registry, ReplicaDivergence};
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
+use time_ext::{current_time_millis, ReadableDuration};
+use tokio::sync::oneshot;
+
+use crate::{
+    error::{Internal, InternalNoCause, Result},
+    metrics::{REPLICA_CHECK_COUNTER_VEC, REPLICA_DIVERGED_WINDOWS_GAUGE_VEC},
+    schema_diff::TargetClient,
+    util::{quote_ident, quote_string},
+    Context, Proxy,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Interval between the checks.
+    pub interval: ReadableDuration,
+    /// Width of the windows compared.
+    pub window: ReadableDuration,
+    /// How far back the windows are compared.
+    pub lookback: ReadableDuration,
+    /// The windows closed within the delay are not compared.
+    pub delay: ReadableDuration,
+    /// Timeout of the queries of a table on both sides.
+    pub timeout: ReadableDuration,
+    pub replicas: Vec<ReplicaConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            interval: ReadableDuration::minutes(60),
+            window: ReadableDuration::minutes(60),
+            lookback: ReadableDuration::hours(24),
+            delay: ReadableDuration::minutes(5),
+            timeout: ReadableDuration::secs(60),
+            replicas: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReplicaConfig {
+    pub name: String,
+    /// Sql http endpoint of the replica, eg: http://127.0.0.1:5440
+    pub endpoint: String,
+    pub schema: String,
+    pub tables: Vec<String>,
+    /// Consumer of the table changes subscribed by the replica.
+    pub consumer: String,
+}
+
+/// Row count and checksum of a window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowStats {
+    pub num_rows: u64,
+    pub checksum: i64,
+}
+
+/// Build the query of the stats of the windows in `[start, end)`, grouped by
+/// the window ids, which are the timestamps divided by the window. The rows
+/// are hashed by the `columns`.
+pub fn window_stats_sql(
+    table: &str,
+    time_column: &str,
+    columns: &[String],
+    window: i64,
+    start: i64,
+    end: i64,
+) -> String {
+    let ts = format!("CAST({} AS BIGINT)", quote_ident(time_column));
+    let columns = columns
+        .iter()
+        .map(|v| quote_ident(v))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT {ts} / {window} AS window_id, count(*) AS num_rows, \
+        sum(row_hash({columns})) AS checksum FROM {} \
+        WHERE {} >= {start} AND {} < {end} GROUP BY {ts} / {window}",
+        quote_ident(table),
+        quote_ident(time_column),
+        quote_ident(time_column),
+    )
+}
+
+/// Diverged windows ordered by the window ids, with the stats of both sides.
+pub fn diff_windows(
+    local: &BTreeMap<i64, WindowStats>,
+    replica: &BTreeMap<i64, WindowStats>,
+) -> Vec<(i64, WindowStats, WindowStats)> {
+    let window_ids: BTreeSet<_> = local.keys().chain(replica.keys()).collect();
+    window_ids
+        .into_iter()
+        .filter_map(|id| {
+            let local = local.get(id).copied().unwrap_or_default();
+            let replica = replica.get(id).copied().unwrap_or_default();
+            (local != replica).then_some((*id, local, replica))
+        })
+        .collect()
+}
+
+pub type ReplicaCheckerRef = Arc<ReplicaChecker>;
+
+struct RunningCheck {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+pub struct ReplicaChecker {
+    config: Config,
+    runtime: RuntimeRef,
+    task_registry: TaskRegistryRef,
+    /// The proxy is set after it is built, as the proxy holds the checker.
+    proxy: OnceLock<Weak<Proxy>>,
+    running: Mutex<Option<RunningCheck>>,
+}
+
+impl ReplicaChecker {
+    pub fn new(config: Config, runtime: RuntimeRef, task_registry: TaskRegistryRef) -> Self {
+        Self {
+            config,
+            runtime,
+            task_registry,
+            proxy: OnceLock::new(),
+            running: Mutex::new(None),
+        }
+    }
+
+    pub fn set_proxy(&self, proxy: Weak<Proxy>) {
+        if self.proxy.set(proxy).is_err() {
+            warn!("Proxy of the replica checker is already set");
+        }
+    }
+
+    /// Start checking the replicas periodically.
+    pub fn start(&self) {
+        let runner = CheckRunner {
+            config: self.config.clone(),
+            proxy: self.proxy.get().cloned().unwrap_or_default(),
+        };
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let spec = TaskSpec::new(TaskKind::ReplicaCheck, "replicas".to_string());
+        let handle = self
+            .task_registry
+            .spawn_tracked(&self.runtime, spec, runner.run(stop_rx));
+
+        let running = RunningCheck { stop_tx, handle };
+        if let Some(old) = self.running.lock().unwrap().replace(running) {
+            old.handle.abort();
+        }
+    }
+
+    pub async fn stop(&self) {
+        let running = self.running.lock().unwrap().take();
+        if let Some(running) = running {
+            let _ = running.stop_tx.send(());
+            if running.handle.await.is_err() {
+                warn!("Replica check task is aborted");
+            }
+        }
+    }
+
+    /// Re-sync the tables of the replica, all the diverged tables of the
+    /// replica are re-synced if `tables` is empty. The re-synced tables are
+    /// returned, or `None` if the replica is not found.
+    pub async fn resync(&self, replica: &str, tables: &[String]) -> Result<Option<Vec<String>>> {
+        let Some(replica) = self.config.replicas.iter().find(|v| v.name == replica) else {
+            return Ok(None);
+        };
+        let proxy = self
+            .proxy
+            .get()
+            .and_then(Weak::upgrade)
+            .context(InternalNoCause {
+                msg: "proxy is dropped",
+            })?;
+        let tables: Vec<_> = if tables.is_empty() {
+            divergence_registry()
+                .list()
+                .into_iter()
+                .filter(|v| v.replica == replica.name && v.schema == replica.schema)
+                .map(|v| v.table)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        } else {
+            tables.to_vec()
+        };
+
+        let ctx = Context::new(Some(self.config.timeout.0), None);
+        for table in &tables {
+            info!(
+                "Re-sync replica, replica:{}, schema:{}, table:{table}, consumer:{}",
+                replica.name, replica.schema, replica.consumer
+            );
+            // The statement is forwarded to the node serving the subscriptions
+            // of the table.
+            let sql = format!(
+                "RESYNC TABLE {} FOR CONSUMER {}",
+                quote_ident(table),
+                quote_string(&replica.consumer)
+            );
+            proxy
+                .handle_sql(&ctx, &replica.schema, &sql, false, false)
+                .await?;
+        }
+
+        Ok(Some(tables))
+    }
+}
+
+struct CheckRunner {
+    config: Config,
+    proxy: Weak<Proxy>,
+}
+
+impl CheckRunner {
+    async fn run(self, mut stop_rx: oneshot::Receiver<()>) {
+        loop {
+            tokio::select! {
+                _ = &mut stop_rx => return,
+                _ = self.check_replicas() => {},
+            }
+            tokio::select! {
+                _ = &mut stop_rx => return,
+                _ = tokio::time::sleep(self.config.interval.0) => {},
+            }
+        }
+    }
+
+    async fn check_replicas(&self) {
+        for replica in &self.config.replicas {
+            for table in &replica.tables {
+                let result = match self.check_table(replica, table).await {
+                    Ok(divergences) => {
+                        let result = if divergences.is_empty() {
+                            "consistent"
+                        } else {
+                            warn!(
+                                "Replica is diverged, replica:{}, table:{table}, windows:{}",
+                                replica.name,
+                                divergences.len()
+                            );
+                            "diverged"
+                        };
+                        divergence_registry().update(
+                            &replica.name,
+                            &replica.schema,
+                            table,
+                            divergences,
+                        );
+                        result
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to check replica, replica:{}, table:{table}, err:{e}",
+                            replica.name
+                        );
+                        "failed"
+                    }
+                };
+                REPLICA_CHECK_COUNTER_VEC
+                    .with_label_values(&[&replica.name, result])
+                    .inc();
+            }
+            REPLICA_DIVERGED_WINDOWS_GAUGE_VEC
+                .with_label_values(&[&replica.name])
+                .set(divergence_registry().num_diverged(&replica.name) as i64);
+        }
+    }
+
+    async fn check_table(
+        &self,
+        replica: &ReplicaConfig,
+        table: &str,
+    ) -> Result<Vec<ReplicaDivergence>> {
+        let proxy = self.proxy.upgrade().context(InternalNoCause {
+            msg: "proxy is dropped",
+        })?;
+        let catalog = proxy.instance.catalog_manager.default_catalog_name();
+        let table_ref = proxy
+            .try_get_table(catalog, &replica.schema, table)?
+            .with_context(|| InternalNoCause {
+                msg: format!("table not found, table:{table}"),
+            })?;
+        let schema = table_ref.schema();
+        let time_column = schema.timestamp_name().to_string();
+        // The tsid is generated by the tags, and may be absent on the replicas.
+        let columns: Vec<_> = schema
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| Some(*idx) != schema.index_of_tsid())
+            .map(|(_, column)| column.name.clone())
+            .collect();
+
+        let window = self.config.window.as_millis() as i64;
+        let now = current_time_millis() as i64;
+        let end = (now - self.config.delay.as_millis() as i64).div_euclid(window) * window;
+        let start = end - self.config.lookback.as_millis() as i64;
+        let sql = window_stats_sql(table, &time_column, &columns, window, start, end);
+
+        let ctx = Context::new(Some(self.config.timeout.0), None);
+        let output = proxy
+            .fetch_sql_query_output(&ctx, &replica.schema, &sql, false, false)
+            .await?;
+        let local = local_window_stats(output)?;
+
+        let client = TargetClient::new(
+            &replica.endpoint,
+            replica.schema.clone(),
+            self.config.timeout.0,
+        )
+        .with_context(|| Internal {
+            msg: format!("failed to build client of replica, replica:{}", replica.name),
+        })?;
+        let rows = client.query(sql).await.with_context(|| Internal {
+            msg: format!("failed to query replica, replica:{}", replica.name),
+        })?;
+        let remote = remote_window_stats(rows).with_context(|| Internal {
+            msg: format!("invalid stats of replica, replica:{}", replica.name),
+        })?;
+
+        let divergences = diff_windows(&local, &remote)
+            .into_iter()
+            .map(|(id, local, remote)| ReplicaDivergence {
+                replica: replica.name.clone(),
+                schema: replica.schema.clone(),
+                table: table.to_string(),
+                window_start: id * window,
+                window_end: (id + 1) * window,
+                local_rows: local.num_rows,
+                replica_rows: remote.num_rows,
+                local_checksum: local.checksum,
+                replica_checksum: remote.checksum,
+                detected_at: now,
+            })
+            .collect();
+
+        Ok(divergences)
+    }
+}
+
+fn local_window_stats(output: Output) -> Result<BTreeMap<i64, WindowStats>> {
+    let Output::Records(batches) = output else {
+        return InternalNoCause {
+            msg: "unexpected output of window stats",
+        }
+        .fail();
+    };
+
+    let as_i64 = |v: DatumView| v.as_i64().or_else(|| v.as_u64().map(|v| v as i64));
+    let mut stats = BTreeMap::new();
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let window_id = as_i64(batch.column(0).datum_view(row));
+            let num_rows = as_i64(batch.column(1).datum_view(row));
+            let checksum = as_i64(batch.column(2).datum_view(row));
+            if let (Some(window_id), Some(num_rows)) = (window_id, num_rows) {
+                let window_stats = WindowStats {
+                    num_rows: num_rows as u64,
+                    checksum: checksum.unwrap_or_default(),
+                };
+                stats.insert(window_id, window_stats);
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+fn remote_window_stats(
+    rows: Vec<HashMap<String, Value>>,
+) -> GenericResult<BTreeMap<i64, WindowStats>> {
+    rows.into_iter()
+        .map(|row| -> GenericResult<_> {
+            let get = |name: &str| row.get(name).and_then(Value::as_i64);
+            let window_id = get("window_id").ok_or("window_id is missing")?;
+            let num_rows = get("num_rows").ok_or("num_rows is missing")?;
+            let window_stats = WindowStats {
+                num_rows: num_rows as u64,
+                checksum: get("checksum").unwrap_or_default(),
+            };
+            Ok((window_id, window_stats))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_stats_sql() {
+        assert_eq!(
+            "SELECT CAST(`ts` AS BIGINT) / 1000 AS window_id, count(*) AS num_rows, \
+            sum(row_hash(`ts`, `host`, `value`)) AS checksum FROM `t` \
+            WHERE `ts` >= 0 AND `ts` < 3000 GROUP BY CAST(`ts` AS BIGINT) / 1000",
+            window_stats_sql(
+                "t",
+                "ts",
+                &["ts".to_string(), "host".to_string(), "value".to_string()],
+                1000,
+                0,
+                3000
+            )
+        );
+    }
+
+    #[test]
+    fn test_diff_windows() {
+        let stats = |num_rows, checksum| WindowStats { num_rows, checksum };
+        let local = BTreeMap::from([(1, stats(10, 100)), (2, stats(5, 50)), (3, stats(1, 1))]);
+        let replica = BTreeMap::from([(1, stats(10, 100)), (2, stats(5, 49)), (4, stats(2, 3))]);
+
+        assert_eq!(
+            vec![
+                (2, stats(5, 50), stats(5, 49)),
+                (3, stats(1, 1), stats(0, 0)),
+                (4, stats(0, 0), stats(2, 3)),
+            ],
+            diff_windows(&local, &replica)
+        );
+    }
+}
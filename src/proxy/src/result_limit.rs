@@ -0,0 +1,124 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Limits of the query results by the authenticated users of the requests, see
+//! [interpreters::result_limit] for how they are applied.
+//!
+//! The limits are keyed by the authenticated users rather than anything set by
+//! the clients, so a client can't lift its limits by itself.
+
+use std::collections::HashMap;
+
+use interpreters::result_limit::ResultLimit;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Limits of the requests without an authenticated user, or whose user is
+    /// not listed.
+    pub default: LimitConfig,
+    /// Limits by the names of the authenticated users of the requests.
+    pub users: HashMap<String, LimitConfig>,
+}
+
+/// Limits of a class of the requests, nothing is limited by default.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LimitConfig {
+    /// The results of the queries without `LIMIT` are truncated to the rows.
+    pub default_rows: Option<usize>,
+    /// The results of the queries without `LIMIT` are truncated to the bytes.
+    pub default_bytes: Option<ReadableSize>,
+    /// The queries returning more rows fail.
+    pub max_rows: Option<usize>,
+    /// The queries returning more bytes fail.
+    pub max_bytes: Option<ReadableSize>,
+}
+
+impl From<LimitConfig> for ResultLimit {
+    fn from(config: LimitConfig) -> Self {
+        Self {
+            default_rows: config.default_rows,
+            default_bytes: config.default_bytes.map(|v| v.as_byte()),
+            max_rows: config.max_rows,
+            max_bytes: config.max_bytes.map(|v| v.as_byte()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ResultLimits {
+    default: ResultLimit,
+    users: HashMap<String, ResultLimit>,
+}
+
+impl ResultLimits {
+    pub fn new(config: &Config) -> Self {
+        let users = config
+            .users
+            .iter()
+            .map(|(user, limit)| (user.clone(), ResultLimit::from(*limit)))
+            .collect();
+        Self {
+            default: config.default.into(),
+            users,
+        }
+    }
+
+    /// Limits of the requests of the authenticated user.
+    pub fn of_user(&self, user: Option<&str>) -> ResultLimit {
+        user.and_then(|user| self.users.get(user))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_limits_of_user() {
+        let config = Config {
+            default: LimitConfig {
+                default_rows: Some(100),
+                max_rows: Some(1000),
+                ..Default::default()
+            },
+            users: HashMap::from([(
+                "export".to_string(),
+                LimitConfig {
+                    max_bytes: Some(ReadableSize::gb(1)),
+                    ..Default::default()
+                },
+            )]),
+        };
+        let limits = ResultLimits::new(&config);
+
+        let default = limits.of_user(None);
+        assert_eq!(Some(100), default.default_rows);
+        assert_eq!(Some(1000), default.max_rows);
+        assert_eq!(default, limits.of_user(Some("unknown")));
+
+        let export = limits.of_user(Some("export"));
+        assert_eq!(None, export.default_rows);
+        assert_eq!(None, export.max_rows);
+        assert_eq!(Some(ReadableSize::gb(1).as_byte()), export.max_bytes);
+    }
+}
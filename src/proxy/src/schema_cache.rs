@@ -0,0 +1,284 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cache of the tables looked up by the writes.
+//!
+//! The writes of all the protocols look up their tables from the catalog by
+//! the names, which takes several locks on the hot path. The tables are cached
+//! once found, and invalidated by the notifications of the DDLs, see
+//! [SchemaEventBus]. The DDLs not going through this node's proxy, e.g. done by
+//! the meta or the remote engine, are only known by the table engines, so all
+//! the tables are invalidated once the [ddl_version] of the engines changes.
+//!
+//! Every invalidation bumps the version of the cache, and the tables looked up
+//! before an invalidation are not cached, so a table dropped concurrently
+//! won't be cached after its invalidation.
+//!
+//! [SchemaEventBus]: crate::schema_events::SchemaEventBus
+//! [ddl_version]: table_engine::event::ddl_version
+
+use std::{collections::HashMap, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use table_engine::{event, table::TableRef};
+
+use crate::{metrics::SCHEMA_CACHE_COUNTER_VEC, schema_events::SchemaEvent};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max number of the cached tables, and all the tables are evicted once
+    /// it's reached.
+    pub capacity: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            capacity: 100_000,
+        }
+    }
+}
+
+/// Tables indexed by the catalog, schema and table names, so they can be
+/// looked up by the borrowed names.
+type Tables = HashMap<String, HashMap<String, HashMap<String, TableRef>>>;
+
+#[derive(Default)]
+struct Inner {
+    version: u64,
+    /// The [event::ddl_version] the cached tables are looked up at.
+    ddl_version: u64,
+    num_tables: usize,
+    tables: Tables,
+}
+
+pub struct SchemaCache {
+    config: Config,
+    inner: RwLock<Inner>,
+}
+
+impl SchemaCache {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            inner: RwLock::new(Inner::default()),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.config.enable
+    }
+
+    /// Version of the cache, which should be taken before looking up the
+    /// table to cache.
+    pub fn version(&self) -> u64 {
+        self.invalidate_on_ddl();
+        self.inner.read().unwrap().version
+    }
+
+    pub fn get(&self, catalog: &str, schema: &str, table: &str) -> Option<TableRef> {
+        self.invalidate_on_ddl();
+        let table = self
+            .inner
+            .read()
+            .unwrap()
+            .tables
+            .get(catalog)
+            .and_then(|schemas| schemas.get(schema))
+            .and_then(|tables| tables.get(table))
+            .cloned();
+        let label = if table.is_some() { "hit" } else { "miss" };
+        SCHEMA_CACHE_COUNTER_VEC.with_label_values(&[label]).inc();
+
+        table
+    }
+
+    /// Cache the `table` looked up at the `version`, and it's ignored if the
+    /// cache has been invalidated since then.
+    pub fn put(&self, catalog: &str, schema: &str, table: TableRef, version: u64) {
+        let mut inner = self.inner.write().unwrap();
+        if inner.version != version {
+            return;
+        }
+        if inner.num_tables >= self.config.capacity {
+            inner.tables.clear();
+            inner.num_tables = 0;
+        }
+
+        let replaced = inner
+            .tables
+            .entry(catalog.to_string())
+            .or_default()
+            .entry(schema.to_string())
+            .or_default()
+            .insert(table.name().to_string(), table);
+        if replaced.is_none() {
+            inner.num_tables += 1;
+        }
+    }
+
+    pub fn invalidate(&self, catalog: &str, schema: &str, table: &str) {
+        let mut inner = self.inner.write().unwrap();
+        inner.version += 1;
+        let removed = inner
+            .tables
+            .get_mut(catalog)
+            .and_then(|schemas| schemas.get_mut(schema))
+            .and_then(|tables| tables.remove(table));
+        if removed.is_some() {
+            inner.num_tables -= 1;
+        }
+        SCHEMA_CACHE_COUNTER_VEC
+            .with_label_values(&["invalidate"])
+            .inc();
+    }
+
+    pub fn invalidate_all(&self) {
+        let mut inner = self.inner.write().unwrap();
+        Self::clear(&mut inner);
+    }
+
+    /// Invalidate all the tables if any table on this node is changed since
+    /// they are cached.
+    fn invalidate_on_ddl(&self) {
+        let ddl_version = event::ddl_version();
+        if self.inner.read().unwrap().ddl_version == ddl_version {
+            return;
+        }
+
+        let mut inner = self.inner.write().unwrap();
+        if inner.ddl_version != ddl_version {
+            inner.ddl_version = ddl_version;
+            Self::clear(&mut inner);
+        }
+    }
+
+    fn clear(inner: &mut Inner) {
+        inner.version += 1;
+        inner.tables.clear();
+        inner.num_tables = 0;
+        SCHEMA_CACHE_COUNTER_VEC
+            .with_label_values(&["invalidate"])
+            .inc();
+    }
+
+    /// Invalidate the table changed by the DDL.
+    pub fn on_schema_event(&self, event: &SchemaEvent) {
+        if self.is_enabled() {
+            self.invalidate(&event.catalog, &event.schema, &event.table);
+        }
+    }
+}
+
+/// Record the lookup again of the table whose cached schema can't serve the
+/// write.
+pub fn record_stale_retry() {
+    SCHEMA_CACHE_COUNTER_VEC
+        .with_label_values(&["stale_retry"])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_types::tests::build_schema;
+    use table_engine::{event::EventKind, memory::MemoryTable, table::TableId};
+
+    use super::*;
+    use crate::schema_events::SchemaEventKind;
+
+    fn new_table(id: u64) -> TableRef {
+        Arc::new(MemoryTable::new(
+            format!("table_{id}"),
+            TableId::from(id),
+            build_schema(),
+            "memory".to_string(),
+        ))
+    }
+
+    #[test]
+    fn test_versioned_invalidation() {
+        let cache = SchemaCache::new(Config {
+            enable: true,
+            capacity: 2,
+        });
+        assert!(cache.get("horaedb", "public", "table_1").is_none());
+
+        let version = cache.version();
+        cache.put("horaedb", "public", new_table(1), version);
+        let table = cache.get("horaedb", "public", "table_1").unwrap();
+        assert_eq!(TableId::from(1), table.id());
+        assert!(cache.get("horaedb", "other", "table_1").is_none());
+
+        // The table looked up before the invalidation isn't cached.
+        let version = cache.version();
+        cache.on_schema_event(&SchemaEvent {
+            kind: SchemaEventKind::DropTable,
+            catalog: "horaedb".to_string(),
+            schema: "public".to_string(),
+            table: "table_1".to_string(),
+            timestamp: 0,
+        });
+        assert!(cache.get("horaedb", "public", "table_1").is_none());
+        cache.put("horaedb", "public", new_table(1), version);
+        assert!(cache.get("horaedb", "public", "table_1").is_none());
+
+        // All the tables are evicted once the capacity is reached.
+        let version = cache.version();
+        for id in 1..=3 {
+            cache.put("horaedb", "public", new_table(id), version);
+        }
+        assert!(cache.get("horaedb", "public", "table_1").is_none());
+        assert!(cache.get("horaedb", "public", "table_3").is_some());
+
+        cache.invalidate_all();
+        assert!(cache.get("horaedb", "public", "table_3").is_none());
+
+        // The DDLs are recorded globally, so they are tested here rather than
+        // in a parallel test.
+        let version = cache.version();
+        cache.put("horaedb", "public", new_table(1), version);
+        assert!(cache.get("horaedb", "public", "table_1").is_some());
+
+        // The events not changing the tables keep the cache.
+        event::record(
+            EventKind::FlushFinish,
+            "table_1",
+            TableId::from(1),
+            String::new(),
+        );
+        assert!(cache.get("horaedb", "public", "table_1").is_some());
+
+        // The DDL done by the engine directly, e.g. by the remote engine,
+        // invalidates the cache, and so do the tables looked up before it.
+        let version = cache.version();
+        event::record(
+            EventKind::AlterSchema,
+            "table_1",
+            TableId::from(1),
+            String::new(),
+        );
+        assert!(cache.get("horaedb", "public", "table_1").is_none());
+        cache.put("horaedb", "public", new_table(1), version);
+        assert!(cache.get("horaedb", "public", "table_1").is_none());
+    }
+}
@@ -0,0 +1,461 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Schema compatibility check between the tables of this cluster and the ones
+//! of a replication (or dual-write) target cluster.
+//!
+//! Both sides are compared by their `SHOW CREATE TABLE` statements, so the
+//! target is only required to serve the sql http API. Only the configured
+//! targets can be checked, otherwise the admin API could be used to send
+//! requests to any address reachable from the server.
+
+use std::{collections::HashMap, time::Duration};
+
+use generic_error::{BoxError, GenericResult};
+use query_frontend::{
+    ast::{CreateTable, Statement},
+    parser::{self, Parser},
+    planner,
+};
+use reqwest::redirect::Policy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlparser::ast::{ColumnDef, ColumnOption, ObjectName, TableConstraint};
+
+use crate::util::{quote_ident, quote_string};
+
+const SCHEMA_HEADER: &str = "x-horaedb-schema";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Replication works, but the target may behave differently.
+    Warning,
+    /// Replication to the target fails.
+    Error,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[schemars(rename = "SchemaDiffIssue")]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+    /// Statement to run on the target to fix the issue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Issue {
+    fn warning(message: String, suggestion: Option<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+            suggestion,
+        }
+    }
+
+    fn error(message: String, suggestion: Option<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+            suggestion,
+        }
+    }
+}
+
+/// Parse the sql returned by `SHOW CREATE TABLE`.
+pub fn parse_create_table(sql: &str) -> GenericResult<CreateTable> {
+    let mut stmts = Parser::parse_sql(sql).box_err()?;
+    match (stmts.len(), stmts.pop()) {
+        (1, Some(Statement::Create(create))) => Ok(*create),
+        _ => Err(format!("not a create table statement, sql:{sql}").into()),
+    }
+}
+
+/// Check whether the rows of the `source` table can be replicated to the
+/// `target` table.
+pub fn diff_tables(source: &CreateTable, target: &CreateTable) -> Vec<Issue> {
+    let table = table_name(&source.table_name);
+    let mut issues = Vec::new();
+
+    let target_columns: HashMap<_, _> = target
+        .columns
+        .iter()
+        .map(|col| (col.name.value.as_str(), col))
+        .collect();
+    for col in &source.columns {
+        let name = &col.name.value;
+        let target_col = match target_columns.get(name.as_str()) {
+            Some(v) => v,
+            None => {
+                issues.push(Issue::error(
+                    format!("column {name} is missing in target"),
+                    Some(format!(
+                        "ALTER TABLE {table} ADD COLUMN ({})",
+                        column_def(col)
+                    )),
+                ));
+                continue;
+            }
+        };
+
+        if !col
+            .data_type
+            .to_string()
+            .eq_ignore_ascii_case(&target_col.data_type.to_string())
+        {
+            issues.push(Issue::error(
+                format!(
+                    "column {name} has different types, source:{}, target:{}",
+                    col.data_type, target_col.data_type
+                ),
+                None,
+            ));
+        }
+        if is_tag(col) != is_tag(target_col) {
+            issues.push(Issue::error(
+                format!("column {name} is a tag only in one side"),
+                None,
+            ));
+        }
+        if is_not_null(target_col) && !is_not_null(col) {
+            issues.push(Issue::error(
+                format!("column {name} is nullable in source but not in target"),
+                None,
+            ));
+        }
+    }
+
+    for col in &target.columns {
+        let name = &col.name.value;
+        if source.columns.iter().any(|v| v.name.value == *name) {
+            continue;
+        }
+
+        if is_not_null(col) && !has_default(col) {
+            issues.push(Issue::error(
+                format!("column {name} is only in target and requires a value"),
+                None,
+            ));
+        } else {
+            issues.push(Issue::warning(
+                format!("column {name} is only in target"),
+                None,
+            ));
+        }
+    }
+
+    let (source_primary_key, source_timestamp_key) = keys(source);
+    let (target_primary_key, target_timestamp_key) = keys(target);
+    if source_primary_key != target_primary_key {
+        issues.push(Issue::error(
+            format!(
+                "primary keys are different, source:{source_primary_key:?}, target:{target_primary_key:?}"
+            ),
+            None,
+        ));
+    }
+    if source_timestamp_key != target_timestamp_key {
+        issues.push(Issue::error(
+            format!(
+                "timestamp keys are different, source:{source_timestamp_key:?}, target:{target_timestamp_key:?}"
+            ),
+            None,
+        ));
+    }
+
+    if source.partition != target.partition {
+        issues.push(Issue::warning("partitions are different".to_string(), None));
+    }
+    if !source.engine.eq_ignore_ascii_case(&target.engine) {
+        issues.push(Issue::warning(
+            format!(
+                "engines are different, source:{}, target:{}",
+                source.engine, target.engine
+            ),
+            None,
+        ));
+    }
+
+    let target_options = options(target);
+    let mut source_options: Vec<_> = options(source).into_iter().collect();
+    source_options.sort();
+    for (key, value) in source_options {
+        let target_value = target_options.get(&key);
+        if target_value == Some(&value) {
+            continue;
+        }
+
+        issues.push(Issue::warning(
+            format!("option {key} is different, source:{value}, target:{target_value:?}"),
+            Some(format!(
+                "ALTER TABLE {table} MODIFY SETTING {key}={}",
+                quote_string(&value)
+            )),
+        ));
+    }
+
+    issues
+}
+
+fn table_name(name: &ObjectName) -> String {
+    let parts: Vec<_> = name.0.iter().map(|v| quote_ident(&v.value)).collect();
+    parts.join(".")
+}
+
+/// Render the column definition with the name quoted, which is printed as is
+/// by the [ColumnDef].
+fn column_def(col: &ColumnDef) -> String {
+    let mut def = format!("{} {}", quote_ident(&col.name.value), col.data_type);
+    for option in &col.options {
+        def.push_str(&format!(" {option}"));
+    }
+    def
+}
+
+/// Whether the `target` is one of the `allowed` endpoints.
+pub fn is_allowed_target(allowed: &[String], target: &str) -> bool {
+    let target = normalize_endpoint(target);
+    allowed.iter().any(|v| normalize_endpoint(v) == target)
+}
+
+fn normalize_endpoint(endpoint: &str) -> &str {
+    endpoint.trim().trim_end_matches('/')
+}
+
+fn is_tag(col: &ColumnDef) -> bool {
+    col.options.iter().any(|v| parser::is_tag_column(&v.option))
+}
+
+fn is_not_null(col: &ColumnDef) -> bool {
+    col.options
+        .iter()
+        .any(|v| matches!(v.option, ColumnOption::NotNull))
+}
+
+fn has_default(col: &ColumnDef) -> bool {
+    col.options
+        .iter()
+        .any(|v| parser::get_default_value(&v.option).is_some())
+}
+
+/// Returns the primary key and timestamp key columns.
+fn keys(create: &CreateTable) -> (Vec<String>, Vec<String>) {
+    let mut primary_key = Vec::new();
+    let mut timestamp_key = Vec::new();
+    for constraint in &create.constraints {
+        if let TableConstraint::Unique {
+            columns,
+            is_primary,
+            ..
+        } = constraint
+        {
+            let columns = columns.iter().map(|v| v.value.clone()).collect();
+            if *is_primary {
+                primary_key = columns;
+            } else if parser::is_timestamp_key_constraint(constraint) {
+                timestamp_key = columns;
+            }
+        }
+    }
+
+    (primary_key, timestamp_key)
+}
+
+fn options(create: &CreateTable) -> HashMap<String, String> {
+    create
+        .options
+        .iter()
+        .filter_map(|opt| {
+            planner::parse_for_option(opt.value.clone())
+                .ok()
+                .flatten()
+                .map(|value| (opt.name.value.to_lowercase(), value))
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct SqlRequest {
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SqlResponse {
+    #[serde(default)]
+    rows: Vec<HashMap<String, Value>>,
+}
+
+/// Client to fetch table definitions from the sql http API of the target.
+pub struct TargetClient {
+    endpoint: String,
+    schema: String,
+    timeout: Duration,
+    client: reqwest::Client,
+}
+
+impl TargetClient {
+    pub fn new(endpoint: &str, schema: String, timeout: Duration) -> GenericResult<Self> {
+        // The redirects are not followed, or the requests could be sent to the
+        // endpoints not configured.
+        let client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .build()
+            .box_err()?;
+
+        Ok(Self {
+            endpoint: normalize_endpoint(endpoint).to_string(),
+            schema,
+            timeout,
+            client,
+        })
+    }
+
+    pub async fn list_tables(&self) -> GenericResult<Vec<String>> {
+        let rows = self.query("SHOW TABLES".to_string()).await?;
+        let tables = rows
+            .into_iter()
+            .filter_map(|row| {
+                row.into_values()
+                    .find_map(|v| v.as_str().map(|v| v.to_string()))
+            })
+            .collect();
+
+        Ok(tables)
+    }
+
+    pub async fn show_create_table(&self, table: &str) -> GenericResult<String> {
+        let sql = format!("SHOW CREATE TABLE {}", quote_ident(table));
+        let rows = self.query(sql).await?;
+        rows.into_iter()
+            .next()
+            .and_then(|mut row| row.remove("Create Table"))
+            .and_then(|v| v.as_str().map(|v| v.to_string()))
+            .ok_or_else(|| format!("no create table returned, table:{table}").into())
+    }
+
+    /// Run the query on the target, and the rows are returned as json objects.
+    pub async fn query(&self, query: String) -> GenericResult<Vec<HashMap<String, Value>>> {
+        let url = format!("{}/sql", self.endpoint);
+        let resp = self
+            .client
+            .post(&url)
+            .header(SCHEMA_HEADER, &self.schema)
+            .timeout(self.timeout)
+            .json(&SqlRequest { query })
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .box_err()?
+            .json::<SqlResponse>()
+            .await
+            .box_err()?;
+
+        Ok(resp.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "CREATE TABLE `t` (`tsid` uint64 NOT NULL, `ts` timestamp NOT NULL, \
+        `host` string TAG, `region` string TAG, `value` double, \
+        PRIMARY KEY(tsid,ts), TIMESTAMP KEY(ts)) ENGINE=Analytic WITH(ttl='7d')";
+
+    fn severities(issues: &[Issue]) -> Vec<Severity> {
+        issues.iter().map(|v| v.severity).collect()
+    }
+
+    #[test]
+    fn test_diff_same_tables() {
+        let source = parse_create_table(SOURCE).unwrap();
+        let target = parse_create_table(SOURCE).unwrap();
+        assert!(diff_tables(&source, &target).is_empty());
+    }
+
+    #[test]
+    fn test_diff_tables() {
+        let source = parse_create_table(SOURCE).unwrap();
+        let target = parse_create_table(
+            "CREATE TABLE `t` (`tsid` uint64 NOT NULL, `ts` timestamp NOT NULL, \
+            `host` string, `value` bigint, `extra` string NOT NULL, \
+            PRIMARY KEY(tsid,ts), TIMESTAMP KEY(ts)) ENGINE=Analytic WITH(ttl='1d')",
+        )
+        .unwrap();
+
+        let issues = diff_tables(&source, &target);
+        assert_eq!(
+            severities(&issues),
+            vec![
+                // host is not a tag.
+                Severity::Error,
+                // region is missing.
+                Severity::Error,
+                // value has different types.
+                Severity::Error,
+                // extra requires a value.
+                Severity::Error,
+                // ttl is different.
+                Severity::Warning,
+            ]
+        );
+        assert_eq!(
+            issues[1].suggestion.as_deref(),
+            Some("ALTER TABLE `t` ADD COLUMN (`region` STRING TAG)")
+        );
+        assert_eq!(
+            issues[4].suggestion.as_deref(),
+            Some("ALTER TABLE `t` MODIFY SETTING ttl='7d'")
+        );
+    }
+
+    #[test]
+    fn test_diff_escaped_identifiers() {
+        let source = parse_create_table(
+            "CREATE TABLE `a``b` (`ts` timestamp NOT NULL, `c``d` string TAG, \
+            TIMESTAMP KEY(ts)) ENGINE=Analytic WITH(storage_format='co''lumnar')",
+        )
+        .unwrap();
+        let target = parse_create_table(
+            "CREATE TABLE `a``b` (`ts` timestamp NOT NULL, TIMESTAMP KEY(ts)) ENGINE=Analytic",
+        )
+        .unwrap();
+
+        let suggestions: Vec<_> = diff_tables(&source, &target)
+            .into_iter()
+            .filter_map(|v| v.suggestion)
+            .collect();
+        assert_eq!(
+            suggestions,
+            vec![
+                "ALTER TABLE `a``b` ADD COLUMN (`c``d` STRING TAG)".to_string(),
+                "ALTER TABLE `a``b` MODIFY SETTING storage_format='co''lumnar'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allowed_target() {
+        let allowed = vec!["http://10.0.0.1:5440/".to_string()];
+        assert!(is_allowed_target(&allowed, "http://10.0.0.1:5440"));
+        assert!(is_allowed_target(&allowed, "http://10.0.0.1:5440/"));
+        assert!(!is_allowed_target(&allowed, "http://169.254.169.254"));
+        assert!(!is_allowed_target(&[], "http://10.0.0.1:5440"));
+    }
+}
@@ -0,0 +1,162 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Notify the subscribers about the changes of the table schemas and the
+//! lifecycle of the tables, so the cached schemas can be invalidated instead
+//! of being found stale by the failed writes.
+
+use query_frontend::plan::{AlterTableOperation, Plan};
+use serde::Serialize;
+use time_ext::current_time_millis;
+use tokio::sync::broadcast;
+
+/// Events not received by the slow subscribers are dropped once the buffer
+/// is full, and the subscribers are notified that they lagged behind.
+const EVENT_BUFFER_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaEventKind {
+    CreateTable,
+    DropTable,
+    AddColumns,
+    DropColumns,
+    RenameColumn,
+    ModifyColumn,
+    ModifyOptions,
+    SwapTable,
+}
+
+impl SchemaEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CreateTable => "create_table",
+            Self::DropTable => "drop_table",
+            Self::AddColumns => "add_columns",
+            Self::DropColumns => "drop_columns",
+            Self::RenameColumn => "rename_column",
+            Self::ModifyColumn => "modify_column",
+            Self::ModifyOptions => "modify_options",
+            Self::SwapTable => "swap_table",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaEvent {
+    pub kind: SchemaEventKind,
+    pub catalog: String,
+    pub schema: String,
+    pub table: String,
+    /// Time of the change in milliseconds.
+    pub timestamp: i64,
+}
+
+impl SchemaEvent {
+    /// Build the events of the plan, empty if the plan doesn't change any
+    /// table.
+    pub fn from_plan(catalog: &str, schema: &str, plan: &Plan) -> Vec<Self> {
+        let changes = match plan {
+            Plan::Create(plan) => vec![(SchemaEventKind::CreateTable, plan.table.clone())],
+            Plan::Drop(plan) => vec![(SchemaEventKind::DropTable, plan.table.clone())],
+            Plan::AlterTable(plan) => {
+                let kind = match plan.operations {
+                    AlterTableOperation::AddColumn(_) => SchemaEventKind::AddColumns,
+                    AlterTableOperation::DropColumn(_) => SchemaEventKind::DropColumns,
+                    AlterTableOperation::RenameColumn { .. } => SchemaEventKind::RenameColumn,
+                    AlterTableOperation::ModifyColumn { .. } => SchemaEventKind::ModifyColumn,
+                    AlterTableOperation::ModifySetting(_) => SchemaEventKind::ModifyOptions,
+                };
+                vec![(kind, plan.table.name().to_string())]
+            }
+            // Both of the names are bound to another table.
+            Plan::SwapTables(plan) => vec![
+                (SchemaEventKind::SwapTable, plan.table.clone()),
+                (SchemaEventKind::SwapTable, plan.other.clone()),
+            ],
+            _ => Vec::new(),
+        };
+
+        let timestamp = current_time_millis() as i64;
+        changes
+            .into_iter()
+            .map(|(kind, table)| Self {
+                kind,
+                catalog: catalog.to_string(),
+                schema: schema.to_string(),
+                table,
+                timestamp,
+            })
+            .collect()
+    }
+}
+
+pub struct SchemaEventBus {
+    sender: broadcast::Sender<SchemaEvent>,
+}
+
+impl Default for SchemaEventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        Self { sender }
+    }
+}
+
+impl SchemaEventBus {
+    pub fn publish(&self, event: SchemaEvent) {
+        // It's fine that there is no subscriber.
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SchemaEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_and_subscribe() {
+        let bus = SchemaEventBus::default();
+        // Publish without any subscriber.
+        bus.publish(SchemaEvent {
+            kind: SchemaEventKind::CreateTable,
+            catalog: "horaedb".to_string(),
+            schema: "public".to_string(),
+            table: "t0".to_string(),
+            timestamp: 0,
+        });
+
+        let mut receiver = bus.subscribe();
+        bus.publish(SchemaEvent {
+            kind: SchemaEventKind::DropTable,
+            catalog: "horaedb".to_string(),
+            schema: "public".to_string(),
+            table: "t1".to_string(),
+            timestamp: 0,
+        });
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.kind, SchemaEventKind::DropTable);
+        assert_eq!(event.table, "t1");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"kind":"drop_table","catalog":"horaedb","schema":"public","table":"t1","timestamp":0}"#
+        );
+    }
+}
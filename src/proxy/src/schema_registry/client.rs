@@ -0,0 +1,179 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Client of the [schema registry][1], which caches the fetched schemas by id.
+//!
+//! [1]: https://docs.confluent.io/platform/current/schema-registry/develop/api.html
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use generic_error::BoxError;
+use http::StatusCode;
+use logger::info;
+use prost::Message;
+use prost_reflect::{DescriptorPool, FileDescriptor};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use time_ext::ReadableDuration;
+
+use crate::error::{ErrNoCause, ErrWithCause, Result};
+
+const SCHEMA_TYPE_AVRO: &str = "AVRO";
+const SCHEMA_TYPE_PROTOBUF: &str = "PROTOBUF";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Endpoint of the schema registry, e.g. `http://127.0.0.1:8081`.
+    ///
+    /// The ingestion of the schema registry encoded rows is disabled if it is
+    /// empty.
+    pub endpoint: String,
+    /// Timeout of fetching a schema from the registry.
+    pub timeout: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            timeout: ReadableDuration::secs(3),
+        }
+    }
+}
+
+/// Schema registered in the registry.
+#[derive(Debug)]
+pub enum Schema {
+    Avro(apache_avro::Schema),
+    Protobuf(FileDescriptor),
+}
+
+/// Response of `GET /schemas/ids/{id}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaResponse {
+    schema: String,
+    /// Absent for avro schemas.
+    schema_type: Option<String>,
+    #[serde(default)]
+    references: Vec<serde_json::Value>,
+}
+
+pub type SchemaRegistryRef = Arc<SchemaRegistry>;
+
+pub struct SchemaRegistry {
+    endpoint: String,
+    timeout: Duration,
+    client: reqwest::Client,
+    /// Schemas are immutable once registered, so they are cached forever.
+    schemas: RwLock<HashMap<u32, Arc<Schema>>>,
+}
+
+impl SchemaRegistry {
+    pub fn new(config: Config) -> Self {
+        Self {
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            timeout: config.timeout.0,
+            client: reqwest::Client::new(),
+            schemas: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get_schema(&self, id: u32) -> Result<Arc<Schema>> {
+        if let Some(schema) = self.schemas.read().unwrap().get(&id) {
+            return Ok(schema.clone());
+        }
+
+        let schema = Arc::new(self.fetch_schema(id).await?);
+        info!("Schema is fetched from the registry, id:{id}, schema:{schema:?}");
+        self.schemas.write().unwrap().insert(id, schema.clone());
+
+        Ok(schema)
+    }
+
+    async fn fetch_schema(&self, id: u32) -> Result<Schema> {
+        // The protobuf schema is returned as the serialized `FileDescriptorProto`
+        // in `serialized` format, which needs no parsing of the proto file.
+        let url = format!("{}/schemas/ids/{id}?format=serialized", self.endpoint);
+        let resp = self
+            .client
+            .get(&url)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .box_err()
+            .with_context(|| ErrWithCause {
+                code: StatusCode::BAD_GATEWAY,
+                msg: format!("failed to fetch schema from registry, url:{url}"),
+            })?
+            .json::<SchemaResponse>()
+            .await
+            .box_err()
+            .with_context(|| ErrWithCause {
+                code: StatusCode::BAD_GATEWAY,
+                msg: format!("invalid schema response from registry, url:{url}"),
+            })?;
+
+        match resp.schema_type.as_deref().unwrap_or(SCHEMA_TYPE_AVRO) {
+            SCHEMA_TYPE_AVRO => apache_avro::Schema::parse_str(&resp.schema)
+                .map(Schema::Avro)
+                .box_err()
+                .with_context(|| ErrWithCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("invalid avro schema, id:{id}"),
+                }),
+            SCHEMA_TYPE_PROTOBUF => {
+                // TODO: support the schemas referencing other schemas.
+                if !resp.references.is_empty() {
+                    return ErrNoCause {
+                        code: StatusCode::BAD_REQUEST,
+                        msg: format!("protobuf schema with references is not supported, id:{id}"),
+                    }
+                    .fail();
+                }
+                decode_file_descriptor(&resp.schema)
+                    .map(Schema::Protobuf)
+                    .with_context(|| ErrNoCause {
+                        code: StatusCode::BAD_REQUEST,
+                        msg: format!("invalid protobuf schema, id:{id}"),
+                    })
+            }
+            schema_type => ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("unsupported schema type:{schema_type}, id:{id}"),
+            }
+            .fail(),
+        }
+    }
+}
+
+/// Decode the base64 encoded `FileDescriptorProto`.
+fn decode_file_descriptor(encoded: &str) -> Option<FileDescriptor> {
+    let bytes = base64::decode(encoded).ok()?;
+    let file = prost_types::FileDescriptorProto::decode(bytes.as_slice()).ok()?;
+    let name = file.name().to_string();
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_proto(file).ok()?;
+
+    pool.get_file_by_name(&name)
+}
@@ -0,0 +1,113 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! This module implements the ingestion of the Avro or Protobuf encoded rows
+//! described by the schema registry, so the kafka pipelines can write with
+//! their current serialization.
+
+use horaedbproto::storage::{
+    RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest,
+};
+use http::StatusCode;
+use logger::debug;
+use snafu::OptionExt;
+
+use crate::{
+    context::RequestContext,
+    error::{ErrNoCause, Result},
+    metrics::HTTP_HANDLER_COUNTER_VEC,
+    schema_registry::types::{
+        convert_records, decode_record, split_records, split_schema_id, WriteRequest, WriteResponse,
+    },
+    Context, Proxy,
+};
+
+pub mod client;
+pub mod types;
+
+impl Proxy {
+    pub async fn handle_schema_registry_write(
+        &self,
+        ctx: RequestContext,
+        req: WriteRequest,
+    ) -> Result<WriteResponse> {
+        let registry = self.schema_registry.as_ref().with_context(|| ErrNoCause {
+            code: StatusCode::NOT_IMPLEMENTED,
+            msg: "schema registry is not configured",
+        })?;
+        let table = req.table().to_string();
+        if table.is_empty() {
+            return ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: "either subject or table must be set",
+            }
+            .fail();
+        }
+
+        let raw_records = split_records(req.records)?;
+        let num_rows = raw_records.len();
+        let mut records = Vec::with_capacity(num_rows);
+        for raw_record in raw_records {
+            let (schema_id, payload) = split_schema_id(raw_record)?;
+            let schema = registry.get_schema(schema_id).await?;
+            records.push(decode_record(&schema, payload)?);
+        }
+        let write_table_request = convert_records(table, &req.params, records)?;
+
+        let table_request = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: ctx.schema.clone(),
+            }),
+            table_requests: vec![write_table_request],
+        };
+        let proxy_context = Context::new(ctx.timeout, None)
+            .with_auth_user(ctx.auth_user.clone());
+
+        match self
+            .handle_write_internal(proxy_context, table_request)
+            .await
+        {
+            Ok(result) => {
+                if result.failed != 0 {
+                    HTTP_HANDLER_COUNTER_VEC.write_failed.inc();
+                    HTTP_HANDLER_COUNTER_VEC
+                        .write_failed_row
+                        .inc_by(result.failed as u64);
+                    ErrNoCause {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        msg: format!("fail to write storage, failed rows:{:?}", result.failed),
+                    }
+                    .fail()?;
+                }
+
+                debug!(
+                    "Schema registry write finished, catalog:{}, schema:{}, subject:{}, result:{result:?}",
+                    ctx.catalog, ctx.schema, req.params.subject
+                );
+
+                Ok(())
+            }
+            Err(e) => {
+                HTTP_HANDLER_COUNTER_VEC.write_failed.inc();
+                HTTP_HANDLER_COUNTER_VEC
+                    .write_failed_row
+                    .inc_by(num_rows as u64);
+                Err(e)
+            }
+        }
+    }
+}
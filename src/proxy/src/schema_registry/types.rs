@@ -0,0 +1,543 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Decode the rows encoded in the [wire format][1] of the schema registry and
+//! convert them into write requests.
+//!
+//! The body of a write request is a sequence of records, and every record is
+//! prefixed with its length as a 4-byte big-endian integer, just like the value
+//! of a kafka message prefixed with its length.
+//!
+//! Every record refers to its schema by id, so the records written by the
+//! different versions of the schema can be mixed in one request. The fields
+//! of the record are mapped to the columns with the same names, and the
+//! columns of the new fields are added automatically if `auto_create_table` is
+//! enabled.
+//!
+//! [1]: https://docs.confluent.io/platform/current/schema-registry/fundamentals/serdes-develop/index.html#wire-format
+
+use std::collections::HashMap;
+
+use apache_avro::types::Value as AvroValue;
+use bytes::{Buf, Bytes};
+use generic_error::BoxError;
+use horaedbproto::storage::{
+    value, Field, FieldGroup, Tag, Value, WriteSeriesEntry, WriteTableRequest,
+};
+use http::StatusCode;
+use prost_reflect::{DynamicMessage, FileDescriptor, MessageDescriptor, Value as ProtoValue};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt};
+
+use crate::{
+    error::{ErrNoCause, ErrWithCause, Result},
+    schema_registry::client::Schema,
+};
+
+const MAGIC_BYTE: u8 = 0;
+const DEFAULT_TIMESTAMP_FIELD: &str = "timestamp";
+/// The value of a subject is registered under `{topic}-value` by default.
+const SUBJECT_VALUE_SUFFIX: &str = "-value";
+
+#[derive(Debug)]
+pub struct WriteRequest {
+    pub records: Bytes,
+    pub params: WriteParams,
+}
+
+impl WriteRequest {
+    pub fn new(records: Bytes, params: WriteParams) -> Self {
+        Self { records, params }
+    }
+
+    /// The table to write, which is the subject without the `-value` suffix if
+    /// not set.
+    pub fn table(&self) -> &str {
+        self.params.table.as_deref().unwrap_or_else(|| {
+            self.params
+                .subject
+                .strip_suffix(SUBJECT_VALUE_SUFFIX)
+                .unwrap_or(&self.params.subject)
+        })
+    }
+}
+
+pub type WriteResponse = ();
+
+/// Query string parameters for the write api.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WriteParams {
+    /// Subject of the schema registry the records belong to.
+    pub subject: String,
+    /// Table to write, derived from the subject if not set.
+    pub table: Option<String>,
+    /// Field holding the timestamp of the record in milliseconds.
+    pub timestamp_field: String,
+    /// Comma separated fields written as tags, and the others are written as
+    /// fields.
+    pub tags: String,
+}
+
+impl Default for WriteParams {
+    fn default() -> Self {
+        Self {
+            subject: String::new(),
+            table: None,
+            timestamp_field: DEFAULT_TIMESTAMP_FIELD.to_string(),
+            tags: String::new(),
+        }
+    }
+}
+
+/// A decoded record, whose values are in the order of the schema fields.
+pub(crate) type Record = Vec<(String, value::Value)>;
+
+/// Split the length prefixed records of the body.
+pub(crate) fn split_records(mut body: Bytes) -> Result<Vec<Bytes>> {
+    let mut records = Vec::new();
+    while body.has_remaining() {
+        if body.remaining() < 4 {
+            return ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: "truncated record length",
+            }
+            .fail();
+        }
+        let len = body.get_u32() as usize;
+        if body.remaining() < len {
+            return ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!(
+                    "truncated record, expect:{len}, remaining:{}",
+                    body.remaining()
+                ),
+            }
+            .fail();
+        }
+        records.push(body.split_to(len));
+    }
+
+    Ok(records)
+}
+
+/// Split the record into the schema id and the payload.
+pub(crate) fn split_schema_id(mut record: Bytes) -> Result<(u32, Bytes)> {
+    if record.remaining() < 5 || record.get_u8() != MAGIC_BYTE {
+        return ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "record is not in the wire format of schema registry",
+        }
+        .fail();
+    }
+    let id = record.get_u32();
+
+    Ok((id, record))
+}
+
+pub(crate) fn decode_record(schema: &Schema, payload: Bytes) -> Result<Record> {
+    match schema {
+        Schema::Avro(schema) => decode_avro_record(schema, payload),
+        Schema::Protobuf(file) => decode_protobuf_record(file, payload),
+    }
+}
+
+fn decode_avro_record(schema: &apache_avro::Schema, payload: Bytes) -> Result<Record> {
+    let value = apache_avro::from_avro_datum(schema, &mut payload.as_ref(), None)
+        .box_err()
+        .context(ErrWithCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "invalid avro record",
+        })?;
+    let AvroValue::Record(fields) = value else {
+        return ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "avro schema of the record must be a record",
+        }
+        .fail();
+    };
+
+    let mut record = Vec::with_capacity(fields.len());
+    for (name, value) in fields {
+        if let Some(value) = convert_avro_value(&name, value)? {
+            record.push((name, value));
+        }
+    }
+
+    Ok(record)
+}
+
+fn convert_avro_value(name: &str, value: AvroValue) -> Result<Option<value::Value>> {
+    let value = match value {
+        AvroValue::Null => return Ok(None),
+        AvroValue::Union(_, v) => return convert_avro_value(name, *v),
+        AvroValue::Boolean(v) => value::Value::BoolValue(v),
+        AvroValue::Int(v) | AvroValue::Date(v) | AvroValue::TimeMillis(v) => {
+            value::Value::Int32Value(v)
+        }
+        AvroValue::Long(v) | AvroValue::TimeMicros(v) => value::Value::Int64Value(v),
+        AvroValue::TimestampMillis(v) => value::Value::TimestampValue(v),
+        AvroValue::TimestampMicros(v) => value::Value::TimestampValue(v / 1000),
+        AvroValue::Float(v) => value::Value::Float32Value(v),
+        AvroValue::Double(v) => value::Value::Float64Value(v),
+        AvroValue::String(v) | AvroValue::Enum(_, v) => value::Value::StringValue(v),
+        AvroValue::Uuid(v) => value::Value::StringValue(v.to_string()),
+        AvroValue::Bytes(v) | AvroValue::Fixed(_, v) => value::Value::VarbinaryValue(v),
+        v => {
+            return ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("unsupported avro value, field:{name}, value:{v:?}"),
+            }
+            .fail()
+        }
+    };
+
+    Ok(Some(value))
+}
+
+fn decode_protobuf_record(file: &FileDescriptor, mut payload: Bytes) -> Result<Record> {
+    let desc = message_descriptor(file, &mut payload)?;
+    let message = DynamicMessage::decode(desc.clone(), payload)
+        .box_err()
+        .context(ErrWithCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "invalid protobuf record",
+        })?;
+
+    let mut record = Vec::new();
+    for field in desc.fields() {
+        if field.supports_presence() && !message.has_field(&field) {
+            continue;
+        }
+        let value = match message.get_field(&field).into_owned() {
+            ProtoValue::Bool(v) => value::Value::BoolValue(v),
+            ProtoValue::I32(v) | ProtoValue::EnumNumber(v) => value::Value::Int32Value(v),
+            ProtoValue::I64(v) => value::Value::Int64Value(v),
+            ProtoValue::U32(v) => value::Value::Uint32Value(v),
+            ProtoValue::U64(v) => value::Value::Uint64Value(v),
+            ProtoValue::F32(v) => value::Value::Float32Value(v),
+            ProtoValue::F64(v) => value::Value::Float64Value(v),
+            ProtoValue::String(v) => value::Value::StringValue(v),
+            ProtoValue::Bytes(v) => value::Value::VarbinaryValue(v.to_vec()),
+            v => {
+                return ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!(
+                        "unsupported protobuf value, field:{}, value:{v:?}",
+                        field.name()
+                    ),
+                }
+                .fail()
+            }
+        };
+        record.push((field.name().to_string(), value));
+    }
+
+    Ok(record)
+}
+
+/// Find the message of the record by the message indexes in the payload, which
+/// is the path of the message in the file, e.g. `[1, 0]` is the first nested
+/// message of the second message.
+fn message_descriptor(file: &FileDescriptor, payload: &mut Bytes) -> Result<MessageDescriptor> {
+    let indexes = read_message_indexes(payload)?;
+    let mut desc = file.messages().nth(indexes[0]);
+    for idx in &indexes[1..] {
+        desc = desc.and_then(|desc| desc.child_messages().nth(*idx));
+    }
+
+    desc.with_context(|| ErrNoCause {
+        code: StatusCode::BAD_REQUEST,
+        msg: format!("message not found in protobuf schema, indexes:{indexes:?}"),
+    })
+}
+
+/// Read the message indexes, which are encoded as a count followed by the
+/// indexes, all in zigzag varints.
+fn read_message_indexes(payload: &mut Bytes) -> Result<Vec<usize>> {
+    let num_indexes = read_zigzag_varint(payload)?;
+    // The most common case `[0]` is encoded as a single `0`.
+    if num_indexes == 0 {
+        return Ok(vec![0]);
+    }
+    // Every index takes at least one byte.
+    if num_indexes < 0 || num_indexes as u64 > payload.len() as u64 {
+        return ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("invalid number of message indexes, num:{num_indexes}"),
+        }
+        .fail();
+    }
+
+    (0..num_indexes)
+        .map(|_| {
+            let idx = read_zigzag_varint(payload)?;
+            usize::try_from(idx).ok().with_context(|| ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("invalid message index, index:{idx}"),
+            })
+        })
+        .collect()
+}
+
+fn read_zigzag_varint(buf: &mut Bytes) -> Result<i64> {
+    let v = prost::encoding::decode_varint(buf)
+        .box_err()
+        .context(ErrWithCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "invalid message indexes",
+        })?;
+
+    Ok(((v >> 1) as i64) ^ -((v & 1) as i64))
+}
+
+/// Convert the records into the write request of the `table`.
+pub(crate) fn convert_records(
+    table: String,
+    params: &WriteParams,
+    records: Vec<Record>,
+) -> Result<WriteTableRequest> {
+    let tag_set: Vec<_> = params
+        .tags
+        .split(',')
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    let mut tag_indexes: HashMap<String, u32> = HashMap::new();
+    let mut field_indexes: HashMap<String, u32> = HashMap::new();
+    let mut req = WriteTableRequest {
+        table,
+        tag_names: Vec::new(),
+        field_names: Vec::new(),
+        entries: Vec::with_capacity(records.len()),
+    };
+
+    for record in records {
+        let mut timestamp = None;
+        let mut tags = Vec::new();
+        let mut fields = Vec::with_capacity(record.len());
+        for (name, value) in record {
+            if name == params.timestamp_field {
+                timestamp = Some(convert_timestamp(&name, value)?);
+            } else if tag_set.contains(&name.as_str()) {
+                let name_index = index_of(&mut tag_indexes, &mut req.tag_names, name);
+                tags.push(Tag {
+                    name_index,
+                    value: Some(Value { value: Some(value) }),
+                });
+            } else {
+                let name_index = index_of(&mut field_indexes, &mut req.field_names, name);
+                fields.push(Field {
+                    name_index,
+                    value: Some(Value { value: Some(value) }),
+                });
+            }
+        }
+
+        let timestamp = timestamp.with_context(|| ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("timestamp field {} is missing", params.timestamp_field),
+        })?;
+        req.entries.push(WriteSeriesEntry {
+            tags,
+            field_groups: vec![FieldGroup { timestamp, fields }],
+        });
+    }
+
+    Ok(req)
+}
+
+fn convert_timestamp(name: &str, value: value::Value) -> Result<i64> {
+    match value {
+        value::Value::TimestampValue(v) | value::Value::Int64Value(v) => Ok(v),
+        v => ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("invalid timestamp, field:{name}, value:{v:?}"),
+        }
+        .fail(),
+    }
+}
+
+fn index_of(indexes: &mut HashMap<String, u32>, names: &mut Vec<String>, name: String) -> u32 {
+    *indexes.entry(name).or_insert_with_key(|name| {
+        names.push(name.clone());
+        names.len() as u32 - 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BufMut;
+
+    use super::*;
+
+    fn avro_schema(fields: &str) -> Schema {
+        let raw = format!(r#"{{"type":"record","name":"cpu","fields":[{fields}]}}"#);
+        Schema::Avro(apache_avro::Schema::parse_str(&raw).unwrap())
+    }
+
+    fn encode_avro(schema: &Schema, fields: Vec<(&str, AvroValue)>) -> Bytes {
+        let Schema::Avro(schema) = schema else {
+            unreachable!()
+        };
+        let record = AvroValue::Record(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        );
+        apache_avro::to_avro_datum(schema, record).unwrap().into()
+    }
+
+    #[test]
+    fn test_split_records() {
+        let mut body = Vec::new();
+        for record in [&b"abc"[..], &b""[..], &b"de"[..]] {
+            body.put_u32(record.len() as u32);
+            body.put_slice(record);
+        }
+        let records = split_records(body.clone().into()).unwrap();
+        assert_eq!(vec!["abc", "", "de"], records);
+
+        body.truncate(body.len() - 1);
+        assert!(split_records(body.into()).is_err());
+    }
+
+    #[test]
+    fn test_split_schema_id() {
+        let record = Bytes::from_static(&[0, 0, 0, 1, 2, 42]);
+        let (id, payload) = split_schema_id(record).unwrap();
+        assert_eq!(258, id);
+        assert_eq!(&[42][..], payload);
+
+        assert!(split_schema_id(Bytes::from_static(&[1, 0, 0, 1, 2, 42])).is_err());
+        assert!(split_schema_id(Bytes::from_static(&[0, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_convert_avro_records() {
+        let v1 = avro_schema(
+            r#"{"name":"timestamp","type":"long"},{"name":"host","type":"string"},{"name":"value","type":"double"}"#,
+        );
+        // The new version of the schema adds a nullable field.
+        let v2 = avro_schema(
+            r#"{"name":"timestamp","type":"long"},{"name":"host","type":"string"},{"name":"value","type":"double"},{"name":"idc","type":["null","string"],"default":null}"#,
+        );
+
+        let records = vec![
+            decode_record(
+                &v1,
+                encode_avro(
+                    &v1,
+                    vec![
+                        ("timestamp", AvroValue::Long(1000)),
+                        ("host", AvroValue::String("h1".to_string())),
+                        ("value", AvroValue::Double(1.0)),
+                    ],
+                ),
+            )
+            .unwrap(),
+            decode_record(
+                &v2,
+                encode_avro(
+                    &v2,
+                    vec![
+                        ("timestamp", AvroValue::Long(2000)),
+                        ("host", AvroValue::String("h2".to_string())),
+                        ("value", AvroValue::Double(2.0)),
+                        (
+                            "idc",
+                            AvroValue::Union(1, Box::new(AvroValue::String("hz".to_string()))),
+                        ),
+                    ],
+                ),
+            )
+            .unwrap(),
+        ];
+
+        let params = WriteParams {
+            tags: "host,idc".to_string(),
+            ..Default::default()
+        };
+        let req = convert_records("cpu".to_string(), &params, records).unwrap();
+        assert_eq!(vec!["host", "idc"], req.tag_names);
+        assert_eq!(vec!["value"], req.field_names);
+        assert_eq!(2, req.entries.len());
+        assert_eq!(1, req.entries[0].tags.len());
+        assert_eq!(2, req.entries[1].tags.len());
+        assert_eq!(2000, req.entries[1].field_groups[0].timestamp);
+
+        let params = WriteParams {
+            timestamp_field: "ts".to_string(),
+            ..Default::default()
+        };
+        let records = vec![vec![("value".to_string(), value::Value::Float64Value(1.0))]];
+        assert!(convert_records("cpu".to_string(), &params, records).is_err());
+    }
+
+    #[test]
+    fn test_read_zigzag_varint() {
+        let mut buf = Bytes::from_static(&[0x00, 0x01, 0x02, 0x04]);
+        assert_eq!(0, read_zigzag_varint(&mut buf).unwrap());
+        assert_eq!(-1, read_zigzag_varint(&mut buf).unwrap());
+        assert_eq!(1, read_zigzag_varint(&mut buf).unwrap());
+        assert_eq!(2, read_zigzag_varint(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn test_read_message_indexes() {
+        let cases: [(&[u8], Option<Vec<usize>>); 6] = [
+            (&[0x00], Some(vec![0])),
+            (&[0x04, 0x02, 0x00], Some(vec![1, 0])),
+            // Negative number of indexes.
+            (&[0x01], None),
+            (&[0x03, 0x00], None),
+            // More indexes than the payload.
+            (&[0x7e, 0x00], None),
+            // Negative index.
+            (&[0x02, 0x01], None),
+        ];
+        for (payload, expect) in cases {
+            let mut payload = Bytes::copy_from_slice(payload);
+            let indexes = read_message_indexes(&mut payload);
+            match expect {
+                Some(expect) => assert_eq!(expect, indexes.unwrap()),
+                None => {
+                    let err = indexes.unwrap_err();
+                    assert_eq!(StatusCode::BAD_REQUEST, err.code());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_request_table() {
+        let mut req = WriteRequest::new(
+            Bytes::new(),
+            WriteParams {
+                subject: "cpu-value".to_string(),
+                ..Default::default()
+            },
+        );
+        assert_eq!("cpu", req.table());
+
+        req.params.table = Some("mem".to_string());
+        assert_eq!("mem", req.table());
+    }
+}
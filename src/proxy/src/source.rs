@@ -0,0 +1,850 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Built-in ingestion sources, which consume the kafka topics and write the
+//! messages into the tables.
+//!
+//! The sources are persisted in a system table, and only the node owning the
+//! shard of the table consumes them, so every message is written by one node
+//! even in the cluster mode. Every node syncs the sources from the table
+//! periodically, so the sources are taken over once the shard is moved.
+//!
+//! Every partition of the topic is consumed, and the messages are written in
+//! batches. The offsets of the last messages of a batch are checkpointed into
+//! the system table after the batch is written, so the source resumes from the
+//! checkpoints after restart. A batch may be written again if the server
+//! crashes before its checkpoint, so the delivery is at least once.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use async_trait::async_trait;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use generic_error::{BoxError, GenericError, GenericResult};
+use horaedbproto::storage::{
+    value, RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest,
+};
+use http::StatusCode;
+use interpreters::{
+    interpreter::Output,
+    source::{SourceManager, SourceStatus},
+};
+use logger::{error, info, warn};
+use message_queue::{
+    kafka::{
+        config::Config as KafkaConfig,
+        kafka_impl::{KafkaConsumeIterator, KafkaImpl},
+    },
+    ConsumeIterator, MessageAndOffset, Offset, StartOffset,
+};
+use query_frontend::plan::{SourceDef, SourceFormat};
+use runtime::{JoinHandle, RuntimeRef};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use snafu::{OptionExt, ResultExt};
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
+use time_ext::ReadableDuration;
+use tokio::{sync::oneshot, time::Instant};
+
+use crate::{
+    error::{ErrNoCause, ErrWithCause, Internal, InternalNoCause, Result},
+    schema_registry::types::{convert_records, Record, WriteParams},
+    Context, Proxy,
+};
+
+/// Timestamp of the row of the definition of a source in the checkpoint table.
+const DEFINITION_ROW_TS: i64 = 0;
+/// Timestamp of the row of the checkpoints of a source, which is separated from
+/// the definition so that checkpointing never overwrites a drop.
+const CHECKPOINT_ROW_TS: i64 = 1;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max rows of a batch written into the table.
+    pub max_batch_rows: usize,
+    /// Max time to wait for the messages of a batch.
+    pub max_batch_delay: ReadableDuration,
+    /// Interval to retry a source after it fails.
+    pub retry_interval: ReadableDuration,
+    /// Interval to sync the sources from the checkpoint table.
+    pub sync_interval: ReadableDuration,
+    /// Table in the default schema to persist the sources and checkpoints.
+    pub checkpoint_table: String,
+    /// Timeout of the writes and the checkpoints.
+    pub write_timeout: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_batch_rows: 1024,
+            max_batch_delay: ReadableDuration::secs(1),
+            retry_interval: ReadableDuration::secs(5),
+            sync_interval: ReadableDuration::secs(10),
+            checkpoint_table: "__source_checkpoints".to_string(),
+            write_timeout: ReadableDuration::secs(30),
+        }
+    }
+}
+
+/// Source definition persisted in the checkpoint table.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PersistedSource {
+    schema: String,
+    name: String,
+    table: String,
+    brokers: Vec<String>,
+    topic: String,
+    tags: Vec<String>,
+    timestamp_field: String,
+}
+
+impl PersistedSource {
+    fn new(schema: String, def: SourceDef) -> Self {
+        let SourceDef {
+            name,
+            table,
+            brokers,
+            topic,
+            format: SourceFormat::Json,
+            tags,
+            timestamp_field,
+        } = def;
+
+        Self {
+            schema,
+            name,
+            table,
+            brokers,
+            topic,
+            tags,
+            timestamp_field,
+        }
+    }
+}
+
+/// Partition -> offset of the last checkpointed message of the partition.
+type Checkpoints = BTreeMap<i32, Offset>;
+
+#[derive(Debug, Default)]
+struct SourceState {
+    checkpoints: Checkpoints,
+    last_error: Option<String>,
+}
+
+/// A source known by this node, which is consumed only if the node owns the
+/// checkpoint table.
+struct KnownSource {
+    source: Arc<PersistedSource>,
+    state: Arc<Mutex<SourceState>>,
+    running: Option<RunningSource>,
+}
+
+struct RunningSource {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// Rows of a source loaded from the checkpoint table.
+#[derive(Default)]
+struct LoadedSource {
+    source: Option<PersistedSource>,
+    checkpoints: Checkpoints,
+    dropped: bool,
+}
+
+pub type SourceManagerImplRef = Arc<SourceManagerImpl>;
+
+pub struct SourceManagerImpl {
+    config: Config,
+    runtime: RuntimeRef,
+    task_registry: TaskRegistryRef,
+    /// The proxy is set after it is built, as the proxy holds the manager.
+    proxy: OnceLock<Weak<Proxy>>,
+    sources: Mutex<HashMap<String, KnownSource>>,
+    sync_task: Mutex<Option<RunningSource>>,
+    /// Serialize the syncs, as the sync task and the ddls may sync at the same
+    /// time.
+    sync_lock: tokio::sync::Mutex<()>,
+}
+
+impl SourceManagerImpl {
+    pub fn new(config: Config, runtime: RuntimeRef, task_registry: TaskRegistryRef) -> Self {
+        Self {
+            config,
+            runtime,
+            task_registry,
+            proxy: OnceLock::new(),
+            sources: Mutex::new(HashMap::new()),
+            sync_task: Mutex::new(None),
+            sync_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn set_proxy(&self, proxy: Weak<Proxy>) {
+        if self.proxy.set(proxy).is_err() {
+            warn!("Proxy of the source manager is already set");
+        }
+    }
+
+    /// Load the persisted sources, and sync them periodically to consume the
+    /// sources if this node owns the checkpoint table.
+    pub async fn open(self: &Arc<Self>) -> Result<()> {
+        let proxy = self.proxy()?;
+        create_checkpoint_table(&proxy, &self.config).await?;
+        self.sync().await?;
+
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+        let manager = Arc::downgrade(self);
+        let interval = self.config.sync_interval.0;
+        let spec = TaskSpec::new(TaskKind::SourceSync, "sources");
+        let sync = async move {
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => return,
+                    _ = tokio::time::sleep(interval) => {},
+                }
+                let Some(manager) = manager.upgrade() else {
+                    return;
+                };
+                if let Err(e) = manager.sync().await {
+                    error!("Failed to sync sources, err:{e}");
+                }
+            }
+        };
+        let handle = self.task_registry.spawn_tracked(&self.runtime, spec, sync);
+        *self.sync_task.lock().unwrap() = Some(RunningSource { stop_tx, handle });
+
+        Ok(())
+    }
+
+    /// Stop consuming all the sources.
+    pub async fn stop(&self) {
+        let sync_task = self.sync_task.lock().unwrap().take();
+        if let Some(sync_task) = sync_task {
+            let _ = stop_running(sync_task).await;
+        }
+
+        let running: Vec<_> = self
+            .sources
+            .lock()
+            .unwrap()
+            .drain()
+            .filter_map(|(name, known)| known.running.map(|running| (name, running)))
+            .collect();
+        for (name, running) in running {
+            if stop_running(running).await.is_err() {
+                warn!("Source task is aborted, name:{name}");
+            }
+        }
+    }
+
+    fn proxy(&self) -> Result<Arc<Proxy>> {
+        self.proxy
+            .get()
+            .and_then(|proxy| proxy.upgrade())
+            .context(InternalNoCause {
+                msg: "proxy of the source manager is not available",
+            })
+    }
+
+    /// Sync the sources from the checkpoint table, and start (or stop)
+    /// consuming them if this node owns (or doesn't own) the table.
+    async fn sync(&self) -> Result<()> {
+        let _guard = self.sync_lock.lock().await;
+        let proxy = self.proxy()?;
+        let schema = proxy.instance.catalog_manager.default_schema_name();
+        let owned = proxy.is_local_table(schema, &self.config.checkpoint_table)?;
+        let loaded = load_sources(&proxy, &self.config).await?;
+
+        let mut to_stop = Vec::new();
+        {
+            let mut sources = self.sources.lock().unwrap();
+            sources.retain(|name, known| {
+                let alive = loaded
+                    .get(name)
+                    .map(|loaded| !loaded.dropped)
+                    .unwrap_or(false);
+                if !alive {
+                    to_stop.extend(known.running.take().map(|running| (name.clone(), running)));
+                }
+                alive
+            });
+
+            for (name, loaded) in loaded {
+                if loaded.dropped {
+                    continue;
+                }
+                let Some(source) = loaded.source else {
+                    continue;
+                };
+                let known = sources.entry(name.clone()).or_insert_with(|| KnownSource {
+                    source: Arc::new(source),
+                    state: Arc::default(),
+                    running: None,
+                });
+
+                if !owned {
+                    to_stop.extend(known.running.take().map(|running| (name, running)));
+                    continue;
+                }
+                if known.running.is_none() {
+                    info!(
+                        "Start consuming source, name:{name}, topic:{}, checkpoints:{:?}",
+                        known.source.topic, loaded.checkpoints
+                    );
+                    // The checkpoints may be advanced by the previous owner.
+                    known.state.lock().unwrap().checkpoints = loaded.checkpoints;
+                    known.running = Some(self.start_consumer(known));
+                }
+            }
+        }
+
+        for (name, running) in to_stop {
+            info!("Stop consuming source, name:{name}");
+            if stop_running(running).await.is_err() {
+                warn!("Source task is aborted, name:{name}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start_consumer(&self, known: &KnownSource) -> RunningSource {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let consumer = SourceConsumer {
+            config: self.config.clone(),
+            proxy: self.proxy.get().cloned().unwrap_or_default(),
+            source: known.source.clone(),
+            state: known.state.clone(),
+        };
+        let spec = TaskSpec::new(TaskKind::SourceConsume, known.source.name.clone());
+        let handle = self
+            .task_registry
+            .spawn_tracked(&self.runtime, spec, consumer.run(stop_rx));
+
+        RunningSource { stop_tx, handle }
+    }
+}
+
+#[async_trait]
+impl SourceManager for SourceManagerImpl {
+    fn contains(&self, name: &str) -> bool {
+        self.sources.lock().unwrap().contains_key(name)
+    }
+
+    async fn create_source(
+        &self,
+        schema: &str,
+        source: SourceDef,
+    ) -> std::result::Result<(), GenericError> {
+        let proxy = self.proxy().box_err()?;
+        create_checkpoint_table(&proxy, &self.config)
+            .await
+            .box_err()?;
+
+        let source = PersistedSource::new(schema.to_string(), source);
+        save_definition(&proxy, &self.config, &source, false)
+            .await
+            .box_err()?;
+        info!("Create source, source:{source:?}");
+        self.sync().await.box_err()
+    }
+
+    async fn drop_source(&self, name: &str) -> std::result::Result<(), GenericError> {
+        let proxy = self.proxy().box_err()?;
+        let source = self
+            .sources
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|known| known.source.clone());
+        let Some(source) = source else {
+            return Ok(());
+        };
+
+        save_definition(&proxy, &self.config, &source, true)
+            .await
+            .box_err()?;
+        info!("Drop source, name:{name}");
+        // The source consumed by other nodes is stopped in their next syncs.
+        self.sync().await.box_err()
+    }
+
+    fn list_sources(&self) -> Vec<SourceStatus> {
+        let sources = self.sources.lock().unwrap();
+        let mut statuses: Vec<_> = sources
+            .values()
+            .map(|known| {
+                let state = known.state.lock().unwrap();
+                SourceStatus {
+                    name: known.source.name.clone(),
+                    schema: known.source.schema.clone(),
+                    table: known.source.table.clone(),
+                    topic: known.source.topic.clone(),
+                    checkpoints: format_checkpoints(&state.checkpoints),
+                    active: known.running.is_some(),
+                    last_error: state.last_error.clone(),
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+
+        statuses
+    }
+}
+
+async fn stop_running(running: RunningSource) -> std::result::Result<(), runtime::Error> {
+    let _ = running.stop_tx.send(());
+    running.handle.await
+}
+
+fn format_checkpoints(checkpoints: &Checkpoints) -> String {
+    checkpoints
+        .iter()
+        .map(|(partition, offset)| format!("{partition}:{offset}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+async fn create_checkpoint_table(proxy: &Proxy, config: &Config) -> Result<()> {
+    let sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (source_name string TAG, ts timestamp NOT NULL, \
+         definition string, checkpoints string, dropped boolean, TIMESTAMP KEY(ts)) \
+         ENGINE=Analytic WITH(enable_ttl='false', update_mode='OVERWRITE')",
+        config.checkpoint_table
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+/// Load the rows of all the sources, including the dropped ones.
+async fn load_sources(proxy: &Proxy, config: &Config) -> Result<HashMap<String, LoadedSource>> {
+    let sql = format!(
+        "SELECT source_name, ts, definition, checkpoints, dropped FROM {}",
+        config.checkpoint_table
+    );
+    let output = proxy
+        .fetch_sql_query_output(
+            &Context::new(Some(config.write_timeout.0), None),
+            proxy.instance.catalog_manager.default_schema_name(),
+            &sql,
+            false,
+            false,
+        )
+        .await?;
+    let Output::Records(batches) = output else {
+        return InternalNoCause {
+            msg: "unexpected output of loading sources",
+        }
+        .fail();
+    };
+
+    let mut sources: HashMap<String, LoadedSource> = HashMap::new();
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let Some(name) = batch.column(0).datum_view(row).into_str() else {
+                continue;
+            };
+            let loaded = sources.entry(name.to_string()).or_default();
+            let ts = batch.column(1).datum_view(row).as_timestamp();
+            if ts.map(|ts| ts.as_i64()) == Some(CHECKPOINT_ROW_TS) {
+                let Some(checkpoints) = batch.column(3).datum_view(row).into_str() else {
+                    continue;
+                };
+                match serde_json::from_str(checkpoints) {
+                    Ok(v) => loaded.checkpoints = v,
+                    Err(e) => error!(
+                        "Failed to decode checkpoints, source:{name}, checkpoints:{checkpoints}, \
+                         err:{e}"
+                    ),
+                }
+                continue;
+            }
+
+            loaded.dropped = batch.column(4).datum_view(row).as_bool() == Some(true);
+            let Some(definition) = batch.column(2).datum_view(row).into_str() else {
+                continue;
+            };
+            match serde_json::from_str(definition) {
+                Ok(v) => loaded.source = Some(v),
+                Err(e) => error!("Failed to decode source, definition:{definition}, err:{e}"),
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
+/// Every source has only one row of its definition in the checkpoint table,
+/// which is overwritten as the timestamp is always the same.
+async fn save_definition(
+    proxy: &Proxy,
+    config: &Config,
+    source: &PersistedSource,
+    dropped: bool,
+) -> Result<()> {
+    let definition = serde_json::to_string(source).box_err().context(Internal {
+        msg: "failed to encode source",
+    })?;
+    let sql = format!(
+        "INSERT INTO {} (source_name, ts, definition, dropped) \
+         VALUES ('{}', {DEFINITION_ROW_TS}, '{}', {dropped})",
+        config.checkpoint_table,
+        escape_string(&source.name),
+        escape_string(&definition),
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+/// Every source has only one row of its checkpoints, like the definition.
+async fn save_checkpoints(
+    proxy: &Proxy,
+    config: &Config,
+    name: &str,
+    checkpoints: &Checkpoints,
+) -> Result<()> {
+    let checkpoints = serde_json::to_string(checkpoints)
+        .box_err()
+        .context(Internal {
+            msg: "failed to encode checkpoints",
+        })?;
+    let sql = format!(
+        "INSERT INTO {} (source_name, ts, checkpoints) \
+         VALUES ('{}', {CHECKPOINT_ROW_TS}, '{}')",
+        config.checkpoint_table,
+        escape_string(name),
+        escape_string(&checkpoints),
+    );
+    execute_sql(proxy, config, &sql).await
+}
+
+async fn execute_sql(proxy: &Proxy, config: &Config, sql: &str) -> Result<()> {
+    proxy
+        .fetch_sql_query_output(
+            &Context::new(Some(config.write_timeout.0), None),
+            proxy.instance.catalog_manager.default_schema_name(),
+            sql,
+            false,
+            false,
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn escape_string(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// Messages of a partition tagged with the partition.
+type PartitionMessages = BoxStream<'static, (i32, GenericResult<MessageAndOffset>)>;
+
+fn partition_messages(partition: i32, iter: KafkaConsumeIterator) -> PartitionMessages {
+    stream::unfold(iter, move |mut iter| async move {
+        let res = iter.next_message().await.map(|(message, _)| message);
+        Some(((partition, res.box_err()), iter))
+    })
+    .boxed()
+}
+
+struct SourceConsumer {
+    config: Config,
+    proxy: Weak<Proxy>,
+    source: Arc<PersistedSource>,
+    state: Arc<Mutex<SourceState>>,
+}
+
+impl SourceConsumer {
+    async fn run(self, mut stop_rx: oneshot::Receiver<()>) {
+        loop {
+            let res = tokio::select! {
+                _ = &mut stop_rx => return,
+                res = self.consume() => res,
+            };
+            if let Err(e) = res {
+                error!(
+                    "Failed to consume source, name:{}, topic:{}, err:{e}",
+                    self.source.name, self.source.topic
+                );
+                self.state.lock().unwrap().last_error = Some(e.to_string());
+            }
+
+            tokio::select! {
+                _ = &mut stop_rx => return,
+                _ = tokio::time::sleep(self.config.retry_interval.0) => {},
+            }
+        }
+    }
+
+    async fn consume(&self) -> Result<()> {
+        let mut kafka_config = KafkaConfig::default();
+        kafka_config.client.boost_brokers = Some(self.source.brokers.clone());
+        let kafka = KafkaImpl::new(kafka_config)
+            .await
+            .box_err()
+            .context(Internal {
+                msg: "failed to connect kafka",
+            })?;
+
+        let partitions = kafka
+            .partitions(&self.source.topic)
+            .await
+            .box_err()
+            .context(Internal {
+                msg: "failed to list partitions",
+            })?;
+        if partitions.is_empty() {
+            return InternalNoCause {
+                msg: format!("topic not found, topic:{}", self.source.topic),
+            }
+            .fail();
+        }
+
+        let checkpoints = self.state.lock().unwrap().checkpoints.clone();
+        let mut partition_streams = Vec::with_capacity(partitions.len());
+        for partition in partitions {
+            let start_offset = match checkpoints.get(&partition) {
+                Some(offset) => StartOffset::At(offset + 1),
+                None => StartOffset::Earliest,
+            };
+            let iter = kafka
+                .consume_partition(&self.source.topic, partition, start_offset)
+                .await
+                .box_err()
+                .context(Internal {
+                    msg: "failed to consume kafka",
+                })?;
+            partition_streams.push(partition_messages(partition, iter));
+        }
+        let mut messages = stream::select_all(partition_streams);
+
+        loop {
+            let mut records = Vec::new();
+            let mut last_offsets = Checkpoints::new();
+            let deadline = Instant::now() + self.config.max_batch_delay.0;
+            while records.len() < self.config.max_batch_rows {
+                let Ok(next) = tokio::time::timeout_at(deadline, messages.next()).await else {
+                    break;
+                };
+                let (partition, res) = next.context(InternalNoCause {
+                    msg: "kafka stream is closed",
+                })?;
+                let message = res.context(Internal {
+                    msg: "failed to fetch message",
+                })?;
+                last_offsets.insert(partition, message.offset);
+
+                let Some(payload) = message.message.value else {
+                    continue;
+                };
+                match json_to_records(&payload, &self.source.timestamp_field) {
+                    Ok(v) => records.extend(v),
+                    Err(e) => warn!(
+                        "Skip invalid message, source:{}, partition:{partition}, offset:{}, \
+                         err:{e}",
+                        self.source.name, message.offset
+                    ),
+                }
+            }
+
+            if !last_offsets.is_empty() {
+                self.write(records).await?;
+                self.checkpoint(last_offsets).await?;
+            }
+        }
+    }
+
+    async fn write(&self, records: Vec<Record>) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let proxy = self.proxy.upgrade().context(InternalNoCause {
+            msg: "proxy is dropped",
+        })?;
+        let params = WriteParams {
+            table: Some(self.source.table.clone()),
+            timestamp_field: self.source.timestamp_field.clone(),
+            tags: self.source.tags.join(","),
+            ..Default::default()
+        };
+        let num_rows = records.len();
+        let req = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: self.source.schema.clone(),
+            }),
+            table_requests: vec![convert_records(
+                self.source.table.clone(),
+                &params,
+                records,
+            )?],
+        };
+        let ctx = Context::new(Some(self.config.write_timeout.0), None);
+        let resp = proxy.handle_write_internal(ctx, req).await?;
+        if resp.failed != 0 {
+            return ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!(
+                    "failed to write source, rows:{num_rows}, failed:{}",
+                    resp.failed
+                ),
+            }
+            .fail();
+        }
+
+        Ok(())
+    }
+
+    async fn checkpoint(&self, last_offsets: Checkpoints) -> Result<()> {
+        let proxy = self.proxy.upgrade().context(InternalNoCause {
+            msg: "proxy is dropped",
+        })?;
+        let mut checkpoints = self.state.lock().unwrap().checkpoints.clone();
+        checkpoints.extend(last_offsets);
+        save_checkpoints(&proxy, &self.config, &self.source.name, &checkpoints).await?;
+
+        let mut state = self.state.lock().unwrap();
+        state.checkpoints = checkpoints;
+        state.last_error = None;
+
+        Ok(())
+    }
+}
+
+/// Convert a message into records, the message is a json object or an array
+/// of json objects.
+///
+/// The timestamp field is converted into an integer, and the other numbers are
+/// converted into floats so the type of a column won't change across the
+/// messages. The nulls are skipped and the nested values are written as json
+/// strings.
+pub(crate) fn json_to_records(payload: &[u8], timestamp_field: &str) -> Result<Vec<Record>> {
+    let value: JsonValue = serde_json::from_slice(payload)
+        .box_err()
+        .context(ErrWithCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: "invalid json message",
+        })?;
+
+    match value {
+        JsonValue::Array(values) => values
+            .into_iter()
+            .map(|v| json_to_record(v, timestamp_field))
+            .collect(),
+        v => Ok(vec![json_to_record(v, timestamp_field)?]),
+    }
+}
+
+fn json_to_record(value: JsonValue, timestamp_field: &str) -> Result<Record> {
+    let JsonValue::Object(object) = value else {
+        return ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("message must be a json object, value:{value}"),
+        }
+        .fail();
+    };
+
+    let mut record = Vec::with_capacity(object.len());
+    for (name, value) in object {
+        let value = match value {
+            JsonValue::Null => continue,
+            JsonValue::Number(v) if name == timestamp_field => {
+                let v = v.as_i64().with_context(|| ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("invalid timestamp, value:{v}"),
+                })?;
+                value::Value::TimestampValue(v)
+            }
+            JsonValue::Number(v) => value::Value::Float64Value(v.as_f64().unwrap_or_default()),
+            JsonValue::Bool(v) => value::Value::BoolValue(v),
+            JsonValue::String(v) => value::Value::StringValue(v),
+            v @ (JsonValue::Array(_) | JsonValue::Object(_)) => {
+                value::Value::StringValue(v.to_string())
+            }
+        };
+        record.push((name, value));
+    }
+
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_to_records() {
+        let payload = br#"[
+            {"timestamp": 1000, "host": "a", "cpu": 1, "ok": true, "extra": null},
+            {"timestamp": 2000, "host": "b", "cpu": 0.5, "labels": {"k": "v"}}
+        ]"#;
+        let records = json_to_records(payload, "timestamp").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0],
+            vec![
+                ("cpu".to_string(), value::Value::Float64Value(1.0)),
+                (
+                    "host".to_string(),
+                    value::Value::StringValue("a".to_string())
+                ),
+                ("ok".to_string(), value::Value::BoolValue(true)),
+                ("timestamp".to_string(), value::Value::TimestampValue(1000)),
+            ]
+        );
+        assert_eq!(
+            records[1][2],
+            (
+                "labels".to_string(),
+                value::Value::StringValue(r#"{"k":"v"}"#.to_string())
+            )
+        );
+
+        let records = json_to_records(br#"{"timestamp": 1}"#, "timestamp").unwrap();
+        assert_eq!(records.len(), 1);
+
+        assert!(json_to_records(br#"{"timestamp": "1"}"#, "ts").is_ok());
+        assert!(json_to_records(br#"{"timestamp": 1.5}"#, "timestamp").is_err());
+        assert!(json_to_records(b"[1]", "timestamp").is_err());
+        assert!(json_to_records(b"not json", "timestamp").is_err());
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let checkpoints: Checkpoints = [(0, 10), (2, 5)].into_iter().collect();
+        assert_eq!("0:10,2:5", format_checkpoints(&checkpoints));
+
+        // The checkpoints are persisted as json.
+        let encoded = serde_json::to_string(&checkpoints).unwrap();
+        let decoded: Checkpoints = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(checkpoints, decoded);
+    }
+
+    #[test]
+    fn test_escape_string() {
+        assert_eq!(escape_string("it's"), "it''s");
+        assert_eq!(escape_string("abc"), "abc");
+    }
+}
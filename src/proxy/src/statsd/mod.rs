@@ -0,0 +1,98 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! This module implements the ingestion of the StatsD metrics, so the legacy
+//! applications can emit the metrics to the database directly.
+
+use horaedbproto::storage::{
+    RequestContext as GrpcRequestContext, WriteRequest as GrpcWriteRequest, WriteTableRequest,
+};
+use http::StatusCode;
+use logger::debug;
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+use crate::{
+    context::RequestContext,
+    error::{ErrNoCause, Result},
+    Context, Proxy,
+};
+
+pub mod types;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    pub port: u16,
+    /// Interval to flush the aggregated metrics into the tables.
+    pub flush_interval: ReadableDuration,
+    /// Schema of the tables, the default schema is used if empty.
+    pub schema: String,
+    /// Prefix of the table names of the metrics.
+    pub table_prefix: String,
+    /// Max distinct metrics aggregated in a flush interval, the new metrics
+    /// beyond it are dropped.
+    pub max_metrics: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            port: 8125,
+            flush_interval: ReadableDuration::secs(10),
+            schema: String::new(),
+            table_prefix: String::new(),
+            max_metrics: 100_000,
+        }
+    }
+}
+
+impl Proxy {
+    pub async fn handle_statsd_write(
+        &self,
+        ctx: RequestContext,
+        table_requests: Vec<WriteTableRequest>,
+    ) -> Result<()> {
+        let num_rows: usize = table_requests.iter().map(|req| req.entries.len()).sum();
+        let req = GrpcWriteRequest {
+            context: Some(GrpcRequestContext {
+                database: ctx.schema.clone(),
+            }),
+            table_requests,
+        };
+
+        let result = self
+            .handle_write_internal(Context::new(ctx.timeout, None), req)
+            .await?;
+        if result.failed != 0 {
+            return ErrNoCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("fail to write storage, failed rows:{:?}", result.failed),
+            }
+            .fail();
+        }
+
+        debug!(
+            "StatsD write finished, catalog:{}, schema:{}, rows:{num_rows}, result:{result:?}",
+            ctx.catalog, ctx.schema
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Parse the [StatsD][1] metrics with the [DogStatsD][2] tags extension, and
+//! aggregate them over the flush interval.
+//!
+//! Every metric name is written into the table of the same name, and the tags
+//! of the metric are written as the tags of the table. The counters, gauges and
+//! sets are written into the `value` field, and the timers are summarized into
+//! the `count`, `sum`, `min`, `max` and `mean` fields.
+//!
+//! [1]: https://github.com/statsd/statsd/blob/master/docs/metric_types.md
+//! [2]: https://docs.datadoghq.com/developers/dogstatsd/datagram_shell
+
+use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet};
+
+use horaedbproto::storage::{value, WriteTableRequest};
+use http::StatusCode;
+use snafu::ensure;
+
+use crate::{
+    error::{ErrNoCause, Result},
+    schema_registry::types::{convert_records, Record, WriteParams},
+};
+
+const VALUE_FIELD: &str = "value";
+const TIMESTAMP_FIELD: &str = "timestamp";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    Counter(f64),
+    /// The value of a relative gauge is added to the current value.
+    Gauge {
+        value: f64,
+        relative: bool,
+    },
+    /// Timers, histograms and distributions are all aggregated as timers.
+    Timer(f64),
+    Set(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: String,
+    pub value: MetricValue,
+    pub sample_rate: f64,
+    pub tags: Vec<(String, String)>,
+}
+
+/// Parse a line of the format `name:value|type[|@sample_rate][|#tag:v,...]`.
+pub fn parse_line(line: &str) -> Result<Metric> {
+    let invalid = |msg: &str| {
+        ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("invalid statsd line, line:{line}, err:{msg}"),
+        }
+        .fail()
+    };
+
+    let mut sections = line.split('|');
+    let Some((name, raw_value)) = sections.next().and_then(|v| v.split_once(':')) else {
+        return invalid("missing value");
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return invalid("empty name");
+    }
+    let Some(kind) = sections.next() else {
+        return invalid("missing type");
+    };
+
+    let mut sample_rate = 1.0;
+    let mut tags = Vec::new();
+    for section in sections {
+        if let Some(rate) = section.strip_prefix('@') {
+            sample_rate = match rate.parse::<f64>() {
+                Ok(v) if v > 0.0 && v <= 1.0 => v,
+                _ => return invalid("invalid sample rate"),
+            };
+        } else if let Some(raw_tags) = section.strip_prefix('#') {
+            for tag in raw_tags.split(',').filter(|v| !v.is_empty()) {
+                let (k, v) = tag.split_once(':').unwrap_or((tag, ""));
+                tags.push((k.to_string(), v.to_string()));
+            }
+        }
+        // Other extensions like the container id are ignored.
+    }
+
+    let parse_f64 = |v: &str| v.parse::<f64>().ok().filter(|v| v.is_finite());
+    let value = match kind {
+        "c" => match parse_f64(raw_value) {
+            Some(v) => MetricValue::Counter(v),
+            None => return invalid("invalid counter"),
+        },
+        "g" => match parse_f64(raw_value) {
+            Some(v) => MetricValue::Gauge {
+                value: v,
+                relative: raw_value.starts_with(['+', '-']),
+            },
+            None => return invalid("invalid gauge"),
+        },
+        "ms" | "h" | "d" => match parse_f64(raw_value) {
+            Some(v) => MetricValue::Timer(v),
+            None => return invalid("invalid timer"),
+        },
+        "s" => MetricValue::Set(raw_value.to_string()),
+        _ => return invalid("unknown type"),
+    };
+
+    Ok(Metric {
+        name: name.to_string(),
+        value,
+        sample_rate,
+        tags,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MetricKind {
+    Counter,
+    Gauge,
+    Timer,
+    Set,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MetricKey {
+    name: String,
+    kind: MetricKind,
+    /// Sorted tags so the same tags in different orders are aggregated
+    /// together.
+    tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+enum Aggregate {
+    Counter(f64),
+    Gauge(f64),
+    Timer {
+        /// Count of the timings estimated by the sample rates.
+        count: f64,
+        /// Count of the received timings.
+        samples: usize,
+        sum: f64,
+        min: f64,
+        max: f64,
+    },
+    Set(HashSet<String>),
+}
+
+/// Aggregate the metrics received in a flush interval.
+#[derive(Debug, Default)]
+pub struct Aggregator {
+    metrics: HashMap<MetricKey, Aggregate>,
+}
+
+impl Aggregator {
+    /// Number of the distinct metrics.
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    pub fn add(&mut self, metric: Metric) {
+        let kind = match &metric.value {
+            MetricValue::Counter(_) => MetricKind::Counter,
+            MetricValue::Gauge { .. } => MetricKind::Gauge,
+            MetricValue::Timer(_) => MetricKind::Timer,
+            MetricValue::Set(_) => MetricKind::Set,
+        };
+        let key = MetricKey {
+            name: metric.name,
+            kind,
+            tags: metric.tags.into_iter().collect(),
+        };
+
+        match (self.metrics.entry(key), metric.value) {
+            (Entry::Vacant(e), value) => {
+                let aggregate = match value {
+                    MetricValue::Counter(v) => Aggregate::Counter(v / metric.sample_rate),
+                    MetricValue::Gauge { value, .. } => Aggregate::Gauge(value),
+                    MetricValue::Timer(v) => Aggregate::Timer {
+                        count: 1.0 / metric.sample_rate,
+                        samples: 1,
+                        sum: v,
+                        min: v,
+                        max: v,
+                    },
+                    MetricValue::Set(v) => Aggregate::Set(HashSet::from([v])),
+                };
+                e.insert(aggregate);
+            }
+            (Entry::Occupied(mut e), value) => match (e.get_mut(), value) {
+                (Aggregate::Counter(sum), MetricValue::Counter(v)) => {
+                    *sum += v / metric.sample_rate;
+                }
+                (Aggregate::Gauge(current), MetricValue::Gauge { value, relative }) => {
+                    if relative {
+                        *current += value;
+                    } else {
+                        *current = value;
+                    }
+                }
+                (
+                    Aggregate::Timer {
+                        count,
+                        samples,
+                        sum,
+                        min,
+                        max,
+                    },
+                    MetricValue::Timer(v),
+                ) => {
+                    *count += 1.0 / metric.sample_rate;
+                    *samples += 1;
+                    *sum += v;
+                    *min = min.min(v);
+                    *max = max.max(v);
+                }
+                (Aggregate::Set(values), MetricValue::Set(v)) => {
+                    values.insert(v);
+                }
+                // The kind is part of the key.
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    /// Take the aggregated metrics and convert them into the write requests,
+    /// the timestamp of all the rows is `timestamp`.
+    pub fn flush(&mut self, table_prefix: &str, timestamp: i64) -> Result<Vec<WriteTableRequest>> {
+        let mut records_by_table: HashMap<String, (BTreeSet<String>, Vec<Record>)> = HashMap::new();
+        for (key, aggregate) in self.metrics.drain() {
+            let table = format!("{table_prefix}{}", key.name);
+            let (tag_names, records) = records_by_table.entry(table).or_default();
+
+            let mut record = Vec::with_capacity(key.tags.len() + 5);
+            record.push((
+                TIMESTAMP_FIELD.to_string(),
+                value::Value::TimestampValue(timestamp),
+            ));
+            for (name, v) in key.tags {
+                tag_names.insert(name.clone());
+                record.push((name, value::Value::StringValue(v)));
+            }
+            match aggregate {
+                Aggregate::Counter(v) | Aggregate::Gauge(v) => {
+                    record.push((VALUE_FIELD.to_string(), value::Value::Float64Value(v)));
+                }
+                Aggregate::Timer {
+                    count,
+                    samples,
+                    sum,
+                    min,
+                    max,
+                } => {
+                    let mean = sum / samples as f64;
+                    record.extend([
+                        ("count".to_string(), value::Value::Float64Value(count)),
+                        ("sum".to_string(), value::Value::Float64Value(sum)),
+                        ("min".to_string(), value::Value::Float64Value(min)),
+                        ("max".to_string(), value::Value::Float64Value(max)),
+                        ("mean".to_string(), value::Value::Float64Value(mean)),
+                    ]);
+                }
+                Aggregate::Set(values) => {
+                    record.push((
+                        VALUE_FIELD.to_string(),
+                        value::Value::Float64Value(values.len() as f64),
+                    ));
+                }
+            }
+            records.push(record);
+        }
+
+        records_by_table
+            .into_iter()
+            .map(|(table, (tag_names, records))| {
+                ensure!(
+                    !tag_names.contains(VALUE_FIELD) && !tag_names.contains(TIMESTAMP_FIELD),
+                    ErrNoCause {
+                        code: StatusCode::BAD_REQUEST,
+                        msg: format!("reserved tag name of statsd metric, table:{table}"),
+                    }
+                );
+                let params = WriteParams {
+                    table: Some(table.clone()),
+                    timestamp_field: TIMESTAMP_FIELD.to_string(),
+                    tags: tag_names.into_iter().collect::<Vec<_>>().join(","),
+                    ..Default::default()
+                };
+                convert_records(table, &params, records)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let metric = parse_line("page.views:1|c|@0.5|#env:prod,canary").unwrap();
+        assert_eq!(
+            metric,
+            Metric {
+                name: "page.views".to_string(),
+                value: MetricValue::Counter(1.0),
+                sample_rate: 0.5,
+                tags: vec![
+                    ("env".to_string(), "prod".to_string()),
+                    ("canary".to_string(), String::new()),
+                ],
+            }
+        );
+
+        assert_eq!(
+            parse_line("fuel:-10|g").unwrap().value,
+            MetricValue::Gauge {
+                value: -10.0,
+                relative: true
+            }
+        );
+        assert_eq!(
+            parse_line("fuel:10|g").unwrap().value,
+            MetricValue::Gauge {
+                value: 10.0,
+                relative: false
+            }
+        );
+        assert_eq!(
+            parse_line("latency:320|ms").unwrap().value,
+            MetricValue::Timer(320.0)
+        );
+        assert_eq!(
+            parse_line("users:alice|s").unwrap().value,
+            MetricValue::Set("alice".to_string())
+        );
+
+        for line in [
+            "no_value",
+            ":1|c",
+            "a:1",
+            "a:x|c",
+            "a:1|unknown",
+            "a:1|c|@2",
+            "a:NaN|g",
+        ] {
+            assert!(parse_line(line).is_err(), "line:{line}");
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let mut aggregator = Aggregator::default();
+        for line in [
+            "hits:1|c|#host:a",
+            "hits:2|c|@0.5|#host:a",
+            "hits:1|c|#host:b",
+            "temp:10|g",
+            "temp:+5|g",
+            "latency:10|ms",
+            "latency:30|ms",
+            "users:a|s",
+            "users:a|s",
+            "users:b|s",
+        ] {
+            aggregator.add(parse_line(line).unwrap());
+        }
+        assert_eq!(aggregator.len(), 5);
+
+        let mut requests = aggregator.flush("statsd_", 1000).unwrap();
+        assert!(aggregator.is_empty());
+        requests.sort_by(|a, b| a.table.cmp(&b.table));
+        let tables: Vec<_> = requests.iter().map(|r| r.table.as_str()).collect();
+        assert_eq!(
+            tables,
+            vec![
+                "statsd_hits",
+                "statsd_latency",
+                "statsd_temp",
+                "statsd_users"
+            ]
+        );
+
+        let value_of = |req: &WriteTableRequest, entry: usize, field: &str| {
+            let group = &req.entries[entry].field_groups[0];
+            assert_eq!(group.timestamp, 1000);
+            group
+                .fields
+                .iter()
+                .find(|f| req.field_names[f.name_index as usize] == field)
+                .and_then(|f| f.value.clone())
+                .and_then(|v| v.value)
+        };
+
+        let hits = &requests[0];
+        assert_eq!(hits.tag_names, vec!["host".to_string()]);
+        let mut counts: Vec<_> = (0..2)
+            .map(|i| match value_of(hits, i, "value") {
+                Some(value::Value::Float64Value(v)) => v,
+                v => panic!("unexpected value:{v:?}"),
+            })
+            .collect();
+        counts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(counts, vec![1.0, 5.0]);
+
+        assert_eq!(
+            value_of(&requests[1], 0, "mean"),
+            Some(value::Value::Float64Value(20.0))
+        );
+        assert_eq!(
+            value_of(&requests[2], 0, "value"),
+            Some(value::Value::Float64Value(15.0))
+        );
+        assert_eq!(
+            value_of(&requests[3], 0, "value"),
+            Some(value::Value::Float64Value(2.0))
+        );
+    }
+}
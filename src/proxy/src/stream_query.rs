@@ -0,0 +1,108 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Limits of the streamed sql queries.
+//!
+//! The record batches of a streamed query are sent to the client as soon as
+//! they are produced by the executor instead of being buffered. The executor
+//! is paused once `channel_capacity` batches are waiting to be sent, so a slow
+//! client slows the scan down rather than growing the memory of the server.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Max number of the rows in a single response of the stream, the larger
+    /// batches are split.
+    pub batch_size: usize,
+    /// Max number of the batches buffered for a stream before the executor
+    /// is paused.
+    pub channel_capacity: usize,
+    /// Max number of the streams open at the same time, the others are
+    /// rejected.
+    pub max_open_streams: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            batch_size: 8192,
+            channel_capacity: 4,
+            max_open_streams: 256,
+        }
+    }
+}
+
+/// Guard the number of the open streams.
+pub struct StreamLimiter {
+    batch_size: usize,
+    channel_capacity: usize,
+    permits: Arc<Semaphore>,
+}
+
+impl StreamLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            batch_size: config.batch_size.max(1),
+            channel_capacity: config.channel_capacity.max(1),
+            permits: Arc::new(Semaphore::new(config.max_open_streams)),
+        }
+    }
+
+    #[inline]
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    #[inline]
+    pub fn channel_capacity(&self) -> usize {
+        self.channel_capacity
+    }
+
+    /// Try to open a stream, `None` if too many streams are open. The stream
+    /// is closed once the returned permit is dropped.
+    pub fn try_open(&self) -> Option<OwnedSemaphorePermit> {
+        self.permits.clone().try_acquire_owned().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_limiter() {
+        let limiter = StreamLimiter::new(&Config {
+            batch_size: 0,
+            channel_capacity: 0,
+            max_open_streams: 2,
+        });
+        assert_eq!(limiter.batch_size(), 1);
+        assert_eq!(limiter.channel_capacity(), 1);
+
+        let first = limiter.try_open().unwrap();
+        let _second = limiter.try_open().unwrap();
+        assert!(limiter.try_open().is_none());
+
+        drop(first);
+        assert!(limiter.try_open().is_some());
+    }
+}
@@ -0,0 +1,174 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Change stream of a single table for the replicas and the cdc pipelines.
+//!
+//! A subscription starts with a consistent snapshot of the table, followed by
+//! the rows written after the snapshot in the order of their wal sequences.
+//! Every response carries a cursor, and the subscription can be resumed from
+//! the cursor of the last applied response. If the consumer doesn't come back
+//! within the retention of the server, the changes after its cursor may be
+//! deleted, and a new snapshot is sent with `reset` set.
+//!
+//! The snapshot is read as of the sequence from which the changes are sent, so
+//! no rows are in both of them. A large snapshot is sent in several responses
+//! with `more` set except the last one, and only the last one carries the
+//! cursor. The rows of both the snapshot and the changes are masked and
+//! filtered by the policies bound to the user as the sql queries.
+//!
+//! The messages are defined here as the rpc is served by its own service, and
+//! the corresponding proto definition is:
+//!
+//! ```protobuf
+//! service TableChangesService {
+//!   rpc SubscribeTableChanges(SubscribeTableChangesRequest)
+//!       returns (stream TableChangesResponse) {}
+//! }
+//!
+//! message SubscribeTableChangesRequest {
+//!   storage.RequestContext context = 1;
+//!   string table = 2;
+//!   string consumer = 3;
+//!   string cursor = 4;
+//!   uint32 max_rows = 5;
+//! }
+//!
+//! message TableChangesResponse {
+//!   common.ResponseHeader header = 1;
+//!   bool snapshot = 2;
+//!   bool reset = 3;
+//!   uint64 start_sequence = 4;
+//!   uint64 end_sequence = 5;
+//!   storage.SqlQueryResponse result = 6;
+//!   string cursor = 7;
+//!   bool more = 8;
+//! }
+//! ```
+
+use horaedbproto::{
+    common::ResponseHeader,
+    storage::{RequestContext, SqlQueryResponse},
+};
+use http::StatusCode;
+
+use crate::error::{ErrNoCause, Result};
+
+const CURSOR_VERSION: &str = "v1";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeTableChangesRequest {
+    #[prost(message, optional, tag = "1")]
+    pub context: Option<RequestContext>,
+    #[prost(string, tag = "2")]
+    pub table: String,
+    /// Name of the consumer, the changes are retained for every consumer
+    /// separately.
+    #[prost(string, tag = "3")]
+    pub consumer: String,
+    /// Cursor of the last applied response to resume the subscription, empty
+    /// to start from a snapshot.
+    #[prost(string, tag = "4")]
+    pub cursor: String,
+    /// Max rows of the changes in a response, a default one is used if zero.
+    #[prost(uint32, tag = "5")]
+    pub max_rows: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TableChangesResponse {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<ResponseHeader>,
+    /// Whether the rows are the snapshot of the table rather than the changes.
+    #[prost(bool, tag = "2")]
+    pub snapshot: bool,
+    /// Whether the consumer should drop its state before applying the
+    /// snapshot, as the changes after its cursor are expired.
+    #[prost(bool, tag = "3")]
+    pub reset: bool,
+    /// The rows are written after this sequence, exclusive.
+    #[prost(uint64, tag = "4")]
+    pub start_sequence: u64,
+    /// The rows are written up to this sequence, inclusive.
+    #[prost(uint64, tag = "5")]
+    pub end_sequence: u64,
+    #[prost(message, optional, tag = "6")]
+    pub result: Option<SqlQueryResponse>,
+    /// Cursor to resume the subscription after this response, empty if more
+    /// rows of the snapshot follow.
+    #[prost(string, tag = "7")]
+    pub cursor: String,
+    /// Whether more rows of the snapshot follow in the next responses.
+    #[prost(bool, tag = "8")]
+    pub more: bool,
+}
+
+/// Fingerprint of the subscribed table, so the cursor can't be used to
+/// subscribe another table, or a recreated one of the same name.
+pub fn fingerprint(schema: &str, table: &str, table_id: u64) -> u64 {
+    let key = format!("{schema}/{table}/{table_id}");
+    hash_ext::hash64(key.as_bytes())
+}
+
+pub fn encode_cursor(sequence: u64, fingerprint: u64) -> String {
+    format!("{CURSOR_VERSION}.{sequence}.{fingerprint:x}")
+}
+
+/// Decode the sequence from the cursor.
+pub fn decode_cursor(cursor: &str, fingerprint: u64) -> Result<u64> {
+    let parts: Vec<_> = cursor.split('.').collect();
+    let decoded = match parts.as_slice() {
+        [CURSOR_VERSION, sequence, cursor_fingerprint] => sequence
+            .parse::<u64>()
+            .ok()
+            .zip(u64::from_str_radix(cursor_fingerprint, 16).ok()),
+        _ => None,
+    };
+
+    match decoded {
+        Some((sequence, cursor_fingerprint)) if cursor_fingerprint == fingerprint => Ok(sequence),
+        Some(_) => ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Cursor doesn't match the table, cursor:{cursor}"),
+        }
+        .fail(),
+        None => ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Invalid cursor, cursor:{cursor}"),
+        }
+        .fail(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let fp = fingerprint("public", "cpu", 1);
+        let cursor = encode_cursor(42, fp);
+        assert_eq!(42, decode_cursor(&cursor, fp).unwrap());
+
+        // Cursor of another table, or the recreated one.
+        assert!(decode_cursor(&cursor, fingerprint("public", "mem", 1)).is_err());
+        assert!(decode_cursor(&cursor, fingerprint("public", "cpu", 2)).is_err());
+
+        for cursor in ["", "v1", "v1.10", "v2.10.0", "v1.x.0", "v1.10.zz", "v1.-1.0"] {
+            assert!(decode_cursor(cursor, fp).is_err(), "{cursor}");
+        }
+    }
+}
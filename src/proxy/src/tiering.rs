@@ -0,0 +1,174 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Hot/cold query routing.
+//!
+//! The queries only reading the data older than the hot time range, decided by
+//! the time range of the predicates at planning time, are routed to the
+//! dedicated cold read nodes, or scheduled with the low priority if no cold
+//! node is configured, so the hot dashboard queries stay responsive.
+//!
+//! The cold queries fall back to the local execution with the low priority
+//! if the cold read nodes fail to serve them, and the cold read nodes are
+//! skipped for a while after the failure.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use logger::warn;
+use query_frontend::plan::QueryPlan;
+use router::endpoint::Endpoint;
+use serde::{Deserialize, Serialize};
+use time_ext::{self, ReadableDuration};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Queries only reading the data older than this range are cold, zero
+    /// means no query is cold.
+    pub hot_time_range: ReadableDuration,
+    /// Endpoint of the cold read nodes, the cold queries are executed locally
+    /// with the low priority if not set.
+    pub cold_endpoint: Option<String>,
+    /// The cold queries are executed locally during this duration after the
+    /// cold read nodes fail to serve a query.
+    pub fallback_duration: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            hot_time_range: ReadableDuration::default(),
+            cold_endpoint: None,
+            fallback_duration: ReadableDuration::secs(30),
+        }
+    }
+}
+
+pub struct ColdQueryRouter {
+    /// Hot time range in milliseconds
+    hot_time_range: u64,
+    cold_endpoint: Option<Endpoint>,
+    /// Fallback duration in milliseconds
+    fallback_duration: u64,
+    /// The cold read nodes are skipped until this time in milliseconds
+    unavailable_until: AtomicU64,
+}
+
+impl ColdQueryRouter {
+    pub fn new(config: &Config) -> Self {
+        let cold_endpoint =
+            config
+                .cold_endpoint
+                .as_ref()
+                .and_then(|endpoint| match endpoint.parse::<Endpoint>() {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        warn!("Invalid cold endpoint is ignored, endpoint:{endpoint}, err:{e}");
+                        None
+                    }
+                });
+
+        Self {
+            hot_time_range: config.hot_time_range.as_millis(),
+            cold_endpoint,
+            fallback_duration: config.fallback_duration.as_millis(),
+            unavailable_until: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn hot_time_range(&self) -> u64 {
+        self.hot_time_range
+    }
+
+    /// Returns the endpoint of the cold read nodes if the query is cold and
+    /// should be routed, otherwise the query is executed locally.
+    pub fn route(&self, plan: &QueryPlan) -> Option<&Endpoint> {
+        let endpoint = self.cold_endpoint.as_ref()?;
+        if !self.is_available_at(time_ext::current_time_millis()) {
+            return None;
+        }
+        match plan.is_cold_query(self.hot_time_range) {
+            Ok(true) => Some(endpoint),
+            Ok(false) => None,
+            Err(e) => {
+                warn!("Failed to decide whether the query is cold, err:{e}");
+                None
+            }
+        }
+    }
+
+    /// Skip the cold read nodes during the fallback duration as they fail to
+    /// serve the query.
+    pub fn mark_unavailable(&self) {
+        self.mark_unavailable_at(time_ext::current_time_millis());
+    }
+
+    fn mark_unavailable_at(&self, now: u64) {
+        self.unavailable_until
+            .store(now.saturating_add(self.fallback_duration), Ordering::Relaxed);
+    }
+
+    fn is_available_at(&self, now: u64) -> bool {
+        now >= self.unavailable_until.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cold_endpoint() {
+        let router = ColdQueryRouter::new(&Config::default());
+        assert_eq!(0, router.hot_time_range());
+        assert!(router.cold_endpoint.is_none());
+
+        let router = ColdQueryRouter::new(&Config {
+            hot_time_range: ReadableDuration::days(7),
+            cold_endpoint: Some("127.0.0.1:8831".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(7 * 24 * 3600 * 1000, router.hot_time_range());
+        assert_eq!(
+            Some("127.0.0.1:8831".parse::<Endpoint>().unwrap()),
+            router.cold_endpoint
+        );
+
+        let router = ColdQueryRouter::new(&Config {
+            hot_time_range: ReadableDuration::days(7),
+            cold_endpoint: Some("invalid".to_string()),
+            ..Default::default()
+        });
+        assert!(router.cold_endpoint.is_none());
+    }
+
+    #[test]
+    fn test_fallback() {
+        let router = ColdQueryRouter::new(&Config {
+            hot_time_range: ReadableDuration::days(7),
+            cold_endpoint: Some("127.0.0.1:8831".to_string()),
+            fallback_duration: ReadableDuration::secs(30),
+        });
+        assert!(router.is_available_at(1000));
+
+        router.mark_unavailable_at(1000);
+        assert!(!router.is_available_at(1000));
+        assert!(!router.is_available_at(30999));
+        assert!(router.is_available_at(31000));
+    }
+}
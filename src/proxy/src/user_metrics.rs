@@ -0,0 +1,350 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Label the metrics of the requests by their users, so the load can be
+//! attributed to the teams directly from the metrics.
+//!
+//! The user of a request is the authenticated user, and the schema of the
+//! request is taken if the authentication is disabled, as the tenant header is
+//! given by the clients freely. Only the `top_k` users sending the most requests
+//! of the last `refresh_interval` have their own labels, and the others share
+//! the `other` label, which bounds the cardinality of the labels. The labels of
+//! the users dropped from the top users are removed from the metrics at the
+//! refresh.
+//!
+//! The requests are counted in the sharded maps, so the requests of different
+//! users rarely contend for the same lock.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+use crate::{
+    metrics::{
+        USER_REQUEST_COUNTER_VEC, USER_REQUEST_DURATION_HISTOGRAM_VEC, USER_WRITE_ROWS_COUNTER_VEC,
+    },
+    Context, Proxy,
+};
+
+/// Label shared by the users not in the top users.
+pub const OTHER_USERS: &str = "other";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max number of the users having their own labels.
+    pub top_k: usize,
+    /// Max number of the users whose requests are counted to pick the top
+    /// users, the requests of the others are not counted.
+    pub max_tracked_users: usize,
+    /// Interval to pick the top users again.
+    pub refresh_interval: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            top_k: 20,
+            max_tracked_users: 10_000,
+            refresh_interval: ReadableDuration::minutes(5),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum RequestKind {
+    Write,
+    Query,
+}
+
+impl RequestKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RequestKind::Write => "write",
+            RequestKind::Query => "query",
+        }
+    }
+}
+
+/// Number of the shards of the request counts, the users are spread among the
+/// shards to reduce the contention of the locks.
+const NUM_SHARDS: usize = 64;
+
+pub struct UserMetrics {
+    top_k: usize,
+    max_tracked_users: usize,
+    refresh_interval: Duration,
+    /// Users having their own labels.
+    labeled: RwLock<HashSet<String>>,
+    /// Requests of the users since the last refresh.
+    counts: Vec<RwLock<HashMap<String, AtomicU64>>>,
+    /// Number of the users in the `counts`.
+    num_tracked: AtomicUsize,
+    start: Instant,
+    /// Elapsed millis of the last refresh since the `start`.
+    last_refresh: AtomicU64,
+}
+
+impl UserMetrics {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            top_k: config.top_k,
+            max_tracked_users: config.max_tracked_users,
+            refresh_interval: config.refresh_interval.0,
+            labeled: RwLock::new(HashSet::new()),
+            counts: (0..NUM_SHARDS).map(|_| RwLock::default()).collect(),
+            num_tracked: AtomicUsize::new(0),
+            start: Instant::now(),
+            last_refresh: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a finished request of the user, `rows` is the number of the rows
+    /// written by it.
+    pub fn observe(&self, user: &str, kind: RequestKind, ok: bool, rows: u64, elapsed: Duration) {
+        let (label, evicted) = self.label_of(user, Instant::now());
+        for user in evicted {
+            remove_labels(&user);
+        }
+
+        let kind = kind.as_str();
+        let result = if ok { "ok" } else { "failed" };
+        USER_REQUEST_COUNTER_VEC
+            .with_label_values(&[&label, kind, result])
+            .inc();
+        USER_REQUEST_DURATION_HISTOGRAM_VEC
+            .with_label_values(&[&label, kind])
+            .observe(elapsed.as_secs_f64());
+        if rows > 0 {
+            USER_WRITE_ROWS_COUNTER_VEC
+                .with_label_values(&[&label])
+                .inc_by(rows);
+        }
+    }
+
+    /// Count the request of the user, and returns the label of the user along
+    /// with the users dropped from the top users.
+    fn label_of(&self, user: &str, now: Instant) -> (String, Vec<String>) {
+        if user.is_empty() {
+            return (OTHER_USERS.to_string(), Vec::new());
+        }
+
+        self.count(user);
+        let evicted = self.maybe_refresh(now);
+
+        let (contained, full) = {
+            let labeled = self.labeled.read().unwrap();
+            (labeled.contains(user), labeled.len() >= self.top_k)
+        };
+        // The first users take the labels until the top users are picked.
+        let labeled = contained
+            || (!full && {
+                let mut labeled = self.labeled.write().unwrap();
+                labeled.contains(user)
+                    || (labeled.len() < self.top_k && labeled.insert(user.to_string()))
+            });
+        let label = if labeled {
+            user.to_string()
+        } else {
+            OTHER_USERS.to_string()
+        };
+
+        (label, evicted)
+    }
+
+    /// Count the request of the user, only the first request of the user since
+    /// the last refresh takes the write lock of its shard.
+    fn count(&self, user: &str) {
+        let shard = &self.counts[hash_ext::hash64(user.as_bytes()) as usize % self.counts.len()];
+        if let Some(count) = shard.read().unwrap().get(user) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        if self.num_tracked.load(Ordering::Relaxed) >= self.max_tracked_users {
+            return;
+        }
+        let mut shard = shard.write().unwrap();
+        match shard.get(user) {
+            Some(count) => {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                shard.insert(user.to_string(), AtomicU64::new(1));
+                self.num_tracked.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pick the top users again if the refresh interval elapses, returns the
+    /// users dropped. Only one of the concurrent callers does the refresh.
+    fn maybe_refresh(&self, now: Instant) -> Vec<String> {
+        let elapsed = now.duration_since(self.start).as_millis() as u64;
+        let last_refresh = self.last_refresh.load(Ordering::Relaxed);
+        if elapsed.saturating_sub(last_refresh) < self.refresh_interval.as_millis() as u64 {
+            return Vec::new();
+        }
+        if self
+            .last_refresh
+            .compare_exchange(last_refresh, elapsed, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return Vec::new();
+        }
+
+        self.refresh()
+    }
+
+    /// Pick the top users by the requests since the last refresh, returns the
+    /// users dropped.
+    fn refresh(&self) -> Vec<String> {
+        let mut counts = Vec::new();
+        for shard in &self.counts {
+            let shard = std::mem::take(&mut *shard.write().unwrap());
+            counts.extend(
+                shard
+                    .into_iter()
+                    .map(|(user, count)| (user, count.into_inner())),
+            );
+        }
+        self.num_tracked.fetch_sub(counts.len(), Ordering::Relaxed);
+
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let labeled: HashSet<_> = counts
+            .into_iter()
+            .take(self.top_k)
+            .map(|(user, _)| user)
+            .collect();
+
+        let mut state = self.labeled.write().unwrap();
+        let evicted = state.difference(&labeled).cloned().collect();
+        *state = labeled;
+        evicted
+    }
+}
+
+impl Proxy {
+    /// The user to label the metrics of the request by, `None` if the request
+    /// is not labelled, e.g. forwarded from other nodes with the internal
+    /// token.
+    pub(crate) fn metrics_user_of(&self, ctx: &Context, schema: Option<&str>) -> Option<String> {
+        if self.user_metrics.is_none() || ctx.internal {
+            return None;
+        }
+        ctx.auth_user
+            .as_ref()
+            .map(|v| v.name.as_str())
+            .or(schema)
+            .map(|v| v.to_string())
+    }
+
+    pub(crate) fn observe_user_request(
+        &self,
+        user: Option<String>,
+        kind: RequestKind,
+        ok: bool,
+        rows: u64,
+        begin_instant: Instant,
+    ) {
+        if let (Some(user_metrics), Some(user)) = (&self.user_metrics, user) {
+            user_metrics.observe(&user, kind, ok, rows, begin_instant.elapsed());
+        }
+    }
+}
+
+fn remove_labels(user: &str) {
+    for kind in [RequestKind::Write, RequestKind::Query] {
+        let kind = kind.as_str();
+        for result in ["ok", "failed"] {
+            let _ = USER_REQUEST_COUNTER_VEC.remove_label_values(&[user, kind, result]);
+        }
+        let _ = USER_REQUEST_DURATION_HISTOGRAM_VEC.remove_label_values(&[user, kind]);
+    }
+    let _ = USER_WRITE_ROWS_COUNTER_VEC.remove_label_values(&[user]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_user_metrics(top_k: usize) -> UserMetrics {
+        let config = Config {
+            enable: true,
+            top_k,
+            max_tracked_users: 100,
+            refresh_interval: ReadableDuration::secs(60),
+        };
+        UserMetrics::new(&config)
+    }
+
+    #[test]
+    fn test_label_of_top_users() {
+        let user_metrics = new_user_metrics(2);
+        let now = Instant::now();
+
+        assert_eq!("a", user_metrics.label_of("a", now).0);
+        assert_eq!("b", user_metrics.label_of("b", now).0);
+        assert_eq!(OTHER_USERS, user_metrics.label_of("c", now).0);
+        assert_eq!(OTHER_USERS, user_metrics.label_of("", now).0);
+        for _ in 0..10 {
+            user_metrics.label_of("c", now);
+        }
+        assert_eq!("a", user_metrics.label_of("a", now).0);
+
+        // The top users are picked again, and b is dropped.
+        let (label, evicted) = user_metrics.label_of("c", now + Duration::from_secs(61));
+        assert_eq!("c", label);
+        assert_eq!(vec!["b".to_string()], evicted);
+        assert_eq!(
+            OTHER_USERS,
+            user_metrics.label_of("b", now + Duration::from_secs(62)).0
+        );
+    }
+
+    #[test]
+    fn test_count_tracked_users() {
+        let user_metrics = new_user_metrics(1);
+        let now = Instant::now();
+
+        for i in 0..200 {
+            user_metrics.label_of(&format!("user-{i}"), now);
+        }
+        // The requests of the users beyond the max tracked users are not counted.
+        assert_eq!(100, user_metrics.num_tracked.load(Ordering::Relaxed));
+        let num_counted: usize = user_metrics
+            .counts
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum();
+        assert_eq!(100, num_counted);
+
+        // The counts are reset by the refresh.
+        user_metrics.label_of("user-0", now + Duration::from_secs(61));
+        assert_eq!(0, user_metrics.num_tracked.load(Ordering::Relaxed));
+        assert_eq!("user-0", user_metrics.label_of("user-0", now).0);
+    }
+}
@@ -23,6 +23,16 @@ use horaedbproto::prometheus::{
 };
 use table_engine::partition::{format_sub_partition_table_name, PartitionInfo};
 
+/// Quote the identifier to be used in the sql.
+pub fn quote_ident(ident: &str) -> String {
+    format!("`{}`", ident.replace('`', "``"))
+}
+
+/// Quote the string literal to be used in the sql.
+pub fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
 pub fn get_sub_partition_name(
     table_name: &str,
     partition_info: &PartitionInfo,
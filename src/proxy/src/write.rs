@@ -20,6 +20,7 @@
 use std::{
     cmp::max,
     collections::{BTreeMap, HashMap},
+    sync::atomic::Ordering,
     time::Instant,
 };
 
@@ -32,15 +33,17 @@ use common_types::{
     row::{Row, RowGroup},
     schema::Schema,
     time::Timestamp,
+    SequenceNumber,
 };
 use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
 use generic_error::BoxError;
 use horaedbproto::storage::{
-    storage_service_client::StorageServiceClient, value, RouteRequest as RouteRequestPb, Value,
-    WriteRequest, WriteResponse as WriteResponsePB, WriteSeriesEntry, WriteTableRequest,
+    storage_service_client::StorageServiceClient, value, FieldGroup,
+    RouteRequest as RouteRequestPb, Tag, Value, WriteRequest, WriteResponse as WriteResponsePB,
+    WriteSeriesEntry, WriteTableRequest,
 };
 use http::StatusCode;
-use interpreters::interpreter::Output;
+use interpreters::{context::WriteSequence, interpreter::Output, result_limit::ResultLimit};
 use logger::{debug, error, info, warn};
 use query_frontend::{
     frontend::{Context as FrontendContext, Frontend},
@@ -50,12 +53,21 @@ use query_frontend::{
 };
 use router::{endpoint::Endpoint, RouteRequest};
 use snafu::{ensure, OptionExt, ResultExt};
-use table_engine::table::TableRef;
+use table_engine::{query_warning::QueryWarnings, table::TableRef, write_trace};
 use tonic::transport::Channel;
+use trace_metric::{Metric, MetricsCollector};
 
 use crate::{
     error::{ErrNoCause, ErrWithCause, Internal, InternalNoCause, Result},
     forward::{ForwardResult, ForwarderRef},
+    schema_cache,
+    user_metrics::RequestKind,
+    with_log_level,
+    write_batcher::{Joined, WriteBatcher},
+    write_status::{
+        RowErrorReason, RowFailure, TableWriteStatus, WriteStatus, MAX_ROW_ERRORS_PER_TABLE,
+    },
+    write_trace::format_trace,
     Context, Proxy,
 };
 
@@ -74,19 +86,144 @@ pub struct WriteContext {
 pub(crate) struct WriteResponse {
     pub success: u32,
     pub failed: u32,
+    pub status: WriteStatus,
 }
 
 impl Proxy {
     pub(crate) async fn handle_write_internal(
         &self,
-        ctx: Context,
+        mut ctx: Context,
         req: WriteRequest,
     ) -> Result<WriteResponse> {
+        let _inflight = self.instance.drainer.track();
+        let user = self.metrics_user_of(&ctx, req.context.as_ref().map(|v| v.database.as_str()));
+        let begin_instant = Instant::now();
+        ctx.log_level = self.log_level_of(&ctx);
+        let log_ctx = ctx.clone();
+        let handle = self.handle_write_traced(ctx, req);
+        let result = with_log_level(&log_ctx, log_ctx.log_level, handle).await;
+        let rows = result.as_ref().map(|v| v.success as u64).unwrap_or(0);
+        self.observe_user_request(
+            user,
+            RequestKind::Write,
+            result.is_ok(),
+            rows,
+            begin_instant,
+        );
+        result
+    }
+
+    async fn handle_write_traced(
+        &self,
+        ctx: Context,
+        mut req: WriteRequest,
+    ) -> Result<WriteResponse> {
+        ensure!(
+            !self.instance.read_only.load(Ordering::Relaxed),
+            ErrNoCause {
+                code: StatusCode::FORBIDDEN,
+                msg: "Write is rejected as the server is in read-only mode",
+            }
+        );
+        let tables = req.table_requests.iter().map(|v| v.table.as_str());
+        self.check_write_role(ctx.auth_user.as_ref(), self.default_catalog_name(), tables)?;
+
+        self.instance
+            .limiter
+            .try_limit_write_by_memory()
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::TOO_MANY_REQUESTS,
+                msg: "Write is rejected",
+            })?;
+
+        let admission = &self.instance.admission;
+        let pending_tasks = self.engine_runtimes.write_runtime.stats().pending_task_num;
+        admission
+            .check_load(pending_tasks)
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::SERVICE_UNAVAILABLE,
+                msg: "Write is rejected",
+            })?;
+
+        let dropped = self.ingest_sampler.sample(&mut req);
+        if dropped > 0 {
+            debug!(
+                "Series dropped by ingest sampling, request_id:{}, dropped:{dropped}",
+                ctx.request_id
+            );
+        }
+
+        let num_rows = req
+            .table_requests
+            .iter()
+            .flat_map(|table_req| &table_req.entries)
+            .map(|entry| entry.field_groups.len())
+            .sum();
+        // The internal writes are admitted by the node receiving them, the
+        // `forwarded_from` is set by the clients too, so it's not trusted.
+        if let (Some(write_ctx), false) = (&req.context, ctx.internal) {
+            let catalog = self.instance.catalog_manager.default_catalog_name();
+            admission
+                .admit_write(catalog, &write_ctx.database, num_rows)
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::TOO_MANY_REQUESTS,
+                    msg: "Write is rejected",
+                })?;
+        }
+
+        let _permit = match &self.write_queue {
+            Some(queue) => {
+                let priority = queue.priority_of(num_rows, ctx.internal);
+                let permit = queue
+                    .acquire(priority, ctx.timeout)
+                    .await
+                    .box_err()
+                    .context(ErrWithCause {
+                        code: StatusCode::TOO_MANY_REQUESTS,
+                        msg: "Write is rejected",
+                    })?;
+                Some(permit)
+            }
+            None => None,
+        };
+
+        // The forwarded writes are mirrored by the node receiving them.
+        if let (Some(tee), None) = (&self.instance.write_tee, &ctx.forwarded_from) {
+            tee.mirror(&ctx, &req);
+        }
+
         let write_context = req.context.clone();
-        let resp = if self.cluster_with_meta {
-            self.handle_write_with_meta(ctx, req).await?
-        } else {
-            self.handle_write_without_meta(ctx, req).await?
+        // The verbose writes are always traced.
+        let collector = self.write_trace_sampler.sample(&req).or_else(|| {
+            ctx.log_level
+                .map(|_| MetricsCollector::new("write_trace, verbose".to_string()))
+        });
+        let resp = match collector {
+            Some(collector) => {
+                let request_id = ctx.request_id.clone();
+                let begin_instant = Instant::now();
+                let result = write_trace::with_trace(
+                    collector.clone(),
+                    self.handle_write_dispatch(ctx, req),
+                )
+                .await;
+                collector.collect(Metric::duration(
+                    "total".to_string(),
+                    begin_instant.elapsed(),
+                    None,
+                ));
+                info!(
+                    "Write traced, request_id:{}, success:{}, trace:\n{}",
+                    request_id,
+                    result.is_ok(),
+                    format_trace(&collector)
+                );
+                result?
+            }
+            None => self.handle_write_dispatch(ctx, req).await?,
         };
 
         debug!(
@@ -96,6 +233,18 @@ impl Proxy {
         Ok(resp)
     }
 
+    pub(crate) async fn handle_write_dispatch(
+        &self,
+        ctx: Context,
+        req: WriteRequest,
+    ) -> Result<WriteResponse> {
+        if self.cluster_with_meta {
+            self.handle_write_with_meta(ctx, req).await
+        } else {
+            self.handle_write_without_meta(ctx, req).await
+        }
+    }
+
     // Handle write requests based on horaemeta.
     // 1. Create table via horaemeta if it does not exist.
     // 2. Split write request.
@@ -114,8 +263,10 @@ impl Proxy {
         self.handle_auto_create_table_with_meta(request_id.clone(), &write_context.database, &req)
             .await?;
 
+        let route_begin = Instant::now();
         let (write_request_to_local, write_requests_to_forward) =
             self.split_write_request(req).await?;
+        write_trace::record_duration("route", route_begin.elapsed());
 
         let mut futures = Vec::with_capacity(write_requests_to_forward.len() + 1);
 
@@ -144,8 +295,10 @@ impl Proxy {
             msg: "Missing context",
             code: StatusCode::BAD_REQUEST,
         })?;
+        let route_begin = Instant::now();
         let (write_request_to_local, write_requests_to_forward) =
             self.split_write_request(req).await?;
+        write_trace::record_duration("route", route_begin.elapsed());
 
         let mut futures = Vec::with_capacity(write_requests_to_forward.len() + 1);
 
@@ -375,9 +528,12 @@ impl Proxy {
         for (endpoint, table_write_request) in write_request {
             let forwarder = self.forwarder.clone();
             let ctx = ctx.clone();
-            let write_handle = self.engine_runtimes.io_runtime.spawn(async move {
-                Self::write_to_remote(ctx, forwarder, endpoint, table_write_request).await
-            });
+            let write_handle =
+                self.engine_runtimes
+                    .io_runtime
+                    .spawn(logger::with_current_request_id(async move {
+                        Self::write_to_remote(ctx, forwarder, endpoint, table_write_request).await
+                    }));
 
             futures.push(write_handle.boxed());
         }
@@ -403,22 +559,21 @@ impl Proxy {
         futures: Vec<BoxFuture<'_, runtime::Result<Result<WriteResponse>>>>,
     ) -> Result<WriteResponse> {
         let mut futures: FuturesUnordered<_> = futures.into_iter().collect();
-        let mut success = 0;
+        let mut write_resp = WriteResponse::default();
         while let Some(resp) = futures.next().await {
             let resp = resp.box_err().context(ErrWithCause {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 msg: "Failed to join task",
-            })?;
-            success += resp?.success;
+            })??;
+            write_resp.success += resp.success;
+            write_resp.failed += resp.failed;
+            write_resp.status.merge(resp.status);
         }
 
-        Ok(WriteResponse {
-            success,
-            ..Default::default()
-        })
+        Ok(write_resp)
     }
 
-    async fn write_to_remote(
+    pub(crate) async fn write_to_remote(
         ctx: Context,
         forwarder: ForwarderRef,
         endpoint: Endpoint,
@@ -431,7 +586,10 @@ impl Proxy {
                 client
                     .write(request)
                     .await
-                    .map(|resp| resp.into_inner())
+                    .map(|resp| {
+                        let status = WriteStatus::from_metadata(resp.metadata());
+                        (resp.into_inner(), status)
+                    })
                     .box_err()
                     .context(ErrWithCause {
                         code: StatusCode::INTERNAL_SERVER_ERROR,
@@ -443,13 +601,9 @@ impl Proxy {
             Box::new(write) as _
         };
 
+        let request = ctx.forwarded_request(table_write_request);
         let forward_result = forwarder
-            .forward_with_endpoint(
-                endpoint,
-                tonic::Request::new(table_write_request),
-                ctx.forwarded_from,
-                do_write,
-            )
+            .forward_with_endpoint(endpoint, request, ctx.forwarded_from, do_write)
             .await;
         let forward_res = forward_result
             .map_err(|e| {
@@ -463,10 +617,13 @@ impl Proxy {
             })?;
 
         match forward_res {
-            ForwardResult::Forwarded(resp) => resp.map(|v: WriteResponsePB| WriteResponse {
-                success: v.success,
-                failed: v.failed,
-            }),
+            ForwardResult::Forwarded(resp) => resp.map(
+                |(v, status): (WriteResponsePB, WriteStatus)| WriteResponse {
+                    success: v.success,
+                    failed: v.failed,
+                    status,
+                },
+            ),
             ForwardResult::Local => InternalNoCause {
                 msg: "Local response is not expected".to_string(),
             }
@@ -493,6 +650,39 @@ impl Proxy {
             req.table_requests.len(),
         );
 
+        for table_req in &req.table_requests {
+            if let Err(rejected) = self
+                .instance
+                .maintenance
+                .wait_writable(&schema_name, &table_req.table)
+                .await
+            {
+                return ErrNoCause {
+                    code: StatusCode::SERVICE_UNAVAILABLE,
+                    msg: format!(
+                        "Write is rejected as table is under maintenance, table:{}, reason:{rejected:?}",
+                        table_req.table
+                    ),
+                }
+                .fail();
+            }
+        }
+
+        if let Some(breaker) = &self.write_circuit_breaker {
+            for table_req in &req.table_requests {
+                if let Some(remaining) = breaker.check(&schema_name, &table_req.table) {
+                    return ErrNoCause {
+                        code: StatusCode::SERVICE_UNAVAILABLE,
+                        msg: format!(
+                            "Write is rejected by circuit breaker, table:{}, retry after:{remaining:?}",
+                            table_req.table
+                        ),
+                    }
+                    .fail();
+                }
+            }
+        }
+
         let write_context = WriteContext {
             request_id: request_id.clone(),
             deadline,
@@ -502,12 +692,33 @@ impl Proxy {
         };
 
         let plan_vec = self
-            .write_request_to_insert_plan(req.table_requests, write_context)
+            .write_request_to_insert_plan(req.table_requests, write_context, ctx.partial_write)
             .await?;
 
-        let mut success = 0;
-        for insert_plan in plan_vec {
+        // The writes are slowed down as the compaction of the tables falls
+        // behind, which is done by this node owning the tables.
+        for (insert_plan, _) in &plan_vec {
+            self.instance
+                .admission
+                .throttle_table_write(&insert_plan.table, insert_plan.rows.num_rows())
+                .await
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::TOO_MANY_REQUESTS,
+                    msg: "Write is rejected",
+                })?;
+        }
+
+        let mut write_resp = WriteResponse::default();
+        for (insert_plan, mut table_status) in plan_vec {
             let table = insert_plan.table.clone();
+            write_resp.failed += table_status.failed;
+            // All the rows of the table are invalid.
+            if insert_plan.rows.is_empty() {
+                write_resp.status.tables.push(table_status);
+                continue;
+            }
+
             match self
                 .execute_insert_plan(
                     request_id.clone(),
@@ -518,10 +729,31 @@ impl Proxy {
                 )
                 .await
             {
-                Ok(n) => {
-                    success += n;
+                Ok((n, sequence)) => {
+                    if let Some(breaker) = &self.write_circuit_breaker {
+                        breaker.on_success(&schema_name, table.name());
+                    }
+                    write_resp.success += n as u32;
+                    table_status.success = n as u32;
+                    table_status.sequence = sequence;
+                    write_resp.status.tables.push(table_status);
                 }
                 Err(e) => {
+                    // Only the server side failures are taken into account.
+                    if let Some(breaker) = &self.write_circuit_breaker {
+                        if e.code().is_server_error() {
+                            breaker.on_failure(&schema_name, table.name());
+                        }
+                    }
+                    // The cached table may be stale, e.g. closed, so look it
+                    // up again in the following writes.
+                    if e.code().is_server_error() && self.instance.schema_cache.is_enabled() {
+                        self.instance.schema_cache.invalidate(
+                            catalog_name,
+                            &schema_name,
+                            table.name(),
+                        );
+                    }
                     // TODO: remove this logic.
                     // Refer to https://github.com/apache/incubator-horaedb/issues/1248.
                     if need_evict_partition_table(e.error_message()) {
@@ -534,17 +766,17 @@ impl Proxy {
             }
         }
 
-        Ok(WriteResponse {
-            success: success as u32,
-            ..Default::default()
-        })
+        Ok(write_resp)
     }
 
+    /// Build the insert plans of the table requests, along with the status of
+    /// the rows skipped if `partial_write` is set.
     async fn write_request_to_insert_plan(
         &self,
         table_requests: Vec<WriteTableRequest>,
         write_context: WriteContext,
-    ) -> Result<Vec<InsertPlan>> {
+        partial_write: bool,
+    ) -> Result<Vec<(InsertPlan, TableWriteStatus)>> {
         let mut plan_vec = Vec::with_capacity(table_requests.len());
 
         let WriteContext {
@@ -559,7 +791,7 @@ impl Proxy {
             self.maybe_open_partition_table_if_not_exist(&catalog, &schema, table_name)
                 .await?;
             let table = self
-                .try_get_table(&catalog, &schema, table_name)?
+                .try_get_table_for_write(&catalog, &schema, &write_table_req)?
                 .with_context(|| ErrNoCause {
                     code: StatusCode::BAD_REQUEST,
                     msg: format!("Table not found, schema:{schema}, table:{table_name}"),
@@ -589,7 +821,9 @@ impl Proxy {
             }
 
             let table_clone = table.clone();
-            let plan = match write_table_request_to_insert_plan(table, write_table_req) {
+            let decode_begin = Instant::now();
+            let result = write_table_request_to_insert_plan(table, write_table_req, partial_write);
+            let (plan, table_status) = match result {
                 Err(e) => {
                     // TODO: remove this logic.
                     // Refer to https://github.com/apache/incubator-horaedb/issues/1248.
@@ -602,12 +836,25 @@ impl Proxy {
                 }
                 Ok(v) => v,
             };
-            plan_vec.push(plan);
+            // The rows skipped may be caused by the outdated partition table too.
+            let need_evict = table_status
+                .row_errors
+                .iter()
+                .any(|v| need_evict_partition_table(v.message.clone()));
+            if need_evict {
+                warn!("Evict partition table:{}", table_clone.name());
+                self.evict_partition_table(table_clone, &catalog, &schema)
+                    .await;
+            }
+            write_trace::record_duration("decode", decode_begin.elapsed());
+            plan_vec.push((plan, table_status));
         }
 
         Ok(plan_vec)
     }
 
+    /// Execute the insert plan, returns the affected rows and the sequence the
+    /// write is committed at.
     async fn execute_insert_plan(
         &self,
         request_id: RequestId,
@@ -615,18 +862,56 @@ impl Proxy {
         schema_name: &str,
         insert_plan: InsertPlan,
         deadline: Option<Instant>,
-    ) -> Result<usize> {
+    ) -> Result<(usize, Option<SequenceNumber>)> {
         debug!(
             "Execute insert plan begin, table:{}, row_num:{}",
             insert_plan.table.name(),
             insert_plan.rows.num_rows()
         );
-        let plan = Plan::Insert(insert_plan);
+        if let Some(batcher) = &self.write_batcher {
+            if batcher.should_batch(&insert_plan) {
+                return self
+                    .execute_insert_plan_in_batch(
+                        batcher,
+                        request_id,
+                        catalog_name,
+                        schema_name,
+                        insert_plan,
+                        deadline,
+                    )
+                    .await;
+            }
+        }
+
+        self.execute_insert(request_id, catalog_name, schema_name, insert_plan, deadline)
+            .await
+    }
+
+    async fn execute_insert(
+        &self,
+        request_id: RequestId,
+        catalog_name: &str,
+        schema_name: &str,
+        insert_plan: InsertPlan,
+        deadline: Option<Instant>,
+    ) -> Result<(usize, Option<SequenceNumber>)> {
+        let write_sequence = WriteSequence::default();
         let output = self
-            .execute_plan(request_id, catalog_name, schema_name, plan, deadline)
+            .execute_plan_with_options(
+                request_id,
+                catalog_name,
+                schema_name,
+                Plan::Insert(insert_plan),
+                deadline,
+                false,
+                None,
+                ResultLimit::default(),
+                QueryWarnings::default(),
+                write_sequence.clone(),
+            )
             .await;
         output.and_then(|output| match output {
-            Output::AffectedRows(n) => Ok(n),
+            Output::AffectedRows(n) => Ok((n, write_sequence.get())),
             Output::Records(_) => ErrNoCause {
                 code: StatusCode::BAD_REQUEST,
                 msg: "Invalid output type, expect AffectedRows, found Records",
@@ -635,7 +920,67 @@ impl Proxy {
         })
     }
 
-    fn try_get_table(
+    /// Coalesce the insert plan with the others to the same table, and the
+    /// affected rows of the plan itself and the sequence the batch is committed
+    /// at are returned.
+    async fn execute_insert_plan_in_batch(
+        &self,
+        batcher: &WriteBatcher,
+        request_id: RequestId,
+        catalog_name: &str,
+        schema_name: &str,
+        insert_plan: InsertPlan,
+        deadline: Option<Instant>,
+    ) -> Result<(usize, Option<SequenceNumber>)> {
+        let num_rows = insert_plan.rows.num_rows();
+        let table_name = insert_plan.table.name().to_string();
+        let leader = match batcher.join(insert_plan) {
+            Joined::Leader(leader) => leader,
+            Joined::Follower(rx) => {
+                let result = rx.await.map_err(|_| "batch is cancelled".to_string());
+                return match result.and_then(|v| v) {
+                    Ok(sequence) => Ok((num_rows, sequence)),
+                    Err(msg) => ErrNoCause {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        msg: format!("Failed to write batch, table:{table_name}, err:{msg}"),
+                    }
+                    .fail(),
+                };
+            }
+        };
+
+        let mut batch = batcher.wait_batch(leader).await;
+        debug!(
+            "Execute coalesced insert plan, table:{table_name}, num_writes:{}",
+            batch.num_writes()
+        );
+        let plan = match batch.take_insert_plan() {
+            Ok(v) => v,
+            Err(msg) => {
+                batch.notify(Err(msg.clone()));
+                return ErrNoCause {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    msg,
+                }
+                .fail();
+            }
+        };
+        let result = self
+            .execute_insert(request_id, catalog_name, schema_name, plan, deadline)
+            .await;
+        match result {
+            Ok((_, sequence)) => {
+                batch.notify(Ok(sequence));
+                Ok((num_rows, sequence))
+            }
+            Err(e) => {
+                batch.notify(Err(e.error_message()));
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn try_get_table(
         &self,
         catalog: &str,
         schema: &str,
@@ -671,6 +1016,45 @@ impl Proxy {
             })
     }
 
+    /// Whether the table is opened on this node, i.e. the shard of the table is
+    /// owned by this node in the cluster mode.
+    pub(crate) fn is_local_table(&self, schema: &str, table_name: &str) -> Result<bool> {
+        let catalog = self.instance.catalog_manager.default_catalog_name();
+        Ok(self.try_get_table(catalog, schema, table_name)?.is_some())
+    }
+
+    /// Get the table to write from the schema cache if it's enabled, and the
+    /// table is looked up from the catalog again if the cached one doesn't
+    /// know all the columns of the request, as it may be stale.
+    fn try_get_table_for_write(
+        &self,
+        catalog: &str,
+        schema: &str,
+        write_table_req: &WriteTableRequest,
+    ) -> Result<Option<TableRef>> {
+        let cache = &self.instance.schema_cache;
+        let table_name = &write_table_req.table;
+        if !cache.is_enabled() {
+            return self.try_get_table(catalog, schema, table_name);
+        }
+
+        if let Some(table) = cache.get(catalog, schema, table_name) {
+            if has_known_columns(&table.schema(), write_table_req) {
+                return Ok(Some(table));
+            }
+            schema_cache::record_stale_retry();
+            cache.invalidate(catalog, schema, table_name);
+        }
+
+        let version = cache.version();
+        let table = self.try_get_table(catalog, schema, table_name)?;
+        if let Some(table) = &table {
+            cache.put(catalog, schema, table.clone(), version);
+        }
+
+        Ok(table)
+    }
+
     async fn execute_add_columns_plan(
         &self,
         request_id: RequestId,
@@ -688,6 +1072,7 @@ impl Proxy {
         let plan = Plan::AlterTable(AlterTablePlan {
             table,
             operations: AlterTableOperation::AddColumn(columns),
+            dry_run: false,
         });
         let _ = self
             .execute_plan(request_id.clone(), catalog, schema, plan, deadline)
@@ -699,6 +1084,11 @@ impl Proxy {
 
     async fn evict_partition_table(&self, table: TableRef, catalog_name: &str, schema_name: &str) {
         if table.partition_info().is_some() {
+            if self.instance.schema_cache.is_enabled() {
+                self.instance
+                    .schema_cache
+                    .invalidate(catalog_name, schema_name, table.name());
+            }
             let catalog = self.get_catalog(catalog_name);
             if catalog.is_err() {
                 return;
@@ -723,6 +1113,15 @@ impl Proxy {
     }
 }
 
+/// Whether all the columns of the request are known to the `schema`.
+fn has_known_columns(schema: &Schema, write_table_req: &WriteTableRequest) -> bool {
+    write_table_req
+        .tag_names
+        .iter()
+        .chain(&write_table_req.field_names)
+        .all(|name| schema.index_of(name).is_some())
+}
+
 fn find_new_columns(
     schema: &Schema,
     write_table_req: &WriteTableRequest,
@@ -813,23 +1212,46 @@ fn build_column<'a>(
     Ok(())
 }
 
+/// Build the insert plan of the table request, and the invalid rows are
+/// skipped and reported in the returned status if `partial_write` is set,
+/// otherwise the whole request is rejected.
 fn write_table_request_to_insert_plan(
     table: TableRef,
     write_table_req: WriteTableRequest,
-) -> Result<InsertPlan> {
+    partial_write: bool,
+) -> Result<(InsertPlan, TableWriteStatus)> {
     let schema = table.schema();
+    let mut table_status = TableWriteStatus {
+        table: write_table_req.table.clone(),
+        ..Default::default()
+    };
 
     // TODO: pre-allocate the memory for the row vector.
     let mut total_rows = Vec::new();
+    let mut row_index = 0;
     for write_entry in write_table_req.entries {
-        let mut rows = write_entry_to_rows(
+        let rows = write_entry_to_rows(
             &write_table_req.table,
             &schema,
             &write_table_req.tag_names,
             &write_table_req.field_names,
             write_entry,
-        )?;
-        total_rows.append(&mut rows);
+        );
+        for row in rows {
+            match row {
+                Ok(row) => total_rows.push(row),
+                Err(failure) if partial_write => {
+                    table_status.failed += 1;
+                    if table_status.row_errors.len() < MAX_ROW_ERRORS_PER_TABLE {
+                        table_status
+                            .row_errors
+                            .push(failure.into_row_error(row_index));
+                    }
+                }
+                Err(failure) => return Err(failure.into()),
+            }
+            row_index += 1;
+        }
     }
     // The row group builder will checks nullable.
     let row_group = RowGroup::try_new(schema, total_rows)
@@ -838,145 +1260,178 @@ fn write_table_request_to_insert_plan(
             code: StatusCode::INTERNAL_SERVER_ERROR,
             msg: format!("Failed to build row group, table:{}", table.name()),
         })?;
-    Ok(InsertPlan {
+    let plan = InsertPlan {
         table,
         rows: row_group,
         default_value_map: BTreeMap::new(),
-    })
+    };
+
+    Ok((plan, table_status))
 }
 
+/// Convert the entry into the rows, and the rows failed to be converted are
+/// returned as the failures.
 fn write_entry_to_rows(
     table_name: &str,
     schema: &Schema,
     tag_names: &[String],
     field_names: &[String],
     write_series_entry: WriteSeriesEntry,
-) -> Result<Vec<Row>> {
+) -> Vec<std::result::Result<Row, RowFailure>> {
     // Init all columns by null.
-    let mut rows = vec![
-        Row::from_datums(vec![Datum::Null; schema.num_columns()]);
-        write_series_entry.field_groups.len()
-    ];
+    let mut base_row = Row::from_datums(vec![Datum::Null; schema.num_columns()]);
 
     // Fill tsid by default value.
     if let Some(tsid_idx) = schema.index_of_tsid() {
         let kind = &schema.tsid_column().unwrap().data_type;
-        let default_datum = Datum::empty(kind);
-        for row in &mut rows {
-            row[tsid_idx] = default_datum.clone();
-        }
+        base_row[tsid_idx] = Datum::empty(kind);
     }
 
-    // Fill tags.
-    for tag in write_series_entry.tags {
-        let name_index = tag.name_index as usize;
-        ensure!(
-            name_index < tag_names.len(),
-            ErrNoCause {
-                code: StatusCode::BAD_REQUEST,
-                msg: format!(
-                    "Tag {tag:?} is not found in tag_names:{tag_names:?}, table:{table_name}",
-                ),
-            }
-        );
+    // Fill tags, which are shared by all the rows of the entry.
+    let num_rows = write_series_entry.field_groups.len();
+    if let Err(failure) = fill_tags(
+        table_name,
+        schema,
+        tag_names,
+        write_series_entry.tags,
+        &mut base_row,
+    ) {
+        return vec![Err(failure); num_rows];
+    }
 
-        let tag_name = &tag_names[name_index];
-        let tag_index_in_schema = schema.index_of(tag_name).with_context(|| ErrNoCause {
-            code: StatusCode::BAD_REQUEST,
-            msg: format!("Can't find tag({tag_name}) in schema, table:{table_name}"),
+    // Fill fields.
+    let mut field_name_index: HashMap<String, usize> = HashMap::new();
+    write_series_entry
+        .field_groups
+        .into_iter()
+        .map(|field_group| {
+            let mut row = base_row.clone();
+            fill_fields(
+                table_name,
+                schema,
+                field_names,
+                field_group,
+                &mut field_name_index,
+                &mut row,
+            )?;
+            Ok(row)
+        })
+        .collect()
+}
+
+fn fill_tags(
+    table_name: &str,
+    schema: &Schema,
+    tag_names: &[String],
+    tags: Vec<Tag>,
+    row: &mut Row,
+) -> std::result::Result<(), RowFailure> {
+    for tag in tags {
+        let tag_name = tag_names.get(tag.name_index as usize).ok_or_else(|| {
+            RowFailure::new(
+                RowErrorReason::UnknownColumn,
+                format!("Tag {tag:?} is not found in tag_names:{tag_names:?}, table:{table_name}"),
+            )
+        })?;
+        let tag_index_in_schema = schema.index_of(tag_name).ok_or_else(|| {
+            RowFailure::new(
+                RowErrorReason::UnknownColumn,
+                format!("Can't find tag({tag_name}) in schema, table:{table_name}"),
+            )
         })?;
 
         let column_schema = schema.column(tag_index_in_schema);
-        ensure!(
-            column_schema.is_tag,
-            ErrNoCause {
-                code: StatusCode::BAD_REQUEST,
-                msg: format!("Column({tag_name}) is a field rather than a tag, table:{table_name}"),
-            }
-        );
+        if !column_schema.is_tag {
+            return Err(RowFailure::new(
+                RowErrorReason::ColumnKindMismatch,
+                format!("Column({tag_name}) is a field rather than a tag, table:{table_name}"),
+            ));
+        }
 
         let tag_value = tag
             .value
-            .with_context(|| ErrNoCause {
-                code: StatusCode::BAD_REQUEST,
-                msg: format!("Tag({tag_name}) value is needed, table:{table_name}"),
+            .ok_or_else(|| {
+                RowFailure::new(
+                    RowErrorReason::MissingValue,
+                    format!("Tag({tag_name}) value is needed, table:{table_name}"),
+                )
             })?
             .value
-            .with_context(|| ErrNoCause {
-                code: StatusCode::BAD_REQUEST,
-                msg: format!(
-                    "Tag({tag_name}) value type is not supported, table_name:{table_name}"
-                ),
+            .ok_or_else(|| {
+                RowFailure::new(
+                    RowErrorReason::MissingValue,
+                    format!("Tag({tag_name}) value type is not supported, table_name:{table_name}"),
+                )
             })?;
-        for row in &mut rows {
-            row[tag_index_in_schema] = convert_proto_value_to_datum(
-                table_name,
-                tag_name,
-                tag_value.clone(),
-                column_schema.data_type,
-            )?;
-        }
+        row[tag_index_in_schema] =
+            convert_proto_value_to_datum(table_name, tag_name, tag_value, column_schema.data_type)?;
     }
 
-    // Fill fields.
-    let mut field_name_index: HashMap<String, usize> = HashMap::new();
-    for (i, field_group) in write_series_entry.field_groups.into_iter().enumerate() {
-        // timestamp
-        let timestamp_index_in_schema = schema.timestamp_index();
-        rows[i][timestamp_index_in_schema] =
-            Datum::Timestamp(Timestamp::new(field_group.timestamp));
-
-        for field in field_group.fields {
-            if (field.name_index as usize) < field_names.len() {
-                let field_name = &field_names[field.name_index as usize];
-                let index_in_schema = if field_name_index.contains_key(field_name) {
-                    field_name_index.get(field_name).unwrap().to_owned()
-                } else {
-                    let index_in_schema =
-                        schema.index_of(field_name).with_context(|| ErrNoCause {
-                            code: StatusCode::BAD_REQUEST,
-                            msg: format!(
-                                "Can't find field in schema, table:{table_name}, field_name:{field_name}"
-                            ),
-                        })?;
-                    field_name_index.insert(field_name.to_string(), index_in_schema);
-                    index_in_schema
-                };
-                let column_schema = schema.column(index_in_schema);
-                ensure!(
-                    !column_schema.is_tag,
-                    ErrNoCause {
-                        code: StatusCode::BAD_REQUEST,
-                        msg: format!(
-                            "Column {field_name} is a tag rather than a field, table:{table_name}"
-                        )
-                    }
-                );
-                let field_value = field
-                    .value
-                    .with_context(|| ErrNoCause {
-                        code: StatusCode::BAD_REQUEST,
-                        msg: format!("Field({field_name}) is needed, table:{table_name}"),
-                    })?
-                    .value
-                    .with_context(|| ErrNoCause {
-                        code: StatusCode::BAD_REQUEST,
-                        msg: format!(
-                            "Field({field_name}) value type is not supported, table:{table_name}"
-                        ),
-                    })?;
+    Ok(())
+}
 
-                rows[i][index_in_schema] = convert_proto_value_to_datum(
-                    table_name,
-                    field_name,
-                    field_value,
-                    column_schema.data_type,
-                )?;
+fn fill_fields(
+    table_name: &str,
+    schema: &Schema,
+    field_names: &[String],
+    field_group: FieldGroup,
+    field_name_index: &mut HashMap<String, usize>,
+    row: &mut Row,
+) -> std::result::Result<(), RowFailure> {
+    // timestamp
+    row[schema.timestamp_index()] = Datum::Timestamp(Timestamp::new(field_group.timestamp));
+
+    for field in field_group.fields {
+        let Some(field_name) = field_names.get(field.name_index as usize) else {
+            continue;
+        };
+        let index_in_schema = match field_name_index.get(field_name) {
+            Some(v) => *v,
+            None => {
+                let index_in_schema = schema.index_of(field_name).ok_or_else(|| {
+                    RowFailure::new(
+                        RowErrorReason::UnknownColumn,
+                        format!(
+                            "Can't find field in schema, table:{table_name}, field_name:{field_name}"
+                        ),
+                    )
+                })?;
+                field_name_index.insert(field_name.to_string(), index_in_schema);
+                index_in_schema
             }
+        };
+        let column_schema = schema.column(index_in_schema);
+        if column_schema.is_tag {
+            return Err(RowFailure::new(
+                RowErrorReason::ColumnKindMismatch,
+                format!("Column {field_name} is a tag rather than a field, table:{table_name}"),
+            ));
         }
+
+        let field_value = field
+            .value
+            .ok_or_else(|| {
+                RowFailure::new(
+                    RowErrorReason::MissingValue,
+                    format!("Field({field_name}) is needed, table:{table_name}"),
+                )
+            })?
+            .value
+            .ok_or_else(|| {
+                RowFailure::new(
+                    RowErrorReason::MissingValue,
+                    format!("Field({field_name}) value type is not supported, table:{table_name}"),
+                )
+            })?;
+        row[index_in_schema] = convert_proto_value_to_datum(
+            table_name,
+            field_name,
+            field_value,
+            column_schema.data_type,
+        )?;
     }
 
-    Ok(rows)
+    Ok(())
 }
 
 /// Convert the `Value_oneof_value` defined in protos into the datum.
@@ -985,7 +1440,7 @@ fn convert_proto_value_to_datum(
     name: &str,
     value: value::Value,
     data_type: DatumKind,
-) -> Result<Datum> {
+) -> std::result::Result<Datum, RowFailure> {
     match (value, data_type) {
         (value::Value::Float64Value(v), DatumKind::Double) => Ok(Datum::Double(v)),
         (value::Value::StringValue(v), DatumKind::String) => Ok(Datum::String(v.into())),
@@ -1001,13 +1456,12 @@ fn convert_proto_value_to_datum(
         (value::Value::Uint8Value(v), DatumKind::UInt8) => Ok(Datum::UInt8(v as u8)),
         (value::Value::TimestampValue(v), DatumKind::Timestamp) => Ok(Datum::Timestamp(Timestamp::new(v))),
         (value::Value::VarbinaryValue(v), DatumKind::Varbinary) => Ok(Datum::Varbinary(Bytes::from(v))),
-        (v, _) => ErrNoCause {
-            code: StatusCode::BAD_REQUEST,
-            msg: format!(
+        (v, _) => Err(RowFailure::new(
+            RowErrorReason::TypeMismatch,
+            format!(
                 "Value type is not same, table:{table_name}, value_name:{name}, schema_type:{data_type:?}, actual_value:{v:?}"
             ),
-        }
-            .fail(),
+        )),
     }
 }
 
@@ -1043,6 +1497,8 @@ mod test {
         let (schema, tag_names, field_names, write_entry) = generate_write_entry();
         let rows =
             write_entry_to_rows("test_table", &schema, &tag_names, &field_names, write_entry)
+                .into_iter()
+                .collect::<std::result::Result<Vec<_>, _>>()
                 .unwrap();
         let row0 = vec![
             Datum::Timestamp(Timestamp::new(1000)),
@@ -1074,6 +1530,35 @@ mod test {
         assert_eq!(rows, expect_rows);
     }
 
+    #[test]
+    fn test_write_entry_to_rows_with_failures() {
+        let (schema, tag_names, field_names, mut write_entry) = generate_write_entry();
+        // The second row writes a string into an int64 column.
+        write_entry.field_groups[1].fields =
+            vec![make_field(1, value::Value::StringValue("10".to_string()))];
+        let rows =
+            write_entry_to_rows("test_table", &schema, &tag_names, &field_names, write_entry);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].is_ok());
+        assert_eq!(
+            rows[1].as_ref().unwrap_err().reason,
+            RowErrorReason::TypeMismatch
+        );
+        assert!(rows[2].is_ok());
+
+        // All the rows of the entry fail if the tag is invalid.
+        let (schema, _, field_names, write_entry) = generate_write_entry();
+        let tag_names = vec![NAME_COL1.to_string(), NAME_NEW_COL1.to_string()];
+        let rows =
+            write_entry_to_rows("test_table", &schema, &tag_names, &field_names, write_entry);
+        assert_eq!(rows.len(), 3);
+        for row in rows {
+            let failure = row.unwrap_err();
+            assert_eq!(failure.reason, RowErrorReason::UnknownColumn);
+            assert!(need_evict_partition_table(failure.message));
+        }
+    }
+
     #[test]
     fn test_find_new_columns() {
         let write_table_request = generate_write_table_request();
@@ -1091,6 +1576,17 @@ mod test {
         assert!(!new_columns.get(NAME_COL5).unwrap().is_tag);
     }
 
+    #[test]
+    fn test_has_known_columns() {
+        let mut write_table_request = generate_write_table_request();
+        let schema = build_schema();
+        assert!(!has_known_columns(&schema, &write_table_request));
+
+        write_table_request.tag_names = vec![NAME_COL1.to_string(), NAME_COL2.to_string()];
+        write_table_request.field_names = vec![NAME_COL3.to_string(), NAME_COL4.to_string()];
+        assert!(has_known_columns(&schema, &write_table_request));
+    }
+
     fn build_schema() -> Schema {
         Builder::new()
             .auto_increment_column_id(true)
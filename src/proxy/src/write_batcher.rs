@@ -0,0 +1,411 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Coalesce the tiny writes to the same table into larger batches.
+//!
+//! The first write to a table becomes the leader of a batch, it waits at most
+//! `max_delay` for the following writes (the followers) to join the batch, and
+//! then writes the whole batch and notifies the followers of the result.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use common_types::{
+    row::{Row, RowGroup},
+    schema::Version,
+    SequenceNumber,
+};
+use query_frontend::plan::InsertPlan;
+use serde::{Deserialize, Serialize};
+use table_engine::table::{TableId, TableRef};
+use time_ext::ReadableDuration;
+use tokio::sync::{oneshot, Notify};
+
+use crate::metrics::WRITE_BATCH_ROWS_HISTOGRAM;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max time the first write of a batch waits for the others.
+    pub max_delay: ReadableDuration,
+    /// Only the writes with no more rows than it are coalesced.
+    pub max_rows_per_write: usize,
+    /// The batch is written immediately once its rows reach it.
+    pub max_rows_per_batch: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_delay: ReadableDuration::millis(5),
+            max_rows_per_write: 16,
+            max_rows_per_batch: 4096,
+        }
+    }
+}
+
+/// Writes can be coalesced only if the rows are built with the same schema.
+type BatchKey = (TableId, Version);
+
+/// Result of the batch sent to the followers, it's the sequence the batch is
+/// committed at, and the error is converted to a message as it can't be
+/// cloned.
+type BatchResult = std::result::Result<Option<SequenceNumber>, String>;
+
+struct PendingBatch {
+    /// Distinguishes the batches of the same key, only the leader of the
+    /// batch can take it out.
+    generation: u64,
+    table: TableRef,
+    rows: Vec<Row>,
+    followers: Vec<oneshot::Sender<BatchResult>>,
+    full: Arc<Notify>,
+}
+
+/// Role of a write after joining a batch.
+pub enum Joined {
+    Leader(BatchLeader),
+    Follower(oneshot::Receiver<BatchResult>),
+}
+
+/// The leader waits for the batch and writes it.
+pub struct BatchLeader {
+    key: BatchKey,
+    generation: u64,
+    full: Arc<Notify>,
+}
+
+/// A batch taken by the leader to write.
+pub struct Batch {
+    table: TableRef,
+    rows: Vec<Row>,
+    followers: Vec<oneshot::Sender<BatchResult>>,
+}
+
+impl Batch {
+    /// Number of the coalesced writes, including the leader's one.
+    pub fn num_writes(&self) -> usize {
+        self.followers.len() + 1
+    }
+
+    /// Build the plan to write the batch.
+    pub fn take_insert_plan(&mut self) -> std::result::Result<InsertPlan, String> {
+        let schema = self.table.schema();
+        let rows = std::mem::take(&mut self.rows);
+        match RowGroup::try_new(schema, rows) {
+            Ok(rows) => Ok(InsertPlan {
+                table: self.table.clone(),
+                rows,
+                default_value_map: BTreeMap::new(),
+            }),
+            Err(e) => Err(format!(
+                "Failed to build row group, table:{}, err:{e}",
+                self.table.name()
+            )),
+        }
+    }
+
+    /// Notify the followers of the result of the batch.
+    pub fn notify(self, result: BatchResult) {
+        for follower in self.followers {
+            // The follower may be cancelled.
+            let _ = follower.send(result.clone());
+        }
+    }
+}
+
+/// Take the pending batch out on drop if it's not taken yet, the followers
+/// are notified with an error as their senders are dropped together.
+struct TakeBatchGuard<'a> {
+    batcher: &'a WriteBatcher,
+    key: BatchKey,
+    generation: u64,
+    taken: bool,
+}
+
+impl<'a> TakeBatchGuard<'a> {
+    fn take(mut self) -> Option<PendingBatch> {
+        self.taken = true;
+        self.batcher.take_pending(&self.key, self.generation)
+    }
+}
+
+impl<'a> Drop for TakeBatchGuard<'a> {
+    fn drop(&mut self) {
+        if !self.taken {
+            self.batcher.take_pending(&self.key, self.generation);
+        }
+    }
+}
+
+pub struct WriteBatcher {
+    config: Config,
+    pending: Mutex<HashMap<BatchKey, PendingBatch>>,
+    next_generation: AtomicU64,
+}
+
+pub type WriteBatcherRef = Arc<WriteBatcher>;
+
+impl WriteBatcher {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            pending: Mutex::new(HashMap::new()),
+            next_generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the write of the plan should be coalesced with others.
+    pub fn should_batch(&self, plan: &InsertPlan) -> bool {
+        plan.default_value_map.is_empty()
+            && plan.rows.num_rows() <= self.config.max_rows_per_write
+            && plan.rows.schema().version() == plan.table.schema().version()
+    }
+
+    /// Join the rows of the plan into the pending batch of the table.
+    pub fn join(&self, mut plan: InsertPlan) -> Joined {
+        let key = (plan.table.id(), plan.rows.schema().version());
+        let mut rows = plan.rows.take_rows();
+
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get_mut(&key) {
+            Some(batch) => {
+                let (tx, rx) = oneshot::channel();
+                batch.rows.append(&mut rows);
+                batch.followers.push(tx);
+                if batch.rows.len() >= self.config.max_rows_per_batch {
+                    batch.full.notify_one();
+                }
+
+                Joined::Follower(rx)
+            }
+            None => {
+                let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+                let full = Arc::new(Notify::new());
+                pending.insert(
+                    key,
+                    PendingBatch {
+                        generation,
+                        table: plan.table,
+                        rows,
+                        followers: Vec::new(),
+                        full: full.clone(),
+                    },
+                );
+
+                Joined::Leader(BatchLeader {
+                    key,
+                    generation,
+                    full,
+                })
+            }
+        }
+    }
+
+    /// Wait until the batch of the leader is full or timeout, and take it out
+    /// so the following writes start a new batch.
+    pub async fn wait_batch(&self, leader: BatchLeader) -> Batch {
+        // Remove the batch if the leader is cancelled while waiting, otherwise the
+        // followers and the later writes to the table would wait forever.
+        let guard = TakeBatchGuard {
+            batcher: self,
+            key: leader.key,
+            generation: leader.generation,
+            taken: false,
+        };
+        let _ = tokio::time::timeout(self.config.max_delay.0, leader.full.notified()).await;
+
+        let batch = guard
+            .take()
+            .expect("pending batch must exist before the leader takes it");
+        WRITE_BATCH_ROWS_HISTOGRAM.observe(batch.rows.len() as f64);
+
+        Batch {
+            table: batch.table,
+            rows: batch.rows,
+            followers: batch.followers,
+        }
+    }
+
+    /// Remove the pending batch of the `key` only if it's of the `generation`,
+    /// as a newer batch of the same key may have been started.
+    fn take_pending(&self, key: &BatchKey, generation: u64) -> Option<PendingBatch> {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(key) {
+            Some(batch) if batch.generation == generation => pending.remove(key),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common_types::tests::{build_row, build_schema};
+    use table_engine::memory::MemoryTable;
+
+    use super::*;
+
+    fn new_batcher(max_rows_per_batch: usize) -> WriteBatcher {
+        WriteBatcher::new(Config {
+            enable: true,
+            max_delay: ReadableDuration::millis(100),
+            max_rows_per_write: 2,
+            max_rows_per_batch,
+        })
+    }
+
+    fn new_insert_plan(table: &TableRef, num_rows: usize) -> InsertPlan {
+        let rows = (0..num_rows)
+            .map(|i| build_row(b"a", i as i64, 1.0, "b", 1, 2))
+            .collect();
+        InsertPlan {
+            table: table.clone(),
+            rows: RowGroup::try_new(table.schema(), rows).unwrap(),
+            default_value_map: BTreeMap::new(),
+        }
+    }
+
+    fn new_table(id: u64) -> TableRef {
+        Arc::new(MemoryTable::new(
+            format!("table_{id}"),
+            TableId::from(id),
+            build_schema(),
+            "memory".to_string(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_writes() {
+        let batcher = new_batcher(4);
+        let table = new_table(1);
+        let other_table = new_table(2);
+
+        assert!(batcher.should_batch(&new_insert_plan(&table, 2)));
+        assert!(!batcher.should_batch(&new_insert_plan(&table, 3)));
+
+        let leader = match batcher.join(new_insert_plan(&table, 1)) {
+            Joined::Leader(v) => v,
+            Joined::Follower(_) => panic!("the first write should be the leader"),
+        };
+        let mut followers = Vec::new();
+        for _ in 0..2 {
+            match batcher.join(new_insert_plan(&table, 1)) {
+                Joined::Follower(rx) => followers.push(rx),
+                Joined::Leader(_) => panic!("the following writes should be followers"),
+            }
+        }
+        // Writes to other tables are not coalesced.
+        assert!(matches!(
+            batcher.join(new_insert_plan(&other_table, 1)),
+            Joined::Leader(_)
+        ));
+
+        let mut batch = batcher.wait_batch(leader).await;
+        assert_eq!(3, batch.num_writes());
+        assert_eq!(3, batch.take_insert_plan().unwrap().rows.num_rows());
+        batch.notify(Err("failed".to_string()));
+        for rx in followers {
+            assert_eq!(Err("failed".to_string()), rx.await.unwrap());
+        }
+
+        // The next write starts a new batch.
+        assert!(matches!(
+            batcher.join(new_insert_plan(&table, 1)),
+            Joined::Leader(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_full_batch() {
+        let batcher = new_batcher(2);
+        let table = new_table(1);
+
+        let Joined::Leader(leader) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the first write should be the leader");
+        };
+        let Joined::Follower(rx) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the second write should be a follower");
+        };
+
+        // The full batch is taken without waiting for the max delay.
+        let batch = tokio::time::timeout(Duration::from_millis(50), batcher.wait_batch(leader))
+            .await
+            .unwrap();
+        batch.notify(Ok(Some(10)));
+        assert_eq!(Ok(Some(10)), rx.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_leader() {
+        let batcher = new_batcher(4);
+        let table = new_table(1);
+
+        let Joined::Leader(leader) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the first write should be the leader");
+        };
+        let Joined::Follower(rx) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the second write should be a follower");
+        };
+
+        let wait = tokio::time::timeout(Duration::from_millis(10), batcher.wait_batch(leader));
+        assert!(wait.await.is_err());
+        assert!(rx.await.is_err());
+        assert!(batcher.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_take_batch_once() {
+        let batcher = new_batcher(4);
+        let table = new_table(1);
+
+        let Joined::Leader(leader) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the first write should be the leader");
+        };
+        let stale_guard = TakeBatchGuard {
+            batcher: &batcher,
+            key: leader.key,
+            generation: leader.generation,
+            taken: false,
+        };
+        let batch = batcher.wait_batch(leader).await;
+        assert_eq!(1, batch.num_writes());
+
+        // A new batch of the same key is started.
+        let Joined::Leader(leader) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the write after the batch is taken should be the leader");
+        };
+        let Joined::Follower(rx) = batcher.join(new_insert_plan(&table, 1)) else {
+            panic!("the second write should be a follower");
+        };
+
+        // The guard of the old batch doesn't remove the new one.
+        drop(stale_guard);
+        let batch = batcher.wait_batch(leader).await;
+        assert_eq!(2, batch.num_writes());
+        batch.notify(Ok(None));
+        assert_eq!(Ok(None), rx.await.unwrap());
+    }
+}
@@ -0,0 +1,315 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Bounded queue with priorities between the write handlers and the engine.
+//!
+//! At most `max_concurrent_writes` writes are executed at the same time, and
+//! the others wait in the queue of their priority. The freed slot is always
+//! handed to the waiter of the highest priority, so the internal and the small
+//! writes are not starved by the bulk loads. A write is rejected if the queue
+//! of its priority is full.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use macros::define_result;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use tokio::sync::oneshot;
+
+use crate::metrics::{WRITE_QUEUE_DEPTH_GAUGE_VEC, WRITE_QUEUE_REJECTED_COUNTER_VEC};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Write queue is full, priority:{}", priority.as_str()))]
+    QueueFull { priority: WritePriority },
+
+    #[snafu(display(
+        "Timeout to wait in the write queue, priority:{}, waited:{:?}",
+        priority.as_str(),
+        waited
+    ))]
+    WaitTimeout {
+        priority: WritePriority,
+        waited: Duration,
+    },
+}
+
+define_result!(Error);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max number of the writes executed at the same time.
+    pub max_concurrent_writes: usize,
+    /// Max number of the writes waiting in the queue of each priority.
+    pub max_queued_writes: usize,
+    /// The writes with no more rows than it are considered small.
+    pub small_write_rows: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_concurrent_writes: 64,
+            max_queued_writes: 1024,
+            small_write_rows: 100,
+        }
+    }
+}
+
+/// Priority of the writes, the lower the value the higher the priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WritePriority {
+    /// Writes issued by the cluster itself, e.g. the forwarded ones.
+    Internal = 0,
+    Small = 1,
+    Bulk = 2,
+}
+
+impl WritePriority {
+    const ALL: [WritePriority; 3] = [Self::Internal, Self::Small, Self::Bulk];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Internal => "internal",
+            Self::Small => "small",
+            Self::Bulk => "bulk",
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    /// Waiters of each priority, indexed by the [WritePriority].
+    waiters: [VecDeque<oneshot::Sender<()>>; 3],
+}
+
+impl State {
+    fn has_waiters(&self) -> bool {
+        self.waiters.iter().any(|v| !v.is_empty())
+    }
+
+    fn update_depth(&self, priority: WritePriority) {
+        WRITE_QUEUE_DEPTH_GAUGE_VEC
+            .with_label_values(&[priority.as_str()])
+            .set(self.waiters[priority as usize].len() as i64);
+    }
+}
+
+pub struct WriteQueue {
+    max_concurrent_writes: usize,
+    max_queued_writes: usize,
+    small_write_rows: usize,
+    state: Mutex<State>,
+}
+
+impl WriteQueue {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            max_concurrent_writes: config.max_concurrent_writes.max(1),
+            max_queued_writes: config.max_queued_writes,
+            small_write_rows: config.small_write_rows,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Decide the priority of a write with `num_rows` rows, `internal` tells
+    /// whether it's forwarded by another node of the cluster.
+    pub fn priority_of(&self, num_rows: usize, internal: bool) -> WritePriority {
+        if internal {
+            WritePriority::Internal
+        } else if num_rows <= self.small_write_rows {
+            WritePriority::Small
+        } else {
+            WritePriority::Bulk
+        }
+    }
+
+    /// Wait until the write can be executed, the write should be executed
+    /// while holding the returned permit.
+    pub async fn acquire(
+        &self,
+        priority: WritePriority,
+        timeout: Option<Duration>,
+    ) -> Result<WritePermit<'_>> {
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            if state.running < self.max_concurrent_writes && !state.has_waiters() {
+                state.running += 1;
+                return Ok(WritePermit { queue: self });
+            }
+
+            // Drop the waiters cancelled already.
+            let waiters = &mut state.waiters[priority as usize];
+            waiters.retain(|tx| !tx.is_closed());
+            if waiters.len() >= self.max_queued_writes {
+                WRITE_QUEUE_REJECTED_COUNTER_VEC
+                    .with_label_values(&[priority.as_str()])
+                    .inc();
+                return QueueFull { priority }.fail();
+            }
+
+            let (tx, rx) = oneshot::channel();
+            waiters.push_back(tx);
+            state.update_depth(priority);
+            rx
+        };
+
+        let begin = Instant::now();
+        let mut waiter = Waiter {
+            queue: self,
+            rx: Some(rx),
+        };
+        let granted = {
+            let rx = waiter.rx.as_mut().unwrap();
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, rx).await.ok(),
+                None => Some(rx.await),
+            }
+        };
+        match granted {
+            Some(Ok(())) => {
+                waiter.rx = None;
+                Ok(WritePermit { queue: self })
+            }
+            // The sender is never dropped without sending unless timeout.
+            _ => WaitTimeout {
+                priority,
+                waited: begin.elapsed(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Hand the slot to the waiter of the highest priority, or free it if no
+    /// one is waiting.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        for priority in WritePriority::ALL {
+            while let Some(tx) = state.waiters[priority as usize].pop_front() {
+                if tx.send(()).is_ok() {
+                    state.update_depth(priority);
+                    return;
+                }
+            }
+            state.update_depth(priority);
+        }
+
+        state.running -= 1;
+    }
+}
+
+/// The write holding it is counted as running.
+pub struct WritePermit<'a> {
+    queue: &'a WriteQueue,
+}
+
+impl<'a> Drop for WritePermit<'a> {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+/// Release the slot granted to a waiter cancelled before seeing it.
+struct Waiter<'a> {
+    queue: &'a WriteQueue,
+    rx: Option<oneshot::Receiver<()>>,
+}
+
+impl<'a> Drop for Waiter<'a> {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+            if rx.try_recv().is_ok() {
+                self.queue.release();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_queue(max_concurrent_writes: usize, max_queued_writes: usize) -> WriteQueue {
+        WriteQueue::new(&Config {
+            enable: true,
+            max_concurrent_writes,
+            max_queued_writes,
+            small_write_rows: 10,
+        })
+    }
+
+    #[test]
+    fn test_priority_of() {
+        let queue = new_queue(1, 1);
+        assert_eq!(WritePriority::Internal, queue.priority_of(1000, true));
+        assert_eq!(WritePriority::Small, queue.priority_of(10, false));
+        assert_eq!(WritePriority::Bulk, queue.priority_of(11, false));
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_first() {
+        let queue = new_queue(1, 1);
+        let permit = queue.acquire(WritePriority::Bulk, None).await.unwrap();
+
+        let bulk = queue.acquire(WritePriority::Bulk, None);
+        let small = queue.acquire(WritePriority::Small, None);
+        tokio::pin!(bulk);
+        tokio::pin!(small);
+        // Enqueue both of the waiters.
+        assert!(futures::poll!(bulk.as_mut()).is_pending());
+        assert!(futures::poll!(small.as_mut()).is_pending());
+
+        // The queue of bulk writes is full.
+        assert!(matches!(
+            queue.acquire(WritePriority::Bulk, None).await,
+            Err(Error::QueueFull { .. })
+        ));
+
+        drop(permit);
+        let permit = small.await.unwrap();
+        assert!(futures::poll!(bulk.as_mut()).is_pending());
+        drop(permit);
+        let permit = bulk.await.unwrap();
+        drop(permit);
+
+        assert_eq!(0, queue.state.lock().unwrap().running);
+    }
+
+    #[tokio::test]
+    async fn test_wait_timeout() {
+        let queue = new_queue(1, 1);
+        let permit = queue.acquire(WritePriority::Small, None).await.unwrap();
+
+        let result = queue
+            .acquire(WritePriority::Small, Some(Duration::from_millis(10)))
+            .await;
+        assert!(matches!(result, Err(Error::WaitTimeout { .. })));
+
+        drop(permit);
+        assert_eq!(0, queue.state.lock().unwrap().running);
+        let _permit = queue.acquire(WritePriority::Small, None).await.unwrap();
+    }
+}
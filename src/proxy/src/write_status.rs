@@ -0,0 +1,203 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Status of the tables and the rows of a write.
+//!
+//! The grpc write response only carries the total numbers of the rows, so the
+//! status of every table written is carried in the binary metadata
+//! [WRITE_STATUS_KEY] of the response, encoded in json. With it, the clients
+//! can retry the failed rows only, and wait for the replicas or the change
+//! streams to reach the committed sequences before reading their writes.
+//!
+//! The rows are rejected along with the whole request by default. If the
+//! request sets the metadata [PARTIAL_WRITE_KEY] to `true`, the invalid rows
+//! are skipped and reported in the status while the others are written.
+//!
+//! [WRITE_STATUS_KEY]: crate::WRITE_STATUS_KEY
+//! [PARTIAL_WRITE_KEY]: crate::PARTIAL_WRITE_KEY
+
+use http::StatusCode;
+use logger::warn;
+use serde::{Deserialize, Serialize};
+use tonic::metadata::MetadataMap;
+
+use crate::{error::Error, WRITE_STATUS_KEY};
+
+/// Max row errors reported for a table, to keep the metadata small. The
+/// `failed` of the table always counts all the failed rows.
+pub const MAX_ROW_ERRORS_PER_TABLE: usize = 100;
+
+/// Reason of a row failed to be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowErrorReason {
+    /// The column is not found in the table or the request.
+    UnknownColumn,
+    /// A tag is written as a field, or vice versa.
+    ColumnKindMismatch,
+    /// The value is missing or its type is not supported.
+    MissingValue,
+    /// The type of the value doesn't match the column.
+    TypeMismatch,
+}
+
+/// Error of a row in the write request of a table.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RowError {
+    /// Index of the row in the table request, where the rows are the field
+    /// groups of all the entries in order.
+    pub index: u32,
+    pub reason: RowErrorReason,
+    pub message: String,
+}
+
+/// Failure of a row whose index is not known yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RowFailure {
+    pub reason: RowErrorReason,
+    pub message: String,
+}
+
+impl RowFailure {
+    pub fn new(reason: RowErrorReason, message: String) -> Self {
+        Self { reason, message }
+    }
+
+    pub fn into_row_error(self, index: usize) -> RowError {
+        RowError {
+            index: index as u32,
+            reason: self.reason,
+            message: self.message,
+        }
+    }
+}
+
+impl From<RowFailure> for Error {
+    fn from(failure: RowFailure) -> Self {
+        Error::ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: failure.message,
+        }
+    }
+}
+
+/// Status of the write to a table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TableWriteStatus {
+    pub table: String,
+    pub success: u32,
+    pub failed: u32,
+    /// Sequence committed into the table after the write, and the written rows
+    /// are visible to the readers reaching this sequence.
+    ///
+    /// `None` if the table doesn't track the sequences, e.g. the partitioned
+    /// tables.
+    pub sequence: Option<u64>,
+    /// Errors of the failed rows, at most [MAX_ROW_ERRORS_PER_TABLE].
+    pub row_errors: Vec<RowError>,
+}
+
+/// Status of the tables of a write request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WriteStatus {
+    pub tables: Vec<TableWriteStatus>,
+    /// Delay in milliseconds before retrying the write rejected by the
+    /// admission control.
+    pub retry_after_ms: Option<u64>,
+}
+
+impl WriteStatus {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty() && self.retry_after_ms.is_none()
+    }
+
+    pub fn merge(&mut self, other: WriteStatus) {
+        self.tables.extend(other.tables);
+        self.retry_after_ms = self.retry_after_ms.max(other.retry_after_ms);
+    }
+
+    pub fn encode(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+    }
+
+    pub fn decode(buf: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(buf)
+    }
+
+    /// Extract the status from the metadata of the write response, and an
+    /// empty status is returned if it's missing or invalid.
+    pub fn from_metadata(metadata: &MetadataMap) -> Self {
+        let Some(value) = metadata.get_bin(WRITE_STATUS_KEY) else {
+            return Self::default();
+        };
+
+        match value.to_bytes() {
+            Ok(buf) => Self::decode(&buf).unwrap_or_else(|e| {
+                warn!("Failed to decode write status, err:{e}");
+                Self::default()
+            }),
+            Err(e) => {
+                warn!("Invalid write status in metadata, err:{e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_write_status() {
+        let mut status = WriteStatus {
+            tables: vec![TableWriteStatus {
+                table: "t1".to_string(),
+                success: 2,
+                failed: 1,
+                sequence: Some(10),
+                row_errors: vec![RowFailure::new(
+                    RowErrorReason::TypeMismatch,
+                    "Value type is not same".to_string(),
+                )
+                .into_row_error(1)],
+            }],
+            retry_after_ms: None,
+        };
+        status.merge(WriteStatus {
+            tables: vec![TableWriteStatus {
+                table: "t2".to_string(),
+                success: 3,
+                ..Default::default()
+            }],
+            retry_after_ms: Some(100),
+        });
+        assert_eq!(Some(100), status.retry_after_ms);
+
+        let buf = status.encode().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(
+            "type_mismatch",
+            json["tables"][0]["row_errors"][0]["reason"]
+        );
+        assert_eq!(status, WriteStatus::decode(&buf).unwrap());
+    }
+}
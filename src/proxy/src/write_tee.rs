@@ -0,0 +1,445 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Mirror a sampled part of the writes of the selected tables to an alternate
+//! table or endpoint, so the experiments, e.g. a new schema or new engine
+//! options of the alternate table, can be validated against the real traffic.
+//!
+//! The mirrored writes run in the background once the original writes are
+//! admitted, and never block or fail the original writes: they are dropped if
+//! too many of them are pending, and their failures are only counted by the
+//! metrics. The series are sampled by the hash of their tags like the ingest
+//! sampling, so the mirrored series are complete.
+//!
+//! The rules select the tables by their schemas and names. The mirrored writes
+//! to the local tables skip the admission and the tee, so they are never
+//! mirrored again. The ones to the endpoints are sent as the writes of the
+//! clients with the credentials of the original writes, so they are
+//! authenticated and admitted by the endpoints as any other writes.
+
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock, Weak,
+    },
+};
+
+use catalog::consts::DEFAULT_SCHEMA;
+use futures::FutureExt;
+use generic_error::BoxError;
+use horaedbproto::storage::{storage_service_client::StorageServiceClient, WriteRequest};
+use http::StatusCode;
+use logger::{debug, warn};
+use router::endpoint::Endpoint;
+use runtime::RuntimeRef;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use table_engine::task::{TaskKind, TaskRegistryRef, TaskSpec};
+use time_ext::ReadableDuration;
+use tonic::transport::Channel;
+
+use crate::{
+    error::{ErrWithCause, Result},
+    ingest_sampling::series_hash,
+    metrics::WRITE_TEE_COUNTER_VEC,
+    Context, Proxy,
+};
+
+/// The percentages of the rules are in the units of 1/100.
+const SAMPLE_BASE: u64 = 10_000;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max number of the mirrored writes in flight, the others are dropped.
+    pub max_pending: usize,
+    /// Timeout of a mirrored write.
+    pub timeout: ReadableDuration,
+    pub rules: Vec<TeeRule>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_pending: 256,
+            timeout: ReadableDuration::secs(10),
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TeeRule {
+    /// Schema of the table, the default schema if empty.
+    pub schema: String,
+    /// Table whose writes are mirrored.
+    pub table: String,
+    /// Percentage of the series mirrored, in `(0, 100]`.
+    pub percent: f64,
+    /// Table the writes are mirrored to in the same schema, the same table if
+    /// empty.
+    pub target_table: String,
+    /// Grpc endpoint the writes are mirrored to, eg: 127.0.0.1:8831, the local
+    /// node if empty.
+    pub endpoint: String,
+}
+
+struct Rule {
+    /// Number of the series mirrored out of [SAMPLE_BASE].
+    ratio: u64,
+    target_table: String,
+    endpoint: Option<Endpoint>,
+}
+
+pub type WriteTeeRef = Arc<WriteTee>;
+
+pub struct WriteTee {
+    /// Rules keyed by the schemas and the names of the tables.
+    rules: HashMap<(String, String), Rule>,
+    max_pending: usize,
+    timeout: ReadableDuration,
+    pending: Arc<AtomicUsize>,
+    runtime: RuntimeRef,
+    task_registry: TaskRegistryRef,
+    /// The proxy is set after it is built, as the proxy holds the tee.
+    proxy: OnceLock<Weak<Proxy>>,
+}
+
+impl WriteTee {
+    /// Build the tee by the rules of the config, and the invalid rules are
+    /// ignored.
+    pub fn new(config: &Config, runtime: RuntimeRef, task_registry: TaskRegistryRef) -> Self {
+        let mut rules = HashMap::with_capacity(config.rules.len());
+        for rule in &config.rules {
+            match Self::build_rule(rule) {
+                Some(v) => {
+                    let schema = if rule.schema.is_empty() {
+                        DEFAULT_SCHEMA
+                    } else {
+                        &rule.schema
+                    };
+                    rules.insert((schema.to_string(), rule.table.clone()), v);
+                }
+                None => warn!("Invalid rule of the write tee is ignored, rule:{rule:?}"),
+            }
+        }
+
+        Self {
+            rules,
+            max_pending: config.max_pending,
+            timeout: config.timeout,
+            pending: Arc::new(AtomicUsize::new(0)),
+            runtime,
+            task_registry,
+            proxy: OnceLock::new(),
+        }
+    }
+
+    fn build_rule(rule: &TeeRule) -> Option<Rule> {
+        if rule.table.is_empty() || rule.percent <= 0.0 || rule.percent > 100.0 {
+            return None;
+        }
+        let endpoint = if rule.endpoint.is_empty() {
+            None
+        } else {
+            Some(Endpoint::from_str(&rule.endpoint).ok()?)
+        };
+        let target_table = if rule.target_table.is_empty() {
+            rule.table.clone()
+        } else {
+            rule.target_table.clone()
+        };
+        // The local writes can't be mirrored to the table itself.
+        if endpoint.is_none() && target_table == rule.table {
+            return None;
+        }
+
+        Some(Rule {
+            ratio: (rule.percent * (SAMPLE_BASE / 100) as f64).round() as u64,
+            target_table,
+            endpoint,
+        })
+    }
+
+    pub fn set_proxy(&self, proxy: Weak<Proxy>) {
+        if self.proxy.set(proxy).is_err() {
+            warn!("Proxy of the write tee is already set");
+        }
+    }
+
+    /// Mirror the sampled series of the request in the background, with the
+    /// credentials of the request `ctx`.
+    pub fn mirror(&self, ctx: &Context, req: &WriteRequest) {
+        if self.rules.is_empty() {
+            return;
+        }
+
+        for (endpoint, mirrored) in self.split(req) {
+            let num_rows = num_rows(&mirrored);
+            if self.pending.fetch_add(1, Ordering::Relaxed) >= self.max_pending {
+                self.pending.fetch_sub(1, Ordering::Relaxed);
+                WRITE_TEE_COUNTER_VEC
+                    .with_label_values(&["dropped"])
+                    .inc_by(num_rows);
+                continue;
+            }
+
+            let proxy = self.proxy.get().cloned().unwrap_or_default();
+            let pending = self.pending.clone();
+            let ctx = Context::new(Some(self.timeout.0), None)
+                .with_partial_write(true)
+                .with_auth_user(ctx.auth_user.clone());
+            let target = endpoint.as_ref().map(|v| v.to_string()).unwrap_or_default();
+            let spec = TaskSpec::new(TaskKind::WriteTee, target);
+            let mirror = async move {
+                let result = match proxy.upgrade() {
+                    Some(proxy) => proxy.write_mirrored(ctx, endpoint, mirrored).await,
+                    None => Ok(()),
+                };
+                pending.fetch_sub(1, Ordering::Relaxed);
+                match &result {
+                    Ok(_) => WRITE_TEE_COUNTER_VEC
+                        .with_label_values(&["mirrored"])
+                        .inc_by(num_rows),
+                    Err(e) => {
+                        debug!("Failed to mirror write, err:{e}");
+                        WRITE_TEE_COUNTER_VEC
+                            .with_label_values(&["failed"])
+                            .inc_by(num_rows);
+                    }
+                }
+                result
+            };
+            self.task_registry
+                .spawn_tracked(&self.runtime, spec, mirror);
+        }
+    }
+
+    /// Build the mirrored requests of the sampled series, grouped by the
+    /// endpoints they are mirrored to.
+    fn split(&self, req: &WriteRequest) -> HashMap<Option<Endpoint>, WriteRequest> {
+        let mut mirrored: HashMap<Option<Endpoint>, WriteRequest> = HashMap::new();
+        let schema = match &req.context {
+            Some(v) => &v.database,
+            None => return mirrored,
+        };
+        for table_req in &req.table_requests {
+            let rule = match self.rules.get(&(schema.clone(), table_req.table.clone())) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let entries: Vec<_> = table_req
+                .entries
+                .iter()
+                .filter(|entry| {
+                    let hash = series_hash(&table_req.tag_names, entry);
+                    // Rehash so the sampled series are independent of the ingest sampling.
+                    hash_ext::hash64(&hash.to_le_bytes()[..]) % SAMPLE_BASE < rule.ratio
+                })
+                .cloned()
+                .collect();
+            if entries.is_empty() {
+                continue;
+            }
+
+            let mut mirrored_req = table_req.clone();
+            mirrored_req.table = rule.target_table.clone();
+            mirrored_req.entries = entries;
+            mirrored
+                .entry(rule.endpoint.clone())
+                .or_insert_with(|| WriteRequest {
+                    context: req.context.clone(),
+                    table_requests: Vec::new(),
+                })
+                .table_requests
+                .push(mirrored_req);
+        }
+
+        mirrored
+    }
+}
+
+fn num_rows(req: &WriteRequest) -> u64 {
+    req.table_requests
+        .iter()
+        .flat_map(|table_req| &table_req.entries)
+        .map(|entry| entry.field_groups.len() as u64)
+        .sum()
+}
+
+impl Proxy {
+    async fn write_mirrored(
+        &self,
+        ctx: Context,
+        endpoint: Option<Endpoint>,
+        req: WriteRequest,
+    ) -> Result<()> {
+        let endpoint = match endpoint {
+            Some(v) => v,
+            None => return self.handle_write_dispatch(ctx, req).await.map(|_| ()),
+        };
+
+        let do_write = |mut client: StorageServiceClient<Channel>,
+                        request: tonic::Request<WriteRequest>,
+                        _: &Endpoint| {
+            let write = async move { client.write(request).await.map(|_| ()) }.boxed();
+            Box::new(write) as _
+        };
+        let mut request = ctx.forwarded_request(req);
+        if let Some(timeout) = ctx.timeout {
+            request.set_timeout(timeout);
+        }
+        self.forwarder
+            .send_with_endpoint(endpoint, request, do_write)
+            .await
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Failed to connect to the endpoint",
+            })?
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Mirrored write failed",
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horaedbproto::storage::{
+        value, FieldGroup, RequestContext, Tag, Value, WriteSeriesEntry, WriteTableRequest,
+    };
+
+    use table_engine::task::{self, TaskRegistry};
+
+    use super::*;
+
+    fn write_request(table: &str, num_series: usize) -> WriteRequest {
+        let entries = (0..num_series)
+            .map(|i| WriteSeriesEntry {
+                tags: vec![Tag {
+                    name_index: 0,
+                    value: Some(Value {
+                        value: Some(value::Value::StringValue(format!("host-{i}"))),
+                    }),
+                }],
+                field_groups: vec![FieldGroup::default()],
+            })
+            .collect();
+        WriteRequest {
+            context: Some(RequestContext {
+                database: "public".to_string(),
+            }),
+            table_requests: vec![WriteTableRequest {
+                table: table.to_string(),
+                tag_names: vec!["host".to_string()],
+                field_names: vec![],
+                entries,
+            }],
+        }
+    }
+
+    fn new_tee(rules: Vec<TeeRule>) -> WriteTee {
+        let config = Config {
+            enable: true,
+            rules,
+            ..Default::default()
+        };
+        let runtime = runtime::Builder::default()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        let task_registry = Arc::new(TaskRegistry::new(&task::Config::default()));
+        WriteTee::new(&config, Arc::new(runtime), task_registry)
+    }
+
+    #[test]
+    fn test_build_rules() {
+        let rule = |table: &str, percent, target_table: &str, endpoint: &str| TeeRule {
+            schema: String::new(),
+            table: table.to_string(),
+            percent,
+            target_table: target_table.to_string(),
+            endpoint: endpoint.to_string(),
+        };
+        let tee = new_tee(vec![
+            rule("t1", 10.0, "t1_exp", ""),
+            rule("t2", 50.0, "", "127.0.0.1:8831"),
+            // Mirrored to the table itself.
+            rule("t3", 10.0, "", ""),
+            rule("t4", 0.0, "t4_exp", ""),
+            rule("t5", 10.0, "", "invalid"),
+            TeeRule {
+                schema: "db1".to_string(),
+                ..rule("t1", 10.0, "t1_exp", "")
+            },
+        ]);
+
+        assert_eq!(3, tee.rules.len());
+        let key = |schema: &str, table: &str| (schema.to_string(), table.to_string());
+        let rule1 = &tee.rules[&key(DEFAULT_SCHEMA, "t1")];
+        assert_eq!(1000, rule1.ratio);
+        assert_eq!("t1_exp", rule1.target_table);
+        assert!(rule1.endpoint.is_none());
+        assert!(tee.rules.contains_key(&key("db1", "t1")));
+        let rule2 = &tee.rules[&key(DEFAULT_SCHEMA, "t2")];
+        assert_eq!("t2", rule2.target_table);
+        assert_eq!(
+            Some(Endpoint::new("127.0.0.1".to_string(), 8831)),
+            rule2.endpoint
+        );
+    }
+
+    #[test]
+    fn test_split_sampled_series() {
+        let tee = new_tee(vec![TeeRule {
+            schema: String::new(),
+            table: "t1".to_string(),
+            percent: 20.0,
+            target_table: "t1_exp".to_string(),
+            endpoint: String::new(),
+        }]);
+
+        let mut req = write_request("t1", 1000);
+        req.table_requests
+            .extend(write_request("t2", 10).table_requests);
+        let mirrored = tee.split(&req);
+        assert_eq!(1, mirrored.len());
+        let local = &mirrored[&None];
+        assert_eq!(1, local.table_requests.len());
+        assert_eq!("t1_exp", local.table_requests[0].table);
+        let num_mirrored = local.table_requests[0].entries.len();
+        assert!((100..300).contains(&num_mirrored), "{num_mirrored}");
+
+        // The same series are mirrored.
+        let mirrored_again = tee.split(&req);
+        assert_eq!(local, &mirrored_again[&None]);
+
+        // The table of the same name in other schemas is not mirrored.
+        req.context = Some(RequestContext {
+            database: "db1".to_string(),
+        });
+        assert!(tee.split(&req).is_empty());
+    }
+}
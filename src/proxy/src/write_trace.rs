@@ -0,0 +1,128 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sample the write requests of the configured tables to trace the stages of
+//! them, so the slow ingestion of a single table can be diagnosed without
+//! tracing all the writes.
+
+use std::collections::HashSet;
+
+use horaedbproto::storage::WriteRequest;
+use serde::{Deserialize, Serialize};
+use trace_metric::{collector::FormatCollectorVisitor, MetricsCollector};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Tables whose writes are sampled, nothing is traced if empty.
+    pub tables: Vec<String>,
+    /// Fraction of the writes to the tables being traced, in [0, 1].
+    pub sample_ratio: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tables: Vec::new(),
+            sample_ratio: 0.01,
+        }
+    }
+}
+
+pub struct WriteTraceSampler {
+    tables: HashSet<String>,
+    sample_ratio: f64,
+}
+
+impl WriteTraceSampler {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            tables: config.tables.iter().cloned().collect(),
+            sample_ratio: config.sample_ratio.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Decide whether to trace the request, and the collector of the trace is
+    /// returned if it's sampled.
+    pub fn sample(&self, req: &WriteRequest) -> Option<MetricsCollector> {
+        if self.tables.is_empty() || self.sample_ratio <= 0.0 {
+            return None;
+        }
+
+        let tables: Vec<_> = req
+            .table_requests
+            .iter()
+            .filter(|table_req| self.tables.contains(&table_req.table))
+            .map(|table_req| table_req.table.as_str())
+            .collect();
+        if tables.is_empty() || rand::random::<f64>() >= self.sample_ratio {
+            return None;
+        }
+
+        Some(MetricsCollector::new(format!(
+            "write_trace, tables:{}",
+            tables.join(",")
+        )))
+    }
+}
+
+/// Format the trace into the multi-line text for logging.
+pub fn format_trace(collector: &MetricsCollector) -> String {
+    let mut visitor = FormatCollectorVisitor::default();
+    collector.visit(&mut visitor);
+    visitor.into_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use horaedbproto::storage::WriteTableRequest;
+
+    use super::*;
+
+    fn write_request(tables: &[&str]) -> WriteRequest {
+        WriteRequest {
+            context: None,
+            table_requests: tables
+                .iter()
+                .map(|table| WriteTableRequest {
+                    table: table.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let sampler = WriteTraceSampler::new(&Config {
+            tables: vec!["t1".to_string(), "t2".to_string()],
+            sample_ratio: 1.0,
+        });
+        assert!(sampler.sample(&write_request(&["t3"])).is_none());
+        let collector = sampler.sample(&write_request(&["t3", "t2"])).unwrap();
+        assert_eq!(collector.name(), "write_trace, tables:t2");
+
+        let sampler = WriteTraceSampler::new(&Config {
+            tables: vec!["t1".to_string()],
+            sample_ratio: 0.0,
+        });
+        assert!(sampler.sample(&write_request(&["t1"])).is_none());
+
+        let sampler = WriteTraceSampler::new(&Config::default());
+        assert!(sampler.sample(&write_request(&["t1"])).is_none());
+    }
+}
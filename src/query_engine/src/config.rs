@@ -16,16 +16,46 @@
 // under the License.
 
 use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
 use time_ext::ReadableDuration;
 
+use crate::remote_udf::RemoteUdfConfig;
+
 // FIXME: Use cpu number as the default parallelism
 const DEFAULT_READ_PARALLELISM: usize = 8;
+const DEFAULT_ADAPTIVE_JOIN_COLLECT_LEFT_THRESHOLD: usize = 100_000;
+const DEFAULT_PARTITION_SCAN_MAX_RETRIES: usize = 2;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 pub struct Config {
     pub read_parallelism: usize,
     pub expensive_query_threshold: ReadableDuration,
+    /// Decide the join strategy according to the actual rows of the build side
+    /// at runtime rather than the estimated statistics.
+    pub enable_adaptive_join: bool,
+    /// The build side with rows not greater than this threshold will be
+    /// collected into one shared hash table in the adaptive join.
+    pub adaptive_join_collect_left_threshold: usize,
+    /// Max execution time of a query, no limit if it's not set.
+    pub max_execution_time: Option<ReadableDuration>,
+    /// Max rows scanned from the tables by a query, no limit if it's not set.
+    pub max_scanned_rows: Option<u64>,
+    /// Max bytes scanned from the tables by a query, no limit if it's not set.
+    pub max_scanned_bytes: Option<ReadableSize>,
+    /// Max memory used by the operators of a query, e.g. sort and aggregate, no
+    /// limit if it's not set.
+    pub max_memory_per_query: Option<ReadableSize>,
+    /// Udfs evaluated by the external services.
+    pub remote_udfs: Vec<RemoteUdfConfig>,
+    /// Directory of the wasm modules whose functions are loaded as udfs, the
+    /// udfs can't be created by sql if it's not set.
+    pub udf_dir: Option<String>,
+    /// Max times to send the scan of a partition again if it fails before
+    /// returning any rows, zero means no retry.
+    pub partition_scan_max_retries: usize,
+    /// Wait time before sending the failed scan of a partition again.
+    pub partition_scan_retry_backoff: ReadableDuration,
 }
 
 impl Default for Config {
@@ -33,6 +63,16 @@ impl Default for Config {
         Self {
             read_parallelism: DEFAULT_READ_PARALLELISM,
             expensive_query_threshold: ReadableDuration::hours(24),
+            enable_adaptive_join: false,
+            adaptive_join_collect_left_threshold: DEFAULT_ADAPTIVE_JOIN_COLLECT_LEFT_THRESHOLD,
+            max_execution_time: None,
+            max_scanned_rows: None,
+            max_scanned_bytes: None,
+            max_memory_per_query: None,
+            remote_udfs: Vec::new(),
+            udf_dir: None,
+            partition_scan_max_retries: DEFAULT_PARTITION_SCAN_MAX_RETRIES,
+            partition_scan_retry_backoff: ReadableDuration::millis(500),
         }
     }
 }
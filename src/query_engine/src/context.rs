@@ -21,6 +21,9 @@ use std::{sync::Arc, time::Instant};
 
 use common_types::request_id::RequestId;
 use runtime::Priority;
+use table_engine::{query_warning::QueryWarnings, scan_quota::ScanUsageRef};
+
+use crate::stage::QueryStagesRef;
 
 pub type ContextRef = Arc<Context>;
 
@@ -32,4 +35,11 @@ pub struct Context {
     pub default_catalog: String,
     pub default_schema: String,
     pub priority: Priority,
+    /// Collect the resources consumed by the table scans if it's set.
+    pub scan_usage: Option<ScanUsageRef>,
+    /// Record the stages of the query if it's set.
+    pub stages: Option<QueryStagesRef>,
+    /// Collect the warnings of the query, including the ones of the remote
+    /// scans.
+    pub warnings: QueryWarnings,
 }
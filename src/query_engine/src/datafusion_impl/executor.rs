@@ -15,14 +15,23 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
+use common_types::{record_batch::RecordBatch, schema::RecordSchema};
+use futures::{Stream, StreamExt};
 use generic_error::BoxError;
 use logger::debug;
 use snafu::ResultExt;
-use table_engine::stream::SendableRecordBatchStream;
+use table_engine::stream::{self, ErrNoSource, RecordBatchStream, SendableRecordBatchStream};
 use time_ext::InstantExt;
+use tokio::time::Sleep;
 
 use crate::{
     context::Context,
@@ -32,6 +41,7 @@ use crate::{
     },
     error::*,
     executor::Executor,
+    metrics::EXECUTE_DURATION_HISTOGRAM,
     physical_planner::{PhysicalPlanRef, TaskExecContext},
 };
 
@@ -89,6 +99,7 @@ impl Executor for DatafusionExecutorImpl {
             .with_context(|| ExecutorWithCause {
                 msg: Some("failed to execute physical plan".to_string()),
             })?;
+        EXECUTE_DURATION_HISTOGRAM.observe(begin_instant.saturating_elapsed().as_secs_f64());
 
         debug!(
             "DatafusionExecutorImpl finish to execute plan, request_id:{}, cost:{}ms, plan_and_metrics:{}",
@@ -97,6 +108,95 @@ impl Executor for DatafusionExecutorImpl {
             physical_plan.metrics_to_string()
         );
 
-        Ok(stream)
+        match self.df_ctx_builder.config().max_execution_time {
+            Some(max_execution_time) => Ok(Box::pin(TimeLimitedStream::new(
+                stream,
+                max_execution_time
+                    .0
+                    .saturating_sub(begin_instant.saturating_elapsed()),
+            ))),
+            None => Ok(stream),
+        }
+    }
+}
+
+/// Stream failing once the query runs longer than the max execution time, and
+/// it ends after the failure.
+struct TimeLimitedStream {
+    inner: SendableRecordBatchStream,
+    timer: Pin<Box<Sleep>>,
+    timed_out: bool,
+}
+
+impl TimeLimitedStream {
+    fn new(inner: SendableRecordBatchStream, remaining: Duration) -> Self {
+        Self {
+            inner,
+            timer: Box::pin(tokio::time::sleep(remaining)),
+            timed_out: false,
+        }
+    }
+}
+
+impl Stream for TimeLimitedStream {
+    type Item = stream::Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        if self.timed_out {
+            return Poll::Ready(None);
+        }
+
+        if self.timer.as_mut().poll(cx).is_ready() {
+            self.timed_out = true;
+            return Poll::Ready(Some(
+                ErrNoSource {
+                    msg: "query exceeds the max execution time",
+                }
+                .fail(),
+            ));
+        }
+
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl RecordBatchStream for TimeLimitedStream {
+    fn schema(&self) -> &RecordSchema {
+        self.inner.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use super::*;
+
+    /// Stream never returns any batch.
+    struct PendingStream(RecordSchema);
+
+    impl Stream for PendingStream {
+        type Item = stream::Result<RecordBatch>;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Pending
+        }
+    }
+
+    impl RecordBatchStream for PendingStream {
+        fn schema(&self) -> &RecordSchema {
+            &self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_limited_stream() {
+        let arrow_schema = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        let schema = RecordSchema::try_from(Arc::new(arrow_schema)).unwrap();
+        let mut stream =
+            TimeLimitedStream::new(Box::pin(PendingStream(schema)), Duration::from_millis(10));
+
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
     }
 }
@@ -0,0 +1,111 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Memory pool of the queries limited by the memory budget.
+
+use std::sync::Arc;
+
+use alloc_tracker::budget::{Category, MemoryBudgetRef};
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation},
+};
+
+/// Memory pool reserving the memory of the queries from the query limit of
+/// the [MemoryBudget](alloc_tracker::budget::MemoryBudget) besides its inner
+/// pool.
+///
+/// The operators able to spill spill their states once the limit is exceeded,
+/// and other operators abort the query.
+#[derive(Debug)]
+pub struct BudgetMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    budget: MemoryBudgetRef,
+}
+
+impl BudgetMemoryPool {
+    pub fn new(inner: Arc<dyn MemoryPool>, budget: MemoryBudgetRef) -> Self {
+        Self { inner, budget }
+    }
+}
+
+impl MemoryPool for BudgetMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.budget.reserve(Category::Query, additional);
+        self.inner.grow(reservation, additional);
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.budget.release(Category::Query, shrink);
+        self.inner.shrink(reservation, shrink);
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        if !self.budget.try_reserve(Category::Query, additional) {
+            return Err(DataFusionError::ResourcesExhausted(format!(
+                "Failed to allocate additional {additional} bytes, memory.query_limit:{} is exceeded, used:{}",
+                self.budget.limit(Category::Query),
+                self.budget.usage(Category::Query),
+            )));
+        }
+
+        if let Err(e) = self.inner.try_grow(reservation, additional) {
+            self.budget.release(Category::Query, additional);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.inner.reserved()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc_tracker::budget::MemoryBudget;
+    use datafusion::execution::memory_pool::GreedyMemoryPool;
+
+    use super::*;
+
+    #[test]
+    fn test_budget_memory_pool() {
+        let budget = Arc::new(MemoryBudget::new(0, 50, 0));
+        let pool: Arc<dyn MemoryPool> = Arc::new(BudgetMemoryPool::new(
+            Arc::new(GreedyMemoryPool::new(100)),
+            budget.clone(),
+        ));
+
+        let mut reservation = MemoryConsumer::new("test").register(&pool);
+        reservation.try_grow(40).unwrap();
+        assert!(reservation.try_grow(20).is_err());
+        assert_eq!(40, budget.usage(Category::Query));
+        assert_eq!(40, pool.reserved());
+
+        drop(reservation);
+        assert_eq!(0, budget.usage(Category::Query));
+        assert_eq!(0, pool.reserved());
+    }
+}
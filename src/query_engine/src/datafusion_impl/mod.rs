@@ -17,30 +17,41 @@
 
 use std::{sync::Arc, time::Instant};
 
+use alloc_tracker::budget::MemoryBudgetRef;
 use catalog::manager::ManagerRef as CatalogManager;
 use datafusion::{
     execution::{
         context::SessionState,
+        memory_pool::{GreedyMemoryPool, MemoryPool},
         runtime_env::{RuntimeConfig, RuntimeEnv},
         FunctionRegistry,
     },
     prelude::{SessionConfig, SessionContext},
 };
-use df_engine_extensions::codec::PhysicalExtensionCodecImpl;
-use table_engine::{provider::HoraeDBOptions, remote::RemoteEngineRef};
+use df_engine_extensions::{
+    codec::PhysicalExtensionCodecImpl,
+    dist_sql_query::{physical_plan::AggregatePushDownChecker, ScanRetryPolicy},
+};
+use table_engine::{provider::HoraeDBOptions, remote::RemoteEngineRef, scan_quota::ScanQuota};
 
 use crate::{
     context::Context,
     datafusion_impl::{
-        executor::DatafusionExecutorImpl, physical_planner::DatafusionPhysicalPlannerImpl,
-        physical_planner_extension::QueryPlannerAdapter, task_context::Preprocessor,
+        executor::DatafusionExecutorImpl,
+        memory_pool::BudgetMemoryPool,
+        physical_optimizer::{adaptive_join::AdaptiveJoinRule, remote_udf::RemoteUdfRule},
+        physical_planner::DatafusionPhysicalPlannerImpl,
+        physical_planner_extension::QueryPlannerAdapter,
+        task_context::Preprocessor,
     },
     executor::ExecutorRef,
     physical_planner::PhysicalPlannerRef,
+    remote_udf::RemoteUdfs,
     Config, QueryEngine,
 };
 
 pub mod executor;
+pub mod memory_pool;
 pub mod physical_optimizer;
 pub mod physical_plan;
 pub mod physical_plan_extension;
@@ -52,6 +63,7 @@ use crate::error::*;
 
 #[derive(Debug)]
 pub struct DatafusionQueryEngineImpl {
+    config: Config,
     physical_planner: PhysicalPlannerRef,
     executor: ExecutorRef,
 }
@@ -63,10 +75,18 @@ impl DatafusionQueryEngineImpl {
         function_registry: Arc<dyn FunctionRegistry + Send + Sync>,
         remote_engine: RemoteEngineRef,
         catalog_manager: CatalogManager,
+        aggr_checker: AggregatePushDownChecker,
+        memory_budget: Option<MemoryBudgetRef>,
+        remote_udfs: Arc<RemoteUdfs>,
     ) -> Result<Self> {
         let runtime_env = Arc::new(RuntimeEnv::new(runtime_config).unwrap());
         let df_physical_planner = Arc::new(QueryPlannerAdapter);
-        let df_ctx_builder = Arc::new(DfContextBuilder::new(config, runtime_env.clone()));
+        let df_ctx_builder = Arc::new(DfContextBuilder::new(
+            config.clone(),
+            runtime_env.clone(),
+            memory_budget,
+            remote_udfs,
+        ));
         let physical_planner = Arc::new(DatafusionPhysicalPlannerImpl::new(
             df_ctx_builder.clone(),
             df_physical_planner,
@@ -74,16 +94,23 @@ impl DatafusionQueryEngineImpl {
 
         // Executor
         let extension_codec = Arc::new(PhysicalExtensionCodecImpl::new());
+        let scan_retry = ScanRetryPolicy {
+            max_retries: config.partition_scan_max_retries,
+            backoff: config.partition_scan_retry_backoff.0,
+        };
         let preprocessor = Arc::new(Preprocessor::new(
             remote_engine,
             catalog_manager,
             runtime_env.clone(),
             function_registry.clone(),
             extension_codec,
+            aggr_checker,
+            scan_retry,
         ));
         let executor = Arc::new(DatafusionExecutorImpl::new(df_ctx_builder, preprocessor));
 
         Ok(Self {
+            config,
             physical_planner,
             executor,
         })
@@ -91,6 +118,10 @@ impl DatafusionQueryEngineImpl {
 }
 
 impl QueryEngine for DatafusionQueryEngineImpl {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
     fn physical_planner(&self) -> PhysicalPlannerRef {
         self.physical_planner.clone()
     }
@@ -105,16 +136,55 @@ impl QueryEngine for DatafusionQueryEngineImpl {
 pub struct DfContextBuilder {
     config: Config,
     runtime_env: Arc<RuntimeEnv>,
+    /// The memory of the queries is reserved from the budget if it's set.
+    memory_budget: Option<MemoryBudgetRef>,
+    remote_udfs: Arc<RemoteUdfs>,
 }
 
 impl DfContextBuilder {
-    pub fn new(config: Config, runtime_env: Arc<RuntimeEnv>) -> Self {
+    pub fn new(
+        config: Config,
+        runtime_env: Arc<RuntimeEnv>,
+        memory_budget: Option<MemoryBudgetRef>,
+        remote_udfs: Arc<RemoteUdfs>,
+    ) -> Self {
         Self {
             config,
             runtime_env,
+            memory_budget,
+            remote_udfs,
         }
     }
 
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The runtime env of a query, whose memory is limited by its own pool if
+    /// `max_memory_per_query` is set, and by the query limit of the memory
+    /// budget shared by all the queries.
+    fn query_runtime_env(&self) -> Arc<RuntimeEnv> {
+        if self.config.max_memory_per_query.is_none() && self.memory_budget.is_none() {
+            return self.runtime_env.clone();
+        }
+
+        let mut memory_pool: Arc<dyn MemoryPool> = match self.config.max_memory_per_query {
+            Some(limit) => Arc::new(GreedyMemoryPool::new(limit.as_byte() as usize)),
+            None => self.runtime_env.memory_pool.clone(),
+        };
+        if let Some(budget) = &self.memory_budget {
+            memory_pool = Arc::new(BudgetMemoryPool::new(memory_pool, budget.clone()));
+        }
+
+        Arc::new(RuntimeEnv {
+            memory_pool,
+            disk_manager: self.runtime_env.disk_manager.clone(),
+            cache_manager: self.runtime_env.cache_manager.clone(),
+            object_store_registry: self.runtime_env.object_store_registry.clone(),
+        })
+    }
+
     pub fn build(&self, ctx: &Context) -> SessionContext {
         let timeout = ctx
             .deadline
@@ -134,10 +204,29 @@ impl DfContextBuilder {
             .with_target_partitions(self.config.read_parallelism);
 
         df_session_config.options_mut().extensions.insert(options);
+        let scan_quota = ScanQuota {
+            usage: ctx.scan_usage.clone().unwrap_or_default(),
+            max_rows: self.config.max_scanned_rows,
+            max_bytes: self.config.max_scanned_bytes.map(|v| v.as_byte()),
+        };
+        let df_session_config = df_session_config
+            .with_extension(Arc::new(scan_quota))
+            .with_extension(Arc::new(ctx.warnings.clone()));
 
         // Using default logcial optimizer, if want to add more custom rule, using
         // `add_optimizer_rule` to add.
-        let state = SessionState::new_with_config_rt(df_session_config, self.runtime_env.clone());
+        let mut state =
+            SessionState::new_with_config_rt(df_session_config, self.query_runtime_env());
+        if self.config.enable_adaptive_join {
+            state = state.add_physical_optimizer_rule(Arc::new(AdaptiveJoinRule::new(
+                self.config.adaptive_join_collect_left_threshold,
+            )));
+        }
+        if !self.remote_udfs.is_empty() {
+            state = state.add_physical_optimizer_rule(Arc::new(RemoteUdfRule::new(
+                self.remote_udfs.clone(),
+            )));
+        }
         SessionContext::new_with_state(state)
     }
 }
@@ -0,0 +1,282 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimization rule replacing the hash join with the [AdaptiveJoinExec].
+
+use std::sync::Arc;
+
+use datafusion::{
+    config::ConfigOptions,
+    error::Result as DataFusionResult,
+    logical_expr::JoinType,
+    physical_optimizer::optimizer::PhysicalOptimizerRule,
+    physical_plan::{
+        coalesce_batches::CoalesceBatchesExec, coalesce_partitions::CoalescePartitionsExec,
+        joins::HashJoinExec, repartition::RepartitionExec,
+        sorts::sort_preserving_merge::SortPreservingMergeExec, Distribution, ExecutionPlan,
+        Partitioning,
+    },
+};
+use logger::debug;
+
+use crate::datafusion_impl::physical_plan_extension::adaptive_join::AdaptiveJoinExec;
+
+/// Replace the [HashJoinExec] with [AdaptiveJoinExec], whose join strategy is
+/// decided according to the actual rows of the build side at runtime.
+///
+/// The output of [AdaptiveJoinExec] is not hash partitioned, so the joins whose
+/// ancestors rely on their hash partitioning, e.g. a partitioned aggregate or
+/// a partitioned outer join above them, are kept as is.
+#[derive(Debug)]
+pub struct AdaptiveJoinRule {
+    collect_left_threshold: usize,
+}
+
+impl AdaptiveJoinRule {
+    pub fn new(collect_left_threshold: usize) -> Self {
+        Self {
+            collect_left_threshold,
+        }
+    }
+
+    /// Remove the exchanges added for the join by the planner, and the
+    /// [AdaptiveJoinExec] will decide them by itself.
+    fn strip_exchange(plan: &Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        if let Some(coalesce) = plan.as_any().downcast_ref::<CoalesceBatchesExec>() {
+            if coalesce.input().as_any().is::<RepartitionExec>() {
+                return Self::strip_exchange(coalesce.input());
+            }
+        }
+
+        if let Some(repartition) = plan.as_any().downcast_ref::<RepartitionExec>() {
+            if matches!(repartition.partitioning(), Partitioning::Hash(_, _)) {
+                return repartition.input().clone();
+            }
+        }
+
+        if let Some(coalesce) = plan.as_any().downcast_ref::<CoalescePartitionsExec>() {
+            return coalesce.input().clone();
+        }
+
+        plan.clone()
+    }
+
+    /// Whether the output partitioning of the plan is decided by the plan
+    /// itself rather than by its children.
+    fn resets_distribution(plan: &Arc<dyn ExecutionPlan>) -> bool {
+        let plan = plan.as_any();
+        plan.is::<RepartitionExec>()
+            || plan.is::<CoalescePartitionsExec>()
+            || plan.is::<SortPreservingMergeExec>()
+    }
+
+    /// Rewrite the hash joins of the plan bottom up, `dist_required` tells
+    /// whether any ancestor relies on the hash partitioning of the plan.
+    fn rewrite(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        dist_required: bool,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let children = plan.children();
+        let passes_through = dist_required && !Self::resets_distribution(&plan);
+        let mut new_children = Vec::with_capacity(children.len());
+        let mut changed = false;
+        let required = plan.required_input_distribution();
+        for (child, distribution) in children.into_iter().zip(required) {
+            let child_required =
+                passes_through || matches!(distribution, Distribution::HashPartitioned(_));
+            let new_child = self.rewrite(child.clone(), child_required)?;
+            changed |= !Arc::ptr_eq(&child, &new_child);
+            new_children.push(new_child);
+        }
+
+        let plan = if changed {
+            plan.with_new_children(new_children)?
+        } else {
+            plan
+        };
+        if dist_required {
+            return Ok(plan);
+        }
+
+        Ok(self.try_rewrite(&plan).unwrap_or(plan))
+    }
+
+    fn try_rewrite(&self, plan: &Arc<dyn ExecutionPlan>) -> Option<Arc<dyn ExecutionPlan>> {
+        let join = plan.as_any().downcast_ref::<HashJoinExec>()?;
+        // The unmatched rows of the build side are output by every partition
+        // when collecting left, so only the join types not outputting them are
+        // supported.
+        if !matches!(
+            join.join_type(),
+            JoinType::Inner | JoinType::Right | JoinType::RightSemi | JoinType::RightAnti
+        ) {
+            return None;
+        }
+
+        let left = Self::strip_exchange(join.left());
+        let right = Self::strip_exchange(join.right());
+
+        match AdaptiveJoinExec::try_new(
+            left,
+            right,
+            join.on().to_vec(),
+            join.filter().cloned(),
+            *join.join_type(),
+            join.null_equals_null(),
+            self.collect_left_threshold,
+        ) {
+            Ok(adaptive_join) => Some(Arc::new(adaptive_join)),
+            Err(e) => {
+                debug!("AdaptiveJoinRule failed to rewrite hash join, err:{e}");
+                None
+            }
+        }
+    }
+}
+
+impl PhysicalOptimizerRule for AdaptiveJoinRule {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        self.rewrite(plan, false)
+    }
+
+    fn name(&self) -> &str {
+        "adaptive_join"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+        util::pretty::pretty_format_batches,
+    };
+    use datafusion::{
+        datasource::MemTable,
+        execution::{context::SessionState, runtime_env::RuntimeEnv},
+        physical_plan::{collect, displayable},
+        prelude::{SessionConfig, SessionContext},
+    };
+
+    use super::*;
+
+    fn build_table(columns: [&str; 2], partitions: &[&[(i64, i64)]]) -> Arc<MemTable> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(columns[0], DataType::Int64, false),
+            Field::new(columns[1], DataType::Int64, false),
+        ]));
+        let partitions = partitions
+            .iter()
+            .map(|rows| {
+                let (keys, values): (Vec<_>, Vec<_>) = rows.iter().cloned().unzip();
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(keys)),
+                        Arc::new(Int64Array::from(values)),
+                    ],
+                )
+                .unwrap();
+                vec![batch]
+            })
+            .collect();
+
+        Arc::new(MemTable::try_new(schema, partitions).unwrap())
+    }
+
+    /// Build the context with the tables, the rule is added only if the
+    /// `threshold` is given.
+    fn build_context(threshold: Option<usize>) -> SessionContext {
+        let mut config = SessionConfig::new().with_target_partitions(4);
+        // Plan the partitioned joins rather than collecting the small tables.
+        config.options_mut().optimizer.hash_join_single_partition_threshold = 0;
+        let mut state = SessionState::new_with_config_rt(config, Arc::new(RuntimeEnv::default()));
+        if let Some(threshold) = threshold {
+            let rule = AdaptiveJoinRule::new(threshold);
+            state = state.add_physical_optimizer_rule(Arc::new(rule));
+        }
+
+        // The same keys are spread in the partitions of the tables, so the
+        // results are wrong if the outputs are taken as hash partitioned.
+        let ctx = SessionContext::new_with_state(state);
+        let tables = [
+            ("t1", ["a", "b"], [[(1, 10), (2, 20)], [(3, 30), (1, 11)]]),
+            ("t2", ["a", "c"], [[(1, 100), (3, 300)], [(2, 200), (1, 101)]]),
+            ("t3", ["a", "d"], [[(1, 1000), (4, 4000)], [(2, 2000), (5, 5000)]]),
+        ];
+        for (name, columns, [p0, p1]) in tables {
+            let table = build_table(columns, &[&p0, &p1]);
+            ctx.register_table(name, table).unwrap();
+        }
+
+        ctx
+    }
+
+    /// Plan and run the sql, returns whether the plan has the adaptive join,
+    /// and the formatted results.
+    async fn run_sql(ctx: &SessionContext, sql: &str) -> (bool, String) {
+        let df = ctx.sql(sql).await.unwrap();
+        let plan = df.create_physical_plan().await.unwrap();
+        let displayed = displayable(plan.as_ref()).indent(true).to_string();
+        let has_adaptive_join = displayed.contains("AdaptiveJoinExec");
+
+        let batches = collect(plan, ctx.task_ctx()).await.unwrap();
+        let results = pretty_format_batches(&batches).unwrap().to_string();
+        (has_adaptive_join, results)
+    }
+
+    async fn check_sql(sql: &str, expect_adaptive_join: bool) {
+        let (_, expected) = run_sql(&build_context(None), sql).await;
+        // Both the modes of the adaptive join are checked.
+        for threshold in [0, usize::MAX] {
+            let ctx = build_context(Some(threshold));
+            let (has_adaptive_join, results) = run_sql(&ctx, sql).await;
+            assert_eq!(expect_adaptive_join, has_adaptive_join, "sql:{sql}");
+            assert_eq!(expected, results, "sql:{sql}, threshold:{threshold}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_join() {
+        let sql = "SELECT t1.a, t1.b, t2.c FROM t1 JOIN t2 ON t1.a = t2.a ORDER BY t1.b, t2.c";
+        check_sql(sql, true).await;
+    }
+
+    #[tokio::test]
+    async fn test_keep_join_under_partitioned_aggregate() {
+        let sql = "SELECT t1.a, count(*) AS cnt FROM t1 JOIN t2 ON t1.a = t2.a \
+                   GROUP BY t1.a ORDER BY t1.a";
+        check_sql(sql, false).await;
+    }
+
+    #[tokio::test]
+    async fn test_keep_join_under_outer_join() {
+        let sql = "SELECT t1.a, t1.b, t2.c, t3.d FROM t1 JOIN t2 ON t1.a = t2.a \
+                   LEFT JOIN t3 ON t1.a = t3.a ORDER BY t1.b, t2.c";
+        check_sql(sql, false).await;
+    }
+}
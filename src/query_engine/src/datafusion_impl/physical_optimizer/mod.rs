@@ -23,7 +23,9 @@ use crate::datafusion_impl::physical_optimizer::{
     coalesce_batches::CoalesceBatchesAdapter, repartition::RepartitionAdapter,
 };
 
+pub mod adaptive_join;
 pub mod coalesce_batches;
+pub mod remote_udf;
 pub mod repartition;
 
 pub type OptimizeRuleRef = Arc<dyn PhysicalOptimizerRule + Send + Sync>;
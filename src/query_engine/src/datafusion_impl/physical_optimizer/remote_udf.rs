@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimization rule replacing the projections calling the remote udfs with
+//! [RemoteUdfExec].
+
+use std::sync::Arc;
+
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    physical_optimizer::optimizer::PhysicalOptimizerRule,
+    physical_plan::{projection::ProjectionExec, ExecutionPlan},
+};
+use logger::debug;
+
+use crate::{
+    datafusion_impl::physical_plan_extension::remote_udf::RemoteUdfExec, remote_udf::RemoteUdfs,
+};
+
+/// Replace the [ProjectionExec] calling the remote udfs at the top level of its
+/// expressions with [RemoteUdfExec].
+///
+/// The calls elsewhere, e.g. in the filters or nested in other expressions,
+/// are not rewritten and evaluated by the stubs of the remote udfs, see
+/// [crate::remote_udf].
+#[derive(Debug)]
+pub struct RemoteUdfRule {
+    udfs: Arc<RemoteUdfs>,
+}
+
+impl RemoteUdfRule {
+    pub fn new(udfs: Arc<RemoteUdfs>) -> Self {
+        Self { udfs }
+    }
+
+    fn try_rewrite(
+        &self,
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> datafusion::error::Result<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(projection) = plan.as_any().downcast_ref::<ProjectionExec>() else {
+            return Ok(None);
+        };
+        let Some(exec) = RemoteUdfExec::try_new(projection, &self.udfs)? else {
+            return Ok(None);
+        };
+
+        debug!(
+            "RemoteUdfRule rewrite projection, expr:{:?}",
+            projection.expr()
+        );
+        Ok(Some(Arc::new(exec)))
+    }
+}
+
+impl PhysicalOptimizerRule for RemoteUdfRule {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        plan.transform_down(&|plan| {
+            Ok(match self.try_rewrite(&plan)? {
+                Some(new_plan) => Transformed::Yes(new_plan),
+                None => Transformed::No(plan),
+            })
+        })
+    }
+
+    fn name(&self) -> &str {
+        "remote_udf"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
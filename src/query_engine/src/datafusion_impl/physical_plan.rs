@@ -25,17 +25,25 @@ use std::{
 
 use async_trait::async_trait;
 use datafusion::physical_plan::{
-    coalesce_partitions::CoalescePartitionsExec, display::DisplayableExecutionPlan, displayable,
-    ExecutionPlan,
+    analyze::AnalyzeExec, coalesce_partitions::CoalescePartitionsExec,
+    display::DisplayableExecutionPlan, displayable, ExecutionPlan,
 };
 use generic_error::BoxError;
 use logger::debug;
 use snafu::{OptionExt, ResultExt};
-use table_engine::stream::{FromDfStream, SendableRecordBatchStream};
+use table_engine::{
+    scan_quota::ScanUsageRef,
+    stream::{FromDfStream, SendableRecordBatchStream},
+};
 
 use crate::{
+    datafusion_impl::physical_plan_extension::{
+        explain_json::{ExplainJson, ExplainJsonExec},
+        stage::StageExec,
+    },
     error::*,
     physical_planner::{PhysicalPlan, TaskExecContext},
+    stage::QueryStagesRef,
 };
 
 pub enum TypedPlan {
@@ -64,6 +72,9 @@ pub struct DataFusionPhysicalPlanAdapter {
 
     /// Executed plan from `original_plan` converting
     executed_plan: RwLock<Option<Arc<dyn ExecutionPlan>>>,
+
+    /// Explain the plan in json format if it's set
+    explain_json: Option<ExplainJson>,
 }
 
 impl DataFusionPhysicalPlanAdapter {
@@ -71,8 +82,45 @@ impl DataFusionPhysicalPlanAdapter {
         Self {
             original_plan: typed_plan,
             executed_plan: RwLock::new(None),
+            explain_json: None,
         }
     }
+
+    pub fn with_explain_json(mut self, explain_json: ExplainJson) -> Self {
+        self.explain_json = Some(explain_json);
+        self
+    }
+
+    /// Record the stages of the `plan` by the [StageExec] on its top.
+    ///
+    /// The [StageExec] is placed under the [AnalyzeExec] (or the analyzing
+    /// [ExplainJsonExec]) to record the stages of the analyzed plan rather
+    /// than its output.
+    fn with_stage_exec(
+        plan: Arc<dyn ExecutionPlan>,
+        stages: QueryStagesRef,
+        scan_usage: Option<ScanUsageRef>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let is_analyze = plan.as_any().is::<AnalyzeExec>()
+            || plan
+                .as_any()
+                .downcast_ref::<ExplainJsonExec>()
+                .is_some_and(ExplainJsonExec::is_analyze);
+        if !is_analyze {
+            return Ok(Arc::new(StageExec::new(plan, stages, scan_usage)));
+        }
+
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| Arc::new(StageExec::new(child, stages.clone(), scan_usage.clone())) as _)
+            .collect();
+        plan.with_new_children(children)
+            .box_err()
+            .context(PhysicalPlanWithCause {
+                msg: Some("failed to record stages of analyze plan".to_string()),
+            })
+    }
 }
 
 impl Debug for DataFusionPhysicalPlanAdapter {
@@ -103,6 +151,12 @@ impl PhysicalPlan for DataFusionPhysicalPlanAdapter {
             .preprocessor
             .process(&self.original_plan, &df_task_ctx.ctx)
             .await?;
+        // Wrapped after the preprocessing, which resolves the partitioned scans
+        // under the `AnalyzeExec` to collect their metrics.
+        let executable = match &self.explain_json {
+            Some(explain_json) => Arc::new(ExplainJsonExec::new(executable, explain_json.clone())),
+            None => executable,
+        };
 
         // Coalesce the multiple outputs plan.
         let partition_count = executable.output_partitioning().partition_count();
@@ -111,6 +165,14 @@ impl PhysicalPlan for DataFusionPhysicalPlanAdapter {
         } else {
             Arc::new(CoalescePartitionsExec::new(executable))
         };
+        let executable = match &df_task_ctx.ctx.stages {
+            Some(stages) => Self::with_stage_exec(
+                executable,
+                stages.clone(),
+                df_task_ctx.ctx.scan_usage.clone(),
+            )?,
+            None => executable,
+        };
 
         debug!(
             "DatafusionExecutorImpl get the executable plan, request_id:{}, physical_plan:{}",
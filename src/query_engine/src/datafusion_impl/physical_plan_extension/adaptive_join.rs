@@ -0,0 +1,510 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Adaptive hash join which decides the join strategy according to the actual
+//! cardinality of the build side.
+
+use std::{
+    any::Any,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use common_types::schema::ArrowSchemaRef;
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::{context::TaskContext, memory_pool::MemoryConsumer},
+    logical_expr::JoinType,
+    physical_expr::{PhysicalExpr, PhysicalSortExpr},
+    physical_plan::{
+        coalesce_partitions::CoalescePartitionsExec,
+        expressions::Column,
+        joins::{utils::JoinFilter, HashJoinExec, PartitionMode},
+        memory::MemoryExec,
+        repartition::RepartitionExec,
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning,
+        SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use logger::info;
+use tokio::sync::OnceCell;
+
+type JoinOn = Vec<(Column, Column)>;
+
+/// AdaptiveJoinExec postpones the decision of the hash join strategy until the
+/// build side (left) is collected, whose cardinality is usually estimated
+/// wildly wrong on the fresh time series data:
+/// - If the actual rows of the build side is not greater than
+///   `collect_left_threshold`, one shared hash table is built and the probe
+///   side is not repartitioned (`PartitionMode::CollectLeft`).
+/// - Otherwise, both the sides are repartitioned by the join keys and the hash
+///   tables are built in parallel (`PartitionMode::Partitioned`).
+///
+/// The build side is read until its rows exceed `collect_left_threshold`, so at
+/// most `collect_left_threshold` rows (plus one batch) are buffered before the
+/// decision, and the buffered batches are accounted in the memory pool of the
+/// query. The rest of the build side is streamed into the repartition rather
+/// than collected.
+///
+/// The output partition count is always the same as the probe side, so the
+/// decision won't change the partition count seen by the parent plans. But the
+/// output is not guaranteed to be hash partitioned by the join keys, so it
+/// can't be placed under the plans relying on that.
+pub struct AdaptiveJoinExec {
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+    on: JoinOn,
+    filter: Option<JoinFilter>,
+    join_type: JoinType,
+    null_equals_null: bool,
+    collect_left_threshold: usize,
+    schema: ArrowSchemaRef,
+    /// The join plan decided at runtime, shared by all the partitions.
+    decided_join: Arc<OnceCell<Arc<dyn ExecutionPlan>>>,
+}
+
+impl AdaptiveJoinExec {
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: JoinOn,
+        filter: Option<JoinFilter>,
+        join_type: JoinType,
+        null_equals_null: bool,
+        collect_left_threshold: usize,
+    ) -> DataFusionResult<Self> {
+        // Build the join to check the arguments and get the output schema.
+        let join = HashJoinExec::try_new(
+            left.clone(),
+            right.clone(),
+            on.clone(),
+            filter.clone(),
+            &join_type,
+            PartitionMode::CollectLeft,
+            null_equals_null,
+        )?;
+        let schema = join.schema();
+
+        Ok(Self {
+            left,
+            right,
+            on,
+            filter,
+            join_type,
+            null_equals_null,
+            collect_left_threshold,
+            schema,
+            decided_join: Arc::new(OnceCell::new()),
+        })
+    }
+
+    fn new_with_children(
+        &self,
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+    ) -> DataFusionResult<Self> {
+        Self::try_new(
+            left,
+            right,
+            self.on.clone(),
+            self.filter.clone(),
+            self.join_type,
+            self.null_equals_null,
+            self.collect_left_threshold,
+        )
+    }
+
+    /// Read the build side until its rows exceed the threshold and decide the
+    /// join plan.
+    async fn decide_join(
+        &self,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let left_schema = self.left.schema();
+        let left = CoalescePartitionsExec::new(self.left.clone());
+        let mut left_stream = left.execute(0, context.clone())?;
+        let num_partitions = self.right.output_partitioning().partition_count();
+
+        let mut reservation =
+            MemoryConsumer::new("AdaptiveJoinExec").register(context.memory_pool());
+        let mut left_batches = Vec::new();
+        let mut left_rows = 0;
+        let mut exceeded = false;
+        while let Some(batch) = left_stream.try_next().await? {
+            reservation.try_grow(batch.get_array_memory_size())?;
+            left_rows += batch.num_rows();
+            left_batches.push(batch);
+            if left_rows > self.collect_left_threshold && num_partitions > 1 {
+                exceeded = true;
+                break;
+            }
+        }
+
+        let (left, right, mode): (Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>, _) =
+            if !exceeded {
+                let left = MemoryExec::try_new(&[left_batches], left_schema, None)?;
+                (
+                    Arc::new(left),
+                    self.right.clone(),
+                    PartitionMode::CollectLeft,
+                )
+            } else {
+                // The buffered batches are followed by the rest of the build side.
+                let left_stream =
+                    stream::iter(left_batches.into_iter().map(Ok)).chain(left_stream);
+                let left_stream = RecordBatchStreamAdapter::new(left_schema.clone(), left_stream);
+                let left = OnceStreamExec::new(left_schema, Box::pin(left_stream));
+                let (left_keys, right_keys) = self
+                    .on
+                    .iter()
+                    .map(|(l, r)| {
+                        (
+                            Arc::new(l.clone()) as Arc<dyn PhysicalExpr>,
+                            Arc::new(r.clone()) as Arc<dyn PhysicalExpr>,
+                        )
+                    })
+                    .unzip();
+                let left = RepartitionExec::try_new(
+                    Arc::new(left),
+                    Partitioning::Hash(left_keys, num_partitions),
+                )?;
+                let right = RepartitionExec::try_new(
+                    self.right.clone(),
+                    Partitioning::Hash(right_keys, num_partitions),
+                )?;
+                (Arc::new(left), Arc::new(right), PartitionMode::Partitioned)
+            };
+        // The hash join accounts the memory of its hash tables by itself.
+        reservation.free();
+
+        info!(
+            "AdaptiveJoinExec decide join mode, buffered_left_rows:{left_rows}, threshold:{}, partitions:{num_partitions}, mode:{mode:?}",
+            self.collect_left_threshold
+        );
+
+        let join = HashJoinExec::try_new(
+            left,
+            right,
+            self.on.clone(),
+            self.filter.clone(),
+            &self.join_type,
+            mode,
+            self.null_equals_null,
+        )?;
+
+        Ok(Arc::new(join))
+    }
+}
+
+impl fmt::Debug for AdaptiveJoinExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdaptiveJoinExec")
+            .field("on", &self.on)
+            .field("join_type", &self.join_type)
+            .field("collect_left_threshold", &self.collect_left_threshold)
+            .finish()
+    }
+}
+
+impl ExecutionPlan for AdaptiveJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.right.output_partitioning().partition_count())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            2 => Ok(Arc::new(
+                self.new_with_children(children[0].clone(), children[1].clone())?,
+            )),
+            _ => Err(DataFusionError::Internal(
+                "AdaptiveJoinExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        let this = Self {
+            left: self.left.clone(),
+            right: self.right.clone(),
+            on: self.on.clone(),
+            filter: self.filter.clone(),
+            join_type: self.join_type,
+            null_equals_null: self.null_equals_null,
+            collect_left_threshold: self.collect_left_threshold,
+            schema: self.schema.clone(),
+            decided_join: self.decided_join.clone(),
+        };
+        let output = stream::once(async move {
+            let join = this
+                .decided_join
+                .get_or_try_init(|| this.decide_join(context.clone()))
+                .await?;
+            join.execute(partition, context)
+        })
+        .try_flatten();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            output,
+        )))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+impl DisplayAs for AdaptiveJoinExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        let on = self
+            .on
+            .iter()
+            .map(|(l, r)| format!("({l}, {r})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "AdaptiveJoinExec: join_type={:?}, on=[{}], collect_left_threshold={}",
+            self.join_type, on, self.collect_left_threshold,
+        )
+    }
+}
+
+/// Plan outputting the given stream as its only partition, which can be
+/// executed only once.
+struct OnceStreamExec {
+    schema: ArrowSchemaRef,
+    stream: Mutex<Option<DfSendableRecordBatchStream>>,
+}
+
+impl OnceStreamExec {
+    fn new(schema: ArrowSchemaRef, stream: DfSendableRecordBatchStream) -> Self {
+        Self {
+            schema,
+            stream: Mutex::new(Some(stream)),
+        }
+    }
+}
+
+impl fmt::Debug for OnceStreamExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnceStreamExec").finish()
+    }
+}
+
+impl ExecutionPlan for OnceStreamExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        Err(DataFusionError::Internal(
+            "OnceStreamExec can't be rebuilt with children".to_string(),
+        ))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "OnceStreamExec invalid partition:{partition}"
+            )));
+        }
+
+        self.stream.lock().unwrap().take().ok_or_else(|| {
+            DataFusionError::Internal("OnceStreamExec is executed more than once".to_string())
+        })
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+impl DisplayAs for OnceStreamExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OnceStreamExec")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Array, Int64Array},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use datafusion::{
+        execution::runtime_env::{RuntimeConfig, RuntimeEnv},
+        physical_plan::collect,
+        prelude::{SessionConfig, SessionContext},
+    };
+
+    use super::*;
+
+    fn build_input(name: &str, partitions: &[&[(i64, i64)]]) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(format!("{name}_key"), DataType::Int64, false),
+            Field::new(format!("{name}_value"), DataType::Int64, false),
+        ]));
+        let partitions: Vec<_> = partitions
+            .iter()
+            .map(|rows| {
+                let (keys, values): (Vec<_>, Vec<_>) = rows.iter().cloned().unzip();
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(keys)),
+                        Arc::new(Int64Array::from(values)),
+                    ],
+                )
+                .unwrap();
+                vec![batch]
+            })
+            .collect();
+
+        Arc::new(MemoryExec::try_new(&partitions, schema, None).unwrap())
+    }
+
+    async fn sorted_rows(plan: Arc<dyn ExecutionPlan>) -> Vec<Vec<Option<i64>>> {
+        let batches = collect(plan, Arc::new(TaskContext::default()))
+            .await
+            .unwrap();
+        let mut rows = Vec::new();
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                let values = batch
+                    .columns()
+                    .iter()
+                    .map(|column| {
+                        let column = column.as_any().downcast_ref::<Int64Array>().unwrap();
+                        column.is_valid(row).then(|| column.value(row))
+                    })
+                    .collect();
+                rows.push(values);
+            }
+        }
+        rows.sort();
+        rows
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_join_exec() {
+        // The same keys are spread in the partitions of both the sides.
+        let left = build_input("l", &[&[(1, 10), (2, 20)], &[(1, 11), (4, 40)]]);
+        let right = build_input("r", &[&[(1, 100), (3, 300)], &[(2, 200), (1, 101)]]);
+        let on = vec![(Column::new("l_key", 0), Column::new("r_key", 0))];
+        let matched = vec![
+            vec![Some(1), Some(10), Some(1), Some(100)],
+            vec![Some(1), Some(10), Some(1), Some(101)],
+            vec![Some(1), Some(11), Some(1), Some(100)],
+            vec![Some(1), Some(11), Some(1), Some(101)],
+            vec![Some(2), Some(20), Some(2), Some(200)],
+        ];
+        let mut right_joined = vec![vec![None, None, Some(3), Some(300)]];
+        right_joined.extend(matched.clone());
+
+        // The build side has 4 rows, so it's collected with the threshold 4, and
+        // both the sides are repartitioned with the threshold 1 and 3, and the
+        // rest of the build side is streamed with the threshold 1.
+        for threshold in [1, 3, 4] {
+            for (join_type, expected) in [
+                (JoinType::Inner, &matched),
+                (JoinType::Right, &right_joined),
+            ] {
+                let join = AdaptiveJoinExec::try_new(
+                    left.clone(),
+                    right.clone(),
+                    on.clone(),
+                    None,
+                    join_type,
+                    false,
+                    threshold,
+                )
+                .unwrap();
+                assert_eq!(2, join.output_partitioning().partition_count());
+
+                let rows = sorted_rows(Arc::new(join)).await;
+                assert_eq!(expected, &rows, "threshold:{threshold}, join_type:{join_type}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_side_memory_limit() {
+        let left = build_input("l", &[&[(1, 10), (2, 20)], &[(1, 11), (4, 40)]]);
+        let right = build_input("r", &[&[(1, 100), (3, 300)], &[(2, 200), (1, 101)]]);
+        let on = vec![(Column::new("l_key", 0), Column::new("r_key", 0))];
+        let join =
+            AdaptiveJoinExec::try_new(left, right, on, None, JoinType::Inner, false, usize::MAX)
+                .unwrap();
+
+        // The buffered build side exceeds the memory limit of the query.
+        let runtime_config = RuntimeConfig::new().with_memory_limit(16, 1.0);
+        let runtime_env = Arc::new(RuntimeEnv::new(runtime_config).unwrap());
+        let ctx = SessionContext::new_with_config_rt(SessionConfig::new(), runtime_env);
+        let res = collect(Arc::new(join), ctx.task_ctx()).await;
+        assert!(matches!(res, Err(DataFusionError::ResourcesExhausted(_))));
+    }
+}
@@ -0,0 +1,491 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Plan explaining its input as a json tree of operators, so the tools can
+//! visualize the plans without parsing the text output by `EXPLAIN`.
+
+use std::{any::Any, collections::BTreeMap, fmt, sync::Arc};
+
+use arrow::{
+    array::StringArray,
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+use common_types::schema::ArrowSchemaRef;
+use datafusion::{
+    common::Statistics,
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::TaskContext,
+    logical_expr::LogicalPlan,
+    physical_expr::PhysicalSortExpr,
+    physical_plan::{
+        analyze::AnalyzeExec, displayable, stream::RecordBatchStreamAdapter, DisplayAs,
+        DisplayFormatType, ExecutionPlan, Partitioning,
+        SendableRecordBatchStream as DfSendableRecordBatchStream,
+    },
+};
+use df_engine_extensions::dist_sql_query::physical_plan::partitioned_scan_metrics;
+use futures::{future, stream, StreamExt};
+use serde::Serialize;
+use table_engine::provider::ScanTable;
+use trace_metric::collector::FlattenCollectorVisitor;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PlanNode {
+    pub operator: String,
+    /// The `key=value` attributes of the operator.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+    /// The description of the operator which is not in `key=value` form.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// The statistics estimated by the planner.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub estimated: BTreeMap<String, String>,
+    /// The actual metrics collected by `EXPLAIN ANALYZE`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, String>,
+    /// The counters of the pruned row groups, ssts and so on.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pruning: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    /// Build the node from the one line description of the operator, such as
+    /// `FilterExec: a@0 > 1`, and its children are not included.
+    fn from_line(line: &str) -> Self {
+        let line = line.trim();
+        let (operator, rest) = match line.split_once(": ") {
+            Some((operator, rest)) if !operator.contains(' ') => (operator, rest),
+            _ => match line.split_once(':') {
+                Some((operator, "")) => (operator, ""),
+                _ => (line, ""),
+            },
+        };
+
+        let mut node = PlanNode {
+            operator: operator.to_string(),
+            ..Default::default()
+        };
+        let mut details = Vec::new();
+        for part in split_top_level(rest) {
+            match split_key_value(part) {
+                Some((key, value)) if key.starts_with("estimated_") => {
+                    node.estimated.insert(key.to_string(), value.to_string());
+                }
+                Some((key, value)) => {
+                    node.attributes.insert(key.to_string(), value.to_string());
+                }
+                None => details.push(part),
+            }
+        }
+        if !details.is_empty() {
+            node.details = Some(details.join(", "));
+        }
+
+        node
+    }
+
+    /// Split the pruning counters from the others, so they are easy to find.
+    fn split_pruning(&mut self) {
+        for map in [&mut self.attributes, &mut self.metrics] {
+            let keys = map
+                .keys()
+                .filter(|key| key.contains("prune"))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in keys {
+                let value = map.remove(&key).unwrap();
+                self.pruning.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Split the `a=1, b=[c, d]` into `a=1` and `b=[c, d]`, the commas inside the
+/// brackets are ignored.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (idx, c) in s.char_indices() {
+        match c {
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(s[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|part| !part.is_empty());
+
+    parts
+}
+
+/// Split the `key=value`, `None` is returned if the part isn't in this form.
+fn split_key_value(part: &str) -> Option<(&str, &str)> {
+    let (key, value) = part.split_once('=')?;
+    let is_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    is_key.then_some((key, value))
+}
+
+/// Build the tree of the operators of the logical plan.
+pub fn logical_plan_tree(plan: &LogicalPlan) -> PlanNode {
+    let mut node = PlanNode::from_line(&plan.display().to_string());
+    node.children = plan.inputs().into_iter().map(logical_plan_tree).collect();
+
+    node
+}
+
+/// Build the tree of the operators of the physical plan, with the statistics
+/// estimated by the operators and the metrics collected by their execution.
+pub fn plan_tree(plan: &dyn ExecutionPlan) -> PlanNode {
+    let mut node = PlanNode::from_line(&displayable(plan).one_line().to_string());
+    if let Ok(statistics) = plan.statistics() {
+        node.estimated.extend(estimated_statistics(&statistics));
+    }
+
+    // The metrics of the scans are traced by their collectors, which are
+    // formatted as one text metric by their `metrics()`.
+    if let Some(scan) = plan.as_any().downcast_ref::<ScanTable>() {
+        let request = scan.request();
+        node.attributes
+            .insert("predicate".to_string(), format!("{:?}", request.predicate));
+        let mut visitor = FlattenCollectorVisitor::default();
+        request.metrics_collector.visit(&mut visitor);
+        node.metrics.extend(visitor.into_map());
+    } else if let Some(metrics) = partitioned_scan_metrics(plan) {
+        node.metrics.extend(metrics);
+    } else if let Some(metrics) = plan.metrics() {
+        let metrics = metrics
+            .aggregate_by_name()
+            .sorted_for_display()
+            .timestamps_removed();
+        for metric in metrics.iter() {
+            let value = metric.value();
+            node.metrics
+                .insert(value.name().to_string(), value.to_string());
+        }
+    }
+    node.split_pruning();
+
+    node.children = plan
+        .children()
+        .iter()
+        .map(|child| plan_tree(child.as_ref()))
+        .collect();
+
+    node
+}
+
+fn estimated_statistics(statistics: &Statistics) -> impl Iterator<Item = (String, String)> + '_ {
+    [
+        ("num_rows", &statistics.num_rows),
+        ("total_byte_size", &statistics.total_byte_size),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some((key.to_string(), value.get_value()?.to_string())))
+}
+
+/// Format the trees as a json array.
+fn to_json(trees: &[PlanNode]) -> String {
+    // Serializing the plain structs never fails.
+    serde_json::to_string(trees).unwrap_or_default()
+}
+
+/// Explain the plan in json format, which is set by `EXPLAIN (FORMAT JSON)`.
+#[derive(Debug, Clone, Default)]
+pub struct ExplainJson {
+    /// The logical plan explained along with the physical plan, `None` for
+    /// `EXPLAIN ANALYZE`.
+    pub logical_plan: Option<PlanNode>,
+}
+
+/// ExplainJsonExec outputs the json trees of the operators of its input, in
+/// the same schema as the text plans output by `EXPLAIN`.
+///
+/// The input is executed and explained with the metrics for `EXPLAIN ANALYZE`.
+#[derive(Debug)]
+pub struct ExplainJsonExec {
+    input: Arc<dyn ExecutionPlan>,
+    analyze: bool,
+    logical_plan: Option<PlanNode>,
+}
+
+impl ExplainJsonExec {
+    /// Explain the `plan`, and the input of the [AnalyzeExec] is explained if
+    /// the `plan` is one.
+    pub fn new(plan: Arc<dyn ExecutionPlan>, explain: ExplainJson) -> Self {
+        match plan.children().pop() {
+            Some(input) if plan.as_any().is::<AnalyzeExec>() => {
+                Self::with_input(input, true, explain.logical_plan)
+            }
+            _ => Self::with_input(plan, false, explain.logical_plan),
+        }
+    }
+
+    fn with_input(
+        input: Arc<dyn ExecutionPlan>,
+        analyze: bool,
+        logical_plan: Option<PlanNode>,
+    ) -> Self {
+        Self {
+            input,
+            analyze,
+            logical_plan,
+        }
+    }
+
+    #[inline]
+    pub fn is_analyze(&self) -> bool {
+        self.analyze
+    }
+
+    /// Execute all the partitions of the input and discard their outputs.
+    async fn drain_input(
+        input: Arc<dyn ExecutionPlan>,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<()> {
+        let partitions = (0..input.output_partitioning().partition_count()).map(|partition| {
+            let stream = input.execute(partition, context.clone());
+            async move {
+                let mut stream = stream?;
+                while let Some(batch) = stream.next().await {
+                    batch?;
+                }
+                Ok::<_, DataFusionError>(())
+            }
+        });
+        future::try_join_all(partitions).await?;
+
+        Ok(())
+    }
+}
+
+impl ExecutionPlan for ExplainJsonExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("plan_type", DataType::Utf8, false),
+            Field::new("plan", DataType::Utf8, false),
+        ]))
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(Self::with_input(
+                children[0].clone(),
+                self.analyze,
+                self.logical_plan.clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "ExplainJsonExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "ExplainJsonExec invalid partition {partition}"
+            )));
+        }
+
+        let schema = self.schema();
+        let input = self.input.clone();
+        let analyze = self.analyze;
+        let logical_plan = self.logical_plan.clone();
+        let output_schema = schema.clone();
+        let output = async move {
+            if analyze {
+                Self::drain_input(input.clone(), context).await?;
+            }
+
+            let mut plan_types = Vec::with_capacity(2);
+            let mut plans = Vec::with_capacity(2);
+            if let Some(logical_plan) = logical_plan {
+                plan_types.push("logical_plan");
+                plans.push(to_json(&[logical_plan]));
+            }
+            plan_types.push(if analyze {
+                "Plan with Metrics"
+            } else {
+                "physical_plan"
+            });
+            plans.push(to_json(&[plan_tree(input.as_ref())]));
+
+            RecordBatch::try_new(
+                output_schema,
+                vec![
+                    Arc::new(StringArray::from(plan_types)),
+                    Arc::new(StringArray::from(plans)),
+                ],
+            )
+            .map_err(DataFusionError::from)
+        };
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            schema,
+            stream::once(output),
+        )))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+impl DisplayAs for ExplainJsonExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ExplainJsonExec: analyze={}", self.analyze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int32Array;
+    use datafusion::{
+        logical_expr::Operator,
+        physical_plan::{
+            collect,
+            expressions::{binary, col, lit},
+            filter::FilterExec,
+            memory::MemoryExec,
+        },
+    };
+    use serde_json::Value;
+
+    use super::*;
+
+    fn build_filter() -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let input =
+            MemoryExec::try_new(&[vec![batch.clone()], vec![batch]], schema.clone(), None).unwrap();
+        let predicate =
+            binary(col("a", &schema).unwrap(), Operator::Gt, lit(1i32), &schema).unwrap();
+
+        Arc::new(FilterExec::try_new(predicate, Arc::new(input)).unwrap())
+    }
+
+    async fn explain(plan: ExplainJsonExec) -> Vec<(String, Value)> {
+        let batches = collect(Arc::new(plan), Arc::new(TaskContext::default()))
+            .await
+            .unwrap();
+        assert_eq!(1, batches.len());
+
+        let column = |idx: usize| {
+            batches[0]
+                .column(idx)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .iter()
+                .map(|v| v.unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        column(0)
+            .into_iter()
+            .zip(column(1))
+            .map(|(plan_type, plan)| (plan_type, serde_json::from_str(&plan).unwrap()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_explain_analyze() {
+        let plan = ExplainJsonExec::with_input(build_filter(), true, None);
+        let plans = explain(plan).await;
+        assert_eq!(1, plans.len());
+        assert_eq!("Plan with Metrics", plans[0].0);
+
+        let filter = &plans[0].1[0];
+        assert_eq!("FilterExec", filter["operator"]);
+        assert_eq!("a@0 > 1", filter["details"]);
+        assert_eq!("4", filter["metrics"]["output_rows"]);
+
+        let children = filter["children"].as_array().unwrap();
+        assert_eq!(1, children.len());
+        assert_eq!("MemoryExec", children[0]["operator"]);
+        assert_eq!("6", children[0]["estimated"]["num_rows"]);
+    }
+
+    #[tokio::test]
+    async fn test_explain() {
+        let logical_plan = PlanNode {
+            operator: "Filter".to_string(),
+            details: Some("t.a > Int32(1)".to_string()),
+            ..Default::default()
+        };
+        let plan = ExplainJsonExec::with_input(build_filter(), false, Some(logical_plan));
+        let plans = explain(plan).await;
+        assert_eq!(2, plans.len());
+
+        assert_eq!("logical_plan", plans[0].0);
+        assert_eq!("Filter", plans[0].1[0]["operator"]);
+
+        // The plan isn't executed, so no metrics are collected.
+        assert_eq!("physical_plan", plans[1].0);
+        assert_eq!("FilterExec", plans[1].1[0]["operator"]);
+        assert!(plans[1].1[0].get("metrics").is_none());
+    }
+
+    #[test]
+    fn test_plan_node_from_line() {
+        let node = PlanNode::from_line("ScanTable: table=t, parallelism=8, estimated_rows=10\n");
+        assert_eq!("ScanTable", node.operator);
+        assert_eq!("t", node.attributes["table"]);
+        assert_eq!("10", node.estimated["estimated_rows"]);
+
+        let node = PlanNode::from_line("TableScan: t projection=[a]");
+        assert_eq!("TableScan", node.operator);
+        assert_eq!(Some("t projection=[a]"), node.details.as_deref());
+    }
+}
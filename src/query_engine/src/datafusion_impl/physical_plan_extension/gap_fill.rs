@@ -0,0 +1,469 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Operator filling the missing time buckets of the time bucketed aggregation,
+//! e.g. the `SELECT ... GROUP BY t, host FILL(linear)` query.
+
+use std::{any::Any, fmt, sync::Arc};
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, BooleanArray, Float64Array, Int64Array, UInt32Array},
+    compute::{
+        cast, concat_batches,
+        kernels::{nullif::nullif, zip::zip},
+        lexsort_to_indices, take, SortColumn,
+    },
+    datatypes::{DataType, Float64Type, Int64Type},
+    record_batch::RecordBatch,
+    row::{RowConverter, SortField},
+};
+use common_types::schema::ArrowSchemaRef;
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::TaskContext,
+    physical_expr::PhysicalSortExpr,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionPlan,
+        Partitioning, SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{stream, StreamExt};
+use logger::debug;
+use query_frontend::gap_fill::FillPolicy;
+
+/// Max number of the rows filled by one query, to protect the server from the
+/// huge gaps between the buckets.
+const MAX_FILLED_ROWS: usize = 1_000_000;
+
+/// GapFillExec fills the missing time buckets of every series between its
+/// first and last buckets, and the values of the filled rows are decided by the
+/// [FillPolicy].
+///
+/// All the input partitions are merged into one partition, and the output is
+/// ordered by the series and the time buckets.
+#[derive(Debug)]
+pub struct GapFillExec {
+    input: Arc<dyn ExecutionPlan>,
+    series_columns: Vec<String>,
+    time_column: String,
+    interval: i64,
+    policy: FillPolicy,
+    series_indices: Vec<usize>,
+    time_index: usize,
+}
+
+impl GapFillExec {
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        series_columns: Vec<String>,
+        time_column: String,
+        interval: i64,
+        policy: FillPolicy,
+    ) -> DataFusionResult<Self> {
+        if interval <= 0 {
+            return Err(DataFusionError::Plan(format!(
+                "Invalid interval of gap fill, interval:{interval}"
+            )));
+        }
+
+        let schema = input.schema();
+        let series_indices = series_columns
+            .iter()
+            .map(|column| schema.index_of(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let time_index = schema.index_of(&time_column)?;
+
+        Ok(Self {
+            input,
+            series_columns,
+            time_column,
+            interval,
+            policy,
+            series_indices,
+            time_index,
+        })
+    }
+}
+
+impl ExecutionPlan for GapFillExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(GapFillExec::try_new(
+                children[0].clone(),
+                self.series_columns.clone(),
+                self.time_column.clone(),
+                self.interval,
+                self.policy,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "GapFillExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        debug!(
+            "GapFillExec: partition:{}, series_columns:{:?}, time_column:{}",
+            partition, self.series_columns, self.time_column
+        );
+
+        let schema = self.schema();
+        let inputs = (0..self.input.output_partitioning().partition_count())
+            .map(|partition| self.input.execute(partition, context.clone()))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        let gap_filler = GapFiller {
+            series_indices: self.series_indices.clone(),
+            time_index: self.time_index,
+            interval: self.interval,
+            policy: self.policy,
+        };
+        let output_schema = schema.clone();
+        let output = stream::once(async move {
+            let mut input = stream::select_all(inputs);
+            let mut batches = Vec::new();
+            while let Some(batch) = input.next().await {
+                batches.push(batch?);
+            }
+            let batch = concat_batches(&output_schema, &batches)?;
+            gap_filler.fill(batch)
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, output)))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+impl DisplayAs for GapFillExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GapFillExec: series_columns={:?}, time_column={}, interval={}, policy={:?}",
+            self.series_columns, self.time_column, self.interval, self.policy,
+        )
+    }
+}
+
+/// An output row, which is either an input row or a filled row between two
+/// input rows of the same series.
+#[derive(Debug, PartialEq)]
+enum OutputRow {
+    Input(usize),
+    Filled {
+        prev: usize,
+        next: usize,
+        timestamp: i64,
+    },
+}
+
+struct GapFiller {
+    series_indices: Vec<usize>,
+    time_index: usize,
+    interval: i64,
+    policy: FillPolicy,
+}
+
+impl GapFiller {
+    fn fill(&self, batch: RecordBatch) -> DataFusionResult<RecordBatch> {
+        if batch.num_rows() == 0 {
+            return Ok(batch);
+        }
+
+        let batch = self.sort(batch)?;
+        let timestamps = cast(batch.column(self.time_index), &DataType::Int64)?;
+        let timestamps = timestamps.as_primitive::<Int64Type>();
+        let output_rows = self.output_rows(&batch, timestamps)?;
+        if output_rows.len() == batch.num_rows() {
+            return Ok(batch);
+        }
+
+        let prev_indices: UInt32Array = output_rows
+            .iter()
+            .map(|row| match row {
+                OutputRow::Input(idx) | OutputRow::Filled { prev: idx, .. } => *idx as u32,
+            })
+            .collect();
+        let filled: BooleanArray = output_rows
+            .iter()
+            .map(|row| Some(matches!(row, OutputRow::Filled { .. })))
+            .collect();
+
+        let columns = (0..batch.num_columns())
+            .map(|col_idx| {
+                let column = batch.column(col_idx);
+                if col_idx == self.time_index {
+                    let output_timestamps: ArrayRef = Arc::new(
+                        output_rows
+                            .iter()
+                            .map(|row| match row {
+                                OutputRow::Input(idx) => timestamps.value(*idx),
+                                OutputRow::Filled { timestamp, .. } => *timestamp,
+                            })
+                            .map(Some)
+                            .collect::<Int64Array>(),
+                    );
+                    return cast(&output_timestamps, column.data_type());
+                }
+
+                // The series columns of the filled rows are the same as the previous
+                // rows, and so do the values if the policy is `previous`.
+                let prev_values = take(column.as_ref(), &prev_indices, None)?;
+                if self.series_indices.contains(&col_idx) {
+                    return Ok(prev_values);
+                }
+                match self.policy {
+                    FillPolicy::Previous => Ok(prev_values),
+                    FillPolicy::Null => nullif(&prev_values, &filled),
+                    FillPolicy::Value(value) => {
+                        let values = Float64Array::from(vec![value; output_rows.len()]);
+                        match cast(&values, column.data_type()) {
+                            Ok(values) => zip(&filled, &values, &prev_values),
+                            Err(_) => nullif(&prev_values, &filled),
+                        }
+                    }
+                    FillPolicy::Linear => {
+                        if !column.data_type().is_numeric() {
+                            return nullif(&prev_values, &filled);
+                        }
+                        let values = Self::interpolate(column, timestamps, &output_rows)?;
+                        let values = cast(&values, column.data_type())?;
+                        zip(&filled, &values, &prev_values)
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RecordBatch::try_new(batch.schema(), columns)?)
+    }
+
+    /// Sort the rows by the series and the timestamp.
+    fn sort(&self, batch: RecordBatch) -> DataFusionResult<RecordBatch> {
+        let sort_columns: Vec<_> = self
+            .series_indices
+            .iter()
+            .chain(std::iter::once(&self.time_index))
+            .map(|idx| SortColumn {
+                values: batch.column(*idx).clone(),
+                options: None,
+            })
+            .collect();
+        let indices = lexsort_to_indices(&sort_columns, None)?;
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| take(column.as_ref(), &indices, None))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RecordBatch::try_new(batch.schema(), columns)?)
+    }
+
+    fn output_rows(
+        &self,
+        batch: &RecordBatch,
+        timestamps: &Int64Array,
+    ) -> DataFusionResult<Vec<OutputRow>> {
+        let sort_fields = self
+            .series_indices
+            .iter()
+            .map(|idx| SortField::new(batch.schema().field(*idx).data_type().clone()))
+            .collect();
+        let row_converter = RowConverter::new(sort_fields)?;
+        let series_columns: Vec<_> = self
+            .series_indices
+            .iter()
+            .map(|idx| batch.column(*idx).clone())
+            .collect();
+        let series = row_converter.convert_columns(&series_columns)?;
+
+        let mut output_rows = Vec::with_capacity(batch.num_rows());
+        let mut filled_rows = 0;
+        for row_idx in 0..batch.num_rows() {
+            if row_idx > 0 && series.row(row_idx - 1) == series.row(row_idx) {
+                let prev_timestamp = timestamps.value(row_idx - 1);
+                let timestamp = timestamps.value(row_idx);
+                let mut filled_timestamp = prev_timestamp.saturating_add(self.interval);
+                while filled_timestamp < timestamp {
+                    // Check the limit before pushing, a single huge gap can't be
+                    // allowed to allocate without bound.
+                    if filled_rows >= MAX_FILLED_ROWS {
+                        return Err(DataFusionError::ResourcesExhausted(format!(
+                            "Too many rows to fill, max_filled_rows:{MAX_FILLED_ROWS}"
+                        )));
+                    }
+                    output_rows.push(OutputRow::Filled {
+                        prev: row_idx - 1,
+                        next: row_idx,
+                        timestamp: filled_timestamp,
+                    });
+                    filled_rows += 1;
+                    filled_timestamp = filled_timestamp.saturating_add(self.interval);
+                }
+            }
+            output_rows.push(OutputRow::Input(row_idx));
+        }
+
+        Ok(output_rows)
+    }
+
+    /// Interpolate the values of the filled rows linearly between their
+    /// previous and next rows, and the values of the other rows are null.
+    fn interpolate(
+        column: &ArrayRef,
+        timestamps: &Int64Array,
+        output_rows: &[OutputRow],
+    ) -> DataFusionResult<ArrayRef> {
+        let values = cast(column, &DataType::Float64)?;
+        let values = values.as_primitive::<Float64Type>();
+        let interpolated: Float64Array = output_rows
+            .iter()
+            .map(|row| match row {
+                OutputRow::Input(_) => None,
+                OutputRow::Filled {
+                    prev,
+                    next,
+                    timestamp,
+                } => {
+                    if values.is_null(*prev) || values.is_null(*next) {
+                        return None;
+                    }
+                    let (t0, t1) = (timestamps.value(*prev), timestamps.value(*next));
+                    let (v0, v1) = (values.value(*prev), values.value(*next));
+                    Some(v0 + (v1 - v0) * (timestamp - t0) as f64 / (t1 - t0) as f64)
+                }
+            })
+            .collect();
+
+        Ok(Arc::new(interpolated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Int64Array, StringArray, TimestampMillisecondArray},
+        datatypes::{Field, Schema, TimeUnit},
+    };
+
+    use super::*;
+
+    fn build_schema() -> ArrowSchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, false),
+            Field::new("t", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+            Field::new("value", DataType::Int64, true),
+        ]))
+    }
+
+    fn build_batch(
+        schema: &ArrowSchemaRef,
+        hosts: &[&str],
+        timestamps: &[i64],
+        values: &[Option<i64>],
+    ) -> RecordBatch {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(hosts.to_vec())),
+                Arc::new(TimestampMillisecondArray::from(timestamps.to_vec())),
+                Arc::new(Int64Array::from(values.to_vec())),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_gap_fill() {
+        let schema = build_schema();
+        let input = build_batch(
+            &schema,
+            &["b", "a", "a", "b"],
+            &[10, 40, 10, 20],
+            &[Some(5), Some(40), Some(10), Some(6)],
+        );
+        let hosts = ["a", "a", "a", "a", "b", "b"];
+        let timestamps = [10, 20, 30, 40, 10, 20];
+
+        let cases = [
+            (FillPolicy::Null, [Some(10), None, None, Some(40)]),
+            (
+                FillPolicy::Value(-1.0),
+                [Some(10), Some(-1), Some(-1), Some(40)],
+            ),
+            (
+                FillPolicy::Previous,
+                [Some(10), Some(10), Some(10), Some(40)],
+            ),
+            (FillPolicy::Linear, [Some(10), Some(20), Some(30), Some(40)]),
+        ];
+        for (policy, values) in cases {
+            let gap_filler = GapFiller {
+                series_indices: vec![0],
+                time_index: 1,
+                interval: 10,
+                policy,
+            };
+            let batch = gap_filler.fill(input.clone()).unwrap();
+            let mut expect_values = values.to_vec();
+            expect_values.extend([Some(5), Some(6)]);
+            let expect = build_batch(&schema, &hosts, &timestamps, &expect_values);
+            assert_eq!(batch, expect, "policy:{policy:?}");
+        }
+    }
+
+    #[test]
+    fn test_gap_fill_too_many_rows() {
+        let schema = build_schema();
+        let input = build_batch(&schema, &["a", "a"], &[0, i64::MAX], &[Some(1), Some(2)]);
+        let gap_filler = GapFiller {
+            series_indices: vec![0],
+            time_index: 1,
+            interval: 1,
+            policy: FillPolicy::Null,
+        };
+        let err = gap_filler.fill(input).unwrap_err();
+        assert!(matches!(err, DataFusionError::ResourcesExhausted(_)));
+    }
+}
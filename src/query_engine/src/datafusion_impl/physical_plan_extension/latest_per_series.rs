@@ -0,0 +1,371 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Operator keeping the latest row of every series, e.g. the
+//! `SELECT LAST_ROW(*) FROM t GROUP BY host` query.
+
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray},
+    compute::{cast, interleave},
+    datatypes::{DataType, Int64Type},
+    record_batch::RecordBatch,
+    row::{RowConverter, SortField},
+};
+use common_types::schema::ArrowSchemaRef;
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::TaskContext,
+    physical_expr::PhysicalSortExpr,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionPlan,
+        Partitioning, SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{stream, StreamExt};
+use logger::debug;
+
+/// The retained batches will be compacted when its number exceeds this
+/// threshold, so the memory usage is bounded by the number of the series
+/// rather than the input.
+const COMPACT_BATCHES_THRESHOLD: usize = 32;
+
+/// LatestPerSeriesExec keeps only the latest row of every series, instead of
+/// aggregating every column of the rows of the series.
+///
+/// The rows not newer than the latest one of their series are skipped without
+/// being copied, and the batches are released once none of their rows is the
+/// latest one. All the input partitions are merged into one partition.
+#[derive(Debug)]
+pub struct LatestPerSeriesExec {
+    input: Arc<dyn ExecutionPlan>,
+    series_columns: Vec<String>,
+    timestamp_column: String,
+    series_indices: Vec<usize>,
+    timestamp_index: usize,
+}
+
+impl LatestPerSeriesExec {
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        series_columns: Vec<String>,
+        timestamp_column: String,
+    ) -> DataFusionResult<Self> {
+        let schema = input.schema();
+        let series_indices = series_columns
+            .iter()
+            .map(|column| schema.index_of(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let timestamp_index = schema.index_of(&timestamp_column)?;
+
+        Ok(Self {
+            input,
+            series_columns,
+            timestamp_column,
+            series_indices,
+            timestamp_index,
+        })
+    }
+}
+
+impl ExecutionPlan for LatestPerSeriesExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(LatestPerSeriesExec::try_new(
+                children[0].clone(),
+                self.series_columns.clone(),
+                self.timestamp_column.clone(),
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "LatestPerSeriesExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        debug!(
+            "LatestPerSeriesExec: partition:{}, series_columns:{:?}",
+            partition, self.series_columns
+        );
+
+        let schema = self.schema();
+        let inputs = (0..self.input.output_partitioning().partition_count())
+            .map(|partition| self.input.execute(partition, context.clone()))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        let mut latest_rows = LatestRows::try_new(
+            schema.clone(),
+            self.series_indices.clone(),
+            self.timestamp_index,
+        )?;
+        let output = stream::once(async move {
+            let mut input = stream::select_all(inputs);
+            while let Some(batch) = input.next().await {
+                latest_rows.insert_batch(batch?)?;
+            }
+            latest_rows.emit()
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, output)))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+impl DisplayAs for LatestPerSeriesExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "LatestPerSeriesExec: series_columns={:?}, timestamp_column={}",
+            self.series_columns, self.timestamp_column,
+        )
+    }
+}
+
+/// The latest row of a series.
+#[derive(Debug, Clone, Copy)]
+struct LatestRow {
+    timestamp: i64,
+    batch_idx: usize,
+    row_idx: usize,
+}
+
+struct LatestRows {
+    schema: ArrowSchemaRef,
+    series_indices: Vec<usize>,
+    timestamp_index: usize,
+    row_converter: RowConverter,
+    /// The latest rows keyed by the encoded series columns.
+    latest: HashMap<Vec<u8>, LatestRow>,
+    /// The batches referenced by the latest rows.
+    batches: Vec<RecordBatch>,
+}
+
+impl LatestRows {
+    fn try_new(
+        schema: ArrowSchemaRef,
+        series_indices: Vec<usize>,
+        timestamp_index: usize,
+    ) -> DataFusionResult<Self> {
+        let sort_fields = series_indices
+            .iter()
+            .map(|idx| SortField::new(schema.field(*idx).data_type().clone()))
+            .collect();
+        let row_converter = RowConverter::new(sort_fields)?;
+
+        Ok(Self {
+            schema,
+            series_indices,
+            timestamp_index,
+            row_converter,
+            latest: HashMap::new(),
+            batches: Vec::new(),
+        })
+    }
+
+    fn insert_batch(&mut self, batch: RecordBatch) -> DataFusionResult<()> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+
+        let series_columns: Vec<_> = self
+            .series_indices
+            .iter()
+            .map(|idx| batch.column(*idx).clone())
+            .collect();
+        let rows = self.row_converter.convert_columns(&series_columns)?;
+        let timestamps = cast(batch.column(self.timestamp_index), &DataType::Int64)?;
+        let timestamps = timestamps.as_primitive::<Int64Type>();
+
+        let batch_idx = self.batches.len();
+        let mut retained = false;
+        for (row_idx, row) in rows.iter().enumerate() {
+            let timestamp = timestamps.value(row_idx);
+            let latest_row = LatestRow {
+                timestamp,
+                batch_idx,
+                row_idx,
+            };
+            match self.latest.get_mut(row.as_ref()) {
+                Some(latest) if latest.timestamp >= timestamp => continue,
+                Some(latest) => *latest = latest_row,
+                None => {
+                    self.latest.insert(row.as_ref().to_vec(), latest_row);
+                }
+            }
+            retained = true;
+        }
+
+        if retained {
+            self.batches.push(batch);
+        }
+        if self.batches.len() > COMPACT_BATCHES_THRESHOLD {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    /// Take the latest rows ordered by the series.
+    fn take_latest_rows(&mut self) -> DataFusionResult<(Vec<(Vec<u8>, i64)>, RecordBatch)> {
+        let mut latest: Vec<_> = std::mem::take(&mut self.latest).into_iter().collect();
+        latest.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let indices: Vec<_> = latest
+            .iter()
+            .map(|(_, row)| (row.batch_idx, row.row_idx))
+            .collect();
+        let batches = std::mem::take(&mut self.batches);
+
+        let columns = (0..self.schema.fields().len())
+            .map(|col_idx| {
+                let arrays: Vec<&dyn Array> = batches
+                    .iter()
+                    .map(|batch| batch.column(col_idx).as_ref())
+                    .collect();
+                interleave(&arrays, &indices)
+            })
+            .collect::<Result<Vec<ArrayRef>, _>>()?;
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+        let keys = latest
+            .into_iter()
+            .map(|(key, row)| (key, row.timestamp))
+            .collect();
+
+        Ok((keys, batch))
+    }
+
+    /// Compact all the latest rows into one batch to release the memory of the
+    /// rest rows.
+    fn compact(&mut self) -> DataFusionResult<()> {
+        let (keys, batch) = self.take_latest_rows()?;
+        self.latest = keys
+            .into_iter()
+            .enumerate()
+            .map(|(row_idx, (key, timestamp))| {
+                let row = LatestRow {
+                    timestamp,
+                    batch_idx: 0,
+                    row_idx,
+                };
+                (key, row)
+            })
+            .collect();
+        self.batches.push(batch);
+
+        Ok(())
+    }
+
+    fn emit(mut self) -> DataFusionResult<RecordBatch> {
+        if self.latest.is_empty() {
+            return Ok(RecordBatch::new_empty(self.schema.clone()));
+        }
+
+        let (_, batch) = self.take_latest_rows()?;
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Int64Array, StringArray, TimestampMillisecondArray},
+        datatypes::{Field, Schema, TimeUnit},
+    };
+
+    use super::*;
+
+    fn build_batch(schema: &ArrowSchemaRef, hosts: &[&str], timestamps: &[i64]) -> RecordBatch {
+        let values: Vec<_> = timestamps.iter().map(|ts| ts * 10).collect();
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(hosts.to_vec())),
+                Arc::new(TimestampMillisecondArray::from(timestamps.to_vec())),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_latest_rows() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, false),
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("value", DataType::Int64, false),
+        ]));
+
+        let mut latest_rows = LatestRows::try_new(schema.clone(), vec![0], 1).unwrap();
+        latest_rows
+            .insert_batch(build_batch(&schema, &["b", "a", "b"], &[3, 1, 2]))
+            .unwrap();
+        // Rows older than the latest ones are skipped.
+        latest_rows
+            .insert_batch(build_batch(&schema, &["b", "a"], &[1, 0]))
+            .unwrap();
+        assert_eq!(latest_rows.batches.len(), 1);
+        for i in 0..COMPACT_BATCHES_THRESHOLD + 1 {
+            latest_rows
+                .insert_batch(build_batch(&schema, &["c"], &[i as i64]))
+                .unwrap();
+        }
+        latest_rows
+            .insert_batch(build_batch(&schema, &["a"], &[5]))
+            .unwrap();
+
+        let batch = latest_rows.emit().unwrap();
+        let expect = build_batch(
+            &schema,
+            &["a", "b", "c"],
+            &[5, 3, COMPACT_BATCHES_THRESHOLD as i64],
+        );
+        assert_eq!(batch, expect);
+    }
+}
@@ -15,5 +15,11 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod adaptive_join;
+pub mod explain_json;
+pub mod gap_fill;
+pub mod latest_per_series;
 pub mod prom_align;
+pub mod remote_udf;
+pub mod stage;
 pub use prom_align::PromAlignExec;
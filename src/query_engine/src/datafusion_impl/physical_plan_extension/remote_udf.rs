@@ -0,0 +1,303 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Plan evaluating the projection whose expressions call the remote udfs.
+
+use std::{any::Any, fmt, sync::Arc};
+
+use arrow::{
+    array::ArrayRef,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use common_types::schema::ArrowSchemaRef;
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::TaskContext,
+    physical_expr::{PhysicalExpr, PhysicalSortExpr, ScalarFunctionExpr},
+    physical_plan::{
+        coalesce_batches::CoalesceBatchesExec, projection::ProjectionExec,
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionPlan,
+        Partitioning, SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
+    },
+};
+use futures::StreamExt;
+
+use crate::remote_udf::{self, RemoteUdf, RemoteUdfs};
+
+/// Expression of the projection.
+#[derive(Debug)]
+enum ProjectedExpr {
+    Local(Arc<dyn PhysicalExpr>),
+    /// Call of the remote udf, whose arguments are evaluated locally.
+    Remote {
+        udf: Arc<RemoteUdf>,
+        args: Vec<Arc<dyn PhysicalExpr>>,
+    },
+}
+
+/// RemoteUdfExec replaces the [ProjectionExec] calling the remote udfs at the
+/// top level of its expressions, and the output of every input batch is
+/// evaluated after the responses of the remote udfs are received.
+///
+/// The output properties are the same as the replaced projection.
+#[derive(Debug)]
+pub struct RemoteUdfExec {
+    projection: Arc<ProjectionExec>,
+    exprs: Arc<Vec<ProjectedExpr>>,
+}
+
+impl RemoteUdfExec {
+    /// Returns `None` if no remote udf is called by the projection.
+    ///
+    /// The input of the projection is coalesced into the batches of the min
+    /// batch size of the called remote udfs to reduce the requests.
+    pub fn try_new(
+        projection: &ProjectionExec,
+        udfs: &RemoteUdfs,
+    ) -> DataFusionResult<Option<Self>> {
+        let exprs: Vec<_> = projection
+            .expr()
+            .iter()
+            .map(|(expr, _)| {
+                let udf = expr
+                    .as_any()
+                    .downcast_ref::<ScalarFunctionExpr>()
+                    .and_then(|func| Some((udfs.get(func.name())?, func)));
+                match udf {
+                    Some((udf, func)) => ProjectedExpr::Remote {
+                        udf: udf.clone(),
+                        args: func.args().to_vec(),
+                    },
+                    None => ProjectedExpr::Local(expr.clone()),
+                }
+            })
+            .collect();
+        let batch_size = exprs
+            .iter()
+            .filter_map(|expr| match expr {
+                ProjectedExpr::Remote { udf, .. } => Some(udf.batch_size()),
+                ProjectedExpr::Local(_) => None,
+            })
+            .min();
+        let Some(batch_size) = batch_size else {
+            return Ok(None);
+        };
+
+        let input = Arc::new(CoalesceBatchesExec::new(
+            projection.input().clone(),
+            batch_size,
+        ));
+        let projection = ProjectionExec::try_new(projection.expr().to_vec(), input)?;
+
+        Ok(Some(Self {
+            projection: Arc::new(projection),
+            exprs: Arc::new(exprs),
+        }))
+    }
+}
+
+impl ExecutionPlan for RemoteUdfExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        self.projection.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.projection.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projection.output_ordering()
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        self.projection.maintains_input_order()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.projection.input().clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => {
+                let projection =
+                    ProjectionExec::try_new(self.projection.expr().to_vec(), children[0].clone())?;
+                Ok(Arc::new(Self {
+                    projection: Arc::new(projection),
+                    exprs: self.exprs.clone(),
+                }))
+            }
+            _ => Err(DataFusionError::Internal(
+                "RemoteUdfExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        let schema = self.schema();
+        let exprs = self.exprs.clone();
+        let input = self.projection.input().execute(partition, context)?;
+        let output_schema = schema.clone();
+        let output = input.then(move |batch| {
+            let schema = output_schema.clone();
+            let exprs = exprs.clone();
+            async move { evaluate(schema, &exprs, batch?).await }
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, output)))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        self.projection.statistics()
+    }
+}
+
+impl DisplayAs for RemoteUdfExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        let remote_udfs: Vec<_> = self
+            .exprs
+            .iter()
+            .filter_map(|expr| match expr {
+                ProjectedExpr::Remote { udf, .. } => Some(udf.name()),
+                ProjectedExpr::Local(_) => None,
+            })
+            .collect();
+        write!(
+            f,
+            "RemoteUdfExec: remote_udfs={remote_udfs:?}, expr={:?}",
+            self.projection.expr()
+        )
+    }
+}
+
+async fn evaluate(
+    schema: ArrowSchemaRef,
+    exprs: &[ProjectedExpr],
+    batch: RecordBatch,
+) -> DataFusionResult<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let mut columns = Vec::with_capacity(exprs.len());
+    for expr in exprs {
+        let column = match expr {
+            ProjectedExpr::Local(expr) => expr.evaluate(&batch)?.into_array(num_rows)?,
+            ProjectedExpr::Remote { udf, args } => {
+                let args = evaluate_args(args, &batch)?;
+                udf.call(&args)
+                    .await
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?
+            }
+        };
+        columns.push(column);
+    }
+
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    Ok(RecordBatch::try_new_with_options(
+        schema, columns, &options,
+    )?)
+}
+
+/// Evaluate the arguments into a batch whose columns are the arguments in
+/// order.
+fn evaluate_args(
+    args: &[Arc<dyn PhysicalExpr>],
+    batch: &RecordBatch,
+) -> DataFusionResult<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let arrays = args
+        .iter()
+        .map(|arg| arg.evaluate(batch)?.into_array(num_rows))
+        .collect::<DataFusionResult<Vec<ArrayRef>>>()?;
+
+    remote_udf::args_batch(arrays, num_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+    };
+    use datafusion::{
+        common::DFSchema,
+        physical_expr::{create_physical_expr, execution_props::ExecutionProps},
+        physical_plan::{collect, memory::MemoryExec},
+        prelude::{col, Expr},
+    };
+
+    use super::*;
+    use crate::remote_udf::RemoteUdfConfig;
+
+    #[tokio::test]
+    async fn test_remote_udf_exec() {
+        let config = RemoteUdfConfig {
+            name: "score".to_string(),
+            // Nothing listens on the port so the call fails.
+            endpoint: "http://127.0.0.1:1".to_string(),
+            ..Default::default()
+        };
+        let udfs = RemoteUdfs::try_new(&[config.clone()]).unwrap();
+        let stub = remote_udf::new_stub(udfs.get(&config.name).unwrap().clone());
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None).unwrap());
+
+        let df_schema = DFSchema::try_from(schema.as_ref().clone()).unwrap();
+        let props = ExecutionProps::new();
+        let exprs: Vec<Expr> = vec![col("a"), stub.to_datafusion_udf().call(vec![col("a")])];
+        let exprs = exprs
+            .iter()
+            .map(|expr| {
+                let physical_expr =
+                    create_physical_expr(expr, &df_schema, &schema, &props).unwrap();
+                (physical_expr, expr.display_name().unwrap())
+            })
+            .collect();
+        let projection = ProjectionExec::try_new(exprs, input).unwrap();
+
+        let local_only = ProjectionExec::try_new(
+            vec![projection.expr()[0].clone()],
+            projection.input().clone(),
+        )
+        .unwrap();
+        assert!(RemoteUdfExec::try_new(&local_only, &udfs)
+            .unwrap()
+            .is_none());
+
+        let exec = RemoteUdfExec::try_new(&projection, &udfs).unwrap().unwrap();
+        assert_eq!(projection.schema(), exec.schema());
+        assert!(exec.children()[0].as_any().is::<CoalesceBatchesExec>());
+
+        let res = collect(Arc::new(exec), Arc::new(TaskContext::default())).await;
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,263 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Plan recording the scan and merge stages of a query.
+
+use std::{
+    any::Any,
+    fmt,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use arrow::record_batch::RecordBatch;
+use common_types::schema::ArrowSchemaRef;
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::TaskContext,
+    physical_expr::PhysicalSortExpr,
+    physical_plan::{
+        DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+        SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{Stream, StreamExt};
+use table_engine::scan_quota::ScanUsageRef;
+
+use crate::stage::{QueryStagesRef, STAGE_MERGE, STAGE_SCAN};
+
+/// Pass-through plan counting the rows and bytes output by its input, the scan
+/// and merge stages are recorded after all the partitions of the input are
+/// finished.
+#[derive(Debug)]
+pub struct StageExec {
+    input: Arc<dyn ExecutionPlan>,
+    recorder: Arc<StageRecorder>,
+}
+
+impl StageExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        stages: QueryStagesRef,
+        scan_usage: Option<ScanUsageRef>,
+    ) -> Self {
+        let recorder = StageRecorder {
+            stages,
+            scan_usage,
+            start: Instant::now(),
+            remaining_partitions: AtomicUsize::new(input.output_partitioning().partition_count()),
+            rows: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        };
+
+        Self {
+            input,
+            recorder: Arc::new(recorder),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StageRecorder {
+    stages: QueryStagesRef,
+    scan_usage: Option<ScanUsageRef>,
+    start: Instant,
+    remaining_partitions: AtomicUsize,
+    rows: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl StageRecorder {
+    fn on_batch(&self, batch: &RecordBatch) {
+        self.rows
+            .fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+        self.bytes
+            .fetch_add(batch.get_array_memory_size() as u64, Ordering::Relaxed);
+    }
+
+    fn on_partition_finished(&self) {
+        if self.remaining_partitions.fetch_sub(1, Ordering::Relaxed) != 1 {
+            return;
+        }
+
+        if let Some(scan_usage) = &self.scan_usage {
+            self.stages.record(
+                STAGE_SCAN,
+                scan_usage.elapsed(),
+                scan_usage.rows(),
+                scan_usage.bytes(),
+            );
+        }
+        self.stages.record(
+            STAGE_MERGE,
+            self.start.elapsed(),
+            self.rows.load(Ordering::Relaxed),
+            self.bytes.load(Ordering::Relaxed),
+        );
+    }
+}
+
+impl ExecutionPlan for StageExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> ArrowSchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(StageExec::new(
+                children[0].clone(),
+                self.recorder.stages.clone(),
+                self.recorder.scan_usage.clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "StageExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<DfSendableRecordBatchStream> {
+        let input = self.input.execute(partition, context)?;
+
+        Ok(Box::pin(StageStream {
+            input,
+            recorder: self.recorder.clone(),
+            finished: false,
+        }))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        self.input.statistics()
+    }
+}
+
+impl DisplayAs for StageExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "StageExec: partition_count={}",
+            self.output_partitioning().partition_count()
+        )
+    }
+}
+
+struct StageStream {
+    input: DfSendableRecordBatchStream,
+    recorder: Arc<StageRecorder>,
+    finished: bool,
+}
+
+impl Stream for StageStream {
+    type Item = DataFusionResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        let polled = self.input.poll_next_unpin(cx);
+        match &polled {
+            Poll::Ready(Some(Ok(batch))) => self.recorder.on_batch(batch),
+            Poll::Ready(None) => {
+                self.finished = true;
+                self.recorder.on_partition_finished();
+            }
+            _ => (),
+        }
+
+        polled
+    }
+}
+
+impl RecordBatchStream for StageStream {
+    fn schema(&self) -> ArrowSchemaRef {
+        self.input.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::Int32Array,
+        datatypes::{DataType, Field, Schema},
+    };
+    use datafusion::physical_plan::{collect, memory::MemoryExec};
+
+    use super::*;
+    use crate::stage::QueryStages;
+
+    #[tokio::test]
+    async fn test_stage_exec() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let bytes = batch.get_array_memory_size() as u64;
+        let input = MemoryExec::try_new(&[vec![batch.clone()], vec![batch]], schema, None).unwrap();
+
+        let stages = Arc::new(QueryStages::default());
+        let plan = Arc::new(StageExec::new(
+            Arc::new(input),
+            stages.clone(),
+            Some(Default::default()),
+        ));
+        let batches = collect(plan, Arc::new(TaskContext::default()))
+            .await
+            .unwrap();
+        assert_eq!(2, batches.len());
+
+        let stages = stages.stages();
+        assert_eq!(2, stages.len());
+        assert_eq!(STAGE_SCAN, stages[0].stage);
+        assert_eq!(STAGE_MERGE, stages[1].stage);
+        assert_eq!(6, stages[1].rows);
+        assert_eq!(2 * bytes, stages[1].bytes);
+    }
+}
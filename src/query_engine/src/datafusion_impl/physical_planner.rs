@@ -15,10 +15,10 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{fmt, sync::Arc};
+use std::{fmt, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
-use datafusion::execution::context::QueryPlanner;
+use datafusion::{execution::context::QueryPlanner, logical_expr::LogicalPlan};
 use generic_error::BoxError;
 use query_frontend::plan::QueryPlan;
 use snafu::ResultExt;
@@ -27,10 +27,13 @@ use crate::{
     context::Context,
     datafusion_impl::{
         physical_plan::{DataFusionPhysicalPlanAdapter, TypedPlan},
+        physical_plan_extension::explain_json::{self, ExplainJson},
         DfContextBuilder,
     },
     error::*,
+    metrics::PHYSICAL_PLAN_DURATION_HISTOGRAM,
     physical_planner::{PhysicalPlanRef, PhysicalPlanner},
+    stage::STAGE_OPTIMIZE,
 };
 
 /// Physical planner based on datafusion
@@ -85,12 +88,32 @@ impl PhysicalPlanner for DatafusionPhysicalPlannerImpl {
         let df_ctx = self.df_ctx_builder.build(ctx);
         let state = df_ctx.state();
 
+        // The plan explained by `EXPLAIN (FORMAT JSON)` is planned directly, and
+        // it's explained after the execution along with its logical plan.
+        let (df_plan, explain_json) = match &logical_plan.df_plan {
+            LogicalPlan::Explain(explain) if logical_plan.explain_json => {
+                let explain_json = ExplainJson {
+                    logical_plan: Some(explain_json::logical_plan_tree(&explain.plan)),
+                };
+                (explain.plan.as_ref(), Some(explain_json))
+            }
+            df_plan => (
+                df_plan,
+                logical_plan.explain_json.then(ExplainJson::default),
+            ),
+        };
+
+        let begin_instant = Instant::now();
         let exec_plan = self
             .physical_planner
-            .create_physical_plan(&logical_plan.df_plan, &state)
+            .create_physical_plan(df_plan, &state)
             .await
             .box_err()
             .context(PhysicalPlannerWithCause { msg: None })?;
+        PHYSICAL_PLAN_DURATION_HISTOGRAM.observe(begin_instant.elapsed().as_secs_f64());
+        if let Some(stages) = &ctx.stages {
+            stages.record(STAGE_OPTIMIZE, begin_instant.elapsed(), 0, 0);
+        }
 
         // Decide if partitioned table exists.
         let has_partitioned_table =
@@ -101,6 +124,10 @@ impl PhysicalPlanner for DatafusionPhysicalPlannerImpl {
             TypedPlan::Normal(exec_plan)
         };
         let physical_plan = DataFusionPhysicalPlanAdapter::new(typed_plan);
+        let physical_plan = match explain_json {
+            Some(explain_json) => physical_plan.with_explain_json(explain_json),
+            None => physical_plan,
+        };
 
         Ok(Arc::new(physical_plan))
     }
@@ -0,0 +1,86 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::{
+    error::Result as DataFusionResult,
+    execution::context::SessionState,
+    logical_expr::logical_plan::{LogicalPlan, UserDefinedLogicalNode},
+    physical_plan::ExecutionPlan,
+    physical_planner::{ExtensionPlanner, PhysicalPlanner},
+};
+use futures::{future::BoxFuture, FutureExt};
+use query_frontend::latest_per_series::LatestPerSeriesNode;
+use table_engine::provider::ScanTable;
+
+use crate::datafusion_impl::physical_plan_extension::latest_per_series::LatestPerSeriesExec;
+
+pub struct LatestPerSeriesPlanner;
+
+#[async_trait]
+impl ExtensionPlanner for LatestPerSeriesPlanner {
+    async fn plan_extension(
+        &self,
+        _planner: &dyn PhysicalPlanner,
+        node: &dyn UserDefinedLogicalNode,
+        logical_inputs: &[&LogicalPlan],
+        physical_inputs: &[Arc<dyn ExecutionPlan>],
+        _session_state: &SessionState,
+    ) -> datafusion::error::Result<Option<Arc<dyn ExecutionPlan>>> {
+        match node.as_any().downcast_ref::<LatestPerSeriesNode>() {
+            Some(node) => {
+                assert_eq!(logical_inputs.len(), 1, "Inconsistent number of inputs");
+                assert_eq!(physical_inputs.len(), 1, "Inconsistent number of inputs");
+                // The newest rows of the series are read first, so the older rows are
+                // skipped without being copied.
+                let input = reverse_scans(physical_inputs[0].clone()).await?;
+                Ok(Some(Arc::new(LatestPerSeriesExec::try_new(
+                    input,
+                    node.series_columns.clone(),
+                    node.timestamp_column.clone(),
+                )?)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Replace the table scans in the plan by the ones reading the tables in the
+/// reverse time order, which is fine as the scans provide no ordering.
+fn reverse_scans(
+    plan: Arc<dyn ExecutionPlan>,
+) -> BoxFuture<'static, DataFusionResult<Arc<dyn ExecutionPlan>>> {
+    async move {
+        if let Some(scan) = plan.as_any().downcast_ref::<ScanTable>() {
+            let scan = scan.try_to_reverse().await?;
+            return Ok(Arc::new(scan) as _);
+        }
+
+        let children = plan.children();
+        if children.is_empty() {
+            return Ok(plan);
+        }
+        let mut new_children = Vec::with_capacity(children.len());
+        for child in children {
+            new_children.push(reverse_scans(child).await?);
+        }
+        plan.with_new_children(new_children)
+    }
+    .boxed()
+}
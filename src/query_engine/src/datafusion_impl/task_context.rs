@@ -17,7 +17,9 @@
 
 use std::{
     fmt,
-    sync::Arc,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context as PollContext, Poll},
     time::{Duration, Instant},
 };
 
@@ -25,9 +27,13 @@ use async_trait::async_trait;
 use catalog::manager::ManagerRef as CatalogManagerRef;
 use common_types::{request_id::RequestId, schema::RecordSchema};
 use datafusion::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
     error::{DataFusionError, Result as DfResult},
     execution::{runtime_env::RuntimeEnv, FunctionRegistry, TaskContext},
-    physical_plan::{display::DisplayableExecutionPlan, ExecutionPlan, SendableRecordBatchStream},
+    physical_plan::{
+        coalesce_partitions::CoalescePartitionsExec, display::DisplayableExecutionPlan,
+        ExecutionPlan, RecordBatchStream, SendableRecordBatchStream,
+    },
 };
 use datafusion_proto::{
     bytes::physical_plan_to_bytes_with_extension_codec,
@@ -35,15 +41,19 @@ use datafusion_proto::{
     protobuf,
 };
 use df_engine_extensions::dist_sql_query::{
-    resolver::Resolver, ExecutableScanBuilder, RemotePhysicalPlanExecutor,
-    RemotePhysicalPlanExecutorRef, RemoteTaskContext, TableScanContext,
+    physical_plan::{AggregatePushDownChecker, UnresolvedSubTableScan},
+    resolver::Resolver,
+    ExecutableScanBuilder,
+    RemotePhysicalPlanExecutor, RemotePhysicalPlanExecutorRef, RemoteTaskContext, ScanRetryPolicy,
+    TableScanContext,
 };
-use futures::future::BoxFuture;
+use futures::{future::BoxFuture, Stream, StreamExt};
 use generic_error::BoxError;
 use runtime::Priority;
 use snafu::ResultExt;
 use table_engine::{
     provider::{HoraeDBOptions, ScanTable, SCAN_TABLE_METRICS_COLLECTOR_NAME},
+    query_warning::QueryWarnings,
     remote::{
         model::{
             ExecContext, ExecutePlanRequest, PhysicalPlan, RemoteExecuteRequest, TableIdentifier,
@@ -78,15 +88,20 @@ impl Preprocessor {
         runtime_env: Arc<RuntimeEnv>,
         function_registry: Arc<dyn FunctionRegistry + Send + Sync>,
         extension_codec: Arc<dyn PhysicalExtensionCodec>,
+        aggr_checker: AggregatePushDownChecker,
+        scan_retry: ScanRetryPolicy,
     ) -> Self {
         let remote_executor = Arc::new(RemotePhysicalPlanExecutorImpl {
             remote_engine,
+            catalog_manager: catalog_manager.clone(),
             extension_codec: extension_codec.clone(),
         });
 
         let dist_query_resolver_builder = DistQueryResolverBuilder {
             remote_executor,
             catalog_manager,
+            aggr_checker,
+            scan_retry,
         };
 
         Self {
@@ -147,11 +162,20 @@ impl Preprocessor {
         ctx: &Context,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         let resolver = self.dist_query_resolver_builder.build(ctx);
-        resolver
+        let resolved_plan = resolver
             .resolve_partitioned_scan(plan.clone())
             .box_err()
             .with_context(|| ExecutorWithCause {
                 msg: format!("failed to preprocess partitioned table plan, plan:{plan:?}"),
+            })?;
+
+        // Send the plans of the sub tables on the same node by one request.
+        resolver
+            .batch_remote_plans(resolved_plan)
+            .await
+            .box_err()
+            .with_context(|| ExecutorWithCause {
+                msg: format!("failed to batch remote plans, plan:{plan:?}"),
             })
     }
 }
@@ -166,12 +190,65 @@ impl fmt::Debug for Preprocessor {
 }
 
 /// Remote physical plan executor impl
-#[derive(Debug)]
+///
+/// The partial plans of the sub tables opened by the local node are executed
+/// in-process, and the others are sent to the remote engine.
 struct RemotePhysicalPlanExecutorImpl {
     remote_engine: RemoteEngineRef,
+    catalog_manager: CatalogManagerRef,
     extension_codec: Arc<dyn PhysicalExtensionCodec>,
 }
 
+impl RemotePhysicalPlanExecutorImpl {
+    /// Find the sub table in the local catalog, which only contains the tables
+    /// of the shards opened by the local node.
+    fn find_local_table(&self, table: &TableIdentifier) -> Option<TableRef> {
+        let catalog = self.catalog_manager.catalog_by_name(&table.catalog).ok()??;
+        let schema = catalog.schema_by_name(&table.schema).ok()??;
+        schema.table_by_name(&table.table).ok()?
+    }
+
+    fn execute_locally(
+        task_context: RemoteTaskContext,
+        table: TableRef,
+        plan: Arc<dyn ExecutionPlan>,
+        scan_builder: ExecutableScanBuilderImpl,
+        priority: Priority,
+    ) -> BoxFuture<'static, DfResult<SendableRecordBatchStream>> {
+        Box::pin(async move {
+            let table_scan_ctx = find_sub_table_scan_ctx(&plan).ok_or_else(|| {
+                DataFusionError::Internal(format!("sub table scan not found, plan:{plan:?}"))
+            })?;
+            let scan = scan_builder.build(table, table_scan_ctx, priority).await?;
+            let mut plan = replace_sub_table_scan(plan, scan)?;
+            if plan.output_partitioning().partition_count() > 1 {
+                plan = Arc::new(CoalescePartitionsExec::new(plan));
+            }
+
+            let inner = plan.execute(0, task_context.task_ctx)?;
+            let remote_metrics = task_context
+                .is_analyze
+                .then_some(task_context.remote_metrics);
+            let stream = LocalPlanStream {
+                inner,
+                plan,
+                remote_metrics,
+            };
+
+            Ok(Box::pin(stream) as _)
+        })
+    }
+}
+
+impl fmt::Debug for RemotePhysicalPlanExecutorImpl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemotePhysicalPlanExecutorImpl")
+            .field("remote_engine", &self.remote_engine)
+            .field("extension_codec", &self.extension_codec)
+            .finish()
+    }
+}
+
 impl RemotePhysicalPlanExecutor for RemotePhysicalPlanExecutorImpl {
     fn execute(
         &self,
@@ -196,6 +273,29 @@ impl RemotePhysicalPlanExecutor for RemotePhysicalPlanExecutorImpl {
         let default_catalog = options.default_catalog.clone();
         let default_schema = options.default_schema.clone();
         let priority = options.priority;
+        let warnings = task_context
+            .task_ctx
+            .session_config()
+            .get_extension::<QueryWarnings>()
+            .as_deref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Skip the encoding and the rpc if the sub table is opened locally.
+        if let Some(local_table) = self.find_local_table(&table) {
+            let scan_builder = ExecutableScanBuilderImpl {
+                request_id,
+                deadline,
+                warnings,
+            };
+            return Ok(Self::execute_locally(
+                task_context,
+                local_table,
+                plan,
+                scan_builder,
+                priority,
+            ));
+        }
 
         let display_plan = DisplayableExecutionPlan::new(plan.as_ref());
         let exec_ctx = ExecContext {
@@ -232,29 +332,127 @@ impl RemotePhysicalPlanExecutor for RemotePhysicalPlanExecutorImpl {
                 plan_schema,
                 remote_request,
                 remote_metrics: task_context.remote_metrics,
+                warnings,
             };
 
-            // Remote execute.
+            // Remote execute, and the error is kept to tell whether it's retryable.
             let stream = remote_engine
                 .execute_physical_plan(request)
                 .await
-                .map_err(|e| {
-                    DataFusionError::Internal(format!(
-                        "failed to execute physical plan by remote engine, err:{e}"
-                    ))
-                })?;
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
 
             Ok(Box::pin(ToDfStream(stream)) as _)
         });
 
         Ok(future)
     }
+
+    fn route(
+        &self,
+        tables: Vec<TableIdentifier>,
+    ) -> BoxFuture<'static, DfResult<Vec<Option<String>>>> {
+        // The plans of the local sub tables are executed in-process one by one.
+        let remote_indexes: Vec<_> = tables
+            .iter()
+            .enumerate()
+            .filter(|(_, table)| self.find_local_table(table).is_none())
+            .map(|(idx, _)| idx)
+            .collect();
+        let remote_engine = self.remote_engine.clone();
+        Box::pin(async move {
+            let mut endpoints = vec![None; tables.len()];
+            if remote_indexes.len() <= 1 {
+                return Ok(endpoints);
+            }
+
+            let remote_tables: Vec<_> = remote_indexes
+                .iter()
+                .map(|idx| tables[*idx].clone())
+                .collect();
+            let remote_endpoints = remote_engine
+                .route(&remote_tables)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            for (idx, endpoint) in remote_indexes.into_iter().zip(remote_endpoints) {
+                endpoints[idx] = Some(endpoint);
+            }
+
+            Ok(endpoints)
+        })
+    }
+}
+
+/// Find the context of the `UnresolvedSubTableScan` in the partial plan.
+fn find_sub_table_scan_ctx(plan: &Arc<dyn ExecutionPlan>) -> Option<TableScanContext> {
+    if let Some(unresolved) = plan.as_any().downcast_ref::<UnresolvedSubTableScan>() {
+        return Some(unresolved.table_scan_ctx.clone());
+    }
+
+    plan.children().iter().find_map(find_sub_table_scan_ctx)
+}
+
+/// Replace the `UnresolvedSubTableScan` in the partial plan with the executable
+/// scan, the same as what `Resolver::resolve_sub_scan` does on the remote node.
+fn replace_sub_table_scan(
+    plan: Arc<dyn ExecutionPlan>,
+    scan: Arc<dyn ExecutionPlan>,
+) -> DfResult<Arc<dyn ExecutionPlan>> {
+    if plan.as_any().is::<UnresolvedSubTableScan>() {
+        return Ok(scan);
+    }
+
+    let children = plan.children();
+    if children.is_empty() {
+        return Ok(plan);
+    }
+
+    let new_children = children
+        .into_iter()
+        .map(|child| replace_sub_table_scan(child, scan.clone()))
+        .collect::<DfResult<Vec<_>>>()?;
+    plan.with_new_children(new_children)
+}
+
+/// Stream of the partial plan executed locally, whose metrics are filled into
+/// the `remote_metrics` at the end for analyze, like the remote path.
+struct LocalPlanStream {
+    inner: SendableRecordBatchStream,
+    plan: Arc<dyn ExecutionPlan>,
+    remote_metrics: Option<Arc<Mutex<Option<String>>>>,
+}
+
+impl Stream for LocalPlanStream {
+    type Item = DfResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut PollContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match this.inner.poll_next_unpin(cx) {
+            Poll::Ready(None) => {
+                if let Some(remote_metrics) = this.remote_metrics.take() {
+                    let metrics = DisplayableExecutionPlan::with_metrics(this.plan.as_ref())
+                        .indent(true)
+                        .to_string();
+                    *remote_metrics.lock().unwrap() = Some(metrics);
+                }
+                Poll::Ready(None)
+            }
+            other => other,
+        }
+    }
+}
+
+impl RecordBatchStream for LocalPlanStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
 }
 
 /// Used to build dist query resolver dynamically
 struct DistQueryResolverBuilder {
     remote_executor: RemotePhysicalPlanExecutorRef,
     catalog_manager: CatalogManagerRef,
+    aggr_checker: AggregatePushDownChecker,
+    scan_retry: ScanRetryPolicy,
 }
 
 impl DistQueryResolverBuilder {
@@ -262,6 +460,7 @@ impl DistQueryResolverBuilder {
         let scan_builder = Box::new(ExecutableScanBuilderImpl {
             request_id: ctx.request_id.clone(),
             deadline: ctx.deadline,
+            warnings: ctx.warnings.clone(),
         });
 
         Resolver::new(
@@ -269,7 +468,9 @@ impl DistQueryResolverBuilder {
             self.catalog_manager.clone(),
             scan_builder,
             ctx.priority,
+            self.aggr_checker.clone(),
         )
+        .with_scan_retry(self.scan_retry)
     }
 }
 
@@ -278,6 +479,7 @@ impl DistQueryResolverBuilder {
 struct ExecutableScanBuilderImpl {
     request_id: RequestId,
     deadline: Option<Instant>,
+    warnings: QueryWarnings,
 }
 
 #[async_trait]
@@ -292,6 +494,10 @@ impl ExecutableScanBuilder for ExecutableScanBuilderImpl {
             batch_size: ctx.batch_size,
             read_parallelism: ctx.read_parallelism,
             deadline: self.deadline,
+            snapshot_time: None,
+            snapshot_sequence: None,
+            warnings: self.warnings.clone(),
+            reverse: false,
         };
 
         let read_request = ReadRequest {
@@ -24,21 +24,31 @@ pub mod context;
 pub mod datafusion_impl;
 pub mod error;
 pub mod executor;
+mod metrics;
 pub mod physical_planner;
+pub mod remote_udf;
+pub mod stage;
 use std::{fmt, sync::Arc};
 
+use alloc_tracker::budget::MemoryBudgetRef;
 use catalog::manager::ManagerRef;
 use datafusion::execution::{runtime_env::RuntimeConfig, FunctionRegistry};
+use df_engine_extensions::dist_sql_query::physical_plan::{AggregatePushDownChecker, UdafRegistry};
+use df_operator::registry::FunctionRegistryRef;
+use logger::warn;
 use snafu::OptionExt;
 use table_engine::remote::RemoteEngineRef;
 
 use crate::{
     config::Config, datafusion_impl::DatafusionQueryEngineImpl, error::*, executor::ExecutorRef,
-    physical_planner::PhysicalPlannerRef,
+    physical_planner::PhysicalPlannerRef, remote_udf::RemoteUdfs,
 };
 
 /// Query engine
 pub trait QueryEngine: fmt::Debug + Send + Sync {
+    /// The config the engine is built with.
+    fn config(&self) -> &Config;
+
     fn physical_planner(&self) -> PhysicalPlannerRef;
 
     fn executor(&self) -> ExecutorRef;
@@ -54,6 +64,9 @@ pub struct QueryEngineBuilder {
     df_runtime_config: Option<RuntimeConfig>,
     catalog_manager: Option<ManagerRef>,
     remote_engine: Option<RemoteEngineRef>,
+    function_registry: Option<FunctionRegistryRef>,
+    memory_budget: Option<MemoryBudgetRef>,
+    remote_udfs: Option<Arc<RemoteUdfs>>,
 }
 
 #[derive(Debug)]
@@ -90,6 +103,27 @@ impl QueryEngineBuilder {
         self
     }
 
+    /// Set the function registry, and the aggregation involving the udafs
+    /// whose partial states can't be merged won't be pushed down to the remote
+    /// nodes.
+    pub fn function_registry(mut self, function_registry: FunctionRegistryRef) -> Self {
+        self.function_registry = Some(function_registry);
+        self
+    }
+
+    /// Set the memory budget whose query limit is shared by all the queries.
+    pub fn memory_budget(mut self, memory_budget: MemoryBudgetRef) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    /// Set the remote udfs whose stubs are registered into the function
+    /// registry.
+    pub fn remote_udfs(mut self, remote_udfs: Arc<RemoteUdfs>) -> Self {
+        self.remote_udfs = Some(remote_udfs);
+        self
+    }
+
     fn build_datafusion_query_engine(self) -> Result<QueryEngineRef> {
         // Check if necessary component exists.
         let config = self.config.with_context(|| InitNoCause {
@@ -112,6 +146,13 @@ impl QueryEngineBuilder {
             msg: "remote_engine not found",
         })?;
 
+        let aggr_checker = match self.function_registry {
+            Some(registry) => {
+                AggregatePushDownChecker::new(Arc::new(UdafRegistryAdapter(registry)))
+            }
+            None => AggregatePushDownChecker::default(),
+        };
+
         // Build engine.
         let df_query_engine = DatafusionQueryEngineImpl::new(
             config,
@@ -119,6 +160,9 @@ impl QueryEngineBuilder {
             df_function_registry,
             remote_engine,
             catalog_manager,
+            aggr_checker,
+            self.memory_budget,
+            self.remote_udfs.unwrap_or_default(),
         )?;
 
         Ok(Arc::new(df_query_engine))
@@ -130,3 +174,25 @@ impl QueryEngineBuilder {
         }
     }
 }
+
+/// Adapter looking up the udafs in the function registry every time, so the
+/// udafs registered at runtime are checked too.
+struct UdafRegistryAdapter(FunctionRegistryRef);
+
+impl fmt::Debug for UdafRegistryAdapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdafRegistryAdapter").finish()
+    }
+}
+
+impl UdafRegistry for UdafRegistryAdapter {
+    fn is_state_mergeable(&self, name: &str) -> bool {
+        match self.0.find_udaf(name) {
+            Ok(udaf) => udaf.map_or(true, |udaf| udaf.is_state_mergeable()),
+            Err(e) => {
+                warn!("Failed to find udaf, consider it unmergeable, name:{name}, err:{e}");
+                false
+            }
+        }
+    }
+}
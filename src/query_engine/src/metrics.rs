@@ -0,0 +1,40 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Metrics of the query engine.
+
+use lazy_static::lazy_static;
+use prometheus::{exponential_buckets, register_histogram_vec, Histogram, HistogramVec};
+
+lazy_static! {
+    // Buckets: 0, 0.0005, .., 0.0005 * 2^15
+    static ref QUERY_ENGINE_DURATION_HISTOGRAM_VEC: HistogramVec = register_histogram_vec!(
+        "query_engine_duration",
+        "Histogram for duration of the stages of the query engine in seconds",
+        &["stage"],
+        exponential_buckets(0.0005, 2.0, 16).unwrap()
+    )
+    .unwrap();
+
+    /// Duration to create the physical plan from the logical plan.
+    pub static ref PHYSICAL_PLAN_DURATION_HISTOGRAM: Histogram =
+        QUERY_ENGINE_DURATION_HISTOGRAM_VEC.with_label_values(&["physical_plan"]);
+
+    /// Duration to execute the physical plan into the output stream.
+    pub static ref EXECUTE_DURATION_HISTOGRAM: Histogram =
+        QUERY_ENGINE_DURATION_HISTOGRAM_VEC.with_label_values(&["execute"]);
+}
@@ -0,0 +1,689 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Udfs evaluated by an external service.
+//!
+//! The remote udfs are registered into the function registry as stubs so that
+//! the queries calling them can be planned. The calls at the top level of the
+//! projections are evaluated asynchronously by sending the arguments to the
+//! service in batches through grpc, and the calls elsewhere, e.g. in the filters
+//! or the arguments of the aggregations, are evaluated by the stubs blocking on
+//! the same calls.
+//!
+//! The service receives the arguments as an arrow ipc stream whose columns are
+//! the arguments in order, and responds with an arrow ipc stream of one column
+//! with the same number of rows as the request. The name of the function is
+//! carried by the `x-horaedb-udf` metadata so that one service can serve
+//! multiple functions.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use arrow::{
+    array::{new_empty_array, ArrayRef},
+    compute,
+    datatypes::{DataType, Field, Schema},
+    error::ArrowError,
+    ipc::{reader::StreamReader, writer::StreamWriter},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use bytes_ext::{Buf, BufMut, Bytes};
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility},
+    scalar::ScalarValue,
+};
+use df_operator::{
+    registry::{self, FunctionRegistry},
+    scalar::ScalarUdf,
+};
+use logger::warn;
+use macros::define_result;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+use time_ext::ReadableDuration;
+use tokio::{
+    runtime::{Handle, RuntimeFlavor},
+    task,
+};
+use tonic::{
+    client::Grpc,
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    codegen::http::uri::PathAndQuery,
+    metadata::{Ascii, MetadataValue},
+    transport::{Channel, Endpoint},
+    Request, Status,
+};
+
+/// Path of the grpc method evaluating the udfs.
+const EVALUATE_PATH: &str = "/horaedb.udf.RemoteUdf/Evaluate";
+/// Metadata key carrying the name of the udf to evaluate.
+const FUNCTION_NAME_KEY: &str = "x-horaedb-udf";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Invalid remote udf config, name:{name}, msg:{msg}.\nBacktrace:\n{backtrace}"
+    ))]
+    InvalidConfig {
+        name: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Invalid endpoint of remote udf, name:{name}, err:{source}"))]
+    InvalidEndpoint {
+        name: String,
+        source: tonic::transport::Error,
+    },
+
+    #[snafu(display(
+        "Remote udf is unavailable as too many failures, name:{name}.\nBacktrace:\n{backtrace}"
+    ))]
+    CircuitOpen { name: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Remote udf timeout, name:{name}, timeout:{timeout:?}.\nBacktrace:\n{backtrace}"
+    ))]
+    Timeout {
+        name: String,
+        timeout: Duration,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to call remote udf, name:{name}, err:{source}"))]
+    Rpc { name: String, source: Status },
+
+    #[snafu(display("Failed to encode or decode the batch of remote udf, err:{source}"))]
+    Ipc { source: ArrowError },
+
+    #[snafu(display(
+        "Invalid response of remote udf, name:{name}, msg:{msg}.\nBacktrace:\n{backtrace}"
+    ))]
+    InvalidResponse {
+        name: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to register remote udf, err:{source}"))]
+    Register { source: registry::Error },
+}
+
+define_result!(Error);
+
+/// Return type of the remote udf.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RemoteUdfType {
+    Float64,
+    Int64,
+    Utf8,
+    Boolean,
+}
+
+impl From<RemoteUdfType> for DataType {
+    fn from(value: RemoteUdfType) -> Self {
+        match value {
+            RemoteUdfType::Float64 => DataType::Float64,
+            RemoteUdfType::Int64 => DataType::Int64,
+            RemoteUdfType::Utf8 => DataType::Utf8,
+            RemoteUdfType::Boolean => DataType::Boolean,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RemoteUdfConfig {
+    /// Name of the udf in the queries.
+    pub name: String,
+    /// Address of the service, e.g. `http://127.0.0.1:9090`.
+    pub endpoint: String,
+    pub return_type: RemoteUdfType,
+    /// Max rows sent to the service in one request.
+    pub batch_size: usize,
+    /// Timeout of one request.
+    pub timeout: ReadableDuration,
+    /// The calls fail fast without requesting the service for `open_duration`
+    /// after `failure_threshold` consecutive failures.
+    pub failure_threshold: usize,
+    pub open_duration: ReadableDuration,
+}
+
+impl Default for RemoteUdfConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            endpoint: String::new(),
+            return_type: RemoteUdfType::Float64,
+            batch_size: 4096,
+            timeout: ReadableDuration::secs(5),
+            failure_threshold: 5,
+            open_duration: ReadableDuration::secs(30),
+        }
+    }
+}
+
+/// Register the stubs of the remote udfs into the function registry.
+pub fn register_stubs(registry: &dyn FunctionRegistry, udfs: &RemoteUdfs) -> Result<()> {
+    for udf in udfs.udfs.values() {
+        registry
+            .register_udf(new_stub(udf.clone()))
+            .context(Register)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn new_stub(udf: Arc<RemoteUdf>) -> ScalarUdf {
+    let stub = RemoteUdfStub {
+        udf,
+        signature: Signature::variadic_any(Volatility::Volatile),
+    };
+
+    ScalarUdf::from_datafusion_udf(ScalarUDF::new_from_impl(stub))
+}
+
+/// Stub of the remote udf in the function registry.
+///
+/// The calls replaced by [RemoteUdfExec] are evaluated asynchronously, and the
+/// stub evaluates the other calls by blocking on the remote call, which moves
+/// the other tasks off the current worker thread.
+///
+/// [RemoteUdfExec]: crate::datafusion_impl::physical_plan_extension::remote_udf::RemoteUdfExec
+#[derive(Debug)]
+struct RemoteUdfStub {
+    udf: Arc<RemoteUdf>,
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for RemoteUdfStub {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.udf.name()
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> DataFusionResult<DataType> {
+        Ok(self.udf.return_type().clone())
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> DataFusionResult<ColumnarValue> {
+        let num_rows = args.iter().find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        });
+        // The call on the scalars only is evaluated once.
+        let arrays = args
+            .iter()
+            .map(|arg| arg.clone().into_array(num_rows.unwrap_or(1)))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        let args = args_batch(arrays, num_rows.unwrap_or(1))?;
+
+        let handle = Handle::try_current()
+            .ok()
+            .filter(|handle| handle.runtime_flavor() == RuntimeFlavor::MultiThread)
+            .ok_or_else(|| {
+                DataFusionError::NotImplemented(format!(
+                    "remote udf must be evaluated in the multi-thread runtime, name:{}",
+                    self.udf.name()
+                ))
+            })?;
+        let array = task::block_in_place(|| handle.block_on(self.udf.call(&args)))
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        match num_rows {
+            Some(_) => Ok(ColumnarValue::Array(array)),
+            None => Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(&array, 0)?)),
+        }
+    }
+}
+
+/// Build the batch sent to the service, whose columns are the arguments in
+/// order.
+pub(crate) fn args_batch(arrays: Vec<ArrayRef>, num_rows: usize) -> DataFusionResult<RecordBatch> {
+    let fields: Vec<_> = arrays
+        .iter()
+        .enumerate()
+        .map(|(idx, array)| Field::new(format!("arg{idx}"), array.data_type().clone(), true))
+        .collect();
+
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        arrays,
+        &options,
+    )?)
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: usize,
+    open_until: Option<Instant>,
+}
+
+/// Circuit breaker rejecting the calls for a while after too many consecutive
+/// failures, and one call is let through to probe the service after that.
+#[derive(Debug)]
+struct CircuitBreaker {
+    failure_threshold: usize,
+    open_duration: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: usize, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold,
+            open_duration,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn allow(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(open_until) => Instant::now() >= open_until,
+            None => true,
+        }
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    fn on_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(Instant::now() + self.open_duration);
+        }
+    }
+}
+
+/// Client of the remote udf.
+#[derive(Debug)]
+pub struct RemoteUdf {
+    name: String,
+    return_type: DataType,
+    batch_size: usize,
+    timeout: Duration,
+    name_value: MetadataValue<Ascii>,
+    endpoint: Endpoint,
+    /// Connected lazily on the first call.
+    channel: Mutex<Option<Channel>>,
+    breaker: CircuitBreaker,
+}
+
+impl RemoteUdf {
+    pub fn try_new(config: &RemoteUdfConfig) -> Result<Self> {
+        ensure!(
+            config.batch_size > 0,
+            InvalidConfig {
+                name: &config.name,
+                msg: "batch_size must be positive",
+            }
+        );
+        let Ok(name_value) = config.name.parse() else {
+            return InvalidConfig {
+                name: &config.name,
+                msg: "name is not a valid metadata value",
+            }
+            .fail();
+        };
+        let endpoint = Endpoint::from_shared(config.endpoint.clone())
+            .context(InvalidEndpoint { name: &config.name })?
+            .timeout(config.timeout.0);
+
+        Ok(Self {
+            name: config.name.clone(),
+            return_type: config.return_type.into(),
+            batch_size: config.batch_size,
+            timeout: config.timeout.0,
+            name_value,
+            endpoint,
+            channel: Mutex::new(None),
+            breaker: CircuitBreaker::new(config.failure_threshold, config.open_duration.0),
+        })
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn return_type(&self) -> &DataType {
+        &self.return_type
+    }
+
+    #[inline]
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Evaluate the udf with the columns of `args` as the arguments, and the
+    /// rows are sent in batches of at most `batch_size` rows.
+    pub async fn call(&self, args: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = args.num_rows();
+        if num_rows == 0 {
+            return Ok(new_empty_array(&self.return_type));
+        }
+
+        let mut arrays = Vec::with_capacity(num_rows.div_ceil(self.batch_size));
+        let mut offset = 0;
+        while offset < num_rows {
+            let len = self.batch_size.min(num_rows - offset);
+            arrays.push(self.call_batch(args.slice(offset, len)).await?);
+            offset += len;
+        }
+
+        if arrays.len() == 1 {
+            return Ok(arrays.pop().unwrap());
+        }
+        let arrays: Vec<_> = arrays.iter().map(|v| v.as_ref()).collect();
+        compute::concat(&arrays).context(Ipc)
+    }
+
+    async fn call_batch(&self, batch: RecordBatch) -> Result<ArrayRef> {
+        ensure!(self.breaker.allow(), CircuitOpen { name: &self.name });
+
+        let res = self.evaluate(&batch).await;
+        match &res {
+            Ok(_) => self.breaker.on_success(),
+            Err(e) => {
+                warn!("Failed to call remote udf, name:{}, err:{e}", self.name);
+                self.breaker.on_failure();
+            }
+        }
+
+        res
+    }
+
+    async fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let mut request = Request::new(encode_batch(batch)?);
+        request
+            .metadata_mut()
+            .insert(FUNCTION_NAME_KEY, self.name_value.clone());
+
+        let mut client = Grpc::new(self.channel());
+        let call = async {
+            client
+                .ready()
+                .await
+                .map_err(|e| Status::unavailable(e.to_string()))?;
+            client
+                .unary(
+                    request,
+                    PathAndQuery::from_static(EVALUATE_PATH),
+                    BytesCodec,
+                )
+                .await
+        };
+        let Ok(response) = tokio::time::timeout(self.timeout, call).await else {
+            return Timeout {
+                name: &self.name,
+                timeout: self.timeout,
+            }
+            .fail();
+        };
+        let response = response.context(Rpc { name: &self.name })?;
+
+        let array = decode_column(&self.name, response.into_inner())?;
+        ensure!(
+            array.len() == batch.num_rows() && array.data_type() == &self.return_type,
+            InvalidResponse {
+                name: &self.name,
+                msg: format!(
+                    "expect {} rows of {}, but got {} rows of {}",
+                    batch.num_rows(),
+                    self.return_type,
+                    array.len(),
+                    array.data_type()
+                ),
+            }
+        );
+
+        Ok(array)
+    }
+
+    fn channel(&self) -> Channel {
+        let mut channel = self.channel.lock().unwrap();
+        channel
+            .get_or_insert_with(|| self.endpoint.connect_lazy())
+            .clone()
+    }
+}
+
+fn encode_batch(batch: &RecordBatch) -> Result<Bytes> {
+    let mut writer = StreamWriter::try_new(Vec::new(), &batch.schema()).context(Ipc)?;
+    writer.write(batch).context(Ipc)?;
+    writer.finish().context(Ipc)?;
+
+    Ok(Bytes::from(writer.into_inner().context(Ipc)?))
+}
+
+fn decode_column(name: &str, payload: Bytes) -> Result<ArrayRef> {
+    let reader = StreamReader::try_new(Cursor::new(payload), None).context(Ipc)?;
+    let mut arrays = Vec::new();
+    for batch in reader {
+        let batch = batch.context(Ipc)?;
+        ensure!(
+            batch.num_columns() == 1,
+            InvalidResponse {
+                name,
+                msg: format!("expect one column, but got {}", batch.num_columns()),
+            }
+        );
+        arrays.push(batch.column(0).clone());
+    }
+
+    ensure!(
+        !arrays.is_empty(),
+        InvalidResponse {
+            name,
+            msg: "no batch in the response",
+        }
+    );
+    let arrays: Vec<_> = arrays.iter().map(|v| v.as_ref()).collect();
+    compute::concat(&arrays).context(Ipc)
+}
+
+/// Codec sending and receiving the raw bytes.
+#[derive(Debug, Clone, Copy, Default)]
+struct BytesCodec;
+
+impl Codec for BytesCodec {
+    type Decode = Bytes;
+    type Decoder = BytesCodec;
+    type Encode = Bytes;
+    type Encoder = BytesCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        *self
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        *self
+    }
+}
+
+impl Encoder for BytesCodec {
+    type Error = Status;
+    type Item = Bytes;
+
+    fn encode(&mut self, item: Bytes, dst: &mut EncodeBuf<'_>) -> std::result::Result<(), Status> {
+        dst.put(item);
+        Ok(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Error = Status;
+    type Item = Bytes;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> std::result::Result<Option<Bytes>, Status> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+/// The remote udfs by name.
+#[derive(Debug, Default)]
+pub struct RemoteUdfs {
+    udfs: HashMap<String, Arc<RemoteUdf>>,
+}
+
+impl RemoteUdfs {
+    pub fn try_new(configs: &[RemoteUdfConfig]) -> Result<Self> {
+        let mut udfs = HashMap::with_capacity(configs.len());
+        for config in configs {
+            udfs.insert(config.name.clone(), Arc::new(RemoteUdf::try_new(config)?));
+        }
+
+        Ok(Self { udfs })
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.udfs.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Arc<RemoteUdf>> {
+        self.udfs.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Float64Array, Int64Array};
+
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        assert!(breaker.allow());
+        breaker.on_failure();
+        assert!(breaker.allow());
+        breaker.on_failure();
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(60));
+        // Half open, the probe fails and the breaker opens again.
+        assert!(breaker.allow());
+        breaker.on_failure();
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(60));
+        breaker.on_success();
+        assert!(breaker.allow());
+        breaker.on_failure();
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn test_ipc_roundtrip() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "score",
+            DataType::Float64,
+            true,
+        )]));
+        let column = Arc::new(Float64Array::from(vec![Some(0.5), None, Some(1.5)])) as ArrayRef;
+        let batch = RecordBatch::try_new(schema, vec![column.clone()]).unwrap();
+
+        let decoded = decode_column("score", encode_batch(&batch).unwrap()).unwrap();
+        assert_eq!(&column, &decoded);
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1])),
+                Arc::new(Int64Array::from(vec![2])),
+            ],
+        )
+        .unwrap();
+        assert!(decode_column("score", encode_batch(&batch).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_stub_evaluation() {
+        let config = RemoteUdfConfig {
+            name: "score".to_string(),
+            // Nothing listens on the port so the call fails.
+            endpoint: "http://127.0.0.1:1".to_string(),
+            ..Default::default()
+        };
+        let stub = RemoteUdfStub {
+            udf: Arc::new(RemoteUdf::try_new(&config).unwrap()),
+            signature: Signature::variadic_any(Volatility::Volatile),
+        };
+        let args = vec![ColumnarValue::Array(Arc::new(Int64Array::from(vec![1, 2])))];
+
+        // The stub can't block on the call outside the multi-thread runtime.
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let err = rt.block_on(async { stub.invoke(&args) }).unwrap_err();
+        assert!(matches!(err, DataFusionError::NotImplemented(_)), "{err}");
+
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let err = rt.block_on(async { stub.invoke(&args) }).unwrap_err();
+        assert!(matches!(err, DataFusionError::External(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_config() {
+        let config = RemoteUdfConfig {
+            name: "score".to_string(),
+            endpoint: "http://127.0.0.1:9090".to_string(),
+            batch_size: 0,
+            ..Default::default()
+        };
+        assert!(RemoteUdf::try_new(&config).is_err());
+
+        let config = RemoteUdfConfig {
+            batch_size: 1,
+            ..config
+        };
+        let udf = RemoteUdf::try_new(&config).unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let empty = RecordBatch::new_empty(schema);
+        assert_eq!(0, udf.call(&empty).await.unwrap().len());
+    }
+}
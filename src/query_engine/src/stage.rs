@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Stages of a query
+//!
+//! The wall time, output rows and bytes of every stage of a query are recorded
+//! into its [QueryStages], which is output by `EXPLAIN ANALYZE` in json format.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+pub const STAGE_PARSE: &str = "parse";
+pub const STAGE_PLAN: &str = "plan";
+pub const STAGE_OPTIMIZE: &str = "optimize";
+pub const STAGE_SCAN: &str = "scan";
+pub const STAGE_MERGE: &str = "merge";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Stage {
+    pub stage: &'static str,
+    pub elapsed_us: u64,
+    /// Rows output by the stage, zero if it outputs no rows.
+    pub rows: u64,
+    /// Bytes output by the stage, zero if it outputs no bytes.
+    pub bytes: u64,
+}
+
+/// Stages of a query in the order they are recorded.
+#[derive(Debug, Default)]
+pub struct QueryStages {
+    stages: Mutex<Vec<Stage>>,
+}
+
+pub type QueryStagesRef = Arc<QueryStages>;
+
+impl QueryStages {
+    pub fn record(&self, stage: &'static str, elapsed: Duration, rows: u64, bytes: u64) {
+        self.stages.lock().unwrap().push(Stage {
+            stage,
+            elapsed_us: elapsed.as_micros() as u64,
+            rows,
+            bytes,
+        });
+    }
+
+    pub fn stages(&self) -> Vec<Stage> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    /// Format the stages as a json array.
+    pub fn to_json(&self) -> String {
+        // Serializing the plain structs never fails.
+        serde_json::to_string(&*self.stages.lock().unwrap()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_stages() {
+        let stages = QueryStages::default();
+        stages.record(STAGE_PARSE, Duration::from_micros(10), 0, 0);
+        stages.record(STAGE_MERGE, Duration::from_millis(2), 3, 100);
+
+        assert_eq!(2, stages.stages().len());
+        assert_eq!(
+            r#"[{"stage":"parse","elapsed_us":10,"rows":0,"bytes":0},{"stage":"merge","elapsed_us":2000,"rows":3,"bytes":100}]"#,
+            stages.to_json()
+        );
+    }
+}
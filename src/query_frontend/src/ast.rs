@@ -17,15 +17,26 @@
 
 //! SQL statement
 
+use std::fmt;
+
 use sqlparser::ast::{
-    ColumnDef, ObjectName, SqlOption, Statement as SqlStatement, TableConstraint,
+    ColumnDef, ObjectName, Query, SqlOption, Statement as SqlStatement, TableConstraint,
 };
+use time_ext::ReadableDuration;
 
 /// Statement representations
 #[derive(Debug, PartialEq, Eq)]
 pub enum Statement {
     /// ANSI SQL AST node
     Standard(Box<SqlStatement>),
+    /// `SELECT ALL COLUMNS ...`, a query whose wildcards are expanded to all
+    /// the columns regardless of the wildcard limit
+    SelectAllColumns(Box<SqlStatement>),
+    /// `EXPLAIN ... WITH SETTINGS ...`, the effective settings of the query
+    /// and the schema versions of the tables are explained besides the plan
+    ExplainWithSettings(Box<SqlStatement>),
+    /// `EXPLAIN (FORMAT JSON) ...`, the plan is explained as a json tree
+    ExplainJson(ExplainJson),
     // Other extensions
     /// CREATE TABLE
     Create(Box<CreateTable>),
@@ -34,11 +45,65 @@ pub enum Statement {
     Describe(DescribeTable),
     AlterModifySetting(AlterModifySetting),
     AlterAddColumn(AlterAddColumn),
+    /// ALTER TABLE ... DROP COLUMN
+    AlterDropColumn(AlterDropColumn),
+    /// ALTER TABLE ... RENAME COLUMN
+    AlterRenameColumn(AlterRenameColumn),
+    AlterModifyColumn(AlterModifyColumn),
+    /// ALTER TABLE ... SWAP WITH
+    AlterSwapTable(AlterSwapTable),
+    /// ALTER SCHEMA ... MODIFY SETTING
+    AlterSchemaSetting(AlterSchemaSetting),
+    /// ALTER SYSTEM SET
+    AlterSystemSet(AlterSystemSet),
+    /// ANALYZE TABLE
+    AnalyzeTable(AnalyzeTable),
+    /// COMPACT TABLE
+    CompactTable(CompactTable),
+    /// RESYNC TABLE
+    ResyncTable(ResyncTable),
     /// SHOW CREATE TABLE
     ShowCreate(ShowCreate),
     ShowDatabases,
     ShowTables(ShowTables),
+    /// SHOW PROCESSLIST
+    ShowProcessList,
+    /// SHOW PARTITIONS
+    ShowPartitions(ShowPartitions),
     Exists(ExistsTable),
+    /// KILL QUERY
+    KillQuery(KillQuery),
+    /// CREATE SOURCE
+    CreateSource(CreateSource),
+    /// DROP SOURCE
+    DropSource(DropSource),
+    /// SHOW SOURCES
+    ShowSources,
+    /// CREATE CONTINUOUS QUERY
+    CreateContinuousQuery(CreateContinuousQuery),
+    /// DROP CONTINUOUS QUERY
+    DropContinuousQuery(DropContinuousQuery),
+    /// CREATE USER
+    CreateUser(CreateUser),
+    /// DROP USER
+    DropUser(DropUser),
+    /// GRANT ... ON CATALOG ... TO ...
+    Grant(Grant),
+    /// REVOKE ... ON CATALOG ... FROM ...
+    Revoke(Revoke),
+    /// CREATE FUNCTION
+    CreateFunction(CreateFunction),
+    /// DROP FUNCTION
+    DropFunction(DropFunction),
+}
+
+/// `EXPLAIN (FORMAT JSON) [ANALYZE] [VERBOSE] [WITH SETTINGS] ...`
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExplainJson {
+    /// The `EXPLAIN` statement without the format option
+    pub statement: Box<SqlStatement>,
+    /// Whether `WITH SETTINGS` is used
+    pub with_settings: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -92,6 +157,7 @@ pub enum Partition {
     Random(RandomPartition),
     Hash(HashPartition),
     Key(KeyPartition),
+    Time(TimePartition),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -117,6 +183,16 @@ pub struct KeyPartition {
     pub partition_key: Vec<String>,
 }
 
+/// `PARTITION BY TIME(column, 'interval') PARTITIONS num`, the time buckets of
+/// the interval are assigned to the partitions in turn.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TimePartition {
+    /// The timestamp column.
+    pub column: String,
+    pub interval: ReadableDuration,
+    pub partition_num: u64,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct DropTable {
     /// Table name
@@ -134,12 +210,98 @@ pub struct DescribeTable {
 pub struct AlterModifySetting {
     pub table_name: TableName,
     pub options: Vec<SqlOption>,
+    /// Only report the changes without applying them.
+    pub dry_run: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct AlterAddColumn {
     pub table_name: TableName,
     pub columns: Vec<ColumnDef>,
+    /// Only report the changes without applying them.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlterDropColumn {
+    pub table_name: TableName,
+    pub columns: Vec<String>,
+    /// Only report the changes without applying them.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlterRenameColumn {
+    pub table_name: TableName,
+    pub from: String,
+    pub to: String,
+    /// Only report the changes without applying them.
+    pub dry_run: bool,
+}
+
+/// Change the annotations of a column, the ones not given are kept.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlterModifyColumn {
+    pub table_name: TableName,
+    pub column: String,
+    pub display_name: Option<String>,
+    pub unit: Option<String>,
+    /// Only report the changes without applying them.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlterSwapTable {
+    pub table_name: TableName,
+    /// Table to exchange with
+    pub other: TableName,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlterSchemaSetting {
+    pub schema_name: String,
+    /// Default options of the tables created in the schema, the option with
+    /// an empty value is removed.
+    pub options: Vec<SqlOption>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlterSystemSet {
+    /// Path of the option in the config file, e.g.
+    /// `analytic.sst_meta_cache_cap`
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AnalyzeTable {
+    pub table_name: TableName,
+    pub operation: AnalyzeOperation,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AnalyzeOperation {
+    /// Rebuild the existing histograms of the table
+    Refresh,
+    /// UPDATE HISTOGRAM ON ... [WITH n BUCKETS]
+    UpdateHistogram {
+        columns: Vec<String>,
+        num_buckets: Option<u64>,
+    },
+    /// DROP HISTOGRAM ON ...
+    DropHistogram { columns: Vec<String> },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompactTable {
+    pub table_name: TableName,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ResyncTable {
+    pub table_name: TableName,
+    /// Consumer of the table changes to start over
+    pub consumer: String,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -154,11 +316,121 @@ pub struct ShowCreate {
     pub table_name: TableName,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct ShowPartitions {
+    /// The partitioned table
+    pub table_name: TableName,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ExistsTable {
     pub table_name: TableName,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct KillQuery {
+    /// Id of the query shown by `SHOW PROCESSLIST`
+    pub query_id: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CreateSource {
+    pub if_not_exists: bool,
+    /// Name of the source
+    pub name: String,
+    /// Table to write, same as the topic if not set
+    pub table_name: Option<TableName>,
+    /// Brokers and topic of kafka, e.g. `broker1:9092,broker2:9092/topic`
+    pub uri: String,
+    /// Format of the messages
+    pub format: String,
+    pub options: Vec<SqlOption>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DropSource {
+    pub if_exists: bool,
+    /// Name of the source
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CreateContinuousQuery {
+    pub if_not_exists: bool,
+    /// Name of the continuous query
+    pub name: String,
+    /// Table to write the aggregated rows
+    pub target: TableName,
+    /// Interval of the aggregation windows, e.g. `5m`
+    pub every: String,
+    /// Time to wait for the late rows before aggregating a window
+    pub delay: Option<String>,
+    pub query: Box<Query>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DropContinuousQuery {
+    pub if_exists: bool,
+    /// Name of the continuous query
+    pub name: String,
+}
+
+#[derive(PartialEq, Eq)]
+pub struct CreateUser {
+    pub if_not_exists: bool,
+    /// Name of the user
+    pub name: String,
+    pub password: String,
+}
+
+impl fmt::Debug for CreateUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUser")
+            .field("if_not_exists", &self.if_not_exists)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DropUser {
+    pub if_exists: bool,
+    /// Name of the user
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Grant {
+    /// Role to grant, e.g. `read_only`
+    pub role: String,
+    /// Catalog of the grant, `*` means all the catalogs
+    pub catalog: String,
+    pub user: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Revoke {
+    /// Catalog of the grant, `*` means all the catalogs
+    pub catalog: String,
+    pub user: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CreateFunction {
+    pub if_not_exists: bool,
+    /// Name of the function, which is also the name of the export
+    pub name: String,
+    /// File name of the wasm module in the udf directory
+    pub module: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DropFunction {
+    pub if_exists: bool,
+    /// Name of the function
+    pub name: String,
+}
+
 #[cfg(test)]
 mod tests {
     use sqlparser::ast::Ident;
@@ -15,17 +15,134 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::atomic::AtomicBool;
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicBool, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
 
 #[derive(Debug)]
 pub struct DynamicConfig {
     pub enable_dist_query_push_down: AtomicBool,
+    pub wildcard_limit: RwLock<WildcardLimit>,
+    pub time_range_guard: RwLock<TimeRangeGuard>,
+    pub masking_policies: RwLock<Vec<MaskingPolicy>>,
+    pub row_policies: RwLock<Vec<RowPolicy>>,
 }
 
 impl Default for DynamicConfig {
     fn default() -> Self {
         Self {
             enable_dist_query_push_down: AtomicBool::new(true),
+            wildcard_limit: RwLock::new(WildcardLimit::default()),
+            time_range_guard: RwLock::new(TimeRangeGuard::default()),
+            masking_policies: RwLock::new(Vec::new()),
+            row_policies: RwLock::new(Vec::new()),
         }
     }
 }
+
+/// Action taken when `SELECT *` reads a table with too many columns.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum WildcardAction {
+    /// Read the default columns of the table instead.
+    #[default]
+    DefaultColumns,
+    /// Reject the query.
+    Reject,
+}
+
+/// Limit of the columns read by `SELECT *`, which prevents the queries from
+/// scanning hundreds of columns of the wide tables accidentally. All the
+/// columns can still be read by `SELECT ALL COLUMNS`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WildcardLimit {
+    /// Tables with more columns than it are limited, no limit if zero.
+    pub max_columns: usize,
+    pub action: WildcardAction,
+    /// Columns read by `SELECT *` of the tables, the timestamp and the tag
+    /// columns are read if not configured.
+    pub default_columns: HashMap<String, Vec<String>>,
+}
+
+/// Action taken when a query reads a guarded table without a bounded time
+/// range.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum TimeRangeAction {
+    /// Read the data of the `default_range` only.
+    #[default]
+    DefaultRange,
+    /// Reject the query.
+    Reject,
+}
+
+/// Guard of the time range of the queries, which prevents the ad-hoc queries
+/// from scanning the full history of the large tables accidentally.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TimeRangeGuard {
+    /// Names of the guarded tables in any schema.
+    pub tables: Vec<String>,
+    pub action: TimeRangeAction,
+    /// The queries without a bounded time range read the data of the last
+    /// `default_range` only.
+    pub default_range: ReadableDuration,
+    /// The time range of a query is bounded if it's not longer than
+    /// `max_range`, and the time range without an upper bound ends at now.
+    pub max_range: ReadableDuration,
+}
+
+impl Default for TimeRangeGuard {
+    fn default() -> Self {
+        Self {
+            tables: Vec::new(),
+            action: TimeRangeAction::default(),
+            default_range: ReadableDuration::hours(24),
+            max_range: ReadableDuration::days(7),
+        }
+    }
+}
+
+/// How the values of a masked column are hidden.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum MaskingAction {
+    /// Replace the strings with their md5 digests, so they can still be grouped
+    /// and joined. The values of other types are replaced with null.
+    #[default]
+    Hash,
+    /// Replace the values with null.
+    Null,
+}
+
+/// Masking policy of a column, the values of the column are masked when read
+/// by the requests of the `users`, or by the requests not authenticated.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MaskingPolicy {
+    /// Name of the table in any schema.
+    pub table: String,
+    pub column: String,
+    /// Names of the authenticated users.
+    #[serde(alias = "roles")]
+    pub users: Vec<String>,
+    pub action: MaskingAction,
+}
+
+/// Row policy of a table, only the rows matching the `filter` can be read by
+/// the requests of the `users`. The requests of other users, or the requests
+/// not authenticated, read no rows of a table with the policies.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RowPolicy {
+    /// Name of the table in any schema.
+    pub table: String,
+    /// Names of the authenticated users.
+    #[serde(alias = "roles")]
+    pub users: Vec<String>,
+    /// Sql expression on the columns of the table, and `current_user()` in it
+    /// is replaced by the user of the request, e.g. `tenant = current_user()`.
+    pub filter: String,
+}
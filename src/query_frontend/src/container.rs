@@ -19,6 +19,7 @@
 
 use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
+use common_types::time::Timestamp;
 pub use datafusion::catalog::{ResolvedTableReference, TableReference};
 use datafusion::datasource::TableProvider;
 use partition_table_engine::scan_builder::PartitionedTableScanBuilder;
@@ -33,6 +34,8 @@ pub struct PlannedTable {
     pub schema: String,
     pub table: TableRef,
     pub enable_dist_query_push_down: bool,
+    /// Set if the table is read by `table_snapshot`.
+    pub snapshot_time: Option<Timestamp>,
 }
 
 impl PlannedTable {
@@ -50,7 +53,10 @@ impl PlannedTable {
         } else {
             let builder = NormalTableScanBuilder::new(self.table.clone());
 
-            Arc::new(TableProviderAdapter::new(self.table.clone(), builder))
+            Arc::new(
+                TableProviderAdapter::new(self.table.clone(), builder)
+                    .with_snapshot_time(self.snapshot_time),
+            )
         }
     }
 }
@@ -0,0 +1,182 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Helpers of the continuous queries
+//!
+//! A continuous query is a select statement reading one table, which is run
+//! once for every window and restricted to the rows of the window by a filter
+//! on the timestamp column.
+
+use macros::define_result;
+use snafu::{ensure, ResultExt, Snafu};
+use sqlparser::ast::{
+    BinaryOperator, Expr as SqlExpr, Ident, Query, SetExpr, Statement as SqlStatement,
+    TableFactor, TableWithJoins, Value,
+};
+
+use crate::{ast::Statement, parser::Parser};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to parse continuous query, err:{}", source))]
+    ParseQuery {
+        source: sqlparser::parser::ParserError,
+    },
+
+    #[snafu(display("Invalid continuous query, msg:{}", msg))]
+    InvalidQuery { msg: String },
+}
+
+define_result!(Error);
+
+const QUOTE_CHAR: char = '`';
+
+/// Name of the only table read by the query.
+pub fn source_table(query: &Query) -> Result<String> {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return InvalidQuery {
+            msg: "only select statement is supported",
+        }
+        .fail();
+    };
+    ensure!(
+        query.with.is_none(),
+        InvalidQuery {
+            msg: "with clause is not supported",
+        }
+    );
+
+    match select.from.as_slice() {
+        [TableWithJoins {
+            relation: TableFactor::Table { name, .. },
+            joins,
+        }] if joins.is_empty() => Ok(name.to_string()),
+        _ => InvalidQuery {
+            msg: "query must read exactly one table without joins",
+        }
+        .fail(),
+    }
+}
+
+/// Restrict the query to the rows whose timestamp is in `[start, end)`.
+pub fn restrict_to_window(
+    query: &mut Query,
+    time_column: &str,
+    start: i64,
+    end: i64,
+) -> Result<()> {
+    let SetExpr::Select(select) = query.body.as_mut() else {
+        return InvalidQuery {
+            msg: "only select statement is supported",
+        }
+        .fail();
+    };
+
+    let column = SqlExpr::Identifier(Ident::with_quote(QUOTE_CHAR, time_column));
+    let compare = |op, value: i64| SqlExpr::BinaryOp {
+        left: Box::new(column.clone()),
+        op,
+        right: Box::new(SqlExpr::Value(Value::Number(value.to_string(), false))),
+    };
+    let window = SqlExpr::BinaryOp {
+        left: Box::new(compare(BinaryOperator::GtEq, start)),
+        op: BinaryOperator::And,
+        right: Box::new(compare(BinaryOperator::Lt, end)),
+    };
+    select.selection = Some(match select.selection.take() {
+        Some(selection) => SqlExpr::BinaryOp {
+            left: Box::new(SqlExpr::Nested(Box::new(selection))),
+            op: BinaryOperator::And,
+            right: Box::new(window),
+        },
+        None => window,
+    });
+
+    Ok(())
+}
+
+/// Parse the query and restrict it to the window, returns the sql to run.
+pub fn window_sql(sql: &str, time_column: &str, start: i64, end: i64) -> Result<String> {
+    let mut statements = Parser::parse_sql(sql).context(ParseQuery)?;
+    let mut query = match (statements.len(), statements.pop()) {
+        (1, Some(Statement::Standard(statement))) => match *statement {
+            SqlStatement::Query(query) => query,
+            _ => {
+                return InvalidQuery {
+                    msg: "only select statement is supported",
+                }
+                .fail()
+            }
+        },
+        _ => {
+            return InvalidQuery {
+                msg: "expect exactly one select statement",
+            }
+            .fail()
+        }
+    };
+    restrict_to_window(&mut query, time_column, start, end)?;
+
+    Ok(query.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_sql() {
+        let sql = "SELECT host, avg(value) AS value FROM cpu GROUP BY host";
+        assert_eq!(
+            "SELECT host, avg(value) AS value FROM cpu WHERE `ts` >= 0 AND `ts` < 300000 GROUP BY host",
+            window_sql(sql, "ts", 0, 300000).unwrap()
+        );
+
+        let sql = "SELECT count(*) FROM cpu WHERE host = 'a' OR host = 'b'";
+        assert_eq!(
+            "SELECT count(*) FROM cpu WHERE (host = 'a' OR host = 'b') AND `ts` >= 10 AND `ts` < 20",
+            window_sql(sql, "ts", 10, 20).unwrap()
+        );
+
+        assert!(window_sql("SHOW TABLES", "ts", 0, 1).is_err());
+        assert!(window_sql("SELECT 1; SELECT 2", "ts", 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_source_table() {
+        let parse = |sql: &str| match Parser::parse_sql(sql).unwrap().pop().unwrap() {
+            Statement::Standard(statement) => match *statement {
+                SqlStatement::Query(query) => query,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            "cpu",
+            source_table(&parse("SELECT count(*) FROM cpu")).unwrap()
+        );
+        for sql in [
+            "SELECT count(*) FROM cpu, mem",
+            "SELECT count(*) FROM cpu JOIN mem ON cpu.host = mem.host",
+            "SELECT count(*) FROM (SELECT * FROM cpu)",
+            "SELECT count(*) FROM cpu UNION SELECT count(*) FROM mem",
+        ] {
+            assert!(source_table(&parse(sql)).is_err(), "sql:{sql}");
+        }
+    }
+}
@@ -21,23 +21,29 @@ use std::{sync::Arc, time::Instant};
 
 use cluster::config::SchemaConfig;
 use common_types::request_id::RequestId;
+use datafusion::{error::DataFusionError, logical_expr::LogicalPlan};
 use generic_error::GenericError;
 use horaedbproto::{prometheus::Expr as PromExpr, storage::WriteTableRequest};
 use influxql_parser::statement::Statement as InfluxqlStatement;
 use macros::define_result;
 use prom_remote_api::types::Query as PromRemoteQuery;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
-use sqlparser::ast::{SetExpr, Statement as SqlStatement, TableFactor};
-use table_engine::table;
+use sqlparser::ast::{
+    Expr as SqlExpr, FunctionArg, FunctionArgExpr, SetExpr, Statement as SqlStatement, TableFactor,
+    Value,
+};
+use table_engine::{query_warning::QueryWarnings, table};
 
 use crate::{
     ast::{Statement, TableName},
     config::DynamicConfig,
+    masking,
     parser::Parser,
     plan::Plan,
-    planner::Planner,
+    planner::{Planner, TABLE_SNAPSHOT_FUNC},
     promql::{ColumnNames, Expr, RemoteQueryPlan},
-    provider::MetaProvider,
+    provider::{ContextProviderAdapter, MetaProvider},
+    row_policy,
 };
 
 #[derive(Debug, Snafu)]
@@ -71,6 +77,12 @@ pub enum Error {
 
     #[snafu(display("Failed to build influxql plan, msg:{}", msg))]
     InfluxqlPlan { msg: String },
+
+    #[snafu(display("Failed to mask plan, err:{}", source))]
+    MaskPlan { source: DataFusionError },
+
+    #[snafu(display("Failed to filter rows of plan, err:{}", source))]
+    FilterPlan { source: DataFusionError },
 }
 
 define_result!(Error);
@@ -89,6 +101,12 @@ pub struct Context {
     pub read_parallelism: usize,
     /// Deadline of this request
     pub deadline: Option<Instant>,
+    /// Name of the authenticated user of the request, the columns are masked
+    /// by the masking policies and the rows are filtered by the row policies
+    /// bound to it.
+    pub user: Option<String>,
+    /// Collect the warnings of the planning.
+    pub warnings: QueryWarnings,
 }
 
 impl Context {
@@ -97,6 +115,8 @@ impl Context {
             request_id,
             deadline,
             read_parallelism: table::DEFAULT_READ_PARALLELISM,
+            user: None,
+            warnings: QueryWarnings::default(),
         }
     }
 }
@@ -150,9 +170,11 @@ impl<P: MetaProvider> Frontend<P> {
             ctx.request_id.clone(),
             ctx.read_parallelism,
             self.dyn_config.as_ref(),
-        );
+        )
+        .with_warnings(ctx.warnings.clone());
 
-        planner.statement_to_plan(stmt).context(CreatePlan)
+        let plan = planner.statement_to_plan(stmt).context(CreatePlan)?;
+        self.apply_policies(ctx, plan)
     }
 
     /// Experimental native promql support, not used in production yet.
@@ -166,9 +188,11 @@ impl<P: MetaProvider> Frontend<P> {
             ctx.request_id.clone(),
             ctx.read_parallelism,
             self.dyn_config.as_ref(),
-        );
+        )
+        .with_warnings(ctx.warnings.clone());
 
-        planner.promql_expr_to_plan(expr).context(CreatePlan)
+        let (plan, column_names) = planner.promql_expr_to_plan(expr).context(CreatePlan)?;
+        Ok((self.apply_policies(ctx, plan)?, column_names))
     }
 
     /// Prometheus remote query support
@@ -182,8 +206,12 @@ impl<P: MetaProvider> Frontend<P> {
             ctx.request_id.clone(),
             ctx.read_parallelism,
             self.dyn_config.as_ref(),
-        );
-        planner.remote_prom_req_to_plan(query).context(CreatePlan)
+        )
+        .with_warnings(ctx.warnings.clone());
+        let mut plan = planner.remote_prom_req_to_plan(query).context(CreatePlan)?;
+        plan.plan = self.apply_policies(ctx, plan.plan)?;
+
+        Ok(plan)
     }
 
     pub fn influxql_stmt_to_plan(&self, ctx: &Context, stmt: InfluxqlStatement) -> Result<Plan> {
@@ -192,8 +220,42 @@ impl<P: MetaProvider> Frontend<P> {
             ctx.request_id.clone(),
             ctx.read_parallelism,
             self.dyn_config.as_ref(),
-        );
-        planner.influxql_stmt_to_plan(stmt).context(CreatePlan)
+        )
+        .with_warnings(ctx.warnings.clone());
+        let plan = planner.influxql_stmt_to_plan(stmt).context(CreatePlan)?;
+        self.apply_policies(ctx, plan)
+    }
+
+    /// Mask the columns and filter the rows read by the query plan by the
+    /// policies bound to the user of the request.
+    fn apply_policies(&self, ctx: &Context, plan: Plan) -> Result<Plan> {
+        match plan {
+            Plan::Query(mut plan) => {
+                plan.df_plan = self.apply_policies_to_df_plan(ctx, plan.df_plan)?;
+                Ok(Plan::Query(plan))
+            }
+            plan => Ok(plan),
+        }
+    }
+
+    /// Mask the columns and filter the rows read by the datafusion plan, e.g.
+    /// the plan reading the rows not queried by sql, such as the changes of
+    /// the tables, so such rows are protected by the same policies.
+    pub fn apply_policies_to_df_plan(
+        &self,
+        ctx: &Context,
+        df_plan: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        let user = ctx.user.as_deref();
+        let masking_policies = self.dyn_config.masking_policies.read().unwrap();
+        let df_plan =
+            masking::mask_plan(df_plan, &masking_policies, user).context(MaskPlan)?;
+
+        let row_policies = self.dyn_config.row_policies.read().unwrap();
+        let context_provider =
+            ContextProviderAdapter::new(&self.provider, ctx.read_parallelism, &self.dyn_config);
+        row_policy::filter_plan(df_plan, &row_policies, user, &context_provider)
+            .context(FilterPlan)
     }
 
     pub fn write_req_to_plan(
@@ -207,7 +269,8 @@ impl<P: MetaProvider> Frontend<P> {
             ctx.request_id.clone(),
             ctx.read_parallelism,
             self.dyn_config.as_ref(),
-        );
+        )
+        .with_warnings(ctx.warnings.clone());
 
         planner
             .write_req_to_plan(schema_config, write_table)
@@ -226,10 +289,8 @@ pub fn parse_table_name_with_standard(sql_statement: &SqlStatement) -> Option<St
                     SetExpr::Select(select) => {
                         if select.from.len() != 1 {
                             None
-                        } else if let TableFactor::Table { name, .. } = &select.from[0].relation {
-                            Some(TableName::from(name.clone()).to_string())
                         } else {
-                            None
+                            table_name_of_relation(&select.from[0].relation)
                         }
                     }
                     // TODO: return unsupported error rather than none.
@@ -243,10 +304,8 @@ pub fn parse_table_name_with_standard(sql_statement: &SqlStatement) -> Option<St
             SetExpr::Select(select) => {
                 if select.from.len() != 1 {
                     None
-                } else if let TableFactor::Table { name, .. } = &select.from[0].relation {
-                    Some(TableName::from(name.clone()).to_string())
                 } else {
-                    None
+                    table_name_of_relation(&select.from[0].relation)
                 }
             }
             _ => None,
@@ -255,22 +314,66 @@ pub fn parse_table_name_with_standard(sql_statement: &SqlStatement) -> Option<St
     }
 }
 
+/// Returns the table read by the relation, `table_snapshot('t', ..)` reads
+/// table `t`.
+fn table_name_of_relation(relation: &TableFactor) -> Option<String> {
+    let TableFactor::Table { name, args, .. } = relation else {
+        return None;
+    };
+
+    match args {
+        Some(args)
+            if name.0.len() == 1 && name.0[0].value.eq_ignore_ascii_case(TABLE_SNAPSHOT_FUNC) =>
+        {
+            match args.first() {
+                Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(SqlExpr::Value(
+                    Value::SingleQuotedString(table),
+                )))) => Some(table.clone()),
+                _ => None,
+            }
+        }
+        _ => Some(TableName::from(name.clone()).to_string()),
+    }
+}
+
 pub fn parse_table_name(statements: &StatementVec) -> Option<String> {
     // maybe have empty sql
     if statements.is_empty() {
         return None;
     }
     match &statements[0] {
-        Statement::Standard(s) => parse_table_name_with_standard(s),
+        Statement::Standard(s)
+        | Statement::SelectAllColumns(s)
+        | Statement::ExplainWithSettings(s) => parse_table_name_with_standard(s),
+        Statement::ExplainJson(s) => parse_table_name_with_standard(&s.statement),
         Statement::Create(s) => Some(s.table_name.to_string()),
         Statement::Drop(s) => Some(s.table_name.to_string()),
         Statement::Describe(s) => Some(s.table_name.to_string()),
         Statement::AlterModifySetting(s) => Some(s.table_name.to_string()),
         Statement::AlterAddColumn(s) => Some(s.table_name.to_string()),
+        Statement::AlterDropColumn(s) => Some(s.table_name.to_string()),
+        Statement::AlterRenameColumn(s) => Some(s.table_name.to_string()),
+        Statement::AlterModifyColumn(s) => Some(s.table_name.to_string()),
+        Statement::AlterSwapTable(s) => Some(s.table_name.to_string()),
+        Statement::AnalyzeTable(s) => Some(s.table_name.to_string()),
+        Statement::CompactTable(s) => Some(s.table_name.to_string()),
+        Statement::ResyncTable(s) => Some(s.table_name.to_string()),
+        Statement::AlterSchemaSetting(_) | Statement::AlterSystemSet(_) => None,
         Statement::ShowCreate(s) => Some(s.table_name.to_string()),
         Statement::ShowTables(_s) => None,
         Statement::ShowDatabases => None,
+        Statement::ShowProcessList => None,
+        Statement::ShowPartitions(s) => Some(s.table_name.to_string()),
         Statement::Exists(s) => Some(s.table_name.to_string()),
+        Statement::KillQuery(_) => None,
+        Statement::CreateSource(_) | Statement::DropSource(_) | Statement::ShowSources => None,
+        Statement::CreateContinuousQuery(s) => Some(s.target.to_string()),
+        Statement::DropContinuousQuery(_) => None,
+        Statement::CreateUser(_)
+        | Statement::DropUser(_)
+        | Statement::Grant(_)
+        | Statement::Revoke(_) => None,
+        Statement::CreateFunction(_) | Statement::DropFunction(_) => None,
     }
 }
 
@@ -0,0 +1,156 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Logical node of the gap filling of the time bucketed aggregation, e.g.
+//! `SELECT time_bucket(ts, 'PT1M') AS t, host, avg(v) FROM t GROUP BY t, host
+//! FILL(linear)`.
+
+use std::{
+    any::Any,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use datafusion::{
+    common::DFSchemaRef,
+    logical_expr::{col, Expr, LogicalPlan, UserDefinedLogicalNode},
+};
+
+/// Name of the pseudo function carrying the fill policy in the GROUP BY
+/// clause, which the `FILL(...)` clause is rewritten to by the parser.
+pub const FILL_FUNC: &str = "fill";
+
+/// Name of the function bucketing the timestamps.
+pub const TIME_BUCKET_FUNC: &str = "time_bucket";
+
+/// How to fill the value columns of the missing buckets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillPolicy {
+    /// `FILL(null)`
+    Null,
+    /// `FILL(<number>)`
+    Value(f64),
+    /// `FILL(previous)`, take the values of the previous bucket.
+    Previous,
+    /// `FILL(linear)`, interpolate the numeric values between the previous and
+    /// the next buckets, and the non-numeric values are filled with null.
+    Linear,
+}
+
+impl Hash for FillPolicy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let FillPolicy::Value(v) = self {
+            v.to_bits().hash(state);
+        }
+    }
+}
+
+/// Parse the fixed length period of `time_bucket` into milliseconds, only the
+/// periods of seconds, minutes and hours (e.g. `PT30S`, `PT1M`, `PT2H`) are
+/// supported.
+pub fn parse_bucket_interval(period: &str) -> Option<i64> {
+    let number = period.strip_prefix("PT")?;
+    let (number, unit_ms) = match number.chars().last()? {
+        'S' => (&number[..number.len() - 1], 1000),
+        'M' => (&number[..number.len() - 1], 60 * 1000),
+        'H' => (&number[..number.len() - 1], 3600 * 1000),
+        _ => return None,
+    };
+    let number = number.parse::<u16>().ok().filter(|n| *n > 0)?;
+
+    Some(i64::from(number) * unit_ms)
+}
+
+/// Fill the missing time buckets of every series, which is identified by the
+/// values of the `series_columns`, between its first and last buckets.
+#[derive(Hash, PartialEq)]
+pub struct GapFillNode {
+    pub input: LogicalPlan,
+    pub series_columns: Vec<String>,
+    pub time_column: String,
+    /// Interval of the time buckets in milliseconds.
+    pub interval: i64,
+    pub policy: FillPolicy,
+}
+
+impl fmt::Debug for GapFillNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_for_explain(f)
+    }
+}
+
+impl UserDefinedLogicalNode for GapFillNode {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "GapFill"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        self.series_columns
+            .iter()
+            .chain(std::iter::once(&self.time_column))
+            .map(col)
+            .collect()
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GapFill: series_columns={:?}, time_column={}, interval={}, policy={:?}",
+            self.series_columns, self.time_column, self.interval, self.policy
+        )
+    }
+
+    fn from_template(
+        &self,
+        _exprs: &[Expr],
+        inputs: &[LogicalPlan],
+    ) -> Arc<dyn UserDefinedLogicalNode> {
+        Arc::new(GapFillNode {
+            input: inputs[0].clone(),
+            series_columns: self.series_columns.clone(),
+            time_column: self.time_column.clone(),
+            interval: self.interval,
+            policy: self.policy,
+        })
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+
+    fn dyn_eq(&self, other: &dyn UserDefinedLogicalNode) -> bool {
+        match other.as_any().downcast_ref::<Self>() {
+            Some(o) => self == o,
+            None => false,
+        }
+    }
+}
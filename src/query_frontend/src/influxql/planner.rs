@@ -194,6 +194,8 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
                     df_plan,
                     tables,
                     table_name: None,
+                    offload: None,
+                    explain_json: false,
                 }))
             }
         }
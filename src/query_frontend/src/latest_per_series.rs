@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Logical node of the latest row per series query, e.g.
+//! `SELECT LAST_ROW(*) FROM t WHERE ... GROUP BY host, region`.
+
+use std::{
+    any::Any,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use datafusion::{
+    common::DFSchemaRef,
+    logical_expr::{col, Expr, LogicalPlan, UserDefinedLogicalNode},
+};
+
+/// Name of the function selecting the latest row per series.
+pub const LAST_ROW_FUNC: &str = "last_row";
+
+/// Keep only the latest row (by the timestamp column) of every series, which
+/// is identified by the values of the `series_columns`.
+#[derive(Hash, PartialEq)]
+pub struct LatestPerSeriesNode {
+    pub input: LogicalPlan,
+    pub series_columns: Vec<String>,
+    pub timestamp_column: String,
+}
+
+impl fmt::Debug for LatestPerSeriesNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_for_explain(f)
+    }
+}
+
+impl UserDefinedLogicalNode for LatestPerSeriesNode {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "LatestPerSeries"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        self.series_columns
+            .iter()
+            .chain(std::iter::once(&self.timestamp_column))
+            .map(col)
+            .collect()
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "LatestPerSeries: series_columns={:?}, timestamp_column={}",
+            self.series_columns, self.timestamp_column
+        )
+    }
+
+    fn from_template(
+        &self,
+        _exprs: &[Expr],
+        inputs: &[LogicalPlan],
+    ) -> Arc<dyn UserDefinedLogicalNode> {
+        Arc::new(LatestPerSeriesNode {
+            input: inputs[0].clone(),
+            series_columns: self.series_columns.clone(),
+            timestamp_column: self.timestamp_column.clone(),
+        })
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+
+    fn dyn_eq(&self, other: &dyn UserDefinedLogicalNode) -> bool {
+        match other.as_any().downcast_ref::<Self>() {
+            Some(o) => self == o,
+            None => false,
+        }
+    }
+}
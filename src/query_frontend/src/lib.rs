@@ -24,14 +24,21 @@
 pub mod ast;
 pub mod config;
 pub mod container;
+pub mod continuous_query;
 pub mod frontend;
+pub mod gap_fill;
 pub mod influxql;
+pub mod latest_per_series;
 mod logical_optimizer;
+pub mod masking;
 pub mod parser;
 mod partition;
+pub mod pipeline;
 pub mod plan;
 pub mod planner;
 pub mod promql;
 pub mod provider;
+pub mod row_policy;
+pub mod time_range_guard;
 #[cfg(any(test, feature = "test"))]
 pub mod tests;
@@ -27,6 +27,7 @@ use datafusion::{
     optimizer::analyzer::Analyzer,
     prelude::SessionConfig,
 };
+pub(crate) use type_conversion::parse_timestamp_ms;
 use type_conversion::TypeConversion;
 
 pub fn optimize_plan(plan: &LogicalPlan) -> Result<LogicalPlan> {
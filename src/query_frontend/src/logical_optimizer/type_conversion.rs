@@ -193,10 +193,7 @@ impl<'a> TypeRewriter<'a> {
     fn cast_scalar_value(value: &ScalarValue, data_type: &DataType) -> Result<ScalarValue> {
         if let DataType::Timestamp(_, _) = data_type {
             if let ScalarValue::Utf8(Some(v)) = value {
-                return match string_to_timestamp_ms_workaround(v) {
-                    Ok(v) => Ok(v),
-                    _ => string_to_timestamp_ms(v),
-                };
+                return parse_timestamp_ms(v);
             }
         }
 
@@ -292,6 +289,15 @@ impl<'a> TreeNodeRewriter for TypeRewriter<'a> {
     }
 }
 
+/// Parse the timestamp string in local time or with an explicit timezone into
+/// a millisecond timestamp.
+pub(crate) fn parse_timestamp_ms(string: &str) -> Result<ScalarValue> {
+    match string_to_timestamp_ms_workaround(string) {
+        Ok(v) => Ok(v),
+        _ => string_to_timestamp_ms(string),
+    }
+}
+
 fn string_to_timestamp_ms(string: &str) -> Result<ScalarValue> {
     let ts = string_to_timestamp_nanos(string)
         .map(|t| t / 1_000_000)
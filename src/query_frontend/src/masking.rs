@@ -0,0 +1,173 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Masking of the sensitive columns read by the queries of some users.
+
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    error::Result,
+    logical_expr::{cast, expr_fn::md5, Expr, LogicalPlan, Projection},
+    scalar::ScalarValue,
+};
+
+use crate::config::{MaskingAction, MaskingPolicy};
+
+/// Mask the columns read by the `plan` according to the `policies` bound to
+/// the `user`. All the policies are applied if the user is unknown, so the
+/// requests not authenticated never read the unmasked values.
+///
+/// The table scans reading the masked columns are wrapped by a projection
+/// replacing the values of the masked columns, so all the operators above,
+/// including the filters, only see the masked values. The schema of the plan is
+/// kept unchanged.
+pub fn mask_plan(
+    plan: LogicalPlan,
+    policies: &[MaskingPolicy],
+    user: Option<&str>,
+) -> Result<LogicalPlan> {
+    let policies: Vec<_> = policies
+        .iter()
+        .filter(|policy| user.map_or(true, |user| policy.users.iter().any(|v| v == user)))
+        .collect();
+    if policies.is_empty() {
+        return Ok(plan);
+    }
+
+    plan.transform_up(&|plan| {
+        let LogicalPlan::TableScan(scan) = &plan else {
+            return Ok(Transformed::No(plan));
+        };
+        let table = scan.table_name.table();
+        let find_policy = |column: &str| {
+            policies
+                .iter()
+                .find(|policy| policy.table == table && policy.column == column)
+        };
+        let fields = scan.projected_schema.fields();
+        if !fields
+            .iter()
+            .any(|field| find_policy(field.name()).is_some())
+        {
+            return Ok(Transformed::No(plan));
+        }
+
+        let exprs = fields
+            .iter()
+            .map(|field| {
+                let column = Expr::Column(field.qualified_column());
+                let expr = match find_policy(field.name()) {
+                    Some(policy) => mask_expr(column, policy.action, field.data_type())?
+                        .alias_qualified(field.qualifier().cloned(), field.name()),
+                    None => column,
+                };
+                Ok(expr)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let projection = Projection::try_new(exprs, Arc::new(plan))?;
+
+        Ok(Transformed::Yes(LogicalPlan::Projection(projection)))
+    })
+}
+
+/// Build the expr masking the `column`, the type of the masked values is the
+/// same as the column.
+fn mask_expr(column: Expr, action: MaskingAction, data_type: &DataType) -> Result<Expr> {
+    let expr = match action {
+        MaskingAction::Hash if is_string(data_type) => {
+            cast(md5(cast(column, DataType::Utf8)), data_type.clone())
+        }
+        // Only the strings can be hashed without changing the type.
+        MaskingAction::Hash | MaskingAction::Null => {
+            Expr::Literal(ScalarValue::try_from(data_type)?)
+        }
+    };
+
+    Ok(expr)
+}
+
+fn is_string(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Utf8 | DataType::LargeUtf8 => true,
+        DataType::Dictionary(_, value_type) => is_string(value_type),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{Field, Schema};
+    use datafusion::logical_expr::LogicalPlanBuilder;
+
+    use super::*;
+
+    fn build_plan() -> LogicalPlan {
+        let schema = Schema::new(vec![
+            Field::new("user_id", DataType::Utf8, true),
+            Field::new("region", DataType::Utf8, true),
+            Field::new("value", DataType::Float64, true),
+        ]);
+        LogicalPlanBuilder::scan_empty(Some("cpu"), &schema, None)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn new_policy(column: &str, action: MaskingAction) -> MaskingPolicy {
+        MaskingPolicy {
+            table: "cpu".to_string(),
+            column: column.to_string(),
+            users: vec!["analyst".to_string()],
+            action,
+        }
+    }
+
+    #[test]
+    fn test_mask_plan() {
+        let policies = vec![
+            new_policy("user_id", MaskingAction::Hash),
+            new_policy("value", MaskingAction::Hash),
+        ];
+
+        let plan = build_plan();
+        let masked = mask_plan(plan.clone(), &policies, Some("analyst")).unwrap();
+        assert_eq!(plan.schema(), masked.schema());
+        let LogicalPlan::Projection(projection) = &masked else {
+            panic!("The scan should be masked by projection, plan:{masked:?}");
+        };
+        let exprs: Vec<_> = projection.expr.iter().map(|v| v.to_string()).collect();
+        assert!(exprs[0].contains("md5"), "exprs:{exprs:?}");
+        assert_eq!("cpu.region", exprs[1]);
+        // The non-string column can't be hashed.
+        assert!(exprs[2].starts_with("NULL"), "exprs:{exprs:?}");
+
+        // Other users are not masked.
+        let masked = mask_plan(plan.clone(), &policies, Some("admin")).unwrap();
+        assert!(matches!(masked, LogicalPlan::TableScan(_)));
+
+        // The unknown user is masked by all the policies.
+        let masked = mask_plan(plan.clone(), &policies, None).unwrap();
+        assert!(matches!(masked, LogicalPlan::Projection(_)));
+
+        // The scan not reading the masked columns is not masked.
+        let policies = vec![new_policy("host", MaskingAction::Null)];
+        let masked = mask_plan(plan, &policies, Some("analyst")).unwrap();
+        assert!(matches!(masked, LogicalPlan::TableScan(_)));
+    }
+}
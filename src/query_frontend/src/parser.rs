@@ -25,21 +25,28 @@ use paste::paste;
 use sqlparser::{
     ast::{
         ColumnDef, ColumnOption, ColumnOptionDef, DataType, Expr, Ident, ObjectName, SetExpr,
-        Statement as SqlStatement, TableConstraint, TableFactor, TableWithJoins,
+        Statement as SqlStatement, TableConstraint, TableFactor, TableWithJoins, Value,
     },
     dialect::{keywords::Keyword, Dialect, MySqlDialect},
     parser::{IsOptional::Mandatory, Parser as SqlParser, ParserError},
-    tokenizer::{Token, Tokenizer},
+    tokenizer::{Token, Tokenizer, Whitespace},
 };
 use table_engine::ANALYTIC_ENGINE_TYPE;
+use time_ext::ReadableDuration;
 
 use crate::{
     ast::{
-        AlterAddColumn, AlterModifySetting, CreateTable, DescribeTable, DropTable, ExistsTable,
-        HashPartition, KeyPartition, Partition, RandomPartition, ShowCreate, ShowCreateObject,
-        ShowTables, Statement,
+        AlterAddColumn, AlterDropColumn, AlterModifyColumn, AlterModifySetting, AlterRenameColumn,
+        AlterSchemaSetting, AlterSwapTable, AlterSystemSet, AnalyzeOperation, AnalyzeTable,
+        CompactTable, CreateContinuousQuery, CreateFunction, CreateSource, CreateTable, CreateUser,
+        DescribeTable, DropContinuousQuery, DropFunction, DropSource, DropTable, DropUser,
+        ExistsTable, ExplainJson, Grant, HashPartition, KeyPartition, KillQuery, Partition,
+        RandomPartition, ResyncTable, Revoke, ShowCreate, ShowCreateObject, ShowPartitions,
+        ShowTables, Statement, TimePartition,
     },
+    gap_fill::FILL_FUNC,
     partition,
+    planner::TABLE_SNAPSHOT_FUNC,
 };
 
 define_result!(ParserError);
@@ -57,6 +64,27 @@ const DICTIONARY: &str = "DICTIONARY";
 const UNSIGN: &str = "UNSIGN";
 const MODIFY: &str = "MODIFY";
 const SETTING: &str = "SETTING";
+const REGEXP: &str = "REGEXP";
+const RLIKE: &str = "RLIKE";
+const SYSTEM_TIME: &str = "SYSTEM_TIME";
+const COLUMNS: &str = "COLUMNS";
+const SETTINGS: &str = "SETTINGS";
+const DRY: &str = "DRY";
+const RUN: &str = "RUN";
+const SYSTEM: &str = "SYSTEM";
+const HISTOGRAM: &str = "HISTOGRAM";
+const BUCKETS: &str = "BUCKETS";
+const SWAP: &str = "SWAP";
+const COMPACT: &str = "COMPACT";
+const RESYNC: &str = "RESYNC";
+const CONSUMER: &str = "CONSUMER";
+const DISPLAY_NAME: &str = "DISPLAY_NAME";
+const UNIT: &str = "UNIT";
+const USER: &str = "USER";
+const IDENTIFIED: &str = "IDENTIFIED";
+const CATALOG: &str = "CATALOG";
+const GRANT: &str = "GRANT";
+const REVOKE: &str = "REVOKE";
 
 macro_rules! is_custom_column {
     ($name: ident) => {
@@ -92,6 +120,29 @@ pub fn get_column_comment(opt: &ColumnOption) -> Option<String> {
     None
 }
 
+/// Get the display name from the [`ColumnOption`] if it is a display name
+/// option.
+pub fn get_column_display_name(opt: &ColumnOption) -> Option<String> {
+    get_column_annotation(opt, DISPLAY_NAME)
+}
+
+/// Get the unit from the [`ColumnOption`] if it is a unit option.
+pub fn get_column_unit(opt: &ColumnOption) -> Option<String> {
+    get_column_annotation(opt, UNIT)
+}
+
+fn get_column_annotation(opt: &ColumnOption, name: &str) -> Option<String> {
+    if let ColumnOption::DialectSpecific(tokens) = opt {
+        if let [Token::Word(word), Token::SingleQuotedString(value)] = &tokens[..] {
+            if word.value == name {
+                return Some(value.clone());
+            }
+        }
+    }
+
+    None
+}
+
 /// Get the default value expr from  [`ColumnOption`] if it is a default-value
 /// option.
 pub fn get_default_value(opt: &ColumnOption) -> Option<Expr> {
@@ -118,21 +169,330 @@ pub fn is_timestamp_key_constraint(constraint: &TableConstraint) -> bool {
     false
 }
 
+/// Rewrite the MySQL style regex operators to the PostgreSQL style ones which
+/// are supported by the native parser:
+/// - `expr REGEXP pattern` (or `RLIKE`) => `expr ~ pattern`
+/// - `expr NOT REGEXP pattern` (or `NOT RLIKE`) => `expr !~ pattern`
+fn rewrite_regexp_tokens(tokens: Vec<Token>) -> Vec<Token> {
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let is_regexp = match &token {
+            Token::Word(w) if w.quote_style.is_none() => {
+                let value = w.value.to_uppercase();
+                value == REGEXP || value == RLIKE
+            }
+            _ => false,
+        };
+        if !is_regexp {
+            rewritten.push(token);
+            continue;
+        }
+
+        // Find the previous non-whitespace token to check whether it is `NOT`.
+        let prev_idx = rewritten
+            .iter()
+            .rposition(|t| !matches!(t, Token::Whitespace(_)));
+        let negated = match prev_idx.map(|idx| &rewritten[idx]) {
+            Some(Token::Word(w)) => w.keyword == Keyword::NOT,
+            _ => false,
+        };
+        if negated {
+            rewritten.truncate(prev_idx.unwrap());
+            rewritten.push(Token::ExclamationMarkTilde);
+        } else {
+            rewritten.push(Token::Tilde);
+        }
+    }
+
+    rewritten
+}
+
+/// Rewrite the time travel clause to the `table_snapshot` table function:
+/// - `t FOR SYSTEM_TIME AS OF <time>` => `table_snapshot('t', <time>)`
+fn rewrite_system_time_tokens(tokens: Vec<Token>) -> Vec<Token> {
+    let next_non_whitespace = |from: usize| {
+        (from..tokens.len()).find(|idx| !matches!(tokens[*idx], Token::Whitespace(_)))
+    };
+    let is_word = |idx: Option<usize>, expected: &str| match idx.map(|idx| &tokens[idx]) {
+        Some(Token::Word(w)) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected),
+        _ => false,
+    };
+
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        if !is_word(Some(idx), "FOR") {
+            rewritten.push(tokens[idx].clone());
+            idx += 1;
+            continue;
+        }
+
+        // Match `FOR SYSTEM_TIME AS OF <time>`.
+        let system_time_idx = next_non_whitespace(idx + 1);
+        let as_idx = system_time_idx.and_then(|i| next_non_whitespace(i + 1));
+        let of_idx = as_idx.and_then(|i| next_non_whitespace(i + 1));
+        let time_idx = of_idx.and_then(|i| next_non_whitespace(i + 1));
+        let is_time_travel = is_word(system_time_idx, SYSTEM_TIME)
+            && is_word(as_idx, "AS")
+            && is_word(of_idx, "OF")
+            && matches!(
+                time_idx.map(|i| &tokens[i]),
+                Some(Token::SingleQuotedString(_) | Token::Number(_, _))
+            );
+        if !is_time_travel {
+            rewritten.push(tokens[idx].clone());
+            idx += 1;
+            continue;
+        }
+
+        // Take the table name (`a.b.c`) before the clause.
+        let name_end = rewritten
+            .iter()
+            .rposition(|t| !matches!(t, Token::Whitespace(_)))
+            .map_or(0, |i| i + 1);
+        let mut name_start = name_end;
+        while name_start > 0 {
+            let Token::Word(_) = &rewritten[name_start - 1] else {
+                break;
+            };
+            name_start -= 1;
+            if name_start > 0 && rewritten[name_start - 1] == Token::Period {
+                name_start -= 1;
+            } else {
+                break;
+            }
+        }
+        if name_start == name_end || rewritten[name_start] == Token::Period {
+            // No table before the clause, leave it to the parser to report.
+            rewritten.push(tokens[idx].clone());
+            idx += 1;
+            continue;
+        }
+
+        let table_name = rewritten[name_start..name_end]
+            .iter()
+            .filter_map(|t| match t {
+                Token::Word(w) => Some(w.value.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        rewritten.truncate(name_start);
+        let time_idx = time_idx.unwrap();
+        rewritten.extend([
+            Token::make_word(TABLE_SNAPSHOT_FUNC, None),
+            Token::LParen,
+            Token::SingleQuotedString(table_name),
+            Token::Comma,
+            tokens[time_idx].clone(),
+            Token::RParen,
+        ]);
+        idx = time_idx + 1;
+    }
+
+    rewritten
+}
+
+/// Rewrite the `FILL(<policy>)` clause following the GROUP BY clause to a
+/// pseudo function in the GROUP BY clause, which is handled by the planner:
+/// - `GROUP BY t, host FILL(linear)` => `GROUP BY t, host, fill(linear)`
+fn rewrite_fill_tokens(tokens: Vec<Token>) -> Vec<Token> {
+    let next_non_whitespace = |from: usize| {
+        (from..tokens.len()).find(|idx| !matches!(tokens[*idx], Token::Whitespace(_)))
+    };
+    let is_word = |idx: Option<usize>, expected: &str| match idx.map(|idx| &tokens[idx]) {
+        Some(Token::Word(w)) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected),
+        _ => false,
+    };
+
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut in_group_by = false;
+    // Depth of the parentheses inside the GROUP BY clause.
+    let mut depth = 0;
+    for idx in 0..tokens.len() {
+        match &tokens[idx] {
+            Token::Word(w) if w.keyword == Keyword::GROUP => {
+                in_group_by = is_word(next_non_whitespace(idx + 1), "BY");
+                depth = 0;
+            }
+            Token::Word(w)
+                if matches!(
+                    w.keyword,
+                    Keyword::HAVING | Keyword::ORDER | Keyword::LIMIT | Keyword::UNION
+                ) =>
+            {
+                in_group_by = false;
+            }
+            Token::LParen => depth += 1,
+            Token::RParen if depth > 0 => depth -= 1,
+            // The end of the subquery containing the GROUP BY clause.
+            Token::RParen | Token::SemiColon => in_group_by = false,
+            _ => {}
+        }
+
+        let is_fill = in_group_by
+            && depth == 0
+            && is_word(Some(idx), FILL_FUNC)
+            && matches!(
+                next_non_whitespace(idx + 1).map(|i| &tokens[i]),
+                Some(Token::LParen)
+            );
+        if is_fill {
+            let prev = rewritten
+                .iter()
+                .rev()
+                .find(|t| !matches!(t, Token::Whitespace(_)));
+            if !matches!(prev, Some(Token::Comma)) {
+                rewritten.push(Token::Comma);
+            }
+            in_group_by = false;
+        }
+        rewritten.push(tokens[idx].clone());
+    }
+
+    rewritten
+}
+
+/// Rewrite `SELECT ALL COLUMNS` to `SELECT *`, and whether it's rewritten is
+/// returned, so the wildcard can be expanded to all the columns of the wide
+/// tables.
+fn rewrite_all_columns_tokens(tokens: Vec<Token>) -> (Vec<Token>, bool) {
+    let next_non_whitespace = |from: usize| {
+        (from..tokens.len()).find(|idx| !matches!(tokens[*idx], Token::Whitespace(_)))
+    };
+    let is_word = |idx: Option<usize>, expected: &str| match idx.map(|idx| &tokens[idx]) {
+        Some(Token::Word(w)) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected),
+        _ => false,
+    };
+
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut all_columns = false;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        rewritten.push(tokens[idx].clone());
+        if is_word(Some(idx), "SELECT") {
+            let all_idx = next_non_whitespace(idx + 1);
+            let columns_idx = all_idx.and_then(|i| next_non_whitespace(i + 1));
+            if is_word(all_idx, "ALL") && is_word(columns_idx, COLUMNS) {
+                all_columns = true;
+                rewritten.extend([Token::Whitespace(Whitespace::Space), Token::Mul]);
+                idx = columns_idx.unwrap() + 1;
+                continue;
+            }
+        }
+        idx += 1;
+    }
+
+    (rewritten, all_columns)
+}
+
+/// Remove the `WITH SETTINGS` following `EXPLAIN [ANALYZE] [VERBOSE]`, and
+/// whether it's removed is returned, so the effective settings of the query
+/// are explained too.
+fn rewrite_explain_settings_tokens(tokens: Vec<Token>) -> (Vec<Token>, bool) {
+    let next_non_whitespace = |from: usize| {
+        (from..tokens.len()).find(|idx| !matches!(tokens[*idx], Token::Whitespace(_)))
+    };
+    let is_word = |idx: Option<usize>, expected: &str| match idx.map(|idx| &tokens[idx]) {
+        Some(Token::Word(w)) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected),
+        _ => false,
+    };
+
+    let explain_idx = next_non_whitespace(0);
+    if !is_word(explain_idx, "EXPLAIN") {
+        return (tokens, false);
+    }
+    let mut with_idx = explain_idx.and_then(|i| next_non_whitespace(i + 1));
+    for option in ["ANALYZE", "VERBOSE"] {
+        if is_word(with_idx, option) {
+            with_idx = with_idx.and_then(|i| next_non_whitespace(i + 1));
+        }
+    }
+    let settings_idx = with_idx.and_then(|i| next_non_whitespace(i + 1));
+    if !(is_word(with_idx, "WITH") && is_word(settings_idx, SETTINGS)) {
+        return (tokens, false);
+    }
+
+    let (with_idx, settings_idx) = (with_idx.unwrap(), settings_idx.unwrap());
+    let rewritten = tokens
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !(with_idx..=settings_idx).contains(idx))
+        .map(|(_, token)| token.clone())
+        .collect();
+
+    (rewritten, true)
+}
+
+/// Remove the `(FORMAT JSON)` following `EXPLAIN`, and whether it's removed
+/// is returned, so the plan is explained as a json tree.
+fn rewrite_explain_format_tokens(tokens: Vec<Token>) -> (Vec<Token>, bool) {
+    let next_non_whitespace = |from: usize| {
+        (from..tokens.len()).find(|idx| !matches!(tokens[*idx], Token::Whitespace(_)))
+    };
+    let is_word = |idx: Option<usize>, expected: &str| match idx.map(|idx| &tokens[idx]) {
+        Some(Token::Word(w)) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected),
+        _ => false,
+    };
+    let is_token =
+        |idx: Option<usize>, expected: &Token| idx.map(|idx| &tokens[idx]) == Some(expected);
+
+    let explain_idx = next_non_whitespace(0);
+    if !is_word(explain_idx, "EXPLAIN") {
+        return (tokens, false);
+    }
+    let lparen_idx = explain_idx.and_then(|i| next_non_whitespace(i + 1));
+    let format_idx = lparen_idx.and_then(|i| next_non_whitespace(i + 1));
+    let json_idx = format_idx.and_then(|i| next_non_whitespace(i + 1));
+    let rparen_idx = json_idx.and_then(|i| next_non_whitespace(i + 1));
+    if !(is_token(lparen_idx, &Token::LParen)
+        && is_word(format_idx, "FORMAT")
+        && is_word(json_idx, "JSON")
+        && is_token(rparen_idx, &Token::RParen))
+    {
+        return (tokens, false);
+    }
+
+    let (lparen_idx, rparen_idx) = (lparen_idx.unwrap(), rparen_idx.unwrap());
+    let rewritten = tokens
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !(lparen_idx..=rparen_idx).contains(idx))
+        .map(|(_, token)| token.clone())
+        .collect();
+
+    (rewritten, true)
+}
+
 /// SQL Parser with horaedb dialect support
 pub struct Parser<'a> {
     parser: SqlParser<'a>,
+    /// Whether `SELECT ALL COLUMNS` is used.
+    all_columns: bool,
+    /// Whether `EXPLAIN ... WITH SETTINGS` is used.
+    with_settings: bool,
+    /// Whether `EXPLAIN (FORMAT JSON)` is used.
+    format_json: bool,
 }
 
 impl<'a> Parser<'a> {
     // Parse the specified tokens with dialect
     fn new_with_dialect(sql: &str, dialect: &'a dyn Dialect) -> Result<Self> {
         let mut tokenizer = Tokenizer::new(dialect, sql);
-        let tokens = tokenizer.tokenize()?;
+        let tokens = rewrite_regexp_tokens(tokenizer.tokenize()?);
+        let tokens = rewrite_system_time_tokens(tokens);
+        let tokens = rewrite_fill_tokens(tokens);
+        let (tokens, all_columns) = rewrite_all_columns_tokens(tokens);
+        let (tokens, format_json) = rewrite_explain_format_tokens(tokens);
+        let (tokens, with_settings) = rewrite_explain_settings_tokens(tokens);
 
         let parser = SqlParser::new(dialect);
 
         Ok(Parser {
             parser: parser.with_tokens(tokens),
+            all_columns,
+            with_settings,
+            format_json,
         })
     }
 
@@ -204,10 +564,47 @@ impl<'a> Parser<'a> {
                         self.parser.next_token();
                         self.parse_exists()
                     }
+                    Keyword::KILL => {
+                        self.parser.next_token();
+                        self.parse_kill()
+                    }
+                    Keyword::ANALYZE => {
+                        self.parser.next_token();
+                        self.parse_analyze()
+                    }
+                    _ if w.value.eq_ignore_ascii_case(COMPACT) => {
+                        self.parser.next_token();
+                        self.parse_compact()
+                    }
+                    _ if w.value.eq_ignore_ascii_case(RESYNC) => {
+                        self.parser.next_token();
+                        self.parse_resync()
+                    }
+                    _ if w.value.eq_ignore_ascii_case(GRANT) => {
+                        self.parser.next_token();
+                        self.parse_grant()
+                    }
+                    _ if w.value.eq_ignore_ascii_case(REVOKE) => {
+                        self.parser.next_token();
+                        self.parse_revoke()
+                    }
                     _ => {
                         // use the native parser
                         let mut statement = self.parser.parse_statement()?;
                         maybe_normalize_table_name(&mut statement);
+                        if self.all_columns && matches!(statement, SqlStatement::Query(_)) {
+                            return Ok(Statement::SelectAllColumns(Box::new(statement)));
+                        }
+                        let is_explain = matches!(statement, SqlStatement::Explain { .. });
+                        if self.format_json && is_explain {
+                            return Ok(Statement::ExplainJson(ExplainJson {
+                                statement: Box::new(statement),
+                                with_settings: self.with_settings,
+                            }));
+                        }
+                        if self.with_settings && is_explain {
+                            return Ok(Statement::ExplainWithSettings(Box::new(statement)));
+                        }
                         Ok(Statement::Standard(Box::new(statement)))
                     }
                 }
@@ -222,6 +619,11 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_alter(&mut self) -> Result<Statement> {
+        // example: ALTER SYSTEM SET analytic.sst_meta_cache_cap = 2000
+        if self.consume_token(SYSTEM) {
+            return self.parse_alter_system_set();
+        }
+
         let nth1_token = self.parser.peek_token().token;
         let nth2_token = self.parser.peek_nth_token(2).token;
         let nth3_token = self.parser.peek_nth_token(3).token;
@@ -244,10 +646,121 @@ impl<'a> Parser<'a> {
             {
                 return self.parse_alter_add_column();
             }
+            // examples:
+            // ALTER TABLE test_table DROP COLUMN col_17
+            // ALTER TABLE test_table DROP COLUMN (col_18, col_19)
+            if let (Keyword::TABLE, Keyword::DROP, Keyword::COLUMN) =
+                (nth1_word.keyword, nth2_word.keyword, nth3_word.keyword)
+            {
+                return self.parse_alter_drop_column();
+            }
+            // example: ALTER TABLE test_table RENAME COLUMN col_17 TO col_20
+            if let (Keyword::TABLE, Keyword::RENAME, Keyword::COLUMN) =
+                (nth1_word.keyword, nth2_word.keyword, nth3_word.keyword)
+            {
+                return self.parse_alter_rename_column();
+            }
+            // example: ALTER TABLE test_table MODIFY COLUMN col_17 UNIT 'bytes'
+            if let (Keyword::TABLE, MODIFY, Keyword::COLUMN) = (
+                nth1_word.keyword,
+                nth2_word.value.to_uppercase().as_str(),
+                nth3_word.keyword,
+            ) {
+                return self.parse_alter_modify_column();
+            }
+            // example: ALTER TABLE test_table SWAP WITH test_table_backfill
+            if let (Keyword::TABLE, SWAP, Keyword::WITH) = (
+                nth1_word.keyword,
+                nth2_word.value.to_uppercase().as_str(),
+                nth3_word.keyword,
+            ) {
+                return self.parse_alter_swap_table();
+            }
+            // example: ALTER SCHEMA public MODIFY SETTING ttl='8d'
+            if let (Keyword::SCHEMA | Keyword::DATABASE, MODIFY) =
+                (nth1_word.keyword, nth3_word.value.to_uppercase().as_str())
+            {
+                return self.parse_alter_schema_setting();
+            }
         }
         Ok(Statement::Standard(Box::new(self.parser.parse_alter()?)))
     }
 
+    // examples:
+    // ANALYZE TABLE t
+    // ANALYZE TABLE t UPDATE HISTOGRAM ON c1, c2 WITH 32 BUCKETS
+    // ANALYZE TABLE t DROP HISTOGRAM ON c1
+    pub fn parse_analyze(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+        let operation = if self.parser.parse_keyword(Keyword::UPDATE) {
+            let columns = self.parse_histogram_columns()?;
+            let num_buckets = if self.parser.parse_keyword(Keyword::WITH) {
+                let num_buckets = self.parser.parse_literal_uint()?;
+                if !self.consume_token(BUCKETS) {
+                    return self.expected(BUCKETS, self.parser.peek_token().token);
+                }
+                Some(num_buckets)
+            } else {
+                None
+            };
+            AnalyzeOperation::UpdateHistogram {
+                columns,
+                num_buckets,
+            }
+        } else if self.parser.parse_keyword(Keyword::DROP) {
+            AnalyzeOperation::DropHistogram {
+                columns: self.parse_histogram_columns()?,
+            }
+        } else {
+            AnalyzeOperation::Refresh
+        };
+
+        Ok(Statement::AnalyzeTable(AnalyzeTable {
+            table_name,
+            operation,
+        }))
+    }
+
+    // example: COMPACT TABLE t
+    pub fn parse_compact(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+
+        Ok(Statement::CompactTable(CompactTable { table_name }))
+    }
+
+    // example: RESYNC TABLE t FOR CONSUMER 'replica'
+    pub fn parse_resync(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+        self.parser.expect_keyword(Keyword::FOR)?;
+        if !self.consume_token(CONSUMER) {
+            return self.expected(CONSUMER, self.parser.peek_token().token);
+        }
+        let consumer = self.parser.parse_literal_string()?;
+
+        Ok(Statement::ResyncTable(ResyncTable {
+            table_name,
+            consumer,
+        }))
+    }
+
+    fn parse_histogram_columns(&mut self) -> Result<Vec<String>> {
+        if !self.consume_token(HISTOGRAM) {
+            return self.expected(HISTOGRAM, self.parser.peek_token().token);
+        }
+        self.parser.expect_keyword(Keyword::ON)?;
+        let columns = self
+            .parser
+            .parse_comma_separated(SqlParser::parse_identifier)?
+            .into_iter()
+            .map(|ident| ident.value)
+            .collect();
+
+        Ok(columns)
+    }
+
     pub fn parse_show(&mut self) -> Result<Statement> {
         if self.consume_token("TABLES") {
             Ok(self.parse_show_tables()?)
@@ -255,8 +768,18 @@ impl<'a> Parser<'a> {
             Ok(Statement::ShowDatabases)
         } else if self.consume_token("CREATE") {
             Ok(self.parse_show_create()?)
+        } else if self.consume_token("PROCESSLIST") {
+            Ok(Statement::ShowProcessList)
+        } else if self.consume_token("SOURCES") {
+            Ok(Statement::ShowSources)
+        } else if self.consume_token("PARTITIONS") {
+            let table_name = self.parser.parse_object_name()?.into();
+            Ok(Statement::ShowPartitions(ShowPartitions { table_name }))
         } else {
-            self.expected("create/tables/databases", self.parser.peek_token().token)
+            self.expected(
+                "create/tables/databases/processlist/sources/partitions",
+                self.parser.peek_token().token,
+            )
         }
     }
 
@@ -298,12 +821,97 @@ impl<'a> Parser<'a> {
             let column_def = self.parse_column_def()?;
             columns.push(column_def);
         }
+        let dry_run = self.parse_dry_run()?;
         Ok(Statement::AlterAddColumn(AlterAddColumn {
             table_name,
             columns,
+            dry_run,
+        }))
+    }
+
+    fn parse_alter_drop_column(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+        self.parser
+            .expect_keywords(&[Keyword::DROP, Keyword::COLUMN])?;
+        let columns = if self.parser.consume_token(&Token::LParen) {
+            let columns = self
+                .parser
+                .parse_comma_separated(SqlParser::parse_identifier)?;
+            self.parser.expect_token(&Token::RParen)?;
+            columns
+        } else {
+            vec![self.parser.parse_identifier()?]
+        };
+        let dry_run = self.parse_dry_run()?;
+        Ok(Statement::AlterDropColumn(AlterDropColumn {
+            table_name,
+            columns: columns.into_iter().map(|ident| ident.value).collect(),
+            dry_run,
+        }))
+    }
+
+    fn parse_alter_rename_column(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+        self.parser
+            .expect_keywords(&[Keyword::RENAME, Keyword::COLUMN])?;
+        let from = self.parser.parse_identifier()?.value;
+        self.parser.expect_keyword(Keyword::TO)?;
+        let to = self.parser.parse_identifier()?.value;
+        let dry_run = self.parse_dry_run()?;
+        Ok(Statement::AlterRenameColumn(AlterRenameColumn {
+            table_name,
+            from,
+            to,
+            dry_run,
+        }))
+    }
+
+    fn parse_alter_modify_column(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+        if !self.consume_token(MODIFY) {
+            return self.expected(MODIFY, self.parser.peek_token().token);
+        }
+        self.parser.expect_keyword(Keyword::COLUMN)?;
+        let column = self.parser.parse_identifier()?.value;
+
+        let (mut display_name, mut unit) = (None, None);
+        loop {
+            if self.consume_token(DISPLAY_NAME) {
+                display_name = Some(self.parser.parse_literal_string()?);
+            } else if self.consume_token(UNIT) {
+                unit = Some(self.parser.parse_literal_string()?);
+            } else {
+                break;
+            }
+        }
+        if display_name.is_none() && unit.is_none() {
+            return self.expected("DISPLAY_NAME or UNIT", self.parser.peek_token().token);
+        }
+
+        let dry_run = self.parse_dry_run()?;
+        Ok(Statement::AlterModifyColumn(AlterModifyColumn {
+            table_name,
+            column,
+            display_name,
+            unit,
+            dry_run,
         }))
     }
 
+    fn parse_alter_swap_table(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?.into();
+        if !self.consume_token(SWAP) {
+            return self.expected(SWAP, self.parser.peek_token().token);
+        }
+        self.parser.expect_keyword(Keyword::WITH)?;
+        let other = self.parser.parse_object_name()?.into();
+        Ok(Statement::AlterSwapTable(AlterSwapTable { table_name, other }))
+    }
+
     fn parse_alter_modify_setting(&mut self) -> Result<Statement> {
         self.parser.expect_keyword(Keyword::TABLE)?;
         let table_name = self.parser.parse_object_name()?.into();
@@ -311,15 +919,65 @@ impl<'a> Parser<'a> {
             let options = self
                 .parser
                 .parse_comma_separated(SqlParser::parse_sql_option)?;
+            let dry_run = self.parse_dry_run()?;
             Ok(Statement::AlterModifySetting(AlterModifySetting {
                 table_name,
                 options,
+                dry_run,
             }))
         } else {
             unreachable!()
         }
     }
 
+    fn parse_alter_schema_setting(&mut self) -> Result<Statement> {
+        self.parser
+            .expect_one_of_keywords(&[Keyword::SCHEMA, Keyword::DATABASE])?;
+        let schema_name = self.parser.parse_identifier()?.value;
+        if !(self.consume_token(MODIFY) && self.consume_token(SETTING)) {
+            return self.expected(SETTING, self.parser.peek_token().token);
+        }
+        let options = self
+            .parser
+            .parse_comma_separated(SqlParser::parse_sql_option)?;
+        Ok(Statement::AlterSchemaSetting(AlterSchemaSetting {
+            schema_name,
+            options,
+        }))
+    }
+
+    fn parse_alter_system_set(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::SET)?;
+        let key = self
+            .parser
+            .parse_object_name()?
+            .0
+            .into_iter()
+            .map(|ident| ident.value)
+            .collect::<Vec<_>>()
+            .join(".");
+        self.parser.expect_token(&Token::Eq)?;
+        let value = match self.parser.parse_value()? {
+            Value::SingleQuotedString(v) | Value::DoubleQuotedString(v) | Value::Number(v, _) => v,
+            Value::Boolean(v) => v.to_string(),
+            v => return parser_err!(format!("Unsupported value of system option, value:{v}")),
+        };
+
+        Ok(Statement::AlterSystemSet(AlterSystemSet { key, value }))
+    }
+
+    // example: ALTER TABLE t MODIFY SETTING ttl='8d' DRY RUN
+    fn parse_dry_run(&mut self) -> Result<bool> {
+        if !self.consume_token(DRY) {
+            return Ok(false);
+        }
+        if !self.consume_token(RUN) {
+            return self.expected(RUN, self.parser.peek_token().token);
+        }
+
+        Ok(true)
+    }
+
     pub fn parse_describe(&mut self) -> Result<Statement> {
         let _ = self.parser.parse_keyword(Keyword::TABLE);
         let table_name = self.parser.parse_object_name()?.into();
@@ -328,6 +986,19 @@ impl<'a> Parser<'a> {
 
     // Parse a SQL CREATE statement
     pub fn parse_create(&mut self) -> Result<Statement> {
+        if self.consume_token("SOURCE") {
+            return self.parse_create_source();
+        }
+        if self.consume_tokens(&["CONTINUOUS", "QUERY"]) {
+            return self.parse_create_continuous_query();
+        }
+        if self.consume_token(USER) {
+            return self.parse_create_user();
+        }
+        if self.parser.parse_keyword(Keyword::FUNCTION) {
+            return self.parse_create_function();
+        }
+
         self.parser.expect_keyword(Keyword::TABLE)?;
         let if_not_exists =
             self.parser
@@ -372,6 +1043,30 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_drop(&mut self) -> Result<Statement> {
+        if self.consume_token("SOURCE") {
+            let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_identifier()?.value;
+            return Ok(Statement::DropSource(DropSource { if_exists, name }));
+        }
+        if self.consume_tokens(&["CONTINUOUS", "QUERY"]) {
+            let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_identifier()?.value;
+            return Ok(Statement::DropContinuousQuery(DropContinuousQuery {
+                if_exists,
+                name,
+            }));
+        }
+        if self.consume_token(USER) {
+            let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_identifier()?.value;
+            return Ok(Statement::DropUser(DropUser { if_exists, name }));
+        }
+        if self.parser.parse_keyword(Keyword::FUNCTION) {
+            let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_identifier()?.value;
+            return Ok(Statement::DropFunction(DropFunction { if_exists, name }));
+        }
+
         self.parser.expect_keyword(Keyword::TABLE)?;
         let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
         let table_name = self.parser.parse_object_name()?.into();
@@ -384,12 +1079,162 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    // example: CREATE SOURCE cpu_source INTO cpu FROM KAFKA 'broker:9092/cpu'
+    // FORMAT JSON WITH (tags='host')
+    fn parse_create_source(&mut self) -> Result<Statement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_identifier()?.value;
+        let table_name = if self.parser.parse_keyword(Keyword::INTO) {
+            Some(self.parser.parse_object_name()?.into())
+        } else {
+            None
+        };
+
+        self.parser.expect_keyword(Keyword::FROM)?;
+        if !self.consume_token("KAFKA") {
+            return self.expected("KAFKA", self.parser.peek_token().token);
+        }
+        let uri = self.parser.parse_literal_string()?;
+
+        if !self.consume_token("FORMAT") {
+            return self.expected("FORMAT", self.parser.peek_token().token);
+        }
+        let format = self.parser.parse_identifier()?.value.to_uppercase();
+        let options = self.parser.parse_options(Keyword::WITH)?;
+
+        Ok(Statement::CreateSource(CreateSource {
+            if_not_exists,
+            name,
+            table_name,
+            uri,
+            format,
+            options,
+        }))
+    }
+
+    // example: CREATE CONTINUOUS QUERY cpu_5m INTO cpu_5m EVERY '5m' DELAY '1m'
+    // AS SELECT host, avg(value) AS value FROM cpu GROUP BY host
+    fn parse_create_continuous_query(&mut self) -> Result<Statement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_identifier()?.value;
+        self.parser.expect_keyword(Keyword::INTO)?;
+        let target = self.parser.parse_object_name()?.into();
+
+        if !self.consume_token("EVERY") {
+            return self.expected("EVERY", self.parser.peek_token().token);
+        }
+        let every = self.parser.parse_literal_string()?;
+        let delay = if self.consume_token("DELAY") {
+            Some(self.parser.parse_literal_string()?)
+        } else {
+            None
+        };
+
+        self.parser.expect_keyword(Keyword::AS)?;
+        let query = self.parser.parse_query()?;
+
+        Ok(Statement::CreateContinuousQuery(CreateContinuousQuery {
+            if_not_exists,
+            name,
+            target,
+            every,
+            delay,
+            query: Box::new(query),
+        }))
+    }
+
+    // example: CREATE FUNCTION celsius AS 'temperature.wasm'
+    fn parse_create_function(&mut self) -> Result<Statement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_identifier()?.value;
+        self.parser.expect_keyword(Keyword::AS)?;
+        let module = self.parser.parse_literal_string()?;
+
+        Ok(Statement::CreateFunction(CreateFunction {
+            if_not_exists,
+            name,
+            module,
+        }))
+    }
+
+    // example: CREATE USER alice IDENTIFIED BY 'secret'
+    fn parse_create_user(&mut self) -> Result<Statement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_identifier()?.value;
+        if !self.consume_token(IDENTIFIED) {
+            return self.expected(IDENTIFIED, self.parser.peek_token().token);
+        }
+        self.parser.expect_keyword(Keyword::BY)?;
+        let password = self.parser.parse_literal_string()?;
+
+        Ok(Statement::CreateUser(CreateUser {
+            if_not_exists,
+            name,
+            password,
+        }))
+    }
+
+    // example: GRANT read_write ON CATALOG horaedb TO alice
+    fn parse_grant(&mut self) -> Result<Statement> {
+        let role = self.parser.parse_identifier()?.value;
+        let catalog = self.parse_grant_catalog()?;
+        self.parser.expect_keyword(Keyword::TO)?;
+        let user = self.parser.parse_identifier()?.value;
+
+        Ok(Statement::Grant(Grant {
+            role,
+            catalog,
+            user,
+        }))
+    }
+
+    // example: REVOKE ALL ON CATALOG horaedb FROM alice
+    fn parse_revoke(&mut self) -> Result<Statement> {
+        // Only one role is granted on a catalog, so `ALL` is optional.
+        let _ = self.parser.parse_keyword(Keyword::ALL);
+        let catalog = self.parse_grant_catalog()?;
+        self.parser.expect_keyword(Keyword::FROM)?;
+        let user = self.parser.parse_identifier()?.value;
+
+        Ok(Statement::Revoke(Revoke { catalog, user }))
+    }
+
+    // example: ON CATALOG horaedb, or ON CATALOG * for all the catalogs
+    fn parse_grant_catalog(&mut self) -> Result<String> {
+        self.parser.expect_keyword(Keyword::ON)?;
+        if !self.consume_token(CATALOG) {
+            return self.expected(CATALOG, self.parser.peek_token().token);
+        }
+        if self.parser.consume_token(&Token::Mul) {
+            return Ok("*".to_string());
+        }
+
+        Ok(self.parser.parse_identifier()?.value)
+    }
+
     pub fn parse_exists(&mut self) -> Result<Statement> {
         let _ = self.parser.parse_keyword(Keyword::TABLE);
         let table_name = self.parser.parse_object_name()?.into();
         Ok(Statement::Exists(ExistsTable { table_name }))
     }
 
+    // example: KILL QUERY 42
+    pub fn parse_kill(&mut self) -> Result<Statement> {
+        // The `QUERY` is optional like MySQL, and killing the connection is not
+        // supported.
+        let _ = self.consume_token("QUERY");
+        let query_id = self.parser.parse_literal_uint()?;
+        Ok(Statement::KillQuery(KillQuery { query_id }))
+    }
+
     // Copy from sqlparser
     fn parse_columns(&mut self) -> Result<(Vec<ColumnDef>, Vec<TableConstraint>)> {
         let mut columns = vec![];
@@ -560,6 +1405,16 @@ impl<'a> Parser<'a> {
             Ok(Some(ColumnOption::Comment(
                 self.parser.parse_literal_string()?,
             )))
+        } else if self.consume_token(DISPLAY_NAME) {
+            Ok(Some(ColumnOption::DialectSpecific(vec![
+                Token::make_keyword(DISPLAY_NAME),
+                Token::SingleQuotedString(self.parser.parse_literal_string()?),
+            ])))
+        } else if self.consume_token(UNIT) {
+            Ok(Some(ColumnOption::DialectSpecific(vec![
+                Token::make_keyword(UNIT),
+                Token::SingleQuotedString(self.parser.parse_literal_string()?),
+            ])))
         } else {
             Ok(None)
         }
@@ -594,6 +1449,9 @@ impl<'a> Parser<'a> {
         if let Some(hash) = self.maybe_parse_and_check_hash_partition(columns)? {
             return Ok(Some(Partition::Hash(hash)));
         }
+        if let Some(time) = self.maybe_parse_and_check_time_partition(columns)? {
+            return Ok(Some(Partition::Time(time)));
+        }
 
         Ok(None)
     }
@@ -694,6 +1552,54 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn maybe_parse_and_check_time_partition(
+        &mut self,
+        columns: &[ColumnDef],
+    ) -> Result<Option<TimePartition>> {
+        // Parse first part: "PARTITION BY TIME(column, 'interval')".
+        if !self.consume_token("TIME") {
+            return Ok(None);
+        }
+        self.parser.expect_token(&Token::LParen)?;
+        let column = self.parser.parse_identifier()?.value;
+        self.parser.expect_token(&Token::Comma)?;
+        let raw_interval = self.parser.parse_literal_string()?;
+        self.parser.expect_token(&Token::RParen)?;
+
+        // The column must exist and be a timestamp.
+        let is_timestamp = columns.iter().any(|c| {
+            c.name.value == column && matches!(c.data_type, DataType::Timestamp(_, _))
+        });
+        if !is_timestamp {
+            return parser_err!(format!(
+                "time partition column must be an existing timestamp column, column:{column}"
+            ));
+        }
+
+        let interval = match raw_interval.parse::<ReadableDuration>() {
+            Ok(v) if v.as_millis() > 0 => v,
+            Ok(_) => {
+                return parser_err!(format!(
+                    "time partition interval must be at least 1ms, interval:{raw_interval}"
+                ))
+            }
+            Err(e) => {
+                return parser_err!(format!(
+                    "invalid time partition interval:{raw_interval}, err:{e}"
+                ))
+            }
+        };
+
+        let partition_num = self.parse_partition_num()?.unwrap_or(1);
+
+        // Parse successfully.
+        Ok(Some(TimePartition {
+            column,
+            interval,
+            partition_num,
+        }))
+    }
+
     // Parse second part: "PARTITIONS num".
     //
     // If not found, return `Ok(None)`.
@@ -1182,6 +2088,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_column_annotations() {
+        let sql = "CREATE TABLE t(c1 bigint display_name 'Used Memory' unit 'bytes', c2 double)";
+        let statements = Parser::parse_sql(sql).unwrap();
+        let Statement::Create(v) = &statements[0] else {
+            panic!("failed");
+        };
+        let options = &v.columns[0].options;
+        assert_eq!(2, options.len());
+        assert_eq!(
+            Some("Used Memory".to_string()),
+            get_column_display_name(&options[0].option)
+        );
+        assert_eq!(None, get_column_unit(&options[0].option));
+        assert_eq!(
+            Some("bytes".to_string()),
+            get_column_unit(&options[1].option)
+        );
+        assert!(v.columns[1].options.is_empty());
+
+        let sql = "ALTER TABLE t MODIFY COLUMN c1 UNIT 'ms'";
+        let expected = Statement::AlterModifyColumn(AlterModifyColumn {
+            table_name: make_table_name("t"),
+            column: "c1".to_string(),
+            display_name: None,
+            unit: Some("ms".to_string()),
+            dry_run: false,
+        });
+        expect_parse_ok(sql, expected).unwrap();
+
+        let sql = "ALTER TABLE t MODIFY COLUMN c1 DISPLAY_NAME 'Latency' UNIT 'ms' DRY RUN";
+        let expected = Statement::AlterModifyColumn(AlterModifyColumn {
+            table_name: make_table_name("t"),
+            column: "c1".to_string(),
+            display_name: Some("Latency".to_string()),
+            unit: Some("ms".to_string()),
+            dry_run: true,
+        });
+        expect_parse_ok(sql, expected).unwrap();
+
+        assert!(Parser::parse_sql("ALTER TABLE t MODIFY COLUMN c1").is_err());
+        assert!(Parser::parse_sql("ALTER TABLE t MODIFY COLUMN c1 UNIT").is_err());
+    }
+
     #[test]
     fn test_timestamp_key_constraint() {
         let sql = "CREATE TABLE IF NOT EXISTS t(c1 TIMESTAMP, TIMESTAMP key(c1))";
@@ -1255,6 +2205,7 @@ mod tests {
                     make_column_def("c1", DataType::Double),
                     make_column_def("c2", DataType::String(None)),
                 ],
+                dry_run: false,
             });
             expect_parse_ok(sql, expected).unwrap();
         }
@@ -1264,9 +2215,192 @@ mod tests {
             let expected = Statement::AlterAddColumn(AlterAddColumn {
                 table_name: make_table_name("t"),
                 columns: vec![make_column_def("c1", DataType::Double)],
+                dry_run: false,
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_alter_table_drop_rename_column() {
+        {
+            let sql = "ALTER TABLE t DROP COLUMN (c1, c2)";
+            let expected = Statement::AlterDropColumn(AlterDropColumn {
+                table_name: make_table_name("t"),
+                columns: vec!["c1".to_string(), "c2".to_string()],
+                dry_run: false,
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+
+        {
+            let sql = "ALTER TABLE t DROP COLUMN c1 DRY RUN";
+            let expected = Statement::AlterDropColumn(AlterDropColumn {
+                table_name: make_table_name("t"),
+                columns: vec!["c1".to_string()],
+                dry_run: true,
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+
+        {
+            let sql = "ALTER TABLE t RENAME COLUMN c1 TO c2";
+            let expected = Statement::AlterRenameColumn(AlterRenameColumn {
+                table_name: make_table_name("t"),
+                from: "c1".to_string(),
+                to: "c2".to_string(),
+                dry_run: false,
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+
+        {
+            let sql = "ALTER TABLE t SWAP WITH t_backfill";
+            let expected = Statement::AlterSwapTable(AlterSwapTable {
+                table_name: make_table_name("t"),
+                other: make_table_name("t_backfill"),
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+
+        assert!(Parser::parse_sql("ALTER TABLE t RENAME COLUMN c1 c2").is_err());
+        assert!(Parser::parse_sql("ALTER TABLE t DROP COLUMN (c1, c2").is_err());
+        assert!(Parser::parse_sql("ALTER TABLE t SWAP WITH").is_err());
+    }
+
+    #[test]
+    fn test_alter_table_dry_run() {
+        {
+            let sql = "ALTER TABLE t ADD COLUMN c1 DOUBLE DRY RUN";
+            let expected = Statement::AlterAddColumn(AlterAddColumn {
+                table_name: make_table_name("t"),
+                columns: vec![make_column_def("c1", DataType::Double)],
+                dry_run: true,
             });
             expect_parse_ok(sql, expected).unwrap();
         }
+
+        {
+            let sql = "ALTER TABLE t MODIFY SETTING ttl='8d' dry run";
+            let statements = Parser::parse_sql(sql).unwrap();
+            match &statements[0] {
+                Statement::AlterModifySetting(v) => assert!(v.dry_run),
+                _ => panic!("failed"),
+            }
+        }
+
+        assert!(Parser::parse_sql("ALTER TABLE t ADD COLUMN c1 DOUBLE DRY").is_err());
+    }
+
+    #[test]
+    fn test_alter_schema_setting() {
+        for sql in [
+            "ALTER SCHEMA public MODIFY SETTING ttl='7d', compression='ZSTD'",
+            "alter database public modify setting ttl='7d', compression='ZSTD'",
+        ] {
+            let statements = Parser::parse_sql(sql).unwrap();
+            match &statements[0] {
+                Statement::AlterSchemaSetting(v) => {
+                    assert_eq!("public", v.schema_name);
+                    let keys: Vec<_> = v.options.iter().map(|o| o.name.value.as_str()).collect();
+                    assert_eq!(vec!["ttl", "compression"], keys);
+                }
+                _ => panic!("failed"),
+            }
+        }
+
+        assert!(Parser::parse_sql("ALTER SCHEMA public MODIFY ttl='7d'").is_err());
+    }
+
+    #[test]
+    fn test_alter_system_set() {
+        let expected = Statement::AlterSystemSet(AlterSystemSet {
+            key: "analytic.sst_meta_cache_cap".to_string(),
+            value: "2000".to_string(),
+        });
+        expect_parse_ok(
+            "ALTER SYSTEM SET analytic.sst_meta_cache_cap = 2000",
+            expected,
+        )
+        .unwrap();
+
+        let expected = Statement::AlterSystemSet(AlterSystemSet {
+            key: "logger.level".to_string(),
+            value: "debug".to_string(),
+        });
+        expect_parse_ok("alter system set logger.level = 'debug'", expected).unwrap();
+
+        assert!(Parser::parse_sql("ALTER SYSTEM logger.level = 'debug'").is_err());
+        assert!(Parser::parse_sql("ALTER SYSTEM SET logger.level 'debug'").is_err());
+    }
+
+    #[test]
+    fn test_analyze_table() {
+        let expected = Statement::AnalyzeTable(AnalyzeTable {
+            table_name: make_table_name("t"),
+            operation: AnalyzeOperation::Refresh,
+        });
+        expect_parse_ok("ANALYZE TABLE t", expected).unwrap();
+
+        let expected = Statement::AnalyzeTable(AnalyzeTable {
+            table_name: make_table_name("t"),
+            operation: AnalyzeOperation::UpdateHistogram {
+                columns: vec!["c1".to_string(), "c2".to_string()],
+                num_buckets: Some(32),
+            },
+        });
+        expect_parse_ok(
+            "ANALYZE TABLE t UPDATE HISTOGRAM ON c1, c2 WITH 32 BUCKETS",
+            expected,
+        )
+        .unwrap();
+
+        let expected = Statement::AnalyzeTable(AnalyzeTable {
+            table_name: make_table_name("t"),
+            operation: AnalyzeOperation::UpdateHistogram {
+                columns: vec!["c1".to_string()],
+                num_buckets: None,
+            },
+        });
+        expect_parse_ok("analyze table t update histogram on c1", expected).unwrap();
+
+        let expected = Statement::AnalyzeTable(AnalyzeTable {
+            table_name: make_table_name("t"),
+            operation: AnalyzeOperation::DropHistogram {
+                columns: vec!["c1".to_string()],
+            },
+        });
+        expect_parse_ok("ANALYZE TABLE t DROP HISTOGRAM ON c1", expected).unwrap();
+
+        assert!(Parser::parse_sql("ANALYZE TABLE t UPDATE HISTOGRAM c1").is_err());
+        assert!(Parser::parse_sql("ANALYZE TABLE t UPDATE HISTOGRAM ON c1 WITH 32").is_err());
+    }
+
+    #[test]
+    fn test_compact_table() {
+        let expected = Statement::CompactTable(CompactTable {
+            table_name: make_table_name("t"),
+        });
+        expect_parse_ok("COMPACT TABLE t", expected).unwrap();
+
+        let expected = Statement::CompactTable(CompactTable {
+            table_name: make_table_name("t"),
+        });
+        expect_parse_ok("compact table t", expected).unwrap();
+
+        assert!(Parser::parse_sql("COMPACT t").is_err());
+    }
+
+    #[test]
+    fn test_resync_table() {
+        let expected = Statement::ResyncTable(ResyncTable {
+            table_name: make_table_name("t"),
+            consumer: "replica".to_string(),
+        });
+        expect_parse_ok("RESYNC TABLE t FOR CONSUMER 'replica'", expected).unwrap();
+
+        assert!(Parser::parse_sql("RESYNC TABLE t").is_err());
+        assert!(Parser::parse_sql("RESYNC TABLE t FOR CONSUMER replica").is_err());
     }
 
     #[test]
@@ -1279,6 +2413,7 @@ mod tests {
                     make_column_def("c1", DataType::Double),
                     make_tag_column_def("c2", DataType::String(None)),
                 ],
+                dry_run: false,
             });
             expect_parse_ok(sql, expected).unwrap();
         }
@@ -1288,6 +2423,7 @@ mod tests {
             let expected = Statement::AlterAddColumn(AlterAddColumn {
                 table_name: make_table_name("t"),
                 columns: vec![make_tag_column_def("c1", DataType::String(None))],
+                dry_run: false,
             });
             expect_parse_ok(sql, expected).unwrap();
         }
@@ -1386,6 +2522,171 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_show_partitions() {
+        let expected = Statement::ShowPartitions(ShowPartitions {
+            table_name: make_table_name("cpu"),
+        });
+        expect_parse_ok("SHOW PARTITIONS cpu", expected).unwrap();
+
+        assert!(Parser::parse_sql("SHOW PARTITIONS").is_err());
+    }
+
+    #[test]
+    fn test_processlist() {
+        let statements = Parser::parse_sql("SHOW PROCESSLIST").unwrap();
+        assert_eq!(statements, vec![Statement::ShowProcessList]);
+
+        let expected = Statement::KillQuery(KillQuery { query_id: 42 });
+        expect_parse_ok("KILL QUERY 42", expected).unwrap();
+        let expected = Statement::KillQuery(KillQuery { query_id: 7 });
+        expect_parse_ok("kill 7;", expected).unwrap();
+
+        assert!(Parser::parse_sql("KILL QUERY abc").is_err());
+    }
+
+    #[test]
+    fn test_source() {
+        let sql = "CREATE SOURCE IF NOT EXISTS cpu_source INTO cpu FROM KAFKA 'b1:9092,b2:9092/cpu' FORMAT json WITH (tags='host')";
+        let statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(1, statements.len());
+        match &statements[0] {
+            Statement::CreateSource(s) => {
+                assert!(s.if_not_exists);
+                assert_eq!("cpu_source", s.name);
+                assert_eq!("cpu", s.table_name.as_ref().unwrap().to_string());
+                assert_eq!("b1:9092,b2:9092/cpu", s.uri);
+                assert_eq!("JSON", s.format);
+                assert_eq!(1, s.options.len());
+            }
+            _ => panic!("failed to parse create source"),
+        }
+
+        let sql = "CREATE SOURCE cpu_source FROM KAFKA 'b1:9092/cpu' FORMAT json";
+        let statements = Parser::parse_sql(sql).unwrap();
+        match &statements[0] {
+            Statement::CreateSource(s) => {
+                assert!(!s.if_not_exists);
+                assert!(s.table_name.is_none());
+                assert!(s.options.is_empty());
+            }
+            _ => panic!("failed to parse create source"),
+        }
+        assert!(Parser::parse_sql("CREATE SOURCE cpu_source FROM KAFKA 'b1:9092/cpu'").is_err());
+
+        let expected = Statement::DropSource(DropSource {
+            if_exists: true,
+            name: "cpu_source".to_string(),
+        });
+        expect_parse_ok("DROP SOURCE IF EXISTS cpu_source", expected).unwrap();
+
+        let statements = Parser::parse_sql("SHOW SOURCES").unwrap();
+        assert_eq!(statements, vec![Statement::ShowSources]);
+    }
+
+    #[test]
+    fn test_continuous_query() {
+        let sql = "CREATE CONTINUOUS QUERY IF NOT EXISTS cpu_5m INTO cpu_5m EVERY '5m' DELAY '1m' \
+                   AS SELECT host, avg(value) AS value FROM cpu GROUP BY host";
+        let statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(1, statements.len());
+        match &statements[0] {
+            Statement::CreateContinuousQuery(s) => {
+                assert!(s.if_not_exists);
+                assert_eq!("cpu_5m", s.name);
+                assert_eq!("cpu_5m", s.target.to_string());
+                assert_eq!("5m", s.every);
+                assert_eq!(Some("1m".to_string()), s.delay);
+                assert_eq!(
+                    "SELECT host, avg(value) AS value FROM cpu GROUP BY host",
+                    s.query.to_string()
+                );
+            }
+            _ => panic!("failed to parse create continuous query"),
+        }
+
+        let sql = "CREATE CONTINUOUS QUERY cpu_5m INTO cpu_5m EVERY '5m' AS SELECT count(*) FROM cpu";
+        let statements = Parser::parse_sql(sql).unwrap();
+        match &statements[0] {
+            Statement::CreateContinuousQuery(s) => {
+                assert!(!s.if_not_exists);
+                assert!(s.delay.is_none());
+            }
+            _ => panic!("failed to parse create continuous query"),
+        }
+        assert!(Parser::parse_sql(
+            "CREATE CONTINUOUS QUERY cpu_5m INTO cpu_5m AS SELECT count(*) FROM cpu"
+        )
+        .is_err());
+
+        let expected = Statement::DropContinuousQuery(DropContinuousQuery {
+            if_exists: true,
+            name: "cpu_5m".to_string(),
+        });
+        expect_parse_ok("DROP CONTINUOUS QUERY IF EXISTS cpu_5m", expected).unwrap();
+    }
+
+    #[test]
+    fn test_user() {
+        let expected = Statement::CreateUser(CreateUser {
+            if_not_exists: true,
+            name: "alice".to_string(),
+            password: "secret".to_string(),
+        });
+        let sql = "CREATE USER IF NOT EXISTS alice IDENTIFIED BY 'secret'";
+        expect_parse_ok(sql, expected).unwrap();
+        assert!(Parser::parse_sql("CREATE USER alice").is_err());
+
+        let expected = Statement::DropUser(DropUser {
+            if_exists: false,
+            name: "alice".to_string(),
+        });
+        expect_parse_ok("DROP USER alice", expected).unwrap();
+
+        let expected = Statement::Grant(Grant {
+            role: "read_write".to_string(),
+            catalog: "horaedb".to_string(),
+            user: "alice".to_string(),
+        });
+        expect_parse_ok("GRANT read_write ON CATALOG horaedb TO alice", expected).unwrap();
+        let expected = Statement::Grant(Grant {
+            role: "admin".to_string(),
+            catalog: "*".to_string(),
+            user: "bob".to_string(),
+        });
+        expect_parse_ok("grant admin on catalog * to bob", expected).unwrap();
+        expect_parse_error("GRANT admin ON horaedb TO bob", "Expected CATALOG");
+
+        for sql in [
+            "REVOKE ALL ON CATALOG horaedb FROM alice",
+            "REVOKE ON CATALOG horaedb FROM alice",
+        ] {
+            let expected = Statement::Revoke(Revoke {
+                catalog: "horaedb".to_string(),
+                user: "alice".to_string(),
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_function() {
+        let expected = Statement::CreateFunction(CreateFunction {
+            if_not_exists: true,
+            name: "celsius".to_string(),
+            module: "temperature.wasm".to_string(),
+        });
+        let sql = "CREATE FUNCTION IF NOT EXISTS celsius AS 'temperature.wasm'";
+        expect_parse_ok(sql, expected).unwrap();
+        assert!(Parser::parse_sql("CREATE FUNCTION celsius").is_err());
+
+        let expected = Statement::DropFunction(DropFunction {
+            if_exists: true,
+            name: "celsius".to_string(),
+        });
+        expect_parse_ok("DROP FUNCTION IF EXISTS celsius", expected).unwrap();
+    }
+
     #[test]
     fn test_normalizing_table_name_in_select() {
         {
@@ -1436,6 +2737,202 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_regexp_operator() {
+        let cases = [
+            ("select * from t where host ~ 'api-.*'", "host ~ 'api-.*'"),
+            (
+                "select * from t where host REGEXP 'api-.*'",
+                "host ~ 'api-.*'",
+            ),
+            (
+                "select * from t where host rlike 'api-.*'",
+                "host ~ 'api-.*'",
+            ),
+            (
+                "select * from t where host NOT REGEXP 'api-.*'",
+                "host !~ 'api-.*'",
+            ),
+            ("select * from t where `regexp` = 'api'", "`regexp` = 'api'"),
+        ];
+
+        for (sql, expected) in cases {
+            let statements = Parser::parse_sql(sql).unwrap();
+            if let Statement::Standard(standard_statement) = &statements[0] {
+                let standard_statement_str = format!("{standard_statement}");
+                assert!(
+                    standard_statement_str.contains(expected),
+                    "sql:{sql}, statement:{standard_statement_str}"
+                );
+            } else {
+                panic!("expect standard statement, sql:{sql}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_system_time_as_of() {
+        let cases = [
+            (
+                "select * from t FOR SYSTEM_TIME AS OF '2023-01-01 00:00:00'",
+                "FROM `table_snapshot`('t', '2023-01-01 00:00:00')",
+            ),
+            (
+                "select * from public.`T1` for system_time as of 1000 as t1 where a = 1",
+                "FROM `table_snapshot`('public.T1', 1000) AS t1",
+            ),
+            (
+                "select * from t where a = 'FOR SYSTEM_TIME AS OF 1'",
+                "FROM `t` WHERE a = 'FOR SYSTEM_TIME AS OF 1'",
+            ),
+        ];
+
+        for (sql, expected) in cases {
+            let statements = Parser::parse_sql(sql).unwrap();
+            if let Statement::Standard(standard_statement) = &statements[0] {
+                let standard_statement_str = format!("{standard_statement}");
+                assert!(
+                    standard_statement_str.contains(expected),
+                    "sql:{sql}, statement:{standard_statement_str}"
+                );
+            } else {
+                panic!("expect standard statement, sql:{sql}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_all_columns() {
+        let cases = [
+            ("select all columns from t", true, "SELECT * FROM `t`"),
+            (
+                "SELECT ALL\n  Columns FROM t WHERE a = 1",
+                true,
+                "SELECT * FROM `t` WHERE a = 1",
+            ),
+            ("select * from t", false, "SELECT * FROM `t`"),
+            ("select all a from t", false, "SELECT a FROM `t`"),
+            (
+                "select all `columns` from t",
+                false,
+                "SELECT `columns` FROM `t`",
+            ),
+        ];
+
+        for (sql, all_columns, expected) in cases {
+            let statements = Parser::parse_sql(sql).unwrap();
+            let statement = match &statements[0] {
+                Statement::SelectAllColumns(v) if all_columns => v,
+                Statement::Standard(v) if !all_columns => v,
+                v => panic!("unexpected statement, sql:{sql}, statement:{v:?}"),
+            };
+            assert_eq!(expected, format!("{statement}"), "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_explain_with_settings() {
+        let cases = [
+            (
+                "explain with settings select * from t",
+                true,
+                "EXPLAIN SELECT * FROM t",
+            ),
+            (
+                "EXPLAIN ANALYZE VERBOSE WITH SETTINGS select a from t",
+                true,
+                "EXPLAIN ANALYZE VERBOSE SELECT a FROM t",
+            ),
+            (
+                "explain verbose select a from t",
+                false,
+                "EXPLAIN VERBOSE SELECT a FROM t",
+            ),
+            (
+                "explain with cte as (select a from t) select * from cte",
+                false,
+                "EXPLAIN WITH cte AS (SELECT a FROM t) SELECT * FROM cte",
+            ),
+        ];
+
+        for (sql, with_settings, expected) in cases {
+            let statements = Parser::parse_sql(sql).unwrap();
+            let statement = match &statements[0] {
+                Statement::ExplainWithSettings(v) if with_settings => v,
+                Statement::Standard(v) if !with_settings => v,
+                v => panic!("unexpected statement, sql:{sql}, statement:{v:?}"),
+            };
+            assert_eq!(expected, format!("{statement}"), "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_explain_format_json() {
+        let cases = [
+            (
+                "explain (format json) select * from t",
+                Some(false),
+                "EXPLAIN SELECT * FROM t",
+            ),
+            (
+                "EXPLAIN (FORMAT JSON) ANALYZE WITH SETTINGS select a from t",
+                Some(true),
+                "EXPLAIN ANALYZE SELECT a FROM t",
+            ),
+            (
+                "explain verbose select a from t",
+                None,
+                "EXPLAIN VERBOSE SELECT a FROM t",
+            ),
+        ];
+
+        for (sql, with_settings, expected) in cases {
+            let statements = Parser::parse_sql(sql).unwrap();
+            let statement = match (&statements[0], with_settings) {
+                (Statement::ExplainJson(v), Some(with_settings)) => {
+                    assert_eq!(with_settings, v.with_settings, "sql:{sql}");
+                    &v.statement
+                }
+                (Statement::Standard(v), None) => v,
+                (v, _) => panic!("unexpected statement, sql:{sql}, statement:{v:?}"),
+            };
+            assert_eq!(expected, format!("{statement}"), "sql:{sql}");
+        }
+
+        assert!(Parser::parse_sql("explain (format yaml) select * from t").is_err());
+    }
+
+    #[test]
+    fn test_fill_clause() {
+        let cases = [
+            (
+                "select t, host, avg(v) from t1 group by t, host fill(linear)",
+                "SELECT t, host, avg(v) FROM `t1` GROUP BY t, host, fill(linear)",
+            ),
+            (
+                "select t, avg(v) from t1 group by t FILL(0) order by t",
+                "SELECT t, avg(v) FROM `t1` GROUP BY t, FILL(0) ORDER BY t",
+            ),
+            (
+                "select avg(v) from t1 group by time_bucket(ts, 'PT1M') fill(previous)",
+                "SELECT avg(v) FROM `t1` GROUP BY time_bucket(ts, 'PT1M'), fill(previous)",
+            ),
+            (
+                "select fill(v) from t1 group by v",
+                "SELECT fill(v) FROM `t1` GROUP BY v",
+            ),
+        ];
+
+        for (sql, expected) in cases {
+            let statements = Parser::parse_sql(sql).unwrap();
+            let statement = match &statements[0] {
+                Statement::Standard(v) => v,
+                v => panic!("unexpected statement, sql:{sql}, statement:{v:?}"),
+            };
+            assert_eq!(expected, format!("{statement}"), "sql:{sql}");
+        }
+    }
+
     #[test]
     fn test_hash_partition() {
         HashPartitionTableCases::basic();
@@ -1597,6 +3094,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_time_partition() {
+        let sql = r#"CREATE TABLE `demo` (`name` string TAG, `value` double NOT NULL, `t` timestamp NOT NULL, TIMESTAMP KEY(t)) PARTITION BY TIME(t, '1d') PARTITIONS 4 ENGINE=Analytic"#;
+        let stmt = Parser::parse_sql(sql).unwrap();
+        assert_eq!(stmt.len(), 1);
+        match &stmt[0] {
+            Statement::Create(v) => {
+                let expected = TimePartition {
+                    column: "t".to_string(),
+                    interval: ReadableDuration::days(1),
+                    partition_num: 4,
+                };
+                assert_eq!(v.partition, Some(Partition::Time(expected)));
+            }
+            _ => panic!("failed"),
+        }
+
+        // The column must be a timestamp.
+        let sql = r#"CREATE TABLE `demo` (`name` string TAG, `value` double NOT NULL, `t` timestamp NOT NULL, TIMESTAMP KEY(t)) PARTITION BY TIME(value, '1d') PARTITIONS 4 ENGINE=Analytic"#;
+        assert_eq!(
+            Parser::parse_sql(sql).err().unwrap(),
+            ParserError(
+                "time partition column must be an existing timestamp column, column:value"
+                    .to_string()
+            )
+        );
+
+        // The interval must be valid.
+        for interval in ["1x", "0s"] {
+            let sql = format!(
+                r#"CREATE TABLE `demo` (`name` string TAG, `t` timestamp NOT NULL, TIMESTAMP KEY(t)) PARTITION BY TIME(t, '{interval}') PARTITIONS 4 ENGINE=Analytic"#
+            );
+            assert!(Parser::parse_sql(&sql).is_err());
+        }
+    }
+
     #[test]
     fn test_partition_num_restriction() {
         let invalid_partition_num = partition::MAX_PARTITION_NUM + 1;
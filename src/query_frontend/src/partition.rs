@@ -17,17 +17,19 @@
 
 //! Parse partition statement to partition info
 
+use common_types::schema::Schema;
 use datafusion::{logical_expr::Expr, prelude::Column};
 use datafusion_proto::bytes::Serializeable;
 use generic_error::BoxError;
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 use sqlparser::ast::Expr as SqlExpr;
 use table_engine::partition::{
     HashPartitionInfo, KeyPartitionInfo, PartitionDefinition, PartitionInfo, RandomPartitionInfo,
+    TimePartitionInfo,
 };
 
 use crate::{
-    ast::{HashPartition, KeyPartition, Partition, RandomPartition},
+    ast::{HashPartition, KeyPartition, Partition, RandomPartition, TimePartition},
     planner::{ParsePartitionWithCause, Result, UnsupportedPartition},
 };
 
@@ -37,11 +39,12 @@ pub const MAX_PARTITION_NUM: u64 = 1024;
 pub struct PartitionParser;
 
 impl PartitionParser {
-    pub fn parse(partition_stmt: Partition) -> Result<PartitionInfo> {
+    pub fn parse(partition_stmt: Partition, schema: &Schema) -> Result<PartitionInfo> {
         Ok(match partition_stmt {
             Partition::Random(stmt) => PartitionInfo::Random(Self::parse_random(stmt)),
             Partition::Hash(stmt) => PartitionInfo::Hash(Self::parse_hash(stmt)?),
             Partition::Key(stmt) => PartitionInfo::Key(Self::parse_key(stmt)?),
+            Partition::Time(stmt) => PartitionInfo::Time(Self::parse_time(stmt, schema)?),
         })
     }
 
@@ -95,6 +98,31 @@ impl PartitionParser {
             linear,
         })
     }
+
+    fn parse_time(time_stmt: TimePartition, schema: &Schema) -> Result<TimePartitionInfo> {
+        let TimePartition {
+            column,
+            interval,
+            partition_num,
+        } = time_stmt;
+
+        // The rows of the same primary key must be in the same partition.
+        ensure!(
+            column == schema.timestamp_name(),
+            UnsupportedPartition {
+                msg: format!(
+                    "time partition column must be the timestamp key, column:{column}, timestamp_key:{}",
+                    schema.timestamp_name()
+                ),
+            }
+        );
+
+        Ok(TimePartitionInfo {
+            definitions: make_partition_definitions(partition_num),
+            column,
+            interval,
+        })
+    }
 }
 
 fn make_partition_definitions(partition_num: u64) -> Vec<PartitionDefinition> {
@@ -0,0 +1,284 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Transformation pipeline of the rows written to a table.
+//!
+//! The pipeline is configured by the `transform_pipeline` option of the table,
+//! and consists of the steps separated by `;`:
+//! - `SET <column> = <expr>` sets the column to the value of the sql expression
+//!   on the columns of the row, e.g. `SET latency = latency / 1000`;
+//! - `RENAME <from> TO <to>` moves the value of the column `from` to `to`;
+//! - `DROP <column>` discards the value of the column.
+//!
+//! The steps are applied in order, so the later steps see the values changed
+//! by the former ones.
+
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use common_types::schema::Schema;
+use datafusion::{
+    common::{Column, DFSchema, ToDFSchema},
+    config::ConfigOptions,
+    error::DataFusionError,
+    logical_expr::{AggregateUDF, Expr, ScalarUDF, TableSource, WindowUDF},
+    sql::{
+        planner::{ContextProvider, PlannerContext, SqlToRel},
+        TableReference,
+    },
+};
+use macros::define_result;
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+use sqlparser::{
+    dialect::MySqlDialect,
+    keywords::Keyword,
+    parser::{Parser, ParserError},
+    tokenizer::Token,
+};
+
+use crate::planner::DEFAULT_PARSER_OPTS;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to parse transform pipeline, err:{}", source))]
+    ParsePipeline { source: ParserError },
+
+    #[snafu(display("Failed to plan expr of transform pipeline, err:{}", source))]
+    PlanExpr { source: DataFusionError },
+
+    #[snafu(display(
+        "Column not found in transform pipeline, column:{}.\nBacktrace:\n{}",
+        column,
+        backtrace
+    ))]
+    ColumnNotFound {
+        column: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid step of transform pipeline, column:{}, msg:{}.\nBacktrace:\n{}",
+        column,
+        msg,
+        backtrace
+    ))]
+    InvalidStep {
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+}
+
+define_result!(Error);
+
+/// Step of the pipeline on the column at `column_idx` of the table schema.
+#[derive(Debug, Clone)]
+pub enum Step {
+    Set { column_idx: usize, expr: Expr },
+    Drop { column_idx: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    /// Parse the `pipeline` with the columns of the table `schema`.
+    pub fn parse(pipeline: &str, schema: &Schema) -> Result<Self> {
+        let dialect = MySqlDialect {};
+        let mut parser = Parser::new(&dialect)
+            .try_with_sql(pipeline)
+            .context(ParsePipeline)?;
+        let df_schema = schema
+            .to_arrow_schema_ref()
+            .to_dfschema()
+            .context(PlanExpr)?;
+        let context_provider = PipelineContextProvider::default();
+        let sql_to_rel = SqlToRel::new_with_options(&context_provider, DEFAULT_PARSER_OPTS);
+
+        let mut steps = Vec::new();
+        loop {
+            while parser.consume_token(&Token::SemiColon) {}
+            if parser.peek_token().token == Token::EOF {
+                break;
+            }
+
+            if parser.parse_keyword(Keyword::SET) {
+                let column = parser.parse_identifier().context(ParsePipeline)?.value;
+                parser.expect_token(&Token::Eq).context(ParsePipeline)?;
+                let expr = parser.parse_expr().context(ParsePipeline)?;
+                let expr = sql_to_rel
+                    .sql_to_expr(expr, &df_schema, &mut PlannerContext::new())
+                    .context(PlanExpr)?;
+                steps.push(Step::Set {
+                    column_idx: settable_column(schema, &column)?,
+                    expr,
+                });
+            } else if parser.parse_keyword(Keyword::RENAME) {
+                let from = parser.parse_identifier().context(ParsePipeline)?.value;
+                parser.expect_keyword(Keyword::TO).context(ParsePipeline)?;
+                let to = parser.parse_identifier().context(ParsePipeline)?.value;
+                let from_idx = droppable_column(schema, &from)?;
+                steps.push(Step::Set {
+                    column_idx: settable_column(schema, &to)?,
+                    expr: column_expr(&df_schema, from_idx),
+                });
+                steps.push(Step::Drop {
+                    column_idx: from_idx,
+                });
+            } else if parser.parse_keyword(Keyword::DROP) {
+                let column = parser.parse_identifier().context(ParsePipeline)?.value;
+                steps.push(Step::Drop {
+                    column_idx: droppable_column(schema, &column)?,
+                });
+            } else {
+                return parser
+                    .expected("SET, RENAME or DROP", parser.peek_token())
+                    .context(ParsePipeline);
+            }
+
+            if !parser.consume_token(&Token::SemiColon) && parser.peek_token().token != Token::EOF {
+                return parser
+                    .expected("; or end of pipeline", parser.peek_token())
+                    .context(ParsePipeline);
+            }
+        }
+
+        Ok(Self { steps })
+    }
+
+    #[inline]
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Index of the columns changed by the pipeline.
+    pub fn columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.steps.iter().map(|step| match step {
+            Step::Set { column_idx, .. } | Step::Drop { column_idx } => *column_idx,
+        })
+    }
+}
+
+fn column_expr(df_schema: &DFSchema, column_idx: usize) -> Expr {
+    Expr::Column(Column::from_name(df_schema.field(column_idx).name()))
+}
+
+fn find_column(schema: &Schema, column: &str) -> Result<usize> {
+    schema.index_of(column).context(ColumnNotFound { column })
+}
+
+/// The tsid is generated from the tags, so it can't be set by the pipeline.
+fn settable_column(schema: &Schema, column: &str) -> Result<usize> {
+    let column_idx = find_column(schema, column)?;
+    ensure!(
+        schema.index_of_tsid() != Some(column_idx),
+        InvalidStep {
+            column,
+            msg: "tsid can't be set",
+        }
+    );
+
+    Ok(column_idx)
+}
+
+fn droppable_column(schema: &Schema, column: &str) -> Result<usize> {
+    let column_idx = find_column(schema, column)?;
+    ensure!(
+        !schema.is_primary_key_index(&column_idx) && schema.column(column_idx).is_nullable,
+        InvalidStep {
+            column,
+            msg: "only the nullable columns out of the primary key can be dropped",
+        }
+    );
+
+    Ok(column_idx)
+}
+
+/// The expressions of the pipeline only access the columns of the row and the
+/// builtin functions.
+#[derive(Default)]
+struct PipelineContextProvider {
+    config: ConfigOptions,
+}
+
+impl ContextProvider for PipelineContextProvider {
+    fn get_table_source(
+        &self,
+        name: TableReference,
+    ) -> std::result::Result<Arc<dyn TableSource>, DataFusionError> {
+        Err(DataFusionError::Plan(format!(
+            "Table can't be accessed by transform pipeline, table:{name}"
+        )))
+    }
+
+    fn get_function_meta(&self, _name: &str) -> Option<Arc<ScalarUDF>> {
+        None
+    }
+
+    fn get_aggregate_meta(&self, _name: &str) -> Option<Arc<AggregateUDF>> {
+        None
+    }
+
+    fn get_window_meta(&self, _name: &str) -> Option<Arc<WindowUDF>> {
+        None
+    }
+
+    fn get_variable_type(&self, _variable_names: &[String]) -> Option<DataType> {
+        None
+    }
+
+    fn options(&self) -> &ConfigOptions {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::build_schema_with_dictionary;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_pipeline() {
+        let schema = build_schema_with_dictionary();
+        let pipeline = Pipeline::parse(
+            "RENAME tag1 TO field2; SET field1 = field1 / 1000;; DROP field3;",
+            &schema,
+        )
+        .unwrap();
+        let steps = pipeline.steps();
+        assert_eq!(4, steps.len());
+        assert!(matches!(steps[0], Step::Set { column_idx, .. } if column_idx == 3));
+        assert!(matches!(steps[1], Step::Drop { column_idx } if column_idx == 6));
+        assert!(matches!(steps[2], Step::Set { column_idx, .. } if column_idx == 2));
+        assert!(matches!(steps[3], Step::Drop { column_idx } if column_idx == 4));
+        assert_eq!(vec![3, 6, 2, 4], pipeline.columns().collect::<Vec<_>>());
+
+        for invalid in [
+            "SET not_exist = 1",
+            "SET field1 = not_exist",
+            "DROP key1",
+            "DROP tag2",
+            "SET field1 = 1 DROP field3",
+            "UPDATE field1 = 1",
+        ] {
+            assert!(Pipeline::parse(invalid, &schema).is_err(), "{invalid}");
+        }
+    }
+}
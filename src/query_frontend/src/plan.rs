@@ -22,10 +22,17 @@ use std::{
     fmt,
     fmt::{Debug, Formatter},
     ops::Bound,
+    str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 
-use common_types::{column_schema::ColumnSchema, row::RowGroup, schema::Schema, time::TimeRange};
+use common_types::{
+    column_schema::ColumnSchema,
+    row::RowGroup,
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+};
 use datafusion::{
     logical_expr::{
         expr::Expr as DfLogicalExpr, logical_plan::LogicalPlan as DataFusionLogicalPlan,
@@ -36,6 +43,7 @@ use datafusion::{
 use logger::{debug, warn};
 use macros::define_result;
 use runtime::Priority;
+use serde::{Deserialize, Serialize};
 use snafu::{OptionExt, Snafu};
 use table_engine::{partition::PartitionInfo, table::TableRef};
 
@@ -77,10 +85,44 @@ pub enum Plan {
     Describe(DescribeTablePlan),
     /// Alter table plan
     AlterTable(AlterTablePlan),
+    /// Alter schema plan
+    AlterSchema(AlterSchemaPlan),
     /// Show plan
     Show(ShowPlan),
     /// Exists table
     Exists(ExistsTablePlan),
+    /// Kill a running query
+    KillQuery(KillQueryPlan),
+    /// Create an ingestion source
+    CreateSource(CreateSourcePlan),
+    /// Drop an ingestion source
+    DropSource(DropSourcePlan),
+    /// Change an option of the server config
+    AlterSystem(AlterSystemPlan),
+    /// Build or drop the statistics of a table
+    AnalyzeTable(AnalyzeTablePlan),
+    /// Force a major compaction of a table
+    CompactTable(CompactTablePlan),
+    /// Make a consumer of the table changes start over with a snapshot
+    ResyncTable(ResyncTablePlan),
+    /// Create a continuous query
+    CreateContinuousQuery(CreateContinuousQueryPlan),
+    /// Drop a continuous query
+    DropContinuousQuery(DropContinuousQueryPlan),
+    /// Exchange the tables bound to two names
+    SwapTables(SwapTablesPlan),
+    /// Create a user
+    CreateUser(CreateUserPlan),
+    /// Drop a user
+    DropUser(DropUserPlan),
+    /// Grant a role on a catalog to a user
+    Grant(GrantPlan),
+    /// Revoke the role on a catalog from a user
+    Revoke(RevokePlan),
+    /// Load a udf from a wasm module
+    CreateFunction(CreateFunctionPlan),
+    /// Drop a udf loaded from a wasm module
+    DropFunction(DropFunctionPlan),
 }
 
 impl Plan {
@@ -92,14 +134,65 @@ impl Plan {
             | Self::Drop(_)
             | Self::Describe(_)
             | Self::AlterTable(_)
+            | Self::AlterSchema(_)
             | Self::Show(_)
-            | Self::Exists(_) => "other",
+            | Self::Exists(_)
+            | Self::KillQuery(_)
+            | Self::CreateSource(_)
+            | Self::DropSource(_)
+            | Self::AlterSystem(_)
+            | Self::AnalyzeTable(_)
+            | Self::CompactTable(_)
+            | Self::ResyncTable(_)
+            | Self::CreateContinuousQuery(_)
+            | Self::DropContinuousQuery(_)
+            | Self::SwapTables(_)
+            | Self::CreateUser(_)
+            | Self::DropUser(_)
+            | Self::Grant(_)
+            | Self::Revoke(_)
+            | Self::CreateFunction(_)
+            | Self::DropFunction(_) => "other",
+        }
+    }
+
+    /// Whether the plan doesn't modify the data or the schemas.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            Self::Query(_)
+            | Self::Describe(_)
+            | Self::Show(_)
+            | Self::Exists(_)
+            | Self::KillQuery(_)
+            | Self::AnalyzeTable(_) => true,
+            Self::Insert(_)
+            | Self::Create(_)
+            | Self::Drop(_)
+            | Self::AlterTable(_)
+            | Self::AlterSchema(_)
+            | Self::CreateSource(_)
+            | Self::DropSource(_)
+            | Self::AlterSystem(_)
+            | Self::CompactTable(_)
+            | Self::ResyncTable(_)
+            | Self::CreateContinuousQuery(_)
+            | Self::DropContinuousQuery(_)
+            | Self::SwapTables(_)
+            | Self::CreateUser(_)
+            | Self::DropUser(_)
+            | Self::Grant(_)
+            | Self::Revoke(_)
+            | Self::CreateFunction(_)
+            | Self::DropFunction(_) => false,
         }
     }
 }
 
 pub struct PriorityContext {
     pub time_range_threshold: u64,
+    /// Queries only reading the data older than this range (in milliseconds)
+    /// are cold, zero means no query is cold.
+    pub hot_time_range: u64,
 }
 
 pub struct QueryPlan {
@@ -109,6 +202,19 @@ pub struct QueryPlan {
     // Use TableProviderAdapter here so we can get the underlying TableRef and also be
     // able to cast to Arc<dyn TableProvider + Send + Sync>
     pub tables: Arc<TableContainer>,
+    /// Offload the results to the result store instead of returning them,
+    /// set by `COPY (query) TO 'path'`.
+    pub offload: Option<ResultOffload>,
+    /// Output the explained plans as json trees instead of text, set by
+    /// `EXPLAIN (FORMAT JSON)`.
+    pub explain_json: bool,
+}
+
+/// Target of offloading the query results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultOffload {
+    /// Path prefix of the output files in the result store
+    pub path: String,
 }
 
 impl QueryPlan {
@@ -216,6 +322,11 @@ impl QueryPlan {
             // When there is no valid time range , we cann't decide its priority.
             None => return Ok(None),
         };
+        // The cold queries are scheduled with the low priority to keep the hot ones
+        // responsive.
+        if is_cold_time_range(&time_range, ctx.hot_time_range) {
+            return Ok(Some(Priority::Low));
+        }
         let is_expensive = if let Some(v) = time_range
             .exclusive_end()
             .as_i64()
@@ -236,6 +347,16 @@ impl QueryPlan {
         Ok(Some(priority))
     }
 
+    /// Whether the query only reads the data older than the `hot_time_range`
+    /// (in milliseconds), zero `hot_time_range` means no query is cold.
+    pub fn is_cold_query(&self, hot_time_range: u64) -> Result<bool> {
+        let is_cold = self
+            .extract_time_range()?
+            .map(|time_range| is_cold_time_range(&time_range, hot_time_range))
+            .unwrap_or(false);
+        Ok(is_cold)
+    }
+
     /// When query contains invalid time range such as `[200, 100]`, it will
     /// return None.
     pub fn query_range(&self) -> Result<Option<i64>> {
@@ -251,6 +372,16 @@ impl QueryPlan {
     }
 }
 
+fn is_cold_time_range(time_range: &TimeRange, hot_time_range: u64) -> bool {
+    if hot_time_range == 0 {
+        return false;
+    }
+
+    let hot_time_range = i64::try_from(hot_time_range).unwrap_or(i64::MAX);
+    let hot_start = Timestamp::now().as_i64().saturating_sub(hot_time_range);
+    time_range.exclusive_end().as_i64() <= hot_start
+}
+
 impl Debug for QueryPlan {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("QueryPlan")
@@ -327,6 +458,21 @@ pub struct DescribeTablePlan {
 pub enum AlterTableOperation {
     /// Add a new column, the column id will be ignored.
     AddColumn(Vec<ColumnSchema>),
+    /// Drop columns by name, the data of the dropped columns is not removed
+    /// from the existing files but is no longer visible.
+    DropColumn(Vec<String>),
+    /// Rename a column, the column id is kept so the existing data is still
+    /// readable.
+    RenameColumn {
+        from: String,
+        to: String,
+    },
+    /// Change the annotations of a column, the ones of `None` are kept.
+    ModifyColumn {
+        name: String,
+        display_name: Option<String>,
+        unit: Option<String>,
+    },
     ModifySetting(HashMap<String, String>),
 }
 
@@ -336,6 +482,25 @@ pub struct AlterTablePlan {
     pub table: TableRef,
     // TODO(yingwen): Maybe use smallvec.
     pub operations: AlterTableOperation,
+    /// Only report the changes without applying them.
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub struct SwapTablesPlan {
+    /// Name of the table to swap.
+    pub table: String,
+    /// Name of the table to exchange with.
+    pub other: String,
+}
+
+#[derive(Debug)]
+pub struct AlterSchemaPlan {
+    /// The schema to alter.
+    pub schema: String,
+    /// Default options of the tables to modify, the option with an empty
+    /// value is removed.
+    pub default_table_options: HashMap<String, String>,
 }
 
 #[derive(Debug)]
@@ -346,6 +511,12 @@ pub struct ShowCreatePlan {
     pub obj_type: ShowCreateObject,
 }
 
+#[derive(Debug)]
+pub struct ShowPartitionsPlan {
+    /// The partitioned table to show.
+    pub table: TableRef,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum QueryType {
     Sql,
@@ -367,6 +538,12 @@ pub enum ShowPlan {
     ShowTablesPlan(ShowTablesPlan),
     /// show database
     ShowDatabase,
+    /// show processlist
+    ShowProcessList,
+    /// show sources
+    ShowSources,
+    /// show partitions
+    ShowPartitions(ShowPartitionsPlan),
 }
 
 #[derive(Debug)]
@@ -374,6 +551,220 @@ pub struct ExistsTablePlan {
     pub exists: bool,
 }
 
+#[derive(Debug)]
+pub struct AlterSystemPlan {
+    /// Path of the option in the config file, e.g.
+    /// `analytic.sst_meta_cache_cap`
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug)]
+pub enum AnalyzeTableOperation {
+    /// Rebuild the existing histograms of the table.
+    Refresh,
+    /// Build the histograms of the columns.
+    UpdateHistogram {
+        columns: Vec<String>,
+        num_buckets: usize,
+    },
+    /// Drop the histograms of the columns.
+    DropHistogram { columns: Vec<String> },
+}
+
+#[derive(Debug)]
+pub struct AnalyzeTablePlan {
+    /// The table to analyze.
+    pub table: TableRef,
+    pub operation: AnalyzeTableOperation,
+}
+
+#[derive(Debug)]
+pub struct CompactTablePlan {
+    /// The table to compact.
+    pub table: TableRef,
+}
+
+#[derive(Debug)]
+pub struct ResyncTablePlan {
+    /// The table subscribed by the consumer.
+    pub table: TableRef,
+    pub consumer: String,
+}
+
+#[derive(Debug)]
+pub struct KillQueryPlan {
+    /// Id of the query to kill
+    pub query_id: u64,
+}
+
+/// Format of the messages consumed by a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    /// Every message is a json object, or an array of json objects.
+    Json,
+}
+
+/// Definition of an ingestion source, which consumes a kafka topic and writes
+/// the messages into a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDef {
+    pub name: String,
+    /// Table to write
+    pub table: String,
+    pub brokers: Vec<String>,
+    pub topic: String,
+    pub format: SourceFormat,
+    /// Fields written as tags, and the others are written as fields
+    pub tags: Vec<String>,
+    /// Field holding the timestamp of the message in milliseconds
+    pub timestamp_field: String,
+}
+
+#[derive(Debug)]
+pub struct CreateSourcePlan {
+    pub if_not_exists: bool,
+    pub source: SourceDef,
+}
+
+#[derive(Debug)]
+pub struct DropSourcePlan {
+    pub if_exists: bool,
+    /// Name of the source to drop
+    pub name: String,
+}
+
+/// Definition of a continuous query, which aggregates the rows of the source
+/// table in the windows of `every` and writes the results into the target
+/// table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuousQueryDef {
+    pub name: String,
+    pub source_table: String,
+    pub target_table: String,
+    /// Timestamp column of the source table, which is also the timestamp
+    /// column of the written rows
+    pub time_column: String,
+    pub every: Duration,
+    /// Time to wait for the late rows before aggregating a window
+    pub delay: Duration,
+    /// The select statement, which is restricted to a window when it runs
+    pub query: String,
+}
+
+#[derive(Debug)]
+pub struct CreateContinuousQueryPlan {
+    pub if_not_exists: bool,
+    pub continuous_query: ContinuousQueryDef,
+}
+
+#[derive(Debug)]
+pub struct DropContinuousQueryPlan {
+    pub if_exists: bool,
+    /// Name of the continuous query to drop
+    pub name: String,
+}
+
+/// Role of a user on a catalog, a role is allowed to do everything the
+/// lower roles are allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    /// Query the tables
+    ReadOnly,
+    /// Write the tables and change the schemas besides querying
+    ReadWrite,
+    /// Manage the users and the server besides all above
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read_only",
+            Self::ReadWrite => "read_write",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "read_only" => Ok(Self::ReadOnly),
+            "read_write" => Ok(Self::ReadWrite),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!(
+                "unknown role:{s}, expect read_only, read_write or admin"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for UserRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Catalog name of the grants applying to all the catalogs.
+pub const ALL_CATALOGS: &str = "*";
+
+pub struct CreateUserPlan {
+    pub if_not_exists: bool,
+    pub name: String,
+    pub password: String,
+}
+
+impl Debug for CreateUserPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUserPlan")
+            .field("if_not_exists", &self.if_not_exists)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct DropUserPlan {
+    pub if_exists: bool,
+    /// Name of the user to drop
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct GrantPlan {
+    pub user: String,
+    /// Catalog of the grant, [ALL_CATALOGS] for all the catalogs
+    pub catalog: String,
+    pub role: UserRole,
+}
+
+#[derive(Debug)]
+pub struct RevokePlan {
+    pub user: String,
+    /// Catalog of the grant to revoke
+    pub catalog: String,
+}
+
+#[derive(Debug)]
+pub struct CreateFunctionPlan {
+    pub if_not_exists: bool,
+    /// Name of the function, which is also the name of the export
+    pub name: String,
+    /// File name of the wasm module in the udf directory
+    pub module: String,
+}
+
+#[derive(Debug)]
+pub struct DropFunctionPlan {
+    pub if_exists: bool,
+    /// Name of the function to drop
+    pub name: String,
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -450,4 +841,41 @@ mod tests {
             assert_eq!(plan.extract_time_range().unwrap(), expected, "sql:{}", sql);
         }
     }
+
+    #[test]
+    fn test_cold_query() {
+        let hot_time_range = 3600 * 1000;
+        let ctx = || PriorityContext {
+            time_range_threshold: u64::MAX,
+            hot_time_range,
+        };
+        let hot_start = Timestamp::now().as_i64() - hot_time_range as i64;
+        let testcases = [
+            ("select * from test_table where key2 < 10".to_string(), true),
+            (
+                format!("select * from test_table where key2 > 1 and key2 < {hot_start}"),
+                true,
+            ),
+            (
+                format!("select * from test_table where key2 > {hot_start}"),
+                false,
+            ),
+            ("select * from test_table".to_string(), false),
+        ];
+
+        for (sql, is_cold) in testcases {
+            let plan = match sql_to_logical_plan(&sql).unwrap() {
+                Plan::Query(v) => v,
+                _ => unreachable!(),
+            };
+            assert_eq!(
+                is_cold,
+                plan.is_cold_query(hot_time_range).unwrap(),
+                "sql:{sql}"
+            );
+            assert!(!plan.is_cold_query(0).unwrap());
+            let priority = plan.decide_query_priority(ctx()).unwrap().unwrap();
+            assert_eq!(is_cold, matches!(priority, Priority::Low), "sql:{sql}");
+        }
+    }
 }
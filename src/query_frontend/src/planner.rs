@@ -21,7 +21,7 @@ use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap},
     convert::TryFrom,
-    mem,
+    iter, mem,
     ops::ControlFlow,
     sync::{atomic::AtomicBool, Arc},
 };
@@ -39,10 +39,13 @@ use common_types::{
     request_id::RequestId,
     row::{RowBuilder, RowGroup},
     schema::{self, Builder as SchemaBuilder, Schema, TSID_COLUMN},
+    time::Timestamp,
+    TRANSFORM_PIPELINE,
 };
 use datafusion::{
     common::{DFField, DFSchema},
     error::DataFusionError,
+    logical_expr::{logical_plan::Extension, LogicalPlan, ScalarValue},
     optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext},
     physical_expr::{create_physical_expr, execution_props::ExecutionProps},
     sql::{
@@ -53,34 +56,52 @@ use datafusion::{
 use generic_error::GenericError;
 use horaedbproto::storage::{value::Value as PbValue, WriteTableRequest};
 use influxql_parser::statement::Statement as InfluxqlStatement;
-use logger::{debug, trace};
+use logger::{debug, trace, warn};
 use macros::define_result;
 use prom_remote_api::types::Query as PromRemoteQuery;
 use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use sqlparser::ast::{
-    visit_statements_mut, ColumnDef, ColumnOption, Expr, Expr as SqlExpr, Ident, Query, SelectItem,
-    SetExpr, SqlOption, Statement as SqlStatement, TableConstraint, UnaryOperator, Value, Values,
+    visit_statements_mut, ColumnDef, ColumnOption, CopyOption, CopySource, CopyTarget, Expr,
+    Expr as SqlExpr, FunctionArg, FunctionArgExpr, GroupByExpr, Ident, ObjectName, Query, Select,
+    SelectItem, SetExpr, SqlOption, Statement as SqlStatement, TableConstraint, TableFactor,
+    TableWithJoins, UnaryOperator, Value, Values, VisitMut, VisitorMut,
+};
+use table_engine::{
+    histogram,
+    query_warning::{QueryWarnings, WarningKind},
+    table::TableRef,
 };
-use table_engine::table::TableRef;
 
 use crate::{
     ast::{
-        AlterAddColumn, AlterModifySetting, CreateTable, DescribeTable, DropTable, ExistsTable,
-        ShowCreate, ShowTables, Statement, TableName,
+        AlterAddColumn, AlterDropColumn, AlterModifyColumn, AlterModifySetting, AlterRenameColumn,
+        AlterSwapTable, AnalyzeOperation, AnalyzeTable, CompactTable, CreateContinuousQuery,
+        CreateSource, CreateTable, CreateUser, DescribeTable, DropTable, ExistsTable, Grant,
+        ResyncTable, ShowCreate, ShowPartitions, ShowTables, Statement, TableName,
     },
-    config::DynamicConfig,
+    config::{DynamicConfig, TimeRangeAction, WildcardAction, WildcardLimit},
     container::TableReference,
+    continuous_query,
     frontend::parse_table_name_with_standard,
-    logical_optimizer::optimize_plan,
+    gap_fill::{parse_bucket_interval, FillPolicy, GapFillNode, FILL_FUNC, TIME_BUCKET_FUNC},
+    latest_per_series::{LatestPerSeriesNode, LAST_ROW_FUNC},
+    logical_optimizer::{optimize_plan, parse_timestamp_ms},
     parser,
     partition::PartitionParser,
+    pipeline::Pipeline,
     plan::{
-        AlterTableOperation, AlterTablePlan, CreateTablePlan, DescribeTablePlan, DropTablePlan,
-        ExistsTablePlan, InsertPlan, Plan, QueryPlan, QueryType, ShowCreatePlan, ShowPlan,
-        ShowTablesPlan,
+        AlterSchemaPlan, AlterSystemPlan, AlterTableOperation, AlterTablePlan,
+        AnalyzeTableOperation, AnalyzeTablePlan, CompactTablePlan, ContinuousQueryDef,
+        CreateContinuousQueryPlan, CreateFunctionPlan, CreateSourcePlan, CreateTablePlan,
+        CreateUserPlan, DescribeTablePlan, DropContinuousQueryPlan, DropFunctionPlan,
+        DropSourcePlan, DropTablePlan, DropUserPlan, ExistsTablePlan, GrantPlan, InsertPlan,
+        KillQueryPlan, Plan, QueryPlan, QueryType, ResultOffload, ResyncTablePlan, RevokePlan,
+        ShowCreatePlan, ShowPartitionsPlan, ShowPlan, ShowTablesPlan, SourceDef, SourceFormat,
+        SwapTablesPlan, UserRole,
     },
     promql::{remote_query_to_plan, ColumnNames, Expr as PromExpr, RemoteQueryPlan},
-    provider::{ContextProviderAdapter, MetaProvider},
+    provider::{resolved_table_key, ContextProviderAdapter, MetaProvider, TableSnapshots},
+    time_range_guard,
 };
 // We do not carry backtrace in sql error because it is mainly used in server
 // handler and the error is usually caused by invalid/unsupported sql, which
@@ -247,6 +268,39 @@ pub enum Error {
     #[snafu(display("Unsupported sql option, value:{}", value))]
     UnsupportedOption { value: String },
 
+    #[snafu(display("Unsupported copy statement, msg:{}", msg))]
+    UnsupportedCopy { msg: String },
+
+    #[snafu(display("Invalid copy path, path:{}, msg:{}", path, msg))]
+    InvalidCopyPath { path: String, msg: String },
+
+    #[snafu(display("Invalid table_snapshot, msg:{}", msg))]
+    InvalidTableSnapshot { msg: String },
+
+    #[snafu(display(
+        "Too many columns are read by wildcard, table:{}, columns:{}, max_columns:{}, select the columns explicitly or use SELECT ALL COLUMNS",
+        table,
+        num_columns,
+        max_columns
+    ))]
+    WildcardOnWideTable {
+        table: String,
+        num_columns: usize,
+        max_columns: usize,
+    },
+
+    #[snafu(display(
+        "Query without a bounded time range on the guarded tables, tables:{:?}, add a timestamp predicate bounding the time range to read them",
+        tables
+    ))]
+    QueryWithoutTimeRange { tables: Vec<String> },
+
+    #[snafu(display("Unsupported LAST_ROW query, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    UnsupportedLastRow { msg: String, backtrace: Backtrace },
+
+    #[snafu(display("Unsupported FILL query, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    UnsupportedFill { msg: String, backtrace: Backtrace },
+
     #[snafu(display("Failed to build plan from promql, error:{}", source))]
     BuildPromPlanError { source: crate::promql::Error },
 
@@ -275,20 +329,55 @@ pub enum Error {
     #[snafu(display("Failed to build plan, msg:{}", msg))]
     InvalidWriteEntry { msg: String },
 
+    #[snafu(display("Invalid source, msg:{}", msg))]
+    InvalidSource { msg: String },
+
+    #[snafu(display("Invalid analyze table statement, msg:{}", msg))]
+    InvalidAnalyze { msg: String },
+
+    #[snafu(display("Invalid swap table statement, msg:{}", msg))]
+    InvalidSwapTable { msg: String },
+
+    #[snafu(display("Invalid continuous query, msg:{}", msg))]
+    InvalidContinuousQueryDef { msg: String },
+
+    #[snafu(display("Invalid user statement, msg:{}", msg))]
+    InvalidUser { msg: String },
+
+    #[snafu(display("Invalid continuous query, err:{}", source))]
+    InvalidContinuousQuery {
+        source: crate::continuous_query::Error,
+    },
+
     #[snafu(display("Failed to build influxql plan, err:{}", source))]
     BuildInfluxqlPlan {
         source: crate::influxql::error::Error,
     },
+
+    #[snafu(display("Invalid transform pipeline, err:{}", source))]
+    InvalidTransformPipeline { source: crate::pipeline::Error },
 }
 
 define_result!(Error);
 
 const DEFAULT_QUOTE_CHAR: char = '`';
-const DEFAULT_PARSER_OPTS: ParserOptions = ParserOptions {
+const SOURCE_FORMAT_JSON: &str = "JSON";
+const SOURCE_OPTION_TAGS: &str = "tags";
+const SOURCE_OPTION_TIMESTAMP_FIELD: &str = "timestamp_field";
+const DEFAULT_SOURCE_TIMESTAMP_FIELD: &str = "timestamp";
+pub(crate) const DEFAULT_PARSER_OPTS: ParserOptions = ParserOptions {
     parse_float_as_decimal: false,
     enable_ident_normalization: false,
 };
 
+/// What's needed to build the [GapFillNode].
+struct GapFill {
+    series_columns: Vec<String>,
+    time_column: String,
+    interval: i64,
+    policy: FillPolicy,
+}
+
 pub struct PlannerHint {
     pub enable_push_down_able_dist_query: AtomicBool,
 }
@@ -300,6 +389,7 @@ pub struct Planner<'a, P: MetaProvider> {
     request_id: RequestId,
     read_parallelism: usize,
     dyn_config: &'a DynamicConfig,
+    warnings: QueryWarnings,
 }
 
 impl<'a, P: MetaProvider> Planner<'a, P> {
@@ -315,9 +405,17 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
             request_id,
             read_parallelism,
             dyn_config,
+            warnings: QueryWarnings::default(),
         }
     }
 
+    /// Record the warnings of the planning, e.g. the implicit limits, into the
+    /// `warnings`.
+    pub fn with_warnings(mut self, warnings: QueryWarnings) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
     /// Create a logical plan from Statement
     ///
     /// Takes the ownership of statement because some statements like INSERT
@@ -335,19 +433,81 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
         // adapter and the SqlToRel in Planner, which is a self-referential
         // case. We wrap a PlannerDelegate to workaround this and avoid the usage of
         // pin.
-        let planner = PlannerDelegate::new(adapter);
+        let planner = PlannerDelegate::new(adapter, self.warnings.clone());
 
         match statement {
-            Statement::Standard(s) => planner.sql_statement_to_plan(*s),
+            Statement::Standard(s) | Statement::ExplainWithSettings(s) => {
+                planner.sql_statement_to_plan(*s)
+            }
+            Statement::ExplainJson(s) => match planner.sql_statement_to_plan(*s.statement)? {
+                Plan::Query(mut plan) => {
+                    plan.explain_json = true;
+                    Ok(Plan::Query(plan))
+                }
+                plan => Ok(plan),
+            },
+            Statement::SelectAllColumns(s) => planner.with_all_columns().sql_statement_to_plan(*s),
             Statement::Create(s) => planner.create_table_to_plan(*s),
             Statement::Drop(s) => planner.drop_table_to_plan(s),
             Statement::Describe(s) => planner.describe_table_to_plan(s),
             Statement::AlterModifySetting(s) => planner.alter_modify_setting_to_plan(s),
             Statement::AlterAddColumn(s) => planner.alter_add_column_to_plan(s),
+            Statement::AlterDropColumn(s) => planner.alter_drop_column_to_plan(s),
+            Statement::AlterRenameColumn(s) => planner.alter_rename_column_to_plan(s),
+            Statement::AlterModifyColumn(s) => planner.alter_modify_column_to_plan(s),
+            Statement::AlterSwapTable(s) => planner.alter_swap_table_to_plan(s),
+            Statement::AlterSchemaSetting(s) => Ok(Plan::AlterSchema(AlterSchemaPlan {
+                schema: s.schema_name,
+                default_table_options: parse_options(s.options)?,
+            })),
             Statement::ShowCreate(s) => planner.show_create_to_plan(s),
             Statement::ShowTables(s) => planner.show_tables_to_plan(s),
             Statement::ShowDatabases => planner.show_databases_to_plan(),
+            Statement::ShowProcessList => Ok(Plan::Show(ShowPlan::ShowProcessList)),
+            Statement::ShowPartitions(s) => planner.show_partitions_to_plan(s),
             Statement::Exists(s) => planner.exists_table_to_plan(s),
+            Statement::AlterSystemSet(s) => Ok(Plan::AlterSystem(AlterSystemPlan {
+                key: s.key,
+                value: s.value,
+            })),
+            Statement::AnalyzeTable(s) => planner.analyze_table_to_plan(s),
+            Statement::CompactTable(s) => planner.compact_table_to_plan(s),
+            Statement::ResyncTable(s) => planner.resync_table_to_plan(s),
+            Statement::KillQuery(s) => Ok(Plan::KillQuery(KillQueryPlan {
+                query_id: s.query_id,
+            })),
+            Statement::CreateSource(s) => planner.create_source_to_plan(s),
+            Statement::DropSource(s) => Ok(Plan::DropSource(DropSourcePlan {
+                if_exists: s.if_exists,
+                name: s.name,
+            })),
+            Statement::ShowSources => Ok(Plan::Show(ShowPlan::ShowSources)),
+            Statement::CreateContinuousQuery(s) => planner.create_continuous_query_to_plan(s),
+            Statement::DropContinuousQuery(s) => {
+                Ok(Plan::DropContinuousQuery(DropContinuousQueryPlan {
+                    if_exists: s.if_exists,
+                    name: s.name,
+                }))
+            }
+            Statement::CreateUser(s) => planner.create_user_to_plan(s),
+            Statement::DropUser(s) => Ok(Plan::DropUser(DropUserPlan {
+                if_exists: s.if_exists,
+                name: s.name,
+            })),
+            Statement::Grant(s) => planner.grant_to_plan(s),
+            Statement::Revoke(s) => Ok(Plan::Revoke(RevokePlan {
+                user: s.user,
+                catalog: s.catalog,
+            })),
+            Statement::CreateFunction(s) => Ok(Plan::CreateFunction(CreateFunctionPlan {
+                if_not_exists: s.if_not_exists,
+                name: s.name,
+                module: s.module,
+            })),
+            Statement::DropFunction(s) => Ok(Plan::DropFunction(DropFunctionPlan {
+                if_exists: s.if_exists,
+                name: s.name,
+            })),
         }
     }
 
@@ -358,7 +518,7 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
         // adapter and the SqlToRel in Planner, which is a self-referential
         // case. We wrap a PlannerDelegate to workaround this and avoid the usage of
         // pin.
-        let planner = PlannerDelegate::new(adapter);
+        let planner = PlannerDelegate::new(adapter, self.warnings.clone());
 
         expr.to_plan(planner.meta_provider, self.read_parallelism)
             .context(BuildPromPlanError)
@@ -367,7 +527,7 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
     pub fn remote_prom_req_to_plan(&self, query: PromRemoteQuery) -> Result<RemoteQueryPlan> {
         let adapter =
             ContextProviderAdapter::new(self.provider, self.read_parallelism, self.dyn_config);
-        let planner = PlannerDelegate::new(adapter);
+        let planner = PlannerDelegate::new(adapter, self.warnings.clone());
 
         remote_query_to_plan(query, planner.meta_provider).context(BuildPromPlanError)
     }
@@ -597,11 +757,27 @@ pub fn try_get_data_type_from_value(value: &PbValue) -> Result<DatumKind> {
 /// select/explain to datafusion's planner.
 pub(crate) struct PlannerDelegate<'a, P: MetaProvider> {
     meta_provider: ContextProviderAdapter<'a, P>,
+    warnings: QueryWarnings,
+    /// Whether the wildcards read all the columns regardless of the
+    /// [WildcardLimit].
+    all_columns: bool,
 }
 
 impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
-    pub(crate) fn new(meta_provider: ContextProviderAdapter<'a, P>) -> Self {
-        Self { meta_provider }
+    pub(crate) fn new(
+        meta_provider: ContextProviderAdapter<'a, P>,
+        warnings: QueryWarnings,
+    ) -> Self {
+        Self {
+            meta_provider,
+            warnings,
+            all_columns: false,
+        }
+    }
+
+    fn with_all_columns(mut self) -> Self {
+        self.all_columns = true;
+        self
     }
 
     pub(crate) fn sql_statement_to_plan(self, mut sql_stmt: SqlStatement) -> Result<Plan> {
@@ -612,11 +788,28 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
                 self.sql_statement_to_datafusion_plan(sql_stmt)
             }
             SqlStatement::Insert { .. } => self.insert_to_plan(sql_stmt),
+            SqlStatement::Copy { .. } => self.copy_to_plan(sql_stmt),
             _ => UnsupportedStatement.fail(),
         }
     }
 
     fn sql_statement_to_datafusion_plan(self, sql_stmt: SqlStatement) -> Result<Plan> {
+        self.sql_statement_to_query_plan(sql_stmt).map(Plan::Query)
+    }
+
+    fn sql_statement_to_query_plan(mut self, mut sql_stmt: SqlStatement) -> Result<QueryPlan> {
+        let table_snapshots = self.rewrite_table_snapshots(&mut sql_stmt)?;
+        self.meta_provider.set_table_snapshots(table_snapshots);
+        if !self.all_columns {
+            let limit = self.meta_provider.wildcard_limit();
+            if limit.max_columns > 0 {
+                self.limit_statement_wildcards(&mut sql_stmt, &limit)?;
+            }
+        }
+
+        let latest_per_series = self.rewrite_last_row(&mut sql_stmt)?;
+        let gap_fill = Self::rewrite_fill(&mut sql_stmt)?;
+
         let df_planner = SqlToRel::new_with_options(&self.meta_provider, DEFAULT_PARSER_OPTS);
         let table_name = parse_table_name_with_standard(&sql_stmt);
 
@@ -624,18 +817,581 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
             .sql_statement_to_plan(sql_stmt)
             .context(DatafusionPlan)?;
         let df_plan = optimize_plan(&df_plan).context(DatafusionPlan)?;
+        let df_plan = self.guard_time_range(df_plan)?;
+        let df_plan = match latest_per_series {
+            Some((series_columns, timestamp_column)) => LogicalPlan::Extension(Extension {
+                node: Arc::new(LatestPerSeriesNode {
+                    input: df_plan,
+                    series_columns,
+                    timestamp_column,
+                }),
+            }),
+            None => df_plan,
+        };
+        let df_plan = match gap_fill {
+            Some(gap_fill) => LogicalPlan::Extension(Extension {
+                node: Arc::new(GapFillNode {
+                    input: df_plan,
+                    series_columns: gap_fill.series_columns,
+                    time_column: gap_fill.time_column,
+                    interval: gap_fill.interval,
+                    policy: gap_fill.policy,
+                }),
+            }),
+            None => df_plan,
+        };
 
         debug!("Sql statement to datafusion plan, df_plan:\n{:#?}", df_plan);
 
         // Get all tables needed in the plan
         let tables = self.meta_provider.try_into_container().context(FindMeta)?;
-        Ok(Plan::Query(QueryPlan {
+        Ok(QueryPlan {
             df_plan,
             table_name,
             tables: Arc::new(tables),
+            offload: None,
+            explain_json: false,
+        })
+    }
+
+    /// Reject the queries reading the guarded tables without a bounded time
+    /// range, or make them read the data of the default range only, according
+    /// to the [TimeRangeGuard](crate::config::TimeRangeGuard).
+    fn guard_time_range(&self, df_plan: LogicalPlan) -> Result<LogicalPlan> {
+        let guard = self.meta_provider.time_range_guard();
+        if guard.tables.is_empty() {
+            return Ok(df_plan);
+        }
+
+        let now = Timestamp::now();
+        let tables =
+            time_range_guard::unguarded_tables(&df_plan, &guard, now).context(DatafusionPlan)?;
+        if tables.is_empty() {
+            return Ok(df_plan);
+        }
+        ensure!(
+            guard.action == TimeRangeAction::DefaultRange,
+            QueryWithoutTimeRange { tables }
+        );
+
+        warn!(
+            "Query without a bounded time range is limited to the default range, tables:{tables:?}, default_range:{}, max_range:{}",
+            guard.default_range, guard.max_range
+        );
+        self.warnings.record(
+            WarningKind::ImplicitLimit,
+            format!(
+                "query without a bounded time range is limited to the data of the last {}, tables:{tables:?}, add a timestamp predicate bounding the time range within {} to read the others",
+                guard.default_range, guard.max_range
+            ),
+        );
+
+        time_range_guard::apply_default_range(df_plan, &guard, now).context(DatafusionPlan)
+    }
+
+    /// Rewrite the `SELECT LAST_ROW(*) FROM t ... GROUP BY c1, c2` query into
+    /// `SELECT * FROM t ...`, and the series columns and the timestamp column
+    /// are returned to build the [LatestPerSeriesNode] on the rewritten query.
+    fn rewrite_last_row(
+        &self,
+        sql_stmt: &mut SqlStatement,
+    ) -> Result<Option<(Vec<String>, String)>> {
+        let query = match sql_stmt {
+            SqlStatement::Query(query) => query,
+            _ => return Ok(None),
+        };
+        let select = match query.body.as_mut() {
+            SetExpr::Select(select) => select,
+            _ => return Ok(None),
+        };
+        let is_last_row = match select.projection.as_slice() {
+            [SelectItem::UnnamedExpr(SqlExpr::Function(func))] => {
+                func.name.to_string().eq_ignore_ascii_case(LAST_ROW_FUNC)
+                    && matches!(
+                        func.args.as_slice(),
+                        [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)]
+                    )
+            }
+            _ => false,
+        };
+        if !is_last_row {
+            return Ok(None);
+        }
+
+        ensure!(
+            query.order_by.is_empty()
+                && query.limit.is_none()
+                && query.offset.is_none()
+                && query.fetch.is_none()
+                && select.having.is_none(),
+            UnsupportedLastRow {
+                msg: "ORDER BY, LIMIT and HAVING are not supported",
+            }
+        );
+        let series_columns = match &select.group_by {
+            GroupByExpr::Expressions(exprs) => exprs
+                .iter()
+                .map(|expr| match expr {
+                    SqlExpr::Identifier(ident) => Ok(ident.value.clone()),
+                    _ => UnsupportedLastRow {
+                        msg: format!("only columns are allowed in GROUP BY, expr:{expr}"),
+                    }
+                    .fail(),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            GroupByExpr::All => {
+                return UnsupportedLastRow {
+                    msg: "GROUP BY ALL is not supported",
+                }
+                .fail()
+            }
+        };
+        let table_ref = match select.from.as_slice() {
+            [TableWithJoins {
+                relation:
+                    TableFactor::Table {
+                        name, args: None, ..
+                    },
+                joins,
+            }] if joins.is_empty() => object_name_to_table_ref(name),
+            _ => None,
+        }
+        .context(UnsupportedLastRow {
+            msg: "only querying a single table is supported",
+        })?;
+        let table_name = table_ref.to_string();
+        let table = self
+            .meta_provider
+            .table(table_ref)
+            .context(MetaProviderFindTable)?
+            .context(TableNotFound { name: table_name })?
+            .table;
+
+        select.projection = vec![SelectItem::Wildcard(Default::default())];
+        select.group_by = GroupByExpr::Expressions(Vec::new());
+
+        Ok(Some((
+            series_columns,
+            table.schema().timestamp_name().to_string(),
+        )))
+    }
+
+    /// Remove the `fill(<policy>)` pseudo function (rewritten from the
+    /// `FILL(<policy>)` clause by the parser) from the GROUP BY clause, and
+    /// return what's needed to build the [GapFillNode] on the rewritten query.
+    ///
+    /// One of the GROUP BY expressions must be a `time_bucket` selected with
+    /// an alias, and the others are the series columns which must be selected
+    /// too.
+    fn rewrite_fill(sql_stmt: &mut SqlStatement) -> Result<Option<GapFill>> {
+        let query = match sql_stmt {
+            SqlStatement::Query(query) => query,
+            _ => return Ok(None),
+        };
+        let select = match query.body.as_mut() {
+            SetExpr::Select(select) => select,
+            _ => return Ok(None),
+        };
+        let group_by = match &mut select.group_by {
+            GroupByExpr::Expressions(exprs) => exprs,
+            GroupByExpr::All => return Ok(None),
+        };
+        let fill_idx = group_by.iter().position(|expr| match expr {
+            SqlExpr::Function(func) => func.name.to_string().eq_ignore_ascii_case(FILL_FUNC),
+            _ => false,
+        });
+        let fill = match fill_idx {
+            Some(idx) => group_by.remove(idx),
+            None => return Ok(None),
+        };
+        let group_by = group_by.clone();
+        let policy = Self::parse_fill_policy(&fill)?;
+
+        ensure!(
+            query.order_by.is_empty()
+                && query.limit.is_none()
+                && query.offset.is_none()
+                && query.fetch.is_none(),
+            UnsupportedFill {
+                msg: "ORDER BY and LIMIT are not supported",
+            }
+        );
+
+        let projection = &select.projection;
+        let find_alias = |expr: &SqlExpr| {
+            projection.iter().find_map(|item| match item {
+                SelectItem::ExprWithAlias { expr: e, alias } if e == expr => {
+                    Some((alias.value.clone(), e))
+                }
+                _ => None,
+            })
+        };
+        let find_aliased = |name: &str| {
+            projection.iter().find_map(|item| match item {
+                SelectItem::ExprWithAlias { expr, alias } if alias.value == name => {
+                    Some((alias.value.clone(), expr))
+                }
+                _ => None,
+            })
+        };
+        let is_selected = |name: &str| {
+            projection.iter().any(|item| match item {
+                SelectItem::UnnamedExpr(SqlExpr::Identifier(ident)) => ident.value == name,
+                SelectItem::Wildcard(_) => true,
+                _ => false,
+            })
+        };
+
+        let mut series_columns = Vec::with_capacity(group_by.len());
+        let mut time_bucket = None;
+        for group_expr in &group_by {
+            let (name, expr) = match group_expr {
+                SqlExpr::Identifier(ident) => match find_aliased(&ident.value) {
+                    Some(aliased) => aliased,
+                    None => {
+                        ensure!(
+                            is_selected(&ident.value),
+                            UnsupportedFill {
+                                msg: format!("GROUP BY column must be selected, column:{ident}"),
+                            }
+                        );
+                        series_columns.push(ident.value.clone());
+                        continue;
+                    }
+                },
+                expr => find_alias(expr).context(UnsupportedFill {
+                    msg: format!("GROUP BY expr must be selected with an alias, expr:{expr}"),
+                })?,
+            };
+
+            match Self::parse_time_bucket_interval(expr)? {
+                Some(interval) => {
+                    ensure!(
+                        time_bucket.is_none(),
+                        UnsupportedFill {
+                            msg: "only one time_bucket is allowed in GROUP BY",
+                        }
+                    );
+                    time_bucket = Some((name, interval));
+                }
+                None => series_columns.push(name),
+            }
+        }
+        let (time_column, interval) = time_bucket.context(UnsupportedFill {
+            msg: "time_bucket is required in GROUP BY",
+        })?;
+
+        Ok(Some(GapFill {
+            series_columns,
+            time_column,
+            interval,
+            policy,
         }))
     }
 
+    fn parse_fill_policy(fill: &SqlExpr) -> Result<FillPolicy> {
+        let arg = match fill {
+            SqlExpr::Function(func) => match func.args.as_slice() {
+                [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))] => Some(arg),
+                _ => None,
+            },
+            _ => None,
+        };
+        let parse_number = |n: &str| n.parse::<f64>().ok().map(FillPolicy::Value);
+        let policy = match arg {
+            Some(SqlExpr::Value(Value::Null)) => Some(FillPolicy::Null),
+            Some(SqlExpr::Value(Value::Number(n, _))) => parse_number(n),
+            Some(SqlExpr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            }) => match expr.as_ref() {
+                SqlExpr::Value(Value::Number(n, _)) => parse_number(&format!("-{n}")),
+                _ => None,
+            },
+            Some(SqlExpr::Identifier(ident)) if ident.value.eq_ignore_ascii_case("previous") => {
+                Some(FillPolicy::Previous)
+            }
+            Some(SqlExpr::Identifier(ident)) if ident.value.eq_ignore_ascii_case("linear") => {
+                Some(FillPolicy::Linear)
+            }
+            _ => None,
+        };
+
+        policy.context(UnsupportedFill {
+            msg: format!("policy must be one of null, previous, linear and a number, found:{fill}"),
+        })
+    }
+
+    /// Returns the bucket interval if the expr is a `time_bucket` call.
+    fn parse_time_bucket_interval(expr: &SqlExpr) -> Result<Option<i64>> {
+        let func = match expr {
+            SqlExpr::Function(func)
+                if func.name.to_string().eq_ignore_ascii_case(TIME_BUCKET_FUNC) =>
+            {
+                func
+            }
+            _ => return Ok(None),
+        };
+        let interval = match func.args.get(1) {
+            Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(SqlExpr::Value(
+                Value::SingleQuotedString(period),
+            )))) => parse_bucket_interval(period),
+            _ => None,
+        };
+
+        interval.map(Some).context(UnsupportedFill {
+            msg: format!("only periods of seconds, minutes and hours are supported, expr:{expr}"),
+        })
+    }
+
+    fn limit_statement_wildcards(
+        &self,
+        sql_stmt: &mut SqlStatement,
+        limit: &WildcardLimit,
+    ) -> Result<()> {
+        match sql_stmt {
+            SqlStatement::Query(query) => self.limit_query_wildcards(query, limit),
+            SqlStatement::Explain { statement, .. } => {
+                self.limit_statement_wildcards(statement, limit)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn limit_query_wildcards(&self, query: &mut Query, limit: &WildcardLimit) -> Result<()> {
+        if let Some(with) = &mut query.with {
+            for cte in &mut with.cte_tables {
+                self.limit_query_wildcards(&mut cte.query, limit)?;
+            }
+        }
+
+        self.limit_set_expr_wildcards(&mut query.body, limit)
+    }
+
+    fn limit_set_expr_wildcards(
+        &self,
+        set_expr: &mut SetExpr,
+        limit: &WildcardLimit,
+    ) -> Result<()> {
+        match set_expr {
+            SetExpr::Select(select) => self.limit_select_wildcards(select, limit),
+            SetExpr::Query(query) => self.limit_query_wildcards(query, limit),
+            SetExpr::SetOperation { left, right, .. } => {
+                self.limit_set_expr_wildcards(left, limit)?;
+                self.limit_set_expr_wildcards(right, limit)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the wildcards reading a table with more columns than
+    /// `max_columns` by its default columns, or reject them, according to the
+    /// [WildcardLimit].
+    fn limit_select_wildcards(&self, select: &mut Select, limit: &WildcardLimit) -> Result<()> {
+        for table_with_joins in &mut select.from {
+            let relations = iter::once(&mut table_with_joins.relation).chain(
+                table_with_joins
+                    .joins
+                    .iter_mut()
+                    .map(|join| &mut join.relation),
+            );
+            for relation in relations {
+                if let TableFactor::Derived { subquery, .. } = relation {
+                    self.limit_query_wildcards(subquery, limit)?;
+                }
+            }
+        }
+
+        let has_wildcard = select.projection.iter().any(|item| {
+            matches!(
+                item,
+                SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(_, _)
+            )
+        });
+        if !has_wildcard {
+            return Ok(());
+        }
+        // Only the wildcards reading a single table are limited, the columns of the
+        // joined tables are left to the datafusion.
+        let table_ref = match select.from.as_slice() {
+            [TableWithJoins {
+                relation:
+                    TableFactor::Table {
+                        name, args: None, ..
+                    },
+                joins,
+            }] if joins.is_empty() => object_name_to_table_ref(name),
+            _ => None,
+        };
+        let table = match table_ref {
+            Some(table_ref) => self
+                .meta_provider
+                .table(table_ref)
+                .context(MetaProviderFindTable)?,
+            None => None,
+        };
+        let table = match table {
+            Some(resolved) => resolved.table,
+            None => return Ok(()),
+        };
+
+        let schema = table.schema();
+        let num_columns = schema.num_columns();
+        if num_columns <= limit.max_columns {
+            return Ok(());
+        }
+        if limit.action == WildcardAction::Reject {
+            return WildcardOnWideTable {
+                table: table.name().to_string(),
+                num_columns,
+                max_columns: limit.max_columns,
+            }
+            .fail();
+        }
+
+        let columns = match limit.default_columns.get(table.name()) {
+            Some(columns) => columns.clone(),
+            None => schema
+                .columns()
+                .iter()
+                .filter(|column| column.is_tag || column.name == schema.timestamp_name())
+                .map(|column| column.name.clone())
+                .collect(),
+        };
+        warn!(
+            "Wildcard reading wide table is replaced by the default columns, table:{}, num_columns:{num_columns}, columns:{columns:?}",
+            table.name()
+        );
+        self.warnings.record(
+            WarningKind::ImplicitLimit,
+            format!(
+                "wildcard of wide table is limited to the default columns, table:{}, num_columns:{num_columns}, columns:{columns:?}, use SELECT ALL COLUMNS to read all of them",
+                table.name()
+            ),
+        );
+
+        let projection = mem::take(&mut select.projection);
+        for item in projection {
+            match item {
+                SelectItem::Wildcard(_) => {
+                    select.projection.extend(columns.iter().map(|column| {
+                        SelectItem::UnnamedExpr(SqlExpr::Identifier(Ident::with_quote(
+                            DEFAULT_QUOTE_CHAR,
+                            column,
+                        )))
+                    }));
+                }
+                SelectItem::QualifiedWildcard(prefix, _) => {
+                    select.projection.extend(columns.iter().map(|column| {
+                        let mut idents = prefix.0.clone();
+                        idents.push(Ident::with_quote(DEFAULT_QUOTE_CHAR, column));
+                        SelectItem::UnnamedExpr(SqlExpr::CompoundIdentifier(idents))
+                    }));
+                }
+                item => select.projection.push(item),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite `table_snapshot('t', <timestamp>)` in the statement into the
+    /// table `t` and returns the snapshot time of such tables.
+    fn rewrite_table_snapshots(&self, sql_stmt: &mut SqlStatement) -> Result<TableSnapshots> {
+        let mut rewriter = TableSnapshotRewriter::default();
+        if let ControlFlow::Break(e) = sql_stmt.visit(&mut rewriter) {
+            return Err(e);
+        }
+
+        let default_catalog = self.meta_provider.default_catalog_name();
+        let default_schema = self.meta_provider.default_schema_name();
+        let mut table_snapshots = TableSnapshots::with_capacity(rewriter.snapshots.len());
+        for (name, snapshot_time) in rewriter.snapshots {
+            let key = resolved_table_key(name, default_catalog, default_schema);
+            if let Some(prev) = table_snapshots.insert(key.clone(), snapshot_time) {
+                ensure!(
+                    prev == snapshot_time,
+                    InvalidTableSnapshot {
+                        msg: format!("table {key} is read at different times"),
+                    }
+                );
+            }
+        }
+        // The rewritten table is indistinguishable from the normal one during
+        // planning.
+        for name in rewriter.tables {
+            let key = resolved_table_key(name, default_catalog, default_schema);
+            ensure!(
+                !table_snapshots.contains_key(&key),
+                InvalidTableSnapshot {
+                    msg: format!("table {key} is read both by table_snapshot and directly"),
+                }
+            );
+        }
+
+        Ok(table_snapshots)
+    }
+
+    /// Plan `COPY (query) TO 'path'`, whose results are written to the result
+    /// store as parquet files rather than returned to the client.
+    fn copy_to_plan(self, sql_stmt: SqlStatement) -> Result<Plan> {
+        let SqlStatement::Copy {
+            source,
+            to,
+            target,
+            options,
+            ..
+        } = sql_stmt
+        else {
+            return UnsupportedStatement.fail();
+        };
+
+        ensure!(
+            to,
+            UnsupportedCopy {
+                msg: "only COPY TO is supported",
+            }
+        );
+        let query = match source {
+            CopySource::Query(query) => query,
+            CopySource::Table { .. } => {
+                return UnsupportedCopy {
+                    msg: "only query is supported as the source",
+                }
+                .fail()
+            }
+        };
+        let path = match target {
+            CopyTarget::File { filename } => filename,
+            other => {
+                return UnsupportedCopy {
+                    msg: format!("unsupported target:{other}"),
+                }
+                .fail()
+            }
+        };
+        for option in options {
+            match option {
+                CopyOption::Format(format) if format.value.eq_ignore_ascii_case("parquet") => {}
+                other => {
+                    return UnsupportedCopy {
+                        msg: format!("unsupported option:{other}"),
+                    }
+                    .fail()
+                }
+            }
+        }
+
+        validate_copy_path(&path)?;
+
+        let mut query_stmt = SqlStatement::Query(query);
+        normalize_func_name(&mut query_stmt);
+        let mut plan = self.sql_statement_to_query_plan(query_stmt)?;
+        plan.offload = Some(ResultOffload { path });
+
+        Ok(Plan::Query(plan))
+    }
+
     fn tsid_column_schema() -> Result<ColumnSchema> {
         column_schema::Builder::new(TSID_COLUMN.to_string(), DatumKind::UInt64)
             .is_nullable(false)
@@ -824,11 +1580,12 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         )?;
 
         let partition_info = match stmt.partition {
-            Some(p) => Some(PartitionParser::parse(p)?),
+            Some(p) => Some(PartitionParser::parse(p, &table_schema)?),
             None => None,
         };
 
         let options = parse_options(stmt.options)?;
+        ensure_transform_pipeline_valid(&options, &table_schema)?;
 
         // ensure default value options are valid
         ensure_column_default_value_valid(table_schema.columns(), &self.meta_provider)?;
@@ -975,7 +1732,7 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
                             } else {
                                 // Column can not be null and input does not contains that column
                                 return InsertMissingColumn {
-                                    table: table.name(),
+                                    table: table.name().to_string(),
                                     column: &column.name,
                                 }
                                 .fail();
@@ -992,34 +1749,176 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
                     default_value_map,
                 }))
             }
-            // We already known this stmt is a INSERT stmt
-            _ => unreachable!(),
-        }
+            // We already known this stmt is a INSERT stmt
+            _ => unreachable!(),
+        }
+    }
+
+    fn alter_modify_setting_to_plan(&self, stmt: AlterModifySetting) -> Result<Plan> {
+        let table_name = stmt.table_name.to_string();
+
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let options = parse_options(stmt.options)?;
+        ensure_transform_pipeline_valid(&options, &table.schema())?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::ModifySetting(options),
+            dry_run: stmt.dry_run,
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn alter_add_column_to_plan(&self, stmt: AlterAddColumn) -> Result<Plan> {
+        let table_name = stmt.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::AddColumn(parse_columns(stmt.columns)?),
+            dry_run: stmt.dry_run,
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn alter_drop_column_to_plan(&self, stmt: AlterDropColumn) -> Result<Plan> {
+        let table_name = stmt.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::DropColumn(stmt.columns),
+            dry_run: stmt.dry_run,
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn alter_rename_column_to_plan(&self, stmt: AlterRenameColumn) -> Result<Plan> {
+        let table_name = stmt.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::RenameColumn {
+                from: stmt.from,
+                to: stmt.to,
+            },
+            dry_run: stmt.dry_run,
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn alter_modify_column_to_plan(&self, stmt: AlterModifyColumn) -> Result<Plan> {
+        let table_name = stmt.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::ModifyColumn {
+                name: stmt.column,
+                display_name: stmt.display_name,
+                unit: stmt.unit,
+            },
+            dry_run: stmt.dry_run,
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn alter_swap_table_to_plan(&self, stmt: AlterSwapTable) -> Result<Plan> {
+        let table = stmt.table_name.to_string();
+        let other = stmt.other.to_string();
+        ensure!(
+            table != other,
+            InvalidSwapTable {
+                msg: format!("table can't be swapped with itself, table:{table}"),
+            }
+        );
+        for name in [&table, &other] {
+            let found = self.find_table(name)?.context(TableNotFound { name })?;
+            ensure!(
+                found.partition_info().is_none(),
+                InvalidSwapTable {
+                    msg: format!("partitioned table can't be swapped, table:{name}"),
+                }
+            );
+        }
+
+        Ok(Plan::SwapTables(SwapTablesPlan { table, other }))
+    }
+
+    fn analyze_table_to_plan(&self, stmt: AnalyzeTable) -> Result<Plan> {
+        let table_name = stmt.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let operation = match stmt.operation {
+            AnalyzeOperation::Refresh => AnalyzeTableOperation::Refresh,
+            AnalyzeOperation::UpdateHistogram {
+                columns,
+                num_buckets,
+            } => {
+                let schema = table.schema();
+                for column in &columns {
+                    let idx = schema.index_of(column).with_context(|| InvalidAnalyze {
+                        msg: format!("column not found, column:{column}"),
+                    })?;
+                    ensure!(
+                        idx != schema.timestamp_index(),
+                        InvalidAnalyze {
+                            msg: format!("timestamp column can't have histogram, column:{column}"),
+                        }
+                    );
+                }
+                let num_buckets = num_buckets
+                    .map(|v| v as usize)
+                    .unwrap_or(histogram::DEFAULT_NUM_BUCKETS);
+                ensure!(
+                    num_buckets > 0 && num_buckets <= histogram::MAX_NUM_BUCKETS,
+                    InvalidAnalyze {
+                        msg: format!(
+                            "number of buckets should be in [1, {}], num_buckets:{num_buckets}",
+                            histogram::MAX_NUM_BUCKETS
+                        ),
+                    }
+                );
+
+                AnalyzeTableOperation::UpdateHistogram {
+                    columns,
+                    num_buckets,
+                }
+            }
+            AnalyzeOperation::DropHistogram { columns } => {
+                AnalyzeTableOperation::DropHistogram { columns }
+            }
+        };
+
+        Ok(Plan::AnalyzeTable(AnalyzeTablePlan { table, operation }))
     }
 
-    fn alter_modify_setting_to_plan(&self, stmt: AlterModifySetting) -> Result<Plan> {
+    fn compact_table_to_plan(&self, stmt: CompactTable) -> Result<Plan> {
         let table_name = stmt.table_name.to_string();
-
         let table = self
             .find_table(&table_name)?
             .context(TableNotFound { name: table_name })?;
-        let plan = AlterTablePlan {
-            table,
-            operations: AlterTableOperation::ModifySetting(parse_options(stmt.options)?),
-        };
-        Ok(Plan::AlterTable(plan))
+
+        Ok(Plan::CompactTable(CompactTablePlan { table }))
     }
 
-    fn alter_add_column_to_plan(&self, stmt: AlterAddColumn) -> Result<Plan> {
+    fn resync_table_to_plan(&self, stmt: ResyncTable) -> Result<Plan> {
         let table_name = stmt.table_name.to_string();
         let table = self
             .find_table(&table_name)?
             .context(TableNotFound { name: table_name })?;
-        let plan = AlterTablePlan {
+
+        Ok(Plan::ResyncTable(ResyncTablePlan {
             table,
-            operations: AlterTableOperation::AddColumn(parse_columns(stmt.columns)?),
-        };
-        Ok(Plan::AlterTable(plan))
+            consumer: stmt.consumer,
+        }))
     }
 
     fn exists_table_to_plan(&self, stmt: ExistsTable) -> Result<Plan> {
@@ -1030,6 +1929,157 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         }
     }
 
+    fn create_continuous_query_to_plan(self, stmt: CreateContinuousQuery) -> Result<Plan> {
+        let parse_duration = |v: &str| {
+            time_ext::parse_duration(v)
+                .map(|v| v.0)
+                .ok()
+                .with_context(|| InvalidContinuousQueryDef {
+                    msg: format!("invalid duration:{v}"),
+                })
+        };
+        let every = parse_duration(&stmt.every)?;
+        ensure!(
+            every.as_millis() > 0,
+            InvalidContinuousQueryDef {
+                msg: format!("interval must be at least 1ms, every:{}", stmt.every),
+            }
+        );
+        let delay = stmt
+            .delay
+            .as_deref()
+            .map(parse_duration)
+            .transpose()?
+            .unwrap_or_default();
+
+        let source_table =
+            continuous_query::source_table(&stmt.query).context(InvalidContinuousQuery)?;
+        let table = self.find_table(&source_table)?.context(TableNotFound {
+            name: &source_table,
+        })?;
+        let target_table = stmt.target.to_string();
+        ensure!(
+            target_table != source_table,
+            InvalidContinuousQueryDef {
+                msg: "target table must be different from the source table",
+            }
+        );
+        let time_column = table.schema().timestamp_name().to_string();
+
+        // Plan the query of the first window to make sure it's valid.
+        let query = stmt.query.to_string();
+        let mut window = stmt.query;
+        continuous_query::restrict_to_window(
+            &mut window,
+            &time_column,
+            0,
+            every.as_millis() as i64,
+        )
+        .context(InvalidContinuousQuery)?;
+        self.sql_statement_to_query_plan(SqlStatement::Query(window))?;
+
+        Ok(Plan::CreateContinuousQuery(CreateContinuousQueryPlan {
+            if_not_exists: stmt.if_not_exists,
+            continuous_query: ContinuousQueryDef {
+                name: stmt.name,
+                source_table,
+                target_table,
+                time_column,
+                every,
+                delay,
+                query,
+            },
+        }))
+    }
+
+    fn create_user_to_plan(&self, stmt: CreateUser) -> Result<Plan> {
+        ensure!(
+            !stmt.password.is_empty(),
+            InvalidUser {
+                msg: format!("password of user {} is empty", stmt.name),
+            }
+        );
+
+        Ok(Plan::CreateUser(CreateUserPlan {
+            if_not_exists: stmt.if_not_exists,
+            name: stmt.name,
+            password: stmt.password,
+        }))
+    }
+
+    fn grant_to_plan(&self, stmt: Grant) -> Result<Plan> {
+        let role = stmt
+            .role
+            .parse::<UserRole>()
+            .map_err(|msg| Error::InvalidUser { msg })?;
+
+        Ok(Plan::Grant(GrantPlan {
+            user: stmt.user,
+            catalog: stmt.catalog,
+            role,
+        }))
+    }
+
+    fn create_source_to_plan(&self, stmt: CreateSource) -> Result<Plan> {
+        let (brokers, topic) = stmt
+            .uri
+            .rsplit_once('/')
+            .filter(|(brokers, topic)| !brokers.is_empty() && !topic.is_empty())
+            .with_context(|| InvalidSource {
+                msg: format!("expect uri like `broker1,broker2/topic`, uri:{}", stmt.uri),
+            })?;
+        let brokers = brokers.split(',').map(|b| b.trim().to_string()).collect();
+
+        let format = match stmt.format.as_str() {
+            SOURCE_FORMAT_JSON => SourceFormat::Json,
+            format => {
+                return InvalidSource {
+                    msg: format!("unsupported format:{format}"),
+                }
+                .fail()
+            }
+        };
+
+        let mut tags = Vec::new();
+        let mut timestamp_field = DEFAULT_SOURCE_TIMESTAMP_FIELD.to_string();
+        for (key, value) in parse_options(stmt.options)? {
+            match key.as_str() {
+                SOURCE_OPTION_TAGS => {
+                    tags = value
+                        .split(',')
+                        .map(|tag| tag.trim().to_string())
+                        .filter(|tag| !tag.is_empty())
+                        .collect();
+                }
+                SOURCE_OPTION_TIMESTAMP_FIELD => timestamp_field = value,
+                _ => {
+                    return InvalidSource {
+                        msg: format!("unknown option:{key}"),
+                    }
+                    .fail()
+                }
+            }
+        }
+
+        let source = SourceDef {
+            name: stmt.name,
+            table: stmt
+                .table_name
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| topic.to_string()),
+            brokers,
+            topic: topic.to_string(),
+            format,
+            tags,
+            timestamp_field,
+        };
+
+        Ok(Plan::CreateSource(CreateSourcePlan {
+            if_not_exists: stmt.if_not_exists,
+            source,
+        }))
+    }
+
     fn show_create_to_plan(&self, show_create: ShowCreate) -> Result<Plan> {
         let table_name = show_create.table_name.to_string();
         let table = self
@@ -1042,6 +2092,15 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         Ok(Plan::Show(ShowPlan::ShowCreatePlan(plan)))
     }
 
+    fn show_partitions_to_plan(&self, show_partitions: ShowPartitions) -> Result<Plan> {
+        let table_name = show_partitions.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let plan = ShowPartitionsPlan { table };
+        Ok(Plan::Show(ShowPlan::ShowPartitions(plan)))
+    }
+
     fn show_tables_to_plan(&self, show_tables: ShowTables) -> Result<Plan> {
         let plan = ShowTablesPlan {
             pattern: show_tables.pattern,
@@ -1064,6 +2123,35 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
     }
 }
 
+/// The path of `COPY TO` must be a relative path under the result store, so
+/// the results can't be written to the other places of the store.
+fn validate_copy_path(path: &str) -> Result<()> {
+    let invalid = |msg: &str| {
+        InvalidCopyPath {
+            path: path.to_string(),
+            msg: msg.to_string(),
+        }
+        .fail()
+    };
+
+    if path.is_empty() {
+        return invalid("path is empty");
+    }
+    if path.starts_with('/') || path.contains("://") {
+        return invalid("only relative path is allowed");
+    }
+    if path.chars().any(|c| c == '\\' || c.is_control()) {
+        return invalid("path contains invalid characters");
+    }
+    for part in path.trim_end_matches('/').split('/') {
+        if part.is_empty() || part == "." || part == ".." {
+            return invalid("path contains empty, `.` or `..` parts");
+        }
+    }
+
+    Ok(())
+}
+
 // Datafusion only support lower-case function name when
 // `enable_ident_normalization` is `true`, but we want to
 // function case-insensitive, so add this normalization.
@@ -1092,6 +2180,131 @@ fn normalize_func_name(sql_stmt: &mut SqlStatement) {
     });
 }
 
+/// Table function to read the ssts of a table as of a point in time.
+pub(crate) const TABLE_SNAPSHOT_FUNC: &str = "table_snapshot";
+
+/// Rewrites `table_snapshot('t', <timestamp>)` into table `t`, and collects
+/// the tables read in the statement.
+#[derive(Default)]
+struct TableSnapshotRewriter {
+    snapshots: Vec<(TableReference<'static>, Timestamp)>,
+    tables: Vec<TableReference<'static>>,
+}
+
+impl VisitorMut for TableSnapshotRewriter {
+    type Break = Error;
+
+    fn pre_visit_table_factor(&mut self, table_factor: &mut TableFactor) -> ControlFlow<Error> {
+        let TableFactor::Table { name, args, .. } = table_factor else {
+            return ControlFlow::Continue(());
+        };
+
+        let is_snapshot = args.is_some()
+            && name.0.len() == 1
+            && name.0[0].value.eq_ignore_ascii_case(TABLE_SNAPSHOT_FUNC);
+        if !is_snapshot {
+            if let Some(table_ref) = object_name_to_table_ref(name) {
+                self.tables.push(table_ref);
+            }
+            return ControlFlow::Continue(());
+        }
+
+        match parse_table_snapshot_args(args.take().unwrap_or_default()) {
+            Ok((table_name, snapshot_time)) => {
+                let parts = table_name
+                    .split('.')
+                    .map(|part| Ident::with_quote('`', part))
+                    .collect();
+                *name = ObjectName(parts);
+                match object_name_to_table_ref(name) {
+                    Some(table_ref) => {
+                        self.snapshots.push((table_ref, snapshot_time));
+                        ControlFlow::Continue(())
+                    }
+                    None => ControlFlow::Break(Error::InvalidTableSnapshot {
+                        msg: format!("invalid table name:{table_name}"),
+                    }),
+                }
+            }
+            Err(e) => ControlFlow::Break(e),
+        }
+    }
+}
+
+fn object_name_to_table_ref(name: &ObjectName) -> Option<TableReference<'static>> {
+    match name.0.as_slice() {
+        [table] => Some(TableReference::bare(table.value.clone())),
+        [schema, table] => Some(TableReference::partial(
+            schema.value.clone(),
+            table.value.clone(),
+        )),
+        [catalog, schema, table] => Some(TableReference::full(
+            catalog.value.clone(),
+            schema.value.clone(),
+            table.value.clone(),
+        )),
+        _ => None,
+    }
+}
+
+/// Parse the table name and snapshot time from the arguments of
+/// `table_snapshot`, the time can be a timestamp string or milliseconds.
+fn parse_table_snapshot_args(args: Vec<FunctionArg>) -> Result<(String, Timestamp)> {
+    let mut exprs = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => exprs.push(expr),
+            other => {
+                return InvalidTableSnapshot {
+                    msg: format!("unexpected argument:{other}"),
+                }
+                .fail()
+            }
+        }
+    }
+
+    let [table, time]: [Expr; 2] = exprs.try_into().map_err(|_| Error::InvalidTableSnapshot {
+        msg: "expect arguments: (table, timestamp)".to_string(),
+    })?;
+    let table_name = match table {
+        Expr::Value(Value::SingleQuotedString(s)) | Expr::Identifier(Ident { value: s, .. }) => s,
+        other => {
+            return InvalidTableSnapshot {
+                msg: format!("invalid table:{other}"),
+            }
+            .fail()
+        }
+    };
+    let snapshot_time = match time {
+        Expr::Value(Value::SingleQuotedString(s)) => match parse_timestamp_ms(&s) {
+            Ok(ScalarValue::TimestampMillisecond(Some(ts), _)) => Timestamp::new(ts),
+            _ => {
+                return InvalidTableSnapshot {
+                    msg: format!("invalid timestamp:{s}"),
+                }
+                .fail()
+            }
+        },
+        Expr::Value(Value::Number(n, _)) => match n.parse::<i64>() {
+            Ok(ts) => Timestamp::new(ts),
+            Err(_) => {
+                return InvalidTableSnapshot {
+                    msg: format!("invalid timestamp:{n}"),
+                }
+                .fail()
+            }
+        },
+        other => {
+            return InvalidTableSnapshot {
+                msg: format!("invalid timestamp:{other}"),
+            }
+            .fail()
+        }
+    };
+
+    Ok((table_name, snapshot_time))
+}
+
 #[derive(Debug)]
 enum InsertMode {
     // Insert the value in expr with given index directly.
@@ -1284,6 +2497,8 @@ fn parse_column(col: &ColumnDef) -> Result<ColumnSchema> {
     let mut is_unsign = false;
     let mut comment = String::new();
     let mut default_value = None;
+    let mut display_name = String::new();
+    let mut unit = String::new();
     for option_def in &col.options {
         if matches!(option_def.option, ColumnOption::NotNull) {
             is_nullable = false;
@@ -1297,6 +2512,10 @@ fn parse_column(col: &ColumnDef) -> Result<ColumnSchema> {
             default_value = Some(default_value_expr);
         } else if let Some(v) = parser::get_column_comment(&option_def.option) {
             comment = v;
+        } else if let Some(v) = parser::get_column_display_name(&option_def.option) {
+            display_name = v;
+        } else if let Some(v) = parser::get_column_unit(&option_def.option) {
+            unit = v;
         }
     }
 
@@ -1311,7 +2530,9 @@ fn parse_column(col: &ColumnDef) -> Result<ColumnSchema> {
         .is_tag(is_tag)
         .is_dictionary(is_dictionary)
         .comment(comment)
-        .default_value(default_value);
+        .default_value(default_value)
+        .display_name(display_name)
+        .unit(unit);
 
     builder.build().context(InvalidColumnSchema {
         column_name: &col.name.value,
@@ -1319,6 +2540,18 @@ fn parse_column(col: &ColumnDef) -> Result<ColumnSchema> {
 }
 
 // Ensure default value option of columns are valid.
+/// The steps of the transform pipeline must be valid on the table schema.
+fn ensure_transform_pipeline_valid(
+    options: &HashMap<String, String>,
+    schema: &Schema,
+) -> Result<()> {
+    if let Some(pipeline) = options.get(TRANSFORM_PIPELINE) {
+        Pipeline::parse(pipeline, schema).context(InvalidTransformPipeline)?;
+    }
+
+    Ok(())
+}
+
 fn ensure_column_default_value_valid<P: MetaProvider>(
     columns: &[ColumnSchema],
     meta_provider: &ContextProviderAdapter<'_, P>,
@@ -1514,6 +2747,8 @@ pub mod tests {
                         comment: "",
                         escaped_name: "c1",
                         default_value: None,
+                        display_name: "",
+                        unit: "",
                     },
                     ColumnSchema {
                         id: 2,
@@ -1525,6 +2760,8 @@ pub mod tests {
                         comment: "",
                         escaped_name: "ts",
                         default_value: None,
+                        display_name: "",
+                        unit: "",
                     },
                     ColumnSchema {
                         id: 3,
@@ -1536,6 +2773,8 @@ pub mod tests {
                         comment: "",
                         escaped_name: "c3",
                         default_value: None,
+                        display_name: "",
+                        unit: "",
                     },
                     ColumnSchema {
                         id: 4,
@@ -1554,6 +2793,8 @@ pub mod tests {
                                 ),
                             ),
                         ),
+                        display_name: "",
+                        unit: "",
                     },
                     ColumnSchema {
                         id: 5,
@@ -1581,6 +2822,8 @@ pub mod tests {
                                 ),
                             },
                         ),
+                        display_name: "",
+                        unit: "",
                     },
                     ColumnSchema {
                         id: 6,
@@ -1599,6 +2842,8 @@ pub mod tests {
                                 },
                             ),
                         ),
+                        display_name: "",
+                        unit: "",
                     },
                 ],
             },
@@ -1660,11 +2905,290 @@ pub mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_last_row_statement_to_plan() {
+        let sql = "select last_row(*) from test_table where field1 > 1 group by key1;";
+        let plan = sql_to_logical_plan(sql).unwrap();
+        let Plan::Query(query_plan) = plan else {
+            panic!("Last row should be planned as query");
+        };
+        let LogicalPlan::Extension(extension) = &query_plan.df_plan else {
+            panic!("Last row should be planned as extension");
+        };
+        let node = extension
+            .node
+            .as_any()
+            .downcast_ref::<LatestPerSeriesNode>()
+            .unwrap();
+        assert_eq!(node.series_columns, vec!["key1".to_string()]);
+        assert_eq!(node.timestamp_column, "key2");
+
+        let sql = "select last_row(*) from test_table group by key1 limit 10;";
+        assert!(sql_to_logical_plan(sql).is_err());
+        let sql = "select last_row(*) from test_table group by key1 + 1;";
+        assert!(sql_to_logical_plan(sql).is_err());
+    }
+
+    #[test]
+    fn test_rewrite_fill() {
+        let rewrite = |sql: &str| {
+            let mut statements = Parser::parse_sql(sql).unwrap();
+            let mut statement = match statements.remove(0) {
+                Statement::Standard(s) => *s,
+                s => panic!("unexpected statement:{s:?}"),
+            };
+            Planner::<MockMetaProvider>::rewrite_fill(&mut statement)
+                .map(|gap_fill| (gap_fill, statement.to_string()))
+        };
+
+        let sql = "select time_bucket(key2, 'PT1M') as t, key1, avg(field1) from test_table \
+                   group by t, key1 fill(linear)";
+        let (gap_fill, rewritten) = rewrite(sql).unwrap();
+        let gap_fill = gap_fill.unwrap();
+        assert_eq!(gap_fill.series_columns, vec!["key1".to_string()]);
+        assert_eq!(gap_fill.time_column, "t");
+        assert_eq!(gap_fill.interval, 60_000);
+        assert_eq!(gap_fill.policy, FillPolicy::Linear);
+        assert!(rewritten.ends_with("GROUP BY t, key1"), "{rewritten}");
+
+        let sql = "select time_bucket(key2, 'PT30S') as t, avg(field1) from test_table \
+                   group by time_bucket(key2, 'PT30S') fill(-1.5)";
+        let (gap_fill, _) = rewrite(sql).unwrap();
+        let gap_fill = gap_fill.unwrap();
+        assert_eq!(gap_fill.interval, 30_000);
+        assert_eq!(gap_fill.policy, FillPolicy::Value(-1.5));
+
+        let sql = "select key1, avg(field1) from test_table group by key1";
+        assert!(rewrite(sql).unwrap().0.is_none());
+
+        let invalid_sqls = [
+            // No time bucket.
+            "select key1, avg(field1) from test_table group by key1 fill(null)",
+            // Unknown policy.
+            "select time_bucket(key2, 'PT1M') as t, avg(field1) from test_table \
+             group by t fill(next)",
+            // Unsupported period.
+            "select time_bucket(key2, 'P1D') as t, avg(field1) from test_table \
+             group by t fill(previous)",
+            // Series column not selected.
+            "select time_bucket(key2, 'PT1M') as t, avg(field1) from test_table \
+             group by t, key1 fill(0)",
+            "select time_bucket(key2, 'PT1M') as t, avg(field1) from test_table \
+             group by t fill(0) limit 10",
+        ];
+        for sql in invalid_sqls {
+            assert!(rewrite(sql).is_err(), "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_explain_json_statement_to_plan() {
+        for (sql, explain_json) in [
+            ("explain (format json) analyze select key1 from test_table", true),
+            ("explain select key1 from test_table", false),
+        ] {
+            let Plan::Query(query_plan) = sql_to_logical_plan(sql).unwrap() else {
+                panic!("Explain should be planned as query, sql:{sql}");
+            };
+            assert_eq!(explain_json, query_plan.explain_json, "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_copy_statement_to_plan() {
+        let sql = "COPY (select key1 from test_table) TO 'exports/test_table';";
+        let plan = sql_to_logical_plan(sql).unwrap();
+        let Plan::Query(query_plan) = plan else {
+            panic!("Copy should be planned as query");
+        };
+        assert_eq!(
+            query_plan.offload,
+            Some(ResultOffload {
+                path: "exports/test_table".to_string()
+            })
+        );
+
+        let sql = "COPY (select key1 from test_table) TO 'exports/test_table' (FORMAT parquet);";
+        assert!(sql_to_logical_plan(sql).is_ok());
+
+        let sql = "COPY (select key1 from test_table) TO 'exports/test_table' (FORMAT csv);";
+        assert!(sql_to_logical_plan(sql).is_err());
+
+        let sql = "COPY test_table TO 'exports/test_table';";
+        assert!(sql_to_logical_plan(sql).is_err());
+
+        for path in [
+            "",
+            "/exports",
+            "s3://bucket/exports",
+            "exports/../data",
+            "exports//t",
+        ] {
+            let sql = format!("COPY (select key1 from test_table) TO '{path}';");
+            assert!(sql_to_logical_plan(&sql).is_err(), "path:{path}");
+        }
+    }
+
+    #[test]
+    fn test_table_snapshot_to_plan() {
+        let sql = "select key1 from table_snapshot('test_table', 1000);";
+        let plan = sql_to_logical_plan(sql).unwrap();
+        let Plan::Query(query_plan) = plan else {
+            panic!("It should be query plan");
+        };
+        assert_eq!(query_plan.table_name.as_deref(), Some("test_table"));
+        let planned_table = query_plan.tables.get(get_table_ref("test_table")).unwrap();
+        assert_eq!(planned_table.snapshot_time, Some(Timestamp::new(1000)));
+
+        let sql = "select key1 from table_snapshot('test_table', '2023-01-01T00:00:00Z');";
+        assert!(sql_to_logical_plan(sql).is_ok());
+
+        // Mix snapshot and normal read of the same table.
+        let sql = "select t1.key1 from table_snapshot('test_table', 1000) t1 join test_table t2 on t1.key1 = t2.key1;";
+        assert!(sql_to_logical_plan(sql).is_err());
+
+        let sql = "select key1 from table_snapshot('test_table');";
+        assert!(sql_to_logical_plan(sql).is_err());
+
+        let sql = "select key1 from table_snapshot('test_table', 'yesterday');";
+        assert!(sql_to_logical_plan(sql).is_err());
+    }
+
+    #[test]
+    fn test_wildcard_limit() {
+        let query_columns = |sql: &str, dyn_config: &DynamicConfig| {
+            let plan = sql_to_logical_plan_with_config(sql, dyn_config).unwrap();
+            let Plan::Query(query_plan) = plan else {
+                panic!("It should be query plan");
+            };
+            query_plan
+                .df_plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect::<Vec<_>>()
+        };
+
+        let dyn_config = DynamicConfig::default();
+        *dyn_config.wildcard_limit.write().unwrap() = WildcardLimit {
+            max_columns: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            query_columns("select * from cpu", &dyn_config),
+            vec!["time", "tag1", "tag2"]
+        );
+        assert_eq!(
+            query_columns("select cpu.*, value from cpu", &dyn_config),
+            vec!["time", "tag1", "tag2", "value"]
+        );
+        assert_eq!(
+            query_columns("select all columns from cpu", &dyn_config).len(),
+            6
+        );
+        // Tables with fewer columns are not limited.
+        dyn_config.wildcard_limit.write().unwrap().max_columns = 6;
+        assert_eq!(query_columns("select * from cpu", &dyn_config).len(), 6);
+
+        dyn_config
+            .wildcard_limit
+            .write()
+            .unwrap()
+            .default_columns
+            .insert(
+                "cpu".to_string(),
+                vec!["time".to_string(), "value".to_string()],
+            );
+        dyn_config.wildcard_limit.write().unwrap().max_columns = 3;
+        assert_eq!(
+            query_columns("select * from (select * from cpu)", &dyn_config),
+            vec!["time", "value"]
+        );
+
+        dyn_config.wildcard_limit.write().unwrap().action = WildcardAction::Reject;
+        assert!(matches!(
+            sql_to_logical_plan_with_config("select * from cpu", &dyn_config),
+            Err(Error::WildcardOnWideTable { .. })
+        ));
+        assert_eq!(
+            query_columns("select all columns from cpu", &dyn_config).len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_time_range_guard() {
+        let is_guarded = |sql: &str, dyn_config: &DynamicConfig| {
+            let plan = sql_to_logical_plan_with_config(sql, dyn_config).unwrap();
+            let Plan::Query(query_plan) = plan else {
+                panic!("It should be query plan");
+            };
+            query_plan
+                .df_plan
+                .display_indent()
+                .to_string()
+                .contains("Filter: cpu.time >= TimestampMillisecond(")
+        };
+
+        let dyn_config = DynamicConfig::default();
+        assert!(!is_guarded("select * from cpu", &dyn_config));
+
+        dyn_config.time_range_guard.write().unwrap().tables = vec!["cpu".to_string()];
+        assert!(is_guarded("select * from cpu", &dyn_config));
+        assert!(is_guarded("select value from cpu", &dyn_config));
+        assert!(is_guarded(
+            "select * from cpu where tag1 = 'a'",
+            &dyn_config
+        ));
+        // The predicates not bounding the time range within the max range.
+        assert!(is_guarded(
+            "select * from cpu where time > 1000",
+            &dyn_config
+        ));
+        assert!(is_guarded(
+            "select * from cpu where time < 1000",
+            &dyn_config
+        ));
+        assert!(is_guarded(
+            "select * from cpu where time is not null",
+            &dyn_config
+        ));
+        assert!(is_guarded(
+            "select * from cpu where time >= 1000 or tag1 = 'a'",
+            &dyn_config
+        ));
+        let sql = "select * from cpu where time >= 1000 and time < 1000000000";
+        assert!(is_guarded(sql, &dyn_config));
+        let sql = "select * from cpu where time >= 1000 and time < 2000";
+        assert!(!is_guarded(sql, &dyn_config));
+        // The time range without an upper bound ends at now.
+        let start = Timestamp::now().as_i64() - 1000;
+        let sql = format!("select * from cpu where time >= {start}");
+        assert!(!is_guarded(&sql, &dyn_config));
+        // Only the guarded tables are limited.
+        assert!(!is_guarded("select * from test_table", &dyn_config));
+
+        dyn_config.time_range_guard.write().unwrap().action = TimeRangeAction::Reject;
+        assert!(matches!(
+            sql_to_logical_plan_with_config("select * from cpu", &dyn_config),
+            Err(Error::QueryWithoutTimeRange { .. })
+        ));
+        let sql = "select * from cpu where time > 1000";
+        assert!(matches!(
+            sql_to_logical_plan_with_config(sql, &dyn_config),
+            Err(Error::QueryWithoutTimeRange { .. })
+        ));
+        let sql = "select * from cpu where time >= 1000 and time < 2000";
+        assert!(sql_to_logical_plan_with_config(sql, &dyn_config).is_ok());
+    }
+
     #[test]
     fn test_partitioned_table_query_statement_to_plan() {
         let sql = "select * from test_partitioned_table;";
         let dyn_config = DynamicConfig {
             enable_dist_query_push_down: AtomicBool::new(true),
+            ..Default::default()
         };
         let plan = sql_to_logical_plan_with_config(sql, &dyn_config).unwrap();
         let df_plan = if let Plan::Query(query_plan) = plan {
@@ -1732,6 +3256,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 2,
@@ -1743,6 +3269,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 3,
@@ -1754,6 +3282,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 4,
@@ -1765,6 +3295,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 5,
@@ -1776,6 +3308,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field3",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 6,
@@ -1787,6 +3321,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field4",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                     ],
                 },
@@ -1813,6 +3349,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 2,
@@ -1824,6 +3362,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 3,
@@ -1835,6 +3375,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 4,
@@ -1846,6 +3388,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 5,
@@ -1857,6 +3401,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field3",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 6,
@@ -1868,6 +3414,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field4",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                     ],
                 },
@@ -1977,6 +3525,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 2,
@@ -1988,6 +3538,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 3,
@@ -1999,6 +3551,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 4,
@@ -2010,6 +3564,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 5,
@@ -2021,6 +3577,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field3",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 6,
@@ -2032,6 +3590,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field4",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                     ],
                 },
@@ -2075,6 +3635,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 2,
@@ -2086,6 +3648,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 3,
@@ -2097,6 +3661,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 4,
@@ -2108,6 +3674,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 5,
@@ -2119,6 +3687,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field3",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 6,
@@ -2130,6 +3700,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field4",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                     ],
                 },
@@ -2152,9 +3724,12 @@ pub mod tests {
                     comment: "",
                     escaped_name: "dic",
                     default_value: None,
+                    display_name: "",
+                    unit: "",
                 },
             ],
         ),
+        dry_run: false,
     },
 )"#,
         )
@@ -2191,6 +3766,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 2,
@@ -2202,6 +3779,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 3,
@@ -2213,6 +3792,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 4,
@@ -2224,6 +3805,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 5,
@@ -2235,6 +3818,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field3",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 6,
@@ -2246,6 +3831,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field4",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                     ],
                 },
@@ -2268,15 +3855,64 @@ pub mod tests {
                     comment: "",
                     escaped_name: "add_col",
                     default_value: None,
+                    display_name: "",
+                    unit: "",
                 },
             ],
         ),
+        dry_run: false,
     },
 )"#,
         )
         .unwrap();
     }
 
+    #[test]
+    fn test_alter_swap_table_statement_to_plan() {
+        let sql = "ALTER TABLE test_table SWAP WITH test_table2";
+        quick_test(
+            sql,
+            r#"SwapTables(
+    SwapTablesPlan {
+        table: "test_table",
+        other: "test_table2",
+    },
+)"#,
+        )
+        .unwrap();
+
+        for sql in [
+            "ALTER TABLE test_table SWAP WITH test_table",
+            "ALTER TABLE test_table SWAP WITH test_tablex",
+            "ALTER TABLE test_table SWAP WITH test_partitioned_table",
+        ] {
+            assert!(sql_to_logical_plan(sql).is_err(), "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_compact_table_statement_to_plan() {
+        let plan = sql_to_logical_plan("COMPACT TABLE test_table").unwrap();
+        match plan {
+            Plan::CompactTable(plan) => assert_eq!("test_table", plan.table.name()),
+            _ => panic!("unexpected plan:{plan:?}"),
+        }
+
+        assert!(sql_to_logical_plan("COMPACT TABLE test_tablex").is_err());
+    }
+
+    #[test]
+    fn test_resync_table_statement_to_plan() {
+        let plan = sql_to_logical_plan("RESYNC TABLE test_table FOR CONSUMER 'replica'").unwrap();
+        match plan {
+            Plan::ResyncTable(plan) => {
+                assert_eq!("test_table", plan.table.name());
+                assert_eq!("replica", plan.consumer);
+            }
+            _ => panic!("unexpected plan:{plan:?}"),
+        }
+    }
+
     #[test]
     fn test_alter_option_statement_to_plan() {
         let sql = "ALTER TABLE test_tablex modify SETTING ttl='9d';";
@@ -2307,6 +3943,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 2,
@@ -2318,6 +3956,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "key2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 3,
@@ -2329,6 +3969,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field1",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 4,
@@ -2340,6 +3982,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field2",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 5,
@@ -2351,6 +3995,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field3",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                         ColumnSchema {
                             id: 6,
@@ -2362,6 +4008,8 @@ pub mod tests {
                             comment: "",
                             escaped_name: "field4",
                             default_value: None,
+                            display_name: "",
+                            unit: "",
                         },
                     ],
                 },
@@ -2377,6 +4025,7 @@ pub mod tests {
                 "ttl": "9d",
             },
         ),
+        dry_run: false,
     },
 )"#,
         )
@@ -2414,6 +4063,8 @@ pub mod tests {
                                 comment: "",
                                 escaped_name: "key1",
                                 default_value: None,
+                                display_name: "",
+                                unit: "",
                             },
                             ColumnSchema {
                                 id: 2,
@@ -2425,6 +4076,8 @@ pub mod tests {
                                 comment: "",
                                 escaped_name: "key2",
                                 default_value: None,
+                                display_name: "",
+                                unit: "",
                             },
                             ColumnSchema {
                                 id: 3,
@@ -2436,6 +4089,8 @@ pub mod tests {
                                 comment: "",
                                 escaped_name: "field1",
                                 default_value: None,
+                                display_name: "",
+                                unit: "",
                             },
                             ColumnSchema {
                                 id: 4,
@@ -2447,6 +4102,8 @@ pub mod tests {
                                 comment: "",
                                 escaped_name: "field2",
                                 default_value: None,
+                                display_name: "",
+                                unit: "",
                             },
                             ColumnSchema {
                                 id: 5,
@@ -2458,6 +4115,8 @@ pub mod tests {
                                 comment: "",
                                 escaped_name: "field3",
                                 default_value: None,
+                                display_name: "",
+                                unit: "",
                             },
                             ColumnSchema {
                                 id: 6,
@@ -2469,6 +4128,8 @@ pub mod tests {
                                 comment: "",
                                 escaped_name: "field4",
                                 default_value: None,
+                                display_name: "",
+                                unit: "",
                             },
                         ],
                     },
@@ -2515,6 +4176,109 @@ pub mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_create_continuous_query_statement_to_plan() {
+        let sql = "CREATE CONTINUOUS QUERY cq INTO test_table_5m EVERY '5m' DELAY '30s' AS SELECT key1, avg(field1) AS field1 FROM test_table GROUP BY key1";
+        quick_test(
+            sql,
+            r#"CreateContinuousQuery(
+    CreateContinuousQueryPlan {
+        if_not_exists: false,
+        continuous_query: ContinuousQueryDef {
+            name: "cq",
+            source_table: "test_table",
+            target_table: "test_table_5m",
+            time_column: "key2",
+            every: 300s,
+            delay: 30s,
+            query: "SELECT key1, avg(field1) AS field1 FROM test_table GROUP BY key1",
+        },
+    },
+)"#,
+        )
+        .unwrap();
+
+        for sql in [
+            "CREATE CONTINUOUS QUERY cq INTO t EVERY '5x' AS SELECT count(*) FROM test_table",
+            "CREATE CONTINUOUS QUERY cq INTO t EVERY '0s' AS SELECT count(*) FROM test_table",
+            "CREATE CONTINUOUS QUERY cq INTO t EVERY '5m' AS SELECT count(*) FROM not_exist",
+            "CREATE CONTINUOUS QUERY cq INTO test_table EVERY '5m' AS SELECT count(*) FROM test_table",
+            "CREATE CONTINUOUS QUERY cq INTO t EVERY '5m' AS SELECT no_column FROM test_table",
+        ] {
+            assert!(sql_to_logical_plan(sql).is_err(), "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_user_statement_to_plan() {
+        quick_test(
+            "CREATE USER alice IDENTIFIED BY 'secret'",
+            r#"CreateUser(
+    CreateUserPlan {
+        if_not_exists: false,
+        name: "alice",
+    },
+)"#,
+        )
+        .unwrap();
+
+        quick_test(
+            "GRANT READ_ONLY ON CATALOG * TO alice",
+            r#"Grant(
+    GrantPlan {
+        user: "alice",
+        catalog: "*",
+        role: ReadOnly,
+    },
+)"#,
+        )
+        .unwrap();
+
+        for sql in [
+            "CREATE USER alice IDENTIFIED BY ''",
+            "GRANT superuser ON CATALOG * TO alice",
+        ] {
+            assert!(sql_to_logical_plan(sql).is_err(), "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_create_source_statement_to_plan() {
+        let sql = "CREATE SOURCE cpu_source FROM KAFKA 'b1:9092,b2:9092/cpu' FORMAT JSON WITH (tags='host, idc', timestamp_field='ts');";
+        quick_test(
+            sql,
+            r#"CreateSource(
+    CreateSourcePlan {
+        if_not_exists: false,
+        source: SourceDef {
+            name: "cpu_source",
+            table: "cpu",
+            brokers: [
+                "b1:9092",
+                "b2:9092",
+            ],
+            topic: "cpu",
+            format: Json,
+            tags: [
+                "host",
+                "idc",
+            ],
+            timestamp_field: "ts",
+        },
+    },
+)"#,
+        )
+        .unwrap();
+
+        for sql in [
+            "CREATE SOURCE cpu_source FROM KAFKA 'b1:9092' FORMAT JSON",
+            "CREATE SOURCE cpu_source FROM KAFKA 'b1:9092/cpu' FORMAT CSV",
+            "CREATE SOURCE cpu_source FROM KAFKA 'b1:9092/cpu' FORMAT JSON WITH (x='1')",
+        ] {
+            assert!(sql_to_logical_plan(sql).is_err());
+        }
+    }
+
     fn make_test_number_expr(val: &str, sign: Option<bool>) -> Expr {
         let expr_val = Expr::Value(Value::Number(val.to_string(), false));
         match sign {
@@ -171,6 +171,8 @@ impl Expr {
                 df_plan: logic_plan,
                 tables,
                 table_name: Some(table_name),
+                offload: None,
+                explain_json: false,
             }),
             column_name,
         ))
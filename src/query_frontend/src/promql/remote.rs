@@ -100,6 +100,8 @@ pub fn remote_query_to_plan<P: MetaProvider>(
             df_plan,
             tables,
             table_name: Some(metric),
+            offload: None,
+            explain_json: false,
         }),
         field_col_name: field,
         timestamp_col_name: timestamp_col_name.to_string(),
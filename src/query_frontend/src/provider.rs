@@ -21,6 +21,7 @@ use std::{any::Any, borrow::Cow, cell::RefCell, collections::HashMap, sync::Arc}
 
 use async_trait::async_trait;
 use catalog::manager::ManagerRef;
+use common_types::time::Timestamp;
 use datafusion::{
     catalog::{schema::SchemaProvider, CatalogProvider},
     common::DataFusionError,
@@ -36,7 +37,7 @@ use snafu::{OptionExt, ResultExt, Snafu};
 use table_engine::table::TableRef;
 
 use crate::{
-    config::DynamicConfig,
+    config::{DynamicConfig, TimeRangeGuard, WildcardLimit},
     container::{PlannedTable, TableContainer, TableReference},
 };
 
@@ -238,6 +239,24 @@ pub struct ContextProviderAdapter<'a, P> {
     config: ConfigOptions,
     /// Hint for logical plan creation.
     dyn_config: &'a DynamicConfig,
+    /// Snapshot time of the tables read by `table_snapshot`.
+    table_snapshots: TableSnapshots,
+}
+
+/// Snapshot time of tables, keyed by [resolved_table_key].
+pub(crate) type TableSnapshots = HashMap<String, Timestamp>;
+
+/// Key of the table reference after filling the default catalog and schema.
+pub(crate) fn resolved_table_key(
+    name: TableReference,
+    default_catalog: &str,
+    default_schema: &str,
+) -> String {
+    let resolved = name.resolve(default_catalog, default_schema);
+    format!(
+        "{}.{}.{}",
+        resolved.catalog, resolved.schema, resolved.table
+    )
 }
 
 impl<'a, P: MetaProvider> ContextProviderAdapter<'a, P> {
@@ -261,9 +280,25 @@ impl<'a, P: MetaProvider> ContextProviderAdapter<'a, P> {
             meta_provider,
             config,
             dyn_config,
+            table_snapshots: HashMap::new(),
         }
     }
 
+    /// Set the snapshot time of the tables read by `table_snapshot`.
+    pub(crate) fn set_table_snapshots(&mut self, table_snapshots: TableSnapshots) {
+        self.table_snapshots = table_snapshots;
+    }
+
+    /// Limit of the columns read by `SELECT *`.
+    pub(crate) fn wildcard_limit(&self) -> WildcardLimit {
+        self.dyn_config.wildcard_limit.read().unwrap().clone()
+    }
+
+    /// Guard of the time range of the queries.
+    pub(crate) fn time_range_guard(&self) -> TimeRangeGuard {
+        self.dyn_config.time_range_guard.read().unwrap().clone()
+    }
+
     /// Consumes the adapter, returning the tables used during planning if no
     /// error occurs, otherwise returning the error
     pub fn try_into_container(self) -> Result<TableContainer> {
@@ -344,11 +379,27 @@ impl<'a, P: MetaProvider> ContextProvider for ContextProviderAdapter<'a, P> {
                     .enable_dist_query_push_down
                     .load(std::sync::atomic::Ordering::Relaxed);
 
+                let snapshot_time = self
+                    .table_snapshots
+                    .get(&resolved_table_key(
+                        name.clone(),
+                        self.meta_provider.default_catalog_name(),
+                        self.meta_provider.default_schema_name(),
+                    ))
+                    .copied();
+                if snapshot_time.is_some() && table.partition_info().is_some() {
+                    return Err(DataFusionError::Plan(format!(
+                        "table_snapshot is not supported by partitioned table, {:?}",
+                        format_table_reference(name),
+                    )));
+                }
+
                 let planned_table = PlannedTable {
                     catalog,
                     schema,
                     table,
                     enable_dist_query_push_down,
+                    snapshot_time,
                 };
 
                 self.table_cache
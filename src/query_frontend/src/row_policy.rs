@@ -0,0 +1,206 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Row-level security of the queries of some users.
+
+use std::sync::Arc;
+
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    error::{DataFusionError, Result},
+    logical_expr::{lit, utils::conjunction, Expr, Filter, LogicalPlan, Projection, TableScan},
+    sql::planner::{ContextProvider, PlannerContext, SqlToRel},
+};
+use sqlparser::{dialect::MySqlDialect, parser::Parser};
+
+use crate::config::RowPolicy;
+
+/// Function in the filters of the policies, which is replaced by the user of
+/// the request.
+const CURRENT_USER_FUNC: &str = "current_user()";
+/// Former name of the [CURRENT_USER_FUNC], kept for the existing policies.
+const CURRENT_ROLE_FUNC: &str = "current_role()";
+
+/// Filter the rows read by the `plan` according to the `policies` bound to
+/// the `user`.
+///
+/// It fails closed: no rows of a table with the policies are read if none of
+/// its policies is bound to the user, or the user is unknown. A policy with the
+/// filter `true` grants the user all the rows.
+///
+/// The table scans of the filtered tables are replaced by the scans of all the
+/// columns wrapped by the filter and a projection to the original columns, so
+/// the filter can read the columns not read by the query. It's applied after
+/// the masking, so the filter sees the unmasked values.
+pub fn filter_plan<S: ContextProvider>(
+    plan: LogicalPlan,
+    policies: &[RowPolicy],
+    user: Option<&str>,
+    context_provider: &S,
+) -> Result<LogicalPlan> {
+    if policies.is_empty() {
+        return Ok(plan);
+    }
+
+    plan.transform_up(&|plan| {
+        let LogicalPlan::TableScan(scan) = &plan else {
+            return Ok(Transformed::No(plan));
+        };
+        let table = scan.table_name.table();
+        let table_policies: Vec<_> = policies
+            .iter()
+            .filter(|policy| policy.table == table)
+            .collect();
+        if table_policies.is_empty() {
+            return Ok(Transformed::No(plan));
+        }
+
+        // The limit pushed down must be applied after the filter.
+        let full_scan = TableScan::try_new(
+            scan.table_name.clone(),
+            scan.source.clone(),
+            None,
+            scan.filters.clone(),
+            None,
+        )?;
+        let sql_to_rel = SqlToRel::new(context_provider);
+        let exprs = match user {
+            Some(user) => table_policies
+                .into_iter()
+                .filter(|policy| policy.users.iter().any(|v| v == user))
+                .map(|policy| {
+                    let expr = parse_filter(&policy.filter, user)?;
+                    sql_to_rel.sql_to_expr(
+                        expr,
+                        &full_scan.projected_schema,
+                        &mut PlannerContext::new(),
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        let predicate = conjunction(exprs).unwrap_or_else(|| lit(false));
+
+        let columns = scan
+            .projected_schema
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()))
+            .collect();
+        let filter = Filter::try_new(predicate, Arc::new(LogicalPlan::TableScan(full_scan)))?;
+        let projection = Projection::try_new(columns, Arc::new(LogicalPlan::Filter(filter)))?;
+
+        Ok(Transformed::Yes(LogicalPlan::Projection(projection)))
+    })
+}
+
+/// Parse the `filter` after replacing the `current_user()` by the `user`.
+fn parse_filter(filter: &str, user: &str) -> Result<sqlparser::ast::Expr> {
+    let user = format!("'{}'", user.replace('\'', "''"));
+    let filter = filter
+        .replace(CURRENT_USER_FUNC, &user)
+        .replace(CURRENT_ROLE_FUNC, &user);
+
+    Parser::new(&MySqlDialect {})
+        .try_with_sql(&filter)
+        .and_then(|mut parser| parser.parse_expr())
+        .map_err(|e| {
+            DataFusionError::Plan(format!(
+                "Invalid filter of row policy, filter:{filter}, err:{e}"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::logical_expr::LogicalPlanBuilder;
+
+    use super::*;
+    use crate::{config::DynamicConfig, provider::ContextProviderAdapter, tests::MockMetaProvider};
+
+    fn build_plan() -> LogicalPlan {
+        let schema = Schema::new(vec![
+            Field::new("tenant", DataType::Utf8, true),
+            Field::new("region", DataType::Utf8, true),
+            Field::new("value", DataType::Float64, true),
+        ]);
+        // Only the `value` column is read.
+        LogicalPlanBuilder::scan_empty(Some("cpu"), &schema, Some(vec![2]))
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filter_plan() {
+        let provider = MockMetaProvider::default();
+        let dyn_config = DynamicConfig::default();
+        let context_provider = ContextProviderAdapter::new(&provider, 1, &dyn_config);
+        let policies = vec![RowPolicy {
+            table: "cpu".to_string(),
+            users: vec!["tenant_a".to_string()],
+            filter: "tenant = current_user()".to_string(),
+        }];
+
+        let plan = build_plan();
+        let filtered =
+            filter_plan(plan.clone(), &policies, Some("tenant_a"), &context_provider).unwrap();
+        assert_eq!(plan.schema(), filtered.schema());
+        let LogicalPlan::Projection(projection) = &filtered else {
+            panic!("The scan should be filtered, plan:{filtered:?}");
+        };
+        let LogicalPlan::Filter(filter) = projection.input.as_ref() else {
+            panic!("The scan should be filtered, plan:{filtered:?}");
+        };
+        assert_eq!(
+            "cpu.tenant = Utf8(\"tenant_a\")",
+            filter.predicate.to_string()
+        );
+        let LogicalPlan::TableScan(scan) = filter.input.as_ref() else {
+            panic!("The scan should be filtered, plan:{filtered:?}");
+        };
+        assert!(scan.projection.is_none());
+
+        // Other users and the unknown user read no rows.
+        for user in [Some("admin"), None] {
+            let filtered = filter_plan(plan.clone(), &policies, user, &context_provider).unwrap();
+            let LogicalPlan::Projection(projection) = &filtered else {
+                panic!("The scan should be filtered, plan:{filtered:?}");
+            };
+            let LogicalPlan::Filter(filter) = projection.input.as_ref() else {
+                panic!("The scan should be filtered, plan:{filtered:?}");
+            };
+            assert_eq!("Boolean(false)", filter.predicate.to_string());
+        }
+
+        // The tables without the policies are not filtered.
+        let other_policies = vec![RowPolicy {
+            table: "mem".to_string(),
+            ..policies[0].clone()
+        }];
+        let filtered = filter_plan(plan.clone(), &other_policies, None, &context_provider).unwrap();
+        assert!(matches!(filtered, LogicalPlan::TableScan(_)));
+
+        // Invalid filter.
+        let policies = vec![RowPolicy {
+            filter: "tenant = ".to_string(),
+            ..policies[0].clone()
+        }];
+        assert!(filter_plan(plan, &policies, Some("tenant_a"), &context_provider).is_err());
+    }
+}
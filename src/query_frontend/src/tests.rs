@@ -24,11 +24,13 @@ use df_operator::{scalar::ScalarUdf, udaf::AggregateUdf};
 use partition_table_engine::test_util::PartitionedMemoryTable;
 use table_engine::{
     memory::MemoryTable,
-    partition::{KeyPartitionInfo, PartitionDefinition, PartitionInfo},
+    partition::{KeyPartitionInfo, PartitionDefinition, PartitionInfo, TimePartitionInfo},
     table::{TableId, TableRef},
     ANALYTIC_ENGINE_TYPE,
 };
 
+use time_ext::ReadableDuration;
+
 use crate::provider::{MetaProvider, ResolvedTable};
 
 pub struct MockMetaProvider {
@@ -50,6 +52,23 @@ impl Default for MockMetaProvider {
             ANALYTIC_ENGINE_TYPE.to_string(),
             partition_info,
         );
+        let partition_info = PartitionInfo::Time(TimePartitionInfo {
+            definitions: (0..4)
+                .map(|i| PartitionDefinition {
+                    name: i.to_string(),
+                    origin_name: None,
+                })
+                .collect(),
+            column: "time".to_string(),
+            interval: ReadableDuration::days(1),
+        });
+        let time_partitioned_table = PartitionedMemoryTable::new(
+            "time_partitioned".to_string(),
+            TableId::from(106),
+            build_schema_for_cpu(),
+            ANALYTIC_ENGINE_TYPE.to_string(),
+            partition_info,
+        );
 
         Self {
             tables: vec![
@@ -86,6 +105,8 @@ impl Default for MockMetaProvider {
                 )),
                 // Used in `test_partitioned_table_query_to_plan`
                 Arc::new(test_partitioned_table),
+                // Used in `test_show_partitions` of the interpreters
+                Arc::new(time_partitioned_table),
             ],
         }
     }
@@ -0,0 +1,139 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Guard of the time range of the queries on some tables.
+
+use std::sync::Arc;
+
+use common_types::{schema::Schema, time::Timestamp};
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode, VisitRecursion},
+    error::Result,
+    logical_expr::{Expr, Filter, LogicalPlan, Projection, TableScan},
+    scalar::ScalarValue,
+};
+
+use table_engine::predicate::PredicateBuilder;
+
+use crate::config::TimeRangeGuard;
+
+/// Returns the guarded tables scanned by the `plan` without a bounded time
+/// range, see [TimeRangeGuard::max_range].
+///
+/// It should be called on the optimized plan, whose predicates are pushed down
+/// to the table scans.
+pub fn unguarded_tables(
+    plan: &LogicalPlan,
+    guard: &TimeRangeGuard,
+    now: Timestamp,
+) -> Result<Vec<String>> {
+    let mut tables = Vec::new();
+    plan.apply(&mut |plan| {
+        if let LogicalPlan::TableScan(scan) = plan {
+            if unguarded_timestamp_column(scan, guard, now).is_some() {
+                tables.push(scan.table_name.to_string());
+            }
+        }
+        Ok(VisitRecursion::Continue)
+    })?;
+
+    Ok(tables)
+}
+
+/// Filter the scans of the guarded tables without a bounded time range to read
+/// the data of the last `default_range` before `now` only.
+///
+/// The scans are replaced by the scans of all the columns wrapped by the
+/// filter and a projection to the original columns, just like the row
+/// policies, so the timestamp column not read by the query can be filtered.
+pub fn apply_default_range(
+    plan: LogicalPlan,
+    guard: &TimeRangeGuard,
+    now: Timestamp,
+) -> Result<LogicalPlan> {
+    let start = now
+        .as_i64()
+        .saturating_sub(guard.default_range.as_millis() as i64);
+
+    plan.transform_up(&|plan| {
+        let LogicalPlan::TableScan(scan) = &plan else {
+            return Ok(Transformed::No(plan));
+        };
+        let Some(timestamp_column) = unguarded_timestamp_column(scan, guard, now) else {
+            return Ok(Transformed::No(plan));
+        };
+
+        let mut full_scan = TableScan::try_new(
+            scan.table_name.clone(),
+            scan.source.clone(),
+            None,
+            scan.filters.clone(),
+            None,
+        )?;
+        let column = full_scan
+            .projected_schema
+            .field_with_unqualified_name(&timestamp_column)?
+            .qualified_column();
+        let predicate = Expr::Column(column).gt_eq(Expr::Literal(
+            ScalarValue::TimestampMillisecond(Some(start), None),
+        ));
+        // The predicate is also pushed down to prune the data out of the range.
+        full_scan.filters.push(predicate.clone());
+
+        let columns = scan
+            .projected_schema
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()))
+            .collect();
+        let filter = Filter::try_new(predicate, Arc::new(LogicalPlan::TableScan(full_scan)))?;
+        let projection = Projection::try_new(columns, Arc::new(LogicalPlan::Filter(filter)))?;
+
+        Ok(Transformed::Yes(LogicalPlan::Projection(projection)))
+    })
+}
+
+/// Returns the timestamp column of the scan if it reads a guarded table
+/// without a bounded time range.
+///
+/// The time range is extracted from the predicates on the timestamp column as
+/// the table scans do, so the predicates not limiting the range, e.g. `time IS
+/// NOT NULL` or `time > 0`, don't bound it.
+fn unguarded_timestamp_column(
+    scan: &TableScan,
+    guard: &TimeRangeGuard,
+    now: Timestamp,
+) -> Option<String> {
+    let table = scan.table_name.table();
+    if !guard.tables.iter().any(|v| v == table) {
+        return None;
+    }
+    // The tables not managed by the engine, e.g. the system tables, have no
+    // timestamp column.
+    let schema = Schema::try_from(scan.source.schema()).ok()?;
+    let time_range = PredicateBuilder::default()
+        .extract_time_range(&schema, &scan.filters)
+        .build()
+        .time_range();
+
+    let start = time_range.inclusive_start();
+    let end = time_range.exclusive_end().min(now);
+    let bounded = start != Timestamp::MIN
+        && end.as_i64().saturating_sub(start.as_i64()) <= guard.max_range.as_millis() as i64;
+
+    (!bounded).then(|| schema.timestamp_name().to_string())
+}
@@ -98,6 +98,77 @@ impl CachedRouter {
         }
     }
 
+    /// Route the tables in batch, and the tables missing in the cache of the
+    /// same schema are routed by one request.
+    ///
+    /// The route contexts are returned in the order of the `table_idents`.
+    pub async fn route_batch(&self, table_idents: &[TableIdentifier]) -> Result<Vec<RouteContext>> {
+        let mut route_contexts = Vec::with_capacity(table_idents.len());
+        // Schema -> indexes of the tables missing in the cache.
+        let mut missing_by_schema: HashMap<&str, Vec<usize>> = HashMap::new();
+        {
+            let cache = self.cache.read().unwrap();
+            for (idx, table_ident) in table_idents.iter().enumerate() {
+                let route_context = cache.get(table_ident).cloned();
+                if route_context.is_none() {
+                    missing_by_schema
+                        .entry(&table_ident.schema)
+                        .or_default()
+                        .push(idx);
+                }
+                route_contexts.push(route_context);
+            }
+        }
+
+        for (schema, indexes) in missing_by_schema {
+            debug!(
+                "CachedRouter didn't find channels in cache, schema:{schema}, table_num:{}",
+                indexes.len()
+            );
+            let first_table_ident = &table_idents[indexes[0]];
+            let request_pb = storage::RouteRequest {
+                context: Some(RequestContext {
+                    database: schema.to_string(),
+                }),
+                tables: indexes
+                    .iter()
+                    .map(|idx| table_idents[*idx].table.clone())
+                    .collect(),
+            };
+            let request = RouteRequest::new(request_pb, true);
+            let route_infos = self.router.route(request).await.context(RouteWithCause {
+                table_ident: first_table_ident.clone(),
+            })?;
+            let endpoints: HashMap<_, _> = route_infos
+                .into_iter()
+                .filter_map(|route| route.endpoint.map(|endpoint| (route.table, endpoint)))
+                .collect();
+
+            for idx in indexes {
+                let table_ident = &table_idents[idx];
+                let endpoint = endpoints
+                    .get(&table_ident.table)
+                    .cloned()
+                    .with_context(|| RouteNoCause {
+                        table_ident: table_ident.clone(),
+                        msg: "no endpoint in route info",
+                    })?;
+                let endpoint = endpoint.into();
+                let channel = self.channel_pool.get(&endpoint).await?;
+                let route_context = RouteContext { channel, endpoint };
+
+                self.cache
+                    .write()
+                    .unwrap()
+                    .entry(table_ident.clone())
+                    .or_insert_with(|| route_context.clone());
+                route_contexts[idx] = Some(route_context);
+            }
+        }
+
+        Ok(route_contexts.into_iter().map(Option::unwrap).collect())
+    }
+
     pub async fn evict(&self, table_idents: &[TableIdentifier]) {
         let mut cache = self.cache.write().unwrap();
         for table_ident in table_idents {
@@ -17,18 +17,26 @@
 
 //! Channel pool
 
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use router::endpoint::Endpoint;
 use snafu::ResultExt;
+use tokio::sync::OnceCell;
 use tonic::transport::{Channel, Endpoint as TonicEndpoint};
 
 use crate::{config::Config, error::*};
 
 /// Pool for reusing the built channel
+///
+/// Only one channel is built for an endpoint, and all the requests to the
+/// endpoint are multiplexed on it.
 pub struct ChannelPool {
-    /// Channels in pool
-    channels: RwLock<HashMap<Endpoint, Channel>>,
+    /// Channels in pool, the channel is built only once even if it's requested
+    /// concurrently.
+    channels: RwLock<HashMap<Endpoint, Arc<OnceCell<Channel>>>>,
 
     /// Channel builder
     builder: ChannelBuilder,
@@ -43,23 +51,23 @@ impl ChannelPool {
     }
 
     pub async fn get(&self, endpoint: &Endpoint) -> Result<Channel> {
-        {
+        let cell = {
             let inner = self.channels.read().unwrap();
-            if let Some(channel) = inner.get(endpoint) {
-                return Ok(channel.clone());
+            inner.get(endpoint).cloned()
+        };
+        let cell = match cell {
+            Some(v) => v,
+            None => {
+                let mut inner = self.channels.write().unwrap();
+                inner.entry(endpoint.clone()).or_default().clone()
             }
-        }
+        };
 
-        let channel = self.builder.build(&endpoint.to_string()).await?;
-        let mut inner = self.channels.write().unwrap();
-        // Double check here.
-        if let Some(channel) = inner.get(endpoint) {
-            return Ok(channel.clone());
-        }
-
-        inner.insert(endpoint.clone(), channel.clone());
-
-        Ok(channel)
+        // The cell is left uninitialized if the building fails, so the channel will
+        // be built again by the next request.
+        cell.get_or_try_init(|| self.builder.build(&endpoint.to_string()))
+            .await
+            .cloned()
     }
 }
 
@@ -74,7 +82,7 @@ impl ChannelBuilder {
     }
 
     async fn build(&self, endpoint: &str) -> Result<Channel> {
-        let formatted_endpoint = make_formatted_endpoint(endpoint);
+        let formatted_endpoint = make_formatted_endpoint(endpoint, self.config.tls.is_some());
         let configured_endpoint =
             TonicEndpoint::from_shared(formatted_endpoint.clone()).context(BuildChannel {
                 addr: formatted_endpoint.clone(),
@@ -86,6 +94,13 @@ impl ChannelBuilder {
             .keep_alive_timeout(self.config.channel_keep_alive_timeout.0)
             .http2_keep_alive_interval(self.config.channel_keep_alive_interval.0)
             .keep_alive_while_idle(true);
+        let configured_endpoint = match &self.config.tls {
+            Some(tls) => configured_endpoint.tls_config(tls.clone()).context(BuildChannel {
+                addr: formatted_endpoint.clone(),
+                msg: "invalid tls config",
+            })?,
+            None => configured_endpoint,
+        };
 
         let channel = configured_endpoint.connect().await.context(BuildChannel {
             addr: formatted_endpoint.clone(),
@@ -96,6 +111,10 @@ impl ChannelBuilder {
     }
 }
 
-fn make_formatted_endpoint(endpoint: &str) -> String {
-    format!("http://{endpoint}")
+fn make_formatted_endpoint(endpoint: &str, tls: bool) -> String {
+    if tls {
+        format!("https://{endpoint}")
+    } else {
+        format!("http://{endpoint}")
+    }
 }
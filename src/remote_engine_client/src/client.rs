@@ -28,8 +28,12 @@ use arrow_ext::{
     ipc,
     ipc::{CompressOptions, CompressionMethod},
 };
-use common_types::{record_batch::RecordBatch, schema::RecordSchema};
-use futures::{Stream, StreamExt};
+use common_types::{
+    protocol::{Protocol, INTERNAL_TOKEN_KEY, PROTOCOL_FEATURES_KEY, PROTOCOL_VERSION_KEY},
+    record_batch::RecordBatch,
+    schema::RecordSchema,
+};
+use futures::{future, Stream, StreamExt};
 use generic_error::BoxError;
 use horaedbproto::{
     remote_engine::{
@@ -44,6 +48,7 @@ use router::{endpoint::Endpoint, RouterRef};
 use runtime::Runtime;
 use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::{
+    query_warning::{self, QueryWarnings},
     remote::model::{
         AlterTableOptionsRequest, AlterTableSchemaRequest, ExecutePlanRequest, GetTableInfoRequest,
         ReadRequest, TableIdentifier, TableInfo, WriteBatchRequest, WriteBatchResult, WriteRequest,
@@ -52,9 +57,18 @@ use table_engine::{
 };
 use time_ext::ReadableDuration;
 use tokio::time::sleep;
-use tonic::{transport::Channel, Request, Streaming};
+use tonic::{
+    metadata::{AsciiMetadataValue, MetadataMap},
+    transport::Channel,
+    Request, Response, Streaming,
+};
 
-use crate::{cached_router::CachedRouter, config::Config, error::*, status_code};
+use crate::{
+    cached_router::{CachedRouter, RouteContext},
+    config::Config,
+    error::*,
+    status_code,
+};
 
 struct WriteBatchContext {
     table_idents: Vec<TableIdentifier>,
@@ -68,6 +82,10 @@ pub struct Client {
     pub compression: CompressOptions,
     max_retry: usize,
     retry_interval: ReadableDuration,
+    /// Protocol of this node.
+    protocol: Protocol,
+    /// Token to be accepted by the other nodes, `None` if it's not set.
+    internal_token: Option<AsciiMetadataValue>,
 }
 
 impl Client {
@@ -75,6 +93,11 @@ impl Client {
         let compression = config.compression;
         let max_retry = config.max_retry;
         let retry_interval = config.retry_interval;
+        let protocol = Protocol::local(&config.protocol);
+        let internal_token = config
+            .internal_token
+            .as_deref()
+            .and_then(|v| v.parse().ok());
         let cached_router = CachedRouter::new(router, config);
 
         Self {
@@ -83,6 +106,8 @@ impl Client {
             compression,
             max_retry,
             retry_interval,
+            protocol,
+            internal_token,
         }
     }
 
@@ -90,9 +115,48 @@ impl Client {
         // Find the channel from router firstly.
         let route_context = self.cached_router.route(&request.table).await?;
 
+        self.read_with_route(route_context, request).await
+    }
+
+    /// Read from multiple tables, the streams are returned in the order of the
+    /// `requests`.
+    ///
+    /// The tables are routed in batch, and the reads to the same endpoint are
+    /// multiplexed on the shared channel.
+    pub async fn read_batch(
+        &self,
+        requests: Vec<ReadRequest>,
+    ) -> Result<Vec<ClientReadRecordBatchStream>> {
+        let table_idents: Vec<_> = requests.iter().map(|v| v.table.clone()).collect();
+        let route_contexts = self.cached_router.route_batch(&table_idents).await?;
+
+        let reads = route_contexts
+            .into_iter()
+            .zip(requests)
+            .map(|(route_context, request)| self.read_with_route(route_context, request));
+        future::try_join_all(reads).await
+    }
+
+    /// Route the tables in batch, the endpoints are returned in the order of
+    /// the `tables`.
+    pub async fn route(&self, tables: &[TableIdentifier]) -> Result<Vec<Endpoint>> {
+        let route_contexts = self.cached_router.route_batch(tables).await?;
+
+        Ok(route_contexts
+            .into_iter()
+            .map(|route_context| route_context.endpoint)
+            .collect())
+    }
+
+    async fn read_with_route(
+        &self,
+        route_context: RouteContext,
+        request: ReadRequest,
+    ) -> Result<ClientReadRecordBatchStream> {
         // Read from remote.
         let table_ident = request.table.clone();
         let record_schema = request.read_request.projected_schema.to_record_schema();
+        let warnings = request.read_request.opts.warnings.clone();
         let mut rpc_client = RemoteEngineServiceClient::<Channel>::new(route_context.channel);
         let request_pb = horaedbproto::remote_engine::ReadRequest::try_from(request)
             .box_err()
@@ -101,12 +165,13 @@ impl Client {
             })?;
 
         let result = rpc_client
-            .read(Request::new(request_pb))
+            .read(self.new_request(request_pb))
             .await
             .with_context(|| Rpc {
                 table_idents: vec![table_ident.clone()],
                 msg: "Failed to read from remote engine",
-            });
+            })
+            .and_then(|response| self.check_peer_protocol(&route_context.endpoint, response));
 
         let response = match result {
             Ok(response) => response,
@@ -128,6 +193,7 @@ impl Client {
             response,
             record_schema,
             Default::default(),
+            warnings,
         );
 
         Ok(remote_read_record_batch_stream)
@@ -146,12 +212,13 @@ impl Client {
         let mut rpc_client = RemoteEngineServiceClient::<Channel>::new(route_context.channel);
 
         let result = rpc_client
-            .write(Request::new(request_pb))
+            .write(self.new_request(request_pb))
             .await
             .with_context(|| Rpc {
                 table_idents: vec![table_ident.clone()],
                 msg: "Failed to write to remote engine",
-            });
+            })
+            .and_then(|response| self.check_peer_protocol(&endpoint, response));
 
         let result = result.and_then(|response| {
             let response = response.into_inner();
@@ -208,10 +275,11 @@ impl Client {
             let batch_request_pb = request.convert_into_pb().box_err().context(Convert {
                 msg: "failed to convert request to pb",
             })?;
+            let request = self.new_request(batch_request_pb);
             let handle = self.io_runtime.spawn(async move {
                 let mut rpc_client = RemoteEngineServiceClient::<Channel>::new(channel);
                 rpc_client
-                    .write_batch(Request::new(batch_request_pb))
+                    .write_batch(request)
                     .await
                     .map(|v| (v, endpoint.clone()))
                     .box_err()
@@ -284,7 +352,7 @@ impl Client {
         // TODO: Define a macro to reuse the retry logic.
         for i in 0..(self.max_retry + 1) {
             let resp = rpc_client
-                .alter_table_schema(Request::new(request_pb.clone()))
+                .alter_table_schema(self.new_request(request_pb.clone()))
                 .await
                 .with_context(|| Rpc {
                     table_idents: vec![table_ident.clone()],
@@ -346,7 +414,7 @@ impl Client {
         // Alter options to remote engine with retry.
         for i in 0..(self.max_retry + 1) {
             let resp = rpc_client
-                .alter_table_options(Request::new(request_pb.clone()))
+                .alter_table_options(self.new_request(request_pb.clone()))
                 .await
                 .with_context(|| Rpc {
                     table_idents: vec![table_ident.clone()],
@@ -406,12 +474,13 @@ impl Client {
         let mut rpc_client = RemoteEngineServiceClient::<Channel>::new(route_context.channel);
 
         let result = rpc_client
-            .get_table_info(Request::new(request_pb))
+            .get_table_info(self.new_request(request_pb))
             .await
             .with_context(|| Rpc {
                 table_idents: vec![table_ident.clone()],
                 msg: "Failed to get table info",
-            });
+            })
+            .and_then(|response| self.check_peer_protocol(&endpoint, response));
 
         let result = result.and_then(|response| {
             let response = response.into_inner();
@@ -496,12 +565,13 @@ impl Client {
             horaedbproto::remote_engine::ExecutePlanRequest::from(request.remote_request);
 
         let result = rpc_client
-            .execute_physical_plan(Request::new(request_pb))
+            .execute_physical_plan(self.new_request(request_pb))
             .await
             .with_context(|| Rpc {
                 table_idents: vec![table_ident.clone()],
                 msg: "Failed to read from remote engine",
-            });
+            })
+            .and_then(|response| self.check_peer_protocol(&route_context.endpoint, response));
 
         let response = match result {
             Ok(response) => response,
@@ -523,11 +593,49 @@ impl Client {
             response,
             plan_schema,
             request.remote_metrics,
+            request.warnings,
         );
 
         Ok(remote_execute_plan_stream)
     }
 
+    /// Build the rpc request carrying the protocol of this node.
+    fn new_request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        let metadata = request.metadata_mut();
+        metadata.insert(
+            PROTOCOL_VERSION_KEY,
+            self.protocol.encode_version().parse().unwrap(),
+        );
+        metadata.insert(
+            PROTOCOL_FEATURES_KEY,
+            self.protocol.encode_features().parse().unwrap(),
+        );
+        if let Some(token) = &self.internal_token {
+            metadata.insert(INTERNAL_TOKEN_KEY, token.clone());
+        }
+        request
+    }
+
+    /// Ensure the server at `endpoint` speaks a protocol compatible with this
+    /// node.
+    fn check_peer_protocol<T>(
+        &self,
+        endpoint: &Endpoint,
+        response: Response<T>,
+    ) -> Result<Response<T>> {
+        let peer = decode_protocol(response.metadata()).context(IncompatibleProtocol {
+            endpoint: endpoint.clone(),
+        })?;
+        self.protocol
+            .negotiate(&peer)
+            .context(IncompatibleProtocol {
+                endpoint: endpoint.clone(),
+            })?;
+
+        Ok(response)
+    }
+
     async fn evict_route_from_cache(&self, table_idents: &[TableIdentifier]) {
         info!(
             "Remote engine client evict route from cache, table_ident:{:?}",
@@ -543,6 +651,8 @@ pub struct ClientReadRecordBatchStream {
     pub response_stream: Streaming<remote_engine::ReadResponse>,
     pub record_schema: RecordSchema,
     pub remote_metrics: Arc<Mutex<Option<String>>>,
+    /// The warnings of the remote execution are merged into it.
+    pub warnings: QueryWarnings,
 }
 
 impl ClientReadRecordBatchStream {
@@ -552,6 +662,7 @@ impl ClientReadRecordBatchStream {
         response_stream: Streaming<remote_engine::ReadResponse>,
         record_schema: RecordSchema,
         remote_metrics: Arc<Mutex<Option<String>>>,
+        warnings: QueryWarnings,
     ) -> Self {
         Self {
             endpoint,
@@ -559,6 +670,7 @@ impl ClientReadRecordBatchStream {
             response_stream,
             record_schema,
             remote_metrics,
+            warnings,
         }
     }
 }
@@ -568,48 +680,66 @@ impl Stream for ClientReadRecordBatchStream {
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
-        match this.response_stream.poll_next_unpin(cx) {
-            Poll::Ready(Some(Ok(response))) => {
-                // Check header.
-                if let Some(header) = response.header
-                    && !status_code::is_ok(header.code)
-                {
-                    return Poll::Ready(Some(
-                        Server {
-                            endpoint: this.endpoint.clone(),
-                            table_idents: vec![this.table_ident.clone()],
-                            code: header.code,
-                            msg: header.error,
-                        }
-                        .fail(),
-                    ));
-                }
+        loop {
+            match this.response_stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(response))) => {
+                    // Check header.
+                    if let Some(header) = &response.header
+                        && !status_code::is_ok(header.code)
+                    {
+                        return Poll::Ready(Some(
+                            Server {
+                                endpoint: this.endpoint.clone(),
+                                table_idents: vec![this.table_ident.clone()],
+                                code: header.code,
+                                msg: header.error.clone(),
+                            }
+                            .fail(),
+                        ));
+                    }
 
-                match response.output {
-                    None => Poll::Ready(None),
-                    Some(v) => match v {
-                        Arrow(v) => Poll::Ready(Some(convert_arrow_payload(v))),
-                        Metric(v) => {
+                    match response.output {
+                        // The response without output carries the warnings in the message of
+                        // its header, and the messages after the results are drained to read
+                        // them.
+                        None => {
+                            if let Some(header) = response.header
+                                && !header.error.is_empty()
+                            {
+                                let warnings = query_warning::decode_warnings(&header.error);
+                                this.warnings.extend(warnings);
+                            }
+                        }
+                        Some(Arrow(v)) => return Poll::Ready(Some(convert_arrow_payload(v))),
+                        Some(Metric(v)) => {
                             let mut remote_metrics = this.remote_metrics.lock().unwrap();
                             *remote_metrics = Some(v.metric);
-                            Poll::Ready(None)
                         }
-                    },
+                    }
                 }
-            }
 
-            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e).context(Rpc {
-                table_idents: vec![this.table_ident.clone()],
-                msg: "poll read response",
-            }))),
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(Err(e).context(Rpc {
+                        table_idents: vec![this.table_ident.clone()],
+                        msg: "poll read response",
+                    })));
+                }
 
-            Poll::Ready(None) => Poll::Ready(None),
+                Poll::Ready(None) => return Poll::Ready(None),
 
-            Poll::Pending => Poll::Pending,
+                Poll::Pending => return Poll::Pending,
+            }
         }
     }
 }
 
+/// Decode the protocol of the peer from the `metadata`, the peer is considered
+/// as legacy if the values are not valid ascii.
+fn decode_protocol(metadata: &MetadataMap) -> common_types::protocol::Result<Protocol> {
+    let get = |key| metadata.get(key).and_then(|value| value.to_str().ok());
+    Protocol::decode(get(PROTOCOL_VERSION_KEY), get(PROTOCOL_FEATURES_KEY))
+}
+
 fn convert_arrow_payload(mut v: ArrowPayload) -> Result<RecordBatch> {
     if v.record_batches.len() != 1 {
         return InvalidRecordBatchNumber {
@@ -18,8 +18,10 @@
 //! Config for [Client]
 
 use arrow_ext::ipc::CompressOptions;
+use common_types::protocol::ProtocolConfig;
 use serde::{Deserialize, Serialize};
 use time_ext::ReadableDuration;
+use tonic::transport::ClientTlsConfig;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
@@ -35,6 +37,17 @@ pub struct Config {
     pub compression: CompressOptions,
     pub max_retry: usize,
     pub retry_interval: ReadableDuration,
+    /// Config of the protocol talking with the remote engines
+    pub protocol: ProtocolConfig,
+    /// Token attached to the requests, so they are accepted by the remote
+    /// engine services of the other nodes. It's the internal token of the
+    /// forwarding rather than configured here.
+    #[serde(skip)]
+    pub internal_token: Option<String>,
+    /// TLS of the channels to the other nodes, which is built from the grpc
+    /// TLS of the server rather than configured here.
+    #[serde(skip)]
+    pub tls: Option<ClientTlsConfig>,
 }
 
 impl Default for Config {
@@ -51,6 +64,9 @@ impl Default for Config {
             compression: CompressOptions::default(),
             max_retry: 5,
             retry_interval: ReadableDuration::secs(5),
+            protocol: ProtocolConfig::default(),
+            internal_token: None,
+            tls: None,
         }
     }
 }
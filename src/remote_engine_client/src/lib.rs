@@ -45,7 +45,8 @@ use table_engine::{
         self,
         model::{
             AlterTableOptionsRequest, AlterTableSchemaRequest, ExecutePlanRequest,
-            GetTableInfoRequest, ReadRequest, TableInfo, WriteBatchResult, WriteRequest,
+            GetTableInfoRequest, ReadRequest, TableIdentifier, TableInfo, WriteBatchResult,
+            WriteRequest,
         },
         RemoteEngine,
     },
@@ -59,7 +60,9 @@ pub mod error {
     use macros::define_result;
     use router::endpoint::Endpoint;
     use snafu::{Backtrace, Snafu};
-    use table_engine::remote::model::TableIdentifier;
+    use table_engine::remote::{self, model::TableIdentifier};
+
+    use crate::status_code::StatusCode;
 
     #[derive(Debug, Snafu)]
     #[snafu(visibility(pub))]
@@ -110,6 +113,16 @@ pub mod error {
             msg: String,
         },
 
+        #[snafu(display(
+            "Incompatible protocol of the remote engine, endpoint:{}, err:{}",
+            endpoint.to_string(),
+            source
+        ))]
+        IncompatibleProtocol {
+            endpoint: Endpoint,
+            source: common_types::protocol::Error,
+        },
+
         #[snafu(display("Failed to route table, table_ident:{:?}, err:{}", table_ident, source,))]
         RouteWithCause {
             table_ident: TableIdentifier,
@@ -124,6 +137,63 @@ pub mod error {
     }
 
     define_result!(Error);
+
+    impl Error {
+        /// Whether the error is caused by the unavailable node or the stale
+        /// route, and the request may succeed after it's routed again.
+        ///
+        /// The other rpc failures, e.g. the deadline exceeded or the cancelled
+        /// query, are not retryable.
+        pub fn is_retryable(&self) -> bool {
+            match self {
+                Error::BuildChannel { .. }
+                | Error::RouteWithCause { .. }
+                | Error::RouteNoCause { .. } => true,
+                Error::Rpc { source, .. } => source.code() == tonic::Code::Unavailable,
+                // The table is not found as it's moved to another node.
+                Error::Server { code, .. } => *code == StatusCode::NotFound.as_u32(),
+                Error::InvalidRecordBatchNumber { .. }
+                | Error::Convert { .. }
+                | Error::IncompatibleProtocol { .. } => false,
+            }
+        }
+
+        /// Box the error, and the retryable one is wrapped into
+        /// [remote::Error::Unavailable] to be recognized by the callers.
+        pub(crate) fn box_retryable(self) -> GenericError {
+            if self.is_retryable() {
+                Box::new(remote::Error::Unavailable {
+                    source: Box::new(self),
+                })
+            } else {
+                Box::new(self)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_retryable_rpc_errors() {
+            let rpc_err = |code| Error::Rpc {
+                table_idents: Vec::new(),
+                msg: String::new(),
+                source: tonic::Status::new(code, ""),
+            };
+
+            assert!(rpc_err(tonic::Code::Unavailable).is_retryable());
+            for code in [
+                tonic::Code::DeadlineExceeded,
+                tonic::Code::Cancelled,
+                tonic::Code::InvalidArgument,
+                tonic::Code::Internal,
+            ] {
+                assert!(!rpc_err(code).is_retryable(), "code:{code:?}");
+            }
+        }
+    }
 }
 
 pub struct RemoteEngineImpl {
@@ -150,6 +220,22 @@ impl RemoteEngine for RemoteEngineImpl {
         Ok(Box::pin(RemoteReadRecordBatchStream(client_read_stream)))
     }
 
+    async fn read_batch(
+        &self,
+        requests: Vec<ReadRequest>,
+    ) -> remote::Result<Vec<SendableRecordBatchStream>> {
+        let client_read_streams = self
+            .client
+            .read_batch(requests)
+            .await
+            .box_err()
+            .context(remote::Read)?;
+        Ok(client_read_streams
+            .into_iter()
+            .map(|v| Box::pin(RemoteReadRecordBatchStream(v)) as _)
+            .collect())
+    }
+
     async fn write(&self, request: WriteRequest) -> remote::Result<usize> {
         self.client
             .write(request)
@@ -201,10 +287,21 @@ impl RemoteEngine for RemoteEngineImpl {
             .client
             .execute_physical_plan(request)
             .await
-            .box_err()
+            .map_err(error::Error::box_retryable)
             .context(remote::ExecutePhysicalPlan)?;
         Ok(Box::pin(RemoteReadRecordBatchStream(client_read_stream)))
     }
+
+    async fn route(&self, tables: &[TableIdentifier]) -> remote::Result<Vec<String>> {
+        let endpoints = self
+            .client
+            .route(tables)
+            .await
+            .map_err(error::Error::box_retryable)
+            .context(remote::Route)?;
+
+        Ok(endpoints.iter().map(ToString::to_string).collect())
+    }
 }
 
 impl fmt::Debug for RemoteEngineImpl {
@@ -222,9 +319,11 @@ impl Stream for RemoteReadRecordBatchStream {
         let this = self.get_mut();
         match this.0.poll_next_unpin(cx) {
             Poll::Ready(Some(result)) => {
-                let result = result.box_err().context(ErrWithSource {
-                    msg: "poll read response failed",
-                });
+                let result = result
+                    .map_err(error::Error::box_retryable)
+                    .context(ErrWithSource {
+                        msg: "poll read response failed",
+                    });
 
                 Poll::Ready(Some(result))
             }
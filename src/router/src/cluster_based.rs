@@ -16,19 +16,27 @@
 // under the License.
 
 //! A router based on the [`cluster::Cluster`].
+//!
+//! The shards are placed by the meta service, so only the node rules are
+//! supported, and the tables matched by a node rule but placed on the other
+//! nodes are still routed to the leaders of their shards, with a warning.
+
+use std::sync::RwLock;
 
 use async_trait::async_trait;
 use cluster::ClusterRef;
 use generic_error::BoxError;
 use horaedbproto::storage::Route;
-use logger::trace;
+use logger::{info, trace, warn};
 use meta_client::types::RouteTablesRequest;
 use moka::future::Cache;
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 
 use crate::{
-    endpoint::Endpoint, OtherWithCause, ParseEndpoint, Result, RouteCacheConfig, RouteRequest,
-    Router, TableInfo,
+    endpoint::Endpoint,
+    rule_based::{NodeRule, Rules},
+    InvalidRule, OtherWithCause, ParseEndpoint, Result, RouteCacheConfig, RouteRequest, Router,
+    RuleList, TableInfo,
 };
 
 #[derive(Clone, Debug)]
@@ -40,10 +48,11 @@ struct RouteData {
 pub struct ClusterBasedRouter {
     cluster: ClusterRef,
     cache: Option<Cache<String, RouteData>>,
+    rules: RwLock<Rules>,
 }
 
 impl ClusterBasedRouter {
-    pub fn new(cluster: ClusterRef, cache_config: RouteCacheConfig) -> Self {
+    pub fn new(cluster: ClusterRef, cache_config: RouteCacheConfig, rules: RuleList) -> Self {
         let cache = if cache_config.enable {
             Some(
                 Cache::builder()
@@ -56,7 +65,56 @@ impl ClusterBasedRouter {
             None
         };
 
-        Self { cluster, cache }
+        info!("ClusterBasedRouter init with rules, rules:{rules:?}");
+
+        Self {
+            cluster,
+            cache,
+            rules: RwLock::new(Rules::new(rules)),
+        }
+    }
+
+    fn validate_rules(rules: &RuleList) -> Result<()> {
+        ensure!(
+            rules.prefix_rules.is_empty()
+                && rules.regex_rules.is_empty()
+                && rules.hash_rules.is_empty(),
+            InvalidRule {
+                msg: "only the node rules are supported in the cluster mode",
+            }
+        );
+        for rule in &rules.node_rules {
+            ensure!(
+                !rule.nodes.is_empty(),
+                InvalidRule {
+                    msg: format!("empty nodes of node rule, schema:{}", rule.schema),
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    fn node_rule(&self, schema: &str, table: &str) -> Option<NodeRule> {
+        let rules = self.rules.read().unwrap();
+        rules
+            .schema_rules
+            .get(schema)
+            .and_then(|rule_list| rule_list.match_node_rule(table))
+            .cloned()
+    }
+
+    /// Warn if the table pinned by a node rule is not placed on the pinned
+    /// nodes.
+    fn check_pinned_nodes(&self, schema: &str, table: &str, endpoint: &Endpoint) {
+        if let Some(node_rule) = self.node_rule(schema, table) {
+            if !node_rule.contains(endpoint) {
+                warn!(
+                    "Table is not placed on the pinned nodes, schema:{schema}, table:{table}, endpoint:{endpoint:?}, nodes:{:?}",
+                    node_rule.nodes
+                );
+            }
+        }
     }
 
     /// route table from local cache, return cache routes and tables which are
@@ -116,6 +174,9 @@ impl ClusterBasedRouter {
             };
 
             if let Some(route) = route {
+                if let Some(endpoint) = &route.endpoint {
+                    self.check_pinned_nodes(&route_tables_req.schema_name, &table_name, endpoint);
+                }
                 if let Some(cache) = &self.cache {
                     // There may be data race here, and it is acceptable currently.
                     cache.insert(table_name.clone(), route.clone()).await;
@@ -195,6 +256,50 @@ impl Router for ClusterBasedRouter {
         let table_info = route_data.table_info;
         Ok(Some(table_info))
     }
+
+    async fn evict(&self, tables: &[String]) {
+        if let Some(cache) = &self.cache {
+            for table in tables {
+                cache.invalidate(table).await;
+            }
+        }
+    }
+
+    async fn fetch_nodes(&self) -> Result<Vec<Endpoint>> {
+        let resp = self
+            .cluster
+            .fetch_nodes()
+            .await
+            .box_err()
+            .context(OtherWithCause {
+                msg: "Failed to fetch nodes by cluster",
+            })?;
+
+        let mut nodes = Vec::with_capacity(resp.cluster_nodes.len());
+        for node_shard in resp.cluster_nodes.iter() {
+            let endpoint: Endpoint = node_shard.endpoint.parse().context(ParseEndpoint {
+                endpoint: &node_shard.endpoint,
+            })?;
+            if !nodes.contains(&endpoint) {
+                nodes.push(endpoint);
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    fn rules(&self) -> Option<RuleList> {
+        Some(self.rules.read().unwrap().origin.clone())
+    }
+
+    fn set_rules(&self, rules: RuleList) -> Result<()> {
+        Self::validate_rules(&rules)?;
+
+        info!("ClusterBasedRouter update rules, rules:{rules:?}");
+        *self.rules.write().unwrap() = Rules::new(rules);
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -213,6 +318,7 @@ mod tests {
     use time_ext::ReadableDuration;
 
     use super::*;
+    use crate::rule_based::{PrefixRule, TablePattern};
 
     struct MockClusterImpl {}
 
@@ -300,7 +406,7 @@ mod tests {
             tti: ReadableDuration::from(Duration::from_secs(2)),
             capacity: 2,
         };
-        let router = ClusterBasedRouter::new(Arc::new(mock_cluster), config);
+        let router = ClusterBasedRouter::new(Arc::new(mock_cluster), config, RuleList::default());
 
         let table1 = "table1";
         let table2 = "table2";
@@ -342,4 +448,40 @@ mod tests {
         assert_eq!(miss.len(), 1);
         assert_eq!(miss[0], table2.to_string());
     }
+
+    #[test]
+    fn test_set_rules() {
+        let router = ClusterBasedRouter::new(
+            Arc::new(MockClusterImpl {}),
+            RouteCacheConfig::default(),
+            RuleList::default(),
+        );
+
+        let node_rule = NodeRule {
+            schema: "public".to_string(),
+            pattern: TablePattern::new("tenant_a_.*").unwrap(),
+            nodes: vec!["127.0.0.1:8831".parse().unwrap()],
+        };
+        let rules = RuleList {
+            node_rules: vec![node_rule.clone()],
+            ..Default::default()
+        };
+        router.set_rules(rules).unwrap();
+        assert_eq!(1, router.rules().unwrap().node_rules.len());
+        assert!(router.node_rule("public", "tenant_a_cpu").is_some());
+        assert!(router.node_rule("public", "tenant_b_cpu").is_none());
+
+        // The shards are placed by the meta service.
+        let rules = RuleList {
+            node_rules: vec![node_rule],
+            prefix_rules: vec![PrefixRule {
+                schema: "public".to_string(),
+                prefix: "tenant_b".to_string(),
+                shard: 0,
+            }],
+            ..Default::default()
+        };
+        assert!(router.set_rules(rules).is_err());
+        assert!(router.rules().unwrap().prefix_rules.is_empty());
+    }
 }
@@ -21,9 +21,10 @@ use std::str::FromStr;
 
 use generic_error::GenericError;
 use horaedbproto::storage;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash, Serialize, JsonSchema)]
 pub struct Endpoint {
     pub addr: String,
     pub port: u16,
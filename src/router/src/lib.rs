@@ -23,6 +23,7 @@ use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 pub use cluster_based::ClusterBasedRouter;
+use endpoint::Endpoint;
 use horaedbproto::storage::{Route, RouteRequest as RouteRequestPb};
 use macros::define_result;
 use meta_client::types::TableInfo;
@@ -62,6 +63,15 @@ pub enum Error {
         source: Box<dyn std::error::Error + Sync + Send>,
     },
 
+    #[snafu(display("Invalid route rule, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    InvalidRule { msg: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Route rules are not supported by the router.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    RulesNotSupported { backtrace: Backtrace },
+
     #[snafu(display("Failure caused by others, msg:{}, err:{}", msg, source))]
     OtherWithCause {
         msg: String,
@@ -80,6 +90,25 @@ pub type RouterRef = Arc<dyn Router + Sync + Send>;
 pub trait Router {
     async fn route(&self, req: RouteRequest) -> Result<Vec<Route>>;
     async fn fetch_table_info(&self, schema: &str, table: &str) -> Result<Option<TableInfo>>;
+
+    /// Evict the cached routes of the tables, e.g. after they are dropped.
+    async fn evict(&self, _tables: &[String]) {}
+
+    /// Get the endpoints of all the nodes of the cluster, empty if the router
+    /// doesn't know the nodes.
+    async fn fetch_nodes(&self) -> Result<Vec<Endpoint>> {
+        Ok(Vec::new())
+    }
+
+    /// Get the route rules, `None` if the router doesn't route by rules.
+    fn rules(&self) -> Option<RuleList> {
+        None
+    }
+
+    /// Replace the route rules at runtime.
+    fn set_rules(&self, _rules: RuleList) -> Result<()> {
+        RulesNotSupported.fail()
+    }
 }
 
 pub struct RouteRequest {
@@ -17,18 +17,21 @@
 
 //! A router based on rules.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, sync::RwLock};
 
 use async_trait::async_trait;
 use cluster::config::SchemaConfig;
 use horaedbproto::storage::{self, Route};
 use logger::info;
 use meta_client::types::ShardId;
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use snafu::{ensure, OptionExt};
 
 use crate::{
-    endpoint::Endpoint, hash, Result, RouteNotFound, RouteRequest, Router, ShardNotFound, TableInfo,
+    endpoint::Endpoint, hash, InvalidRule, Result, RouteNotFound, RouteRequest, Router,
+    ShardNotFound, TableInfo,
 };
 
 pub type ShardNodes = HashMap<ShardId, Endpoint>;
@@ -39,7 +42,7 @@ pub struct ClusterView {
     pub schema_configs: HashMap<String, SchemaConfig>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct PrefixRule {
     /// Schema name of the prefix.
     pub schema: String,
@@ -49,7 +52,95 @@ pub struct PrefixRule {
     pub shard: ShardId,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Regex on the table name, which must match the whole name.
+#[derive(Clone)]
+pub struct TablePattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl TablePattern {
+    pub fn new(pattern: &str) -> std::result::Result<Self, regex::Error> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub(crate) fn is_match(&self, table: &str) -> bool {
+        self.regex.is_match(table)
+    }
+}
+
+impl fmt::Debug for TablePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl Serialize for TablePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl JsonSchema for TablePattern {
+    fn schema_name() -> String {
+        "TablePattern".to_string()
+    }
+
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl<'de> Deserialize<'de> for TablePattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        TablePattern::new(&pattern).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct RegexRule {
+    /// Schema name of the pattern.
+    pub schema: String,
+    /// Pattern of the table name.
+    pub pattern: TablePattern,
+    /// The shard of matched tables.
+    pub shard: ShardId,
+}
+
+/// Pin the matched tables to a set of nodes, e.g. the dedicated nodes of a
+/// tenant.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct NodeRule {
+    /// Schema name of the pattern.
+    pub schema: String,
+    /// Pattern of the table name.
+    pub pattern: TablePattern,
+    /// The nodes of matched tables.
+    pub nodes: Vec<Endpoint>,
+}
+
+impl NodeRule {
+    #[inline]
+    pub(crate) fn contains(&self, endpoint: &Endpoint) -> bool {
+        self.nodes.contains(endpoint)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct HashRule {
     /// Schema name of the prefix.
     pub schema: String,
@@ -57,10 +148,12 @@ pub struct HashRule {
     pub shards: Vec<ShardId>,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
 pub struct RuleList {
     pub prefix_rules: Vec<PrefixRule>,
+    pub regex_rules: Vec<RegexRule>,
+    pub node_rules: Vec<NodeRule>,
     pub hash_rules: Vec<HashRule>,
 }
 
@@ -79,6 +172,28 @@ impl RuleList {
             rule_list.prefix_rules.push(rule);
         }
 
+        for rule in self.regex_rules {
+            let rule_list = match schema_rules.get_mut(&rule.schema) {
+                Some(v) => v,
+                None => schema_rules
+                    .entry(rule.schema.clone())
+                    .or_insert_with(RuleList::default),
+            };
+
+            rule_list.regex_rules.push(rule);
+        }
+
+        for rule in self.node_rules {
+            let rule_list = match schema_rules.get_mut(&rule.schema) {
+                Some(v) => v,
+                None => schema_rules
+                    .entry(rule.schema.clone())
+                    .or_insert_with(RuleList::default),
+            };
+
+            rule_list.node_rules.push(rule);
+        }
+
         for rule in self.hash_rules {
             let rule_list = match schema_rules.get_mut(&rule.schema) {
                 Some(v) => v,
@@ -92,38 +207,150 @@ impl RuleList {
 
         schema_rules
     }
+
+    /// Find the node rule of the `table` in the rule list of its schema.
+    pub(crate) fn match_node_rule(&self, table: &str) -> Option<&NodeRule> {
+        self.node_rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(table))
+    }
 }
 
 // Schema -> Rule list of the schema.
 type SchemaRules = HashMap<String, RuleList>;
 
+pub(crate) struct Rules {
+    /// The rules set by the user.
+    pub(crate) origin: RuleList,
+    pub(crate) schema_rules: SchemaRules,
+}
+
+impl Rules {
+    pub(crate) fn new(origin: RuleList) -> Self {
+        let schema_rules = origin.clone().split_by_schema();
+        Self {
+            origin,
+            schema_rules,
+        }
+    }
+}
+
 pub struct RuleBasedRouter {
     cluster_view: ClusterView,
-    schema_rules: SchemaRules,
+    rules: RwLock<Rules>,
 }
 
 impl RuleBasedRouter {
     pub fn new(cluster_view: ClusterView, rules: RuleList) -> Self {
-        let schema_rules = rules.split_by_schema();
+        let rules = Rules::new(rules);
 
         info!(
             "RuleBasedRouter init with rules, rules:{:?}, cluster_view:{:?}",
-            schema_rules, cluster_view
+            rules.schema_rules, cluster_view
         );
 
         Self {
-            schema_rules,
+            rules: RwLock::new(rules),
             cluster_view,
         }
     }
 
-    fn maybe_route_by_rule(table: &str, rule_list: &RuleList) -> Option<ShardId> {
+    /// Ensure the shards and nodes referred by the `rules` exist in the
+    /// cluster.
+    fn validate_rules(&self, rules: &RuleList) -> Result<()> {
+        let check_shard = |schema: &str, shard: ShardId| -> Result<()> {
+            let exists = self
+                .cluster_view
+                .schema_shards
+                .get(schema)
+                .map(|shard_nodes| shard_nodes.contains_key(&shard))
+                .unwrap_or(false);
+            ensure!(
+                exists,
+                InvalidRule {
+                    msg: format!("shard not found, schema:{schema}, shard:{shard}"),
+                }
+            );
+
+            Ok(())
+        };
+
+        for rule in &rules.prefix_rules {
+            check_shard(&rule.schema, rule.shard)?;
+        }
+        for rule in &rules.regex_rules {
+            check_shard(&rule.schema, rule.shard)?;
+        }
+        for rule in &rules.node_rules {
+            ensure!(
+                !rule.nodes.is_empty(),
+                InvalidRule {
+                    msg: format!("empty nodes of node rule, schema:{}", rule.schema),
+                }
+            );
+            for node in &rule.nodes {
+                let exists = self
+                    .cluster_view
+                    .schema_shards
+                    .get(&rule.schema)
+                    .map(|shard_nodes| shard_nodes.values().any(|v| v == node))
+                    .unwrap_or(false);
+                ensure!(
+                    exists,
+                    InvalidRule {
+                        msg: format!("node not found, schema:{}, node:{node:?}", rule.schema),
+                    }
+                );
+            }
+        }
+        for rule in &rules.hash_rules {
+            ensure!(
+                !rule.shards.is_empty(),
+                InvalidRule {
+                    msg: format!("empty shards of hash rule, schema:{}", rule.schema),
+                }
+            );
+            for shard in &rule.shards {
+                check_shard(&rule.schema, *shard)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn maybe_route_by_rule(
+        table: &str,
+        rule_list: &RuleList,
+        shard_nodes: &ShardNodes,
+    ) -> Option<ShardId> {
         for prefix_rule in &rule_list.prefix_rules {
             if table.starts_with(&prefix_rule.prefix) {
                 return Some(prefix_rule.shard);
             }
         }
 
+        for regex_rule in &rule_list.regex_rules {
+            if regex_rule.pattern.is_match(table) {
+                return Some(regex_rule.shard);
+            }
+        }
+
+        if let Some(node_rule) = rule_list.match_node_rule(table) {
+            // Hash the table to the shards on the pinned nodes.
+            let mut shards: Vec<_> = shard_nodes
+                .iter()
+                .filter(|(_, endpoint)| node_rule.contains(endpoint))
+                .map(|(shard, _)| *shard)
+                .collect();
+            if !shards.is_empty() {
+                shards.sort_unstable();
+                let hash_value = hash::hash_table(table);
+                let index = hash_value as usize % shards.len();
+
+                return Some(shards[index]);
+            }
+        }
+
         if let Some(hash_rule) = rule_list.hash_rules.first() {
             let total_shards = hash_rule.shards.len();
             let hash_value = hash::hash_table(table);
@@ -141,15 +368,20 @@ impl RuleBasedRouter {
         (hash_value as usize % total_shards) as ShardId
     }
 
-    fn route_table(table: &str, rule_list_opt: Option<&RuleList>, total_shards: usize) -> ShardId {
+    fn route_table(
+        table: &str,
+        rule_list_opt: Option<&RuleList>,
+        shard_nodes: &ShardNodes,
+    ) -> ShardId {
         if let Some(rule_list) = rule_list_opt {
-            if let Some(shard_id) = Self::maybe_route_by_rule(table, rule_list) {
+            if let Some(shard_id) = Self::maybe_route_by_rule(table, rule_list, shard_nodes) {
                 return shard_id;
             }
         }
 
         // Fallback to hash route rule.
-        Self::route_by_hash(table, total_shards)
+        // TODO(yingwen): Better way to get total shard number
+        Self::route_by_hash(table, shard_nodes.len())
     }
 }
 
@@ -162,13 +394,12 @@ impl Router for RuleBasedRouter {
             ensure!(!shard_nodes.is_empty(), RouteNotFound { schema });
 
             // Get rule list of this schema.
-            let rule_list_opt = self.schema_rules.get(schema);
+            let rules = self.rules.read().unwrap();
+            let rule_list_opt = rules.schema_rules.get(schema);
 
-            // TODO(yingwen): Better way to get total shard number
-            let total_shards = shard_nodes.len();
             let mut route_results = Vec::with_capacity(req.inner.tables.len());
             for table in req.inner.tables {
-                let shard_id = Self::route_table(&table, rule_list_opt, total_shards);
+                let shard_id = Self::route_table(&table, rule_list_opt, shard_nodes);
 
                 let endpoint = shard_nodes.get(&shard_id).with_context(|| ShardNotFound {
                     schema,
@@ -191,4 +422,164 @@ impl Router for RuleBasedRouter {
     async fn fetch_table_info(&self, _schema: &str, _table: &str) -> Result<Option<TableInfo>> {
         return Ok(None);
     }
+
+    async fn fetch_nodes(&self) -> Result<Vec<Endpoint>> {
+        let mut nodes: Vec<_> = self
+            .cluster_view
+            .schema_shards
+            .values()
+            .flat_map(|shard_nodes| shard_nodes.values().cloned())
+            .collect();
+        nodes.sort_unstable_by(|a, b| (&a.addr, a.port).cmp(&(&b.addr, b.port)));
+        nodes.dedup();
+
+        Ok(nodes)
+    }
+
+    fn rules(&self) -> Option<RuleList> {
+        Some(self.rules.read().unwrap().origin.clone())
+    }
+
+    fn set_rules(&self, rules: RuleList) -> Result<()> {
+        self.validate_rules(&rules)?;
+
+        let rules = Rules::new(rules);
+        info!(
+            "RuleBasedRouter update rules, rules:{:?}",
+            rules.schema_rules
+        );
+        *self.rules.write().unwrap() = rules;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horaedbproto::storage::{RequestContext, RouteRequest as RouteRequestPb};
+
+    use super::*;
+
+    fn route_request(tables: &[&str]) -> RouteRequest {
+        let request_pb = RouteRequestPb {
+            context: Some(RequestContext {
+                database: String::from("public"),
+            }),
+            tables: tables.iter().map(|v| v.to_string()).collect(),
+        };
+        RouteRequest::new(request_pb, false)
+    }
+
+    fn routed_ports(routes: Vec<Route>) -> Vec<u32> {
+        routes
+            .into_iter()
+            .map(|route| route.endpoint.unwrap().port)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_route_by_regex_rule() {
+        let shard_nodes = (0..3)
+            .map(|shard| (shard, Endpoint::new("127.0.0.1".to_string(), shard as u16)))
+            .collect();
+        let cluster_view = ClusterView {
+            schema_shards: HashMap::from([("public".to_string(), shard_nodes)]),
+            schema_configs: HashMap::new(),
+        };
+        let regex_rule = |pattern: &str, shard| RegexRule {
+            schema: "public".to_string(),
+            pattern: TablePattern::new(pattern).unwrap(),
+            shard,
+        };
+        let rules = RuleList {
+            regex_rules: vec![regex_rule("tenant_[ab]_.*", 1)],
+            hash_rules: vec![HashRule {
+                schema: "public".to_string(),
+                shards: vec![0],
+            }],
+            ..Default::default()
+        };
+        let router = RuleBasedRouter::new(cluster_view, rules);
+
+        let tables = ["tenant_a_cpu", "tenant_c_cpu", "x_tenant_a_cpu"];
+        let routes = router.route(route_request(&tables)).await.unwrap();
+        assert_eq!(vec![1, 0, 0], routed_ports(routes));
+
+        // Update the rules at runtime.
+        let mut rules = router.rules().unwrap();
+        rules.regex_rules = vec![regex_rule("tenant_c_.*", 2)];
+        router.set_rules(rules).unwrap();
+        let routes = router.route(route_request(&tables)).await.unwrap();
+        assert_eq!(vec![0, 2, 0], routed_ports(routes));
+
+        // The rules referring to the unknown shards are rejected.
+        let mut rules = router.rules().unwrap();
+        rules.regex_rules = vec![regex_rule("tenant_d_.*", 3)];
+        assert!(router.set_rules(rules).is_err());
+        assert_eq!(
+            "tenant_c_.*",
+            router.rules().unwrap().regex_rules[0].pattern.as_str()
+        );
+
+        assert!(TablePattern::new("tenant_(").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_route_by_node_rule() {
+        let endpoint = |port| Endpoint::new("127.0.0.1".to_string(), port);
+        let shard_nodes = (0..4)
+            .map(|shard| (shard, endpoint(shard as u16)))
+            .collect();
+        let cluster_view = ClusterView {
+            schema_shards: HashMap::from([("public".to_string(), shard_nodes)]),
+            schema_configs: HashMap::new(),
+        };
+        let node_rule = |nodes: Vec<Endpoint>| NodeRule {
+            schema: "public".to_string(),
+            pattern: TablePattern::new("tenant_a_.*").unwrap(),
+            nodes,
+        };
+        let rules = RuleList {
+            node_rules: vec![node_rule(vec![endpoint(2), endpoint(3)])],
+            ..Default::default()
+        };
+        let router = RuleBasedRouter::new(cluster_view, rules);
+
+        let tables: Vec<_> = (0..16).map(|i| format!("tenant_a_{i}")).collect();
+        let tables: Vec<_> = tables.iter().map(String::as_str).collect();
+        let routes = router.route(route_request(&tables)).await.unwrap();
+        assert!(routed_ports(routes).iter().all(|port| *port >= 2));
+
+        // The rules referring to the unknown nodes are rejected.
+        let rules = RuleList {
+            node_rules: vec![node_rule(vec![endpoint(4)])],
+            ..Default::default()
+        };
+        assert!(router.set_rules(rules).is_err());
+        let rules = RuleList {
+            node_rules: vec![node_rule(vec![])],
+            ..Default::default()
+        };
+        assert!(router.set_rules(rules).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_nodes() {
+        let endpoint = |port| Endpoint::new("127.0.0.1".to_string(), port);
+        // The nodes are shared by the schemas and the shards.
+        let shard_nodes: ShardNodes = (0..4)
+            .map(|shard| (shard, endpoint(shard as u16 % 2)))
+            .collect();
+        let cluster_view = ClusterView {
+            schema_shards: HashMap::from([
+                ("public".to_string(), shard_nodes.clone()),
+                ("test".to_string(), shard_nodes),
+            ]),
+            schema_configs: HashMap::new(),
+        };
+        let router = RuleBasedRouter::new(cluster_view, RuleList::default());
+
+        let nodes = router.fetch_nodes().await.unwrap();
+        assert_eq!(vec![endpoint(0), endpoint(1)], nodes);
+    }
 }
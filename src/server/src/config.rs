@@ -25,7 +25,14 @@ use std::{
 use cluster::config::SchemaConfig;
 use common_types::schema::TIMESTAMP_COLUMN;
 use meta_client::types::ShardId;
-use proxy::{forward, hotspot, SubTableAccessPerm};
+use object_store::config::ObjectStoreOptions;
+use proxy::{
+    admission, auth, circuit_breaker, continuous_query, forward, graphite, hotspot, influxdb,
+    ingest_sampling, json_write, maintenance, mqtt, replica_check, result_limit, schema_cache,
+    schema_registry, source, statsd, stream_query, tiering, user_metrics, write_batcher,
+    write_queue, write_tee, write_trace, SubTableAccessPerm,
+};
+use query_frontend::config::{MaskingPolicy, RowPolicy, TimeRangeGuard, WildcardLimit};
 use router::{
     endpoint::Endpoint,
     rule_based::{ClusterView, RuleList},
@@ -35,6 +42,8 @@ use size_ext::ReadableSize;
 use table_engine::ANALYTIC_ENGINE_TYPE;
 use time_ext::ReadableDuration;
 
+use crate::{console, memory_watermark};
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(default)]
 pub struct StaticRouteConfig {
@@ -125,6 +134,22 @@ impl Default for QueryDedupConfig {
     }
 }
 
+/// Config of the result store, where the results of `COPY (query) TO 'path'`
+/// are offloaded to
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResultOffloadConfig {
+    pub object_store: ObjectStoreOptions,
+    /// A new file is started after the rows of current file reach it
+    #[serde(default = "ResultOffloadConfig::default_max_rows_per_file")]
+    pub max_rows_per_file: usize,
+}
+
+impl ResultOffloadConfig {
+    fn default_max_rows_per_file() -> usize {
+        1_000_000
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 pub struct ServerConfig {
@@ -135,6 +160,16 @@ pub struct ServerConfig {
     pub http_port: u16,
     pub grpc_port: u16,
 
+    /// Whether to serve the HTTP protocol, which also serves the admin apis
+    pub enable_http: bool,
+    /// Whether to serve the MySQL protocol
+    pub enable_mysql: bool,
+    /// Whether to serve the PostgreSQL protocol
+    pub enable_postgresql: bool,
+    /// Whether to serve the gRPC protocol, it can only be disabled in the
+    /// standalone mode because the inter-node communication relies on it
+    pub enable_grpc: bool,
+
     pub timeout: Option<ReadableDuration>,
     pub http_max_body_size: ReadableSize,
     pub grpc_server_cq_count: usize,
@@ -152,6 +187,8 @@ pub struct ServerConfig {
 
     // Config of route
     pub route_cache: router::RouteCacheConfig,
+    /// Route rules of the cluster mode, only the node rules are supported.
+    pub route_rules: router::RuleList,
 
     /// Record hotspot query or write requests
     pub hotspot: hotspot::Config,
@@ -164,6 +201,119 @@ pub struct ServerConfig {
 
     /// Whether enable to access partition table
     pub sub_table_access_perm: SubTableAccessPerm,
+
+    /// Config of result offloading, `COPY TO` is rejected if not set
+    pub result_offload: Option<ResultOffloadConfig>,
+
+    /// Config of the memory watermark to shed load under memory pressure
+    pub memory_watermark: memory_watermark::Config,
+
+    /// Config of coalescing the tiny writes into larger batches
+    pub write_batch: write_batcher::Config,
+
+    /// Config of caching the tables looked up by the writes
+    pub schema_cache: schema_cache::Config,
+
+    /// Config of the schema registry to decode the ingested Avro/Protobuf rows
+    pub schema_registry: schema_registry::client::Config,
+
+    /// Config of the built-in ingestion sources consuming the kafka topics
+    pub source: source::Config,
+
+    /// Config of the continuous queries aggregating the rows periodically
+    pub continuous_query: continuous_query::Config,
+
+    /// Config of checking the consistency of the tables with their replicas
+    pub replica_check: replica_check::Config,
+
+    /// Sql http endpoints of the clusters allowed to be the targets of the
+    /// schema diff, besides the replicas of the replica check
+    pub schema_diff_targets: Vec<String>,
+
+    /// Config of the mqtt listener accepting the publishes of the devices
+    pub mqtt: mqtt::Config,
+
+    /// Config of the UDP listener receiving the StatsD metrics
+    pub statsd: statsd::Config,
+
+    /// Config of the graphite protocols
+    pub graphite: graphite::Config,
+
+    /// Mappings of the json documents written by the json write apis
+    pub json_write: json_write::Config,
+
+    /// Config of the influxdb compatible apis
+    pub influxdb: influxdb::Config,
+
+    /// Config of the built-in web console served by the http service
+    pub console: console::Config,
+
+    /// Config of sampling the writes of the specific tables to trace
+    pub write_trace: write_trace::Config,
+
+    /// Config of failing fast the writes to the tables failing repeatedly
+    pub write_circuit_breaker: circuit_breaker::Config,
+
+    /// Config of the tables under maintenance
+    pub maintenance: maintenance::Config,
+
+    /// Limit of the columns read by `SELECT *` of the wide tables
+    pub wildcard_limit: WildcardLimit,
+
+    /// Guard of the time range of the queries on the large tables
+    pub time_range_guard: TimeRangeGuard,
+
+    /// Policies of masking the columns read by the users of the requests
+    pub masking_policies: Vec<MaskingPolicy>,
+
+    /// Policies of filtering the rows read by the users of the requests
+    pub row_policies: Vec<RowPolicy>,
+
+    /// Config of routing the cold queries to the cold read nodes
+    pub tiering: tiering::Config,
+
+    /// Start the server in read-only mode, which can be changed by the admin
+    /// api later
+    pub read_only: bool,
+
+    /// Serve the queries only, e.g. over a restored backup, and the read-only
+    /// mode can't be switched off
+    pub query_only: bool,
+
+    /// Tables not accessed during this duration are flagged as unused in
+    /// `system.tables`, nothing is flagged if not set
+    pub unused_table_threshold: Option<ReadableDuration>,
+
+    /// Config of sampling the rows written to the high-volume tables, the
+    /// queries scale the aggregations by the `sample_rate` udf
+    pub ingest_sampling: ingest_sampling::Config,
+
+    /// Config of mirroring the writes of the selected tables for the testing
+    pub write_tee: write_tee::Config,
+
+    /// Config of labelling the metrics of the requests by the users
+    pub user_metrics: user_metrics::Config,
+
+    /// Config of bounding and prioritizing the writes to execute
+    pub write_queue: write_queue::Config,
+
+    /// Config of the streamed sql queries over grpc
+    pub stream_query: stream_query::Config,
+
+    /// Limits of the query results by the roles of the requests
+    pub result_limit: result_limit::Config,
+
+    /// Quotas of the tenants and the load shedding, which can be changed by
+    /// the admin api later
+    pub admission: admission::Config,
+
+    /// Config of authenticating the requests and authorizing them by the
+    /// roles granted to the users on the catalogs
+    pub auth: auth::Config,
+
+    /// Max time to wait for the in-flight requests to finish during the
+    /// graceful shutdown
+    pub shutdown_timeout: ReadableDuration,
 }
 
 impl Default for ServerConfig {
@@ -174,6 +324,10 @@ impl Default for ServerConfig {
             mysql_port: 3307,
             postgresql_port: 5433,
             grpc_port: 8831,
+            enable_http: true,
+            enable_mysql: true,
+            enable_postgresql: true,
+            enable_grpc: true,
             timeout: None,
             http_max_body_size: ReadableSize::mb(64),
             grpc_server_cq_count: 20,
@@ -182,10 +336,46 @@ impl Default for ServerConfig {
             auto_create_table: true,
             default_schema_config: Default::default(),
             route_cache: router::RouteCacheConfig::default(),
+            route_rules: router::RuleList::default(),
             hotspot: hotspot::Config::default(),
             remote_client: remote_engine_client::Config::default(),
             query_dedup: QueryDedupConfig::default(),
             sub_table_access_perm: SubTableAccessPerm::default(),
+            result_offload: None,
+            memory_watermark: memory_watermark::Config::default(),
+            write_batch: write_batcher::Config::default(),
+            schema_cache: schema_cache::Config::default(),
+            schema_registry: schema_registry::client::Config::default(),
+            source: source::Config::default(),
+            continuous_query: continuous_query::Config::default(),
+            replica_check: replica_check::Config::default(),
+            schema_diff_targets: Vec::new(),
+            mqtt: mqtt::Config::default(),
+            statsd: statsd::Config::default(),
+            graphite: graphite::Config::default(),
+            json_write: json_write::Config::default(),
+            influxdb: influxdb::Config::default(),
+            console: console::Config::default(),
+            write_trace: write_trace::Config::default(),
+            write_circuit_breaker: circuit_breaker::Config::default(),
+            maintenance: maintenance::Config::default(),
+            wildcard_limit: WildcardLimit::default(),
+            time_range_guard: TimeRangeGuard::default(),
+            masking_policies: Vec::new(),
+            row_policies: Vec::new(),
+            tiering: tiering::Config::default(),
+            read_only: false,
+            query_only: false,
+            unused_table_threshold: None,
+            ingest_sampling: ingest_sampling::Config::default(),
+            write_tee: write_tee::Config::default(),
+            user_metrics: user_metrics::Config::default(),
+            write_queue: write_queue::Config::default(),
+            stream_query: stream_query::Config::default(),
+            result_limit: result_limit::Config::default(),
+            admission: admission::Config::default(),
+            auth: auth::Config::default(),
+            shutdown_timeout: ReadableDuration::secs(30),
         }
     }
 }
@@ -0,0 +1,88 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Trigger of reloading the config of the server.
+//!
+//! The config file is reloaded by its owner, i.e. the main loop of the server,
+//! and the http admin api only sends the reloading request and waits for the
+//! result through the [ReloadTrigger]. The options set by the `ALTER SYSTEM`
+//! statements are applied by the owner in the same way.
+
+use async_trait::async_trait;
+use generic_error::GenericError;
+use interpreters::alter_system::SystemConfigManager;
+use tokio::sync::{mpsc, oneshot};
+
+/// Sections of the config changed by the reloading if succeeded, otherwise
+/// the reason of the failure.
+pub type ReloadResult = std::result::Result<Vec<String>, String>;
+
+#[derive(Debug)]
+pub enum ReloadKind {
+    /// Reload the config file.
+    File,
+    /// Set the option of the `key` in the config and persist it.
+    SetOption { key: String, value: String },
+}
+
+pub struct ReloadRequest {
+    pub kind: ReloadKind,
+    pub result_tx: oneshot::Sender<ReloadResult>,
+}
+
+#[derive(Clone)]
+pub struct ReloadTrigger {
+    tx: mpsc::Sender<ReloadRequest>,
+}
+
+impl ReloadTrigger {
+    pub async fn reload(&self) -> ReloadResult {
+        self.send(ReloadKind::File).await
+    }
+
+    pub async fn set_option(&self, key: String, value: String) -> ReloadResult {
+        self.send(ReloadKind::SetOption { key, value }).await
+    }
+
+    async fn send(&self, kind: ReloadKind) -> ReloadResult {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tx
+            .send(ReloadRequest { kind, result_tx })
+            .await
+            .map_err(|_| "Config reloading is not running".to_string())?;
+
+        result_rx
+            .await
+            .map_err(|_| "Config reloading is aborted".to_string())?
+    }
+}
+
+#[async_trait]
+impl SystemConfigManager for ReloadTrigger {
+    async fn set_option(&self, key: &str, value: &str) -> Result<Vec<String>, GenericError> {
+        ReloadTrigger::set_option(self, key.to_string(), value.to_string())
+            .await
+            .map_err(GenericError::from)
+    }
+}
+
+/// Create the trigger and the receiver of the reloading requests.
+pub fn channel() -> (ReloadTrigger, mpsc::Receiver<ReloadRequest>) {
+    let (tx, rx) = mpsc::channel(1);
+
+    (ReloadTrigger { tx }, rx)
+}
@@ -0,0 +1,39 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Built-in web console of the http service for running the sql, browsing the
+//! schemas and watching the node, served at `/console`.
+//!
+//! The console is authenticated and authorized as the other apis, so it's only
+//! served when the authentication is enabled.
+
+use serde::{Deserialize, Serialize};
+
+/// The page of the console, which calls the apis under `/console/api`.
+pub(crate) const INDEX_HTML: &str = include_str!("console.html");
+/// Challenge responded to the unauthorized requests, so the browsers can ask
+/// for the credentials.
+pub(crate) const AUTHENTICATE_CHALLENGE: &str = "Basic realm=\"horaedb console\"";
+/// Prefix of the apis called by the console.
+pub(crate) const API_PREFIX: &str = "/console/api";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to serve the console.
+    pub enable: bool,
+}
@@ -20,7 +20,7 @@
 use std::{collections::HashMap, env, sync::Arc};
 
 use arrow::{
-    array::StringArray,
+    array::{StringArray, UInt16Array},
     datatypes::{DataType, Field, Schema},
     record_batch::RecordBatch,
 };
@@ -49,6 +49,10 @@ static SELECT_DATABASE_PATTERN: Lazy<Regex> =
 static SELECT_TIME_DIFF_FUNC_PATTERN: Lazy<Regex> =
     Lazy::new(|| Regex::new("(?i)^(SELECT TIMEDIFF\\(NOW\\(\\), UTC_TIMESTAMP\\(\\)\\))").unwrap());
 
+static SHOW_WARNINGS_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new("(?i)^(SHOW WARNINGS|/\\* ApplicationName=(.*)SHOW WARNINGS)").unwrap()
+});
+
 // sqlalchemy < 1.4.30
 static SHOW_SQL_MODE_PATTERN: Lazy<Regex> =
     Lazy::new(|| Regex::new("(?i)^(SHOW VARIABLES LIKE 'sql_mode'(.*))").unwrap());
@@ -94,8 +98,6 @@ static OTHER_NOT_SUPPORTED_STMT: Lazy<RegexSet> = Lazy::new(|| {
         "(?i)^(/\\*!40101 SET(.*) \\*/)$",
 
         // DBeaver.
-        "(?i)^(SHOW WARNINGS)",
-        "(?i)^(/\\* ApplicationName=(.*)SHOW WARNINGS)",
         "(?i)^(/\\* ApplicationName=(.*)SHOW PLUGINS)",
         "(?i)^(/\\* ApplicationName=(.*)SHOW COLLATION)",
         "(?i)^(/\\* ApplicationName=(.*)SHOW CHARSET)",
@@ -248,6 +250,46 @@ fn check_show_variables(query: &str) -> Option<Output> {
     record_batch_vec.map(Output::Records)
 }
 
+// RecordBatchVec for show warnings statement, the warnings of the last query
+// of the session.
+// Format is:
+// | Level   | Code | Message |
+// | Warning | xx   | yy      |
+fn show_warnings(session: &SessionRef) -> RecordBatchVec {
+    let warnings = session.warnings();
+    let schema = Schema::new(vec![
+        Field::new("Level", DataType::Utf8, false),
+        Field::new("Code", DataType::UInt16, false),
+        Field::new("Message", DataType::Utf8, false),
+    ]);
+
+    let arrow_record_batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(StringArray::from(vec!["Warning"; warnings.len()])),
+            Arc::new(UInt16Array::from_iter_values(
+                warnings.iter().map(|v| v.kind.code()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                warnings.iter().map(|v| v.to_string()),
+            )),
+        ],
+    )
+    .unwrap();
+
+    let record_batch = arrow_record_batch.try_into().unwrap();
+
+    vec![record_batch]
+}
+
+fn check_show_warnings(query: &str, session: &SessionRef) -> Option<Output> {
+    if SHOW_WARNINGS_PATTERN.is_match(query) {
+        Some(Output::Records(show_warnings(session)))
+    } else {
+        None
+    }
+}
+
 // TODO(sunng87): extract this to use sqlparser for more variables
 fn check_set_variables(_query: &str, _session: SessionRef) -> Option<Output> {
     None
@@ -289,6 +331,7 @@ pub(crate) fn check(query: &str, session: SessionRef) -> Option<Output> {
     check_select_variable(query, session.clone())
         // Then to check "show variables like ...".
         .or_else(|| check_show_variables(query))
+        .or_else(|| check_show_warnings(query, &session))
         .or_else(|| check_set_variables(query, session.clone()))
         // Last check
         .or_else(|| check_others(query, session))
@@ -301,6 +344,7 @@ fn get_version() -> String {
 #[cfg(test)]
 mod test {
     use arrow::util::pretty;
+    use table_engine::query_warning::{Warning, WarningKind};
 
     use super::*;
     use crate::session::{Channel, Session};
@@ -396,5 +440,22 @@ mod test {
 | 00:00:00                         |
 +----------------------------------+";
         test(query, expected);
+
+        let query = "show warnings";
+        session.set_warnings(vec![Warning::new(
+            WarningKind::ImplicitLimit,
+            "columns are limited".to_string(),
+        )]);
+        let output = check(query, session.clone());
+        let expected = "\
++---------+------+-------------------------------------+
+| Level   | Code | Message                             |
++---------+------+-------------------------------------+
+| Warning | 1001 | implicit_limit: columns are limited |
++---------+------+-------------------------------------+";
+        match output.unwrap() {
+            Output::Records(r) => assert_eq!(pretty_print(r), expected),
+            _ => unreachable!(),
+        }
     }
 }
@@ -0,0 +1,88 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use proxy::{graphite::Config, Proxy};
+use snafu::{OptionExt, ResultExt};
+use table_engine::engine::EngineRuntimes;
+
+use crate::graphite::{
+    error::{MissingProxy, MissingRuntimes, ParseIpAddr, Result},
+    GraphiteService,
+};
+
+pub struct Builder {
+    ip: String,
+    config: Config,
+    runtimes: Option<Arc<EngineRuntimes>>,
+    proxy: Option<Arc<Proxy>>,
+    timeout: Option<Duration>,
+}
+
+impl Builder {
+    pub fn new(config: Config) -> Self {
+        Self {
+            ip: "127.0.0.1".to_string(),
+            config,
+            runtimes: None,
+            proxy: None,
+            timeout: None,
+        }
+    }
+
+    pub fn build(self) -> Result<GraphiteService> {
+        let runtimes = self.runtimes.context(MissingRuntimes)?;
+        let proxy = self.proxy.context(MissingProxy)?;
+
+        let addr: SocketAddr = format!("{}:{}", self.ip, self.config.port)
+            .parse()
+            .context(ParseIpAddr { ip: &self.ip })?;
+        let pickle_addr: SocketAddr = format!("{}:{}", self.ip, self.config.pickle_port)
+            .parse()
+            .context(ParseIpAddr { ip: self.ip })?;
+
+        Ok(GraphiteService::new(
+            self.config,
+            proxy,
+            runtimes,
+            addr,
+            pickle_addr,
+            self.timeout,
+        ))
+    }
+
+    pub fn ip(mut self, ip: String) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    pub fn runtimes(mut self, runtimes: Arc<EngineRuntimes>) -> Self {
+        self.runtimes = Some(runtimes);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Arc<Proxy>) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
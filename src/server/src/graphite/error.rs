@@ -0,0 +1,52 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use macros::define_result;
+use snafu::{Backtrace, Snafu};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Missing runtimes to build service.\nBacktrace:\n{}", backtrace))]
+    MissingRuntimes { backtrace: Backtrace },
+
+    #[snafu(display("Missing proxy to build service.\nBacktrace:\n{}", backtrace))]
+    MissingProxy { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse ip addr, ip:{}, err:{}.\nBacktrace:\n{}",
+        ip,
+        source,
+        backtrace
+    ))]
+    ParseIpAddr {
+        ip: String,
+        source: std::net::AddrParseError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unexpected error, err:{}", source))]
+    Unexpected { source: std::io::Error },
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Unexpected { source: e }
+    }
+}
+
+define_result!(Error);
@@ -0,0 +1,246 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use logger::{debug, error, info, warn};
+use proxy::{
+    context::RequestContext,
+    graphite::{
+        types::{parse_pickle, parse_plaintext_line, Point},
+        Config,
+    },
+    Proxy,
+};
+use runtime::JoinHandle;
+use table_engine::engine::EngineRuntimes;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::oneshot::{self, Receiver, Sender},
+};
+
+use crate::graphite::error::Result;
+
+/// The received points are written if no more point is received in it.
+const FLUSH_IDLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Max size of a message of the pickle protocol.
+const MAX_PICKLE_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+enum Protocol {
+    Plaintext,
+    Pickle,
+}
+
+pub struct GraphiteService {
+    addr: SocketAddr,
+    pickle_addr: SocketAddr,
+    config: Arc<Config>,
+    proxy: Arc<Proxy>,
+    runtimes: Arc<EngineRuntimes>,
+    join_handler: Option<JoinHandle<()>>,
+    tx: Option<Sender<()>>,
+    timeout: Option<Duration>,
+}
+
+impl GraphiteService {
+    pub fn new(
+        config: Config,
+        proxy: Arc<Proxy>,
+        runtimes: Arc<EngineRuntimes>,
+        addr: SocketAddr,
+        pickle_addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            addr,
+            pickle_addr,
+            config: Arc::new(config),
+            proxy,
+            runtimes,
+            join_handler: None,
+            tx: None,
+            timeout,
+        }
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.tx = Some(tx);
+
+        info!(
+            "Graphite server tries to listen on {} for plaintext and {} for pickle",
+            self.addr, self.pickle_addr
+        );
+
+        let handler = Handler {
+            config: self.config.clone(),
+            proxy: self.proxy.clone(),
+            timeout: self.timeout,
+        };
+        self.join_handler = Some(self.runtimes.default_runtime.spawn(Self::loop_accept(
+            handler,
+            self.runtimes.clone(),
+            self.addr,
+            self.pickle_addr,
+            rx,
+        )));
+
+        Ok(())
+    }
+
+    pub fn shutdown(self) {
+        if let Some(tx) = self.tx {
+            let _ = tx.send(());
+        }
+    }
+
+    async fn loop_accept(
+        handler: Handler,
+        runtimes: Arc<EngineRuntimes>,
+        addr: SocketAddr,
+        pickle_addr: SocketAddr,
+        mut rx: Receiver<()>,
+    ) {
+        let bind = |addr| async move {
+            TcpListener::bind(addr).await.unwrap_or_else(|e| {
+                panic!("Graphite server listens failed, addr:{addr}, err:{e}");
+            })
+        };
+        let listener = bind(addr).await;
+        let pickle_listener = bind(pickle_addr).await;
+
+        loop {
+            let (conn_result, protocol) = tokio::select! {
+                conn_result = listener.accept() => (conn_result, Protocol::Plaintext),
+                conn_result = pickle_listener.accept() => (conn_result, Protocol::Pickle),
+                _ = &mut rx => break,
+            };
+            let (stream, peer) = match conn_result {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Graphite server accept new connection fail, err:{e}");
+                    break;
+                }
+            };
+
+            let handler = handler.clone();
+            runtimes.write_runtime.spawn(async move {
+                let res = match protocol {
+                    Protocol::Plaintext => handler.handle_plaintext(stream).await,
+                    Protocol::Pickle => handler.handle_pickle(stream).await,
+                };
+                if let Err(e) = res {
+                    warn!("Graphite connection is closed, peer:{peer}, protocol:{protocol:?}, err:{e}");
+                }
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Handler {
+    config: Arc<Config>,
+    proxy: Arc<Proxy>,
+    timeout: Option<Duration>,
+}
+
+impl Handler {
+    async fn handle_plaintext(&self, stream: TcpStream) -> Result<()> {
+        let mut lines = BufReader::new(stream).lines();
+        let mut points = Vec::new();
+        loop {
+            // The `next_line` is cancel safe, so no line is lost on the timeout.
+            match tokio::time::timeout(FLUSH_IDLE_INTERVAL, lines.next_line()).await {
+                Ok(Ok(Some(line))) => {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match parse_plaintext_line(&line) {
+                        Ok(point) => points.push(point),
+                        Err(e) => debug!("Drop invalid graphite line, err:{e}"),
+                    }
+                    if points.len() >= self.config.max_batch_points {
+                        self.write(std::mem::take(&mut points)).await;
+                    }
+                }
+                Ok(Ok(None)) => break,
+                Ok(Err(e)) => {
+                    self.write(points).await;
+                    return Err(e.into());
+                }
+                Err(_) => self.write(std::mem::take(&mut points)).await,
+            }
+        }
+
+        self.write(points).await;
+
+        Ok(())
+    }
+
+    async fn handle_pickle(&self, mut stream: TcpStream) -> Result<()> {
+        loop {
+            let len = match stream.read_u32().await {
+                Ok(v) => v as usize,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            if len > MAX_PICKLE_SIZE {
+                warn!("Graphite pickle is too large, size:{len}, limit:{MAX_PICKLE_SIZE}");
+                return Ok(());
+            }
+
+            let mut payload = vec![0; len];
+            stream.read_exact(&mut payload).await?;
+            match parse_pickle(&payload) {
+                Ok(points) => self.write(points).await,
+                Err(e) => debug!("Drop invalid graphite pickle, err:{e}"),
+            }
+        }
+    }
+
+    /// The errors are logged as the protocols have no response.
+    async fn write(&self, points: Vec<Point>) {
+        if points.is_empty() {
+            return;
+        }
+
+        let catalog_manager = &self.proxy.instance().catalog_manager;
+        let schema = if self.config.schema.is_empty() {
+            catalog_manager.default_schema_name().to_string()
+        } else {
+            self.config.schema.clone()
+        };
+        let ctx = match RequestContext::builder()
+            .catalog(catalog_manager.default_catalog_name().to_string())
+            .schema(schema)
+            .timeout(self.timeout)
+            .build()
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to create graphite request context, err:{e}");
+                return;
+            }
+        };
+
+        if let Err(e) = self.proxy.handle_graphite_write(ctx, points).await {
+            error!("Failed to write graphite points, err:{e}");
+        }
+    }
+}
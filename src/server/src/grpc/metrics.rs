@@ -33,6 +33,7 @@ make_auto_flush_static_metric! {
         handle_prom_query,
         handle_stream_write,
         handle_stream_sql_query,
+        handle_subscribe_table_changes,
     }
 
     pub struct GrpcHandlerDurationHistogramVec: LocalHistogram {
@@ -18,6 +18,7 @@
 //! Grpc services
 
 use std::{
+    fs,
     net::{AddrParseError, SocketAddr},
     stringify,
     sync::Arc,
@@ -25,7 +26,10 @@ use std::{
 };
 
 use cluster::ClusterRef;
-use common_types::column_schema;
+use common_types::{
+    column_schema,
+    protocol::{Protocol, ProtocolConfig},
+};
 use futures::FutureExt;
 use generic_error::GenericError;
 use horaedbproto::{
@@ -37,6 +41,7 @@ use logger::{info, warn};
 use macros::define_result;
 use notifier::notifier::RequestNotifiers;
 use proxy::{
+    auth::{AuthUser, GrpcTlsConfig, AUTHORIZATION_KEY},
     forward,
     hotspot::HotspotRecorder,
     instance::InstanceRef,
@@ -44,18 +49,23 @@ use proxy::{
     Proxy,
 };
 use runtime::{JoinHandle, Runtime};
-use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use table_engine::engine::EngineRuntimes;
 use tokio::sync::oneshot::{self, Sender};
-use tonic::transport::Server;
+use tonic::{
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::{Certificate, ClientTlsConfig, Identity, Server, ServerTlsConfig},
+};
 use wal::manager::OpenedWals;
 
 use self::remote_engine_service::QueryDedup;
 use crate::{
     config::QueryDedupConfig,
     grpc::{
-        meta_event_service::MetaServiceImpl, remote_engine_service::RemoteEngineServiceImpl,
+        meta_event_service::MetaServiceImpl,
+        remote_engine_service::RemoteEngineServiceImpl,
         storage_service::StorageServiceImpl,
+        table_changes_service::{TableChangesServiceImpl, TableChangesServiceServer},
     },
 };
 
@@ -63,6 +73,7 @@ mod meta_event_service;
 mod metrics;
 mod remote_engine_service;
 mod storage_service;
+mod table_changes_service;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -151,16 +162,37 @@ pub enum Error {
     GetSchemaConfig {
         source: schema_config_provider::Error,
     },
+
+    #[snafu(display(
+        "Failed to read tls file, path:{}, err:{}.\nBacktrace:\n{}",
+        path,
+        source,
+        backtrace
+    ))]
+    ReadTlsFile {
+        path: String,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Missing ca of grpc tls.\nBacktrace:\n{}", backtrace))]
+    MissingTlsCa { backtrace: Backtrace },
 }
 
 define_result!(Error);
 
+type MetaEventServer = InterceptedService<MetaEventServiceServer<MetaServiceImpl>, InternalAuth>;
+type RemoteEngineServer =
+    InterceptedService<RemoteEngineServiceServer<RemoteEngineServiceImpl>, InternalAuth>;
+
 /// Rpc services manages all grpc services of the server.
 pub struct RpcServices {
     serve_addr: SocketAddr,
     rpc_server: StorageServiceServer<StorageServiceImpl>,
-    meta_rpc_server: Option<MetaEventServiceServer<MetaServiceImpl>>,
-    remote_engine_server: RemoteEngineServiceServer<RemoteEngineServiceImpl>,
+    meta_rpc_server: Option<MetaEventServer>,
+    remote_engine_server: RemoteEngineServer,
+    table_changes_server: TableChangesServiceServer,
+    tls: Option<ServerTlsConfig>,
     runtime: Arc<Runtime>,
     stop_tx: Option<Sender<()>>,
     join_handle: Option<JoinHandle<()>>,
@@ -171,12 +203,17 @@ impl RpcServices {
         let rpc_server = self.rpc_server.clone();
         let meta_rpc_server = self.meta_rpc_server.clone();
         let remote_engine_server = self.remote_engine_server.clone();
+        let table_changes_server = self.table_changes_server.clone();
         let serve_addr = self.serve_addr;
+        let mut server = Server::builder();
+        if let Some(tls) = &self.tls {
+            server = server.tls_config(tls.clone()).context(FailServe)?;
+        }
         let (stop_tx, stop_rx) = oneshot::channel();
         let join_handle = self.runtime.spawn(async move {
             info!("Grpc server tries to listen on {}", serve_addr);
 
-            let mut router = Server::builder().add_service(rpc_server);
+            let mut router = server.add_service(rpc_server);
 
             if let Some(s) = meta_rpc_server {
                 info!("Grpc server serves meta rpc service");
@@ -186,6 +223,9 @@ impl RpcServices {
             info!("Grpc server serves remote engine rpc service");
             router = router.add_service(remote_engine_server);
 
+            info!("Grpc server serves table changes rpc service");
+            router = router.add_service(table_changes_server);
+
             router
                 .serve_with_shutdown(serve_addr, stop_rx.map(drop))
                 .await
@@ -221,6 +261,8 @@ pub struct Builder {
     proxy: Option<Arc<Proxy>>,
     query_dedup_config: Option<QueryDedupConfig>,
     hotspot_recorder: Option<Arc<HotspotRecorder>>,
+    protocol_config: ProtocolConfig,
+    tls: Option<ServerTlsConfig>,
 }
 
 impl Builder {
@@ -235,6 +277,8 @@ impl Builder {
             proxy: None,
             query_dedup_config: None,
             hotspot_recorder: None,
+            protocol_config: ProtocolConfig::default(),
+            tls: None,
         }
     }
 
@@ -283,6 +327,16 @@ impl Builder {
         self.query_dedup_config = Some(config);
         self
     }
+
+    pub fn protocol(mut self, config: ProtocolConfig) -> Self {
+        self.protocol_config = config;
+        self
+    }
+
+    pub fn tls(mut self, tls: Option<ServerTlsConfig>) -> Self {
+        self.tls = tls;
+        self
+    }
 }
 
 impl Builder {
@@ -292,6 +346,9 @@ impl Builder {
         let opened_wals = self.opened_wals.context(MissingWals)?;
         let proxy = self.proxy.context(MissingProxy)?;
         let hotspot_recorder = self.hotspot_recorder.context(MissingHotspotRecorder)?;
+        let internal_auth = InternalAuth {
+            proxy: proxy.clone(),
+        };
 
         let meta_rpc_server = self.cluster.map(|v| {
             let builder = meta_event_service::Builder {
@@ -300,7 +357,7 @@ impl Builder {
                 runtime: runtimes.meta_runtime.clone(),
                 opened_wals,
             };
-            MetaEventServiceServer::new(builder.build())
+            MetaEventServiceServer::with_interceptor(builder.build(), internal_auth.clone())
         });
 
         let remote_engine_server = {
@@ -319,12 +376,19 @@ impl Builder {
                 runtimes: runtimes.clone(),
                 query_dedup,
                 hotspot_recorder,
+                protocol: Protocol::local(&self.protocol_config),
             };
-            RemoteEngineServiceServer::new(service)
+            RemoteEngineServiceServer::with_interceptor(service, internal_auth)
         };
 
         let runtime = runtimes.default_runtime.clone();
 
+        let table_changes_server = TableChangesServiceServer::new(TableChangesServiceImpl {
+            proxy: proxy.clone(),
+            runtimes: runtimes.clone(),
+            timeout: self.timeout,
+        });
+
         let storage_service = StorageServiceImpl {
             proxy,
             runtimes,
@@ -339,9 +403,97 @@ impl Builder {
             rpc_server,
             meta_rpc_server,
             remote_engine_server,
+            table_changes_server,
+            tls: self.tls,
             runtime,
             stop_tx: None,
             join_handle: None,
         })
     }
 }
+
+/// Authenticate the credentials in the metadata of the request, `None` if the
+/// authentication is disabled.
+fn authenticate<T>(
+    proxy: &Proxy,
+    req: &tonic::Request<T>,
+) -> std::result::Result<Option<AuthUser>, tonic::Status> {
+    let authorization = req
+        .metadata()
+        .get(AUTHORIZATION_KEY)
+        .and_then(|value| value.to_str().ok());
+    proxy
+        .authenticate(authorization)
+        .map_err(|e| tonic::Status::unauthenticated(e.error_message()))
+}
+
+/// Only accept the requests from the nodes of the cluster on the internal
+/// services once the authentication is enabled, that is, the requests carrying
+/// the internal token or verified by the client certificates.
+#[derive(Clone)]
+pub struct InternalAuth {
+    proxy: Arc<Proxy>,
+}
+
+impl Interceptor for InternalAuth {
+    fn call(
+        &mut self,
+        req: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        if self.proxy.instance().authenticator.is_none()
+            || self.proxy.is_internal_request(&req)
+            || req.peer_certs().is_some()
+        {
+            return Ok(req);
+        }
+
+        Err(tonic::Status::unauthenticated(
+            "Only the nodes of the cluster are accepted",
+        ))
+    }
+}
+
+/// Build the TLS of the grpc server, `None` if it's disabled.
+pub fn server_tls_config(config: &GrpcTlsConfig) -> Result<Option<ServerTlsConfig>> {
+    if !config.enable {
+        return Ok(None);
+    }
+
+    let tls = ServerTlsConfig::new().identity(load_identity(config)?);
+    if config.client_auth {
+        return Ok(Some(tls.client_ca_root(load_ca(config)?)));
+    }
+    Ok(Some(tls))
+}
+
+/// Build the TLS of the connections to the other nodes, `None` if it's
+/// disabled.
+pub fn client_tls_config(config: &GrpcTlsConfig) -> Result<Option<ClientTlsConfig>> {
+    if !config.enable {
+        return Ok(None);
+    }
+
+    let mut tls = ClientTlsConfig::new().ca_certificate(load_ca(config)?);
+    if config.client_auth {
+        tls = tls.identity(load_identity(config)?);
+    }
+    if let Some(domain_name) = &config.domain_name {
+        tls = tls.domain_name(domain_name);
+    }
+    Ok(Some(tls))
+}
+
+fn load_identity(config: &GrpcTlsConfig) -> Result<Identity> {
+    let cert = read_tls_file(&config.cert_path)?;
+    let key = read_tls_file(&config.key_path)?;
+    Ok(Identity::from_pem(cert, key))
+}
+
+fn load_ca(config: &GrpcTlsConfig) -> Result<Certificate> {
+    ensure!(!config.ca_path.is_empty(), MissingTlsCa);
+    read_tls_file(&config.ca_path).map(Certificate::from_pem)
+}
+
+fn read_tls_file(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).context(ReadTlsFile { path })
+}
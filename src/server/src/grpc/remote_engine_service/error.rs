@@ -72,6 +72,7 @@ pub enum StatusCode {
     Ok = 0,
     BadRequest = 401,
     NotFound = 404,
+    TooManyRequests = 429,
     Internal = 500,
 }
 
@@ -20,7 +20,7 @@
 use std::{
     hash::Hash,
     pin::Pin,
-    sync::Arc,
+    sync::{atomic::Ordering, Arc},
     task::{Context, Poll},
     time::{Duration, Instant},
 };
@@ -28,8 +28,13 @@ use std::{
 use arrow_ext::ipc::{self, CompressOptions, CompressOutput, CompressionMethod};
 use async_trait::async_trait;
 use catalog::{manager::ManagerRef, schema::SchemaRef};
-use common_types::{record_batch::RecordBatch, request_id::RequestId};
+use common_types::{
+    protocol::{Feature, Protocol, PROTOCOL_FEATURES_KEY, PROTOCOL_VERSION_KEY},
+    record_batch::RecordBatch,
+    request_id::RequestId,
+};
 use futures::{
+    future,
     stream::{self, BoxStream, FuturesUnordered, StreamExt},
     Future,
 };
@@ -59,10 +64,12 @@ use query_engine::{
     QueryEngineRef, QueryEngineType,
 };
 use runtime::{Priority, RuntimeRef};
-use snafu::{OptionExt, ResultExt};
+use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::{
+    access_stats,
     engine::EngineRuntimes,
     predicate::PredicateRef,
+    query_warning::{self, QueryWarnings, Warning},
     remote::model::{self, TableIdentifier},
     stream::{PartitionedStreams, SendableRecordBatchStream},
     table::{AlterSchemaRequest, TableRef},
@@ -97,6 +104,7 @@ const DEFAULT_COMPRESS_MIN_LENGTH: usize = 80 * 1024;
 pub enum RecordBatchWithMetric {
     RecordBatch(RecordBatch),
     Metric(String),
+    Warnings(Vec<Warning>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -224,16 +232,20 @@ impl<M: MetricCollector> Drop for StreamWithMetric<M> {
 struct RemoteExecStream {
     inner: BoxStream<'static, Result<RecordBatch>>,
     physical_plan_for_explain: Option<PhysicalPlanRef>,
+    /// Warnings of the execution, sent after all the results.
+    warnings: QueryWarnings,
 }
 
 impl RemoteExecStream {
     fn new(
         inner: BoxStream<'static, Result<RecordBatch>>,
         physical_plan_for_explain: Option<PhysicalPlanRef>,
+        warnings: QueryWarnings,
     ) -> Self {
         Self {
             inner,
             physical_plan_for_explain,
+            warnings,
         }
     }
 }
@@ -252,14 +264,18 @@ impl Stream for RemoteExecStream {
                         return Poll::Ready(Some(res.map(RecordBatchWithMetric::RecordBatch)));
                     }
                 }
-                Poll::Ready(None) => match &this.physical_plan_for_explain {
-                    Some(physical_plan) => {
+                Poll::Ready(None) => {
+                    if let Some(physical_plan) = this.physical_plan_for_explain.take() {
                         let metrics = physical_plan.metrics_to_string();
-                        this.physical_plan_for_explain = None;
                         return Poll::Ready(Some(Ok(RecordBatchWithMetric::Metric(metrics))));
                     }
-                    None => return Poll::Ready(None),
-                },
+
+                    let warnings = this.warnings.take();
+                    if warnings.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Ok(RecordBatchWithMetric::Warnings(warnings))));
+                }
                 Poll::Pending => return Poll::Pending,
             }
         }
@@ -267,10 +283,26 @@ impl Stream for RemoteExecStream {
 }
 
 macro_rules! record_stream_to_response_stream {
-    ($record_stream_result:ident, $StreamType:ident) => {
+    ($record_stream_result:ident, $StreamType:ident, $protocol:ident) => {
         match $record_stream_result {
             Ok(stream) => {
-                let new_stream: Self::$StreamType = Box::pin(stream.map(|res| match res {
+                // The metrics and the warnings can't be understood by the peer without
+                // the features.
+                let send_metrics = $protocol.supports(Feature::RemoteMetrics);
+                let send_warnings = $protocol.supports(Feature::RemoteWarnings);
+                let compression_method = if $protocol.supports(Feature::ZstdArrowPayload) {
+                    CompressionMethod::Zstd
+                } else {
+                    CompressionMethod::None
+                };
+                let stream = stream.filter(move |res| {
+                    future::ready(match res {
+                        Ok(RecordBatchWithMetric::Metric(_)) => send_metrics,
+                        Ok(RecordBatchWithMetric::Warnings(_)) => send_warnings,
+                        _ => true,
+                    })
+                });
+                let new_stream: Self::$StreamType = Box::pin(stream.map(move |res| match res {
                     Ok(res) => match res {
                         RecordBatchWithMetric::Metric(metric) => {
                             let resp = ReadResponse {
@@ -279,12 +311,23 @@ macro_rules! record_stream_to_response_stream {
                             };
                             Ok(resp)
                         }
+                        // The warnings are carried by the message of the ok header without any
+                        // output.
+                        RecordBatchWithMetric::Warnings(warnings) => {
+                            let mut header = error::build_ok_header();
+                            header.error = query_warning::encode_warnings(&warnings);
+                            let resp = ReadResponse {
+                                header: Some(header),
+                                output: None,
+                            };
+                            Ok(resp)
+                        }
                         RecordBatchWithMetric::RecordBatch(record_batch) => {
                             let resp = match ipc::encode_record_batch(
                                 &record_batch.into_arrow_record_batch(),
                                 CompressOptions {
                                     compress_min_length: DEFAULT_COMPRESS_MIN_LENGTH,
-                                    method: CompressionMethod::Zstd,
+                                    method: compression_method,
                                 },
                             )
                             .box_err()
@@ -350,6 +393,8 @@ pub struct RemoteEngineServiceImpl {
     pub runtimes: Arc<EngineRuntimes>,
     pub query_dedup: Option<QueryDedup>,
     pub hotspot_recorder: Arc<HotspotRecorder>,
+    /// Protocol of this node.
+    pub protocol: Protocol,
 }
 
 impl RemoteEngineServiceImpl {
@@ -360,10 +405,12 @@ impl RemoteEngineServiceImpl {
         let metric = StreamReadMetricCollector(Instant::now());
 
         let ctx = self.handler_ctx();
+        let warnings = QueryWarnings::default();
         let (tx, rx) = mpsc::channel(STREAM_QUERY_CHANNEL_LEN);
+        let read_warnings = warnings.clone();
         let handle = self.runtimes.read_runtime.spawn(async move {
             let read_request = request.into_inner();
-            handle_stream_read(ctx, read_request).await.map_err(|e| {
+            handle_stream_read(ctx, read_request, read_warnings).await.map_err(|e| {
                 error!("Handle stream read failed, err:{e}");
                 e
             })
@@ -399,7 +446,7 @@ impl RemoteEngineServiceImpl {
         }
 
         let stream = StreamWithMetric::new(Box::pin(ReceiverStream::new(rx)), metric);
-        Ok(RemoteExecStream::new(Box::pin(stream), None))
+        Ok(RemoteExecStream::new(Box::pin(stream), None, warnings))
     }
 
     async fn dedup_stream_read_internal(
@@ -430,12 +477,16 @@ impl RemoteEngineServiceImpl {
             ..
         } = query_dedup;
 
+        // The warnings are only reported to the request running the read, the
+        // waiting ones share its results but not its warnings.
+        let warnings = QueryWarnings::default();
         let (tx, rx) = mpsc::channel(config.notify_queue_cap);
         match request_notifiers.insert_notifier(request_key.clone(), tx) {
             // The first request, need to handle it, and then notify the other requests.
             RequestResult::First => {
                 let ctx = self.handler_ctx();
-                let query = async move { handle_stream_read(ctx, request).await };
+                let read_warnings = warnings.clone();
+                let query = async move { handle_stream_read(ctx, request, read_warnings).await };
                 self.read_and_send_dedupped_resps(
                     request_key,
                     query,
@@ -454,7 +505,7 @@ impl RemoteEngineServiceImpl {
         }
 
         let stream = StreamWithMetric::new(Box::pin(ReceiverStream::new(rx)), metric);
-        Ok(RemoteExecStream::new(Box::pin(stream), None))
+        Ok(RemoteExecStream::new(Box::pin(stream), None, warnings))
     }
 
     async fn read_and_send_dedupped_resps<K, F>(
@@ -727,6 +778,7 @@ impl RemoteEngineServiceImpl {
         ));
         // TODO: Use in handle_execute_plan fn to build stream with metrics
         let physical_plan_for_explain = ctx.explain.map(|_| physical_plan.clone());
+        let warnings = query_ctx.warnings.clone();
 
         let rt = self
             .runtimes
@@ -752,6 +804,7 @@ impl RemoteEngineServiceImpl {
         Ok(RemoteExecStream::new(
             Box::pin(stream),
             physical_plan_for_explain,
+            warnings,
         ))
     }
 
@@ -795,6 +848,8 @@ impl RemoteEngineServiceImpl {
         ));
         // TODO: Use in handle_execute_plan fn to build stream with metrics
         let physical_plan_for_explain = ctx.explain.map(|_| physical_plan.clone());
+        // The warnings are only reported to the request executing the plan.
+        let warnings = query_ctx.warnings.clone();
 
         let QueryDedup {
             config,
@@ -835,6 +890,7 @@ impl RemoteEngineServiceImpl {
         Ok(RemoteExecStream::new(
             Box::pin(stream),
             physical_plan_for_explain,
+            warnings,
         ))
     }
 
@@ -905,10 +961,38 @@ impl RemoteEngineServiceImpl {
         Ok(Response::new(resp))
     }
 
+    /// Negotiate the protocol with the client sending the `request`.
+    fn negotiate_protocol<T>(&self, request: &Request<T>) -> std::result::Result<Protocol, Status> {
+        let metadata = request.metadata();
+        let get = |key| metadata.get(key).and_then(|value| value.to_str().ok());
+        Protocol::decode(get(PROTOCOL_VERSION_KEY), get(PROTOCOL_FEATURES_KEY))
+            .and_then(|peer| self.protocol.negotiate(&peer))
+            .map_err(|e| {
+                error!("Failed to negotiate protocol with remote client, err:{e}");
+                Status::failed_precondition(e.to_string())
+            })
+    }
+
+    /// Attach the protocol of this node to the `response`.
+    fn with_protocol<T>(&self, mut response: Response<T>) -> Response<T> {
+        let metadata = response.metadata_mut();
+        metadata.insert(
+            PROTOCOL_VERSION_KEY,
+            self.protocol.encode_version().parse().unwrap(),
+        );
+        metadata.insert(
+            PROTOCOL_FEATURES_KEY,
+            self.protocol.encode_features().parse().unwrap(),
+        );
+        response
+    }
+
     fn handler_ctx(&self) -> HandlerContext {
         HandlerContext {
+            instance: self.instance.clone(),
             catalog_manager: self.instance.catalog_manager.clone(),
             hotspot_recorder: self.hotspot_recorder.clone(),
+            read_only: self.instance.read_only.load(Ordering::Relaxed),
         }
     }
 }
@@ -916,8 +1000,11 @@ impl RemoteEngineServiceImpl {
 /// Context for handling all kinds of remote engine service.
 #[derive(Clone)]
 struct HandlerContext {
+    instance: InstanceRef,
     catalog_manager: ManagerRef,
     hotspot_recorder: Arc<HotspotRecorder>,
+    /// Whether the server is in read-only mode
+    read_only: bool,
 }
 
 #[async_trait]
@@ -929,6 +1016,7 @@ impl RemoteEngineService for RemoteEngineServiceImpl {
         &self,
         request: Request<ReadRequest>,
     ) -> std::result::Result<Response<Self::ReadStream>, Status> {
+        let protocol = self.negotiate_protocol(&request)?;
         if let Some(table) = &request.get_ref().table {
             self.hotspot_recorder
                 .send_msg_or_log(
@@ -944,34 +1032,45 @@ impl RemoteEngineService for RemoteEngineServiceImpl {
             None => self.stream_read_internal(request).await,
         };
 
-        record_stream_to_response_stream!(result, ReadStream)
+        record_stream_to_response_stream!(result, ReadStream, protocol)
+            .map(|resp| self.with_protocol(resp))
     }
 
     async fn write(
         &self,
         request: Request<WriteRequest>,
     ) -> std::result::Result<Response<WriteResponse>, Status> {
-        self.write_internal(request).await
+        self.negotiate_protocol(&request)?;
+        self.write_internal(request)
+            .await
+            .map(|resp| self.with_protocol(resp))
     }
 
     async fn get_table_info(
         &self,
         request: Request<GetTableInfoRequest>,
     ) -> std::result::Result<Response<GetTableInfoResponse>, Status> {
-        self.get_table_info_internal(request).await
+        self.negotiate_protocol(&request)?;
+        self.get_table_info_internal(request)
+            .await
+            .map(|resp| self.with_protocol(resp))
     }
 
     async fn write_batch(
         &self,
         request: Request<WriteBatchRequest>,
     ) -> std::result::Result<Response<WriteResponse>, Status> {
-        self.write_batch_internal(request).await
+        self.negotiate_protocol(&request)?;
+        self.write_batch_internal(request)
+            .await
+            .map(|resp| self.with_protocol(resp))
     }
 
     async fn execute_physical_plan(
         &self,
         request: Request<ExecutePlanRequest>,
     ) -> std::result::Result<Response<Self::ExecutePhysicalPlanStream>, Status> {
+        let protocol = self.negotiate_protocol(&request)?;
         if let Some(table) = &request.get_ref().table {
             self.hotspot_recorder
                 .send_msg_or_log(
@@ -998,35 +1097,45 @@ impl RemoteEngineService for RemoteEngineServiceImpl {
                 }),
         };
 
-        record_stream_to_response_stream!(record_stream_result, ExecutePhysicalPlanStream)
+        record_stream_to_response_stream!(record_stream_result, ExecutePhysicalPlanStream, protocol)
+            .map(|resp| self.with_protocol(resp))
     }
 
     async fn alter_table_schema(
         &self,
         request: Request<AlterTableSchemaRequest>,
     ) -> std::result::Result<Response<AlterTableSchemaResponse>, Status> {
-        self.alter_table_schema_internal(request).await
+        self.negotiate_protocol(&request)?;
+        self.alter_table_schema_internal(request)
+            .await
+            .map(|resp| self.with_protocol(resp))
     }
 
     async fn alter_table_options(
         &self,
         request: Request<AlterTableOptionsRequest>,
     ) -> std::result::Result<Response<AlterTableOptionsResponse>, Status> {
-        self.alter_table_options_internal(request).await
+        self.negotiate_protocol(&request)?;
+        self.alter_table_options_internal(request)
+            .await
+            .map(|resp| self.with_protocol(resp))
     }
 }
 
+/// Read the table, the warnings of the read are recorded into the `warnings`.
 async fn handle_stream_read(
     ctx: HandlerContext,
     request: ReadRequest,
+    warnings: QueryWarnings,
 ) -> Result<PartitionedStreams> {
     let table_engine::remote::model::ReadRequest {
         table: table_ident,
-        read_request,
+        mut read_request,
     } = request.try_into().box_err().context(ErrWithCause {
         code: StatusCode::BadRequest,
         msg: "fail to convert read request",
     })?;
+    read_request.opts.warnings = warnings;
 
     let request_id = &read_request.request_id;
     info!(
@@ -1037,6 +1146,7 @@ async fn handle_stream_read(
 
     let begin = Instant::now();
     let table = find_table_by_identifier(&ctx, &table_ident)?;
+    access_stats::record_query(table.id());
     let res = table
         .partitioned_read(read_request.clone())
         .await
@@ -1099,6 +1209,14 @@ async fn record_write(
 }
 
 async fn handle_write(ctx: HandlerContext, request: WriteRequest) -> Result<WriteResponse> {
+    ensure!(
+        !ctx.read_only,
+        ErrNoCause {
+            code: StatusCode::BadRequest,
+            msg: "write is rejected as the server is in read-only mode",
+        }
+    );
+
     let table_ident: TableIdentifier = request
         .table
         .context(ErrNoCause {
@@ -1150,6 +1268,18 @@ async fn handle_write(ctx: HandlerContext, request: WriteRequest) -> Result<Writ
     let num_rows = write_request.write_request.row_group.num_rows();
     REMOTE_ENGINE_WRITE_BATCH_NUM_ROWS_HISTOGRAM.observe(num_rows as f64);
 
+    // The writes to the sub tables of the partitioned tables are throttled by
+    // this node owning them.
+    ctx.instance
+        .admission
+        .throttle_table_write(&table, num_rows)
+        .await
+        .box_err()
+        .context(ErrWithCause {
+            code: StatusCode::TooManyRequests,
+            msg: format!("write is rejected, table:{:?}", write_request.table),
+        })?;
+
     let res = table
         .write(write_request.write_request)
         .await
@@ -1258,6 +1388,9 @@ fn create_query_ctx(
         default_catalog,
         default_schema,
         priority,
+        scan_usage: None,
+        stages: None,
+        warnings: QueryWarnings::default(),
     }
 }
 
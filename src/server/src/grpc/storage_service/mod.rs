@@ -35,11 +35,16 @@ use horaedbproto::{
     },
 };
 use http::StatusCode;
-use proxy::{Context, Proxy, FORWARDED_FROM};
+use logger::{warn, Level};
+use proxy::{
+    write_status::WriteStatus, Context, Proxy, FORWARDED_FROM, LOG_LEVEL_KEY, PARTIAL_WRITE_KEY,
+    QUERY_WARNINGS_KEY, WRITE_STATUS_KEY,
+};
 use table_engine::engine::EngineRuntimes;
 use time_ext::InstantExt;
+use tonic::metadata::MetadataValue;
 
-use crate::grpc::metrics::GRPC_HANDLER_DURATION_HISTOGRAM_VEC;
+use crate::grpc::{self, metrics::GRPC_HANDLER_DURATION_HISTOGRAM_VEC};
 
 #[derive(Clone)]
 pub struct StorageServiceImpl {
@@ -148,7 +153,10 @@ impl StorageService for StorageServiceImpl {
     ) -> Result<tonic::Response<Self::StreamSqlQueryStream>, tonic::Status> {
         let begin_instant = Instant::now();
         let proxy = self.proxy.clone();
-        let ctx = Context::new(self.timeout, get_forwarded_from(&req));
+        let ctx = Context::new(self.timeout, get_forwarded_from(&req))
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_log_level(get_log_level(&req))
+            .with_auth_user(grpc::authenticate(&self.proxy, &req)?);
 
         let stream = self.stream_sql_query_internal(ctx, proxy, req).await;
 
@@ -166,13 +174,52 @@ fn get_forwarded_from<T>(req: &tonic::Request<T>) -> Option<String> {
         .map(|value| value.to_str().unwrap().to_string())
 }
 
+fn get_log_level<T>(req: &tonic::Request<T>) -> Option<Level> {
+    let value = req.metadata().get(LOG_LEVEL_KEY)?.to_str().ok()?;
+    match logger::parse_verbose_level(value) {
+        Ok(level) => Some(level),
+        Err(e) => {
+            warn!("Ignore invalid log level of the request, err:{e}");
+            None
+        }
+    }
+}
+
+fn get_partial_write<T>(req: &tonic::Request<T>) -> bool {
+    req.metadata()
+        .get(PARTIAL_WRITE_KEY)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+/// Build the write response carrying the write status in its metadata.
+fn build_write_response(
+    resp: WriteResponse,
+    status: WriteStatus,
+) -> tonic::Response<WriteResponse> {
+    let mut resp = tonic::Response::new(resp);
+    if !status.is_empty() {
+        match status.encode() {
+            Ok(v) => {
+                resp.metadata_mut()
+                    .insert_bin(WRITE_STATUS_KEY, MetadataValue::from_bytes(&v));
+            }
+            Err(e) => warn!("Failed to encode write status, err:{e}"),
+        }
+    }
+
+    resp
+}
+
 // TODO: Use macros to simplify duplicate code
 impl StorageServiceImpl {
     async fn route_internal(
         &self,
         req: tonic::Request<RouteRequest>,
     ) -> Result<tonic::Response<RouteResponse>, tonic::Status> {
-        let ctx = Context::new(self.timeout, get_forwarded_from(&req));
+        let ctx = Context::new(self.timeout, get_forwarded_from(&req))
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_auth_user(grpc::authenticate(&self.proxy, &req)?);
         let req = req.into_inner();
         let proxy = self.proxy.clone();
 
@@ -199,74 +246,104 @@ impl StorageServiceImpl {
         &self,
         req: tonic::Request<WriteRequest>,
     ) -> Result<tonic::Response<WriteResponse>, tonic::Status> {
-        let ctx = Context::new(self.timeout, get_forwarded_from(&req));
+        let ctx = Context::new(self.timeout, get_forwarded_from(&req))
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_log_level(get_log_level(&req))
+            .with_partial_write(get_partial_write(&req))
+            .with_auth_user(grpc::authenticate(&self.proxy, &req)?);
 
         let req = req.into_inner();
         let proxy = self.proxy.clone();
 
         let join_handle = self.runtimes.write_runtime.spawn(async move {
             if req.context.is_none() {
-                return WriteResponse {
+                let resp = WriteResponse {
                     header: Some(error::build_err_header(
                         StatusCode::BAD_REQUEST.as_u16() as u32,
                         "database is not set".to_string(),
                     )),
                     ..Default::default()
                 };
+                return (resp, WriteStatus::default());
             }
 
-            proxy.handle_write(ctx, req).await
+            proxy.handle_write_with_status(ctx, req).await
         });
 
-        let resp = match join_handle.await {
+        let (resp, status) = match join_handle.await {
             Ok(v) => v,
-            Err(e) => WriteResponse {
-                header: Some(error::build_err_header(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
-                    format!("fail to join the spawn task, err:{e:?}"),
-                )),
-                ..Default::default()
-            },
+            Err(e) => {
+                let resp = WriteResponse {
+                    header: Some(error::build_err_header(
+                        StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
+                        format!("fail to join the spawn task, err:{e:?}"),
+                    )),
+                    ..Default::default()
+                };
+                (resp, WriteStatus::default())
+            }
         };
 
-        Ok(tonic::Response::new(resp))
+        Ok(build_write_response(resp, status))
     }
 
     async fn sql_query_internal(
         &self,
         req: tonic::Request<SqlQueryRequest>,
     ) -> Result<tonic::Response<SqlQueryResponse>, tonic::Status> {
-        let ctx = Context::new(self.timeout, get_forwarded_from(&req));
+        let ctx = Context::new(self.timeout, get_forwarded_from(&req))
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_log_level(get_log_level(&req))
+            .with_auth_user(grpc::authenticate(&self.proxy, &req)?);
         let proxy = self.proxy.clone();
 
-        let join_handle = self
-            .runtimes
-            .read_runtime
-            .spawn(async move { proxy.handle_sql_query(ctx, req.into_inner()).await });
+        let join_handle = self.runtimes.read_runtime.spawn(async move {
+            proxy
+                .handle_sql_query_with_warnings(ctx, req.into_inner())
+                .await
+        });
 
-        let resp = match join_handle.await {
+        let (resp, warnings) = match join_handle.await {
             Ok(v) => v,
-            Err(e) => SqlQueryResponse {
-                header: Some(error::build_err_header(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
-                    format!("fail to join the spawn task, err:{e:?}"),
-                )),
-                ..Default::default()
-            },
+            Err(e) => {
+                let resp = SqlQueryResponse {
+                    header: Some(error::build_err_header(
+                        StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
+                        format!("fail to join the spawn task, err:{e:?}"),
+                    )),
+                    ..Default::default()
+                };
+                (resp, Vec::new())
+            }
         };
 
-        Ok(tonic::Response::new(resp))
+        let mut resp = tonic::Response::new(resp);
+        if !warnings.is_empty() {
+            match serde_json::to_vec(&warnings) {
+                Ok(v) => {
+                    resp.metadata_mut()
+                        .insert_bin(QUERY_WARNINGS_KEY, MetadataValue::from_bytes(&v));
+                }
+                Err(e) => warn!("Failed to encode query warnings, err:{e}"),
+            }
+        }
+
+        Ok(resp)
     }
 
     async fn prom_remote_query_internal(
         &self,
         req: tonic::Request<PrometheusRemoteQueryRequest>,
     ) -> Result<tonic::Response<PrometheusRemoteQueryResponse>, tonic::Status> {
+        let auth_user = grpc::authenticate(&self.proxy, &req)?;
         let req = req.into_inner();
         let proxy = self.proxy.clone();
         let timeout = self.timeout;
         let join_handle = self.runtimes.read_runtime.spawn(async move {
-            match proxy.handle_prom_grpc_query(timeout, req).await {
+            match proxy
+                .handle_prom_grpc_query(timeout, auth_user, req)
+                .await
+            {
                 Ok(v) => v,
                 Err(e) => PrometheusRemoteQueryResponse {
                     header: Some(error::build_err_header(
@@ -295,7 +372,9 @@ impl StorageServiceImpl {
         &self,
         req: tonic::Request<PrometheusQueryRequest>,
     ) -> Result<tonic::Response<PrometheusQueryResponse>, tonic::Status> {
-        let ctx = Context::new(self.timeout, get_forwarded_from(&req));
+        let ctx = Context::new(self.timeout, get_forwarded_from(&req))
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_auth_user(grpc::authenticate(&self.proxy, &req)?);
 
         let req = req.into_inner();
         let proxy = self.proxy.clone();
@@ -331,30 +410,39 @@ impl StorageServiceImpl {
         &self,
         req: tonic::Request<tonic::Streaming<WriteRequest>>,
     ) -> Result<tonic::Response<WriteResponse>, tonic::Status> {
-        let ctx = Context::new(self.timeout, get_forwarded_from(&req));
+        let ctx = Context::new(self.timeout, get_forwarded_from(&req))
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_log_level(get_log_level(&req))
+            .with_partial_write(get_partial_write(&req))
+            .with_auth_user(grpc::authenticate(&self.proxy, &req)?);
         let mut stream = req.into_inner();
         let proxy = self.proxy.clone();
 
         let mut total_success = 0;
+        let mut total_failed = 0;
         let join_handle = self.runtimes.write_runtime.spawn(async move {
             let mut resp = WriteResponse::default();
+            let mut status = WriteStatus::default();
             let mut has_err = false;
 
             while let Some(req) = stream.next().await {
                 let write_req = match req {
                     Ok(v) => v,
                     Err(e) => {
-                        return WriteResponse {
+                        let resp = WriteResponse {
                             header: Some(error::build_err_header(
                                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
                                 format!("fail to fetch request, err:{e:?}"),
                             )),
                             ..Default::default()
                         };
+                        return (resp, WriteStatus::default());
                     }
                 };
 
-                let write_resp = proxy.handle_write(ctx.clone(), write_req).await;
+                let (write_resp, write_status) = proxy
+                    .handle_write_with_status(ctx.clone(), write_req)
+                    .await;
 
                 if let Some(header) = write_resp.header {
                     if header.code != StatusCode::OK.as_u16() as u32 {
@@ -364,6 +452,8 @@ impl StorageServiceImpl {
                     }
                 }
                 total_success += write_resp.success;
+                total_failed += write_resp.failed;
+                status.merge(write_status);
             }
 
             if !has_err {
@@ -372,23 +462,27 @@ impl StorageServiceImpl {
                     ..Default::default()
                 });
                 resp.success = total_success;
+                resp.failed = total_failed;
             }
 
-            resp
+            (resp, status)
         });
 
-        let resp = match join_handle.await {
+        let (resp, status) = match join_handle.await {
             Ok(v) => v,
-            Err(e) => WriteResponse {
-                header: Some(error::build_err_header(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
-                    format!("fail to join the spawn task, err:{e:?}"),
-                )),
-                ..Default::default()
-            },
+            Err(e) => {
+                let resp = WriteResponse {
+                    header: Some(error::build_err_header(
+                        StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
+                        format!("fail to join the spawn task, err:{e:?}"),
+                    )),
+                    ..Default::default()
+                };
+                (resp, WriteStatus::default())
+            }
         };
 
-        Ok(tonic::Response::new(resp))
+        Ok(build_write_response(resp, status))
     }
 
     async fn stream_sql_query_internal(
@@ -0,0 +1,173 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Table changes service
+//!
+//! Served by hand like the bulk query service, see [`proxy::table_changes`]
+//! for the proto definition.
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::{stream, stream::BoxStream, StreamExt};
+use logger::warn;
+use proxy::{
+    table_changes::{SubscribeTableChangesRequest, TableChangesResponse},
+    Context, Proxy, FORWARDED_FROM,
+};
+use table_engine::engine::EngineRuntimes;
+use time_ext::InstantExt;
+use tokio::sync::mpsc;
+use tonic::{
+    body::BoxBody,
+    codegen::{empty_body, http, Body, BoxFuture, Service, StdError},
+    server::{Grpc, NamedService, ServerStreamingService},
+};
+
+use crate::grpc::{self, metrics::GRPC_HANDLER_DURATION_HISTOGRAM_VEC};
+
+const SUBSCRIBE_TABLE_CHANGES_PATH: &str =
+    "/horaedb.changes.TableChangesService/SubscribeTableChanges";
+/// Responses buffered ahead of the client, the wal is not read once the
+/// buffer is full.
+const RESPONSE_BUFFER_SIZE: usize = 2;
+
+#[derive(Clone)]
+pub struct TableChangesServiceImpl {
+    pub proxy: Arc<Proxy>,
+    pub runtimes: Arc<EngineRuntimes>,
+    pub timeout: Option<Duration>,
+}
+
+impl TableChangesServiceImpl {
+    fn subscribe_table_changes(
+        &self,
+        req: tonic::Request<SubscribeTableChangesRequest>,
+    ) -> BoxStream<'static, Result<TableChangesResponse, tonic::Status>> {
+        let begin_instant = Instant::now();
+        let forwarded_from = req
+            .metadata()
+            .get(FORWARDED_FROM)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let auth_user = match grpc::authenticate(&self.proxy, &req) {
+            Ok(v) => v,
+            Err(status) => return stream::once(async { Err(status) }).boxed(),
+        };
+        let ctx = Context::new(self.timeout, forwarded_from)
+            .with_internal(self.proxy.is_internal_request(&req))
+            .with_auth_user(auth_user);
+        let proxy = self.proxy.clone();
+        let req = req.into_inner();
+
+        // Tail the table on the read runtime, and the responses are sent to the
+        // client through the channel.
+        let (tx, rx) = mpsc::channel(RESPONSE_BUFFER_SIZE);
+        self.runtimes.read_runtime.spawn(async move {
+            let mut responses = proxy.handle_subscribe_table_changes(ctx, req).await;
+            loop {
+                // The subscription may wait for the changes for a long time,
+                // so stop it as soon as the client goes away.
+                let resp = tokio::select! {
+                    resp = responses.next() => resp,
+                    _ = tx.closed() => None,
+                };
+                let Some(resp) = resp else {
+                    break;
+                };
+                if tx.send(Ok(resp)).await.is_err() {
+                    break;
+                }
+            }
+            if tx.is_closed() {
+                warn!("Table changes subscription is cancelled by the client");
+            }
+
+            GRPC_HANDLER_DURATION_HISTOGRAM_VEC
+                .handle_subscribe_table_changes
+                .observe(begin_instant.saturating_elapsed().as_secs_f64());
+        });
+
+        stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|v| (v, rx)) }).boxed()
+    }
+}
+
+struct SubscribeTableChangesSvc(TableChangesServiceImpl);
+
+impl ServerStreamingService<SubscribeTableChangesRequest> for SubscribeTableChangesSvc {
+    type Future = BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+    type Response = TableChangesResponse;
+    type ResponseStream = BoxStream<'static, Result<TableChangesResponse, tonic::Status>>;
+
+    fn call(&mut self, req: tonic::Request<SubscribeTableChangesRequest>) -> Self::Future {
+        let stream = self.0.subscribe_table_changes(req);
+        Box::pin(async move { Ok(tonic::Response::new(stream)) })
+    }
+}
+
+#[derive(Clone)]
+pub struct TableChangesServiceServer {
+    inner: TableChangesServiceImpl,
+}
+
+impl TableChangesServiceServer {
+    pub fn new(inner: TableChangesServiceImpl) -> Self {
+        Self { inner }
+    }
+}
+
+impl<B> Service<http::Request<B>> for TableChangesServiceServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    type Response = http::Response<BoxBody>;
+
+    fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if req.uri().path() != SUBSCRIBE_TABLE_CHANGES_PATH {
+            // Unimplemented.
+            return Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12")
+                    .header("content-type", "application/grpc")
+                    .body(empty_body())
+                    .unwrap())
+            });
+        }
+
+        let svc = SubscribeTableChangesSvc(self.inner.clone());
+        Box::pin(async move {
+            let mut grpc = Grpc::new(tonic::codec::ProstCodec::default());
+            Ok(grpc.server_streaming(svc, req).await)
+        })
+    }
+}
+
+impl NamedService for TableChangesServiceServer {
+    const NAME: &'static str = "horaedb.changes.TableChangesService";
+}
@@ -27,43 +27,72 @@ use std::{
     time::Duration,
 };
 
+use analytic_engine::{
+    backup::{BackupLease, BackupLeasesRef},
+    dynamic_config::DynamicConfigRef,
+};
 use bytes_ext::Bytes;
 use cluster::ClusterRef;
 use datafusion::parquet::data_type::AsBytes;
 use flate2::read::GzDecoder;
 use generic_error::{BoxError, GenericError};
-use logger::{error, info, RuntimeLevel};
+use logger::{error, info, warn, RuntimeLevel};
 use macros::define_result;
 use profile::Profiler;
 use prom_remote_api::web;
 use proxy::{
+    auth::{self, AUTHORIZATION_KEY},
     context::RequestContext,
+    graphite::types::RenderParams,
     handlers::{self},
-    http::sql::{convert_output, Request},
-    influxdb::types::{InfluxqlParams, InfluxqlRequest, WriteParams, WriteRequest},
+    http::sql::Request,
+    influxdb::{
+        self,
+        types::{
+            InfluxqlParams, InfluxqlRequest, Precision, WriteParams, WriteRequest, WriteV2Params,
+        },
+    },
     instance::InstanceRef,
+    json_write::JsonWriteParams,
     opentsdb::types::{PutParams, PutRequest},
-    Proxy,
+    schema_registry::types::{
+        WriteParams as SchemaRegistryWriteParams, WriteRequest as SchemaRegistryWriteRequest,
+    },
+    Proxy, LOG_LEVEL_KEY,
+};
+use query_frontend::{
+    config::{TimeRangeGuard, WildcardLimit},
+    plan::UserRole,
 };
-use router::endpoint::Endpoint;
+use router::{endpoint::Endpoint, RuleList};
 use runtime::{PriorityRuntime, Runtime};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 use table_engine::{engine::EngineRuntimes, table::FlushRequest};
-use tokio::sync::oneshot::{self, Receiver, Sender};
+use time_ext::ReadableDuration;
+use tokio::sync::{
+    broadcast::error::RecvError,
+    oneshot::{self, Receiver, Sender},
+};
 use wal::manager::OpenedWals;
 use warp::{
     header,
-    http::StatusCode,
+    http::{header::WWW_AUTHENTICATE, HeaderValue, StatusCode},
+    hyper,
+    path::FullPath,
     reject,
     reply::{self, Reply},
-    Filter, Rejection,
+    sse, Filter, Rejection,
 };
 
 use crate::{
+    config_reload::ReloadTrigger,
+    console,
     consts::{self, CONTENT_ENCODING_HEADER, GZIP_ENCODING},
     error_util,
     metrics::{self, HTTP_HANDLER_DURATION_HISTOGRAM_VEC},
+    openapi,
 };
 
 #[derive(Debug, Snafu)]
@@ -77,6 +106,15 @@ pub enum Error {
     #[snafu(display("Failed to handle update log level, err:{}", msg))]
     HandleUpdateLogLevel { msg: String },
 
+    #[snafu(display("Failed to reload config, err:{}", msg))]
+    ReloadConfig { msg: String },
+
+    #[snafu(display("Failed to control compaction, err:{}", msg))]
+    ControlCompaction { msg: String },
+
+    #[snafu(display("Failed to control backup, err:{}", msg))]
+    ControlBackup { msg: String },
+
     #[snafu(display("Missing engine runtimes to build service.\nBacktrace:\n{}", backtrace))]
     MissingEngineRuntimes { backtrace: Backtrace },
 
@@ -108,6 +146,18 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display(
+        "Fail to {} memory of the allocator, err:{}.\nBacktrace:\n{}",
+        op,
+        source,
+        backtrace
+    ))]
+    ControlAllocator {
+        op: String,
+        source: profile::Error,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Fail to join async task, err:{}.", source))]
     JoinAsyncTask { source: runtime::Error },
 
@@ -148,6 +198,15 @@ pub enum Error {
 
     #[snafu(display("Querying shards is only supported in cluster mode"))]
     QueryShards {},
+
+    #[snafu(display("Query not found, id:{}", id))]
+    QueryNotFound { id: u64 },
+
+    #[snafu(display("Failed to authenticate the request, err:{}", msg))]
+    Authenticate { msg: String },
+
+    #[snafu(display("{}", msg))]
+    Forbidden { msg: String },
 }
 
 define_result!(Error);
@@ -171,6 +230,48 @@ impl TryFrom<&str> for ContentEncodingType {
     }
 }
 
+/// Decode the body according to its content encoding.
+fn decode_body(body: Bytes, encoding: Option<String>) -> Result<Bytes> {
+    let Some(encoding) = encoding else {
+        return Ok(body);
+    };
+
+    match ContentEncodingType::try_from(encoding.as_str())? {
+        ContentEncodingType::Gzip => {
+            let bytes = body.as_bytes();
+            let mut decoder = GzDecoder::new(bytes);
+            let mut decompressed_data = Vec::with_capacity(bytes.len() * 2);
+            decoder
+                .read_to_end(&mut decompressed_data)
+                .context(UnGzip)?;
+            Ok(decompressed_data.into())
+        }
+    }
+}
+
+/// Whether the path is served without the authentication, the metrics and the
+/// readiness probe are scraped without credentials.
+fn is_public_path(path: &str) -> bool {
+    path == "/metrics" || path == "/ready"
+}
+
+/// The role required to access the path, which is `None` for the public
+/// paths. The apis called by the console require the same roles as the apis
+/// themselves.
+fn required_role(path: &str, api_prefix: &str) -> Option<UserRole> {
+    let path = path
+        .strip_prefix(api_prefix)
+        .or_else(|| path.strip_prefix(console::API_PREFIX))
+        .unwrap_or(path);
+    if is_public_path(path) {
+        None
+    } else if path.starts_with("/admin/") || path.starts_with("/debug/") {
+        Some(UserRole::Admin)
+    } else {
+        Some(UserRole::ReadOnly)
+    }
+}
+
 /// Http service
 ///
 /// Endpoints beginning with /debug are for internal use, and may subject to
@@ -187,6 +288,8 @@ pub struct Service {
     config: HttpConfig,
     config_content: String,
     opened_wals: OpenedWals,
+    config_reload: Option<ReloadTrigger>,
+    engine_dynamic_config: Option<DynamicConfigRef>,
 }
 
 impl Service {
@@ -207,15 +310,31 @@ impl Service {
         );
 
         // Register filters to warp and rejection handler
-        let routes = self.routes().recover(handle_rejection);
-        let (_addr, server) = warp::serve(routes).bind_with_graceful_shutdown(
-            (ip_addr, self.config.endpoint.port),
-            async {
-                rx.await.ok();
-            },
-        );
-
-        self.engine_runtimes.default_runtime.spawn(server);
+        let routes = self
+            .with_api_auth()
+            .and(self.routes())
+            .recover(handle_rejection);
+        let addr = (ip_addr, self.config.endpoint.port);
+        let shutdown = async {
+            rx.await.ok();
+        };
+        let tls = &self.config.tls;
+        if tls.enable {
+            let server = warp::serve(routes)
+                .tls()
+                .cert_path(&tls.cert_path)
+                .key_path(&tls.key_path);
+            let (_addr, server) = match &tls.client_ca_path {
+                Some(path) => server
+                    .client_auth_required_path(path)
+                    .bind_with_graceful_shutdown(addr, shutdown),
+                None => server.bind_with_graceful_shutdown(addr, shutdown),
+            };
+            self.engine_runtimes.default_runtime.spawn(server);
+        } else {
+            let (_addr, server) = warp::serve(routes).bind_with_graceful_shutdown(addr, shutdown);
+            self.engine_runtimes.default_runtime.spawn(server);
+        }
 
         Ok(())
     }
@@ -234,16 +353,47 @@ impl Service {
         self.home()
             // public APIs
             .or(self.metrics())
+            .or(self.ready())
             .or(self.sql())
             .or(self.influxdb_api())
             .or(self.opentsdb_api())
+            .or(self.schema_registry_api())
+            .or(self.json_write_api())
+            .or(self.graphite_render())
+            .or(self.schema_events())
             .or(self.prom_api())
             .or(self.route())
+            .or(self.api_v1())
             // admin APIs
             .or(self.admin_block())
+            .or(self.admin_maintenance())
+            .or(self.admin_read_only())
+            .or(self.admin_route_rules())
+            .or(self.admin_quotas())
+            .or(self.list_queries())
+            .or(self.query_history())
+            .or(self.kill_query())
+            .or(self.list_functions())
+            .or(self.reload_functions())
+            .or(self.schema_diff())
+            .or(self.resync_replica())
+            .or(self.json_mappings())
+            .or(self.admin_import())
+            .or(self.list_imports())
+            .or(self.release_allocator_memory())
+            .or(self.compaction_status())
+            .or(self.control_compaction())
+            .or(self.recompression_status())
+            .or(self.control_recompression())
+            .or(self.backup_status())
+            .or(self.acquire_backup_lease())
+            .or(self.renew_backup_lease())
+            .or(self.release_backup_lease())
+            .or(self.console())
             // debug APIs
             .or(self.flush_memtable())
             .or(self.update_log_level())
+            .or(self.reload_config())
             .or(self.profile_cpu())
             .or(self.profile_heap())
             .or(self.server_config())
@@ -251,6 +401,8 @@ impl Service {
             .or(self.wal_stats())
             .or(self.query_push_down())
             .or(self.slow_threshold())
+            .or(self.wildcard_limit())
+            .or(self.time_range_guard())
             .with(warp::log("http_requests"))
             .with(warp::log::custom(|info| {
                 let path = info.path();
@@ -265,6 +417,57 @@ impl Service {
             }))
     }
 
+    /// Expose the query and admin APIs under `/api/v1`, together with the
+    /// OpenAPI document describing them at `/api/v1/openapi.json`.
+    ///
+    /// The unversioned paths are kept for compatibility, the write protocols
+    /// are served at their own protocol-compatible paths too.
+    fn api_v1(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let document = Arc::new(openapi::document());
+        let openapi_json = warp::path!("openapi.json")
+            .and(warp::get())
+            .map(move || reply::json(document.as_ref()));
+
+        let apis = openapi_json
+            .or(self.sql())
+            .or(self.route())
+            .or(self.influxdb_api())
+            .or(self.opentsdb_api())
+            .or(self.schema_registry_api())
+            .or(self.json_write_api())
+            .or(self.prom_api())
+            .or(self.admin_block())
+            .or(self.admin_maintenance())
+            .or(self.admin_read_only())
+            .or(self.admin_route_rules())
+            .or(self.admin_quotas())
+            .or(self.list_queries())
+            .or(self.query_history())
+            .or(self.kill_query())
+            .or(self.list_functions())
+            .or(self.reload_functions())
+            .or(self.schema_diff())
+            .or(self.resync_replica())
+            .or(self.json_mappings())
+            .or(self.admin_import())
+            .or(self.list_imports())
+            .or(self.release_allocator_memory())
+            .or(self.compaction_status())
+            .or(self.control_compaction())
+            .or(self.recompression_status())
+            .or(self.control_recompression())
+            .or(self.backup_status())
+            .or(self.acquire_backup_lease())
+            .or(self.renew_backup_lease())
+            .or(self.release_backup_lease());
+
+        warp::path("api")
+            .and(warp::path(openapi::API_VERSION))
+            .and(apis)
+    }
+
     /// Expose `/prom/v1/read` and `/prom/v1/write` to serve Prometheus remote
     /// storage request
     fn prom_api(
@@ -296,6 +499,29 @@ impl Service {
         })
     }
 
+    // GET /console
+    //
+    // The console calls the sql, queries and metrics apis under
+    // `/console/api`, which require the roles as the apis do, and the page
+    // requires the read only role.
+    fn console(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let page = warp::path::end()
+            .and(warp::get())
+            .map(|| reply::html(console::INDEX_HTML));
+        let apis = warp::path("api").and(
+            self.sql()
+                .or(self.list_queries())
+                .or(self.kill_query())
+                .or(self.metrics()),
+        );
+
+        warp::path("console")
+            .and(self.with_console_auth())
+            .and(page.or(apis))
+    }
+
     // POST /sql
     fn sql(&self) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         // accept json or plain text
@@ -317,18 +543,18 @@ impl Service {
                     // We don't timeout http api since it's mainly used for debugging.
                     ctx.timeout = None;
 
+                    // The rows are streamed in the body as soon as they are produced.
                     let result = runtime
-                        .spawn(async move {
-                            proxy
-                                .handle_http_sql_query(&ctx, req)
-                                .await
-                                .map(convert_output)
-                        })
+                        .spawn(async move { proxy.handle_http_stream_sql_query(&ctx, req).await })
                         .await
                         .box_err()
                         .context(HandleRequest);
                     match result {
-                        Ok(Ok(res)) => Ok(reply::json(&res)),
+                        Ok(Ok(body)) => Ok(reply::with_header(
+                            reply::Response::new(hyper::Body::wrap_stream(body)),
+                            "content-type",
+                            "application/json",
+                        )),
                         Ok(Err(e)) => {
                             if let proxy::error::Error::QueryMaybeExceedTTL { msg } = e {
                                 return Err(reject::custom(Error::QueryMaybeExceedTTL { msg }));
@@ -364,16 +590,22 @@ impl Service {
 
     /// for write api:
     ///     POST `/influxdb/v1/write`
+    ///     POST `/influxdb/api/v2/write`
     ///
     /// for query api:
     ///     POST/GET `/influxdb/v1/query`
     ///
     /// It's derived from the influxdb 1.x query api described doc of 1.8:
     ///     https://docs.influxdata.com/influxdb/v1.8/tools/api/#query-http-endpoint
+    ///
+    /// The agents like Telegraf can write to HoraeDB by setting the url of their
+    /// influxdb outputs to `http://{host}:{port}/influxdb/v1` (1.x) or
+    /// `http://{host}:{port}/influxdb` (2.x).
     fn influxdb_api(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         let body_limit = warp::body::content_length_limit(self.config.max_body_size);
+        let default_precision = Precision::from(self.config.influxdb.default_precision.as_str());
 
         let write_api = warp::path!("write")
             .and(warp::post())
@@ -381,15 +613,19 @@ impl Service {
             .and(self.with_context())
             .and(warp::query::<WriteParams>())
             .and(warp::body::bytes())
+            .and(header::optional::<String>(CONTENT_ENCODING_HEADER))
             .and(self.with_proxy())
-            .and_then(|ctx, params, lines, proxy: Arc<Proxy>| async move {
-                let request = WriteRequest::new(lines, params);
-                let result = proxy.handle_influxdb_write(ctx, request).await;
-                match result {
-                    Ok(res) => Ok(reply::json(&res)),
-                    Err(e) => Err(reject::custom(e)),
-                }
-            });
+            .and_then(
+                move |ctx, params, lines, encoding: Option<String>, proxy: Arc<Proxy>| async move {
+                    let lines = decode_body(lines, encoding).map_err(reject::custom)?;
+                    let request = WriteRequest::new(lines, params, default_precision);
+                    let result = proxy.handle_influxdb_write(ctx, request).await;
+                    match result {
+                        Ok(res) => Ok(reply::json(&res)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            );
 
         // Query support both get and post method, so we can't add `body_limit` here.
         // Otherwise it will throw `Rejection(LengthRequired)`
@@ -414,7 +650,29 @@ impl Service {
                 }
             });
 
-        warp::path!("influxdb" / "v1" / ..).and(write_api.or(query_api))
+        let write_v2_api = warp::path!("influxdb" / "api" / "v2" / "write")
+            .and(warp::post())
+            .and(body_limit)
+            .and(self.with_context())
+            .and(warp::query::<WriteV2Params>())
+            .and(warp::body::bytes())
+            .and(header::optional::<String>(CONTENT_ENCODING_HEADER))
+            .and(self.with_proxy())
+            .and_then(
+                |ctx, params, lines, encoding: Option<String>, proxy: Arc<Proxy>| async move {
+                    let lines = decode_body(lines, encoding).map_err(reject::custom)?;
+                    let request = WriteRequest::new_v2(lines, params);
+                    let result = proxy.handle_influxdb_write(ctx, request).await;
+                    match result {
+                        Ok(_res) => Ok(reply::with_status(warp::reply(), StatusCode::NO_CONTENT)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            );
+
+        warp::path!("influxdb" / "v1" / ..)
+            .and(write_api.or(query_api))
+            .or(write_v2_api)
     }
 
     // POST /opentsdb/api/put
@@ -432,21 +690,7 @@ impl Service {
             .and(self.with_proxy())
             .and(header::optional::<String>(CONTENT_ENCODING_HEADER))
             .and_then(|ctx, params, points: Bytes, proxy: Arc<Proxy>, encoding: Option<String>| async move {
-                let points = match encoding {
-                    Some(encoding) => {
-                        let encode_type = ContentEncodingType::try_from(encoding.as_str())?;
-                        match encode_type {
-                            ContentEncodingType::Gzip => {
-                                let bytes = points.as_bytes();
-                                let mut decoder = GzDecoder::new(bytes);
-                                let mut decompressed_data = Vec::with_capacity(bytes.len()* 2);
-                                decoder.read_to_end(&mut decompressed_data).context(UnGzip)?;
-                                decompressed_data.into()
-                            },
-                        }
-                    },
-                    None => points,
-                };
+                let points = decode_body(points, encoding).map_err(reject::custom)?;
                 let request = PutRequest::new(points, params);
                 let result = proxy.handle_opentsdb_put(ctx, request).await;
                 match result {
@@ -458,6 +702,124 @@ impl Service {
         warp::path!("opentsdb" / "api" / ..).and(put_api)
     }
 
+    // POST /schema_registry/write
+    fn schema_registry_api(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let body_limit = warp::body::content_length_limit(self.config.max_body_size);
+
+        warp::path!("schema_registry" / "write")
+            .and(warp::post())
+            .and(body_limit)
+            .and(self.with_context())
+            .and(warp::query::<SchemaRegistryWriteParams>())
+            .and(warp::body::bytes())
+            .and(self.with_proxy())
+            .and_then(
+                |ctx, params, records: Bytes, proxy: Arc<Proxy>| async move {
+                    let request = SchemaRegistryWriteRequest::new(records, params);
+                    let result = proxy.handle_schema_registry_write(ctx, request).await;
+                    match result {
+                        Ok(_res) => Ok(reply::with_status(warp::reply(), StatusCode::NO_CONTENT)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    // POST /write/json?table={table}
+    fn json_write_api(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let body_limit = warp::body::content_length_limit(self.config.max_body_size);
+
+        warp::path!("write" / "json")
+            .and(warp::post())
+            .and(body_limit)
+            .and(self.with_context())
+            .and(warp::query::<JsonWriteParams>())
+            .and(warp::body::bytes())
+            .and(self.with_proxy())
+            .and_then(
+                |ctx, params: JsonWriteParams, documents: Bytes, proxy: Arc<Proxy>| async move {
+                    let result = proxy
+                        .handle_http_json_write(ctx, &params.table, &documents)
+                        .await;
+                    match result {
+                        Ok(res) => Ok(reply::json(&res)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    // GET/POST /render
+    fn graphite_render(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        // The params may be in the query string or the form body like the influxdb
+        // query api.
+        warp::path!("render")
+            .and(warp::get().or(warp::post()).unify())
+            .and(self.with_context())
+            .and(warp::query::<RenderParams>())
+            .and(warp::body::form::<HashMap<String, String>>())
+            .and(self.with_proxy())
+            .and_then(
+                |ctx, params: RenderParams, form, proxy: Arc<Proxy>| async move {
+                    let params = params.merge_form(form);
+                    match proxy.handle_graphite_render(ctx, params).await {
+                        Ok(res) => Ok(reply::json(&res)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    // GET /schema_events?schema={schema}
+    //
+    // Stream the changes of the tables as server-sent events, and the changes
+    // of all the schemas are streamed if no schema is given.
+    fn schema_events(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("schema_events")
+            .and(warp::get())
+            .and(warp::query::<HashMap<String, String>>())
+            .and(self.with_instance())
+            .map(|params: HashMap<String, String>, instance: InstanceRef| {
+                let schema = params.get("schema").cloned();
+                let receiver = instance.schema_events.subscribe();
+                let events = futures::stream::unfold(receiver, move |mut receiver| {
+                    let schema = schema.clone();
+                    async move {
+                        loop {
+                            let event = match receiver.recv().await {
+                                Ok(event) => event,
+                                // Tell the client to invalidate all the cached schemas as some
+                                // events are missed.
+                                Err(RecvError::Lagged(n)) => {
+                                    let event =
+                                        sse::Event::default().event("lagged").data(n.to_string());
+                                    return Some((Ok::<_, Infallible>(event), receiver));
+                                }
+                                Err(RecvError::Closed) => return None,
+                            };
+                            if schema.as_ref().map_or(false, |v| v != &event.schema) {
+                                continue;
+                            }
+
+                            let data = serde_json::to_string(&event).unwrap_or_default();
+                            let event = sse::Event::default().event(event.kind.as_str()).data(data);
+                            return Some((Ok(event), receiver));
+                        }
+                    }
+                });
+
+                sse::reply(sse::keep_alive().stream(events))
+            })
+    }
+
     // POST /debug/flush_memtable
     fn flush_memtable(
         &self,
@@ -507,10 +869,48 @@ impl Service {
     }
 
     // GET /metrics
+    // GET /ready
+    //
+    // Responds 503 once the server starts draining for the graceful shutdown,
+    // so the load balancers can stop routing requests to it.
+    fn ready(&self) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("ready")
+            .and(warp::get())
+            .and(self.with_instance())
+            .map(|instance: InstanceRef| {
+                let drainer = &instance.drainer;
+                let status = if drainer.is_draining() {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                };
+                let body = serde_json::json!({
+                    "ready": !drainer.is_draining(),
+                    "inflight": drainer.inflight(),
+                });
+                reply::with_status(reply::json(&body), status)
+            })
+    }
+
     fn metrics(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
-        warp::path!("metrics").and(warp::get()).map(metrics::dump)
+        warp::path!("metrics")
+            .and(warp::get())
+            .and(warp::header::optional::<String>("accept"))
+            .map(|accept: Option<String>| {
+                // Prometheus asks for OpenMetrics to scrape the exemplars.
+                if accept.is_some_and(|v| v.contains("application/openmetrics-text")) {
+                    reply::with_header(
+                        metrics::dump_openmetrics(),
+                        "content-type",
+                        metrics::OPENMETRICS_CONTENT_TYPE,
+                    )
+                    .into_response()
+                } else {
+                    metrics::dump().into_response()
+                }
+            })
     }
 
     // GET /debug/profile/cpu/{seconds}
@@ -637,6 +1037,28 @@ impl Service {
             )
     }
 
+    // POST /admin/reload_config
+    fn reload_config(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let config_reload = self.config_reload.clone();
+        warp::path!("admin" / "reload_config")
+            .and(warp::post())
+            .and_then(move || {
+                let config_reload = config_reload.clone();
+                async move {
+                    let result = match config_reload {
+                        Some(config_reload) => config_reload.reload().await,
+                        None => Err("Config reloading is not supported".to_string()),
+                    };
+                    match result {
+                        Ok(changed) => Ok(reply::json(&serde_json::json!({ "changed": changed }))),
+                        Err(msg) => Err(reject::custom(Error::ReloadConfig { msg })),
+                    }
+                }
+            })
+    }
+
     // POST /admin/block
     fn admin_block(
         &self,
@@ -659,85 +1081,770 @@ impl Service {
             })
     }
 
-    // POST /debug/query_push_down/{true/false}
-    fn query_push_down(
+    // POST /admin/maintenance
+    fn admin_maintenance(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
-        warp::path!("debug" / "query_push_down" / ..)
-            .and(warp::path::param::<bool>())
+        warp::path!("admin" / "maintenance")
             .and(warp::post())
-            .and(self.with_proxy())
-            .and_then(|enable: bool, proxy: Arc<Proxy>| async move {
-                proxy
-                    .instance()
-                    .dyn_config
-                    .fronted
-                    .enable_dist_query_push_down
-                    .store(enable, Ordering::Relaxed);
-                std::result::Result::<_, Rejection>::Ok(format!("{enable}").into_response())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_maintenance(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
             })
     }
 
-    // PUT /debug/slow_threshold/{seconds}
-    fn slow_threshold(
+    // GET/POST /admin/read_only
+    fn admin_read_only(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
-        warp::path!("debug" / "slow_threshold" / ..)
-            .and(warp::path::param::<u64>())
-            .and(warp::put())
-            .and(self.with_proxy())
-            .and_then(|slow_threshold_secs: u64, proxy: Arc<Proxy>| async move {
-                proxy
-                    .instance()
-                    .dyn_config
-                    .slow_threshold
-                    .store(slow_threshold_secs, Ordering::Relaxed);
-                std::result::Result::<_, Rejection>::Ok(
-                    format!("current_slow_threshold:{slow_threshold_secs}s").into_response(),
-                )
+        let get = warp::get().map(|| None::<handlers::admin::ReadOnlyMode>);
+        let post = warp::post().and(warp::body::json()).map(Some);
+
+        warp::path!("admin" / "read_only")
+            .and(get.or(post).unify())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_read_only(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
             })
     }
 
-    fn with_context(
+    // GET/POST /admin/route_rules
+    fn admin_route_rules(
         &self,
-    ) -> impl Filter<Extract = (RequestContext,), Error = warp::Rejection> + Clone {
-        let default_catalog = self
-            .proxy
-            .instance()
-            .catalog_manager
-            .default_catalog_name()
-            .to_string();
-        let default_schema = self
-            .proxy
-            .instance()
-            .catalog_manager
-            .default_schema_name()
-            .to_string();
-        let timeout = self.config.timeout;
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let get = warp::get().map(|| None::<RuleList>);
+        let post = warp::post().and(warp::body::json()).map(Some);
 
-        header::optional::<String>(consts::CATALOG_HEADER)
-            .and(header::optional::<String>(consts::SCHEMA_HEADER))
-            .and(header::optional::<String>(consts::TENANT_HEADER))
-            .and_then(
-                move |catalog: Option<_>, schema: Option<_>, _tenant: Option<_>| {
-                    // Clone the captured variables
-                    let default_catalog = default_catalog.clone();
-                    let schema = schema.unwrap_or_else(|| default_schema.clone());
-                    async move {
-                        RequestContext::builder()
-                            .catalog(catalog.unwrap_or(default_catalog))
-                            .schema(schema)
-                            .timeout(timeout)
-                            .build()
-                            .context(CreateContext)
-                            .map_err(reject::custom)
-                    }
-                },
-            )
+        warp::path!("admin" / "route_rules")
+            .and(get.or(post).unify())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and_then(|req, ctx, proxy: Arc<Proxy>| async move {
+                let result = handlers::admin::handle_route_rules(ctx, proxy.router(), req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
     }
 
-    fn with_profiler(&self) -> impl Filter<Extract = (Arc<Profiler>,), Error = Infallible> + Clone {
-        let profiler = self.profiler.clone();
+    // GET/POST /admin/quotas
+    fn admin_quotas(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let get = warp::get().map(|| None::<handlers::admin::QuotaRequest>);
+        let post = warp::post().and(warp::body::json()).map(Some);
+
+        warp::path!("admin" / "quotas")
+            .and(get.or(post).unify())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_quotas(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // GET /admin/queries
+    fn list_queries(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "queries")
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|ctx, instance| async {
+                let result = handlers::admin::handle_list_queries(ctx, instance)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /admin/allocator/{purge|decay}
+    fn release_allocator_memory(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "allocator" / String)
+            .and(warp::post())
+            .and(self.with_runtime())
+            .and_then(|op: String, runtime: Arc<Runtime>| async move {
+                let release: fn() -> profile::Result<()> = match op.as_str() {
+                    "purge" => profile::alloc::purge,
+                    "decay" => profile::alloc::decay,
+                    _ => return Err(reject::not_found()),
+                };
+
+                // Releasing memory may take a while for a large heap.
+                let handle = runtime.spawn_blocking(move || {
+                    release().context(ControlAllocator { op })?;
+                    profile::alloc::stats().context(ControlAllocator { op: "stat" })
+                });
+                match handle.await.context(JoinAsyncTask) {
+                    Ok(Ok(stats)) => Ok(reply::json(&stats)),
+                    Ok(Err(e)) | Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // GET /admin/compaction
+    fn compaction_status(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let dynamic_config = self.engine_dynamic_config.clone();
+        warp::path!("admin" / "compaction")
+            .and(warp::get())
+            .and_then(move || {
+                let dynamic_config = dynamic_config.clone();
+                async move {
+                    match dynamic_config {
+                        Some(dynamic_config) => Ok(reply::json(&CompactionStatus {
+                            paused: dynamic_config.compaction_paused(),
+                        })),
+                        None => Err(reject::custom(Error::ControlCompaction {
+                            msg: "Compaction control is not supported".to_string(),
+                        })),
+                    }
+                }
+            })
+    }
+
+    // POST /admin/compaction/{pause|resume}
+    fn control_compaction(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let dynamic_config = self.engine_dynamic_config.clone();
+        warp::path!("admin" / "compaction" / String)
+            .and(warp::post())
+            .and_then(move |op: String| {
+                let dynamic_config = dynamic_config.clone();
+                async move {
+                    let paused = match op.as_str() {
+                        "pause" => true,
+                        "resume" => false,
+                        _ => return Err(reject::not_found()),
+                    };
+                    match dynamic_config {
+                        Some(dynamic_config) => {
+                            dynamic_config.set_compaction_paused(paused);
+                            info!("Compaction is controlled by admin api, paused:{paused}");
+                            Ok(reply::json(&CompactionStatus { paused }))
+                        }
+                        None => Err(reject::custom(Error::ControlCompaction {
+                            msg: "Compaction control is not supported".to_string(),
+                        })),
+                    }
+                }
+            })
+    }
+
+    // GET /admin/recompression
+    fn recompression_status(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let dynamic_config = self.engine_dynamic_config.clone();
+        warp::path!("admin" / "recompression")
+            .and(warp::get())
+            .and_then(move || {
+                let dynamic_config = dynamic_config.clone();
+                async move {
+                    match dynamic_config {
+                        Some(dynamic_config) => {
+                            Ok(reply::json(&dynamic_config.recompression().status()))
+                        }
+                        None => Err(reject::custom(Error::ControlCompaction {
+                            msg: "Recompression control is not supported".to_string(),
+                        })),
+                    }
+                }
+            })
+    }
+
+    // POST /admin/recompression/{pause|resume}
+    fn control_recompression(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let dynamic_config = self.engine_dynamic_config.clone();
+        warp::path!("admin" / "recompression" / String)
+            .and(warp::post())
+            .and_then(move |op: String| {
+                let dynamic_config = dynamic_config.clone();
+                async move {
+                    let paused = match op.as_str() {
+                        "pause" => true,
+                        "resume" => false,
+                        _ => return Err(reject::not_found()),
+                    };
+                    match dynamic_config {
+                        Some(dynamic_config) => {
+                            let progress = dynamic_config.recompression();
+                            progress.set_paused(paused);
+                            info!("Recompression is controlled by admin api, paused:{paused}");
+                            Ok(reply::json(&progress.status()))
+                        }
+                        None => Err(reject::custom(Error::ControlCompaction {
+                            msg: "Recompression control is not supported".to_string(),
+                        })),
+                    }
+                }
+            })
+    }
+
+    fn with_backup_leases(
+        &self,
+    ) -> impl Filter<Extract = (BackupLeasesRef,), Error = warp::Rejection> + Clone {
+        let backup_leases = self
+            .engine_dynamic_config
+            .as_ref()
+            .map(|v| v.backup_leases().clone());
+        warp::any().and_then(move || {
+            let backup_leases = backup_leases.clone();
+            async move {
+                backup_leases.ok_or_else(|| {
+                    reject::custom(Error::ControlBackup {
+                        msg: "Backup control is not supported".to_string(),
+                    })
+                })
+            }
+        })
+    }
+
+    // GET /admin/backup
+    fn backup_status(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "backup")
+            .and(warp::get())
+            .and(self.with_backup_leases())
+            .map(|backup_leases: BackupLeasesRef| {
+                reply::json(&BackupStatus {
+                    leases: backup_leases.leases(),
+                })
+            })
+    }
+
+    // POST /admin/backup/lease?ttl=30m
+    //
+    // The files of the engine are pinned until the lease is released or
+    // expired, so a backup should acquire a lease on every node before copying
+    // the files.
+    fn acquire_backup_lease(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "backup" / "lease")
+            .and(warp::post())
+            .and(warp::query::<BackupLeaseParams>())
+            .and(self.with_backup_leases())
+            .map(
+                |params: BackupLeaseParams, backup_leases: BackupLeasesRef| {
+                    let lease = backup_leases.acquire(params.ttl());
+                    info!("Backup lease is acquired, lease:{lease:?}");
+                    reply::json(&lease)
+                },
+            )
+    }
+
+    // POST /admin/backup/lease/{id}?ttl=30m
+    fn renew_backup_lease(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "backup" / "lease" / u64)
+            .and(warp::post())
+            .and(warp::query::<BackupLeaseParams>())
+            .and(self.with_backup_leases())
+            .and_then(
+                |id, params: BackupLeaseParams, backup_leases: BackupLeasesRef| async move {
+                    match backup_leases.renew(id, params.ttl()) {
+                        Some(lease) => Ok(reply::json(&lease)),
+                        None => Err(reject::custom(Error::ControlBackup {
+                            msg: format!("Backup lease not found or expired, id:{id}"),
+                        })),
+                    }
+                },
+            )
+    }
+
+    // DELETE /admin/backup/lease/{id}
+    fn release_backup_lease(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "backup" / "lease" / u64)
+            .and(warp::delete())
+            .and(self.with_backup_leases())
+            .and_then(|id, backup_leases: BackupLeasesRef| async move {
+                if !backup_leases.release(id) {
+                    return Err(reject::custom(Error::ControlBackup {
+                        msg: format!("Backup lease not found or expired, id:{id}"),
+                    }));
+                }
+
+                info!("Backup lease is released, id:{id}");
+                Ok(reply::json(&BackupStatus {
+                    leases: backup_leases.leases(),
+                }))
+            })
+    }
+
+    // GET /admin/query_history
+    fn query_history(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "query_history")
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|ctx, instance| async {
+                let result = handlers::admin::handle_query_history(ctx, instance)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // DELETE /admin/queries/{id}
+    fn kill_query(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "queries" / u64)
+            .and(warp::delete())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|id, ctx, instance| async move {
+                let result = match handlers::admin::handle_kill_query(ctx, instance, id).await {
+                    Ok(res) => Ok(res),
+                    Err(handlers::error::Error::QueryNotFound { .. }) => {
+                        QueryNotFound { id }.fail()
+                    }
+                    Err(e) => Err(e).box_err().context(HandleRequest),
+                };
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // GET /admin/functions
+    fn list_functions(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "functions")
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|ctx, instance| async {
+                let result = handlers::admin::handle_list_functions(ctx, instance)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /admin/functions/reload
+    fn reload_functions(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "functions" / "reload")
+            .and(warp::post())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|ctx, instance| async {
+                let result = handlers::admin::handle_reload_functions(ctx, instance)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /admin/schema_diff
+    fn schema_diff(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "schema_diff")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_schema_diff(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /admin/replica_check/resync
+    fn resync_replica(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "replica_check" / "resync")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_resync_replica(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // GET/POST /admin/json_mappings, DELETE /admin/json_mappings/{table}
+    fn json_mappings(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let list = warp::path!("admin" / "json_mappings")
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and_then(|ctx, proxy| async {
+                let result = handlers::admin::handle_list_json_mappings(ctx, proxy)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            });
+        let register = warp::path!("admin" / "json_mappings")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and_then(|spec, ctx, proxy| async {
+                let result = handlers::admin::handle_register_json_mapping(ctx, proxy, spec)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            });
+        let remove = warp::path!("admin" / "json_mappings" / String)
+            .and(warp::delete())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and_then(|table, ctx, proxy| async {
+                let result = handlers::admin::handle_remove_json_mapping(ctx, proxy, table)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            });
+
+        list.or(register).or(remove)
+    }
+
+    // POST /admin/import
+    fn admin_import(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "import")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and_then(|req, ctx, proxy| async {
+                let result = handlers::admin::handle_import(ctx, proxy, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // GET /admin/import
+    fn list_imports(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "import")
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and_then(|ctx, proxy| async {
+                let result = handlers::admin::handle_list_imports(ctx, proxy)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /debug/query_push_down/{true/false}
+    fn query_push_down(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "query_push_down" / ..)
+            .and(warp::path::param::<bool>())
+            .and(warp::post())
+            .and(self.with_proxy())
+            .and_then(|enable: bool, proxy: Arc<Proxy>| async move {
+                proxy
+                    .instance()
+                    .dyn_config
+                    .fronted
+                    .enable_dist_query_push_down
+                    .store(enable, Ordering::Relaxed);
+                std::result::Result::<_, Rejection>::Ok(format!("{enable}").into_response())
+            })
+    }
+
+    // PUT /debug/slow_threshold/{seconds}
+    fn slow_threshold(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "slow_threshold" / ..)
+            .and(warp::path::param::<u64>())
+            .and(warp::put())
+            .and(self.with_proxy())
+            .and_then(|slow_threshold_secs: u64, proxy: Arc<Proxy>| async move {
+                proxy
+                    .instance()
+                    .dyn_config
+                    .slow_threshold
+                    .store(slow_threshold_secs, Ordering::Relaxed);
+                std::result::Result::<_, Rejection>::Ok(
+                    format!("current_slow_threshold:{slow_threshold_secs}s").into_response(),
+                )
+            })
+    }
+
+    // PUT /debug/wildcard_limit
+    fn wildcard_limit(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "wildcard_limit")
+            .and(warp::put())
+            .and(warp::body::json())
+            .and(self.with_proxy())
+            .and_then(|limit: WildcardLimit, proxy: Arc<Proxy>| async move {
+                *proxy
+                    .instance()
+                    .dyn_config
+                    .fronted
+                    .wildcard_limit
+                    .write()
+                    .unwrap() = limit.clone();
+                std::result::Result::<_, Rejection>::Ok(reply::json(&limit))
+            })
+    }
+
+    // PUT /debug/time_range_guard
+    fn time_range_guard(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "time_range_guard")
+            .and(warp::put())
+            .and(warp::body::json())
+            .and(self.with_proxy())
+            .and_then(|guard: TimeRangeGuard, proxy: Arc<Proxy>| async move {
+                *proxy
+                    .instance()
+                    .dyn_config
+                    .fronted
+                    .time_range_guard
+                    .write()
+                    .unwrap() = guard.clone();
+                std::result::Result::<_, Rejection>::Ok(reply::json(&guard))
+            })
+    }
+
+    fn with_context(
+        &self,
+    ) -> impl Filter<Extract = (RequestContext,), Error = warp::Rejection> + Clone {
+        let default_catalog = self
+            .proxy
+            .instance()
+            .catalog_manager
+            .default_catalog_name()
+            .to_string();
+        let default_schema = self
+            .proxy
+            .instance()
+            .catalog_manager
+            .default_schema_name()
+            .to_string();
+        let timeout = self.config.timeout;
+        let proxy = self.proxy.clone();
+
+        header::optional::<String>(consts::CATALOG_HEADER)
+            .and(header::optional::<String>(consts::SCHEMA_HEADER))
+            .and(header::optional::<String>(consts::TENANT_HEADER))
+            .and(header::optional::<String>(LOG_LEVEL_KEY))
+            .and(header::optional::<String>(AUTHORIZATION_KEY))
+            .and_then(
+                move |catalog: Option<_>,
+                      schema: Option<_>,
+                      _tenant: Option<_>,
+                      log_level: Option<String>,
+                      authorization: Option<String>| {
+                    // Clone the captured variables
+                    let default_catalog = default_catalog.clone();
+                    let schema = schema.unwrap_or_else(|| default_schema.clone());
+                    let log_level = log_level.and_then(|level| {
+                        logger::parse_verbose_level(&level)
+                            .map_err(|e| warn!("Ignore invalid log level of the request, err:{e}"))
+                            .ok()
+                    });
+                    let auth_user = proxy.authenticate(authorization.as_deref());
+                    async move {
+                        let auth_user = auth_user
+                            .map_err(|e| Error::Authenticate {
+                                msg: e.error_message(),
+                            })
+                            .map_err(reject::custom)?;
+                        RequestContext::builder()
+                            .catalog(catalog.unwrap_or(default_catalog))
+                            .schema(schema)
+                            .timeout(timeout)
+                            .log_level(log_level)
+                            .auth_user(auth_user)
+                            .build()
+                            .context(CreateContext)
+                            .map_err(reject::custom)
+                    }
+                },
+            )
+    }
+
+    /// Require the admin role on the default catalog to access the admin and
+    /// debug APIs, and the read only role on the catalog of the request to
+    /// access the others, if the authentication is enabled. The metrics and
+    /// the readiness probe are public. The statements are also authorized by
+    /// their plans.
+    fn with_api_auth(&self) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+        let proxy = self.proxy.clone();
+        let api_prefix = format!("/api/{}", openapi::API_VERSION);
+        warp::path::full()
+            .and(header::optional::<String>(consts::CATALOG_HEADER))
+            .and(header::optional::<String>(AUTHORIZATION_KEY))
+            .and_then(
+                move |path: FullPath, catalog: Option<String>, authorization: Option<String>| {
+                    let proxy = proxy.clone();
+                    let role = required_role(path.as_str(), &api_prefix);
+                    async move {
+                        let Some(role) = role else {
+                            return Ok(());
+                        };
+                        let user = proxy
+                            .authenticate(authorization.as_deref())
+                            .map_err(|e| Error::Authenticate {
+                                msg: e.error_message(),
+                            })
+                            .map_err(reject::custom)?;
+                        let catalog_manager = &proxy.instance().catalog_manager;
+                        let catalog = match (role, &catalog) {
+                            (UserRole::ReadOnly, Some(catalog)) => catalog.as_str(),
+                            _ => catalog_manager.default_catalog_name(),
+                        };
+                        proxy
+                            .check_role(user.as_ref(), catalog, role)
+                            .map_err(|e| Error::Forbidden {
+                                msg: e.error_message(),
+                            })
+                            .map_err(reject::custom)
+                    }
+                },
+            )
+            .untuple_one()
+    }
+
+    /// The console is only served when the authentication is enabled, as the
+    /// requests without the authenticated users are run as the internal ones.
+    /// The requests are authenticated and authorized by [Self::with_api_auth].
+    fn with_console_auth(&self) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+        let enable = self.config.console.enable && self.proxy.instance().authenticator.is_some();
+        warp::any()
+            .and_then(move || async move {
+                if enable {
+                    Ok(())
+                } else {
+                    Err(reject::not_found())
+                }
+            })
+            .untuple_one()
+    }
+
+    fn with_profiler(&self) -> impl Filter<Extract = (Arc<Profiler>,), Error = Infallible> + Clone {
+        let profiler = self.profiler.clone();
         warp::any().map(move || profiler.clone())
     }
 
@@ -792,6 +1899,8 @@ pub struct Builder {
     cluster: Option<ClusterRef>,
     proxy: Option<Arc<Proxy>>,
     opened_wals: Option<OpenedWals>,
+    config_reload: Option<ReloadTrigger>,
+    engine_dynamic_config: Option<DynamicConfigRef>,
 }
 
 impl Builder {
@@ -804,6 +1913,8 @@ impl Builder {
             cluster: None,
             proxy: None,
             opened_wals: None,
+            config_reload: None,
+            engine_dynamic_config: None,
         }
     }
 
@@ -836,6 +1947,16 @@ impl Builder {
         self.opened_wals = Some(opened_wals);
         self
     }
+
+    pub fn config_reload(mut self, config_reload: Option<ReloadTrigger>) -> Self {
+        self.config_reload = config_reload;
+        self
+    }
+
+    pub fn engine_dynamic_config(mut self, dynamic_config: Option<DynamicConfigRef>) -> Self {
+        self.engine_dynamic_config = dynamic_config;
+        self
+    }
 }
 
 impl Builder {
@@ -861,6 +1982,8 @@ impl Builder {
             config: self.config,
             config_content,
             opened_wals,
+            config_reload: self.config_reload,
+            engine_dynamic_config: self.engine_dynamic_config,
         };
 
         Ok(service)
@@ -873,12 +1996,45 @@ pub struct HttpConfig {
     pub endpoint: Endpoint,
     pub max_body_size: u64,
     pub timeout: Option<Duration>,
+    pub influxdb: influxdb::Config,
+    pub console: console::Config,
+    pub tls: auth::TlsConfig,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorResponse {
-    code: u16,
-    message: String,
+/// Query params of the backup lease api.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct BackupLeaseParams {
+    /// Time to live of the lease, and the backup should renew the lease before
+    /// it expires, e.g. 30m.
+    #[schemars(with = "Option<String>")]
+    ttl: Option<ReadableDuration>,
+}
+
+impl BackupLeaseParams {
+    const DEFAULT_TTL: Duration = Duration::from_secs(30 * 60);
+
+    fn ttl(&self) -> Duration {
+        self.ttl.map(|v| v.0).unwrap_or(Self::DEFAULT_TTL)
+    }
+}
+
+/// The status of the compaction returned by the compaction api.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct CompactionStatus {
+    pub(crate) paused: bool,
+}
+
+/// The leases of the running backups returned by the backup api.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct BackupStatus {
+    pub(crate) leases: Vec<BackupLease>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ErrorResponse {
+    pub(crate) code: u16,
+    pub(crate) message: String,
 }
 
 fn error_to_status_code(err: &Error) -> StatusCode {
@@ -901,9 +2057,17 @@ fn error_to_status_code(err: &Error) -> StatusCode {
         | Error::AlreadyStarted { .. }
         | Error::MissingRouter { .. }
         | Error::MissingWal { .. }
-        | Error::QueryShards { .. } => StatusCode::BAD_REQUEST,
-        Error::HandleUpdateLogLevel { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        | Error::QueryShards { .. }
+        | Error::ReloadConfig { .. }
+        | Error::ControlCompaction { .. }
+        | Error::ControlBackup { .. } => StatusCode::BAD_REQUEST,
+        Error::HandleUpdateLogLevel { .. } | Error::ControlAllocator { .. } => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
         Error::QueryMaybeExceedTTL { .. } => StatusCode::OK,
+        Error::Authenticate { .. } => StatusCode::UNAUTHORIZED,
+        Error::Forbidden { .. } => StatusCode::FORBIDDEN,
+        Error::QueryNotFound { .. } => StatusCode::NOT_FOUND,
     }
 }
 
@@ -933,6 +2097,75 @@ async fn handle_rejection(
         code: code.as_u16(),
         message,
     });
+    let mut resp = reply::with_status(json, code).into_response();
+    if code == StatusCode::UNAUTHORIZED {
+        resp.headers_mut().insert(
+            WWW_AUTHENTICATE,
+            HeaderValue::from_static(console::AUTHENTICATE_CHALLENGE),
+        );
+    }
 
-    Ok((reply::with_status(json, code),))
+    Ok((resp,))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public_path() {
+        for path in ["/metrics", "/ready"] {
+            assert!(is_public_path(path), "path:{path}");
+        }
+        for path in [
+            "/",
+            "/sql",
+            "/route/t",
+            "/schema_events",
+            "/metrics/x",
+            "/console",
+            "/console/api/sql",
+        ] {
+            assert!(!is_public_path(path), "path:{path}");
+        }
+    }
+
+    #[test]
+    fn test_required_role() {
+        let api_prefix = "/api/v1";
+        for path in ["/metrics", "/api/v1/ready", "/console/api/metrics"] {
+            assert_eq!(None, required_role(path, api_prefix), "path:{path}");
+        }
+        for path in [
+            "/sql",
+            "/api/v1/sql",
+            "/console",
+            "/console/",
+            "/console/api/sql",
+        ] {
+            assert_eq!(
+                Some(UserRole::ReadOnly),
+                required_role(path, api_prefix),
+                "path:{path}"
+            );
+        }
+        for path in [
+            "/admin/queries",
+            "/debug/profile/cpu/1",
+            "/console/api/admin/queries",
+            "/console/api/admin/queries/1",
+        ] {
+            assert_eq!(
+                Some(UserRole::Admin),
+                required_role(path, api_prefix),
+                "path:{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_kill_missing_query_not_found() {
+        let err = QueryNotFound { id: 1 }.fail::<()>().unwrap_err();
+        assert_eq!(StatusCode::NOT_FOUND, error_to_status_code(&err));
+    }
 }
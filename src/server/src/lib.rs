@@ -21,14 +21,21 @@
 // Borrow some ideas from tikv: https://github.com/tikv/tikv/blob/dc8ce2cf6a8904cb3dad556f71b11bac3531689b/src/server/service/kv.rs#L51
 
 pub mod config;
+pub mod config_reload;
+pub mod console;
 mod consts;
 mod error_util;
 mod federated;
+mod graphite;
 mod grpc;
 mod http;
 pub mod local_tables;
+pub mod memory_watermark;
 mod metrics;
+mod mqtt;
 mod mysql;
+mod openapi;
 mod postgresql;
 pub mod server;
 mod session;
+mod statsd;
@@ -23,6 +23,7 @@ use catalog::{
 };
 use common_types::table::DEFAULT_SHARD_ID;
 use generic_error::{BoxError, GenericError};
+use logger::warn;
 use macros::define_result;
 use snafu::{Backtrace, ResultExt, Snafu};
 use table_engine::table::TableInfo;
@@ -51,6 +52,8 @@ pub struct LocalTablesRecoverer {
     table_infos: Vec<TableInfo>,
     table_operator: TableOperator,
     open_opts: OpenOptions,
+    /// Start even if some tables fail to open in the repair mode
+    repair: bool,
 }
 
 impl LocalTablesRecoverer {
@@ -58,11 +61,13 @@ impl LocalTablesRecoverer {
         table_infos: Vec<TableInfo>,
         table_operator: TableOperator,
         open_opts: OpenOptions,
+        repair: bool,
     ) -> Self {
         Self {
             table_infos,
             table_operator,
             open_opts,
+            repair,
         }
     }
 
@@ -89,7 +94,8 @@ impl LocalTablesRecoverer {
         };
         let opts = self.open_opts.clone();
 
-        self.table_operator
+        let result = self
+            .table_operator
             .open_shard(request, opts)
             .await
             .box_err()
@@ -98,8 +104,14 @@ impl LocalTablesRecoverer {
                     "failed to recover tables, table_info:{:?}",
                     self.table_infos
                 ),
-            })?;
-
-        Ok(())
+            });
+        match result {
+            // The tables opened successfully are still registered.
+            Err(e) if self.repair => {
+                warn!("Skip the tables failing to open in repair mode, err:{e}");
+                Ok(())
+            }
+            other => other,
+        }
     }
 }
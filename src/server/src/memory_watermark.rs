@@ -0,0 +1,308 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Memory watermark, which tracks the memory used by the process against the
+//! configured limit and sheds load progressively to avoid being OOM-killed.
+
+use catalog::manager::ManagerRef;
+use generic_error::{BoxError, GenericResult};
+use logger::{error, info, warn};
+use proxy::{instance::InstanceRef, limiter::MemoryPressure};
+use runtime::Runtime;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use table_engine::table::{FlushRequest, TableRef};
+use time_ext::ReadableDuration;
+use timed_task::{TaskHandle, TimedTask};
+
+use crate::metrics::{MEMORY_PRESSURE_GAUGE, MEMORY_WATERMARK_USED_BYTES_GAUGE};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Memory limit of the process, the watermark is disabled if it's zero.
+    ///
+    /// Defaults to the memory limit of the cgroup if any.
+    pub memory_limit: ReadableSize,
+    /// Interval to check the memory usage.
+    pub check_interval: ReadableDuration,
+    /// The following ratios of the `memory_limit` are the watermarks of the
+    /// [MemoryPressure] levels.
+    pub shrink_caches_ratio: f64,
+    pub flush_ratio: f64,
+    pub reject_heavy_queries_ratio: f64,
+    pub reject_writes_ratio: f64,
+    /// The pressure goes down only after the memory used drops below the
+    /// watermark by this ratio of the `memory_limit`, so that the pressure
+    /// won't flap around a watermark.
+    pub recover_margin_ratio: f64,
+    /// Flush all the tables under the [MemoryPressure::Flush] pressure, which
+    /// produces lots of small ssts and is disabled by default.
+    pub enable_flush: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            memory_limit: ReadableSize(system_stats::cgroup_limits().memory_limit.unwrap_or(0)),
+            check_interval: ReadableDuration::secs(1),
+            shrink_caches_ratio: 0.7,
+            flush_ratio: 0.8,
+            reject_heavy_queries_ratio: 0.9,
+            reject_writes_ratio: 0.95,
+            recover_margin_ratio: 0.05,
+            enable_flush: false,
+        }
+    }
+}
+
+impl Config {
+    fn enabled(&self) -> bool {
+        self.memory_limit.as_byte() > 0
+    }
+
+    /// Pressure of the `used` memory given the pressure of the last check.
+    fn pressure_of(&self, used: u64, prev: MemoryPressure) -> MemoryPressure {
+        let pressure = self.pressure_with_margin(used, 0.0);
+        if pressure >= prev {
+            return pressure;
+        }
+
+        // Only go down to the level whose watermark (minus the margin) is still
+        // exceeded.
+        self.pressure_with_margin(used, self.recover_margin_ratio)
+            .min(prev)
+    }
+
+    fn pressure_with_margin(&self, used: u64, margin_ratio: f64) -> MemoryPressure {
+        let limit = self.memory_limit.as_byte() as f64;
+        let used = used as f64;
+        let watermarks = [
+            (self.reject_writes_ratio, MemoryPressure::RejectWrites),
+            (
+                self.reject_heavy_queries_ratio,
+                MemoryPressure::RejectHeavyQueries,
+            ),
+            (self.flush_ratio, MemoryPressure::Flush),
+            (self.shrink_caches_ratio, MemoryPressure::ShrinkCaches),
+        ];
+
+        watermarks
+            .into_iter()
+            .find(|(ratio, _)| used >= limit * (ratio - margin_ratio))
+            .map(|(_, pressure)| pressure)
+            .unwrap_or(MemoryPressure::Normal)
+    }
+}
+
+/// Start the task to check the memory periodically, returns `None` if the
+/// watermark is disabled.
+pub fn start_watermark_task(
+    config: Config,
+    instance: InstanceRef,
+    runtime: &Runtime,
+) -> Option<TaskHandle> {
+    if !config.enabled() {
+        info!("Memory watermark is disabled");
+        return None;
+    }
+
+    info!("Memory watermark is enabled, config:{config:?}");
+    let interval = config.check_interval.0;
+    let builder = move || {
+        let config = config.clone();
+        let instance = instance.clone();
+        async move { check_memory(&config, &instance).await }
+    };
+
+    Some(TimedTask::start_timed_task(
+        String::from("memory_watermark"),
+        runtime,
+        interval,
+        builder,
+    ))
+}
+
+async fn check_memory(config: &Config, instance: &InstanceRef) {
+    let used = match process_resident_memory() {
+        Some(v) => v,
+        None => return,
+    };
+    MEMORY_WATERMARK_USED_BYTES_GAUGE.set(used as i64);
+
+    let limiter = &instance.limiter;
+    let prev_pressure = limiter.memory_pressure();
+    let pressure = config.pressure_of(used, prev_pressure);
+    if pressure != prev_pressure {
+        warn!(
+            "Memory pressure changes from {} to {}, used:{}, limit:{}",
+            prev_pressure.as_str(),
+            pressure.as_str(),
+            used,
+            config.memory_limit.as_byte()
+        );
+        limiter.set_memory_pressure(pressure);
+        MEMORY_PRESSURE_GAUGE.set(pressure as i64);
+    }
+
+    // The actions are repeated in every check until the pressure goes down.
+    if pressure >= MemoryPressure::ShrinkCaches {
+        if let Err(e) = instance.table_engine.shrink_caches().await {
+            error!("Memory watermark failed to shrink caches, err:{e}");
+        }
+    }
+    if config.enable_flush && pressure >= MemoryPressure::Flush {
+        flush_all_tables(&instance.catalog_manager).await;
+    }
+}
+
+fn all_tables(catalog_manager: &ManagerRef) -> GenericResult<Vec<TableRef>> {
+    let mut tables = Vec::new();
+    for catalog in catalog_manager.all_catalogs().box_err()? {
+        for schema in catalog.all_schemas().box_err()? {
+            tables.extend(schema.all_tables().box_err()?);
+        }
+    }
+
+    Ok(tables)
+}
+
+async fn flush_all_tables(catalog_manager: &ManagerRef) {
+    let tables = match all_tables(catalog_manager) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Memory watermark failed to list tables, err:{e}");
+            return;
+        }
+    };
+
+    for table in tables {
+        // Don't wait for the flush, the memtables are released once it finishes.
+        if let Err(e) = table.flush(FlushRequest { sync: false }).await {
+            error!(
+                "Memory watermark failed to flush table, table:{}, err:{e}",
+                table.name()
+            );
+        }
+    }
+}
+
+/// Resident memory of the process in bytes.
+#[cfg(target_os = "linux")]
+fn process_resident_memory() -> Option<u64> {
+    let status = match std::fs::read_to_string("/proc/self/status") {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Failed to read the status of the process, err:{e}");
+            return None;
+        }
+    };
+
+    parse_vm_rss(&status)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_resident_memory() -> Option<u64> {
+    profile::alloc::stats()
+        .ok()
+        .flatten()
+        .map(|stats| stats.resident as u64)
+}
+
+/// Parse the `VmRSS` line like `VmRSS:     1024 kB`.
+#[cfg(target_os = "linux")]
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure_of() {
+        let config = Config {
+            memory_limit: ReadableSize(100),
+            ..Default::default()
+        };
+
+        let cases = [
+            (0, MemoryPressure::Normal),
+            (69, MemoryPressure::Normal),
+            (70, MemoryPressure::ShrinkCaches),
+            (85, MemoryPressure::Flush),
+            (90, MemoryPressure::RejectHeavyQueries),
+            (95, MemoryPressure::RejectWrites),
+            (200, MemoryPressure::RejectWrites),
+        ];
+        for (used, expect) in cases {
+            assert_eq!(
+                expect,
+                config.pressure_of(used, MemoryPressure::Normal),
+                "used:{used}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pressure_hysteresis() {
+        use MemoryPressure::*;
+
+        let config = Config {
+            memory_limit: ReadableSize(100),
+            ..Default::default()
+        };
+
+        let cases = [
+            // (used, prev, expect)
+            (80, Flush, Flush),
+            (76, Flush, Flush),
+            (74, Flush, ShrinkCaches),
+            (64, Flush, Normal),
+            (66, ShrinkCaches, ShrinkCaches),
+            (92, ShrinkCaches, RejectHeavyQueries),
+            (92, RejectWrites, RejectWrites),
+            (89, RejectWrites, RejectHeavyQueries),
+        ];
+        for (used, prev, expect) in cases {
+            assert_eq!(
+                expect,
+                config.pressure_of(used, prev),
+                "used:{used}, prev:{prev:?}"
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\thoraedb\nVmPeak:\t  2048 kB\nVmRSS:\t  1024 kB\nThreads:\t8\n";
+        assert_eq!(Some(1024 * 1024), parse_vm_rss(status));
+        assert_eq!(None, parse_vm_rss("Name:\thoraedb\n"));
+
+        assert!(process_resident_memory().unwrap() > 0);
+    }
+}
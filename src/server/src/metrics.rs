@@ -19,7 +19,15 @@
 
 use lazy_static::lazy_static;
 use logger::warn;
-use prometheus::{exponential_buckets, register_histogram_vec, Encoder, HistogramVec, TextEncoder};
+use prometheus::{
+    exponential_buckets, register_histogram_vec, register_int_gauge, register_int_gauge_vec,
+    Encoder, HistogramVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use proxy::metrics::{exemplars_enabled, QUERY_DURATION_EXEMPLARS, QUERY_DURATION_METRIC};
+
+/// Content type of the OpenMetrics text format.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
 
 lazy_static! {
     pub static ref HTTP_HANDLER_DURATION_HISTOGRAM_VEC: HistogramVec = register_histogram_vec!(
@@ -30,10 +38,55 @@ lazy_static! {
         exponential_buckets(0.01, 2.0, 15).unwrap()
     )
     .unwrap();
+    pub static ref ALLOCATOR_STATS_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "allocator_stats_bytes",
+        "Memory statistics of the global allocator",
+        &["allocator", "type"]
+    )
+    .unwrap();
+    pub static ref MEMORY_WATERMARK_USED_BYTES_GAUGE: IntGauge = register_int_gauge!(
+        "memory_watermark_used_bytes",
+        "Memory used by the process checked by the memory watermark"
+    )
+    .unwrap();
+    pub static ref MEMORY_PRESSURE_GAUGE: IntGauge = register_int_gauge!(
+        "memory_pressure_level",
+        "Memory pressure level, 0 is normal and 4 rejects the writes"
+    )
+    .unwrap();
+}
+
+/// Refresh the allocator statistics, which are not updated on every
+/// allocation so only collected when the metrics are dumped.
+fn refresh_allocator_stats() {
+    let stats = match profile::alloc::stats() {
+        Ok(Some(stats)) => stats,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Failed to collect allocator stats, err:{}", e);
+            return;
+        }
+    };
+
+    let allocator = profile::alloc::allocator_name();
+    for (typ, value) in [
+        ("allocated", stats.allocated),
+        ("active", stats.active),
+        ("resident", stats.resident),
+        ("mapped", stats.mapped),
+        ("retained", stats.retained),
+        ("metadata", stats.metadata),
+    ] {
+        ALLOCATOR_STATS_GAUGE_VEC
+            .with_label_values(&[allocator, typ])
+            .set(value as i64);
+    }
 }
 
 /// Gather and dump prometheus to string.
 pub fn dump() -> String {
+    refresh_allocator_stats();
+
     let mut buffer = vec![];
     let encoder = TextEncoder::new();
     let metric_families = prometheus::gather();
@@ -44,3 +97,26 @@ pub fn dump() -> String {
     }
     String::from_utf8(buffer).unwrap()
 }
+
+/// Gather and dump prometheus to string in the OpenMetrics format, with the
+/// exemplars of the query duration attached if enabled.
+pub fn dump_openmetrics() -> String {
+    let mut output = String::new();
+    for line in dump().lines() {
+        // The names of the counters don't follow the `_total` convention of
+        // OpenMetrics, so export them as unknown.
+        match line.strip_prefix("# TYPE ").and_then(|v| v.split_once(' ')) {
+            Some((name, "counter" | "untyped")) => {
+                output.push_str(&format!("# TYPE {name} unknown"));
+            }
+            _ => output.push_str(line),
+        }
+        output.push('\n');
+    }
+
+    if exemplars_enabled() {
+        output = QUERY_DURATION_EXEMPLARS.append_to(QUERY_DURATION_METRIC, &output);
+    }
+    output.push_str("# EOF\n");
+    output
+}
@@ -0,0 +1,430 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Codec of the mqtt 3.1 and 3.1.1 packets.
+//!
+//! Only the packets sent by a publishing client are decoded, and only the
+//! acknowledgements of them are encoded.
+
+use bytes_ext::{Buf, BufMut, Bytes, BytesMut};
+use snafu::{ensure, OptionExt};
+
+use crate::mqtt::error::{InvalidPacket, PacketTooLarge, Result};
+
+const CONNECT: u8 = 1;
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+const PUBACK: u8 = 4;
+const SUBSCRIBE: u8 = 8;
+const SUBACK: u8 = 9;
+const UNSUBSCRIBE: u8 = 10;
+const UNSUBACK: u8 = 11;
+const PINGREQ: u8 = 12;
+const PINGRESP: u8 = 13;
+const DISCONNECT: u8 = 14;
+
+/// Protocol level of mqtt 3.1.
+const PROTOCOL_LEVEL_V31: u8 = 3;
+/// Protocol level of mqtt 3.1.1.
+const PROTOCOL_LEVEL_V311: u8 = 4;
+
+pub const CONNACK_ACCEPTED: u8 = 0;
+pub const CONNACK_UNACCEPTABLE_PROTOCOL: u8 = 1;
+pub const CONNACK_BAD_CREDENTIALS: u8 = 4;
+pub const CONNACK_NOT_AUTHORIZED: u8 = 5;
+/// Flags of the CONNECT packet.
+const CONNECT_FLAG_WILL: u8 = 0x04;
+const CONNECT_FLAG_PASSWORD: u8 = 0x40;
+const CONNECT_FLAG_USERNAME: u8 = 0x80;
+/// Return code of the SUBACK for a rejected subscription.
+const SUBACK_FAILURE: u8 = 0x80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    Connect {
+        protocol_level: u8,
+        client_id: String,
+        keep_alive: u16,
+        username: Option<String>,
+        password: Option<Bytes>,
+    },
+    Publish {
+        qos: u8,
+        packet_id: Option<u16>,
+        topic: String,
+        payload: Bytes,
+    },
+    Subscribe {
+        packet_id: u16,
+        num_filters: usize,
+    },
+    Unsubscribe {
+        packet_id: u16,
+    },
+    PingReq,
+    Disconnect,
+}
+
+impl Packet {
+    pub fn is_supported_protocol(protocol_level: u8) -> bool {
+        protocol_level == PROTOCOL_LEVEL_V31 || protocol_level == PROTOCOL_LEVEL_V311
+    }
+}
+
+/// Decode a packet from the buffer, returns `None` if the buffer doesn't
+/// contain a whole packet yet.
+pub fn decode(buf: &mut BytesMut, max_packet_size: usize) -> Result<Option<Packet>> {
+    let Some((header_len, remaining_len)) = decode_fixed_header(buf)? else {
+        return Ok(None);
+    };
+    let size = header_len + remaining_len;
+    ensure!(
+        size <= max_packet_size,
+        PacketTooLarge {
+            size,
+            limit: max_packet_size,
+        }
+    );
+    if buf.len() < size {
+        return Ok(None);
+    }
+
+    let first_byte = buf[0];
+    buf.advance(header_len);
+    let body = buf.split_to(remaining_len).freeze();
+
+    decode_packet(first_byte >> 4, first_byte & 0x0f, body).map(Some)
+}
+
+/// Decode the length of the fixed header and the remaining length.
+fn decode_fixed_header(buf: &[u8]) -> Result<Option<(usize, usize)>> {
+    let mut remaining_len = 0;
+    // The remaining length is encoded in at most 4 bytes following the first
+    // byte.
+    for i in 0..4 {
+        let Some(byte) = buf.get(i + 1) else {
+            return Ok(None);
+        };
+        remaining_len |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((i + 2, remaining_len)));
+        }
+    }
+
+    InvalidPacket {
+        msg: "malformed remaining length",
+    }
+    .fail()
+}
+
+fn decode_packet(packet_type: u8, flags: u8, mut body: Bytes) -> Result<Packet> {
+    let packet = match packet_type {
+        CONNECT => {
+            let _protocol_name = read_string(&mut body)?;
+            ensure_remaining(&body, 4)?;
+            let protocol_level = body.get_u8();
+            let connect_flags = body.get_u8();
+            let keep_alive = body.get_u16();
+            // The payload is unknown in the packets of the unsupported protocols.
+            if !Packet::is_supported_protocol(protocol_level) {
+                return Ok(Packet::Connect {
+                    protocol_level,
+                    client_id: String::new(),
+                    keep_alive,
+                    username: None,
+                    password: None,
+                });
+            }
+
+            let client_id = read_string(&mut body)?;
+            // The will is not published, as the listener has no subscribers.
+            if connect_flags & CONNECT_FLAG_WILL != 0 {
+                let _will_topic = read_string(&mut body)?;
+                let _will_message = read_binary(&mut body)?;
+            }
+            let username = if connect_flags & CONNECT_FLAG_USERNAME != 0 {
+                Some(read_string(&mut body)?)
+            } else {
+                None
+            };
+            let password = if connect_flags & CONNECT_FLAG_PASSWORD != 0 {
+                Some(read_binary(&mut body)?)
+            } else {
+                None
+            };
+
+            Packet::Connect {
+                protocol_level,
+                client_id,
+                keep_alive,
+                username,
+                password,
+            }
+        }
+        PUBLISH => {
+            let qos = (flags >> 1) & 0x03;
+            ensure!(
+                qos < 3,
+                InvalidPacket {
+                    msg: "invalid qos of publish",
+                }
+            );
+            let topic = read_string(&mut body)?;
+            let packet_id = if qos > 0 {
+                Some(read_u16(&mut body)?)
+            } else {
+                None
+            };
+
+            Packet::Publish {
+                qos,
+                packet_id,
+                topic,
+                payload: body,
+            }
+        }
+        SUBSCRIBE => {
+            let packet_id = read_u16(&mut body)?;
+            let mut num_filters = 0;
+            while body.has_remaining() {
+                let _filter = read_string(&mut body)?;
+                ensure_remaining(&body, 1)?;
+                let _qos = body.get_u8();
+                num_filters += 1;
+            }
+
+            Packet::Subscribe {
+                packet_id,
+                num_filters,
+            }
+        }
+        UNSUBSCRIBE => Packet::Unsubscribe {
+            packet_id: read_u16(&mut body)?,
+        },
+        PINGREQ => Packet::PingReq,
+        DISCONNECT => Packet::Disconnect,
+        _ => {
+            return InvalidPacket {
+                msg: format!("unexpected packet type:{packet_type}"),
+            }
+            .fail()
+        }
+    };
+
+    Ok(packet)
+}
+
+fn ensure_remaining(body: &Bytes, len: usize) -> Result<()> {
+    ensure!(
+        body.remaining() >= len,
+        InvalidPacket {
+            msg: "packet is truncated",
+        }
+    );
+
+    Ok(())
+}
+
+fn read_u16(body: &mut Bytes) -> Result<u16> {
+    ensure_remaining(body, 2)?;
+    Ok(body.get_u16())
+}
+
+fn read_binary(body: &mut Bytes) -> Result<Bytes> {
+    let len = read_u16(body)? as usize;
+    ensure_remaining(body, len)?;
+    Ok(body.split_to(len))
+}
+
+fn read_string(body: &mut Bytes) -> Result<String> {
+    let bytes = read_binary(body)?;
+
+    String::from_utf8(bytes.to_vec())
+        .ok()
+        .context(InvalidPacket {
+            msg: "string is not utf8",
+        })
+}
+
+pub fn encode_connack(return_code: u8) -> Vec<u8> {
+    vec![CONNACK << 4, 2, 0, return_code]
+}
+
+pub fn encode_puback(packet_id: u16) -> Vec<u8> {
+    encode_ack(PUBACK << 4, packet_id)
+}
+
+pub fn encode_unsuback(packet_id: u16) -> Vec<u8> {
+    encode_ack(UNSUBACK << 4, packet_id)
+}
+
+/// The listener only accepts publishes, so all the subscriptions are rejected.
+pub fn encode_suback(packet_id: u16, num_filters: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(num_filters + 8);
+    buf.put_u8(SUBACK << 4);
+    encode_remaining_length(&mut buf, num_filters + 2);
+    buf.put_u16(packet_id);
+    buf.extend(std::iter::repeat(SUBACK_FAILURE).take(num_filters));
+    buf
+}
+
+pub fn encode_pingresp() -> Vec<u8> {
+    vec![PINGRESP << 4, 0]
+}
+
+fn encode_ack(first_byte: u8, packet_id: u16) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(4);
+    buf.put_u8(first_byte);
+    buf.put_u8(2);
+    buf.put_u16(packet_id);
+    buf
+}
+
+fn encode_remaining_length(buf: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        buf.put_u8(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put_string(buf: &mut Vec<u8>, s: &str) {
+        buf.put_u16(s.len() as u16);
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn packet(first_byte: u8, body: &[u8]) -> BytesMut {
+        let mut buf = vec![first_byte];
+        encode_remaining_length(&mut buf, body.len());
+        buf.extend_from_slice(body);
+        BytesMut::from(&buf[..])
+    }
+
+    #[test]
+    fn test_decode_connect() {
+        let mut body = Vec::new();
+        put_string(&mut body, "MQTT");
+        body.extend_from_slice(&[4, 0x02, 0, 60]);
+        put_string(&mut body, "device-1");
+
+        let mut buf = packet(CONNECT << 4, &body);
+        let packet = decode(&mut buf, 1024).unwrap().unwrap();
+        assert_eq!(
+            packet,
+            Packet::Connect {
+                protocol_level: 4,
+                client_id: "device-1".to_string(),
+                keep_alive: 60,
+                username: None,
+                password: None,
+            }
+        );
+        assert!(buf.is_empty());
+
+        // With the will, the username and the password.
+        let mut body = Vec::new();
+        put_string(&mut body, "MQTT");
+        body.extend_from_slice(&[4, 0xc6, 0, 0]);
+        put_string(&mut body, "device-2");
+        put_string(&mut body, "will/topic");
+        put_string(&mut body, "offline");
+        put_string(&mut body, "user");
+        put_string(&mut body, "pass");
+
+        let mut buf = packet(CONNECT << 4, &body);
+        let packet = decode(&mut buf, 1024).unwrap().unwrap();
+        assert_eq!(
+            packet,
+            Packet::Connect {
+                protocol_level: 4,
+                client_id: "device-2".to_string(),
+                keep_alive: 0,
+                username: Some("user".to_string()),
+                password: Some(Bytes::from_static(b"pass")),
+            }
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_publish() {
+        let payload = vec![b'x'; 200];
+        let mut body = Vec::new();
+        put_string(&mut body, "sensors/temp");
+        body.put_u16(7);
+        body.extend_from_slice(&payload);
+
+        // Qos 1, the remaining length takes 2 bytes.
+        let whole = packet((PUBLISH << 4) | 0x02, &body);
+        let mut buf = BytesMut::from(&whole[..10]);
+        assert!(decode(&mut buf, 1024).unwrap().is_none());
+
+        buf.extend_from_slice(&whole[10..]);
+        buf.extend_from_slice(&packet(PINGREQ << 4, &[]));
+        let packet = decode(&mut buf, 1024).unwrap().unwrap();
+        assert_eq!(
+            packet,
+            Packet::Publish {
+                qos: 1,
+                packet_id: Some(7),
+                topic: "sensors/temp".to_string(),
+                payload: Bytes::from(payload),
+            }
+        );
+        assert_eq!(decode(&mut buf, 1024).unwrap().unwrap(), Packet::PingReq);
+        assert!(buf.is_empty());
+
+        let mut buf = whole.clone();
+        assert!(decode(&mut buf, 100).is_err());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let mut buf = BytesMut::from(&[0x30, 0xff, 0xff, 0xff, 0xff][..]);
+        assert!(decode(&mut buf, usize::MAX).is_err());
+
+        // Publish with qos 3.
+        let mut buf = packet((PUBLISH << 4) | 0x06, &[0, 1, b'a', 0, 1]);
+        assert!(decode(&mut buf, 1024).is_err());
+
+        // Truncated topic.
+        let mut buf = packet(PUBLISH << 4, &[0, 5, b'a']);
+        assert!(decode(&mut buf, 1024).is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode_connack(CONNACK_ACCEPTED), vec![0x20, 2, 0, 0]);
+        assert_eq!(encode_puback(258), vec![0x40, 2, 1, 2]);
+        assert_eq!(encode_suback(1, 2), vec![0x90, 4, 0, 1, 0x80, 0x80]);
+        assert_eq!(encode_pingresp(), vec![0xd0, 0]);
+
+        let mut buf = Vec::new();
+        encode_remaining_length(&mut buf, 321);
+        assert_eq!(buf, vec![0xc1, 0x02]);
+    }
+}
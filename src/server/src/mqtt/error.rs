@@ -0,0 +1,99 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use generic_error::GenericError;
+use macros::define_result;
+use snafu::{Backtrace, Snafu};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Missing runtimes to build service.\nBacktrace:\n{}", backtrace))]
+    MissingRuntimes { backtrace: Backtrace },
+
+    #[snafu(display("Missing proxy to build service.\nBacktrace:\n{}", backtrace))]
+    MissingProxy { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse ip addr, ip:{}, err:{}.\nBacktrace:\n{}",
+        ip,
+        source,
+        backtrace
+    ))]
+    ParseIpAddr {
+        ip: String,
+        source: std::net::AddrParseError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to bind mqtt listener, addr:{}, err:{}", addr, source))]
+    BindListener {
+        addr: std::net::SocketAddr,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Failed to authenticate mqtt client, err:{}", source))]
+    Authenticate { source: GenericError },
+
+    #[snafu(display("Mqtt client is not authorized to write, err:{}", source))]
+    Unauthorized { source: GenericError },
+
+    #[snafu(display(
+        "Mqtt publish with unsupported qos, qos:{}.\nBacktrace:\n{}",
+        qos,
+        backtrace
+    ))]
+    UnsupportedQos { qos: u8, backtrace: Backtrace },
+
+    #[snafu(display("Invalid mqtt packet, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    InvalidPacket { msg: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Mqtt packet is too large, size:{}, limit:{}.\nBacktrace:\n{}",
+        size,
+        limit,
+        backtrace
+    ))]
+    PacketTooLarge {
+        size: usize,
+        limit: usize,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Mqtt client doesn't send any packet in time.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    KeepAliveTimeout { backtrace: Backtrace },
+
+    #[snafu(display("Failed to create request context, err:{}", source))]
+    CreateContext { source: proxy::context::Error },
+
+    #[snafu(display("Failed to write publish, topic:{}, err:{}", topic, source))]
+    WritePublish { topic: String, source: GenericError },
+
+    #[snafu(display("Unexpected error, err:{}", source))]
+    Unexpected { source: std::io::Error },
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Unexpected { source: e }
+    }
+}
+
+define_result!(Error);
@@ -0,0 +1,32 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Mqtt listener accepting the publishes of the devices, so the devices which
+//! can't speak grpc or http can write without a bridging service.
+//!
+//! The username and the password of the CONNECT packet are authenticated as
+//! the basic credentials if the authentication is enabled. Only the publishes
+//! of qos 0 and 1 are accepted, and the clients publishing with qos 2 are
+//! disconnected.
+
+mod builder;
+mod codec;
+pub mod error;
+mod service;
+
+pub use builder::Builder;
+pub use service::MqttService;
@@ -0,0 +1,327 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use bytes_ext::{Bytes, BytesMut};
+use generic_error::BoxError;
+use logger::{debug, error, info, warn};
+use proxy::{
+    auth::AuthUser,
+    context::RequestContext,
+    mqtt::{self, Config},
+    Proxy,
+};
+use query_frontend::plan::UserRole;
+use runtime::JoinHandle;
+use snafu::{OptionExt, ResultExt};
+use table_engine::engine::EngineRuntimes;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot::{self, Receiver, Sender},
+};
+
+use crate::mqtt::{
+    codec::{self, Packet},
+    error::{
+        Authenticate, BindListener, CreateContext, Error, InvalidPacket, KeepAliveTimeout, Result,
+        Unauthorized, UnsupportedQos, WritePublish,
+    },
+};
+
+/// Max time to wait for the CONNECT packet after the connection is accepted.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_BUFFER_SIZE: usize = 4096;
+
+pub struct MqttService {
+    addr: SocketAddr,
+    config: Arc<Config>,
+    proxy: Arc<Proxy>,
+    runtimes: Arc<EngineRuntimes>,
+    join_handler: Option<JoinHandle<()>>,
+    tx: Option<Sender<()>>,
+    timeout: Option<Duration>,
+}
+
+impl MqttService {
+    pub fn new(
+        config: Config,
+        proxy: Arc<Proxy>,
+        runtimes: Arc<EngineRuntimes>,
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            addr,
+            config: Arc::new(config),
+            proxy,
+            runtimes,
+            join_handler: None,
+            tx: None,
+            timeout,
+        }
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.tx = Some(tx);
+
+        info!("Mqtt server tries to listen on {}", self.addr);
+        let listener = TcpListener::bind(self.addr)
+            .await
+            .context(BindListener { addr: self.addr })?;
+
+        self.join_handler = Some(self.runtimes.default_runtime.spawn(Self::loop_accept(
+            self.config.clone(),
+            self.proxy.clone(),
+            self.timeout,
+            self.runtimes.clone(),
+            listener,
+            rx,
+        )));
+
+        Ok(())
+    }
+
+    pub fn shutdown(self) {
+        if let Some(tx) = self.tx {
+            let _ = tx.send(());
+        }
+    }
+
+    async fn loop_accept(
+        config: Arc<Config>,
+        proxy: Arc<Proxy>,
+        timeout: Option<Duration>,
+        runtimes: Arc<EngineRuntimes>,
+        listener: TcpListener,
+        mut rx: Receiver<()>,
+    ) {
+        loop {
+            tokio::select! {
+                conn_result = listener.accept() => {
+                    let (stream, addr) = match conn_result {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!("Mqtt server accept new connection fail, err:{e}");
+                            break;
+                        }
+                    };
+                    let conn = Connection {
+                        config: config.clone(),
+                        proxy: proxy.clone(),
+                        timeout,
+                        stream,
+                        buf: BytesMut::with_capacity(READ_BUFFER_SIZE),
+                        auth_user: None,
+                    };
+                    runtimes.write_runtime.spawn(async move {
+                        if let Err(e) = conn.handle().await {
+                            warn!("Mqtt connection is closed, addr:{addr}, err:{e}");
+                        }
+                    });
+                },
+                _ = &mut rx => {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+struct Connection {
+    config: Arc<Config>,
+    proxy: Arc<Proxy>,
+    timeout: Option<Duration>,
+    stream: TcpStream,
+    buf: BytesMut,
+    /// User authenticated by the CONNECT packet, `None` if the authentication
+    /// is disabled.
+    auth_user: Option<AuthUser>,
+}
+
+impl Connection {
+    async fn handle(mut self) -> Result<()> {
+        let Some(Packet::Connect {
+            protocol_level,
+            client_id,
+            keep_alive,
+            username,
+            password,
+        }) = self.read_packet(Some(CONNECT_TIMEOUT)).await?
+        else {
+            return InvalidPacket {
+                msg: "first packet must be CONNECT",
+            }
+            .fail();
+        };
+        if !Packet::is_supported_protocol(protocol_level) {
+            self.stream
+                .write_all(&codec::encode_connack(codec::CONNACK_UNACCEPTABLE_PROTOCOL))
+                .await?;
+            return InvalidPacket {
+                msg: format!("unsupported protocol level:{protocol_level}"),
+            }
+            .fail();
+        }
+        self.auth_user = match self.authenticate(username, password) {
+            Ok(v) => v,
+            Err((return_code, e)) => {
+                self.stream
+                    .write_all(&codec::encode_connack(return_code))
+                    .await?;
+                return Err(e);
+            }
+        };
+        self.stream
+            .write_all(&codec::encode_connack(codec::CONNACK_ACCEPTED))
+            .await?;
+        debug!("Mqtt client connected, client_id:{client_id}, keep_alive:{keep_alive}");
+
+        // The client is disconnected if no packet is received within one and a half
+        // times of the keep alive, and zero keep alive disables it.
+        let idle_timeout =
+            (keep_alive > 0).then(|| Duration::from_millis(keep_alive as u64 * 1500));
+        while let Some(packet) = self.read_packet(idle_timeout).await? {
+            let resp = match packet {
+                Packet::Publish {
+                    qos,
+                    packet_id,
+                    topic,
+                    payload,
+                } => {
+                    // The exactly once delivery is not supported, and the client
+                    // is disconnected as mqtt 3.1.1 can't reject a publish.
+                    if qos > 1 {
+                        return UnsupportedQos { qos }.fail();
+                    }
+                    if let Err(e) = self.write_publish(&topic, &payload).await {
+                        // Close the connection so the client will publish the message again
+                        // if it's not acknowledged.
+                        if qos > 0 {
+                            return Err(e);
+                        }
+                        error!("Failed to write mqtt publish, client_id:{client_id}, err:{e}");
+                    }
+                    packet_id.map(codec::encode_puback)
+                }
+                Packet::Subscribe {
+                    packet_id,
+                    num_filters,
+                } => Some(codec::encode_suback(packet_id, num_filters)),
+                Packet::Unsubscribe { packet_id } => Some(codec::encode_unsuback(packet_id)),
+                Packet::PingReq => Some(codec::encode_pingresp()),
+                Packet::Disconnect => break,
+                Packet::Connect { .. } => {
+                    return InvalidPacket {
+                        msg: "duplicate CONNECT",
+                    }
+                    .fail()
+                }
+            };
+            if let Some(resp) = resp {
+                self.stream.write_all(&resp).await?;
+            }
+        }
+
+        debug!("Mqtt client disconnected, client_id:{client_id}");
+
+        Ok(())
+    }
+
+    /// Authenticate the username and the password of the CONNECT packet as the
+    /// basic credentials, returns the return code of the CONNACK on failure.
+    fn authenticate(
+        &self,
+        username: Option<String>,
+        password: Option<Bytes>,
+    ) -> std::result::Result<Option<AuthUser>, (u8, Error)> {
+        let authorization = username.map(|username| {
+            let password = password.unwrap_or_default();
+            let mut credentials = format!("{username}:").into_bytes();
+            credentials.extend_from_slice(&password);
+            format!("Basic {}", base64::encode(credentials))
+        });
+        let auth_user = self
+            .proxy
+            .authenticate(authorization.as_deref())
+            .box_err()
+            .context(Authenticate)
+            .map_err(|e| (codec::CONNACK_BAD_CREDENTIALS, e))?;
+
+        let catalog = self.proxy.instance().catalog_manager.default_catalog_name();
+        self.proxy
+            .check_role(auth_user.as_ref(), catalog, UserRole::ReadWrite)
+            .box_err()
+            .context(Unauthorized)
+            .map_err(|e| (codec::CONNACK_NOT_AUTHORIZED, e))?;
+
+        Ok(auth_user)
+    }
+
+    /// Read the next packet, returns `None` if the connection is closed.
+    async fn read_packet(&mut self, idle_timeout: Option<Duration>) -> Result<Option<Packet>> {
+        loop {
+            if let Some(packet) = codec::decode(
+                &mut self.buf,
+                self.config.max_packet_size.as_byte() as usize,
+            )? {
+                return Ok(Some(packet));
+            }
+
+            let read = self.stream.read_buf(&mut self.buf);
+            let n = match idle_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, read)
+                    .await
+                    .ok()
+                    .context(KeepAliveTimeout)??,
+                None => read.await?,
+            };
+            if n == 0 {
+                return Ok(None);
+            }
+        }
+    }
+
+    async fn write_publish(&self, topic: &str, payload: &[u8]) -> Result<()> {
+        let Some(mapping) = mqtt::find_mapping(&self.config.topics, topic) else {
+            warn!("Drop mqtt publish of unknown topic, topic:{topic}");
+            return Ok(());
+        };
+
+        let catalog_manager = &self.proxy.instance().catalog_manager;
+        let schema = if mapping.schema.is_empty() {
+            catalog_manager.default_schema_name().to_string()
+        } else {
+            mapping.schema.clone()
+        };
+        let ctx = RequestContext::builder()
+            .catalog(catalog_manager.default_catalog_name().to_string())
+            .schema(schema)
+            .timeout(self.timeout)
+            .auth_user(self.auth_user.clone())
+            .build()
+            .context(CreateContext)?;
+
+        self.proxy
+            .handle_mqtt_publish(ctx, mapping, topic, payload)
+            .await
+            .box_err()
+            .context(WritePublish { topic })
+    }
+}
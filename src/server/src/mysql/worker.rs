@@ -98,8 +98,9 @@ where
         writer: QueryResultWriter<'a, W>,
     ) -> Result<()> {
         match self.do_query(sql).await {
-            Ok(res) => {
-                let mut writer = MysqlQueryResultWriter::create(writer);
+            Ok((res, num_warnings)) => {
+                let num_warnings = num_warnings.min(u16::MAX as usize) as u16;
+                let mut writer = MysqlQueryResultWriter::create(writer).with_warnings(num_warnings);
                 writer.write(res)
             }
             Err(error) => {
@@ -125,17 +126,22 @@ impl<W> MysqlWorker<W>
 where
     W: std::io::Write + Send + Sync,
 {
-    async fn do_query<'a>(&'a mut self, sql: &'a str) -> Result<Output> {
+    /// Returns the output and the number of the warnings of the query.
+    async fn do_query<'a>(&'a mut self, sql: &'a str) -> Result<(Output, usize)> {
         if let Some(output) = federated::check(sql, self.session.clone()) {
-            return Ok(output);
+            return Ok((output, 0));
         }
 
         let req = Request {
             query: sql.to_string(),
         };
         let ctx = self.create_ctx(self.session.clone())?;
-        self.proxy
-            .handle_http_sql_query(&ctx, req)
+        // The warnings of the last query are kept until the next query, so the client
+        // can fetch them by `SHOW WARNINGS`.
+        self.session.set_warnings(Vec::new());
+        let (output, warnings) = self
+            .proxy
+            .handle_http_sql_query_with_warnings(&ctx, req)
             .await
             .map_err(|e| {
                 error!("Mysql service Failed to handle sql, err: {}", e);
@@ -144,7 +150,11 @@ where
             .box_err()
             .context(HandleSql {
                 sql: sql.to_string(),
-            })
+            })?;
+        let num_warnings = warnings.len();
+        self.session.set_warnings(warnings);
+
+        Ok((output, num_warnings))
     }
 
     fn create_ctx(&self, session: SessionRef) -> Result<RequestContext> {
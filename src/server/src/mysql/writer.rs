@@ -26,34 +26,59 @@ use crate::mysql::error::Result;
 
 pub struct MysqlQueryResultWriter<'a, W: std::io::Write> {
     inner: Option<QueryResultWriter<'a, W>>,
+    /// Number of the warnings of the query, the client can fetch them by
+    /// `SHOW WARNINGS`.
+    num_warnings: u16,
 }
 
 impl<'a, W: std::io::Write> MysqlQueryResultWriter<'a, W> {
     pub fn create(inner: QueryResultWriter<'a, W>) -> Self {
-        Self { inner: Some(inner) }
+        Self {
+            inner: Some(inner),
+            num_warnings: 0,
+        }
+    }
+
+    pub fn with_warnings(mut self, num_warnings: u16) -> Self {
+        self.num_warnings = num_warnings;
+        self
     }
 
     pub fn write(&mut self, query_result: Output) -> Result<()> {
         if let Some(inner) = self.inner.take() {
             return match query_result {
-                Output::AffectedRows(count) => Self::write_affected_rows(inner, count),
-                Output::Records(rows) => Self::write_rows(inner, rows),
+                Output::AffectedRows(count) => {
+                    Self::write_affected_rows(inner, count, self.num_warnings)
+                }
+                Output::Records(rows) => Self::write_rows(inner, rows, self.num_warnings),
             };
         }
         Ok(())
     }
 
-    fn write_affected_rows(writer: QueryResultWriter<'a, W>, count: usize) -> Result<()> {
+    fn write_affected_rows(
+        writer: QueryResultWriter<'a, W>,
+        count: usize,
+        num_warnings: u16,
+    ) -> Result<()> {
         let res = OkResponse {
             affected_rows: count as u64,
+            warnings: num_warnings,
             ..Default::default()
         };
         writer.completed(res)?;
         Ok(())
     }
 
-    fn write_rows(writer: QueryResultWriter<'a, W>, records: RecordBatchVec) -> Result<()> {
-        let default_response = OkResponse::default();
+    fn write_rows(
+        writer: QueryResultWriter<'a, W>,
+        records: RecordBatchVec,
+        num_warnings: u16,
+    ) -> Result<()> {
+        let default_response = OkResponse {
+            warnings: num_warnings,
+            ..Default::default()
+        };
         if records.is_empty() {
             writer.completed(default_response)?;
             return Ok(());
@@ -169,6 +194,8 @@ mod tests {
                     comment: "".to_string(),
                     escaped_name: "id".to_string(),
                     default_value: None,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
                 target_type: ColumnType::MYSQL_TYPE_LONG,
             },
@@ -183,6 +210,8 @@ mod tests {
                     comment: "".to_string(),
                     escaped_name: "name".to_string(),
                     default_value: None,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
                 target_type: ColumnType::MYSQL_TYPE_VARCHAR,
             },
@@ -197,6 +226,8 @@ mod tests {
                     comment: "".to_string(),
                     escaped_name: "birthday".to_string(),
                     default_value: None,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
                 target_type: ColumnType::MYSQL_TYPE_LONG,
             },
@@ -211,6 +242,8 @@ mod tests {
                     comment: "".to_string(),
                     escaped_name: "is_show".to_string(),
                     default_value: None,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
                 target_type: ColumnType::MYSQL_TYPE_SHORT,
             },
@@ -225,6 +258,8 @@ mod tests {
                     comment: "".to_string(),
                     escaped_name: "money".to_string(),
                     default_value: None,
+                    display_name: "".to_string(),
+                    unit: "".to_string(),
                 },
                 target_type: ColumnType::MYSQL_TYPE_DOUBLE,
             },
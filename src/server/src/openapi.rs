@@ -0,0 +1,845 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! OpenAPI description of the versioned http api.
+//!
+//! The schemas of the request and response bodies are derived from the types
+//! used by the handlers by [JsonSchema], so they follow the serde attributes
+//! of the types, and the document is served at `/api/v1/openapi.json` so that
+//! the client generators and gateways can integrate without reading the
+//! handlers.
+
+use analytic_engine::{backup::BackupLease, compaction::recompression::RecompressionStatus};
+use proxy::{
+    admission::Config as AdmissionConfig,
+    handlers::admin::{
+        BlockRequest, BlockResponse, ImportRequest, ImportResponse, KillQueryResponse,
+        ListFunctionsResponse, ListImportsResponse, ListJsonMappingsResponse, ListQueriesResponse,
+        MaintenanceRequest, MaintenanceResponse, QueryHistoryResponse, QuotaRequest, ReadOnlyMode,
+        ReloadFunctionsResponse, RemoveJsonMappingResponse, ResyncReplicaRequest,
+        ResyncReplicaResponse, SchemaDiffRequest, SchemaDiffResponse,
+    },
+    http::{
+        route::RouteResponse,
+        sql::{Request as SqlRequest, ResponseWithWarnings},
+    },
+    influxdb::types::{WriteParams as InfluxdbWriteParams, WriteV2Params},
+    json_write::{JsonWriteParams, JsonWriteResponse, MappingSpec},
+    opentsdb::types::{Point, PutParams},
+    schema_registry::types::WriteParams as SchemaRegistryWriteParams,
+};
+use router::RuleList;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    visit::Visitor,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+use crate::http::{BackupLeaseParams, BackupStatus, CompactionStatus, ErrorResponse};
+
+/// Version of the http api, the paths of the api are prefixed by
+/// `/api/{API_VERSION}`.
+pub const API_VERSION: &str = "v1";
+
+/// The schemas registered in the components of the document.
+pub struct Components {
+    gen: SchemaGenerator,
+}
+
+impl Default for Components {
+    fn default() -> Self {
+        Self {
+            gen: SchemaSettings::openapi3().into_generator(),
+        }
+    }
+}
+
+impl Components {
+    /// Returns the schema of `T`, which refers to the registered one if `T`
+    /// is referenceable.
+    pub fn schema_of<T: JsonSchema>(&mut self) -> Value {
+        let schema = self.gen.subschema_for::<T>();
+        self.to_json(schema)
+    }
+
+    /// Returns the query parameters described by the fields of `T`.
+    pub fn query_params_of<T: JsonSchema>(&mut self) -> Vec<Value> {
+        let schema = T::json_schema(&mut self.gen).into_object();
+        let Some(object) = schema.object else {
+            return Vec::new();
+        };
+
+        let mut params = Vec::with_capacity(object.properties.len());
+        for (name, schema) in object.properties {
+            let required = object.required.contains(&name);
+            let mut schema = self.to_json(schema);
+            let description = schema
+                .as_object_mut()
+                .and_then(|schema| schema.remove("description"));
+            let mut param = json!({
+                "name": name,
+                "in": "query",
+                "required": required,
+                "schema": schema,
+            });
+            if let Some(description) = description {
+                param["description"] = description;
+            }
+            params.push(param);
+        }
+
+        params
+    }
+
+    /// The openapi flavor of the json schema is applied to the schema, e.g.
+    /// `nullable` instead of the `null` type.
+    fn to_json(&mut self, mut schema: Schema) -> Value {
+        for visitor in self.gen.visitors_mut() {
+            visitor.visit_schema(&mut schema);
+        }
+        serde_json::to_value(schema).expect("schema must be serializable")
+    }
+
+    fn into_schemas(mut self) -> Map<String, Value> {
+        let definitions = self.gen.take_definitions();
+        definitions
+            .into_iter()
+            .map(|(name, schema)| (name, self.to_json(schema)))
+            .collect()
+    }
+}
+
+/// An operation of the api.
+struct ApiOperation {
+    method: &'static str,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    parameters: Vec<Value>,
+    /// Content type and schema of the request body.
+    request: Option<(&'static str, Value)>,
+    /// Status code of the success response.
+    status: &'static str,
+    response: Option<Value>,
+}
+
+impl ApiOperation {
+    fn new(
+        method: &'static str,
+        path: &'static str,
+        tag: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Self {
+            method,
+            path,
+            tag,
+            summary,
+            parameters: Vec::new(),
+            request: None,
+            status: "200",
+            response: None,
+        }
+    }
+
+    fn path_param(mut self, name: &str, schema: Value) -> Self {
+        self.parameters.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": schema,
+        }));
+        self
+    }
+
+    fn query_params(mut self, params: Vec<Value>) -> Self {
+        self.parameters.extend(params);
+        self
+    }
+
+    fn request(self, schema: Value) -> Self {
+        self.request_of("application/json", schema)
+    }
+
+    fn request_of(mut self, content_type: &'static str, schema: Value) -> Self {
+        self.request = Some((content_type, schema));
+        self
+    }
+
+    fn response(mut self, schema: Value) -> Self {
+        self.response = Some(schema);
+        self
+    }
+
+    fn no_content(mut self) -> Self {
+        self.status = "204";
+        self
+    }
+
+    fn into_json(self, error: &Value) -> Value {
+        let mut operation = json!({
+            "tags": [self.tag],
+            "summary": self.summary,
+            "responses": {
+                "default": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": error } },
+                },
+            },
+        });
+        operation["responses"][self.status] = match self.response {
+            Some(schema) => json!({
+                "description": "OK",
+                "content": { "application/json": { "schema": schema } },
+            }),
+            None => json!({ "description": "OK" }),
+        };
+        if !self.parameters.is_empty() {
+            operation["parameters"] = Value::Array(self.parameters);
+        }
+        if let Some((content_type, schema)) = self.request {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { content_type: { "schema": schema } },
+            });
+        }
+
+        operation
+    }
+}
+
+fn operations(c: &mut Components) -> Vec<ApiOperation> {
+    use ApiOperation as Op;
+
+    let text = json!({ "type": "string" });
+    let binary = json!({ "type": "string", "format": "binary" });
+    let ttl_params = c.query_params_of::<BackupLeaseParams>();
+    vec![
+        Op::new(
+            "post",
+            "/sql",
+            "query",
+            "Execute a sql statement, e.g. query, insert or ddl",
+        )
+        .request(c.schema_of::<SqlRequest>())
+        .response(c.schema_of::<ResponseWithWarnings>()),
+        Op::new(
+            "get",
+            "/route/{table}",
+            "query",
+            "Route the table to its endpoint",
+        )
+        .path_param("table", c.schema_of::<String>())
+        .response(c.schema_of::<RouteResponse>()),
+        Op::new(
+            "post",
+            "/influxdb/v1/write",
+            "write",
+            "Write the influxdb line protocol",
+        )
+        .query_params(c.query_params_of::<InfluxdbWriteParams>())
+        .request_of("text/plain", text.clone()),
+        Op::new(
+            "post",
+            "/influxdb/api/v2/write",
+            "write",
+            "Write the influxdb line protocol by the v2 api",
+        )
+        .query_params(c.query_params_of::<WriteV2Params>())
+        .request_of("text/plain", text)
+        .no_content(),
+        Op::new(
+            "post",
+            "/opentsdb/api/put",
+            "write",
+            "Write the opentsdb data points",
+        )
+        .query_params(c.query_params_of::<PutParams>())
+        .request(json!({ "oneOf": [c.schema_of::<Point>(), c.schema_of::<Vec<Point>>()] }))
+        .no_content(),
+        Op::new(
+            "post",
+            "/schema_registry/write",
+            "write",
+            "Write the records encoded by the schema of the subject",
+        )
+        .query_params(c.query_params_of::<SchemaRegistryWriteParams>())
+        .request_of("application/octet-stream", binary.clone())
+        .no_content(),
+        Op::new(
+            "post",
+            "/write/json",
+            "write",
+            "Write the json documents by the mapping",
+        )
+        .query_params(c.query_params_of::<JsonWriteParams>())
+        .request(json!({
+            "description": "An object, an array of objects or the newline delimited objects",
+        }))
+        .response(c.schema_of::<JsonWriteResponse>()),
+        Op::new("post", "/prom/v1/write", "write", "Prometheus remote write")
+            .request_of("application/x-protobuf", binary),
+        Op::new("post", "/admin/block", "admin", "Block the reads or writes")
+            .request(c.schema_of::<BlockRequest>())
+            .response(c.schema_of::<BlockResponse>()),
+        Op::new(
+            "post",
+            "/admin/maintenance",
+            "admin",
+            "Put the tables into maintenance",
+        )
+        .request(c.schema_of::<MaintenanceRequest>())
+        .response(c.schema_of::<MaintenanceResponse>()),
+        Op::new("get", "/admin/read_only", "admin", "Get the read only mode")
+            .response(c.schema_of::<ReadOnlyMode>()),
+        Op::new(
+            "post",
+            "/admin/read_only",
+            "admin",
+            "Set the read only mode",
+        )
+        .request(c.schema_of::<ReadOnlyMode>())
+        .response(c.schema_of::<ReadOnlyMode>()),
+        Op::new("get", "/admin/route_rules", "admin", "Get the route rules")
+            .response(c.schema_of::<RuleList>()),
+        Op::new(
+            "post",
+            "/admin/route_rules",
+            "admin",
+            "Replace the route rules",
+        )
+        .request(c.schema_of::<RuleList>())
+        .response(c.schema_of::<RuleList>()),
+        Op::new(
+            "get",
+            "/admin/quotas",
+            "admin",
+            "Get the quotas of the tenants",
+        )
+        .response(c.schema_of::<AdmissionConfig>()),
+        Op::new(
+            "post",
+            "/admin/quotas",
+            "admin",
+            "Update the quotas of the tenants",
+        )
+        .request(c.schema_of::<QuotaRequest>())
+        .response(c.schema_of::<AdmissionConfig>()),
+        Op::new("get", "/admin/queries", "admin", "List the running queries")
+            .response(c.schema_of::<ListQueriesResponse>()),
+        Op::new(
+            "delete",
+            "/admin/queries/{id}",
+            "admin",
+            "Kill the running query",
+        )
+        .path_param("id", c.schema_of::<u64>())
+        .response(c.schema_of::<KillQueryResponse>()),
+        Op::new(
+            "get",
+            "/admin/query_history",
+            "admin",
+            "List the finished queries",
+        )
+        .response(c.schema_of::<QueryHistoryResponse>()),
+        Op::new(
+            "get",
+            "/admin/functions",
+            "admin",
+            "List the udfs loaded from the wasm modules",
+        )
+        .response(c.schema_of::<ListFunctionsResponse>()),
+        Op::new(
+            "post",
+            "/admin/functions/reload",
+            "admin",
+            "Load the new wasm modules",
+        )
+        .response(c.schema_of::<ReloadFunctionsResponse>()),
+        Op::new(
+            "post",
+            "/admin/schema_diff",
+            "admin",
+            "Diff the schemas with the target",
+        )
+        .request(c.schema_of::<SchemaDiffRequest>())
+        .response(c.schema_of::<SchemaDiffResponse>()),
+        Op::new(
+            "post",
+            "/admin/replica_check/resync",
+            "admin",
+            "Re-sync the replica",
+        )
+        .request(c.schema_of::<ResyncReplicaRequest>())
+        .response(c.schema_of::<ResyncReplicaResponse>()),
+        Op::new(
+            "get",
+            "/admin/json_mappings",
+            "admin",
+            "List the json mappings",
+        )
+        .response(c.schema_of::<ListJsonMappingsResponse>()),
+        Op::new(
+            "post",
+            "/admin/json_mappings",
+            "admin",
+            "Register the json mapping",
+        )
+        .request(c.schema_of::<MappingSpec>())
+        .response(c.schema_of::<MappingSpec>()),
+        Op::new(
+            "delete",
+            "/admin/json_mappings/{table}",
+            "admin",
+            "Remove the json mapping",
+        )
+        .path_param("table", c.schema_of::<String>())
+        .response(c.schema_of::<RemoveJsonMappingResponse>()),
+        Op::new(
+            "post",
+            "/admin/import",
+            "admin",
+            "Import the files into the table",
+        )
+        .request(c.schema_of::<ImportRequest>())
+        .response(c.schema_of::<ImportResponse>()),
+        Op::new("get", "/admin/import", "admin", "List the import jobs")
+            .response(c.schema_of::<ListImportsResponse>()),
+        Op::new(
+            "get",
+            "/admin/compaction",
+            "admin",
+            "Get the compaction status",
+        )
+        .response(c.schema_of::<CompactionStatus>()),
+        Op::new(
+            "post",
+            "/admin/compaction/{op}",
+            "admin",
+            "Pause or resume the compaction",
+        )
+        .path_param(
+            "op",
+            json!({ "type": "string", "enum": ["pause", "resume"] }),
+        )
+        .response(c.schema_of::<CompactionStatus>()),
+        Op::new(
+            "get",
+            "/admin/recompression",
+            "admin",
+            "Get the recompression status",
+        )
+        .response(c.schema_of::<RecompressionStatus>()),
+        Op::new(
+            "post",
+            "/admin/recompression/{op}",
+            "admin",
+            "Pause or resume the recompression of the old ssts",
+        )
+        .path_param(
+            "op",
+            json!({ "type": "string", "enum": ["pause", "resume"] }),
+        )
+        .response(c.schema_of::<RecompressionStatus>()),
+        Op::new(
+            "get",
+            "/admin/backup",
+            "admin",
+            "List the leases of the running backups",
+        )
+        .response(c.schema_of::<BackupStatus>()),
+        Op::new(
+            "post",
+            "/admin/backup/lease",
+            "admin",
+            "Pin the files for a backup",
+        )
+        .query_params(ttl_params.clone())
+        .response(c.schema_of::<BackupLease>()),
+        Op::new(
+            "post",
+            "/admin/backup/lease/{id}",
+            "admin",
+            "Renew the lease of a backup",
+        )
+        .path_param("id", c.schema_of::<u64>())
+        .query_params(ttl_params)
+        .response(c.schema_of::<BackupLease>()),
+        Op::new(
+            "delete",
+            "/admin/backup/lease/{id}",
+            "admin",
+            "Release the lease of a backup",
+        )
+        .path_param("id", c.schema_of::<u64>())
+        .response(c.schema_of::<BackupStatus>()),
+        Op::new(
+            "post",
+            "/admin/allocator/{op}",
+            "admin",
+            "Release the allocator memory",
+        )
+        .path_param(
+            "op",
+            json!({ "type": "string", "enum": ["purge", "decay"] }),
+        ),
+    ]
+}
+
+/// Build the OpenAPI document of the versioned api.
+pub fn document() -> Value {
+    let mut components = Components::default();
+    let error = components.schema_of::<ErrorResponse>();
+
+    let mut paths = Map::new();
+    for operation in operations(&mut components) {
+        let path = format!("/api/{API_VERSION}{}", operation.path);
+        let method = operation.method;
+        let item = paths.entry(path).or_insert_with(|| json!({}));
+        item[method] = operation.into_json(&error);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "HoraeDB HTTP API",
+            "version": API_VERSION,
+        },
+        "paths": paths,
+        "components": { "schemas": components.into_schemas() },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use analytic_engine::{backup::BackupLease, compaction::recompression::RecompressionStatus};
+    use common_types::datum::{Datum, DatumKind};
+    use proxy::{
+        http::sql::{Response, ResponseColumn, ResponseRows},
+        import::{ImportProgress, ImportState},
+        json_write::{CoerceType, ColumnSpec},
+        limiter::BlockRule,
+        maintenance::{MaintainedTable, MaintenanceMode, WriteMode},
+    };
+    use router::rule_based::{RegexRule, TablePattern};
+    use serde::Serialize;
+    use table_engine::query_warning::{Warning, WarningKind};
+
+    use super::*;
+
+    /// Collect the references to the schemas in the `value`.
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(r)) = map.get("$ref") {
+                    refs.push(r.clone());
+                }
+                map.values().for_each(|v| collect_refs(v, refs));
+            }
+            Value::Array(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+
+    fn resolve<'a>(schemas: &'a Map<String, Value>, schema: &'a Value) -> &'a Value {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(r) => {
+                let name = r.strip_prefix("#/components/schemas/").unwrap();
+                resolve(schemas, &schemas[name])
+            }
+            None => schema,
+        }
+    }
+
+    /// Collect the properties described by the schema and its subschemas,
+    /// returns false if the other properties are allowed.
+    fn collect_properties(
+        schemas: &Map<String, Value>,
+        schema: &Value,
+        names: &mut HashSet<String>,
+    ) -> bool {
+        let schema = resolve(schemas, schema);
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            names.extend(properties.keys().cloned());
+        }
+        let mut closed = matches!(
+            schema.get("additionalProperties"),
+            None | Some(Value::Bool(false))
+        );
+        for key in ["allOf", "oneOf", "anyOf"] {
+            let subschemas = schema.get(key).and_then(Value::as_array);
+            for subschema in subschemas.into_iter().flatten() {
+                closed &= collect_properties(schemas, subschema, names);
+            }
+        }
+
+        closed
+    }
+
+    /// Check the `value` against the `schema`, and the properties of the
+    /// `value` must be described by the schema.
+    fn check(
+        schemas: &Map<String, Value>,
+        schema: &Value,
+        value: &Value,
+        path: &str,
+    ) -> Result<(), String> {
+        validate(schemas, schema, value, path)?;
+
+        if let Value::Object(object) = value {
+            let mut names = HashSet::new();
+            if collect_properties(schemas, schema, &mut names) && !names.is_empty() {
+                if let Some(key) = object.keys().find(|key| !names.contains(*key)) {
+                    return Err(format!("{path}.{key} is not described"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(
+        schemas: &Map<String, Value>,
+        schema: &Value,
+        value: &Value,
+        path: &str,
+    ) -> Result<(), String> {
+        let schema = resolve(schemas, schema);
+        if value.is_null() && schema.get("nullable") == Some(&Value::Bool(true)) {
+            return Ok(());
+        }
+
+        for subschema in schema
+            .get("allOf")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            validate(schemas, subschema, value, path)?;
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(subschemas) = schema.get(key).and_then(Value::as_array) {
+                let matched = subschemas
+                    .iter()
+                    .filter(|subschema| validate(schemas, subschema, value, path).is_ok())
+                    .count();
+                if matched == 0 || (key == "oneOf" && matched > 1) {
+                    return Err(format!("{path} matches {matched} schemas of {key}:{value}"));
+                }
+            }
+        }
+        if let Some(ty) = schema.get("type").and_then(Value::as_str) {
+            let matched = match ty {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "number" => value.is_number(),
+                "boolean" => value.is_boolean(),
+                _ => false,
+            };
+            if !matched {
+                return Err(format!("{path} is not {ty}:{value}"));
+            }
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.contains(value) {
+                return Err(format!("{path} is not in the enum:{value}"));
+            }
+        }
+
+        match value {
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        check(schemas, item_schema, item, &format!("{path}[{i}]"))?;
+                    }
+                }
+            }
+            Value::Object(object) => {
+                let required = schema.get("required").and_then(Value::as_array);
+                for name in required.into_iter().flatten() {
+                    let name = name.as_str().unwrap();
+                    if !object.contains_key(name) {
+                        return Err(format!("{path}.{name} is required"));
+                    }
+                }
+
+                let properties = schema.get("properties").and_then(Value::as_object);
+                for (key, field) in object {
+                    let path = format!("{path}.{key}");
+                    let property = properties.and_then(|properties| properties.get(key));
+                    match (property, schema.get("additionalProperties")) {
+                        (Some(property), _) => check(schemas, property, field, &path)?,
+                        (None, Some(Value::Bool(false))) => {
+                            return Err(format!("{path} is not allowed"))
+                        }
+                        (None, Some(additional)) => check(schemas, additional, field, &path)?,
+                        (None, None) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Check the serialized `value` against its schema in the document.
+    fn check_serialized<T: JsonSchema + Serialize>(value: &T) {
+        let doc = document();
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        let schema = Components::default().schema_of::<T>();
+        let value = serde_json::to_value(value).unwrap();
+        if let Err(e) = check(schemas, &schema, &value, "$") {
+            panic!("{} doesn't match the schema, err:{e}", T::schema_name());
+        }
+    }
+
+    #[test]
+    fn test_document() {
+        let doc = document();
+        let paths = doc["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/api/v1/sql"));
+        let read_only = &paths["/api/v1/admin/read_only"];
+        assert!(read_only.get("get").is_some());
+        assert!(read_only.get("post").is_some());
+        for path in [
+            "/api/v1/influxdb/v1/write",
+            "/api/v1/influxdb/api/v2/write",
+            "/api/v1/opentsdb/api/put",
+            "/api/v1/schema_registry/write",
+            "/api/v1/write/json",
+            "/api/v1/prom/v1/write",
+        ] {
+            assert!(paths[path].get("post").is_some(), "missing path:{path}");
+        }
+        let json_write = &paths["/api/v1/write/json"]["post"];
+        let params = json_write["parameters"].as_array().unwrap();
+        assert!(params
+            .iter()
+            .any(|param| param["name"] == "table" && param["required"] == true));
+
+        // All the references are resolved by the components.
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        let mut refs = Vec::new();
+        collect_refs(&doc, &mut refs);
+        assert!(!refs.is_empty());
+        for r in refs {
+            let name = r.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas.contains_key(name), "missing schema:{name}");
+        }
+
+        // The fields with defaults are optional.
+        let request = &schemas["ImportRequest"];
+        let required = request["required"].as_array().unwrap();
+        assert!(required.contains(&json!("table")));
+        assert!(!required.contains(&json!("rows_per_sst")));
+        let mode = &schemas["MaintenanceMode"];
+        assert!(mode.get("required").is_none());
+    }
+
+    #[test]
+    fn test_serialized_responses() {
+        let rows = ResponseRows {
+            column_names: vec![ResponseColumn {
+                name: "value".to_string(),
+                data_type: DatumKind::Double,
+                display_name: "Value".to_string(),
+                unit: "ms".to_string(),
+            }],
+            data: vec![vec![Datum::Double(1.0)], vec![Datum::Null]],
+        };
+        let warning = Warning::new(WarningKind::PartialResult, "skipped".to_string());
+        check_serialized(&ResponseWithWarnings::new(
+            Response::Rows(rows),
+            vec![warning],
+        ));
+        check_serialized(&ResponseWithWarnings::new(
+            Response::AffectedRows(1),
+            vec![],
+        ));
+
+        check_serialized(&AdmissionConfig::default());
+        check_serialized(&MaintainedTable {
+            schema: "public".to_string(),
+            table: "cpu".to_string(),
+            mode: MaintenanceMode {
+                write_mode: WriteMode::Queue,
+                redirect_endpoint: Some("127.0.0.1:8831".to_string()),
+            },
+        });
+        check_serialized(&MappingSpec {
+            schema: String::new(),
+            table: "events".to_string(),
+            timestamp: "$.ts".to_string(),
+            columns: vec![ColumnSpec {
+                name: "host".to_string(),
+                path: "$.host".to_string(),
+                tag: true,
+                coerce: Some(CoerceType::String),
+                default: Some(json!("unknown")),
+                required: false,
+            }],
+        });
+        check_serialized(&ImportProgress {
+            id: 1,
+            table: "cpu".to_string(),
+            path: "/data/cpu.parquet".to_string(),
+            state: ImportState::Failed {
+                error: "invalid file".to_string(),
+            },
+            num_rows: 10,
+            num_ssts: 1,
+        });
+        check_serialized(&BlockRule::QueryRange(24 * 3600 * 1000));
+        check_serialized(&BlockRule::AnyInsert);
+        check_serialized(&RuleList {
+            regex_rules: vec![RegexRule {
+                schema: "public".to_string(),
+                pattern: TablePattern::new("cpu_.*").unwrap(),
+                shard: 1,
+            }],
+            ..Default::default()
+        });
+
+        let lease = BackupLease {
+            id: 1,
+            remaining_ms: 1000,
+        };
+        check_serialized(&lease);
+        check_serialized(&BackupStatus {
+            leases: vec![lease],
+        });
+        check_serialized(&CompactionStatus { paused: true });
+        check_serialized(&RecompressionStatus {
+            paused: false,
+            pending_ssts: 1,
+            recompressed_ssts: 2,
+            input_bytes: 3,
+            output_bytes: 4,
+        });
+        check_serialized(&ErrorResponse {
+            code: 500,
+            message: "internal error".to_string(),
+        });
+    }
+}
@@ -17,43 +17,84 @@
 
 //! Server
 
-use std::sync::Arc;
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 
+use alloc_tracker::budget::MemoryBudgetRef;
+use analytic_engine::dynamic_config::DynamicConfigRef;
 use catalog::manager::ManagerRef;
 use cluster::ClusterRef;
 use datafusion::execution::{runtime_env::RuntimeConfig, FunctionRegistry};
-use df_operator::registry::FunctionRegistryRef;
-use interpreters::table_manipulator::TableManipulatorRef;
-use logger::{info, warn, RuntimeLevel};
+use df_operator::{
+    registry::FunctionRegistryRef,
+    wasm::{self, WasmUdfManager},
+};
+use interpreters::{
+    alter_system::SystemConfigManagerRef,
+    continuous_query::ContinuousQueryManagerRef,
+    function::WasmUdfManagerRef,
+    offload::{ResultOffloader, ResultOffloaderRef},
+    query_tracker::QueryTracker,
+    resync::ResyncRequests,
+    source::SourceManagerRef,
+    table_manipulator::TableManipulatorRef,
+};
+use logger::{error, info, warn, RuntimeLevel};
 use macros::define_result;
 use notifier::notifier::RequestNotifiers;
+use object_store::{
+    aliyun, config::ObjectStoreOptions, prefix::StoreWithPrefix, s3, LocalFileSystem,
+    ObjectStoreRef,
+};
 use partition_table_engine::PartitionTableEngine;
 use proxy::{
+    admission::AdmissionController,
+    auth::{Authenticator, AuthenticatorRef},
+    continuous_query::{ContinuousQueryManagerImpl, ContinuousQueryManagerImplRef},
+    drain::Drainer,
     hotspot::HotspotRecorder,
     instance::{DynamicConfig, Instance, InstanceRef},
-    limiter::Limiter,
+    json_write::JsonMappingsRef,
+    limiter::{Limiter, LimiterConfig},
+    maintenance::TableMaintenance,
+    replica_check::{ReplicaChecker, ReplicaCheckerRef},
+    schema_cache::SchemaCache,
     schema_config_provider::SchemaConfigProviderRef,
+    schema_events::SchemaEventBus,
+    source::{SourceManagerImpl, SourceManagerImplRef},
+    write_tee::WriteTee,
     Proxy,
 };
-use query_engine::{QueryEngineBuilder, QueryEngineType};
+use query_engine::{remote_udf::RemoteUdfs, QueryEngineBuilder, QueryEngineType};
 use remote_engine_client::RemoteEngineImpl;
 use router::{endpoint::Endpoint, RouterRef};
-use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use table_engine::{
+    access_stats,
     engine::{EngineRuntimes, TableEngineRef},
     remote::RemoteEngineRef,
 };
+use timed_task::TaskHandle;
 use wal::manager::OpenedWals;
 
 use crate::{
-    config::ServerConfig,
+    config::{ResultOffloadConfig, ServerConfig},
+    config_reload::ReloadTrigger,
+    graphite,
+    graphite::error::Error as GraphiteError,
     grpc::{self, RpcServices},
     http::{self, HttpConfig, Service},
     local_tables::{self, LocalTablesRecoverer},
+    memory_watermark, mqtt,
+    mqtt::error::Error as MqttError,
     mysql,
     mysql::error::Error as MysqlError,
     postgresql,
     postgresql::error::Error as PostgresqlError,
+    statsd,
+    statsd::error::Error as StatsdError,
 };
 
 #[derive(Debug, Snafu)]
@@ -112,12 +153,36 @@ pub enum Error {
     #[snafu(display("Failed to build postgresql service, err:{}", source))]
     BuildPostgresqlService { source: PostgresqlError },
 
+    #[snafu(display("Failed to build authenticator, err:{}", source))]
+    BuildAuthenticator { source: proxy::error::Error },
+
+    #[snafu(display("Failed to load wasm udfs, err:{}", source))]
+    LoadWasmUdfs { source: wasm::Error },
+
+    #[snafu(display("Failed to build mqtt service, err:{}", source))]
+    BuildMqttService { source: MqttError },
+
+    #[snafu(display("Failed to build statsd service, err:{}", source))]
+    BuildStatsdService { source: StatsdError },
+
+    #[snafu(display("Failed to build graphite service, err:{}", source))]
+    BuildGraphiteService { source: GraphiteError },
+
     #[snafu(display("Failed to start mysql service, err:{}", source))]
     StartMysqlService { source: MysqlError },
 
     #[snafu(display("Failed to start postgresql service, err:{}", source))]
     StartPostgresqlService { source: PostgresqlError },
 
+    #[snafu(display("Failed to start mqtt service, err:{}", source))]
+    StartMqttService { source: MqttError },
+
+    #[snafu(display("Failed to start statsd service, err:{}", source))]
+    StartStatsdService { source: StatsdError },
+
+    #[snafu(display("Failed to start graphite service, err:{}", source))]
+    StartGraphiteService { source: GraphiteError },
+
     #[snafu(display("Failed to register system catalog, err:{}", source))]
     RegisterSystemCatalog { source: catalog::manager::Error },
 
@@ -135,32 +200,183 @@ pub enum Error {
 
     #[snafu(display("Failed to build query engine, err:{source}"))]
     BuildQueryEngine { source: query_engine::error::Error },
+
+    #[snafu(display("Failed to create dir of result store, path:{}, err:{}", path, source))]
+    CreateResultStoreDir {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Failed to open result store, err:{}", source))]
+    OpenResultStore {
+        source: object_store::ObjectStoreError,
+    },
+
+    #[snafu(display("Unsupported result store, kind:{}.\nBacktrace:\n{}", kind, backtrace))]
+    UnsupportedResultStore { kind: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "gRPC service can't be disabled in cluster mode.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    GrpcRequiredInCluster { backtrace: Backtrace },
 }
 
 define_result!(Error);
 
 // TODO(yingwen): Consider a config manager
 /// Server
+///
+/// Every protocol service is optional, and the disabled ones are `None`.
 pub struct Server {
-    http_service: Service,
-    rpc_services: RpcServices,
-    mysql_service: mysql::MysqlService,
-    postgresql_service: postgresql::PostgresqlService,
+    http_service: Option<Service>,
+    rpc_services: Option<RpcServices>,
+    mysql_service: Option<mysql::MysqlService>,
+    postgresql_service: Option<postgresql::PostgresqlService>,
+    mqtt_service: Option<mqtt::MqttService>,
+    statsd_service: Option<statsd::StatsdService>,
+    graphite_service: Option<graphite::GraphiteService>,
     instance: InstanceRef,
     cluster: Option<ClusterRef>,
     local_tables_recoverer: Option<LocalTablesRecoverer>,
+    memory_watermark_task: Option<TaskHandle>,
+    source_manager: Option<SourceManagerImplRef>,
+    continuous_query_manager: Option<ContinuousQueryManagerImplRef>,
+    replica_checker: Option<ReplicaCheckerRef>,
+    authenticator: Option<AuthenticatorRef>,
+    json_mappings: JsonMappingsRef,
+    shutdown_timeout: Duration,
+    engine_dynamic_config: Option<DynamicConfigRef>,
 }
 
 impl Server {
     pub async fn stop(mut self) {
-        self.rpc_services.shutdown().await;
-        self.http_service.stop();
-        self.mysql_service.shutdown();
-        self.postgresql_service.shutdown();
+        // Report the server as not ready first, so that the load balancers stop
+        // routing new requests to it during the draining.
+        self.instance.drainer.start_drain();
+
+        // Stop the ingestion sources before the services as they write through the
+        // proxy.
+        if let Some(source_manager) = &self.source_manager {
+            source_manager.stop().await;
+        }
+        if let Some(continuous_query_manager) = &self.continuous_query_manager {
+            continuous_query_manager.stop().await;
+        }
+        if let Some(replica_checker) = &self.replica_checker {
+            replica_checker.stop().await;
+        }
+        if let Some(authenticator) = &self.authenticator {
+            authenticator.stop().await;
+        }
+        self.json_mappings.stop().await;
+
+        // Stop the client facing services first, and the gRPC service is stopped
+        // at last because the other nodes may still send requests to it.
+        if let Some(mysql_service) = self.mysql_service.take() {
+            mysql_service.shutdown();
+        }
+        if let Some(postgresql_service) = self.postgresql_service.take() {
+            postgresql_service.shutdown();
+        }
+        if let Some(mqtt_service) = self.mqtt_service.take() {
+            mqtt_service.shutdown();
+        }
+        if let Some(statsd_service) = self.statsd_service.take() {
+            statsd_service.shutdown();
+        }
+        if let Some(graphite_service) = self.graphite_service.take() {
+            graphite_service.shutdown();
+        }
+
+        // The http and gRPC services are kept serving during the draining, as the
+        // readiness is probed through http and the in-flight requests are
+        // responded through them.
+        info!(
+            "Server stop, drain in-flight requests, inflight:{}, timeout:{:?}",
+            self.instance.drainer.inflight(),
+            self.shutdown_timeout
+        );
+        if !self.instance.drainer.wait_idle(self.shutdown_timeout).await {
+            warn!(
+                "Server stop, in-flight requests are not drained before timeout, inflight:{}",
+                self.instance.drainer.inflight()
+            );
+        }
+
+        if let Some(http_service) = self.http_service.take() {
+            http_service.stop();
+        }
+        if let Some(rpc_services) = &mut self.rpc_services {
+            rpc_services.shutdown().await;
+        }
 
         if let Some(cluster) = &self.cluster {
             cluster.stop().await.expect("fail to stop cluster");
         }
+
+        if let Some(task) = self.memory_watermark_task.take() {
+            if let Err(e) = task.stop_task().await {
+                error!("Failed to stop memory watermark task, err:{e}");
+            }
+        }
+
+        // Close the table engine at last to flush the tables and stop the background
+        // jobs, after all the requests are stopped.
+        if let Err(e) = self.instance.table_engine.close().await {
+            error!("Failed to close table engine, err:{e}");
+        }
+    }
+
+    /// Apply the options which can be changed without restarting, the
+    /// block lists, the rules and the quotas changed by the admin api are
+    /// replaced.
+    pub fn apply_dynamic_config(
+        &self,
+        server_config: &ServerConfig,
+        limiter_config: &LimiterConfig,
+        analytic_config: &analytic_engine::Config,
+    ) {
+        let limiter = &self.instance.limiter;
+        limiter.set_write_block_list(limiter_config.write_block_list.clone());
+        limiter.set_read_block_list(limiter_config.read_block_list.clone());
+        limiter.set_block_rules(limiter_config.rules.clone());
+        self.instance
+            .admission
+            .set_config(server_config.admission.clone());
+
+        *self
+            .instance
+            .dyn_config
+            .fronted
+            .wildcard_limit
+            .write()
+            .unwrap() = server_config.wildcard_limit.clone();
+        *self
+            .instance
+            .dyn_config
+            .fronted
+            .time_range_guard
+            .write()
+            .unwrap() = server_config.time_range_guard.clone();
+        *self
+            .instance
+            .dyn_config
+            .fronted
+            .masking_policies
+            .write()
+            .unwrap() = server_config.masking_policies.clone();
+        *self
+            .instance
+            .dyn_config
+            .fronted
+            .row_policies
+            .write()
+            .unwrap() = server_config.row_policies.clone();
+
+        if let Some(dynamic_config) = &self.engine_dynamic_config {
+            dynamic_config.update(analytic_config);
+        }
     }
 
     pub async fn start(&mut self) -> Result<()> {
@@ -183,23 +399,74 @@ impl Server {
         info!("Server start, create default schema if not exist");
         self.create_default_schema_if_not_exists().await;
 
+        if let Some(authenticator) = &self.authenticator {
+            info!("Server start, load users");
+            if let Err(e) = authenticator.open().await {
+                error!("Failed to load users, err:{e}");
+            }
+        }
+
+        info!("Server start, load json mappings");
+        if let Err(e) = self.json_mappings.open().await {
+            error!("Failed to load json mappings, err:{e}");
+        }
+
+        if let Some(source_manager) = &self.source_manager {
+            info!("Server start, open ingestion sources");
+            if let Err(e) = source_manager.open().await {
+                error!("Failed to open ingestion sources, err:{e}");
+            }
+        }
+
+        if let Some(continuous_query_manager) = &self.continuous_query_manager {
+            info!("Server start, open continuous queries");
+            if let Err(e) = continuous_query_manager.open().await {
+                error!("Failed to open continuous queries, err:{e}");
+            }
+        }
+
+        if let Some(replica_checker) = &self.replica_checker {
+            info!("Server start, start checking replicas");
+            replica_checker.start();
+        }
+
         info!("Server start, start services");
 
-        self.http_service.start().await.context(HttpService {
-            msg: "start failed",
-        })?;
+        if let Some(http_service) = &mut self.http_service {
+            http_service.start().await.context(HttpService {
+                msg: "start failed",
+            })?;
+        }
+
+        if let Some(mysql_service) = &mut self.mysql_service {
+            mysql_service.start().await.context(StartMysqlService)?;
+        }
+
+        if let Some(postgresql_service) = &mut self.postgresql_service {
+            postgresql_service
+                .start()
+                .await
+                .context(StartPostgresqlService)?;
+        }
 
-        self.mysql_service
-            .start()
-            .await
-            .context(StartMysqlService)?;
+        if let Some(mqtt_service) = &mut self.mqtt_service {
+            mqtt_service.start().await.context(StartMqttService)?;
+        }
 
-        self.postgresql_service
-            .start()
-            .await
-            .context(StartPostgresqlService)?;
+        if let Some(statsd_service) = &mut self.statsd_service {
+            statsd_service.start().await.context(StartStatsdService)?;
+        }
+
+        if let Some(graphite_service) = &mut self.graphite_service {
+            graphite_service
+                .start()
+                .await
+                .context(StartGraphiteService)?;
+        }
 
-        self.rpc_services.start().await.context(StartGrpcService)?;
+        if let Some(rpc_services) = &mut self.rpc_services {
+            rpc_services.start().await.context(StartGrpcService)?;
+        }
 
         info!("Server start finished");
 
@@ -247,6 +514,9 @@ pub struct Builder {
     opened_wals: Option<OpenedWals>,
     remote_engine: Option<RemoteEngineRef>,
     datatfusion_context: Option<DatafusionContext>,
+    config_reload: Option<ReloadTrigger>,
+    engine_dynamic_config: Option<DynamicConfigRef>,
+    memory_budget: Option<MemoryBudgetRef>,
 }
 
 impl Builder {
@@ -270,6 +540,9 @@ impl Builder {
             opened_wals: None,
             remote_engine: None,
             datatfusion_context: None,
+            config_reload: None,
+            engine_dynamic_config: None,
+            memory_budget: None,
         }
     }
 
@@ -359,6 +632,22 @@ impl Builder {
         self
     }
 
+    pub fn config_reload(mut self, config_reload: ReloadTrigger) -> Self {
+        self.config_reload = Some(config_reload);
+        self
+    }
+
+    pub fn engine_dynamic_config(mut self, dynamic_config: DynamicConfigRef) -> Self {
+        self.engine_dynamic_config = Some(dynamic_config);
+        self
+    }
+
+    /// Set the memory budget shared by the queries and the table engine.
+    pub fn memory_budget(mut self, memory_budget: MemoryBudgetRef) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
     pub fn datafusion_context(mut self, datafusion_context: DatafusionContext) -> Self {
         self.datatfusion_context = Some(datafusion_context);
         self
@@ -382,15 +671,31 @@ impl Builder {
         let query_engine_config = self.query_engine_config.context(MissingQueryEngineConfig)?;
         let datafusion_context = self.datatfusion_context.context(MissingDatafusionContext)?;
         let expensive_query_threshold = query_engine_config.expensive_query_threshold.as_millis();
+        let wasm_udf_manager = query_engine_config
+            .udf_dir
+            .as_ref()
+            .map(|dir| load_wasm_udfs(dir, function_registry.clone()))
+            .transpose()?;
 
         let hotspot_recorder = Arc::new(HotspotRecorder::new(
             self.server_config.hotspot,
             engine_runtimes.default_runtime.clone(),
+            engine_runtimes.task_registry.clone(),
         ));
 
+        // The nodes connect to each other by the internal token and the TLS of
+        // the grpc service.
+        let grpc_tls = &self.server_config.auth.grpc_tls;
+        let node_tls = grpc::client_tls_config(grpc_tls).context(BuildGrpcService)?;
+        let mut forward_config = self.server_config.forward.clone();
+        forward_config.tls = node_tls.clone();
+        let mut remote_client_config = self.server_config.remote_client.clone();
+        remote_client_config.internal_token = forward_config.internal_token.clone();
+        remote_client_config.tls = node_tls;
+
         // Build remote engine.
         let remote_engine_ref = Arc::new(RemoteEngineImpl::new(
-            self.server_config.remote_client.clone(),
+            remote_client_config,
             router.clone(),
             engine_runtimes.io_runtime.clone(),
         ));
@@ -404,14 +709,85 @@ impl Builder {
             .config(query_engine_config)
             .catalog_manager(catalog_manager.clone())
             .df_function_registry(datafusion_context.function_registry)
+            .function_registry(function_registry.clone())
             .df_runtime_config(datafusion_context.runtime_config)
+            .remote_udfs(datafusion_context.remote_udfs)
             .remote_engine(remote_engine_ref.clone());
+        let query_engine_builder = match self.memory_budget {
+            Some(memory_budget) => query_engine_builder.memory_budget(memory_budget),
+            None => query_engine_builder,
+        };
         let query_engine = query_engine_builder
             .build(QueryEngineType::Datafusion)
             .context(BuildQueryEngine)?;
 
+        let result_offloader = self
+            .server_config
+            .result_offload
+            .as_ref()
+            .map(open_result_offloader)
+            .transpose()?;
+
+        let source_manager = self.server_config.source.enable.then(|| {
+            Arc::new(SourceManagerImpl::new(
+                self.server_config.source.clone(),
+                engine_runtimes.default_runtime.clone(),
+                engine_runtimes.task_registry.clone(),
+            ))
+        });
+        let continuous_query_manager = self.server_config.continuous_query.enable.then(|| {
+            Arc::new(ContinuousQueryManagerImpl::new(
+                self.server_config.continuous_query.clone(),
+                engine_runtimes.default_runtime.clone(),
+                engine_runtimes.task_registry.clone(),
+            ))
+        });
+        let replica_checker = self.server_config.replica_check.enable.then(|| {
+            Arc::new(ReplicaChecker::new(
+                self.server_config.replica_check.clone(),
+                engine_runtimes.default_runtime.clone(),
+                engine_runtimes.task_registry.clone(),
+            ))
+        });
+        let replica_endpoints = self.server_config.replica_check.replicas.iter();
+        let schema_diff_targets = self
+            .server_config
+            .schema_diff_targets
+            .iter()
+            .chain(replica_endpoints.map(|v| &v.endpoint))
+            .cloned()
+            .collect();
+        let write_tee = self.server_config.write_tee.enable.then(|| {
+            Arc::new(WriteTee::new(
+                &self.server_config.write_tee,
+                engine_runtimes.io_runtime.clone(),
+                engine_runtimes.task_registry.clone(),
+            ))
+        });
+        let authenticator = if self.server_config.auth.enable {
+            let authenticator = Authenticator::new(
+                self.server_config.auth.clone(),
+                engine_runtimes.default_runtime.clone(),
+                engine_runtimes.task_registry.clone(),
+            )
+            .context(BuildAuthenticator)?;
+            Some(Arc::new(authenticator))
+        } else {
+            None
+        };
+
         // TODO: build dynamic config from server config.
+        access_stats::set_unused_threshold(self.server_config.unused_table_threshold.map(|v| v.0));
+
         let proxy_dyn_config = DynamicConfig::default();
+        *proxy_dyn_config.fronted.wildcard_limit.write().unwrap() =
+            self.server_config.wildcard_limit.clone();
+        *proxy_dyn_config.fronted.time_range_guard.write().unwrap() =
+            self.server_config.time_range_guard.clone();
+        *proxy_dyn_config.fronted.masking_policies.write().unwrap() =
+            self.server_config.masking_policies.clone();
+        *proxy_dyn_config.fronted.row_policies.write().unwrap() =
+            self.server_config.row_policies.clone();
         let instance = {
             let instance = Instance {
                 catalog_manager,
@@ -420,13 +796,39 @@ impl Builder {
                 table_engine,
                 partition_table_engine,
                 function_registry,
+                wasm_udf_manager,
                 limiter: self.limiter,
+                admission: AdmissionController::new(self.server_config.admission.clone()),
                 table_manipulator,
                 remote_engine_ref,
                 dyn_config: proxy_dyn_config,
+                result_offloader,
+                query_tracker: Arc::new(QueryTracker::default()),
+                resync_requests: Arc::new(ResyncRequests::default()),
+                source_manager: source_manager.clone().map(|v| v as SourceManagerRef),
+                system_config_manager: self
+                    .config_reload
+                    .clone()
+                    .map(|v| Arc::new(v) as SystemConfigManagerRef),
+                continuous_query_manager: continuous_query_manager
+                    .clone()
+                    .map(|v| v as ContinuousQueryManagerRef),
+                replica_checker: replica_checker.clone(),
+                schema_diff_targets,
+                write_tee: write_tee.clone(),
+                authenticator: authenticator.clone(),
+                maintenance: TableMaintenance::new(&self.server_config.maintenance),
+                schema_events: SchemaEventBus::default(),
+                schema_cache: SchemaCache::new(self.server_config.schema_cache.clone()),
+                read_only: AtomicBool::new(
+                    self.server_config.read_only || self.server_config.query_only,
+                ),
+                query_only: self.server_config.query_only,
+                drainer: Drainer::default(),
             };
             InstanceRef::new(instance)
         };
+        let shutdown_timeout = self.server_config.shutdown_timeout.0;
 
         let grpc_endpoint = Endpoint {
             addr: self.server_config.bind_addr.clone(),
@@ -443,6 +845,9 @@ impl Builder {
             endpoint: http_endpoint,
             max_body_size: self.server_config.http_max_body_size.as_byte(),
             timeout: self.server_config.timeout.map(|v| v.0),
+            influxdb: self.server_config.influxdb.clone(),
+            console: self.server_config.console.clone(),
+            tls: self.server_config.auth.tls.clone(),
         };
 
         let request_notifiers = self
@@ -454,7 +859,7 @@ impl Builder {
         let proxy = Arc::new(Proxy::new(
             router.clone(),
             instance.clone(),
-            self.server_config.forward,
+            forward_config,
             Endpoint::new(self.node_addr, self.server_config.grpc_port),
             self.server_config.resp_compress_min_length.as_byte() as usize,
             self.server_config.auto_create_table,
@@ -465,61 +870,192 @@ impl Builder {
             self.server_config.sub_table_access_perm,
             request_notifiers,
             expensive_query_threshold,
+            self.server_config.write_batch.clone(),
+            self.server_config.schema_registry.clone(),
+            &self.server_config.graphite,
+            &self.server_config.write_trace,
+            &self.server_config.write_circuit_breaker,
+            &self.server_config.tiering,
+            &self.server_config.ingest_sampling,
+            &self.server_config.write_queue,
+            &self.server_config.stream_query,
+            &self.server_config.result_limit,
+            &self.server_config.json_write,
+            &self.server_config.user_metrics,
         ));
+        if let Some(source_manager) = &source_manager {
+            source_manager.set_proxy(Arc::downgrade(&proxy));
+        }
+        if let Some(continuous_query_manager) = &continuous_query_manager {
+            continuous_query_manager.set_proxy(Arc::downgrade(&proxy));
+        }
+        if let Some(replica_checker) = &replica_checker {
+            replica_checker.set_proxy(Arc::downgrade(&proxy));
+        }
+        if let Some(write_tee) = &write_tee {
+            write_tee.set_proxy(Arc::downgrade(&proxy));
+        }
+        if let Some(authenticator) = &authenticator {
+            authenticator.set_proxy(Arc::downgrade(&proxy));
+        }
+        let json_mappings = proxy.json_mappings();
+        json_mappings.set_proxy(Arc::downgrade(&proxy));
+
+        // The protocols without the credentials can't be authenticated.
+        let auth_enabled = authenticator.is_some();
+
+        let http_service = if self.server_config.enable_http {
+            let service = http::Builder::new(http_config)
+                .engine_runtimes(engine_runtimes.clone())
+                .log_runtime(log_runtime)
+                .config_content(config_content)
+                .cluster(self.cluster.clone())
+                .proxy(proxy.clone())
+                .opened_wals(opened_wals.clone())
+                .config_reload(self.config_reload.clone())
+                .engine_dynamic_config(self.engine_dynamic_config.clone())
+                .build()
+                .context(HttpService {
+                    msg: "build failed",
+                })?;
+            Some(service)
+        } else {
+            info!("Http service is disabled");
+            None
+        };
 
-        let http_service = http::Builder::new(http_config)
-            .engine_runtimes(engine_runtimes.clone())
-            .log_runtime(log_runtime)
-            .config_content(config_content)
-            .cluster(self.cluster.clone())
-            .proxy(proxy.clone())
-            .opened_wals(opened_wals.clone())
-            .build()
-            .context(HttpService {
-                msg: "build failed",
-            })?;
+        let mysql_service = if self.server_config.enable_mysql && auth_enabled {
+            warn!("Mysql service is disabled as the authentication is enabled");
+            None
+        } else if self.server_config.enable_mysql {
+            let mysql_config = mysql::MysqlConfig {
+                ip: self.server_config.bind_addr.clone(),
+                port: self.server_config.mysql_port,
+                timeout: self.server_config.timeout.map(|v| v.0),
+            };
+            let service = mysql::Builder::new(mysql_config)
+                .runtimes(engine_runtimes.clone())
+                .proxy(proxy.clone())
+                .build()
+                .context(BuildMysqlService)?;
+            Some(service)
+        } else {
+            info!("Mysql service is disabled");
+            None
+        };
 
-        let mysql_config = mysql::MysqlConfig {
-            ip: self.server_config.bind_addr.clone(),
-            port: self.server_config.mysql_port,
-            timeout: self.server_config.timeout.map(|v| v.0),
+        let postgresql_service = if self.server_config.enable_postgresql && auth_enabled {
+            warn!("Postgresql service is disabled as the authentication is enabled");
+            None
+        } else if self.server_config.enable_postgresql {
+            let service = postgresql::Builder::new()
+                .ip(self.server_config.bind_addr.clone())
+                .port(self.server_config.postgresql_port)
+                .proxy(proxy.clone())
+                .runtimes(engine_runtimes.clone())
+                .build()
+                .context(BuildPostgresqlService)?;
+            Some(service)
+        } else {
+            info!("Postgresql service is disabled");
+            None
+        };
+
+        let mqtt_service = if self.server_config.mqtt.enable {
+            let service = mqtt::Builder::new(self.server_config.mqtt.clone())
+                .ip(self.server_config.bind_addr.clone())
+                .proxy(proxy.clone())
+                .runtimes(engine_runtimes.clone())
+                .timeout(self.server_config.timeout.map(|v| v.0))
+                .build()
+                .context(BuildMqttService)?;
+            Some(service)
+        } else {
+            info!("Mqtt service is disabled");
+            None
         };
 
-        let mysql_service = mysql::Builder::new(mysql_config)
-            .runtimes(engine_runtimes.clone())
-            .proxy(proxy.clone())
-            .build()
-            .context(BuildMysqlService)?;
-
-        let postgresql_service = postgresql::Builder::new()
-            .ip(self.server_config.bind_addr.clone())
-            .port(self.server_config.postgresql_port)
-            .proxy(proxy.clone())
-            .runtimes(engine_runtimes.clone())
-            .build()
-            .context(BuildPostgresqlService)?;
-
-        let rpc_services = grpc::Builder::new()
-            .endpoint(grpc_endpoint.to_string())
-            .runtimes(engine_runtimes)
-            .instance(instance.clone())
-            .cluster(self.cluster.clone())
-            .opened_wals(opened_wals)
-            .timeout(self.server_config.timeout.map(|v| v.0))
-            .proxy(proxy)
-            .hotspot_recorder(hotspot_recorder)
-            .query_dedup(self.server_config.query_dedup)
-            .build()
-            .context(BuildGrpcService)?;
+        let statsd_service = if self.server_config.statsd.enable && auth_enabled {
+            warn!("StatsD service is disabled as the authentication is enabled");
+            None
+        } else if self.server_config.statsd.enable {
+            let service = statsd::Builder::new(self.server_config.statsd.clone())
+                .ip(self.server_config.bind_addr.clone())
+                .proxy(proxy.clone())
+                .runtimes(engine_runtimes.clone())
+                .timeout(self.server_config.timeout.map(|v| v.0))
+                .build()
+                .context(BuildStatsdService)?;
+            Some(service)
+        } else {
+            info!("StatsD service is disabled");
+            None
+        };
+
+        let graphite_service = if self.server_config.graphite.enable && auth_enabled {
+            warn!("Graphite service is disabled as the authentication is enabled");
+            None
+        } else if self.server_config.graphite.enable {
+            let service = graphite::Builder::new(self.server_config.graphite.clone())
+                .ip(self.server_config.bind_addr.clone())
+                .proxy(proxy.clone())
+                .runtimes(engine_runtimes.clone())
+                .timeout(self.server_config.timeout.map(|v| v.0))
+                .build()
+                .context(BuildGraphiteService)?;
+            Some(service)
+        } else {
+            info!("Graphite service is disabled");
+            None
+        };
+
+        let memory_watermark_task = memory_watermark::start_watermark_task(
+            self.server_config.memory_watermark.clone(),
+            instance.clone(),
+            &engine_runtimes.default_runtime,
+        );
+
+        let rpc_services = if self.server_config.enable_grpc {
+            let services = grpc::Builder::new()
+                .endpoint(grpc_endpoint.to_string())
+                .runtimes(engine_runtimes)
+                .instance(instance.clone())
+                .cluster(self.cluster.clone())
+                .opened_wals(opened_wals)
+                .timeout(self.server_config.timeout.map(|v| v.0))
+                .proxy(proxy)
+                .hotspot_recorder(hotspot_recorder)
+                .query_dedup(self.server_config.query_dedup)
+                .protocol(self.server_config.remote_client.protocol.clone())
+                .tls(grpc::server_tls_config(grpc_tls).context(BuildGrpcService)?)
+                .build()
+                .context(BuildGrpcService)?;
+            Some(services)
+        } else {
+            ensure!(self.cluster.is_none(), GrpcRequiredInCluster);
+            info!("Grpc service is disabled");
+            None
+        };
 
         let server = Server {
             http_service,
             rpc_services,
             mysql_service,
             postgresql_service,
+            mqtt_service,
+            statsd_service,
+            graphite_service,
             instance,
             cluster: self.cluster,
             local_tables_recoverer: self.local_tables_recoverer,
+            memory_watermark_task,
+            source_manager,
+            continuous_query_manager,
+            replica_checker,
+            authenticator,
+            json_mappings,
+            shutdown_timeout,
+            engine_dynamic_config: self.engine_dynamic_config,
         };
         Ok(server)
     }
@@ -528,4 +1064,46 @@ impl Builder {
 pub struct DatafusionContext {
     pub function_registry: Arc<dyn FunctionRegistry + Send + Sync>,
     pub runtime_config: RuntimeConfig,
+    /// The remote udfs whose stubs are registered into the function registry.
+    pub remote_udfs: Arc<RemoteUdfs>,
+}
+
+fn load_wasm_udfs(dir: &str, registry: FunctionRegistryRef) -> Result<WasmUdfManagerRef> {
+    let manager = WasmUdfManager::try_new(dir, registry).context(LoadWasmUdfs)?;
+    let loaded = manager.load_all().context(LoadWasmUdfs)?;
+    info!("Wasm udfs are loaded, dir:{dir}, functions:{loaded:?}");
+
+    Ok(Arc::new(manager))
+}
+
+fn open_result_offloader(config: &ResultOffloadConfig) -> Result<ResultOffloaderRef> {
+    let store: ObjectStoreRef = match &config.object_store {
+        ObjectStoreOptions::Local(local_opts) => {
+            std::fs::create_dir_all(&local_opts.data_dir).context(CreateResultStoreDir {
+                path: local_opts.data_dir.clone(),
+            })?;
+            Arc::new(
+                LocalFileSystem::new_with_prefix(&local_opts.data_dir).context(OpenResultStore)?,
+            )
+        }
+        ObjectStoreOptions::Aliyun(aliyun_opts) => {
+            let oss: ObjectStoreRef =
+                Arc::new(aliyun::try_new(aliyun_opts).context(OpenResultStore)?);
+            Arc::new(
+                StoreWithPrefix::new(aliyun_opts.prefix.clone(), oss).context(OpenResultStore)?,
+            )
+        }
+        ObjectStoreOptions::S3(s3_opts) => {
+            let oss: ObjectStoreRef = Arc::new(s3::try_new(s3_opts).context(OpenResultStore)?);
+            Arc::new(StoreWithPrefix::new(s3_opts.prefix.clone(), oss).context(OpenResultStore)?)
+        }
+        ObjectStoreOptions::Obkv(_) => {
+            return UnsupportedResultStore { kind: "obkv" }.fail();
+        }
+    };
+
+    Ok(Arc::new(ResultOffloader::new(
+        store,
+        config.max_rows_per_file,
+    )))
 }
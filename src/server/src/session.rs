@@ -23,12 +23,15 @@ use std::{
 use arc_swap::ArcSwap;
 use catalog::consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
 use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
+use table_engine::query_warning::Warning;
 
 /// Session for persistent connection such as MySQL, PostgreSQL etc.
 #[derive(Debug)]
 pub struct Session {
     catalog: ArcSwap<String>,
     schema: ArcSwap<String>,
+    /// Warnings of the last query of the session.
+    warnings: ArcSwap<Vec<Warning>>,
     conn_info: ConnInfo,
 }
 
@@ -39,6 +42,7 @@ impl Session {
         Session {
             catalog: ArcSwap::new(Arc::new(DEFAULT_CATALOG.clone())),
             schema: ArcSwap::new(Arc::new(DEFAULT_SCHEMA.into())),
+            warnings: ArcSwap::new(Arc::new(Vec::new())),
             conn_info: ConnInfo::new(addr, channel),
         }
     }
@@ -68,6 +72,16 @@ impl Session {
     pub fn set_schema(&self, schema: String) {
         self.schema.store(Arc::new(schema));
     }
+
+    #[inline]
+    pub fn warnings(&self) -> Arc<Vec<Warning>> {
+        self.warnings.load_full()
+    }
+
+    #[inline]
+    pub fn set_warnings(&self, warnings: Vec<Warning>) {
+        self.warnings.store(Arc::new(warnings));
+    }
 }
 
 #[derive(Debug)]
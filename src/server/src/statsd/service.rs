@@ -0,0 +1,208 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use logger::{debug, error, info, warn};
+use proxy::{
+    context::RequestContext,
+    statsd::{
+        types::{parse_line, Aggregator},
+        Config,
+    },
+    Proxy,
+};
+use runtime::JoinHandle;
+use table_engine::engine::EngineRuntimes;
+use tokio::{
+    net::UdpSocket,
+    sync::oneshot::{self, Sender},
+    time::MissedTickBehavior,
+};
+
+use crate::statsd::error::Result;
+
+/// Max size of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
+pub struct StatsdService {
+    addr: SocketAddr,
+    config: Arc<Config>,
+    proxy: Arc<Proxy>,
+    runtimes: Arc<EngineRuntimes>,
+    join_handler: Option<JoinHandle<()>>,
+    tx: Option<Sender<()>>,
+    timeout: Option<Duration>,
+}
+
+impl StatsdService {
+    pub fn new(
+        config: Config,
+        proxy: Arc<Proxy>,
+        runtimes: Arc<EngineRuntimes>,
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            addr,
+            config: Arc::new(config),
+            proxy,
+            runtimes,
+            join_handler: None,
+            tx: None,
+            timeout,
+        }
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.tx = Some(tx);
+
+        info!("StatsD server tries to listen on {}", self.addr);
+
+        let receiver = MetricReceiver {
+            config: self.config.clone(),
+            proxy: self.proxy.clone(),
+            runtimes: self.runtimes.clone(),
+            timeout: self.timeout,
+            aggregator: Aggregator::default(),
+            num_dropped: 0,
+        };
+        self.join_handler = Some(
+            self.runtimes
+                .default_runtime
+                .spawn(receiver.loop_recv(self.addr, rx)),
+        );
+
+        Ok(())
+    }
+
+    pub fn shutdown(self) {
+        if let Some(tx) = self.tx {
+            let _ = tx.send(());
+        }
+    }
+}
+
+struct MetricReceiver {
+    config: Arc<Config>,
+    proxy: Arc<Proxy>,
+    runtimes: Arc<EngineRuntimes>,
+    timeout: Option<Duration>,
+    aggregator: Aggregator,
+    /// Number of the metrics dropped in the current flush interval.
+    num_dropped: usize,
+}
+
+impl MetricReceiver {
+    async fn loop_recv(mut self, socket_addr: SocketAddr, mut rx: oneshot::Receiver<()>) {
+        let socket = UdpSocket::bind(socket_addr).await.unwrap_or_else(|e| {
+            panic!("StatsD server listens failed, err:{e}");
+        });
+
+        let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+        let mut ticker = tokio::time::interval(self.config.flush_interval.0);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                recv_result = socket.recv_from(&mut buf) => match recv_result {
+                    Ok((n, _)) => self.handle_datagram(&buf[..n]),
+                    Err(e) => error!("StatsD server receive fail, err:{e}"),
+                },
+                _ = ticker.tick() => self.flush(),
+                _ = &mut rx => {
+                    self.flush();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_datagram(&mut self, datagram: &[u8]) {
+        let datagram = String::from_utf8_lossy(datagram);
+        for line in datagram.lines().filter(|line| !line.trim().is_empty()) {
+            let metric = match parse_line(line) {
+                Ok(v) => v,
+                Err(e) => {
+                    debug!("Drop invalid statsd metric, err:{e}");
+                    continue;
+                }
+            };
+            if self.aggregator.len() >= self.config.max_metrics {
+                self.num_dropped += 1;
+                continue;
+            }
+            self.aggregator.add(metric);
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.num_dropped > 0 {
+            warn!(
+                "StatsD metrics are dropped as too many distinct metrics, dropped:{}, limit:{}",
+                self.num_dropped, self.config.max_metrics
+            );
+            self.num_dropped = 0;
+        }
+        if self.aggregator.is_empty() {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.as_millis() as i64)
+            .unwrap_or_default();
+        let table_requests = match self.aggregator.flush(&self.config.table_prefix, timestamp) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to convert statsd metrics, err:{e}");
+                return;
+            }
+        };
+
+        let catalog_manager = &self.proxy.instance().catalog_manager;
+        let schema = if self.config.schema.is_empty() {
+            catalog_manager.default_schema_name().to_string()
+        } else {
+            self.config.schema.clone()
+        };
+        let ctx = match RequestContext::builder()
+            .catalog(catalog_manager.default_catalog_name().to_string())
+            .schema(schema)
+            .timeout(self.timeout)
+            .build()
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to create statsd request context, err:{e}");
+                return;
+            }
+        };
+
+        // Write in background so the receiving is not blocked by the writes.
+        let proxy = self.proxy.clone();
+        self.runtimes.write_runtime.spawn(async move {
+            if let Err(e) = proxy.handle_statsd_write(ctx, table_requests).await {
+                error!("Failed to write statsd metrics, err:{e}");
+            }
+        });
+    }
+}
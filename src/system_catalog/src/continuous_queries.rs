@@ -0,0 +1,270 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// implementation of system table: ContinuousQueries
+/// For example `SELECT * FROM system.public.continuous_queries`
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Formatter},
+    sync::{OnceLock, RwLock},
+};
+
+use async_trait::async_trait;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    projected_schema::RowProjector,
+    record_batch::FetchedRecordBatchBuilder,
+    row::Row,
+    schema,
+    schema::Schema,
+    time::Timestamp,
+};
+use generic_error::BoxError;
+use snafu::ResultExt;
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{
+    tables::ENTRY_TIMESTAMP, OneRecordBatchStream, SystemTable, CONTINUOUS_QUERIES_TABLE_ID,
+    CONTINUOUS_QUERIES_TABLE_NAME,
+};
+
+/// Status of a running continuous query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuousQueryStatus {
+    pub name: String,
+    pub schema: String,
+    pub source_table: String,
+    pub target_table: String,
+    pub interval_ms: u64,
+    /// End of the last aggregated window, the rows before it are aggregated
+    pub watermark: Option<i64>,
+    /// Time of the last successful run in milliseconds
+    pub last_run_time: Option<i64>,
+    /// Error of the last failed run, `None` if the query is healthy
+    pub last_error: Option<String>,
+}
+
+/// Statuses of the continuous queries, which are reported by the runners.
+#[derive(Debug, Default)]
+pub struct StatusRegistry {
+    statuses: RwLock<BTreeMap<String, ContinuousQueryStatus>>,
+}
+
+impl StatusRegistry {
+    pub fn update(&self, status: ContinuousQueryStatus) {
+        self.statuses
+            .write()
+            .unwrap()
+            .insert(status.name.clone(), status);
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.statuses.write().unwrap().remove(name);
+    }
+
+    /// Statuses ordered by the names.
+    pub fn list(&self) -> Vec<ContinuousQueryStatus> {
+        self.statuses.read().unwrap().values().cloned().collect()
+    }
+}
+
+static STATUS_REGISTRY: OnceLock<StatusRegistry> = OnceLock::new();
+
+/// The global registry of the continuous query statuses.
+pub fn status_registry() -> &'static StatusRegistry {
+    STATUS_REGISTRY.get_or_init(StatusRegistry::default)
+}
+
+/// Build a new table schema for continuous queries
+fn continuous_queries_schema() -> Schema {
+    schema::Builder::with_capacity(9)
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("name".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("schema".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("source_table".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("target_table".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("interval_ms".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("watermark".to_string(), DatumKind::Timestamp)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("last_run_time".to_string(), DatumKind::Timestamp)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("last_error".to_string(), DatumKind::String)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .primary_key_indexes(vec![0, 1])
+        .build()
+        .unwrap()
+}
+
+pub struct ContinuousQueries {
+    schema: Schema,
+}
+
+impl Debug for ContinuousQueries {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysContinuousQueries")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Default for ContinuousQueries {
+    fn default() -> Self {
+        Self {
+            schema: continuous_queries_schema(),
+        }
+    }
+}
+
+impl ContinuousQueries {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_status(&self, status: ContinuousQueryStatus) -> Row {
+        let timestamp_or_null =
+            |v: Option<i64>| v.map_or(Datum::Null, |v| Datum::Timestamp(Timestamp::new(v)));
+
+        let mut datums = Vec::with_capacity(self.schema.num_columns());
+        datums.push(Datum::Timestamp(ENTRY_TIMESTAMP));
+        datums.push(Datum::from(status.name.as_str()));
+        datums.push(Datum::from(status.schema.as_str()));
+        datums.push(Datum::from(status.source_table.as_str()));
+        datums.push(Datum::from(status.target_table.as_str()));
+        datums.push(Datum::from(status.interval_ms));
+        datums.push(timestamp_or_null(status.watermark));
+        datums.push(timestamp_or_null(status.last_run_time));
+        datums.push(
+            status
+                .last_error
+                .as_deref()
+                .map_or(Datum::Null, Datum::from),
+        );
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl SystemTable for ContinuousQueries {
+    fn name(&self) -> &str {
+        CONTINUOUS_QUERIES_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        CONTINUOUS_QUERIES_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let fetched_schema = request.projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(
+            fetched_schema.clone(),
+            Some(primary_key_indexes.clone()),
+        );
+
+        let table_schema = request.projected_schema.table_schema();
+        let row_projector = RowProjector::new(
+            &fetched_schema,
+            Some(primary_key_indexes),
+            table_schema,
+            &self.schema,
+        )
+        .expect("Should succeed to try_project_key of sys_continuous_queries");
+        for status in status_registry().list() {
+            let row = self.from_status(status);
+            let projected_row = row_projector.project_row(&row, Vec::new());
+            builder
+                .append_row(projected_row)
+                .box_err()
+                .context(table_engine::table::Scan { table: self.name() })?;
+        }
+        let record_batch = builder.build().unwrap().into_record_batch();
+        Ok(Box::pin(OneRecordBatchStream {
+            schema: self.schema.clone().to_record_schema(),
+            record_batch: Some(record_batch),
+        }))
+    }
+}
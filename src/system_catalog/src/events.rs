@@ -0,0 +1,182 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// implementation of system table: Events
+/// For example `SELECT * FROM system.public.events`
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    projected_schema::RowProjector,
+    record_batch::FetchedRecordBatchBuilder,
+    row::Row,
+    schema,
+    schema::Schema,
+    time::Timestamp,
+};
+use generic_error::BoxError;
+use snafu::ResultExt;
+use table_engine::{
+    event::{self, Event},
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{OneRecordBatchStream, SystemTable, EVENTS_TABLE_ID, EVENTS_TABLE_NAME};
+
+/// Build a new table schema for events
+fn events_schema() -> Schema {
+    schema::Builder::with_capacity(6)
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("seq".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("kind".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("table_name".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("table_id".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("detail".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .primary_key_indexes(vec![0, 1])
+        .build()
+        .unwrap()
+}
+
+pub struct Events {
+    schema: Schema,
+}
+
+impl Debug for Events {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysEvents")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self {
+            schema: events_schema(),
+        }
+    }
+}
+
+impl Events {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_event(&self, event: Event) -> Row {
+        let mut datums = Vec::with_capacity(self.schema.num_columns());
+        datums.push(Datum::Timestamp(Timestamp::new(event.timestamp)));
+        datums.push(Datum::from(event.seq));
+        datums.push(Datum::from(event.kind.as_str()));
+        datums.push(Datum::from(event.table.as_str()));
+        datums.push(Datum::from(event.table_id));
+        datums.push(Datum::from(event.detail.as_str()));
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl SystemTable for Events {
+    fn name(&self) -> &str {
+        EVENTS_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        EVENTS_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let fetched_schema = request.projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(
+            fetched_schema.clone(),
+            Some(primary_key_indexes.clone()),
+        );
+
+        let table_schema = request.projected_schema.table_schema();
+        let row_projector = RowProjector::new(
+            &fetched_schema,
+            Some(primary_key_indexes),
+            table_schema,
+            &self.schema,
+        )
+        .expect("Should succeed to try_project_key of sys_events");
+        for event in event::event_log().list() {
+            let row = self.from_event(event);
+            let projected_row = row_projector.project_row(&row, Vec::new());
+            builder
+                .append_row(projected_row)
+                .box_err()
+                .context(table_engine::table::Scan { table: self.name() })?;
+        }
+        let record_batch = builder.build().unwrap().into_record_batch();
+        Ok(Box::pin(OneRecordBatchStream {
+            schema: self.schema.clone().to_record_schema(),
+            record_batch: Some(record_batch),
+        }))
+    }
+}
@@ -43,8 +43,14 @@ use table_engine::{
     },
 };
 
+pub mod continuous_queries;
+pub mod events;
+pub mod replica_divergences;
+pub mod scheduler;
 pub mod sys_catalog_table;
 pub mod tables;
+pub mod tasks;
+pub mod users;
 
 /// Schema id of the sys catalog schema (`system/public`).
 pub const SYSTEM_SCHEMA_ID: SchemaId = SchemaId::from_u32(1);
@@ -64,11 +70,56 @@ pub const TABLES_TABLE_SEQ: TableSeq = TableSeq::from_u32(2);
 /// Table id of the `tables` table.
 pub const TABLES_TABLE_ID: TableId = TableId::with_seq(SYSTEM_SCHEMA_ID, TABLES_TABLE_SEQ).unwrap();
 
+/// Table name of the `events` table.
+pub const EVENTS_TABLE_NAME: &str = "events";
+/// Table sequence of the `events` table.
+pub const EVENTS_TABLE_SEQ: TableSeq = TableSeq::from_u32(3);
+/// Table id of the `events` table.
+pub const EVENTS_TABLE_ID: TableId = TableId::with_seq(SYSTEM_SCHEMA_ID, EVENTS_TABLE_SEQ).unwrap();
+
+/// Table name of the `users` table.
+pub const USERS_TABLE_NAME: &str = "users";
+/// Table sequence of the `users` table.
+pub const USERS_TABLE_SEQ: TableSeq = TableSeq::from_u32(4);
+/// Table id of the `users` table.
+pub const USERS_TABLE_ID: TableId = TableId::with_seq(SYSTEM_SCHEMA_ID, USERS_TABLE_SEQ).unwrap();
+
+/// Table name of the `continuous_queries` table.
+pub const CONTINUOUS_QUERIES_TABLE_NAME: &str = "continuous_queries";
+/// Table sequence of the `continuous_queries` table.
+pub const CONTINUOUS_QUERIES_TABLE_SEQ: TableSeq = TableSeq::from_u32(5);
+/// Table id of the `continuous_queries` table.
+pub const CONTINUOUS_QUERIES_TABLE_ID: TableId =
+    TableId::with_seq(SYSTEM_SCHEMA_ID, CONTINUOUS_QUERIES_TABLE_SEQ).unwrap();
+
+/// Table name of the `scheduler` table.
+pub const SCHEDULER_TABLE_NAME: &str = "scheduler";
+/// Table sequence of the `scheduler` table.
+pub const SCHEDULER_TABLE_SEQ: TableSeq = TableSeq::from_u32(6);
+/// Table id of the `scheduler` table.
+pub const SCHEDULER_TABLE_ID: TableId =
+    TableId::with_seq(SYSTEM_SCHEMA_ID, SCHEDULER_TABLE_SEQ).unwrap();
+
+/// Table name of the `tasks` table.
+pub const TASKS_TABLE_NAME: &str = "tasks";
+/// Table sequence of the `tasks` table.
+pub const TASKS_TABLE_SEQ: TableSeq = TableSeq::from_u32(7);
+/// Table id of the `tasks` table.
+pub const TASKS_TABLE_ID: TableId = TableId::with_seq(SYSTEM_SCHEMA_ID, TASKS_TABLE_SEQ).unwrap();
+
+/// Table name of the `replica_divergences` table.
+pub const REPLICA_DIVERGENCES_TABLE_NAME: &str = "replica_divergences";
+/// Table sequence of the `replica_divergences` table.
+pub const REPLICA_DIVERGENCES_TABLE_SEQ: TableSeq = TableSeq::from_u32(8);
+/// Table id of the `replica_divergences` table.
+pub const REPLICA_DIVERGENCES_TABLE_ID: TableId =
+    TableId::with_seq(SYSTEM_SCHEMA_ID, REPLICA_DIVERGENCES_TABLE_SEQ).unwrap();
+
 // NOTE: The MAX_SYSTEM_TABLE_ID should be updated if any new system table is
 // added.
 
 /// Max table id of all the system tables.
-pub const MAX_SYSTEM_TABLE_SEQ: TableSeq = TABLES_TABLE_SEQ;
+pub const MAX_SYSTEM_TABLE_SEQ: TableSeq = REPLICA_DIVERGENCES_TABLE_SEQ;
 
 /// The minimal thing that a system table needs to implement
 #[async_trait]
@@ -0,0 +1,299 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+/// implementation of system table: ReplicaDivergences
+/// For example `SELECT * FROM system.public.replica_divergences`
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Formatter},
+    sync::{OnceLock, RwLock},
+};
+
+use async_trait::async_trait;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    projected_schema::RowProjector,
+    record_batch::FetchedRecordBatchBuilder,
+    row::Row,
+    schema,
+    schema::Schema,
+    time::Timestamp,
+};
+use generic_error::BoxError;
+use snafu::ResultExt;
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{
+    OneRecordBatchStream, SystemTable, REPLICA_DIVERGENCES_TABLE_ID,
+    REPLICA_DIVERGENCES_TABLE_NAME,
+};
+
+/// A time window of a table whose rows differ from the ones of a replica.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaDivergence {
+    pub replica: String,
+    pub schema: String,
+    pub table: String,
+    /// Start of the window in milliseconds, inclusive
+    pub window_start: i64,
+    /// End of the window in milliseconds, exclusive
+    pub window_end: i64,
+    pub local_rows: u64,
+    pub replica_rows: u64,
+    pub local_checksum: i64,
+    pub replica_checksum: i64,
+    /// Time of the check finding the divergence in milliseconds
+    pub detected_at: i64,
+}
+
+/// Divergences found by the last check of every table of every replica.
+#[derive(Debug, Default)]
+pub struct DivergenceRegistry {
+    /// Keyed by the replica, the schema and the table.
+    divergences: RwLock<BTreeMap<(String, String, String), Vec<ReplicaDivergence>>>,
+}
+
+impl DivergenceRegistry {
+    /// Replace the divergences of the table found by the previous check.
+    pub fn update(
+        &self,
+        replica: &str,
+        schema: &str,
+        table: &str,
+        divergences: Vec<ReplicaDivergence>,
+    ) {
+        let key = (replica.to_string(), schema.to_string(), table.to_string());
+        let mut all = self.divergences.write().unwrap();
+        if divergences.is_empty() {
+            all.remove(&key);
+        } else {
+            all.insert(key, divergences);
+        }
+    }
+
+    /// Number of the diverged windows of the replica.
+    pub fn num_diverged(&self, replica: &str) -> usize {
+        self.divergences
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|((v, _, _), _)| v == replica)
+            .map(|(_, divergences)| divergences.len())
+            .sum()
+    }
+
+    /// Divergences ordered by the replicas, the schemas and the tables.
+    pub fn list(&self) -> Vec<ReplicaDivergence> {
+        self.divergences
+            .read()
+            .unwrap()
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+static DIVERGENCE_REGISTRY: OnceLock<DivergenceRegistry> = OnceLock::new();
+
+/// The global registry of the replica divergences, which are reported by the
+/// replica checker.
+pub fn divergence_registry() -> &'static DivergenceRegistry {
+    DIVERGENCE_REGISTRY.get_or_init(DivergenceRegistry::default)
+}
+
+/// Build a new table schema for replica divergences
+fn replica_divergences_schema() -> Schema {
+    schema::Builder::with_capacity(10)
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("replica".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("schema".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("table_name".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("window_start".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("window_end".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("local_rows".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("replica_rows".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("local_checksum".to_string(), DatumKind::Int64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("replica_checksum".to_string(), DatumKind::Int64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .primary_key_indexes(vec![0, 1, 2, 3, 4])
+        .build()
+        .unwrap()
+}
+
+pub struct ReplicaDivergences {
+    schema: Schema,
+}
+
+impl Debug for ReplicaDivergences {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysReplicaDivergences")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Default for ReplicaDivergences {
+    fn default() -> Self {
+        Self {
+            schema: replica_divergences_schema(),
+        }
+    }
+}
+
+impl ReplicaDivergences {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_divergence(&self, divergence: ReplicaDivergence) -> Row {
+        let mut datums = Vec::with_capacity(self.schema.num_columns());
+        datums.push(Datum::Timestamp(Timestamp::new(divergence.detected_at)));
+        datums.push(Datum::from(divergence.replica.as_str()));
+        datums.push(Datum::from(divergence.schema.as_str()));
+        datums.push(Datum::from(divergence.table.as_str()));
+        datums.push(Datum::Timestamp(Timestamp::new(divergence.window_start)));
+        datums.push(Datum::Timestamp(Timestamp::new(divergence.window_end)));
+        datums.push(Datum::from(divergence.local_rows));
+        datums.push(Datum::from(divergence.replica_rows));
+        datums.push(Datum::from(divergence.local_checksum));
+        datums.push(Datum::from(divergence.replica_checksum));
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl SystemTable for ReplicaDivergences {
+    fn name(&self) -> &str {
+        REPLICA_DIVERGENCES_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        REPLICA_DIVERGENCES_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let fetched_schema = request.projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(
+            fetched_schema.clone(),
+            Some(primary_key_indexes.clone()),
+        );
+
+        let table_schema = request.projected_schema.table_schema();
+        let row_projector = RowProjector::new(
+            &fetched_schema,
+            Some(primary_key_indexes),
+            table_schema,
+            &self.schema,
+        )
+        .expect("Should succeed to try_project_key of sys_replica_divergences");
+        for divergence in divergence_registry().list() {
+            let row = self.from_divergence(divergence);
+            let projected_row = row_projector.project_row(&row, Vec::new());
+            builder
+                .append_row(projected_row)
+                .box_err()
+                .context(table_engine::table::Scan { table: self.name() })?;
+        }
+        let record_batch = builder.build().unwrap().into_record_batch();
+        Ok(Box::pin(OneRecordBatchStream {
+            schema: self.schema.clone().to_record_schema(),
+            record_batch: Some(record_batch),
+        }))
+    }
+}
@@ -0,0 +1,400 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// implementation of system table: Scheduler
+/// For example `SELECT * FROM system.public.scheduler`
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Debug, Formatter},
+    sync::{Mutex, OnceLock},
+};
+
+use async_trait::async_trait;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    projected_schema::RowProjector,
+    record_batch::FetchedRecordBatchBuilder,
+    row::Row,
+    schema,
+    schema::Schema,
+    time::Timestamp,
+};
+use generic_error::BoxError;
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use snafu::ResultExt;
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{
+    tables::ENTRY_TIMESTAMP, OneRecordBatchStream, SystemTable, SCHEDULER_TABLE_ID,
+    SCHEDULER_TABLE_NAME,
+};
+
+/// The rejections in this window are counted as the recent ones.
+const RECENT_REJECTION_WINDOW_MS: i64 = 60 * 1000;
+/// Max number of the recent rejections kept for each priority class.
+const MAX_RECENT_REJECTIONS: usize = 1024;
+
+lazy_static! {
+    static ref SCHEDULER_QUEUED_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "scheduler_queued_queries",
+        "Number of the queries waiting to be executed",
+        &["priority"]
+    )
+    .unwrap();
+    static ref SCHEDULER_RUNNING_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "scheduler_running_queries",
+        "Number of the queries being executed",
+        &["priority"]
+    )
+    .unwrap();
+    static ref SCHEDULER_REJECTED_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "scheduler_rejected_queries",
+        "Number of the queries rejected by the admission control",
+        &["priority"]
+    )
+    .unwrap();
+}
+
+/// State of the queries of a priority class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityClassState {
+    pub priority: String,
+    pub queued: u64,
+    pub running: u64,
+    /// Number of the rejected queries since the server starts
+    pub rejected: u64,
+    /// Number of the queries rejected in the last minute
+    pub recent_rejected: u64,
+    /// Time of the last rejection in milliseconds
+    pub last_rejected_time: Option<i64>,
+    pub last_rejected_reason: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct ClassState {
+    queued: u64,
+    running: u64,
+    rejected: u64,
+    /// Time of the recent rejections in milliseconds
+    recent_rejections: VecDeque<i64>,
+    last_rejected_time: Option<i64>,
+    last_rejected_reason: Option<String>,
+}
+
+/// State of the admission-control scheduler of the queries, which is updated
+/// as the queries are admitted, queued, executed and rejected.
+#[derive(Debug, Default)]
+pub struct SchedulerState {
+    classes: Mutex<BTreeMap<String, ClassState>>,
+}
+
+impl SchedulerState {
+    /// Put the query of the `priority` into the queue, and it leaves the queue
+    /// once the returned guard is started or dropped.
+    pub fn enqueue<'a>(&'a self, priority: &str) -> QueuedQuery<'a> {
+        self.update(priority, |class| class.queued += 1);
+        SCHEDULER_QUEUED_GAUGE_VEC
+            .with_label_values(&[priority])
+            .inc();
+
+        QueuedQuery {
+            state: self,
+            priority: priority.to_string(),
+        }
+    }
+
+    pub fn reject(&self, priority: &str, reason: String) {
+        let now = Timestamp::now().as_i64();
+        self.update(priority, |class| {
+            class.rejected += 1;
+            if class.recent_rejections.len() >= MAX_RECENT_REJECTIONS {
+                class.recent_rejections.pop_front();
+            }
+            class.recent_rejections.push_back(now);
+            class.last_rejected_time = Some(now);
+            class.last_rejected_reason = Some(reason);
+        });
+        SCHEDULER_REJECTED_COUNTER_VEC
+            .with_label_values(&[priority])
+            .inc();
+    }
+
+    /// States ordered by the priority classes.
+    pub fn list(&self) -> Vec<PriorityClassState> {
+        let recent_start = Timestamp::now().as_i64() - RECENT_REJECTION_WINDOW_MS;
+        let mut classes = self.classes.lock().unwrap();
+        classes
+            .iter_mut()
+            .map(|(priority, class)| {
+                while let Some(time) = class.recent_rejections.front() {
+                    if *time >= recent_start {
+                        break;
+                    }
+                    class.recent_rejections.pop_front();
+                }
+
+                PriorityClassState {
+                    priority: priority.clone(),
+                    queued: class.queued,
+                    running: class.running,
+                    rejected: class.rejected,
+                    recent_rejected: class.recent_rejections.len() as u64,
+                    last_rejected_time: class.last_rejected_time,
+                    last_rejected_reason: class.last_rejected_reason.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn update(&self, priority: &str, f: impl FnOnce(&mut ClassState)) {
+        let mut classes = self.classes.lock().unwrap();
+        match classes.get_mut(priority) {
+            Some(class) => f(class),
+            None => {
+                let mut class = ClassState::default();
+                f(&mut class);
+                classes.insert(priority.to_string(), class);
+            }
+        }
+    }
+}
+
+/// Guard of a query waiting in the queue.
+pub struct QueuedQuery<'a> {
+    state: &'a SchedulerState,
+    priority: String,
+}
+
+impl<'a> QueuedQuery<'a> {
+    /// Start executing the query, and it's finished once the returned guard is
+    /// dropped.
+    pub fn start(self) -> RunningQuery<'a> {
+        self.state.update(&self.priority, |class| class.running += 1);
+        SCHEDULER_RUNNING_GAUGE_VEC
+            .with_label_values(&[&self.priority])
+            .inc();
+
+        RunningQuery {
+            state: self.state,
+            priority: self.priority.clone(),
+        }
+    }
+}
+
+impl Drop for QueuedQuery<'_> {
+    fn drop(&mut self) {
+        self.state.update(&self.priority, |class| class.queued -= 1);
+        SCHEDULER_QUEUED_GAUGE_VEC
+            .with_label_values(&[&self.priority])
+            .dec();
+    }
+}
+
+/// Guard of a query being executed.
+pub struct RunningQuery<'a> {
+    state: &'a SchedulerState,
+    priority: String,
+}
+
+impl Drop for RunningQuery<'_> {
+    fn drop(&mut self) {
+        self.state.update(&self.priority, |class| class.running -= 1);
+        SCHEDULER_RUNNING_GAUGE_VEC
+            .with_label_values(&[&self.priority])
+            .dec();
+    }
+}
+
+static SCHEDULER_STATE: OnceLock<SchedulerState> = OnceLock::new();
+
+/// The global state of the query scheduler.
+pub fn scheduler_state() -> &'static SchedulerState {
+    SCHEDULER_STATE.get_or_init(SchedulerState::default)
+}
+
+/// Build a new table schema for scheduler
+fn scheduler_schema() -> Schema {
+    schema::Builder::with_capacity(8)
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("priority".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("queued".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("running".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("rejected".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("recent_rejected".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("last_rejected_time".to_string(), DatumKind::Timestamp)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("last_rejected_reason".to_string(), DatumKind::String)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .primary_key_indexes(vec![0, 1])
+        .build()
+        .unwrap()
+}
+
+pub struct Scheduler {
+    schema: Schema,
+}
+
+impl Debug for Scheduler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysScheduler")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            schema: scheduler_schema(),
+        }
+    }
+}
+
+impl Scheduler {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_state(&self, state: PriorityClassState) -> Row {
+        let mut datums = Vec::with_capacity(self.schema.num_columns());
+        datums.push(Datum::Timestamp(ENTRY_TIMESTAMP));
+        datums.push(Datum::from(state.priority.as_str()));
+        datums.push(Datum::from(state.queued));
+        datums.push(Datum::from(state.running));
+        datums.push(Datum::from(state.rejected));
+        datums.push(Datum::from(state.recent_rejected));
+        datums.push(
+            state
+                .last_rejected_time
+                .map_or(Datum::Null, |v| Datum::Timestamp(Timestamp::new(v))),
+        );
+        datums.push(
+            state
+                .last_rejected_reason
+                .as_deref()
+                .map_or(Datum::Null, Datum::from),
+        );
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl SystemTable for Scheduler {
+    fn name(&self) -> &str {
+        SCHEDULER_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        SCHEDULER_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let fetched_schema = request.projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(
+            fetched_schema.clone(),
+            Some(primary_key_indexes.clone()),
+        );
+
+        let table_schema = request.projected_schema.table_schema();
+        let row_projector = RowProjector::new(
+            &fetched_schema,
+            Some(primary_key_indexes),
+            table_schema,
+            &self.schema,
+        )
+        .expect("Should succeed to try_project_key of sys_scheduler");
+        for state in scheduler_state().list() {
+            let row = self.from_state(state);
+            let projected_row = row_projector.project_row(&row, Vec::new());
+            builder
+                .append_row(projected_row)
+                .box_err()
+                .context(table_engine::table::Scan { table: self.name() })?;
+        }
+        let record_batch = builder.build().unwrap().into_record_batch();
+        Ok(Box::pin(OneRecordBatchStream {
+            schema: self.schema.clone().to_record_schema(),
+            record_batch: Some(record_batch),
+        }))
+    }
+}
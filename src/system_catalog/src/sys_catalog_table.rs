@@ -377,6 +377,19 @@ impl SysCatalogTable {
         Ok(())
     }
 
+    /// Update the stored schema info, the stored entry of the schema is
+    /// overwritten.
+    pub async fn update_schema(&self, request: CreateSchemaRequest) -> Result<()> {
+        info!("Update schema in sys_catalog table, request:{:?}", request);
+
+        let row_group = request.into_row_group(self.table.schema())?;
+
+        let write_req = WriteRequest { row_group };
+        self.table.write(write_req).await.context(PersistSchema)?;
+
+        Ok(())
+    }
+
     /// Create table in the catalog.
     pub async fn create_table(&self, table_info: TableInfo) -> Result<()> {
         info!(
@@ -455,6 +468,19 @@ impl SysCatalogTable {
         Ok(())
     }
 
+    /// Bind each of the two tables to the name of the other one.
+    ///
+    /// Both of the table infos are persisted by one write, so the two bindings
+    /// are changed atomically.
+    pub async fn swap_tables(&self, mut table: TableInfo, mut other: TableInfo) -> Result<()> {
+        info!("Swap tables in sys_catalog table, table:{table:?}, other:{other:?}");
+
+        std::mem::swap(&mut table.table_name, &mut other.table_name);
+        let _lock = self.update_table_lock.lock().await;
+        self.write_table_infos(vec![table, other], TableRequestType::Create)
+            .await
+    }
+
     /// Returns the inner table of the sys catalog.
     #[inline]
     pub fn inner_table(&self) -> TableRef {
@@ -463,14 +489,24 @@ impl SysCatalogTable {
 
     /// Write the table info to the sys_catalog table without lock.
     async fn write_table_info(&self, table_info: TableInfo, typ: TableRequestType) -> Result<()> {
+        self.write_table_infos(vec![table_info], typ).await
+    }
+
+    /// Write the table infos to the sys_catalog table in one write without
+    /// lock.
+    async fn write_table_infos(
+        &self,
+        table_infos: Vec<TableInfo>,
+        typ: TableRequestType,
+    ) -> Result<()> {
         info!(
-            "Write table info to sys_catalog table, table_info:{:?}",
-            table_info
+            "Write table infos to sys_catalog table, table_infos:{:?}",
+            table_infos
         );
 
         let serializer = TableWriter {
             catalog_table: self.table.clone(),
-            table_to_write: table_info,
+            tables_to_write: table_infos,
             typ,
         };
 
@@ -925,6 +961,8 @@ pub struct CreateSchemaRequest {
     pub catalog_name: String,
     pub schema_name: String,
     pub schema_id: SchemaId,
+    /// Default options of the tables created in the schema
+    pub default_table_options: HashMap<String, String>,
 }
 
 impl CreateSchemaRequest {
@@ -956,13 +994,29 @@ impl CreateSchemaRequest {
         Ok(buf.into())
     }
 
-    fn into_bytes(self) -> Bytes {
+    fn into_bytes(mut self) -> Bytes {
+        let ext = SchemaEntryExt {
+            default_table_options: mem::take(&mut self.default_table_options),
+        };
         let entry = SchemaEntry::from(self);
 
-        entry.encode_to_vec().into()
+        let mut buf = entry.encode_to_vec();
+        buf.extend(ext.encode_to_vec());
+        buf.into()
     }
 }
 
+/// Extension of the [SchemaEntry].
+///
+/// It's encoded after the pb in the same buffer, and its tags are unused by
+/// the pb, so the decoders of the pb skip it as unknown fields, and its
+/// decoder skips the fields of the pb in turn.
+#[derive(Clone, PartialEq, prost::Message)]
+struct SchemaEntryExt {
+    #[prost(map = "string, string", tag = "1000")]
+    default_table_options: HashMap<String, String>,
+}
+
 impl From<CreateSchemaRequest> for SchemaEntry {
     fn from(v: CreateSchemaRequest) -> Self {
         SchemaEntry {
@@ -982,6 +1036,7 @@ impl From<SchemaEntry> for CreateSchemaRequest {
             catalog_name: entry.catalog_name,
             schema_name: entry.schema_name,
             schema_id,
+            default_table_options: HashMap::new(),
         }
     }
 }
@@ -999,7 +1054,7 @@ pub struct AlterTableRequest {
 /// Writer for writing the table information into the catalog table.
 pub struct TableWriter {
     catalog_table: TableRef,
-    table_to_write: TableInfo,
+    tables_to_write: Vec<TableInfo>,
     typ: TableRequestType,
 }
 
@@ -1015,19 +1070,22 @@ impl TableWriter {
         Ok(())
     }
 
-    /// Convert the table to write into [common_types::row::RowGroup].
+    /// Convert the tables to write into [common_types::row::RowGroup].
     fn convert_table_info_to_row_group(&self) -> Result<RowGroup> {
         let schema = self.catalog_table.schema();
-        let key = Self::build_create_table_key(&self.table_to_write)?;
-        let value = Self::build_create_table_value(self.table_to_write.clone(), self.typ)?;
+        let mut rows = Vec::with_capacity(self.tables_to_write.len());
+        for table_info in &self.tables_to_write {
+            let key = Self::build_create_table_key(table_info)?;
+            let value = Self::build_create_table_value(table_info.clone(), self.typ)?;
 
-        debug!(
-            "TableWriter build key value, key:{:?}, value:{:?}",
-            key, value
-        );
+            debug!(
+                "TableWriter build key value, key:{:?}, value:{:?}",
+                key, value
+            );
 
-        let row = Self::build_table_info_row(&schema, key, value)?;
-        let row_group = RowGroup::new_unchecked(schema, vec![row]);
+            rows.push(Self::build_table_info_row(&schema, key, value)?);
+        }
+        let row_group = RowGroup::new_unchecked(schema, rows);
 
         Ok(row_group)
     }
@@ -1104,7 +1162,10 @@ fn decode_one_request(key: &[u8], value: &[u8]) -> Result<DecodedRequest> {
         }
         KeyType::CreateSchema => {
             let entry = SchemaEntry::decode(value).context(DecodeEntryPb)?;
-            DecodedRequest::CreateSchema(CreateSchemaRequest::from(entry))
+            let ext = SchemaEntryExt::decode(value).context(DecodeEntryPb)?;
+            let mut request = CreateSchemaRequest::from(entry);
+            request.default_table_options = ext.default_table_options;
+            DecodedRequest::CreateSchema(request)
         }
         KeyType::TableEntry => {
             let entry = TableEntry::decode(value).context(DecodeEntryPb)?;
@@ -1115,3 +1176,34 @@ fn decode_one_request(key: &[u8], value: &[u8]) -> Result<DecodedRequest> {
 
     Ok(req)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_entry_with_default_table_options() {
+        let default_table_options = HashMap::from([("ttl".to_string(), "7d".to_string())]);
+        let request = CreateSchemaRequest {
+            catalog_name: "horaedb".to_string(),
+            schema_name: "public".to_string(),
+            schema_id: SchemaId::from(2),
+            default_table_options: default_table_options.clone(),
+        };
+        let key = request.to_key().unwrap();
+        let value = request.into_bytes();
+
+        // The pb skips the extension.
+        let entry = SchemaEntry::decode(&value[..]).unwrap();
+        assert_eq!("public", entry.schema_name);
+
+        match decode_one_request(&key, &value).unwrap() {
+            DecodedRequest::CreateSchema(request) => {
+                assert_eq!("public", request.schema_name);
+                assert_eq!(SchemaId::from(2), request.schema_id);
+                assert_eq!(default_table_options, request.default_table_options);
+            }
+            v => panic!("unexpected request:{v:?}"),
+        }
+    }
+}
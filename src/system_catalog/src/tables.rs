@@ -34,6 +34,7 @@ use common_types::{
 use generic_error::BoxError;
 use snafu::ResultExt;
 use table_engine::{
+    access_stats,
     stream::SendableRecordBatchStream,
     table::{ReadRequest, TableId, TableRef},
 };
@@ -45,7 +46,7 @@ pub const ENTRY_TIMESTAMP: Timestamp = Timestamp::new(0);
 
 /// Build a new table schema for tables
 fn tables_schema() -> Schema {
-    schema::Builder::with_capacity(6)
+    schema::Builder::with_capacity(11)
         .auto_increment_column_id(true)
         .add_key_column(
             column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
@@ -95,6 +96,47 @@ fn tables_schema() -> Schema {
                 .unwrap(),
         )
         .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("last_written".to_string(), DatumKind::Timestamp)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("write_count".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("last_queried".to_string(), DatumKind::Timestamp)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("query_count".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        // Null if the unused threshold isn't configured.
+        .add_normal_column(
+            column_schema::Builder::new("unused".to_string(), DatumKind::Boolean)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
         .primary_key_indexes(vec![0, 1, 2])
         .build()
         .unwrap()
@@ -130,6 +172,13 @@ impl Tables {
         datums.push(Datum::from(table.name()));
         datums.push(Datum::from(table.id().as_u64()));
         datums.push(Datum::from(table.engine_type()));
+
+        let stats = access_stats::get(table.id());
+        datums.push(stats.last_written.map_or(Datum::Null, Datum::Timestamp));
+        datums.push(Datum::from(stats.write_count));
+        datums.push(stats.last_queried.map_or(Datum::Null, Datum::Timestamp));
+        datums.push(Datum::from(stats.query_count));
+        datums.push(access_stats::is_unused(table.id()).map_or(Datum::Null, Datum::Boolean));
         Row::from_datums(datums)
     }
 }
@@ -0,0 +1,214 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+/// implementation of system table: Users
+/// For example `SELECT * FROM system.public.users`
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{OnceLock, RwLock},
+};
+
+use async_trait::async_trait;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    projected_schema::RowProjector,
+    record_batch::FetchedRecordBatchBuilder,
+    row::Row,
+    schema,
+    schema::Schema,
+};
+use generic_error::BoxError;
+use snafu::ResultExt;
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{
+    tables::ENTRY_TIMESTAMP, OneRecordBatchStream, SystemTable, USERS_TABLE_ID, USERS_TABLE_NAME,
+};
+
+/// A grant of a user, and a user without any grant has one entry without the
+/// catalog and the role.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserInfo {
+    pub name: String,
+    /// Catalog of the grant, `*` for all the catalogs
+    pub catalog: Option<String>,
+    pub role: Option<&'static str>,
+    /// Where the user is defined, `config` or `sql`
+    pub source: &'static str,
+}
+
+/// Users and their grants, which are reported by the authenticator.
+#[derive(Debug, Default)]
+pub struct UserRegistry {
+    users: RwLock<Vec<UserInfo>>,
+}
+
+impl UserRegistry {
+    pub fn set(&self, mut users: Vec<UserInfo>) {
+        users.sort_by(|a, b| (&a.name, &a.catalog).cmp(&(&b.name, &b.catalog)));
+        *self.users.write().unwrap() = users;
+    }
+
+    /// Users ordered by the names.
+    pub fn list(&self) -> Vec<UserInfo> {
+        self.users.read().unwrap().clone()
+    }
+}
+
+static USER_REGISTRY: OnceLock<UserRegistry> = OnceLock::new();
+
+/// The global registry of the users.
+pub fn user_registry() -> &'static UserRegistry {
+    USER_REGISTRY.get_or_init(UserRegistry::default)
+}
+
+/// Build a new table schema for users
+fn users_schema() -> Schema {
+    schema::Builder::with_capacity(5)
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("name".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("catalog".to_string(), DatumKind::String)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("role".to_string(), DatumKind::String)
+                .is_nullable(true)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("source".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .primary_key_indexes(vec![0, 1])
+        .build()
+        .unwrap()
+}
+
+pub struct Users {
+    schema: Schema,
+}
+
+impl Debug for Users {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysUsers")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Default for Users {
+    fn default() -> Self {
+        Self {
+            schema: users_schema(),
+        }
+    }
+}
+
+impl Users {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_user(&self, user: UserInfo) -> Row {
+        let mut datums = Vec::with_capacity(self.schema.num_columns());
+        datums.push(Datum::Timestamp(ENTRY_TIMESTAMP));
+        datums.push(Datum::from(user.name.as_str()));
+        datums.push(user.catalog.as_deref().map_or(Datum::Null, Datum::from));
+        datums.push(user.role.map_or(Datum::Null, Datum::from));
+        datums.push(Datum::from(user.source));
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl SystemTable for Users {
+    fn name(&self) -> &str {
+        USERS_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        USERS_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let fetched_schema = request.projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(
+            fetched_schema.clone(),
+            Some(primary_key_indexes.clone()),
+        );
+
+        let table_schema = request.projected_schema.table_schema();
+        let row_projector = RowProjector::new(
+            &fetched_schema,
+            Some(primary_key_indexes),
+            table_schema,
+            &self.schema,
+        )
+        .expect("Should succeed to try_project_key of sys_users");
+        for user in user_registry().list() {
+            let row = self.from_user(user);
+            let projected_row = row_projector.project_row(&row, Vec::new());
+            builder
+                .append_row(projected_row)
+                .box_err()
+                .context(table_engine::table::Scan { table: self.name() })?;
+        }
+        let record_batch = builder.build().unwrap().into_record_batch();
+        Ok(Box::pin(OneRecordBatchStream {
+            schema: self.schema.clone().to_record_schema(),
+            record_batch: Some(record_batch),
+        }))
+    }
+}
@@ -0,0 +1,209 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Access statistics of the tables, i.e. when and how many times a table is
+//! written or queried, so the dead tables can be found and archived or dropped.
+//!
+//! The statistics are kept in memory since the process starts, so a table
+//! is considered unused only if it isn't accessed during the threshold after
+//! the start either.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
+
+use common_types::time::Timestamp;
+use lazy_static::lazy_static;
+
+use crate::table::TableId;
+
+/// Number of the shards of the registry, the tables are spread among the
+/// shards to reduce the contention of the locks.
+const NUM_SHARDS: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessStats {
+    pub last_written: Option<Timestamp>,
+    pub write_count: u64,
+    pub last_queried: Option<Timestamp>,
+    pub query_count: u64,
+}
+
+impl AccessStats {
+    /// Whether the table isn't accessed during the `threshold` before `now`,
+    /// `start` is the time when the stats began to be recorded.
+    pub fn is_unused(&self, threshold: Duration, start: Timestamp, now: Timestamp) -> bool {
+        let last_access = [self.last_written, self.last_queried]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(start)
+            .max(start);
+
+        now.as_i64() - last_access.as_i64() >= threshold.as_millis() as i64
+    }
+}
+
+/// Access statistics of a table updated in place, the zero timestamps mean the
+/// table is never accessed.
+#[derive(Debug, Default)]
+struct TableStats {
+    last_written: AtomicI64,
+    write_count: AtomicU64,
+    last_queried: AtomicI64,
+    query_count: AtomicU64,
+}
+
+impl TableStats {
+    fn snapshot(&self) -> AccessStats {
+        let to_timestamp = |v: &AtomicI64| match v.load(Ordering::Relaxed) {
+            0 => None,
+            v => Some(Timestamp::new(v)),
+        };
+
+        AccessStats {
+            last_written: to_timestamp(&self.last_written),
+            write_count: self.write_count.load(Ordering::Relaxed),
+            last_queried: to_timestamp(&self.last_queried),
+            query_count: self.query_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Registry {
+    start: Timestamp,
+    shards: Vec<RwLock<HashMap<TableId, TableStats>>>,
+}
+
+impl Registry {
+    fn shard(&self, table_id: TableId) -> &RwLock<HashMap<TableId, TableStats>> {
+        &self.shards[table_id.as_u64() as usize % self.shards.len()]
+    }
+
+    /// Update the stats of the table, only the first access of the table takes
+    /// the write lock of its shard.
+    fn update(&self, table_id: TableId, f: impl Fn(&TableStats)) {
+        let shard = self.shard(table_id);
+        if let Some(stats) = shard.read().unwrap().get(&table_id) {
+            f(stats);
+            return;
+        }
+
+        f(shard.write().unwrap().entry(table_id).or_default());
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry {
+        start: Timestamp::now(),
+        shards: (0..NUM_SHARDS).map(|_| RwLock::default()).collect(),
+    };
+}
+
+/// Threshold in milliseconds of the tables to be flagged as unused, zero means
+/// no table is flagged.
+static UNUSED_THRESHOLD_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Set the threshold of the tables to be flagged as unused.
+pub fn set_unused_threshold(threshold: Option<Duration>) {
+    let threshold_ms = threshold.map(|v| v.as_millis() as u64).unwrap_or(0);
+    UNUSED_THRESHOLD_MS.store(threshold_ms, Ordering::Relaxed);
+}
+
+pub fn record_write(table_id: TableId) {
+    REGISTRY.update(table_id, |stats| {
+        stats
+            .last_written
+            .store(Timestamp::now().as_i64(), Ordering::Relaxed);
+        stats.write_count.fetch_add(1, Ordering::Relaxed);
+    });
+}
+
+pub fn record_query(table_id: TableId) {
+    REGISTRY.update(table_id, |stats| {
+        stats
+            .last_queried
+            .store(Timestamp::now().as_i64(), Ordering::Relaxed);
+        stats.query_count.fetch_add(1, Ordering::Relaxed);
+    });
+}
+
+pub fn get(table_id: TableId) -> AccessStats {
+    let tables = REGISTRY.shard(table_id).read().unwrap();
+    tables
+        .get(&table_id)
+        .map(TableStats::snapshot)
+        .unwrap_or_default()
+}
+
+/// Whether the table is unused, `None` if the unused threshold isn't set.
+pub fn is_unused(table_id: TableId) -> Option<bool> {
+    let threshold_ms = UNUSED_THRESHOLD_MS.load(Ordering::Relaxed);
+    if threshold_ms == 0 {
+        return None;
+    }
+
+    Some(get(table_id).is_unused(
+        Duration::from_millis(threshold_ms),
+        REGISTRY.start,
+        Timestamp::now(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_stats() {
+        let table_id = TableId::from(u64::MAX - 1);
+        assert_eq!(get(table_id), AccessStats::default());
+
+        record_write(table_id);
+        record_write(table_id);
+        record_query(table_id);
+        let stats = get(table_id);
+        assert_eq!(stats.write_count, 2);
+        assert_eq!(stats.query_count, 1);
+        assert!(stats.last_written.is_some());
+        assert!(stats.last_queried.is_some());
+    }
+
+    #[test]
+    fn test_is_unused() {
+        let day = Duration::from_secs(24 * 3600);
+        let start = Timestamp::new(0);
+        let now = Timestamp::new(3 * day.as_millis() as i64);
+
+        let stats = AccessStats::default();
+        assert!(stats.is_unused(2 * day, start, now));
+        assert!(!stats.is_unused(4 * day, start, now));
+
+        let stats = AccessStats {
+            last_written: Some(Timestamp::new(2 * day.as_millis() as i64)),
+            write_count: 1,
+            ..Default::default()
+        };
+        assert!(!stats.is_unused(2 * day, start, now));
+        assert!(stats.is_unused(day, start, now));
+    }
+}
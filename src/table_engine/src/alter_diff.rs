@@ -0,0 +1,230 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Diff of the table schema and options made by altering, which is recorded
+//! into the event log and reported by the dry run of the alter statements.
+
+use std::{collections::HashMap, fmt};
+
+use common_types::{
+    column_schema::ColumnSchema, schema::Schema, COMPACTION_STRATEGY, COMPRESSION, ENABLE_TTL,
+    NUM_ROWS_PER_ROW_GROUP, ROW_GROUP_SIZE_POLICY, SEGMENT_DURATION, STORAGE_FORMAT, TTL,
+};
+
+/// Change of an item of the table, the item doesn't exist before or after
+/// altering if the value is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlterDiff {
+    pub item: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl AlterDiff {
+    /// Estimated work to rewrite the existing data after the change is
+    /// applied.
+    pub fn rewrite_estimate(&self) -> &'static str {
+        if self.item.starts_with(COLUMN_ITEM_PREFIX) {
+            return "memtable flushed, existing sst files are not rewritten";
+        }
+
+        match self.item.as_str() {
+            SEGMENT_DURATION
+            | COMPACTION_STRATEGY
+            | NUM_ROWS_PER_ROW_GROUP
+            | ROW_GROUP_SIZE_POLICY
+            | COMPRESSION
+            | STORAGE_FORMAT => {
+                "applied to new sst files, existing sst files are rewritten by later compactions"
+            }
+            TTL | ENABLE_TTL => "expired sst files are purged by later compactions",
+            _ => "none",
+        }
+    }
+}
+
+impl fmt::Display for AlterDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}->{}",
+            self.item,
+            self.before.as_deref().unwrap_or("none"),
+            self.after.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+const COLUMN_ITEM_PREFIX: &str = "column:";
+
+fn describe_column(column: &ColumnSchema) -> String {
+    let mut desc = column.data_type.to_string();
+    if column.is_tag {
+        desc.push_str(" tag");
+    }
+    if !column.is_nullable {
+        desc.push_str(" not null");
+    }
+    if !column.display_name.is_empty() {
+        desc.push_str(&format!(" display_name '{}'", column.display_name));
+    }
+    if !column.unit.is_empty() {
+        desc.push_str(&format!(" unit '{}'", column.unit));
+    }
+    desc
+}
+
+/// Diff of the schema version and the columns.
+pub fn schema_diffs(before: &Schema, after: &Schema) -> Vec<AlterDiff> {
+    let mut diffs = Vec::new();
+    if before.version() != after.version() {
+        diffs.push(AlterDiff {
+            item: "schema_version".to_string(),
+            before: Some(before.version().to_string()),
+            after: Some(after.version().to_string()),
+        });
+    }
+
+    let describe = |schema: &Schema, name: &str| {
+        schema
+            .index_of(name)
+            .map(|idx| describe_column(schema.column(idx)))
+    };
+    let names = before
+        .columns()
+        .iter()
+        .chain(after.columns())
+        .map(|column| column.name.as_str());
+    let mut visited = Vec::new();
+    for name in names {
+        if visited.contains(&name) {
+            continue;
+        }
+        visited.push(name);
+
+        let (before, after) = (describe(before, name), describe(after, name));
+        if before != after {
+            diffs.push(AlterDiff {
+                item: format!("{COLUMN_ITEM_PREFIX}{name}"),
+                before,
+                after,
+            });
+        }
+    }
+
+    diffs
+}
+
+/// Diff of the options, ordered by the option keys.
+pub fn options_diffs(
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+) -> Vec<AlterDiff> {
+    let mut keys: Vec<_> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .map(|key| AlterDiff {
+            item: key.clone(),
+            before: before.get(key).cloned(),
+            after: after.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Format the diffs as the detail of the event.
+pub fn format_diffs(diffs: &[AlterDiff]) -> String {
+    diffs
+        .iter()
+        .map(|diff| diff.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::{datum::DatumKind, tests::build_schema};
+
+    use super::*;
+
+    #[test]
+    fn test_schema_diffs() {
+        let before = build_schema();
+        let mut builder = common_types::schema::Builder::with_capacity(before.num_columns() + 1)
+            .primary_key_indexes(before.primary_key_indexes().to_vec())
+            .version(before.version() + 1);
+        for (idx, column) in before.columns().iter().enumerate() {
+            builder = if before.is_primary_key_index(&idx) {
+                builder.add_key_column(column.clone()).unwrap()
+            } else {
+                builder.add_normal_column(column.clone()).unwrap()
+            };
+        }
+        let after = builder
+            .auto_increment_column_id(true)
+            .add_normal_column(
+                common_types::column_schema::Builder::new("host".to_string(), DatumKind::String)
+                    .is_tag(true)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let diffs = schema_diffs(&before, &after);
+        assert_eq!(2, diffs.len());
+        assert_eq!(
+            format!(
+                "schema_version:{}->{}, column:host:none->string tag",
+                before.version(),
+                after.version()
+            ),
+            format_diffs(&diffs)
+        );
+        assert_eq!(
+            "memtable flushed, existing sst files are not rewritten",
+            diffs[1].rewrite_estimate()
+        );
+        assert!(schema_diffs(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_options_diffs() {
+        let before: HashMap<_, _> = [("ttl", "7d"), ("update_mode", "OVERWRITE")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let mut after = before.clone();
+        after.insert("ttl".to_string(), "8d".to_string());
+        after.insert("compression".to_string(), "ZSTD".to_string());
+
+        let diffs = options_diffs(&before, &after);
+        assert_eq!("compression:none->ZSTD, ttl:7d->8d", format_diffs(&diffs));
+        assert_eq!(
+            "applied to new sst files, existing sst files are rewritten by later compactions",
+            diffs[0].rewrite_estimate()
+        );
+        assert_eq!(
+            "expired sst files are purged by later compactions",
+            diffs[1].rewrite_estimate()
+        );
+    }
+}
@@ -33,6 +33,7 @@ use snafu::{ensure, Backtrace, Snafu};
 use crate::{
     partition::PartitionInfo,
     table::{SchemaId, TableId, TableInfo, TableRef},
+    task::TaskRegistryRef,
 };
 
 #[derive(Debug, Snafu)]
@@ -47,6 +48,9 @@ pub enum Error {
     #[snafu(display("Invalid arguments, table:{table}, err:{source}"))]
     InvalidArguments { table: String, source: GenericError },
 
+    #[snafu(display("Invalid table options, err:{source}"))]
+    InvalidTableOptions { source: GenericError },
+
     #[snafu(display("Failed to write meta data, err:{}", source))]
     WriteMeta { source: GenericError },
 
@@ -333,6 +337,12 @@ pub trait TableEngine: Send + Sync {
     /// unnecessary works if the params is invalid.
     async fn validate_create_table(&self, request: &CreateTableParams) -> Result<()>;
 
+    /// Validate the table options not bound to a table yet, e.g. the default
+    /// table options of a schema.
+    async fn validate_table_options(&self, _options: &HashMap<String, String>) -> Result<()> {
+        Ok(())
+    }
+
     /// Create table
     async fn create_table(&self, request: CreateTableRequest) -> Result<TableRef>;
 
@@ -355,6 +365,12 @@ pub trait TableEngine: Send + Sync {
     async fn report_statistics(&self) -> Result<Option<TableEngineStats>> {
         Ok(None)
     }
+
+    /// Release the memory held by the caches of the engine, usually called
+    /// when the memory is under pressure.
+    async fn shrink_caches(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub type OpenShardResult = HashMap<TableId, GenericResult<Option<TableRef>>>;
@@ -376,4 +392,6 @@ pub struct EngineRuntimes {
     pub default_runtime: RuntimeRef,
     /// Runtime for io task
     pub io_runtime: RuntimeRef,
+    /// Registry of the background tasks spawned on the runtimes
+    pub task_registry: TaskRegistryRef,
 }
@@ -0,0 +1,429 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Event log of the table engines
+//!
+//! The lifecycle events of the tables (flush, compaction, open/close, schema
+//! changes and quarantine of the corrupt ssts) are recorded into a ring
+//! buffer, and optionally appended to a bounded local file in background so
+//! that they survive restarts. The events are queryable as the
+//! `system.public.events` table.
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Mutex, OnceLock,
+    },
+    thread::{self, JoinHandle},
+};
+
+use logger::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::table::TableId;
+
+/// Kind of the recorded event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    FlushBegin,
+    FlushFinish,
+    CompactionBegin,
+    CompactionFinish,
+    TableCreate,
+    TableDrop,
+    TableOpen,
+    TableClose,
+    AlterSchema,
+    AlterOptions,
+    SstQuarantine,
+    RecoveryIssue,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::FlushBegin => "flush_begin",
+            EventKind::FlushFinish => "flush_finish",
+            EventKind::CompactionBegin => "compaction_begin",
+            EventKind::CompactionFinish => "compaction_finish",
+            EventKind::TableCreate => "table_create",
+            EventKind::TableDrop => "table_drop",
+            EventKind::TableOpen => "table_open",
+            EventKind::TableClose => "table_close",
+            EventKind::AlterSchema => "alter_schema",
+            EventKind::AlterOptions => "alter_options",
+            EventKind::SstQuarantine => "sst_quarantine",
+            EventKind::RecoveryIssue => "recovery_issue",
+        }
+    }
+
+    /// Whether the event changes the table itself, e.g. its schema or whether
+    /// it's opened on this node.
+    pub fn is_ddl(&self) -> bool {
+        matches!(
+            self,
+            EventKind::TableCreate
+                | EventKind::TableDrop
+                | EventKind::TableOpen
+                | EventKind::TableClose
+                | EventKind::AlterSchema
+                | EventKind::AlterOptions
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    /// Sequence of the event, increases monotonically.
+    pub seq: u64,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: i64,
+    pub kind: EventKind,
+    pub table: String,
+    pub table_id: u64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Max number of the events kept in memory.
+    pub capacity: usize,
+    /// Path of the file to persist the events to, the events are only kept in
+    /// memory if not set.
+    pub persist_path: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            capacity: 10000,
+            persist_path: None,
+        }
+    }
+}
+
+pub struct EventLog {
+    capacity: usize,
+    next_seq: AtomicU64,
+    /// Only held to push or copy the events, the persisting is done by the
+    /// writer thread so no io happens under the lock.
+    events: Mutex<VecDeque<Event>>,
+    writer: Option<PersistWriter>,
+}
+
+impl EventLog {
+    pub fn new(config: &Config) -> Self {
+        let capacity = config.capacity.max(1);
+        let mut events = VecDeque::with_capacity(capacity);
+        let writer = config.persist_path.as_ref().and_then(|path| {
+            let path = PathBuf::from(path);
+            load_events(&path, capacity, &mut events);
+            match PersistWriter::start(path, capacity, events.clone()) {
+                Ok(writer) => Some(writer),
+                Err(e) => {
+                    warn!("Failed to open event log file, err:{e}");
+                    None
+                }
+            }
+        });
+        let next_seq = events.back().map(|event| event.seq + 1).unwrap_or(1);
+
+        Self {
+            capacity,
+            next_seq: AtomicU64::new(next_seq),
+            events: Mutex::new(events),
+            writer,
+        }
+    }
+
+    pub fn record(&self, kind: EventKind, table: &str, table_id: TableId, detail: String) {
+        let event = Event {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            timestamp: time_ext::current_time_millis() as i64,
+            kind,
+            table: table.to_string(),
+            table_id: table_id.as_u64(),
+            detail,
+        };
+
+        if let Some(writer) = &self.writer {
+            writer.send(event.clone());
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// List the events kept in memory ordered by their sequences.
+    pub fn list(&self) -> Vec<Event> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Max number of the events waiting to be persisted.
+const PERSIST_CHANNEL_SIZE: usize = 4096;
+
+/// Persist the events in a dedicated thread, so that the recording never
+/// blocks the callers (mostly async tasks) on the file io.
+///
+/// The file is rewritten with the last `capacity` events once it contains
+/// more than twice of that, so it won't grow unboundedly.
+struct PersistWriter {
+    sender: Option<SyncSender<Event>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PersistWriter {
+    fn start(path: PathBuf, capacity: usize, events: VecDeque<Event>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (sender, receiver) = mpsc::sync_channel(PERSIST_CHANNEL_SIZE);
+        let mut writer = FileWriter {
+            path,
+            capacity,
+            file,
+            // The file may contain more lines than the loaded events, so it's
+            // rewritten on the first write.
+            line_num: capacity * 2,
+            events,
+        };
+        let handle = thread::Builder::new()
+            .name("event-log-writer".to_string())
+            .spawn(move || writer.run(receiver))?;
+
+        Ok(Self {
+            sender: Some(sender),
+            handle: Some(handle),
+        })
+    }
+
+    fn send(&self, event: Event) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        match sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(event)) => {
+                warn!("Event log writer is busy, event is not persisted, event:{event:?}");
+            }
+            Err(TrySendError::Disconnected(event)) => {
+                warn!("Event log writer is stopped, event is not persisted, event:{event:?}");
+            }
+        }
+    }
+}
+
+impl Drop for PersistWriter {
+    /// Wait for the pending events to be persisted.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("Event log writer panicked");
+            }
+        }
+    }
+}
+
+struct FileWriter {
+    path: PathBuf,
+    capacity: usize,
+    file: File,
+    /// Number of the lines in the file.
+    line_num: usize,
+    /// The last `capacity` events, used to rewrite the file.
+    events: VecDeque<Event>,
+}
+
+impl FileWriter {
+    fn run(&mut self, receiver: Receiver<Event>) {
+        while let Ok(event) = receiver.recv() {
+            let mut line = serde_json::to_string(&event).expect("event is serializable");
+            line.push('\n');
+            if let Err(e) = self.file.write_all(line.as_bytes()) {
+                warn!("Failed to persist event, event:{event:?}, err:{e}");
+            }
+            self.line_num += 1;
+
+            if self.events.len() >= self.capacity {
+                self.events.pop_front();
+            }
+            self.events.push_back(event);
+
+            if self.line_num > self.capacity * 2 {
+                if let Err(e) = self.rewrite() {
+                    warn!(
+                        "Failed to rewrite event log file, path:{}, err:{e}",
+                        self.path.display()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Replace the file with the events kept in memory.
+    fn rewrite(&mut self) -> std::io::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        for event in &self.events {
+            let mut line = serde_json::to_string(event).expect("event is serializable");
+            line.push('\n');
+            tmp_file.write_all(line.as_bytes())?;
+        }
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.line_num = self.events.len();
+        Ok(())
+    }
+}
+
+/// Load the last `capacity` events from the persisted file.
+fn load_events(path: &Path, capacity: usize, events: &mut VecDeque<Event>) {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        // Nothing persisted yet.
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        // The last line may be partially written.
+        let Ok(event) = serde_json::from_str::<Event>(&line) else {
+            continue;
+        };
+        if events.len() >= capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    info!(
+        "Event log loaded, path:{}, event_num:{}",
+        path.display(),
+        events.len()
+    );
+}
+
+static EVENT_LOG: OnceLock<EventLog> = OnceLock::new();
+
+/// Bumped by every DDL event of the tables on this node, no matter where the
+/// DDL comes from, e.g. the other nodes or the meta.
+static DDL_VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Init the global event log, which should be called before any event is
+/// recorded, otherwise the default config is used.
+pub fn init(config: &Config) {
+    if EVENT_LOG.set(EventLog::new(config)).is_err() {
+        warn!("Event log is already initialized");
+    }
+}
+
+/// The global event log.
+pub fn event_log() -> &'static EventLog {
+    EVENT_LOG.get_or_init(|| EventLog::new(&Config::default()))
+}
+
+/// Record an event into the global event log.
+#[inline]
+pub fn record(kind: EventKind, table: &str, table_id: TableId, detail: String) {
+    if kind.is_ddl() {
+        DDL_VERSION.fetch_add(1, Ordering::Release);
+    }
+    event_log().record(kind, table, table_id, detail);
+}
+
+/// Version of the DDLs of the tables on this node, the tables cached before it
+/// changes may be stale.
+#[inline]
+pub fn ddl_version() -> u64 {
+    DDL_VERSION.load(Ordering::Acquire)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_log_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.log");
+        let config = Config {
+            capacity: 2,
+            persist_path: Some(path.to_string_lossy().to_string()),
+        };
+
+        let event_log = EventLog::new(&config);
+        let table_id = TableId::from(1);
+        event_log.record(EventKind::TableOpen, "t", table_id, String::new());
+        event_log.record(EventKind::FlushBegin, "t", table_id, String::new());
+        event_log.record(EventKind::FlushFinish, "t", table_id, "rows:10".to_string());
+
+        let events = event_log.list();
+        assert_eq!(2, events.len());
+        assert_eq!(EventKind::FlushBegin, events[0].kind);
+        assert_eq!(3, events[1].seq);
+        drop(event_log);
+
+        // Reload the last events from the persisted file.
+        let event_log = EventLog::new(&config);
+        let events = event_log.list();
+        assert_eq!(2, events.len());
+        assert_eq!("rows:10", events[1].detail);
+        event_log.record(EventKind::TableClose, "t", table_id, String::new());
+        assert_eq!(4, event_log.list()[1].seq);
+    }
+
+    #[test]
+    fn test_event_log_file_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.log");
+        let config = Config {
+            capacity: 4,
+            persist_path: Some(path.to_string_lossy().to_string()),
+        };
+
+        let event_log = EventLog::new(&config);
+        let table_id = TableId::from(1);
+        for i in 0..100 {
+            event_log.record(EventKind::FlushFinish, "t", table_id, format!("{i}"));
+        }
+        // Wait for the events to be persisted.
+        drop(event_log);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.lines().count() <= config.capacity * 2);
+
+        let event_log = EventLog::new(&config);
+        let events = event_log.list();
+        assert_eq!(4, events.len());
+        assert_eq!("99", events[3].detail);
+        assert_eq!(100, events[3].seq);
+    }
+}
@@ -0,0 +1,356 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Equi-depth histograms of the column values, used to estimate how many rows
+//! the predicates select.
+
+use std::collections::HashMap;
+
+use common_types::datum::Datum;
+use datafusion::logical_expr::{BinaryExpr, Expr, Operator};
+use rand::Rng;
+
+/// Default number of the buckets of a histogram.
+pub const DEFAULT_NUM_BUCKETS: usize = 100;
+/// Max number of the buckets of a histogram.
+pub const MAX_NUM_BUCKETS: usize = 1024;
+/// Max number of the values sampled to build a histogram.
+pub const DEFAULT_MAX_SAMPLES: usize = 100_000;
+/// Number of the rows scanned to build the histograms, the time ranges to scan
+/// are sampled if the table has more rows.
+pub const DEFAULT_MAX_SCAN_ROWS: usize = 1_000_000;
+
+/// Histograms of the columns, keyed by the column names.
+pub type ColumnHistograms = HashMap<String, Histogram>;
+
+/// An equi-depth histogram of a column, every bucket holds roughly the same
+/// number of the non-null values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Number of the rows when the histogram is built.
+    pub num_rows: usize,
+    /// Number of the null values.
+    pub num_nulls: usize,
+    /// Number of the distinct non-null values in the samples.
+    pub num_distinct: usize,
+    /// The min non-null value, `None` if all the values are null.
+    pub min: Option<Datum>,
+    /// Upper bounds of the buckets in ascending order, the last one is the max
+    /// non-null value.
+    pub bounds: Vec<Datum>,
+}
+
+impl Histogram {
+    pub fn num_buckets(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// Fraction of the non-null values.
+    fn non_null_fraction(&self) -> f64 {
+        if self.num_rows == 0 {
+            return 1.0;
+        }
+
+        (self.num_rows - self.num_nulls) as f64 / self.num_rows as f64
+    }
+
+    /// Fraction of the non-null values less than the `value`, or less than or
+    /// equal to it if `inclusive` is true.
+    fn fraction_below(&self, value: &Datum, inclusive: bool) -> f64 {
+        let Some(min) = &self.min else {
+            return 0.0;
+        };
+        if value < min || (!inclusive && value == min) {
+            return 0.0;
+        }
+
+        let below = self
+            .bounds
+            .iter()
+            .take_while(|bound| *bound < value || (inclusive && *bound == value))
+            .count();
+        if below == self.bounds.len() {
+            return 1.0;
+        }
+
+        // The value falls into the bucket `below`, assume it is in the middle of
+        // the bucket.
+        (below as f64 + 0.5) / self.bounds.len() as f64
+    }
+
+    /// Fraction of the non-null values equal to the `value`.
+    fn fraction_equal(&self, value: &Datum) -> f64 {
+        let (Some(min), Some(max)) = (&self.min, self.bounds.last()) else {
+            return 0.0;
+        };
+        if value < min || value > max {
+            return 0.0;
+        }
+
+        // A frequent value may be the bound of several buckets.
+        let num_bounds = self.bounds.iter().filter(|bound| *bound == value).count();
+        let frequent = num_bounds.saturating_sub(1) as f64 / self.bounds.len() as f64;
+        frequent.max(1.0 / self.num_distinct.max(1) as f64)
+    }
+}
+
+/// Builds the [Histogram] from the values sampled by reservoir sampling.
+pub struct HistogramBuilder {
+    max_samples: usize,
+    samples: Vec<Datum>,
+    num_rows: usize,
+    num_nulls: usize,
+}
+
+impl HistogramBuilder {
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            max_samples,
+            samples: Vec::new(),
+            num_rows: 0,
+            num_nulls: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: Datum) {
+        self.num_rows += 1;
+        if value.is_null() {
+            self.num_nulls += 1;
+            return;
+        }
+
+        let num_values = self.num_rows - self.num_nulls;
+        if self.samples.len() < self.max_samples {
+            self.samples.push(value);
+        } else {
+            let idx = rand::thread_rng().gen_range(0..num_values);
+            if idx < self.max_samples {
+                self.samples[idx] = value;
+            }
+        }
+    }
+
+    pub fn build(mut self, num_buckets: usize) -> Histogram {
+        // Values of a column are of the same kind, so they are always comparable.
+        self.samples
+            .sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let num_samples = self.samples.len();
+        let num_buckets = num_buckets.clamp(1, MAX_NUM_BUCKETS).min(num_samples);
+
+        let bounds = (1..=num_buckets)
+            .map(|i| self.samples[i * num_samples / num_buckets - 1].clone())
+            .collect();
+        let mut num_distinct = 0;
+        for (i, value) in self.samples.iter().enumerate() {
+            if i == 0 || self.samples[i - 1] != *value {
+                num_distinct += 1;
+            }
+        }
+
+        Histogram {
+            num_rows: self.num_rows,
+            num_nulls: self.num_nulls,
+            num_distinct,
+            min: self.samples.into_iter().next(),
+            bounds,
+        }
+    }
+}
+
+/// Estimate the fraction of the rows selected by the conjunction of the
+/// `exprs`.
+///
+/// Only the comparisons between the columns with histograms and the literals
+/// are considered, other exprs are assumed to select all the rows. The columns
+/// are assumed to be independent.
+pub fn estimate_selectivity(histograms: &ColumnHistograms, exprs: &[Expr]) -> f64 {
+    // Range of the fractions of the values selected by the exprs, keyed by the
+    // column names.
+    let mut ranges: HashMap<&str, ColumnRange> = HashMap::new();
+    for expr in exprs {
+        collect_column_ranges(histograms, expr, &mut ranges);
+    }
+
+    ranges
+        .into_iter()
+        .map(|(name, range)| histograms[name].non_null_fraction() * range.selectivity())
+        .product()
+}
+
+struct ColumnRange {
+    low: f64,
+    high: f64,
+    equal: f64,
+}
+
+impl Default for ColumnRange {
+    fn default() -> Self {
+        Self {
+            low: 0.0,
+            high: 1.0,
+            equal: 1.0,
+        }
+    }
+}
+
+impl ColumnRange {
+    fn selectivity(&self) -> f64 {
+        (self.high - self.low).clamp(0.0, 1.0).min(self.equal)
+    }
+}
+
+fn collect_column_ranges<'a>(
+    histograms: &'a ColumnHistograms,
+    expr: &Expr,
+    ranges: &mut HashMap<&'a str, ColumnRange>,
+) {
+    let Expr::BinaryExpr(BinaryExpr { left, op, right }) = expr else {
+        return;
+    };
+    if *op == Operator::And {
+        collect_column_ranges(histograms, left, ranges);
+        collect_column_ranges(histograms, right, ranges);
+        return;
+    }
+
+    let (column, value, op) = match (left.as_ref(), right.as_ref()) {
+        (Expr::Column(column), Expr::Literal(value)) => (column, value, *op),
+        (Expr::Literal(value), Expr::Column(column)) => match op.swap() {
+            Some(op) => (column, value, op),
+            None => return,
+        },
+        _ => return,
+    };
+    let Some((name, histogram)) = histograms.get_key_value(&column.name) else {
+        return;
+    };
+    let Some(value) = Datum::from_scalar_value(value) else {
+        return;
+    };
+    // The literal of another kind can't be compared with the bounds.
+    if histogram
+        .min
+        .as_ref()
+        .is_some_and(|min| min.kind() != value.kind())
+    {
+        return;
+    }
+
+    let range = ranges.entry(name.as_str()).or_default();
+    match op {
+        Operator::Eq => range.equal = range.equal.min(histogram.fraction_equal(&value)),
+        Operator::Lt => range.high = range.high.min(histogram.fraction_below(&value, false)),
+        Operator::LtEq => range.high = range.high.min(histogram.fraction_below(&value, true)),
+        Operator::Gt => range.low = range.low.max(histogram.fraction_below(&value, true)),
+        Operator::GtEq => range.low = range.low.max(histogram.fraction_below(&value, false)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::logical_expr::{col, lit};
+
+    use super::*;
+
+    fn build_histogram(values: impl IntoIterator<Item = Datum>, num_buckets: usize) -> Histogram {
+        let mut builder = HistogramBuilder::new(DEFAULT_MAX_SAMPLES);
+        for value in values {
+            builder.push(value);
+        }
+        builder.build(num_buckets)
+    }
+
+    #[test]
+    fn test_build_histogram() {
+        let values = (0..100).rev().map(Datum::Int64).chain([Datum::Null]);
+        let histogram = build_histogram(values, 4);
+        assert_eq!(101, histogram.num_rows);
+        assert_eq!(1, histogram.num_nulls);
+        assert_eq!(100, histogram.num_distinct);
+        assert_eq!(Some(Datum::Int64(0)), histogram.min);
+        assert_eq!(
+            vec![
+                Datum::Int64(24),
+                Datum::Int64(49),
+                Datum::Int64(74),
+                Datum::Int64(99)
+            ],
+            histogram.bounds
+        );
+
+        let histogram = build_histogram([Datum::Int64(1), Datum::Int64(2)], 10);
+        assert_eq!(2, histogram.num_buckets());
+
+        let histogram = build_histogram([Datum::Null], 10);
+        assert_eq!(None, histogram.min);
+        assert!(histogram.bounds.is_empty());
+    }
+
+    #[test]
+    fn test_sample_values() {
+        let mut builder = HistogramBuilder::new(10);
+        for i in 0..1000 {
+            builder.push(Datum::Int64(i));
+        }
+        let histogram = builder.build(5);
+        assert_eq!(1000, histogram.num_rows);
+        assert_eq!(10, histogram.num_distinct);
+        assert_eq!(5, histogram.num_buckets());
+    }
+
+    #[test]
+    fn test_estimate_selectivity() {
+        let histograms = ColumnHistograms::from([(
+            "a".to_string(),
+            build_histogram((0..100).map(Datum::Int64), 10),
+        )]);
+
+        let cases = vec![
+            (vec![], 1.0),
+            (vec![col("a").lt(lit(-1i64))], 0.0),
+            (vec![col("a").gt(lit(200i64))], 0.0),
+            (vec![col("a").lt(lit(200i64))], 1.0),
+            (vec![col("a").lt(lit(45i64))], 0.45),
+            (vec![lit(45i64).gt(col("a"))], 0.45),
+            (
+                vec![col("a").gt_eq(lit(25i64)), col("a").lt(lit(75i64))],
+                0.5,
+            ),
+            (
+                vec![col("a").gt_eq(lit(25i64)).and(col("a").lt(lit(75i64)))],
+                0.5,
+            ),
+            (vec![col("a").eq(lit(10i64))], 0.01),
+            // Not supported exprs select all the rows.
+            (vec![col("b").lt(lit(10i64))], 1.0),
+            (
+                vec![col("a").lt(lit(10i64)).or(col("a").gt(lit(90i64)))],
+                1.0,
+            ),
+            (vec![col("a").lt(lit("10"))], 1.0),
+        ];
+
+        for (exprs, expected) in cases {
+            let selectivity = estimate_selectivity(&histograms, &exprs);
+            assert!(
+                (selectivity - expected).abs() < 1e-6,
+                "exprs:{exprs:?}, selectivity:{selectivity}, expected:{expected}"
+            );
+        }
+    }
+}
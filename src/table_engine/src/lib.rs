@@ -17,15 +17,23 @@
 
 //! Table engine facade, provides read/write interfaces of table
 
+pub mod access_stats;
+pub mod alter_diff;
 pub mod engine;
+pub mod event;
+pub mod histogram;
 pub mod memory;
 pub mod partition;
 pub mod predicate;
 pub mod provider;
 pub mod proxy;
+pub mod query_warning;
 pub mod remote;
+pub mod scan_quota;
 pub mod stream;
 pub mod table;
+pub mod task;
+pub mod write_trace;
 
 pub const MEMORY_ENGINE_TYPE: &str = "Memory";
 pub const ANALYTIC_ENGINE_TYPE: &str = "Analytic";
@@ -388,4 +388,8 @@ impl RemoteEngine for MockRemoteEngine {
     ) -> remote::Result<SendableRecordBatchStream> {
         unimplemented!()
     }
+
+    async fn route(&self, _tables: &[model::TableIdentifier]) -> remote::Result<Vec<String>> {
+        unimplemented!()
+    }
 }
@@ -23,9 +23,15 @@ use bytes_ext::Bytes;
 use horaedbproto::cluster::partition_info::Info;
 use macros::define_result;
 use regex::Regex;
-use snafu::{Backtrace, Snafu};
+use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, ResultExt, Snafu};
+use time_ext::ReadableDuration;
 
 const PARTITION_TABLE_PREFIX: &str = "__";
+/// Version of the hash partition info in protobuf carrying a time partition,
+/// as there is no time partition info in protobuf. It is negative so it never
+/// collides with the versions of the real hash partitions.
+const TIME_PARTITION_PB_VERSION: i32 = -1;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -81,6 +87,9 @@ pub enum Error {
 
     #[snafu(display("Column in the partition key is not found.\nBacktrace:\n{backtrace}"))]
     InvalidPartitionKey { backtrace: Backtrace },
+
+    #[snafu(display("Failed to decode time partition, err:{}", source))]
+    DecodeTimePartition { source: serde_json::Error },
 }
 
 define_result!(Error);
@@ -91,6 +100,7 @@ pub enum PartitionInfo {
     Random(RandomPartitionInfo),
     Hash(HashPartitionInfo),
     Key(KeyPartitionInfo),
+    Time(TimePartitionInfo),
 }
 
 impl PartitionInfo {
@@ -100,6 +110,7 @@ impl PartitionInfo {
             Self::Random(v) => v.definitions.clone(),
             Self::Hash(v) => v.definitions.clone(),
             Self::Key(v) => v.definitions.clone(),
+            Self::Time(v) => v.definitions.clone(),
         }
     }
 
@@ -109,6 +120,7 @@ impl PartitionInfo {
             Self::Random(v) => v.definitions.len(),
             Self::Hash(v) => v.definitions.len(),
             Self::Key(v) => v.definitions.len(),
+            Self::Time(v) => v.definitions.len(),
         }
     }
 }
@@ -140,6 +152,67 @@ pub struct KeyPartitionInfo {
     pub linear: bool,
 }
 
+/// The rows are partitioned by the time buckets of the timestamp column, and
+/// the consecutive buckets are assigned to the partitions in turn, so a query
+/// of a short time range only reads a few partitions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimePartitionInfo {
+    pub definitions: Vec<PartitionDefinition>,
+    /// The timestamp column to partition by.
+    pub column: String,
+    /// Length of the time buckets.
+    pub interval: ReadableDuration,
+}
+
+/// The time partition encoded in the `expr` of the hash partition info in
+/// protobuf.
+#[derive(Deserialize, Serialize)]
+struct TimePartitionExpr {
+    column: String,
+    interval_ms: u64,
+}
+
+impl From<TimePartitionInfo> for horaedbproto::cluster::HashPartitionInfo {
+    fn from(partition_info: TimePartitionInfo) -> Self {
+        let expr = TimePartitionExpr {
+            column: partition_info.column,
+            interval_ms: partition_info.interval.as_millis(),
+        };
+        // Serializing a struct of a string and an integer never fails.
+        let expr = serde_json::to_vec(&expr).expect("encode time partition expr");
+
+        horaedbproto::cluster::HashPartitionInfo {
+            version: TIME_PARTITION_PB_VERSION,
+            definitions: partition_info
+                .definitions
+                .into_iter()
+                .map(|v| v.into())
+                .collect(),
+            expr,
+            linear: false,
+        }
+    }
+}
+
+impl TryFrom<horaedbproto::cluster::HashPartitionInfo> for TimePartitionInfo {
+    type Error = Error;
+
+    fn try_from(partition_info_pb: horaedbproto::cluster::HashPartitionInfo) -> Result<Self> {
+        let expr: TimePartitionExpr =
+            serde_json::from_slice(&partition_info_pb.expr).context(DecodeTimePartition)?;
+
+        Ok(TimePartitionInfo {
+            definitions: partition_info_pb
+                .definitions
+                .into_iter()
+                .map(|v| v.into())
+                .collect(),
+            column: expr.column,
+            interval: ReadableDuration::millis(expr.interval_ms),
+        })
+    }
+}
+
 impl From<PartitionDefinition> for horaedbproto::cluster::PartitionDefinition {
     fn from(definition: PartitionDefinition) -> Self {
         Self {
@@ -273,6 +346,14 @@ impl From<PartitionInfo> for horaedbproto::cluster::PartitionInfo {
                     info: Some(Info::Random(random_partition_info)),
                 }
             }
+            // The time partition is persisted as a hash partition with a marker
+            // version, see [TIME_PARTITION_PB_VERSION].
+            PartitionInfo::Time(v) => {
+                let hash_partition_info = horaedbproto::cluster::HashPartitionInfo::from(v);
+                horaedbproto::cluster::PartitionInfo {
+                    info: Some(Info::Hash(hash_partition_info)),
+                }
+            }
         }
     }
 }
@@ -285,6 +366,10 @@ impl TryFrom<horaedbproto::cluster::PartitionInfo> for PartitionInfo {
     ) -> std::result::Result<Self, Self::Error> {
         match partition_info_pb.info {
             Some(info) => match info {
+                Info::Hash(v) if v.version == TIME_PARTITION_PB_VERSION => {
+                    let time_partition_info = TimePartitionInfo::try_from(v)?;
+                    Ok(Self::Time(time_partition_info))
+                }
                 Info::Hash(v) => {
                     let hash_partition_info = HashPartitionInfo::from(v);
                     Ok(Self::Hash(hash_partition_info))
@@ -322,7 +407,9 @@ pub fn maybe_extract_partitioned_table_name(sub_table_name: &str) -> Option<Stri
 
 #[cfg(test)]
 mod tests {
-    use crate::partition::maybe_extract_partitioned_table_name;
+    use std::time::Duration;
+
+    use super::*;
 
     #[test]
     fn test_extract_partitioned_table_name() {
@@ -345,4 +432,32 @@ mod tests {
         let result = maybe_extract_partitioned_table_name(invalid_sub_table_name);
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_time_partition_info_pb() {
+        let partition_info = PartitionInfo::Time(TimePartitionInfo {
+            definitions: vec![
+                PartitionDefinition {
+                    name: "0".to_string(),
+                    origin_name: None,
+                },
+                PartitionDefinition {
+                    name: "1".to_string(),
+                    origin_name: None,
+                },
+            ],
+            column: "ts".to_string(),
+            interval: ReadableDuration(Duration::from_secs(86400)),
+        });
+
+        let partition_info_pb = horaedbproto::cluster::PartitionInfo::from(partition_info.clone());
+        match &partition_info_pb.info {
+            Some(Info::Hash(v)) => assert_eq!(TIME_PARTITION_PB_VERSION, v.version),
+            _ => panic!("time partition should be persisted as hash partition"),
+        }
+        assert_eq!(
+            partition_info,
+            PartitionInfo::try_from(partition_info_pb).unwrap()
+        );
+    }
 }
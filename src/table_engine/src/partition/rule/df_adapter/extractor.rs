@@ -20,7 +20,13 @@
 use std::collections::HashSet;
 
 use common_types::datum::Datum;
-use datafusion::logical_expr::{expr::InList, Expr, Operator};
+use datafusion::{
+    logical_expr::{
+        expr::{Between, InList},
+        Expr, Operator,
+    },
+    scalar::ScalarValue,
+};
 use df_operator::visitor::find_columns_by_expr;
 
 use crate::partition::rule::filter::{PartitionCondition, PartitionFilter};
@@ -33,7 +39,7 @@ use crate::partition::rule::filter::{PartitionCondition, PartitionFilter};
 /// NOTICE: When you implements [PartitionRule] for specific partition strategy,
 /// you should implement the corresponding [FilterExtractor], too.
 ///
-/// For example: [KeyRule] and [KeyExtractor].
+/// For example: [KeyRule] and [KeyExtractor], [TimeRule] and [TimeExtractor].
 /// If they are not related, [PartitionRule] may not take effect.
 pub trait FilterExtractor: Send + Sync + 'static {
     fn extract(&self, filters: &[Expr], columns: &[String]) -> Vec<PartitionFilter>;
@@ -134,10 +140,108 @@ impl FilterExtractor for KeyExtractor {
     }
 }
 
+/// Extractor for the time partition, the comparisons between the time column
+/// and the literals are extracted, so the time range of the query can be
+/// figured out.
+pub struct TimeExtractor;
+
+impl TimeExtractor {
+    fn condition_of(op: Operator, datum: Datum) -> Option<PartitionCondition> {
+        match op {
+            Operator::Eq => Some(PartitionCondition::Eq(datum)),
+            Operator::Lt => Some(PartitionCondition::Lt(datum)),
+            Operator::LtEq => Some(PartitionCondition::LtEq(datum)),
+            Operator::Gt => Some(PartitionCondition::Gt(datum)),
+            Operator::GtEq => Some(PartitionCondition::GtEq(datum)),
+            _ => None,
+        }
+    }
+
+    /// Swap the sides of the comparison, e.g. "1 < col" to "col > 1".
+    fn swap_op(op: Operator) -> Operator {
+        match op {
+            Operator::Lt => Operator::Gt,
+            Operator::LtEq => Operator::GtEq,
+            Operator::Gt => Operator::Lt,
+            Operator::GtEq => Operator::LtEq,
+            op => op,
+        }
+    }
+
+    fn extract_one(filter: &Expr, column: &str, target: &mut Vec<PartitionFilter>) {
+        let new_filter = |op: Operator, val: &ScalarValue| {
+            let datum = Datum::from_scalar_value(val)?;
+            let condition = Self::condition_of(op, datum)?;
+            Some(PartitionFilter::new(column.to_string(), condition))
+        };
+
+        let partition_filter = match filter {
+            Expr::BinaryExpr(datafusion::logical_expr::BinaryExpr { left, op, right }) => {
+                match (left.as_ref(), right.as_ref()) {
+                    (Expr::Column(col), Expr::Literal(val)) if col.name == column => {
+                        new_filter(*op, val)
+                    }
+                    (Expr::Literal(val), Expr::Column(col)) if col.name == column => {
+                        new_filter(Self::swap_op(*op), val)
+                    }
+                    _ => None,
+                }
+            }
+            Expr::InList(InList {
+                expr,
+                list,
+                negated: false,
+            }) if matches!(expr.as_ref(), Expr::Column(col) if col.name == column) => {
+                let datums: Option<Vec<_>> = list
+                    .iter()
+                    .map(|entry| match entry {
+                        Expr::Literal(val) => Datum::from_scalar_value(val),
+                        _ => None,
+                    })
+                    .collect();
+                datums.map(|datums| {
+                    PartitionFilter::new(column.to_string(), PartitionCondition::In(datums))
+                })
+            }
+            Expr::Between(Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            }) if matches!(expr.as_ref(), Expr::Column(col) if col.name == column) => {
+                if let (Expr::Literal(low), Expr::Literal(high)) = (low.as_ref(), high.as_ref()) {
+                    target.extend(new_filter(Operator::GtEq, low));
+                    target.extend(new_filter(Operator::LtEq, high));
+                }
+                None
+            }
+            _ => None,
+        };
+
+        target.extend(partition_filter);
+    }
+}
+
+impl FilterExtractor for TimeExtractor {
+    fn extract(&self, filters: &[Expr], columns: &[String]) -> Vec<PartitionFilter> {
+        let Some(column) = columns.first() else {
+            return Vec::new();
+        };
+
+        let mut target = Vec::with_capacity(filters.len());
+        for filter in filters {
+            Self::extract_one(filter, column, &mut target);
+        }
+
+        target
+    }
+}
+
 pub type FilterExtractorRef = Box<dyn FilterExtractor>;
 
 #[cfg(test)]
 mod tests {
+    use common_types::time::Timestamp;
     use datafusion::{
         logical_expr::{col, Expr::Literal},
         scalar::ScalarValue,
@@ -200,4 +304,36 @@ mod tests {
         let partition_filter = extractor.extract(&[accepted_expr], &columns);
         assert!(partition_filter.is_empty())
     }
+
+    #[test]
+    fn test_time_extractor() {
+        let extractor = TimeExtractor;
+
+        let columns = vec!["ts".to_string()];
+        let ts = |v| Literal(ScalarValue::TimestampMillisecond(Some(v), None));
+        let datum = |v| Datum::Timestamp(Timestamp::new(v));
+        let exprs = [
+            col("ts").gt_eq(ts(1)),
+            ts(10).gt(col("ts")),
+            col("ts").between(ts(2), ts(8)),
+            col("ts").in_list(vec![ts(3), ts(4)], false),
+            // The negated exprs and the exprs of other columns are ignored.
+            col("ts").in_list(vec![ts(5)], true),
+            col("ts").not_between(ts(2), ts(8)),
+            col("col1").lt(ts(1)),
+            col("ts").not_eq(ts(1)),
+        ];
+        let partition_filters = extractor.extract(&exprs, &columns);
+        let expected = vec![
+            PartitionCondition::GtEq(datum(1)),
+            PartitionCondition::Lt(datum(10)),
+            PartitionCondition::GtEq(datum(2)),
+            PartitionCondition::LtEq(datum(8)),
+            PartitionCondition::In(vec![datum(3), datum(4)]),
+        ]
+        .into_iter()
+        .map(|condition| PartitionFilter::new("ts".to_string(), condition))
+        .collect::<Vec<_>>();
+        assert_eq!(partition_filters, expected);
+    }
 }
@@ -20,7 +20,7 @@
 use common_types::{row::RowGroup, schema::Schema};
 use datafusion::logical_expr::Expr;
 
-use self::extractor::{KeyExtractor, NoopExtractor};
+use self::extractor::{KeyExtractor, NoopExtractor, TimeExtractor};
 use crate::partition::{
     rule::{
         df_adapter::extractor::FilterExtractorRef, factory::PartitionRuleFactory, PartitionRulePtr,
@@ -72,12 +72,15 @@ impl DfPartitionRuleAdapter {
             }
             .fail(),
             PartitionInfo::Random(_) => Ok(Box::new(NoopExtractor)),
+            PartitionInfo::Time(_) => Ok(Box::new(TimeExtractor)),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use common_types::{
         column_schema,
         datum::{Datum, DatumKind},
@@ -86,13 +89,17 @@ mod tests {
         string::StringBytes,
         time::Timestamp,
     };
-    use datafusion::logical_expr::{col, lit};
+    use datafusion::{
+        logical_expr::{col, lit},
+        scalar::ScalarValue,
+    };
     use itertools::Itertools;
+    use time_ext::ReadableDuration;
 
     use super::*;
     use crate::partition::{
         rule::key::{compute_partition, DEFAULT_PARTITION_VERSION},
-        KeyPartitionInfo, PartitionDefinition,
+        KeyPartitionInfo, PartitionDefinition, TimePartitionInfo,
     };
 
     // TODO: this test maybe not reasonable to place here.
@@ -253,6 +260,62 @@ mod tests {
         assert_eq!(partition_ids, expecteds);
     }
 
+    #[test]
+    fn test_time_partition() {
+        let schema = build_schema();
+        let time_partition = TimePartitionInfo {
+            definitions: vec![PartitionDefinition::default(); 4],
+            column: "timestamp".to_string(),
+            interval: ReadableDuration(Duration::from_millis(10)),
+        };
+        let rule_adapter =
+            DfPartitionRuleAdapter::new(PartitionInfo::Time(time_partition), &schema).unwrap();
+
+        // Read the rows in [10, 30).
+        let ts = |v| lit(ScalarValue::TimestampMillisecond(Some(v), None));
+        let filters = vec![
+            col("timestamp").gt_eq(ts(10)),
+            col("timestamp").lt(ts(30)),
+            col("col1").eq(lit(1_i32)),
+        ];
+        let partitions = rule_adapter.locate_partitions_for_read(&filters).unwrap();
+        assert_eq!(partitions, vec![1, 2]);
+
+        // Write the rows of the different time buckets.
+        let rows = [5, 15, 45]
+            .into_iter()
+            .map(|v| {
+                RowBuilder::new(&schema)
+                    .append_datum(Datum::UInt64(0))
+                    .unwrap()
+                    .append_datum(Datum::Timestamp(Timestamp::new(v)))
+                    .unwrap()
+                    .append_datum(Datum::Int32(1))
+                    .unwrap()
+                    .append_datum(Datum::String(StringBytes::from("test")))
+                    .unwrap()
+                    .append_datum(Datum::UInt64(42))
+                    .unwrap()
+                    .finish()
+                    .unwrap()
+            })
+            .collect();
+        let row_group = RowGroup::new_unchecked(schema.clone(), rows);
+        let partition_ids = match rule_adapter.locate_partitions_for_write(row_group).unwrap() {
+            PartitionedRows::Multiple(iter) => iter.map(|v| v.partition_id).collect_vec(),
+            _ => panic!("invalid partitioned rows"),
+        };
+        assert_eq!(partition_ids, vec![0, 1, 0]);
+
+        // The time column must be timestamp.
+        let time_partition = TimePartitionInfo {
+            definitions: vec![PartitionDefinition::default(); 4],
+            column: "col1".to_string(),
+            interval: ReadableDuration(Duration::from_millis(10)),
+        };
+        assert!(DfPartitionRuleAdapter::new(PartitionInfo::Time(time_partition), &schema).is_err());
+    }
+
     fn build_schema() -> Schema {
         Builder::new()
             .auto_increment_column_id(true)
@@ -24,10 +24,11 @@ use crate::partition::{
     rule::{
         key::{KeyRule, DEFAULT_PARTITION_VERSION},
         random::RandomRule,
+        time::TimeRule,
         PartitionRulePtr,
     },
     BuildPartitionRule, InvalidPartitionKey, KeyPartitionInfo, PartitionInfo, RandomPartitionInfo,
-    Result,
+    Result, TimePartitionInfo,
 };
 
 pub struct PartitionRuleFactory;
@@ -37,6 +38,7 @@ impl PartitionRuleFactory {
         match partition_info {
             PartitionInfo::Key(key_info) => Self::create_key_rule(key_info, schema),
             PartitionInfo::Random(random_info) => Self::create_random_rule(random_info),
+            PartitionInfo::Time(time_info) => Self::create_time_rule(time_info, schema),
             _ => BuildPartitionRule {
                 msg: format!("unsupported partition strategy, strategy:{partition_info:?}"),
             }
@@ -66,6 +68,34 @@ impl PartitionRuleFactory {
         )))
     }
 
+    fn create_time_rule(time_info: TimePartitionInfo, schema: &Schema) -> Result<PartitionRulePtr> {
+        // The rows of the same primary key must be in the same partition, so only
+        // the timestamp key is allowed.
+        ensure!(
+            schema.timestamp_name() == time_info.column,
+            BuildPartitionRule {
+                msg: format!(
+                    "time partition column must be the timestamp key, column:{}, timestamp_key:{}",
+                    time_info.column,
+                    schema.timestamp_name()
+                )
+            }
+        );
+        let interval_ms = i64::try_from(time_info.interval.as_millis()).unwrap_or(i64::MAX);
+        ensure!(
+            interval_ms > 0,
+            BuildPartitionRule {
+                msg: format!("invalid time partition interval:{}", time_info.interval)
+            }
+        );
+
+        Ok(Box::new(TimeRule::new(
+            time_info.definitions.len(),
+            time_info.column,
+            interval_ms,
+        )))
+    }
+
     fn create_random_rule(random_info: RandomPartitionInfo) -> Result<PartitionRulePtr> {
         Ok(Box::new(RandomRule {
             partition_num: random_info.definitions.len(),
@@ -22,6 +22,7 @@ mod factory;
 mod filter;
 mod key;
 mod random;
+mod time;
 
 use common_types::row::{Row, RowGroup};
 
@@ -0,0 +1,285 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Time partition rule
+
+use std::collections::BTreeSet;
+
+use common_types::{datum::Datum, row::RowGroup};
+use itertools::Itertools;
+use snafu::OptionExt;
+
+use crate::partition::{
+    rule::{
+        filter::PartitionCondition, PartitionFilter, PartitionRule, PartitionedRow, PartitionedRows,
+    },
+    LocateWritePartition, Result,
+};
+
+/// The rows are put into the time buckets of `interval_ms` by the timestamp
+/// column, and the buckets are assigned to the partitions in turn.
+pub struct TimeRule {
+    columns: Vec<String>,
+    interval_ms: i64,
+    partition_num: usize,
+}
+
+impl TimeRule {
+    pub fn new(partition_num: usize, column: String, interval_ms: i64) -> Self {
+        assert!(interval_ms > 0);
+
+        Self {
+            columns: vec![column],
+            interval_ms,
+            partition_num,
+        }
+    }
+
+    #[inline]
+    fn bucket_of(&self, timestamp: i64) -> i64 {
+        timestamp.div_euclid(self.interval_ms)
+    }
+
+    #[inline]
+    fn partition_of_bucket(&self, bucket: i64) -> usize {
+        bucket.rem_euclid(self.partition_num as i64) as usize
+    }
+
+    #[inline]
+    fn partition_of(&self, timestamp: i64) -> usize {
+        self.partition_of_bucket(self.bucket_of(timestamp))
+    }
+
+    /// Partitions of the timestamps in the inclusive range `[start, end]`.
+    fn partitions_of_range(&self, start: i64, end: i64) -> BTreeSet<usize> {
+        if start > end {
+            return BTreeSet::new();
+        }
+
+        let start_bucket = self.bucket_of(start);
+        let end_bucket = self.bucket_of(end);
+        // All the partitions are covered if the range spans enough buckets.
+        if end_bucket as i128 - start_bucket as i128 >= self.partition_num as i128 {
+            return (0..self.partition_num).collect();
+        }
+
+        (start_bucket..=end_bucket)
+            .map(|bucket| self.partition_of_bucket(bucket))
+            .collect()
+    }
+
+    #[inline]
+    fn all_partitions(&self) -> Vec<usize> {
+        (0..self.partition_num).collect_vec()
+    }
+}
+
+/// Timestamp in millis of the datum in the filters.
+fn timestamp_of(datum: &Datum) -> Option<i64> {
+    datum
+        .as_timestamp()
+        .map(|v| v.as_i64())
+        .or_else(|| datum.as_i64())
+}
+
+impl PartitionRule for TimeRule {
+    fn involved_columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    fn location_partitions_for_write(&self, row_group: RowGroup) -> Result<PartitionedRows> {
+        let column = &self.columns[0];
+        let column_idx = row_group
+            .schema()
+            .index_of(column)
+            .context(LocateWritePartition {
+                msg: format!(
+                    "time column not found in schema when locate partition by time strategy, column:{column}"
+                ),
+            })?;
+
+        // The rows without valid timestamp are put into the first partition.
+        let partition_num = self.partition_num;
+        let interval_ms = self.interval_ms;
+        let iter = row_group.into_iter().map(move |row| {
+            let partition_id = timestamp_of(&row[column_idx])
+                .map(|v| v.div_euclid(interval_ms).rem_euclid(partition_num as i64) as usize)
+                .unwrap_or(0);
+            PartitionedRow { partition_id, row }
+        });
+
+        Ok(PartitionedRows::Multiple(Box::new(iter)))
+    }
+
+    fn locate_partitions_for_read(&self, filters: &[PartitionFilter]) -> Result<Vec<usize>> {
+        let mut start = i64::MIN;
+        let mut end = i64::MAX;
+        let mut in_lists: Vec<Vec<i64>> = Vec::new();
+        // The filters are combined by AND, so the ones unknown to the rule are
+        // just skipped, which only makes more partitions read.
+        for filter in filters {
+            if filter.column != self.columns[0] {
+                continue;
+            }
+
+            match &filter.condition {
+                PartitionCondition::In(datums) => {
+                    let timestamps: Option<Vec<_>> = datums.iter().map(timestamp_of).collect();
+                    if let Some(timestamps) = timestamps {
+                        in_lists.push(timestamps);
+                    }
+                }
+                PartitionCondition::Eq(datum) => {
+                    if let Some(v) = timestamp_of(datum) {
+                        start = start.max(v);
+                        end = end.min(v);
+                    }
+                }
+                PartitionCondition::Lt(datum) => match timestamp_of(datum) {
+                    Some(i64::MIN) => return Ok(Vec::new()),
+                    Some(v) => end = end.min(v - 1),
+                    None => {}
+                },
+                PartitionCondition::LtEq(datum) => {
+                    if let Some(v) = timestamp_of(datum) {
+                        end = end.min(v);
+                    }
+                }
+                PartitionCondition::Gt(datum) => match timestamp_of(datum) {
+                    Some(i64::MAX) => return Ok(Vec::new()),
+                    Some(v) => start = start.max(v + 1),
+                    None => {}
+                },
+                PartitionCondition::GtEq(datum) => {
+                    if let Some(v) = timestamp_of(datum) {
+                        start = start.max(v);
+                    }
+                }
+            }
+        }
+
+        let partitions = match in_lists.split_first() {
+            Some((first, rest)) => first
+                .iter()
+                .filter(|v| (start..=end).contains(*v))
+                .filter(|v| rest.iter().all(|list| list.contains(*v)))
+                .map(|v| self.partition_of(*v))
+                .collect::<BTreeSet<_>>(),
+            None if start == i64::MIN && end == i64::MAX => return Ok(self.all_partitions()),
+            None => self.partitions_of_range(start, end),
+        };
+
+        Ok(partitions.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::time::Timestamp;
+
+    use super::*;
+
+    const DAY_MS: i64 = 24 * 3600 * 1000;
+
+    fn build_rule() -> TimeRule {
+        TimeRule::new(4, "ts".to_string(), DAY_MS)
+    }
+
+    fn filter(condition: PartitionCondition) -> PartitionFilter {
+        PartitionFilter::new("ts".to_string(), condition)
+    }
+
+    fn timestamp(v: i64) -> Datum {
+        Datum::Timestamp(Timestamp::new(v))
+    }
+
+    #[test]
+    fn test_partition_of() {
+        let rule = build_rule();
+        assert_eq!(0, rule.partition_of(0));
+        assert_eq!(0, rule.partition_of(DAY_MS - 1));
+        assert_eq!(1, rule.partition_of(DAY_MS));
+        assert_eq!(0, rule.partition_of(4 * DAY_MS));
+        assert_eq!(3, rule.partition_of(-1));
+    }
+
+    #[test]
+    fn test_locate_partitions_for_read() {
+        let rule = build_rule();
+
+        // No filters.
+        assert_eq!(vec![0, 1, 2, 3], rule.locate_partitions_for_read(&[]).unwrap());
+
+        // Range in two days.
+        let filters = [
+            filter(PartitionCondition::GtEq(timestamp(DAY_MS))),
+            filter(PartitionCondition::Lt(timestamp(3 * DAY_MS))),
+        ];
+        assert_eq!(vec![1, 2], rule.locate_partitions_for_read(&filters).unwrap());
+
+        // The end of the range is exclusive.
+        let filters = [
+            filter(PartitionCondition::Gt(timestamp(DAY_MS - 1))),
+            filter(PartitionCondition::LtEq(timestamp(DAY_MS))),
+        ];
+        assert_eq!(vec![1], rule.locate_partitions_for_read(&filters).unwrap());
+
+        // Range covers all the partitions.
+        let filters = [
+            filter(PartitionCondition::GtEq(timestamp(0))),
+            filter(PartitionCondition::Lt(timestamp(10 * DAY_MS))),
+        ];
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            rule.locate_partitions_for_read(&filters).unwrap()
+        );
+
+        // Range wraps around the partitions.
+        let filters = [
+            filter(PartitionCondition::GtEq(timestamp(3 * DAY_MS))),
+            filter(PartitionCondition::Lt(timestamp(5 * DAY_MS))),
+        ];
+        assert_eq!(vec![0, 3], rule.locate_partitions_for_read(&filters).unwrap());
+
+        // Empty range.
+        let filters = [
+            filter(PartitionCondition::Gt(timestamp(DAY_MS))),
+            filter(PartitionCondition::Lt(timestamp(DAY_MS))),
+        ];
+        assert!(rule.locate_partitions_for_read(&filters).unwrap().is_empty());
+
+        // Eq and In.
+        let filters = [filter(PartitionCondition::Eq(timestamp(2 * DAY_MS)))];
+        assert_eq!(vec![2], rule.locate_partitions_for_read(&filters).unwrap());
+        let filters = [
+            filter(PartitionCondition::In(vec![
+                timestamp(0),
+                timestamp(2 * DAY_MS),
+                timestamp(3 * DAY_MS),
+            ])),
+            filter(PartitionCondition::Lt(timestamp(3 * DAY_MS))),
+        ];
+        assert_eq!(vec![0, 2], rule.locate_partitions_for_read(&filters).unwrap());
+
+        // Unknown datum is skipped.
+        let filters = [
+            filter(PartitionCondition::Eq(Datum::Double(1.0))),
+            filter(PartitionCondition::Lt(timestamp(DAY_MS))),
+        ];
+        assert_eq!(vec![0], rule.locate_partitions_for_read(&filters).unwrap());
+    }
+}
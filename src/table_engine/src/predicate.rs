@@ -20,14 +20,18 @@
 
 use std::{fmt, sync::Arc};
 
+use arrow::{
+    array::{Array, BooleanArray, DictionaryArray, StringArray},
+    datatypes::Int32Type,
+};
 use common_types::{
     schema::Schema,
     time::{TimeRange, Timestamp},
 };
 use datafusion::{
     logical_expr::{
-        expr::{Alias, InList},
-        Expr, Operator,
+        expr::{Alias, Cast, InList, TryCast},
+        BinaryExpr, Expr, Operator,
     },
     scalar::ScalarValue,
 };
@@ -35,6 +39,7 @@ use datafusion_proto::bytes::Serializeable;
 use generic_error::{BoxError, GenericError};
 use logger::debug;
 use macros::define_result;
+use regex::{Regex, RegexBuilder};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 
 #[derive(Debug, Snafu)]
@@ -107,6 +112,19 @@ impl Predicate {
     pub fn time_range(&self) -> TimeRange {
         self.time_range
     }
+
+    /// Whether the exprs only filter on the timestamp column, so the rows they
+    /// select are exactly the rows inside the `time_range`.
+    pub fn is_time_range_only(&self, timestamp_column_name: &str) -> bool {
+        let extractor = TimeRangeExtractor {
+            timestamp_column_name,
+            filters: &self.exprs,
+        };
+
+        self.exprs
+            .iter()
+            .all(|expr| extractor.is_exact_time_filter(expr))
+    }
 }
 
 impl TryFrom<&Predicate> for horaedbproto::remote_engine::Predicate {
@@ -202,6 +220,105 @@ impl PredicateBuilder {
     }
 }
 
+/// Regex match on a single column against a literal pattern, e.g. `tag ~
+/// 'api-.*'`.
+///
+/// The pattern is compiled only once, so it can be evaluated once per distinct
+/// value (e.g. per dictionary entry) rather than once per row.
+#[derive(Debug, Clone)]
+pub struct ColumnRegexMatch {
+    column: String,
+    regex: Regex,
+    negated: bool,
+}
+
+impl ColumnRegexMatch {
+    /// Try to extract the regex match from the `expr`.
+    ///
+    /// Returns `None` if the `expr` is not the form of `column ~ 'pattern'`
+    /// (including the case insensitive and negated variants) or the pattern is
+    /// invalid.
+    pub fn try_from_expr(expr: &Expr) -> Option<Self> {
+        let (left, op, right) = match expr {
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => (left, op, right),
+            _ => return None,
+        };
+
+        let (case_insensitive, negated) = match op {
+            Operator::RegexMatch => (false, false),
+            Operator::RegexIMatch => (true, false),
+            Operator::RegexNotMatch => (false, true),
+            Operator::RegexNotIMatch => (true, true),
+            _ => return None,
+        };
+
+        let column = Self::column_name(left)?;
+        let pattern = match right.as_ref() {
+            Expr::Literal(ScalarValue::Utf8(Some(v)))
+            | Expr::Literal(ScalarValue::LargeUtf8(Some(v))) => v,
+            _ => return None,
+        };
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .ok()?;
+
+        Some(Self {
+            column,
+            regex,
+            negated,
+        })
+    }
+
+    /// The column may be casted to string by the type coercion if it is
+    /// dictionary encoded.
+    fn column_name(expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Column(column) => Some(column.name.clone()),
+            Expr::Cast(Cast { expr, .. }) | Expr::TryCast(TryCast { expr, .. }) => {
+                Self::column_name(expr)
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    #[inline]
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value) != self.negated
+    }
+
+    /// Evaluate the regex on the dictionary encoded `array`.
+    ///
+    /// The regex is evaluated only once for every dictionary value, and the
+    /// result of each row is looked up by its key. Null rows never match.
+    pub fn evaluate_dictionary(&self, array: &DictionaryArray<Int32Type>) -> BooleanArray {
+        let values = array.values();
+        let matched_values: Vec<bool> = match values.as_any().downcast_ref::<StringArray>() {
+            Some(values) => values
+                .iter()
+                .map(|v| v.map(|v| self.is_match(v)).unwrap_or(false))
+                .collect(),
+            None => vec![false; values.len()],
+        };
+
+        array
+            .keys()
+            .iter()
+            .map(|key| {
+                let matched = key
+                    .and_then(|key| matched_values.get(key as usize).copied())
+                    .unwrap_or(false);
+                Some(matched)
+            })
+            .collect()
+    }
+}
+
 /// Extract the time range requirement from expressions.
 struct TimeRangeExtractor<'a> {
     timestamp_column_name: &'a str,
@@ -337,6 +454,30 @@ impl<'a> TimeRangeExtractor<'a> {
         }
     }
 
+    /// Whether the time range extracted from the `expr` is exactly the rows
+    /// selected by it, i.e. the `expr` is a conjunction of comparisons between
+    /// the timestamp column and a timestamp literal.
+    fn is_exact_time_filter(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::BinaryExpr(datafusion::logical_expr::BinaryExpr { left, op, right }) => {
+                match op {
+                    Operator::And => {
+                        self.is_exact_time_filter(left) && self.is_exact_time_filter(right)
+                    }
+                    Operator::Eq
+                    | Operator::Lt
+                    | Operator::LtEq
+                    | Operator::Gt
+                    | Operator::GtEq => self
+                        .timestamp_from_column_and_value_expr(left, right)
+                        .is_some(),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
     /// Extract time range from the between expression.
     fn time_range_from_between_expr(low: &Expr, high: &Expr, negated: bool) -> TimeRange {
         if negated {
@@ -451,16 +592,18 @@ impl<'a> TimeRangeExtractor<'a> {
 
 #[cfg(test)]
 mod tests {
+    use arrow::{array::DictionaryArray, datatypes::Int32Type};
     use common_types::{
         tests::build_schema_with_dictionary,
         time::{TimeRange, Timestamp},
     };
     use datafusion::{
-        prelude::{col, Expr},
+        logical_expr::{binary_expr, cast, Operator},
+        prelude::{col, lit, Expr},
         scalar::ScalarValue,
     };
 
-    use crate::predicate::PredicateBuilder;
+    use crate::predicate::{ColumnRegexMatch, PredicateBuilder};
 
     fn set_timestamp(ts: i64) -> Expr {
         Expr::Literal(ScalarValue::TimestampMillisecond(Some(ts), None))
@@ -584,4 +727,106 @@ mod tests {
             assert_eq!(predict.time_range(), expcted);
         }
     }
+
+    #[test]
+    fn test_time_range_only() {
+        let cases = vec![
+            (vec![], true),
+            (vec![col("key2").gt(set_timestamp(10000))], true),
+            (
+                vec![
+                    col("key2").gt_eq(set_timestamp(10000)),
+                    set_timestamp(20000).gt(col("key2")),
+                ],
+                true,
+            ),
+            (
+                vec![col("key2")
+                    .gt(set_timestamp(10000))
+                    .and(col("key2").lt(set_timestamp(20000)))],
+                true,
+            ),
+            (
+                vec![col("key2")
+                    .gt(set_timestamp(10000))
+                    .or(col("key2").lt(set_timestamp(500)))],
+                false,
+            ),
+            (
+                vec![
+                    col("key2").gt(set_timestamp(10000)),
+                    col("key1").eq(lit("a")),
+                ],
+                false,
+            ),
+            (vec![col("key2").gt(lit(10000))], false),
+        ];
+
+        for (exprs, expected) in cases {
+            let predicate = PredicateBuilder::default()
+                .add_pushdown_exprs(&exprs)
+                .build();
+            assert_eq!(expected, predicate.is_time_range_only("key2"));
+        }
+    }
+
+    #[test]
+    fn test_extract_column_regex_match() {
+        let cases = vec![
+            (
+                binary_expr(col("host"), Operator::RegexMatch, lit("api-.*")),
+                Some(("host", false)),
+            ),
+            (
+                binary_expr(
+                    cast(col("host"), arrow::datatypes::DataType::Utf8),
+                    Operator::RegexNotMatch,
+                    lit("api-.*"),
+                ),
+                Some(("host", true)),
+            ),
+            (
+                binary_expr(col("host"), Operator::RegexMatch, col("ip")),
+                None,
+            ),
+            (
+                binary_expr(col("host"), Operator::RegexMatch, lit("(")),
+                None,
+            ),
+            (col("host").eq(lit("api-1")), None),
+        ];
+
+        for (expr, expected) in cases {
+            let regex_match = ColumnRegexMatch::try_from_expr(&expr);
+            assert_eq!(
+                regex_match.map(|v| (v.column().to_string(), v.negated)),
+                expected.map(|(c, n)| (c.to_string(), n)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_dictionary_regex() {
+        let array: DictionaryArray<Int32Type> = vec![
+            Some("api-1"),
+            Some("web-1"),
+            None,
+            Some("API-2"),
+            Some("api-1"),
+        ]
+        .into_iter()
+        .collect();
+
+        let expr = binary_expr(col("host"), Operator::RegexMatch, lit("^api-"));
+        let regex_match = ColumnRegexMatch::try_from_expr(&expr).unwrap();
+        let matched: Vec<_> = regex_match.evaluate_dictionary(&array).iter().collect();
+        let expected = [true, false, false, false, true].map(Some);
+        assert_eq!(matched, expected);
+
+        let expr = binary_expr(col("host"), Operator::RegexNotIMatch, lit("^api-"));
+        let regex_match = ColumnRegexMatch::try_from_expr(&expr).unwrap();
+        let matched: Vec<_> = regex_match.evaluate_dictionary(&array).iter().collect();
+        let expected = [false, true, false, false, false].map(Some);
+        assert_eq!(matched, expected);
+    }
 }
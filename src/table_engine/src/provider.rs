@@ -27,8 +27,11 @@ use std::{
 
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
-use common_types::{projected_schema::ProjectedSchema, request_id::RequestId, schema::Schema};
+use common_types::{
+    projected_schema::ProjectedSchema, request_id::RequestId, schema::Schema, time::Timestamp,
+};
 use datafusion::{
+    common::stats::Precision,
     config::{ConfigEntry, ConfigExtension, ExtensionOptions},
     datasource::TableProvider,
     error::{DataFusionError, Result},
@@ -42,6 +45,7 @@ use datafusion::{
         DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, PhysicalExpr,
         SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
     },
+    scalar::ScalarValue,
 };
 use df_operator::visitor;
 use logger::debug;
@@ -49,7 +53,10 @@ use runtime::Priority;
 use trace_metric::{collector::FormatCollectorVisitor, MetricsCollector};
 
 use crate::{
+    access_stats,
     predicate::{PredicateBuilder, PredicateRef},
+    query_warning::QueryWarnings,
+    scan_quota::{QuotaStream, ScanQuota},
     stream::{ScanStreamState, ToDfStream},
     table::{ReadOptions, ReadRequest, TableRef},
 };
@@ -187,6 +194,9 @@ pub struct TableProviderAdapter<B> {
 
     /// Table scan builder
     builder: B,
+
+    /// Read the ssts as of this time if set, see [ReadOptions::snapshot_time].
+    snapshot_time: Option<Timestamp>,
 }
 
 impl<B: TableScanBuilder> TableProviderAdapter<B> {
@@ -198,9 +208,15 @@ impl<B: TableScanBuilder> TableProviderAdapter<B> {
             table,
             current_table_schema,
             builder,
+            snapshot_time: None,
         }
     }
 
+    pub fn with_snapshot_time(mut self, snapshot_time: Option<Timestamp>) -> Self {
+        self.snapshot_time = snapshot_time;
+        self
+    }
+
     pub fn as_table_ref(&self) -> &TableRef {
         &self.table
     }
@@ -221,6 +237,13 @@ impl<B: TableScanBuilder> TableProviderAdapter<B> {
             .map(|n| Instant::now() + Duration::from_millis(n));
         let read_parallelism = state.config().target_partitions();
         let priority = options.priority;
+        // The warnings of the scan are recorded into the query's if it's set.
+        let warnings = state
+            .config()
+            .get_extension::<QueryWarnings>()
+            .as_deref()
+            .cloned()
+            .unwrap_or_default();
         debug!(
             "TableProvider scan table, table:{}, request_id:{}, projection:{:?}, filters:{:?}, limit:{:?}, deadline:{:?}, parallelism:{}, priority:{:?}",
             self.table.name(),
@@ -288,6 +311,10 @@ impl<B: TableScanBuilder> TableProviderAdapter<B> {
             deadline,
             read_parallelism,
             batch_size: state.config_options().execution.batch_size,
+            snapshot_time: self.snapshot_time,
+            snapshot_sequence: None,
+            warnings,
+            reverse: false,
         };
 
         // TODO: metrics collector name should relate to detail scan impl?
@@ -369,6 +396,8 @@ impl<B: TableScanBuilder> TableProvider for TableProviderAdapter<B> {
         filters: &[Expr],
         limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
+        access_stats::record_query(self.table.id());
+
         self.scan_table(state, projection, filters, limit).await
     }
 
@@ -432,6 +461,11 @@ impl ScanTable {
         }
     }
 
+    #[inline]
+    pub fn request(&self) -> &ReadRequest {
+        &self.request
+    }
+
     pub async fn maybe_init_stream(&mut self) -> Result<()> {
         let read_res = self.table.partitioned_read(self.request.clone()).await;
 
@@ -444,6 +478,17 @@ impl ScanTable {
 
         Ok(())
     }
+
+    /// Build the scan reading the table in the reverse time order, see
+    /// [ReadOptions::reverse].
+    pub async fn try_to_reverse(&self) -> Result<Self> {
+        let mut request = self.request.clone();
+        request.opts.reverse = true;
+        let mut scan = ScanTable::new(self.table.clone(), request);
+        scan.maybe_init_stream().await?;
+
+        Ok(scan)
+    }
 }
 
 impl ExecutionPlan for ScanTable {
@@ -484,7 +529,7 @@ impl ExecutionPlan for ScanTable {
     fn execute(
         &self,
         partition: usize,
-        _context: Arc<TaskContext>,
+        context: Arc<TaskContext>,
     ) -> Result<DfSendableRecordBatchStream> {
         let mut stream_state = self.stream_state.lock().unwrap();
 
@@ -495,8 +540,12 @@ impl ExecutionPlan for ScanTable {
         }
 
         let stream = stream_state.take_stream(partition)?;
+        let stream = Box::pin(ToDfStream(stream));
 
-        Ok(Box::pin(ToDfStream(stream)))
+        match context.session_config().get_extension::<ScanQuota>() {
+            Some(quota) => Ok(Box::pin(QuotaStream::new(stream, quota))),
+            None => Ok(stream),
+        }
     }
 
     fn metrics(&self) -> Option<MetricsSet> {
@@ -521,8 +570,47 @@ impl ExecutionPlan for ScanTable {
         &self,
     ) -> std::result::Result<datafusion::common::Statistics, datafusion::error::DataFusionError>
     {
-        // TODO(yingwen): Implement this
-        Ok(Statistics::new_unknown(&self.schema()))
+        let schema = self.schema();
+        let mut statistics = Statistics::new_unknown(&schema);
+        let read_statistics = match self.table.read_statistics(&self.request) {
+            Some(v) => v,
+            None => {
+                // Fall back to the estimation by the histograms of the columns.
+                if let Some(num_rows) = self.table.estimate_rows(&self.request) {
+                    statistics.num_rows = Precision::Inexact(num_rows);
+                }
+                return Ok(statistics);
+            }
+        };
+
+        statistics.num_rows = Precision::Exact(read_statistics.num_rows);
+        let timestamp_name = self
+            .request
+            .projected_schema
+            .table_schema()
+            .timestamp_name();
+        if let (Some(time_range), Ok(idx)) =
+            (read_statistics.time_range, schema.index_of(timestamp_name))
+        {
+            let column_statistics = &mut statistics.column_statistics[idx];
+            column_statistics.min_value = Precision::Exact(ScalarValue::TimestampMillisecond(
+                Some(time_range.inclusive_start().as_i64()),
+                None,
+            ));
+            column_statistics.max_value = Precision::Exact(ScalarValue::TimestampMillisecond(
+                Some(time_range.exclusive_end().as_i64() - 1),
+                None,
+            ));
+        }
+
+        debug!(
+            "ScanTable answers statistics from table, table:{}, request_id:{}, statistics:{:?}",
+            self.table.name(),
+            self.request.request_id,
+            statistics
+        );
+
+        Ok(statistics)
     }
 }
 
@@ -535,7 +623,16 @@ impl DisplayAs for ScanTable {
             self.request.opts.read_parallelism,
             self.request.priority,
             self.output_partitioning()
-        )
+        )?;
+        if self.request.opts.reverse {
+            write!(f, ", reverse=true")?;
+        }
+        // Compared with the `output_rows` metric by `EXPLAIN ANALYZE`.
+        if let Some(num_rows) = self.table.estimate_rows(&self.request) {
+            write!(f, ", estimated_rows={num_rows}")?;
+        }
+
+        Ok(())
     }
 }
 
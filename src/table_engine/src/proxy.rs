@@ -17,6 +17,8 @@
 
 //! Table engine proxy
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 
 use crate::{
@@ -59,6 +61,14 @@ impl TableEngine for TableEngineProxy {
         }
     }
 
+    async fn validate_table_options(
+        &self,
+        options: &HashMap<String, String>,
+    ) -> crate::engine::Result<()> {
+        // New tables are created by the analytic engine by default.
+        self.analytic.validate_table_options(options).await
+    }
+
     async fn create_table(&self, request: CreateTableRequest) -> crate::engine::Result<TableRef> {
         match request.params.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.create_table(request).await,
@@ -115,4 +125,9 @@ impl TableEngine for TableEngineProxy {
             engine_type => vec![UnknownEngineType { engine_type }.fail()],
         }
     }
+
+    async fn shrink_caches(&self) -> crate::engine::Result<()> {
+        self.memory.shrink_caches().await?;
+        self.analytic.shrink_caches().await
+    }
 }
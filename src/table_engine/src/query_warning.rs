@@ -0,0 +1,185 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Warnings of the queries, e.g. the results are partial because some corrupt
+//! ssts are skipped, or an implicit limit is applied. The warnings are
+//! recorded into the [QueryWarnings] carried by the context of the query
+//! during the execution, and taken by the handlers of the requests to tell the
+//! clients instead of returning incomplete data silently.
+//!
+//! The warnings recorded on the other nodes, e.g. by the remote scans or the
+//! forwarded queries, are sent back with the responses and merged into the
+//! [QueryWarnings] of the query.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Max number of the warnings kept for one query, the others are dropped.
+const MAX_WARNINGS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// Some data is not read, e.g. the corrupt ssts are skipped.
+    PartialResult,
+    /// Limit not specified by the query is applied, e.g. the columns of the
+    /// wildcard are limited.
+    ImplicitLimit,
+    /// The query is about to time out.
+    ApproachingTimeout,
+    /// Some partitions are scanned again after failures, so they are read later
+    /// than the others and may include the rows written in between.
+    RetriedScan,
+}
+
+impl WarningKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningKind::PartialResult => "partial_result",
+            WarningKind::ImplicitLimit => "implicit_limit",
+            WarningKind::ApproachingTimeout => "approaching_timeout",
+            WarningKind::RetriedScan => "retried_scan",
+        }
+    }
+
+    /// Code of the warning, used by the protocols requiring a numeric code,
+    /// e.g. mysql.
+    pub fn code(&self) -> u16 {
+        match self {
+            WarningKind::PartialResult => 1000,
+            WarningKind::ImplicitLimit => 1001,
+            WarningKind::ApproachingTimeout => 1002,
+            WarningKind::RetriedScan => 1003,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: String) -> Self {
+        Self { kind, message }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.message)
+    }
+}
+
+/// Warnings of one query, shared by all the parts of the query executing it.
+#[derive(Clone, Debug, Default)]
+pub struct QueryWarnings(Arc<Mutex<Vec<Warning>>>);
+
+impl QueryWarnings {
+    /// Record a warning of the query.
+    pub fn record(&self, kind: WarningKind, message: String) {
+        self.extend(vec![Warning::new(kind, message)]);
+    }
+
+    /// Merge the warnings reported by other nodes into the query.
+    pub fn extend(&self, warnings: Vec<Warning>) {
+        let mut current = self.0.lock().unwrap();
+        for warning in warnings {
+            if current.len() >= MAX_WARNINGS {
+                break;
+            }
+            if !current.contains(&warning) {
+                current.push(warning);
+            }
+        }
+    }
+
+    /// Take the warnings of the query, empty if nothing is wrong.
+    pub fn take(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Encode the warnings to be sent to other nodes.
+pub fn encode_warnings(warnings: &[Warning]) -> String {
+    serde_json::to_string(warnings).unwrap_or_default()
+}
+
+/// Decode the warnings encoded by [encode_warnings], empty if they are
+/// invalid.
+pub fn decode_warnings(encoded: &str) -> Vec<Warning> {
+    serde_json::from_str(encoded).unwrap_or_default()
+}
+
+/// Format the warnings into a single line message.
+pub fn format_warnings(warnings: &[Warning]) -> String {
+    warnings
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warning(message: &str) -> Warning {
+        Warning::new(WarningKind::PartialResult, message.to_string())
+    }
+
+    #[test]
+    fn test_query_warnings() {
+        let query_warnings = QueryWarnings::default();
+        query_warnings.record(WarningKind::PartialResult, "a".to_string());
+        // The warnings recorded by the clones are shared.
+        query_warnings
+            .clone()
+            .record(WarningKind::ImplicitLimit, "b".to_string());
+        // The duplicated warnings are ignored, e.g. the same warning reported by
+        // several partitions.
+        query_warnings.extend(vec![warning("a")]);
+        let warnings = query_warnings.take();
+        assert_eq!(
+            "partial_result: a; implicit_limit: b",
+            format_warnings(&warnings)
+        );
+        assert!(query_warnings.take().is_empty());
+
+        let warnings: Vec<_> = (0..MAX_WARNINGS + 1)
+            .map(|i| warning(&i.to_string()))
+            .collect();
+        query_warnings.extend(warnings.clone());
+        assert_eq!(&warnings[..MAX_WARNINGS], &query_warnings.take());
+    }
+
+    #[test]
+    fn test_encode_warnings() {
+        let warnings = vec![
+            warning("a"),
+            Warning::new(WarningKind::ApproachingTimeout, "b".to_string()),
+        ];
+        assert_eq!(warnings, decode_warnings(&encode_warnings(&warnings)));
+        assert!(decode_warnings("invalid").is_empty());
+    }
+}
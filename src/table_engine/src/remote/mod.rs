@@ -30,7 +30,7 @@ use snafu::Snafu;
 use crate::{
     remote::model::{
         AlterTableOptionsRequest, AlterTableSchemaRequest, ExecutePlanRequest, GetTableInfoRequest,
-        TableInfo, WriteBatchResult,
+        TableIdentifier, TableInfo, WriteBatchResult,
     },
     stream::SendableRecordBatchStream,
 };
@@ -55,16 +55,39 @@ pub enum Error {
 
     #[snafu(display("Failed to execute physical plan from remote, err:{}", source))]
     ExecutePhysicalPlan { source: GenericError },
+
+    #[snafu(display("Failed to route tables, err:{}", source))]
+    Route { source: GenericError },
+
+    #[snafu(display("Remote engine is unavailable, err:{}", source))]
+    Unavailable { source: GenericError },
 }
 
 define_result!(Error);
 
+impl Error {
+    /// Whether the request may succeed if it's sent again, e.g. the remote
+    /// node is down or the table is moved to another node.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Unavailable { .. })
+    }
+}
+
 /// Remote table engine interface
 #[async_trait]
 pub trait RemoteEngine: fmt::Debug + Send + Sync {
     /// Read from the remote engine.
     async fn read(&self, request: ReadRequest) -> Result<SendableRecordBatchStream>;
 
+    /// Read from multiple tables of the remote engine, the streams are returned
+    /// in the order of the `requests`.
+    async fn read_batch(
+        &self,
+        requests: Vec<ReadRequest>,
+    ) -> Result<Vec<SendableRecordBatchStream>> {
+        futures::future::try_join_all(requests.into_iter().map(|v| self.read(v))).await
+    }
+
     /// Write to the remote engine.
     async fn write(&self, request: WriteRequest) -> Result<usize>;
 
@@ -80,6 +103,10 @@ pub trait RemoteEngine: fmt::Debug + Send + Sync {
         &self,
         request: ExecutePlanRequest,
     ) -> Result<SendableRecordBatchStream>;
+
+    /// Route the tables, the endpoints of the nodes serving them are returned
+    /// in the order of the `tables`.
+    async fn route(&self, tables: &[TableIdentifier]) -> Result<Vec<String>>;
 }
 
 /// Remote engine reference
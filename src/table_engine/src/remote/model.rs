@@ -43,6 +43,7 @@ use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 
 use crate::{
     partition::PartitionInfo,
+    query_warning::QueryWarnings,
     table::{
         ReadRequest as TableReadRequest, SchemaId, TableId, WriteRequest as TableWriteRequest,
         NO_TIMEOUT,
@@ -417,6 +418,9 @@ pub struct ExecutePlanRequest {
 
     /// Collect metrics of remote plan
     pub remote_metrics: Arc<Mutex<Option<String>>>,
+
+    /// Collect warnings of remote plan
+    pub warnings: QueryWarnings,
 }
 
 impl ExecutePlanRequest {
@@ -426,6 +430,7 @@ impl ExecutePlanRequest {
         context: ExecContext,
         physical_plan: PhysicalPlan,
         remote_metrics: Arc<Mutex<Option<String>>>,
+        warnings: QueryWarnings,
     ) -> Self {
         let remote_request = RemoteExecuteRequest {
             table,
@@ -437,6 +442,7 @@ impl ExecutePlanRequest {
             plan_schema,
             remote_request,
             remote_metrics,
+            warnings,
         }
     }
 }
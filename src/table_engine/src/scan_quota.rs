@@ -0,0 +1,212 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Quota of the table scans of a query
+//!
+//! The quota is set as an extension of the datafusion session config, and it's
+//! shared by all the [ScanTable](crate::provider::ScanTable)s of the query.
+
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
+use datafusion::{
+    error::{DataFusionError, Result},
+    physical_plan::{RecordBatchStream, SendableRecordBatchStream},
+};
+use futures::{Stream, StreamExt};
+
+/// Resources consumed by the table scans of a query.
+#[derive(Debug, Default)]
+pub struct ScanUsage {
+    rows: AtomicU64,
+    bytes: AtomicU64,
+    /// Span of the table scans, from the first scan started to the last scan
+    /// finished.
+    span: Mutex<ScanSpan>,
+}
+
+#[derive(Debug, Default)]
+struct ScanSpan {
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+}
+
+pub type ScanUsageRef = Arc<ScanUsage>;
+
+impl ScanUsage {
+    #[inline]
+    pub fn rows(&self) -> u64 {
+        self.rows.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Wall time of the table scans, zero if no scan is started.
+    ///
+    /// The time is counted until now if some scan is not finished yet.
+    pub fn elapsed(&self) -> Duration {
+        let span = self.span.lock().unwrap();
+        match (span.started_at, span.finished_at) {
+            (Some(started_at), Some(finished_at)) => {
+                finished_at.saturating_duration_since(started_at)
+            }
+            (Some(started_at), None) => started_at.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn on_scan_started(&self) {
+        let mut span = self.span.lock().unwrap();
+        if span.started_at.is_none() {
+            span.started_at = Some(Instant::now());
+        }
+    }
+
+    fn on_scan_finished(&self) {
+        self.span.lock().unwrap().finished_at = Some(Instant::now());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScanQuota {
+    pub usage: ScanUsageRef,
+    /// Max rows to scan, no limit if it's `None`.
+    pub max_rows: Option<u64>,
+    /// Max bytes to scan, no limit if it's `None`.
+    pub max_bytes: Option<u64>,
+}
+
+impl ScanQuota {
+    /// Consume the quota by the scanned rows and bytes, error is returned if
+    /// the quota is exhausted.
+    pub fn consume(&self, rows: u64, bytes: u64) -> Result<()> {
+        let total_rows = self.usage.rows.fetch_add(rows, Ordering::Relaxed) + rows;
+        let total_bytes = self.usage.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+
+        if let Some(max_rows) = self.max_rows {
+            if total_rows > max_rows {
+                return Err(DataFusionError::ResourcesExhausted(format!(
+                    "Query scans too many rows, scanned:{total_rows}, limit:{max_rows}"
+                )));
+            }
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            if total_bytes > max_bytes {
+                return Err(DataFusionError::ResourcesExhausted(format!(
+                    "Query scans too many bytes, scanned:{total_bytes}, limit:{max_bytes}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Stream consuming the [ScanQuota] by the batches polled from it, and it ends
+/// after the quota is exhausted.
+pub struct QuotaStream {
+    inner: SendableRecordBatchStream,
+    quota: Arc<ScanQuota>,
+    exhausted: bool,
+}
+
+impl QuotaStream {
+    pub fn new(inner: SendableRecordBatchStream, quota: Arc<ScanQuota>) -> Self {
+        quota.usage.on_scan_started();
+
+        Self {
+            inner,
+            quota,
+            exhausted: false,
+        }
+    }
+}
+
+impl Stream for QuotaStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.exhausted {
+            return Poll::Ready(None);
+        }
+
+        match self.inner.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(batch))) => {
+                let consumed = self.quota.consume(
+                    batch.num_rows() as u64,
+                    batch.get_array_memory_size() as u64,
+                );
+                match consumed {
+                    Ok(()) => Poll::Ready(Some(Ok(batch))),
+                    Err(e) => {
+                        self.exhausted = true;
+                        self.quota.usage.on_scan_finished();
+                        Poll::Ready(Some(Err(e)))
+                    }
+                }
+            }
+            Poll::Ready(None) => {
+                self.quota.usage.on_scan_finished();
+                Poll::Ready(None)
+            }
+            other => other,
+        }
+    }
+}
+
+impl RecordBatchStream for QuotaStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_quota() {
+        let quota = ScanQuota {
+            max_rows: Some(10),
+            max_bytes: Some(100),
+            ..Default::default()
+        };
+        quota.consume(5, 50).unwrap();
+        quota.consume(5, 50).unwrap();
+        assert_eq!(10, quota.usage.rows());
+        assert_eq!(100, quota.usage.bytes());
+        assert!(quota.consume(1, 0).is_err());
+
+        let quota = ScanQuota {
+            max_bytes: Some(100),
+            ..Default::default()
+        };
+        quota.consume(1000, 100).unwrap();
+        assert!(quota.consume(0, 1).is_err());
+    }
+}
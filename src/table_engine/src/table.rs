@@ -32,9 +32,12 @@ use common_types::{
     column_schema::ColumnSchema,
     datum::Datum,
     projected_schema::ProjectedSchema,
+    record_batch::RecordBatch,
     request_id::RequestId,
     row::{Row, RowGroup},
     schema::{RecordSchemaWithKey, Schema, Version},
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
 };
 use generic_error::{BoxError, GenericError};
 use horaedbproto::sys_catalog as sys_catalog_pb;
@@ -46,8 +49,10 @@ use trace_metric::MetricsCollector;
 
 use crate::{
     engine::TableState,
+    histogram::{ColumnHistograms, Histogram},
     partition::PartitionInfo,
     predicate::PredicateRef,
+    query_warning::QueryWarnings,
     stream::{PartitionedStreams, SendableRecordBatchStream},
 };
 
@@ -122,6 +127,12 @@ pub enum Error {
     #[snafu(display("Failed to compact table, table:{}, err:{}", table, source))]
     Compact { table: String, source: GenericError },
 
+    #[snafu(display("Failed to import data into table, table:{}, err:{}", table, source))]
+    Import { table: String, source: GenericError },
+
+    #[snafu(display("Failed to read changes of table, table:{}, err:{}", table, source))]
+    ReadChanges { table: String, source: GenericError },
+
     #[snafu(display("Failed to convert read request to pb, msg:{}, err:{}", msg, source))]
     ReadRequestToPb { msg: String, source: GenericError },
 
@@ -327,6 +338,18 @@ pub struct ReadOptions {
     pub read_parallelism: usize,
     /// Request deadline
     pub deadline: Option<Instant>,
+    /// Read the ssts of the table as of this time instead of the latest data,
+    /// the data still in memtables is not visible to such read.
+    pub snapshot_time: Option<Timestamp>,
+    /// Read the table as of this sequence, the rows written after it are not
+    /// visible. The read fails if such rows are already flushed, and it can be
+    /// retried with a newer sequence.
+    pub snapshot_sequence: Option<SequenceNumber>,
+    /// Warnings of the query this read belongs to.
+    pub warnings: QueryWarnings,
+    /// Read the time-aligned segments of the table from the newest one to the
+    /// oldest one, the rows of a segment are still in ascending order.
+    pub reverse: bool,
 }
 
 impl Default for ReadOptions {
@@ -335,6 +358,10 @@ impl Default for ReadOptions {
             batch_size: 10000,
             read_parallelism: DEFAULT_READ_PARALLELISM,
             deadline: None,
+            snapshot_time: None,
+            snapshot_sequence: None,
+            warnings: QueryWarnings::default(),
+            reverse: false,
         }
     }
 }
@@ -349,6 +376,10 @@ impl From<horaedbproto::remote_engine::ReadOptions> for ReadOptions {
             } else {
                 Some(Instant::now() + Duration::from_millis(pb.timeout_ms as u64))
             },
+            snapshot_time: None,
+            snapshot_sequence: None,
+            warnings: QueryWarnings::default(),
+            reverse: false,
         }
     }
 }
@@ -503,6 +534,49 @@ impl Default for FlushRequest {
     }
 }
 
+/// Request to import the data into the table directly.
+#[derive(Debug)]
+pub struct ImportRequest {
+    /// The batches to import, whose schema must be the same as the table
+    /// schema.
+    pub batches: Vec<RecordBatch>,
+}
+
+/// Request to read the changes written into the table.
+#[derive(Debug, Clone)]
+pub struct ReadChangesRequest {
+    /// The consumer reading the changes, the changes after the last read
+    /// sequence are retained for the consumer for a while even if they are
+    /// flushed.
+    pub consumer: String,
+    /// The changes written after the sequence are read, `None` to start from
+    /// the last sequence of the table.
+    pub after: Option<SequenceNumber>,
+    /// Max number of the rows to read, but at least one change is read if
+    /// any.
+    pub max_rows: usize,
+}
+
+/// Changes written into the table.
+#[derive(Debug, Default)]
+pub struct TableChanges {
+    /// The changes are written after the sequence.
+    pub after: SequenceNumber,
+    /// Changes ordered by the sequence.
+    pub changes: Vec<TableChange>,
+    /// Some of the changes after `after` are not available anymore, e.g. they
+    /// are flushed and removed from the wal, and the consumer should start
+    /// again from a new snapshot.
+    pub expired: bool,
+}
+
+/// Rows written into the table by one write.
+#[derive(Debug)]
+pub struct TableChange {
+    pub sequence: SequenceNumber,
+    pub rows: RecordBatch,
+}
+
 /// Table abstraction
 ///
 /// We do not let Table trait extends datafusion's TableProvider, since
@@ -532,6 +606,55 @@ pub trait Table: std::fmt::Debug {
     /// Get table's statistics.
     fn stats(&self) -> TableStats;
 
+    /// Bytes of the data waiting to be compacted, by which the writes to the
+    /// table are throttled.
+    fn compaction_debt(&self) -> u64 {
+        0
+    }
+
+    /// Get the exact statistics of the rows the `request` would read, if the
+    /// table can answer it without scanning.
+    ///
+    /// Returns `None` if the statistics are unknown or inexact, and the caller
+    /// should fall back to scan.
+    fn read_statistics(&self, _request: &ReadRequest) -> Option<ReadStatistics> {
+        None
+    }
+
+    /// Estimate the number of the rows the `request` would read by the
+    /// histograms of the columns.
+    ///
+    /// Returns `None` if the table can't estimate it.
+    fn estimate_rows(&self, _request: &ReadRequest) -> Option<usize> {
+        None
+    }
+
+    /// Get the histograms of the columns built by `ANALYZE TABLE`.
+    fn histograms(&self) -> Arc<ColumnHistograms> {
+        Arc::default()
+    }
+
+    /// Pick the time ranges covering about `max_rows` rows randomly, so the
+    /// histograms can be built without scanning the whole table.
+    ///
+    /// Returns `None` if the whole table should be scanned.
+    fn sample_time_ranges(&self, _max_rows: usize) -> Option<Vec<TimeRange>> {
+        None
+    }
+
+    /// Update the histograms of the columns, the histograms of the columns
+    /// mapped to `None` are dropped.
+    async fn update_histograms(
+        &self,
+        _histograms: HashMap<String, Option<Histogram>>,
+    ) -> Result<()> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "update_histograms",
+        }
+        .fail()
+    }
+
     /// Whether the columns used in filter expr can be pushdown.
     ///
     /// `read_schema` is used here to avoid upper layer see different schema
@@ -541,6 +664,18 @@ pub trait Table: std::fmt::Debug {
     /// Write to table.
     async fn write(&self, request: WriteRequest) -> Result<usize>;
 
+    /// Write to table, returns the number of the written rows and the
+    /// sequence the write is committed at.
+    ///
+    /// The sequence is `None` if the table doesn't track the sequences.
+    async fn write_with_sequence(
+        &self,
+        request: WriteRequest,
+    ) -> Result<(usize, Option<SequenceNumber>)> {
+        let num_rows = self.write(request).await?;
+        Ok((num_rows, None))
+    }
+
     /// Read from table.
     async fn read(&self, request: ReadRequest) -> Result<SendableRecordBatchStream>;
 
@@ -561,11 +696,57 @@ pub trait Table: std::fmt::Debug {
     /// Returns the affected rows (always 0).
     async fn alter_options(&self, options: HashMap<String, String>) -> Result<usize>;
 
+    /// Validate the request like [Table::alter_schema] does, without altering
+    /// the schema.
+    fn validate_alter_schema(&self, _request: &AlterSchemaRequest) -> Result<()> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "validate_alter_schema",
+        }
+        .fail()
+    }
+
+    /// Validate the options like [Table::alter_options] does, without altering
+    /// them.
+    ///
+    /// Returns the options of the table after the alter.
+    fn validate_alter_options(
+        &self,
+        _options: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "validate_alter_options",
+        }
+        .fail()
+    }
+
     /// Flush this table.
     async fn flush(&self, request: FlushRequest) -> Result<()>;
 
     /// Compact this table and wait until compaction completes.
     async fn compact(&self) -> Result<()>;
+
+    /// Import the data into this table directly, bypassing the wal and the
+    /// memtable.
+    ///
+    /// Returns the number of the imported rows.
+    async fn import(&self, _request: ImportRequest) -> Result<usize> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "import",
+        }
+        .fail()
+    }
+
+    /// Read the changes written into the table in the order of the sequences.
+    async fn read_changes(&self, _request: ReadChangesRequest) -> Result<TableChanges> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "read_changes",
+        }
+        .fail()
+    }
 }
 
 /// Basic statistics of table.
@@ -579,6 +760,15 @@ pub struct TableStats {
     pub num_flush: u64,
 }
 
+/// Exact statistics of the rows a read request would return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadStatistics {
+    /// Number of rows
+    pub num_rows: usize,
+    /// Time range covered by the rows, `None` if there is no row.
+    pub time_range: Option<TimeRange>,
+}
+
 /// A reference-counted pointer to Table
 pub type TableRef = Arc<dyn Table + Send + Sync>;
 
@@ -0,0 +1,596 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Framework of the background tasks
+//!
+//! The background work of the engines (flush, compaction, migration and purge
+//! of the ssts, continuous queries, sources, imports) is run through the
+//! [TaskRegistry] instead of being spawned directly, so every task has an id,
+//! a state, a retry policy and a concurrency group. The registry is shared by
+//! the [EngineRuntimes](crate::engine::EngineRuntimes). The tasks are queryable as the
+//! `system.public.tasks` table, which makes the wedged ones visible.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::Display,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use future_ext::{Backoff, BackoffConfig};
+use futures::FutureExt;
+use logger::warn;
+use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+pub type TaskId = u64;
+
+/// Kind of the background task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    Flush,
+    Compaction,
+    CompactionSchedule,
+    Migration,
+    Recompression,
+    FilePurge,
+    ContinuousQuery,
+    ContinuousQuerySync,
+    ReplicaCheck,
+    UserRefresh,
+    JsonMappingRefresh,
+    SourceSync,
+    SourceConsume,
+    HotspotRecord,
+    Import,
+    WriteTee,
+}
+
+impl TaskKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskKind::Flush => "flush",
+            TaskKind::Compaction => "compaction",
+            TaskKind::CompactionSchedule => "compaction_schedule",
+            TaskKind::Migration => "migration",
+            TaskKind::Recompression => "recompression",
+            TaskKind::FilePurge => "file_purge",
+            TaskKind::ContinuousQuery => "continuous_query",
+            TaskKind::ContinuousQuerySync => "continuous_query_sync",
+            TaskKind::ReplicaCheck => "replica_check",
+            TaskKind::UserRefresh => "user_refresh",
+            TaskKind::JsonMappingRefresh => "json_mapping_refresh",
+            TaskKind::SourceSync => "source_sync",
+            TaskKind::SourceConsume => "source_consume",
+            TaskKind::HotspotRecord => "hotspot_record",
+            TaskKind::Import => "import",
+            TaskKind::WriteTee => "write_tee",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    /// Waiting for the permit of its concurrency group.
+    Pending,
+    Running,
+    /// Waiting for the backoff before the next attempt.
+    Retrying,
+    Succeeded,
+    Failed,
+    /// Dropped before finished, e.g. aborted or the runtime is shut down.
+    Cancelled,
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskState::Pending => "pending",
+            TaskState::Running => "running",
+            TaskState::Retrying => "retrying",
+            TaskState::Succeeded => "succeeded",
+            TaskState::Failed => "failed",
+            TaskState::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Retry policy of the failed attempts of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub init_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// The failed task is never retried.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_retries: 0,
+        init_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+}
+
+/// What to run and how to run it.
+#[derive(Debug, Clone)]
+pub struct TaskSpec {
+    pub kind: TaskKind,
+    /// What the task works on, e.g. the name of the table.
+    pub target: String,
+    /// Concurrency group of the task, which is the kind by default.
+    pub group: String,
+    pub retry: RetryPolicy,
+}
+
+impl TaskSpec {
+    pub fn new(kind: TaskKind, target: impl Into<String>) -> Self {
+        Self {
+            kind,
+            target: target.into(),
+            group: kind.as_str().to_string(),
+            retry: RetryPolicy::NONE,
+        }
+    }
+
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = group.into();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub kind: TaskKind,
+    pub target: String,
+    pub group: String,
+    pub state: TaskState,
+    /// Number of the started attempts.
+    pub attempts: usize,
+    /// Milliseconds since the unix epoch.
+    pub create_time: i64,
+    pub start_time: Option<i64>,
+    pub finish_time: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Max number of the finished tasks kept for the inspection.
+    pub history_capacity: usize,
+    /// Max number of the running tasks of the concurrency groups, and the
+    /// groups not listed are unlimited.
+    pub group_concurrency: HashMap<String, usize>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            history_capacity: 1000,
+            group_concurrency: HashMap::new(),
+        }
+    }
+}
+
+/// Output of the tracked tasks, which tells whether the task failed.
+pub trait TaskOutput {
+    fn error_message(&self) -> Option<String>;
+}
+
+impl TaskOutput for () {
+    fn error_message(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<T, E: Display> TaskOutput for Result<T, E> {
+    fn error_message(&self) -> Option<String> {
+        self.as_ref().err().map(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tasks {
+    active: BTreeMap<TaskId, TaskInfo>,
+    finished: VecDeque<TaskInfo>,
+}
+
+pub type TaskRegistryRef = Arc<TaskRegistry>;
+
+#[derive(Debug)]
+pub struct TaskRegistry {
+    history_capacity: usize,
+    next_id: AtomicU64,
+    tasks: Mutex<Tasks>,
+    groups: HashMap<String, Arc<Semaphore>>,
+}
+
+impl TaskRegistry {
+    pub fn new(config: &Config) -> Self {
+        let groups = config
+            .group_concurrency
+            .iter()
+            .map(|(group, limit)| (group.clone(), Arc::new(Semaphore::new((*limit).max(1)))))
+            .collect();
+
+        Self {
+            history_capacity: config.history_capacity,
+            next_id: AtomicU64::new(1),
+            tasks: Mutex::new(Tasks::default()),
+            groups,
+        }
+    }
+
+    /// Run the task, the failed attempts are retried by its retry policy and
+    /// the error of the last attempt is returned.
+    pub async fn run<F, Fut, T, E>(&self, spec: TaskSpec, f: F) -> Result<T, E>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Display,
+    {
+        let mut guard = self.register(&spec);
+        let _permit = self.acquire_permit(&spec.group).await;
+
+        let mut backoff = None;
+        loop {
+            guard.start_attempt();
+            match f().await {
+                Ok(v) => {
+                    guard.finish(TaskState::Succeeded, None);
+                    return Ok(v);
+                }
+                Err(e) if guard.attempts > spec.retry.max_retries => {
+                    guard.finish(TaskState::Failed, Some(e.to_string()));
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!(
+                        "Background task failed and will be retried, id:{}, kind:{}, target:{}, \
+                         attempts:{}, err:{e}",
+                        guard.id,
+                        spec.kind.as_str(),
+                        spec.target,
+                        guard.attempts
+                    );
+                    guard.retry(e.to_string());
+                    let delay = if spec.retry.init_backoff.is_zero() {
+                        Duration::ZERO
+                    } else {
+                        backoff
+                            .get_or_insert_with(|| {
+                                Backoff::new(&BackoffConfig {
+                                    init_backoff: spec.retry.init_backoff,
+                                    max_backoff: spec.retry.max_backoff,
+                                    base: 2.,
+                                })
+                            })
+                            .next()
+                    };
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Track the long running task which is never retried, such as the loop of
+    /// a background worker. The task is marked as failed if it returns an
+    /// error or panics.
+    pub async fn track<Fut>(&self, spec: TaskSpec, fut: Fut) -> Fut::Output
+    where
+        Fut: Future,
+        Fut::Output: TaskOutput,
+    {
+        let mut guard = self.register(&spec);
+        let _permit = self.acquire_permit(&spec.group).await;
+
+        guard.start_attempt();
+        match AssertUnwindSafe(fut).catch_unwind().await {
+            Ok(output) => {
+                match output.error_message() {
+                    Some(err) => guard.finish(TaskState::Failed, Some(err)),
+                    None => guard.finish(TaskState::Succeeded, None),
+                }
+                output
+            }
+            Err(panic) => {
+                let err = panic
+                    .downcast_ref::<&str>()
+                    .map(|msg| msg.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "panicked".to_string());
+                guard.finish(TaskState::Failed, Some(format!("panicked: {err}")));
+                std::panic::resume_unwind(panic)
+            }
+        }
+    }
+
+    /// Spawn the task on the `runtime`, and run it by the registry.
+    pub fn spawn<F, Fut, T, E>(
+        self: &Arc<Self>,
+        runtime: &Runtime,
+        spec: TaskSpec,
+        f: F,
+    ) -> JoinHandle<Result<T, E>>
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send,
+        T: Send + 'static,
+        E: Display + Send + 'static,
+    {
+        let registry = self.clone();
+        runtime.spawn(async move { registry.run(spec, f).await })
+    }
+
+    /// Spawn the long running task on the `runtime`, and track it by the
+    /// registry.
+    pub fn spawn_tracked<Fut>(
+        self: &Arc<Self>,
+        runtime: &Runtime,
+        spec: TaskSpec,
+        fut: Fut,
+    ) -> JoinHandle<Fut::Output>
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: TaskOutput + Send + 'static,
+    {
+        let registry = self.clone();
+        runtime.spawn(async move { registry.track(spec, fut).await })
+    }
+
+    /// List the unfinished tasks ordered by their ids, followed by the recent
+    /// finished ones ordered by their finish time.
+    pub fn list(&self) -> Vec<TaskInfo> {
+        let tasks = self.tasks.lock().unwrap();
+        tasks
+            .active
+            .values()
+            .chain(tasks.finished.iter())
+            .cloned()
+            .collect()
+    }
+
+    fn register(&self, spec: &TaskSpec) -> TaskGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let info = TaskInfo {
+            id,
+            kind: spec.kind,
+            target: spec.target.clone(),
+            group: spec.group.clone(),
+            state: TaskState::Pending,
+            attempts: 0,
+            create_time: time_ext::current_time_millis() as i64,
+            start_time: None,
+            finish_time: None,
+            last_error: None,
+        };
+        self.tasks.lock().unwrap().active.insert(id, info);
+
+        TaskGuard {
+            registry: self,
+            id,
+            attempts: 0,
+            finished: false,
+        }
+    }
+
+    async fn acquire_permit(&self, group: &str) -> Option<tokio::sync::SemaphorePermit<'_>> {
+        match self.groups.get(group) {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("semaphore of the group is never closed"),
+            ),
+            None => None,
+        }
+    }
+
+    fn update(&self, id: TaskId, f: impl FnOnce(&mut TaskInfo)) {
+        if let Some(info) = self.tasks.lock().unwrap().active.get_mut(&id) {
+            f(info);
+        }
+    }
+
+    fn finish(&self, id: TaskId, state: TaskState, err: Option<String>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some(mut info) = tasks.active.remove(&id) else {
+            return;
+        };
+        info.state = state;
+        info.finish_time = Some(time_ext::current_time_millis() as i64);
+        if err.is_some() {
+            info.last_error = err;
+        }
+
+        if self.history_capacity == 0 {
+            return;
+        }
+        if tasks.finished.len() >= self.history_capacity {
+            tasks.finished.pop_front();
+        }
+        tasks.finished.push_back(info);
+    }
+}
+
+/// The task is marked as cancelled if it's dropped before finished.
+struct TaskGuard<'a> {
+    registry: &'a TaskRegistry,
+    id: TaskId,
+    attempts: usize,
+    finished: bool,
+}
+
+impl TaskGuard<'_> {
+    fn start_attempt(&mut self) {
+        self.attempts += 1;
+        let attempts = self.attempts;
+        self.registry.update(self.id, |info| {
+            info.state = TaskState::Running;
+            info.attempts = attempts;
+            info.start_time.get_or_insert(time_ext::current_time_millis() as i64);
+        });
+    }
+
+    fn retry(&self, err: String) {
+        self.registry.update(self.id, |info| {
+            info.state = TaskState::Retrying;
+            info.last_error = Some(err);
+        });
+    }
+
+    fn finish(&mut self, state: TaskState, err: Option<String>) {
+        self.finished = true;
+        self.registry.finish(self.id, state, err);
+    }
+}
+
+impl Drop for TaskGuard<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.registry.finish(self.id, TaskState::Cancelled, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    fn fast_retry(max_retries: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            init_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_with_retry() {
+        let registry = TaskRegistry::new(&Config::default());
+        let runs = AtomicUsize::new(0);
+        let spec = TaskSpec::new(TaskKind::Flush, "t1").with_retry(fast_retry(3));
+        let res = registry
+            .run(spec, || {
+                let failed = runs.fetch_add(1, Ordering::Relaxed) < 2;
+                async move {
+                    if failed {
+                        return Err("io error");
+                    }
+                    Ok(10)
+                }
+            })
+            .await;
+        assert_eq!(Ok(10), res);
+
+        let spec = TaskSpec::new(TaskKind::Compaction, "t2").with_retry(fast_retry(1));
+        let res: Result<(), _> = registry.run(spec, || async { Err("corrupt") }).await;
+        assert!(res.is_err());
+
+        let tasks = registry.list();
+        assert_eq!(2, tasks.len());
+        assert_eq!(TaskState::Succeeded, tasks[0].state);
+        assert_eq!(3, tasks[0].attempts);
+        assert_eq!(Some("io error".to_string()), tasks[0].last_error);
+        assert_eq!(TaskState::Failed, tasks[1].state);
+        assert_eq!(2, tasks[1].attempts);
+        assert_eq!(Some("corrupt".to_string()), tasks[1].last_error);
+    }
+
+    #[tokio::test]
+    async fn test_track_and_cancel() {
+        let config = Config {
+            history_capacity: 1,
+            group_concurrency: HashMap::from([("flush".to_string(), 1)]),
+        };
+        let registry = Arc::new(TaskRegistry::new(&config));
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let running = {
+            let registry = registry.clone();
+            tokio::spawn(async move {
+                let spec = TaskSpec::new(TaskKind::Flush, "t1");
+                registry.track(spec, rx).await
+            })
+        };
+        let pending = {
+            let registry = registry.clone();
+            tokio::spawn(async move {
+                let spec = TaskSpec::new(TaskKind::Flush, "t2");
+                registry.track(spec, async {}).await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The second task waits for the permit of the group.
+        let tasks = registry.list();
+        assert_eq!(TaskState::Running, tasks[0].state);
+        assert_eq!(TaskState::Pending, tasks[1].state);
+
+        pending.abort();
+        let _ = pending.await;
+        let tasks = registry.list();
+        assert_eq!(2, tasks.len());
+        assert_eq!(TaskState::Cancelled, tasks[1].state);
+
+        tx.send(()).unwrap();
+        running.await.unwrap().unwrap();
+        let tasks = registry.list();
+        assert_eq!(1, tasks.len());
+        assert_eq!("t1", tasks[0].target);
+        assert_eq!(TaskState::Succeeded, tasks[0].state);
+    }
+
+    #[tokio::test]
+    async fn test_track_failure() {
+        let registry = TaskRegistry::new(&Config::default());
+        let spec = TaskSpec::new(TaskKind::Compaction, "t1");
+        let res: Result<(), _> = registry.track(spec, async { Err("disk full") }).await;
+        assert!(res.is_err());
+
+        let spec = TaskSpec::new(TaskKind::FilePurge, "t2");
+        let res = AssertUnwindSafe(registry.track(spec, async { panic!("invalid file") }))
+            .catch_unwind()
+            .await;
+        assert!(res.is_err());
+
+        let tasks = registry.list();
+        assert_eq!(2, tasks.len());
+        assert_eq!(TaskState::Failed, tasks[0].state);
+        assert_eq!(Some("disk full".to_string()), tasks[0].last_error);
+        assert_eq!(TaskState::Failed, tasks[1].state);
+        assert_eq!(
+            Some("panicked: invalid file".to_string()),
+            tasks[1].last_error
+        );
+    }
+}
@@ -0,0 +1,74 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Trace of the sampled write requests.
+//!
+//! The trace is carried by the task executing the write, so the stages inside
+//! the engines can be recorded without changing the write interfaces. Writes
+//! executed by other tasks, e.g. the coalesced or forwarded ones, are not
+//! covered by the trace.
+
+use std::{future::Future, time::Duration};
+
+use trace_metric::{Metric, MetricsCollector};
+
+tokio::task_local! {
+    static WRITE_TRACE: MetricsCollector;
+}
+
+/// Run the write future with the trace, and the stages recorded during it are
+/// collected into the `collector`.
+pub async fn with_trace<F: Future>(collector: MetricsCollector, f: F) -> F::Output {
+    WRITE_TRACE.scope(collector, f).await
+}
+
+/// Whether the current write is traced.
+pub fn is_traced() -> bool {
+    WRITE_TRACE.try_with(|_| ()).is_ok()
+}
+
+/// Record the elapsed time of the stage if the current write is traced.
+pub fn record_duration(stage: &str, duration: Duration) {
+    let _ = WRITE_TRACE.try_with(|collector| {
+        collector.collect(Metric::duration(stage.to_string(), duration, None))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use trace_metric::collector::FormatCollectorVisitor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_in_trace() {
+        // Nothing happens outside of the trace.
+        record_duration("outside", Duration::from_millis(1));
+        assert!(!is_traced());
+
+        let collector = MetricsCollector::new("write".to_string());
+        with_trace(collector.clone(), async {
+            assert!(is_traced());
+            record_duration("wal", Duration::from_millis(10));
+        })
+        .await;
+
+        let mut visitor = FormatCollectorVisitor::default();
+        collector.visit(&mut visitor);
+        assert_eq!("write:\n    wal=10ms\n", visitor.into_string());
+    }
+}
@@ -0,0 +1,185 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A cli to simulate compaction of a table with the given table options, and
+//! report the write amplification, sst count and space usage over time.
+
+use std::{collections::HashMap, sync::Arc};
+
+use analytic_engine::{
+    simulator::{SimulationOptions, Simulator},
+    sst::{file::FileMeta, meta_data::cache::MetaData, parquet::async_reader::ChunkReaderAdapter},
+    table_options::{StorageFormat, TableOptions},
+};
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use futures::StreamExt;
+use object_store::{LocalFileSystem, ObjectStoreRef};
+use parquet_ext::meta_data::fetch_parquet_metadata;
+use runtime::Runtime;
+use size_ext::ReadableSize;
+use time_ext::{format_as_ymdhms, ReadableDuration};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// SST directory of the table, empty means starting from an empty table
+    #[clap(short, long)]
+    dir: Option<String>,
+
+    /// Table options to simulate, eg: compaction_strategy=size_tiered
+    #[clap(short, long = "option")]
+    options: Vec<String>,
+
+    /// Number of flushes to simulate
+    #[clap(short, long, default_value_t = 100)]
+    flushes: usize,
+
+    /// Time range covered by a flushed sst
+    #[clap(long, default_value = "15m")]
+    flush_interval: ReadableDuration,
+
+    /// Size of a flushed sst
+    #[clap(long, default_value = "32MB")]
+    flush_size: ReadableSize,
+
+    /// Row number of a flushed sst
+    #[clap(long, default_value_t = 1_000_000)]
+    flush_rows: u64,
+
+    /// Print the state after every flush
+    #[clap(short, long, required(false))]
+    verbose: bool,
+}
+
+fn new_runtime() -> Runtime {
+    runtime::Builder::default()
+        .thread_name("compaction-sim")
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    let args = Args::parse();
+    let rt = Arc::new(new_runtime());
+    rt.block_on(async move {
+        if let Err(e) = run(args).await {
+            eprintln!("Run failed, err:{e}");
+        }
+    });
+}
+
+async fn run(args: Args) -> Result<()> {
+    let mut options = HashMap::with_capacity(args.options.len());
+    for option in &args.options {
+        let (k, v) = option
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid table option, option:{option}"))?;
+        options.insert(k.trim().to_string(), v.trim().to_string());
+    }
+    let table_opts = TableOptions::from_map(&options, true).context("parse table options")?;
+
+    let files = match &args.dir {
+        Some(dir) => load_files(dir).await?,
+        None => Vec::new(),
+    };
+    let initial_num = files.len();
+    let initial_size: u64 = files.iter().map(|f| f.size).sum();
+
+    let opts = SimulationOptions {
+        table_opts,
+        num_flushes: args.flushes,
+        flush_interval: args.flush_interval.0,
+        flush_size: args.flush_size.as_byte(),
+        flush_rows: args.flush_rows,
+    };
+    let report = Simulator::new(opts, files)
+        .run()
+        .context("simulate compaction")?;
+
+    if args.verbose {
+        for (i, step) in report.steps.iter().enumerate() {
+            println!(
+                "flush:{}, time:{}, compactions:{}, ssts:{}, size:{:.3}M",
+                i + 1,
+                format_as_ymdhms(step.time.as_i64()),
+                step.num_compactions,
+                step.num_ssts,
+                as_mb(step.total_size)
+            );
+        }
+    }
+
+    let (final_num, final_size) = report
+        .steps
+        .last()
+        .map(|step| (step.num_ssts, step.total_size))
+        .unwrap_or((initial_num, initial_size));
+    println!(
+        "SimulationReport {{\n\tinitial_ssts: {},\n\tinitial_size: {:.2}M,\n\tflushed: {:.2}M,\n\tcompacted: {:.2}M,\n\texpired: {:.2}M,\n\tcompactions: {},\n\twrite_amplification: {:.2},\n\tmax_ssts: {},\n\tmax_size: {:.2}M,\n\tfinal_ssts: {},\n\tfinal_size: {:.2}M,\n}}",
+        initial_num,
+        as_mb(initial_size),
+        as_mb(report.flushed_bytes),
+        as_mb(report.compacted_bytes),
+        as_mb(report.expired_bytes),
+        report.num_compactions,
+        report.write_amplification(),
+        report.max_num_ssts,
+        as_mb(report.max_total_size),
+        final_num,
+        as_mb(final_size),
+    );
+
+    Ok(())
+}
+
+/// Load the meta of all the ssts under the dir.
+async fn load_files(dir: &str) -> Result<Vec<FileMeta>> {
+    let storage = LocalFileSystem::new_with_prefix(dir)?;
+    let storage: ObjectStoreRef = Arc::new(storage);
+
+    let mut files = Vec::new();
+    let mut ssts = storage.list(None).await?;
+    while let Some(object_meta) = ssts.next().await {
+        let object_meta = object_meta?;
+        let reader = ChunkReaderAdapter::new(&object_meta.location, &storage);
+        let (parquet_metadata, _) = fetch_parquet_metadata(object_meta.size, &reader)
+            .await
+            .with_context(|| format!("fetch metadata of {}", object_meta.location))?;
+        let num_rows = parquet_metadata.file_metadata().num_rows();
+        let meta_data = MetaData::try_new(&parquet_metadata, false, storage.clone()).await?;
+        let custom = meta_data.custom();
+
+        files.push(FileMeta {
+            id: files.len() as u64,
+            size: object_meta.size as u64,
+            row_num: num_rows as u64,
+            time_range: custom.time_range,
+            max_seq: custom.max_sequence,
+            storage_format: StorageFormat::default(),
+            associated_files: Vec::new(),
+        });
+    }
+
+    Ok(files)
+}
+
+fn as_mb(v: u64) -> f64 {
+    v as f64 / 1024.0 / 1024.0
+}
@@ -0,0 +1,213 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A cli to migrate the data of InfluxDB or Prometheus into HoraeDB.
+//!
+//! The source data is converted to the parquet files of the tables by the
+//! mapping rules first, then the tables are created and the files are
+//! imported through the http api of the server. The files are not uploaded,
+//! so the output dir must be readable by the server at the same path, eg: run
+//! it on the server host or put the output dir on a shared file system.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use futures::{stream, StreamExt, TryStreamExt};
+use runtime::Runtime;
+use tools::migrate::{
+    client::{Credentials, ImportClient},
+    influxdb::{self, Precision},
+    mapping::{Mapper, MappingConfig},
+    prometheus, ConvertedTable, Converter,
+};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Rules to map the measurements or metrics to the tables, in toml
+    #[clap(short, long)]
+    mapping: Option<String>,
+
+    /// Dir to write the converted parquet files, it must be readable by the
+    /// server at the same path
+    #[clap(short, long, required(true))]
+    output_dir: String,
+
+    /// Max number of rows of a converted file
+    #[clap(long, default_value_t = 1_000_000)]
+    rows_per_file: usize,
+
+    /// Http endpoint of the server, eg: http://127.0.0.1:5440, and the files
+    /// are converted only if not set
+    #[clap(short, long)]
+    endpoint: Option<String>,
+
+    /// Schema of the tables
+    #[clap(short, long)]
+    schema: Option<String>,
+
+    /// User to authenticate to the server, with `--password`
+    #[clap(long, requires("password"), conflicts_with("token"))]
+    user: Option<String>,
+
+    /// Password of the `--user`
+    #[clap(long, requires("user"))]
+    password: Option<String>,
+
+    /// Token to authenticate to the server
+    #[clap(long)]
+    token: Option<String>,
+
+    /// Max number of rows of an imported sst
+    #[clap(long, default_value_t = 100_000)]
+    rows_per_sst: usize,
+
+    /// Number of the files imported concurrently
+    #[clap(long, default_value_t = 4)]
+    parallelism: usize,
+
+    #[clap(subcommand)]
+    source: Source,
+}
+
+#[derive(Subcommand, Debug)]
+enum Source {
+    /// Import the InfluxDB line protocol files, eg: exported by
+    /// `influx_inspect export`
+    Influxdb {
+        /// Precision of the timestamps
+        #[clap(long, value_enum, default_value = "ns")]
+        precision: Precision,
+
+        #[clap(required(true))]
+        files: Vec<String>,
+    },
+    /// Import the Prometheus TSDB blocks, or the data dirs of the blocks
+    Prometheus {
+        #[clap(required(true))]
+        paths: Vec<PathBuf>,
+    },
+}
+
+fn new_runtime(thread_num: usize) -> Runtime {
+    runtime::Builder::default()
+        .thread_name("tools")
+        .worker_threads(thread_num)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(args) {
+        eprintln!("Import failed, err:{e:?}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let config = match &args.mapping {
+        Some(path) => {
+            let mut toml_buf = String::new();
+            toml_ext::parse_toml_from_path(path, &mut toml_buf)?
+        }
+        None => MappingConfig::default(),
+    };
+    let mapper = Mapper::try_new(config)?;
+    let timestamp_column = mapper.timestamp_column().to_string();
+    let mut converter =
+        Converter::try_new(mapper, PathBuf::from(&args.output_dir), args.rows_per_file)?;
+
+    match &args.source {
+        Source::Influxdb { precision, files } => {
+            for file in files {
+                let num_points = influxdb::read_file(file, *precision, |p| converter.add(p))?;
+                println!("Read file, path:{file}, points:{num_points}");
+            }
+        }
+        Source::Prometheus { paths } => {
+            for path in paths {
+                for block in prometheus::find_blocks(path)? {
+                    let stats = prometheus::read_block(&block, |p| converter.add(p))
+                        .with_context(|| format!("read block:{block:?}"))?;
+                    println!("Read block, path:{block:?}, stats:{stats:?}");
+                }
+            }
+        }
+    }
+
+    let num_skipped = converter.num_skipped();
+    let tables = converter.finish()?;
+    for table in &tables {
+        println!(
+            "Converted table:{}, rows:{}, files:{}",
+            table.name,
+            table.num_rows,
+            table.files.len()
+        );
+    }
+    println!("Skipped points:{num_skipped}");
+
+    let Some(endpoint) = &args.endpoint else {
+        return Ok(());
+    };
+    let credentials = match (&args.user, &args.password, &args.token) {
+        (Some(user), Some(password), _) => Some(Credentials::Basic {
+            user: user.clone(),
+            password: password.clone(),
+        }),
+        (_, _, Some(token)) => Some(Credentials::Token(token.clone())),
+        _ => None,
+    };
+    let client = ImportClient::new(endpoint, args.schema.clone(), credentials);
+    let rt = new_runtime(2);
+    rt.block_on(import_tables(&client, &tables, &timestamp_column, &args))
+}
+
+async fn import_tables(
+    client: &ImportClient,
+    tables: &[ConvertedTable],
+    timestamp_column: &str,
+    args: &Args,
+) -> Result<()> {
+    for table in tables {
+        let sql = table.schema.create_table_sql(&table.name, timestamp_column);
+        client.execute_sql(&sql).await?;
+    }
+
+    let files = tables
+        .iter()
+        .flat_map(|table| table.files.iter().map(move |file| (table, file)));
+    let num_rows: usize = stream::iter(files)
+        .map(|(table, file)| async move {
+            let path = file.to_string_lossy();
+            let num_rows = client.import(&table.name, &path, args.rows_per_sst).await?;
+            println!(
+                "Imported file:{path}, table:{}, rows:{num_rows}",
+                table.name
+            );
+            Ok::<_, anyhow::Error>(num_rows)
+        })
+        .buffer_unordered(args.parallelism.max(1))
+        .try_fold(0, |acc, num_rows| async move { Ok(acc + num_rows) })
+        .await?;
+    println!("Import finished, rows:{num_rows}");
+
+    Ok(())
+}
@@ -28,7 +28,7 @@ use analytic_engine::{
         },
         file::Level,
     },
-    table_options::{Compression, StorageFormatHint},
+    table_options::{Compression, StorageFormatHint, DEFAULT_DATA_PAGE_SIZE},
 };
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -109,6 +109,7 @@ async fn run(args: Args, runtime: Arc<Runtime>) -> Result<()> {
         predicate: Arc::new(Predicate::empty()),
         meta_cache: None,
         scan_options,
+        scheduled_io: None,
         runtime,
         row_projector_builder,
     };
@@ -129,10 +130,17 @@ async fn run(args: Args, runtime: Arc<Runtime>) -> Result<()> {
     let builder_opts = SstWriteOptions {
         storage_format_hint: output_format_hint,
         num_rows_per_row_group: args.batch_size,
+        data_page_size: DEFAULT_DATA_PAGE_SIZE as usize,
         compression: Compression::parse_from(&args.compression)
             .with_context(|| format!("invalid compression:{}", args.compression))?,
+        compression_level: None,
+        column_options: Default::default(),
+        adaptive_compression: false,
+        separated_fields: Vec::new(),
         max_buffer_size: 10 * 1024 * 1024,
         column_stats: Default::default(),
+        bloom_filter: None,
+        recompressed: false,
     };
     let output = Path::from(args.output);
     let mut writer = factory
@@ -0,0 +1,172 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Client of the http api of the server to create the tables and import the
+//! converted files.
+//!
+//! The files are not uploaded, the server reads them at the same path, so the
+//! files must be on the local file system of the server, eg: the client runs
+//! on the server host or the files are on a shared file system.
+
+use std::{path::Path, time::Duration};
+
+use anyhow::{bail, ensure, Context, Result};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+
+const SCHEMA_HEADER: &str = "x-horaedb-schema";
+/// Interval to poll the progress of the imports.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize)]
+struct SqlRequest<'a> {
+    query: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportRequest<'a> {
+    table: &'a str,
+    path: &'a str,
+    format: &'a str,
+    rows_per_sst: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportResponse {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportProgress {
+    id: u64,
+    state: String,
+    #[serde(default)]
+    error: Option<String>,
+    num_rows: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListImportsResponse {
+    imports: Vec<ImportProgress>,
+}
+
+/// Credentials of the requests if the authentication of the server is
+/// enabled, the user must be granted the admin role to import the files.
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    Basic { user: String, password: String },
+    Token(String),
+}
+
+pub struct ImportClient {
+    endpoint: String,
+    schema: Option<String>,
+    credentials: Option<Credentials>,
+    client: reqwest::Client,
+}
+
+impl ImportClient {
+    /// Create the client of the server at the http `endpoint`, eg:
+    /// http://127.0.0.1:5440
+    pub fn new(endpoint: &str, schema: Option<String>, credentials: Option<Credentials>) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            schema,
+            credentials,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Build the request with the schema and the credentials.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/api/v1{path}", self.endpoint);
+        let mut request = self.client.request(method, url);
+        if let Some(schema) = &self.schema {
+            request = request.header(SCHEMA_HEADER, schema);
+        }
+        match &self.credentials {
+            Some(Credentials::Basic { user, password }) => request.basic_auth(user, Some(password)),
+            Some(Credentials::Token(token)) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    pub async fn execute_sql(&self, query: &str) -> Result<()> {
+        let request = self
+            .request(Method::POST, "/sql")
+            .json(&SqlRequest { query });
+        check_status(request.send().await?)
+            .await
+            .with_context(|| format!("execute sql:{query}"))?;
+
+        Ok(())
+    }
+
+    /// Import the parquet file on the server into the table and wait for it
+    /// to finish, returns the number of the imported rows.
+    ///
+    /// The `path` must be an absolute path of the file on the server.
+    pub async fn import(&self, table: &str, path: &str, rows_per_sst: usize) -> Result<usize> {
+        ensure!(
+            Path::new(path).is_absolute(),
+            "the path of the imported file must be absolute, path:{path}"
+        );
+
+        let request = self
+            .request(Method::POST, "/admin/import")
+            .json(&ImportRequest {
+                table,
+                path,
+                format: "parquet",
+                rows_per_sst,
+            });
+        let resp = check_status(request.send().await?)
+            .await
+            .with_context(|| format!("import file:{path}"))?;
+        let id = resp.json::<ImportResponse>().await?.id;
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let request = self.request(Method::GET, "/admin/import");
+            let resp = check_status(request.send().await?).await?;
+            let imports = resp.json::<ListImportsResponse>().await?.imports;
+            let Some(progress) = imports.into_iter().find(|v| v.id == id) else {
+                bail!("import not found, id:{id}, file:{path}");
+            };
+            match progress.state.as_str() {
+                "running" => continue,
+                "finished" => return Ok(progress.num_rows),
+                _ => bail!(
+                    "import failed, file:{path}, err:{}",
+                    progress.error.unwrap_or_default()
+                ),
+            }
+        }
+    }
+}
+
+/// Return the error with the message in the body if the request failed.
+async fn check_status(resp: Response) -> Result<Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+
+    let body = resp.text().await.unwrap_or_default();
+    bail!("request failed, status:{status}, body:{body}")
+}
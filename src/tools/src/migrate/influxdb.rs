@@ -0,0 +1,161 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Read the points from the InfluxDB line protocol files, eg: the files
+//! exported by `influx_inspect export`.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use influxdb_line_protocol::{FieldValue as InfluxFieldValue, ParsedLine};
+
+use crate::migrate::{FieldValue, Point};
+
+/// Precision of the timestamps in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Precision {
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl Precision {
+    fn to_millis(self, timestamp: i64) -> Option<i64> {
+        match self {
+            Precision::Ns => Some(timestamp.div_euclid(1_000_000)),
+            Precision::Us => Some(timestamp.div_euclid(1_000)),
+            Precision::Ms => Some(timestamp),
+            Precision::S => timestamp.checked_mul(1_000),
+        }
+    }
+}
+
+/// Read the points of the file and pass them to `f`, returns the number of
+/// the points read.
+///
+/// The comments and the ddl statements in the exported files are ignored.
+pub fn read_file(
+    path: &str,
+    precision: Precision,
+    mut f: impl FnMut(Point) -> Result<()>,
+) -> Result<usize> {
+    let file = File::open(path).with_context(|| format!("open file:{path}"))?;
+    let mut num_points = 0;
+    for (line_no, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("read file:{path}"))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("CREATE ") {
+            continue;
+        }
+
+        let point = parse_line(line, precision)
+            .with_context(|| format!("invalid line, file:{path}, line:{}", line_no + 1))?;
+        f(point)?;
+        num_points += 1;
+    }
+
+    Ok(num_points)
+}
+
+fn parse_line(line: &str, precision: Precision) -> Result<Point> {
+    let parsed = influxdb_line_protocol::parse_lines(line)
+        .next()
+        .ok_or_else(|| anyhow!("empty line"))??;
+
+    to_point(parsed, precision)
+}
+
+fn to_point(line: ParsedLine<'_>, precision: Precision) -> Result<Point> {
+    let timestamp = line
+        .timestamp
+        .ok_or_else(|| anyhow!("timestamp is missing"))?;
+    let timestamp = precision
+        .to_millis(timestamp)
+        .ok_or_else(|| anyhow!("timestamp out of range:{timestamp}"))?;
+    let tags = line
+        .series
+        .tag_set
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let fields = line
+        .field_set
+        .into_iter()
+        .map(|(k, v)| {
+            let value = match v {
+                InfluxFieldValue::F64(v) => FieldValue::Double(v),
+                InfluxFieldValue::I64(v) => FieldValue::Int64(v),
+                InfluxFieldValue::U64(v) => FieldValue::UInt64(v),
+                InfluxFieldValue::String(v) => FieldValue::String(v.to_string()),
+                InfluxFieldValue::Boolean(v) => FieldValue::Boolean(v),
+            };
+            (k.to_string(), value)
+        })
+        .collect();
+
+    Ok(Point {
+        source: line.series.measurement.to_string(),
+        tags,
+        fields,
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let point = parse_line(
+            r#"cpu,host=a,region=x usage=0.5,count=3i,ok=true,msg="hi" 1700000000123456789"#,
+            Precision::Ns,
+        )
+        .unwrap();
+        assert_eq!(point.source, "cpu");
+        assert_eq!(
+            point.tags,
+            vec![
+                ("host".to_string(), "a".to_string()),
+                ("region".to_string(), "x".to_string()),
+            ]
+        );
+        assert_eq!(
+            point.fields,
+            vec![
+                ("usage".to_string(), FieldValue::Double(0.5)),
+                ("count".to_string(), FieldValue::Int64(3)),
+                ("ok".to_string(), FieldValue::Boolean(true)),
+                ("msg".to_string(), FieldValue::String("hi".to_string())),
+            ]
+        );
+        assert_eq!(point.timestamp, 1700000000123);
+
+        let point = parse_line("mem free=1 1700000000", Precision::S).unwrap();
+        assert_eq!(point.timestamp, 1700000000000);
+        assert!(point.tags.is_empty());
+
+        assert!(parse_line("mem free=1", Precision::S).is_err());
+        assert!(parse_line("mem", Precision::S).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Rules to map the measurements/metrics of the source to the tables.
+//!
+//! Example:
+//! ```toml
+//! timestamp_column = "ts"
+//! table_prefix = "influx_"
+//!
+//! [[rules]]
+//! source = "cpu"
+//! table = "host_cpu"
+//! rename = { usage_idle = "idle" }
+//! drop = ["region"]
+//!
+//! [[rules]]
+//! source = "debug_events"
+//! skip = true
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{ensure, Result};
+use serde::Deserialize;
+
+use crate::migrate::Point;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MappingConfig {
+    /// Name of the timestamp column of the tables.
+    pub timestamp_column: String,
+    /// Prefix of the names of the tables not named by the rules.
+    pub table_prefix: String,
+    /// Skip the sources not matched by any rule instead of importing them
+    /// into the tables named after the sources.
+    pub skip_unmatched: bool,
+    pub rules: Vec<MappingRule>,
+}
+
+impl Default for MappingConfig {
+    fn default() -> Self {
+        Self {
+            timestamp_column: "timestamp".to_string(),
+            table_prefix: String::new(),
+            skip_unmatched: false,
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MappingRule {
+    /// Name of the measurement or the metric.
+    pub source: String,
+    /// Name of the table, defaults to the source name with the prefix.
+    #[serde(default)]
+    pub table: Option<String>,
+    /// Rename the tags and the fields.
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+    /// Tags and fields not imported.
+    #[serde(default)]
+    pub drop: HashSet<String>,
+    /// Don't import the source.
+    #[serde(default)]
+    pub skip: bool,
+}
+
+/// Map the points by the [MappingConfig].
+#[derive(Debug)]
+pub struct Mapper {
+    timestamp_column: String,
+    table_prefix: String,
+    skip_unmatched: bool,
+    rules: HashMap<String, MappingRule>,
+}
+
+impl Mapper {
+    pub fn try_new(config: MappingConfig) -> Result<Self> {
+        ensure!(
+            !config.timestamp_column.is_empty(),
+            "timestamp column can't be empty"
+        );
+
+        let mut rules = HashMap::with_capacity(config.rules.len());
+        for rule in config.rules {
+            if let Some(table) = &rule.table {
+                ensure!(is_valid_name(table), "invalid table name:{table}");
+            }
+            for column in rule.rename.values() {
+                ensure!(is_valid_name(column), "invalid column name:{column}");
+            }
+            let source = rule.source.clone();
+            ensure!(
+                rules.insert(source.clone(), rule).is_none(),
+                "duplicate rules of source:{source}"
+            );
+        }
+
+        Ok(Self {
+            timestamp_column: config.timestamp_column,
+            table_prefix: config.table_prefix,
+            skip_unmatched: config.skip_unmatched,
+            rules,
+        })
+    }
+
+    pub fn timestamp_column(&self) -> &str {
+        &self.timestamp_column
+    }
+
+    /// Map the point to the table, and the `source` of the returned point is
+    /// the table name. Returns None if the point is skipped.
+    pub fn map(&self, mut point: Point) -> Result<Option<Point>> {
+        let Some(rule) = self.rules.get(&point.source) else {
+            if self.skip_unmatched {
+                return Ok(None);
+            }
+            let table = format!("{}{}", self.table_prefix, point.source);
+            ensure!(is_valid_name(&table), "invalid table name:{table}");
+            point.source = table;
+            return self.check_columns(point).map(Some);
+        };
+        if rule.skip {
+            return Ok(None);
+        }
+
+        point.source = match &rule.table {
+            Some(table) => table.clone(),
+            None => format!("{}{}", self.table_prefix, point.source),
+        };
+        let rename = |name: String| match rule.rename.get(&name) {
+            Some(new_name) => new_name.clone(),
+            None => name,
+        };
+        point.tags = point
+            .tags
+            .into_iter()
+            .filter(|(name, _)| !rule.drop.contains(name))
+            .map(|(name, value)| (rename(name), value))
+            .collect();
+        point.fields = point
+            .fields
+            .into_iter()
+            .filter(|(name, _)| !rule.drop.contains(name))
+            .map(|(name, value)| (rename(name), value))
+            .collect();
+        if point.fields.is_empty() {
+            return Ok(None);
+        }
+
+        self.check_columns(point).map(Some)
+    }
+
+    fn check_columns(&self, point: Point) -> Result<Point> {
+        let names = point
+            .tags
+            .iter()
+            .map(|(name, _)| name)
+            .chain(point.fields.iter().map(|(name, _)| name));
+        for name in names {
+            ensure!(
+                is_valid_name(name) && *name != self.timestamp_column,
+                "invalid column name, table:{}, column:{name}",
+                point.source
+            );
+        }
+
+        Ok(point)
+    }
+}
+
+/// The names are quoted by the backticks in the sql.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('`')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrate::FieldValue;
+
+    fn new_point(source: &str) -> Point {
+        Point {
+            source: source.to_string(),
+            tags: vec![
+                ("host".to_string(), "a".to_string()),
+                ("region".to_string(), "x".to_string()),
+            ],
+            fields: vec![("usage_idle".to_string(), FieldValue::Double(1.0))],
+            timestamp: 1000,
+        }
+    }
+
+    #[test]
+    fn test_map_points() {
+        let config: MappingConfig = toml::from_str(
+            r#"
+table_prefix = "influx_"
+
+[[rules]]
+source = "cpu"
+table = "host_cpu"
+rename = { usage_idle = "idle" }
+drop = ["region"]
+
+[[rules]]
+source = "debug"
+skip = true
+"#,
+        )
+        .unwrap();
+        let mapper = Mapper::try_new(config).unwrap();
+
+        let point = mapper.map(new_point("cpu")).unwrap().unwrap();
+        assert_eq!(point.source, "host_cpu");
+        assert_eq!(point.tags, vec![("host".to_string(), "a".to_string())]);
+        assert_eq!(point.fields[0].0, "idle");
+
+        let point = mapper.map(new_point("mem")).unwrap().unwrap();
+        assert_eq!(point.source, "influx_mem");
+        assert_eq!(point.tags.len(), 2);
+
+        assert!(mapper.map(new_point("debug")).unwrap().is_none());
+
+        // The column conflicts with the timestamp column.
+        let mut point = new_point("mem");
+        point.tags.push(("timestamp".to_string(), "x".to_string()));
+        assert!(mapper.map(point).is_err());
+    }
+}
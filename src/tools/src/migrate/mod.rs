@@ -0,0 +1,424 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Migrate the data of the other time series databases.
+//!
+//! The points read from the source are mapped to the tables by the
+//! [mapping](mapping::MappingConfig) rules, and written into the parquet files
+//! of at most `rows_per_file` rows. The files are then imported by the server
+//! directly as ssts through the admin import api, see [client::ImportClient].
+
+pub mod client;
+pub mod influxdb;
+pub mod mapping;
+pub mod prometheus;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, TimestampMillisecondArray,
+        UInt64Array,
+    },
+    datatypes::{DataType, Field, Schema, TimeUnit},
+    record_batch::RecordBatch,
+};
+use parquet::arrow::ArrowWriter;
+
+use crate::migrate::mapping::Mapper;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Double(f64),
+    Int64(i64),
+    UInt64(u64),
+    String(String),
+    Boolean(bool),
+}
+
+impl FieldValue {
+    fn data_type(&self) -> DataType {
+        match self {
+            FieldValue::Double(_) => DataType::Float64,
+            FieldValue::Int64(_) => DataType::Int64,
+            FieldValue::UInt64(_) => DataType::UInt64,
+            FieldValue::String(_) => DataType::Utf8,
+            FieldValue::Boolean(_) => DataType::Boolean,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::Double(v) => Some(*v),
+            FieldValue::Int64(v) => Some(*v as f64),
+            FieldValue::UInt64(v) => Some(*v as f64),
+            FieldValue::String(_) | FieldValue::Boolean(_) => None,
+        }
+    }
+}
+
+/// A point read from the source database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    /// Name of the measurement or the metric.
+    pub source: String,
+    pub tags: Vec<(String, String)>,
+    pub fields: Vec<(String, FieldValue)>,
+    /// Timestamp in milliseconds.
+    pub timestamp: i64,
+}
+
+/// Columns of a table, the tags are strings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableSchema {
+    pub tags: BTreeSet<String>,
+    pub fields: BTreeMap<String, DataType>,
+}
+
+impl TableSchema {
+    fn add_point(&mut self, point: &Point) -> Result<()> {
+        for (name, _) in &point.tags {
+            ensure!(
+                !self.fields.contains_key(name),
+                "column is both a tag and a field, table:{}, column:{name}",
+                point.source
+            );
+            self.tags.insert(name.clone());
+        }
+        for (name, value) in &point.fields {
+            ensure!(
+                !self.tags.contains(name),
+                "column is both a tag and a field, table:{}, column:{name}",
+                point.source
+            );
+            let data_type = value.data_type();
+            let merged = match self.fields.get(name) {
+                Some(prev) => merge_type(prev, &data_type).with_context(|| {
+                    format!(
+                        "conflict types of field, table:{}, field:{name}, types:{prev}/{data_type}",
+                        point.source
+                    )
+                })?,
+                None => data_type,
+            };
+            self.fields.insert(name.clone(), merged);
+        }
+
+        Ok(())
+    }
+
+    /// The sql to create the table of this schema.
+    pub fn create_table_sql(&self, table: &str, timestamp_column: &str) -> String {
+        let mut columns = vec![format!("`{timestamp_column}` timestamp NOT NULL")];
+        columns.extend(self.tags.iter().map(|tag| format!("`{tag}` string TAG")));
+        columns.extend(
+            self.fields
+                .iter()
+                .map(|(field, data_type)| format!("`{field}` {}", sql_type(data_type))),
+        );
+        columns.push(format!("TIMESTAMP KEY(`{timestamp_column}`)"));
+
+        format!(
+            "CREATE TABLE IF NOT EXISTS `{table}` ({}) ENGINE=Analytic",
+            columns.join(", ")
+        )
+    }
+}
+
+/// The numbers of different types are stored as doubles.
+fn merge_type(a: &DataType, b: &DataType) -> Option<DataType> {
+    let is_number =
+        |t: &DataType| matches!(t, DataType::Float64 | DataType::Int64 | DataType::UInt64);
+    if a == b {
+        Some(a.clone())
+    } else if is_number(a) && is_number(b) {
+        Some(DataType::Float64)
+    } else {
+        None
+    }
+}
+
+fn sql_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Float64 => "double",
+        DataType::Int64 => "bigint",
+        DataType::UInt64 => "uint64",
+        DataType::Boolean => "boolean",
+        _ => "string",
+    }
+}
+
+/// The table converted from the source.
+#[derive(Debug)]
+pub struct ConvertedTable {
+    pub name: String,
+    pub schema: TableSchema,
+    pub files: Vec<PathBuf>,
+    pub num_rows: usize,
+}
+
+struct TableWriter {
+    /// Index of the table, used to name the files.
+    index: usize,
+    schema: TableSchema,
+    buffer: Vec<Point>,
+    files: Vec<PathBuf>,
+    num_rows: usize,
+}
+
+impl TableWriter {
+    fn flush(&mut self, output_dir: &Path, timestamp_column: &str) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let batch = build_batch(&self.schema, &self.buffer, timestamp_column)?;
+        let path = output_dir.join(format!("{}_{}.parquet", self.index, self.files.len()));
+        let file = File::create(&path).with_context(|| format!("create file:{path:?}"))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        self.num_rows += self.buffer.len();
+        self.buffer.clear();
+        self.files.push(path);
+        Ok(())
+    }
+}
+
+/// Build the record batch of the `points` with the columns in the `schema`.
+fn build_batch(
+    schema: &TableSchema,
+    points: &[Point],
+    timestamp_column: &str,
+) -> Result<RecordBatch> {
+    let mut fields = Vec::with_capacity(1 + schema.tags.len() + schema.fields.len());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(fields.capacity());
+
+    fields.push(Field::new(
+        timestamp_column,
+        DataType::Timestamp(TimeUnit::Millisecond, None),
+        false,
+    ));
+    let timestamps = points.iter().map(|p| p.timestamp).collect::<Vec<_>>();
+    columns.push(Arc::new(TimestampMillisecondArray::from(timestamps)));
+
+    for tag in &schema.tags {
+        let values = points
+            .iter()
+            .map(|p| p.tags.iter().find(|(k, _)| k == tag).map(|(_, v)| v.as_str()))
+            .collect::<Vec<_>>();
+        fields.push(Field::new(tag, DataType::Utf8, true));
+        columns.push(Arc::new(StringArray::from(values)));
+    }
+
+    for (name, data_type) in &schema.fields {
+        let values = points
+            .iter()
+            .map(|p| p.fields.iter().find(|(k, _)| k == name).map(|(_, v)| v))
+            .collect::<Vec<_>>();
+        let column: ArrayRef = match data_type {
+            DataType::Float64 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| v.and_then(FieldValue::as_f64))
+                    .collect::<Float64Array>(),
+            ),
+            DataType::Int64 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        Some(FieldValue::Int64(v)) => Some(*v),
+                        _ => None,
+                    })
+                    .collect::<Int64Array>(),
+            ),
+            DataType::UInt64 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        Some(FieldValue::UInt64(v)) => Some(*v),
+                        _ => None,
+                    })
+                    .collect::<UInt64Array>(),
+            ),
+            DataType::Boolean => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        Some(FieldValue::Boolean(v)) => Some(*v),
+                        _ => None,
+                    })
+                    .collect::<BooleanArray>(),
+            ),
+            DataType::Utf8 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        Some(FieldValue::String(v)) => Some(v.as_str()),
+                        _ => None,
+                    })
+                    .collect::<StringArray>(),
+            ),
+            _ => bail!("unsupported field type:{data_type}"),
+        };
+        fields.push(Field::new(name, data_type.clone(), true));
+        columns.push(column);
+    }
+
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+}
+
+/// Convert the points read from the source into the parquet files of the
+/// tables.
+pub struct Converter {
+    mapper: Mapper,
+    output_dir: PathBuf,
+    rows_per_file: usize,
+    tables: HashMap<String, TableWriter>,
+    num_skipped: usize,
+}
+
+impl Converter {
+    pub fn try_new(mapper: Mapper, output_dir: PathBuf, rows_per_file: usize) -> Result<Self> {
+        ensure!(rows_per_file > 0, "rows_per_file must be positive");
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("create output dir:{output_dir:?}"))?;
+        // The files are read by the server, so the paths must be absolute.
+        let output_dir = fs::canonicalize(&output_dir)?;
+
+        Ok(Self {
+            mapper,
+            output_dir,
+            rows_per_file,
+            tables: HashMap::new(),
+            num_skipped: 0,
+        })
+    }
+
+    pub fn add(&mut self, point: Point) -> Result<()> {
+        let Some(point) = self.mapper.map(point)? else {
+            self.num_skipped += 1;
+            return Ok(());
+        };
+
+        let next_index = self.tables.len();
+        let writer = self
+            .tables
+            .entry(point.source.clone())
+            .or_insert_with(|| TableWriter {
+                index: next_index,
+                schema: TableSchema::default(),
+                buffer: Vec::new(),
+                files: Vec::new(),
+                num_rows: 0,
+            });
+        writer.schema.add_point(&point)?;
+        writer.buffer.push(point);
+        if writer.buffer.len() >= self.rows_per_file {
+            writer.flush(&self.output_dir, self.mapper.timestamp_column())?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of the points skipped by the mapping rules.
+    pub fn num_skipped(&self) -> usize {
+        self.num_skipped
+    }
+
+    /// Flush the buffered points and return the converted tables.
+    pub fn finish(mut self) -> Result<Vec<ConvertedTable>> {
+        let mut tables = Vec::with_capacity(self.tables.len());
+        for (name, mut writer) in self.tables.drain() {
+            writer.flush(&self.output_dir, self.mapper.timestamp_column())?;
+            tables.push(ConvertedTable {
+                name,
+                schema: writer.schema,
+                files: writer.files,
+                num_rows: writer.num_rows,
+            });
+        }
+        tables.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(tables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_point(tags: &[(&str, &str)], fields: Vec<(&str, FieldValue)>) -> Point {
+        Point {
+            source: "cpu".to_string(),
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            fields: fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            timestamp: 1000,
+        }
+    }
+
+    #[test]
+    fn test_table_schema() {
+        let mut schema = TableSchema::default();
+        let points = vec![
+            new_point(&[("host", "a")], vec![("usage", FieldValue::Int64(1))]),
+            new_point(
+                &[("host", "b"), ("dc", "x")],
+                vec![
+                    ("usage", FieldValue::Double(0.5)),
+                    ("up", FieldValue::Boolean(true)),
+                ],
+            ),
+        ];
+        for point in &points {
+            schema.add_point(point).unwrap();
+        }
+        assert_eq!(schema.fields["usage"], DataType::Float64);
+        assert_eq!(
+            schema.create_table_sql("cpu", "timestamp"),
+            "CREATE TABLE IF NOT EXISTS `cpu` (`timestamp` timestamp NOT NULL, `dc` string TAG, \
+             `host` string TAG, `up` boolean, `usage` double, TIMESTAMP KEY(`timestamp`)) \
+             ENGINE=Analytic"
+        );
+
+        let batch = build_batch(&schema, &points, "timestamp").unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 5);
+        // The missing values are nulls.
+        assert_eq!(batch.column(1).null_count(), 1);
+
+        // The types can't be merged.
+        let point = new_point(&[], vec![("usage", FieldValue::String("x".to_string()))]);
+        assert!(schema.add_point(&point).is_err());
+        // The tag is used as a field.
+        let point = new_point(&[("usage", "x")], vec![]);
+        assert!(schema.add_point(&point).is_err());
+    }
+}
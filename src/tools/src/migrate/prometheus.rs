@@ -0,0 +1,587 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Read the samples from the Prometheus TSDB blocks.
+//!
+//! Only the float samples are read, the native histograms are skipped and so
+//! are the tombstones, so compact the blocks before migration if the series
+//! are deleted.
+//!
+//! See the format of the index and the chunks:
+//! <https://github.com/prometheus/prometheus/tree/main/tsdb/docs/format>
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::migrate::{FieldValue, Point};
+
+const INDEX_MAGIC: u32 = 0xBAAD_D700;
+const INDEX_VERSION: u8 = 2;
+/// Size of the table of contents at the end of the index, 6 offsets and the
+/// crc32.
+const INDEX_TOC_SIZE: usize = 6 * 8 + 4;
+/// The series are aligned to 16 bytes in the index.
+const SERIES_ALIGNMENT: usize = 16;
+const CHUNKS_MAGIC: u32 = 0x85BD_40DD;
+const CHUNKS_HEADER_SIZE: u64 = 8;
+const ENCODING_XOR: u8 = 1;
+/// Value marking the series as stale.
+const STALE_NAN_BITS: u64 = 0x7ff0_0000_0000_0002;
+const METRIC_NAME_LABEL: &str = "__name__";
+/// Name of the field of the sample values.
+pub const VALUE_FIELD: &str = "value";
+
+/// Find the blocks in the `path`, which is a block or a data dir containing
+/// the blocks.
+pub fn find_blocks(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.join("index").is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut blocks = Vec::new();
+    for entry in fs::read_dir(path).with_context(|| format!("read dir:{path:?}"))? {
+        let block = entry?.path();
+        if block.join("index").is_file() && block.join("chunks").is_dir() {
+            blocks.push(block);
+        }
+    }
+    blocks.sort_unstable();
+
+    Ok(blocks)
+}
+
+/// Statistics of reading a block.
+#[derive(Debug, Default)]
+pub struct ReadStats {
+    pub num_series: usize,
+    pub num_samples: usize,
+    /// Number of the chunks skipped as they're not float samples.
+    pub num_skipped_chunks: usize,
+}
+
+/// Read the samples of the block and pass them to `f`.
+pub fn read_block(block: &Path, mut f: impl FnMut(Point) -> Result<()>) -> Result<ReadStats> {
+    let index_path = block.join("index");
+    let index = fs::read(&index_path).with_context(|| format!("read index:{index_path:?}"))?;
+    let index = Index::try_new(&index)?;
+    let mut chunks = ChunkReader::open(&block.join("chunks"))?;
+
+    let mut stats = ReadStats::default();
+    let mut buf = Vec::new();
+    for series in index.series() {
+        let series = series?;
+        stats.num_series += 1;
+
+        let mut name = None;
+        let mut tags = Vec::with_capacity(series.labels.len());
+        for (label, value) in series.labels {
+            if label == METRIC_NAME_LABEL {
+                name = Some(value);
+            } else {
+                tags.push((label, value));
+            }
+        }
+        let Some(name) = name else {
+            continue;
+        };
+
+        for chunk_ref in series.chunk_refs {
+            let encoding = chunks.read(chunk_ref, &mut buf)?;
+            if encoding != ENCODING_XOR {
+                stats.num_skipped_chunks += 1;
+                continue;
+            }
+            for (timestamp, value) in decode_xor_chunk(&buf)? {
+                if value.to_bits() == STALE_NAN_BITS {
+                    continue;
+                }
+                stats.num_samples += 1;
+                f(Point {
+                    source: name.clone(),
+                    tags: tags.clone(),
+                    fields: vec![(VALUE_FIELD.to_string(), FieldValue::Double(value))],
+                    timestamp,
+                })?;
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Reader of the bytes in the index.
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        ensure!(
+            self.pos + len <= self.data.len(),
+            "unexpected end of data, pos:{}, len:{len}",
+            self.pos
+        );
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn uvarint(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        bail!("varint overflows, pos:{}", self.pos)
+    }
+
+    fn varint(&mut self) -> Result<i64> {
+        self.uvarint().map(zigzag_decode)
+    }
+}
+
+fn zigzag_decode(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+struct Index<'a> {
+    data: &'a [u8],
+    symbols: Vec<&'a str>,
+    /// Range of the series section.
+    series_start: usize,
+    series_end: usize,
+}
+
+struct Series {
+    labels: Vec<(String, String)>,
+    chunk_refs: Vec<u64>,
+}
+
+impl<'a> Index<'a> {
+    fn try_new(data: &'a [u8]) -> Result<Self> {
+        ensure!(data.len() > 5 + INDEX_TOC_SIZE, "index is too small");
+        let mut decoder = Decoder::new(data, 0);
+        ensure!(decoder.u32()? == INDEX_MAGIC, "invalid index magic");
+        let version = decoder.bytes(1)?[0];
+        ensure!(
+            version == INDEX_VERSION,
+            "unsupported index version:{version}"
+        );
+
+        let mut decoder = Decoder::new(data, data.len() - INDEX_TOC_SIZE);
+        let mut toc = [0; 6];
+        for offset in &mut toc {
+            let bytes = decoder.bytes(8)?;
+            *offset = u64::from_be_bytes(bytes.try_into().unwrap()) as usize;
+        }
+        let [symbols_start, series_start, ..] = toc;
+        // The series section ends at the next section.
+        let series_end = toc[2..]
+            .iter()
+            .copied()
+            .filter(|offset| *offset > series_start)
+            .min()
+            .unwrap_or(data.len() - INDEX_TOC_SIZE);
+
+        let mut decoder = Decoder::new(data, symbols_start);
+        let _len = decoder.u32()?;
+        let num_symbols = decoder.u32()? as usize;
+        let mut symbols = Vec::with_capacity(num_symbols);
+        for _ in 0..num_symbols {
+            let len = decoder.uvarint()? as usize;
+            let symbol = std::str::from_utf8(decoder.bytes(len)?).context("invalid symbol")?;
+            symbols.push(symbol);
+        }
+
+        Ok(Self {
+            data,
+            symbols,
+            series_start,
+            series_end,
+        })
+    }
+
+    fn symbol(&self, index: u64) -> Result<String> {
+        match self.symbols.get(index as usize) {
+            Some(symbol) => Ok(symbol.to_string()),
+            None => bail!("symbol not found, index:{index}"),
+        }
+    }
+
+    fn series(&self) -> impl Iterator<Item = Result<Series>> + '_ {
+        let mut pos = self.series_start;
+        std::iter::from_fn(move || {
+            pos = pos.next_multiple_of(SERIES_ALIGNMENT);
+            if pos >= self.series_end {
+                return None;
+            }
+            let mut decoder = Decoder::new(self.data, pos);
+            let series = self.read_series(&mut decoder);
+            // Skip the crc32.
+            pos = decoder.pos + 4;
+            Some(series)
+        })
+    }
+
+    fn read_series(&self, decoder: &mut Decoder<'_>) -> Result<Series> {
+        let len = decoder.uvarint()? as usize;
+        let end = decoder.pos + len;
+
+        let num_labels = decoder.uvarint()? as usize;
+        let mut labels = Vec::with_capacity(num_labels);
+        for _ in 0..num_labels {
+            let name = self.symbol(decoder.uvarint()?)?;
+            let value = self.symbol(decoder.uvarint()?)?;
+            labels.push((name, value));
+        }
+
+        // The chunk metas are delta encoded: the min time, the max time and
+        // the reference of the chunk.
+        let num_chunks = decoder.uvarint()? as usize;
+        let mut chunk_refs = Vec::with_capacity(num_chunks);
+        let mut max_time = 0;
+        let mut chunk_ref = 0;
+        for i in 0..num_chunks {
+            if i == 0 {
+                let min_time = decoder.varint()?;
+                max_time = min_time + decoder.uvarint()? as i64;
+                chunk_ref = decoder.uvarint()? as i64;
+            } else {
+                let min_time = max_time + decoder.uvarint()? as i64;
+                max_time = min_time + decoder.uvarint()? as i64;
+                chunk_ref += decoder.varint()?;
+            }
+            chunk_refs.push(chunk_ref as u64);
+        }
+        ensure!(decoder.pos == end, "invalid series, pos:{end}");
+
+        Ok(Series { labels, chunk_refs })
+    }
+}
+
+/// Read the chunks from the segment files.
+struct ChunkReader {
+    segments: Vec<File>,
+}
+
+impl ChunkReader {
+    fn open(dir: &Path) -> Result<Self> {
+        let mut paths = fs::read_dir(dir)
+            .with_context(|| format!("read dir:{dir:?}"))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.sort_unstable();
+
+        let mut segments = Vec::with_capacity(paths.len());
+        for path in paths {
+            let mut file = File::open(&path).with_context(|| format!("open file:{path:?}"))?;
+            let mut magic = [0; 4];
+            file.read_exact(&mut magic)?;
+            ensure!(
+                u32::from_be_bytes(magic) == CHUNKS_MAGIC,
+                "invalid chunks magic, file:{path:?}"
+            );
+            segments.push(file);
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Read the chunk of the `chunk_ref` into `buf`, returns the encoding of
+    /// the chunk.
+    fn read(&mut self, chunk_ref: u64, buf: &mut Vec<u8>) -> Result<u8> {
+        // The upper 4 bytes are the index of the segment, and the lower are
+        // the offset in the segment.
+        let segment = (chunk_ref >> 32) as usize;
+        let offset = chunk_ref & 0xffff_ffff;
+        ensure!(
+            offset >= CHUNKS_HEADER_SIZE,
+            "invalid chunk ref:{chunk_ref}"
+        );
+        let Some(file) = self.segments.get_mut(segment) else {
+            bail!("chunk segment not found, ref:{chunk_ref}");
+        };
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut reader = BufReader::new(file);
+        let mut len = 0;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0; 1];
+            reader.read_exact(&mut byte)?;
+            len |= u64::from(byte[0] & 0x7f) << shift;
+            if byte[0] < 0x80 {
+                break;
+            }
+        }
+        let mut encoding = [0; 1];
+        reader.read_exact(&mut encoding)?;
+        buf.resize(len as usize, 0);
+        reader.read_exact(buf)?;
+
+        Ok(encoding[0])
+    }
+}
+
+/// Read the bits of the xor chunk.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read_bit(&mut self) -> Result<bool> {
+        let byte = self.pos / 8;
+        ensure!(byte < self.data.len(), "unexpected end of chunk");
+        let bit = self.data[byte] & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, n: u32) -> Result<u64> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | u64::from(self.read_bit()?);
+        }
+        Ok(value)
+    }
+
+    fn read_uvarint(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_bits(8)?;
+            value |= (byte & 0x7f) << shift;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        bail!("varint overflows in chunk")
+    }
+}
+
+/// Decode the samples of the xor chunk, the timestamps are delta-of-delta
+/// encoded and the values are xor encoded with the previous one.
+fn decode_xor_chunk(data: &[u8]) -> Result<Vec<(i64, f64)>> {
+    ensure!(data.len() >= 2, "chunk is too small");
+    let num_samples = u16::from_be_bytes([data[0], data[1]]) as usize;
+    let mut reader = BitReader {
+        data: &data[2..],
+        pos: 0,
+    };
+
+    let mut samples = Vec::with_capacity(num_samples);
+    let mut timestamp = 0;
+    let mut delta = 0;
+    let mut value_bits = 0;
+    let mut leading = 0;
+    let mut trailing = 0;
+    for i in 0..num_samples {
+        match i {
+            0 => {
+                timestamp = zigzag_decode(reader.read_uvarint()?);
+                value_bits = reader.read_bits(64)?;
+            }
+            _ => {
+                if i == 1 {
+                    delta = reader.read_uvarint()? as i64;
+                } else {
+                    delta += read_dod(&mut reader)?;
+                }
+                timestamp += delta;
+
+                if reader.read_bit()? {
+                    if reader.read_bit()? {
+                        leading = reader.read_bits(5)? as u32;
+                        let mut significant = reader.read_bits(6)? as u32;
+                        if significant == 0 {
+                            significant = 64;
+                        }
+                        ensure!(leading + significant <= 64, "invalid xor value");
+                        trailing = 64 - leading - significant;
+                    }
+                    let significant = 64 - leading - trailing;
+                    value_bits ^= reader.read_bits(significant)? << trailing;
+                }
+            }
+        }
+        samples.push((timestamp, f64::from_bits(value_bits)));
+    }
+
+    Ok(samples)
+}
+
+/// Read the delta of the delta of the timestamps.
+fn read_dod(reader: &mut BitReader<'_>) -> Result<i64> {
+    // The number of the leading ones tells the size of the value.
+    let mut ones = 0;
+    while ones < 4 && reader.read_bit()? {
+        ones += 1;
+    }
+    let size = match ones {
+        0 => return Ok(0),
+        1 => 14,
+        2 => 17,
+        3 => 20,
+        _ => return Ok(reader.read_bits(64)? as i64),
+    };
+
+    let bits = reader.read_bits(size)? as i64;
+    if bits > 1 << (size - 1) {
+        Ok(bits - (1 << size))
+    } else {
+        Ok(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write the bits like the xor appender of Prometheus.
+    #[derive(Default)]
+    struct BitWriter {
+        data: Vec<u8>,
+        num_bits: usize,
+    }
+
+    impl BitWriter {
+        fn write_bits(&mut self, value: u64, n: u32) {
+            for i in (0..n).rev() {
+                if self.num_bits % 8 == 0 {
+                    self.data.push(0);
+                }
+                if (value >> i) & 1 == 1 {
+                    *self.data.last_mut().unwrap() |= 0x80 >> (self.num_bits % 8);
+                }
+                self.num_bits += 1;
+            }
+        }
+
+        fn write_uvarint(&mut self, mut value: u64) {
+            while value >= 0x80 {
+                self.write_bits((value & 0x7f) | 0x80, 8);
+                value >>= 7;
+            }
+            self.write_bits(value, 8);
+        }
+    }
+
+    fn encode_xor_chunk(samples: &[(i64, f64)]) -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        let mut prev_t = 0;
+        let mut prev_delta = 0;
+        let mut prev_v = 0u64;
+        for (i, (t, v)) in samples.iter().enumerate() {
+            let v = v.to_bits();
+            match i {
+                0 => {
+                    writer.write_uvarint(((t << 1) ^ (t >> 63)) as u64);
+                    writer.write_bits(v, 64);
+                }
+                _ => {
+                    let delta = t - prev_t;
+                    if i == 1 {
+                        writer.write_uvarint(delta as u64);
+                    } else {
+                        let dod = delta - prev_delta;
+                        match dod {
+                            0 => writer.write_bits(0, 1),
+                            -8191..=8192 => {
+                                writer.write_bits(0b10, 2);
+                                writer.write_bits(dod as u64, 14);
+                            }
+                            _ => {
+                                writer.write_bits(0b1111, 4);
+                                writer.write_bits(dod as u64, 64);
+                            }
+                        }
+                    }
+                    prev_delta = delta;
+
+                    // Always write the leading and the trailing zeros.
+                    let xor = v ^ prev_v;
+                    if xor == 0 {
+                        writer.write_bits(0, 1);
+                    } else {
+                        let leading = xor.leading_zeros().min(31);
+                        let trailing = xor.trailing_zeros();
+                        let significant = 64 - leading - trailing;
+                        writer.write_bits(0b11, 2);
+                        writer.write_bits(leading as u64, 5);
+                        writer.write_bits(significant as u64 & 0x3f, 6);
+                        writer.write_bits(xor >> trailing, significant);
+                    }
+                }
+            }
+            prev_t = *t;
+            prev_v = v;
+        }
+
+        let mut data = (samples.len() as u16).to_be_bytes().to_vec();
+        data.extend(writer.data);
+        data
+    }
+
+    #[test]
+    fn test_decode_xor_chunk() {
+        let samples = vec![
+            (1700000000000, 1.0),
+            (1700000015000, 1.0),
+            (1700000030000, 2.5),
+            (1700000045000, -3.25),
+            (1700000059000, 1e10),
+            (1700000059001, 0.0),
+            (1700009059001, f64::MAX),
+        ];
+        let chunk = encode_xor_chunk(&samples);
+        assert_eq!(decode_xor_chunk(&chunk).unwrap(), samples);
+
+        let chunk = encode_xor_chunk(&samples[..1]);
+        assert_eq!(decode_xor_chunk(&chunk).unwrap(), samples[..1]);
+
+        // The chunk is truncated.
+        let chunk = encode_xor_chunk(&samples);
+        assert!(decode_xor_chunk(&chunk[..chunk.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn test_varint() {
+        let data = [0xac, 0x02, 0x03];
+        let mut decoder = Decoder::new(&data, 0);
+        assert_eq!(decoder.uvarint().unwrap(), 300);
+        assert_eq!(decoder.varint().unwrap(), -2);
+        assert!(decoder.uvarint().is_err());
+    }
+}
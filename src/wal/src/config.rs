@@ -16,6 +16,10 @@
 // under the License.
 
 use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use time_ext::ReadableDuration;
+
+use crate::encryption::EncryptionConfig;
 
 #[cfg(feature = "wal-rocksdb")]
 pub type RocksDBStorageConfig = crate::rocksdb_impl::config::RocksDBStorageConfig;
@@ -45,6 +49,28 @@ pub struct Config {
     // Note: this is only used for test, we shouldn't enable this in production.
     #[serde(default)]
     pub disable_data: bool,
+    /// Compression of the entries written into the data wal.
+    #[serde(default)]
+    pub compression: Compression,
+    /// Append a checksum to each entry written into the data wal, which is
+    /// verified when the entry is read.
+    #[serde(default)]
+    pub checksum: bool,
+    /// Encryption of the entries written into the data wal.
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+    /// Max time to wait for the entries of the concurrent writers before
+    /// writing them in one group, and the group commit is disabled if it's
+    /// zero or the wal can't write the batches in one io.
+    #[serde(default)]
+    pub flush_interval: ReadableDuration,
+    /// Max bytes of the entries written in one group.
+    #[serde(default = "default_max_batch_bytes")]
+    pub max_batch_bytes: ReadableSize,
+}
+
+fn default_max_batch_bytes() -> ReadableSize {
+    ReadableSize::mb(4)
 }
 
 impl Default for Config {
@@ -52,6 +78,35 @@ impl Default for Config {
         Self {
             storage: StorageConfig::RocksDB(Box::default()),
             disable_data: false,
+            compression: Compression::default(),
+            checksum: false,
+            encryption: EncryptionConfig::default(),
+            flush_interval: ReadableDuration::default(),
+            max_batch_bytes: default_max_batch_bytes(),
+        }
+    }
+}
+
+/// Compression of the wal entries
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            value if value == Self::None as u8 => Some(Self::None),
+            value if value == Self::Lz4 as u8 => Some(Self::Lz4),
+            value if value == Self::Zstd as u8 => Some(Self::Zstd),
+            _ => None,
         }
     }
 }
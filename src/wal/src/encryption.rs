@@ -0,0 +1,275 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Encryption of the wal payloads
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use macros::define_result;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+/// Size of the AES-256 keys in bytes.
+pub const KEY_SIZE: usize = 32;
+/// Size of the AES-GCM nonces in bytes.
+pub const NONCE_SIZE: usize = 12;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to decode the hex key, id:{}, err:{}", id, source))]
+    DecodeKey { id: u32, source: hex::FromHexError },
+
+    #[snafu(display(
+        "Invalid key length, id:{}, expect:{}, given:{}.\nBacktrace:\n{}",
+        id,
+        KEY_SIZE,
+        given,
+        backtrace
+    ))]
+    InvalidKeyLength {
+        id: u32,
+        given: usize,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Duplicate key id:{}.\nBacktrace:\n{}", id, backtrace))]
+    DuplicateKey { id: u32, backtrace: Backtrace },
+
+    #[snafu(display("Encryption key is not found, id:{}.\nBacktrace:\n{}", id, backtrace))]
+    KeyNotFound { id: u32, backtrace: Backtrace },
+
+    #[snafu(display("Failed to encrypt payload, key id:{}.\nBacktrace:\n{}", id, backtrace))]
+    Encrypt { id: u32, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to decrypt payload, the key may be wrong or the payload is corrupted, key \
+         id:{}.\nBacktrace:\n{}",
+        id,
+        backtrace
+    ))]
+    Decrypt { id: u32, backtrace: Backtrace },
+}
+
+define_result!(Error);
+
+/// Encryption of the entries written into the data wal.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// Id of the key to encrypt the new entries, and the entries are not
+    /// encrypted if it's not set.
+    pub active_key_id: Option<u32>,
+    /// All the known keys. To rotate the key, add a new key and make it
+    /// active, and keep the old keys until the entries encrypted by them are
+    /// deleted from the wal.
+    pub keys: Vec<EncryptionKey>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct EncryptionKey {
+    pub id: u32,
+    /// Hex encoded 256-bit key.
+    pub key: String,
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .field("key", &"******")
+            .finish()
+    }
+}
+
+/// Payload encrypted by the key of `key_id`.
+#[derive(Debug)]
+pub struct EncryptedPayload {
+    pub key_id: u32,
+    pub nonce: [u8; NONCE_SIZE],
+    pub ciphertext: Vec<u8>,
+}
+
+/// The keys to encrypt and decrypt the wal payloads.
+#[derive(Default)]
+pub struct KeyRing {
+    active_key_id: Option<u32>,
+    ciphers: HashMap<u32, Aes256Gcm>,
+}
+
+pub type KeyRingRef = Arc<KeyRing>;
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut key_ids: Vec<_> = self.ciphers.keys().collect();
+        key_ids.sort_unstable();
+        f.debug_struct("KeyRing")
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &key_ids)
+            .finish()
+    }
+}
+
+impl KeyRing {
+    pub fn try_new(config: &EncryptionConfig) -> Result<Self> {
+        let mut ciphers = HashMap::with_capacity(config.keys.len());
+        for key in &config.keys {
+            let id = key.id;
+            let raw = hex::decode(key.key.trim()).context(DecodeKey { id })?;
+            ensure!(
+                raw.len() == KEY_SIZE,
+                InvalidKeyLength {
+                    id,
+                    given: raw.len()
+                }
+            );
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&raw));
+            ensure!(ciphers.insert(id, cipher).is_none(), DuplicateKey { id });
+        }
+
+        if let Some(id) = config.active_key_id {
+            ensure!(ciphers.contains_key(&id), KeyNotFound { id });
+        }
+
+        Ok(Self {
+            active_key_id: config.active_key_id,
+            ciphers,
+        })
+    }
+
+    pub fn active_key_id(&self) -> Option<u32> {
+        self.active_key_id
+    }
+
+    /// Encrypt the payload with the active key, and None is returned if no
+    /// key is active.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Option<EncryptedPayload>> {
+        let Some(key_id) = self.active_key_id else {
+            return Ok(None);
+        };
+        let cipher = self
+            .ciphers
+            .get(&key_id)
+            .context(KeyNotFound { id: key_id })?;
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .ok()
+            .context(Encrypt { id: key_id })?;
+
+        let mut raw_nonce = [0; NONCE_SIZE];
+        raw_nonce.copy_from_slice(&nonce);
+        Ok(Some(EncryptedPayload {
+            key_id,
+            nonce: raw_nonce,
+            ciphertext,
+        }))
+    }
+
+    pub fn decrypt(&self, key_id: u32, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let cipher = self
+            .ciphers
+            .get(&key_id)
+            .context(KeyNotFound { id: key_id })?;
+
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+            .context(Decrypt { id: key_id })
+    }
+}
+
+/// Build the key ring from the config, which is shared by the wal to decrypt
+/// the payloads and by the value encoder to encrypt them.
+pub fn build_key_ring(config: &EncryptionConfig) -> Result<KeyRingRef> {
+    let key_ring = KeyRing::try_new(config)?;
+
+    Ok(Arc::new(key_ring))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn new_test_key_ring(active_key_id: Option<u32>, key_ids: &[u32]) -> KeyRing {
+        let keys = key_ids
+            .iter()
+            .map(|id| EncryptionKey {
+                id: *id,
+                key: hex::encode([*id as u8; KEY_SIZE]),
+            })
+            .collect();
+        let config = EncryptionConfig {
+            active_key_id,
+            keys,
+        };
+
+        KeyRing::try_new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_key_ring_rotation() {
+        let old = new_test_key_ring(Some(1), &[1]);
+        let encrypted = old.encrypt(b"payload").unwrap().unwrap();
+        assert_eq!(1, encrypted.key_id);
+        assert_ne!(b"payload".as_slice(), encrypted.ciphertext.as_slice());
+
+        // The payload encrypted by the old key can still be decrypted after
+        // rotation.
+        let rotated = new_test_key_ring(Some(2), &[1, 2]);
+        let decrypted = rotated
+            .decrypt(encrypted.key_id, &encrypted.nonce, &encrypted.ciphertext)
+            .unwrap();
+        assert_eq!(b"payload".as_slice(), decrypted.as_slice());
+        assert_eq!(2, rotated.encrypt(b"payload").unwrap().unwrap().key_id);
+
+        // Fail to decrypt the payload of the removed key.
+        let removed = new_test_key_ring(Some(2), &[2]);
+        assert!(removed
+            .decrypt(encrypted.key_id, &encrypted.nonce, &encrypted.ciphertext)
+            .is_err());
+
+        // Nothing is encrypted without the active key.
+        let inactive = new_test_key_ring(None, &[1]);
+        assert!(inactive.encrypt(b"payload").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_key_ring_config() {
+        let config = EncryptionConfig {
+            active_key_id: Some(1),
+            keys: vec![EncryptionKey {
+                id: 1,
+                key: "0102".to_string(),
+            }],
+        };
+        assert!(KeyRing::try_new(&config).is_err());
+
+        let config = EncryptionConfig {
+            active_key_id: Some(2),
+            keys: vec![EncryptionKey {
+                id: 1,
+                key: hex::encode([1; KEY_SIZE]),
+            }],
+        };
+        assert!(KeyRing::try_new(&config).is_err());
+    }
+}
@@ -0,0 +1,426 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Group commit of the wal writes.
+//!
+//! The batches written by the concurrent writers are collected during the
+//! `flush_interval` (or until `max_batch_bytes` is reached), and written by
+//! one [WalManager::write_batches] call.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use common_types::SequenceNumber;
+use logger::{info, warn};
+use runtime::Runtime;
+use snafu::ResultExt;
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{self, Instant},
+};
+
+use crate::{
+    config::Config,
+    log_batch::LogWriteBatch,
+    manager::{
+        error::*, BatchLogIteratorAdapter, ReadContext, ReadRequest, RegionId, ScanContext,
+        ScanRequest, WalLocation, WalManager, WalManagerRef, WriteContext,
+    },
+    metrics::WAL_GROUP_COMMIT_BATCHES_HISTOGRAM,
+};
+
+const WRITE_CHANNEL_SIZE: usize = 1024;
+
+/// Wrap the `wal` to write in groups if the group commit is enabled by the
+/// `config`.
+pub fn maybe_group_commit(wal: WalManagerRef, config: &Config, runtime: &Runtime) -> WalManagerRef {
+    if config.flush_interval.is_zero() {
+        return wal;
+    }
+    if !wal.supports_write_batches() {
+        warn!(
+            "Wal group commit is disabled as the wal can't write batches in one io, wal:{wal:?}"
+        );
+        return wal;
+    }
+
+    info!(
+        "Wal group commit is enabled, flush_interval:{}, max_batch_bytes:{}",
+        config.flush_interval,
+        config.max_batch_bytes.as_byte()
+    );
+    Arc::new(GroupCommitWal::new(
+        wal,
+        config.flush_interval.0,
+        config.max_batch_bytes.as_byte() as usize,
+        runtime,
+    ))
+}
+
+struct WriteTask {
+    ctx: WriteContext,
+    batch: LogWriteBatch,
+    tx: oneshot::Sender<Result<SequenceNumber>>,
+}
+
+/// [WalManager] writing the batches of the concurrent writers in groups.
+pub struct GroupCommitWal {
+    inner: WalManagerRef,
+    sender: mpsc::Sender<WriteTask>,
+}
+
+impl GroupCommitWal {
+    pub fn new(
+        inner: WalManagerRef,
+        flush_interval: Duration,
+        max_batch_bytes: usize,
+        runtime: &Runtime,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(WRITE_CHANNEL_SIZE);
+        runtime.spawn(Self::commit_in_groups(
+            inner.clone(),
+            receiver,
+            flush_interval,
+            max_batch_bytes,
+        ));
+
+        Self { inner, sender }
+    }
+
+    /// Collect the tasks into groups and write them, it exits when the
+    /// [GroupCommitWal] is dropped.
+    async fn commit_in_groups(
+        inner: WalManagerRef,
+        mut receiver: mpsc::Receiver<WriteTask>,
+        flush_interval: Duration,
+        max_batch_bytes: usize,
+    ) {
+        while let Some(task) = receiver.recv().await {
+            let deadline = Instant::now() + flush_interval;
+            let mut group_bytes = batch_bytes(&task.batch);
+            let mut tasks = vec![task];
+            while group_bytes < max_batch_bytes {
+                match time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(task)) => {
+                        group_bytes += batch_bytes(&task.batch);
+                        tasks.push(task);
+                    }
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            WAL_GROUP_COMMIT_BATCHES_HISTOGRAM.observe(tasks.len() as f64);
+            // The longest timeout of the writers is used for the group.
+            let ctx = tasks
+                .iter()
+                .map(|task| &task.ctx)
+                .max_by_key(|ctx| ctx.timeout)
+                .cloned()
+                .unwrap_or_default();
+            let (batches, senders): (Vec<_>, Vec<_>) =
+                tasks.into_iter().map(|task| (task.batch, task.tx)).unzip();
+            let results = inner.write_batches(&ctx, &batches).await;
+            for (tx, result) in senders.into_iter().zip(results) {
+                if tx.send(result).is_err() {
+                    warn!("Wal group commit failed to send result, writer is cancelled");
+                }
+            }
+        }
+
+        info!("Wal group commit is stopped");
+    }
+}
+
+fn batch_bytes(batch: &LogWriteBatch) -> usize {
+    batch.entries.iter().map(|entry| entry.payload.len()).sum()
+}
+
+impl fmt::Debug for GroupCommitWal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupCommitWal")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl WalManager for GroupCommitWal {
+    async fn sequence_num(&self, location: WalLocation) -> Result<SequenceNumber> {
+        self.inner.sequence_num(location).await
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        location: WalLocation,
+        sequence_num: SequenceNumber,
+    ) -> Result<()> {
+        self.inner
+            .mark_delete_entries_up_to(location, sequence_num)
+            .await
+    }
+
+    async fn close_region(&self, region: RegionId) -> Result<()> {
+        self.inner.close_region(region).await
+    }
+
+    async fn close_gracefully(&self) -> Result<()> {
+        self.inner.close_gracefully().await
+    }
+
+    async fn read_batch(
+        &self,
+        ctx: &ReadContext,
+        req: &ReadRequest,
+    ) -> Result<BatchLogIteratorAdapter> {
+        self.inner.read_batch(ctx, req).await
+    }
+
+    /// The `batch` is copied to be written by the group commit task, and the
+    /// write fails if the result isn't received within the `ctx.timeout`
+    /// (the batch may still be written by the group).
+    async fn write(&self, ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        let (tx, rx) = oneshot::channel();
+        let task = WriteTask {
+            ctx: ctx.clone(),
+            batch: batch.clone(),
+            tx,
+        };
+        if self.sender.send(task).await.is_err() {
+            return WriteGroup {
+                msg: "group commit task is stopped",
+            }
+            .fail();
+        }
+
+        match time::timeout(ctx.timeout, rx).await {
+            Ok(result) => result.box_err().context(Write)?,
+            Err(_) => WriteGroup {
+                msg: format!("timeout to wait for the group, timeout:{:?}", ctx.timeout),
+            }
+            .fail(),
+        }
+    }
+
+    async fn write_batches(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Vec<Result<SequenceNumber>> {
+        self.inner.write_batches(ctx, batches).await
+    }
+
+    fn supports_write_batches(&self) -> bool {
+        self.inner.supports_write_batches()
+    }
+
+    async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
+        self.inner.scan(ctx, req).await
+    }
+
+    async fn get_statistics(&self) -> Option<String> {
+        self.inner.get_statistics().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use time_ext::ReadableDuration;
+
+    use super::*;
+    use crate::{kv_encoder::LogBatchEncoder, log_batch::MemoryPayload};
+
+    /// Wal recording the number of batches of each write.
+    #[derive(Debug, Default)]
+    struct RecordingWal {
+        groups: Mutex<Vec<usize>>,
+        supports_write_batches: bool,
+        write_delay: Duration,
+    }
+
+    impl RecordingWal {
+        fn new(write_delay: Duration) -> Self {
+            Self {
+                supports_write_batches: true,
+                write_delay,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl WalManager for RecordingWal {
+        async fn sequence_num(&self, _location: WalLocation) -> Result<SequenceNumber> {
+            unimplemented!()
+        }
+
+        async fn mark_delete_entries_up_to(
+            &self,
+            _location: WalLocation,
+            _sequence_num: SequenceNumber,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn close_region(&self, _region: RegionId) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn close_gracefully(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn read_batch(
+            &self,
+            _ctx: &ReadContext,
+            _req: &ReadRequest,
+        ) -> Result<BatchLogIteratorAdapter> {
+            unimplemented!()
+        }
+
+        async fn write(
+            &self,
+            _ctx: &WriteContext,
+            batch: &LogWriteBatch,
+        ) -> Result<SequenceNumber> {
+            self.groups.lock().unwrap().push(1);
+            Ok(batch.location.table_id)
+        }
+
+        async fn write_batches(
+            &self,
+            _ctx: &WriteContext,
+            batches: &[LogWriteBatch],
+        ) -> Vec<Result<SequenceNumber>> {
+            time::sleep(self.write_delay).await;
+            self.groups.lock().unwrap().push(batches.len());
+            batches
+                .iter()
+                .map(|batch| Ok(batch.location.table_id))
+                .collect()
+        }
+
+        fn supports_write_batches(&self) -> bool {
+            self.supports_write_batches
+        }
+
+        async fn scan(
+            &self,
+            _ctx: &ScanContext,
+            _req: &ScanRequest,
+        ) -> Result<BatchLogIteratorAdapter> {
+            unimplemented!()
+        }
+
+        async fn get_statistics(&self) -> Option<String> {
+            None
+        }
+    }
+
+    fn new_runtime() -> Arc<Runtime> {
+        Arc::new(
+            runtime::Builder::default()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn new_batch(table_id: u64) -> LogWriteBatch {
+        LogBatchEncoder::create(WalLocation::new(1, table_id))
+            .encode(&MemoryPayload { val: 1 })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_group_commit() {
+        let runtime = new_runtime();
+        let writers = 8;
+        let bytes = batch_bytes(&new_batch(0));
+        for group_size in [1, 2, 8] {
+            let inner = Arc::new(RecordingWal::new(Duration::ZERO));
+            // The groups are closed by the bytes instead of the long flush
+            // interval, so they are formed regardless of the timing.
+            let wal = Arc::new(GroupCommitWal::new(
+                inner.clone(),
+                Duration::from_secs(3600),
+                bytes * group_size,
+                &runtime,
+            ));
+
+            runtime.block_on(async {
+                let handles: Vec<_> = (0..writers)
+                    .map(|table_id| {
+                        let wal = wal.clone();
+                        runtime.spawn(async move {
+                            let batch = new_batch(table_id);
+                            let sequence = wal.write(&WriteContext::default(), &batch).await;
+                            (table_id, sequence.unwrap())
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    let (table_id, sequence) = handle.await.unwrap();
+                    assert_eq!(table_id, sequence);
+                }
+            });
+
+            let groups = inner.groups.lock().unwrap();
+            let expect = vec![group_size; writers as usize / group_size];
+            assert_eq!(expect, *groups);
+        }
+    }
+
+    #[test]
+    fn test_group_commit_timeout() {
+        let runtime = new_runtime();
+        let inner = Arc::new(RecordingWal::new(Duration::from_secs(3600)));
+        let wal = GroupCommitWal::new(inner, Duration::from_millis(1), usize::MAX, &runtime);
+
+        runtime.block_on(async {
+            let ctx = WriteContext {
+                timeout: Duration::from_millis(10),
+            };
+            let result = wal.write(&ctx, &new_batch(0)).await;
+            assert!(matches!(result, Err(Error::WriteGroup { .. })));
+        });
+    }
+
+    #[test]
+    fn test_group_commit_disabled() {
+        let runtime = new_runtime();
+        let config = Config {
+            flush_interval: ReadableDuration(Duration::from_millis(1)),
+            ..Default::default()
+        };
+
+        let supported = Arc::new(RecordingWal::new(Duration::ZERO)) as WalManagerRef;
+        let wal = maybe_group_commit(supported.clone(), &config, &runtime);
+        assert!(!Arc::ptr_eq(&supported, &wal));
+
+        // The wal writing the batches one by one is not wrapped.
+        let unsupported = Arc::new(RecordingWal::default()) as WalManagerRef;
+        let wal = maybe_group_commit(unsupported.clone(), &config, &runtime);
+        assert!(Arc::ptr_eq(&unsupported, &wal));
+
+        let config = Config::default();
+        let wal = maybe_group_commit(supported.clone(), &config, &runtime);
+        assert!(Arc::ptr_eq(&supported, &wal));
+    }
+}
@@ -17,14 +17,19 @@
 
 //! Common Encoding for Wal logs
 
+use std::borrow::Cow;
+
 use bytes_ext::{self, Buf, BufMut, BytesMut, SafeBuf, SafeBufMut};
 use codec::{Decoder, Encoder};
 use common_types::{table::TableId, SequenceNumber};
+use crc::{Crc, CRC_32_ISCSI};
 use generic_error::{BoxError, GenericError};
 use macros::define_result;
-use snafu::{ensure, Backtrace, ResultExt, Snafu};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 
 use crate::{
+    config::Compression,
+    encryption::{self, KeyRing, KeyRingRef, NONCE_SIZE},
     log_batch::{LogWriteBatch, LogWriteEntry, Payload},
     manager::{self, Encoding, WalLocation},
 };
@@ -33,7 +38,11 @@ pub const LOG_KEY_ENCODING_V0: u8 = 0;
 pub const NEWEST_LOG_KEY_ENCODING_VERSION: u8 = LOG_KEY_ENCODING_V0;
 
 pub const LOG_VALUE_ENCODING_V0: u8 = 0;
-pub const NEWEST_LOG_VALUE_ENCODING_VERSION: u8 = LOG_VALUE_ENCODING_V0;
+/// The payload of the value is compressed.
+pub const LOG_VALUE_ENCODING_V1: u8 = 1;
+/// The payload of the value may be compressed, encrypted and checksummed.
+pub const LOG_VALUE_ENCODING_V2: u8 = 2;
+pub const NEWEST_LOG_VALUE_ENCODING_VERSION: u8 = LOG_VALUE_ENCODING_V2;
 
 pub const META_KEY_ENCODING_V0: u8 = 0;
 pub const NEWEST_META_KEY_ENCODING_VERSION: u8 = META_KEY_ENCODING_V0;
@@ -41,6 +50,17 @@ pub const NEWEST_META_KEY_ENCODING_VERSION: u8 = META_KEY_ENCODING_V0;
 pub const META_VALUE_ENCODING_V0: u8 = 0;
 pub const NEWEST_META_VALUE_ENCODING_VERSION: u8 = META_VALUE_ENCODING_V0;
 
+const ZSTD_LEVEL: i32 = 3;
+
+/// Flags of the V2 log value.
+const LOG_VALUE_FLAG_CHECKSUM: u8 = 1;
+const LOG_VALUE_FLAG_ENCRYPTED: u8 = 1 << 1;
+const CHECKSUM_SIZE: usize = 4;
+/// Max size of the V2 log value except the payload, including the header,
+/// the authentication tag of the encrypted payload and the checksum.
+const V2_MAX_EXTRA_SIZE: usize = 3 + 4 + NONCE_SIZE + 16 + CHECKSUM_SIZE;
+const CASTAGNOLI: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Failed to encode log key, err:{}", source))]
@@ -64,6 +84,56 @@ pub enum Error {
     #[snafu(display("Failed to decode log value payload, err:{}", source))]
     DecodeLogValuePayload { source: GenericError },
 
+    #[snafu(display("Failed to compress log value payload, err:{}", source))]
+    CompressLogValuePayload { source: std::io::Error },
+
+    #[snafu(display("Failed to decompress log value payload, err:{}", source))]
+    DecompressLogValuePayload { source: GenericError },
+
+    #[snafu(display(
+        "Found invalid compression of log value, given:{}.\nBacktrace:\n{}",
+        given,
+        backtrace
+    ))]
+    InvalidCompression { given: u8, backtrace: Backtrace },
+
+    #[snafu(display("Failed to encrypt log value payload, err:{}", source))]
+    EncryptLogValuePayload { source: encryption::Error },
+
+    #[snafu(display("Failed to decrypt log value payload, err:{}", source))]
+    DecryptLogValuePayload { source: encryption::Error },
+
+    #[snafu(display(
+        "No key ring to decrypt log value, key id:{}.\nBacktrace:\n{}",
+        key_id,
+        backtrace
+    ))]
+    NoKeyRing { key_id: u32, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Log value is truncated, flags:{}, len:{}.\nBacktrace:\n{}",
+        flags,
+        len,
+        backtrace
+    ))]
+    TruncatedLogValue {
+        flags: u8,
+        len: usize,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Checksum of log value mismatch, expect:{}, actual:{}.\nBacktrace:\n{}",
+        expect,
+        actual,
+        backtrace
+    ))]
+    LogValueChecksumMismatch {
+        expect: u32,
+        actual: u32,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Failed to encode meta key, err:{}", source))]
     EncodeMetaKey {
         source: bytes_ext::Error,
@@ -212,14 +282,89 @@ impl Decoder<LogKey> for LogKeyEncoder {
 #[derive(Debug, Clone)]
 pub struct LogValueEncoder {
     pub version: u8,
+    pub compression: Compression,
+    /// Append the checksum of the value, only supported since V2.
+    pub checksum: bool,
+    /// Encrypt the payload with the active key of the key ring, only
+    /// supported since V2.
+    pub key_ring: Option<KeyRingRef>,
 }
 
 impl LogValueEncoder {
-    /// Create newest version encoder.
+    /// Create newest version encoder, which doesn't compress the payloads.
     pub fn newest() -> Self {
+        Self::with_compression(Compression::None)
+    }
+
+    /// Create the encoder compressing the payloads, and the uncompressed
+    /// payloads are still encoded in V0 to be read by the old versions.
+    pub fn with_compression(compression: Compression) -> Self {
+        Self::new(compression, false, None)
+    }
+
+    /// Create the encoder in the oldest version supporting the given options
+    /// so that the values can be read by as many versions as possible.
+    pub fn new(compression: Compression, checksum: bool, key_ring: Option<KeyRingRef>) -> Self {
+        let encrypted = key_ring
+            .as_ref()
+            .and_then(|key_ring| key_ring.active_key_id())
+            .is_some();
+        let version = if checksum || encrypted {
+            LOG_VALUE_ENCODING_V2
+        } else if compression != Compression::None {
+            LOG_VALUE_ENCODING_V1
+        } else {
+            LOG_VALUE_ENCODING_V0
+        };
+
         Self {
-            version: NEWEST_LOG_VALUE_ENCODING_VERSION,
+            version,
+            compression,
+            checksum,
+            key_ring,
+        }
+    }
+
+    fn encode_v2<T: Payload>(&self, payload: &T) -> Result<Vec<u8>> {
+        let mut raw = Vec::with_capacity(payload.encode_size());
+        payload
+            .encode_to(&mut raw)
+            .box_err()
+            .context(EncodeLogValuePayload)?;
+        let compressed = compress(self.compression, &raw)?;
+        let encrypted = match &self.key_ring {
+            Some(key_ring) => key_ring
+                .encrypt(&compressed)
+                .context(EncryptLogValuePayload)?,
+            None => None,
+        };
+
+        let mut flags = 0;
+        if self.checksum {
+            flags |= LOG_VALUE_FLAG_CHECKSUM;
+        }
+        if encrypted.is_some() {
+            flags |= LOG_VALUE_FLAG_ENCRYPTED;
         }
+
+        let mut value = Vec::with_capacity(compressed.len() + V2_MAX_EXTRA_SIZE);
+        value.put_u8(self.version);
+        value.put_u8(flags);
+        value.put_u8(self.compression.to_u8());
+        match encrypted {
+            Some(encrypted) => {
+                value.put_u32(encrypted.key_id);
+                value.put_slice(&encrypted.nonce);
+                value.put_slice(&encrypted.ciphertext);
+            }
+            None => value.put_slice(&compressed),
+        }
+        if self.checksum {
+            let checksum = CASTAGNOLI.checksum(&value);
+            value.put_u32(checksum);
+        }
+
+        Ok(value)
     }
 }
 
@@ -230,40 +375,172 @@ impl<T: Payload> Encoder<T> for LogValueEncoder {
     /// +--------------------+---------+
     /// | version_header(u8) | payload |
     /// +--------------------+---------+
+    ///
+    /// Value format of the compressed payload (V1):
+    /// +--------------------+-----------------+--------------------+
+    /// | version_header(u8) | compression(u8) | compressed payload |
+    /// +--------------------+-----------------+--------------------+
+    ///
+    /// Value format of V2, the payload is compressed and then encrypted, and
+    /// the key_id and nonce only exist if it's encrypted, and the checksum of
+    /// all the preceding bytes only exists if it's enabled:
+    /// +--------------------+-----------+-----------------+-------------+
+    /// | version_header(u8) | flags(u8) | compression(u8) | key_id(u32) |
+    /// +--------------------+-----------+-----------------+-------------+
+    /// +-----------------+---------+---------------+
+    /// | nonce(12 bytes) | payload | checksum(u32) |
+    /// +-----------------+---------+---------------+
     fn encode<B: BufMut>(&self, buf: &mut B, payload: &T) -> Result<()> {
+        if self.version == LOG_VALUE_ENCODING_V2 {
+            let encoded = self.encode_v2(payload)?;
+            return buf
+                .try_put(&encoded)
+                .box_err()
+                .context(EncodeLogValuePayload);
+        }
+
         buf.try_put_u8(self.version).context(EncodeLogValueHeader)?;
 
+        if self.version == LOG_VALUE_ENCODING_V0 {
+            return payload
+                .encode_to(buf)
+                .box_err()
+                .context(EncodeLogValuePayload);
+        }
+
+        buf.try_put_u8(self.compression.to_u8())
+            .context(EncodeLogValueHeader)?;
+        let mut raw = Vec::with_capacity(payload.encode_size());
         payload
-            .encode_to(buf)
+            .encode_to(&mut raw)
+            .box_err()
+            .context(EncodeLogValuePayload)?;
+        let compressed = compress(self.compression, &raw)?;
+        buf.try_put(&compressed)
             .box_err()
             .context(EncodeLogValuePayload)
     }
 
     fn estimate_encoded_size(&self, payload: &T) -> usize {
-        // Refer to value format.
-        1 + payload.encode_size()
+        // Refer to value format, the size of the compressed payload is unknown
+        // so the size of the raw payload is used.
+        let header_size = match self.version {
+            LOG_VALUE_ENCODING_V0 => 1,
+            LOG_VALUE_ENCODING_V1 => 2,
+            _ => V2_MAX_EXTRA_SIZE,
+        };
+        header_size + payload.encode_size()
+    }
+}
+
+fn compress(compression: Compression, raw: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(raw.to_vec()),
+        Compression::Lz4 => Ok(lz4_flex::block::compress_prepend_size(raw)),
+        Compression::Zstd => {
+            zstd::stream::encode_all(raw, ZSTD_LEVEL).context(CompressLogValuePayload)
+        }
     }
 }
 
-pub struct LogValueDecoder {
+fn decompress(compression: Compression, compressed: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(compressed.to_vec()),
+        Compression::Lz4 => lz4_flex::block::decompress_size_prepended(compressed)
+            .box_err()
+            .context(DecompressLogValuePayload),
+        Compression::Zstd => zstd::stream::decode_all(compressed)
+            .box_err()
+            .context(DecompressLogValuePayload),
+    }
+}
+
+pub struct LogValueDecoder<'k> {
+    /// The newest version can be decoded.
     pub version: u8,
+    /// Key ring to decrypt the encrypted payloads.
+    pub key_ring: Option<&'k KeyRing>,
 }
 
-impl LogValueDecoder {
-    pub fn decode<'a>(&self, mut buf: &'a [u8]) -> Result<&'a [u8]> {
+impl LogValueDecoder<'_> {
+    /// Decode the payload from the value, and the payload is verified,
+    /// decrypted and decompressed if necessary.
+    pub fn decode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let mut buf = value;
         let version = buf.try_get_u8().context(DecodeLogValueHeader)?;
         ensure!(
-            version == self.version,
+            version <= self.version,
             InvalidVersion {
                 expect: self.version,
                 given: version
             }
         );
 
-        Ok(buf)
+        match version {
+            LOG_VALUE_ENCODING_V0 => Ok(Cow::Borrowed(buf)),
+            LOG_VALUE_ENCODING_V1 => {
+                let compression = decode_compression(&mut buf)?;
+                decompress(compression, buf).map(Cow::Owned)
+            }
+            _ => self.decode_v2(value),
+        }
+    }
+
+    fn decode_v2<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        // Skip the version header.
+        let mut buf = &value[1..];
+        let flags = buf.try_get_u8().context(DecodeLogValueHeader)?;
+        if flags & LOG_VALUE_FLAG_CHECKSUM != 0 {
+            let content_len = value
+                .len()
+                .checked_sub(CHECKSUM_SIZE)
+                .filter(|len| *len >= 2)
+                .context(TruncatedLogValue {
+                    flags,
+                    len: value.len(),
+                })?;
+            let (content, mut checksum) = value.split_at(content_len);
+            let expect = checksum.try_get_u32().context(DecodeLogValueHeader)?;
+            let actual = CASTAGNOLI.checksum(content);
+            ensure!(
+                expect == actual,
+                LogValueChecksumMismatch { expect, actual }
+            );
+            buf = &content[2..];
+        }
+
+        let compression = decode_compression(&mut buf)?;
+        let payload = if flags & LOG_VALUE_FLAG_ENCRYPTED != 0 {
+            let key_id = buf.try_get_u32().context(DecodeLogValueHeader)?;
+            ensure!(
+                buf.len() >= NONCE_SIZE,
+                TruncatedLogValue {
+                    flags,
+                    len: value.len(),
+                }
+            );
+            let (nonce, ciphertext) = buf.split_at(NONCE_SIZE);
+            let key_ring = self.key_ring.context(NoKeyRing { key_id })?;
+            let plaintext = key_ring
+                .decrypt(key_id, nonce, ciphertext)
+                .context(DecryptLogValuePayload)?;
+            Cow::Owned(plaintext)
+        } else {
+            Cow::Borrowed(buf)
+        };
+
+        match compression {
+            Compression::None => Ok(payload),
+            compression => decompress(compression, &payload).map(Cow::Owned),
+        }
     }
 }
 
+fn decode_compression(buf: &mut &[u8]) -> Result<Compression> {
+    let given = buf.try_get_u8().context(DecodeLogValueHeader)?;
+    Compression::from_u8(given).context(InvalidCompression { given })
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum MetaKeyType {
     MaxSeq = 0,
@@ -484,6 +761,8 @@ pub struct LogEncoding {
     value_enc: LogValueEncoder,
     // value decoder is created dynamically from the version,
     value_enc_version: u8,
+    /// Key ring to decrypt the values.
+    key_ring: Option<KeyRingRef>,
 }
 
 impl LogEncoding {
@@ -492,9 +771,17 @@ impl LogEncoding {
             key_enc: LogKeyEncoder::newest(),
             value_enc: LogValueEncoder::newest(),
             value_enc_version: NEWEST_LOG_VALUE_ENCODING_VERSION,
+            key_ring: None,
         }
     }
 
+    /// Decrypt the values with the given key ring, and the encrypted values
+    /// can't be decoded without it.
+    pub fn with_key_ring(mut self, key_ring: Option<KeyRingRef>) -> Self {
+        self.key_ring = key_ring;
+        self
+    }
+
     /// Encode [LogKey] into `buf` and caller should knows that the keys are
     /// ordered by ([RegionId], [SequenceNum]) so the caller can use this
     /// method to generate min/max key in specific scope(global or in some
@@ -521,9 +808,10 @@ impl LogEncoding {
         self.key_enc.decode(&mut buf)
     }
 
-    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<&'a [u8]> {
+    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<Cow<'a, [u8]>> {
         let value_dec = LogValueDecoder {
             version: self.value_enc_version,
+            key_ring: self.key_ring.as_deref(),
         };
 
         value_dec.decode(buf)
@@ -546,6 +834,19 @@ impl LogBatchEncoder {
         }
     }
 
+    /// Compress the encoded payloads.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.log_encoding.value_enc = LogValueEncoder::with_compression(compression);
+        self
+    }
+
+    /// Compress, encrypt and checksum the encoded payloads according to the
+    /// value encoder.
+    pub fn with_value_encoder(mut self, value_enc: LogValueEncoder) -> Self {
+        self.log_encoding.value_enc = value_enc;
+        self
+    }
+
     /// Consume LogBatchEncoder and encode single payload to LogWriteBatch.
     pub fn encode(self, payload: &impl Payload) -> manager::Result<LogWriteBatch> {
         let mut write_batch = LogWriteBatch::new(self.location);
@@ -698,6 +999,8 @@ pub struct CommonLogEncoding {
     value_enc: LogValueEncoder,
     // value decoder is created dynamically from the version,
     value_enc_version: u8,
+    /// Key ring to decrypt the values.
+    key_ring: Option<KeyRingRef>,
 }
 
 impl CommonLogEncoding {
@@ -706,9 +1009,17 @@ impl CommonLogEncoding {
             key_enc: CommonLogKeyEncoder::newest(),
             value_enc: LogValueEncoder::newest(),
             value_enc_version: NEWEST_LOG_VALUE_ENCODING_VERSION,
+            key_ring: None,
         }
     }
 
+    /// Decrypt the values with the given key ring, and the encrypted values
+    /// can't be decoded without it.
+    pub fn with_key_ring(mut self, key_ring: Option<KeyRingRef>) -> Self {
+        self.key_ring = key_ring;
+        self
+    }
+
     /// Encode [LogKey] into `buf` and caller should knows that the keys are
     /// ordered by ([RegionId], [SequenceNum]) so the caller can use this
     /// method to generate min/max key in specific scope(global or in some
@@ -735,9 +1046,10 @@ impl CommonLogEncoding {
         self.key_enc.decode(&mut buf)
     }
 
-    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<&'a [u8]> {
+    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<Cow<'a, [u8]>> {
         let value_dec = LogValueDecoder {
             version: self.value_enc_version,
+            key_ring: self.key_ring.as_deref(),
         };
 
         value_dec.decode(buf)
@@ -746,10 +1058,13 @@ impl CommonLogEncoding {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use bytes_ext::BytesMut;
 
     use super::*;
     use crate::{
+        encryption::tests::new_test_key_ring,
         kv_encoder::CommonLogKey,
         log_batch::{MemoryPayload, MemoryPayloadDecoder, PayloadDecodeContext, PayloadDecoder},
     };
@@ -777,15 +1092,99 @@ mod tests {
 
             encoding.encode_value(&mut buf, &payload).unwrap();
 
-            let mut value = encoding.decode_value(&buf).unwrap();
+            let value = encoding.decode_value(&buf).unwrap();
             let decoded_value = decoder
-                .decode(&PayloadDecodeContext::default(), &mut value)
+                .decode(&PayloadDecodeContext::default(), &mut value.as_ref())
                 .unwrap();
 
             assert_eq!(payload, decoded_value);
         }
     }
 
+    #[test]
+    fn test_compressed_log_value_encoding() {
+        let encoding = CommonLogEncoding::newest();
+        let decoder = MemoryPayloadDecoder;
+        let payload = MemoryPayload { val: 42 };
+        for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+            let batch = LogBatchEncoder::create(WalLocation::new(1, 1))
+                .with_compression(compression)
+                .encode(&payload)
+                .unwrap();
+            let expect_version = if compression == Compression::None {
+                LOG_VALUE_ENCODING_V0
+            } else {
+                LOG_VALUE_ENCODING_V1
+            };
+            assert_eq!(expect_version, batch.entries[0].payload[0]);
+
+            let value = encoding.decode_value(&batch.entries[0].payload).unwrap();
+            let decoded_value = decoder
+                .decode(&PayloadDecodeContext::default(), &mut value.as_ref())
+                .unwrap();
+            assert_eq!(payload, decoded_value);
+        }
+    }
+
+    #[test]
+    fn test_checksummed_and_encrypted_log_value_encoding() {
+        let key_ring = Arc::new(new_test_key_ring(Some(1), &[1]));
+        let decoder = MemoryPayloadDecoder;
+        let payload = MemoryPayload { val: 42 };
+        for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+            for (checksum, key_ring) in [
+                (true, None),
+                (false, Some(key_ring.clone())),
+                (true, Some(key_ring.clone())),
+            ] {
+                let value_enc = LogValueEncoder::new(compression, checksum, key_ring.clone());
+                let batch = LogBatchEncoder::create(WalLocation::new(1, 1))
+                    .with_value_encoder(value_enc)
+                    .encode(&payload)
+                    .unwrap();
+                assert_eq!(LOG_VALUE_ENCODING_V2, batch.entries[0].payload[0]);
+
+                let encoding = CommonLogEncoding::newest().with_key_ring(key_ring);
+                let value = encoding.decode_value(&batch.entries[0].payload).unwrap();
+                let decoded_value = decoder
+                    .decode(&PayloadDecodeContext::default(), &mut value.as_ref())
+                    .unwrap();
+                assert_eq!(payload, decoded_value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_log_value() {
+        let key_ring = Arc::new(new_test_key_ring(Some(1), &[1]));
+        let value_enc = LogValueEncoder::new(Compression::Zstd, true, Some(key_ring.clone()));
+        let batch = LogBatchEncoder::create(WalLocation::new(1, 1))
+            .with_value_encoder(value_enc)
+            .encode(&MemoryPayload { val: 42 })
+            .unwrap();
+        let mut value = batch.entries[0].payload.clone();
+
+        // The key ring is necessary to decrypt the value.
+        let encoding = CommonLogEncoding::newest().with_key_ring(None);
+        assert!(matches!(
+            encoding.decode_value(&value),
+            Err(Error::NoKeyRing { key_id: 1, .. })
+        ));
+
+        // The value encrypted by the old key can be decoded after rotation.
+        let rotated = Arc::new(new_test_key_ring(Some(2), &[1, 2]));
+        let encoding = CommonLogEncoding::newest().with_key_ring(Some(rotated));
+        assert!(encoding.decode_value(&value).is_ok());
+        assert!(encoding.decode_value(&value[..3]).is_err());
+
+        let last = value.len() - CHECKSUM_SIZE - 1;
+        value[last] ^= 0xff;
+        assert!(matches!(
+            encoding.decode_value(&value),
+            Err(Error::LogValueChecksumMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_common_log_key_encoding() {
         let region_id = 1234;
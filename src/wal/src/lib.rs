@@ -21,6 +21,8 @@
 
 pub mod config;
 mod dummy;
+pub mod encryption;
+pub mod group_commit;
 pub mod kv_encoder;
 pub mod log_batch;
 pub mod manager;
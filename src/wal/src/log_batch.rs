@@ -65,13 +65,13 @@ pub struct LogEntry<P> {
 }
 
 /// An encoded entry to be written into the Wal.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct LogWriteEntry {
     pub payload: Vec<u8>,
 }
 
 /// A batch of `LogWriteEntry`s.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct LogWriteBatch {
     pub location: WalLocation,
     pub entries: Vec<LogWriteEntry>,
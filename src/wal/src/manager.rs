@@ -32,6 +32,7 @@ use snafu::ResultExt;
 
 use crate::{
     config::Config,
+    encryption::KeyRingRef,
     log_batch::{LogEntry, LogWriteBatch, PayloadDecodeContext, PayloadDecoder},
     metrics::WAL_WRITE_BYTES_HISTOGRAM,
 };
@@ -105,6 +106,13 @@ pub mod error {
             backtrace: Backtrace,
         },
 
+        #[snafu(display(
+            "Failed to write log entries in group, msg:{}.\nBacktrace:\n{}",
+            msg,
+            backtrace
+        ))]
+        WriteGroup { msg: String, backtrace: Backtrace },
+
         #[snafu(display(
             "Failed to read log entries, err:{}.\nBacktrace:\n{}",
             source,
@@ -352,6 +360,32 @@ pub trait WalManager: Send + Sync + fmt::Debug + 'static {
     /// Returns the max sequence number for the batch of log entries.
     async fn write(&self, ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber>;
 
+    /// Write multiple batches of log entries, which may belong to different
+    /// locations, to log.
+    ///
+    /// Returns the result of each batch. The default implementation writes
+    /// the batches one by one, and the implementations able to write them in
+    /// one io should override it.
+    async fn write_batches(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Vec<Result<SequenceNumber>> {
+        let mut results = Vec::with_capacity(batches.len());
+        for batch in batches {
+            results.push(self.write(ctx, batch).await);
+        }
+
+        results
+    }
+
+    /// Whether [WalManager::write_batches] is overridden to write the batches
+    /// in one io, and the group commit is only enabled for such
+    /// implementations.
+    fn supports_write_batches(&self) -> bool {
+        false
+    }
+
     /// Scan all logs from a `Region`.
     async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter>;
 
@@ -544,6 +578,9 @@ pub type WalManagerRef = Arc<dyn WalManager>;
 pub struct OpenedWals {
     pub data_wal: WalManagerRef,
     pub manifest_wal: WalManagerRef,
+    /// Key ring built from the encryption config, used by the data wal to
+    /// decrypt the entries.
+    pub key_ring: KeyRingRef,
 }
 
 /// Runtimes of wal.
@@ -29,6 +29,7 @@ use timed_task::{TaskHandle, TimedTask};
 use tokio::sync::RwLock;
 
 use crate::{
+    encryption::KeyRingRef,
     kv_encoder::LogEncoding,
     log_batch::{LogEntry, LogWriteBatch},
     manager::{
@@ -144,8 +145,9 @@ impl<M: MessageQueue> Namespace<M> {
         message_queue: Arc<M>,
         default_runtime: Arc<Runtime>,
         config: KafkaWalConfig,
+        key_ring: Option<KeyRingRef>,
     ) -> Self {
-        let inner = Arc::new(NamespaceInner::new(namespace, message_queue));
+        let inner = Arc::new(NamespaceInner::new(namespace, message_queue, key_ring));
         let cleaner_handle = start_log_cleaner(
             default_runtime.as_ref(),
             config.clean_period.0,
@@ -256,16 +258,19 @@ struct NamespaceInner<M: MessageQueue> {
     message_queue: Arc<M>,
     meta_encoding: MetaEncoding,
     log_encoding: LogEncoding,
+    /// Key ring to decrypt the logs of the regions.
+    key_ring: Option<KeyRingRef>,
 }
 
 impl<M: MessageQueue> NamespaceInner<M> {
-    pub fn new(namespace: String, message_queue: Arc<M>) -> Self {
+    pub fn new(namespace: String, message_queue: Arc<M>, key_ring: Option<KeyRingRef>) -> Self {
         Self {
             namespace,
             regions: Default::default(),
             message_queue,
             meta_encoding: MetaEncoding::newest(),
-            log_encoding: LogEncoding::newest(),
+            log_encoding: LogEncoding::newest().with_key_ring(key_ring.clone()),
+            key_ring,
         }
     }
 
@@ -301,8 +306,14 @@ impl<M: MessageQueue> NamespaceInner<M> {
             return Ok(region.clone());
         }
 
-        let region =
-            Arc::new(Region::open(&self.namespace, region_id, self.message_queue.clone()).await?);
+        let region = Region::open(
+            &self.namespace,
+            region_id,
+            self.message_queue.clone(),
+            self.key_ring.clone(),
+        )
+        .await?;
+        let region = Arc::new(region);
         regions.insert(region_id, region.clone());
 
         info!(
@@ -29,6 +29,7 @@ use tokio::sync::{Mutex, RwLock};
 use util::*;
 
 use crate::{
+    encryption::KeyRingRef,
     kv_encoder::CommonLogEncoding,
     log_batch::{LogEntry, LogWriteBatch},
     manager,
@@ -139,8 +140,13 @@ pub struct Region<M: MessageQueue> {
 }
 
 impl<M: MessageQueue> Region<M> {
-    /// Init the region.
-    pub async fn open(namespace: &str, region_id: u64, message_queue: Arc<M>) -> Result<Self> {
+    /// Init the region, and the logs are decrypted with the `key_ring`.
+    pub async fn open(
+        namespace: &str,
+        region_id: u64,
+        message_queue: Arc<M>,
+        key_ring: Option<KeyRingRef>,
+    ) -> Result<Self> {
         info!(
             "Begin to open region in namespace, namespace:{}, region id:{}",
             namespace, region_id
@@ -149,7 +155,7 @@ impl<M: MessageQueue> Region<M> {
         // Format to the topic name.
         let log_topic = format_wal_data_topic_name(namespace, region_id);
         let meta_topic = format_wal_meta_topic_name(namespace, region_id);
-        let log_encoding = CommonLogEncoding::newest();
+        let log_encoding = CommonLogEncoding::newest().with_key_ring(key_ring);
         let meta_encoding = MetaEncoding::newest();
 
         message_queue
@@ -920,7 +926,7 @@ impl<C: ConsumeIterator> MessageQueueLogIterator<C> {
                 msg: "failed while polling log",
             })?;
 
-        self.previous_value = payload.to_owned();
+        self.previous_value = payload.into_owned();
 
         Ok(Some(LogEntry {
             table_id: log_key.table_id,
@@ -28,6 +28,7 @@ use snafu::ResultExt;
 
 use crate::{
     config::{Config, StorageConfig},
+    encryption::{self, KeyRingRef},
     log_batch::{LogEntry, LogWriteBatch},
     manager::{
         self, error::*, AsyncLogIterator, BatchLogIteratorAdapter, OpenedWals, ReadContext,
@@ -49,12 +50,14 @@ impl<M: MessageQueue> MessageQueueImpl<M> {
         message_queue: M,
         default_runtime: Arc<Runtime>,
         config: KafkaWalConfig,
+        key_ring: Option<KeyRingRef>,
     ) -> Self {
         MessageQueueImpl(Namespace::open(
             namespace,
             Arc::new(message_queue),
             default_runtime,
             config,
+            key_ring,
         ))
     }
 }
@@ -153,6 +156,9 @@ impl WalsOpener for KafkaWalsOpener {
                 .fail();
             }
         };
+        let key_ring = encryption::build_key_ring(&config.encryption)
+            .box_err()
+            .context(Initialization)?;
 
         let default_runtime = &runtimes.default_runtime;
 
@@ -167,6 +173,7 @@ impl WalsOpener for KafkaWalsOpener {
                 kafka.clone(),
                 default_runtime.clone(),
                 kafka_wal_config.data_namespace,
+                Some(key_ring.clone()),
             );
             Arc::new(data_wal) as Arc<_>
         };
@@ -176,11 +183,13 @@ impl WalsOpener for KafkaWalsOpener {
             kafka,
             default_runtime.clone(),
             kafka_wal_config.meta_namespace,
+            Some(key_ring.clone()),
         );
 
         Ok(OpenedWals {
             data_wal,
             manifest_wal: Arc::new(manifest_wal),
+            key_ring,
         })
     }
 }
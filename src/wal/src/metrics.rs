@@ -25,4 +25,10 @@ lazy_static! {
         exponential_buckets(64.0, 4.0, 10).unwrap()
     )
     .unwrap();
+    pub static ref WAL_GROUP_COMMIT_BATCHES_HISTOGRAM: Histogram = register_histogram!(
+        "wal_group_commit_batches_distribution",
+        "Bucketed histogram of the batches written in one group",
+        exponential_buckets(1.0, 2.0, 12).unwrap()
+    )
+    .unwrap();
 }
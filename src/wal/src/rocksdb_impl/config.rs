@@ -29,6 +29,9 @@ pub struct RocksDBStorageConfig {
     pub data_namespace: RocksDBConfig,
     /// Namespace config for meta.
     pub meta_namespace: RocksDBConfig,
+    /// Open the RocksDB read-only, the writes to the wals fail and the
+    /// `data_dir` is never modified.
+    pub read_only: bool,
 }
 
 impl Default for RocksDBStorageConfig {
@@ -37,6 +40,7 @@ impl Default for RocksDBStorageConfig {
             data_dir: "/tmp/horaedb".to_string(),
             data_namespace: Default::default(),
             meta_namespace: Default::default(),
+            read_only: false,
         }
     }
 }
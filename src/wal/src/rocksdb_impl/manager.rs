@@ -18,6 +18,7 @@
 //! WalManager implementation based on RocksDB
 
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fmt,
     fmt::Formatter,
@@ -43,6 +44,7 @@ use tokio::sync::Mutex;
 
 use crate::{
     config::{Config, StorageConfig},
+    encryption::{self, KeyRingRef},
     kv_encoder::{CommonLogEncoding, CommonLogKey, MaxSeqMetaEncoding, MaxSeqMetaValue, MetaKey},
     log_batch::{LogEntry, LogWriteBatch},
     manager::{
@@ -210,32 +212,8 @@ impl TableUnit {
             batch.entries.len()
         );
 
-        manager::collect_write_log_metrics(batch);
-
-        let entries_num = batch.len() as u64;
-        let (wb, max_sequence_num) = {
-            let wb = WriteBatch::default();
-            let mut next_sequence_num = self.alloc_sequence_num(entries_num);
-            let mut key_buf = BytesMut::new();
-
-            for entry in &batch.entries {
-                let region_id = batch.location.region_id;
-                self.log_encoding
-                    .encode_key(
-                        &mut key_buf,
-                        &CommonLogKey::new(region_id, batch.location.table_id, next_sequence_num),
-                    )
-                    .box_err()
-                    .context(Encoding)?;
-                wb.put(&key_buf, &entry.payload)
-                    .map_err(|e| e.into())
-                    .context(Write)?;
-
-                next_sequence_num += 1;
-            }
-
-            (wb, next_sequence_num - 1)
-        };
+        let wb = WriteBatch::default();
+        let max_sequence_num = self.put_batch(&wb, batch)?;
 
         let db = self.db.clone();
         self.runtime
@@ -249,6 +227,35 @@ impl TableUnit {
             .box_err()
             .context(Write)?
     }
+
+    /// Put the entries of the `batch` into the `wb`.
+    ///
+    /// Returns the max sequence number for the batch of log entries.
+    fn put_batch(&self, wb: &WriteBatch, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        manager::collect_write_log_metrics(batch);
+
+        let entries_num = batch.len() as u64;
+        let mut next_sequence_num = self.alloc_sequence_num(entries_num);
+        let mut key_buf = BytesMut::new();
+
+        for entry in &batch.entries {
+            let region_id = batch.location.region_id;
+            self.log_encoding
+                .encode_key(
+                    &mut key_buf,
+                    &CommonLogKey::new(region_id, batch.location.table_id, next_sequence_num),
+                )
+                .box_err()
+                .context(Encoding)?;
+            wb.put(&key_buf, &entry.payload)
+                .map_err(|e| e.into())
+                .context(Write)?;
+
+            next_sequence_num += 1;
+        }
+
+        Ok(next_sequence_num - 1)
+    }
 }
 
 /// [WalManager] implementation based on RocksDB.
@@ -581,6 +588,8 @@ pub struct Builder {
     level_zero_slowdown_writes_trigger: Option<i32>,
     level_zero_stop_writes_trigger: Option<i32>,
     fifo_compaction_max_table_files_size: Option<u64>,
+    key_ring: Option<KeyRingRef>,
+    read_only: bool,
 }
 
 impl Builder {
@@ -598,6 +607,8 @@ impl Builder {
             level_zero_slowdown_writes_trigger: None,
             level_zero_stop_writes_trigger: None,
             fifo_compaction_max_table_files_size: None,
+            key_ring: None,
+            read_only: false,
         }
     }
 
@@ -646,9 +657,20 @@ impl Builder {
         self
     }
 
+    pub fn key_ring(mut self, v: KeyRingRef) -> Self {
+        self.key_ring = Some(v);
+        self
+    }
+
+    /// Open the existing RocksDB read-only, the writes to the wal fail.
+    pub fn read_only(mut self, v: bool) -> Self {
+        self.read_only = v;
+        self
+    }
+
     pub fn build(self) -> Result<RocksImpl> {
         let mut rocksdb_config = DBOptions::default();
-        rocksdb_config.create_if_missing(true);
+        rocksdb_config.create_if_missing(!self.read_only);
 
         if let Some(v) = self.max_subcompactions {
             rocksdb_config.set_max_subcompactions(v);
@@ -696,16 +718,19 @@ impl Builder {
             options: cf_opts,
             ..ColumnFamilyDescriptor::default()
         };
-        let db = DB::open_cf(rocksdb_config, &self.wal_path, vec![default_cfd])
-            .map_err(|e| e.into())
-            .context(Open {
-                wal_path: self.wal_path.clone(),
-            })?;
+        let db = if self.read_only {
+            DB::open_cf_for_read_only(rocksdb_config, &self.wal_path, vec![default_cfd], false)
+        } else {
+            DB::open_cf(rocksdb_config, &self.wal_path, vec![default_cfd])
+        };
+        let db = db.map_err(|e| e.into()).context(Open {
+            wal_path: self.wal_path.clone(),
+        })?;
         let rocks_impl = RocksImpl {
             wal_path: self.wal_path,
             db: Arc::new(db),
             runtime: self.runtime,
-            log_encoding: CommonLogEncoding::newest(),
+            log_encoding: CommonLogEncoding::newest().with_key_ring(self.key_ring),
             max_seq_meta_encoding: MaxSeqMetaEncoding::newest(),
             table_units: RwLock::new(HashMap::new()),
             stats,
@@ -729,6 +754,8 @@ pub struct RocksLogIterator {
     seeked: bool,
     /// RocksDB iterator
     iter: DBIterator<Arc<DB>>,
+    /// Buffer holding the decompressed payload
+    value_buf: Vec<u8>,
 }
 
 impl fmt::Debug for RocksLogIterator {
@@ -758,6 +785,7 @@ impl RocksLogIterator {
             max_log_key,
             seeked: false,
             iter,
+            value_buf: Vec::new(),
         }
     }
 
@@ -770,6 +798,7 @@ impl RocksLogIterator {
             max_log_key: CommonLogKey::new(0, 0, 0),
             seeked: false,
             iter,
+            value_buf: Vec::new(),
         }
     }
 
@@ -835,11 +864,18 @@ impl SyncLogIterator for RocksLogIterator {
         self.no_more_data = self.is_end_reached(&curr_log_key);
 
         if self.is_valid_log_key(&curr_log_key) {
-            let payload = self
+            let payload = match self
                 .log_encoding
                 .decode_value(self.iter.value())
                 .box_err()
-                .context(Decoding)?;
+                .context(Decoding)?
+            {
+                Cow::Borrowed(payload) => payload,
+                Cow::Owned(payload) => {
+                    self.value_buf = payload;
+                    self.value_buf.as_slice()
+                }
+            };
             let log_entry = LogEntry {
                 table_id: curr_log_key.table_id,
                 sequence: curr_log_key.sequence_num,
@@ -917,6 +953,51 @@ impl WalManager for RocksImpl {
         table_unit.write(ctx, batch).await
     }
 
+    /// All the batches are written in one rocksdb write, and fail together.
+    async fn write_batches(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Vec<Result<SequenceNumber>> {
+        debug!(
+            "Wal begin writing batches in group, ctx:{:?}, batches_num:{}",
+            ctx,
+            batches.len()
+        );
+
+        let fail_all = |msg: String| -> Vec<Result<SequenceNumber>> {
+            batches
+                .iter()
+                .map(|_| WriteGroup { msg: msg.clone() }.fail())
+                .collect()
+        };
+
+        let wb = WriteBatch::default();
+        let mut max_sequence_nums = Vec::with_capacity(batches.len());
+        for batch in batches {
+            let table_unit = self.get_or_create_table_unit(batch.location);
+            match table_unit.put_batch(&wb, batch) {
+                Ok(max_sequence_num) => max_sequence_nums.push(max_sequence_num),
+                Err(e) => return fail_all(e.to_string()),
+            }
+        }
+
+        let db = self.db.clone();
+        let write_res = self
+            .runtime
+            .spawn_blocking(move || db.write(&wb).map_err(|e| e.to_string()))
+            .await;
+        match write_res {
+            Ok(Ok(())) => max_sequence_nums.into_iter().map(Ok).collect(),
+            Ok(Err(msg)) => fail_all(msg),
+            Err(e) => fail_all(e.to_string()),
+        }
+    }
+
+    fn supports_write_batches(&self) -> bool {
+        true
+    }
+
     async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
         debug!("Wal region begin scanning, ctx:{:?}, req:{:?}", ctx, req);
 
@@ -978,6 +1059,8 @@ impl RocksDBWalsOpener {
         wal_path: PathBuf,
         runtime: Arc<Runtime>,
         config: RocksDBConfig,
+        key_ring: KeyRingRef,
+        read_only: bool,
     ) -> Result<WalManagerRef> {
         let rocks = Builder::new(wal_path, runtime)
             .max_subcompactions(config.max_subcompactions)
@@ -989,6 +1072,8 @@ impl RocksDBWalsOpener {
             .level_zero_slowdown_writes_trigger(config.level_zero_slowdown_writes_trigger)
             .level_zero_stop_writes_trigger(config.level_zero_stop_writes_trigger)
             .fifo_compaction_max_table_files_size(config.fifo_compaction_max_table_files_size.0)
+            .key_ring(key_ring)
+            .read_only(read_only)
             .build()?;
 
         Ok(Arc::new(rocks))
@@ -1009,6 +1094,9 @@ impl WalsOpener for RocksDBWalsOpener {
                 .fail();
             }
         };
+        let key_ring = encryption::build_key_ring(&config.encryption)
+            .box_err()
+            .context(Initialization)?;
 
         let write_runtime = runtimes.write_runtime.clone();
         let data_path = Path::new(&rocksdb_wal_config.data_dir);
@@ -1021,6 +1109,8 @@ impl WalsOpener for RocksDBWalsOpener {
                 data_path.join(WAL_DIR_NAME),
                 write_runtime.clone(),
                 rocksdb_wal_config.data_namespace,
+                key_ring.clone(),
+                rocksdb_wal_config.read_only,
             )?
         };
 
@@ -1029,11 +1119,14 @@ impl WalsOpener for RocksDBWalsOpener {
             data_path.join(MANIFEST_DIR_NAME),
             write_runtime,
             rocksdb_wal_config.meta_namespace,
+            key_ring.clone(),
+            rocksdb_wal_config.read_only,
         )?;
 
         Ok(OpenedWals {
             data_wal,
             manifest_wal,
+            key_ring,
         })
     }
 }
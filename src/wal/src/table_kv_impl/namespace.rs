@@ -2003,11 +2003,11 @@ mod tests {
         let mut key_values = Vec::new();
         while iter.valid() {
             let decoded_key = log_encoding.decode_key(iter.key()).unwrap();
-            let mut raw_value = log_encoding.decode_value(iter.value()).unwrap();
+            let raw_value = log_encoding.decode_value(iter.value()).unwrap();
             let ctx = PayloadDecodeContext {
                 table_id: region_id,
             };
-            let decoded_value = decoder.decode(&ctx, &mut raw_value).unwrap();
+            let decoded_value = decoder.decode(&ctx, &mut raw_value.as_ref()).unwrap();
             key_values.push((decoded_key.1, decoded_value));
 
             iter.next().unwrap();
@@ -41,6 +41,7 @@ use table_kv::{
 use tokio::sync::Mutex;
 
 use crate::{
+    encryption::KeyRingRef,
     kv_encoder::{CommonLogEncoding, CommonLogKey},
     log_batch::{LogEntry, LogWriteBatch},
     manager::{self, ReadContext, ReadRequest, SequenceNumber, SyncLogIterator, WalRuntimes},
@@ -724,6 +725,12 @@ impl<T: TableKv> TableLogIterator<T> {
         }
     }
 
+    /// Decrypt the logs with the given key ring.
+    pub fn with_key_ring(mut self, key_ring: Option<KeyRingRef>) -> Self {
+        self.log_encoding = self.log_encoding.with_key_ring(key_ring);
+        self
+    }
+
     #[inline]
     fn no_more_data(&self) -> bool {
         self.current_bucket_index >= self.buckets.len() || self.current_log_key > self.max_log_key
@@ -821,7 +828,7 @@ impl<T: TableKv> SyncLogIterator for TableLogIterator<T> {
 
         // To unblock pr#119, we use the following to simple resolve borrow-check error.
         // detail info: https://github.com/apache/incubator-horaedb/issues/120
-        self.previous_value = payload.to_owned();
+        self.previous_value = payload.into_owned();
 
         // Step current iterator, if it becomes invalid, reset `current_iter` to None
         // and advance `current_bucket_index`.
@@ -28,6 +28,7 @@ use table_kv::{memory::MemoryImpl, obkv::ObkvImpl, TableKv};
 
 use crate::{
     config::{Config, StorageConfig},
+    encryption::{self, KeyRingRef},
     log_batch::LogWriteBatch,
     manager::{
         self, error::*, BatchLogIteratorAdapter, OpenedWals, ReadContext, ReadRequest, RegionId,
@@ -43,6 +44,8 @@ use crate::{
 
 pub struct WalNamespaceImpl<T> {
     namespace: NamespaceRef<T>,
+    /// Key ring to decrypt the logs.
+    key_ring: Option<KeyRingRef>,
 }
 
 impl<T: TableKv> WalNamespaceImpl<T> {
@@ -58,11 +61,19 @@ impl<T: TableKv> WalNamespaceImpl<T> {
 
         let namespace = Self::open_namespace(table_kv, runtimes, namespace_name, config).await?;
 
-        let wal = WalNamespaceImpl { namespace };
+        let wal = WalNamespaceImpl {
+            namespace,
+            key_ring: None,
+        };
 
         Ok(wal)
     }
 
+    pub fn with_key_ring(mut self, key_ring: KeyRingRef) -> Self {
+        self.key_ring = Some(key_ring);
+        self
+    }
+
     /// Open namespace, create it if not exists.
     async fn open_namespace(
         table_kv: T,
@@ -162,7 +173,8 @@ impl<T: TableKv> WalManager for WalNamespaceImpl<T> {
             .read_log(ctx, req)
             .await
             .box_err()
-            .context(Read)?;
+            .context(Read)?
+            .with_key_ring(self.key_ring.clone());
         let runtime = self.namespace.read_runtime().clone();
 
         Ok(BatchLogIteratorAdapter::new_with_sync(
@@ -192,7 +204,8 @@ impl<T: TableKv> WalManager for WalNamespaceImpl<T> {
             .scan_log(ctx, req)
             .await
             .box_err()
-            .context(Read)?;
+            .context(Read)?
+            .with_key_ring(self.key_ring.clone());
         let runtime = self.namespace.read_runtime().clone();
 
         Ok(BatchLogIteratorAdapter::new_with_sync(
@@ -226,6 +239,9 @@ impl WalsOpener for ObkvWalsOpener {
                 .fail();
             }
         };
+        let key_ring = encryption::build_key_ring(&config.encryption)
+            .box_err()
+            .context(Initialization)?;
 
         // Notice the creation of obkv client may block current thread.
         let obkv_config = obkv_wal_config.obkv.clone();
@@ -235,8 +251,14 @@ impl WalsOpener for ObkvWalsOpener {
             .await
             .context(RuntimeExec)??;
 
-        open_wal_and_manifest_with_table_kv(*obkv_wal_config, runtimes, obkv, config.disable_data)
-            .await
+        open_wal_and_manifest_with_table_kv(
+            *obkv_wal_config,
+            runtimes,
+            obkv,
+            config.disable_data,
+            key_ring,
+        )
+        .await
     }
 }
 
@@ -261,12 +283,16 @@ impl WalsOpener for MemWalsOpener {
                 .fail();
             }
         };
+        let key_ring = encryption::build_key_ring(&config.encryption)
+            .box_err()
+            .context(Initialization)?;
 
         open_wal_and_manifest_with_table_kv(
             *obkv_wal_config,
             runtimes,
             self.table_kv.clone(),
             config.disable_data,
+            key_ring,
         )
         .await
     }
@@ -277,6 +303,7 @@ async fn open_wal_and_manifest_with_table_kv<T: TableKv>(
     runtimes: WalRuntimes,
     table_kv: T,
     disable_data: bool,
+    key_ring: KeyRingRef,
 ) -> Result<OpenedWals> {
     let data_wal = if disable_data {
         Arc::new(crate::dummy::DoNothing) as Arc<_>
@@ -287,7 +314,8 @@ async fn open_wal_and_manifest_with_table_kv<T: TableKv>(
             WAL_DIR_NAME,
             config.data_namespace.clone().into(),
         )
-        .await?;
+        .await?
+        .with_key_ring(key_ring.clone());
         Arc::new(data_wal) as Arc<_>
     };
 
@@ -297,10 +325,12 @@ async fn open_wal_and_manifest_with_table_kv<T: TableKv>(
         MANIFEST_DIR_NAME,
         config.meta_namespace.clone().into(),
     )
-    .await?;
+    .await?
+    .with_key_ring(key_ring.clone());
 
     Ok(OpenedWals {
         data_wal,
         manifest_wal: Arc::new(manifest_wal),
+        key_ring,
     })
 }
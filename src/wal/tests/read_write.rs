@@ -41,7 +41,7 @@ use wal::{
         WalManager, WalManagerRef, WalRuntimes, WriteContext,
     },
     message_queue_impl::{config::KafkaWalConfig, wal::MessageQueueImpl},
-    rocksdb_impl::manager::RocksImpl,
+    rocksdb_impl::manager::{Builder, RocksImpl},
     table_kv_impl::{model::NamespaceConfig, wal::WalNamespaceImpl},
 };
 
@@ -51,6 +51,29 @@ fn test_rocksdb_wal() {
     test_all(builder, false);
 }
 
+#[test]
+fn test_rocksdb_wal_read_only() {
+    let env = TestEnv::new(2, RocksWalBuilder);
+    env.runtime.block_on(async {
+        let location = WalLocation::new(DEFAULT_SHARD_ID as u64, 0);
+        let (_, write_batch) = env.build_log_batch(location, 0, 10).await;
+        let seq = {
+            let wal = env.build_wal().await;
+            let seq = wal.write(&env.write_ctx, &write_batch).await.unwrap();
+            wal.close_gracefully().await.unwrap();
+            seq
+        };
+
+        let wal = Builder::new(env.dir.path(), env.runtime.clone())
+            .read_only(true)
+            .build()
+            .unwrap();
+        // The written logs are still readable, but the writes are rejected.
+        assert_eq!(seq, wal.sequence_num(location).await.unwrap());
+        assert!(wal.write(&env.write_ctx, &write_batch).await.is_err());
+    });
+}
+
 #[test]
 fn test_memory_table_wal_default() {
     let builder = MemoryTableWalBuilder::default();
@@ -966,6 +989,7 @@ impl WalBuilder for KafkaWalBuilder {
             kafka_impl,
             runtime.clone(),
             KafkaWalConfig::default(),
+            None,
         );
 
         Arc::new(message_queue_impl)